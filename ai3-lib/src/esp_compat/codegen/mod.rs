@@ -1,6 +1,10 @@
-use crate::esp_compat::config::ESPMiningConfig;
+use crate::esp_compat::config::{ESPMiningConfig, ESPProvisioning, ESPTransport, MiningProfile, BALANCED_BELOW_PERCENT, BATTERY_SAVER_BELOW_PERCENT};
+use crate::esp_compat::miners::{BATTERY_EMPTY_MV, BATTERY_FULL_MV};
 use crate::tensor::Tensor;
 use crate::esp_compat::devices::ESPDeviceType;
+use crate::esp_compat::{kernels, mesh, mqtt, project, provisioning};
+use std::path::{Path, PathBuf};
+use crate::esp_compat::protocol::{device_code, ESP_PROTOCOL_VERSION, MAX_FRAME_PAYLOAD};
 
 pub struct ESPTensorUtils;
 
@@ -16,7 +20,7 @@ impl ESPTensorUtils {
             let tensor_size = tensor.shape.total_elements() * 4; // 4 bytes per f32
             
             if tensor_size > memory_limit {
-                return Err(tribechain_core::TribeError::InvalidOperation(
+                return Err(tribechain_core::TribeError::Esp(
                     format!("Tensor size {}B exceeds device memory limit {}B", tensor_size, memory_limit)
                 ));
             }
@@ -75,30 +79,535 @@ impl ESPTensorUtils {
     }
 }
 
+/// Frame building and field lookup, shared by every transport's sketch
+const SKETCH_FRAMING: &str = r#"void beginFrame(uint8_t type) {
+    frame[0] = type;
+    frame_len = 3;
+}
+
+void putField(uint8_t tag, const uint8_t* value, uint16_t length) {
+    if (frame_len + 3 + length > sizeof(frame)) return;
+    frame[frame_len++] = tag;
+    frame[frame_len++] = length & 0xFF;
+    frame[frame_len++] = length >> 8;
+    memcpy(frame + frame_len, value, length);
+    frame_len += length;
+}
+
+// Finds a field in a received payload; returns its length or -1
+int findField(const uint8_t* payload, uint16_t length, uint8_t tag, const uint8_t** value, int skip = 0) {
+    uint16_t pos = 0;
+    while (pos + 3 <= length) {
+        uint8_t field_tag = payload[pos];
+        uint16_t field_len = payload[pos + 1] | (payload[pos + 2] << 8);
+        if (pos + 3 + field_len > length) return -1;
+        if (field_tag == tag && skip-- == 0) {
+            *value = payload + pos + 3;
+            return field_len;
+        }
+        pos += 3 + field_len;
+    }
+    return -1;
+}
+
+uint32_t readU32(const uint8_t* p) {
+    return p[0] | (p[1] << 8) | (p[2] << 16) | ((uint32_t)p[3] << 24);
+}
+
+uint64_t readU64(const uint8_t* p) {
+    return readU32(p) | ((uint64_t)readU32(p + 4) << 32);
+}
+"#;
+
+/// Loading WiFi and server secrets sealed with the device key, for `ESPProvisioning::Encrypted`
+const SKETCH_SECRETS: &str = r#"#if defined(ESP8266)
+#include <EEPROM.h>
+#else
+#include <Preferences.h>
+#endif
+
+// Stored as: "AI3K" | device key (32) | blob length (u16 LE) | sealed blob
+const int SECRETS_EEPROM_SIZE = 4 + 32 + 2 + SECRETS_MAX_BLOB;
+uint8_t device_key[32];
+uint8_t secrets_blob[SECRETS_MAX_BLOB];
+
+void hmacSha256(const uint8_t* key, const uint8_t* a, size_t aLen, const uint8_t* b, size_t bLen, uint8_t* out) {
+    SHA256 hmac;
+    hmac.resetHMAC(key, 32);
+    hmac.update(a, aLen);
+    if (bLen > 0) hmac.update(b, bLen);
+    hmac.finalizeHMAC(key, 32, out, 32);
+}
+
+// Loads the device key and sealed blob; returns the blob's length or -1
+int readStoredSecrets() {
+#if defined(ESP8266)
+    EEPROM.begin(SECRETS_EEPROM_SIZE);
+    if (EEPROM.read(0) != 'A' || EEPROM.read(1) != 'I' || EEPROM.read(2) != '3' || EEPROM.read(3) != 'K') return -1;
+    for (int i = 0; i < 32; i++) device_key[i] = EEPROM.read(4 + i);
+    int len = EEPROM.read(36) | (EEPROM.read(37) << 8);
+    if (len > SECRETS_MAX_BLOB) return -1;
+    for (int i = 0; i < len; i++) secrets_blob[i] = EEPROM.read(38 + i);
+    return len;
+#else
+    Preferences prefs;
+    prefs.begin("ai3", true);
+    size_t keyLen = prefs.getBytes("device_key", device_key, 32);
+    size_t len = prefs.getBytes("secrets", secrets_blob, SECRETS_MAX_BLOB);
+    prefs.end();
+    return keyLen == 32 ? (int)len : -1;
+#endif
+}
+
+void storeSecrets(int len) {
+#if defined(ESP8266)
+    EEPROM.begin(SECRETS_EEPROM_SIZE);
+    const char* magic = "AI3K";
+    for (int i = 0; i < 4; i++) EEPROM.write(i, magic[i]);
+    for (int i = 0; i < 32; i++) EEPROM.write(4 + i, device_key[i]);
+    EEPROM.write(36, len & 0xFF);
+    EEPROM.write(37, len >> 8);
+    for (int i = 0; i < len; i++) EEPROM.write(38 + i, secrets_blob[i]);
+    EEPROM.commit();
+#else
+    Preferences prefs;
+    prefs.begin("ai3", false);
+    prefs.putBytes("device_key", device_key, 32);
+    prefs.putBytes("secrets", secrets_blob, len);
+    prefs.end();
+#endif
+}
+
+// Checks the blob's tag, decrypts it in place and reads the fields out
+bool openSecrets(int len) {
+    const int header = 4 + 1 + 16;
+    if (len < header + 32 || memcmp(secrets_blob, SECRETS_MAGIC, 4) != 0 || secrets_blob[4] != SECRETS_VERSION) return false;
+    uint8_t encKey[32], macKey[32], tag[32];
+    hmacSha256(device_key, (const uint8_t*)ENC_KEY_LABEL, strlen(ENC_KEY_LABEL), NULL, 0, encKey);
+    hmacSha256(device_key, (const uint8_t*)MAC_KEY_LABEL, strlen(MAC_KEY_LABEL), NULL, 0, macKey);
+    const uint8_t* nonce = secrets_blob + 5;
+    uint8_t* body = secrets_blob + header;
+    int bodyLen = len - header - 32;
+    hmacSha256(macKey, nonce, 16, body, bodyLen, tag);
+    uint8_t diff = 0;
+    for (int i = 0; i < 32; i++) diff |= tag[i] ^ body[bodyLen + i];
+    if (diff != 0) return false;
+    
+    for (int block = 0; block * 32 < bodyLen; block++) {
+        uint8_t counter[4] = { (uint8_t)block, (uint8_t)(block >> 8), (uint8_t)(block >> 16), (uint8_t)(block >> 24) };
+        uint8_t stream[32];
+        hmacSha256(encKey, nonce, 16, counter, 4, stream);
+        for (int i = 0; i < 32 && block * 32 + i < bodyLen; i++) body[block * 32 + i] ^= stream[i];
+    }
+    
+    int pos = 0;
+    while (pos + 3 <= bodyLen) {
+        uint8_t field = body[pos];
+        int fieldLen = body[pos + 1] | (body[pos + 2] << 8);
+        const uint8_t* value = body + pos + 3;
+        if (pos + 3 + fieldLen > bodyLen) return false;
+        String text = "";
+        for (int i = 0; i < fieldLen; i++) text += (char)value[i];
+        if (field == SECRET_WIFI_SSID) ssid = text;
+        else if (field == SECRET_WIFI_PASSWORD) wifi_password = text;
+        else if (field == SECRET_SERVER_ADDRESS) server_address = text;
+        else if (field == SECRET_SERVER_PORT && fieldLen == 2) server_port = value[0] | (value[1] << 8);
+        pos += 3 + fieldLen;
+    }
+    return server_port != 0;
+}
+
+int hexNibble(char c) {
+    if (c >= '0' && c <= '9') return c - '0';
+    if (c >= 'a' && c <= 'f') return c - 'a' + 10;
+    if (c >= 'A' && c <= 'F') return c - 'A' + 10;
+    return -1;
+}
+
+// Decodes `hex` into `out`; returns the byte count or -1
+int hexDecode(const String& hex, uint8_t* out, int maxLen) {
+    if (hex.length() % 2 != 0 || (int)hex.length() / 2 > maxLen) return -1;
+    for (unsigned int i = 0; i < hex.length(); i += 2) {
+        int hi = hexNibble(hex[i]), lo = hexNibble(hex[i + 1]);
+        if (hi < 0 || lo < 0) return -1;
+        out[i / 2] = (hi << 4) | lo;
+    }
+    return hex.length() / 2;
+}
+
+// Blocks until stored secrets open, taking `PROVISION <key hex> <blob hex>` lines on serial
+void loadSecrets() {
+    int len = readStoredSecrets();
+    while (len < 0 || !openSecrets(len)) {
+        Serial.println("Waiting for secrets: PROVISION <device key> <sealed secrets>");
+        Serial.setTimeout(60000);
+        String line = Serial.readStringUntil('\n');
+        line.trim();
+        if (!line.startsWith("PROVISION ")) {
+            len = -1;
+            continue;
+        }
+        int split = line.indexOf(' ', 10);
+        if (split < 0 || hexDecode(line.substring(10, split), device_key, 32) != 32) {
+            len = -1;
+            continue;
+        }
+        len = hexDecode(line.substring(split + 1), secrets_blob, SECRETS_MAX_BLOB);
+        if (len > 0) {
+            // Store only what opens, so a bad line can't wipe working secrets
+            uint8_t copy[SECRETS_MAX_BLOB];
+            memcpy(copy, secrets_blob, len);
+            if (openSecrets(len)) {
+                memcpy(secrets_blob, copy, len);
+                storeSecrets(len);
+                Serial.println("Secrets stored");
+                break;
+            }
+        }
+    }
+}
+"#;
+
+/// Task, result, heartbeat and config handling, shared by every transport's sketch
+const SKETCH_MESSAGES: &str = r#"bool sendHello() {
+    uint8_t device_type = DEVICE_TYPE;
+    uint8_t version = PROTOCOL_VERSION;
+    beginFrame(MSG_HELLO);
+    putField(TAG_DEVICE_ID, (const uint8_t*)deviceId.c_str(), deviceId.length());
+    putField(TAG_DEVICE_TYPE, &device_type, 1);
+    putField(TAG_VERSION, &version, 1);
+    putField(TAG_FIRMWARE_VERSION, (const uint8_t*)FIRMWARE_VERSION, strlen(FIRMWARE_VERSION));
+    return sendFrame();
+}
+
+bool processMiningTask(const uint8_t* payload, uint16_t length) {
+    const uint8_t* value;
+    int taskIdLen = findField(payload, length, TAG_TASK_ID, &value);
+    if (taskIdLen < 0) return false;
+    String taskId = "";
+    for (int i = 0; i < taskIdLen; i++) taskId += (char)value[i];
+    
+    if (findField(payload, length, TAG_OPERATION, &value) != 1) return false;
+    uint8_t operation = value[0];
+    if (findField(payload, length, TAG_DIFFICULTY, &value) != 1) return false;
+    int difficulty = value[0];
+    if (findField(payload, length, TAG_NONCE_RANGE, &value) != 16) return false;
+    uint64_t nonceStart = readU64(value);
+    uint64_t nonceEnd = readU64(value + 8);
+    
+    // Fixed-point devices run the task's kernel on its Q8.8 inputs before the nonce search
+    int kernelResultLen = runTaskKernel(payload, length);
+    
+    Serial.println("Processing task: " + taskId);
+    Serial.println("Operation: " + String(operation));
+    Serial.println("Difficulty: " + String(difficulty));
+    
+    unsigned long startTime = millis();
+    uint8_t hash[32];
+    
+    // Try nonces from the assigned range until we find a valid hash
+    for (uint64_t nonce = nonceStart; nonce < nonceEnd; nonce++) {
+        calculateHash(taskId, operation, nonce, hash);
+        
+        if (meetsdifficulty(hash, difficulty)) {
+            // Found valid hash!
+            unsigned long computationTime = millis() - startTime;
+            
+            // Submit result to server
+            return submitResult(taskId, nonce, hash, computationTime, kernelResultLen);
+        }
+        
+        // Check for throttling conditions
+        if (millis() - startTime > 30000) { // 30 second timeout
+            Serial.println("Task timeout");
+            break;
+        }
+    }
+    
+    return false;
+}
+
+void calculateHash(String taskId, uint8_t operation, uint64_t nonce, uint8_t* hash) {
+    sha256.reset();
+    sha256.update(taskId.c_str(), taskId.length());
+    sha256.update(&operation, 1);
+    sha256.update(&nonce, 8);
+    sha256.finalize(hash, 32);
+}
+
+// Difficulty counts leading zero hex digits
+bool meetsdifficulty(const uint8_t* hash, int difficulty) {
+    int leadingZeros = 0;
+    for (int i = 0; i < 32; i++) {
+        if (hash[i] == 0) {
+            leadingZeros += 2;
+            continue;
+        }
+        if (hash[i] < 16) leadingZeros++;
+        break;
+    }
+    return leadingZeros >= difficulty;
+}
+
+int16_t kernel_inputs[MAX_FRAME_PAYLOAD / 2]; // Aligned copies of the task's Q8.8 inputs
+int16_t kernel_output[MAX_KERNEL_OUTPUT];
+uint8_t kernel_result[6 + MAX_KERNEL_OUTPUT * 2]; // Kernel output as a tensor field value
+
+// Runs the task's fixed-point kernel, if it has one; returns the length of the
+// output tensor in kernel_result, or -1
+int runTaskKernel(const uint8_t* payload, uint16_t length) {
+    const uint8_t* kernel;
+    int kernelLen = findField(payload, length, TAG_KERNEL, &kernel);
+    if (kernelLen < 4 || kernel[3] > 2) return -1;
+    int count = kernel[3];
+    const int16_t* inputs[2];
+    int used = 0;
+    for (int i = 0; i < count; i++) {
+        const uint8_t* tensor;
+        int tensorLen = findField(payload, length, TAG_TENSOR, &tensor, i);
+        if (tensorLen < 1) return -1;
+        int header = 2 + tensor[0] * 2;
+        if (tensorLen < header || tensor[header - 1] != ENCODING_Q8_8) return -1;
+        int elements = (tensorLen - header) / 2;
+        if (used + elements > MAX_FRAME_PAYLOAD / 2) return -1;
+        memcpy(kernel_inputs + used, tensor + header, elements * 2);
+        inputs[i] = kernel_inputs + used;
+        used += elements;
+    }
+    
+    int elements = fixedKernelRun(kernel, kernelLen, inputs, kernel_output);
+    if (elements < 0) return -1;
+    
+    // Output dims come from the descriptor's output operand
+    const uint8_t* out = kernel + 4 + count * 8;
+    int pos = 0;
+    kernel_result[pos++] = kernel[2];
+    if (kernel[2] == 2) {
+        kernel_result[pos++] = out[0];
+        kernel_result[pos++] = out[1];
+    }
+    kernel_result[pos++] = out[2];
+    kernel_result[pos++] = out[3];
+    kernel_result[pos++] = ENCODING_Q8_8;
+    memcpy(kernel_result + pos, kernel_output, elements * 2);
+    return pos + elements * 2;
+}
+
+bool submitResult(String taskId, uint64_t nonce, const uint8_t* hash, unsigned long computationTime, int kernelResultLen) {
+    uint32_t computeTime = computationTime;
+    beginFrame(MSG_RESULT_SUBMIT);
+    putField(TAG_TASK_ID, (const uint8_t*)taskId.c_str(), taskId.length());
+    putField(TAG_NONCE, (const uint8_t*)&nonce, 8);
+    putField(TAG_HASH, hash, 32);
+    putField(TAG_COMPUTE_TIME, (const uint8_t*)&computeTime, 4);
+    if (kernelResultLen > 0) {
+        putField(TAG_TENSOR, kernel_result, kernelResultLen);
+    }
+    
+    bool success = sendFrame();
+    if (success) {
+        Serial.println("Result submitted successfully");
+    } else {
+        Serial.println("Failed to submit result");
+    }
+    return success;
+}
+
+// Reads the battery, updating battery_percent; false on mains power
+bool readBattery(uint16_t* millivolts) {
+#ifdef BATTERY_ADC_PIN
+    // The pin sees half the cell voltage through the divider
+    uint32_t mv = analogRead(BATTERY_ADC_PIN) * 2UL * ADC_REFERENCE_MV / ADC_MAX;
+    battery_percent = constrain(((int)mv - BATTERY_EMPTY_MV) * 100 / (BATTERY_FULL_MV - BATTERY_EMPTY_MV), 0, 100);
+    if (millivolts) *millivolts = mv;
+    return true;
+#else
+    return false;
+#endif
+}
+
+// Steps down to cheaper profiles as the battery drains, and back up as it charges
+void applyProfile() {
+    int profile = CONFIGURED_PROFILE;
+    if (battery_percent >= 0 && battery_percent < BATTERY_SAVER_BELOW_PERCENT) {
+        profile = PROFILE_BATTERY_SAVER;
+    } else if (battery_percent >= 0 && battery_percent < BALANCED_BELOW_PERCENT && profile < PROFILE_BALANCED) {
+        profile = PROFILE_BALANCED;
+    }
+    if (profile == active_profile) return;
+    active_profile = profile;
+    mining_intensity = min(base_intensity, PROFILE_MAX_INTENSITY[profile]);
+    power_save_mode = base_power_save || profile == PROFILE_BATTERY_SAVER;
+#if defined(ESP8266)
+    system_update_cpu_freq(PROFILE_CPU_MHZ[profile]);
+#else
+    setCpuFrequencyMhz(PROFILE_CPU_MHZ[profile]);
+#endif
+    Serial.println("Mining profile " + String(profile) + ", intensity " + String(mining_intensity));
+}
+
+void sendHeartbeat() {
+    uint16_t batteryMv;
+    bool hasBattery = readBattery(&batteryMv);
+    applyProfile();
+    uint32_t uptime = (millis() - uptime_start) / 1000;
+    uint32_t freeHeap = ESP.getFreeHeap();
+    int16_t temperature = (int16_t)(cpu_temperature * 10); // Tenths of a degree
+    int8_t rssi = wifi_signal_strength;
+    uint32_t counts[2] = { (uint32_t)successful_tasks, (uint32_t)failed_tasks };
+    
+    beginFrame(MSG_HEARTBEAT);
+    putField(TAG_UPTIME, (const uint8_t*)&uptime, 4);
+    putField(TAG_FREE_HEAP, (const uint8_t*)&freeHeap, 4);
+    putField(TAG_TEMPERATURE, (const uint8_t*)&temperature, 2);
+    putField(TAG_RSSI, (const uint8_t*)&rssi, 1);
+    putField(TAG_TASK_COUNTS, (const uint8_t*)counts, 8);
+    if (hasBattery) {
+        uint8_t battery[3] = { (uint8_t)(batteryMv & 0xFF), (uint8_t)(batteryMv >> 8), (uint8_t)battery_percent };
+        putField(TAG_BATTERY, battery, 3);
+    }
+    sendFrame();
+    last_heartbeat = millis();
+}
+
+void applyConfigUpdate(const uint8_t* payload, uint16_t length) {
+    const uint8_t* value;
+    if (findField(payload, length, TAG_INTENSITY, &value) == 1) {
+        base_intensity = constrain(value[0], 1, 10);
+    }
+    if (findField(payload, length, TAG_POWER_SAVE, &value) == 1) {
+        base_power_save = value[0] != 0;
+    }
+    // Re-apply the profile over the new settings
+    active_profile = -1;
+    applyProfile();
+    WiFi.setSleep(power_save_mode);
+    if (findField(payload, length, TAG_HEARTBEAT_INTERVAL, &value) == 2) {
+        heartbeat_interval_ms = (value[0] | (value[1] << 8)) * 1000UL;
+    }
+    Serial.println("Config updated: intensity " + String(mining_intensity));
+}
+
+// Downloads and flashes an offered image; the device reboots into it on success,
+// and says hello with the new version
+void applyFirmwareOffer(const uint8_t* payload, uint16_t length) {
+    const uint8_t* value;
+    int versionLen = findField(payload, length, TAG_FIRMWARE_VERSION, &value);
+    if (versionLen < 0) return;
+    String version = "";
+    for (int i = 0; i < versionLen; i++) version += (char)value[i];
+    if (version == FIRMWARE_VERSION) return;
+    
+    int urlLen = findField(payload, length, TAG_FIRMWARE_URL, &value);
+    if (urlLen < 0) return;
+    String url = "";
+    for (int i = 0; i < urlLen; i++) url += (char)value[i];
+    
+    if (findField(payload, length, TAG_FIRMWARE_SIZE, &value) != 4) return;
+    uint32_t size = readU32(value);
+    if (size > ESP.getFreeSketchSpace()) {
+        Serial.println("Firmware " + version + " does not fit");
+        sendFirmwareReport(version, false);
+        return;
+    }
+    
+    Serial.println("Updating firmware to " + version);
+    WiFiClient updateClient;
+    OTA_UPDATER.rebootOnUpdate(true);
+    if (OTA_UPDATER.update(updateClient, url) != HTTP_UPDATE_OK) {
+        Serial.println("Firmware update failed: " + OTA_UPDATER.getLastErrorString());
+        sendFirmwareReport(version, false);
+    }
+}
+
+void sendFirmwareReport(String version, bool success) {
+    uint8_t ok = success ? 1 : 0;
+    beginFrame(MSG_FIRMWARE_REPORT);
+    putField(TAG_FIRMWARE_VERSION, (const uint8_t*)version.c_str(), version.length());
+    putField(TAG_SUCCESS, &ok, 1);
+    sendFrame();
+}
+
+void updatePerformanceStats() {
+    // Simulate temperature increase based on mining intensity
+    cpu_temperature = 25.0 + (mining_intensity * 2.0);
+    
+    // Update WiFi signal strength
+    wifi_signal_strength = WiFi.RSSI();
+    
+    // Print stats every 30 seconds
+    static unsigned long lastStatsTime = 0;
+    if (millis() - lastStatsTime > 30000) {
+        unsigned long uptime = (millis() - uptime_start) / 1000;
+        
+        Serial.println("=== Performance Stats ===");
+        Serial.println("Uptime: " + String(uptime) + " seconds");
+        Serial.println("CPU Temperature: " + String(cpu_temperature) + "°C");
+        Serial.println("WiFi Signal: " + String(wifi_signal_strength) + " dBm");
+        Serial.println("Successful Tasks: " + String(successful_tasks));
+        Serial.println("Failed Tasks: " + String(failed_tasks));
+        Serial.println("Free Heap: " + String(ESP.getFreeHeap()) + " bytes");
+        Serial.println("========================");
+        
+        lastStatsTime = millis();
+    }
+}
+"#;
+
+/// Escape `s` for a C string literal
+fn c_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
 pub struct ESPCodeGenerator;
 
 impl ESPCodeGenerator {
     pub fn generate_mining_code(config: &ESPMiningConfig) -> String {
+        match &config.transport {
+            ESPTransport::Tcp => Self::generate_tcp_mining_code(config, false),
+            ESPTransport::Mqtt => Self::generate_mqtt_mining_code(config),
+            ESPTransport::MeshGateway => Self::generate_tcp_mining_code(config, true),
+            ESPTransport::MeshPeer { gateway_mac, channel } => Self::generate_mesh_peer_code(config, gateway_mac, *channel),
+        }
+    }
+
+    /// Write a buildable firmware project for `config` under `path`: a PlatformIO project
+    /// or an esp-idf Rust crate, per `config.project_kind`. Returns the files written.
+    pub fn generate_project(path: &Path, config: &ESPMiningConfig) -> tribechain_core::TribeResult<Vec<PathBuf>> {
+        project::write_project(path, config)
+    }
+
+    /// Sketch holding one TCP connection to the mining server, which pushes tasks over it
+    fn generate_tcp_mining_code(config: &ESPMiningConfig, gateway: bool) -> String {
+        let mesh = |fragment: &str| if gateway { fragment.to_string() } else { String::new() };
         format!(r#"
 #include <WiFi.h>
-#include <HTTPClient.h>
-#include <ArduinoJson.h>
 #include <SHA256.h>
+#if defined(ESP8266)
+#include <ESP8266httpUpdate.h>
+#define OTA_UPDATER ESPhttpUpdate
+#else
+#include <HTTPUpdate.h>
+#define OTA_UPDATER httpUpdate
+#endif{}
 
 // Configuration
-const char* ssid = "{}";
-const char* server_address = "{}";
-const int server_port = {};
-const int mining_intensity = {};
-const bool power_save_mode = {};
+{}int mining_intensity = {};
+bool power_save_mode = {};
+unsigned long heartbeat_interval_ms = 30000;
+const char* FIRMWARE_VERSION = "{}";
 
+{}
 // Global variables
 WiFiClient client;
-HTTPClient http;
 SHA256 sha256;
+String deviceId;
+uint8_t frame[MAX_FRAME_PAYLOAD + 3];
+uint16_t frame_len = 0;
 
 // Performance monitoring
 unsigned long uptime_start;
+unsigned long last_heartbeat = 0;
 float cpu_temperature = 25.0;
 int wifi_signal_strength;
 unsigned long successful_tasks = 0;
@@ -109,7 +618,8 @@ void setup() {{
     uptime_start = millis();
     
     // Initialize WiFi
-    WiFi.begin(ssid);
+    loadSecrets();
+    WiFi.begin(ssid.c_str(), wifi_password.c_str());
     while (WiFi.status() != WL_CONNECTED) {{
         delay(1000);
         Serial.println("Connecting to WiFi...");
@@ -117,14 +627,16 @@ void setup() {{
     
     Serial.println("Connected to WiFi");
     wifi_signal_strength = WiFi.RSSI();
+    deviceId = WiFi.macAddress();
     
-    // Configure power management
+    // Configure power management for the profile and battery charge
+    readBattery(NULL);
+    applyProfile();
     if (power_save_mode) {{
         WiFi.setSleep(true);
-        setCpuFrequencyMhz(80); // Reduce CPU frequency
     }}
     
-    Serial.println("ESP Miner initialized");
+{}    Serial.println("ESP Miner initialized");
 }}
 
 void loop() {{
@@ -136,20 +648,34 @@ void loop() {{
         return;
     }}
     
-    // Get mining task from server
-    String task = getMiningTask();
-    if (task.length() > 0) {{
-        // Process mining task
-        bool success = processMiningTask(task);
-        if (success) {{
-            successful_tasks++;
-        }} else {{
-            failed_tasks++;
-        }}
+    // Keep one TCP connection to the server; tasks are pushed over it
+    if (!client.connected() && !connectToServer()) {{
+        delay(5000);
+        return;
     }}
     
-    // Update performance stats
+    uint8_t type;
+    uint16_t length;
+    if (readFrame(&type, &length)) {{
+        if (type == MSG_TASK_PUSH) {{
+            bool success = processMiningTask(frame, length);
+            if (success) {{
+                successful_tasks++;
+            }} else {{
+                failed_tasks++;
+            }}
+        }} else if (type == MSG_CONFIG_UPDATE) {{
+            applyConfigUpdate(frame, length);
+        }} else if (type == MSG_FIRMWARE_OFFER) {{
+            applyFirmwareOffer(frame, length);
+        }}{}
+    }}
+    
+{}    // Update performance stats
     updatePerformanceStats();
+    if (millis() - last_heartbeat > heartbeat_interval_ms) {{
+        sendHeartbeat();
+    }}
     
     // Throttle based on temperature
     if (cpu_temperature > 80.0) {{
@@ -160,154 +686,619 @@ void loop() {{
     delay(100 * (11 - mining_intensity)); // Adjust delay based on intensity
 }}
 
-String getMiningTask() {{
-    String url = "http://" + String(server_address) + ":" + String(server_port) + "/api/mining/task";
-    
-    http.begin(client, url);
-    http.addHeader("Content-Type", "application/json");
-    
-    int httpResponseCode = http.GET();
-    String response = "";
-    
-    if (httpResponseCode == 200) {{
-        response = http.getString();
-    }} else {{
-        Serial.println("Failed to get mining task: " + String(httpResponseCode));
+bool connectToServer() {{
+    if (!client.connect(server_address.c_str(), server_port)) {{
+        Serial.println("Failed to connect to mining server");
+        return false;
     }}
+    client.setNoDelay(true);
     
-    http.end();
-    return response;
+    return sendHello();
 }}
 
-bool processMiningTask(String taskJson) {{
-    DynamicJsonDocument doc(1024);
-    deserializeJson(doc, taskJson);
-    
-    String taskId = doc["id"];
-    String operationType = doc["operation_type"];
-    int difficulty = doc["difficulty"];
+// --- Framing ---
+
+bool sendFrame() {{
+    uint16_t payload = frame_len - 3;
+    frame[1] = payload & 0xFF;
+    frame[2] = payload >> 8;
+    return client.write(frame, frame_len) == frame_len;
+}}
+
+bool readExact(uint8_t* buffer, uint16_t length) {{
+    unsigned long start = millis();
+    uint16_t read = 0;
+    while (read < length) {{
+        if (millis() - start > 5000 || !client.connected()) return false;
+        int n = client.read(buffer + read, length - read);
+        if (n > 0) read += n;
+    }}
+    return true;
+}}
+
+// Reads one frame's payload into `frame`; oversized frames drop the connection
+bool readFrame(uint8_t* type, uint16_t* length) {{
+    if (client.available() < 3) return false;
+    uint8_t header[3];
+    if (!readExact(header, 3)) return false;
+    *type = header[0];
+    *length = header[1] | (header[2] << 8);
+    if (*length > MAX_FRAME_PAYLOAD) {{
+        Serial.println("Frame too large, reconnecting");
+        client.stop();
+        return false;
+    }}
+    return readExact(frame, *length);
+}}
+
+{}
+// --- Messages ---
+
+{}{}"#,
+            mesh("\n#include <esp_now.h>"),
+            Self::sketch_secrets(config),
+            config.mining_intensity,
+            config.power_save_mode,
+            config.firmware_version,
+            Self::sketch_protocol(config) + &Self::sketch_profile(config),
+            mesh("    setupMesh();\n    \n"),
+            mesh(" else if (type == MSG_MESH_RELAY) {\n            relayToPeer(frame, length);\n        }"),
+            mesh("    flushMeshBatch();\n    \n"),
+            SKETCH_FRAMING,
+            SKETCH_MESSAGES,
+            mesh(&Self::sketch_mesh_gateway())
+        )
+    }
+
+    /// Sketch for fleets on existing MQTT infrastructure: `server_address` is the broker,
+    /// tasks arrive on the device's capability topics
+    fn generate_mqtt_mining_code(config: &ESPMiningConfig) -> String {
+        let capabilities = mqtt::capabilities(&config.device_type);
+        let capability_list = capabilities.iter()
+            .map(|capability| format!("\"{}\"", capability))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        format!(r#"
+#include <WiFi.h>
+#include <PubSubClient.h>
+#include <SHA256.h>
+#if defined(ESP8266)
+#include <ESP8266httpUpdate.h>
+#define OTA_UPDATER ESPhttpUpdate
+#else
+#include <HTTPUpdate.h>
+#define OTA_UPDATER httpUpdate
+#endif
+
+// Configuration
+{}int mining_intensity = {};
+bool power_save_mode = {};
+unsigned long heartbeat_interval_ms = 30000;
+const char* FIRMWARE_VERSION = "{}";
+
+const char* TOPIC_ROOT = "{}";
+const char* CAPABILITIES[] = {{ {} }};
+const int CAPABILITY_COUNT = {};
+
+{}
+// Global variables
+WiFiClient wifiClient;
+PubSubClient mqtt(wifiClient);
+SHA256 sha256;
+String deviceId;
+uint8_t frame[MAX_FRAME_PAYLOAD + 3];
+uint16_t frame_len = 0;
+
+// Performance monitoring
+unsigned long uptime_start;
+unsigned long last_heartbeat = 0;
+float cpu_temperature = 25.0;
+int wifi_signal_strength;
+unsigned long successful_tasks = 0;
+unsigned long failed_tasks = 0;
+
+void setup() {{
+    Serial.begin(115200);
+    uptime_start = millis();
     
-    Serial.println("Processing task: " + taskId);
-    Serial.println("Operation: " + operationType);
-    Serial.println("Difficulty: " + String(difficulty));
+    // Initialize WiFi
+    loadSecrets();
+    WiFi.begin(ssid.c_str(), wifi_password.c_str());
+    while (WiFi.status() != WL_CONNECTED) {{
+        delay(1000);
+        Serial.println("Connecting to WiFi...");
+    }}
     
-    // Simple mining simulation
-    unsigned long startTime = millis();
-    String hash = "";
-    uint64_t nonce = 0;
+    Serial.println("Connected to WiFi");
+    wifi_signal_strength = WiFi.RSSI();
+    deviceId = WiFi.macAddress();
     
-    // Try different nonces until we find a valid hash
-    for (int i = 0; i < 1000; i++) {{
-        nonce = random(0, UINT32_MAX);
-        hash = calculateHash(taskId, operationType, nonce);
-        
-        if (meetsdifficulty(hash, difficulty)) {{
-            // Found valid hash!
-            unsigned long computationTime = millis() - startTime;
-            
-            // Submit result to server
-            return submitResult(taskId, nonce, hash, computationTime);
-        }}
-        
-        // Check for throttling conditions
-        if (millis() - startTime > 30000) {{ // 30 second timeout
-            Serial.println("Task timeout");
-            break;
-        }}
+    // Configure power management for the profile and battery charge
+    readBattery(NULL);
+    applyProfile();
+    if (power_save_mode) {{
+        WiFi.setSleep(true);
     }}
     
-    return false;
+    mqtt.setServer(server_address.c_str(), server_port);
+    mqtt.setBufferSize(MAX_FRAME_PAYLOAD + 3);
+    mqtt.setCallback(onMessage);
+    
+    Serial.println("ESP Miner initialized");
 }}
 
-String calculateHash(String taskId, String operationType, uint64_t nonce) {{
-    String input = taskId + operationType + String(nonce);
+void loop() {{
+    // Check WiFi connection
+    if (WiFi.status() != WL_CONNECTED) {{
+        Serial.println("WiFi disconnected, reconnecting...");
+        WiFi.reconnect();
+        delay(5000);
+        return;
+    }}
     
-    sha256.reset();
-    sha256.update(input.c_str(), input.length());
+    // Tasks and config updates arrive through onMessage
+    if (!mqtt.connected() && !connectToBroker()) {{
+        delay(5000);
+        return;
+    }}
+    mqtt.loop();
     
-    uint8_t hash[32];
-    sha256.finalize(hash, 32);
+    // Update performance stats
+    updatePerformanceStats();
+    if (millis() - last_heartbeat > heartbeat_interval_ms) {{
+        sendHeartbeat();
+    }}
     
-    String hashString = "";
-    for (int i = 0; i < 32; i++) {{
-        if (hash[i] < 16) hashString += "0";
-        hashString += String(hash[i], HEX);
+    // Throttle based on temperature
+    if (cpu_temperature > 80.0) {{
+        Serial.println("Temperature too high, throttling...");
+        delay(5000);
     }}
     
-    return hashString;
+    delay(100 * (11 - mining_intensity)); // Adjust delay based on intensity
 }}
 
-bool meetsdifficulty(String hash, int difficulty) {{
-    int leadingZeros = 0;
-    for (int i = 0; i < hash.length(); i++) {{
-        if (hash[i] == '0') {{
-            leadingZeros++;
+bool connectToBroker() {{
+    if (!mqtt.connect(deviceId.c_str())) {{
+        Serial.println("Failed to connect to MQTT broker: " + String(mqtt.state()));
+        return false;
+    }}
+    
+    String root = String(TOPIC_ROOT);
+    mqtt.subscribe((root + "/devices/" + deviceId + "/config").c_str());
+    for (int i = 0; i < CAPABILITY_COUNT; i++) {{
+        mqtt.subscribe((root + "/tasks/" + CAPABILITIES[i]).c_str());
+    }}
+    return sendHello();
+}}
+
+void onMessage(char* topic, byte* payload, unsigned int length) {{
+    // Each MQTT payload is one whole frame
+    if (length < 3) return;
+    uint16_t payloadLength = payload[1] | (payload[2] << 8);
+    if (payloadLength + 3 != length) return;
+    
+    if (payload[0] == MSG_TASK_PUSH) {{
+        bool success = processMiningTask(payload + 3, payloadLength);
+        if (success) {{
+            successful_tasks++;
         }} else {{
-            break;
+            failed_tasks++;
         }}
+    }} else if (payload[0] == MSG_CONFIG_UPDATE) {{
+        applyConfigUpdate(payload + 3, payloadLength);
+    }} else if (payload[0] == MSG_FIRMWARE_OFFER) {{
+        applyFirmwareOffer(payload + 3, payloadLength);
     }}
-    return leadingZeros >= difficulty;
 }}
 
-bool submitResult(String taskId, uint64_t nonce, String hash, unsigned long computationTime) {{
-    String url = "http://" + String(server_address) + ":" + String(server_port) + "/api/mining/result";
+
+// --- Framing ---
+
+// Frames go to the topic for their message type
+bool sendFrame() {{
+    uint16_t payload = frame_len - 3;
+    frame[1] = payload & 0xFF;
+    frame[2] = payload >> 8;
     
-    DynamicJsonDocument doc(512);
-    doc["task_id"] = taskId;
-    doc["miner_id"] = WiFi.macAddress();
-    doc["nonce"] = String(nonce);
-    doc["hash"] = hash;
-    doc["computation_time"] = computationTime;
+    String topic = String(TOPIC_ROOT);
+    switch (frame[0]) {{
+        case MSG_HELLO: topic += "/devices/" + deviceId + "/hello"; break;
+        case MSG_RESULT_SUBMIT: topic += "/results/" + deviceId; break;
+        case MSG_HEARTBEAT:
+        case MSG_FIRMWARE_REPORT: topic += "/telemetry/" + deviceId; break;
+        default: return false;
+    }}
+    return mqtt.publish(topic.c_str(), frame, frame_len);
+}}
+
+{}
+// --- Messages ---
+
+{}"#, 
+            Self::sketch_secrets(config),
+            config.mining_intensity,
+            config.power_save_mode,
+            config.firmware_version,
+            mqtt::TOPIC_ROOT,
+            capability_list,
+            capabilities.len(),
+            Self::sketch_protocol(config) + &Self::sketch_profile(config),
+            SKETCH_FRAMING,
+            SKETCH_MESSAGES
+        )
+    }
+
+    /// Gateway additions to the TCP sketch: peers' frames are buffered as they arrive
+    /// over ESP-NOW and uploaded as one batch per loop; relays go straight out
+    fn sketch_mesh_gateway() -> String {
+        format!(r#"
+// --- Mesh gateway ---
+
+const int MAX_MESH_ENTRIES = {};
+
+struct MeshEntry {{
+    uint8_t data[6 + ESPNOW_MAX_PAYLOAD]; // Peer MAC, then its frame
+    uint16_t len;
+}};
+MeshEntry mesh_entries[MAX_MESH_ENTRIES];
+int mesh_count = 0;
+portMUX_TYPE mesh_mux = portMUX_INITIALIZER_UNLOCKED;
+
+void onMeshReceive(const uint8_t* mac, const uint8_t* data, int len) {{
+    if (len < 3 || len > ESPNOW_MAX_PAYLOAD) return;
+    portENTER_CRITICAL(&mesh_mux);
+    if (mesh_count < MAX_MESH_ENTRIES) {{
+        MeshEntry& entry = mesh_entries[mesh_count++];
+        memcpy(entry.data, mac, 6);
+        memcpy(entry.data + 6, data, len);
+        entry.len = 6 + len;
+    }}
+    portEXIT_CRITICAL(&mesh_mux);
+}}
+
+void setupMesh() {{
+    if (esp_now_init() != ESP_OK) {{
+        Serial.println("ESP-NOW init failed");
+        return;
+    }}
+    esp_now_register_recv_cb(onMeshReceive);
+    Serial.println("Mesh gateway on channel " + String(WiFi.channel()));
+}}
+
+bool ensurePeer(const uint8_t* mac) {{
+    if (esp_now_is_peer_exist(mac)) return true;
+    esp_now_peer_info_t peer = {{}};
+    memcpy(peer.peer_addr, mac, 6);
+    peer.channel = 0; // The channel the gateway's WiFi is on
+    peer.encrypt = false;
+    return esp_now_add_peer(&peer) == ESP_OK;
+}}
+
+void relayToPeer(const uint8_t* payload, uint16_t length) {{
+    const uint8_t* mac;
+    const uint8_t* data;
+    if (findField(payload, length, TAG_PEER, &mac) != 6) return;
+    int len = findField(payload, length, TAG_FRAME, &data);
+    if (len < 3 || len > ESPNOW_MAX_PAYLOAD || !ensurePeer(mac)) return;
+    esp_now_send(mac, data, len);
+}}
+
+void flushMeshBatch() {{
+    if (mesh_count == 0) return;
+    beginFrame(MSG_MESH_BATCH);
+    portENTER_CRITICAL(&mesh_mux);
+    for (int i = 0; i < mesh_count; i++) {{
+        putField(TAG_MESH_ENTRY, mesh_entries[i].data, mesh_entries[i].len);
+    }}
+    mesh_count = 0;
+    portEXIT_CRITICAL(&mesh_mux);
+    sendFrame();
+}}
+"#,
+            mesh::MAX_BATCH_ENTRIES
+        )
+    }
+
+    /// Sketch for nodes off WiFi: every frame goes over ESP-NOW to `gateway_mac`, whose
+    /// WiFi must be on `channel`. Peers can't fetch firmware, so OTA offers are ignored.
+    fn generate_mesh_peer_code(config: &ESPMiningConfig, gateway_mac: &str, channel: u8) -> String {
+        let gateway_bytes = mesh::parse_mac(gateway_mac)
+            .map(|mac| mac.iter().map(|byte| format!("0x{:02X}", byte)).collect::<Vec<_>>().join(", "))
+            .unwrap_or_else(|_| "0, 0, 0, 0, 0, 0".to_string());
+        format!(r#"
+#include <SHA256.h>
+#if defined(ESP8266)
+#include <ESP8266WiFi.h>
+#include <espnow.h>
+#include <ESP8266httpUpdate.h>
+#define OTA_UPDATER ESPhttpUpdate
+#else
+#include <WiFi.h>
+#include <esp_now.h>
+#include <esp_wifi.h>
+#include <HTTPUpdate.h>
+#define OTA_UPDATER httpUpdate
+#endif
+
+// Configuration
+const uint8_t GATEWAY_MAC[6] = {{ {} }}; // {}
+const uint8_t MESH_CHANNEL = {};
+int mining_intensity = {};
+bool power_save_mode = {};
+unsigned long heartbeat_interval_ms = 30000;
+const char* FIRMWARE_VERSION = "{}";
+
+{}
+// Global variables
+SHA256 sha256;
+String deviceId;
+uint8_t frame[MAX_FRAME_PAYLOAD + 3];
+uint16_t frame_len = 0;
+uint8_t inbox[ESPNOW_MAX_PAYLOAD];
+volatile int inbox_len = 0; // Non-zero while a frame from the gateway waits
+
+// Performance monitoring
+unsigned long uptime_start;
+unsigned long last_heartbeat = 0;
+float cpu_temperature = 25.0;
+int wifi_signal_strength;
+unsigned long successful_tasks = 0;
+unsigned long failed_tasks = 0;
+
+// Frames arriving while the last one is still waiting are dropped
+#if defined(ESP8266)
+void onMeshReceive(uint8_t* mac, uint8_t* data, uint8_t len) {{
+#else
+void onMeshReceive(const uint8_t* mac, const uint8_t* data, int len) {{
+#endif
+    if (inbox_len > 0 || len < 3 || len > ESPNOW_MAX_PAYLOAD) return;
+    if (memcmp(mac, GATEWAY_MAC, 6) != 0) return;
+    memcpy(inbox, data, len);
+    inbox_len = len;
+}}
+
+void setup() {{
+    Serial.begin(115200);
+    uptime_start = millis();
     
-    String jsonString;
-    serializeJson(doc, jsonString);
+    // Radio only, tuned to the gateway's channel
+    WiFi.mode(WIFI_STA);
+    WiFi.disconnect();
+    deviceId = WiFi.macAddress();
+#if defined(ESP8266)
+    wifi_set_channel(MESH_CHANNEL);
+    esp_now_init();
+    esp_now_set_self_role(ESP_NOW_ROLE_COMBO);
+    esp_now_add_peer((uint8_t*)GATEWAY_MAC, ESP_NOW_ROLE_COMBO, MESH_CHANNEL, NULL, 0);
+#else
+    esp_wifi_set_channel(MESH_CHANNEL, WIFI_SECOND_CHAN_NONE);
+    esp_now_init();
+    esp_now_peer_info_t peer = {{}};
+    memcpy(peer.peer_addr, GATEWAY_MAC, 6);
+    peer.channel = MESH_CHANNEL;
+    esp_now_add_peer(&peer);
+#endif
+    esp_now_register_recv_cb(onMeshReceive);
+    readBattery(NULL);
+    applyProfile();
     
-    http.begin(client, url);
-    http.addHeader("Content-Type", "application/json");
+    sendHello();
+    Serial.println("ESP mesh peer initialized");
+}}
+
+void loop() {{
+    if (inbox_len > 0) {{
+        uint8_t type = inbox[0];
+        uint16_t length = inbox[1] | (inbox[2] << 8);
+        if (length + 3 == inbox_len) {{
+            memcpy(frame, inbox + 3, length);
+            inbox_len = 0;
+            if (type == MSG_TASK_PUSH) {{
+                bool success = processMiningTask(frame, length);
+                if (success) {{
+                    successful_tasks++;
+                }} else {{
+                    failed_tasks++;
+                }}
+            }} else if (type == MSG_CONFIG_UPDATE) {{
+                applyConfigUpdate(frame, length);
+            }}
+        }} else {{
+            inbox_len = 0;
+        }}
+    }}
     
-    int httpResponseCode = http.POST(jsonString);
-    bool success = (httpResponseCode == 200);
+    // Update performance stats
+    updatePerformanceStats();
+    if (millis() - last_heartbeat > heartbeat_interval_ms) {{
+        sendHeartbeat();
+    }}
     
-    if (success) {{
-        Serial.println("Result submitted successfully");
-    }} else {{
-        Serial.println("Failed to submit result: " + String(httpResponseCode));
+    // Throttle based on temperature
+    if (cpu_temperature > 80.0) {{
+        Serial.println("Temperature too high, throttling...");
+        delay(5000);
     }}
     
-    http.end();
-    return success;
+    delay(100 * (11 - mining_intensity)); // Adjust delay based on intensity
 }}
 
-void updatePerformanceStats() {{
-    // Simulate temperature increase based on mining intensity
-    cpu_temperature = 25.0 + (mining_intensity * 2.0);
-    
-    // Update WiFi signal strength
-    wifi_signal_strength = WiFi.RSSI();
-    
-    // Print stats every 30 seconds
-    static unsigned long lastStatsTime = 0;
-    if (millis() - lastStatsTime > 30000) {{
-        unsigned long uptime = (millis() - uptime_start) / 1000;
-        
-        Serial.println("=== Performance Stats ===");
-        Serial.println("Uptime: " + String(uptime) + " seconds");
-        Serial.println("CPU Temperature: " + String(cpu_temperature) + "°C");
-        Serial.println("WiFi Signal: " + String(wifi_signal_strength) + " dBm");
-        Serial.println("Successful Tasks: " + String(successful_tasks));
-        Serial.println("Failed Tasks: " + String(failed_tasks));
-        Serial.println("Free Heap: " + String(ESP.getFreeHeap()) + " bytes");
-        Serial.println("========================");
-        
-        lastStatsTime = millis();
-    }}
+// --- Framing ---
+
+// Frames must fit one ESP-NOW packet; the gateway forwards them in its next batch
+bool sendFrame() {{
+    uint16_t payload = frame_len - 3;
+    frame[1] = payload & 0xFF;
+    frame[2] = payload >> 8;
+    if (frame_len > ESPNOW_MAX_PAYLOAD) return false;
+#if defined(ESP8266)
+    return esp_now_send((uint8_t*)GATEWAY_MAC, frame, frame_len) == 0;
+#else
+    return esp_now_send(GATEWAY_MAC, frame, frame_len) == ESP_OK;
+#endif
 }}
-"#, 
-            config.wifi_ssid,
-            config.server_address,
-            config.server_port,
+
+{}
+// --- Messages ---
+
+{}"#,
+            gateway_bytes,
+            gateway_mac,
+            channel,
+            config.mining_intensity,
+            config.power_save_mode,
+            config.firmware_version,
+            Self::sketch_protocol(config) + &Self::sketch_profile(config),
+            SKETCH_FRAMING,
+            SKETCH_MESSAGES
+        )
+    }
+
+    /// Wire protocol constants shared by every transport
+    /// WiFi and server settings: literals, or loaded at boot from sealed flash storage
+    fn sketch_secrets(config: &ESPMiningConfig) -> String {
+        match config.provisioning {
+            ESPProvisioning::Embedded => format!(
+                "String ssid = \"{}\";\n\
+                 String wifi_password = \"{}\";\n\
+                 String server_address = \"{}\";\n\
+                 int server_port = {};\n\n\
+                 // Compiled-in secrets; nothing to load\n\
+                 void loadSecrets() {{}}\n",
+                c_escape(&config.wifi_ssid),
+                c_escape(&config.wifi_password),
+                c_escape(&config.server_address),
+                config.server_port
+            ),
+            ESPProvisioning::Encrypted => format!(
+                "// Loaded by loadSecrets(); none of these are in the firmware image\n\
+                 String ssid;\n\
+                 String wifi_password;\n\
+                 String server_address;\n\
+                 int server_port = 0;\n\n\
+                 const int SECRETS_MAX_BLOB = {};\n\
+                 const char* SECRETS_MAGIC = \"{}\";\n\
+                 const uint8_t SECRETS_VERSION = {};\n\
+                 const char* ENC_KEY_LABEL = \"{}\";\n\
+                 const char* MAC_KEY_LABEL = \"{}\";\n\
+                 const uint8_t SECRET_WIFI_SSID = 1;\n\
+                 const uint8_t SECRET_WIFI_PASSWORD = 2;\n\
+                 const uint8_t SECRET_SERVER_ADDRESS = 3;\n\
+                 const uint8_t SECRET_SERVER_PORT = 4;\n\n{}",
+                provisioning::SECRETS_MAX_BLOB,
+                String::from_utf8_lossy(provisioning::SECRETS_MAGIC),
+                provisioning::SECRETS_VERSION,
+                provisioning::ENC_KEY_LABEL,
+                provisioning::MAC_KEY_LABEL,
+                SKETCH_SECRETS
+            ),
+        }
+    }
+
+    /// Mining profile constants: the configured profile and what each allows, stepped
+    /// down on the device as its battery drains
+    fn sketch_profile(config: &ESPMiningConfig) -> String {
+        let settings = MiningProfile::ALL.map(|profile| profile.settings(config.clock_speed_mhz));
+        let battery_pin = match config.battery_adc_pin {
+            Some(pin) => format!("#define BATTERY_ADC_PIN {}\n", pin),
+            None => String::new(),
+        };
+        let (adc_max, adc_reference_mv) = match config.device_type {
+            ESPDeviceType::ESP8266 => (1023, 3300), // Board divider scales the 1V ADC to 3.3V
+            _ => (4095, 3300),
+        };
+        format!(r#"
+// Mining profiles: 0 performance, 1 balanced, 2 battery-saver
+const int PROFILE_BALANCED = 1;
+const int PROFILE_BATTERY_SAVER = 2;
+const int CONFIGURED_PROFILE = {};
+const int PROFILE_MAX_INTENSITY[3] = {{ {}, {}, {} }};
+const int PROFILE_CPU_MHZ[3] = {{ {}, {}, {} }};
+const int BALANCED_BELOW_PERCENT = {};
+const int BATTERY_SAVER_BELOW_PERCENT = {};
+int base_intensity = {};
+bool base_power_save = {};
+int active_profile = -1;
+
+// Battery
+{}const int ADC_MAX = {};
+const int ADC_REFERENCE_MV = {};
+const int BATTERY_EMPTY_MV = {};
+const int BATTERY_FULL_MV = {};
+int battery_percent = -1; // -1 on mains power
+"#,
+            config.profile as u8,
+            settings[0].max_intensity, settings[1].max_intensity, settings[2].max_intensity,
+            settings[0].cpu_mhz, settings[1].cpu_mhz, settings[2].cpu_mhz,
+            BALANCED_BELOW_PERCENT,
+            BATTERY_SAVER_BELOW_PERCENT,
             config.mining_intensity,
-            config.power_save_mode
+            config.power_save_mode,
+            battery_pin,
+            adc_max,
+            adc_reference_mv,
+            BATTERY_EMPTY_MV,
+            BATTERY_FULL_MV
+        )
+    }
+
+    fn sketch_protocol(config: &ESPMiningConfig) -> String {
+        format!(r#"// Wire protocol: frame = type (u8) | payload length (u16 LE) | TLV fields,
+// each field = tag (u8) | length (u16 LE) | value. Unknown tags are skipped.
+const uint8_t PROTOCOL_VERSION = {};
+const uint8_t DEVICE_TYPE = {};
+const uint16_t MAX_FRAME_PAYLOAD = {};
+
+const uint8_t MSG_HELLO = 1;
+const uint8_t MSG_TASK_PUSH = 2;
+const uint8_t MSG_HEARTBEAT = 3;
+const uint8_t MSG_RESULT_SUBMIT = 4;
+const uint8_t MSG_CONFIG_UPDATE = 5;
+const uint8_t MSG_FIRMWARE_OFFER = 6;
+const uint8_t MSG_FIRMWARE_REPORT = 7;
+const uint8_t MSG_MESH_RELAY = 8;
+const uint8_t MSG_MESH_BATCH = 9;
+
+const uint8_t TAG_DEVICE_ID = 1;
+const uint8_t TAG_DEVICE_TYPE = 2;
+const uint8_t TAG_VERSION = 3;
+const uint8_t TAG_TASK_ID = 4;
+const uint8_t TAG_OPERATION = 5;
+const uint8_t TAG_DIFFICULTY = 6;
+const uint8_t TAG_NONCE_RANGE = 7;
+const uint8_t TAG_MAX_TIME = 8;
+const uint8_t TAG_TENSOR = 9;
+const uint8_t TAG_NONCE = 10;
+const uint8_t TAG_HASH = 11;
+const uint8_t TAG_COMPUTE_TIME = 12;
+const uint8_t TAG_UPTIME = 13;
+const uint8_t TAG_FREE_HEAP = 14;
+const uint8_t TAG_TEMPERATURE = 15;
+const uint8_t TAG_RSSI = 16;
+const uint8_t TAG_TASK_COUNTS = 17;
+const uint8_t TAG_INTENSITY = 18;
+const uint8_t TAG_POWER_SAVE = 19;
+const uint8_t TAG_HEARTBEAT_INTERVAL = 20;
+const uint8_t TAG_FIRMWARE_VERSION = 21;
+const uint8_t TAG_FIRMWARE_SIZE = 22;
+const uint8_t TAG_FIRMWARE_URL = 23;
+const uint8_t TAG_SUCCESS = 24;
+const uint8_t TAG_PEER = 25;
+const uint8_t TAG_FRAME = 26;
+const uint8_t TAG_MESH_ENTRY = 27;
+const uint8_t TAG_KERNEL = 28;
+const uint8_t TAG_BATTERY = 29;
+const uint8_t ENCODING_Q8_8 = 1;
+const uint16_t ESPNOW_MAX_PAYLOAD = {};
+const int MAX_KERNEL_OUTPUT = {};
+"#,
+            ESP_PROTOCOL_VERSION,
+            device_code(&config.device_type),
+            MAX_FRAME_PAYLOAD,
+            mesh::ESPNOW_MAX_PAYLOAD,
+            kernels::MAX_KERNEL_OUTPUT
         )
     }
 
@@ -436,6 +1427,88 @@ fixed_t fixed_multiply(fixed_t a, fixed_t b) {
 fixed_t fixed_add(fixed_t a, fixed_t b) {
     return a + b;
 }
+
+fixed_t fixed_saturate(int64_t v) {
+    return v > 32767 ? 32767 : (v < -32768 ? -32768 : (fixed_t)v);
+}
+
+// Piecewise-linear sigmoid, no float emulation
+fixed_t fixed_sigmoid(fixed_t x) {
+    int32_t a = x < 0 ? -(int32_t)x : x;
+    int32_t y;
+    if (a >= 1280) y = 256;
+    else if (a >= 608) y = (a >> 5) + 216;
+    else if (a >= 256) y = (a >> 3) + 160;
+    else y = (a >> 2) + 128;
+    return (fixed_t)(x < 0 ? 256 - y : y);
+}
+
+struct KernelOperand {
+    uint16_t rows, cols, row_stride, col_stride;
+};
+
+// Runs a kernel descriptor pushed with the task on Q8.8 inputs; returns the
+// number of output elements, or -1 if the descriptor can't be run
+int fixedKernelRun(const uint8_t* kernel, int kernelLen, const fixed_t** inputs, fixed_t* output) {
+    if (kernelLen < 4 || kernel[1] != 8) return -1;
+    int count = kernel[3];
+    if (count > 2 || kernelLen != 4 + (count + 1) * 8) return -1;
+    KernelOperand operands[3];
+    for (int i = 0; i <= count; i++) {
+        const uint8_t* p = kernel + 4 + i * 8;
+        operands[i].rows = p[0] | (p[1] << 8);
+        operands[i].cols = p[2] | (p[3] << 8);
+        operands[i].row_stride = p[4] | (p[5] << 8);
+        operands[i].col_stride = p[6] | (p[7] << 8);
+    }
+    const KernelOperand& a = operands[0];
+    const KernelOperand& b = operands[1];
+    const KernelOperand& out = operands[count];
+    if (out.rows * out.cols > MAX_KERNEL_OUTPUT) return -1;
+    
+    #define AT(op, r, c) ((r) * op.row_stride + (c) * op.col_stride)
+    switch (kernel[0]) {
+        case 1: // matrix_multiply
+            for (int r = 0; r < out.rows; r++) {
+                for (int c = 0; c < out.cols; c++) {
+                    int64_t acc = 0;
+                    for (int k = 0; k < a.cols; k++) {
+                        acc += (int32_t)inputs[0][AT(a, r, k)] * inputs[1][AT(b, k, c)];
+                    }
+                    output[AT(out, r, c)] = fixed_saturate(acc >> 8);
+                }
+            }
+            break;
+        case 2: // relu
+        case 3: // sigmoid
+        case 4: // vector_add
+            for (int r = 0; r < out.rows; r++) {
+                for (int c = 0; c < out.cols; c++) {
+                    fixed_t x = inputs[0][AT(a, r, c)];
+                    fixed_t y;
+                    if (kernel[0] == 2) y = x > 0 ? x : 0;
+                    else if (kernel[0] == 3) y = fixed_sigmoid(x);
+                    else y = fixed_saturate((int32_t)x + inputs[1][AT(b, r, c)]);
+                    output[AT(out, r, c)] = y;
+                }
+            }
+            break;
+        case 5: { // dot_product
+            int64_t acc = 0;
+            for (int r = 0; r < a.rows; r++) {
+                for (int c = 0; c < a.cols; c++) {
+                    acc += (int32_t)inputs[0][AT(a, r, c)] * inputs[1][AT(b, r, c)];
+                }
+            }
+            output[0] = fixed_saturate(acc >> 8);
+            break;
+        }
+        default:
+            return -1;
+    }
+    #undef AT
+    return out.rows * out.cols;
+}
 "#.to_string()
     }
 } 
\ No newline at end of file
@@ -1,6 +1,108 @@
 use serde::{Deserialize, Serialize};
 use crate::esp_compat::devices::ESPDeviceType;
 
+/// How a device talks to the mining network
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub enum ESPTransport {
+    /// Direct TCP connection to the mining server
+    #[default]
+    Tcp,
+    /// Through an MQTT broker; `server_address` and `server_port` point at the broker
+    Mqtt,
+    /// Direct TCP like `Tcp`, also relaying for ESP-NOW peers; ESP32 family only
+    MeshGateway,
+    /// Off WiFi, over ESP-NOW through a gateway on WiFi channel `channel`
+    MeshPeer { gateway_mac: String, channel: u8 },
+}
+
+/// Build system `ESPCodeGenerator::generate_project` emits
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub enum ESPProjectKind {
+    /// PlatformIO project around the generated Arduino sketch
+    #[default]
+    PlatformIO,
+    /// Rust crate on esp-idf-svc; not available for the ESP8266
+    EspIdfRust,
+}
+
+/// Where generated firmware gets its WiFi and server settings from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum ESPProvisioning {
+    /// Compiled into the sketch as string literals
+    #[default]
+    Embedded,
+    /// Read at boot from flash, sealed with a per-device key; the sketch holds no secrets
+    /// and waits on serial for a `provisioning::provisioning_command` line until it has them
+    Encrypted,
+}
+
+/// Trade-off between hash rate and battery life. Battery-powered devices step down to
+/// cheaper profiles as they drain; see `MiningProfile::for_battery`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Default)]
+pub enum MiningProfile {
+    #[default]
+    Performance,
+    Balanced,
+    BatterySaver,
+}
+
+/// Battery percentage below which a device drops to `Balanced`
+pub const BALANCED_BELOW_PERCENT: u8 = 50;
+
+/// Battery percentage below which a device drops to `BatterySaver`
+pub const BATTERY_SAVER_BELOW_PERCENT: u8 = 20;
+
+/// What a mining profile allows
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileSettings {
+    pub max_intensity: u8,
+    pub cpu_mhz: u32,
+    pub power_save: bool,
+    pub min_battery_percent: u8, // New tasks are refused below this charge
+    pub max_task_elements: Option<usize>, // Largest task accepted, in input elements
+}
+
+impl MiningProfile {
+    pub const ALL: [MiningProfile; 3] = [MiningProfile::Performance, MiningProfile::Balanced, MiningProfile::BatterySaver];
+
+    /// Profile to run at `battery_percent`: this one, or a cheaper one once the battery
+    /// drains; mains-powered devices (`None`) keep this one
+    pub fn for_battery(self, battery_percent: Option<u8>) -> Self {
+        match battery_percent {
+            Some(percent) if percent < BATTERY_SAVER_BELOW_PERCENT => MiningProfile::BatterySaver,
+            Some(percent) if percent < BALANCED_BELOW_PERCENT => self.max(MiningProfile::Balanced),
+            _ => self,
+        }
+    }
+
+    /// Settings on a device clocked at up to `clock_speed_mhz`
+    pub fn settings(&self, clock_speed_mhz: u32) -> ProfileSettings {
+        match self {
+            MiningProfile::Performance => ProfileSettings {
+                max_intensity: 10,
+                cpu_mhz: clock_speed_mhz,
+                power_save: false,
+                min_battery_percent: 5,
+                max_task_elements: None,
+            },
+            MiningProfile::Balanced => ProfileSettings {
+                max_intensity: 6,
+                cpu_mhz: clock_speed_mhz.min(160),
+                power_save: false,
+                min_battery_percent: 10,
+                max_task_elements: None,
+            },
+            MiningProfile::BatterySaver => ProfileSettings {
+                max_intensity: 2,
+                cpu_mhz: clock_speed_mhz.min(80),
+                power_save: true,
+                min_battery_percent: 15,
+                max_task_elements: Some(256),
+            },
+        }
+    }
+}
+
 /// ESP mining configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ESPMiningConfig {
@@ -8,10 +110,28 @@ pub struct ESPMiningConfig {
     pub max_memory_kb: usize,
     pub clock_speed_mhz: u32,
     pub wifi_ssid: String,
+    #[serde(skip)]
+    pub wifi_password: String, // Never serialized; empty for open networks
     pub server_address: String,
     pub server_port: u16,
     pub mining_intensity: u8, // 1-10 scale
     pub power_save_mode: bool,
+    #[serde(default)]
+    pub transport: ESPTransport,
+    #[serde(default = "default_firmware_version")]
+    pub firmware_version: String, // Version baked into generated firmware, compared against OTA offers
+    #[serde(default)]
+    pub project_kind: ESPProjectKind,
+    #[serde(default)]
+    pub profile: MiningProfile,
+    #[serde(default)]
+    pub battery_adc_pin: Option<u8>, // ADC pin reading the battery through a 1:2 divider; None on mains power
+    #[serde(default)]
+    pub provisioning: ESPProvisioning,
+}
+
+fn default_firmware_version() -> String {
+    "0.1.0".to_string()
 }
 
 impl Default for ESPMiningConfig {
@@ -21,10 +141,17 @@ impl Default for ESPMiningConfig {
             max_memory_kb: 320,
             clock_speed_mhz: 240,
             wifi_ssid: "TribeChain_Mining".to_string(),
+            wifi_password: String::new(),
             server_address: "192.168.1.100".to_string(),
             server_port: 8333,
             mining_intensity: 5,
             power_save_mode: false,
+            transport: ESPTransport::Tcp,
+            firmware_version: default_firmware_version(),
+            project_kind: ESPProjectKind::PlatformIO,
+            profile: MiningProfile::Performance,
+            battery_adc_pin: None,
+            provisioning: ESPProvisioning::Embedded,
         }
     }
 } 
\ No newline at end of file
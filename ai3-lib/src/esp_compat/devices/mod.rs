@@ -1,12 +1,14 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ESPDeviceType {
     ESP32,
     ESP8266,
     ESP32S2,
     ESP32S3,
     ESP32C3,
+    ESP32C6,
+    ESP32H2,
 }
 
 impl ESPDeviceType {
@@ -17,6 +19,8 @@ impl ESPDeviceType {
             ESPDeviceType::ESP32S2 => 320,    // 320KB RAM
             ESPDeviceType::ESP32S3 => 512,    // 512KB RAM
             ESPDeviceType::ESP32C3 => 400,    // 400KB RAM
+            ESPDeviceType::ESP32C6 => 512,    // 512KB HP SRAM
+            ESPDeviceType::ESP32H2 => 320,    // 320KB RAM
         }
     }
 
@@ -27,6 +31,21 @@ impl ESPDeviceType {
             ESPDeviceType::ESP32S2 => 240,    // 240MHz single core
             ESPDeviceType::ESP32S3 => 240,    // 240MHz dual core
             ESPDeviceType::ESP32C3 => 160,    // 160MHz single core
+            ESPDeviceType::ESP32C6 => 160,    // 160MHz single core
+            ESPDeviceType::ESP32H2 => 96,     // 96MHz single core
+        }
+    }
+
+    /// Peak draw while mining flat out
+    pub fn get_power_limit_mw(&self) -> u32 {
+        match self {
+            ESPDeviceType::ESP32 => 1000,
+            ESPDeviceType::ESP8266 => 600,
+            ESPDeviceType::ESP32S2 => 800,
+            ESPDeviceType::ESP32S3 => 1100,
+            ESPDeviceType::ESP32C3 => 700,
+            ESPDeviceType::ESP32C6 => 750,
+            ESPDeviceType::ESP32H2 => 250,
         }
     }
 
@@ -37,6 +56,17 @@ impl ESPDeviceType {
             ESPDeviceType::ESP32S2 => true,
             ESPDeviceType::ESP32S3 => true,
             ESPDeviceType::ESP32C3 => true,
+            ESPDeviceType::ESP32C6 => false,  // RV32IMAC, no FPU
+            ESPDeviceType::ESP32H2 => false,  // RV32IMAC, no FPU
         }
     }
+
+    pub fn is_riscv(&self) -> bool {
+        matches!(self, ESPDeviceType::ESP32C3 | ESPDeviceType::ESP32C6 | ESPDeviceType::ESP32H2)
+    }
+
+    /// The H2 only has 802.15.4 and BLE radios
+    pub fn has_wifi(&self) -> bool {
+        *self != ESPDeviceType::ESP32H2
+    }
 } 
\ No newline at end of file
@@ -0,0 +1,223 @@
+use crate::esp_compat::protocol::{operation_code, operation_name};
+use crate::mining::MiningTask;
+use crate::tensor::Tensor;
+use tribechain_core::{TribeResult, TribeError};
+
+/// Fractional bits of the Q8.8 format kernels run in
+pub const FRAC_BITS: u8 = 8;
+
+/// Largest kernel output the firmware buffers, in elements
+pub const MAX_KERNEL_OUTPUT: usize = 512;
+
+/// Q8.8 value of `x`, saturating at the format's range
+pub fn to_fixed(x: f32) -> i16 {
+    (x * 256.0).clamp(-32768.0, 32767.0) as i16
+}
+
+/// A tensor's elements as Q8.8, the way they're sent to fixed-point devices
+pub fn quantize(tensor: &Tensor) -> TribeResult<Vec<i16>> {
+    Ok(tensor.data.as_f32_vec()?.into_iter().map(to_fixed).collect())
+}
+
+/// One operand viewed as a matrix: element (r, c) sits at `r * row_stride + c * col_stride`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KernelOperand {
+    pub rows: u16,
+    pub cols: u16,
+    pub row_stride: u16,
+    pub col_stride: u16,
+}
+
+impl KernelOperand {
+    fn contiguous(rows: u16, cols: u16) -> Self {
+        Self { rows, cols, row_stride: cols, col_stride: 1 }
+    }
+
+    fn elements(&self) -> usize {
+        self.rows as usize * self.cols as usize
+    }
+
+    fn index(&self, row: usize, col: usize) -> usize {
+        row * self.row_stride as usize + col * self.col_stride as usize
+    }
+
+    /// Elements a buffer needs for every index of this view
+    fn span(&self) -> usize {
+        if self.elements() == 0 {
+            0
+        } else {
+            self.index(self.rows as usize - 1, self.cols as usize - 1) + 1
+        }
+    }
+}
+
+/// What a fixed-point device runs for a task, so it never emulates floats: the
+/// operation and the layout of its Q8.8 inputs and output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KernelDescriptor {
+    pub operation: String,
+    pub inputs: Vec<KernelOperand>,
+    pub output: KernelOperand,
+    pub output_rank: u8, // Rank the output tensor is reported with, 1 or 2
+}
+
+fn kernel_error(message: String) -> TribeError {
+    TribeError::Esp(message)
+}
+
+/// A rank 1 or 2 tensor's shape as (rows, cols)
+fn as_matrix(tensor: &Tensor) -> TribeResult<(u16, u16)> {
+    let dims = &tensor.shape.dimensions;
+    let (rows, cols) = match dims.as_slice() {
+        [cols] => (1, *cols),
+        [rows, cols] => (*rows, *cols),
+        _ => return Err(kernel_error(format!("No fixed-point kernel for rank {} tensors", dims.len()))),
+    };
+    match (u16::try_from(rows), u16::try_from(cols)) {
+        (Ok(rows), Ok(cols)) => Ok((rows, cols)),
+        _ => Err(kernel_error("Tensor too large for a fixed-point kernel".to_string())),
+    }
+}
+
+fn saturate(value: i64) -> i16 {
+    value.clamp(i16::MIN as i64, i16::MAX as i64) as i16
+}
+
+/// Piecewise-linear sigmoid in Q8.8, matching the firmware's
+fn fixed_sigmoid(x: i16) -> i16 {
+    let a = (x as i32).abs();
+    let y = if a >= 1280 {
+        256
+    } else if a >= 608 {
+        (a >> 5) + 216
+    } else if a >= 256 {
+        (a >> 3) + 160
+    } else {
+        (a >> 2) + 128
+    };
+    (if x < 0 { 256 - y } else { y }) as i16
+}
+
+impl KernelDescriptor {
+    /// Descriptor for `task`'s inputs, laid out row-major
+    pub fn for_task(task: &MiningTask) -> TribeResult<Self> {
+        let shapes = task.input_tensors.iter().map(as_matrix).collect::<TribeResult<Vec<_>>>()?;
+        let arity = match task.operation_type.as_str() {
+            "relu" | "sigmoid" => 1,
+            "vector_add" | "dot_product" | "matrix_multiply" => 2,
+            operation => return Err(kernel_error(format!("No fixed-point kernel for {}", operation))),
+        };
+        if shapes.len() != arity {
+            return Err(kernel_error(format!("{} takes {} inputs, got {}", task.operation_type, arity, shapes.len())));
+        }
+
+        let output = match task.operation_type.as_str() {
+            "vector_add" | "dot_product" if shapes[0] != shapes[1] => {
+                return Err(kernel_error("Input shapes don't match".to_string()));
+            }
+            "matrix_multiply" if shapes[0].1 != shapes[1].0 => {
+                return Err(kernel_error("Matrix dimensions incompatible".to_string()));
+            }
+            "matrix_multiply" => (shapes[0].0, shapes[1].1),
+            "dot_product" => (1, 1),
+            _ => shapes[0],
+        };
+        let output = KernelOperand::contiguous(output.0, output.1);
+        if output.elements() > MAX_KERNEL_OUTPUT {
+            return Err(kernel_error(format!("Kernel output of {} elements is over the device limit", output.elements())));
+        }
+
+        let output_rank = match task.operation_type.as_str() {
+            "dot_product" => 1,
+            "matrix_multiply" => 2,
+            _ => task.input_tensors[0].shape.dimensions.len() as u8,
+        };
+        Ok(Self {
+            operation: task.operation_type.clone(),
+            inputs: shapes.into_iter().map(|(rows, cols)| KernelOperand::contiguous(rows, cols)).collect(),
+            output,
+            output_rank,
+        })
+    }
+
+    /// `operation | frac bits | output rank | input count | operands`, each operand
+    /// `rows | cols | row stride | col stride` as u16 LE, inputs then output
+    pub fn encode(&self) -> TribeResult<Vec<u8>> {
+        let mut bytes = vec![operation_code(&self.operation)?, FRAC_BITS, self.output_rank, self.inputs.len() as u8];
+        for operand in self.inputs.iter().chain(std::iter::once(&self.output)) {
+            for value in [operand.rows, operand.cols, operand.row_stride, operand.col_stride] {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        Ok(bytes)
+    }
+
+    pub fn decode(bytes: &[u8]) -> TribeResult<Self> {
+        if bytes.len() < 4 || bytes[1] != FRAC_BITS {
+            return Err(kernel_error("Invalid kernel descriptor".to_string()));
+        }
+        let count = bytes[3] as usize;
+        if bytes.len() != 4 + (count + 1) * 8 {
+            return Err(kernel_error("Kernel descriptor length does not match its operands".to_string()));
+        }
+        let mut operands: Vec<KernelOperand> = bytes[4..].chunks_exact(8)
+            .map(|p| KernelOperand {
+                rows: u16::from_le_bytes([p[0], p[1]]),
+                cols: u16::from_le_bytes([p[2], p[3]]),
+                row_stride: u16::from_le_bytes([p[4], p[5]]),
+                col_stride: u16::from_le_bytes([p[6], p[7]]),
+            })
+            .collect();
+        let output = operands.pop().expect("output operand");
+        Ok(Self { operation: operation_name(bytes[0])?, inputs: operands, output, output_rank: bytes[2] })
+    }
+
+    /// Run the kernel on Q8.8 inputs exactly as the firmware does, to check device results
+    pub fn execute(&self, inputs: &[Vec<i16>]) -> TribeResult<Vec<i16>> {
+        if inputs.len() != self.inputs.len() {
+            return Err(kernel_error(format!("Kernel takes {} inputs, got {}", self.inputs.len(), inputs.len())));
+        }
+        for (input, operand) in inputs.iter().zip(&self.inputs) {
+            if input.len() < operand.span() {
+                return Err(kernel_error("Kernel input is shorter than its operand".to_string()));
+            }
+        }
+
+        let out = self.output;
+        let mut output = vec![0i16; out.span()];
+        let elementwise = |output: &mut Vec<i16>, f: &dyn Fn(usize, usize) -> i16| {
+            for r in 0..out.rows as usize {
+                for c in 0..out.cols as usize {
+                    output[out.index(r, c)] = f(r, c);
+                }
+            }
+        };
+        let a = |r: usize, c: usize| inputs[0][self.inputs[0].index(r, c)] as i64;
+        let b = |r: usize, c: usize| inputs[1][self.inputs[1].index(r, c)] as i64;
+
+        match self.operation.as_str() {
+            "relu" => elementwise(&mut output, &|r, c| (a(r, c) as i16).max(0)),
+            "sigmoid" => elementwise(&mut output, &|r, c| fixed_sigmoid(a(r, c) as i16)),
+            "vector_add" => elementwise(&mut output, &|r, c| saturate(a(r, c) + b(r, c))),
+            "dot_product" => {
+                let a_op = self.inputs[0];
+                let mut acc = 0i64;
+                for r in 0..a_op.rows as usize {
+                    for c in 0..a_op.cols as usize {
+                        acc += a(r, c) * b(r, c);
+                    }
+                }
+                output[0] = saturate(acc >> FRAC_BITS);
+            }
+            "matrix_multiply" => {
+                let inner = self.inputs[0].cols as usize;
+                elementwise(&mut output, &|r, c| {
+                    let acc: i64 = (0..inner).map(|k| a(r, k) * b(k, c)).sum();
+                    saturate(acc >> FRAC_BITS)
+                });
+            }
+            operation => return Err(kernel_error(format!("No fixed-point kernel for {}", operation))),
+        }
+        Ok(output)
+    }
+}
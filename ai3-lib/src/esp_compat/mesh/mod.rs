@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+use crate::esp_compat::protocol::ESPMessage;
+use tribechain_core::{TribeResult, TribeError};
+
+/// Largest ESP-NOW payload; every frame relayed to a peer must fit in one
+pub const ESPNOW_MAX_PAYLOAD: usize = 250;
+
+/// Peer frames a gateway buffers between batches; 15 full entries fit one frame
+pub const MAX_BATCH_ENTRIES: usize = 15;
+
+/// MAC in the `AA:BB:CC:DD:EE:FF` form devices use as their id
+pub fn format_mac(mac: &[u8; 6]) -> String {
+    mac.iter().map(|byte| format!("{:02X}", byte)).collect::<Vec<_>>().join(":")
+}
+
+pub fn parse_mac(mac: &str) -> TribeResult<[u8; 6]> {
+    let bytes = mac.split(':')
+        .map(|part| u8::from_str_radix(part, 16))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| TribeError::Esp(format!("Invalid MAC address {}", mac)))?;
+    bytes.try_into().map_err(|_| TribeError::Esp(format!("Invalid MAC address {}", mac)))
+}
+
+/// Routes to ESP-NOW peers that reach the network through a WiFi gateway
+#[derive(Debug, Clone, Default)]
+pub struct MeshNetwork {
+    pub routes: HashMap<String, String>, // peer id -> gateway id
+}
+
+impl MeshNetwork {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Split a gateway's batch into per-peer messages. A peer's hello routes it through
+    /// this gateway; other messages from peers without that route, or that fail to
+    /// decode, are dropped without affecting the rest of the batch.
+    pub fn unpack(&mut self, gateway_id: &str, batch: ESPMessage) -> TribeResult<Vec<(String, ESPMessage)>> {
+        let ESPMessage::MeshBatch { entries } = batch else {
+            return Err(TribeError::Esp("Not a mesh batch".to_string()));
+        };
+
+        let mut messages = Vec::new();
+        for (mac, frame) in entries {
+            let peer_id = format_mac(&mac);
+            let Ok(message) = ESPMessage::from_frame(&frame) else { continue };
+            match &message {
+                ESPMessage::Hello { device_id, .. } if *device_id == peer_id => {
+                    self.routes.insert(peer_id.clone(), gateway_id.to_string());
+                }
+                ESPMessage::Hello { .. } => continue,
+                _ if self.routes.get(&peer_id).map(String::as_str) != Some(gateway_id) => continue,
+                _ => {}
+            }
+            messages.push((peer_id, message));
+        }
+        Ok(messages)
+    }
+
+    /// Wrap an encoded frame for `peer_id` in a relay frame, returning the gateway to send it to
+    pub fn relay(&self, peer_id: &str, frame: Vec<u8>) -> TribeResult<(String, Vec<u8>)> {
+        let gateway = self.routes.get(peer_id)
+            .ok_or_else(|| TribeError::Esp(format!("No mesh route to {}", peer_id)))?;
+        if frame.len() > ESPNOW_MAX_PAYLOAD {
+            return Err(TribeError::Esp(format!(
+                "Frame of {} bytes does not fit in an ESP-NOW packet", frame.len()
+            )));
+        }
+        let relay = ESPMessage::MeshRelay { peer: parse_mac(peer_id)?, frame }.encode(false)?;
+        Ok((gateway.clone(), relay))
+    }
+
+    pub fn peers_of(&self, gateway_id: &str) -> Vec<&String> {
+        self.routes.iter()
+            .filter(|(_, gateway)| *gateway == gateway_id)
+            .map(|(peer, _)| peer)
+            .collect()
+    }
+
+    /// Drop a disconnected gateway's routes; its peers say hello again through another
+    pub fn remove_gateway(&mut self, gateway_id: &str) {
+        self.routes.retain(|_, gateway| gateway != gateway_id);
+    }
+}
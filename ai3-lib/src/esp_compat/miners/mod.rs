@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 use crate::mining::{AI3Miner, MiningTask, MiningResult, MinerStats};
-use crate::esp_compat::{devices::ESPDeviceType, config::ESPMiningConfig};
+use crate::esp_compat::{devices::ESPDeviceType, config::{ESPMiningConfig, MiningProfile}, protocol::ESPMessage};
+use crate::esp_compat::tiling::TiledExecutor;
 use tribechain_core::{TribeResult, TribeError};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -11,6 +12,26 @@ pub enum ConnectionStatus {
     Error(String),
 }
 
+/// Battery voltage the firmware counts as empty, and as full
+pub const BATTERY_EMPTY_MV: u16 = 3300;
+pub const BATTERY_FULL_MV: u16 = 4200;
+
+/// Charge a battery-powered device reported
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatteryState {
+    pub voltage_mv: u16,
+    pub percent: u8,
+}
+
+impl BatteryState {
+    /// Charge of a single-cell LiPo at `voltage_mv`, linear between empty and full
+    pub fn from_voltage(voltage_mv: u16) -> Self {
+        let span = (BATTERY_FULL_MV - BATTERY_EMPTY_MV) as u32;
+        let charged = voltage_mv.clamp(BATTERY_EMPTY_MV, BATTERY_FULL_MV) - BATTERY_EMPTY_MV;
+        Self { voltage_mv, percent: (charged as u32 * 100 / span) as u8 }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ESPPerformanceStats {
     pub uptime_seconds: u64,
@@ -18,9 +39,55 @@ pub struct ESPPerformanceStats {
     pub cpu_temperature: f32,
     pub wifi_signal_strength: i8, // dBm
     pub power_consumption_mw: u32,
+    #[serde(default)]
+    pub power_limit_mw: u32, // Most the device may draw; 0 when unknown
     pub hash_rate: f64, // hashes per second
     pub successful_tasks: u64,
     pub failed_tasks: u64,
+    #[serde(default)]
+    pub battery: Option<BatteryState>, // None on mains power
+}
+
+/// Telemetry metric names the default alert thresholds watch
+pub const TEMPERATURE_METRIC: &str = "temperature_c";
+pub const FAILED_TASK_RATE_METRIC: &str = "failed_task_rate";
+
+impl ESPPerformanceStats {
+    /// Stats for a device that hasn't reported yet
+    pub fn new(device_type: &ESPDeviceType) -> Self {
+        Self {
+            uptime_seconds: 0,
+            memory_usage_kb: 0,
+            cpu_temperature: 25.0,
+            wifi_signal_strength: -50,
+            power_consumption_mw: 500,
+            power_limit_mw: device_type.get_power_limit_mw(),
+            hash_rate: 0.0,
+            successful_tasks: 0,
+            failed_tasks: 0,
+            battery: None,
+        }
+    }
+
+    /// Metrics to record in a `TelemetryStore`
+    pub fn telemetry_metrics(&self) -> Vec<(String, f64)> {
+        let finished = self.successful_tasks + self.failed_tasks;
+        let failed_task_rate = if finished == 0 { 0.0 } else { self.failed_tasks as f64 / finished as f64 };
+        let mut metrics = vec![
+            (TEMPERATURE_METRIC.to_string(), self.cpu_temperature as f64),
+            (FAILED_TASK_RATE_METRIC.to_string(), failed_task_rate),
+            ("power_mw".to_string(), self.power_consumption_mw as f64),
+            ("memory_kb".to_string(), self.memory_usage_kb as f64),
+            ("wifi_rssi_dbm".to_string(), self.wifi_signal_strength as f64),
+            ("hash_rate".to_string(), self.hash_rate),
+            ("uptime_seconds".to_string(), self.uptime_seconds as f64),
+        ];
+        if let Some(battery) = &self.battery {
+            metrics.push(("battery_mv".to_string(), battery.voltage_mv as f64));
+            metrics.push(("battery_percent".to_string(), battery.percent as f64));
+        }
+        metrics
+    }
 }
 
 /// ESP32 specific miner implementation
@@ -35,21 +102,13 @@ pub struct ESP32Miner {
 impl ESP32Miner {
     pub fn new(id: String, address: String, config: ESPMiningConfig) -> Self {
         let base_miner = AI3Miner::new(id, address, true);
+        let performance_stats = ESPPerformanceStats::new(&config.device_type);
         
         Self {
             base_miner,
             config,
             connection_status: ConnectionStatus::Disconnected,
-            performance_stats: ESPPerformanceStats {
-                uptime_seconds: 0,
-                memory_usage_kb: 0,
-                cpu_temperature: 25.0,
-                wifi_signal_strength: -50,
-                power_consumption_mw: 500,
-                hash_rate: 0.0,
-                successful_tasks: 0,
-                failed_tasks: 0,
-            },
+            performance_stats,
         }
     }
 
@@ -68,7 +127,7 @@ impl ESP32Miner {
     fn check_memory_constraints(&self) -> TribeResult<()> {
         let available_memory = self.config.device_type.get_memory_limit();
         if self.config.max_memory_kb > available_memory {
-            return Err(TribeError::InvalidOperation(
+            return Err(TribeError::Esp(
                 format!("Requested memory {}KB exceeds device limit {}KB", 
                        self.config.max_memory_kb, available_memory)
             ));
@@ -79,7 +138,7 @@ impl ESP32Miner {
     fn configure_wifi(&mut self) -> TribeResult<()> {
         // Simulate WiFi configuration
         if self.config.wifi_ssid.is_empty() {
-            return Err(TribeError::InvalidOperation("WiFi SSID cannot be empty".to_string()));
+            return Err(TribeError::Esp("WiFi SSID cannot be empty".to_string()));
         }
         
         // Update signal strength (simulated)
@@ -90,16 +149,57 @@ impl ESP32Miner {
     fn connect_to_server(&mut self) -> TribeResult<()> {
         // Simulate server connection
         if self.config.server_address.is_empty() {
-            return Err(TribeError::InvalidOperation("Server address cannot be empty".to_string()));
+            return Err(TribeError::Esp("Server address cannot be empty".to_string()));
         }
         
         // In real implementation, this would establish TCP connection
         Ok(())
     }
 
+    /// Take `task` if its operation fits the device, tiled if its tensors don't fit at once
+    pub fn assign_task(&mut self, task: MiningTask) -> TribeResult<()> {
+        if !self.base_miner.is_active || !self.base_miner.capabilities.supported_operations.contains(&task.operation_type) {
+            return Err(TribeError::Esp("Miner cannot handle this task".to_string()));
+        }
+        self.check_battery_for(&task)?;
+        TiledExecutor::new(&self.config).plan(&task)?;
+        self.base_miner.start_task(task);
+        Ok(())
+    }
+
+    /// Configured profile, stepped down for the last reported battery charge
+    pub fn active_profile(&self) -> MiningProfile {
+        self.config.profile.for_battery(self.performance_stats.battery.map(|battery| battery.percent))
+    }
+
+    /// Intensity to mine at: the configured one, capped by the active profile
+    pub fn effective_intensity(&self) -> u8 {
+        let settings = self.active_profile().settings(self.config.clock_speed_mhz);
+        self.config.mining_intensity.min(settings.max_intensity)
+    }
+
+    fn check_battery_for(&self, task: &MiningTask) -> TribeResult<()> {
+        let profile = self.active_profile();
+        let settings = profile.settings(self.config.clock_speed_mhz);
+        if let Some(battery) = &self.performance_stats.battery {
+            if battery.percent < settings.min_battery_percent {
+                return Err(TribeError::Esp(format!(
+                    "Battery at {}%, below the {}% {:?} needs to take tasks", battery.percent, settings.min_battery_percent, profile
+                )));
+            }
+        }
+        let elements: usize = task.input_tensors.iter().map(|t| t.shape.total_elements()).sum();
+        match settings.max_task_elements {
+            Some(max) if elements > max => Err(TribeError::Esp(format!(
+                "Task of {} elements is over the {} {:?} takes", elements, max, profile
+            ))),
+            _ => Ok(()),
+        }
+    }
+
     pub fn mine_step(&mut self) -> TribeResult<Option<MiningResult>> {
         if !matches!(self.connection_status, ConnectionStatus::Connected) {
-            return Err(TribeError::InvalidOperation("Not connected to server".to_string()));
+            return Err(TribeError::Esp("Not connected to server".to_string()));
         }
 
         // Update performance stats
@@ -110,7 +210,14 @@ impl ESP32Miner {
             return Ok(None);
         }
 
-        // Delegate to base miner with ESP optimizations
+        // Run the operation with this device's kernel before the base miner's nonce search
+        if let Some(task) = &self.base_miner.current_task {
+            if self.base_miner.partial_output.is_none() && !task.is_expired() {
+                let (output, plan) = TiledExecutor::new(&self.config).execute(task)?;
+                self.performance_stats.memory_usage_kb = plan.peak_bytes.div_ceil(1024);
+                self.base_miner.partial_output = Some(output);
+            }
+        }
         let result = self.base_miner.mine_step()?;
         
         if let Some(ref mining_result) = result {
@@ -126,14 +233,14 @@ impl ESP32Miner {
         
         // Simulate temperature based on mining intensity
         let base_temp = 25.0;
-        let temp_increase = (self.config.mining_intensity as f32) * 2.0;
+        let temp_increase = (self.effective_intensity() as f32) * 2.0;
         self.performance_stats.cpu_temperature = base_temp + temp_increase;
         
         // Simulate memory usage
         self.performance_stats.memory_usage_kb = (self.config.max_memory_kb as f32 * 0.7) as usize;
     }
 
-    fn should_throttle(&self) -> bool {
+    pub fn should_throttle(&self) -> bool {
         // Throttle if temperature is too high
         if self.performance_stats.cpu_temperature > 80.0 {
             return true;
@@ -188,9 +295,41 @@ impl ESP32Miner {
 
         Ok(())
     }
+
+    /// Fold a heartbeat or config update from the wire protocol into this miner's view of the device
+    pub fn apply_message(&mut self, message: &ESPMessage) {
+        match message {
+            ESPMessage::Heartbeat { uptime_seconds, free_heap, temperature_c, wifi_rssi, successful_tasks, failed_tasks, battery } => {
+                self.performance_stats.uptime_seconds = *uptime_seconds as u64;
+                self.performance_stats.memory_usage_kb = self.config.max_memory_kb.saturating_sub(*free_heap as usize / 1024);
+                self.performance_stats.cpu_temperature = *temperature_c;
+                self.performance_stats.wifi_signal_strength = *wifi_rssi;
+                self.performance_stats.successful_tasks = *successful_tasks as u64;
+                self.performance_stats.failed_tasks = *failed_tasks as u64;
+                self.performance_stats.battery = *battery;
+                self.connection_status = ConnectionStatus::Connected;
+            }
+            ESPMessage::ConfigUpdate { mining_intensity, power_save_mode, .. } => {
+                self.config.mining_intensity = *mining_intensity;
+                self.config.power_save_mode = *power_save_mode;
+            }
+            _ => {}
+        }
+    }
+
+    /// Config update frame carrying this miner's current settings, as its active profile allows
+    pub fn config_update(&self, heartbeat_interval_secs: u16) -> TribeResult<Vec<u8>> {
+        let settings = self.active_profile().settings(self.config.clock_speed_mhz);
+        ESPMessage::ConfigUpdate {
+            mining_intensity: self.effective_intensity(),
+            power_save_mode: self.config.power_save_mode || settings.power_save,
+            heartbeat_interval_secs,
+        }.encode(false)
+    }
 }
 
 /// ESP8266 specific miner (simplified ESP32)
+#[derive(Debug, Clone)]
 pub struct ESP8266Miner {
     pub esp32_miner: ESP32Miner,
 }
@@ -216,6 +355,10 @@ impl ESP8266Miner {
         Ok(())
     }
 
+    pub fn assign_task(&mut self, task: MiningTask) -> TribeResult<()> {
+        self.esp32_miner.assign_task(task)
+    }
+
     pub fn mine_step(&mut self) -> TribeResult<Option<MiningResult>> {
         self.esp32_miner.mine_step()
     }
@@ -5,16 +5,32 @@ pub mod devices;
 pub mod config;
 pub mod miners;
 pub mod codegen;
+pub mod protocol;
+pub mod mqtt;
+pub mod ota;
+pub mod project;
+pub mod mesh;
+pub mod power;
+pub mod kernels;
+pub mod tiling;
+pub mod provisioning;
 pub mod tests;
 
 // Re-export key types for convenience
 pub use devices::ESPDeviceType;
-pub use config::ESPMiningConfig;
-pub use miners::{ESP32Miner, ESP8266Miner, ConnectionStatus, ESPPerformanceStats};
+pub use config::{ESPMiningConfig, ESPTransport, ESPProjectKind, ESPProvisioning, MiningProfile, ProfileSettings};
+pub use miners::{ESP32Miner, ESP8266Miner, ConnectionStatus, ESPPerformanceStats, BatteryState, TEMPERATURE_METRIC, FAILED_TASK_RATE_METRIC};
 pub use codegen::{ESPTensorUtils, ESPCodeGenerator};
+pub use protocol::{ESPMessage, ESPFrameDecoder, ESP_PROTOCOL_VERSION};
+pub use mqtt::{MqttBridge, MqttPublish};
+pub use ota::{OtaManager, FirmwareImage, RolloutPolicy, RolloutStage};
+pub use mesh::MeshNetwork;
+pub use power::{FleetPowerScheduler, PowerPolicy};
+pub use kernels::{KernelDescriptor, KernelOperand};
+pub use tiling::{TiledExecutor, TilePlan, TileKernel};
+pub use provisioning::DeviceSecrets;
 
 use crate::tensor::Tensor;
-use crate::mining::{AI3Miner, MinerCapabilities, MinerStats};
 
 /// Main ESP compatibility interface
 pub struct ESPCompatibility;
@@ -22,7 +38,7 @@ pub struct ESPCompatibility;
 impl ESPCompatibility {
     /// Check if a tensor operation is compatible with ESP devices
     pub fn is_esp_compatible(tensor: &Tensor, operation: &str, device_type: &ESPDeviceType) -> bool {
-        ESPTensorUtils::can_run_on_esp(&[tensor.clone()], operation, device_type)
+        ESPTensorUtils::can_run_on_esp(std::slice::from_ref(tensor), operation, device_type)
     }
 
     /// Optimize tensor for ESP device constraints
@@ -42,10 +58,12 @@ impl ESPCompatibility {
 
     /// Get recommended configuration for device type
     pub fn get_recommended_config(device_type: ESPDeviceType) -> ESPMiningConfig {
-        let mut config = ESPMiningConfig::default();
-        config.device_type = device_type.clone();
-        config.max_memory_kb = device_type.get_memory_limit();
-        config.clock_speed_mhz = device_type.get_compute_power();
+        let mut config = ESPMiningConfig {
+            device_type: device_type.clone(),
+            max_memory_kb: device_type.get_memory_limit(),
+            clock_speed_mhz: device_type.get_compute_power() as u32,
+            ..Default::default()
+        };
         
         // Adjust settings based on device capabilities
         match device_type {
@@ -69,17 +87,35 @@ impl ESPCompatibility {
                 config.mining_intensity = 4; // Conservative for RISC-V
                 config.power_save_mode = false;
             },
+            ESPDeviceType::ESP32C6 => {
+                config.mining_intensity = 4; // Fixed-point kernels, like the C3 it replaces
+                config.power_save_mode = false;
+            },
+            ESPDeviceType::ESP32H2 => {
+                config.mining_intensity = 2; // Slow core, usually battery powered
+                config.power_save_mode = true;
+            },
         }
         
         config
     }
 
-    /// Create an ESP miner instance
-    pub fn create_miner(config: ESPMiningConfig) -> Box<dyn AI3Miner> {
-        match config.device_type {
-            ESPDeviceType::ESP8266 => Box::new(ESP8266Miner::new(config)),
-            _ => Box::new(ESP32Miner::new(config)),
-        }
+    /// Host a signed firmware image and start rolling it out under the manager's policy
+    pub fn publish_firmware(
+        ota: &mut OtaManager,
+        device_type: ESPDeviceType,
+        image: Vec<u8>,
+        version: &str,
+        signature: &str,
+    ) -> tribechain_core::TribeResult<()> {
+        ota.publish(device_type, image, version, signature)
+    }
+
+    /// Telemetry store alerting on hot devices and ones failing a quarter of their tasks
+    pub fn telemetry_store() -> tribechain_core::TelemetryStore {
+        tribechain_core::TelemetryStore::new()
+            .with_threshold(TEMPERATURE_METRIC, 75.0)
+            .with_threshold(FAILED_TASK_RATE_METRIC, 0.25)
     }
 
     /// Estimate mining performance for device
@@ -97,8 +133,13 @@ impl ESPCompatibility {
         
         let memory_factor = if device_type.get_memory_limit() < 100 { 0.8 } else { 1.0 };
         let fp_factor = if device_type.supports_floating_point() { 1.0 } else { 0.6 };
+        // Newer RISC-V cores do more per clock than the C3's
+        let core_factor = match device_type {
+            ESPDeviceType::ESP32C6 | ESPDeviceType::ESP32H2 => 1.2,
+            _ => 1.0,
+        };
         
-        base_performance * operation_multiplier * memory_factor * fp_factor
+        base_performance * operation_multiplier * memory_factor * fp_factor * core_factor
     }
 
     /// Get device limitations and recommendations
@@ -106,7 +147,7 @@ impl ESPCompatibility {
         DeviceInfo {
             device_type: device_type.clone(),
             memory_limit_kb: device_type.get_memory_limit(),
-            compute_power_mhz: device_type.get_compute_power(),
+            compute_power_mhz: device_type.get_compute_power() as usize,
             supports_floating_point: device_type.supports_floating_point(),
             recommended_operations: Self::get_recommended_operations(device_type),
             limitations: Self::get_device_limitations(device_type),
@@ -147,6 +188,10 @@ impl ESPCompatibility {
             limitations.push("Limited compute power - prefer simple operations".to_string());
         }
 
+        if !device_type.has_wifi() {
+            limitations.push("No Wi-Fi radio - reach the network through a border router".to_string());
+        }
+
         limitations
     }
 }
@@ -239,6 +284,18 @@ pub mod utils {
                     "-march=rv32imc".to_string(), // RISC-V specific
                 ]);
             },
+            ESPDeviceType::ESP32C6 => {
+                flags.extend(vec![
+                    "-DESP32C6".to_string(),
+                    "-march=rv32imac".to_string(), // Adds atomics over the C3
+                ]);
+            },
+            ESPDeviceType::ESP32H2 => {
+                flags.extend(vec![
+                    "-DESP32H2".to_string(),
+                    "-march=rv32imac".to_string(),
+                ]);
+            },
         }
 
         flags
@@ -262,6 +319,12 @@ pub mod utils {
             ESPDeviceType::ESP32C3 => {
                 "Use esp32c3.common.ld with RISC-V optimizations".to_string()
             },
+            ESPDeviceType::ESP32C6 => {
+                "Use esp32c6.common.ld; keep LP SRAM free for the low-power core".to_string()
+            },
+            ESPDeviceType::ESP32H2 => {
+                "Use esp32h2.common.ld with a 2MB or larger flash partition table".to_string()
+            },
         }
     }
 } 
\ No newline at end of file
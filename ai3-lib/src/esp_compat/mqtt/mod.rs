@@ -0,0 +1,239 @@
+use std::collections::HashMap;
+use crate::esp_compat::ESPCompatibility;
+use crate::esp_compat::devices::ESPDeviceType;
+use crate::esp_compat::miners::ESP32Miner;
+use crate::esp_compat::protocol::ESPMessage;
+use crate::esp_compat::ota::OtaManager;
+use crate::mining::{MiningTaskKind, TaskDistributor};
+use tribechain_core::{TelemetryStore, TribeResult, TribeError};
+
+/// Root of every topic the fleet uses
+pub const TOPIC_ROOT: &str = "tribechain";
+
+/// Heartbeat interval sent to devices when they say hello
+pub const DEFAULT_HEARTBEAT_INTERVAL_SECS: u16 = 30;
+
+/// Miner id tasks are held under in the distributor while offered on a topic
+pub const MQTT_POOL_PREFIX: &str = "mqtt:";
+
+/// Suffix of capabilities for devices that take Q8.8 tensors
+const FIXED_POINT_SUFFIX: &str = "_q8";
+
+/// Where devices pick up tasks for one capability
+pub fn task_topic(capability: &str) -> String {
+    format!("{}/tasks/{}", TOPIC_ROOT, capability)
+}
+
+pub fn hello_topic(device_id: &str) -> String {
+    format!("{}/devices/{}/hello", TOPIC_ROOT, device_id)
+}
+
+/// Per-device config updates, published retained
+pub fn config_topic(device_id: &str) -> String {
+    format!("{}/devices/{}/config", TOPIC_ROOT, device_id)
+}
+
+pub fn result_topic(device_id: &str) -> String {
+    format!("{}/results/{}", TOPIC_ROOT, device_id)
+}
+
+pub fn telemetry_topic(device_id: &str) -> String {
+    format!("{}/telemetry/{}", TOPIC_ROOT, device_id)
+}
+
+/// Topics the server side subscribes to
+pub fn server_subscriptions() -> Vec<String> {
+    vec![hello_topic("+"), result_topic("+"), telemetry_topic("+")]
+}
+
+/// Capabilities a device subscribes to: its recommended operations, with the
+/// fixed-point suffix for devices without an FPU
+pub fn capabilities(device_type: &ESPDeviceType) -> Vec<String> {
+    let suffix = if device_type.supports_floating_point() { "" } else { FIXED_POINT_SUFFIX };
+    ESPCompatibility::get_device_info(device_type).recommended_operations
+        .into_iter()
+        .map(|operation| format!("{}{}", operation, suffix))
+        .collect()
+}
+
+/// One message to publish; payloads are wire protocol frames
+#[derive(Debug, Clone)]
+pub struct MqttPublish {
+    pub topic: String,
+    pub payload: Vec<u8>,
+    pub retain: bool,
+}
+
+/// Bridges the fleet's MQTT topics into a `TaskDistributor`: pending tasks are offered
+/// on capability topics and the first valid result from any registered device completes them
+#[derive(Debug, Default)]
+pub struct MqttBridge {
+    pub devices: HashMap<String, ESP32Miner>,
+    pub ota: Option<OtaManager>, // Offers firmware updates to devices as they say hello
+    pub telemetry: Option<TelemetryStore>, // Records each heartbeat's stats
+}
+
+impl MqttBridge {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_ota(mut self, ota: OtaManager) -> Self {
+        self.ota = Some(ota);
+        self
+    }
+
+    pub fn with_telemetry(mut self, telemetry: TelemetryStore) -> Self {
+        self.telemetry = Some(telemetry);
+        self
+    }
+
+    /// Offer every pending single task on its capability topics, in both tensor encodings.
+    /// Tasks the wire protocol can't carry stay pending for other miners; ones without a
+    /// fixed-point kernel are only offered to float devices.
+    pub fn publish_pending(&mut self, distributor: &mut TaskDistributor) -> Vec<MqttPublish> {
+        let offerable: Vec<_> = distributor.pending_tasks.values()
+            .filter(|task| task.kind == MiningTaskKind::Single)
+            .filter_map(|task| {
+                let float = ESPMessage::task_push(task, &ESPDeviceType::ESP32).ok()?;
+                let fixed = ESPMessage::task_push(task, &ESPDeviceType::ESP8266).ok();
+                Some((task.id.clone(), task.operation_type.clone(), float, fixed))
+            })
+            .collect();
+
+        let mut publishes = Vec::new();
+        for (task_id, operation, float, fixed) in offerable {
+            if distributor.assign(&task_id, &format!("{}{}", MQTT_POOL_PREFIX, operation)).is_err() {
+                continue;
+            }
+            publishes.push(MqttPublish { topic: task_topic(&operation), payload: float, retain: false });
+            if let Some(fixed) = fixed {
+                publishes.push(MqttPublish {
+                    topic: task_topic(&format!("{}{}", operation, FIXED_POINT_SUFFIX)),
+                    payload: fixed,
+                    retain: false,
+                });
+            }
+        }
+        publishes
+    }
+
+    /// Handle a message from a device, returning anything to publish in reply
+    pub fn handle(&mut self, distributor: &mut TaskDistributor, topic: &str, payload: &[u8]) -> TribeResult<Vec<MqttPublish>> {
+        let levels: Vec<&str> = topic.split('/').collect();
+        let message = ESPMessage::from_frame(payload)?;
+        match (levels.as_slice(), message) {
+            ([TOPIC_ROOT, "devices", device_id, "hello"], ESPMessage::Hello { device_id: claimed, device_type, firmware_version, .. }) => {
+                if claimed != *device_id {
+                    return Err(TribeError::Esp(format!("Device {} said hello as {}", device_id, claimed)));
+                }
+                let offer = match (&mut self.ota, &firmware_version) {
+                    (Some(ota), Some(running)) => ota.check_update(device_id, &device_type, running),
+                    _ => None,
+                };
+                let config = ESPCompatibility::get_recommended_config(device_type);
+                let mut miner = ESP32Miner::new(device_id.to_string(), device_id.to_string(), config);
+                miner.connection_status = crate::esp_compat::ConnectionStatus::Connected;
+                let reply = MqttPublish {
+                    topic: config_topic(device_id),
+                    payload: miner.config_update(DEFAULT_HEARTBEAT_INTERVAL_SECS)?,
+                    retain: true,
+                };
+                self.devices.insert(device_id.to_string(), miner);
+                let mut replies = vec![reply];
+                if let Some(offer) = offer {
+                    replies.push(MqttPublish { topic: config_topic(device_id), payload: offer.encode(false)?, retain: false });
+                }
+                Ok(replies)
+            }
+            ([TOPIC_ROOT, "results", device_id], message @ ESPMessage::ResultSubmit { .. }) => {
+                let miner = self.devices.get_mut(*device_id)
+                    .ok_or_else(|| TribeError::Esp(format!("Unknown device {}", device_id)))?;
+                let result = message.into_mining_result(device_id.to_string()).expect("result submit");
+                let pooled = distributor.active_tasks.get(&result.task_id)
+                    .is_some_and(|(_, miner_id)| miner_id.starts_with(MQTT_POOL_PREFIX));
+                if !pooled {
+                    return Err(TribeError::Esp("Task is not offered over MQTT".to_string()));
+                }
+                match distributor.submit_result(result) {
+                    Ok(()) => miner.performance_stats.successful_tasks += 1,
+                    Err(e) => {
+                        miner.performance_stats.failed_tasks += 1;
+                        return Err(e);
+                    }
+                }
+                Ok(Vec::new())
+            }
+            ([TOPIC_ROOT, "telemetry", device_id], message @ ESPMessage::Heartbeat { .. }) => {
+                let miner = self.devices.get_mut(*device_id)
+                    .ok_or_else(|| TribeError::Esp(format!("Unknown device {}", device_id)))?;
+                miner.apply_message(&message);
+                if let Some(telemetry) = &mut self.telemetry {
+                    let now = chrono::Utc::now().timestamp() as u64;
+                    telemetry.record(device_id, now, &miner.performance_stats.telemetry_metrics())?;
+                }
+                Ok(Vec::new())
+            }
+            ([TOPIC_ROOT, "telemetry", device_id], report @ ESPMessage::FirmwareReport { .. }) => {
+                if let Some(ota) = &mut self.ota {
+                    ota.report(device_id, &report)?;
+                }
+                Ok(Vec::new())
+            }
+            _ => Err(TribeError::Esp(format!("Unexpected message on topic {}", topic))),
+        }
+    }
+}
+
+/// Broker connection for the server side of the bridge
+#[cfg(feature = "mqtt")]
+pub struct MqttConnection {
+    client: rumqttc::Client,
+    connection: rumqttc::Connection,
+}
+
+#[cfg(feature = "mqtt")]
+impl MqttConnection {
+    pub fn connect(client_id: &str, host: &str, port: u16) -> TribeResult<Self> {
+        let mut options = rumqttc::MqttOptions::new(client_id, host, port);
+        options.set_keep_alive(std::time::Duration::from_secs(30));
+        let (client, connection) = rumqttc::Client::new(options, 64);
+        for topic in server_subscriptions() {
+            client.subscribe(topic, rumqttc::QoS::AtLeastOnce)
+                .map_err(|e| TribeError::Network(format!("MQTT subscribe failed: {}", e)))?;
+        }
+        Ok(Self { client, connection })
+    }
+
+    pub fn publish(&self, message: MqttPublish) -> TribeResult<()> {
+        self.client.publish(message.topic, rumqttc::QoS::AtLeastOnce, message.retain, message.payload)
+            .map_err(|e| TribeError::Network(format!("MQTT publish failed: {}", e)))
+    }
+
+    /// Offer pending tasks, then feed messages arriving within `timeout` through the bridge.
+    /// Returns how many device messages were handled; malformed ones are dropped.
+    pub fn poll(&mut self, bridge: &mut MqttBridge, distributor: &mut TaskDistributor, timeout: std::time::Duration) -> TribeResult<usize> {
+        for publish in bridge.publish_pending(distributor) {
+            self.publish(publish)?;
+        }
+
+        let mut handled = 0;
+        loop {
+            let event = match self.connection.recv_timeout(timeout) {
+                Ok(event) => event.map_err(|e| TribeError::Network(format!("MQTT connection error: {}", e)))?,
+                Err(rumqttc::RecvTimeoutError::Timeout) => return Ok(handled),
+                Err(rumqttc::RecvTimeoutError::Disconnected) => {
+                    return Err(TribeError::Network("MQTT connection closed".to_string()));
+                }
+            };
+            if let rumqttc::Event::Incoming(rumqttc::Packet::Publish(message)) = event {
+                if let Ok(replies) = bridge.handle(distributor, &message.topic, &message.payload) {
+                    handled += 1;
+                    for reply in replies {
+                        self.publish(reply)?;
+                    }
+                }
+            }
+        }
+    }
+}
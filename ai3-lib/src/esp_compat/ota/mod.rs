@@ -0,0 +1,223 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use crate::esp_compat::devices::ESPDeviceType;
+use crate::esp_compat::protocol::ESPMessage;
+use tribechain_core::{TribeResult, TribeError};
+
+/// A firmware build for one device type, signed by the release key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FirmwareImage {
+    pub device_type: ESPDeviceType,
+    pub version: String, // Dotted numeric, e.g. "1.4.2"
+    pub image: Vec<u8>,
+    pub sha256: String,
+    pub signature: String,
+    pub published_at: DateTime<Utc>,
+}
+
+/// How a new image reaches the fleet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RolloutPolicy {
+    pub canary_percent: u8, // Share of devices offered the image first
+    pub promote_after: u32, // Successful canary updates before everyone gets it
+    pub max_failure_rate: f32, // Failure share that rolls the image back
+    pub min_reports: u32, // Reports needed before the failure rate counts
+}
+
+impl Default for RolloutPolicy {
+    fn default() -> Self {
+        Self {
+            canary_percent: 10,
+            promote_after: 5,
+            max_failure_rate: 0.2,
+            min_reports: 3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum RolloutStage {
+    Canary,
+    Full,
+    RolledBack,
+}
+
+/// Progress of the newest image for one device type
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rollout {
+    pub version: String,
+    pub previous_version: Option<String>, // What devices go back to on rollback
+    pub stage: RolloutStage,
+    pub successes: u32,
+    pub failures: u32,
+}
+
+/// Firmware the node hosts and the rollouts in progress, per device type
+#[derive(Debug, Clone)]
+pub struct OtaManager {
+    pub release_key: String,
+    pub download_base: String, // Devices fetch `<base>/<device>/<version>.bin`
+    pub policy: RolloutPolicy,
+    pub images: HashMap<ESPDeviceType, Vec<FirmwareImage>>,
+    pub rollouts: HashMap<ESPDeviceType, Rollout>,
+    pub device_versions: HashMap<String, (ESPDeviceType, String)>, // device_id -> (type, running version)
+}
+
+/// Sign an image hash with the release key
+/// (simplified - in real implementation would use proper cryptography)
+pub fn sign_firmware(sha256: &str, key: &str) -> String {
+    hex::encode(Sha256::digest(format!("{}{}", sha256, key).as_bytes()))
+}
+
+fn parse_version(version: &str) -> TribeResult<Vec<u32>> {
+    version.split('.')
+        .map(|part| part.parse::<u32>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| TribeError::Esp(format!("Invalid firmware version {}", version)))
+}
+
+impl OtaManager {
+    pub fn new(release_key: String, download_base: String) -> Self {
+        Self {
+            release_key,
+            download_base,
+            policy: RolloutPolicy::default(),
+            images: HashMap::new(),
+            rollouts: HashMap::new(),
+            device_versions: HashMap::new(),
+        }
+    }
+
+    pub fn with_policy(mut self, policy: RolloutPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Host a signed image and start a canary rollout of it
+    pub fn publish(&mut self, device_type: ESPDeviceType, image: Vec<u8>, version: &str, signature: &str) -> TribeResult<()> {
+        let sha256 = hex::encode(Sha256::digest(&image));
+        if sign_firmware(&sha256, &self.release_key) != signature {
+            return Err(TribeError::Esp("Firmware signature does not match the release key".to_string()));
+        }
+        let parsed = parse_version(version)?;
+        if let Some(latest) = self.latest(&device_type) {
+            if parsed <= parse_version(&latest.version)? {
+                return Err(TribeError::Esp(format!(
+                    "Firmware {} is not newer than {}", version, latest.version
+                )));
+            }
+        }
+
+        let previous_version = self.stable_version(&device_type);
+        self.images.entry(device_type.clone()).or_default().push(FirmwareImage {
+            device_type: device_type.clone(),
+            version: version.to_string(),
+            image,
+            sha256,
+            signature: signature.to_string(),
+            published_at: Utc::now(),
+        });
+        self.rollouts.insert(device_type, Rollout {
+            version: version.to_string(),
+            previous_version,
+            stage: RolloutStage::Canary,
+            successes: 0,
+            failures: 0,
+        });
+        Ok(())
+    }
+
+    pub fn latest(&self, device_type: &ESPDeviceType) -> Option<&FirmwareImage> {
+        self.images.get(device_type).and_then(|images| images.last())
+    }
+
+    pub fn get_image(&self, device_type: &ESPDeviceType, version: &str) -> Option<&FirmwareImage> {
+        self.images.get(device_type)?.iter().find(|image| image.version == version)
+    }
+
+    /// Version every device of this type should run outside a canary
+    fn stable_version(&self, device_type: &ESPDeviceType) -> Option<String> {
+        let rollout = self.rollouts.get(device_type)?;
+        match rollout.stage {
+            RolloutStage::Full => Some(rollout.version.clone()),
+            RolloutStage::Canary | RolloutStage::RolledBack => rollout.previous_version.clone(),
+        }
+    }
+
+    /// Stable per-device bucket, so the same devices stay in a version's canary
+    fn in_canary(&self, device_id: &str, version: &str) -> bool {
+        let digest = Sha256::digest(format!("{}{}", device_id, version).as_bytes());
+        (u16::from_le_bytes([digest[0], digest[1]]) % 100) < self.policy.canary_percent as u16
+    }
+
+    /// Record the version a device runs and return the update it should apply, if any.
+    /// A device that comes back running the rollout's version counts as a success.
+    pub fn check_update(&mut self, device_id: &str, device_type: &ESPDeviceType, running: &str) -> Option<ESPMessage> {
+        let previous = self.device_versions.insert(device_id.to_string(), (device_type.clone(), running.to_string()));
+        if let Some(rollout) = self.rollouts.get(device_type) {
+            let upgraded = previous.is_some_and(|(_, version)| version != running);
+            if upgraded && running == rollout.version && rollout.stage != RolloutStage::RolledBack {
+                self.record(device_type, true);
+            }
+        }
+
+        let rollout = self.rollouts.get(device_type)?;
+        let target = match rollout.stage {
+            RolloutStage::Full => rollout.version.clone(),
+            RolloutStage::Canary if self.in_canary(device_id, &rollout.version) => rollout.version.clone(),
+            _ => self.stable_version(device_type)?,
+        };
+        if target == running {
+            return None;
+        }
+
+        let image = self.get_image(device_type, &target)?;
+        let mut sha256 = [0u8; 32];
+        hex::decode_to_slice(&image.sha256, &mut sha256).ok()?;
+        Some(ESPMessage::FirmwareOffer {
+            version: target.clone(),
+            size: image.image.len() as u32,
+            sha256,
+            url: format!("{}/{:?}/{}.bin", self.download_base, device_type, target),
+        })
+    }
+
+    /// Count a device's `FirmwareReport`
+    pub fn report(&mut self, device_id: &str, report: &ESPMessage) -> TribeResult<()> {
+        let ESPMessage::FirmwareReport { version, success } = report else {
+            return Err(TribeError::Esp("Not a firmware report".to_string()));
+        };
+        let (device_type, _) = self.device_versions.get(device_id)
+            .ok_or_else(|| TribeError::Esp(format!("Unknown device {}", device_id)))?
+            .clone();
+        if self.rollouts.get(&device_type).is_some_and(|rollout| rollout.version == *version) {
+            self.record(&device_type, *success);
+        }
+        Ok(())
+    }
+
+    /// Promote a canary that has enough successes, roll back on a failure spike
+    fn record(&mut self, device_type: &ESPDeviceType, success: bool) {
+        let policy = self.policy.clone();
+        let Some(rollout) = self.rollouts.get_mut(device_type) else { return };
+        if success {
+            rollout.successes += 1;
+        } else {
+            rollout.failures += 1;
+        }
+
+        let reports = rollout.successes + rollout.failures;
+        let failure_rate = rollout.failures as f32 / reports as f32;
+        if reports >= policy.min_reports && failure_rate > policy.max_failure_rate {
+            rollout.stage = RolloutStage::RolledBack;
+        } else if rollout.stage == RolloutStage::Canary && rollout.successes >= policy.promote_after {
+            rollout.stage = RolloutStage::Full;
+        }
+    }
+
+    pub fn get_rollout(&self, device_type: &ESPDeviceType) -> Option<&Rollout> {
+        self.rollouts.get(device_type)
+    }
+}
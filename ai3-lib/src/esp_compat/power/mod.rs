@@ -0,0 +1,110 @@
+use std::collections::{HashMap, HashSet};
+use crate::esp_compat::miners::ESPPerformanceStats;
+
+/// Fleet-wide power and thermal limits
+#[derive(Debug, Clone)]
+pub struct PowerPolicy {
+    pub fleet_budget_mw: Option<u32>, // Total draw of mining devices; unlimited when None
+    pub thermal_limit_c: f32, // Devices at or above this rest
+    pub thermal_margin_c: f32, // Devices within this of the limit are duty-cycled
+}
+
+impl Default for PowerPolicy {
+    fn default() -> Self {
+        Self {
+            fleet_budget_mw: None,
+            thermal_limit_c: 80.0,
+            thermal_margin_c: 10.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct DeviceBudget {
+    stats: ESPPerformanceStats,
+    credit: f32, // Accumulated duty cycle; a device may start work once it reaches 1
+}
+
+/// Decides which ESP devices may start new work each scheduling round, from the
+/// temperature and power draw they last reported
+#[derive(Debug, Clone, Default)]
+pub struct FleetPowerScheduler {
+    pub policy: PowerPolicy,
+    devices: HashMap<String, DeviceBudget>,
+}
+
+impl FleetPowerScheduler {
+    pub fn new(policy: PowerPolicy) -> Self {
+        Self { policy, devices: HashMap::new() }
+    }
+
+    /// Record a device's latest stats, registering it on first report
+    pub fn update(&mut self, device_id: &str, stats: &ESPPerformanceStats) {
+        self.devices.entry(device_id.to_string())
+            .and_modify(|device| device.stats = stats.clone())
+            .or_insert(DeviceBudget { stats: stats.clone(), credit: 0.0 });
+    }
+
+    pub fn remove(&mut self, device_id: &str) {
+        self.devices.remove(device_id);
+    }
+
+    pub fn contains(&self, device_id: &str) -> bool {
+        self.devices.contains_key(device_id)
+    }
+
+    /// Share of rounds a device may start work in: 1 when cool, falling to 0 at the thermal limit
+    pub fn duty_cycle(&self, device_id: &str) -> f32 {
+        let Some(device) = self.devices.get(device_id) else { return 1.0 };
+        let headroom = self.policy.thermal_limit_c - device.stats.cpu_temperature;
+        if headroom <= 0.0 {
+            0.0
+        } else if self.policy.thermal_margin_c <= 0.0 {
+            1.0
+        } else {
+            (headroom / self.policy.thermal_margin_c).min(1.0)
+        }
+    }
+
+    /// Reported draw of the fleet's busy devices
+    pub fn busy_draw_mw(&self, busy: &HashSet<String>) -> u64 {
+        busy.iter()
+            .filter_map(|id| self.devices.get(id))
+            .map(|device| device.stats.power_consumption_mw as u64)
+            .sum()
+    }
+
+    /// Idle devices allowed to start a task this round. Busy devices' draw is charged to the
+    /// fleet budget first; idle ones are then admitted coolest first while their duty cycle
+    /// and the budget allow. Devices drawing more than their own power limit are held back.
+    pub fn admit(&mut self, busy: &HashSet<String>) -> HashSet<String> {
+        let mut draw = self.busy_draw_mw(busy);
+        let mut idle: Vec<(String, f32)> = self.devices.keys()
+            .filter(|id| !busy.contains(*id))
+            .map(|id| (id.clone(), self.duty_cycle(id)))
+            .collect();
+        idle.sort_by(|(a_id, _), (b_id, _)| {
+            let a = self.devices[a_id].stats.cpu_temperature;
+            let b = self.devices[b_id].stats.cpu_temperature;
+            a.total_cmp(&b).then_with(|| a_id.cmp(b_id))
+        });
+
+        let mut admitted = HashSet::new();
+        for (id, duty) in idle {
+            let device = self.devices.get_mut(&id).expect("listed device");
+            device.credit = (device.credit + duty).min(1.0);
+            let stats = &device.stats;
+            if device.credit < 1.0 || (stats.power_limit_mw > 0 && stats.power_consumption_mw > stats.power_limit_mw) {
+                continue;
+            }
+            let device_draw = stats.power_consumption_mw as u64;
+            if self.policy.fleet_budget_mw.is_some_and(|budget| draw + device_draw > budget as u64) {
+                continue;
+            }
+            device.credit -= 1.0;
+            draw += device_draw;
+            admitted.insert(id);
+        }
+        admitted
+    }
+}
@@ -0,0 +1,503 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::esp_compat::codegen::ESPCodeGenerator;
+use crate::esp_compat::config::{ESPMiningConfig, ESPProjectKind, ESPProvisioning, ESPTransport};
+use crate::esp_compat::devices::ESPDeviceType;
+use crate::esp_compat::mesh;
+use crate::esp_compat::protocol::{device_code, ESP_PROTOCOL_VERSION, MAX_FRAME_PAYLOAD};
+use crate::esp_compat::utils;
+use tribechain_core::{TribeResult, TribeError};
+
+/// 4MB flash layout with two app slots, so OTA updates can fall back to the running image
+pub const OTA_PARTITION_TABLE: &str = "\
+# Name,   Type, SubType, Offset,   Size
+nvs,      data, nvs,     0x9000,   0x5000
+otadata,  data, ota,     0xe000,   0x2000
+app0,     app,  ota_0,   0x10000,  0x1E0000
+app1,     app,  ota_1,   0x1F0000, 0x1E0000
+spiffs,   data, spiffs,  0x3D0000, 0x30000
+";
+
+/// Files of the project for `config`, relative to the project root
+pub fn project_files(config: &ESPMiningConfig) -> TribeResult<Vec<(PathBuf, String)>> {
+    if !config.device_type.has_wifi() {
+        return Err(TribeError::Esp(format!(
+            "{:?} has no Wi-Fi radio for the generated firmware's transports", config.device_type
+        )));
+    }
+    match config.project_kind {
+        ESPProjectKind::PlatformIO => platformio_files(config),
+        ESPProjectKind::EspIdfRust => esp_idf_rust_files(config),
+    }
+}
+
+/// Write the project for `config` under `root`, returning the paths written
+pub fn write_project(root: &Path, config: &ESPMiningConfig) -> TribeResult<Vec<PathBuf>> {
+    let mut written = Vec::new();
+    for (relative, contents) in project_files(config)? {
+        let path = root.join(relative);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| TribeError::Storage(format!("Failed to create {}: {}", parent.display(), e)))?;
+        }
+        fs::write(&path, contents)
+            .map_err(|e| TribeError::Storage(format!("Failed to write {}: {}", path.display(), e)))?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+fn board(device_type: &ESPDeviceType) -> (&'static str, &'static str) {
+    match device_type {
+        ESPDeviceType::ESP32 => ("espressif32", "esp32dev"),
+        ESPDeviceType::ESP8266 => ("espressif8266", "nodemcuv2"),
+        ESPDeviceType::ESP32S2 => ("espressif32", "esp32-s2-saola-1"),
+        ESPDeviceType::ESP32S3 => ("espressif32", "esp32-s3-devkitc-1"),
+        ESPDeviceType::ESP32C3 => ("espressif32", "esp32-c3-devkitm-1"),
+        ESPDeviceType::ESP32C6 => ("espressif32", "esp32-c6-devkitc-1"),
+        ESPDeviceType::ESP32H2 => ("espressif32", "esp32-h2-devkitm-1"),
+    }
+}
+
+fn platformio_files(config: &ESPMiningConfig) -> TribeResult<Vec<(PathBuf, String)>> {
+    match &config.transport {
+        ESPTransport::MeshGateway if config.device_type == ESPDeviceType::ESP8266 => {
+            return Err(TribeError::Esp("Mesh gateways need an ESP32-family device".to_string()));
+        }
+        ESPTransport::MeshPeer { gateway_mac, .. } => {
+            mesh::parse_mac(gateway_mac)?;
+        }
+        _ => {}
+    }
+    let (platform, board) = board(&config.device_type);
+    let flash_layout = match config.device_type {
+        ESPDeviceType::ESP8266 => "board_build.ldscript = eagle.flash.4m1m.ld",
+        _ => "board_build.partitions = partitions.csv",
+    };
+    let build_flags: String = utils::get_compiler_flags(&config.device_type).iter()
+        .map(|flag| format!("\n    {}", flag))
+        .collect();
+    let mut lib_deps = String::from("\n    rweather/Crypto");
+    if config.transport == ESPTransport::Mqtt {
+        lib_deps.push_str("\n    knolleary/PubSubClient");
+    }
+
+    let ini = format!(
+        "; Generated by ai3-lib for {:?}, firmware {}\n\
+         [env:{}]\n\
+         platform = {}\n\
+         board = {}\n\
+         framework = arduino\n\
+         monitor_speed = 115200\n\
+         {}\n\
+         build_flags ={}\n\
+         lib_deps ={}\n",
+        config.device_type, config.firmware_version, board, platform, board, flash_layout, build_flags, lib_deps
+    );
+
+    let mut files = vec![
+        (PathBuf::from("platformio.ini"), ini),
+        (PathBuf::from("src/main.ino"), ESPCodeGenerator::generate_mining_code(config)),
+        (PathBuf::from("src/tensor_ops.ino"), ESPCodeGenerator::generate_tensor_operations()),
+    ];
+    if config.device_type != ESPDeviceType::ESP8266 {
+        files.push((PathBuf::from("partitions.csv"), OTA_PARTITION_TABLE.to_string()));
+    }
+    Ok(files)
+}
+
+/// Rust target triple and esp-idf MCU name
+fn rust_target(device_type: &ESPDeviceType) -> TribeResult<(&'static str, &'static str)> {
+    match device_type {
+        ESPDeviceType::ESP32 => Ok(("xtensa-esp32-espidf", "esp32")),
+        ESPDeviceType::ESP32S2 => Ok(("xtensa-esp32s2-espidf", "esp32s2")),
+        ESPDeviceType::ESP32S3 => Ok(("xtensa-esp32s3-espidf", "esp32s3")),
+        ESPDeviceType::ESP32C3 => Ok(("riscv32imc-esp-espidf", "esp32c3")),
+        ESPDeviceType::ESP32C6 => Ok(("riscv32imac-esp-espidf", "esp32c6")),
+        ESPDeviceType::ESP32H2 => Ok(("riscv32imac-esp-espidf", "esp32h2")),
+        ESPDeviceType::ESP8266 => Err(TribeError::Esp(
+            "ESP-IDF does not support the ESP8266; generate a PlatformIO project instead".to_string(),
+        )),
+    }
+}
+
+fn esp_idf_rust_files(config: &ESPMiningConfig) -> TribeResult<Vec<(PathBuf, String)>> {
+    let (target, mcu) = rust_target(&config.device_type)?;
+    if config.transport != ESPTransport::Tcp {
+        return Err(TribeError::Esp("The Rust firmware only speaks the TCP transport".to_string()));
+    }
+    if config.provisioning == ESPProvisioning::Encrypted {
+        return Err(TribeError::Esp("Encrypted provisioning is only generated for PlatformIO projects".to_string()));
+    }
+    // RISC-V parts build on nightly; Xtensa needs the esp toolchain fork
+    let channel = if config.device_type.is_riscv() { "nightly" } else { "esp" };
+    let cflags = utils::get_compiler_flags(&config.device_type).join(" ");
+
+    let cargo_toml = format!(
+        "[package]\n\
+         name = \"tribechain-esp-miner\"\n\
+         version = \"{}\"\n\
+         edition = \"2021\"\n\
+         \n\
+         [[bin]]\n\
+         name = \"tribechain-esp-miner\"\n\
+         harness = false\n\
+         \n\
+         [profile.release]\n\
+         opt-level = \"s\"\n\
+         \n\
+         [profile.dev]\n\
+         debug = true\n\
+         opt-level = \"z\"\n\
+         \n\
+         [dependencies]\n\
+         anyhow = \"1\"\n\
+         log = \"0.4\"\n\
+         sha2 = {{ version = \"0.10\", default-features = false }}\n\
+         esp-idf-svc = \"0.49\"\n\
+         \n\
+         [build-dependencies]\n\
+         embuild = \"0.32\"\n",
+        config.firmware_version
+    );
+    let cargo_config = format!(
+        "[build]\n\
+         target = \"{target}\"\n\
+         \n\
+         [target.{target}]\n\
+         linker = \"ldproxy\"\n\
+         runner = \"espflash flash --monitor --partition-table partitions.csv\"\n\
+         rustflags = [\"--cfg\", \"espidf_time64\"]\n\
+         \n\
+         [unstable]\n\
+         build-std = [\"std\", \"panic_abort\"]\n\
+         \n\
+         [env]\n\
+         MCU = \"{mcu}\"\n\
+         ESP_IDF_VERSION = \"v5.2.2\"\n\
+         CFLAGS = \"{cflags}\"\n\
+         CXXFLAGS = \"{cflags}\"\n"
+    );
+    let toolchain = format!("[toolchain]\nchannel = \"{}\"\n", channel);
+    let sdkconfig = "CONFIG_ESP_MAIN_TASK_STACK_SIZE=16000\n\
+                     CONFIG_PARTITION_TABLE_CUSTOM=y\n\
+                     CONFIG_PARTITION_TABLE_CUSTOM_FILENAME=\"partitions.csv\"\n\
+                     CONFIG_BOOTLOADER_APP_ROLLBACK_ENABLE=y\n";
+    let settings = format!(
+        "// Generated by ai3-lib; regenerate rather than editing\n\
+         pub const WIFI_SSID: &str = {:?};\n\
+         pub const SERVER_ADDRESS: &str = {:?};\n\
+         pub const SERVER_PORT: u16 = {};\n\
+         pub const MINING_INTENSITY: u8 = {};\n\
+         pub const POWER_SAVE_MODE: bool = {};\n\
+         pub const FIRMWARE_VERSION: &str = {:?};\n\
+         pub const PROTOCOL_VERSION: u8 = {};\n\
+         pub const DEVICE_TYPE: u8 = {};\n\
+         pub const MAX_FRAME_PAYLOAD: usize = {};\n",
+        config.wifi_ssid,
+        config.server_address,
+        config.server_port,
+        config.mining_intensity,
+        config.power_save_mode,
+        config.firmware_version,
+        ESP_PROTOCOL_VERSION,
+        device_code(&config.device_type),
+        MAX_FRAME_PAYLOAD
+    );
+
+    Ok(vec![
+        (PathBuf::from("Cargo.toml"), cargo_toml),
+        (PathBuf::from(".cargo/config.toml"), cargo_config),
+        (PathBuf::from("rust-toolchain.toml"), toolchain),
+        (PathBuf::from("build.rs"), "fn main() {\n    embuild::espidf::sysenv::output();\n}\n".to_string()),
+        (PathBuf::from("sdkconfig.defaults"), sdkconfig.to_string()),
+        (PathBuf::from("partitions.csv"), OTA_PARTITION_TABLE.to_string()),
+        (PathBuf::from("src/config.rs"), settings),
+        (PathBuf::from("src/main.rs"), RUST_FIRMWARE_MAIN.to_string()),
+    ])
+}
+
+/// Firmware speaking the TLV wire protocol over TCP, with OTA through esp-idf's app slots
+const RUST_FIRMWARE_MAIN: &str = r#"//! TribeChain AI3 miner firmware, generated by ai3-lib
+mod config;
+
+use config::*;
+use esp_idf_svc::eventloop::EspSystemEventLoop;
+use esp_idf_svc::hal::prelude::Peripherals;
+use esp_idf_svc::http::client::{Configuration as HttpConfiguration, EspHttpConnection};
+use esp_idf_svc::io::{Read as _, Write as _};
+use esp_idf_svc::nvs::EspDefaultNvsPartition;
+use esp_idf_svc::ota::EspOta;
+use esp_idf_svc::wifi::{AuthMethod, BlockingWifi, ClientConfiguration, Configuration, EspWifi};
+use sha2::{Digest, Sha256};
+use std::io::{ErrorKind, Read, Write};
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+
+const MSG_HELLO: u8 = 1;
+const MSG_TASK_PUSH: u8 = 2;
+const MSG_HEARTBEAT: u8 = 3;
+const MSG_RESULT_SUBMIT: u8 = 4;
+const MSG_CONFIG_UPDATE: u8 = 5;
+const MSG_FIRMWARE_OFFER: u8 = 6;
+const MSG_FIRMWARE_REPORT: u8 = 7;
+
+const TAG_DEVICE_ID: u8 = 1;
+const TAG_DEVICE_TYPE: u8 = 2;
+const TAG_VERSION: u8 = 3;
+const TAG_TASK_ID: u8 = 4;
+const TAG_OPERATION: u8 = 5;
+const TAG_DIFFICULTY: u8 = 6;
+const TAG_NONCE_RANGE: u8 = 7;
+const TAG_NONCE: u8 = 10;
+const TAG_HASH: u8 = 11;
+const TAG_COMPUTE_TIME: u8 = 12;
+const TAG_UPTIME: u8 = 13;
+const TAG_FREE_HEAP: u8 = 14;
+const TAG_TEMPERATURE: u8 = 15;
+const TAG_RSSI: u8 = 16;
+const TAG_TASK_COUNTS: u8 = 17;
+const TAG_INTENSITY: u8 = 18;
+const TAG_HEARTBEAT_INTERVAL: u8 = 20;
+const TAG_FIRMWARE_VERSION: u8 = 21;
+const TAG_FIRMWARE_URL: u8 = 23;
+const TAG_SUCCESS: u8 = 24;
+
+/// Outgoing frame: type | payload length (u16 LE) | tag | length (u16 LE) | value ...
+struct Frame {
+    kind: u8,
+    payload: Vec<u8>,
+}
+
+impl Frame {
+    fn new(kind: u8) -> Self {
+        Self { kind, payload: Vec::new() }
+    }
+
+    fn field(mut self, tag: u8, value: &[u8]) -> Self {
+        self.payload.push(tag);
+        self.payload.extend_from_slice(&(value.len() as u16).to_le_bytes());
+        self.payload.extend_from_slice(value);
+        self
+    }
+
+    fn send(self, stream: &mut TcpStream) -> std::io::Result<()> {
+        let mut frame = vec![self.kind];
+        frame.extend_from_slice(&(self.payload.len() as u16).to_le_bytes());
+        frame.extend_from_slice(&self.payload);
+        stream.write_all(&frame)
+    }
+}
+
+fn find_field(payload: &[u8], tag: u8) -> Option<&[u8]> {
+    let mut pos = 0;
+    while pos + 3 <= payload.len() {
+        let len = u16::from_le_bytes([payload[pos + 1], payload[pos + 2]]) as usize;
+        let value = payload.get(pos + 3..pos + 3 + len)?;
+        if payload[pos] == tag {
+            return Some(value);
+        }
+        pos += 3 + len;
+    }
+    None
+}
+
+/// Buffers partial reads until a whole frame has arrived
+#[derive(Default)]
+struct FrameReader {
+    buffer: Vec<u8>,
+}
+
+impl FrameReader {
+    fn poll(&mut self, stream: &mut TcpStream) -> std::io::Result<Option<(u8, Vec<u8>)>> {
+        let mut chunk = [0u8; 512];
+        match stream.read(&mut chunk) {
+            Ok(0) => return Err(ErrorKind::UnexpectedEof.into()),
+            Ok(n) => self.buffer.extend_from_slice(&chunk[..n]),
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            Err(e) => return Err(e),
+        }
+        if self.buffer.len() < 3 {
+            return Ok(None);
+        }
+        let len = u16::from_le_bytes([self.buffer[1], self.buffer[2]]) as usize;
+        if len > MAX_FRAME_PAYLOAD {
+            return Err(ErrorKind::InvalidData.into());
+        }
+        if self.buffer.len() < 3 + len {
+            return Ok(None);
+        }
+        let frame: Vec<u8> = self.buffer.drain(..3 + len).collect();
+        Ok(Some((frame[0], frame[3..].to_vec())))
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    esp_idf_svc::sys::link_patches();
+    esp_idf_svc::log::EspLogger::initialize_default();
+
+    let peripherals = Peripherals::take()?;
+    let sys_loop = EspSystemEventLoop::take()?;
+    let nvs = EspDefaultNvsPartition::take()?;
+    let mut wifi = BlockingWifi::wrap(EspWifi::new(peripherals.modem, sys_loop.clone(), Some(nvs))?, sys_loop)?;
+    wifi.set_configuration(&Configuration::Client(ClientConfiguration {
+        ssid: WIFI_SSID.try_into().expect("SSID fits"),
+        auth_method: AuthMethod::None,
+        ..Default::default()
+    }))?;
+    wifi.start()?;
+    wifi.connect()?;
+    wifi.wait_netif_up()?;
+    if POWER_SAVE_MODE {
+        esp_idf_svc::sys::esp!(unsafe { esp_idf_svc::sys::esp_wifi_set_ps(esp_idf_svc::sys::wifi_ps_type_t_WIFI_PS_MIN_MODEM) })?;
+    }
+
+    // Booting this image worked, so keep it rather than rolling back
+    EspOta::new()?.mark_running_slot_valid()?;
+
+    let mac = wifi.wifi().sta_netif().get_mac()?;
+    let device_id = mac.iter().map(|b| format!("{:02X}", b)).collect::<Vec<_>>().join(":");
+    log::info!("ESP miner {} running firmware {}", device_id, FIRMWARE_VERSION);
+
+    loop {
+        if let Err(e) = mine(&device_id) {
+            log::warn!("Mining connection lost: {:?}", e);
+            std::thread::sleep(Duration::from_secs(5));
+        }
+    }
+}
+
+fn mine(device_id: &str) -> anyhow::Result<()> {
+    let mut stream = TcpStream::connect((SERVER_ADDRESS, SERVER_PORT))?;
+    stream.set_nodelay(true)?;
+    stream.set_read_timeout(Some(Duration::from_millis(200)))?;
+    Frame::new(MSG_HELLO)
+        .field(TAG_DEVICE_ID, device_id.as_bytes())
+        .field(TAG_DEVICE_TYPE, &[DEVICE_TYPE])
+        .field(TAG_VERSION, &[PROTOCOL_VERSION])
+        .field(TAG_FIRMWARE_VERSION, FIRMWARE_VERSION.as_bytes())
+        .send(&mut stream)?;
+
+    let started = Instant::now();
+    let mut reader = FrameReader::default();
+    let mut intensity = MINING_INTENSITY.clamp(1, 10);
+    let mut heartbeat_interval = Duration::from_secs(30);
+    let mut last_heartbeat = Instant::now();
+    let (mut successful, mut failed) = (0u32, 0u32);
+
+    loop {
+        match reader.poll(&mut stream)? {
+            Some((MSG_TASK_PUSH, payload)) => match mine_task(&payload) {
+                Some(result) => {
+                    result.send(&mut stream)?;
+                    successful += 1;
+                }
+                None => failed += 1,
+            },
+            Some((MSG_CONFIG_UPDATE, payload)) => {
+                if let Some([value]) = find_field(&payload, TAG_INTENSITY) {
+                    intensity = (*value).clamp(1, 10);
+                }
+                if let Some(&[lo, hi]) = find_field(&payload, TAG_HEARTBEAT_INTERVAL) {
+                    heartbeat_interval = Duration::from_secs(u16::from_le_bytes([lo, hi]) as u64);
+                }
+            }
+            Some((MSG_FIRMWARE_OFFER, payload)) => {
+                let version = find_field(&payload, TAG_FIRMWARE_VERSION).unwrap_or_default();
+                if version != FIRMWARE_VERSION.as_bytes() {
+                    if let Err(e) = apply_firmware_offer(&payload) {
+                        log::warn!("Firmware update failed: {:?}", e);
+                        Frame::new(MSG_FIRMWARE_REPORT)
+                            .field(TAG_FIRMWARE_VERSION, version)
+                            .field(TAG_SUCCESS, &[0])
+                            .send(&mut stream)?;
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        if last_heartbeat.elapsed() >= heartbeat_interval {
+            let free_heap = unsafe { esp_idf_svc::sys::esp_get_free_heap_size() };
+            let mut counts = successful.to_le_bytes().to_vec();
+            counts.extend_from_slice(&failed.to_le_bytes());
+            Frame::new(MSG_HEARTBEAT)
+                .field(TAG_UPTIME, &(started.elapsed().as_secs() as u32).to_le_bytes())
+                .field(TAG_FREE_HEAP, &free_heap.to_le_bytes())
+                .field(TAG_TEMPERATURE, &0i16.to_le_bytes())
+                .field(TAG_RSSI, &0i8.to_le_bytes())
+                .field(TAG_TASK_COUNTS, &counts)
+                .send(&mut stream)?;
+            last_heartbeat = Instant::now();
+        }
+        std::thread::sleep(Duration::from_millis(10 * (11 - intensity as u64)));
+    }
+}
+
+/// Search the pushed nonce range for a hash meeting the difficulty (leading zero hex digits)
+fn mine_task(payload: &[u8]) -> Option<Frame> {
+    let task_id = find_field(payload, TAG_TASK_ID)?;
+    let operation = *find_field(payload, TAG_OPERATION)?.first()?;
+    let difficulty = *find_field(payload, TAG_DIFFICULTY)?.first()? as u32;
+    let range = find_field(payload, TAG_NONCE_RANGE)?;
+    let start = u64::from_le_bytes(range.get(..8)?.try_into().ok()?);
+    let end = u64::from_le_bytes(range.get(8..16)?.try_into().ok()?);
+
+    let started = Instant::now();
+    for nonce in start..end {
+        let mut hasher = Sha256::new();
+        hasher.update(task_id);
+        hasher.update([operation]);
+        hasher.update(nonce.to_le_bytes());
+        let hash: [u8; 32] = hasher.finalize().into();
+
+        let mut leading_zeros = 0;
+        for byte in hash {
+            leading_zeros += if byte == 0 { 2 } else if byte < 16 { 1 } else { 0 };
+            if byte != 0 {
+                break;
+            }
+        }
+        if leading_zeros >= difficulty {
+            return Some(Frame::new(MSG_RESULT_SUBMIT)
+                .field(TAG_TASK_ID, task_id)
+                .field(TAG_NONCE, &nonce.to_le_bytes())
+                .field(TAG_HASH, &hash)
+                .field(TAG_COMPUTE_TIME, &(started.elapsed().as_millis() as u32).to_le_bytes()));
+        }
+        if started.elapsed() > Duration::from_secs(30) {
+            break;
+        }
+    }
+    None
+}
+
+/// Stream the offered image into the spare app slot, checking its hash before switching to it
+fn apply_firmware_offer(payload: &[u8]) -> anyhow::Result<()> {
+    let url = std::str::from_utf8(find_field(payload, TAG_FIRMWARE_URL).ok_or(anyhow::anyhow!("offer without url"))?)?;
+    let expected = find_field(payload, TAG_HASH).ok_or(anyhow::anyhow!("offer without hash"))?;
+
+    let mut connection = EspHttpConnection::new(&HttpConfiguration::default())?;
+    connection.initiate_request(esp_idf_svc::http::Method::Get, url, &[])?;
+    connection.initiate_response()?;
+
+    let mut ota = EspOta::new()?;
+    let mut update = ota.initiate_update()?;
+    let mut hasher = Sha256::new();
+    let mut chunk = [0u8; 1024];
+    loop {
+        let n = connection.read(&mut chunk)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&chunk[..n]);
+        update.write_all(&chunk[..n])?;
+    }
+    if hasher.finalize().as_slice() != expected {
+        update.abort()?;
+        anyhow::bail!("firmware image does not match the offered hash");
+    }
+    update.complete()?;
+    esp_idf_svc::hal::reset::restart();
+}
+"#;
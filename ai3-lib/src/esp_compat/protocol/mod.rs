@@ -0,0 +1,529 @@
+use crate::esp_compat::devices::ESPDeviceType;
+use crate::esp_compat::kernels::{self, KernelDescriptor};
+use crate::esp_compat::miners::BatteryState;
+use crate::mining::{MiningTask, MiningResult};
+use crate::tensor::{Tensor, TensorShape};
+use tribechain_core::{TribeResult, TribeError};
+
+/// Wire protocol version sent in `Hello`
+pub const ESP_PROTOCOL_VERSION: u8 = 1;
+
+/// Largest frame payload, sized for the ESP8266's receive buffer
+pub const MAX_FRAME_PAYLOAD: usize = 4096;
+
+/// Frame header: message type (u8) + payload length (u16 LE)
+pub const FRAME_HEADER_LEN: usize = 3;
+
+// Message types
+const MSG_HELLO: u8 = 1;
+const MSG_TASK_PUSH: u8 = 2;
+const MSG_HEARTBEAT: u8 = 3;
+const MSG_RESULT_SUBMIT: u8 = 4;
+const MSG_CONFIG_UPDATE: u8 = 5;
+const MSG_FIRMWARE_OFFER: u8 = 6;
+const MSG_FIRMWARE_REPORT: u8 = 7;
+const MSG_MESH_RELAY: u8 = 8;
+const MSG_MESH_BATCH: u8 = 9;
+
+// TLV field tags; receivers skip tags they don't know
+const TAG_DEVICE_ID: u8 = 1;
+const TAG_DEVICE_TYPE: u8 = 2;
+const TAG_VERSION: u8 = 3;
+const TAG_TASK_ID: u8 = 4;
+const TAG_OPERATION: u8 = 5;
+const TAG_DIFFICULTY: u8 = 6;
+const TAG_NONCE_RANGE: u8 = 7;
+const TAG_MAX_TIME: u8 = 8;
+const TAG_TENSOR: u8 = 9;
+const TAG_NONCE: u8 = 10;
+const TAG_HASH: u8 = 11;
+const TAG_COMPUTE_TIME: u8 = 12;
+const TAG_UPTIME: u8 = 13;
+const TAG_FREE_HEAP: u8 = 14;
+const TAG_TEMPERATURE: u8 = 15;
+const TAG_RSSI: u8 = 16;
+const TAG_TASK_COUNTS: u8 = 17;
+const TAG_INTENSITY: u8 = 18;
+const TAG_POWER_SAVE: u8 = 19;
+const TAG_HEARTBEAT_INTERVAL: u8 = 20;
+const TAG_FIRMWARE_VERSION: u8 = 21;
+const TAG_FIRMWARE_SIZE: u8 = 22;
+const TAG_FIRMWARE_URL: u8 = 23;
+const TAG_SUCCESS: u8 = 24;
+const TAG_PEER: u8 = 25;
+const TAG_FRAME: u8 = 26;
+const TAG_MESH_ENTRY: u8 = 27; // Peer MAC (6 bytes) followed by its frame; repeated
+const TAG_KERNEL: u8 = 28;
+const TAG_BATTERY: u8 = 29; // Voltage in mV (u16 LE) + percent (u8)
+
+// Tensor element encodings
+const ENCODING_F32: u8 = 0;
+const ENCODING_Q8_8: u8 = 1;
+
+/// Operations the firmware implements, by wire code
+const OPERATIONS: &[(u8, &str)] = &[
+    (1, "matrix_multiply"),
+    (2, "relu"),
+    (3, "sigmoid"),
+    (4, "vector_add"),
+    (5, "dot_product"),
+    (6, "tanh"),
+    (7, "softmax"),
+    (8, "convolution"),
+    (9, "normalize"),
+];
+
+/// Messages between the mining server and ESP devices, framed as
+/// `type | len | TLV fields` over a plain TCP stream
+#[derive(Debug, Clone)]
+pub enum ESPMessage {
+    /// Device -> server, first message on a connection; older firmware leaves out its version
+    Hello { device_id: String, device_type: ESPDeviceType, version: u8, firmware_version: Option<String> },
+    /// Server -> device
+    TaskPush {
+        task_id: String,
+        operation: String,
+        difficulty: u8,
+        nonce_range: (u64, u64),
+        max_computation_time: u32,
+        inputs: Vec<Tensor>,
+        kernel: Option<KernelDescriptor>, // Fixed-point kernel for devices without an FPU
+    },
+    /// Device -> server
+    Heartbeat {
+        uptime_seconds: u32,
+        free_heap: u32,
+        temperature_c: f32,
+        wifi_rssi: i8,
+        successful_tasks: u32,
+        failed_tasks: u32,
+        battery: Option<BatteryState>, // Left out by mains-powered devices
+    },
+    /// Device -> server
+    ResultSubmit {
+        task_id: String,
+        nonce: u64,
+        hash: [u8; 32],
+        computation_time_ms: u32,
+        output: Option<Tensor>,
+    },
+    /// Server -> device
+    ConfigUpdate { mining_intensity: u8, power_save_mode: bool, heartbeat_interval_secs: u16 },
+    /// Server -> device, an OTA image to download from `url`
+    FirmwareOffer { version: String, size: u32, sha256: [u8; 32], url: String },
+    /// Device -> server, sent when an offered update fails to apply
+    FirmwareReport { version: String, success: bool },
+    /// Server -> mesh gateway, a frame to forward to one of its ESP-NOW peers
+    MeshRelay { peer: [u8; 6], frame: Vec<u8> },
+    /// Mesh gateway -> server, frames its peers sent since the last batch
+    MeshBatch { entries: Vec<([u8; 6], Vec<u8>)> },
+}
+
+pub(crate) fn device_code(device_type: &ESPDeviceType) -> u8 {
+    match device_type {
+        ESPDeviceType::ESP32 => 1,
+        ESPDeviceType::ESP8266 => 2,
+        ESPDeviceType::ESP32S2 => 3,
+        ESPDeviceType::ESP32S3 => 4,
+        ESPDeviceType::ESP32C3 => 5,
+        ESPDeviceType::ESP32C6 => 6,
+        ESPDeviceType::ESP32H2 => 7,
+    }
+}
+
+fn device_from_code(code: u8) -> TribeResult<ESPDeviceType> {
+    match code {
+        1 => Ok(ESPDeviceType::ESP32),
+        2 => Ok(ESPDeviceType::ESP8266),
+        3 => Ok(ESPDeviceType::ESP32S2),
+        4 => Ok(ESPDeviceType::ESP32S3),
+        5 => Ok(ESPDeviceType::ESP32C3),
+        6 => Ok(ESPDeviceType::ESP32C6),
+        7 => Ok(ESPDeviceType::ESP32H2),
+        _ => Err(protocol_error(format!("Unknown device type {}", code))),
+    }
+}
+
+/// Wire code of `operation`, as devices receive it
+pub fn operation_code(operation: &str) -> TribeResult<u8> {
+    OPERATIONS.iter()
+        .find(|(_, name)| *name == operation)
+        .map(|(code, _)| *code)
+        .ok_or_else(|| protocol_error(format!("Operation {} has no wire code", operation)))
+}
+
+pub(crate) fn operation_name(code: u8) -> TribeResult<String> {
+    OPERATIONS.iter()
+        .find(|(wire, _)| *wire == code)
+        .map(|(_, name)| name.to_string())
+        .ok_or_else(|| protocol_error(format!("Unknown operation code {}", code)))
+}
+
+fn protocol_error(message: String) -> TribeError {
+    TribeError::Esp(format!("ESP protocol: {}", message))
+}
+
+/// Payload builder writing `tag | len (u16 LE) | value` fields
+#[derive(Default)]
+struct FieldWriter {
+    buf: Vec<u8>,
+}
+
+impl FieldWriter {
+    fn field(&mut self, tag: u8, value: &[u8]) -> TribeResult<()> {
+        let len = u16::try_from(value.len()).map_err(|_| protocol_error("Field too large".to_string()))?;
+        self.buf.push(tag);
+        self.buf.extend_from_slice(&len.to_le_bytes());
+        self.buf.extend_from_slice(value);
+        Ok(())
+    }
+
+    fn tensor(&mut self, tensor: &Tensor, fixed_point: bool) -> TribeResult<()> {
+        let dims = &tensor.shape.dimensions;
+        let mut value = vec![dims.len() as u8];
+        for dim in dims {
+            let dim = u16::try_from(*dim).map_err(|_| protocol_error("Tensor dimension too large".to_string()))?;
+            value.extend_from_slice(&dim.to_le_bytes());
+        }
+        let data = tensor.data.as_f32_vec()?;
+        if fixed_point {
+            value.push(ENCODING_Q8_8);
+            for x in data {
+                value.extend_from_slice(&kernels::to_fixed(x).to_le_bytes());
+            }
+        } else {
+            value.push(ENCODING_F32);
+            for x in data {
+                value.extend_from_slice(&x.to_le_bytes());
+            }
+        }
+        self.field(TAG_TENSOR, &value)
+    }
+}
+
+/// Cursor over a payload's TLV fields
+struct FieldReader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Iterator for FieldReader<'a> {
+    type Item = TribeResult<(u8, &'a [u8])>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.buf.is_empty() {
+            return None;
+        }
+        if self.buf.len() < 3 {
+            self.buf = &[];
+            return Some(Err(protocol_error("Truncated field header".to_string())));
+        }
+        let tag = self.buf[0];
+        let len = u16::from_le_bytes([self.buf[1], self.buf[2]]) as usize;
+        if self.buf.len() < 3 + len {
+            self.buf = &[];
+            return Some(Err(protocol_error("Truncated field".to_string())));
+        }
+        let value = &self.buf[3..3 + len];
+        self.buf = &self.buf[3 + len..];
+        Some(Ok((tag, value)))
+    }
+}
+
+fn read_uint<const N: usize>(value: &[u8]) -> TribeResult<[u8; N]> {
+    value.try_into().map_err(|_| protocol_error(format!("Expected {} byte field, got {}", N, value.len())))
+}
+
+fn read_string(value: &[u8]) -> TribeResult<String> {
+    String::from_utf8(value.to_vec()).map_err(|_| protocol_error("Invalid UTF-8 string".to_string()))
+}
+
+fn read_tensor(value: &[u8]) -> TribeResult<Tensor> {
+    let rank = *value.first().ok_or_else(|| protocol_error("Empty tensor".to_string()))? as usize;
+    let header = 1 + rank * 2 + 1;
+    if value.len() < header {
+        return Err(protocol_error("Truncated tensor header".to_string()));
+    }
+    let dims: Vec<usize> = (0..rank)
+        .map(|i| u16::from_le_bytes([value[1 + i * 2], value[2 + i * 2]]) as usize)
+        .collect();
+    let elements: usize = dims.iter().product();
+    let body = &value[header..];
+
+    let data: Vec<f32> = match value[header - 1] {
+        ENCODING_F32 if body.len() == elements * 4 => body.chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect(),
+        ENCODING_Q8_8 if body.len() == elements * 2 => body.chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / 256.0)
+            .collect(),
+        _ => return Err(protocol_error("Tensor data does not match its shape".to_string())),
+    };
+    Tensor::from_vec(data, TensorShape::new(dims))
+}
+
+impl ESPMessage {
+    /// Push `task` to a device. Devices without an FPU get Q8.8 inputs and the kernel
+    /// to run on them; tasks with no fixed-point kernel can't go to those devices.
+    pub fn task_push(task: &MiningTask, device_type: &ESPDeviceType) -> TribeResult<Vec<u8>> {
+        let fixed_point = !device_type.supports_floating_point();
+        ESPMessage::TaskPush {
+            task_id: task.id.clone(),
+            operation: task.operation_type.clone(),
+            difficulty: task.difficulty.min(u8::MAX as u64) as u8,
+            nonce_range: task.nonce_range,
+            max_computation_time: task.max_computation_time.min(u32::MAX as u64) as u32,
+            inputs: task.input_tensors.clone(),
+            kernel: if fixed_point { Some(KernelDescriptor::for_task(task)?) } else { None },
+        }.encode(fixed_point)
+    }
+
+    /// Encode as one frame; `fixed_point` sends tensors as Q8.8
+    pub fn encode(&self, fixed_point: bool) -> TribeResult<Vec<u8>> {
+        let mut fields = FieldWriter::default();
+        let kind = match self {
+            ESPMessage::Hello { device_id, device_type, version, firmware_version } => {
+                fields.field(TAG_DEVICE_ID, device_id.as_bytes())?;
+                fields.field(TAG_DEVICE_TYPE, &[device_code(device_type)])?;
+                fields.field(TAG_VERSION, &[*version])?;
+                if let Some(firmware_version) = firmware_version {
+                    fields.field(TAG_FIRMWARE_VERSION, firmware_version.as_bytes())?;
+                }
+                MSG_HELLO
+            }
+            ESPMessage::TaskPush { task_id, operation, difficulty, nonce_range, max_computation_time, inputs, kernel } => {
+                fields.field(TAG_TASK_ID, task_id.as_bytes())?;
+                fields.field(TAG_OPERATION, &[operation_code(operation)?])?;
+                fields.field(TAG_DIFFICULTY, &[*difficulty])?;
+                let mut range = nonce_range.0.to_le_bytes().to_vec();
+                range.extend_from_slice(&nonce_range.1.to_le_bytes());
+                fields.field(TAG_NONCE_RANGE, &range)?;
+                fields.field(TAG_MAX_TIME, &max_computation_time.to_le_bytes())?;
+                for input in inputs {
+                    fields.tensor(input, fixed_point)?;
+                }
+                if let Some(kernel) = kernel {
+                    fields.field(TAG_KERNEL, &kernel.encode()?)?;
+                }
+                MSG_TASK_PUSH
+            }
+            ESPMessage::Heartbeat { uptime_seconds, free_heap, temperature_c, wifi_rssi, successful_tasks, failed_tasks, battery } => {
+                fields.field(TAG_UPTIME, &uptime_seconds.to_le_bytes())?;
+                fields.field(TAG_FREE_HEAP, &free_heap.to_le_bytes())?;
+                // Tenths of a degree
+                fields.field(TAG_TEMPERATURE, &((temperature_c * 10.0).round() as i16).to_le_bytes())?;
+                fields.field(TAG_RSSI, &wifi_rssi.to_le_bytes())?;
+                let mut counts = successful_tasks.to_le_bytes().to_vec();
+                counts.extend_from_slice(&failed_tasks.to_le_bytes());
+                fields.field(TAG_TASK_COUNTS, &counts)?;
+                if let Some(battery) = battery {
+                    let mut value = battery.voltage_mv.to_le_bytes().to_vec();
+                    value.push(battery.percent);
+                    fields.field(TAG_BATTERY, &value)?;
+                }
+                MSG_HEARTBEAT
+            }
+            ESPMessage::ResultSubmit { task_id, nonce, hash, computation_time_ms, output } => {
+                fields.field(TAG_TASK_ID, task_id.as_bytes())?;
+                fields.field(TAG_NONCE, &nonce.to_le_bytes())?;
+                fields.field(TAG_HASH, hash)?;
+                fields.field(TAG_COMPUTE_TIME, &computation_time_ms.to_le_bytes())?;
+                if let Some(output) = output {
+                    fields.tensor(output, fixed_point)?;
+                }
+                MSG_RESULT_SUBMIT
+            }
+            ESPMessage::ConfigUpdate { mining_intensity, power_save_mode, heartbeat_interval_secs } => {
+                fields.field(TAG_INTENSITY, &[*mining_intensity])?;
+                fields.field(TAG_POWER_SAVE, &[*power_save_mode as u8])?;
+                fields.field(TAG_HEARTBEAT_INTERVAL, &heartbeat_interval_secs.to_le_bytes())?;
+                MSG_CONFIG_UPDATE
+            }
+            ESPMessage::FirmwareOffer { version, size, sha256, url } => {
+                fields.field(TAG_FIRMWARE_VERSION, version.as_bytes())?;
+                fields.field(TAG_FIRMWARE_SIZE, &size.to_le_bytes())?;
+                fields.field(TAG_HASH, sha256)?;
+                fields.field(TAG_FIRMWARE_URL, url.as_bytes())?;
+                MSG_FIRMWARE_OFFER
+            }
+            ESPMessage::FirmwareReport { version, success } => {
+                fields.field(TAG_FIRMWARE_VERSION, version.as_bytes())?;
+                fields.field(TAG_SUCCESS, &[*success as u8])?;
+                MSG_FIRMWARE_REPORT
+            }
+            ESPMessage::MeshRelay { peer, frame } => {
+                fields.field(TAG_PEER, peer)?;
+                fields.field(TAG_FRAME, frame)?;
+                MSG_MESH_RELAY
+            }
+            ESPMessage::MeshBatch { entries } => {
+                for (peer, frame) in entries {
+                    let mut entry = peer.to_vec();
+                    entry.extend_from_slice(frame);
+                    fields.field(TAG_MESH_ENTRY, &entry)?;
+                }
+                MSG_MESH_BATCH
+            }
+        };
+
+        if fields.buf.len() > MAX_FRAME_PAYLOAD {
+            return Err(protocol_error(format!(
+                "Payload of {} bytes exceeds the {} byte frame limit", fields.buf.len(), MAX_FRAME_PAYLOAD
+            )));
+        }
+        let mut frame = Vec::with_capacity(FRAME_HEADER_LEN + fields.buf.len());
+        frame.push(kind);
+        frame.extend_from_slice(&(fields.buf.len() as u16).to_le_bytes());
+        frame.extend_from_slice(&fields.buf);
+        Ok(frame)
+    }
+
+    /// Decode one frame's type and payload
+    pub fn decode(kind: u8, payload: &[u8]) -> TribeResult<Self> {
+        let mut fields = std::collections::HashMap::new();
+        let mut tensors = Vec::new();
+        let mut mesh_entries = Vec::new();
+        for field in (FieldReader { buf: payload }) {
+            let (tag, value) = field?;
+            if tag == TAG_TENSOR {
+                tensors.push(read_tensor(value)?);
+            } else if tag == TAG_MESH_ENTRY {
+                if value.len() < 6 {
+                    return Err(protocol_error("Truncated mesh entry".to_string()));
+                }
+                mesh_entries.push((read_uint::<6>(&value[..6])?, value[6..].to_vec()));
+            } else {
+                fields.insert(tag, value);
+            }
+        }
+        let get = |tag: u8| fields.get(&tag).copied()
+            .ok_or_else(|| protocol_error(format!("Missing field {} in message {}", tag, kind)));
+
+        match kind {
+            MSG_HELLO => Ok(ESPMessage::Hello {
+                device_id: read_string(get(TAG_DEVICE_ID)?)?,
+                device_type: device_from_code(read_uint::<1>(get(TAG_DEVICE_TYPE)?)?[0])?,
+                version: read_uint::<1>(get(TAG_VERSION)?)?[0],
+                firmware_version: fields.get(&TAG_FIRMWARE_VERSION).map(|value| read_string(value)).transpose()?,
+            }),
+            MSG_TASK_PUSH => {
+                let range = read_uint::<16>(get(TAG_NONCE_RANGE)?)?;
+                Ok(ESPMessage::TaskPush {
+                    task_id: read_string(get(TAG_TASK_ID)?)?,
+                    operation: operation_name(read_uint::<1>(get(TAG_OPERATION)?)?[0])?,
+                    difficulty: read_uint::<1>(get(TAG_DIFFICULTY)?)?[0],
+                    nonce_range: (
+                        u64::from_le_bytes(range[..8].try_into().expect("16 byte range")),
+                        u64::from_le_bytes(range[8..].try_into().expect("16 byte range")),
+                    ),
+                    max_computation_time: u32::from_le_bytes(read_uint(get(TAG_MAX_TIME)?)?),
+                    inputs: tensors,
+                    kernel: fields.get(&TAG_KERNEL).map(|value| KernelDescriptor::decode(value)).transpose()?,
+                })
+            }
+            MSG_HEARTBEAT => {
+                let counts = read_uint::<8>(get(TAG_TASK_COUNTS)?)?;
+                Ok(ESPMessage::Heartbeat {
+                    uptime_seconds: u32::from_le_bytes(read_uint(get(TAG_UPTIME)?)?),
+                    free_heap: u32::from_le_bytes(read_uint(get(TAG_FREE_HEAP)?)?),
+                    temperature_c: i16::from_le_bytes(read_uint(get(TAG_TEMPERATURE)?)?) as f32 / 10.0,
+                    wifi_rssi: i8::from_le_bytes(read_uint(get(TAG_RSSI)?)?),
+                    successful_tasks: u32::from_le_bytes(counts[..4].try_into().expect("8 byte counts")),
+                    failed_tasks: u32::from_le_bytes(counts[4..].try_into().expect("8 byte counts")),
+                    battery: fields.get(&TAG_BATTERY)
+                        .map(|value| read_uint::<3>(value))
+                        .transpose()?
+                        .map(|b| BatteryState { voltage_mv: u16::from_le_bytes([b[0], b[1]]), percent: b[2].min(100) }),
+                })
+            }
+            MSG_RESULT_SUBMIT => Ok(ESPMessage::ResultSubmit {
+                task_id: read_string(get(TAG_TASK_ID)?)?,
+                nonce: u64::from_le_bytes(read_uint(get(TAG_NONCE)?)?),
+                hash: read_uint(get(TAG_HASH)?)?,
+                computation_time_ms: u32::from_le_bytes(read_uint(get(TAG_COMPUTE_TIME)?)?),
+                output: tensors.into_iter().next(),
+            }),
+            MSG_CONFIG_UPDATE => Ok(ESPMessage::ConfigUpdate {
+                mining_intensity: read_uint::<1>(get(TAG_INTENSITY)?)?[0],
+                power_save_mode: read_uint::<1>(get(TAG_POWER_SAVE)?)?[0] != 0,
+                heartbeat_interval_secs: u16::from_le_bytes(read_uint(get(TAG_HEARTBEAT_INTERVAL)?)?),
+            }),
+            MSG_FIRMWARE_OFFER => Ok(ESPMessage::FirmwareOffer {
+                version: read_string(get(TAG_FIRMWARE_VERSION)?)?,
+                size: u32::from_le_bytes(read_uint(get(TAG_FIRMWARE_SIZE)?)?),
+                sha256: read_uint(get(TAG_HASH)?)?,
+                url: read_string(get(TAG_FIRMWARE_URL)?)?,
+            }),
+            MSG_FIRMWARE_REPORT => Ok(ESPMessage::FirmwareReport {
+                version: read_string(get(TAG_FIRMWARE_VERSION)?)?,
+                success: read_uint::<1>(get(TAG_SUCCESS)?)?[0] != 0,
+            }),
+            MSG_MESH_RELAY => Ok(ESPMessage::MeshRelay {
+                peer: read_uint(get(TAG_PEER)?)?,
+                frame: get(TAG_FRAME)?.to_vec(),
+            }),
+            MSG_MESH_BATCH => Ok(ESPMessage::MeshBatch { entries: mesh_entries }),
+            _ => Err(protocol_error(format!("Unknown message type {}", kind))),
+        }
+    }
+
+    /// Decode a complete frame, as carried whole in an MQTT payload
+    pub fn from_frame(frame: &[u8]) -> TribeResult<Self> {
+        if frame.len() < FRAME_HEADER_LEN {
+            return Err(protocol_error("Truncated frame header".to_string()));
+        }
+        let len = u16::from_le_bytes([frame[1], frame[2]]) as usize;
+        if len > MAX_FRAME_PAYLOAD || frame.len() != FRAME_HEADER_LEN + len {
+            return Err(protocol_error("Frame length does not match its header".to_string()));
+        }
+        Self::decode(frame[0], &frame[FRAME_HEADER_LEN..])
+    }
+
+    /// Result to validate, for a `ResultSubmit`; devices that skip the output send only the nonce
+    pub fn into_mining_result(self, miner_id: String) -> Option<MiningResult> {
+        match self {
+            ESPMessage::ResultSubmit { task_id, nonce, hash, computation_time_ms, output } => Some(MiningResult::new(
+                task_id,
+                miner_id,
+                nonce,
+                hex::encode(hash),
+                output.unwrap_or_else(|| Tensor::vector(Vec::new())),
+                computation_time_ms as u64,
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// Reassembles frames from a TCP byte stream
+#[derive(Debug, Default)]
+pub struct ESPFrameDecoder {
+    buffer: Vec<u8>,
+}
+
+impl ESPFrameDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// Next complete message, if one has arrived. An oversized frame is an error,
+    /// after which the connection should be dropped.
+    pub fn next_message(&mut self) -> TribeResult<Option<ESPMessage>> {
+        if self.buffer.len() < FRAME_HEADER_LEN {
+            return Ok(None);
+        }
+        let len = u16::from_le_bytes([self.buffer[1], self.buffer[2]]) as usize;
+        if len > MAX_FRAME_PAYLOAD {
+            return Err(protocol_error(format!("Frame of {} bytes exceeds the limit", len)));
+        }
+        if self.buffer.len() < FRAME_HEADER_LEN + len {
+            return Ok(None);
+        }
+
+        let frame: Vec<u8> = self.buffer.drain(..FRAME_HEADER_LEN + len).collect();
+        ESPMessage::decode(frame[0], &frame[FRAME_HEADER_LEN..]).map(Some)
+    }
+}
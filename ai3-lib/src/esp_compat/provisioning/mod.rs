@@ -0,0 +1,168 @@
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use crate::esp_compat::config::ESPMiningConfig;
+use tribechain_core::{TribeResult, TribeError};
+
+/// Length of the per-device key secrets are encrypted with
+pub const DEVICE_KEY_LEN: usize = 32;
+
+/// Largest sealed secrets blob the firmware stores
+pub const SECRETS_MAX_BLOB: usize = 400;
+
+/// Sealed blob: magic | version | nonce | ciphertext | HMAC-SHA256 tag
+pub const SECRETS_MAGIC: &[u8; 4] = b"AI3S";
+pub const SECRETS_VERSION: u8 = 1;
+const NONCE_LEN: usize = 16;
+const TAG_LEN: usize = 32;
+const HEADER_LEN: usize = SECRETS_MAGIC.len() + 1 + NONCE_LEN;
+
+// Secret fields, TLV encoded like wire protocol fields
+const SECRET_WIFI_SSID: u8 = 1;
+const SECRET_WIFI_PASSWORD: u8 = 2;
+const SECRET_SERVER_ADDRESS: u8 = 3;
+const SECRET_SERVER_PORT: u8 = 4;
+
+/// Labels deriving the encryption and MAC keys from the device key
+pub const ENC_KEY_LABEL: &str = "ai3-secrets-enc";
+pub const MAC_KEY_LABEL: &str = "ai3-secrets-mac";
+
+/// What a device needs to reach the mining network, kept out of generated firmware
+/// when provisioning is `ESPProvisioning::Encrypted`
+#[derive(Clone, PartialEq, Eq)]
+pub struct DeviceSecrets {
+    pub wifi_ssid: String,
+    pub wifi_password: String,
+    pub server_address: String,
+    pub server_port: u16,
+}
+
+impl std::fmt::Debug for DeviceSecrets {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DeviceSecrets")
+            .field("wifi_ssid", &self.wifi_ssid)
+            .field("wifi_password", &"<redacted>")
+            .field("server_address", &self.server_address)
+            .field("server_port", &self.server_port)
+            .finish()
+    }
+}
+
+fn secrets_error(message: &str) -> TribeError {
+    TribeError::Esp(format!("ESP secrets: {}", message))
+}
+
+/// HMAC-SHA256 of the concatenated `parts`; keys are at most one block long
+pub(crate) fn hmac_sha256(key: &[u8], parts: &[&[u8]]) -> [u8; 32] {
+    let mut block = [0u8; 64];
+    block[..key.len()].copy_from_slice(key);
+    let mut inner = Sha256::new();
+    inner.update(block.map(|b| b ^ 0x36));
+    for part in parts {
+        inner.update(part);
+    }
+    let mut outer = Sha256::new();
+    outer.update(block.map(|b| b ^ 0x5c));
+    outer.update(inner.finalize());
+    outer.finalize().into()
+}
+
+/// XOR `data` with an HMAC-SHA256 counter-mode keystream
+fn apply_keystream(enc_key: &[u8; 32], nonce: &[u8], data: &mut [u8]) {
+    for (block, chunk) in data.chunks_mut(32).enumerate() {
+        let stream = hmac_sha256(enc_key, &[nonce, &(block as u32).to_le_bytes()]);
+        for (byte, key) in chunk.iter_mut().zip(stream) {
+            *byte ^= key;
+        }
+    }
+}
+
+fn derived_keys(device_key: &[u8; DEVICE_KEY_LEN]) -> ([u8; 32], [u8; 32]) {
+    (hmac_sha256(device_key, &[ENC_KEY_LABEL.as_bytes()]), hmac_sha256(device_key, &[MAC_KEY_LABEL.as_bytes()]))
+}
+
+/// A fresh random device key
+pub fn generate_device_key() -> [u8; DEVICE_KEY_LEN] {
+    let mut key = [0u8; DEVICE_KEY_LEN];
+    rand::thread_rng().fill_bytes(&mut key);
+    key
+}
+
+/// Serial line a device waiting for secrets stores and boots with
+pub fn provisioning_command(device_key: &[u8; DEVICE_KEY_LEN], sealed: &[u8]) -> String {
+    format!("PROVISION {} {}", hex::encode(device_key), hex::encode(sealed))
+}
+
+impl DeviceSecrets {
+    pub fn from_config(config: &ESPMiningConfig) -> Self {
+        Self {
+            wifi_ssid: config.wifi_ssid.clone(),
+            wifi_password: config.wifi_password.clone(),
+            server_address: config.server_address.clone(),
+            server_port: config.server_port,
+        }
+    }
+
+    /// Encrypt and authenticate under `device_key` with a random nonce
+    pub fn seal(&self, device_key: &[u8; DEVICE_KEY_LEN]) -> TribeResult<Vec<u8>> {
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::thread_rng().fill_bytes(&mut nonce);
+
+        let mut body = Vec::new();
+        for (tag, value) in [
+            (SECRET_WIFI_SSID, self.wifi_ssid.as_bytes()),
+            (SECRET_WIFI_PASSWORD, self.wifi_password.as_bytes()),
+            (SECRET_SERVER_ADDRESS, self.server_address.as_bytes()),
+            (SECRET_SERVER_PORT, &self.server_port.to_le_bytes()),
+        ] {
+            let len = u16::try_from(value.len()).map_err(|_| secrets_error("field too large"))?;
+            body.push(tag);
+            body.extend_from_slice(&len.to_le_bytes());
+            body.extend_from_slice(value);
+        }
+
+        let (enc_key, mac_key) = derived_keys(device_key);
+        apply_keystream(&enc_key, &nonce, &mut body);
+        let mut sealed = SECRETS_MAGIC.to_vec();
+        sealed.push(SECRETS_VERSION);
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&body);
+        sealed.extend_from_slice(&hmac_sha256(&mac_key, &[&nonce, &body]));
+        if sealed.len() > SECRETS_MAX_BLOB {
+            return Err(secrets_error(&format!("{} bytes sealed, devices store at most {}", sealed.len(), SECRETS_MAX_BLOB)));
+        }
+        Ok(sealed)
+    }
+
+    /// Check and decrypt a blob from `seal`, as the firmware does at boot
+    pub fn open(sealed: &[u8], device_key: &[u8; DEVICE_KEY_LEN]) -> TribeResult<Self> {
+        if sealed.len() < HEADER_LEN + TAG_LEN || &sealed[..4] != SECRETS_MAGIC || sealed[4] != SECRETS_VERSION {
+            return Err(secrets_error("not a sealed secrets blob"));
+        }
+        let nonce = &sealed[5..HEADER_LEN];
+        let (body, tag) = sealed[HEADER_LEN..].split_at(sealed.len() - HEADER_LEN - TAG_LEN);
+        let (enc_key, mac_key) = derived_keys(device_key);
+        let expected = hmac_sha256(&mac_key, &[nonce, body]);
+        if expected.iter().zip(tag).fold(0u8, |diff, (a, b)| diff | (a ^ b)) != 0 {
+            return Err(secrets_error("authentication failed; wrong device key or tampered blob"));
+        }
+
+        let mut body = body.to_vec();
+        apply_keystream(&enc_key, nonce, &mut body);
+        let mut secrets = Self { wifi_ssid: String::new(), wifi_password: String::new(), server_address: String::new(), server_port: 0 };
+        let mut rest = body.as_slice();
+        while rest.len() >= 3 {
+            let len = u16::from_le_bytes([rest[1], rest[2]]) as usize;
+            let value = rest.get(3..3 + len).ok_or_else(|| secrets_error("truncated field"))?;
+            let text = || String::from_utf8(value.to_vec()).map_err(|_| secrets_error("invalid UTF-8"));
+            match rest[0] {
+                SECRET_WIFI_SSID => secrets.wifi_ssid = text()?,
+                SECRET_WIFI_PASSWORD => secrets.wifi_password = text()?,
+                SECRET_SERVER_ADDRESS => secrets.server_address = text()?,
+                SECRET_SERVER_PORT if len == 2 => secrets.server_port = u16::from_le_bytes([value[0], value[1]]),
+                _ => {}
+            }
+            rest = &rest[3 + len..];
+        }
+        Ok(secrets)
+    }
+}
@@ -1,16 +1,16 @@
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests {
-    use super::super::*;
     use crate::esp_compat::devices::ESPDeviceType;
     use crate::esp_compat::config::ESPMiningConfig;
     use crate::esp_compat::miners::{ESP32Miner, ESP8266Miner, ConnectionStatus};
     use crate::esp_compat::codegen::{ESPTensorUtils, ESPCodeGenerator};
-    use crate::tensor::Tensor;
+    use crate::tensor::{Tensor, TensorShape};
 
     #[test]
     fn test_esp_device_types() {
         let esp32 = ESPDeviceType::ESP32;
-        assert_eq!(esp32.get_memory_limit(), 520);
+        assert_eq!(esp32.get_memory_limit(), 320);
         assert_eq!(esp32.get_compute_power(), 240);
         assert!(esp32.supports_floating_point());
 
@@ -29,21 +29,24 @@ mod tests {
     fn test_esp_mining_config() {
         let config = ESPMiningConfig::default();
         assert_eq!(config.device_type, ESPDeviceType::ESP32);
-        assert_eq!(config.max_memory_kb, 520);
+        assert_eq!(config.max_memory_kb, 320);
         assert_eq!(config.clock_speed_mhz, 240);
-        assert_eq!(config.wifi_ssid, "TribeChain_Network");
+        assert_eq!(config.wifi_ssid, "TribeChain_Mining");
         assert_eq!(config.server_address, "192.168.1.100");
-        assert_eq!(config.server_port, 8080);
+        assert_eq!(config.server_port, 8333);
         assert_eq!(config.mining_intensity, 5);
         assert!(!config.power_save_mode);
     }
 
+    fn esp32_miner(config: ESPMiningConfig) -> ESP32Miner {
+        ESP32Miner::new("esp32-1".to_string(), "miner".to_string(), config)
+    }
+
     #[test]
     fn test_esp32_miner_creation() {
-        let config = ESPMiningConfig::default();
-        let miner = ESP32Miner::new(config);
+        let miner = esp32_miner(ESPMiningConfig::default());
         
-        assert_eq!(miner.connection_status, ConnectionStatus::Disconnected);
+        assert!(matches!(miner.connection_status, ConnectionStatus::Disconnected));
         assert_eq!(miner.performance_stats.uptime_seconds, 0);
         assert_eq!(miner.performance_stats.successful_tasks, 0);
         assert_eq!(miner.performance_stats.failed_tasks, 0);
@@ -51,33 +54,29 @@ mod tests {
 
     #[test]
     fn test_esp8266_miner_creation() {
-        let mut config = ESPMiningConfig::default();
-        config.device_type = ESPDeviceType::ESP8266;
-        config.max_memory_kb = 80;
+        let miner = ESP8266Miner::new("esp8266-1".to_string(), "miner".to_string(), ESPMiningConfig::default());
         
-        let miner = ESP8266Miner::new(config);
-        
-        assert_eq!(miner.connection_status, ConnectionStatus::Disconnected);
-        assert_eq!(miner.performance_stats.uptime_seconds, 0);
+        assert_eq!(miner.esp32_miner.config.device_type, ESPDeviceType::ESP8266);
+        assert_eq!(miner.esp32_miner.config.max_memory_kb, 80);
+        assert!(matches!(miner.esp32_miner.connection_status, ConnectionStatus::Disconnected));
+        assert_eq!(miner.esp32_miner.performance_stats.uptime_seconds, 0);
     }
 
     #[test]
     fn test_esp_memory_check() {
-        let config = ESPMiningConfig::default();
-        let miner = ESP32Miner::new(config);
-        
-        // Test with small tensor (should pass)
-        let small_tensor = Tensor::zeros(vec![10, 10]).unwrap();
-        assert!(miner.check_memory_constraints(&small_tensor));
+        // Within the device's memory the miner connects
+        let mut miner = esp32_miner(ESPMiningConfig::default());
+        miner.initialize().unwrap();
+        assert!(matches!(miner.connection_status, ConnectionStatus::Connected));
         
-        // Test with large tensor (should fail)
-        let large_tensor = Tensor::zeros(vec![1000, 1000]).unwrap();
-        assert!(!miner.check_memory_constraints(&large_tensor));
+        // Asking for more memory than the device has fails
+        let mut miner = esp32_miner(ESPMiningConfig { max_memory_kb: 1000, ..Default::default() });
+        assert!(matches!(miner.initialize(), Err(tribechain_core::TribeError::Esp(_))));
     }
 
     #[test]
     fn test_esp_tensor_optimization() {
-        let tensor = Tensor::from_vec(vec![1.5, -2.3, 0.7, -0.1], vec![2, 2]).unwrap();
+        let tensor = Tensor::from_vec(vec![1.5, -2.3, 0.7, -0.1], TensorShape::new(vec![2, 2])).unwrap();
         
         // Test optimization for ESP32 (should preserve floating point)
         let esp32_result = ESPTensorUtils::optimize_tensor_for_esp(&tensor, &ESPDeviceType::ESP32);
@@ -91,13 +90,13 @@ mod tests {
         let data = optimized.data.as_f32_vec().unwrap();
         
         // Values should be quantized due to fixed-point conversion
-        assert!((data[0] - 1.49609375).abs() < 0.01); // 1.5 quantized to Q8.8
+        assert!((data[0] - 1.496_093_8).abs() < 0.01); // 1.5 quantized to Q8.8
     }
 
     #[test]
     fn test_esp_memory_estimation() {
-        let tensor1 = Tensor::zeros(vec![10, 10]).unwrap();
-        let tensor2 = Tensor::zeros(vec![10, 5]).unwrap();
+        let tensor1 = Tensor::zeros(TensorShape::new(vec![10, 10]));
+        let tensor2 = Tensor::zeros(TensorShape::new(vec![10, 5]));
         let tensors = vec![tensor1, tensor2];
         
         let matrix_memory = ESPTensorUtils::estimate_memory_usage(&tensors, "matrix_multiply");
@@ -113,19 +112,18 @@ mod tests {
 
     #[test]
     fn test_esp_operation_feasibility() {
-        let small_tensor = Tensor::zeros(vec![5, 5]).unwrap();
-        let large_tensor = Tensor::zeros(vec![100, 100]).unwrap();
-        
+        let small_tensor = Tensor::zeros(TensorShape::new(vec![5, 5]));
+        let large_tensor = Tensor::zeros(TensorShape::new(vec![100, 100]));
         // Small tensor should work on ESP8266
         assert!(ESPTensorUtils::can_run_on_esp(
-            &[small_tensor.clone()], 
+            std::slice::from_ref(&small_tensor), 
             "matrix_multiply", 
             &ESPDeviceType::ESP8266
         ));
         
         // Large tensor should not work on ESP8266
         assert!(!ESPTensorUtils::can_run_on_esp(
-            &[large_tensor.clone()], 
+            std::slice::from_ref(&large_tensor), 
             "matrix_multiply", 
             &ESPDeviceType::ESP8266
         ));
@@ -145,13 +143,16 @@ mod tests {
         
         // Check that generated code contains expected elements
         assert!(code.contains("WiFi.h"));
-        assert!(code.contains("HTTPClient.h"));
-        assert!(code.contains("TribeChain_Network")); // Default SSID
+        assert!(!code.contains("HTTPClient.h"));
+        assert!(code.contains("TribeChain_Mining")); // Default SSID
         assert!(code.contains("192.168.1.100")); // Default server address
-        assert!(code.contains("8080")); // Default port
+        assert!(code.contains("8333")); // Default port
         assert!(code.contains("void setup()"));
         assert!(code.contains("void loop()"));
-        assert!(code.contains("getMiningTask()"));
+        assert!(code.contains("connectToServer()"));
+        assert!(code.contains("readFrame"));
+        assert!(code.contains("sendHeartbeat"));
+        assert!(code.contains("applyConfigUpdate"));
         assert!(code.contains("processMiningTask"));
         assert!(code.contains("submitResult"));
     }
@@ -175,14 +176,14 @@ mod tests {
 
     #[test]
     fn test_esp_performance_monitoring() {
-        let config = ESPMiningConfig::default();
-        let mut miner = ESP32Miner::new(config);
+        let mut miner = esp32_miner(ESPMiningConfig::default());
         
         // Simulate some mining activity
         miner.performance_stats.successful_tasks = 10;
         miner.performance_stats.failed_tasks = 2;
         miner.performance_stats.uptime_seconds = 3600; // 1 hour
         miner.performance_stats.cpu_temperature = 65.0;
+        miner.performance_stats.wifi_signal_strength = -60;
         
         // Test throttling check
         assert!(!miner.should_throttle()); // 65°C should be fine
@@ -193,52 +194,687 @@ mod tests {
 
     #[test]
     fn test_esp_task_optimization() {
-        let config = ESPMiningConfig::default();
-        let miner = ESP32Miner::new(config);
-        
-        // Test task optimization for different operation types
-        let matrix_task = "matrix_multiply";
-        let relu_task = "relu";
-        let conv_task = "convolution";
-        
-        // All should return some optimization (even if minimal)
-        assert!(miner.optimize_task_for_device(matrix_task).len() > 0);
-        assert!(miner.optimize_task_for_device(relu_task).len() > 0);
-        assert!(miner.optimize_task_for_device(conv_task).len() > 0);
-    }
+        use crate::mining::MiningTask;
 
-    #[test]
-    fn test_esp8266_fixed_point_conversion() {
-        let mut config = ESPMiningConfig::default();
-        config.device_type = ESPDeviceType::ESP8266;
-        
-        let miner = ESP8266Miner::new(config);
-        
-        // Test fixed-point conversion
-        let float_val = 3.14159;
-        let fixed_val = miner.float_to_fixed_point(float_val);
-        let converted_back = miner.fixed_point_to_float(fixed_val);
+        let mut miner = esp32_miner(ESPMiningConfig::default());
+        let task = |elements: usize, max_computation_time: u64| MiningTask::new(
+            "relu".to_string(),
+            vec![Tensor::zeros(TensorShape::new(vec![elements]))],
+            2,
+            100,
+            max_computation_time,
+            "requester".to_string(),
+        );
         
-        // Should be close but not exact due to quantization
-        assert!((converted_back - float_val).abs() < 0.01);
+        // Small tasks mine hard, large ones back off, long ones save power
+        miner.optimize_for_task(&task(16, 60)).unwrap();
+        assert_eq!((miner.config.mining_intensity, miner.config.power_save_mode), (7, false));
+        miner.optimize_for_task(&task(1024, 600)).unwrap();
+        assert_eq!((miner.config.mining_intensity, miner.config.power_save_mode), (3, true));
     }
 
     #[test]
     fn test_esp_connection_status() {
-        let config = ESPMiningConfig::default();
-        let mut miner = ESP32Miner::new(config);
+        let mut miner = esp32_miner(ESPMiningConfig::default());
         
         // Test connection status transitions
-        assert_eq!(miner.connection_status, ConnectionStatus::Disconnected);
+        assert!(matches!(miner.connection_status, ConnectionStatus::Disconnected));
         
-        // Simulate connection process
-        miner.connection_status = ConnectionStatus::Connecting;
-        assert_eq!(miner.connection_status, ConnectionStatus::Connecting);
+        // Mining needs a connection
+        assert!(miner.mine_step().is_err());
+        miner.initialize().unwrap();
+        assert!(matches!(miner.connection_status, ConnectionStatus::Connected));
         
+        miner.connection_status = ConnectionStatus::Error("lost".to_string());
+        assert!(matches!(&miner.connection_status, ConnectionStatus::Error(reason) if reason == "lost"));
+        assert!(miner.mine_step().is_err());
+    }
+
+    #[test]
+    fn test_esp_protocol_round_trip() {
+        use crate::esp_compat::protocol::{ESPMessage, ESPFrameDecoder};
+        use crate::mining::MiningTask;
+
+        let task = MiningTask::new(
+            "relu".to_string(),
+            vec![Tensor::vector(vec![1.5, -0.25, 3.0])],
+            2,
+            100,
+            60,
+            "requester".to_string(),
+        );
+        let push = ESPMessage::task_push(&task, &ESPDeviceType::ESP8266).unwrap();
+        let heartbeat = ESPMessage::Heartbeat {
+            uptime_seconds: 120,
+            free_heap: 40 * 1024,
+            temperature_c: 41.5,
+            wifi_rssi: -62,
+            successful_tasks: 3,
+            failed_tasks: 1,
+            battery: None,
+        };
+
+        // Frames split across reads are reassembled in order
+        let mut stream = push.clone();
+        stream.extend(heartbeat.encode(false).unwrap());
+        let mut decoder = ESPFrameDecoder::new();
+        decoder.push(&stream[..5]);
+        assert!(decoder.next_message().unwrap().is_none());
+        decoder.push(&stream[5..]);
+
+        match decoder.next_message().unwrap().unwrap() {
+            ESPMessage::TaskPush { task_id, operation, inputs, .. } => {
+                assert_eq!(task_id, task.id);
+                assert_eq!(operation, "relu");
+                // Q8.8 for the FPU-less ESP8266
+                assert_eq!(inputs[0].data.as_f32_vec().unwrap(), vec![1.5, -0.25, 3.0]);
+            }
+            other => panic!("unexpected message {:?}", other),
+        }
+        let received = decoder.next_message().unwrap().unwrap();
+        assert!(matches!(
+            received,
+            ESPMessage::Heartbeat { uptime_seconds: 120, wifi_rssi: -62, temperature_c, .. } if temperature_c == 41.5
+        ));
+        assert!(decoder.next_message().unwrap().is_none());
+
+        let mut miner = ESP32Miner::new("esp".to_string(), "addr".to_string(), ESPMiningConfig::default());
+        miner.apply_message(&received);
+        assert_eq!(miner.performance_stats.successful_tasks, 3);
+        assert_eq!(miner.performance_stats.wifi_signal_strength, -62);
+
+        // Results submitted as raw hash bytes come back as a hex MiningResult
+        let submit = ESPMessage::ResultSubmit {
+            task_id: task.id.clone(),
+            nonce: 7,
+            hash: [0xab; 32],
+            computation_time_ms: 15,
+            output: None,
+        };
+        let (kind, payload) = {
+            let frame = submit.encode(false).unwrap();
+            (frame[0], frame[3..].to_vec())
+        };
+        let result = ESPMessage::decode(kind, &payload).unwrap().into_mining_result("esp".to_string()).unwrap();
+        assert_eq!(result.nonce, 7);
+        assert_eq!(result.hash, "ab".repeat(32));
+
+        // Oversized frames are refused
+        let mut decoder = ESPFrameDecoder::new();
+        decoder.push(&[3, 0xff, 0xff]);
+        assert!(decoder.next_message().is_err());
+    }
+
+    #[test]
+    fn test_mqtt_bridge() {
+        use crate::esp_compat::mqtt::{self, MqttBridge};
+        use crate::esp_compat::protocol::{ESPMessage, ESP_PROTOCOL_VERSION};
+        use crate::mining::{MiningTask, TaskDistributor};
+
+        let mut bridge = MqttBridge::new();
+        let mut distributor = TaskDistributor::new();
+
+        let hello = ESPMessage::Hello {
+            device_id: "esp-1".to_string(),
+            device_type: ESPDeviceType::ESP8266,
+            version: ESP_PROTOCOL_VERSION,
+            firmware_version: None,
+        }.encode(false).unwrap();
+        let replies = bridge.handle(&mut distributor, &mqtt::hello_topic("esp-1"), &hello).unwrap();
+        assert_eq!(replies.len(), 1);
+        assert_eq!(replies[0].topic, mqtt::config_topic("esp-1"));
+        assert!(replies[0].retain);
+        // A device can't say hello for another
+        assert!(bridge.handle(&mut distributor, &mqtt::hello_topic("esp-2"), &hello).is_err());
+
+        // Pending tasks are offered on both encodings of their capability
+        let task = MiningTask::new(
+            "relu".to_string(),
+            vec![Tensor::vector(vec![1.0, -1.0])],
+            0,
+            100,
+            60,
+            "requester".to_string(),
+        );
+        distributor.add_task(task.clone());
+        let offers = bridge.publish_pending(&mut distributor);
+        let topics: Vec<_> = offers.iter().map(|offer| offer.topic.as_str()).collect();
+        assert_eq!(topics, vec!["tribechain/tasks/relu", "tribechain/tasks/relu_q8"]);
+        assert!(mqtt::capabilities(&ESPDeviceType::ESP8266).contains(&"relu_q8".to_string()));
+        assert!(distributor.pending_tasks.is_empty());
+        assert!(bridge.publish_pending(&mut distributor).is_empty());
+
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(&hex::decode(task.calculate_hash(9)).unwrap());
+        let submit = ESPMessage::ResultSubmit {
+            task_id: task.id.clone(),
+            nonce: 9,
+            hash,
+            computation_time_ms: 20,
+            output: Some(Tensor::vector(vec![1.0, 0.0])),
+        }.encode(false).unwrap();
+        assert!(bridge.handle(&mut distributor, &mqtt::result_topic("stranger"), &submit).is_err());
+        bridge.handle(&mut distributor, &mqtt::result_topic("esp-1"), &submit).unwrap();
+        assert_eq!(distributor.completed_tasks[&task.id].miner_id, "esp-1");
+        assert_eq!(bridge.devices["esp-1"].performance_stats.successful_tasks, 1);
+
+        let heartbeat = ESPMessage::Heartbeat {
+            uptime_seconds: 60,
+            free_heap: 20 * 1024,
+            temperature_c: 38.0,
+            wifi_rssi: -70,
+            successful_tasks: 1,
+            failed_tasks: 0,
+            battery: None,
+        }.encode(false).unwrap();
+        bridge.handle(&mut distributor, &mqtt::telemetry_topic("esp-1"), &heartbeat).unwrap();
+        assert_eq!(bridge.devices["esp-1"].performance_stats.wifi_signal_strength, -70);
+
+        let mut config = ESPMiningConfig::default();
+        config.device_type = ESPDeviceType::ESP8266;
+        config.transport = crate::esp_compat::config::ESPTransport::Mqtt;
+        let code = ESPCodeGenerator::generate_mining_code(&config);
+        assert!(code.contains("PubSubClient.h"));
+        assert!(code.contains("\"relu_q8\""));
+        assert!(code.contains("onMessage"));
+    }
+
+    #[test]
+    fn test_ota_rollout_and_rollback() {
+        use crate::esp_compat::ESPCompatibility;
+        use crate::esp_compat::ota::{sign_firmware, OtaManager, RolloutPolicy, RolloutStage};
+        use crate::esp_compat::protocol::ESPMessage;
+        use sha2::{Digest, Sha256};
+
+        let policy = RolloutPolicy { canary_percent: 100, promote_after: 2, max_failure_rate: 0.5, min_reports: 3 };
+        let mut ota = OtaManager::new("release-key".to_string(), "http://node/ota".to_string()).with_policy(policy);
+        let signed = |image: &[u8]| sign_firmware(&hex::encode(Sha256::digest(image)), "release-key");
+        let esp32 = ESPDeviceType::ESP32;
+
+        let v1 = b"firmware v1".to_vec();
+        assert!(ESPCompatibility::publish_firmware(&mut ota, esp32.clone(), v1.clone(), "1.0.0", "forged").is_err());
+        ESPCompatibility::publish_firmware(&mut ota, esp32.clone(), v1.clone(), "1.0.0", &signed(&v1)).unwrap();
+
+        // Canary devices are offered the image and count as successes once they run it
+        for device in ["a", "b"] {
+            match ota.check_update(device, &esp32, "0.9.0") {
+                Some(ESPMessage::FirmwareOffer { version, size, url, .. }) => {
+                    assert_eq!(version, "1.0.0");
+                    assert_eq!(size as usize, v1.len());
+                    assert_eq!(url, "http://node/ota/ESP32/1.0.0.bin");
+                }
+                other => panic!("expected an offer, got {:?}", other),
+            }
+            assert!(ota.check_update(device, &esp32, "1.0.0").is_none());
+        }
+        assert_eq!(ota.get_rollout(&esp32).unwrap().stage, RolloutStage::Full);
+
+        let v2 = b"firmware v2".to_vec();
+        ota.publish(esp32.clone(), v2.clone(), "1.1.0", &signed(&v2)).unwrap();
+        assert!(ota.publish(esp32.clone(), v2.clone(), "1.0.5", &signed(&v2)).is_err());
+
+        // A failure spike rolls the fleet back to the last stable image
+        for device in ["c", "d", "e"] {
+            assert!(ota.check_update(device, &esp32, "1.0.0").is_some());
+            ota.report(device, &ESPMessage::FirmwareReport { version: "1.1.0".to_string(), success: false }).unwrap();
+        }
+        assert_eq!(ota.get_rollout(&esp32).unwrap().stage, RolloutStage::RolledBack);
+        assert!(ota.check_update("c", &esp32, "1.0.0").is_none());
+        match ota.check_update("f", &esp32, "1.1.0") {
+            Some(ESPMessage::FirmwareOffer { version, .. }) => assert_eq!(version, "1.0.0"),
+            other => panic!("expected a rollback, got {:?}", other),
+        }
+
+        // Offers survive the wire
+        let offer = ota.check_update("f", &esp32, "1.1.0").unwrap().encode(false).unwrap();
+        assert!(matches!(ESPMessage::from_frame(&offer).unwrap(), ESPMessage::FirmwareOffer { ref version, .. } if version == "1.0.0"));
+
+        let code = ESPCodeGenerator::generate_mining_code(&ESPMiningConfig::default());
+        assert!(code.contains("applyFirmwareOffer"));
+        assert!(code.contains("FIRMWARE_VERSION = \"0.1.0\""));
+    }
+
+    #[test]
+    fn test_project_generation() {
+        use crate::esp_compat::config::ESPProjectKind;
+
+        let dir = std::env::temp_dir().join(format!("ai3_esp_project_{}", uuid::Uuid::new_v4()));
+
+        let mut config = ESPMiningConfig { device_type: ESPDeviceType::ESP8266, ..Default::default() };
+        let written = ESPCodeGenerator::generate_project(&dir.join("pio"), &config).unwrap();
+        assert_eq!(written.len(), 3); // No partition table on the ESP8266
+        let ini = std::fs::read_to_string(dir.join("pio/platformio.ini")).unwrap();
+        assert!(ini.contains("platform = espressif8266"));
+        assert!(ini.contains("    -DESP8266"));
+        assert!(ini.contains("eagle.flash.4m1m.ld"));
+        assert!(std::fs::read_to_string(dir.join("pio/src/main.ino")).unwrap().contains("void setup()"));
+
+        // The ESP8266 has no ESP-IDF port
+        config.project_kind = ESPProjectKind::EspIdfRust;
+        assert!(ESPCodeGenerator::generate_project(&dir.join("rust"), &config).is_err());
+
+        config.device_type = ESPDeviceType::ESP32C3;
+        ESPCodeGenerator::generate_project(&dir.join("rust"), &config).unwrap();
+        let cargo_config = std::fs::read_to_string(dir.join("rust/.cargo/config.toml")).unwrap();
+        assert!(cargo_config.contains("target = \"riscv32imc-esp-espidf\""));
+        assert!(cargo_config.contains("-march=rv32imc"));
+        assert!(std::fs::read_to_string(dir.join("rust/src/config.rs")).unwrap().contains("pub const DEVICE_TYPE: u8 = 5;"));
+        assert!(dir.join("rust/partitions.csv").exists());
+        assert!(dir.join("rust/src/main.rs").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_mesh_relay() {
+        use crate::esp_compat::ESPCompatibility;
+        use crate::esp_compat::config::ESPTransport;
+        use crate::esp_compat::mesh::{format_mac, parse_mac, MeshNetwork, ESPNOW_MAX_PAYLOAD};
+        use crate::esp_compat::protocol::{ESPMessage, ESP_PROTOCOL_VERSION};
+
+        let peer_mac = [0x24, 0x0a, 0xc4, 0x01, 0x02, 0x03];
+        let peer_id = format_mac(&peer_mac);
+        assert_eq!(peer_id, "24:0A:C4:01:02:03");
+        assert_eq!(parse_mac(&peer_id).unwrap(), peer_mac);
+        assert!(parse_mac("24:0A:C4").is_err());
+
+        let hello = ESPMessage::Hello {
+            device_id: peer_id.clone(),
+            device_type: ESPDeviceType::ESP8266,
+            version: ESP_PROTOCOL_VERSION,
+            firmware_version: None,
+        };
+        let heartbeat = ESPMessage::Heartbeat {
+            uptime_seconds: 60,
+            free_heap: 20_000,
+            temperature_c: 31.5,
+            wifi_rssi: 0,
+            successful_tasks: 1,
+            failed_tasks: 0,
+            battery: None,
+        };
+        let stranger = [0x24, 0x0a, 0xc4, 0xff, 0xff, 0xff];
+        let batch = ESPMessage::MeshBatch {
+            entries: vec![
+                (stranger, heartbeat.encode(false).unwrap()), // Never said hello
+                (peer_mac, hello.encode(false).unwrap()),
+                (peer_mac, vec![0xff]), // Garbled over the air
+                (peer_mac, heartbeat.encode(false).unwrap()),
+            ],
+        };
+        let batch = ESPMessage::from_frame(&batch.encode(false).unwrap()).unwrap();
+
+        let mut mesh = MeshNetwork::new();
+        let messages = mesh.unpack("gateway-1", batch).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert!(matches!(messages[0], (ref id, ESPMessage::Hello { .. }) if *id == peer_id));
+        assert!(matches!(messages[1], (_, ESPMessage::Heartbeat { uptime_seconds: 60, .. })));
+        assert_eq!(mesh.peers_of("gateway-1"), vec![&peer_id]);
+
+        // Tasks reach the peer wrapped for its gateway
+        let config = ESPMessage::ConfigUpdate { mining_intensity: 2, power_save_mode: false, heartbeat_interval_secs: 30 };
+        let (gateway, relay) = mesh.relay(&peer_id, config.encode(false).unwrap()).unwrap();
+        assert_eq!(gateway, "gateway-1");
+        match ESPMessage::from_frame(&relay).unwrap() {
+            ESPMessage::MeshRelay { peer, frame } => {
+                assert_eq!(peer, peer_mac);
+                assert!(matches!(ESPMessage::from_frame(&frame).unwrap(), ESPMessage::ConfigUpdate { mining_intensity: 2, .. }));
+            }
+            other => panic!("expected a relay, got {:?}", other),
+        }
+        assert!(mesh.relay(&peer_id, vec![0; ESPNOW_MAX_PAYLOAD + 1]).is_err());
+        assert!(mesh.relay(&format_mac(&stranger), vec![0; 3]).is_err());
+
+        mesh.remove_gateway("gateway-1");
+        assert!(mesh.relay(&peer_id, vec![0; 3]).is_err());
+
+        let mut gateway_config = ESPMiningConfig { transport: ESPTransport::MeshGateway, ..Default::default() };
+        let code = ESPCodeGenerator::generate_mining_code(&gateway_config);
+        assert!(code.contains("#include <esp_now.h>"));
+        assert!(code.contains("setupMesh();"));
+        assert!(code.contains("relayToPeer(frame, length);"));
+        assert!(code.contains("flushMeshBatch();"));
+        assert!(!ESPCodeGenerator::generate_mining_code(&ESPMiningConfig::default()).contains("esp_now"));
+
+        let mut peer_config = ESPCompatibility::get_recommended_config(ESPDeviceType::ESP8266);
+        peer_config.transport = ESPTransport::MeshPeer { gateway_mac: "24:0A:C4:AA:BB:CC".to_string(), channel: 6 };
+        let code = ESPCodeGenerator::generate_mining_code(&peer_config);
+        assert!(code.contains("GATEWAY_MAC[6] = { 0x24, 0x0A, 0xC4, 0xAA, 0xBB, 0xCC }"));
+        assert!(code.contains("MESH_CHANNEL = 6;"));
+        assert!(code.contains("esp_now_send"));
+        assert!(!code.contains("WiFi.begin"));
+
+        // Gateways need an ESP32-family radio
+        gateway_config.device_type = ESPDeviceType::ESP8266;
+        assert!(crate::esp_compat::project::project_files(&gateway_config).is_err());
+        peer_config.transport = ESPTransport::MeshPeer { gateway_mac: "not a mac".to_string(), channel: 6 };
+        assert!(crate::esp_compat::project::project_files(&peer_config).is_err());
+    }
+
+    #[test]
+    fn test_fleet_power_scheduler() {
+        use crate::esp_compat::miners::ESPPerformanceStats;
+        use crate::esp_compat::power::{FleetPowerScheduler, PowerPolicy};
+        use crate::mining::MiningTask;
+        use crate::{AI3Engine, EngineConfig};
+        use std::collections::HashSet;
+
+        let stats = |temperature: f32, draw: u32| ESPPerformanceStats {
+            cpu_temperature: temperature,
+            power_consumption_mw: draw,
+            ..ESPPerformanceStats::new(&ESPDeviceType::ESP32)
+        };
+        let mut fleet = FleetPowerScheduler::new(PowerPolicy { fleet_budget_mw: Some(1200), ..Default::default() });
+        fleet.update("cool", &stats(40.0, 500));
+        fleet.update("warm", &stats(75.0, 500)); // Halfway into the thermal margin
+        fleet.update("hot", &stats(85.0, 300));
+        fleet.update("greedy", &stats(30.0, 1500)); // Over its own power limit
+        assert_eq!(fleet.duty_cycle("warm"), 0.5);
+        assert_eq!(fleet.duty_cycle("hot"), 0.0);
+
+        // The warm device works every other round; the hot one rests
+        let idle = HashSet::new();
+        let rounds: Vec<HashSet<String>> = (0..4).map(|_| fleet.admit(&idle)).collect();
+        for (round, admitted) in rounds.iter().enumerate() {
+            assert!(admitted.contains("cool"));
+            assert_eq!(admitted.contains("warm"), round % 2 == 1);
+            assert!(!admitted.contains("hot") && !admitted.contains("greedy"));
+        }
+
+        // Busy devices' draw leaves no room under the budget for another
+        let busy: HashSet<String> = ["cool".to_string(), "warm".to_string()].into();
+        assert_eq!(fleet.busy_draw_mw(&busy), 1000);
+        fleet.update("hot", &stats(50.0, 300));
+        assert!(fleet.admit(&busy).is_empty());
+        fleet.policy.fleet_budget_mw = Some(1300);
+        assert!(fleet.admit(&busy).contains("hot"));
+
+        // The engine holds tasks back from an ESP miner until it cools down
+        let mut engine = AI3Engine::with_config(EngineConfig { auto_optimize_tensors: false, ..Default::default() });
+        let miner_id = engine.add_esp_miner(ESPDeviceType::ESP32).unwrap();
+        assert!(engine.report_esp_stats("unknown", &stats(30.0, 500)).is_err());
+        engine.report_esp_stats(&miner_id, &stats(90.0, 500)).unwrap();
+        let input = Tensor::vector(vec![-1.0, 2.0, -3.0, 4.0]);
+        engine.submit_task(MiningTask::new("relu".to_string(), vec![input], 0, 100, 60, "requester".to_string())).unwrap();
+        assert!(engine.process_tasks().unwrap().is_empty());
+        assert_eq!(engine.queued_task_count(), 1);
+
+        engine.report_esp_stats(&miner_id, &stats(40.0, 500)).unwrap();
+        engine.process_tasks().unwrap();
+        assert_eq!(engine.queued_task_count(), 0);
+    }
+
+    #[test]
+    fn test_esp_telemetry() {
+        use crate::esp_compat::{ESPCompatibility, FAILED_TASK_RATE_METRIC, TEMPERATURE_METRIC};
+        use crate::esp_compat::mqtt::{self, MqttBridge};
+        use crate::esp_compat::protocol::{ESPMessage, ESP_PROTOCOL_VERSION};
+        use crate::mining::TaskDistributor;
+
+        let mut bridge = MqttBridge::new().with_telemetry(ESPCompatibility::telemetry_store());
+        let mut distributor = TaskDistributor::new();
+        let hello = ESPMessage::Hello {
+            device_id: "esp-1".to_string(),
+            device_type: ESPDeviceType::ESP32,
+            version: ESP_PROTOCOL_VERSION,
+            firmware_version: None,
+        }.encode(false).unwrap();
+        bridge.handle(&mut distributor, &mqtt::hello_topic("esp-1"), &hello).unwrap();
+
+        let heartbeat = |temperature_c: f32, failed_tasks: u32| ESPMessage::Heartbeat {
+            uptime_seconds: 60,
+            free_heap: 100 * 1024,
+            temperature_c,
+            wifi_rssi: -60,
+            successful_tasks: 6,
+            failed_tasks,
+            battery: None,
+        }.encode(false).unwrap();
+        for (temperature, failed) in [(40.0, 0), (78.0, 0), (79.0, 3)] {
+            bridge.handle(&mut distributor, &mqtt::telemetry_topic("esp-1"), &heartbeat(temperature, failed)).unwrap();
+        }
+
+        let telemetry = bridge.telemetry.as_ref().unwrap();
+        let alerts: Vec<&str> = telemetry.recent_alerts(Some("esp-1")).iter().map(|alert| alert.metric.as_str()).collect();
+        assert_eq!(alerts, vec![FAILED_TASK_RATE_METRIC, TEMPERATURE_METRIC]);
+
+        let now = chrono::Utc::now().timestamp() as u64;
+        let buckets = telemetry.get_device_metrics("esp-1", now - 300, now);
+        let samples: u32 = buckets.iter().map(|bucket| bucket.metrics[TEMPERATURE_METRIC].count).sum();
+        assert_eq!(samples, 3);
+        let hottest = buckets.iter().map(|bucket| bucket.metrics[TEMPERATURE_METRIC].max).fold(0.0, f64::max);
+        assert_eq!(hottest, 79.0);
+        assert!(telemetry.get_device_metrics("esp-2", 0, now).is_empty());
+    }
+
+    #[test]
+    fn test_fixed_point_kernels() {
+        use crate::esp_compat::kernels::{self, KernelDescriptor, KernelOperand};
+        use crate::esp_compat::protocol::ESPMessage;
+        use crate::mining::MiningTask;
+
+        let a = Tensor::matrix(vec![1.0, 2.0, -0.5, 0.25, 3.0, -1.0], 2, 3).unwrap();
+        let b = Tensor::matrix(vec![0.5, 1.0, -2.0, 0.0, 1.5, 0.75], 3, 2).unwrap();
+        let task = MiningTask::new("matrix_multiply".to_string(), vec![a.clone(), b.clone()], 2, 100, 60, "requester".to_string());
+
+        // ESP8266 pushes carry the kernel; FPU devices get floats and no kernel
+        let frame = ESPMessage::task_push(&task, &ESPDeviceType::ESP8266).unwrap();
+        let kernel = match ESPMessage::from_frame(&frame).unwrap() {
+            ESPMessage::TaskPush { kernel: Some(kernel), .. } => kernel,
+            other => panic!("unexpected message {:?}", other),
+        };
+        assert_eq!(kernel, KernelDescriptor::for_task(&task).unwrap());
+        assert_eq!(kernel.inputs[0], KernelOperand { rows: 2, cols: 3, row_stride: 3, col_stride: 1 });
+        assert_eq!((kernel.output.rows, kernel.output.cols, kernel.output_rank), (2, 2, 2));
+        let frame = ESPMessage::task_push(&task, &ESPDeviceType::ESP32).unwrap();
+        assert!(matches!(ESPMessage::from_frame(&frame).unwrap(), ESPMessage::TaskPush { kernel: None, .. }));
+
+        // The reference kernel matches the float result in Q8.8
+        let inputs = vec![kernels::quantize(&a).unwrap(), kernels::quantize(&b).unwrap()];
+        let output = kernel.execute(&inputs).unwrap();
+        let actual: Vec<f32> = output.iter().map(|&x| x as f32 / 256.0).collect();
+        assert_eq!(actual, vec![-4.25, 0.625, -7.375, -0.5]);
+
+        // Sigmoid runs piecewise-linear, relu clamps, sums saturate
+        let x = Tensor::vector(vec![-8.0, -1.0, 0.0, 1.0, 200.0]);
+        let sigmoid = MiningTask::new("sigmoid".to_string(), vec![x.clone()], 2, 100, 60, "requester".to_string());
+        let output = KernelDescriptor::for_task(&sigmoid).unwrap().execute(&[kernels::quantize(&x).unwrap()]).unwrap();
+        assert_eq!(output, vec![0, 64, 128, 192, 256]);
+        let relu = MiningTask::new("relu".to_string(), vec![x.clone()], 2, 100, 60, "requester".to_string());
+        let output = KernelDescriptor::for_task(&relu).unwrap().execute(&[kernels::quantize(&x).unwrap()]).unwrap();
+        assert_eq!(output, vec![0, 0, 0, 256, 32767]);
+        let add = MiningTask::new("vector_add".to_string(), vec![x.clone(), x.clone()], 2, 100, 60, "requester".to_string());
+        let inputs = vec![kernels::quantize(&x).unwrap(); 2];
+        assert_eq!(KernelDescriptor::for_task(&add).unwrap().execute(&inputs).unwrap()[4], i16::MAX);
+
+        // Operations without a kernel aren't pushed to fixed-point devices
+        let softmax = MiningTask::new("softmax".to_string(), vec![x], 2, 100, 60, "requester".to_string());
+        assert!(ESPMessage::task_push(&softmax, &ESPDeviceType::ESP8266).is_err());
+        assert!(ESPMessage::task_push(&softmax, &ESPDeviceType::ESP32).is_ok());
+        let wide = Tensor::matrix(vec![0.0; 40 * 40], 40, 40).unwrap();
+        let too_big = MiningTask::new("relu".to_string(), vec![wide], 2, 100, 60, "requester".to_string());
+        assert!(KernelDescriptor::for_task(&too_big).is_err());
+
+        let code = ESPCodeGenerator::generate_tensor_operations();
+        assert!(code.contains("int fixedKernelRun("));
+    }
+
+    #[test]
+    fn test_tiled_execution() {
+        use crate::esp_compat::tiling::{TiledExecutor, TileKernel};
+        use crate::esp_compat::miners::{ConnectionStatus, ESP8266Miner};
+        use crate::mining::MiningTask;
+
+        // 4KB leaves 3KB, or 1536 Q8.8 elements, on an ESP8266
+        let config = ESPMiningConfig { device_type: ESPDeviceType::ESP8266, max_memory_kb: 4, ..Default::default() };
+        let executor = TiledExecutor::new(&config);
+        let task = |operation: &str, inputs: Vec<Tensor>| MiningTask::new(operation.to_string(), inputs, 0, 100, 60, "requester".to_string());
+
+        let a = Tensor::matrix((0..1600).map(|i| (i % 7) as f32 - 3.0).collect(), 40, 40).unwrap();
+        let b = Tensor::matrix((0..1600).map(|i| (i % 5) as f32 * 0.5).collect(), 40, 40).unwrap();
+        let matmul = task("matrix_multiply", vec![a, b]);
+        let (output, plan) = executor.execute(&matmul).unwrap();
+        assert_eq!(plan.kernel, TileKernel::MatrixBlocks { rows: 1, cols: 36 });
+        assert_eq!(plan.tiles, 80);
+        assert!(plan.peak_bytes <= executor.budget_bytes);
+        assert_eq!(output.shape.dimensions, vec![40, 40]);
+        assert_eq!(output.data.as_f32_vec().unwrap(), matmul.execute_operation().unwrap().data.as_f32_vec().unwrap());
+
+        let x: Vec<f32> = (0..5000).map(|i| (i as f32 - 2500.0) / 100.0).collect();
+        let relu = task("relu", vec![Tensor::vector(x.clone())]);
+        let (output, plan) = executor.execute(&relu).unwrap();
+        assert_eq!((plan.kernel, plan.tiles), (TileKernel::Elementwise { elements: 768 }, 7));
+        assert_eq!(output.data.as_f32_vec().unwrap(), relu.execute_operation().unwrap().data.as_f32_vec().unwrap());
+
+        let dot = task("dot_product", vec![Tensor::vector(x.clone()), Tensor::vector(x.clone())]);
+        let (output, plan) = executor.execute(&dot).unwrap();
+        assert!(matches!(plan.kernel, TileKernel::Accumulate { .. }));
+        assert_eq!(output.data.as_f32_vec().unwrap(), dot.execute_operation().unwrap().data.as_f32_vec().unwrap());
+
+        // Softmax needs the whole input at once, so it's still rejected
+        assert!(executor.plan(&task("softmax", vec![Tensor::vector(x.clone())])).is_err());
+
+        // Too big for the generic ESP limit, but the miner takes it tiled
+        let mut miner = ESP8266Miner::new("esp".to_string(), "addr".to_string(), config);
+        miner.esp32_miner.config.max_memory_kb = 4;
+        miner.esp32_miner.connection_status = ConnectionStatus::Connected;
+        assert!(miner.esp32_miner.base_miner.clone().assign_task(relu.clone()).is_err());
+        miner.assign_task(relu.clone()).unwrap();
+        let result = miner.mine_step().unwrap().unwrap();
+        assert_eq!(result.output_tensor.data.as_f32_vec().unwrap(), relu.execute_operation().unwrap().data.as_f32_vec().unwrap());
+        assert!(miner.esp32_miner.performance_stats.memory_usage_kb <= 3);
+    }
+
+    #[test]
+    fn test_battery_profiles() {
+        use crate::esp_compat::config::MiningProfile;
+        use crate::esp_compat::miners::{BatteryState, ConnectionStatus};
+        use crate::esp_compat::protocol::ESPMessage;
+        use crate::mining::MiningTask;
+
+        assert_eq!(MiningProfile::Performance.for_battery(None), MiningProfile::Performance);
+        assert_eq!(MiningProfile::Performance.for_battery(Some(60)), MiningProfile::Performance);
+        assert_eq!(MiningProfile::Performance.for_battery(Some(40)), MiningProfile::Balanced);
+        assert_eq!(MiningProfile::Balanced.for_battery(Some(10)), MiningProfile::BatterySaver);
+        assert_eq!(MiningProfile::BatterySaver.for_battery(Some(90)), MiningProfile::BatterySaver);
+        assert_eq!(BatteryState::from_voltage(3750).percent, 50);
+        assert_eq!(BatteryState::from_voltage(4300).percent, 100);
+
+        let config = ESPMiningConfig { mining_intensity: 8, battery_adc_pin: Some(34), ..Default::default() };
+        let mut miner = ESP32Miner::new("esp".to_string(), "addr".to_string(), config.clone());
         miner.connection_status = ConnectionStatus::Connected;
-        assert_eq!(miner.connection_status, ConnectionStatus::Connected);
-        
-        miner.connection_status = ConnectionStatus::Error;
-        assert_eq!(miner.connection_status, ConnectionStatus::Error);
+        let report = |miner: &mut ESP32Miner, voltage_mv: u16| {
+            let frame = ESPMessage::Heartbeat {
+                uptime_seconds: 60,
+                free_heap: 100 * 1024,
+                temperature_c: 40.0,
+                wifi_rssi: -60,
+                successful_tasks: 0,
+                failed_tasks: 0,
+                battery: Some(BatteryState::from_voltage(voltage_mv)),
+            }.encode(false).unwrap();
+            miner.apply_message(&ESPMessage::from_frame(&frame).unwrap());
+        };
+
+        report(&mut miner, 3750);
+        assert_eq!((miner.active_profile(), miner.effective_intensity()), (MiningProfile::Performance, 8));
+        report(&mut miner, 3660);
+        assert_eq!((miner.active_profile(), miner.effective_intensity()), (MiningProfile::Balanced, 6));
+
+        // Drained: battery-saver settings go to the device, and only small tasks are taken
+        report(&mut miner, 3435);
+        assert_eq!(miner.active_profile(), MiningProfile::BatterySaver);
+        let update = ESPMessage::from_frame(&miner.config_update(30).unwrap()).unwrap();
+        assert!(matches!(update, ESPMessage::ConfigUpdate { mining_intensity: 2, power_save_mode: true, .. }));
+        let task = |elements: usize| MiningTask::new("relu".to_string(), vec![Tensor::vector(vec![1.0; elements])], 0, 100, 60, "requester".to_string());
+        assert!(miner.assign_task(task(300)).is_err());
+        miner.assign_task(task(100)).unwrap();
+        report(&mut miner, 3390);
+        assert!(miner.assign_task(task(100)).is_err());
+        assert!(miner.performance_stats.telemetry_metrics().iter().any(|(name, value)| name == "battery_percent" && *value == 10.0));
+
+        let code = ESPCodeGenerator::generate_mining_code(&config);
+        assert!(code.contains("#define BATTERY_ADC_PIN 34"));
+        assert!(code.contains("PROFILE_CPU_MHZ[3] = { 240, 160, 80 }"));
+        assert!(code.contains("putField(TAG_BATTERY"));
+    }
+
+    #[test]
+    fn test_secret_provisioning() {
+        use crate::esp_compat::config::{ESPProjectKind, ESPProvisioning};
+        use crate::esp_compat::project;
+        use crate::esp_compat::provisioning::{self, DeviceSecrets};
+
+        // RFC 4231 test case 2, which the firmware's HMAC must agree with
+        let mac = provisioning::hmac_sha256(b"Jefe", &[b"what do ya want ", b"for nothing?"]);
+        assert_eq!(hex::encode(mac), "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843");
+
+        let config = ESPMiningConfig {
+            wifi_ssid: "Barn WiFi".to_string(),
+            wifi_password: "hunter2".to_string(),
+            server_address: "pool.example.org".to_string(),
+            provisioning: ESPProvisioning::Encrypted,
+            ..Default::default()
+        };
+        assert!(!serde_json::to_string(&config).unwrap().contains("hunter2"));
+
+        let key = provisioning::generate_device_key();
+        let secrets = DeviceSecrets::from_config(&config);
+        let sealed = secrets.seal(&key).unwrap();
+        assert_eq!(DeviceSecrets::open(&sealed, &key).unwrap(), secrets);
+        assert!(!format!("{:?}", secrets).contains("hunter2"));
+        assert!(DeviceSecrets::open(&sealed, &provisioning::generate_device_key()).is_err());
+        let mut tampered = sealed.clone();
+        tampered[30] ^= 1;
+        assert!(DeviceSecrets::open(&tampered, &key).is_err());
+        assert!(!sealed.windows(7).any(|window| window == b"hunter2"));
+        let command = provisioning::provisioning_command(&key, &sealed);
+        assert!(command.starts_with(&format!("PROVISION {} ", hex::encode(key))));
+
+        // The sealed sketch carries no secrets; the embedded one still compiles them in
+        let code = ESPCodeGenerator::generate_mining_code(&config);
+        assert!(!code.contains("hunter2") && !code.contains("pool.example.org"));
+        assert!(code.contains("loadSecrets();"));
+        let embedded = ESPMiningConfig { provisioning: ESPProvisioning::Embedded, ..config.clone() };
+        assert!(ESPCodeGenerator::generate_mining_code(&embedded).contains("String wifi_password = \"hunter2\";"));
+
+        let rust = ESPMiningConfig { project_kind: ESPProjectKind::EspIdfRust, ..config };
+        assert!(project::project_files(&rust).is_err());
+    }
+
+    #[test]
+    fn test_riscv_c6_h2_devices() {
+        use crate::esp_compat::config::ESPProjectKind;
+        use crate::esp_compat::protocol::ESPMessage;
+        use crate::esp_compat::{mqtt, project, utils, ESPCompatibility};
+
+        let c6 = ESPCompatibility::get_recommended_config(ESPDeviceType::ESP32C6);
+        assert_eq!((c6.max_memory_kb, c6.clock_speed_mhz), (512, 160));
+        assert!(!ESPDeviceType::ESP32C6.supports_floating_point());
+        assert!(utils::get_compiler_flags(&ESPDeviceType::ESP32C6).contains(&"-march=rv32imac".to_string()));
+        let h2 = ESPCompatibility::get_recommended_config(ESPDeviceType::ESP32H2);
+        assert!(h2.power_save_mode && h2.mining_intensity < c6.mining_intensity);
+        assert!(ESPCompatibility::get_device_info(&ESPDeviceType::ESP32H2).limitations.iter().any(|l| l.contains("Wi-Fi")));
+        assert_eq!("esp32-c6".parse::<ESPDeviceType>().unwrap(), ESPDeviceType::ESP32C6);
+
+        // Same clock as the C3 but no FPU, partly made up by the newer core
+        let c3_rate = ESPCompatibility::estimate_performance(&ESPDeviceType::ESP32C3, "relu");
+        let c6_rate = ESPCompatibility::estimate_performance(&ESPDeviceType::ESP32C6, "relu");
+        assert!(c6_rate < c3_rate && c6_rate > c3_rate * 0.6);
+
+        // Both take fixed-point tasks and identify themselves on the wire
+        assert!(mqtt::capabilities(&ESPDeviceType::ESP32C6).contains(&"relu_q8".to_string()));
+        for device_type in [ESPDeviceType::ESP32C6, ESPDeviceType::ESP32H2] {
+            let hello = ESPMessage::Hello { device_id: "dev".to_string(), device_type: device_type.clone(), version: 1, firmware_version: None };
+            match ESPMessage::from_frame(&hello.encode(false).unwrap()).unwrap() {
+                ESPMessage::Hello { device_type: decoded, .. } => assert_eq!(decoded, device_type),
+                other => panic!("unexpected {:?}", other),
+            }
+        }
+
+        let rust = ESPMiningConfig { project_kind: ESPProjectKind::EspIdfRust, ..c6 };
+        let files = project::project_files(&rust).unwrap();
+        assert!(files.iter().any(|(_, contents)| contents.contains("target = \"riscv32imac-esp-espidf\"")));
+        assert!(project::project_files(&h2).is_err());
     }
-} 
\ No newline at end of file
+}
@@ -0,0 +1,180 @@
+use crate::esp_compat::config::ESPMiningConfig;
+use crate::esp_compat::devices::ESPDeviceType;
+use crate::mining::MiningTask;
+use crate::tensor::{Tensor, TensorShape};
+use tribechain_core::{TribeResult, TribeError};
+
+/// Kernel a device runs a task's operation with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TileKernel {
+    /// Whole operation at once; everything fits
+    Direct,
+    /// Elementwise operation over `elements` of each input at a time
+    Elementwise { elements: usize },
+    /// Dot product accumulated over `elements` of each input at a time
+    Accumulate { elements: usize },
+    /// Matrix multiply one `rows` x `cols` block of the output at a time
+    MatrixBlocks { rows: usize, cols: usize },
+}
+
+/// How a task runs within a device's memory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TilePlan {
+    pub kernel: TileKernel,
+    pub tiles: usize,
+    pub element_bytes: usize, // 4 for f32 devices, 2 for Q8.8 ones
+    pub peak_bytes: usize, // Input and output tiles resident at once
+}
+
+/// Runs tasks on an ESP device's memory budget, splitting ones whose tensors don't
+/// fit at once into tiles run in sequence
+#[derive(Debug, Clone)]
+pub struct TiledExecutor {
+    pub device_type: ESPDeviceType,
+    pub budget_bytes: usize,
+}
+
+fn tiling_error(message: String) -> TribeError {
+    TribeError::Esp(message)
+}
+
+impl TiledExecutor {
+    /// Executor for a configured device, keeping 25% of its memory for the system
+    pub fn new(config: &ESPMiningConfig) -> Self {
+        let memory_kb = config.max_memory_kb.min(config.device_type.get_memory_limit());
+        Self {
+            device_type: config.device_type.clone(),
+            budget_bytes: memory_kb * 1024 * 3 / 4,
+        }
+    }
+
+    fn element_bytes(&self) -> usize {
+        if self.device_type.supports_floating_point() { 4 } else { 2 }
+    }
+
+    /// Pick a kernel for `task`, erroring if even its smallest tile won't fit
+    pub fn plan(&self, task: &MiningTask) -> TribeResult<TilePlan> {
+        if !task.input_refs.is_empty() {
+            return Err(tiling_error("ESP devices need inline task inputs".to_string()));
+        }
+        let element_bytes = self.element_bytes();
+        let capacity = self.budget_bytes / element_bytes; // Elements resident at once
+        let inputs = &task.input_tensors;
+        let input_elements: usize = inputs.iter().map(|t| t.shape.total_elements()).sum();
+        let too_large = |needed: usize| tiling_error(format!(
+            "{} needs {}B at once, device has {}B", task.operation_type, needed * element_bytes, self.budget_bytes
+        ));
+        let plan = |kernel, tiles, resident: usize| TilePlan { kernel, tiles, element_bytes, peak_bytes: resident * element_bytes };
+
+        match task.operation_type.as_str() {
+            "relu" | "sigmoid" | "tanh" | "vector_add" => {
+                let total = inputs.first().map(|t| t.shape.total_elements()).unwrap_or(0);
+                if input_elements + total <= capacity {
+                    return Ok(plan(TileKernel::Direct, 1, input_elements + total));
+                }
+                let per_element = inputs.len() + 1;
+                let elements = capacity / per_element;
+                if elements == 0 {
+                    return Err(too_large(per_element));
+                }
+                Ok(plan(TileKernel::Elementwise { elements }, total.div_ceil(elements), elements * per_element))
+            }
+            "dot_product" => {
+                if input_elements < capacity {
+                    return Ok(plan(TileKernel::Direct, 1, input_elements + 1));
+                }
+                let elements = capacity.saturating_sub(1) / 2;
+                if elements == 0 {
+                    return Err(too_large(3));
+                }
+                let total = inputs.first().map(|t| t.shape.total_elements()).unwrap_or(0);
+                Ok(plan(TileKernel::Accumulate { elements }, total.div_ceil(elements), elements * 2 + 1))
+            }
+            "matrix_multiply" if inputs.len() == 2 && inputs.iter().all(|t| t.shape.dimensions.len() == 2) => {
+                let (m, k) = (inputs[0].shape.dimensions[0], inputs[0].shape.dimensions[1]);
+                let n = inputs[1].shape.dimensions[1];
+                let block = |rows: usize, cols: usize| rows * k + k * cols + rows * cols;
+                if block(m, n) <= capacity {
+                    return Ok(plan(TileKernel::Direct, 1, block(m, n)));
+                }
+                // Prefer whole output rows so B is read once per row block
+                let (rows, cols) = if k * n < capacity && (capacity - k * n) / (k + n) > 0 {
+                    (((capacity - k * n) / (k + n)).min(m), n)
+                } else if capacity > k {
+                    (1, ((capacity - k) / (k + 1)).min(n))
+                } else {
+                    (0, 0)
+                };
+                if rows == 0 || cols == 0 {
+                    return Err(too_large(block(1, 1)));
+                }
+                Ok(plan(TileKernel::MatrixBlocks { rows, cols }, m.div_ceil(rows) * n.div_ceil(cols), block(rows, cols)))
+            }
+            _ => {
+                // No tiled kernel: the operation needs its whole input at once
+                let needed = input_elements * 2;
+                if needed > capacity {
+                    return Err(too_large(needed));
+                }
+                Ok(plan(TileKernel::Direct, 1, needed))
+            }
+        }
+    }
+
+    /// Run `task` tile by tile under its plan; outputs match running it whole
+    pub fn execute(&self, task: &MiningTask) -> TribeResult<(Tensor, TilePlan)> {
+        let plan = self.plan(task)?;
+        let inputs = &task.input_tensors;
+        let output = match plan.kernel {
+            TileKernel::Direct => task.execute_operation()?,
+            TileKernel::Elementwise { elements } => {
+                let operation = task.get_operation()?;
+                operation.validate_inputs(inputs)?;
+                let data = inputs.iter().map(|t| t.data.as_f32_vec()).collect::<TribeResult<Vec<_>>>()?;
+                let mut output = Vec::with_capacity(data[0].len());
+                for start in (0..data[0].len()).step_by(elements) {
+                    let end = (start + elements).min(data[0].len());
+                    let tiles: Vec<Tensor> = data.iter().map(|d| Tensor::vector(d[start..end].to_vec())).collect();
+                    output.extend(operation.execute(&tiles)?.data.as_f32_vec()?);
+                }
+                Tensor::from_vec(output, inputs[0].shape.clone())?
+            }
+            TileKernel::Accumulate { elements } => {
+                task.get_operation()?.validate_inputs(inputs)?;
+                let a = inputs[0].data.as_f32_vec()?;
+                let b = inputs[1].data.as_f32_vec()?;
+                // Carry the running sum across tiles so rounding matches one pass
+                let mut acc = 0.0f32;
+                for start in (0..a.len()).step_by(elements) {
+                    let end = (start + elements).min(a.len());
+                    acc = a[start..end].iter().zip(&b[start..end]).fold(acc, |acc, (x, y)| acc + x * y);
+                }
+                Tensor::scalar(acc)
+            }
+            TileKernel::MatrixBlocks { rows, cols } => {
+                let operation = task.get_operation()?;
+                operation.validate_inputs(inputs)?;
+                let (m, k) = (inputs[0].shape.dimensions[0], inputs[0].shape.dimensions[1]);
+                let n = inputs[1].shape.dimensions[1];
+                let a = inputs[0].data.as_f32_vec()?;
+                let b = inputs[1].data.as_f32_vec()?;
+                let mut output = vec![0.0f32; m * n];
+                for row in (0..m).step_by(rows) {
+                    let row_end = (row + rows).min(m);
+                    let a_tile = Tensor::matrix(a[row * k..row_end * k].to_vec(), row_end - row, k)?;
+                    for col in (0..n).step_by(cols) {
+                        let col_end = (col + cols).min(n);
+                        let b_tile: Vec<f32> = (0..k).flat_map(|i| b[i * n + col..i * n + col_end].iter().copied()).collect();
+                        let b_tile = Tensor::matrix(b_tile, k, col_end - col)?;
+                        let block = operation.execute(&[a_tile.clone(), b_tile])?.data.as_f32_vec()?;
+                        for (i, block_row) in block.chunks(col_end - col).enumerate() {
+                            output[(row + i) * n + col..(row + i) * n + col_end].copy_from_slice(block_row);
+                        }
+                    }
+                }
+                Tensor::from_vec(output, TensorShape::matrix(m, n))?
+            }
+        };
+        Ok((output, plan))
+    }
+}
@@ -7,12 +7,13 @@ pub mod tensor;
 pub mod esp_compat;
 
 // Re-export key types for convenience
-pub use mining::{AI3Miner, MiningTask, MiningResult, TaskDistributor, MinerCapabilities, MinerStats};
-pub use operations::{TensorOp, MatrixMultiply, Convolution, ActivationFunction, VectorOp};
-pub use tensor::{Tensor, TensorShape, TensorData};
-pub use esp_compat::{ESPCompatibility, ESPDeviceType, ESPMiningConfig, ESP32Miner, ESP8266Miner};
+pub use mining::{AI3Miner, MiningTask, MiningTaskKind, MiningResult, TaskDistributor, MinerCapabilities, MinerStats, CheckpointStore, TaskCheckpoint, WorkStealingScheduler, VerificationPolicy, BenchmarkReport, OperationBenchmark, TaskMarketplace, TaskListing, Bid, AwardedTask, AggregateOutcome, ComputationProof};
+pub use operations::{TensorOp, MatrixMultiply, Convolution, ActivationFunction, VectorOp, ResultCache};
+pub use tensor::{Tensor, TensorShape, TensorData, SharedTensorStore, ChunkStore, TensorManifest, ShardRef};
+pub use esp_compat::{ESPCompatibility, ESPDeviceType, ESPMiningConfig, ESP32Miner, ESP8266Miner, ESPPerformanceStats, FleetPowerScheduler, PowerPolicy};
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
@@ -22,6 +23,11 @@ pub struct AI3Engine {
     task_distributor: TaskDistributor,
     performance_stats: Arc<Mutex<EngineStats>>,
     config: EngineConfig,
+    checkpoint_store: Option<CheckpointStore>,
+    shared_tensors: SharedTensorStore,
+    chunk_store: ChunkStore,
+    scheduler: WorkStealingScheduler,
+    power_scheduler: FleetPowerScheduler,
 }
 
 /// Engine configuration
@@ -32,6 +38,11 @@ pub struct EngineConfig {
     pub enable_esp_support: bool,
     pub auto_optimize_tensors: bool,
     pub performance_monitoring: bool,
+    pub checkpoint_dir: Option<PathBuf>,
+    pub checkpoint_interval: Duration,
+    pub result_cache_entries: usize,
+    pub result_cache_bytes: usize,
+    pub esp_power_budget_mw: Option<u32>, // Total draw allowed across ESP miners
 }
 
 impl Default for EngineConfig {
@@ -42,12 +53,17 @@ impl Default for EngineConfig {
             enable_esp_support: true,
             auto_optimize_tensors: true,
             performance_monitoring: true,
+            checkpoint_dir: None,
+            checkpoint_interval: Duration::from_secs(30),
+            result_cache_entries: 1024,
+            result_cache_bytes: 64 * 1024 * 1024, // 64MB
+            esp_power_budget_mw: None,
         }
     }
 }
 
 /// Engine performance statistics
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone)]
 pub struct EngineStats {
     pub total_tasks_processed: u64,
     pub successful_tasks: u64,
@@ -59,6 +75,30 @@ pub struct EngineStats {
     pub start_time: Instant,
 }
 
+impl Default for EngineStats {
+    fn default() -> Self {
+        Self {
+            total_tasks_processed: 0,
+            successful_tasks: 0,
+            failed_tasks: 0,
+            average_task_time: Duration::ZERO,
+            active_miners: 0,
+            total_compute_time: Duration::ZERO,
+            uptime: Duration::ZERO,
+            start_time: Instant::now(),
+        }
+    }
+}
+
+impl std::fmt::Debug for AI3Engine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AI3Engine")
+            .field("miners", &self.miners.len())
+            .field("config", &self.config)
+            .finish_non_exhaustive()
+    }
+}
+
 impl AI3Engine {
     /// Create a new AI3 Engine with default configuration
     pub fn new() -> Self {
@@ -67,42 +107,89 @@ impl AI3Engine {
 
     /// Create a new AI3 Engine with custom configuration
     pub fn with_config(config: EngineConfig) -> Self {
-        let stats = EngineStats {
-            start_time: Instant::now(),
+        let stats = EngineStats::default();
+
+        let checkpoint_store = config.checkpoint_dir.as_ref().and_then(|dir| {
+            match CheckpointStore::new(dir) {
+                Ok(store) => Some(store),
+                Err(e) => {
+                    eprintln!("Checkpointing disabled: {}", e);
+                    None
+                }
+            }
+        });
+
+        if let Ok(mut cache) = ResultCache::global().lock() {
+            cache.max_entries = config.result_cache_entries;
+            cache.max_bytes = config.result_cache_bytes;
+        }
+
+        let power_scheduler = FleetPowerScheduler::new(PowerPolicy {
+            fleet_budget_mw: config.esp_power_budget_mw,
             ..Default::default()
-        };
+        });
 
         Self {
             miners: Vec::new(),
             task_distributor: TaskDistributor::new(),
             performance_stats: Arc::new(Mutex::new(stats)),
             config,
+            checkpoint_store,
+            shared_tensors: SharedTensorStore::new(),
+            chunk_store: ChunkStore::new(),
+            scheduler: WorkStealingScheduler::new(),
+            power_scheduler,
         }
     }
 
     /// Add a miner to the engine
-    pub fn add_miner(&mut self, miner: AI3Miner) {
+    pub fn add_miner(&mut self, mut miner: AI3Miner) {
+        if miner.checkpoint_store.is_none() {
+            if let Some(store) = &self.checkpoint_store {
+                miner = miner.with_checkpointing(store.clone(), self.config.checkpoint_interval);
+            }
+        }
+
+        self.scheduler.register_miner(&miner);
         self.miners.push(miner);
         if let Ok(mut stats) = self.performance_stats.lock() {
             stats.active_miners = self.miners.len();
         }
     }
 
-    /// Add an ESP miner with automatic configuration
-    pub fn add_esp_miner(&mut self, device_type: ESPDeviceType) -> tribechain_core::TribeResult<()> {
+    /// Add an ESP miner with automatic configuration, returning its id
+    pub fn add_esp_miner(&mut self, device_type: ESPDeviceType) -> tribechain_core::TribeResult<String> {
         if !self.config.enable_esp_support {
-            return Err(tribechain_core::TribeError::InvalidOperation(
+            return Err(tribechain_core::TribeError::AI3(
                 "ESP support is disabled in engine configuration".to_string()
             ));
         }
 
         let esp_config = esp_compat::ESPCompatibility::get_recommended_config(device_type);
         let miner_id = format!("esp_miner_{}", uuid::Uuid::new_v4());
-        let miner = AI3Miner::new(miner_id, "esp_address".to_string(), true);
+        let miner = AI3Miner::new(miner_id.clone(), "esp_address".to_string(), true);
         self.add_miner(miner);
+        self.power_scheduler.update(&miner_id, &ESPPerformanceStats::new(&esp_config.device_type));
+        Ok(miner_id)
+    }
+
+    /// Feed an ESP miner's reported temperature and power draw to the fleet power scheduler
+    pub fn report_esp_stats(&mut self, miner_id: &str, stats: &ESPPerformanceStats) -> tribechain_core::TribeResult<()> {
+        if !self.power_scheduler.contains(miner_id) {
+            return Err(tribechain_core::TribeError::AI3(
+                format!("{} is not an ESP miner of this engine", miner_id)
+            ));
+        }
+        self.power_scheduler.update(miner_id, stats);
         Ok(())
     }
 
+    /// Total draw allowed across ESP miners
+    pub fn set_esp_power_budget(&mut self, budget_mw: Option<u32>) {
+        self.config.esp_power_budget_mw = budget_mw;
+        self.power_scheduler.policy.fleet_budget_mw = budget_mw;
+    }
+
     /// Submit a mining task
     pub fn submit_task(&mut self, task: MiningTask) -> tribechain_core::TribeResult<String> {
         // Auto-optimize tensors if enabled
@@ -117,43 +204,247 @@ impl AI3Engine {
         Ok(task_id)
     }
 
+    /// Submit many tasks at once, storing identical input tensors only once.
+    /// Tasks that fail optimization are skipped; ids of accepted tasks are returned.
+    pub fn submit_batch(&mut self, tasks: Vec<MiningTask>) -> Vec<String> {
+        // Raw input hash -> hash of the (possibly optimized) tensor kept in the store
+        let mut seen: HashMap<String, String> = HashMap::new();
+        let mut task_ids = Vec::with_capacity(tasks.len());
+
+        'tasks: for mut task in tasks {
+            let mut input_refs = Vec::with_capacity(task.input_tensors.len());
+
+            for tensor in task.input_tensors.drain(..) {
+                let raw_hash = tensor.calculate_hash();
+                if let Some(stored_hash) = seen.get(&raw_hash) {
+                    input_refs.push(stored_hash.clone());
+                    continue;
+                }
+
+                let stored = if self.config.auto_optimize_tensors {
+                    match self.optimize_tensor(&tensor) {
+                        Ok(optimized) => optimized,
+                        Err(e) => {
+                            eprintln!("Skipping task {}: {}", task.id, e);
+                            continue 'tasks;
+                        }
+                    }
+                } else {
+                    tensor
+                };
+
+                let stored_hash = self.shared_tensors.intern(stored);
+                seen.insert(raw_hash, stored_hash.clone());
+                input_refs.push(stored_hash);
+            }
+
+            task.input_refs.extend(input_refs);
+            task_ids.push(task.id.clone());
+            self.task_distributor.add_task(task);
+        }
+
+        task_ids
+    }
+
+    /// Number of distinct input tensors held for batched tasks
+    pub fn shared_tensor_count(&self) -> usize {
+        self.shared_tensors.len()
+    }
+
+    /// Split a task's inputs into content-addressed chunks and submit one subtask per
+    /// `chunks_per_subtask` chunks of its first input. Returns the subtask ids.
+    pub fn submit_sharded(
+        &mut self,
+        mut task: MiningTask,
+        chunk_elements: usize,
+        chunks_per_subtask: usize,
+    ) -> tribechain_core::TribeResult<Vec<String>> {
+        task.chunk_inputs(&mut self.chunk_store, chunk_elements)?;
+        let subtasks = task.split_by_chunks(chunks_per_subtask)?;
+        let task_ids = subtasks.iter().map(|subtask| subtask.id.clone()).collect();
+        for subtask in subtasks {
+            self.task_distributor.add_task(subtask);
+        }
+        Ok(task_ids)
+    }
+
+    /// Chunks pending tasks need that haven't been fetched from peers yet
+    pub fn missing_chunks(&self) -> Vec<String> {
+        let mut missing: Vec<String> = self.task_distributor.pending_tasks.values()
+            .chain(self.scheduler.queued_tasks())
+            .flat_map(|task| task.input_shards.iter())
+            .flat_map(|shard| self.chunk_store.missing(shard))
+            .collect();
+        missing.sort();
+        missing.dedup();
+        missing
+    }
+
+    /// Store a chunk fetched from a peer after checking it against its hash
+    pub fn add_chunk(&mut self, hash: String, bytes: Vec<u8>) -> tribechain_core::TribeResult<()> {
+        self.chunk_store.insert(hash, bytes)
+    }
+
+    /// Chunk bytes to serve to peers
+    pub fn get_chunk(&self, hash: &str) -> Option<&Vec<u8>> {
+        self.chunk_store.get(hash)
+    }
+
+    /// Every chunk held, for `NetworkManager::announce_chunks`
+    pub fn held_chunks(&self) -> Vec<(String, Vec<u8>)> {
+        self.chunk_store.iter().map(|(hash, bytes)| (hash.clone(), bytes.clone())).collect()
+    }
+
+    /// Resume a checkpointed task on the first idle miner that can handle it
+    pub fn resume_task(&mut self, task_id: &str) -> tribechain_core::TribeResult<()> {
+        let store = self.checkpoint_store.as_ref().ok_or_else(|| {
+            tribechain_core::TribeError::AI3("Checkpointing is not enabled".to_string())
+        })?;
+
+        let checkpoint = store.load(task_id)?.ok_or_else(|| {
+            tribechain_core::TribeError::AI3(format!("No checkpoint found for task {}", task_id))
+        })?;
+
+        let miner = self.miners.iter_mut()
+            .filter(|miner| miner.current_task.is_none())
+            .find(|miner| miner.can_handle_task(&checkpoint.task))
+            .ok_or_else(|| {
+                tribechain_core::TribeError::AI3(format!("No idle miner can resume task {}", task_id))
+            })?;
+
+        self.task_distributor.pending_tasks.remove(task_id);
+        self.scheduler.remove_task(task_id);
+        miner.resume_from_checkpoint(checkpoint)
+    }
+
+    /// Ids of tasks with a checkpoint waiting to be resumed
+    pub fn checkpointed_tasks(&self) -> tribechain_core::TribeResult<Vec<String>> {
+        match &self.checkpoint_store {
+            Some(store) => store.list_task_ids(),
+            None => Ok(Vec::new()),
+        }
+    }
+
     /// Process pending tasks
     pub fn process_tasks(&mut self) -> tribechain_core::TribeResult<Vec<MiningResult>> {
         let start_time = Instant::now();
         let mut results = Vec::new();
 
-        // Get pending tasks and distribute to miners
-        let pending_tasks = self.task_distributor.get_pending_tasks();
-        
-        for task in pending_tasks {
-            // Find available miners for this task
-            for miner in &mut self.miners {
-                if miner.can_handle_task(task) && miner.current_task.is_none() {
-                    // Assign task to miner
-                    if let Ok(()) = miner.assign_task(task.clone()) {
-                        // Try mining step
-                        match miner.mine_step() {
-                            Ok(Some(result)) => {
-                                results.push(result);
-                                self.update_stats(true, start_time.elapsed());
-                            }
-                            Ok(None) => {
-                                // No result yet, continue mining
-                            }
-                            Err(e) => {
-                                eprintln!("Task processing failed: {}", e);
-                                self.update_stats(false, start_time.elapsed());
-                            }
-                        }
-                        break; // Task assigned, move to next task
+        // Keep miners that already hold a task (e.g. a resumed one) making progress
+        let mut outcomes = Vec::new();
+        for miner in self.miners.iter_mut().filter(|miner| miner.current_task.is_some()) {
+            match miner.mine_step() {
+                Ok(Some(result)) => {
+                    results.push(result);
+                    outcomes.push(true);
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    eprintln!("Task processing failed: {}", e);
+                    outcomes.push(false);
+                }
+            }
+        }
+        for success in outcomes {
+            self.update_stats(success, start_time.elapsed());
+        }
+
+        // Queue pending tasks on the miner expected to finish them soonest
+        let pending_ids: Vec<String> = self.task_distributor.pending_tasks.keys().cloned().collect();
+        for task_id in pending_ids {
+            let resolved_task = match self.task_distributor.pending_tasks[&task_id]
+                .with_resolved_inputs(&self.shared_tensors)
+                .and_then(|task| task.with_fetched_inputs(&self.chunk_store))
+            {
+                Ok(task) => task,
+                Err(e) => {
+                    eprintln!("Task {} skipped: {}", task_id, e);
+                    continue;
+                }
+            };
+
+            let eligible: Vec<&AI3Miner> = self.miners.iter()
+                .filter(|miner| miner.can_handle_task(&resolved_task))
+                .collect();
+            if eligible.is_empty() {
+                continue; // Stays pending until a capable miner joins
+            }
+
+            let cost = resolved_task.input_tensors.iter()
+                .map(|tensor| tensor.shape.total_elements() as u64)
+                .sum();
+            if let Some(task) = self.task_distributor.pending_tasks.remove(&task_id) {
+                self.scheduler.enqueue(task, cost, &eligible);
+            }
+        }
+
+        // ESP miners near their thermal limit or over the fleet power budget sit this round out
+        let busy: HashSet<String> = self.miners.iter()
+            .filter(|miner| miner.current_task.is_some())
+            .map(|miner| miner.id.clone())
+            .collect();
+        let admitted = self.power_scheduler.admit(&busy);
+        let power_scheduler = &self.power_scheduler;
+
+        // Idle miners drain their own queue first, then steal from backed-up peers
+        let mut outcomes = Vec::new();
+        for miner in self.miners.iter_mut()
+            .filter(|miner| miner.current_task.is_none())
+            .filter(|miner| !power_scheduler.contains(&miner.id) || admitted.contains(&miner.id))
+        {
+            while let Some(task) = self.scheduler.next_task(&miner.id) {
+                let task = match task.with_resolved_inputs(&self.shared_tensors)
+                    .and_then(|task| task.with_fetched_inputs(&self.chunk_store))
+                {
+                    Ok(task) => task,
+                    Err(e) => {
+                        eprintln!("Task {} skipped: {}", task.id, e);
+                        continue;
+                    }
+                };
+
+                if miner.assign_task(task).is_err() {
+                    continue;
+                }
+
+                match miner.mine_step() {
+                    Ok(Some(result)) => {
+                        results.push(result);
+                        outcomes.push(true);
+                    }
+                    Ok(None) => {
+                        // No result yet, continue mining
+                    }
+                    Err(e) => {
+                        eprintln!("Task processing failed: {}", e);
+                        outcomes.push(false);
                     }
                 }
+                break;
             }
         }
+        for success in outcomes {
+            self.update_stats(success, start_time.elapsed());
+        }
 
         // Clean up expired tasks
         self.task_distributor.cleanup_expired_tasks();
 
+        // Drop shared tensors no pending task refers to any more
+        let referenced: HashSet<String> = self.task_distributor.pending_tasks
+            .values()
+            .chain(self.scheduler.queued_tasks())
+            .flat_map(|task| task.input_refs.iter().cloned())
+            .collect();
+        self.shared_tensors.retain_referenced(&referenced);
+
+        let referenced_chunks: HashSet<String> = self.task_distributor.pending_tasks
+            .values()
+            .chain(self.scheduler.queued_tasks())
+            .flat_map(|task| task.required_chunks())
+            .collect();
+        self.chunk_store.retain(&referenced_chunks);
+
         Ok(results)
     }
 
@@ -167,6 +458,16 @@ impl AI3Engine {
         }
     }
 
+    /// Hit rate of the shared operation result cache
+    pub fn result_cache_hit_rate(&self) -> f64 {
+        ResultCache::global().lock().map(|cache| cache.hit_rate()).unwrap_or(0.0)
+    }
+
+    /// Number of tasks queued across all miners
+    pub fn queued_task_count(&self) -> usize {
+        self.scheduler.total_queued()
+    }
+
     /// Get miner capabilities summary
     pub fn get_miner_capabilities(&self) -> Vec<MinerCapabilities> {
         self.miners.iter().map(|miner| miner.capabilities.clone()).collect()
@@ -174,6 +475,15 @@ impl AI3Engine {
 
     /// Optimize task tensors for available miners
     fn optimize_task_tensors(&self, mut task: MiningTask) -> tribechain_core::TribeResult<MiningTask> {
+        for tensor in &mut task.input_tensors {
+            *tensor = self.optimize_tensor(tensor)?;
+        }
+
+        Ok(task)
+    }
+
+    /// Optimize a single tensor for the most restrictive ESP miner, if any
+    fn optimize_tensor(&self, tensor: &Tensor) -> tribechain_core::TribeResult<Tensor> {
         // Check if we have ESP miners and optimize accordingly
         let has_esp_miners = self.miners.iter().any(|miner| {
             miner.capabilities.is_esp_device
@@ -185,13 +495,11 @@ impl AI3Engine {
             
             if let Some(device_type) = most_restrictive_device {
                 // Optimize tensors for the most restrictive device
-                for tensor in &mut task.input_tensors {
-                    *tensor = esp_compat::ESPCompatibility::optimize_for_esp(tensor, &device_type)?;
-                }
+                return esp_compat::ESPCompatibility::optimize_for_esp(tensor, &device_type);
             }
         }
 
-        Ok(task)
+        Ok(tensor.clone())
     }
 
     /// Find the most restrictive ESP device among miners
@@ -236,6 +544,8 @@ impl AI3Engine {
     pub fn shutdown(&mut self) {
         // Clean up resources
         self.miners.clear();
+        self.scheduler.clear();
+        self.power_scheduler = FleetPowerScheduler::new(self.power_scheduler.policy.clone());
         
         if let Ok(mut stats) = self.performance_stats.lock() {
             stats.active_miners = 0;
@@ -256,6 +566,11 @@ impl std::str::FromStr for ESPDeviceType {
         match s.to_lowercase().as_str() {
             "esp32" => Ok(ESPDeviceType::ESP32),
             "esp8266" => Ok(ESPDeviceType::ESP8266),
+            "esp32s2" | "esp32-s2" => Ok(ESPDeviceType::ESP32S2),
+            "esp32s3" | "esp32-s3" => Ok(ESPDeviceType::ESP32S3),
+            "esp32c3" | "esp32-c3" => Ok(ESPDeviceType::ESP32C3),
+            "esp32c6" | "esp32-c6" => Ok(ESPDeviceType::ESP32C6),
+            "esp32h2" | "esp32-h2" => Ok(ESPDeviceType::ESP32H2),
             _ => Err(format!("Unknown ESP device type: {}", s)),
         }
     }
@@ -0,0 +1,91 @@
+use serde::{Deserialize, Serialize};
+use crate::tensor::{Tensor, TensorShape};
+use crate::mining::results::MiningResult;
+use tribechain_core::{TribeResult, TribeError};
+
+/// Outlier threshold used when an aggregate task doesn't set one
+pub const DEFAULT_OUTLIER_THRESHOLD: f32 = 3.0;
+
+/// Federated average of the weight deltas submitted for an aggregate task
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AggregateOutcome {
+    pub task_id: String,
+    pub output_tensor: Tensor,
+    pub accepted: Vec<String>, // Miners whose deltas went into the average
+    pub rejected: Vec<String>, // Miners dropped as outliers
+    pub total_samples: u64,
+}
+
+/// FedAvg over `results`, weighted by each miner's shard size.
+///
+/// Deltas further from the coordinate-wise median than `outlier_threshold` times
+/// the median distance are rejected first, so a few poisoned updates can't drag
+/// the average.
+pub fn federated_average(task_id: &str, results: &[MiningResult], outlier_threshold: f32) -> TribeResult<AggregateOutcome> {
+    if results.is_empty() {
+        return Err(TribeError::AI3("No weight deltas to aggregate".to_string()));
+    }
+    if outlier_threshold < 1.0 {
+        return Err(TribeError::AI3("Outlier threshold must be at least 1".to_string()));
+    }
+
+    let shape = results[0].output_tensor.shape.clone();
+    let mut deltas = Vec::with_capacity(results.len());
+    for result in results {
+        if result.output_tensor.shape != shape {
+            return Err(TribeError::AI3(format!(
+                "Weight delta from {} has shape {:?}, expected {:?}",
+                result.miner_id, result.output_tensor.shape.dimensions, shape.dimensions
+            )));
+        }
+        deltas.push(result.output_tensor.data.as_f32_vec()?);
+    }
+
+    let center = coordinate_median(&deltas);
+    let distances: Vec<f32> = deltas.iter().map(|delta| distance(delta, &center)).collect();
+    let cutoff = outlier_threshold * median(distances.clone()).max(f32::EPSILON);
+
+    let mut sum = vec![0.0f64; center.len()];
+    let (mut accepted, mut rejected, mut total_samples) = (Vec::new(), Vec::new(), 0u64);
+    for ((result, delta), dist) in results.iter().zip(&deltas).zip(&distances) {
+        if *dist > cutoff {
+            rejected.push(result.miner_id.clone());
+            continue;
+        }
+        let weight = result.sample_count.max(1);
+        for (total, value) in sum.iter_mut().zip(delta) {
+            *total += *value as f64 * weight as f64;
+        }
+        accepted.push(result.miner_id.clone());
+        total_samples += weight;
+    }
+
+    let averaged = sum.into_iter().map(|total| (total / total_samples as f64) as f32).collect();
+    Ok(AggregateOutcome {
+        task_id: task_id.to_string(),
+        output_tensor: Tensor::from_vec(averaged, TensorShape::new(shape.dimensions))?,
+        accepted,
+        rejected,
+        total_samples,
+    })
+}
+
+fn median(mut values: Vec<f32>) -> f32 {
+    values.sort_by(|a, b| a.total_cmp(b));
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    }
+}
+
+fn coordinate_median(deltas: &[Vec<f32>]) -> Vec<f32> {
+    (0..deltas[0].len())
+        .map(|i| median(deltas.iter().map(|delta| delta[i]).collect()))
+        .collect()
+}
+
+fn distance(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum::<f32>().sqrt()
+}
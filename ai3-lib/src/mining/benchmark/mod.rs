@@ -0,0 +1,157 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use crate::mining::tasks::MiningTask;
+use crate::mining::miners::MinerCapabilities;
+use crate::tensor::{Tensor, TensorShape};
+use tribechain_core::{TribeResult, TribeError};
+
+/// Operations exercised by the benchmark, in report order
+pub const BENCHMARK_OPERATIONS: &[&str] = &[
+    "matrix_multiply",
+    "convolution",
+    "relu",
+    "sigmoid",
+    "tanh",
+    "softmax",
+    "dot_product",
+    "normalize",
+    "vector_add",
+];
+
+/// Measured throughput of one tensor operation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OperationBenchmark {
+    pub ops_per_sec: f64,
+    pub est_memory_bytes: usize, // Inputs plus output of one execution
+    pub complexity_score: u64,
+}
+
+/// Capability report produced by `tribechain mine bench`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkReport {
+    pub operations: BTreeMap<String, OperationBenchmark>,
+    pub pow_hashes_per_sec: f64,
+    pub duration_per_test_ms: u64,
+    pub created_at: DateTime<Utc>,
+}
+
+impl BenchmarkReport {
+    /// Run every supported operation and plain SHA-256 PoW for `duration` each
+    pub fn run(duration: Duration) -> TribeResult<Self> {
+        let mut operations = BTreeMap::new();
+        for operation in BENCHMARK_OPERATIONS {
+            operations.insert(operation.to_string(), benchmark_operation(operation, duration)?);
+        }
+
+        Ok(Self {
+            operations,
+            pow_hashes_per_sec: benchmark_pow(duration),
+            duration_per_test_ms: duration.as_millis() as u64,
+            created_at: Utc::now(),
+        })
+    }
+
+    /// Average complexity-weighted throughput, scaled so a typical CPU lands near 1000
+    pub fn compute_power(&self) -> u64 {
+        if self.operations.is_empty() {
+            return 1;
+        }
+
+        let weighted: f64 = self.operations.values()
+            .map(|bench| bench.ops_per_sec * bench.complexity_score as f64)
+            .sum();
+        ((weighted / self.operations.len() as f64 / 1000.0).round() as u64).max(1)
+    }
+
+    pub fn to_json(&self) -> TribeResult<String> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| TribeError::AI3(format!("Failed to encode benchmark report: {}", e)))
+    }
+
+    pub fn save(&self, path: &Path) -> TribeResult<()> {
+        std::fs::write(path, self.to_json()?)
+            .map_err(|e| TribeError::Storage(format!("Failed to write benchmark report: {}", e)))
+    }
+
+    pub fn load(path: &Path) -> TribeResult<Self> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| TribeError::Storage(format!("Failed to read benchmark report: {}", e)))?;
+        serde_json::from_str(&json)
+            .map_err(|e| TribeError::AI3(format!("Invalid benchmark report: {}", e)))
+    }
+}
+
+impl MinerCapabilities {
+    /// Capabilities measured on this device rather than assumed from its type
+    pub fn from_benchmark(report: &BenchmarkReport, is_esp_device: bool) -> Self {
+        Self {
+            max_tensor_size: if is_esp_device { 1024 } else { 1024 * 1024 },
+            supported_operations: report.operations.keys().cloned().collect(),
+            compute_power: report.compute_power(),
+            is_esp_device,
+        }
+    }
+}
+
+/// Inputs sized to fit within an ESP miner's `max_tensor_size`
+fn benchmark_inputs(operation: &str) -> Vec<Tensor> {
+    match operation {
+        "matrix_multiply" => vec![
+            Tensor::random(TensorShape::matrix(16, 16)),
+            Tensor::random(TensorShape::matrix(16, 16)),
+        ],
+        "convolution" => vec![
+            Tensor::random(TensorShape::matrix(16, 16)),
+            Tensor::random(TensorShape::matrix(3, 3)),
+        ],
+        "dot_product" | "vector_add" => vec![
+            Tensor::random(TensorShape::vector(512)),
+            Tensor::random(TensorShape::vector(512)),
+        ],
+        _ => vec![Tensor::random(TensorShape::vector(512))],
+    }
+}
+
+fn benchmark_operation(operation: &str, duration: Duration) -> TribeResult<OperationBenchmark> {
+    let op = MiningTask::operation_for(operation)?;
+    let inputs = benchmark_inputs(operation);
+
+    // Call the operation directly so the result cache doesn't inflate the numbers
+    let output = op.execute(&inputs)?;
+    let elements = inputs.iter().map(|t| t.shape.total_elements()).sum::<usize>()
+        + output.shape.total_elements();
+
+    let mut iterations = 0u64;
+    let start = Instant::now();
+    while start.elapsed() < duration || iterations == 0 {
+        op.execute(&inputs)?;
+        iterations += 1;
+    }
+
+    Ok(OperationBenchmark {
+        ops_per_sec: iterations as f64 / start.elapsed().as_secs_f64(),
+        est_memory_bytes: elements * std::mem::size_of::<f32>(),
+        complexity_score: op.get_complexity_score(),
+    })
+}
+
+fn benchmark_pow(duration: Duration) -> f64 {
+    let header = [0u8; 80];
+    let mut nonce = 0u64;
+    let start = Instant::now();
+    while start.elapsed() < duration || nonce == 0 {
+        // Check the clock in batches so timing overhead doesn't dominate
+        for _ in 0..1024 {
+            let mut hasher = Sha256::new();
+            hasher.update(header);
+            hasher.update(nonce.to_le_bytes());
+            std::hint::black_box(hasher.finalize());
+            nonce += 1;
+        }
+    }
+    nonce as f64 / start.elapsed().as_secs_f64()
+}
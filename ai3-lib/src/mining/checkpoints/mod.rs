@@ -0,0 +1,117 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::mining::tasks::MiningTask;
+use crate::tensor::Tensor;
+use tribechain_core::{TribeResult, TribeError};
+
+/// Partial progress of a mining task, persisted so work survives restarts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskCheckpoint {
+    pub task: MiningTask,
+    pub miner_id: String,
+    pub next_nonce: u64,
+    pub nonces_tried: u64,
+    pub partial_output: Option<Tensor>, // Operation output computed before the nonce search
+    pub saved_at: DateTime<Utc>,
+}
+
+impl TaskCheckpoint {
+    pub fn new(
+        task: MiningTask,
+        miner_id: String,
+        next_nonce: u64,
+        nonces_tried: u64,
+        partial_output: Option<Tensor>,
+    ) -> Self {
+        Self {
+            task,
+            miner_id,
+            next_nonce,
+            nonces_tried,
+            partial_output,
+            saved_at: Utc::now(),
+        }
+    }
+
+    pub fn task_id(&self) -> &str {
+        &self.task.id
+    }
+}
+
+/// File-backed checkpoint store, one JSON file per task
+#[derive(Debug, Clone)]
+pub struct CheckpointStore {
+    pub directory: PathBuf,
+}
+
+impl CheckpointStore {
+    pub fn new<P: AsRef<Path>>(directory: P) -> TribeResult<Self> {
+        let directory = directory.as_ref().to_path_buf();
+        fs::create_dir_all(&directory)
+            .map_err(|e| TribeError::Storage(format!("Failed to create checkpoint directory: {}", e)))?;
+
+        Ok(Self { directory })
+    }
+
+    pub fn save(&self, checkpoint: &TaskCheckpoint) -> TribeResult<()> {
+        let serialized = serde_json::to_vec(checkpoint)
+            .map_err(|e| TribeError::Storage(format!("Failed to serialize checkpoint: {}", e)))?;
+
+        // Write to a temporary file first so a power loss never leaves a torn checkpoint
+        let path = self.checkpoint_path(checkpoint.task_id());
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, serialized)
+            .map_err(|e| TribeError::Storage(format!("Failed to write checkpoint: {}", e)))?;
+        fs::rename(&tmp_path, &path)
+            .map_err(|e| TribeError::Storage(format!("Failed to commit checkpoint: {}", e)))?;
+
+        Ok(())
+    }
+
+    pub fn load(&self, task_id: &str) -> TribeResult<Option<TaskCheckpoint>> {
+        let path = self.checkpoint_path(task_id);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let data = fs::read(&path)
+            .map_err(|e| TribeError::Storage(format!("Failed to read checkpoint: {}", e)))?;
+        let checkpoint = serde_json::from_slice(&data)
+            .map_err(|e| TribeError::Storage(format!("Failed to deserialize checkpoint: {}", e)))?;
+
+        Ok(Some(checkpoint))
+    }
+
+    pub fn remove(&self, task_id: &str) -> TribeResult<()> {
+        let path = self.checkpoint_path(task_id);
+        if path.exists() {
+            fs::remove_file(&path)
+                .map_err(|e| TribeError::Storage(format!("Failed to remove checkpoint: {}", e)))?;
+        }
+        Ok(())
+    }
+
+    /// Ids of all tasks that have a checkpoint on disk
+    pub fn list_task_ids(&self) -> TribeResult<Vec<String>> {
+        let entries = fs::read_dir(&self.directory)
+            .map_err(|e| TribeError::Storage(format!("Failed to list checkpoints: {}", e)))?;
+
+        let mut task_ids = Vec::new();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) == Some("json") {
+                if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
+                    task_ids.push(stem.to_string());
+                }
+            }
+        }
+
+        Ok(task_ids)
+    }
+
+    fn checkpoint_path(&self, task_id: &str) -> PathBuf {
+        self.directory.join(format!("{}.json", task_id))
+    }
+}
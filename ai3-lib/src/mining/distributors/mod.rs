@@ -1,15 +1,27 @@
 use std::collections::HashMap;
-use crate::mining::tasks::MiningTask;
+use crate::mining::tasks::{MiningTask, MiningTaskKind};
 use crate::mining::results::MiningResult;
 use crate::mining::miners::AI3Miner;
+use crate::mining::marketplace::{TaskMarketplace, AwardedTask};
+use crate::mining::aggregation::{federated_average, AggregateOutcome};
 use tribechain_core::{TribeResult, TribeError};
 
+/// Weight deltas collected so far for an aggregate task
+#[derive(Debug, Clone)]
+pub struct AggregateRound {
+    pub task: MiningTask,
+    pub miners: Vec<String>,
+    pub results: Vec<MiningResult>,
+}
+
 /// Task distributor for managing mining tasks
 #[derive(Debug)]
 pub struct TaskDistributor {
     pub pending_tasks: HashMap<String, MiningTask>,
     pub active_tasks: HashMap<String, (MiningTask, String)>, // task_id -> (task, miner_id)
     pub completed_tasks: HashMap<String, MiningResult>,
+    pub aggregate_rounds: HashMap<String, AggregateRound>,
+    pub aggregated_tasks: HashMap<String, AggregateOutcome>,
 }
 
 impl TaskDistributor {
@@ -18,6 +30,8 @@ impl TaskDistributor {
             pending_tasks: HashMap::new(),
             active_tasks: HashMap::new(),
             completed_tasks: HashMap::new(),
+            aggregate_rounds: HashMap::new(),
+            aggregated_tasks: HashMap::new(),
         }
     }
 
@@ -26,6 +40,10 @@ impl TaskDistributor {
     }
 
     pub fn distribute(&mut self, task: MiningTask, miners: &[AI3Miner]) -> TribeResult<Vec<String>> {
+        if let MiningTaskKind::Aggregate { .. } = task.kind {
+            return self.distribute_aggregate(task, miners);
+        }
+
         let mut assigned_miners = Vec::new();
 
         // Find suitable miners
@@ -40,7 +58,7 @@ impl TaskDistributor {
         if assigned_miners.is_empty() {
             // No suitable miners found, keep in pending
             self.pending_tasks.insert(task.id.clone(), task);
-            return Err(TribeError::InvalidOperation("No suitable miners available".to_string()));
+            return Err(TribeError::AI3("No suitable miners available".to_string()));
         }
 
         // Remove from pending if it was there
@@ -49,7 +67,56 @@ impl TaskDistributor {
         Ok(assigned_miners)
     }
 
+    /// Hand an aggregate task to `task.participants()` distinct miners, or none at all
+    fn distribute_aggregate(&mut self, task: MiningTask, miners: &[AI3Miner]) -> TribeResult<Vec<String>> {
+        let mut assigned_miners: Vec<String> = Vec::new();
+        for miner in miners {
+            if assigned_miners.len() == task.participants() {
+                break;
+            }
+            if miner.can_handle_task(&task) && !assigned_miners.contains(&miner.id) {
+                assigned_miners.push(miner.id.clone());
+            }
+        }
+
+        if assigned_miners.len() < task.participants() {
+            let message = format!(
+                "Aggregate task needs {} miners, only {} available", task.participants(), assigned_miners.len()
+            );
+            self.pending_tasks.insert(task.id.clone(), task);
+            return Err(TribeError::AI3(message));
+        }
+
+        self.pending_tasks.remove(&task.id);
+        self.aggregate_rounds.insert(task.id.clone(), AggregateRound {
+            task,
+            miners: assigned_miners.clone(),
+            results: Vec::new(),
+        });
+        Ok(assigned_miners)
+    }
+
+    /// Assign a listed task to its winning bidder at the agreed price
+    pub fn award_from_market(&mut self, market: &mut TaskMarketplace, task_id: &str) -> TribeResult<AwardedTask> {
+        let awarded = market.award(task_id)?;
+        self.pending_tasks.remove(task_id);
+        self.active_tasks.insert(awarded.task.id.clone(), (awarded.task.clone(), awarded.miner_id.clone()));
+        Ok(awarded)
+    }
+
+    /// Move a pending task to `miner_id` directly, e.g. a pool of devices sharing a topic
+    pub fn assign(&mut self, task_id: &str, miner_id: &str) -> TribeResult<MiningTask> {
+        let task = self.pending_tasks.remove(task_id)
+            .ok_or_else(|| TribeError::AI3("Task not found in pending tasks".to_string()))?;
+        self.active_tasks.insert(task.id.clone(), (task.clone(), miner_id.to_string()));
+        Ok(task)
+    }
+
     pub fn submit_result(&mut self, result: MiningResult) -> TribeResult<()> {
+        if self.aggregate_rounds.contains_key(&result.task_id) {
+            return self.submit_weight_delta(result);
+        }
+
         // Validate that this task was actually assigned
         if let Some((task, _miner_id)) = self.active_tasks.remove(&result.task_id) {
             // Validate the result
@@ -59,8 +126,42 @@ impl TaskDistributor {
             self.completed_tasks.insert(task.id.clone(), validated_result);
             Ok(())
         } else {
-            Err(TribeError::InvalidOperation("Task not found in active tasks".to_string()))
+            Err(TribeError::AI3("Task not found in active tasks".to_string()))
+        }
+    }
+
+    /// Collect one miner's weight delta, aggregating once every participant has submitted
+    fn submit_weight_delta(&mut self, mut result: MiningResult) -> TribeResult<()> {
+        let task_id = result.task_id.clone();
+        let round = self.aggregate_rounds.get_mut(&task_id)
+            .ok_or_else(|| TribeError::AI3("Task not found in active tasks".to_string()))?;
+        if !round.miners.contains(&result.miner_id) {
+            return Err(TribeError::AI3("Miner was not assigned to this task".to_string()));
+        }
+        if round.results.iter().any(|submitted| submitted.miner_id == result.miner_id) {
+            return Err(TribeError::AI3("Miner already submitted a weight delta".to_string()));
+        }
+        if !result.validate(&round.task)? {
+            return Err(TribeError::AI3("Invalid weight delta".to_string()));
+        }
+        round.results.push(result);
+
+        if round.results.len() < round.task.participants() {
+            return Ok(());
         }
+
+        let round = self.aggregate_rounds.remove(&task_id).expect("round checked above");
+        let outlier_threshold = match round.task.kind {
+            MiningTaskKind::Aggregate { outlier_threshold, .. } => outlier_threshold,
+            MiningTaskKind::Single => unreachable!("only aggregate tasks have rounds"),
+        };
+        let outcome = federated_average(&task_id, &round.results, outlier_threshold)?;
+        self.aggregated_tasks.insert(task_id, outcome);
+        Ok(())
+    }
+
+    pub fn get_aggregate_outcome(&self, task_id: &str) -> Option<&AggregateOutcome> {
+        self.aggregated_tasks.get(task_id)
     }
 
     pub fn get_pending_tasks(&self) -> Vec<&MiningTask> {
@@ -74,6 +175,7 @@ impl TaskDistributor {
     pub fn cleanup_expired_tasks(&mut self) {
         self.pending_tasks.retain(|_, task| !task.is_expired());
         self.active_tasks.retain(|_, (task, _)| !task.is_expired());
+        self.aggregate_rounds.retain(|_, round| !round.task.is_expired());
     }
 }
 
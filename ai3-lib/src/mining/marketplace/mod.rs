@@ -0,0 +1,174 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use crate::mining::tasks::MiningTask;
+use crate::mining::miners::AI3Miner;
+use crate::mining::benchmark::BenchmarkReport;
+use tribechain_core::{TribeResult, TribeError};
+
+/// Success rate below which a miner's bids are not considered credible
+pub const DEFAULT_MIN_SUCCESS_RATE: f64 = 0.5;
+
+/// A miner's offer to compute a listed task
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bid {
+    pub miner_id: String,
+    pub price: u64,
+    pub estimated_time: u64, // seconds, from the miner's benchmark when it has one
+    pub submitted_at: DateTime<Utc>,
+}
+
+/// A task posted by its requester, open for bids up to `max_price`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskListing {
+    pub task: MiningTask,
+    pub max_price: u64,
+    pub bids: Vec<Bid>,
+    pub posted_at: DateTime<Utc>,
+}
+
+/// Task and price a listing was settled at
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AwardedTask {
+    pub task: MiningTask, // `reward` holds the agreed price
+    pub miner_id: String,
+    pub price: u64,
+    pub max_price: u64,
+}
+
+/// Order book pricing tensor tasks by miner bids instead of a fixed reward
+#[derive(Debug, Clone)]
+pub struct TaskMarketplace {
+    pub listings: HashMap<String, TaskListing>,
+    pub benchmarks: HashMap<String, BenchmarkReport>, // miner_id -> latest report
+    pub min_success_rate: f64,
+}
+
+impl TaskMarketplace {
+    pub fn new() -> Self {
+        Self {
+            listings: HashMap::new(),
+            benchmarks: HashMap::new(),
+            min_success_rate: DEFAULT_MIN_SUCCESS_RATE,
+        }
+    }
+
+    pub fn with_min_success_rate(mut self, min_success_rate: f64) -> Self {
+        self.min_success_rate = min_success_rate;
+        self
+    }
+
+    /// List a task; requesters pay at most `max_price` for it
+    pub fn post_task(&mut self, task: MiningTask, max_price: u64) -> TribeResult<()> {
+        if max_price == 0 {
+            return Err(TribeError::AI3("Max price must be positive".to_string()));
+        }
+        if self.listings.contains_key(&task.id) {
+            return Err(TribeError::AI3("Task is already listed".to_string()));
+        }
+
+        self.listings.insert(task.id.clone(), TaskListing {
+            task,
+            max_price,
+            bids: Vec::new(),
+            posted_at: Utc::now(),
+        });
+        Ok(())
+    }
+
+    /// Withdraw a listing before it is awarded
+    pub fn cancel_task(&mut self, task_id: &str) -> TribeResult<TaskListing> {
+        self.listings.remove(task_id)
+            .ok_or_else(|| TribeError::AI3("Task not listed".to_string()))
+    }
+
+    pub fn register_benchmark(&mut self, miner_id: String, report: BenchmarkReport) {
+        self.benchmarks.insert(miner_id, report);
+    }
+
+    /// Seconds `miner` needs for `task` by its benchmark, if it has one for the operation
+    pub fn estimate_time(&self, miner_id: &str, task: &MiningTask) -> Option<u64> {
+        let benchmark = self.benchmarks.get(miner_id)?.operations.get(&task.operation_type)?;
+        if benchmark.ops_per_sec <= 0.0 {
+            return None;
+        }
+
+        // Benchmarks run on fixed-size inputs, so scale by how much larger this task is
+        let elements: usize = task.input_tensors.iter().map(|t| t.shape.total_elements()).sum();
+        let benchmark_elements = (benchmark.est_memory_bytes / std::mem::size_of::<f32>()).max(1);
+        let scale = (elements as f64 / benchmark_elements as f64).max(1.0);
+        Some((scale / benchmark.ops_per_sec).ceil() as u64)
+    }
+
+    /// Bid on a listed task, replacing the miner's earlier bid
+    pub fn submit_bid(&mut self, task_id: &str, miner: &AI3Miner, price: u64) -> TribeResult<()> {
+        let listing = self.listings.get(task_id)
+            .ok_or_else(|| TribeError::AI3("Task not listed".to_string()))?;
+        if price == 0 || price > listing.max_price {
+            return Err(TribeError::AI3(format!(
+                "Bid must be between 1 and {}", listing.max_price
+            )));
+        }
+        if !miner.can_handle_task(&listing.task) {
+            return Err(TribeError::AI3("Miner cannot handle this task".to_string()));
+        }
+        if miner.stats.tasks_completed > 0 && miner.stats.success_rate < self.min_success_rate {
+            return Err(TribeError::AI3("Miner success rate too low to bid".to_string()));
+        }
+
+        let estimated_time = match self.estimate_time(&miner.id, &listing.task) {
+            Some(estimate) if estimate > listing.task.max_computation_time => {
+                return Err(TribeError::AI3(
+                    "Benchmark shows the miner cannot finish in time".to_string(),
+                ));
+            }
+            Some(estimate) => estimate,
+            None => listing.task.max_computation_time,
+        };
+
+        let listing = self.listings.get_mut(task_id).expect("listing checked above");
+        listing.bids.retain(|bid| bid.miner_id != miner.id);
+        listing.bids.push(Bid {
+            miner_id: miner.id.clone(),
+            price,
+            estimated_time,
+            submitted_at: Utc::now(),
+        });
+        Ok(())
+    }
+
+    /// Lowest bid for a task; ties go to the faster miner, then the earlier bid
+    pub fn best_bid(&self, task_id: &str) -> Option<&Bid> {
+        self.listings.get(task_id)?.bids.iter().min_by(|a, b| {
+            a.price.cmp(&b.price)
+                .then(a.estimated_time.cmp(&b.estimated_time))
+                .then(a.submitted_at.cmp(&b.submitted_at))
+        })
+    }
+
+    /// Close a listing at its best bid. The returned task's reward is the agreed price.
+    pub fn award(&mut self, task_id: &str) -> TribeResult<AwardedTask> {
+        let bid = self.best_bid(task_id)
+            .cloned()
+            .ok_or_else(|| TribeError::AI3("No bids for this task".to_string()))?;
+        let mut listing = self.cancel_task(task_id)?;
+        listing.task.reward = bid.price;
+
+        Ok(AwardedTask {
+            task: listing.task,
+            miner_id: bid.miner_id,
+            price: bid.price,
+            max_price: listing.max_price,
+        })
+    }
+
+    pub fn get_open_listings(&self) -> Vec<&TaskListing> {
+        self.listings.values().collect()
+    }
+}
+
+impl Default for TaskMarketplace {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,7 +1,9 @@
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
+use std::time::{Duration, Instant};
 use crate::mining::tasks::MiningTask;
 use crate::mining::results::MiningResult;
+use crate::mining::checkpoints::{CheckpointStore, TaskCheckpoint};
 use crate::tensor::Tensor;
 use tribechain_core::{TribeResult, TribeError};
 
@@ -34,6 +36,14 @@ pub struct AI3Miner {
     pub latest_result: Option<MiningResult>,
     pub stats: MinerStats,
     pub is_active: bool,
+    pub next_nonce: Option<u64>,
+    pub nonces_tried: u64,
+    pub partial_output: Option<Tensor>,
+    pub checkpoint_interval: Duration,
+    #[serde(skip)]
+    pub checkpoint_store: Option<CheckpointStore>,
+    #[serde(skip)]
+    last_checkpoint: Option<Instant>,
 }
 
 impl AI3Miner {
@@ -67,9 +77,28 @@ impl AI3Miner {
             latest_result: None,
             stats,
             is_active: true,
+            next_nonce: None,
+            nonces_tried: 0,
+            partial_output: None,
+            checkpoint_interval: Duration::from_secs(30),
+            checkpoint_store: None,
+            last_checkpoint: None,
         }
     }
 
+    /// Replace the assumed capabilities, e.g. with ones from a benchmark report
+    pub fn with_capabilities(mut self, capabilities: MinerCapabilities) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    /// Persist partial progress to `store` every `interval`
+    pub fn with_checkpointing(mut self, store: CheckpointStore, interval: Duration) -> Self {
+        self.checkpoint_store = Some(store);
+        self.checkpoint_interval = interval;
+        self
+    }
+
     pub fn can_handle_task(&self, task: &MiningTask) -> bool {
         if !self.is_active {
             return false;
@@ -95,13 +124,43 @@ impl AI3Miner {
 
     pub fn assign_task(&mut self, task: MiningTask) -> TribeResult<()> {
         if !self.can_handle_task(&task) {
-            return Err(TribeError::InvalidOperation("Miner cannot handle this task".to_string()));
+            return Err(TribeError::AI3("Miner cannot handle this task".to_string()));
         }
 
+        self.start_task(task);
+        Ok(())
+    }
+
+    /// Make `task` current without checking capabilities, for callers that did their own
+    pub(crate) fn start_task(&mut self, task: MiningTask) {
         self.current_task = Some(task);
+        self.next_nonce = None;
+        self.nonces_tried = 0;
+        self.partial_output = None;
+        self.last_checkpoint = Some(Instant::now());
+    }
+
+    /// Pick up a task where a previous run left off
+    pub fn resume_from_checkpoint(&mut self, checkpoint: TaskCheckpoint) -> TribeResult<()> {
+        self.assign_task(checkpoint.task)?;
+        self.next_nonce = Some(checkpoint.next_nonce);
+        self.nonces_tried = checkpoint.nonces_tried;
+        self.partial_output = checkpoint.partial_output;
         Ok(())
     }
 
+    /// Snapshot of the current task's progress, if any
+    pub fn checkpoint(&self) -> Option<TaskCheckpoint> {
+        let task = self.current_task.as_ref()?;
+        Some(TaskCheckpoint::new(
+            task.clone(),
+            self.id.clone(),
+            self.next_nonce.unwrap_or(task.nonce_range.0),
+            self.nonces_tried,
+            self.partial_output.clone(),
+        ))
+    }
+
     pub fn mine_step(&mut self) -> TribeResult<Option<MiningResult>> {
         let task = match &self.current_task {
             Some(task) => task.clone(),
@@ -109,22 +168,28 @@ impl AI3Miner {
         };
 
         if task.is_expired() {
-            self.current_task = None;
+            self.clear_task(&task.id)?;
             return Ok(None);
         }
 
         let start_time = std::time::Instant::now();
 
-        // Simple mining simulation - try random nonces
-        use rand::Rng;
-        let mut rng = rand::thread_rng();
-        let nonce = rng.gen_range(task.nonce_range.0..=task.nonce_range.1);
+        // Execute the operation once up front; the nonce search only needs the hash
+        if self.partial_output.is_none() {
+            self.partial_output = Some(task.execute_operation()?);
+        }
 
+        // Walk the nonce range sequentially so progress can be checkpointed
+        let nonce = self.next_nonce.unwrap_or(task.nonce_range.0);
         let hash = task.calculate_hash(nonce);
-        
+        self.nonces_tried += 1;
+
         if task.meets_difficulty(&hash) {
-            // Found valid hash, execute operation
-            let output_tensor = task.execute_operation()?;
+            // Found valid hash, hand out the precomputed output
+            let output_tensor = match self.partial_output.take() {
+                Some(tensor) => tensor,
+                None => task.execute_operation()?,
+            };
             let computation_time = start_time.elapsed().as_millis() as u64;
 
             let result = MiningResult::new(
@@ -137,15 +202,57 @@ impl AI3Miner {
             );
 
             self.latest_result = Some(result.clone());
-            self.current_task = None;
+            self.clear_task(&task.id)?;
             self.update_stats(computation_time, true);
 
             return Ok(Some(result));
         }
 
+        if nonce >= task.nonce_range.1 {
+            // Range exhausted without a solution
+            self.clear_task(&task.id)?;
+            return Ok(None);
+        }
+
+        self.next_nonce = Some(nonce + 1);
+        self.maybe_checkpoint()?;
+
         Ok(None)
     }
 
+    /// Write a checkpoint if one is configured and the interval has elapsed
+    fn maybe_checkpoint(&mut self) -> TribeResult<()> {
+        let store = match &self.checkpoint_store {
+            Some(store) => store,
+            None => return Ok(()),
+        };
+
+        let due = self.last_checkpoint
+            .map(|last| last.elapsed() >= self.checkpoint_interval)
+            .unwrap_or(true);
+
+        if due {
+            if let Some(checkpoint) = self.checkpoint() {
+                store.save(&checkpoint)?;
+            }
+            self.last_checkpoint = Some(Instant::now());
+        }
+
+        Ok(())
+    }
+
+    fn clear_task(&mut self, task_id: &str) -> TribeResult<()> {
+        self.current_task = None;
+        self.next_nonce = None;
+        self.nonces_tried = 0;
+        self.partial_output = None;
+
+        if let Some(store) = &self.checkpoint_store {
+            store.remove(task_id)?;
+        }
+        Ok(())
+    }
+
     pub fn get_latest_result(&self) -> Option<MiningResult> {
         self.latest_result.clone()
     }
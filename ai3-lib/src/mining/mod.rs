@@ -2,10 +2,24 @@ pub mod tasks;
 pub mod miners;
 pub mod distributors;
 pub mod results;
+pub mod checkpoints;
+pub mod scheduler;
+pub mod verification;
+pub mod benchmark;
+pub mod marketplace;
+pub mod aggregation;
+pub mod proofs;
 pub mod tests;
 
 // Re-export main types for convenience
-pub use tasks::MiningTask;
+pub use tasks::{MiningTask, MiningTaskKind};
 pub use miners::{AI3Miner, MinerCapabilities, MinerStats};
-pub use distributors::TaskDistributor;
-pub use results::MiningResult; 
\ No newline at end of file
+pub use distributors::{TaskDistributor, AggregateRound};
+pub use results::MiningResult;
+pub use checkpoints::{CheckpointStore, TaskCheckpoint};
+pub use scheduler::{WorkStealingScheduler, QueuedTask};
+pub use verification::{VerificationPolicy, verification_challenge};
+pub use benchmark::{BenchmarkReport, OperationBenchmark};
+pub use marketplace::{TaskMarketplace, TaskListing, Bid, AwardedTask};
+pub use aggregation::{federated_average, AggregateOutcome};
+pub use proofs::{ComputationProof, FreivaldsRound}; 
\ No newline at end of file
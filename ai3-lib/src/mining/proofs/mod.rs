@@ -0,0 +1,204 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use crate::tensor::Tensor;
+use crate::mining::tasks::MiningTask;
+use tribechain_core::{TribeResult, TribeError};
+
+/// Freivalds rounds a miner includes by default; a wrong product passes each with probability ≤ 1/2
+pub const DEFAULT_PROOF_ROUNDS: usize = 16;
+
+/// Rows or elements a light validator recomputes per check
+pub const DEFAULT_PROOF_SAMPLES: usize = 16;
+
+/// Relative tolerance for float accumulation differences between prover and verifier
+pub const PROOF_TOLERANCE: f32 = 1e-3;
+
+/// One Fiat-Shamir Freivalds round for `C = A·B` with challenge `r`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FreivaldsRound {
+    pub br: Vec<f32>, // B·r
+    pub abr: Vec<f32>, // A·(B·r)
+}
+
+/// Succinct evidence that an output is correct, checked without recomputing the operation.
+/// Challenges and sampled rows come from the transcript, so every validator checks the same ones.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ComputationProof {
+    /// Matrix multiply: the validator checks `A·(B·r) == C·r` and spot-checks
+    /// sampled rows of the claimed `B·r` and `A·(B·r)`, in O(n·p + samples·(m + p))
+    Freivalds { output_hash: String, rounds: Vec<FreivaldsRound> },
+    /// Element-local operations: the validator recomputes sampled elements
+    Elementwise { output_hash: String },
+}
+
+/// Deterministic challenge stream seeded by the proof transcript
+struct Transcript {
+    seed: [u8; 32],
+    counter: u64,
+}
+
+impl Transcript {
+    fn new(parts: &[&[u8]]) -> Self {
+        let mut hasher = Sha256::new();
+        for part in parts {
+            hasher.update((part.len() as u64).to_le_bytes());
+            hasher.update(part);
+        }
+        Self { seed: hasher.finalize().into(), counter: 0 }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut hasher = Sha256::new();
+        hasher.update(self.seed);
+        hasher.update(self.counter.to_le_bytes());
+        self.counter += 1;
+        let digest = hasher.finalize();
+        u64::from_le_bytes(digest[..8].try_into().expect("digest is 32 bytes"))
+    }
+
+    /// Random 0/1 vector, as in Freivalds' algorithm
+    fn binary_vector(&mut self, len: usize) -> Vec<f64> {
+        (0..len).map(|_| (self.next_u64() & 1) as f64).collect()
+    }
+
+    fn indices(&mut self, count: usize, len: usize) -> Vec<usize> {
+        (0..count).map(|_| (self.next_u64() % len as u64) as usize).collect()
+    }
+}
+
+fn within_tolerance(a: f64, b: f64) -> bool {
+    let scale = 1.0f64.max(a.abs()).max(b.abs());
+    (a - b).abs() <= PROOF_TOLERANCE as f64 * scale
+}
+
+/// `(n, m, p)` for `A` n×m times `B` m×p
+fn matmul_dims(task: &MiningTask) -> TribeResult<(usize, usize, usize)> {
+    let (a, b) = match task.input_tensors.as_slice() {
+        [a, b] if a.shape.rank() == 2 && b.shape.rank() == 2 => (a, b),
+        _ => return Err(TribeError::AI3("Matrix multiply proofs need two 2D inputs".to_string())),
+    };
+    let (n, m, p) = (a.shape.dimensions[0], a.shape.dimensions[1], b.shape.dimensions[1]);
+    if b.shape.dimensions[0] != m {
+        return Err(TribeError::AI3("Matrix dimensions do not line up".to_string()));
+    }
+    Ok((n, m, p))
+}
+
+fn challenge(task: &MiningTask, output_hash: &str, round: usize) -> Transcript {
+    Transcript::new(&[task.id.as_bytes(), output_hash.as_bytes(), &(round as u64).to_le_bytes()])
+}
+
+impl ComputationProof {
+    /// Operations a proof can be produced for
+    pub fn supports(operation_type: &str) -> bool {
+        matches!(operation_type, "matrix_multiply" | "relu" | "sigmoid" | "tanh" | "vector_add")
+    }
+
+    /// Build the proof for `output` of `task`, whose inputs must be resolved
+    pub fn generate(task: &MiningTask, output: &Tensor, rounds: usize) -> TribeResult<Self> {
+        let output_hash = output.calculate_hash();
+        if task.operation_type != "matrix_multiply" {
+            if !Self::supports(&task.operation_type) {
+                return Err(TribeError::AI3(format!("No proof system for {}", task.operation_type)));
+            }
+            return Ok(ComputationProof::Elementwise { output_hash });
+        }
+
+        let (n, m, p) = matmul_dims(task)?;
+        let a = task.input_tensors[0].data.as_f32_slice()?;
+        let b = task.input_tensors[1].data.as_f32_slice()?;
+
+        let rounds = (0..rounds.max(1)).map(|round| {
+            let r = challenge(task, &output_hash, round).binary_vector(p);
+            let br: Vec<f64> = (0..m)
+                .map(|k| (0..p).map(|j| b[k * p + j] as f64 * r[j]).sum())
+                .collect();
+            let abr = (0..n)
+                .map(|i| (0..m).map(|k| a[i * m + k] as f64 * br[k]).sum::<f64>() as f32)
+                .collect();
+            FreivaldsRound { br: br.into_iter().map(|value| value as f32).collect(), abr }
+        }).collect();
+
+        Ok(ComputationProof::Freivalds { output_hash, rounds })
+    }
+
+    /// Check the proof against the task inputs and claimed output without recomputing
+    pub fn verify(&self, task: &MiningTask, output: &Tensor, samples: usize) -> TribeResult<bool> {
+        match self {
+            ComputationProof::Freivalds { output_hash, rounds } => {
+                if task.operation_type != "matrix_multiply" || *output_hash != output.calculate_hash() || rounds.is_empty() {
+                    return Ok(false);
+                }
+                let (n, m, p) = matmul_dims(task)?;
+                if output.shape.dimensions != [n, p] {
+                    return Ok(false);
+                }
+                let a = task.input_tensors[0].data.as_f32_slice()?;
+                let b = task.input_tensors[1].data.as_f32_slice()?;
+                let c = output.data.as_f32_slice()?;
+
+                for (index, round) in rounds.iter().enumerate() {
+                    if round.br.len() != m || round.abr.len() != n {
+                        return Ok(false);
+                    }
+                    let mut transcript = challenge(task, output_hash, index);
+                    let r = transcript.binary_vector(p);
+
+                    // C·r must match the claimed A·(B·r) everywhere
+                    for i in 0..n {
+                        let cr: f64 = (0..p).map(|j| c[i * p + j] as f64 * r[j]).sum();
+                        if !within_tolerance(cr, round.abr[i] as f64) {
+                            return Ok(false);
+                        }
+                    }
+
+                    // ...and the claimed intermediates must be honest on sampled rows
+                    for k in transcript.indices(samples.max(1), m) {
+                        let br: f64 = (0..p).map(|j| b[k * p + j] as f64 * r[j]).sum();
+                        if !within_tolerance(br, round.br[k] as f64) {
+                            return Ok(false);
+                        }
+                    }
+                    for i in transcript.indices(samples.max(1), n) {
+                        let abr: f64 = (0..m).map(|k| a[i * m + k] as f64 * round.br[k] as f64).sum();
+                        if !within_tolerance(abr, round.abr[i] as f64) {
+                            return Ok(false);
+                        }
+                    }
+                }
+                Ok(true)
+            }
+            ComputationProof::Elementwise { output_hash } => {
+                if !Self::supports(&task.operation_type) || *output_hash != output.calculate_hash() {
+                    return Ok(false);
+                }
+                let actual = output.data.as_f32_slice()?;
+                let inputs = task.input_tensors.iter()
+                    .map(|tensor| tensor.data.as_f32_slice())
+                    .collect::<TribeResult<Vec<_>>>()?;
+                if actual.is_empty() || inputs.is_empty() || inputs.iter().any(|input| input.len() != actual.len()) {
+                    return Ok(false);
+                }
+
+                let operation = task.get_operation()?;
+                let mut transcript = Transcript::new(&[task.id.as_bytes(), output_hash.as_bytes()]);
+                for index in transcript.indices(samples.max(1), actual.len()) {
+                    let scalars: Vec<Tensor> = inputs.iter().map(|input| Tensor::vector(vec![input[index]])).collect();
+                    let expected = operation.execute(&scalars)?;
+                    if !within_tolerance(expected.data.as_f32_slice()?[0] as f64, actual[index] as f64) {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+        }
+    }
+
+    pub fn to_bytes(&self) -> TribeResult<Vec<u8>> {
+        bincode::serialize(self).map_err(|e| TribeError::AI3(format!("Failed to encode proof: {}", e)))
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> TribeResult<Self> {
+        bincode::deserialize(bytes).map_err(|e| TribeError::AI3(format!("Invalid computation proof: {}", e)))
+    }
+}
@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 use crate::tensor::Tensor;
-use crate::mining::tasks::MiningTask;
+use crate::mining::tasks::{MiningTask, MiningTaskKind};
 use tribechain_core::TribeResult;
 
 /// Mining result
@@ -15,6 +15,8 @@ pub struct MiningResult {
     pub computation_time: u64, // milliseconds
     pub timestamp: DateTime<Utc>,
     pub is_valid: bool,
+    #[serde(default)]
+    pub sample_count: u64, // Local shard size behind a weight delta, used as its FedAvg weight
 }
 
 impl MiningResult {
@@ -35,9 +37,15 @@ impl MiningResult {
             computation_time,
             timestamp: Utc::now(),
             is_valid: false, // Will be validated by network
+            sample_count: 0,
         }
     }
 
+    pub fn with_sample_count(mut self, sample_count: u64) -> Self {
+        self.sample_count = sample_count;
+        self
+    }
+
     pub fn validate(&mut self, task: &MiningTask) -> TribeResult<bool> {
         // Check if hash meets difficulty
         if !task.meets_difficulty(&self.hash) {
@@ -52,9 +60,18 @@ impl MiningResult {
             return Ok(false);
         }
 
-        // Verify tensor computation
-        let expected_output = task.execute_operation()?;
-        if !self.tensors_approximately_equal(&self.output_tensor, &expected_output) {
+        // Weight deltas come from private shards and can't be recomputed;
+        // they are checked against each other when aggregated
+        if let MiningTaskKind::Aggregate { .. } = task.kind {
+            let shape_ok = task.expected_output_shape.as_ref()
+                .is_none_or(|shape| &self.output_tensor.shape.dimensions == shape);
+            self.is_valid = shape_ok;
+            return Ok(shape_ok);
+        }
+
+        // Verify tensor computation under the task's policy, in full since there is
+        // no challenge to sample from here
+        if !task.verification.verify(task, &self.output_tensor, None)? {
             self.is_valid = false;
             return Ok(false);
         }
@@ -62,20 +79,4 @@ impl MiningResult {
         self.is_valid = true;
         Ok(true)
     }
-
-    fn tensors_approximately_equal(&self, a: &Tensor, b: &Tensor) -> bool {
-        if a.shape != b.shape {
-            return false;
-        }
-
-        let a_data = a.data.as_f32_vec().unwrap_or_default();
-        let b_data = b.data.as_f32_vec().unwrap_or_default();
-
-        if a_data.len() != b_data.len() {
-            return false;
-        }
-
-        const EPSILON: f32 = 1e-6;
-        a_data.iter().zip(b_data.iter()).all(|(x, y)| (x - y).abs() < EPSILON)
-    }
-} 
\ No newline at end of file
+}
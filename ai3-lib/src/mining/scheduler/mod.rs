@@ -0,0 +1,164 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use crate::mining::tasks::MiningTask;
+use crate::mining::miners::AI3Miner;
+
+/// Task waiting in a miner's queue
+#[derive(Debug, Clone)]
+pub struct QueuedTask {
+    pub task: MiningTask,
+    pub cost: u64, // Relative work units, e.g. input elements
+    pub eligible_miners: HashSet<String>,
+}
+
+/// Per-miner task queues where idle miners steal from the most backed-up peer.
+/// Backlog is weighted by `MinerCapabilities.compute_power`, so a fast miner
+/// only steals work it is expected to finish before the owner would reach it.
+#[derive(Debug, Default)]
+pub struct WorkStealingScheduler {
+    queues: HashMap<String, VecDeque<QueuedTask>>,
+    compute_power: HashMap<String, u64>,
+    pub steals: u64,
+}
+
+impl WorkStealingScheduler {
+    pub fn new() -> Self {
+        Self {
+            queues: HashMap::new(),
+            compute_power: HashMap::new(),
+            steals: 0,
+        }
+    }
+
+    pub fn register_miner(&mut self, miner: &AI3Miner) {
+        self.queues.entry(miner.id.clone()).or_default();
+        self.compute_power.insert(miner.id.clone(), miner.capabilities.compute_power.max(1));
+    }
+
+    /// Unregister a miner and hand back the tasks it still had queued
+    pub fn remove_miner(&mut self, miner_id: &str) -> Vec<MiningTask> {
+        self.compute_power.remove(miner_id);
+        self.queues
+            .remove(miner_id)
+            .map(|queue| queue.into_iter().map(|queued| queued.task).collect())
+            .unwrap_or_default()
+    }
+
+    /// Queue a task on the eligible miner expected to finish it soonest.
+    /// Returns the chosen miner id, or `None` if no miner is eligible.
+    pub fn enqueue(&mut self, task: MiningTask, cost: u64, eligible: &[&AI3Miner]) -> Option<String> {
+        let cost = cost.max(1);
+
+        let chosen = eligible
+            .iter()
+            .filter(|miner| self.queues.contains_key(&miner.id))
+            .min_by(|a, b| {
+                let finish_a = (self.backlog(&a.id) + cost) as f64 / self.power(&a.id) as f64;
+                let finish_b = (self.backlog(&b.id) + cost) as f64 / self.power(&b.id) as f64;
+                finish_a.partial_cmp(&finish_b).unwrap_or(std::cmp::Ordering::Equal)
+            })?
+            .id
+            .clone();
+
+        let queued = QueuedTask {
+            task,
+            cost,
+            eligible_miners: eligible.iter().map(|miner| miner.id.clone()).collect(),
+        };
+        self.queues.get_mut(&chosen)?.push_back(queued);
+        Some(chosen)
+    }
+
+    /// Next task for `miner_id`: its own queue first, otherwise stolen work
+    pub fn next_task(&mut self, miner_id: &str) -> Option<MiningTask> {
+        loop {
+            let queued = self.queues.get_mut(miner_id)?.pop_front();
+            match queued {
+                Some(queued) if queued.task.is_expired() => continue,
+                Some(queued) => return Some(queued.task),
+                None => break,
+            }
+        }
+
+        self.steal(miner_id)
+    }
+
+    /// Take one task from the back of the most loaded queue the thief can help with
+    pub fn steal(&mut self, thief_id: &str) -> Option<MiningTask> {
+        let thief_power = *self.compute_power.get(thief_id)? as f64;
+        let mut best: Option<(String, usize, f64)> = None; // (victim, index, victim wait)
+
+        for (victim_id, queue) in &self.queues {
+            if victim_id == thief_id || queue.is_empty() {
+                continue;
+            }
+
+            let victim_wait = self.backlog(victim_id) as f64 / self.power(victim_id) as f64;
+            if best.as_ref().map(|(_, _, wait)| victim_wait <= *wait).unwrap_or(false) {
+                continue;
+            }
+
+            // Steal from the back: that is the work the owner would reach last
+            let candidate = queue
+                .iter()
+                .enumerate()
+                .rev()
+                .find(|(_, queued)| queued.eligible_miners.contains(thief_id) && !queued.task.is_expired());
+
+            if let Some((index, queued)) = candidate {
+                let thief_finish = queued.cost as f64 / thief_power;
+                if thief_finish < victim_wait {
+                    best = Some((victim_id.clone(), index, victim_wait));
+                }
+            }
+        }
+
+        let (victim_id, index, _) = best?;
+        let queued = self.queues.get_mut(&victim_id)?.remove(index)?;
+        self.steals += 1;
+        Some(queued.task)
+    }
+
+    /// Remove a task from whichever queue holds it
+    pub fn remove_task(&mut self, task_id: &str) -> Option<MiningTask> {
+        for queue in self.queues.values_mut() {
+            if let Some(index) = queue.iter().position(|queued| queued.task.id == task_id) {
+                return queue.remove(index).map(|queued| queued.task);
+            }
+        }
+        None
+    }
+
+    /// Queued work units for a miner
+    pub fn backlog(&self, miner_id: &str) -> u64 {
+        self.queues
+            .get(miner_id)
+            .map(|queue| queue.iter().map(|queued| queued.cost).sum())
+            .unwrap_or(0)
+    }
+
+    /// Estimated time until a miner drains its queue, in work units per compute power
+    pub fn estimated_wait(&self, miner_id: &str) -> f64 {
+        self.backlog(miner_id) as f64 / self.power(miner_id) as f64
+    }
+
+    pub fn queue_len(&self, miner_id: &str) -> usize {
+        self.queues.get(miner_id).map(|queue| queue.len()).unwrap_or(0)
+    }
+
+    pub fn total_queued(&self) -> usize {
+        self.queues.values().map(|queue| queue.len()).sum()
+    }
+
+    pub fn queued_tasks(&self) -> impl Iterator<Item = &MiningTask> {
+        self.queues.values().flat_map(|queue| queue.iter().map(|queued| &queued.task))
+    }
+
+    pub fn clear(&mut self) {
+        self.queues.clear();
+        self.compute_power.clear();
+    }
+
+    fn power(&self, miner_id: &str) -> u64 {
+        self.compute_power.get(miner_id).copied().unwrap_or(1)
+    }
+}
@@ -1,10 +1,22 @@
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
-use crate::tensor::Tensor;
-use crate::operations::{TensorOp, MatrixMultiply, Convolution, ActivationFunction, VectorOp};
+use crate::tensor::{Tensor, SharedTensorStore, ChunkStore, ShardRef};
+use crate::mining::verification::VerificationPolicy;
+use crate::operations::{ResultCache, TensorOp, MatrixMultiply, Convolution, ActivationFunction, VectorOp};
 use tribechain_core::{TribeResult, TribeError};
 
+/// How many miners work on a task and how their outputs are combined
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub enum MiningTaskKind {
+    /// One miner computes the output, which is verified by recomputation
+    #[default]
+    Single,
+    /// `participants` miners train on local shards and submit weight deltas,
+    /// which are combined with federated averaging
+    Aggregate { participants: usize, outlier_threshold: f32 },
+}
+
 /// Mining task for tensor operations
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MiningTask {
@@ -18,6 +30,14 @@ pub struct MiningTask {
     pub created_at: DateTime<Utc>,
     pub requester: String,
     pub nonce_range: (u64, u64), // Range for mining nonce
+    #[serde(default)]
+    pub input_refs: Vec<String>, // Hashes of inputs held in a SharedTensorStore
+    #[serde(default)]
+    pub verification: VerificationPolicy,
+    #[serde(default)]
+    pub kind: MiningTaskKind,
+    #[serde(default)]
+    pub input_shards: Vec<ShardRef>, // Chunked inputs fetched from peers, after `input_tensors`
 }
 
 impl MiningTask {
@@ -40,6 +60,10 @@ impl MiningTask {
             created_at: Utc::now(),
             requester,
             nonce_range: (0, u64::MAX),
+            input_refs: Vec::new(),
+            verification: VerificationPolicy::default(),
+            kind: MiningTaskKind::Single,
+            input_shards: Vec::new(),
         }
     }
 
@@ -53,6 +77,25 @@ impl MiningTask {
         self
     }
 
+    pub fn with_verification_policy(mut self, policy: VerificationPolicy) -> Self {
+        self.verification = policy;
+        self
+    }
+
+    /// Collect weight deltas from `participants` miners instead of one output
+    pub fn with_aggregation(mut self, participants: usize, outlier_threshold: f32) -> Self {
+        self.kind = MiningTaskKind::Aggregate { participants, outlier_threshold };
+        self
+    }
+
+    /// Miners that must submit before the task completes
+    pub fn participants(&self) -> usize {
+        match self.kind {
+            MiningTaskKind::Single => 1,
+            MiningTaskKind::Aggregate { participants, .. } => participants.max(1),
+        }
+    }
+
     /// Calculate task hash for mining
     pub fn calculate_hash(&self, nonce: u64) -> String {
         use sha2::{Digest, Sha256};
@@ -74,10 +117,77 @@ impl MiningTask {
         for tensor in &self.input_tensors {
             hasher.update(tensor.calculate_hash().as_bytes());
         }
+
+        // Shared inputs are already addressed by their tensor hash
+        for tensor_hash in &self.input_refs {
+            hasher.update(tensor_hash.as_bytes());
+        }
+
+        for chunk_hash in self.input_shards.iter().flat_map(ShardRef::chunk_hashes) {
+            hasher.update(chunk_hash.as_bytes());
+        }
         
         hex::encode(hasher.finalize())
     }
 
+    /// Move input tensors into `store`, keeping only their hashes on the task
+    pub fn share_inputs(&mut self, store: &mut SharedTensorStore) {
+        for tensor in self.input_tensors.drain(..) {
+            self.input_refs.push(store.intern(tensor));
+        }
+    }
+
+    /// Copy of this task with shared inputs materialized from `store`
+    pub fn with_resolved_inputs(&self, store: &SharedTensorStore) -> TribeResult<Self> {
+        let mut resolved = self.clone();
+        for tensor_hash in resolved.input_refs.drain(..) {
+            let tensor = store.get(&tensor_hash).ok_or_else(|| {
+                TribeError::AI3(format!("Shared tensor {} not found", tensor_hash))
+            })?;
+            resolved.input_tensors.push((*tensor).clone());
+        }
+        Ok(resolved)
+    }
+
+    /// Move input tensors into chunks in `store`, keeping only their manifests on the task
+    pub fn chunk_inputs(&mut self, store: &mut ChunkStore, chunk_elements: usize) -> TribeResult<()> {
+        for tensor in self.input_tensors.drain(..) {
+            self.input_shards.push(ShardRef::whole(store.split(&tensor, chunk_elements)?));
+        }
+        Ok(())
+    }
+
+    /// Split a task over chunked inputs into one subtask per `chunks_per_subtask` chunks of
+    /// its first input; other inputs are shared whole. Only suits row-wise operations.
+    pub fn split_by_chunks(&self, chunks_per_subtask: usize) -> TribeResult<Vec<MiningTask>> {
+        let first = self.input_shards.first()
+            .ok_or_else(|| TribeError::AI3("Task has no chunked inputs".to_string()))?;
+        let total = first.chunks().len();
+        let step = chunks_per_subtask.max(1);
+
+        Ok((0..total).step_by(step).map(|start| {
+            let mut subtask = self.clone();
+            subtask.id = Uuid::new_v4().to_string();
+            subtask.expected_output_shape = None;
+            subtask.input_shards[0] = ShardRef::range(first.manifest.clone(), start, (start + step).min(total));
+            subtask
+        }).collect())
+    }
+
+    /// Chunk hashes a miner has to fetch before running this task
+    pub fn required_chunks(&self) -> Vec<String> {
+        self.input_shards.iter().flat_map(ShardRef::chunk_hashes).collect()
+    }
+
+    /// Copy of this task with chunked inputs reassembled from `store`
+    pub fn with_fetched_inputs(&self, store: &ChunkStore) -> TribeResult<Self> {
+        let mut fetched = self.clone();
+        for shard in fetched.input_shards.drain(..) {
+            fetched.input_tensors.push(store.assemble(&shard)?);
+        }
+        Ok(fetched)
+    }
+
     /// Check if hash meets difficulty target
     pub fn meets_difficulty(&self, hash: &str) -> bool {
         let leading_zeros = hash.chars().take_while(|&c| c == '0').count();
@@ -86,7 +196,12 @@ impl MiningTask {
 
     /// Get operation instance
     pub fn get_operation(&self) -> TribeResult<Box<dyn TensorOp>> {
-        match self.operation_type.as_str() {
+        Self::operation_for(&self.operation_type)
+    }
+
+    /// Look up the tensor operation implementing `operation_type`
+    pub fn operation_for(operation_type: &str) -> TribeResult<Box<dyn TensorOp>> {
+        match operation_type {
             "matrix_multiply" => Ok(Box::new(MatrixMultiply::new())),
             "convolution" => Ok(Box::new(Convolution::new(3))), // Default kernel size
             "relu" => Ok(Box::new(ActivationFunction::relu())),
@@ -96,14 +211,36 @@ impl MiningTask {
             "dot_product" => Ok(Box::new(VectorOp::dot_product())),
             "normalize" => Ok(Box::new(VectorOp::normalize())),
             "vector_add" => Ok(Box::new(VectorOp::add())),
-            _ => Err(TribeError::InvalidOperation(format!("Unknown operation type: {}", self.operation_type))),
+            _ => Err(TribeError::AI3(format!("Unknown operation type: {}", operation_type))),
         }
     }
 
-    /// Execute the tensor operation
+    /// Result cache key: operation name plus the content hash of every input
+    pub fn result_cache_key(&self) -> String {
+        let input_hashes: Vec<String> = self.input_tensors.iter().map(|t| t.calculate_hash()).collect();
+        let chunk_hashes = self.required_chunks();
+        ResultCache::key(
+            &self.operation_type,
+            input_hashes.iter()
+                .chain(self.input_refs.iter())
+                .chain(chunk_hashes.iter())
+                .map(String::as_str),
+        )
+    }
+
+    /// Execute the tensor operation, reusing a cached output for identical inputs
     pub fn execute_operation(&self) -> TribeResult<Tensor> {
+        let key = self.result_cache_key();
+        if let Some(output) = ResultCache::global().lock().ok().and_then(|mut cache| cache.get(&key)) {
+            return Ok(output);
+        }
+
         let operation = self.get_operation()?;
-        operation.execute(&self.input_tensors)
+        let output = operation.execute(&self.input_tensors)?;
+        if let Ok(mut cache) = ResultCache::global().lock() {
+            cache.insert(key, output.clone());
+        }
+        Ok(output)
     }
 
     /// Check if task is expired
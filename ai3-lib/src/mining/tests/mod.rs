@@ -1,7 +1,18 @@
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests {
     use super::super::{tasks::MiningTask, miners::AI3Miner, distributors::TaskDistributor};
-    use crate::tensor::{Tensor, TensorShape};
+    use super::super::checkpoints::CheckpointStore;
+    use super::super::scheduler::WorkStealingScheduler;
+    use super::super::verification::{VerificationPolicy, verification_challenge};
+    use super::super::benchmark::{BenchmarkReport, BENCHMARK_OPERATIONS};
+    use super::super::miners::MinerCapabilities;
+    use super::super::marketplace::TaskMarketplace;
+    use super::super::results::MiningResult;
+    use super::super::proofs::{ComputationProof, DEFAULT_PROOF_ROUNDS, DEFAULT_PROOF_SAMPLES};
+    use super::super::benchmark::OperationBenchmark;
+    use std::time::Duration;
+    use crate::tensor::{Tensor, TensorShape, SharedTensorStore, ChunkStore};
 
     #[test]
     fn test_mining_task_creation() {
@@ -70,4 +81,316 @@ mod tests {
         let invalid_hash = "000abcdef123456789";
         assert!(!task.meets_difficulty(invalid_hash));
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_checkpoint_resume() {
+        let dir = std::env::temp_dir().join(format!("ai3_checkpoints_{}", uuid::Uuid::new_v4()));
+        let store = CheckpointStore::new(&dir).unwrap();
+
+        let input_tensor = Tensor::vector(vec![1.0, -2.0, 3.0]);
+        // Unreachable difficulty so the miner keeps searching
+        let task = MiningTask::new(
+            "relu".to_string(),
+            vec![input_tensor],
+            64,
+            100,
+            60,
+            "test_requester".to_string(),
+        );
+        let task_id = task.id.clone();
+
+        let mut miner = AI3Miner::new("test_miner".to_string(), "127.0.0.1:8080".to_string(), false)
+            .with_checkpointing(store.clone(), Duration::from_secs(0));
+        miner.assign_task(task).unwrap();
+        for _ in 0..5 {
+            assert!(miner.mine_step().unwrap().is_none());
+        }
+
+        let checkpoint = store.load(&task_id).unwrap().unwrap();
+        assert_eq!(checkpoint.next_nonce, 5);
+        assert_eq!(checkpoint.nonces_tried, 5);
+        assert!(checkpoint.partial_output.is_some());
+
+        let mut resumed = AI3Miner::new("other_miner".to_string(), "127.0.0.1:8081".to_string(), false);
+        resumed.resume_from_checkpoint(checkpoint).unwrap();
+        assert_eq!(resumed.next_nonce, Some(5));
+        assert!(resumed.partial_output.is_some());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_shared_inputs_roundtrip() {
+        let mut store = SharedTensorStore::new();
+        let shared_input = Tensor::vector(vec![1.0, 2.0, 3.0]);
+
+        let mut task_a = MiningTask::new(
+            "relu".to_string(),
+            vec![shared_input.clone()],
+            1,
+            100,
+            60,
+            "test_requester".to_string(),
+        );
+        let mut task_b = task_a.clone();
+        task_b.id = "other_task".to_string();
+        let hash_before = task_a.calculate_hash(7);
+
+        task_a.share_inputs(&mut store);
+        task_b.share_inputs(&mut store);
+        assert_eq!(store.len(), 1);
+        assert!(task_a.input_tensors.is_empty());
+        assert_eq!(task_a.calculate_hash(7), hash_before);
+
+        let resolved = task_a.with_resolved_inputs(&store).unwrap();
+        assert_eq!(resolved.input_tensors.len(), 1);
+        assert!(resolved.input_refs.is_empty());
+        assert_eq!(resolved.calculate_hash(7), hash_before);
+    }
+
+    #[test]
+    fn test_fast_miner_steals_from_loaded_esp() {
+        let esp = AI3Miner::new("esp".to_string(), "127.0.0.1:8081".to_string(), true);
+        let cpu = AI3Miner::new("cpu".to_string(), "127.0.0.1:8082".to_string(), false);
+        let mut scheduler = WorkStealingScheduler::new();
+        scheduler.register_miner(&esp);
+
+        // Work piles up on the ESP before the CPU miner joins
+        for i in 0..4 {
+            let mut task = MiningTask::new(
+                "relu".to_string(),
+                vec![Tensor::vector(vec![1.0, 2.0, 3.0])],
+                1,
+                100,
+                60,
+                "test_requester".to_string(),
+            );
+            task.id = format!("task_{}", i);
+            assert_eq!(scheduler.enqueue(task, 50, &[&esp, &cpu]).as_deref(), Some("esp"));
+        }
+
+        scheduler.register_miner(&cpu);
+        let stolen = scheduler.next_task("cpu").unwrap();
+        assert_eq!(stolen.id, "task_3");
+        assert_eq!(scheduler.steals, 1);
+        assert_eq!(scheduler.queue_len("esp"), 3);
+
+        // The ESP still drains its own queue front to back
+        assert_eq!(scheduler.next_task("esp").unwrap().id, "task_0");
+    }
+
+    #[test]
+    fn test_verification_policies() {
+        let a = Tensor::matrix(vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0], 2, 3).unwrap();
+        let b = Tensor::matrix(vec![7.0, 8.0, 9.0, 10.0, 11.0, 12.0], 3, 2).unwrap();
+        let task = MiningTask::new(
+            "matrix_multiply".to_string(),
+            vec![a, b],
+            1,
+            100,
+            60,
+            "test_requester".to_string(),
+        );
+        let output = task.execute_operation().unwrap();
+        let mut tampered = output.clone();
+        tampered.data = crate::tensor::TensorData::F32(vec![58.0, 64.0, 139.0, 155.0]);
+
+        let policies = vec![
+            VerificationPolicy::Exact,
+            VerificationPolicy::default(),
+            VerificationPolicy::RoundedHash { decimals: 3 },
+            VerificationPolicy::Freivalds { rounds: 20, tolerance: 1e-5 },
+        ];
+        let challenge = verification_challenge("commitment", "later-block");
+        for policy in policies {
+            assert!(policy.verify(&task, &output, Some(&challenge)).unwrap());
+            assert!(!policy.verify(&task, &tampered, Some(&challenge)).unwrap(), "{:?} accepted bad output", policy);
+        }
+
+        let spot_check = VerificationPolicy::SpotCheck { samples: 4, tolerance: 1e-5 };
+        assert!(spot_check.verify(&task, &output, Some(&challenge)).unwrap());
+
+        // Without a challenge everything is recomputed, so the bad element is always found
+        let single = VerificationPolicy::SpotCheck { samples: 1, tolerance: 1e-5 };
+        assert!(!single.verify(&task, &tampered, None).unwrap());
+
+        // A single sample may miss it, but every validator holding the challenge samples
+        // the same element, and which one follows the later block rather than the output
+        let verdict = single.verify(&task, &tampered, Some(&challenge)).unwrap();
+        assert!((0..20).all(|_| single.verify(&task, &tampered, Some(&challenge)).unwrap() == verdict));
+        let verdicts: std::collections::HashSet<bool> = (0..32)
+            .map(|i| {
+                let challenge = verification_challenge("commitment", &format!("block-{}", i));
+                single.verify(&task, &tampered, Some(&challenge)).unwrap()
+            })
+            .collect();
+        assert_eq!(verdicts.len(), 2);
+    }
+
+    #[test]
+    fn test_benchmark_seeds_capabilities() {
+        let report = BenchmarkReport::run(Duration::from_millis(5)).unwrap();
+        assert_eq!(report.operations.len(), BENCHMARK_OPERATIONS.len());
+        assert!(report.pow_hashes_per_sec > 0.0);
+        assert!(report.operations.values().all(|bench| bench.ops_per_sec > 0.0 && bench.est_memory_bytes > 0));
+
+        let decoded: BenchmarkReport = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+        let capabilities = MinerCapabilities::from_benchmark(&decoded, true);
+        assert_eq!(capabilities.compute_power, report.compute_power());
+        assert!(capabilities.supported_operations.contains(&"convolution".to_string()));
+
+        let miner = AI3Miner::new("esp".to_string(), "addr".to_string(), true).with_capabilities(capabilities);
+        assert_eq!(miner.capabilities.compute_power, report.compute_power());
+    }
+
+    #[test]
+    fn test_marketplace_awards_lowest_credible_bid() {
+        let task = MiningTask::new(
+            "relu".to_string(),
+            vec![Tensor::vector(vec![1.0; 64])],
+            1,
+            0,
+            60,
+            "requester".to_string(),
+        );
+        let task_id = task.id.clone();
+        let mut market = TaskMarketplace::new();
+        market.post_task(task, 500).unwrap();
+
+        let fast = AI3Miner::new("fast".to_string(), "fast_addr".to_string(), false);
+        let cheap = AI3Miner::new("cheap".to_string(), "cheap_addr".to_string(), false);
+        let mut flaky = AI3Miner::new("flaky".to_string(), "flaky_addr".to_string(), false);
+        flaky.stats.tasks_completed = 10;
+        flaky.stats.success_rate = 0.2;
+        let slow = AI3Miner::new("slow".to_string(), "slow_addr".to_string(), true);
+
+        // A benchmark showing the miner can't finish in time rules its bid out
+        let mut report = BenchmarkReport::run(Duration::from_millis(1)).unwrap();
+        report.operations.insert("relu".to_string(), OperationBenchmark {
+            ops_per_sec: 0.001,
+            est_memory_bytes: 256,
+            complexity_score: 1,
+        });
+        market.register_benchmark("slow".to_string(), report);
+
+        market.submit_bid(&task_id, &fast, 300).unwrap();
+        market.submit_bid(&task_id, &cheap, 250).unwrap();
+        assert!(market.submit_bid(&task_id, &cheap, 600).is_err()); // Above max price
+        assert!(market.submit_bid(&task_id, &flaky, 100).is_err());
+        assert!(market.submit_bid(&task_id, &slow, 50).is_err());
+        assert_eq!(market.best_bid(&task_id).unwrap().miner_id, "cheap");
+
+        let mut distributor = TaskDistributor::new();
+        let awarded = distributor.award_from_market(&mut market, &task_id).unwrap();
+        assert_eq!((awarded.miner_id.as_str(), awarded.price, awarded.task.reward), ("cheap", 250, 250));
+        assert_eq!(distributor.active_tasks[&task_id].1, "cheap");
+        assert!(market.get_open_listings().is_empty());
+        assert!(distributor.award_from_market(&mut market, &task_id).is_err());
+    }
+
+    #[test]
+    fn test_federated_averaging_rejects_outliers() {
+        let task = MiningTask::new(
+            "vector_add".to_string(),
+            vec![Tensor::vector(vec![0.0; 4]), Tensor::vector(vec![0.0; 4])],
+            0,
+            100,
+            60,
+            "requester".to_string(),
+        ).with_aggregation(4, 3.0);
+        let task_id = task.id.clone();
+        let miners: Vec<AI3Miner> = (0..5)
+            .map(|i| AI3Miner::new(format!("miner{}", i), format!("addr{}", i), false))
+            .collect();
+
+        let mut distributor = TaskDistributor::new();
+        assert!(distributor.distribute(task.clone(), &miners[..3]).is_err());
+        let assigned = distributor.distribute(task.clone(), &miners).unwrap();
+        assert_eq!(assigned.len(), 4);
+
+        let delta = |miner: &str, values: Vec<f32>, samples: u64| {
+            MiningResult::new(task_id.clone(), miner.to_string(), 0, task.calculate_hash(0), Tensor::vector(values), 10)
+                .with_sample_count(samples)
+        };
+        distributor.submit_result(delta("miner0", vec![1.0, 1.0, 1.0, 1.0], 100)).unwrap();
+        assert!(distributor.submit_result(delta("miner0", vec![1.0; 4], 100)).is_err()); // Duplicate
+        assert!(distributor.submit_result(delta("miner4", vec![1.0; 4], 100)).is_err()); // Not assigned
+        distributor.submit_result(delta("miner1", vec![1.2, 1.2, 1.2, 1.2], 300)).unwrap();
+        distributor.submit_result(delta("miner2", vec![0.9, 0.9, 0.9, 0.9], 100)).unwrap();
+        assert!(distributor.get_aggregate_outcome(&task_id).is_none());
+        distributor.submit_result(delta("miner3", vec![500.0, -500.0, 500.0, -500.0], 1000)).unwrap();
+
+        let outcome = distributor.get_aggregate_outcome(&task_id).unwrap();
+        assert_eq!(outcome.rejected, vec!["miner3".to_string()]);
+        assert_eq!(outcome.total_samples, 500);
+        let averaged = outcome.output_tensor.data.as_f32_vec().unwrap();
+        assert!(averaged.iter().all(|value| (value - 1.1).abs() < 1e-5));
+        assert!(distributor.aggregate_rounds.is_empty());
+    }
+
+    #[test]
+    fn test_chunked_inputs_fetch_only_their_shard() {
+        let input = Tensor::matrix((0..32).map(|i| i as f32 - 16.0).collect(), 8, 4).unwrap();
+        let mut task = MiningTask::new("relu".to_string(), vec![input.clone()], 0, 100, 60, "requester".to_string());
+
+        // Two rows per chunk, two chunks per subtask
+        let mut requester = ChunkStore::new();
+        task.chunk_inputs(&mut requester, 8).unwrap();
+        assert!(task.input_tensors.is_empty());
+        assert_eq!(task.input_shards[0].manifest.chunks.len(), 4);
+        assert_eq!(requester.assemble(&task.input_shards[0]).unwrap().calculate_hash(), input.calculate_hash());
+
+        let subtasks = task.split_by_chunks(2).unwrap();
+        assert_eq!(subtasks.len(), 2);
+        let second = &subtasks[1];
+        let needed = second.required_chunks();
+        assert_eq!(needed.len(), 2);
+
+        // The miner fetches only its shard's chunks and rejects corrupted ones
+        let mut miner = ChunkStore::new();
+        assert!(second.with_fetched_inputs(&miner).is_err());
+        assert!(miner.insert(needed[0].clone(), vec![0u8; 32]).is_err());
+        for hash in &needed {
+            miner.insert(hash.clone(), requester.get(hash).unwrap().clone()).unwrap();
+        }
+        assert!(miner.missing(&second.input_shards[0]).is_empty());
+        assert_eq!(miner.len(), 2);
+
+        let fetched = second.with_fetched_inputs(&miner).unwrap();
+        assert_eq!(fetched.input_tensors[0].shape.dimensions, vec![4, 4]);
+        let output = fetched.execute_operation().unwrap().data.as_f32_vec().unwrap();
+        assert_eq!(output, (16..32).map(|i| (i as f32 - 16.0).max(0.0)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_computation_proofs() {
+        let a = Tensor::random(TensorShape::matrix(12, 8));
+        let b = Tensor::random(TensorShape::matrix(8, 10));
+        let task = MiningTask::new("matrix_multiply".to_string(), vec![a, b], 0, 100, 60, "requester".to_string());
+        let output = task.execute_operation().unwrap();
+
+        let proof = ComputationProof::generate(&task, &output, DEFAULT_PROOF_ROUNDS).unwrap();
+        let decoded = ComputationProof::from_bytes(&proof.to_bytes().unwrap()).unwrap();
+        assert!(decoded.verify(&task, &output, DEFAULT_PROOF_SAMPLES).unwrap());
+
+        // A wrong product fails, even with a proof generated for it
+        let mut wrong = output.clone();
+        if let crate::tensor::TensorData::F32(values) = &mut wrong.data {
+            values[5] += 10.0;
+        }
+        assert!(!proof.verify(&task, &wrong, DEFAULT_PROOF_SAMPLES).unwrap());
+        let forged = ComputationProof::generate(&task, &wrong, DEFAULT_PROOF_ROUNDS).unwrap();
+        assert!(!forged.verify(&task, &wrong, 12).unwrap());
+
+        let relu = MiningTask::new("relu".to_string(), vec![Tensor::vector(vec![-1.0, 2.0, -3.0, 4.0])], 0, 100, 60, "requester".to_string());
+        let output = relu.execute_operation().unwrap();
+        let proof = ComputationProof::generate(&relu, &output, DEFAULT_PROOF_ROUNDS).unwrap();
+        assert!(proof.verify(&relu, &output, 8).unwrap());
+        let tampered = Tensor::vector(vec![-1.0, 2.0, -3.0, 4.0]);
+        let forged = ComputationProof::generate(&relu, &tampered, DEFAULT_PROOF_ROUNDS).unwrap();
+        assert!(!forged.verify(&relu, &tampered, 32).unwrap());
+
+        let softmax = MiningTask::new("softmax".to_string(), vec![Tensor::vector(vec![1.0])], 0, 100, 60, "requester".to_string());
+        assert!(ComputationProof::generate(&softmax, &output, DEFAULT_PROOF_ROUNDS).is_err());
+    }
+}
@@ -0,0 +1,195 @@
+use serde::{Deserialize, Serialize};
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use sha2::{Digest, Sha256};
+use crate::tensor::Tensor;
+use crate::mining::tasks::MiningTask;
+use tribechain_core::{TribeResult, TribeError};
+
+/// How a validator decides that a miner's output tensor is correct
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum VerificationPolicy {
+    /// Bit-for-bit equality with the recomputed output
+    Exact,
+    /// `|a - b| <= tolerance * max(1, |a|, |b|)` for every element
+    RelativeTolerance { tolerance: f32 },
+    /// Recompute only `samples` elements, sampled from the verification challenge
+    SpotCheck { samples: usize, tolerance: f32 },
+    /// Compare hashes of both outputs rounded to `decimals` places
+    RoundedHash { decimals: u32 },
+    /// Probabilistic `A·(B·r) == C·r` check for matrix multiply, relative tolerance otherwise
+    Freivalds { rounds: usize, tolerance: f32 },
+}
+
+impl Default for VerificationPolicy {
+    fn default() -> Self {
+        VerificationPolicy::RelativeTolerance { tolerance: 1e-6 }
+    }
+}
+
+/// Seed for the sampled policies. It is only known once the miner is bound to its
+/// output: the commitment it published, mixed with the hash of a later block
+pub fn verification_challenge(commitment_hash: &str, later_block_hash: &str) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(commitment_hash.as_bytes());
+    hasher.update(later_block_hash.as_bytes());
+    hasher.finalize().into()
+}
+
+impl VerificationPolicy {
+    /// Check `output` against `task` without trusting the miner's computation.
+    /// Sampled policies draw from `challenge`; without one they recompute everything
+    pub fn verify(&self, task: &MiningTask, output: &Tensor, challenge: Option<&[u8; 32]>) -> TribeResult<bool> {
+        if let Some(shape) = &task.expected_output_shape {
+            if &output.shape.dimensions != shape {
+                return Ok(false);
+            }
+        }
+
+        match self {
+            VerificationPolicy::Exact => {
+                let expected = task.execute_operation()?;
+                compare_all(&expected, output, |a, b| a.to_bits() == b.to_bits())
+            }
+            VerificationPolicy::RelativeTolerance { tolerance } => {
+                let expected = task.execute_operation()?;
+                compare_all(&expected, output, |a, b| within_tolerance(a, b, *tolerance))
+            }
+            VerificationPolicy::SpotCheck { samples, tolerance } => match challenge {
+                Some(challenge) => spot_check(task, output, *samples, *tolerance, challenge),
+                None => VerificationPolicy::RelativeTolerance { tolerance: *tolerance }.verify(task, output, None),
+            },
+            VerificationPolicy::RoundedHash { decimals } => {
+                let expected = task.execute_operation()?;
+                if expected.shape != output.shape {
+                    return Ok(false);
+                }
+                Ok(rounded_hash(&expected, *decimals)? == rounded_hash(output, *decimals)?)
+            }
+            VerificationPolicy::Freivalds { rounds, tolerance } => match challenge {
+                Some(challenge) if task.operation_type == "matrix_multiply" && task.input_tensors.len() == 2 => {
+                    freivalds_check(&task.input_tensors[0], &task.input_tensors[1], output, *rounds, *tolerance, challenge)
+                }
+                _ => VerificationPolicy::RelativeTolerance { tolerance: *tolerance }.verify(task, output, None),
+            },
+        }
+    }
+}
+
+/// Freivalds' check that `c == a·b` in O(n²) per round.
+/// A wrong product survives each round with probability at most 1/2. The random
+/// vectors come from `challenge`, so every validator reaches the same verdict.
+pub fn freivalds_check(a: &Tensor, b: &Tensor, c: &Tensor, rounds: usize, tolerance: f32, challenge: &[u8; 32]) -> TribeResult<bool> {
+    if a.shape.rank() != 2 || b.shape.rank() != 2 || c.shape.rank() != 2 {
+        return Err(TribeError::AI3("Freivalds check requires 2D matrices".to_string()));
+    }
+
+    let (n, m) = (a.shape.dimensions[0], a.shape.dimensions[1]);
+    let p = b.shape.dimensions[1];
+    if b.shape.dimensions[0] != m || c.shape.dimensions != [n, p] {
+        return Ok(false);
+    }
+
+    let mut rng = StdRng::from_seed(*challenge);
+    let a = a.data.as_f32_slice()?;
+    let b = b.data.as_f32_slice()?;
+    let c = c.data.as_f32_slice()?;
+
+    for _ in 0..rounds.max(1) {
+        let r: Vec<f64> = (0..p).map(|_| if rng.gen::<bool>() { 1.0 } else { 0.0 }).collect();
+
+        let br: Vec<f64> = (0..m)
+            .map(|k| (0..p).map(|j| b[k * p + j] as f64 * r[j]).sum())
+            .collect();
+
+        for i in 0..n {
+            let abr: f64 = (0..m).map(|k| a[i * m + k] as f64 * br[k]).sum();
+            let cr: f64 = (0..p).map(|j| c[i * p + j] as f64 * r[j]).sum();
+            if !within_tolerance(abr as f32, cr as f32, tolerance) {
+                return Ok(false);
+            }
+        }
+    }
+
+    Ok(true)
+}
+
+fn within_tolerance(a: f32, b: f32, tolerance: f32) -> bool {
+    let scale = 1.0f32.max(a.abs()).max(b.abs());
+    (a - b).abs() <= tolerance * scale
+}
+
+fn compare_all(expected: &Tensor, actual: &Tensor, eq: impl Fn(f32, f32) -> bool) -> TribeResult<bool> {
+    if expected.shape != actual.shape {
+        return Ok(false);
+    }
+
+    let expected = expected.data.as_f32_slice()?;
+    let actual = actual.data.as_f32_slice()?;
+    Ok(expected.len() == actual.len() && expected.iter().zip(actual).all(|(&a, &b)| eq(a, b)))
+}
+
+fn rounded_hash(tensor: &Tensor, decimals: u32) -> TribeResult<String> {
+    let scale = 10f64.powi(decimals as i32);
+    let mut hasher = Sha256::new();
+    for &value in tensor.data.as_f32_slice()? {
+        let rounded = (value as f64 * scale).round() as i64;
+        hasher.update(rounded.to_le_bytes());
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+fn spot_check(task: &MiningTask, output: &Tensor, samples: usize, tolerance: f32, challenge: &[u8; 32]) -> TribeResult<bool> {
+    let actual = output.data.as_f32_slice()?;
+    if actual.is_empty() {
+        return Ok(false);
+    }
+
+    let mut rng = StdRng::from_seed(*challenge);
+    let indices: Vec<usize> = (0..samples.max(1)).map(|_| rng.gen_range(0..actual.len())).collect();
+
+    // Element-local operations can be recomputed one element at a time
+    let elementwise = matches!(task.operation_type.as_str(), "relu" | "sigmoid" | "tanh");
+    if task.operation_type == "matrix_multiply" && task.input_tensors.len() == 2 {
+        let (a, b) = (&task.input_tensors[0], &task.input_tensors[1]);
+        if a.shape.rank() != 2 || b.shape.rank() != 2 {
+            return Ok(false);
+        }
+        let (n, m) = (a.shape.dimensions[0], a.shape.dimensions[1]);
+        let p = b.shape.dimensions[1];
+        if actual.len() != n * p {
+            return Ok(false);
+        }
+
+        let (a, b) = (a.data.as_f32_slice()?, b.data.as_f32_slice()?);
+        for index in indices {
+            let (i, j) = (index / p, index % p);
+            let expected: f32 = (0..m).map(|k| a[i * m + k] * b[k * p + j]).sum();
+            if !within_tolerance(expected, actual[index], tolerance) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    } else if elementwise && task.input_tensors.len() == 1 {
+        let input = task.input_tensors[0].data.as_f32_slice()?;
+        if input.len() != actual.len() {
+            return Ok(false);
+        }
+
+        let operation = task.get_operation()?;
+        for index in indices {
+            let expected = operation.execute(&[Tensor::scalar(input[index])])?;
+            if !within_tolerance(expected.data.as_f32_slice()?[0], actual[index], tolerance) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    } else {
+        let expected = task.execute_operation()?;
+        if expected.shape != output.shape {
+            return Ok(false);
+        }
+        let expected = expected.data.as_f32_slice()?;
+        Ok(indices.into_iter().all(|index| within_tolerance(expected[index], actual[index], tolerance)))
+    }
+}
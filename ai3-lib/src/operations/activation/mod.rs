@@ -1,5 +1,5 @@
 use serde::{Deserialize, Serialize};
-use crate::tensor::{Tensor, TensorShape};
+use crate::tensor::Tensor;
 use crate::operations::TensorOp;
 use tribechain_core::{TribeResult, TribeError};
 
@@ -74,7 +74,7 @@ impl TensorOp for ActivationFunction {
 
     fn validate_inputs(&self, inputs: &[Tensor]) -> TribeResult<()> {
         if inputs.len() != 1 {
-            return Err(TribeError::InvalidOperation("Activation function requires exactly 1 input".to_string()));
+            return Err(TribeError::Tensor("Activation function requires exactly 1 input".to_string()));
         }
         Ok(())
     }
@@ -0,0 +1,154 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Mutex, OnceLock};
+use sha2::{Digest, Sha256};
+use crate::tensor::Tensor;
+
+const DEFAULT_MAX_ENTRIES: usize = 1024;
+const DEFAULT_MAX_BYTES: usize = 64 * 1024 * 1024; // 64MB
+
+static GLOBAL_CACHE: OnceLock<Mutex<ResultCache>> = OnceLock::new();
+
+#[derive(Debug, Clone)]
+struct CacheEntry {
+    output: Tensor,
+    size: usize,
+    last_used: u64,
+}
+
+/// Content-addressed LRU cache of operation outputs, keyed by operation name and input hashes
+#[derive(Debug, Clone)]
+pub struct ResultCache {
+    entries: HashMap<String, CacheEntry>,
+    recency: BTreeMap<u64, String>, // last_used tick -> key, oldest first
+    tick: u64,
+    current_bytes: usize,
+    pub max_entries: usize,
+    pub max_bytes: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl ResultCache {
+    pub fn new(max_entries: usize, max_bytes: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            tick: 0,
+            current_bytes: 0,
+            max_entries,
+            max_bytes,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Process-wide cache shared by task execution and validation
+    pub fn global() -> &'static Mutex<ResultCache> {
+        GLOBAL_CACHE.get_or_init(|| Mutex::new(ResultCache::default()))
+    }
+
+    /// Cache key for an operation applied to inputs with the given content hashes
+    pub fn key<'a>(operation: &str, input_hashes: impl IntoIterator<Item = &'a str>) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(operation.as_bytes());
+        for hash in input_hashes {
+            hasher.update(b":");
+            hasher.update(hash.as_bytes());
+        }
+        hex::encode(hasher.finalize())
+    }
+
+    pub fn get(&mut self, key: &str) -> Option<Tensor> {
+        let tick = self.next_tick();
+        match self.entries.get_mut(key) {
+            Some(entry) => {
+                self.recency.remove(&entry.last_used);
+                entry.last_used = tick;
+                self.recency.insert(tick, key.to_string());
+                self.hits += 1;
+                Some(entry.output.clone())
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    pub fn insert(&mut self, key: String, output: Tensor) {
+        let size = output.shape.total_elements() * 4;
+        if size > self.max_bytes || self.max_entries == 0 {
+            return; // Would evict everything else and still not fit
+        }
+
+        self.remove(&key);
+        while self.entries.len() >= self.max_entries || self.current_bytes + size > self.max_bytes {
+            if !self.evict_oldest() {
+                break;
+            }
+        }
+
+        let tick = self.next_tick();
+        self.recency.insert(tick, key.clone());
+        self.current_bytes += size;
+        self.entries.insert(key, CacheEntry { output, size, last_used: tick });
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<Tensor> {
+        let entry = self.entries.remove(key)?;
+        self.recency.remove(&entry.last_used);
+        self.current_bytes -= entry.size;
+        Some(entry.output)
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+        self.current_bytes = 0;
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn memory_usage(&self) -> usize {
+        self.current_bytes
+    }
+
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+
+    fn evict_oldest(&mut self) -> bool {
+        let oldest = self.recency.keys().next().copied();
+        match oldest.and_then(|tick| self.recency.remove(&tick)) {
+            Some(key) => {
+                if let Some(entry) = self.entries.remove(&key) {
+                    self.current_bytes -= entry.size;
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+}
+
+impl Default for ResultCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_ENTRIES, DEFAULT_MAX_BYTES)
+    }
+}
@@ -53,15 +53,15 @@ impl TensorOp for Convolution {
             let output_size = self.calculate_output_size(input_size);
             let mut output = vec![0.0; output_size];
 
-            for i in 0..output_size {
+            for (i, out) in output.iter_mut().enumerate() {
                 let mut sum = 0.0;
-                for j in 0..self.kernel_size {
+                for (j, weight) in kernel_data.iter().enumerate().take(self.kernel_size) {
                     let input_idx = i * self.stride + j * self.dilation;
                     if input_idx < input_size {
-                        sum += input_data[input_idx] * kernel_data[j];
+                        sum += input_data[input_idx] * weight;
                     }
                 }
-                output[i] = sum;
+                *out = sum;
             }
 
             return Tensor::from_vec(output, TensorShape::vector(output_size));
@@ -100,23 +100,23 @@ impl TensorOp for Convolution {
             return Tensor::from_vec(output, TensorShape::matrix(output_h, output_w));
         }
 
-        Err(TribeError::InvalidOperation("Unsupported tensor dimensions for convolution".to_string()))
+        Err(TribeError::Tensor("Unsupported tensor dimensions for convolution".to_string()))
     }
 
     fn validate_inputs(&self, inputs: &[Tensor]) -> TribeResult<()> {
         if inputs.len() != 2 {
-            return Err(TribeError::InvalidOperation("Convolution requires exactly 2 inputs (input and kernel)".to_string()));
+            return Err(TribeError::Tensor("Convolution requires exactly 2 inputs (input and kernel)".to_string()));
         }
 
         let input = &inputs[0];
         let kernel = &inputs[1];
 
         if input.shape.rank() != kernel.shape.rank() {
-            return Err(TribeError::InvalidOperation("Input and kernel must have same number of dimensions".to_string()));
+            return Err(TribeError::Tensor("Input and kernel must have same number of dimensions".to_string()));
         }
 
         if input.shape.rank() < 1 || input.shape.rank() > 2 {
-            return Err(TribeError::InvalidOperation("Convolution only supports 1D and 2D tensors".to_string()));
+            return Err(TribeError::Tensor("Convolution only supports 1D and 2D tensors".to_string()));
         }
 
         Ok(())
@@ -44,9 +44,9 @@ impl TensorOp for MatrixMultiply {
 
         // Convert to 2D arrays
         let a_2d = a_array.into_dimensionality::<ndarray::Ix2>()
-            .map_err(|e| TribeError::InvalidOperation(format!("Failed to convert tensor A to 2D: {}", e)))?;
+            .map_err(|e| TribeError::Tensor(format!("Failed to convert tensor A to 2D: {}", e)))?;
         let b_2d = b_array.into_dimensionality::<ndarray::Ix2>()
-            .map_err(|e| TribeError::InvalidOperation(format!("Failed to convert tensor B to 2D: {}", e)))?;
+            .map_err(|e| TribeError::Tensor(format!("Failed to convert tensor B to 2D: {}", e)))?;
 
         // Apply transpose if needed
         let a_final = if self.transpose_a { a_2d.t().to_owned() } else { a_2d };
@@ -64,21 +64,21 @@ impl TensorOp for MatrixMultiply {
 
     fn validate_inputs(&self, inputs: &[Tensor]) -> TribeResult<()> {
         if inputs.len() != 2 {
-            return Err(TribeError::InvalidOperation("Matrix multiply requires exactly 2 inputs".to_string()));
+            return Err(TribeError::Tensor("Matrix multiply requires exactly 2 inputs".to_string()));
         }
 
         let a = &inputs[0];
         let b = &inputs[1];
 
         if a.shape.rank() != 2 || b.shape.rank() != 2 {
-            return Err(TribeError::InvalidOperation("Both inputs must be 2D matrices".to_string()));
+            return Err(TribeError::Tensor("Both inputs must be 2D matrices".to_string()));
         }
 
         let a_cols = if self.transpose_a { a.shape.dimensions[0] } else { a.shape.dimensions[1] };
         let b_rows = if self.transpose_b { b.shape.dimensions[1] } else { b.shape.dimensions[0] };
 
         if a_cols != b_rows {
-            return Err(TribeError::InvalidOperation(
+            return Err(TribeError::Tensor(
                 format!("Matrix dimensions incompatible: {} vs {}", a_cols, b_rows)
             ));
         }
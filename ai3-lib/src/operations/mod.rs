@@ -5,6 +5,7 @@ pub mod matrix;
 pub mod convolution;
 pub mod activation;
 pub mod vector;
+pub mod cache;
 pub mod tests;
 
 /// Trait for tensor operations
@@ -19,4 +20,5 @@ pub trait TensorOp {
 pub use matrix::MatrixMultiply;
 pub use convolution::Convolution;
 pub use activation::{ActivationFunction, ActivationType};
-pub use vector::{VectorOp, VectorOpType}; 
\ No newline at end of file
+pub use vector::{VectorOp, VectorOpType};
+pub use cache::ResultCache; 
\ No newline at end of file
@@ -1,4 +1,5 @@
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests {
     use super::super::{
         TensorOp,
@@ -6,8 +7,9 @@ mod tests {
         activation::ActivationFunction,
         vector::VectorOp,
         convolution::Convolution,
+        cache::ResultCache,
     };
-    use crate::tensor::{Tensor, TensorShape};
+    use crate::tensor::Tensor;
 
     #[test]
     fn test_matrix_multiply() {
@@ -55,4 +57,27 @@ mod tests {
         let result_data = result.data.as_f32_vec().unwrap();
         assert_eq!(result_data.len(), 3); // Output size should be 3
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_result_cache_lru_eviction() {
+        let mut cache = ResultCache::new(2, 1024);
+        let key_a = ResultCache::key("relu", ["a"]);
+        let key_b = ResultCache::key("relu", ["b"]);
+        let key_c = ResultCache::key("relu", ["c"]);
+
+        cache.insert(key_a.clone(), Tensor::vector(vec![1.0]));
+        cache.insert(key_b.clone(), Tensor::vector(vec![2.0]));
+        assert!(cache.get(&key_a).is_some()); // a is now most recently used
+
+        cache.insert(key_c.clone(), Tensor::vector(vec![3.0]));
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&key_b).is_none());
+        assert!(cache.get(&key_a).is_some());
+        assert!(cache.get(&key_c).is_some());
+
+        // Entries larger than the byte limit are never cached
+        cache.insert(key_b.clone(), Tensor::vector(vec![0.0; 512]));
+        assert!(cache.get(&key_b).is_none());
+        assert_eq!(cache.memory_usage(), 8);
+    }
+}
@@ -58,7 +58,7 @@ impl TensorOp for VectorOp {
                 let magnitude: f32 = input_data.iter().map(|x| x * x).sum::<f32>().sqrt();
                 
                 if magnitude == 0.0 {
-                    return Err(TribeError::InvalidOperation("Cannot normalize zero vector".to_string()));
+                    return Err(TribeError::Tensor("Cannot normalize zero vector".to_string()));
                 }
                 
                 let normalized: Vec<f32> = input_data.iter().map(|x| x / magnitude).collect();
@@ -101,7 +101,7 @@ impl TensorOp for VectorOp {
             VectorOpType::CrossProduct => {
                 // 3D cross product only
                 if inputs[0].shape.total_elements() != 3 || inputs[1].shape.total_elements() != 3 {
-                    return Err(TribeError::InvalidOperation("Cross product requires 3D vectors".to_string()));
+                    return Err(TribeError::Tensor("Cross product requires 3D vectors".to_string()));
                 }
                 
                 let a = inputs[0].data.as_f32_vec()?;
@@ -122,17 +122,17 @@ impl TensorOp for VectorOp {
         match self.op_type {
             VectorOpType::Normalize => {
                 if inputs.len() != 1 {
-                    return Err(TribeError::InvalidOperation("Normalize requires exactly 1 input".to_string()));
+                    return Err(TribeError::Tensor("Normalize requires exactly 1 input".to_string()));
                 }
             }
             _ => {
                 if inputs.len() != 2 {
-                    return Err(TribeError::InvalidOperation("Binary vector operation requires exactly 2 inputs".to_string()));
+                    return Err(TribeError::Tensor("Binary vector operation requires exactly 2 inputs".to_string()));
                 }
                 
                 // Check compatible shapes for binary operations
                 if inputs[0].shape.total_elements() != inputs[1].shape.total_elements() {
-                    return Err(TribeError::InvalidOperation("Input tensors must have same number of elements".to_string()));
+                    return Err(TribeError::Tensor("Input tensors must have same number of elements".to_string()));
                 }
             }
         }
@@ -0,0 +1,180 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use crate::tensor::{Tensor, TensorShape};
+use tribechain_core::{TribeResult, TribeError};
+
+/// Elements per chunk when splitting tensors for distribution (256 KiB of f32)
+pub const DEFAULT_CHUNK_ELEMENTS: usize = 64 * 1024;
+
+/// One content-addressed piece of a tensor, covering whole rows of its leading dimension
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChunkRef {
+    pub hash: String, // SHA-256 of the chunk bytes
+    pub rows: usize,
+}
+
+/// How a tensor was split into chunks; what a task carries instead of the data itself
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TensorManifest {
+    pub tensor_hash: String,
+    pub shape: Vec<usize>,
+    pub chunks: Vec<ChunkRef>,
+}
+
+/// Chunks of one manifest a subtask needs; `None` means all of them
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShardRef {
+    pub manifest: TensorManifest,
+    pub chunk_range: Option<(usize, usize)>, // [start, end) chunk indexes
+}
+
+/// Hash of a chunk's bytes, as announced to peers
+pub fn chunk_hash(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+fn encode_chunk(values: &[f32]) -> Vec<u8> {
+    values.iter().flat_map(|value| value.to_le_bytes()).collect()
+}
+
+fn decode_chunk(bytes: &[u8]) -> Vec<f32> {
+    bytes.chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect()
+}
+
+impl TensorManifest {
+    /// Elements in one row of the leading dimension
+    pub fn row_len(&self) -> usize {
+        self.shape.iter().skip(1).product::<usize>().max(1)
+    }
+
+    pub fn chunk_hashes(&self) -> Vec<String> {
+        self.chunks.iter().map(|chunk| chunk.hash.clone()).collect()
+    }
+}
+
+impl ShardRef {
+    pub fn whole(manifest: TensorManifest) -> Self {
+        Self { manifest, chunk_range: None }
+    }
+
+    pub fn range(manifest: TensorManifest, start: usize, end: usize) -> Self {
+        Self { manifest, chunk_range: Some((start, end)) }
+    }
+
+    /// Chunks this shard covers
+    pub fn chunks(&self) -> &[ChunkRef] {
+        let (start, end) = self.chunk_range.unwrap_or((0, self.manifest.chunks.len()));
+        let end = end.min(self.manifest.chunks.len());
+        &self.manifest.chunks[start.min(end)..end]
+    }
+
+    pub fn chunk_hashes(&self) -> Vec<String> {
+        self.chunks().iter().map(|chunk| chunk.hash.clone()).collect()
+    }
+}
+
+/// Local chunk cache, filled by splitting tensors or by chunks fetched from peers
+#[derive(Debug, Clone, Default)]
+pub struct ChunkStore {
+    chunks: HashMap<String, Vec<u8>>,
+}
+
+impl ChunkStore {
+    pub fn new() -> Self {
+        Self {
+            chunks: HashMap::new(),
+        }
+    }
+
+    /// Split `tensor` into chunks of at most `chunk_elements` (but at least one row) each
+    pub fn split(&mut self, tensor: &Tensor, chunk_elements: usize) -> TribeResult<TensorManifest> {
+        let values = tensor.data.as_f32_vec()?;
+        let shape = tensor.shape.dimensions.clone();
+        let mut manifest = TensorManifest {
+            tensor_hash: tensor.calculate_hash(),
+            shape,
+            chunks: Vec::new(),
+        };
+
+        let row_len = manifest.row_len();
+        let rows_per_chunk = (chunk_elements / row_len).max(1);
+        for rows in values.chunks(rows_per_chunk * row_len) {
+            let bytes = encode_chunk(rows);
+            let hash = chunk_hash(&bytes);
+            manifest.chunks.push(ChunkRef { hash: hash.clone(), rows: rows.len() / row_len });
+            self.chunks.insert(hash, bytes);
+        }
+        Ok(manifest)
+    }
+
+    /// Store a chunk from a peer, rejecting bytes that don't match `hash`
+    pub fn insert(&mut self, hash: String, bytes: Vec<u8>) -> TribeResult<()> {
+        if chunk_hash(&bytes) != hash {
+            return Err(TribeError::Tensor(format!("Chunk {} failed its hash check", hash)));
+        }
+        self.chunks.insert(hash, bytes);
+        Ok(())
+    }
+
+    pub fn get(&self, hash: &str) -> Option<&Vec<u8>> {
+        self.chunks.get(hash)
+    }
+
+    pub fn contains(&self, hash: &str) -> bool {
+        self.chunks.contains_key(hash)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Vec<u8>)> {
+        self.chunks.iter()
+    }
+
+    /// Chunks of `shard` still to be fetched
+    pub fn missing(&self, shard: &ShardRef) -> Vec<String> {
+        shard.chunk_hashes().into_iter().filter(|hash| !self.contains(hash)).collect()
+    }
+
+    /// Rebuild the rows a shard covers, checking every chunk against the manifest
+    pub fn assemble(&self, shard: &ShardRef) -> TribeResult<Tensor> {
+        let row_len = shard.manifest.row_len();
+        let (mut values, mut rows) = (Vec::new(), 0);
+        for chunk in shard.chunks() {
+            let bytes = self.chunks.get(&chunk.hash)
+                .ok_or_else(|| TribeError::AI3(format!("Chunk {} not fetched", chunk.hash)))?;
+            if chunk_hash(bytes) != chunk.hash || bytes.len() != chunk.rows * row_len * 4 {
+                return Err(TribeError::Tensor(format!("Chunk {} does not match the manifest", chunk.hash)));
+            }
+            values.extend(decode_chunk(bytes));
+            rows += chunk.rows;
+        }
+
+        let mut shape = shard.manifest.shape.clone();
+        if let Some(leading) = shape.first_mut() {
+            *leading = rows;
+        }
+        let tensor = Tensor::from_vec(values, TensorShape::new(shape))?;
+        if shard.chunk_range.is_none() && tensor.calculate_hash() != shard.manifest.tensor_hash {
+            return Err(TribeError::Tensor("Reassembled tensor does not match the manifest".to_string()));
+        }
+        Ok(tensor)
+    }
+
+    /// Drop chunks no manifest in `referenced` still needs
+    pub fn retain(&mut self, referenced: &HashSet<String>) {
+        self.chunks.retain(|hash, _| referenced.contains(hash));
+    }
+
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    pub fn memory_usage(&self) -> usize {
+        self.chunks.values().map(Vec::len).sum()
+    }
+}
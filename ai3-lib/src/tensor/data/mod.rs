@@ -29,7 +29,7 @@ impl TensorData {
     pub fn as_f32_slice(&self) -> TribeResult<&[f32]> {
         match self {
             TensorData::F32(v) => Ok(v),
-            _ => Err(TribeError::InvalidOperation("Expected F32 tensor data".to_string())),
+            _ => Err(TribeError::Tensor("Expected F32 tensor data".to_string())),
         }
     }
 
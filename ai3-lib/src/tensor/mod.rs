@@ -5,11 +5,15 @@ use tribechain_core::{TribeResult, TribeError};
 pub mod shape;
 pub mod data;
 pub mod utils;
+pub mod store;
+pub mod chunks;
 pub mod tests;
 
 // Re-export main types
 pub use shape::TensorShape;
 pub use data::TensorData;
+pub use store::SharedTensorStore;
+pub use chunks::{ChunkStore, ChunkRef, TensorManifest, ShardRef, chunk_hash};
 
 /// Main tensor structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,7 +27,7 @@ impl Tensor {
     /// Create a new tensor
     pub fn new(shape: TensorShape, data: TensorData, name: Option<String>) -> TribeResult<Self> {
         if shape.total_elements() != data.len() {
-            return Err(TribeError::InvalidOperation(
+            return Err(TribeError::Tensor(
                 format!("Shape {} doesn't match data length {}", shape, data.len())
             ));
         }
@@ -58,7 +62,7 @@ impl Tensor {
     /// Create matrix tensor
     pub fn matrix(data: Vec<f32>, rows: usize, cols: usize) -> TribeResult<Self> {
         if data.len() != rows * cols {
-            return Err(TribeError::InvalidOperation(
+            return Err(TribeError::Tensor(
                 format!("Data length {} doesn't match matrix dimensions {}x{}", data.len(), rows, cols)
             ));
         }
@@ -0,0 +1,55 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use crate::tensor::Tensor;
+
+/// Content-addressed tensor store so identical inputs are kept once
+#[derive(Debug, Clone, Default)]
+pub struct SharedTensorStore {
+    tensors: HashMap<String, Arc<Tensor>>,
+}
+
+impl SharedTensorStore {
+    pub fn new() -> Self {
+        Self {
+            tensors: HashMap::new(),
+        }
+    }
+
+    /// Store a tensor under its content hash and return the hash
+    pub fn intern(&mut self, tensor: Tensor) -> String {
+        let hash = tensor.calculate_hash();
+        self.insert(hash.clone(), tensor);
+        hash
+    }
+
+    /// Store a tensor under a precomputed hash, keeping the existing copy if present
+    pub fn insert(&mut self, hash: String, tensor: Tensor) {
+        self.tensors.entry(hash).or_insert_with(|| Arc::new(tensor));
+    }
+
+    pub fn get(&self, hash: &str) -> Option<Arc<Tensor>> {
+        self.tensors.get(hash).cloned()
+    }
+
+    pub fn contains(&self, hash: &str) -> bool {
+        self.tensors.contains_key(hash)
+    }
+
+    /// Drop every tensor whose hash is not in `referenced`
+    pub fn retain_referenced(&mut self, referenced: &HashSet<String>) {
+        self.tensors.retain(|hash, _| referenced.contains(hash));
+    }
+
+    pub fn len(&self) -> usize {
+        self.tensors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tensors.is_empty()
+    }
+
+    /// Approximate memory held by stored tensor data
+    pub fn memory_usage(&self) -> usize {
+        self.tensors.values().map(|t| t.shape.total_elements() * 4).sum()
+    }
+}
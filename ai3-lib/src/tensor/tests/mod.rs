@@ -1,6 +1,8 @@
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests {
     use super::super::{Tensor, TensorShape, TensorData};
+    use tribechain_core::TribeError;
 
     #[test]
     fn test_tensor_creation() {
@@ -31,4 +33,15 @@ mod tests {
         let large_tensor = Tensor::vector(vec![1.0; 2000]);
         assert!(!large_tensor.is_esp_compatible());
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_shape_mismatches_are_tensor_errors() {
+        let mismatched = Tensor::new(TensorShape::matrix(2, 2), TensorData::F32(vec![1.0; 3]), None);
+        assert!(matches!(mismatched, Err(TribeError::Tensor(_))));
+        assert!(matches!(Tensor::matrix(vec![1.0; 3], 2, 2), Err(TribeError::Tensor(_))));
+
+        let vector = Tensor::vector(vec![1.0, 2.0, 3.0]);
+        assert!(matches!(vector.reshape(TensorShape::matrix(2, 2)), Err(TribeError::Tensor(_))));
+        assert!(matches!(vector.get_2d(0, 0), Err(TribeError::Tensor(_))));
+    }
+}
@@ -9,7 +9,7 @@ impl Tensor {
         let shape: Vec<usize> = self.shape.dimensions.clone();
         
         Array::from_shape_vec(IxDyn(&shape), data)
-            .map_err(|e| TribeError::InvalidOperation(format!("Failed to create ndarray: {}", e)))
+            .map_err(|e| TribeError::Tensor(format!("Failed to create ndarray: {}", e)))
     }
 
     /// Create tensor from ndarray
@@ -22,7 +22,7 @@ impl Tensor {
     /// Reshape tensor
     pub fn reshape(&self, new_shape: TensorShape) -> TribeResult<Self> {
         if new_shape.total_elements() != self.shape.total_elements() {
-            return Err(TribeError::InvalidOperation(
+            return Err(TribeError::Tensor(
                 format!("Cannot reshape tensor: {} elements to {} elements", 
                        self.shape.total_elements(), new_shape.total_elements())
             ));
@@ -40,13 +40,13 @@ impl Tensor {
         let data = self.data.as_f32_vec()?;
         data.get(index)
             .copied()
-            .ok_or_else(|| TribeError::InvalidOperation(format!("Index {} out of bounds", index)))
+            .ok_or_else(|| TribeError::Tensor(format!("Index {} out of bounds", index)))
     }
 
     /// Get element at 2D coordinates
     pub fn get_2d(&self, row: usize, col: usize) -> TribeResult<f32> {
         if self.shape.rank() != 2 {
-            return Err(TribeError::InvalidOperation("get_2d requires 2D tensor".to_string()));
+            return Err(TribeError::Tensor("get_2d requires 2D tensor".to_string()));
         }
         
         let cols = self.shape.dimensions[1];
@@ -59,12 +59,12 @@ impl Tensor {
         match &mut self.data {
             TensorData::F32(ref mut vec) => {
                 if index >= vec.len() {
-                    return Err(TribeError::InvalidOperation(format!("Index {} out of bounds", index)));
+                    return Err(TribeError::Tensor(format!("Index {} out of bounds", index)));
                 }
                 vec[index] = value;
                 Ok(())
             }
-            _ => Err(TribeError::InvalidOperation("set() only supported for F32 tensors".to_string())),
+            _ => Err(TribeError::Tensor("set() only supported for F32 tensors".to_string())),
         }
     }
 
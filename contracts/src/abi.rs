@@ -0,0 +1,401 @@
+use tribechain_core::{TribeResult, TribeError};
+use serde::{Deserialize, Serialize};
+use crate::contracts::{MethodSignature, Parameter, ParameterType};
+
+/// A typed argument or return value.
+/// 256-bit integers are carried in 128 bits; larger values are rejected.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum AbiValue {
+    Uint(u128),
+    Int(i128),
+    Bool(bool),
+    String(String),
+    Bytes(Vec<u8>),
+    Address(String),
+    Tensor { shape: Vec<usize>, data: Vec<f32> },
+    Array(Vec<AbiValue>),
+    Tuple(Vec<AbiValue>),
+}
+
+fn abi_error(message: impl Into<String>) -> TribeError {
+    TribeError::Contract(format!("ABI: {}", message.into()))
+}
+
+/// Byte width of a fixed-size integer type and whether it is signed
+fn integer_width(param_type: &ParameterType) -> Option<(usize, bool)> {
+    match param_type {
+        ParameterType::Uint8 => Some((1, false)),
+        ParameterType::Uint16 => Some((2, false)),
+        ParameterType::Uint32 => Some((4, false)),
+        ParameterType::Uint64 => Some((8, false)),
+        ParameterType::Uint128 => Some((16, false)),
+        ParameterType::Uint256 => Some((32, false)),
+        ParameterType::Int8 => Some((1, true)),
+        ParameterType::Int16 => Some((2, true)),
+        ParameterType::Int32 => Some((4, true)),
+        ParameterType::Int64 => Some((8, true)),
+        ParameterType::Int128 => Some((16, true)),
+        ParameterType::Int256 => Some((32, true)),
+        _ => None,
+    }
+}
+
+fn fits_unsigned(value: u128, width: usize) -> bool {
+    width >= 16 || value >> (width * 8) == 0
+}
+
+fn fits_signed(value: i128, width: usize) -> bool {
+    if width >= 16 {
+        return true;
+    }
+    let bits = width * 8;
+    let min = -(1i128 << (bits - 1));
+    let max = (1i128 << (bits - 1)) - 1;
+    (min..=max).contains(&value)
+}
+
+fn write_len(out: &mut Vec<u8>, len: usize) -> TribeResult<()> {
+    let len = u32::try_from(len).map_err(|_| abi_error("value too long"))?;
+    out.extend_from_slice(&len.to_be_bytes());
+    Ok(())
+}
+
+/// Encode `values` as arguments of the given types
+pub fn encode(types: &[ParameterType], values: &[AbiValue]) -> TribeResult<Vec<u8>> {
+    if types.len() != values.len() {
+        return Err(abi_error(format!("expected {} values, got {}", types.len(), values.len())));
+    }
+    let mut out = Vec::new();
+    for (param_type, value) in types.iter().zip(values) {
+        encode_value(&mut out, param_type, value)?;
+    }
+    Ok(out)
+}
+
+fn encode_value(out: &mut Vec<u8>, param_type: &ParameterType, value: &AbiValue) -> TribeResult<()> {
+    if let Some((width, signed)) = integer_width(param_type) {
+        let bytes = match (value, signed) {
+            (AbiValue::Uint(v), false) if fits_unsigned(*v, width) => v.to_be_bytes(),
+            (AbiValue::Int(v), true) if fits_signed(*v, width) => v.to_be_bytes(),
+            (AbiValue::Uint(_), false) | (AbiValue::Int(_), true) => {
+                return Err(abi_error(format!("value out of range for {:?}", param_type)));
+            }
+            _ => return Err(abi_error(format!("expected {:?}, got {:?}", param_type, value))),
+        };
+        if width > 16 {
+            let fill = if bytes[0] & 0x80 != 0 && signed { 0xff } else { 0x00 };
+            out.extend(std::iter::repeat_n(fill, width - 16));
+            out.extend_from_slice(&bytes);
+        } else {
+            out.extend_from_slice(&bytes[16 - width..]);
+        }
+        return Ok(());
+    }
+
+    match (param_type, value) {
+        (ParameterType::Bool, AbiValue::Bool(v)) => out.push(*v as u8),
+        (ParameterType::String, AbiValue::String(v)) | (ParameterType::Address, AbiValue::Address(v)) => {
+            write_len(out, v.len())?;
+            out.extend_from_slice(v.as_bytes());
+        }
+        (ParameterType::Bytes, AbiValue::Bytes(v)) => {
+            write_len(out, v.len())?;
+            out.extend_from_slice(v);
+        }
+        (ParameterType::Tensor, AbiValue::Tensor { shape, data }) => {
+            if shape.iter().product::<usize>() != data.len() {
+                return Err(abi_error("tensor shape does not match its data"));
+            }
+            write_len(out, shape.len())?;
+            for dim in shape {
+                write_len(out, *dim)?;
+            }
+            for element in data {
+                out.extend_from_slice(&element.to_be_bytes());
+            }
+        }
+        (ParameterType::Array(element_type), AbiValue::Array(items)) => {
+            write_len(out, items.len())?;
+            for item in items {
+                encode_value(out, element_type, item)?;
+            }
+        }
+        (ParameterType::Tuple(types), AbiValue::Tuple(items)) => {
+            out.extend(encode(types, items)?);
+        }
+        _ => return Err(abi_error(format!("expected {:?}, got {:?}", param_type, value))),
+    }
+    Ok(())
+}
+
+/// Decode arguments of the given types; trailing bytes are an error
+pub fn decode(types: &[ParameterType], data: &[u8]) -> TribeResult<Vec<AbiValue>> {
+    let mut reader = Reader { data, pos: 0 };
+    let values = types
+        .iter()
+        .map(|param_type| reader.value(param_type))
+        .collect::<TribeResult<Vec<_>>>()?;
+    if reader.pos != data.len() {
+        return Err(abi_error(format!("{} trailing bytes", data.len() - reader.pos)));
+    }
+    Ok(values)
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> TribeResult<&'a [u8]> {
+        let end = self.pos.checked_add(len).filter(|end| *end <= self.data.len())
+            .ok_or_else(|| abi_error("unexpected end of data"))?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn len(&mut self) -> TribeResult<usize> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+    }
+
+    fn value(&mut self, param_type: &ParameterType) -> TribeResult<AbiValue> {
+        if let Some((width, signed)) = integer_width(param_type) {
+            let bytes = self.take(width)?;
+            let (high, low) = bytes.split_at(width.saturating_sub(16));
+            let negative = signed && low[0] & 0x80 != 0;
+            let fill = if negative { 0xff } else { 0x00 };
+            if high.iter().any(|b| *b != fill) {
+                return Err(abi_error(format!("value out of supported range for {:?}", param_type)));
+            }
+            let mut buf = [fill; 16];
+            buf[16 - low.len()..].copy_from_slice(low);
+            return Ok(if signed {
+                AbiValue::Int(i128::from_be_bytes(buf))
+            } else {
+                AbiValue::Uint(u128::from_be_bytes(buf))
+            });
+        }
+
+        Ok(match param_type {
+            ParameterType::Bool => match self.take(1)?[0] {
+                0 => AbiValue::Bool(false),
+                1 => AbiValue::Bool(true),
+                other => return Err(abi_error(format!("invalid bool byte {}", other))),
+            },
+            ParameterType::String | ParameterType::Address => {
+                let len = self.len()?;
+                let text = String::from_utf8(self.take(len)?.to_vec())
+                    .map_err(|_| abi_error("invalid UTF-8"))?;
+                if matches!(param_type, ParameterType::Address) {
+                    AbiValue::Address(text)
+                } else {
+                    AbiValue::String(text)
+                }
+            }
+            ParameterType::Bytes => {
+                let len = self.len()?;
+                AbiValue::Bytes(self.take(len)?.to_vec())
+            }
+            ParameterType::Tensor => {
+                let rank = self.len()?;
+                let shape = (0..rank).map(|_| self.len()).collect::<TribeResult<Vec<_>>>()?;
+                let count = shape.iter().try_fold(1usize, |acc, dim| acc.checked_mul(*dim))
+                    .filter(|count| count.saturating_mul(4) <= self.data.len() - self.pos)
+                    .ok_or_else(|| abi_error("tensor larger than data"))?;
+                let data = self.take(count * 4)?
+                    .chunks_exact(4)
+                    .map(|chunk| f32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+                    .collect();
+                AbiValue::Tensor { shape, data }
+            }
+            ParameterType::Array(element_type) => {
+                let len = self.len()?;
+                if len > self.data.len() - self.pos {
+                    return Err(abi_error("array longer than data"));
+                }
+                AbiValue::Array((0..len).map(|_| self.value(element_type)).collect::<TribeResult<_>>()?)
+            }
+            ParameterType::Tuple(types) => {
+                AbiValue::Tuple(types.iter().map(|t| self.value(t)).collect::<TribeResult<_>>()?)
+            }
+            _ => unreachable!("integer types are handled above"),
+        })
+    }
+}
+
+/// Convert a JSON argument to a value of `param_type`.
+/// Integers may be given as numbers or decimal strings, bytes as hex strings.
+pub fn from_json(param_type: &ParameterType, json: &serde_json::Value) -> TribeResult<AbiValue> {
+    use serde_json::Value;
+
+    let mismatch = || abi_error(format!("expected {:?}, got {}", param_type, json));
+    if let Some((_, signed)) = integer_width(param_type) {
+        let text = match json {
+            Value::Number(n) => n.to_string(),
+            Value::String(s) => s.clone(),
+            _ => return Err(mismatch()),
+        };
+        return if signed {
+            text.parse().map(AbiValue::Int).map_err(|_| mismatch())
+        } else {
+            text.parse().map(AbiValue::Uint).map_err(|_| mismatch())
+        };
+    }
+
+    match (param_type, json) {
+        (ParameterType::Bool, Value::Bool(v)) => Ok(AbiValue::Bool(*v)),
+        (ParameterType::String, Value::String(v)) => Ok(AbiValue::String(v.clone())),
+        (ParameterType::Address, Value::String(v)) => Ok(AbiValue::Address(v.clone())),
+        (ParameterType::Bytes, Value::String(v)) => {
+            hex::decode(v.trim_start_matches("0x")).map(AbiValue::Bytes).map_err(|_| mismatch())
+        }
+        (ParameterType::Tensor, Value::Object(fields)) => {
+            let shape = fields.get("shape").and_then(Value::as_array).ok_or_else(mismatch)?
+                .iter()
+                .map(|dim| dim.as_u64().map(|d| d as usize).ok_or_else(mismatch))
+                .collect::<TribeResult<Vec<_>>>()?;
+            let data = fields.get("data").and_then(Value::as_array).ok_or_else(mismatch)?
+                .iter()
+                .map(|x| x.as_f64().map(|x| x as f32).ok_or_else(mismatch))
+                .collect::<TribeResult<Vec<_>>>()?;
+            Ok(AbiValue::Tensor { shape, data })
+        }
+        (ParameterType::Array(element_type), Value::Array(items)) => {
+            items.iter().map(|item| from_json(element_type, item)).collect::<TribeResult<_>>().map(AbiValue::Array)
+        }
+        (ParameterType::Tuple(types), Value::Array(items)) if types.len() == items.len() => {
+            types.iter().zip(items).map(|(t, item)| from_json(t, item)).collect::<TribeResult<_>>().map(AbiValue::Tuple)
+        }
+        _ => Err(mismatch()),
+    }
+}
+
+impl AbiValue {
+    /// JSON form accepted back by `from_json`; integers beyond 64 bits become decimal strings
+    pub fn to_json(&self) -> serde_json::Value {
+        use serde_json::{json, Value};
+
+        match self {
+            AbiValue::Uint(v) => u64::try_from(*v).map_or_else(|_| json!(v.to_string()), Value::from),
+            AbiValue::Int(v) => i64::try_from(*v).map_or_else(|_| json!(v.to_string()), Value::from),
+            AbiValue::Bool(v) => json!(v),
+            AbiValue::String(v) | AbiValue::Address(v) => json!(v),
+            AbiValue::Bytes(v) => json!(hex::encode(v)),
+            AbiValue::Tensor { shape, data } => json!({ "shape": shape, "data": data }),
+            AbiValue::Array(items) | AbiValue::Tuple(items) => Value::Array(items.iter().map(AbiValue::to_json).collect()),
+        }
+    }
+}
+
+fn param_types(params: &[Parameter]) -> Vec<ParameterType> {
+    params.iter().map(|p| p.param_type.clone()).collect()
+}
+
+impl MethodSignature {
+    /// Encode call arguments against this method's inputs
+    pub fn encode_inputs(&self, values: &[AbiValue]) -> TribeResult<Vec<u8>> {
+        encode(&param_types(&self.inputs), values)
+    }
+
+    /// Decode call arguments against this method's inputs
+    pub fn decode_inputs(&self, data: &[u8]) -> TribeResult<Vec<AbiValue>> {
+        decode(&param_types(&self.inputs), data)
+    }
+
+    /// Decode return data against this method's outputs
+    pub fn decode_outputs(&self, data: &[u8]) -> TribeResult<Vec<AbiValue>> {
+        decode(&param_types(&self.outputs), data)
+    }
+
+    /// Decode return data against this method's outputs, as JSON
+    pub fn decode_json_outputs(&self, data: &[u8]) -> TribeResult<Vec<serde_json::Value>> {
+        Ok(self.decode_outputs(data)?.iter().map(AbiValue::to_json).collect())
+    }
+
+    /// Encode JSON arguments against this method's inputs
+    pub fn encode_json_inputs(&self, args: &[serde_json::Value]) -> TribeResult<Vec<u8>> {
+        if args.len() != self.inputs.len() {
+            return Err(abi_error(format!("{} expects {} arguments, got {}", self.name, self.inputs.len(), args.len())));
+        }
+        let values = self.inputs.iter().zip(args)
+            .map(|(param, arg)| from_json(&param.param_type, arg))
+            .collect::<TribeResult<Vec<_>>>()?;
+        self.encode_inputs(&values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let types = vec![
+            ParameterType::Uint64,
+            ParameterType::Int256,
+            ParameterType::Address,
+            ParameterType::Array(Box::new(ParameterType::String)),
+            ParameterType::Tensor,
+            ParameterType::Tuple(vec![ParameterType::Bool, ParameterType::Bytes]),
+        ];
+        let values = vec![
+            AbiValue::Uint(42),
+            AbiValue::Int(-7),
+            AbiValue::Address("tribe1alice".to_string()),
+            AbiValue::Array(vec![AbiValue::String("a".to_string()), AbiValue::String("bc".to_string())]),
+            AbiValue::Tensor { shape: vec![2, 2], data: vec![1.0, 2.0, 3.0, 4.0] },
+            AbiValue::Tuple(vec![AbiValue::Bool(true), AbiValue::Bytes(vec![0xde, 0xad])]),
+        ];
+
+        let encoded = encode(&types, &values).unwrap();
+        assert_eq!(decode(&types, &encoded).unwrap(), values);
+
+        // Truncated or padded data is rejected
+        assert!(decode(&types, &encoded[..encoded.len() - 1]).is_err());
+        let mut padded = encoded.clone();
+        padded.push(0);
+        assert!(decode(&types, &padded).is_err());
+    }
+
+    #[test]
+    fn test_type_checking() {
+        assert!(encode(&[ParameterType::Uint8], &[AbiValue::Uint(256)]).is_err());
+        assert!(encode(&[ParameterType::Int8], &[AbiValue::Int(-129)]).is_err());
+        assert!(encode(&[ParameterType::Uint64], &[AbiValue::Int(1)]).is_err());
+        assert!(encode(&[ParameterType::String], &[AbiValue::Address("x".to_string())]).is_err());
+        assert!(encode(&[ParameterType::Tensor], &[AbiValue::Tensor { shape: vec![3], data: vec![1.0] }]).is_err());
+        assert!(encode(&[ParameterType::Bool], &[]).is_err());
+        assert!(decode(&[ParameterType::Bool], &[2]).is_err());
+        assert!(decode(&[ParameterType::Uint256], &[0xff; 32]).is_err());
+    }
+
+    #[test]
+    fn test_json_arguments() {
+        let method = MethodSignature::new(
+            "transfer".to_string(),
+            vec![
+                Parameter::new("to".to_string(), ParameterType::Address),
+                Parameter::new("amount".to_string(), ParameterType::Uint128),
+            ],
+            vec![Parameter::new("ok".to_string(), ParameterType::Bool)],
+        );
+
+        let args = [serde_json::json!("tribe1bob"), serde_json::json!("1000")];
+        let data = method.encode_json_inputs(&args).unwrap();
+        assert_eq!(
+            method.decode_inputs(&data).unwrap(),
+            vec![AbiValue::Address("tribe1bob".to_string()), AbiValue::Uint(1000)]
+        );
+
+        assert!(method.encode_json_inputs(&args[..1]).is_err());
+        assert!(method.encode_json_inputs(&[serde_json::json!("tribe1bob"), serde_json::json!(-1)]).is_err());
+        let outputs = encode(&[ParameterType::Bool], &[AbiValue::Bool(true)]).unwrap();
+        assert_eq!(method.decode_json_outputs(&outputs).unwrap(), vec![serde_json::json!(true)]);
+        // JSON output converts back to the same values
+        let big = AbiValue::Tuple(vec![AbiValue::Uint(u128::MAX), AbiValue::Bytes(vec![0xbe, 0xef])]);
+        let tuple_type = ParameterType::Tuple(vec![ParameterType::Uint128, ParameterType::Bytes]);
+        assert_eq!(from_json(&tuple_type, &big.to_json()).unwrap(), big);
+    }
+}
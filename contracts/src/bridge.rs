@@ -0,0 +1,362 @@
+use tribechain_core::{TribeResult, TribeError};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use sha2::{Digest, Sha256};
+
+fn bridge_error(message: impl Into<String>) -> TribeError {
+    TribeError::Bridge(message.into())
+}
+
+/// Check an EVM address, `0x` and 20 bytes of hex, returning it lowercased
+pub fn normalize_evm_address(address: &str) -> TribeResult<String> {
+    let hex_part = address.strip_prefix("0x").or_else(|| address.strip_prefix("0X"))
+        .ok_or_else(|| bridge_error(format!("{} is not a 0x address", address)))?;
+    if hex_part.len() != 40 || !hex_part.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(bridge_error(format!("{} is not a 20-byte address", address)));
+    }
+    Ok(format!("0x{}", hex_part.to_lowercase()))
+}
+
+/// How a token crosses the bridge
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BridgeMode {
+    /// Native here, e.g. TRIBE or STOMP: locked in the bridge on the way out and
+    /// released on the way back
+    Lock,
+    /// Native on the EVM chain: minted here on the way in and burned on the way out.
+    /// The token must be mintable and burnable, with the bridge as a minter.
+    Mint,
+}
+
+/// A token the bridge carries and its counterpart on the EVM chain
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BridgeRoute {
+    pub token_id: String,
+    pub remote_token: String, // Lowercase 0x address
+    pub mode: BridgeMode,
+    pub local_decimals: u8,
+    pub remote_decimals: u8,
+}
+
+impl BridgeRoute {
+    /// Local units for `remote_amount`; dust below one local unit is dropped
+    pub fn to_local(&self, remote_amount: u128) -> TribeResult<u64> {
+        let amount = if self.remote_decimals >= self.local_decimals {
+            remote_amount / 10u128.pow((self.remote_decimals - self.local_decimals) as u32)
+        } else {
+            remote_amount.checked_mul(10u128.pow((self.local_decimals - self.remote_decimals) as u32))
+                .ok_or_else(|| bridge_error("Deposit amount overflows"))?
+        };
+        u64::try_from(amount).map_err(|_| bridge_error("Deposit amount overflows"))
+    }
+
+    pub fn to_remote(&self, local_amount: u64) -> TribeResult<u128> {
+        let amount = local_amount as u128;
+        if self.remote_decimals >= self.local_decimals {
+            amount.checked_mul(10u128.pow((self.remote_decimals - self.local_decimals) as u32))
+                .ok_or_else(|| bridge_error("Transfer amount overflows"))
+        } else {
+            Ok(amount / 10u128.pow((self.local_decimals - self.remote_decimals) as u32))
+        }
+    }
+}
+
+/// A `Deposit` event seen on the EVM chain, as relayers report it
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BridgeDeposit {
+    pub remote_chain_id: u64,
+    pub tx_hash: String,
+    pub log_index: u64,
+    pub remote_token: String,
+    pub sender: String, // EVM address that deposited
+    pub recipient: String, // TribeChain address to credit
+    pub amount: u128, // In the remote token's units
+}
+
+impl BridgeDeposit {
+    /// Identifies the event, so it is credited at most once
+    pub fn key(&self) -> String {
+        format!("{}:{}:{}", self.remote_chain_id, self.tx_hash.to_lowercase(), self.log_index)
+    }
+
+    /// Hash of everything a relayer attests to; only matching attestations add up
+    pub fn digest(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.key().as_bytes());
+        hasher.update(self.remote_token.to_lowercase().as_bytes());
+        hasher.update(self.sender.to_lowercase().as_bytes());
+        hasher.update(self.recipient.as_bytes());
+        hasher.update(self.amount.to_le_bytes());
+        hex::encode(hasher.finalize())
+    }
+}
+
+/// Attestations collected for one version of a deposit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingDeposit {
+    pub deposit: BridgeDeposit,
+    pub attesters: Vec<String>,
+    pub first_seen_height: u64,
+}
+
+/// Tokens sent out through the bridge, for relayers to deliver on the EVM chain
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutboundTransfer {
+    pub nonce: u64,
+    pub token_id: String,
+    pub remote_token: String,
+    pub sender: String,
+    pub recipient: String, // EVM address
+    pub amount: u64, // Local units taken from the sender
+    pub remote_amount: u128, // What the recipient receives on the EVM chain
+    pub block_height: u64,
+}
+
+/// Lock-and-mint bridge to one EVM chain. Deposits there are credited here once
+/// `threshold` of the `attesters` report them identically.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BridgeContract {
+    pub id: String,
+    pub admin: String,
+    pub remote_chain_id: u64,
+    pub remote_bridge: String, // EVM contract emitting the deposit events
+    pub attesters: Vec<String>,
+    pub threshold: usize,
+    pub routes: HashMap<String, BridgeRoute>, // remote token -> route
+    pub pending: HashMap<String, PendingDeposit>, // deposit digest -> attestations
+    pub processed: HashSet<String>, // Keys of deposits already credited
+    pub outbound: Vec<OutboundTransfer>,
+    pub next_nonce: u64,
+    pub locked: HashMap<String, u64>, // token id -> amount held for `Lock` routes
+    pub paused: bool,
+}
+
+impl BridgeContract {
+    pub fn new(
+        admin: String,
+        remote_chain_id: u64,
+        remote_bridge: &str,
+        attesters: Vec<String>,
+        threshold: usize,
+    ) -> TribeResult<Self> {
+        let remote_bridge = normalize_evm_address(remote_bridge)?;
+        let mut unique = attesters.clone();
+        unique.sort();
+        unique.dedup();
+        if unique.len() != attesters.len() || attesters.iter().any(|a| a.is_empty()) {
+            return Err(bridge_error("Attesters must be unique and non-empty"));
+        }
+        if threshold == 0 || threshold > attesters.len() {
+            return Err(bridge_error(format!("Threshold must be between 1 and {}", attesters.len())));
+        }
+
+        let mut hasher = Sha256::new();
+        hasher.update(remote_chain_id.to_le_bytes());
+        hasher.update(remote_bridge.as_bytes());
+        hasher.update(admin.as_bytes());
+        Ok(Self {
+            id: format!("bridge_{}", &hex::encode(hasher.finalize())[..16]),
+            admin,
+            remote_chain_id,
+            remote_bridge,
+            attesters,
+            threshold,
+            routes: HashMap::new(),
+            pending: HashMap::new(),
+            processed: HashSet::new(),
+            outbound: Vec::new(),
+            next_nonce: 1,
+            locked: HashMap::new(),
+            paused: false,
+        })
+    }
+
+    fn check_admin(&self, caller: &str) -> TribeResult<()> {
+        if caller != self.admin {
+            return Err(bridge_error("Only the bridge admin can do this"));
+        }
+        Ok(())
+    }
+
+    pub fn is_attester(&self, address: &str) -> bool {
+        self.attesters.iter().any(|attester| attester == address)
+    }
+
+    pub fn add_route(&mut self, caller: &str, mut route: BridgeRoute) -> TribeResult<()> {
+        self.check_admin(caller)?;
+        route.remote_token = normalize_evm_address(&route.remote_token)?;
+        if self.routes.values().any(|existing| existing.token_id == route.token_id && existing.remote_token != route.remote_token) {
+            return Err(bridge_error(format!("Token {} already has a route", route.token_id)));
+        }
+        self.routes.insert(route.remote_token.clone(), route);
+        Ok(())
+    }
+
+    /// Route carrying local token `token_id`
+    pub fn route_for_token(&self, token_id: &str) -> TribeResult<&BridgeRoute> {
+        self.routes.values().find(|route| route.token_id == token_id)
+            .ok_or_else(|| bridge_error(format!("Token {} is not bridged", token_id)))
+    }
+
+    pub fn set_paused(&mut self, caller: &str, paused: bool) -> TribeResult<()> {
+        self.check_admin(caller)?;
+        self.paused = paused;
+        Ok(())
+    }
+
+    /// Count `attester`'s report of `deposit`. Returns the deposit and its route once
+    /// enough attesters agree on it, at which point it counts as credited.
+    pub fn attest(&mut self, attester: &str, deposit: BridgeDeposit, block_height: u64) -> TribeResult<Option<(BridgeDeposit, BridgeRoute)>> {
+        if self.paused {
+            return Err(bridge_error("Bridge is paused"));
+        }
+        if !self.is_attester(attester) {
+            return Err(bridge_error("Only attesters can report deposits"));
+        }
+        if deposit.remote_chain_id != self.remote_chain_id {
+            return Err(bridge_error(format!("Deposit is from chain {}, not {}", deposit.remote_chain_id, self.remote_chain_id)));
+        }
+        let key = deposit.key();
+        if self.processed.contains(&key) {
+            return Err(bridge_error("Deposit was already credited"));
+        }
+        let route = self.routes.get(&normalize_evm_address(&deposit.remote_token)?)
+            .ok_or_else(|| bridge_error(format!("Token {} is not bridged", deposit.remote_token)))?
+            .clone();
+
+        let digest = deposit.digest();
+        let pending = self.pending.entry(digest.clone()).or_insert_with(|| PendingDeposit {
+            deposit,
+            attesters: Vec::new(),
+            first_seen_height: block_height,
+        });
+        if pending.attesters.iter().any(|existing| existing == attester) {
+            return Err(bridge_error("Deposit already attested by this attester"));
+        }
+        pending.attesters.push(attester.to_string());
+        if pending.attesters.len() < self.threshold {
+            return Ok(None);
+        }
+
+        // Conflicting reports of the same event are dropped with it
+        let deposit = pending.deposit.clone();
+        self.pending.retain(|_, other| other.deposit.key() != key);
+        self.processed.insert(key);
+        Ok(Some((deposit, route)))
+    }
+
+    /// Queue a transfer out to `recipient` on the EVM chain, once the sender's tokens
+    /// are locked or burned
+    pub fn record_outbound(
+        &mut self,
+        sender: String,
+        token_id: &str,
+        recipient: &str,
+        amount: u64,
+        block_height: u64,
+    ) -> TribeResult<OutboundTransfer> {
+        if self.paused {
+            return Err(bridge_error("Bridge is paused"));
+        }
+        if amount == 0 {
+            return Err(bridge_error("Transfer amount must be greater than 0"));
+        }
+        let recipient = normalize_evm_address(recipient)?;
+        let route = self.route_for_token(token_id)?.clone();
+        let remote_amount = route.to_remote(amount)?;
+        if remote_amount == 0 {
+            return Err(bridge_error("Transfer amount is below one unit of the remote token"));
+        }
+
+        let transfer = OutboundTransfer {
+            nonce: self.next_nonce,
+            token_id: token_id.to_string(),
+            remote_token: route.remote_token,
+            sender,
+            recipient,
+            amount,
+            remote_amount,
+            block_height,
+        };
+        self.next_nonce += 1;
+        if route.mode == BridgeMode::Lock {
+            *self.locked.entry(token_id.to_string()).or_insert(0) += amount;
+        }
+        self.outbound.push(transfer.clone());
+        Ok(transfer)
+    }
+
+    /// Outbound transfers with a nonce of at least `from_nonce`
+    pub fn outbound_since(&self, from_nonce: u64) -> &[OutboundTransfer] {
+        let start = self.outbound.partition_point(|transfer| transfer.nonce < from_nonce);
+        &self.outbound[start..]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REMOTE_TOKEN: &str = "0x00000000000000000000000000000000000000aa";
+
+    fn bridge() -> BridgeContract {
+        let attesters = vec!["r1".to_string(), "r2".to_string(), "r3".to_string()];
+        let mut bridge = BridgeContract::new("admin".to_string(), 5, "0x00000000000000000000000000000000000000BB", attesters, 2).unwrap();
+        bridge.add_route("admin", BridgeRoute {
+            token_id: "TRIBE".to_string(),
+            remote_token: REMOTE_TOKEN.to_string(),
+            mode: BridgeMode::Lock,
+            local_decimals: 6,
+            remote_decimals: 18,
+        }).unwrap();
+        bridge
+    }
+
+    fn deposit(amount: u128) -> BridgeDeposit {
+        BridgeDeposit {
+            remote_chain_id: 5,
+            tx_hash: "0xabc".to_string(),
+            log_index: 0,
+            remote_token: REMOTE_TOKEN.to_string(),
+            sender: "0x00000000000000000000000000000000000000cc".to_string(),
+            recipient: "alice".to_string(),
+            amount,
+        }
+    }
+
+    #[test]
+    fn test_attestation_threshold() {
+        let mut bridge = bridge();
+        assert_eq!(bridge.remote_bridge, "0x00000000000000000000000000000000000000bb");
+        assert!(bridge.attest("mallory", deposit(1), 1).is_err());
+
+        // A relayer misreporting the amount doesn't count towards the real deposit
+        assert!(bridge.attest("r1", deposit(5_000_000_000_000), 1).unwrap().is_none());
+        assert!(bridge.attest("r1", deposit(5_000_000_000_000), 1).is_err());
+        assert!(bridge.attest("r3", deposit(9_000_000_000_000), 1).unwrap().is_none());
+        let (credited, route) = bridge.attest("r2", deposit(5_000_000_000_000), 2).unwrap().unwrap();
+        assert_eq!(route.to_local(credited.amount).unwrap(), 5);
+        assert!(bridge.pending.is_empty());
+
+        // Replays are refused, however they are reported
+        assert!(bridge.attest("r3", deposit(5_000_000_000_000), 3).is_err());
+        assert!(bridge.attest("r3", deposit(9_000_000_000_000), 3).is_err());
+    }
+
+    #[test]
+    fn test_outbound_transfers() {
+        let mut bridge = bridge();
+        assert!(bridge.record_outbound("alice".to_string(), "TRIBE", "not-an-address", 10, 1).is_err());
+        assert!(bridge.record_outbound("alice".to_string(), "STOMP", REMOTE_TOKEN, 10, 1).is_err());
+
+        let transfer = bridge.record_outbound("alice".to_string(), "TRIBE", REMOTE_TOKEN, 10, 1).unwrap();
+        assert_eq!((transfer.nonce, transfer.remote_amount), (1, 10_000_000_000_000));
+        bridge.record_outbound("bob".to_string(), "TRIBE", REMOTE_TOKEN, 5, 2).unwrap();
+        assert_eq!(bridge.locked["TRIBE"], 15);
+        assert_eq!(bridge.outbound_since(2).len(), 1);
+
+        bridge.set_paused("admin", true).unwrap();
+        assert!(bridge.record_outbound("alice".to_string(), "TRIBE", REMOTE_TOKEN, 10, 3).is_err());
+        assert!(bridge.set_paused("alice", false).is_err());
+    }
+}
@@ -0,0 +1,415 @@
+use tribechain_core::{TribeResult, TribeError};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Tick bounds; tick `i` is the price 1.0001^i of token A in token B
+pub const MIN_TICK: i32 = -887_272;
+pub const MAX_TICK: i32 = 887_272;
+
+pub fn sqrt_price_at_tick(tick: i32) -> f64 {
+    1.0001f64.powf(tick as f64 / 2.0)
+}
+
+pub fn tick_at_sqrt_price(sqrt_price: f64) -> i32 {
+    ((2.0 * sqrt_price.ln()) / 1.0001f64.ln()).floor() as i32
+}
+
+/// Liquidity boundary; `fee_growth_outside_*` is the fee growth on the side of
+/// the tick away from the current price, flipped whenever the price crosses it
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TickInfo {
+    pub liquidity_gross: u128,
+    pub liquidity_net: i128, // Added to active liquidity when crossed upwards
+    pub fee_growth_outside_a: f64,
+    pub fee_growth_outside_b: f64,
+}
+
+/// Liquidity provided between two ticks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RangePosition {
+    pub id: u64,
+    pub provider: String,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    pub liquidity: u128,
+    pub fee_growth_inside_last_a: f64,
+    pub fee_growth_inside_last_b: f64,
+    pub tokens_owed_a: u64,
+    pub tokens_owed_b: u64,
+}
+
+/// Result of a concentrated-liquidity swap
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RangeSwap {
+    pub amount_in: u64, // Less than requested if liquidity ran out
+    pub amount_out: u64,
+    pub fee: u64,
+}
+
+/// Concentrated-liquidity state: only positions whose range contains the current
+/// price provide liquidity, and swap fees accrue per unit of in-range liquidity
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConcentratedLiquidity {
+    pub sqrt_price: f64,
+    pub current_tick: i32,
+    pub tick_spacing: i32,
+    pub liquidity: u128, // Active at the current price
+    pub fee_growth_global_a: f64, // Fees earned per unit of liquidity, all time
+    pub fee_growth_global_b: f64,
+    pub ticks: BTreeMap<i32, TickInfo>,
+    pub positions: HashMap<u64, RangePosition>,
+    pub next_position_id: u64,
+}
+
+impl ConcentratedLiquidity {
+    pub fn new(initial_price: f64, tick_spacing: i32) -> TribeResult<Self> {
+        if initial_price <= 0.0 || !initial_price.is_finite() {
+            return Err(TribeError::Contract("Initial price must be positive".to_string()));
+        }
+        if tick_spacing <= 0 {
+            return Err(TribeError::Contract("Tick spacing must be positive".to_string()));
+        }
+
+        let sqrt_price = initial_price.sqrt();
+        Ok(Self {
+            sqrt_price,
+            current_tick: tick_at_sqrt_price(sqrt_price),
+            tick_spacing,
+            liquidity: 0,
+            fee_growth_global_a: 0.0,
+            fee_growth_global_b: 0.0,
+            ticks: BTreeMap::new(),
+            positions: HashMap::new(),
+            next_position_id: 1,
+        })
+    }
+
+    pub fn price(&self) -> f64 {
+        self.sqrt_price * self.sqrt_price
+    }
+
+    /// Token amounts backing `liquidity` between two ticks at the current price
+    pub fn amounts_for_liquidity(&self, tick_lower: i32, tick_upper: i32, liquidity: u128) -> (f64, f64) {
+        let (lower, upper) = (sqrt_price_at_tick(tick_lower), sqrt_price_at_tick(tick_upper));
+        let liquidity = liquidity as f64;
+        if self.sqrt_price <= lower {
+            (liquidity * (1.0 / lower - 1.0 / upper), 0.0)
+        } else if self.sqrt_price >= upper {
+            (0.0, liquidity * (upper - lower))
+        } else {
+            (liquidity * (1.0 / self.sqrt_price - 1.0 / upper), liquidity * (self.sqrt_price - lower))
+        }
+    }
+
+    /// Most liquidity the given amounts can back between two ticks
+    pub fn liquidity_for_amounts(&self, tick_lower: i32, tick_upper: i32, amount_a: u64, amount_b: u64) -> u128 {
+        let (lower, upper) = (sqrt_price_at_tick(tick_lower), sqrt_price_at_tick(tick_upper));
+        let from_a = |from: f64| amount_a as f64 / (1.0 / from - 1.0 / upper);
+        let from_b = |to: f64| amount_b as f64 / (to - lower);
+        let liquidity = if self.sqrt_price <= lower {
+            from_a(lower)
+        } else if self.sqrt_price >= upper {
+            from_b(upper)
+        } else {
+            from_a(self.sqrt_price).min(from_b(self.sqrt_price))
+        };
+        liquidity.max(0.0) as u128
+    }
+
+    /// Fee growth per unit of liquidity earned inside a tick range
+    pub fn fee_growth_inside(&self, tick_lower: i32, tick_upper: i32) -> (f64, f64) {
+        let outside = |tick: i32| self.ticks.get(&tick)
+            .map(|info| (info.fee_growth_outside_a, info.fee_growth_outside_b))
+            .unwrap_or((0.0, 0.0));
+        let global = (self.fee_growth_global_a, self.fee_growth_global_b);
+
+        let (lower_a, lower_b) = outside(tick_lower);
+        let below = if self.current_tick >= tick_lower {
+            (lower_a, lower_b)
+        } else {
+            (global.0 - lower_a, global.1 - lower_b)
+        };
+        let (upper_a, upper_b) = outside(tick_upper);
+        let above = if self.current_tick < tick_upper {
+            (upper_a, upper_b)
+        } else {
+            (global.0 - upper_a, global.1 - upper_b)
+        };
+        (global.0 - below.0 - above.0, global.1 - below.1 - above.1)
+    }
+
+    /// Open a position with as much liquidity as the amounts allow; returns
+    /// (position id, amount A used, amount B used)
+    pub fn mint(
+        &mut self,
+        provider: String,
+        tick_lower: i32,
+        tick_upper: i32,
+        amount_a: u64,
+        amount_b: u64,
+    ) -> TribeResult<(u64, u64, u64)> {
+        self.check_ticks(tick_lower, tick_upper)?;
+        let liquidity = self.liquidity_for_amounts(tick_lower, tick_upper, amount_a, amount_b);
+        if liquidity == 0 {
+            return Err(TribeError::Contract("Amounts provide no liquidity in this range".to_string()));
+        }
+
+        let (used_a, used_b) = self.amounts_for_liquidity(tick_lower, tick_upper, liquidity);
+        let (used_a, used_b) = ((used_a.ceil() as u64).min(amount_a), (used_b.ceil() as u64).min(amount_b));
+
+        self.modify_liquidity(tick_lower, tick_upper, liquidity as i128);
+        let (inside_a, inside_b) = self.fee_growth_inside(tick_lower, tick_upper);
+
+        let id = self.next_position_id;
+        self.next_position_id += 1;
+        self.positions.insert(id, RangePosition {
+            id,
+            provider,
+            tick_lower,
+            tick_upper,
+            liquidity,
+            fee_growth_inside_last_a: inside_a,
+            fee_growth_inside_last_b: inside_b,
+            tokens_owed_a: 0,
+            tokens_owed_b: 0,
+        });
+        Ok((id, used_a, used_b))
+    }
+
+    /// Close a position, returning its tokens plus any uncollected fees
+    pub fn burn(&mut self, position_id: u64, provider: &str) -> TribeResult<(u64, u64)> {
+        self.check_owner(position_id, provider)?;
+        self.accrue_fees(position_id);
+        let position = self.positions.remove(&position_id)
+            .ok_or_else(|| TribeError::Contract("Position not found".to_string()))?;
+
+        let (amount_a, amount_b) = self.amounts_for_liquidity(position.tick_lower, position.tick_upper, position.liquidity);
+        self.modify_liquidity(position.tick_lower, position.tick_upper, -(position.liquidity as i128));
+        Ok((
+            amount_a.floor() as u64 + position.tokens_owed_a,
+            amount_b.floor() as u64 + position.tokens_owed_b,
+        ))
+    }
+
+    /// Withdraw the fees a position has earned so far
+    pub fn collect_fees(&mut self, position_id: u64, provider: &str) -> TribeResult<(u64, u64)> {
+        self.check_owner(position_id, provider)?;
+        self.accrue_fees(position_id);
+        let position = self.positions.get_mut(&position_id)
+            .ok_or_else(|| TribeError::Contract("Position not found".to_string()))?;
+        Ok((std::mem::take(&mut position.tokens_owed_a), std::mem::take(&mut position.tokens_owed_b)))
+    }
+
+    /// Share fees earned outside a swap (e.g. flash loans) among in-range liquidity
+    pub fn donate_fees(&mut self, is_token_a: bool, amount: u64) {
+        if self.liquidity == 0 {
+            return;
+        }
+        let growth = amount as f64 / self.liquidity as f64;
+        if is_token_a {
+            self.fee_growth_global_a += growth;
+        } else {
+            self.fee_growth_global_b += growth;
+        }
+    }
+
+    /// Swap through the initialized ticks. Selling A moves the price down, selling B up.
+    pub fn swap(&mut self, a_to_b: bool, amount_in: u64, fee_rate: f64) -> TribeResult<RangeSwap> {
+        let mut remaining = amount_in as f64;
+        let mut amount_out = 0.0;
+        let mut fees = 0.0;
+
+        while remaining > 0.0 {
+            let next_tick = if a_to_b {
+                self.ticks.range(..=self.current_tick).next_back().map(|(tick, _)| *tick)
+            } else {
+                self.ticks.range(self.current_tick + 1..).next().map(|(tick, _)| *tick)
+            };
+            let next_tick = match next_tick {
+                Some(tick) => tick,
+                None => break, // No liquidity left in this direction
+            };
+            let target = sqrt_price_at_tick(next_tick);
+            let liquidity = self.liquidity as f64;
+
+            if liquidity > 0.0 {
+                let available = remaining * (1.0 - fee_rate);
+                let to_target = if a_to_b {
+                    liquidity * (1.0 / target - 1.0 / self.sqrt_price)
+                } else {
+                    liquidity * (target - self.sqrt_price)
+                };
+                let reaches_target = available >= to_target;
+                let (net_in, new_sqrt_price) = if reaches_target {
+                    (to_target, target)
+                } else if a_to_b {
+                    (available, liquidity * self.sqrt_price / (liquidity + available * self.sqrt_price))
+                } else {
+                    (available, self.sqrt_price + available / liquidity)
+                };
+
+                let step_out = if a_to_b {
+                    liquidity * (self.sqrt_price - new_sqrt_price)
+                } else {
+                    liquidity * (1.0 / self.sqrt_price - 1.0 / new_sqrt_price)
+                };
+                let gross_in = if reaches_target { net_in / (1.0 - fee_rate) } else { remaining };
+                let step_fee = gross_in - net_in;
+                if a_to_b {
+                    self.fee_growth_global_a += step_fee / liquidity;
+                } else {
+                    self.fee_growth_global_b += step_fee / liquidity;
+                }
+
+                remaining -= gross_in;
+                amount_out += step_out;
+                fees += step_fee;
+                self.sqrt_price = new_sqrt_price;
+
+                if !reaches_target {
+                    // Stopped between ticks
+                    let tick = tick_at_sqrt_price(new_sqrt_price);
+                    self.current_tick = if a_to_b {
+                        tick.clamp(next_tick, self.current_tick)
+                    } else {
+                        tick.clamp(self.current_tick, next_tick - 1)
+                    };
+                    break;
+                }
+            } else {
+                self.sqrt_price = target;
+            }
+            self.cross(next_tick, a_to_b);
+        }
+
+        let used = (amount_in as f64 - remaining.max(0.0)).ceil() as u64;
+        Ok(RangeSwap {
+            amount_in: used.min(amount_in),
+            amount_out: amount_out.max(0.0).floor() as u64,
+            fee: fees.max(0.0) as u64,
+        })
+    }
+
+    fn cross(&mut self, tick: i32, a_to_b: bool) {
+        let (global_a, global_b) = (self.fee_growth_global_a, self.fee_growth_global_b);
+        let liquidity_net = match self.ticks.get_mut(&tick) {
+            Some(info) => {
+                info.fee_growth_outside_a = global_a - info.fee_growth_outside_a;
+                info.fee_growth_outside_b = global_b - info.fee_growth_outside_b;
+                info.liquidity_net
+            }
+            None => 0,
+        };
+
+        if a_to_b {
+            self.liquidity = (self.liquidity as i128 - liquidity_net).max(0) as u128;
+            self.current_tick = tick - 1;
+        } else {
+            self.liquidity = (self.liquidity as i128 + liquidity_net).max(0) as u128;
+            self.current_tick = tick;
+        }
+    }
+
+    fn modify_liquidity(&mut self, tick_lower: i32, tick_upper: i32, delta: i128) {
+        self.update_tick(tick_lower, delta, delta);
+        self.update_tick(tick_upper, delta, -delta);
+        if tick_lower <= self.current_tick && self.current_tick < tick_upper {
+            self.liquidity = (self.liquidity as i128 + delta).max(0) as u128;
+        }
+    }
+
+    fn update_tick(&mut self, tick: i32, gross_delta: i128, net_delta: i128) {
+        let below_price = tick <= self.current_tick;
+        let (global_a, global_b) = (self.fee_growth_global_a, self.fee_growth_global_b);
+        let info = self.ticks.entry(tick).or_insert_with(|| {
+            // By convention all fees so far happened below an initialized tick at or under the price
+            if below_price {
+                TickInfo { fee_growth_outside_a: global_a, fee_growth_outside_b: global_b, ..TickInfo::default() }
+            } else {
+                TickInfo::default()
+            }
+        });
+
+        info.liquidity_gross = (info.liquidity_gross as i128 + gross_delta).max(0) as u128;
+        info.liquidity_net += net_delta;
+        if info.liquidity_gross == 0 {
+            self.ticks.remove(&tick);
+        }
+    }
+
+    fn accrue_fees(&mut self, position_id: u64) {
+        let (tick_lower, tick_upper) = match self.positions.get(&position_id) {
+            Some(position) => (position.tick_lower, position.tick_upper),
+            None => return,
+        };
+        let (inside_a, inside_b) = self.fee_growth_inside(tick_lower, tick_upper);
+        if let Some(position) = self.positions.get_mut(&position_id) {
+            let liquidity = position.liquidity as f64;
+            position.tokens_owed_a += ((inside_a - position.fee_growth_inside_last_a) * liquidity).max(0.0) as u64;
+            position.tokens_owed_b += ((inside_b - position.fee_growth_inside_last_b) * liquidity).max(0.0) as u64;
+            position.fee_growth_inside_last_a = inside_a;
+            position.fee_growth_inside_last_b = inside_b;
+        }
+    }
+
+    fn check_ticks(&self, tick_lower: i32, tick_upper: i32) -> TribeResult<()> {
+        if tick_lower >= tick_upper || tick_lower < MIN_TICK || tick_upper > MAX_TICK {
+            return Err(TribeError::Contract("Invalid tick range".to_string()));
+        }
+        if tick_lower % self.tick_spacing != 0 || tick_upper % self.tick_spacing != 0 {
+            return Err(TribeError::Contract(format!(
+                "Ticks must be multiples of {}", self.tick_spacing
+            )));
+        }
+        Ok(())
+    }
+
+    fn check_owner(&self, position_id: u64, provider: &str) -> TribeResult<()> {
+        match self.positions.get(&position_id) {
+            Some(position) if position.provider == provider => Ok(()),
+            Some(_) => Err(TribeError::Contract("Not the position owner".to_string())),
+            None => Err(TribeError::Contract("Position not found".to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_in_range_swap_earns_fees() {
+        let mut pool = ConcentratedLiquidity::new(1.0, 10).unwrap();
+        let (narrow, used_a, used_b) = pool.mint("lp1".to_string(), -100, 100, 10000, 10000).unwrap();
+        assert!(used_a <= 10000 && used_b <= 10000);
+        let (far, _, _) = pool.mint("lp2".to_string(), 1000, 2000, 10000, 0).unwrap();
+        assert!(pool.mint("lp3".to_string(), -15, 100, 10, 10).is_err());
+
+        // A narrow range around 1.0 trades almost 1:1 instead of sliding along x*y=k
+        let swap = pool.swap(true, 1000, 0.003).unwrap();
+        assert_eq!(swap.amount_in, 1000);
+        assert!(swap.amount_out > 990, "got {}", swap.amount_out);
+        assert!(pool.price() < 1.0);
+
+        let (fees_a, fees_b) = pool.collect_fees(narrow, "lp1").unwrap();
+        assert!(fees_a >= 2 && fees_b == 0);
+        assert_eq!(pool.collect_fees(far, "lp2").unwrap(), (0, 0));
+        assert!(pool.collect_fees(far, "lp1").is_err());
+    }
+
+    #[test]
+    fn test_swap_stops_at_range_edge() {
+        let mut pool = ConcentratedLiquidity::new(1.0, 10).unwrap();
+        let (position, _, _) = pool.mint("lp1".to_string(), -50, 50, 1000, 1000).unwrap();
+
+        let swap = pool.swap(false, 1_000_000, 0.003).unwrap();
+        assert!(swap.amount_in < 1_000_000);
+        assert_eq!(pool.liquidity, 0);
+        assert_eq!(pool.current_tick, 50);
+
+        let (amount_a, amount_b) = pool.burn(position, "lp1").unwrap();
+        assert_eq!(amount_a, 0);
+        assert!(amount_b > 1000);
+        assert!(pool.ticks.is_empty());
+    }
+}
@@ -2,6 +2,7 @@ use tribechain_core::{TribeResult, TribeError};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use chrono::{DateTime, Utc};
+use crate::abi::AbiValue;
 
 /// Contract types supported by the system
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -10,6 +11,9 @@ pub enum ContractType {
     Staking,
     Liquidity,
     TensorCompute,
+    Multisig,
+    OrderBook,
+    Proxy, // Delegates to an implementation contract
     Custom,
 }
 
@@ -38,6 +42,15 @@ pub struct ContractMetadata {
     pub source_url: Option<String>,
     pub abi: Vec<MethodSignature>,
     pub events: Vec<EventSignature>,
+    #[serde(default)]
+    pub storage_layout: Vec<StorageSlot>,
+}
+
+/// Named, typed storage slot; upgrades may only append to the layout
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StorageSlot {
+    pub name: String,
+    pub slot_type: ParameterType,
 }
 
 /// Contract state information
@@ -51,6 +64,8 @@ pub struct ContractState {
     pub last_updated: DateTime<Utc>,
     pub execution_count: u64,
     pub gas_consumed: u64,
+    #[serde(default)]
+    pub rent_paid_through: u64, // Block height storage rent is paid up to
 }
 
 /// Method signature for ABI
@@ -81,7 +96,7 @@ pub struct Parameter {
 }
 
 /// Parameter types
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ParameterType {
     Uint8,
     Uint16,
@@ -101,6 +116,7 @@ pub enum ParameterType {
     Address,
     Array(Box<ParameterType>),
     Tuple(Vec<ParameterType>),
+    Tensor, // Shape plus f32 elements
 }
 
 /// Contract deployment structure
@@ -179,6 +195,7 @@ impl Contract {
                 last_updated: Utc::now(),
                 execution_count: 0,
                 gas_consumed: 0,
+                rent_paid_through: 0,
             },
         }
     }
@@ -205,7 +222,7 @@ impl Contract {
     /// Pause contract execution
     pub fn pause(&mut self, caller: &str) -> TribeResult<()> {
         if caller != self.state.owner {
-            return Err(TribeError::InvalidOperation("Only owner can pause contract".to_string()));
+            return Err(TribeError::Contract("Only owner can pause contract".to_string()));
         }
         self.state.is_paused = true;
         self.state.last_updated = Utc::now();
@@ -215,7 +232,7 @@ impl Contract {
     /// Resume contract execution
     pub fn resume(&mut self, caller: &str) -> TribeResult<()> {
         if caller != self.state.owner {
-            return Err(TribeError::InvalidOperation("Only owner can resume contract".to_string()));
+            return Err(TribeError::Contract("Only owner can resume contract".to_string()));
         }
         self.state.is_paused = false;
         self.state.last_updated = Utc::now();
@@ -225,7 +242,7 @@ impl Contract {
     /// Transfer ownership
     pub fn transfer_ownership(&mut self, current_owner: &str, new_owner: String) -> TribeResult<()> {
         if current_owner != self.state.owner {
-            return Err(TribeError::InvalidOperation("Only current owner can transfer ownership".to_string()));
+            return Err(TribeError::Contract("Only current owner can transfer ownership".to_string()));
         }
         self.state.owner = new_owner;
         self.state.last_updated = Utc::now();
@@ -252,25 +269,28 @@ impl Contract {
     /// Validate method call
     pub fn validate_method_call(&self, call: &ContractCall) -> TribeResult<()> {
         if !self.is_callable() {
-            return Err(TribeError::InvalidOperation("Contract is not callable".to_string()));
+            return Err(TribeError::Contract("Contract is not callable".to_string()));
         }
 
         if let Some(method_sig) = self.get_method_signature(&call.method) {
             // Validate gas limit
             if let Some(gas_limit) = call.gas_limit {
                 if gas_limit < method_sig.gas_estimate {
-                    return Err(TribeError::InvalidOperation("Insufficient gas limit".to_string()));
+                    return Err(TribeError::Contract("Insufficient gas limit".to_string()));
                 }
             }
 
             // Validate value for payable methods
             if call.value > 0 && !method_sig.is_payable {
-                return Err(TribeError::InvalidOperation("Method is not payable".to_string()));
+                return Err(TribeError::Contract("Method is not payable".to_string()));
             }
 
+            // Arguments must decode against the published inputs
+            method_sig.decode_inputs(&call.args)?;
+
             Ok(())
         } else {
-            Err(TribeError::InvalidOperation("Method not found".to_string()))
+            Err(TribeError::Contract("Method not found".to_string()))
         }
     }
 
@@ -288,6 +308,9 @@ impl Contract {
             "Staking" => matches!(self.contract_type, ContractType::Staking),
             "Liquidity" => matches!(self.contract_type, ContractType::Liquidity),
             "TensorCompute" => matches!(self.contract_type, ContractType::TensorCompute),
+            "Multisig" => matches!(self.contract_type, ContractType::Multisig),
+            "OrderBook" => matches!(self.contract_type, ContractType::OrderBook),
+            "Proxy" => matches!(self.contract_type, ContractType::Proxy),
             _ => false,
         }
     }
@@ -345,16 +368,16 @@ impl ContractDeployment {
     /// Validate deployment
     pub fn validate(&self) -> TribeResult<()> {
         if self.code.is_empty() {
-            return Err(TribeError::InvalidOperation("Contract code cannot be empty".to_string()));
+            return Err(TribeError::Contract("Contract code cannot be empty".to_string()));
         }
 
         if self.deployer.is_empty() {
-            return Err(TribeError::InvalidOperation("Deployer address cannot be empty".to_string()));
+            return Err(TribeError::Contract("Deployer address cannot be empty".to_string()));
         }
 
         // Check code size limits (1MB)
         if self.code.len() > 1024 * 1024 {
-            return Err(TribeError::InvalidOperation("Contract code too large".to_string()));
+            return Err(TribeError::Contract("Contract code too large".to_string()));
         }
 
         Ok(())
@@ -392,6 +415,12 @@ impl ContractCall {
         self
     }
 
+    /// Set arguments, ABI-encoded against `method`'s inputs
+    pub fn with_abi_args(mut self, method: &MethodSignature, values: &[AbiValue]) -> TribeResult<Self> {
+        self.args = method.encode_inputs(values)?;
+        Ok(self)
+    }
+
     /// Set nonce
     pub fn with_nonce(mut self, nonce: u64) -> Self {
         self.nonce = nonce;
@@ -401,15 +430,15 @@ impl ContractCall {
     /// Validate call
     pub fn validate(&self) -> TribeResult<()> {
         if self.contract_address.is_empty() {
-            return Err(TribeError::InvalidOperation("Contract address cannot be empty".to_string()));
+            return Err(TribeError::Contract("Contract address cannot be empty".to_string()));
         }
 
         if self.method.is_empty() {
-            return Err(TribeError::InvalidOperation("Method name cannot be empty".to_string()));
+            return Err(TribeError::Contract("Method name cannot be empty".to_string()));
         }
 
         if self.caller.is_empty() {
-            return Err(TribeError::InvalidOperation("Caller address cannot be empty".to_string()));
+            return Err(TribeError::Contract("Caller address cannot be empty".to_string()));
         }
 
         Ok(())
@@ -427,7 +456,37 @@ impl Default for ContractMetadata {
             source_url: None,
             abi: Vec::new(),
             events: Vec::new(),
+            storage_layout: Vec::new(),
+        }
+    }
+}
+
+impl ContractMetadata {
+    /// Check that `new` keeps every existing storage slot in place, so an
+    /// upgraded implementation reads the state the old one wrote
+    pub fn check_layout_compatible(&self, new: &ContractMetadata) -> TribeResult<()> {
+        for (index, slot) in self.storage_layout.iter().enumerate() {
+            match new.storage_layout.get(index) {
+                Some(new_slot) if new_slot == slot => {}
+                Some(new_slot) => {
+                    return Err(TribeError::Contract(format!(
+                        "Storage slot {} changed from {} {:?} to {} {:?}",
+                        index, slot.name, slot.slot_type, new_slot.name, new_slot.slot_type
+                    )));
+                }
+                None => {
+                    return Err(TribeError::Contract(format!("Storage slot {} ({}) removed", index, slot.name)));
+                }
+            }
         }
+        Ok(())
+    }
+}
+
+impl StorageSlot {
+    /// Create a new storage slot
+    pub fn new(name: String, slot_type: ParameterType) -> Self {
+        Self { name, slot_type }
     }
 }
 
@@ -0,0 +1,604 @@
+use tribechain_core::{TribeResult, TribeError, StateTrie, StateProof, FinalityValidator, TensorTask};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use sha2::{Digest, Sha256};
+
+/// Counterparty commitment roots a light client keeps for proofs
+pub const MAX_TRUSTED_ROOTS: usize = 256;
+
+/// Accounts holding tokens sent out over a channel, and tensor tasks that came in on one
+pub const IBC_ESCROW_PREFIX: &str = "ibc/escrow/";
+
+/// Value of a live key in the commitment store; retired keys are set to 0
+const COMMITTED: u64 = 1;
+const RETIRED: u64 = 0;
+
+fn ibc_error(message: impl Into<String>) -> TribeError {
+    TribeError::Bridge(message.into())
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+/// Stage of the channel opening handshake
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChannelState {
+    Init,
+    TryOpen,
+    Open,
+}
+
+impl ChannelState {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ChannelState::Init => "init",
+            ChannelState::TryOpen => "tryopen",
+            ChannelState::Open => "open",
+        }
+    }
+}
+
+/// Store key proving a channel end is in `state` facing `counterparty`
+pub fn channel_key(channel_id: &str, state: ChannelState, counterparty: Option<&str>) -> String {
+    format!("channels/{}/{}/{}", channel_id, state.as_str(), counterparty.unwrap_or(""))
+}
+
+/// Store key proving `packet` was sent
+pub fn commitment_key(packet: &Packet) -> String {
+    format!("commitments/{}/{}/{}", packet.source_channel, packet.sequence, packet.commitment())
+}
+
+/// Store key proving a packet was received; its absence proves a timeout
+pub fn receipt_key(channel_id: &str, sequence: u64) -> String {
+    format!("receipts/{}/{}", channel_id, sequence)
+}
+
+/// Store key proving how a received packet was acknowledged
+pub fn ack_key(channel_id: &str, sequence: u64, ack: &Acknowledgement) -> String {
+    format!("acks/{}/{}/{}", channel_id, sequence, ack.digest())
+}
+
+/// Escrow account of `channel_id`; it also mints the channel's vouchers
+pub fn ibc_escrow_address(channel_id: &str) -> String {
+    format!("{}{}", IBC_ESCROW_PREFIX, channel_id)
+}
+
+/// Header a chain's validators sign for the light clients other chains keep of it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IbcHeader {
+    pub chain_id: u64,
+    pub height: u64,
+    pub block_hash: String,
+    pub commitment_root: String, // Root of the chain's IBC commitment store at `height`
+    pub next_validators: Option<BTreeMap<String, FinalityValidator>>, // Set signing later headers, when it changes
+}
+
+impl IbcHeader {
+    pub fn digest(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.chain_id.to_le_bytes());
+        hasher.update(self.height.to_le_bytes());
+        hasher.update(self.block_hash.as_bytes());
+        hasher.update(self.commitment_root.as_bytes());
+        hasher.update(serde_json::to_vec(&self.next_validators).unwrap_or_default());
+        hex::encode(hasher.finalize())
+    }
+}
+
+/// A header with its validators' signatures, as relayers submit it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedHeader {
+    pub header: IbcHeader,
+    pub signatures: BTreeMap<String, String>, // Validator -> signature over the header digest
+}
+
+impl SignedHeader {
+    pub fn new(header: IbcHeader) -> Self {
+        Self { header, signatures: BTreeMap::new() }
+    }
+
+    /// Add `validator`'s signature (simplified - in real implementation would use proper cryptography)
+    pub fn sign(&mut self, validator: &str, private_key: &str) {
+        let signature = Self::signature_for(&self.header.digest(), private_key);
+        self.signatures.insert(validator.to_string(), signature);
+    }
+
+    fn signature_for(digest: &str, key: &str) -> String {
+        sha256_hex(format!("{}{}", digest, key).as_bytes())
+    }
+
+    /// Stake of `validators` with a valid signature on the header
+    pub fn signed_stake(&self, validators: &HashMap<String, FinalityValidator>) -> u64 {
+        let digest = self.header.digest();
+        self.signatures.iter()
+            .filter_map(|(name, signature)| validators.get(name).map(|validator| (validator, signature)))
+            .filter(|(validator, signature)| **signature == Self::signature_for(&digest, &validator.public_key))
+            .map(|(validator, _)| validator.stake)
+            .sum()
+    }
+}
+
+/// Another TribeChain tracked by its validator-signed headers. Headers backed by 2/3
+/// of the trusted stake are accepted, and their commitment roots verify proofs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LightClient {
+    pub client_id: String,
+    pub chain_id: u64,
+    pub validators: HashMap<String, FinalityValidator>,
+    pub latest_height: u64,
+    pub roots: BTreeMap<u64, String>, // Counterparty height -> commitment root
+    pub frozen: bool, // Set on conflicting headers for one height; nothing verifies after
+}
+
+impl LightClient {
+    /// Start from a header trusted out of band, e.g. read from the counterparty's RPC
+    pub fn new(client_id: String, validators: HashMap<String, FinalityValidator>, trusted: &IbcHeader) -> TribeResult<Self> {
+        if validators.values().map(|validator| validator.stake).sum::<u64>() == 0 {
+            return Err(ibc_error("Light client needs validators with stake"));
+        }
+        Ok(Self {
+            client_id,
+            chain_id: trusted.chain_id,
+            validators,
+            latest_height: trusted.height,
+            roots: BTreeMap::from([(trusted.height, trusted.commitment_root.clone())]),
+            frozen: false,
+        })
+    }
+
+    /// Accept a newer header signed by the trusted validators, adopting its validator
+    /// set if it names one. A second, different header for a known height is proof the
+    /// validators equivocated and freezes the client.
+    pub fn update(&mut self, signed: &SignedHeader) -> TribeResult<()> {
+        let header = &signed.header;
+        if self.frozen {
+            return Err(ibc_error(format!("Client {} is frozen", self.client_id)));
+        }
+        if header.chain_id != self.chain_id {
+            return Err(ibc_error(format!("Header is for chain {}, not {}", header.chain_id, self.chain_id)));
+        }
+        let total = self.validators.values().map(|validator| validator.stake).sum::<u64>() as u128;
+        if (signed.signed_stake(&self.validators) as u128) * 3 < total * 2 {
+            return Err(ibc_error("Header lacks signatures from 2/3 of validator stake"));
+        }
+
+        if let Some(root) = self.roots.get(&header.height) {
+            if *root != header.commitment_root {
+                self.frozen = true;
+                return Err(ibc_error(format!("Conflicting headers at height {}; client {} frozen", header.height, self.client_id)));
+            }
+            return Ok(());
+        }
+        if header.height < self.latest_height {
+            return Err(ibc_error(format!("Header {} is older than the client's latest, {}", header.height, self.latest_height)));
+        }
+
+        self.roots.insert(header.height, header.commitment_root.clone());
+        while self.roots.len() > MAX_TRUSTED_ROOTS {
+            self.roots.pop_first();
+        }
+        self.latest_height = header.height;
+        if let Some(next) = &header.next_validators {
+            self.validators = next.clone().into_iter().collect();
+        }
+        Ok(())
+    }
+
+    fn checked_root(&self, height: u64, key: &str, proof: &StateProof) -> TribeResult<()> {
+        if self.frozen {
+            return Err(ibc_error(format!("Client {} is frozen", self.client_id)));
+        }
+        let root = self.roots.get(&height)
+            .ok_or_else(|| ibc_error(format!("No trusted header at height {}", height)))?;
+        if proof.address != key || proof.root != *root || !proof.verify() {
+            return Err(ibc_error(format!("Invalid proof for {}", key)));
+        }
+        Ok(())
+    }
+
+    /// Check `key` was committed on the counterparty at `height`
+    pub fn verify_membership(&self, height: u64, key: &str, proof: &StateProof) -> TribeResult<()> {
+        self.checked_root(height, key, proof)?;
+        if proof.balance != Some(COMMITTED) {
+            return Err(ibc_error(format!("{} is not committed", key)));
+        }
+        Ok(())
+    }
+
+    /// Check `key` was never committed on the counterparty as of `height`
+    pub fn verify_non_membership(&self, height: u64, key: &str, proof: &StateProof) -> TribeResult<()> {
+        self.checked_root(height, key, proof)?;
+        if proof.balance.is_some() {
+            return Err(ibc_error(format!("{} is committed", key)));
+        }
+        Ok(())
+    }
+}
+
+/// One end of a channel between this chain and a counterparty
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Channel {
+    pub channel_id: String,
+    pub client_id: String, // Light client of the counterparty chain
+    pub counterparty_channel_id: Option<String>, // Unknown until the counterparty answers the handshake
+    pub state: ChannelState,
+    pub next_send_sequence: u64,
+}
+
+/// Tokens carried by a packet. `denom` gains a `<channel>/` prefix for every hop away
+/// from the chain the token is native to.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Coin {
+    pub denom: String,
+    pub symbol: String,
+    pub decimals: u8,
+    pub amount: u64,
+}
+
+/// What a packet asks the destination chain to do
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum PacketData {
+    /// Credit `receiver` with `coin`
+    Transfer { coin: Coin, sender: String, receiver: String },
+    /// Mine `task` on the destination chain for a reward of `coin`
+    TensorTask { task: TensorTask, coin: Coin, timeout_blocks: u64 },
+    /// Result of an outsourced tensor task, sent back to the chain it came from
+    TensorResult { task_id: String, miner: String, result: Vec<f32> },
+}
+
+/// Data sent over a channel, proven to the destination by its commitment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Packet {
+    pub sequence: u64,
+    pub source_channel: String,
+    pub destination_channel: String,
+    pub data: PacketData,
+    pub timeout_height: u64, // Destination height from which the packet can't be received
+}
+
+impl Packet {
+    pub fn commitment(&self) -> String {
+        sha256_hex(&serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    /// Identifies the packet among those sent from its source chain
+    pub fn key(&self) -> String {
+        format!("{}/{}", self.source_channel, self.sequence)
+    }
+}
+
+/// The destination's answer to a packet, proven back to the source
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Acknowledgement {
+    Success(String),
+    Error(String), // The source refunds the packet's tokens
+}
+
+impl Acknowledgement {
+    pub fn digest(&self) -> String {
+        sha256_hex(&serde_json::to_vec(self).unwrap_or_default())
+    }
+}
+
+/// A tensor task sent to another chain, waiting for its result
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutsourcedTask {
+    pub channel_id: String,
+    pub requester: String,
+    pub miner: Option<String>, // Miner on the other chain, once the result is back
+    pub result: Option<Vec<f32>>,
+}
+
+/// Light clients, channels and the commitment store counterparties verify against
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IbcHandler {
+    pub clients: HashMap<String, LightClient>,
+    pub channels: HashMap<String, Channel>,
+    pub store: StateTrie, // Commitments, receipts, acks and channel ends; its root goes in headers
+    pub sent: HashMap<String, Packet>, // Packet key -> packet awaiting an ack or timeout
+    pub acks: HashMap<String, Acknowledgement>, // Packet key -> ack written here, for relayers
+    pub vouchers: HashMap<String, String>, // Denom with channel prefixes -> local token id
+    pub outsourced: HashMap<String, OutsourcedTask>, // Task id -> task sent away
+    pub results_returned: HashSet<String>, // Task ids whose results went back
+    next_client: u64,
+    next_channel: u64,
+}
+
+impl IbcHandler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn commitment_root(&self) -> String {
+        self.store.root()
+    }
+
+    /// Header for this chain at `height`, for its validators to sign
+    pub fn header(&self, chain_id: u64, height: u64, block_hash: &str) -> IbcHeader {
+        IbcHeader {
+            chain_id,
+            height,
+            block_hash: block_hash.to_string(),
+            commitment_root: self.commitment_root(),
+            next_validators: None,
+        }
+    }
+
+    /// Proof of `key`, or of its absence, under `commitment_root()`
+    pub fn prove(&self, key: &str) -> StateProof {
+        self.store.proof(key)
+    }
+
+    fn commit(&mut self, key: String) {
+        self.store.insert(key, COMMITTED);
+    }
+
+    fn retire(&mut self, key: String) {
+        self.store.insert(key, RETIRED);
+    }
+
+    pub fn create_client(&mut self, validators: HashMap<String, FinalityValidator>, trusted: &IbcHeader) -> TribeResult<String> {
+        let client_id = format!("client-{}", self.next_client);
+        let client = LightClient::new(client_id.clone(), validators, trusted)?;
+        self.next_client += 1;
+        self.clients.insert(client_id.clone(), client);
+        Ok(client_id)
+    }
+
+    pub fn update_client(&mut self, client_id: &str, signed: &SignedHeader) -> TribeResult<()> {
+        self.clients.get_mut(client_id)
+            .ok_or_else(|| ibc_error(format!("Unknown client {}", client_id)))?
+            .update(signed)
+    }
+
+    fn client(&self, client_id: &str) -> TribeResult<&LightClient> {
+        self.clients.get(client_id).ok_or_else(|| ibc_error(format!("Unknown client {}", client_id)))
+    }
+
+    pub fn channel(&self, channel_id: &str) -> TribeResult<&Channel> {
+        self.channels.get(channel_id).ok_or_else(|| ibc_error(format!("Unknown channel {}", channel_id)))
+    }
+
+    fn new_channel(&mut self, client_id: &str, counterparty: Option<String>, state: ChannelState) -> TribeResult<String> {
+        self.client(client_id)?;
+        let channel_id = format!("channel-{}", self.next_channel);
+        self.next_channel += 1;
+        self.commit(channel_key(&channel_id, state, counterparty.as_deref()));
+        self.channels.insert(channel_id.clone(), Channel {
+            channel_id: channel_id.clone(),
+            client_id: client_id.to_string(),
+            counterparty_channel_id: counterparty,
+            state,
+            next_send_sequence: 1,
+        });
+        Ok(channel_id)
+    }
+
+    /// Move a channel to `state`, retiring the proof of its previous one
+    fn advance_channel(&mut self, channel_id: &str, counterparty: &str, state: ChannelState) {
+        let Some(channel) = self.channels.get_mut(channel_id) else { return };
+        let previous = channel_key(channel_id, channel.state, channel.counterparty_channel_id.as_deref());
+        channel.state = state;
+        channel.counterparty_channel_id = Some(counterparty.to_string());
+        self.retire(previous);
+        self.commit(channel_key(channel_id, state, Some(counterparty)));
+    }
+
+    /// Handshake step 1, on chain A: propose a channel to the chain `client_id` tracks
+    pub fn chan_open_init(&mut self, client_id: &str) -> TribeResult<String> {
+        self.new_channel(client_id, None, ChannelState::Init)
+    }
+
+    /// Step 2, on chain B: answer A's proven `Init`
+    pub fn chan_open_try(&mut self, client_id: &str, counterparty: &str, proof_height: u64, proof: &StateProof) -> TribeResult<String> {
+        self.client(client_id)?.verify_membership(proof_height, &channel_key(counterparty, ChannelState::Init, None), proof)?;
+        self.new_channel(client_id, Some(counterparty.to_string()), ChannelState::TryOpen)
+    }
+
+    /// Step 3, on chain A: open once B's `TryOpen` is proven
+    pub fn chan_open_ack(&mut self, channel_id: &str, counterparty: &str, proof_height: u64, proof: &StateProof) -> TribeResult<()> {
+        let channel = self.channel(channel_id)?;
+        if channel.state != ChannelState::Init {
+            return Err(ibc_error(format!("Channel {} is not awaiting an ack", channel_id)));
+        }
+        let key = channel_key(counterparty, ChannelState::TryOpen, Some(channel_id));
+        self.client(&channel.client_id)?.verify_membership(proof_height, &key, proof)?;
+        self.advance_channel(channel_id, counterparty, ChannelState::Open);
+        Ok(())
+    }
+
+    /// Step 4, on chain B: open once A is proven open
+    pub fn chan_open_confirm(&mut self, channel_id: &str, proof_height: u64, proof: &StateProof) -> TribeResult<()> {
+        let channel = self.channel(channel_id)?;
+        let (ChannelState::TryOpen, Some(counterparty)) = (channel.state, channel.counterparty_channel_id.clone()) else {
+            return Err(ibc_error(format!("Channel {} is not awaiting confirmation", channel_id)));
+        };
+        let key = channel_key(&counterparty, ChannelState::Open, Some(channel_id));
+        self.client(&channel.client_id)?.verify_membership(proof_height, &key, proof)?;
+        self.advance_channel(channel_id, &counterparty, ChannelState::Open);
+        Ok(())
+    }
+
+    fn open_channel(&self, channel_id: &str) -> TribeResult<(&Channel, String)> {
+        let channel = self.channel(channel_id)?;
+        match (&channel.state, &channel.counterparty_channel_id) {
+            (ChannelState::Open, Some(counterparty)) => Ok((channel, counterparty.clone())),
+            _ => Err(ibc_error(format!("Channel {} is not open", channel_id))),
+        }
+    }
+
+    /// Commit a packet for relayers to deliver
+    pub fn send_packet(&mut self, channel_id: &str, data: PacketData, timeout_height: u64) -> TribeResult<Packet> {
+        let (_, counterparty) = self.open_channel(channel_id)?;
+        let channel = self.channels.get_mut(channel_id).expect("open channel exists");
+        let packet = Packet {
+            sequence: channel.next_send_sequence,
+            source_channel: channel_id.to_string(),
+            destination_channel: counterparty,
+            data,
+            timeout_height,
+        };
+        channel.next_send_sequence += 1;
+        self.commit(commitment_key(&packet));
+        self.sent.insert(packet.key(), packet.clone());
+        Ok(packet)
+    }
+
+    /// Accept a packet proven sent by the counterparty, recording its receipt.
+    /// `block_height` is this chain's; the packet must arrive before its timeout.
+    pub fn recv_packet(&mut self, packet: &Packet, proof_height: u64, proof: &StateProof, block_height: u64) -> TribeResult<()> {
+        let (channel, counterparty) = self.open_channel(&packet.destination_channel)?;
+        if counterparty != packet.source_channel {
+            return Err(ibc_error(format!("Channel {} is not connected to {}", packet.destination_channel, packet.source_channel)));
+        }
+        if block_height >= packet.timeout_height {
+            return Err(ibc_error(format!("Packet {} timed out at height {}", packet.key(), packet.timeout_height)));
+        }
+        let receipt = receipt_key(&packet.destination_channel, packet.sequence);
+        if self.store.get(&receipt).is_some() {
+            return Err(ibc_error(format!("Packet {} was already received", packet.key())));
+        }
+        self.client(&channel.client_id)?.verify_membership(proof_height, &commitment_key(packet), proof)?;
+        self.commit(receipt);
+        Ok(())
+    }
+
+    pub fn write_acknowledgement(&mut self, packet: &Packet, ack: Acknowledgement) {
+        self.commit(ack_key(&packet.destination_channel, packet.sequence, &ack));
+        self.acks.insert(packet.key(), ack);
+    }
+
+    /// Packet this chain sent and still awaits, if `packet` matches it
+    fn take_sent(&mut self, packet: &Packet) -> TribeResult<Packet> {
+        match self.sent.get(&packet.key()) {
+            Some(sent) if sent.commitment() == packet.commitment() => {}
+            _ => return Err(ibc_error(format!("Packet {} is not awaiting an ack or timeout", packet.key()))),
+        }
+        self.retire(commitment_key(packet));
+        Ok(self.sent.remove(&packet.key()).expect("sent packet exists"))
+    }
+
+    /// Settle a sent packet with the counterparty's proven acknowledgement
+    pub fn acknowledge_packet(&mut self, packet: &Packet, ack: &Acknowledgement, proof_height: u64, proof: &StateProof) -> TribeResult<Packet> {
+        let channel = self.channel(&packet.source_channel)?;
+        let key = ack_key(&packet.destination_channel, packet.sequence, ack);
+        self.client(&channel.client_id)?.verify_membership(proof_height, &key, proof)?;
+        self.take_sent(packet)
+    }
+
+    /// Settle a sent packet the counterparty is proven not to have received by its timeout
+    pub fn timeout_packet(&mut self, packet: &Packet, proof_height: u64, proof: &StateProof) -> TribeResult<Packet> {
+        if proof_height < packet.timeout_height {
+            return Err(ibc_error(format!("Packet {} times out at height {}", packet.key(), packet.timeout_height)));
+        }
+        let channel = self.channel(&packet.source_channel)?;
+        let key = receipt_key(&packet.destination_channel, packet.sequence);
+        self.client(&channel.client_id)?.verify_non_membership(proof_height, &key, proof)?;
+        self.take_sent(packet)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validators() -> HashMap<String, FinalityValidator> {
+        ["v1", "v2", "v3"].iter()
+            .map(|name| (name.to_string(), FinalityValidator { stake: 100, public_key: format!("{}_key", name) }))
+            .collect()
+    }
+
+    /// Header of `handler`'s chain signed by `signers`
+    fn signed(handler: &IbcHandler, chain_id: u64, height: u64, signers: &[&str]) -> SignedHeader {
+        let mut header = SignedHeader::new(handler.header(chain_id, height, &format!("hash{}", height)));
+        for signer in signers {
+            header.sign(signer, &format!("{}_key", signer));
+        }
+        header
+    }
+
+    fn transfer(amount: u64) -> PacketData {
+        let coin = Coin { denom: "tribe".to_string(), symbol: "TRIBE".to_string(), decimals: 6, amount };
+        PacketData::Transfer { coin, sender: "alice".to_string(), receiver: "bob".to_string() }
+    }
+
+    #[test]
+    fn test_light_client_updates() {
+        let chain = IbcHandler::new();
+        let mut client = LightClient::new("client-0".to_string(), validators(), &chain.header(2, 1, "hash1")).unwrap();
+
+        assert!(client.update(&signed(&chain, 2, 2, &["v1"])).is_err());
+        assert!(client.update(&signed(&chain, 3, 2, &["v1", "v2"])).is_err());
+        client.update(&signed(&chain, 2, 2, &["v1", "v2"])).unwrap();
+        assert_eq!(client.latest_height, 2);
+
+        // A forged signature doesn't count towards the quorum
+        let mut forged = signed(&chain, 2, 3, &["v1"]);
+        forged.signatures.insert("v2".to_string(), "00".repeat(32));
+        assert!(client.update(&forged).is_err());
+
+        // Equivocation freezes the client
+        let mut other = IbcHandler::new();
+        other.commit("anything".to_string());
+        assert!(client.update(&signed(&other, 2, 2, &["v1", "v2", "v3"])).is_err());
+        assert!(client.frozen);
+        assert!(client.update(&signed(&chain, 2, 4, &["v1", "v2", "v3"])).is_err());
+    }
+
+    #[test]
+    fn test_handshake_and_packet_lifecycle() {
+        let (mut a, mut b) = (IbcHandler::new(), IbcHandler::new());
+        let client_on_a = a.create_client(validators(), &b.header(2, 1, "hash1")).unwrap();
+        let client_on_b = b.create_client(validators(), &a.header(1, 1, "hash1")).unwrap();
+        let mut heights = (1, 1);
+        // Relay the current state of a chain to the other's client, returning the proof height
+        macro_rules! relay {
+            (a -> b) => {{ heights.0 += 1; b.update_client(&client_on_b, &signed(&a, 1, heights.0, &["v1", "v2"])).unwrap(); heights.0 }};
+            (b -> a) => {{ heights.1 += 1; a.update_client(&client_on_a, &signed(&b, 2, heights.1, &["v1", "v2"])).unwrap(); heights.1 }};
+        }
+
+        let chan_a = a.chan_open_init(&client_on_a).unwrap();
+        let height = relay!(a -> b);
+        let proof = a.prove(&channel_key(&chan_a, ChannelState::Init, None));
+        assert!(b.chan_open_try(&client_on_b, "channel-9", height, &proof).is_err());
+        let chan_b = b.chan_open_try(&client_on_b, &chan_a, height, &proof).unwrap();
+        assert!(a.send_packet(&chan_a, transfer(5), 100).is_err());
+
+        let height = relay!(b -> a);
+        a.chan_open_ack(&chan_a, &chan_b, height, &b.prove(&channel_key(&chan_b, ChannelState::TryOpen, Some(&chan_a)))).unwrap();
+        let height = relay!(a -> b);
+        b.chan_open_confirm(&chan_b, height, &a.prove(&channel_key(&chan_a, ChannelState::Open, Some(&chan_b)))).unwrap();
+        assert_eq!(b.channel(&chan_b).unwrap().state, ChannelState::Open);
+
+        // Received once, then acknowledged back
+        let packet = a.send_packet(&chan_a, transfer(5), 100).unwrap();
+        let height = relay!(a -> b);
+        let proof = a.prove(&commitment_key(&packet));
+        let mut forged = packet.clone();
+        forged.data = transfer(500);
+        assert!(b.recv_packet(&forged, height, &proof, 10).is_err());
+        b.recv_packet(&packet, height, &proof, 10).unwrap();
+        assert!(b.recv_packet(&packet, height, &proof, 10).is_err());
+        let ack = Acknowledgement::Success(String::new());
+        b.write_acknowledgement(&packet, ack.clone());
+
+        let height = relay!(b -> a);
+        let proof = b.prove(&ack_key(&chan_b, packet.sequence, &ack));
+        assert!(a.acknowledge_packet(&packet, &Acknowledgement::Error("no".to_string()), height, &proof).is_err());
+        a.acknowledge_packet(&packet, &ack, height, &proof).unwrap();
+        assert!(a.acknowledge_packet(&packet, &ack, height, &proof).is_err());
+
+        // Not received by its timeout height
+        let late = a.send_packet(&chan_a, transfer(7), 8).unwrap();
+        let height = relay!(a -> b);
+        assert!(b.recv_packet(&late, height, &a.prove(&commitment_key(&late)), 8).is_err());
+        let early = relay!(b -> a);
+        let proof = b.prove(&receipt_key(&chan_b, late.sequence));
+        assert!(a.timeout_packet(&late, early, &proof).is_err());
+        heights.1 = 8;
+        let height = relay!(b -> a);
+        a.timeout_packet(&late, height, &b.prove(&receipt_key(&chan_b, late.sequence))).unwrap();
+        assert!(a.sent.is_empty());
+    }
+}
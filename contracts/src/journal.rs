@@ -0,0 +1,158 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use tribechain_core::TensorTask;
+use crate::{
+    Contract, ContractEngine, LendingMarket, LiquidityPool, MultisigWallet, OrderBook,
+    StakingContract, TensorEscrow, Timelock, TokenContract, VestingContract, BridgeContract, IbcHandler,
+};
+
+/// Blocks of undo history kept for reorgs by default
+pub const DEFAULT_JOURNAL_DEPTH: usize = 100;
+
+/// A piece of engine state the journal can snapshot
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum JournalKey {
+    Contract(String),
+    Token(String),
+    Staking(String),
+    Pool(String),
+    OrderBook(String),
+    Lending(String),
+    Multisig(String),
+    Vesting(String),
+    Timelock(String),
+    TensorEscrow(String),
+    Bridge(String),
+    Ibc,
+    VmStorage,
+    NativeCredits,
+    TensorTaskQueue,
+}
+
+/// State as it was before its first change in a block; `None` if it didn't exist yet
+#[derive(Debug, Clone)]
+pub enum JournalEntry {
+    Contract(String, Option<Contract>),
+    Token(String, Option<TokenContract>),
+    Staking(String, Option<StakingContract>),
+    Pool(String, Option<LiquidityPool>),
+    OrderBook(String, Option<OrderBook>),
+    Lending(String, Option<LendingMarket>),
+    Multisig(String, Option<MultisigWallet>),
+    Vesting(String, Option<VestingContract>),
+    Timelock(String, Option<Timelock>),
+    TensorEscrow(String, Option<TensorEscrow>),
+    Bridge(String, Option<BridgeContract>),
+    Ibc(Box<IbcHandler>),
+    VmStorage(HashMap<String, Vec<u8>>),
+    NativeCredits(HashMap<String, u64>),
+    TensorTaskQueue(Vec<TensorTask>),
+}
+
+/// Undo log for one block
+#[derive(Debug, Clone, Default)]
+pub struct BlockJournal {
+    pub block_height: u64,
+    pub receipt_count: usize, // Pending receipts when the block began
+    pub entries: Vec<JournalEntry>,
+    touched: HashSet<JournalKey>,
+}
+
+/// Undo logs for the open block and recent committed blocks
+#[derive(Debug, Clone)]
+pub struct StateJournal {
+    pub current: Option<BlockJournal>,
+    pub committed: VecDeque<BlockJournal>,
+    pub max_depth: usize,
+}
+
+impl Default for StateJournal {
+    fn default() -> Self {
+        Self {
+            current: None,
+            committed: VecDeque::new(),
+            max_depth: DEFAULT_JOURNAL_DEPTH,
+        }
+    }
+}
+
+fn restore<T>(map: &mut HashMap<String, T>, key: String, previous: Option<T>) {
+    match previous {
+        Some(value) => map.insert(key, value),
+        None => map.remove(&key),
+    };
+}
+
+impl JournalEntry {
+    fn capture(engine: &ContractEngine, key: JournalKey) -> Self {
+        match key {
+            JournalKey::Contract(id) => JournalEntry::Contract(id.clone(), engine.deployed_contracts.get(&id).cloned()),
+            JournalKey::Token(id) => JournalEntry::Token(id.clone(), engine.token_contracts.get(&id).cloned()),
+            JournalKey::Staking(id) => JournalEntry::Staking(id.clone(), engine.staking_contracts.get(&id).cloned()),
+            JournalKey::Pool(id) => JournalEntry::Pool(id.clone(), engine.liquidity_pools.get(&id).cloned()),
+            JournalKey::OrderBook(id) => JournalEntry::OrderBook(id.clone(), engine.order_books.get(&id).cloned()),
+            JournalKey::Lending(id) => JournalEntry::Lending(id.clone(), engine.lending_markets.get(&id).cloned()),
+            JournalKey::Multisig(id) => JournalEntry::Multisig(id.clone(), engine.multisig_wallets.get(&id).cloned()),
+            JournalKey::Vesting(id) => JournalEntry::Vesting(id.clone(), engine.vesting_contracts.get(&id).cloned()),
+            JournalKey::Timelock(id) => JournalEntry::Timelock(id.clone(), engine.timelocks.get(&id).cloned()),
+            JournalKey::TensorEscrow(id) => JournalEntry::TensorEscrow(id.clone(), engine.tensor_escrows.get(&id).cloned()),
+            JournalKey::Bridge(id) => JournalEntry::Bridge(id.clone(), engine.bridges.get(&id).cloned()),
+            JournalKey::Ibc => JournalEntry::Ibc(Box::new(engine.ibc.clone())),
+            JournalKey::VmStorage => JournalEntry::VmStorage(engine.vm.storage.clone()),
+            JournalKey::NativeCredits => JournalEntry::NativeCredits(engine.native_credits.clone()),
+            JournalKey::TensorTaskQueue => JournalEntry::TensorTaskQueue(engine.pending_tensor_tasks.clone()),
+        }
+    }
+
+    fn revert(self, engine: &mut ContractEngine) {
+        match self {
+            JournalEntry::Contract(id, previous) => restore(&mut engine.deployed_contracts, id, previous),
+            JournalEntry::Token(id, previous) => restore(&mut engine.token_contracts, id, previous),
+            JournalEntry::Staking(id, previous) => restore(&mut engine.staking_contracts, id, previous),
+            JournalEntry::Pool(id, previous) => restore(&mut engine.liquidity_pools, id, previous),
+            JournalEntry::OrderBook(id, previous) => restore(&mut engine.order_books, id, previous),
+            JournalEntry::Lending(id, previous) => restore(&mut engine.lending_markets, id, previous),
+            JournalEntry::Multisig(id, previous) => restore(&mut engine.multisig_wallets, id, previous),
+            JournalEntry::Vesting(id, previous) => restore(&mut engine.vesting_contracts, id, previous),
+            JournalEntry::Timelock(id, previous) => restore(&mut engine.timelocks, id, previous),
+            JournalEntry::TensorEscrow(id, previous) => restore(&mut engine.tensor_escrows, id, previous),
+            JournalEntry::Bridge(id, previous) => restore(&mut engine.bridges, id, previous),
+            JournalEntry::Ibc(ibc) => engine.ibc = *ibc,
+            JournalEntry::VmStorage(storage) => engine.vm.storage = storage,
+            JournalEntry::NativeCredits(credits) => engine.native_credits = credits,
+            JournalEntry::TensorTaskQueue(tasks) => engine.pending_tensor_tasks = tasks,
+        }
+    }
+}
+
+impl BlockJournal {
+    pub fn new(block_height: u64, receipt_count: usize) -> Self {
+        Self {
+            block_height,
+            receipt_count,
+            ..Self::default()
+        }
+    }
+
+    /// Undo every change in the block, newest first
+    pub fn revert(self, engine: &mut ContractEngine) {
+        for entry in self.entries.into_iter().rev() {
+            entry.revert(engine);
+        }
+    }
+}
+
+impl ContractEngine {
+    /// Snapshot `key` the first time it changes in the open block
+    pub(crate) fn record(&mut self, key: JournalKey) {
+        let first_touch = match self.journal.current.as_mut() {
+            Some(block) => block.touched.insert(key.clone()),
+            None => false,
+        };
+        if first_touch {
+            let entry = JournalEntry::capture(self, key);
+            if let Some(block) = self.journal.current.as_mut() {
+                block.entries.push(entry);
+            }
+        }
+    }
+}
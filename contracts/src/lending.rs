@@ -0,0 +1,331 @@
+use tribechain_core::{TribeResult, TribeError};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+
+/// Largest share of a debt one liquidation may repay
+pub const CLOSE_FACTOR: f64 = 0.5;
+
+/// A borrower's collateral and debt; debt is stored scaled by the borrow index
+/// at the time it was taken so interest applies without touching every position
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BorrowPosition {
+    pub collateral: u64,
+    pub scaled_debt: f64,
+}
+
+/// Lending market for one asset, borrowed against one collateral token.
+/// Suppliers hold shares of the asset pool, which grows as borrowers pay interest.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LendingMarket {
+    pub id: String,
+    pub asset_token: String,
+    pub collateral_token: String,
+    pub price_pool_id: String, // Pool whose TWAP prices the collateral in the asset
+    pub oracle_window_secs: u64,
+    pub max_ltv: f64, // Borrow limit as a share of collateral value
+    pub liquidation_threshold: f64, // Debt / collateral value above which positions can be liquidated
+    pub liquidation_bonus: f64, // Extra collateral paid to liquidators
+    pub borrow_rate_per_block: f64,
+    pub cash: u64, // Asset held by the market and available to borrow
+    pub total_shares: u64,
+    pub supplier_shares: HashMap<String, u64>,
+    pub borrow_index: f64,
+    pub total_scaled_debt: f64,
+    pub positions: HashMap<String, BorrowPosition>,
+    pub last_accrual_block: u64,
+    pub created_at: DateTime<Utc>,
+}
+
+impl LendingMarket {
+    pub fn new(
+        asset_token: String,
+        collateral_token: String,
+        price_pool_id: String,
+        max_ltv: f64,
+        liquidation_threshold: f64,
+        borrow_rate_per_block: f64,
+    ) -> TribeResult<Self> {
+        if asset_token == collateral_token {
+            return Err(TribeError::Contract("Asset and collateral must differ".to_string()));
+        }
+        if !(max_ltv > 0.0 && max_ltv < liquidation_threshold && liquidation_threshold < 1.0) {
+            return Err(TribeError::Contract("Require 0 < max LTV < liquidation threshold < 1".to_string()));
+        }
+        if borrow_rate_per_block < 0.0 {
+            return Err(TribeError::Contract("Borrow rate cannot be negative".to_string()));
+        }
+
+        let created_at = Utc::now();
+        let mut hasher = Sha256::new();
+        hasher.update(asset_token.as_bytes());
+        hasher.update(collateral_token.as_bytes());
+        hasher.update(created_at.timestamp_nanos_opt().unwrap_or_default().to_le_bytes());
+
+        Ok(Self {
+            id: format!("lending_{}", &hex::encode(hasher.finalize())[..16]),
+            asset_token,
+            collateral_token,
+            price_pool_id,
+            oracle_window_secs: 1800,
+            max_ltv,
+            liquidation_threshold,
+            liquidation_bonus: 0.05,
+            borrow_rate_per_block,
+            cash: 0,
+            total_shares: 0,
+            supplier_shares: HashMap::new(),
+            borrow_index: 1.0,
+            total_scaled_debt: 0.0,
+            positions: HashMap::new(),
+            last_accrual_block: 0,
+            created_at,
+        })
+    }
+
+    /// Apply interest for the blocks since the last accrual
+    pub fn accrue_interest(&mut self, block: u64) {
+        if block > self.last_accrual_block {
+            let blocks = (block - self.last_accrual_block) as f64;
+            self.borrow_index *= 1.0 + self.borrow_rate_per_block * blocks;
+            self.last_accrual_block = block;
+        }
+    }
+
+    pub fn total_borrows(&self) -> u64 {
+        (self.total_scaled_debt * self.borrow_index).round() as u64
+    }
+
+    /// Everything suppliers own: idle cash plus outstanding loans with interest
+    pub fn total_assets(&self) -> u64 {
+        self.cash + self.total_borrows()
+    }
+
+    pub fn debt_of(&self, borrower: &str) -> u64 {
+        self.positions.get(borrower)
+            .map(|position| (position.scaled_debt * self.borrow_index).round() as u64)
+            .unwrap_or(0)
+    }
+
+    pub fn collateral_of(&self, borrower: &str) -> u64 {
+        self.positions.get(borrower).map(|position| position.collateral).unwrap_or(0)
+    }
+
+    /// Asset a supplier could withdraw if the market had the cash
+    pub fn supplied_balance(&self, supplier: &str) -> u64 {
+        let shares = self.supplier_shares.get(supplier).copied().unwrap_or(0);
+        self.shares_to_assets(shares)
+    }
+
+    /// Collateral value over debt, scaled so that below 1.0 the position can be liquidated
+    pub fn health_factor(&self, borrower: &str, collateral_price: f64) -> f64 {
+        let debt = self.debt_of(borrower);
+        if debt == 0 {
+            return f64::INFINITY;
+        }
+        self.collateral_of(borrower) as f64 * collateral_price * self.liquidation_threshold / debt as f64
+    }
+
+    pub fn supply(&mut self, supplier: String, amount: u64, block: u64) -> TribeResult<u64> {
+        if amount == 0 {
+            return Err(TribeError::Contract("Supply amount must be greater than 0".to_string()));
+        }
+        self.accrue_interest(block);
+
+        let total_assets = self.total_assets();
+        let shares = if self.total_shares == 0 || total_assets == 0 {
+            amount
+        } else {
+            (amount as u128 * self.total_shares as u128 / total_assets as u128) as u64
+        };
+        if shares == 0 {
+            return Err(TribeError::Contract("Supply too small".to_string()));
+        }
+
+        self.cash += amount;
+        self.total_shares += shares;
+        *self.supplier_shares.entry(supplier).or_insert(0) += shares;
+        Ok(shares)
+    }
+
+    /// Redeem shares worth `amount` of the asset
+    pub fn withdraw(&mut self, supplier: &str, amount: u64, block: u64) -> TribeResult<u64> {
+        self.accrue_interest(block);
+        if amount > self.supplied_balance(supplier) {
+            return Err(TribeError::Contract("Insufficient supplied balance".to_string()));
+        }
+        if amount > self.cash {
+            return Err(TribeError::Contract("Not enough idle liquidity".to_string()));
+        }
+
+        let total_assets = self.total_assets();
+        let shares = (amount as u128 * self.total_shares as u128).div_ceil(total_assets as u128) as u64;
+        let held = self.supplier_shares.entry(supplier.to_string()).or_insert(0);
+        let shares = shares.min(*held);
+        *held -= shares;
+        self.total_shares -= shares;
+        self.cash -= amount;
+        Ok(amount)
+    }
+
+    pub fn deposit_collateral(&mut self, borrower: String, amount: u64) -> TribeResult<()> {
+        if amount == 0 {
+            return Err(TribeError::Contract("Collateral amount must be greater than 0".to_string()));
+        }
+        self.positions.entry(borrower).or_default().collateral += amount;
+        Ok(())
+    }
+
+    pub fn withdraw_collateral(&mut self, borrower: &str, amount: u64, collateral_price: f64, block: u64) -> TribeResult<()> {
+        self.accrue_interest(block);
+        let collateral = self.collateral_of(borrower);
+        if amount > collateral {
+            return Err(TribeError::Contract("Insufficient collateral".to_string()));
+        }
+        let debt = self.debt_of(borrower);
+        if debt as f64 > self.borrow_limit(collateral - amount, collateral_price) {
+            return Err(TribeError::Contract("Withdrawal would exceed max LTV".to_string()));
+        }
+
+        if let Some(position) = self.positions.get_mut(borrower) {
+            position.collateral -= amount;
+        }
+        Ok(())
+    }
+
+    pub fn borrow(&mut self, borrower: &str, amount: u64, collateral_price: f64, block: u64) -> TribeResult<()> {
+        if amount == 0 {
+            return Err(TribeError::Contract("Borrow amount must be greater than 0".to_string()));
+        }
+        self.accrue_interest(block);
+        if amount > self.cash {
+            return Err(TribeError::Contract("Not enough idle liquidity".to_string()));
+        }
+        let debt = self.debt_of(borrower) + amount;
+        if debt as f64 > self.borrow_limit(self.collateral_of(borrower), collateral_price) {
+            return Err(TribeError::Contract("Borrow would exceed max LTV".to_string()));
+        }
+
+        let scaled = amount as f64 / self.borrow_index;
+        self.positions.entry(borrower.to_string()).or_default().scaled_debt += scaled;
+        self.total_scaled_debt += scaled;
+        self.cash -= amount;
+        Ok(())
+    }
+
+    /// Repay up to `amount`; returns how much was actually owed and repaid
+    pub fn repay(&mut self, borrower: &str, amount: u64, block: u64) -> TribeResult<u64> {
+        self.accrue_interest(block);
+        let repaid = amount.min(self.debt_of(borrower));
+        if repaid == 0 {
+            return Err(TribeError::Contract("Nothing to repay".to_string()));
+        }
+        self.reduce_debt(borrower, repaid);
+        Ok(repaid)
+    }
+
+    /// Repay part of an unhealthy position's debt in exchange for its collateral at a
+    /// bonus; returns (asset repaid, collateral seized)
+    pub fn liquidate(&mut self, borrower: &str, amount: u64, collateral_price: f64, block: u64) -> TribeResult<(u64, u64)> {
+        self.accrue_interest(block);
+        if collateral_price <= 0.0 {
+            return Err(TribeError::Contract("Invalid collateral price".to_string()));
+        }
+        if self.health_factor(borrower, collateral_price) >= 1.0 {
+            return Err(TribeError::Contract("Position is healthy".to_string()));
+        }
+
+        let max_repay = (self.debt_of(borrower) as f64 * CLOSE_FACTOR).ceil() as u64;
+        let collateral = self.collateral_of(borrower);
+        let mut repaid = amount.min(max_repay);
+        let mut seized = (repaid as f64 * (1.0 + self.liquidation_bonus) / collateral_price) as u64;
+        if seized > collateral {
+            // Not enough collateral left for the bonus; repay only what it covers
+            seized = collateral;
+            repaid = (collateral as f64 * collateral_price / (1.0 + self.liquidation_bonus)) as u64;
+        }
+        if repaid == 0 {
+            return Err(TribeError::Contract("Liquidation amount too small".to_string()));
+        }
+
+        self.reduce_debt(borrower, repaid);
+        if let Some(position) = self.positions.get_mut(borrower) {
+            position.collateral -= seized;
+        }
+        Ok((repaid, seized))
+    }
+
+    fn borrow_limit(&self, collateral: u64, collateral_price: f64) -> f64 {
+        collateral as f64 * collateral_price * self.max_ltv
+    }
+
+    fn reduce_debt(&mut self, borrower: &str, amount: u64) {
+        let debt = self.debt_of(borrower);
+        if let Some(position) = self.positions.get_mut(borrower) {
+            let scaled = if amount >= debt {
+                position.scaled_debt
+            } else {
+                (amount as f64 / self.borrow_index).min(position.scaled_debt)
+            };
+            position.scaled_debt -= scaled;
+            self.total_scaled_debt = (self.total_scaled_debt - scaled).max(0.0);
+        }
+        self.cash += amount;
+    }
+
+    fn shares_to_assets(&self, shares: u64) -> u64 {
+        if self.total_shares == 0 {
+            return 0;
+        }
+        (shares as u128 * self.total_assets() as u128 / self.total_shares as u128) as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn market() -> LendingMarket {
+        LendingMarket::new("USDC".to_string(), "TRIBE".to_string(), "pool".to_string(), 0.5, 0.8, 0.001).unwrap()
+    }
+
+    #[test]
+    fn test_interest_accrues_to_suppliers() {
+        let mut market = market();
+        market.supply("alice".to_string(), 10000, 0).unwrap();
+        market.deposit_collateral("bob".to_string(), 10000).unwrap();
+
+        // Collateral worth 2 USDC each: limit is 10000
+        assert!(market.borrow("bob", 10001, 2.0, 0).is_err());
+        market.borrow("bob", 5000, 2.0, 0).unwrap();
+
+        market.accrue_interest(100);
+        assert_eq!(market.debt_of("bob"), 5500);
+        assert_eq!(market.supplied_balance("alice"), 10500);
+
+        assert_eq!(market.repay("bob", 10000, 100).unwrap(), 5500);
+        assert_eq!(market.debt_of("bob"), 0);
+        assert_eq!(market.withdraw("alice", 10500, 100).unwrap(), 10500);
+        assert!(market.withdraw("alice", 1, 100).is_err());
+    }
+
+    #[test]
+    fn test_liquidation() {
+        let mut market = market();
+        market.supply("alice".to_string(), 10000, 0).unwrap();
+        market.deposit_collateral("bob".to_string(), 1000).unwrap();
+        market.borrow("bob", 1000, 2.0, 0).unwrap();
+
+        assert!(market.liquidate("bob", 500, 2.0, 0).is_err());
+        assert!(market.withdraw_collateral("bob", 1, 2.0, 0).is_err());
+
+        // Collateral halves in value: 1000 * 1.0 * 0.8 < 1000 debt
+        assert!(market.health_factor("bob", 1.0) < 1.0);
+        let (repaid, seized) = market.liquidate("bob", 800, 1.0, 0).unwrap();
+        assert_eq!(repaid, 500);
+        assert_eq!(seized, 525);
+        assert_eq!(market.debt_of("bob"), 500);
+        assert_eq!(market.collateral_of("bob"), 475);
+    }
+}
@@ -3,17 +3,42 @@ pub mod contracts;
 pub mod tokens;
 pub mod staking;
 pub mod liquidity;
+pub mod concentrated;
+pub mod multisig;
+pub mod vesting;
+pub mod orderbook;
+pub mod lending;
+pub mod journal;
+pub mod abi;
+pub mod tensor_escrow;
+pub mod bridge;
+pub mod ibc;
 
 // Re-export main types
-pub use vm::{ContractVM, VMState, ExecutionResult, VMError};
-pub use contracts::{Contract, ContractType, ContractCall, ContractDeployment};
-pub use tokens::{TokenContract, TokenOperation, TokenInfo, TokenBalance};
-pub use staking::{StakingContract, StakeInfo, ValidatorInfo, StakingRewards};
-pub use liquidity::{LiquidityPool, PoolInfo, LiquidityPosition, SwapResult};
+pub use vm::{ContractVM, VMState, ExecutionResult, VMError, LogEntry};
+pub use contracts::{Contract, ContractType, ContractCall, ContractDeployment, StorageSlot};
+use contracts::ParameterType;
+pub use tokens::{TokenContract, TokenOperation, TokenInfo, TokenBalance, PermitMessage, TokenSnapshots, TokenEventLog, TransferEvent, ApprovalEvent};
+pub use staking::{StakingContract, StakeInfo, ValidatorInfo, StakingRewards, UnbondingEntry, EmissionCurve};
+pub use liquidity::{LiquidityPool, LiquidityPosition, PoolStats, PriceOracle, PriceObservation};
+pub use concentrated::{ConcentratedLiquidity, RangePosition, TickInfo};
+pub use multisig::{MultisigWallet, MultisigAction, MultisigProposal, ProposalStatus};
+pub use vesting::{VestingContract, VestingSchedule, Timelock};
+pub use orderbook::{OrderBook, OrderSide, LimitOrder, OrderResult, Trade, Payout};
+pub use lending::{LendingMarket, BorrowPosition};
+pub use journal::{StateJournal, BlockJournal, JournalEntry, JournalKey};
+pub use abi::AbiValue;
+pub use tensor_escrow::{TensorEscrow, EscrowStatus, DEFAULT_TENSOR_TIMEOUT_BLOCKS};
+pub use bridge::{BridgeContract, BridgeMode, BridgeRoute, BridgeDeposit, PendingDeposit, OutboundTransfer, normalize_evm_address};
+pub use ibc::{
+    IbcHandler, LightClient, IbcHeader, SignedHeader, Channel, ChannelState, Coin, Packet, PacketData,
+    Acknowledgement, OutsourcedTask, ibc_escrow_address, IBC_ESCROW_PREFIX,
+};
 
-use tribechain_core::{TribeResult, TribeError};
+use tribechain_core::{TribeResult, TribeError, Receipt, ValidatorPerformanceReport, TensorTask, AI3Proof, ChainSpec, FinalityValidator, StateProof};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use chrono::{DateTime, Utc};
 
 /// Smart contract execution environment
 #[derive(Debug)]
@@ -23,6 +48,20 @@ pub struct ContractEngine {
     pub token_contracts: HashMap<String, TokenContract>,
     pub staking_contracts: HashMap<String, StakingContract>,
     pub liquidity_pools: HashMap<String, LiquidityPool>,
+    pub multisig_wallets: HashMap<String, MultisigWallet>,
+    pub vesting_contracts: HashMap<String, VestingContract>,
+    pub timelocks: HashMap<String, Timelock>,
+    pub order_books: HashMap<String, OrderBook>,
+    pub lending_markets: HashMap<String, LendingMarket>,
+    pub block_height: u64, // Height contracts see as the current block
+    pub pending_receipts: Vec<Receipt>, // Receipts of calls not yet sealed into a block
+    pub journal: StateJournal,
+    pub storage_rent_per_byte: u64, // Native TRIBE per stored byte per block; 0 disables rent
+    pub native_credits: HashMap<String, u64>, // Native TRIBE released to accounts, for the chain to apply
+    pub tensor_escrows: HashMap<String, TensorEscrow>,
+    pub pending_tensor_tasks: Vec<TensorTask>, // Escrowed tasks not yet handed to the mining pool
+    pub bridges: HashMap<String, BridgeContract>,
+    pub ibc: IbcHandler, // Channels to other TribeChain networks
 }
 
 impl ContractEngine {
@@ -33,32 +72,359 @@ impl ContractEngine {
             token_contracts: HashMap::new(),
             staking_contracts: HashMap::new(),
             liquidity_pools: HashMap::new(),
+            multisig_wallets: HashMap::new(),
+            vesting_contracts: HashMap::new(),
+            timelocks: HashMap::new(),
+            order_books: HashMap::new(),
+            lending_markets: HashMap::new(),
+            block_height: 0,
+            pending_receipts: Vec::new(),
+            journal: StateJournal::default(),
+            storage_rent_per_byte: 0,
+            native_credits: HashMap::new(),
+            tensor_escrows: HashMap::new(),
+            pending_tensor_tasks: Vec::new(),
+            bridges: HashMap::new(),
+            ibc: IbcHandler::new(),
         }
     }
 
+    /// Engine holding a chain spec's genesis tokens, under IDs every node agrees on
+    pub fn from_chain_spec(spec: &ChainSpec) -> TribeResult<Self> {
+        let mut engine = Self::new();
+        for definition in &spec.tokens {
+            let mut token = TokenContract::new(
+                definition.name.clone(),
+                definition.symbol.clone(),
+                definition.total_supply,
+                definition.decimals,
+                definition.owner.clone(),
+            )?;
+            token.token_info.id = definition.token_id(spec.chain_id);
+            token.is_mintable = definition.mintable;
+            engine.token_contracts.insert(token.token_info.id.clone(), token);
+        }
+        Ok(engine)
+    }
+
+    /// Charge contracts rent for their storage every block
+    pub fn with_storage_rent(mut self, rent_per_byte: u64) -> Self {
+        self.storage_rent_per_byte = rent_per_byte;
+        self
+    }
+
     /// Deploy a new contract
     pub fn deploy_contract(&mut self, deployment: ContractDeployment) -> TribeResult<String> {
+        self.record(JournalKey::VmStorage);
         let contract_address = self.vm.deploy(deployment.clone())?;
         
-        let contract = Contract::new(
+        let mut contract = Contract::new_with_metadata(
             contract_address.clone(),
             deployment.contract_type,
             deployment.code,
             deployment.constructor_args,
             deployment.deployer,
+            deployment.metadata,
         );
+        contract.state.rent_paid_through = self.block_height;
 
+        self.record(JournalKey::Contract(contract_address.clone()));
         self.deployed_contracts.insert(contract_address.clone(), contract);
         Ok(contract_address)
     }
 
+    /// Advance the block height used for per-block interest
+    pub fn set_block_height(&mut self, block_height: u64) {
+        self.block_height = block_height;
+    }
+
+    /// Start journaling state changes for the block at `block_height`.
+    /// A block still open is committed first.
+    pub fn begin_block(&mut self, block_height: u64) {
+        self.commit_block();
+        self.block_height = block_height;
+        self.journal.current = Some(BlockJournal::new(block_height, self.pending_receipts.len()));
+        if self.storage_rent_per_byte > 0 {
+            self.charge_storage_rent();
+        }
+        self.refund_expired_tensor_tasks();
+    }
+
+    /// Charge every contract rent for its storage up to the current block.
+    /// A contract that can't pay is deactivated and its storage freed.
+    /// Returns the rent collected.
+    pub fn charge_storage_rent(&mut self) -> u64 {
+        let mut addresses: Vec<String> = self.deployed_contracts.keys().cloned().collect();
+        addresses.sort();
+
+        let mut collected = 0;
+        for address in addresses {
+            let bytes = self.vm.storage_bytes(&address);
+            let state = &self.deployed_contracts[&address].state;
+            let blocks = self.block_height.saturating_sub(state.rent_paid_through);
+            if blocks == 0 || !state.is_active {
+                continue;
+            }
+            let due = (bytes as u64).saturating_mul(self.storage_rent_per_byte).saturating_mul(blocks);
+            let evicted = due > state.balance;
+
+            self.record(JournalKey::Contract(address.clone()));
+            if evicted {
+                self.record(JournalKey::VmStorage);
+                self.vm.clear_contract_storage(&address);
+            }
+            if let Some(contract) = self.deployed_contracts.get_mut(&address) {
+                let paid = due.min(contract.state.balance);
+                contract.state.balance -= paid;
+                contract.state.rent_paid_through = self.block_height;
+                contract.state.storage_size = if evicted { 0 } else { bytes };
+                contract.state.is_active = !evicted;
+                collected += paid;
+            }
+        }
+        collected
+    }
+
+    /// Deposit native TRIBE into a contract's balance, e.g. to pay its storage rent
+    pub fn fund_contract(&mut self, contract_address: &str, amount: u64) -> TribeResult<()> {
+        self.record(JournalKey::Contract(contract_address.to_string()));
+        let contract = self.deployed_contracts.get_mut(contract_address)
+            .ok_or_else(|| TribeError::Contract("Contract not found".to_string()))?;
+        contract.state.balance += amount;
+        Ok(())
+    }
+
+    /// Native TRIBE released to accounts since the last call, for the chain to credit
+    pub fn take_native_credits(&mut self) -> HashMap<String, u64> {
+        self.record(JournalKey::NativeCredits);
+        std::mem::take(&mut self.native_credits)
+    }
+
+    /// Remove a contract that self-destructed, paying its balance to `beneficiary`
+    fn destroy_contract(&mut self, contract_address: &str, beneficiary: String) {
+        self.record(JournalKey::Contract(contract_address.to_string()));
+        let balance = match self.deployed_contracts.remove(contract_address) {
+            Some(contract) => contract.state.balance,
+            None => return,
+        };
+        if balance == 0 {
+            return;
+        }
+
+        if self.deployed_contracts.contains_key(&beneficiary) {
+            self.record(JournalKey::Contract(beneficiary.clone()));
+            if let Some(contract) = self.deployed_contracts.get_mut(&beneficiary) {
+                contract.state.balance += balance;
+            }
+        } else if self.multisig_wallets.contains_key(&beneficiary) {
+            self.record(JournalKey::Multisig(beneficiary.clone()));
+            if let Some(wallet) = self.multisig_wallets.get_mut(&beneficiary) {
+                wallet.balance += balance;
+            }
+        } else {
+            self.record(JournalKey::NativeCredits);
+            *self.native_credits.entry(beneficiary).or_insert(0) += balance;
+        }
+    }
+
+    /// Keep the open block's changes, holding its undo log for reorgs
+    pub fn commit_block(&mut self) {
+        if let Some(block) = self.journal.current.take() {
+            self.journal.committed.push_back(block);
+            while self.journal.committed.len() > self.journal.max_depth {
+                self.journal.committed.pop_front();
+            }
+        }
+    }
+
+    /// Undo every change made since `begin_block`, e.g. when a later step of the block fails
+    pub fn abort_block(&mut self) {
+        if let Some(block) = self.journal.current.take() {
+            self.pending_receipts.truncate(block.receipt_count);
+            block.revert(self);
+        }
+    }
+
+    /// Undo committed blocks above `block_height` after they are reorged out
+    pub fn revert_to_height(&mut self, block_height: u64) -> TribeResult<()> {
+        self.abort_block();
+        if self.journal.committed.back().is_some_and(|block| block.block_height > block_height)
+            && self.journal.committed.front().is_some_and(|block| block.block_height > block_height + 1)
+        {
+            return Err(TribeError::Contract(format!(
+                "Journal doesn't reach back to block {}", block_height
+            )));
+        }
+
+        while self.journal.committed.back().is_some_and(|block| block.block_height > block_height) {
+            if let Some(block) = self.journal.committed.pop_back() {
+                block.revert(self);
+            }
+        }
+        self.block_height = block_height;
+        Ok(())
+    }
+
     /// Call a contract method
     pub fn call_contract(&mut self, call: ContractCall) -> TribeResult<ExecutionResult> {
-        if let Some(contract) = self.deployed_contracts.get(&call.contract_address) {
-            self.vm.call(contract, call)
+        let is_proxy = self.deployed_contracts.get(&call.contract_address)
+            .is_some_and(|contract| contract.contract_type == ContractType::Proxy);
+        if is_proxy && (call.method == vm::PROXY_UPGRADE_METHOD || call.method == vm::PROXY_CHANGE_ADMIN_METHOD) {
+            return self.call_proxy_admin(call);
+        }
+        self.validate_call(&call)?;
+        self.record(JournalKey::VmStorage);
+        let contract = &self.deployed_contracts[&call.contract_address];
+        let result = self.vm.call_with_contracts(contract, call, &self.deployed_contracts)?;
+
+        for (contract_address, beneficiary) in std::mem::take(&mut self.vm.destructed) {
+            self.destroy_contract(&contract_address, beneficiary);
+        }
+        Ok(result)
+    }
+
+    /// Run a proxy's upgrade or admin-change method on behalf of `call.caller`
+    fn call_proxy_admin(&mut self, call: ContractCall) -> TribeResult<ExecutionResult> {
+        let target = match abi::decode(&[ParameterType::Address], &call.args)?.pop() {
+            Some(AbiValue::Address(target)) => target,
+            _ => return Err(TribeError::Contract("Expected an address argument".to_string())),
+        };
+        let key = if call.method == vm::PROXY_UPGRADE_METHOD {
+            self.upgrade_proxy(&call.contract_address, &target, &call.caller)?;
+            vm::proxy_implementation_key(&call.contract_address)
         } else {
-            Err(TribeError::InvalidOperation("Contract not found".to_string()))
+            self.change_proxy_admin(&call.contract_address, target.clone(), &call.caller)?;
+            vm::proxy_admin_key(&call.contract_address)
+        };
+
+        Ok(ExecutionResult {
+            success: true,
+            return_data: vec![1],
+            gas_used: vm::CALL_BASE_GAS,
+            error: None,
+            logs: vec![LogEntry {
+                contract_address: call.contract_address.clone(),
+                topics: vec![call.method.clone(), target.clone()],
+                data: call.args.clone(),
+                timestamp: Utc::now(),
+            }],
+            state_changes: HashMap::from([(key, target.into_bytes())]),
+            execution_time: std::time::Duration::from_millis(0),
+        })
+    }
+
+    /// Deploy a proxy delegating to `implementation`, upgradeable by `admin`.
+    /// `admin` may be an account or a governance contract such as a multisig wallet.
+    pub fn deploy_proxy(&mut self, implementation: &str, admin: String, deployer: String) -> TribeResult<String> {
+        let metadata = self.proxy_target(implementation)?.metadata.clone();
+        let deployment = ContractDeployment::new(ContractType::Proxy, implementation.as_bytes().to_vec(), deployer)
+            .with_metadata(metadata);
+        let proxy = self.deploy_contract(deployment)?;
+
+        self.vm.storage.insert(vm::proxy_implementation_key(&proxy), implementation.as_bytes().to_vec());
+        self.vm.storage.insert(vm::proxy_admin_key(&proxy), admin.into_bytes());
+        Ok(proxy)
+    }
+
+    /// Implementation a proxy currently delegates to
+    pub fn proxy_implementation(&self, proxy: &str) -> Option<String> {
+        self.vm.storage.get(&vm::proxy_implementation_key(proxy))
+            .and_then(|address| String::from_utf8(address.clone()).ok())
+    }
+
+    /// Address allowed to upgrade a proxy
+    pub fn proxy_admin(&self, proxy: &str) -> Option<String> {
+        self.vm.storage.get(&vm::proxy_admin_key(proxy))
+            .and_then(|admin| String::from_utf8(admin.clone()).ok())
+    }
+
+    /// Point `proxy` at `implementation`, whose storage layout must extend the current one
+    pub fn upgrade_proxy(&mut self, proxy: &str, implementation: &str, caller: &str) -> TribeResult<()> {
+        self.check_proxy_admin(proxy, caller)?;
+        let metadata = self.proxy_target(implementation)?.metadata.clone();
+        self.deployed_contracts[proxy].metadata.check_layout_compatible(&metadata)?;
+
+        self.record(JournalKey::VmStorage);
+        self.record(JournalKey::Contract(proxy.to_string()));
+        self.vm.storage.insert(vm::proxy_implementation_key(proxy), implementation.as_bytes().to_vec());
+        if let Some(contract) = self.deployed_contracts.get_mut(proxy) {
+            contract.metadata = metadata;
+            contract.state.last_updated = Utc::now();
         }
+        Ok(())
+    }
+
+    /// Hand a proxy's upgrade rights to `new_admin`
+    pub fn change_proxy_admin(&mut self, proxy: &str, new_admin: String, caller: &str) -> TribeResult<()> {
+        self.check_proxy_admin(proxy, caller)?;
+        self.record(JournalKey::VmStorage);
+        self.vm.storage.insert(vm::proxy_admin_key(proxy), new_admin.into_bytes());
+        Ok(())
+    }
+
+    fn check_proxy_admin(&self, proxy: &str, caller: &str) -> TribeResult<()> {
+        match self.deployed_contracts.get(proxy) {
+            Some(contract) if contract.contract_type == ContractType::Proxy => {}
+            _ => return Err(TribeError::Contract("Proxy not found".to_string())),
+        }
+        if self.proxy_admin(proxy).as_deref() != Some(caller) {
+            return Err(TribeError::Contract("Only the proxy admin can do this".to_string()));
+        }
+        Ok(())
+    }
+
+    /// A deployed, non-proxy contract a proxy may delegate to
+    fn proxy_target(&self, implementation: &str) -> TribeResult<&Contract> {
+        match self.deployed_contracts.get(implementation) {
+            Some(contract) if contract.contract_type == ContractType::Proxy => {
+                Err(TribeError::Contract("Proxy implementation cannot be a proxy".to_string()))
+            }
+            Some(contract) => Ok(contract),
+            None => Err(TribeError::Contract("Implementation contract not found".to_string())),
+        }
+    }
+
+    /// Check a call against the target's published ABI; contracts without one accept raw args
+    pub fn validate_call(&self, call: &ContractCall) -> TribeResult<()> {
+        let contract = self.deployed_contracts.get(&call.contract_address)
+            .ok_or_else(|| TribeError::Contract("Contract not found".to_string()))?;
+        if !contract.is_callable() {
+            return Err(TribeError::Contract("Contract is not callable".to_string()));
+        }
+        if contract.metadata.abi.is_empty() {
+            return Ok(());
+        }
+        contract.validate_method_call(call)
+    }
+
+    /// Call a contract on behalf of a transaction, keeping its receipt and logs
+    pub fn execute_transaction_call(&mut self, tx_hash: &str, call: ContractCall) -> TribeResult<ExecutionResult> {
+        let result = self.call_contract(call)?;
+        self.pending_receipts.push(result.to_receipt(tx_hash));
+        Ok(result)
+    }
+
+    /// Run a view method for its return data, discarding any storage it writes
+    pub fn query_contract(&mut self, call: ContractCall) -> TribeResult<ExecutionResult> {
+        self.validate_call(&call)?;
+        let contract = &self.deployed_contracts[&call.contract_address];
+        if contract.get_method_signature(&call.method).is_some_and(|signature| !signature.is_view) {
+            return Err(TribeError::Contract(format!("{} is not a view method; submit it as a transaction", call.method)));
+        }
+        if call.value > 0 {
+            return Err(TribeError::Contract("Queries cannot send value".to_string()));
+        }
+
+        let storage = self.vm.storage.clone();
+        let result = self.vm.call_with_contracts(contract, call, &self.deployed_contracts);
+        self.vm.storage = storage;
+        self.vm.destructed.clear();
+        result
+    }
+
+    /// Receipts collected since the last block, for `TribeChain::attach_receipts`
+    pub fn take_receipts(&mut self) -> Vec<Receipt> {
+        std::mem::take(&mut self.pending_receipts)
     }
 
     /// Create a new token
@@ -73,6 +439,7 @@ impl ContractEngine {
         let token_contract = TokenContract::new(name, symbol, total_supply, decimals, creator)?;
         let token_id = token_contract.token_info.id.clone();
         
+        self.record(JournalKey::Token(token_id.clone()));
         self.token_contracts.insert(token_id.clone(), token_contract);
         Ok(token_id)
     }
@@ -85,11 +452,110 @@ impl ContractEngine {
         to: String,
         amount: u64,
     ) -> TribeResult<()> {
+        self.record(JournalKey::Token(token_id.clone()));
         if let Some(token_contract) = self.token_contracts.get_mut(&token_id) {
             token_contract.transfer(from, to, amount)
         } else {
-            Err(TribeError::InvalidOperation("Token not found".to_string()))
+            Err(TribeError::Contract("Token not found".to_string()))
+        }
+    }
+
+    /// Run `f` against the engine, keeping its changes only if it succeeds.
+    /// Token, staking, pool, order book, lending, contract, multisig, bridge, IBC and tensor
+    /// escrow state, VM storage and native credits are restored on error.
+    pub fn atomically<T>(&mut self, f: impl FnOnce(&mut Self) -> TribeResult<T>) -> TribeResult<T> {
+        let checkpoint = EngineCheckpoint {
+            token_contracts: self.token_contracts.clone(),
+            staking_contracts: self.staking_contracts.clone(),
+            liquidity_pools: self.liquidity_pools.clone(),
+            order_books: self.order_books.clone(),
+            lending_markets: self.lending_markets.clone(),
+            vm_storage: self.vm.storage.clone(),
+            deployed_contracts: self.deployed_contracts.clone(),
+            multisig_wallets: self.multisig_wallets.clone(),
+            bridges: self.bridges.clone(),
+            ibc: self.ibc.clone(),
+            tensor_escrows: self.tensor_escrows.clone(),
+            pending_tensor_tasks: self.pending_tensor_tasks.clone(),
+            native_credits: self.native_credits.clone(),
+        };
+
+        let outcome = f(self);
+        if outcome.is_err() {
+            self.token_contracts = checkpoint.token_contracts;
+            self.staking_contracts = checkpoint.staking_contracts;
+            self.liquidity_pools = checkpoint.liquidity_pools;
+            self.order_books = checkpoint.order_books;
+            self.lending_markets = checkpoint.lending_markets;
+            self.vm.storage = checkpoint.vm_storage;
+            self.deployed_contracts = checkpoint.deployed_contracts;
+            self.multisig_wallets = checkpoint.multisig_wallets;
+            self.bridges = checkpoint.bridges;
+            self.ibc = checkpoint.ibc;
+            self.tensor_escrows = checkpoint.tensor_escrows;
+            self.pending_tensor_tasks = checkpoint.pending_tensor_tasks;
+            self.native_credits = checkpoint.native_credits;
+        }
+        outcome
+    }
+
+    /// Run a bundle of token operations and contract calls as `caller`, all or nothing.
+    /// Returns the results of the contract calls in order.
+    pub fn multicall(&mut self, caller: String, operations: Vec<MulticallOperation>) -> TribeResult<Vec<ExecutionResult>> {
+        self.atomically(|engine| engine.run_multicall(&caller, operations))
+    }
+
+    /// Lend `amount` of a pool's `token` (held under the pool's ID) to `borrower`, run
+    /// `operations` as the borrower, then take back the loan plus the pool fee.
+    /// Everything is undone if the operations fail or the borrower can't repay.
+    /// Returns the fee paid.
+    pub fn flash_swap(
+        &mut self,
+        pool_id: &str,
+        borrower: String,
+        token: String,
+        amount: u64,
+        operations: Vec<MulticallOperation>,
+    ) -> TribeResult<u64> {
+        self.atomically(|engine| {
+            engine.record(JournalKey::Pool(pool_id.to_string()));
+            let mut pool = engine.liquidity_pools.remove(pool_id)
+                .ok_or_else(|| TribeError::Contract("Liquidity pool not found".to_string()))?;
+
+            let outcome = pool.flash_swap(&borrower, &token, amount, |token, amount, fee| {
+                engine.transfer_token(token.to_string(), pool_id.to_string(), borrower.clone(), amount)?;
+                engine.run_multicall(&borrower, operations)?;
+                engine.transfer_token(token.to_string(), borrower.clone(), pool_id.to_string(), amount + fee)?;
+                Ok(amount + fee)
+            });
+            engine.liquidity_pools.insert(pool_id.to_string(), pool);
+            outcome
+        })
+    }
+
+    fn run_multicall(&mut self, caller: &str, operations: Vec<MulticallOperation>) -> TribeResult<Vec<ExecutionResult>> {
+        let mut results = Vec::new();
+        for (step, operation) in operations.into_iter().enumerate() {
+            match operation {
+                MulticallOperation::Token { token_id, operation } => {
+                    self.record(JournalKey::Token(token_id.clone()));
+                    let token_contract = self.token_contracts.get_mut(&token_id)
+                        .ok_or_else(|| TribeError::Contract("Token not found".to_string()))?;
+                    token_contract.execute_operation(operation, caller.to_string())?;
+                }
+                MulticallOperation::Call(mut call) => {
+                    call.caller = caller.to_string();
+                    let result = self.call_contract(call)?;
+                    if !result.success {
+                        return Err(TribeError::Contract(format!(
+                            "Multicall step {} failed: {}", step, result.error.unwrap_or_default()
+                        )));
+                    }
+                    results.push(result);
+                }
+            }
         }
+        Ok(results)
     }
 
     /// Create staking contract
@@ -103,6 +569,7 @@ impl ContractEngine {
         let staking_contract = StakingContract::new(token_id, validator, min_stake, reward_rate)?;
         let contract_id = staking_contract.id.clone();
         
+        self.record(JournalKey::Staking(contract_id.clone()));
         self.staking_contracts.insert(contract_id.clone(), staking_contract);
         Ok(contract_id)
     }
@@ -115,23 +582,90 @@ impl ContractEngine {
         amount: u64,
         duration: u64,
     ) -> TribeResult<()> {
+        self.record(JournalKey::Staking(staking_contract_id.clone()));
         if let Some(staking_contract) = self.staking_contracts.get_mut(&staking_contract_id) {
             staking_contract.stake(staker, amount, duration)
         } else {
-            Err(TribeError::InvalidOperation("Staking contract not found".to_string()))
+            Err(TribeError::Contract("Staking contract not found".to_string()))
+        }
+    }
+
+    /// Unstake tokens into the staking contract's unbonding queue
+    pub fn unstake_tokens(
+        &mut self,
+        staking_contract_id: String,
+        staker: String,
+        amount: u64,
+    ) -> TribeResult<u64> {
+        self.record(JournalKey::Staking(staking_contract_id.clone()));
+        if let Some(staking_contract) = self.staking_contracts.get_mut(&staking_contract_id) {
+            staking_contract.unstake(staker, amount)
+        } else {
+            Err(TribeError::Contract("Staking contract not found".to_string()))
+        }
+    }
+
+    /// Withdraw unstaked tokens whose unbonding period has ended
+    pub fn withdraw_unbonded(
+        &mut self,
+        staking_contract_id: String,
+        staker: String,
+    ) -> TribeResult<u64> {
+        self.record(JournalKey::Staking(staking_contract_id.clone()));
+        if let Some(staking_contract) = self.staking_contracts.get_mut(&staking_contract_id) {
+            staking_contract.withdraw_unbonded(&staker, Utc::now())
+        } else {
+            Err(TribeError::Contract("Staking contract not found".to_string()))
+        }
+    }
+
+    /// Apply a verified consensus performance report to every staking contract the
+    /// validator belongs to; returns the total slashed
+    pub fn apply_performance_report(&mut self, report: &ValidatorPerformanceReport) -> TribeResult<u64> {
+        self.record_all_staking();
+        let mut slashed = 0;
+        for staking_contract in self.staking_contracts.values_mut() {
+            if staking_contract.validators.contains_key(&report.validator) {
+                slashed += staking_contract.apply_performance_report(report)?;
+            }
+        }
+        Ok(slashed)
+    }
+
+    /// Close finished reward epochs on every staking contract; returns the total emitted
+    pub fn advance_staking_epochs(&mut self, now: DateTime<Utc>) -> TribeResult<u64> {
+        self.record_all_staking();
+        let mut emitted = 0;
+        for staking_contract in self.staking_contracts.values_mut() {
+            emitted += staking_contract.advance_epoch(now)?;
+        }
+        Ok(emitted)
+    }
+
+    fn record_all_staking(&mut self) {
+        let contract_ids: Vec<String> = self.staking_contracts.keys().cloned().collect();
+        for contract_id in contract_ids {
+            self.record(JournalKey::Staking(contract_id));
         }
     }
 
-    /// Create liquidity pool
+    /// Create liquidity pool seeded with `provider`'s initial reserves
     pub fn create_liquidity_pool(
         &mut self,
         token_a: String,
         token_b: String,
+        initial_a: u64,
+        initial_b: u64,
+        provider: String,
         fee_rate: f64,
     ) -> TribeResult<String> {
-        let pool = LiquidityPool::new(token_a, token_b, fee_rate)?;
+        let pool = LiquidityPool::new(token_a, token_b, initial_a, initial_b, provider, fee_rate)?;
         let pool_id = pool.id.clone();
-        
+        if self.liquidity_pools.contains_key(&pool_id) {
+            return Err(TribeError::Contract("Liquidity pool already exists".to_string()));
+        }
+
+        self.record(JournalKey::Pool(pool_id.clone()));
         self.liquidity_pools.insert(pool_id.clone(), pool);
         Ok(pool_id)
     }
@@ -143,15 +677,17 @@ impl ContractEngine {
         provider: String,
         amount_a: u64,
         amount_b: u64,
+        min_liquidity: u64,
     ) -> TribeResult<u64> {
+        self.record(JournalKey::Pool(pool_id.clone()));
         if let Some(pool) = self.liquidity_pools.get_mut(&pool_id) {
-            pool.add_liquidity(provider, amount_a, amount_b)
+            pool.add_liquidity(provider, amount_a, amount_b, min_liquidity)
         } else {
-            Err(TribeError::InvalidOperation("Liquidity pool not found".to_string()))
+            Err(TribeError::Contract("Liquidity pool not found".to_string()))
         }
     }
 
-    /// Swap tokens in pool
+    /// Swap tokens in pool, returning the amount received
     pub fn swap_tokens(
         &mut self,
         pool_id: String,
@@ -159,12 +695,351 @@ impl ContractEngine {
         token_in: String,
         amount_in: u64,
         min_amount_out: u64,
-    ) -> TribeResult<SwapResult> {
+    ) -> TribeResult<u64> {
+        self.record(JournalKey::Pool(pool_id.clone()));
         if let Some(pool) = self.liquidity_pools.get_mut(&pool_id) {
             pool.swap(trader, token_in, amount_in, min_amount_out)
         } else {
-            Err(TribeError::InvalidOperation("Liquidity pool not found".to_string()))
+            Err(TribeError::Contract("Liquidity pool not found".to_string()))
+        }
+    }
+
+    /// TWAP of a pool's token A price over the last `window_secs` seconds
+    pub fn get_twap(&self, pool_id: &str, window_secs: u64) -> TribeResult<f64> {
+        let pool = self.liquidity_pools.get(pool_id)
+            .ok_or_else(|| TribeError::Contract("Liquidity pool not found".to_string()))?;
+        pool.get_twap(chrono::Duration::seconds(window_secs as i64))
+    }
+
+    /// Open an order book trading `base_token` against `quote_token`
+    pub fn create_order_book(
+        &mut self,
+        base_token: String,
+        quote_token: String,
+        maker_fee_rate: f64,
+        taker_fee_rate: f64,
+    ) -> TribeResult<String> {
+        if !self.token_contracts.contains_key(&base_token) || !self.token_contracts.contains_key(&quote_token) {
+            return Err(TribeError::Contract("Token not found".to_string()));
+        }
+        let book = OrderBook::new(base_token, quote_token, maker_fee_rate, taker_fee_rate)?;
+        let book_id = book.id.clone();
+
+        self.record(JournalKey::OrderBook(book_id.clone()));
+        self.order_books.insert(book_id.clone(), book);
+        Ok(book_id)
+    }
+
+    /// Lock the order's funds under the book, match it, and settle the fills
+    pub fn place_limit_order(
+        &mut self,
+        book_id: &str,
+        owner: String,
+        side: OrderSide,
+        price: u64,
+        amount: u64,
+    ) -> TribeResult<OrderResult> {
+        self.atomically(|engine| {
+            engine.record(JournalKey::OrderBook(book_id.to_string()));
+            let book = engine.order_books.get_mut(book_id)
+                .ok_or_else(|| TribeError::Contract("Order book not found".to_string()))?;
+            let (escrow_token, escrow) = book.required_escrow(side, price, amount);
+            let result = book.place_order(owner.clone(), side, price, amount)?;
+
+            engine.transfer_token(escrow_token, owner, book_id.to_string(), escrow)?;
+            engine.settle_payouts(book_id, &result.payouts)?;
+            Ok(result)
+        })
+    }
+
+    /// Cancel an open order and refund its remaining escrow
+    pub fn cancel_limit_order(&mut self, book_id: &str, order_id: u64, owner: &str) -> TribeResult<u64> {
+        self.atomically(|engine| {
+            engine.record(JournalKey::OrderBook(book_id.to_string()));
+            let book = engine.order_books.get_mut(book_id)
+                .ok_or_else(|| TribeError::Contract("Order book not found".to_string()))?;
+            let refund = book.cancel_order(order_id, owner)?;
+            let amount = refund.amount;
+
+            engine.settle_payouts(book_id, &[refund])?;
+            Ok(amount)
+        })
+    }
+
+    fn settle_payouts(&mut self, from: &str, payouts: &[Payout]) -> TribeResult<()> {
+        for payout in payouts.iter().filter(|payout| payout.amount > 0) {
+            self.transfer_token(payout.token_id.clone(), from.to_string(), payout.to.clone(), payout.amount)?;
         }
+        Ok(())
+    }
+
+    /// Open a market lending `asset_token` against `collateral_token`, priced by the
+    /// TWAP of `price_pool_id`
+    pub fn create_lending_market(
+        &mut self,
+        asset_token: String,
+        collateral_token: String,
+        price_pool_id: String,
+        max_ltv: f64,
+        liquidation_threshold: f64,
+        borrow_rate_per_block: f64,
+    ) -> TribeResult<String> {
+        let pool = self.liquidity_pools.get(&price_pool_id)
+            .ok_or_else(|| TribeError::Contract("Liquidity pool not found".to_string()))?;
+        let pair = [pool.token_a.as_str(), pool.token_b.as_str()];
+        if !pair.contains(&asset_token.as_str()) || !pair.contains(&collateral_token.as_str()) {
+            return Err(TribeError::Contract("Price pool doesn't trade this pair".to_string()));
+        }
+
+        let market = LendingMarket::new(
+            asset_token,
+            collateral_token,
+            price_pool_id,
+            max_ltv,
+            liquidation_threshold,
+            borrow_rate_per_block,
+        )?;
+        let market_id = market.id.clone();
+
+        self.record(JournalKey::Lending(market_id.clone()));
+        self.lending_markets.insert(market_id.clone(), market);
+        Ok(market_id)
+    }
+
+    /// Collateral price in units of the asset, from the market's pool TWAP
+    pub fn collateral_price(&self, market_id: &str) -> TribeResult<f64> {
+        let market = self.lending_market(market_id)?;
+        let pool = self.liquidity_pools.get(&market.price_pool_id)
+            .ok_or_else(|| TribeError::Contract("Liquidity pool not found".to_string()))?;
+        let twap = pool.get_twap(chrono::Duration::seconds(market.oracle_window_secs as i64))?;
+
+        // Pool prices token A in token B
+        if pool.token_a == market.collateral_token {
+            Ok(twap)
+        } else if twap > 0.0 {
+            Ok(1.0 / twap)
+        } else {
+            Err(TribeError::Contract("Invalid oracle price".to_string()))
+        }
+    }
+
+    fn lending_market(&self, market_id: &str) -> TribeResult<&LendingMarket> {
+        self.lending_markets.get(market_id)
+            .ok_or_else(|| TribeError::Contract("Lending market not found".to_string()))
+    }
+
+    fn lending_market_mut(&mut self, market_id: &str) -> TribeResult<&mut LendingMarket> {
+        self.record(JournalKey::Lending(market_id.to_string()));
+        self.lending_markets.get_mut(market_id)
+            .ok_or_else(|| TribeError::Contract("Lending market not found".to_string()))
+    }
+
+    /// Deposit the asset to earn interest; returns supply shares minted
+    pub fn supply_lending(&mut self, market_id: &str, supplier: String, amount: u64) -> TribeResult<u64> {
+        let block = self.block_height;
+        self.atomically(|engine| {
+            let market = engine.lending_market_mut(market_id)?;
+            let shares = market.supply(supplier.clone(), amount, block)?;
+            let asset = market.asset_token.clone();
+
+            engine.transfer_token(asset, supplier, market_id.to_string(), amount)?;
+            Ok(shares)
+        })
+    }
+
+    /// Withdraw supplied asset plus earned interest
+    pub fn withdraw_lending(&mut self, market_id: &str, supplier: String, amount: u64) -> TribeResult<u64> {
+        let block = self.block_height;
+        self.atomically(|engine| {
+            let market = engine.lending_market_mut(market_id)?;
+            let amount = market.withdraw(&supplier, amount, block)?;
+            let asset = market.asset_token.clone();
+
+            engine.transfer_token(asset, market_id.to_string(), supplier, amount)?;
+            Ok(amount)
+        })
+    }
+
+    pub fn deposit_collateral(&mut self, market_id: &str, borrower: String, amount: u64) -> TribeResult<()> {
+        self.atomically(|engine| {
+            let market = engine.lending_market_mut(market_id)?;
+            market.deposit_collateral(borrower.clone(), amount)?;
+            let collateral = market.collateral_token.clone();
+
+            engine.transfer_token(collateral, borrower, market_id.to_string(), amount)
+        })
+    }
+
+    pub fn withdraw_collateral(&mut self, market_id: &str, borrower: String, amount: u64) -> TribeResult<()> {
+        let block = self.block_height;
+        let price = self.collateral_price(market_id)?;
+        self.atomically(|engine| {
+            let market = engine.lending_market_mut(market_id)?;
+            market.withdraw_collateral(&borrower, amount, price, block)?;
+            let collateral = market.collateral_token.clone();
+
+            engine.transfer_token(collateral, market_id.to_string(), borrower, amount)
+        })
+    }
+
+    pub fn borrow(&mut self, market_id: &str, borrower: String, amount: u64) -> TribeResult<()> {
+        let block = self.block_height;
+        let price = self.collateral_price(market_id)?;
+        self.atomically(|engine| {
+            let market = engine.lending_market_mut(market_id)?;
+            market.borrow(&borrower, amount, price, block)?;
+            let asset = market.asset_token.clone();
+
+            engine.transfer_token(asset, market_id.to_string(), borrower, amount)
+        })
+    }
+
+    /// Repay up to `amount` of debt; returns the amount repaid
+    pub fn repay(&mut self, market_id: &str, borrower: String, amount: u64) -> TribeResult<u64> {
+        let block = self.block_height;
+        self.atomically(|engine| {
+            let market = engine.lending_market_mut(market_id)?;
+            let repaid = market.repay(&borrower, amount, block)?;
+            let asset = market.asset_token.clone();
+
+            engine.transfer_token(asset, borrower, market_id.to_string(), repaid)?;
+            Ok(repaid)
+        })
+    }
+
+    /// Repay an unhealthy borrower's debt and receive their collateral at a bonus;
+    /// returns (asset repaid, collateral seized)
+    pub fn liquidate(&mut self, market_id: &str, liquidator: String, borrower: &str, amount: u64) -> TribeResult<(u64, u64)> {
+        let block = self.block_height;
+        let price = self.collateral_price(market_id)?;
+        self.atomically(|engine| {
+            let market = engine.lending_market_mut(market_id)?;
+            let (repaid, seized) = market.liquidate(borrower, amount, price, block)?;
+            let (asset, collateral) = (market.asset_token.clone(), market.collateral_token.clone());
+
+            engine.transfer_token(asset, liquidator.clone(), market_id.to_string(), repaid)?;
+            engine.transfer_token(collateral, market_id.to_string(), liquidator, seized)?;
+            Ok((repaid, seized))
+        })
+    }
+
+    /// Create an M-of-N multi-signature wallet
+    pub fn create_multisig(&mut self, signers: Vec<String>, threshold: usize) -> TribeResult<String> {
+        let wallet = MultisigWallet::new(signers, threshold)?;
+        let wallet_id = wallet.id.clone();
+
+        self.record(JournalKey::Multisig(wallet_id.clone()));
+        self.multisig_wallets.insert(wallet_id.clone(), wallet);
+        Ok(wallet_id)
+    }
+
+    /// Propose an action for a multisig wallet's signers to approve
+    pub fn submit_multisig_proposal(
+        &mut self,
+        wallet_id: &str,
+        proposer: String,
+        action: MultisigAction,
+    ) -> TribeResult<u64> {
+        self.record(JournalKey::Multisig(wallet_id.to_string()));
+        if let Some(wallet) = self.multisig_wallets.get_mut(wallet_id) {
+            wallet.submit_proposal(proposer, action)
+        } else {
+            Err(TribeError::Contract("Multisig wallet not found".to_string()))
+        }
+    }
+
+    /// Approve a proposal, executing its action once the threshold is reached.
+    /// Returns true if the proposal was executed.
+    pub fn approve_proposal(&mut self, wallet_id: &str, proposal_id: u64, signer: &str) -> TribeResult<bool> {
+        self.record(JournalKey::Multisig(wallet_id.to_string()));
+        let wallet = self.multisig_wallets.get_mut(wallet_id)
+            .ok_or_else(|| TribeError::Contract("Multisig wallet not found".to_string()))?;
+        if !wallet.approve(proposal_id, signer)? {
+            return Ok(false);
+        }
+
+        let action = wallet.proposals[&proposal_id].action.clone();
+        match action {
+            MultisigAction::Transfer { .. } => {} // Debited from the wallet balance below
+            MultisigAction::TokenTransfer { token_id, to, amount } => {
+                self.transfer_token(token_id, wallet_id.to_string(), to, amount)?;
+            }
+            MultisigAction::ContractCall(mut call) => {
+                call.caller = wallet_id.to_string();
+                let result = self.call_contract(call)?;
+                if !result.success {
+                    return Err(TribeError::Contract(format!(
+                        "Multisig contract call failed: {}", result.error.unwrap_or_default()
+                    )));
+                }
+            }
+        }
+
+        if let Some(wallet) = self.multisig_wallets.get_mut(wallet_id) {
+            wallet.mark_executed(proposal_id)?;
+        }
+        Ok(true)
+    }
+
+    /// Lock `total_amount` of the creator's tokens into a vesting schedule for `beneficiary`
+    pub fn create_vesting(
+        &mut self,
+        token_id: String,
+        creator: String,
+        beneficiary: String,
+        total_amount: u64,
+        schedule: VestingSchedule,
+    ) -> TribeResult<String> {
+        let vesting = VestingContract::new(token_id.clone(), creator.clone(), beneficiary, total_amount, schedule)?;
+        let vesting_id = vesting.id.clone();
+
+        // Escrow the allocation under the vesting contract's address
+        self.transfer_token(token_id, creator, vesting_id.clone(), total_amount)?;
+        self.record(JournalKey::Vesting(vesting_id.clone()));
+        self.vesting_contracts.insert(vesting_id.clone(), vesting);
+        Ok(vesting_id)
+    }
+
+    /// Amount the beneficiary could claim right now
+    pub fn get_claimable(&self, vesting_id: &str) -> u64 {
+        self.vesting_contracts
+            .get(vesting_id)
+            .map(|v| v.claimable(Utc::now()))
+            .unwrap_or(0)
+    }
+
+    /// Release vested tokens to the beneficiary; returns the amount released
+    pub fn claim_vested(&mut self, vesting_id: &str, claimant: &str) -> TribeResult<u64> {
+        self.record(JournalKey::Vesting(vesting_id.to_string()));
+        let vesting = self.vesting_contracts.get_mut(vesting_id)
+            .ok_or_else(|| TribeError::Contract("Vesting contract not found".to_string()))?;
+        let amount = vesting.claim(claimant, Utc::now())?;
+        let token_id = vesting.token_id.clone();
+
+        self.transfer_token(token_id, vesting_id.to_string(), claimant.to_string(), amount)?;
+        Ok(amount)
+    }
+
+    /// Schedule a contract call that can't run before `release_at`
+    pub fn create_timelock(&mut self, creator: String, call: ContractCall, release_at: DateTime<Utc>) -> TribeResult<String> {
+        if !self.deployed_contracts.contains_key(&call.contract_address) {
+            return Err(TribeError::Contract("Contract not found".to_string()));
+        }
+        let timelock = Timelock::new(creator, call, release_at);
+        let timelock_id = timelock.id.clone();
+
+        self.record(JournalKey::Timelock(timelock_id.clone()));
+        self.timelocks.insert(timelock_id.clone(), timelock);
+        Ok(timelock_id)
+    }
+
+    /// Run a timelocked call once its lock has expired
+    pub fn execute_timelock(&mut self, timelock_id: &str) -> TribeResult<ExecutionResult> {
+        self.record(JournalKey::Timelock(timelock_id.to_string()));
+        let call = self.timelocks.get_mut(timelock_id)
+            .ok_or_else(|| TribeError::Contract("Timelock not found".to_string()))?
+            .release(Utc::now())?;
+        self.call_contract(call)
     }
 
     /// Get contract state
@@ -193,57 +1068,504 @@ impl ContractEngine {
             .and_then(|c| c.stakes.get(staker))
     }
 
-    /// Get liquidity pool info
-    pub fn get_pool_info(&self, pool_id: &str) -> Option<&PoolInfo> {
-        self.liquidity_pools.get(pool_id).map(|p| &p.info)
+    /// Get liquidity pool stats
+    pub fn get_pool_info(&self, pool_id: &str) -> Option<PoolStats> {
+        self.liquidity_pools.get(pool_id).map(|p| p.get_stats())
     }
 
-    /// Process tensor computation contract
+    /// Escrow `task.reward` of `token_id` from the requester in a TensorCompute contract
+    /// and queue the task for the mining pool. Returns the task id.
     pub fn process_tensor_computation(
         &mut self,
         contract_address: String,
-        operation: String,
-        input_data: Vec<f32>,
-        requester: String,
-        reward: u64,
+        token_id: String,
+        mut task: TensorTask,
+        timeout_blocks: u64,
     ) -> TribeResult<String> {
-        // Create a tensor computation task
-        let task_id = uuid::Uuid::new_v4().to_string();
-        
-        // In a real implementation, this would:
-        // 1. Validate the contract exists and has sufficient funds
-        // 2. Create a mining task for the AI3 engine
-        // 3. Distribute the task to miners
-        // 4. Handle result validation and reward distribution
-        
-        // For now, we'll simulate this process
-        if let Some(_contract) = self.deployed_contracts.get(&contract_address) {
-            // Simulate task creation and distribution
-            Ok(task_id)
-        } else {
-            Err(TribeError::InvalidOperation("Tensor computation contract not found".to_string()))
+        match self.deployed_contracts.get(&contract_address) {
+            Some(contract) if contract.contract_type == ContractType::TensorCompute && contract.is_callable() => {}
+            _ => return Err(TribeError::Contract("Tensor computation contract not found".to_string())),
         }
+
+        task.id = uuid::Uuid::new_v4().to_string();
+        task.completed = false;
+        task.result = None;
+        task.assigned_miner = None;
+        task.created_at = Utc::now().timestamp() as u64;
+        let escrow = TensorEscrow::new(task, contract_address, token_id.clone(), self.block_height, timeout_blocks)?;
+        let task_id = escrow.task.id.clone();
+
+        // The reward is held under the task id until it is paid or refunded
+        self.transfer_token(token_id, escrow.task.requester.clone(), task_id.clone(), escrow.task.reward)?;
+        self.record(JournalKey::TensorEscrow(task_id.clone()));
+        self.record(JournalKey::TensorTaskQueue);
+        self.pending_tensor_tasks.push(escrow.task.clone());
+        self.tensor_escrows.insert(task_id.clone(), escrow);
+        Ok(task_id)
     }
 
-    /// Calculate rewards for completed tensor computations
+    /// Escrowed tasks since the last call, for `AI3MiningPool::queue_escrow_tasks`
+    pub fn take_tensor_tasks(&mut self) -> Vec<TensorTask> {
+        self.record(JournalKey::TensorTaskQueue);
+        std::mem::take(&mut self.pending_tensor_tasks)
+    }
+
+    /// Settle a task at a marketplace bid: only `miner` can claim it, for `price`,
+    /// and the rest of the escrowed reward goes back to the requester.
+    pub fn award_tensor_task(&mut self, task_id: String, miner: String, price: u64) -> TribeResult<()> {
+        let escrow = self.tensor_escrows.get(&task_id)
+            .ok_or_else(|| TribeError::Contract("Tensor task not found".to_string()))?;
+        if escrow.status != EscrowStatus::Open || escrow.agreed_price.is_some() {
+            return Err(TribeError::Contract("Tensor task is already awarded".to_string()));
+        }
+        if price == 0 || price > escrow.task.reward {
+            return Err(TribeError::Contract(format!(
+                "Agreed price must be between 1 and {}", escrow.task.reward
+            )));
+        }
+        let (token_id, requester, change) = (escrow.token_id.clone(), escrow.task.requester.clone(), escrow.task.reward - price);
+
+        if change > 0 {
+            self.transfer_token(token_id, task_id.clone(), requester, change)?;
+        }
+        self.record(JournalKey::TensorEscrow(task_id.clone()));
+        if let Some(escrow) = self.tensor_escrows.get_mut(&task_id) {
+            escrow.agreed_price = Some(price);
+            escrow.task.assigned_miner = Some(miner);
+        }
+        Ok(())
+    }
+
+    /// Pay a task's escrowed reward to `miner` once its proof and result check out.
+    /// Returns the reward paid.
     pub fn distribute_tensor_rewards(
         &mut self,
         task_id: String,
         miner: String,
+        proof: &AI3Proof,
         computation_result: Vec<f32>,
     ) -> TribeResult<u64> {
-        // In a real implementation, this would:
-        // 1. Validate the computation result
-        // 2. Calculate rewards based on task complexity and accuracy
-        // 3. Transfer rewards to the miner
-        // 4. Update contract state
-        
-        // For now, return a simulated reward
-        let base_reward = 100;
-        let complexity_bonus = computation_result.len() as u64;
-        let total_reward = base_reward + complexity_bonus;
-        
-        Ok(total_reward)
+        let escrow = self.tensor_escrows.get(&task_id)
+            .ok_or_else(|| TribeError::Contract("Tensor task not found".to_string()))?;
+        escrow.verify(&miner, proof, &computation_result, self.block_height)?;
+        let (token_id, reward) = (escrow.token_id.clone(), escrow.locked_amount());
+
+        self.transfer_token(token_id, task_id.clone(), miner.clone(), reward)?;
+        self.record(JournalKey::TensorEscrow(task_id.clone()));
+        if let Some(escrow) = self.tensor_escrows.get_mut(&task_id) {
+            escrow.task.completed = true;
+            escrow.task.result = Some(computation_result);
+            escrow.task.assigned_miner = Some(miner.clone());
+            escrow.status = EscrowStatus::Paid { miner };
+        }
+        Ok(reward)
+    }
+
+    /// Return the rewards of open tasks past their deadline to their requesters.
+    /// Returns the refunded task ids.
+    pub fn refund_expired_tensor_tasks(&mut self) -> Vec<String> {
+        let mut expired: Vec<String> = self.tensor_escrows.iter()
+            .filter(|(_, escrow)| escrow.status == EscrowStatus::Open && escrow.is_expired(self.block_height))
+            .map(|(task_id, _)| task_id.clone())
+            .collect();
+        expired.sort();
+
+        expired.retain(|task_id| {
+            let escrow = &self.tensor_escrows[task_id];
+            let (token_id, requester, reward) = (escrow.token_id.clone(), escrow.task.requester.clone(), escrow.locked_amount());
+            if self.transfer_token(token_id, task_id.clone(), requester, reward).is_err() {
+                return false;
+            }
+            self.record(JournalKey::TensorEscrow(task_id.clone()));
+            if let Some(escrow) = self.tensor_escrows.get_mut(task_id) {
+                escrow.status = EscrowStatus::Refunded;
+            }
+            true
+        });
+        expired
+    }
+
+    /// Create a bridge to the EVM chain `remote_chain_id`, whose deposits are credited
+    /// once `threshold` of `attesters` report them
+    pub fn create_bridge(
+        &mut self,
+        admin: String,
+        remote_chain_id: u64,
+        remote_bridge: &str,
+        attesters: Vec<String>,
+        threshold: usize,
+    ) -> TribeResult<String> {
+        let bridge = BridgeContract::new(admin, remote_chain_id, remote_bridge, attesters, threshold)?;
+        let bridge_id = bridge.id.clone();
+        if self.bridges.contains_key(&bridge_id) {
+            return Err(TribeError::Bridge("Bridge already exists".to_string()));
+        }
+
+        self.record(JournalKey::Bridge(bridge_id.clone()));
+        self.bridges.insert(bridge_id.clone(), bridge);
+        Ok(bridge_id)
+    }
+
+    fn bridge_mut(&mut self, bridge_id: &str) -> TribeResult<&mut BridgeContract> {
+        self.record(JournalKey::Bridge(bridge_id.to_string()));
+        self.bridges.get_mut(bridge_id).ok_or_else(|| TribeError::Bridge("Bridge not found".to_string()))
+    }
+
+    /// Carry `token_id` across the bridge as `remote_token`, which has `remote_decimals`.
+    /// `Mint` routes need a mintable, burnable token that lets the bridge mint.
+    pub fn add_bridge_route(
+        &mut self,
+        bridge_id: &str,
+        caller: &str,
+        token_id: &str,
+        remote_token: &str,
+        mode: BridgeMode,
+        remote_decimals: u8,
+    ) -> TribeResult<()> {
+        let token = self.token_contracts.get(token_id)
+            .ok_or_else(|| TribeError::Contract("Token not found".to_string()))?;
+        if mode == BridgeMode::Mint && !(token.is_mintable && token.is_burnable && token.minters.iter().any(|minter| minter == bridge_id)) {
+            return Err(TribeError::Bridge(
+                "Minted tokens must be mintable and burnable, with the bridge as a minter".to_string()
+            ));
+        }
+        let route = BridgeRoute {
+            token_id: token_id.to_string(),
+            remote_token: remote_token.to_string(),
+            mode,
+            local_decimals: token.token_info.decimals,
+            remote_decimals,
+        };
+        self.bridge_mut(bridge_id)?.add_route(caller, route)
+    }
+
+    pub fn set_bridge_paused(&mut self, bridge_id: &str, caller: &str, paused: bool) -> TribeResult<()> {
+        self.bridge_mut(bridge_id)?.set_paused(caller, paused)
+    }
+
+    /// Send `amount` of `sender`'s `token_id` to `recipient` on the EVM chain: the tokens
+    /// are locked in the bridge or burned, and the transfer queued for relayers
+    pub fn bridge_out(
+        &mut self,
+        bridge_id: &str,
+        sender: String,
+        token_id: &str,
+        recipient: &str,
+        amount: u64,
+    ) -> TribeResult<OutboundTransfer> {
+        self.atomically(|engine| {
+            let block_height = engine.block_height;
+            let bridge = engine.bridge_mut(bridge_id)?;
+            let mode = bridge.route_for_token(token_id)?.mode;
+            let transfer = bridge.record_outbound(sender.clone(), token_id, recipient, amount, block_height)?;
+            match mode {
+                BridgeMode::Lock => engine.transfer_token(token_id.to_string(), sender, bridge_id.to_string(), amount)?,
+                BridgeMode::Mint => {
+                    engine.record(JournalKey::Token(token_id.to_string()));
+                    let token = engine.token_contracts.get_mut(token_id)
+                        .ok_or_else(|| TribeError::Contract("Token not found".to_string()))?;
+                    token.burn(sender, amount)?;
+                }
+            }
+            Ok(transfer)
+        })
+    }
+
+    /// Count `attester`'s report of a deposit on the EVM chain. Once enough attesters
+    /// agree, the recipient is paid from locked tokens or minted new ones.
+    /// Returns the amount credited, if this report completed the deposit.
+    pub fn attest_bridge_deposit(&mut self, bridge_id: &str, attester: &str, deposit: BridgeDeposit) -> TribeResult<Option<u64>> {
+        self.atomically(|engine| {
+            let block_height = engine.block_height;
+            let Some((deposit, route)) = engine.bridge_mut(bridge_id)?.attest(attester, deposit, block_height)? else {
+                return Ok(None);
+            };
+            let amount = route.to_local(deposit.amount)?;
+            match route.mode {
+                BridgeMode::Lock => {
+                    let bridge = engine.bridge_mut(bridge_id)?;
+                    let locked = bridge.locked.entry(route.token_id.clone()).or_insert(0);
+                    if *locked < amount {
+                        return Err(TribeError::Bridge(format!("Bridge holds only {} of {}", locked, route.token_id)));
+                    }
+                    *locked -= amount;
+                    engine.transfer_token(route.token_id, bridge_id.to_string(), deposit.recipient, amount)?;
+                }
+                BridgeMode::Mint => {
+                    engine.record(JournalKey::Token(route.token_id.clone()));
+                    let token = engine.token_contracts.get_mut(&route.token_id)
+                        .ok_or_else(|| TribeError::Contract("Token not found".to_string()))?;
+                    token.mint(deposit.recipient, amount, bridge_id.to_string())?;
+                }
+            }
+            Ok(Some(amount))
+        })
+    }
+
+    pub fn get_bridge(&self, bridge_id: &str) -> Option<&BridgeContract> {
+        self.bridges.get(bridge_id)
+    }
+
+    fn ibc_mut(&mut self) -> &mut IbcHandler {
+        self.record(JournalKey::Ibc);
+        &mut self.ibc
+    }
+
+    /// Track another TribeChain network from a header trusted out of band
+    pub fn create_ibc_client(&mut self, validators: HashMap<String, FinalityValidator>, trusted: &IbcHeader) -> TribeResult<String> {
+        self.ibc_mut().create_client(validators, trusted)
+    }
+
+    /// Advance a light client. Conflicting headers freeze it, and the freeze stays
+    /// although this errors.
+    pub fn update_ibc_client(&mut self, client_id: &str, header: &SignedHeader) -> TribeResult<()> {
+        self.ibc_mut().update_client(client_id, header)
+    }
+
+    /// Open a channel, as in `IbcHandler::chan_open_init`. Returns the channel id.
+    pub fn ibc_channel_open_init(&mut self, client_id: &str) -> TribeResult<String> {
+        self.ibc_mut().chan_open_init(client_id)
+    }
+
+    pub fn ibc_channel_open_try(&mut self, client_id: &str, counterparty: &str, proof_height: u64, proof: &StateProof) -> TribeResult<String> {
+        self.ibc_mut().chan_open_try(client_id, counterparty, proof_height, proof)
+    }
+
+    pub fn ibc_channel_open_ack(&mut self, channel_id: &str, counterparty: &str, proof_height: u64, proof: &StateProof) -> TribeResult<()> {
+        self.ibc_mut().chan_open_ack(channel_id, counterparty, proof_height, proof)
+    }
+
+    pub fn ibc_channel_open_confirm(&mut self, channel_id: &str, proof_height: u64, proof: &StateProof) -> TribeResult<()> {
+        self.ibc_mut().chan_open_confirm(channel_id, proof_height, proof)
+    }
+
+    /// Local token of `denom`: a voucher if it came from elsewhere, else the token itself
+    fn ibc_token_id(&self, denom: &str) -> String {
+        self.ibc.vouchers.get(denom).cloned().unwrap_or_else(|| denom.to_string())
+    }
+
+    fn mint_ibc_voucher(&mut self, token_id: &str, channel_id: &str, to: &str, amount: u64) -> TribeResult<()> {
+        self.record(JournalKey::Token(token_id.to_string()));
+        self.token_contracts.get_mut(token_id)
+            .ok_or_else(|| TribeError::Contract("Token not found".to_string()))?
+            .mint(to.to_string(), amount, ibc_escrow_address(channel_id))
+    }
+
+    /// Take `amount` of `token_id` from `owner` for a packet on `channel_id`. Vouchers
+    /// going back the way they came are burned; anything else waits in the channel's escrow.
+    fn ibc_debit(&mut self, channel_id: &str, owner: &str, token_id: &str, amount: u64) -> TribeResult<Coin> {
+        let denom = self.ibc.vouchers.iter()
+            .find(|(_, id)| *id == token_id)
+            .map(|(denom, _)| denom.clone())
+            .unwrap_or_else(|| token_id.to_string());
+        self.record(JournalKey::Token(token_id.to_string()));
+        let token = self.token_contracts.get_mut(token_id)
+            .ok_or_else(|| TribeError::Contract("Token not found".to_string()))?;
+        let (symbol, decimals) = (token.token_info.symbol.clone(), token.token_info.decimals);
+        if denom.starts_with(&format!("{}/", channel_id)) {
+            token.burn(owner.to_string(), amount)?;
+        } else {
+            token.transfer(owner.to_string(), ibc_escrow_address(channel_id), amount)?;
+        }
+        Ok(Coin { denom, symbol, decimals, amount })
+    }
+
+    /// Pay `coin`, arriving in `packet`, to `to`. Tokens coming home leave the channel's
+    /// escrow; others are minted as vouchers, created on first arrival. Returns the token id.
+    fn ibc_credit(&mut self, packet: &Packet, coin: &Coin, to: &str) -> TribeResult<String> {
+        let channel_id = &packet.destination_channel;
+        if let Some(denom) = coin.denom.strip_prefix(&format!("{}/", packet.source_channel)) {
+            let token_id = self.ibc_token_id(denom);
+            self.transfer_token(token_id.clone(), ibc_escrow_address(channel_id), to.to_string(), coin.amount)?;
+            return Ok(token_id);
+        }
+
+        let trace = format!("{}/{}", channel_id, coin.denom);
+        if let Some(token_id) = self.ibc.vouchers.get(&trace).cloned() {
+            self.mint_ibc_voucher(&token_id, channel_id, to, coin.amount)?;
+            return Ok(token_id);
+        }
+        let minter = ibc_escrow_address(channel_id);
+        let mut voucher = TokenContract::new_mintable(trace.clone(), coin.symbol.clone(), coin.amount, None, coin.decimals, minter.clone())?;
+        voucher.is_burnable = true;
+        voucher.transfer(minter, to.to_string(), coin.amount)?;
+        let token_id = voucher.token_info.id.clone();
+        if self.token_contracts.contains_key(&token_id) {
+            return Err(TribeError::Bridge(format!("Voucher for {} clashes with token {}", trace, token_id)));
+        }
+        self.record(JournalKey::Token(token_id.clone()));
+        self.token_contracts.insert(token_id.clone(), voucher);
+        self.ibc_mut().vouchers.insert(trace, token_id.clone());
+        Ok(token_id)
+    }
+
+    /// Give `owner` back what sending `coin` in `packet` took from them
+    fn ibc_refund(&mut self, packet: &Packet, coin: &Coin, owner: &str) -> TribeResult<()> {
+        let channel_id = &packet.source_channel;
+        let token_id = self.ibc_token_id(&coin.denom);
+        if coin.denom.starts_with(&format!("{}/", channel_id)) {
+            self.mint_ibc_voucher(&token_id, channel_id, owner, coin.amount)
+        } else {
+            self.transfer_token(token_id, ibc_escrow_address(channel_id), owner.to_string(), coin.amount)
+        }
+    }
+
+    /// Send `amount` of `sender`'s `token_id` to `receiver` on the chain at the other end
+    /// of `channel_id`. It must arrive before that chain reaches `timeout_height`.
+    pub fn ibc_transfer(
+        &mut self,
+        channel_id: &str,
+        sender: String,
+        token_id: &str,
+        receiver: &str,
+        amount: u64,
+        timeout_height: u64,
+    ) -> TribeResult<Packet> {
+        self.atomically(|engine| {
+            let coin = engine.ibc_debit(channel_id, &sender, token_id, amount)?;
+            let data = PacketData::Transfer { coin, sender, receiver: receiver.to_string() };
+            engine.ibc_mut().send_packet(channel_id, data, timeout_height)
+        })
+    }
+
+    /// Have `task` mined on the chain at the other end of `channel_id`, paying its reward
+    /// in `token_id` from the requester. Returns the task id its result comes back under.
+    pub fn ibc_outsource_tensor_task(
+        &mut self,
+        channel_id: &str,
+        token_id: &str,
+        mut task: TensorTask,
+        timeout_blocks: u64,
+        timeout_height: u64,
+    ) -> TribeResult<String> {
+        task.id = uuid::Uuid::new_v4().to_string();
+        task.completed = false;
+        task.result = None;
+        task.assigned_miner = None;
+        task.created_at = Utc::now().timestamp() as u64;
+        // The checks the escrow on the other chain makes
+        TensorEscrow::new(task.clone(), String::new(), token_id.to_string(), self.block_height, timeout_blocks)?;
+
+        self.atomically(|engine| {
+            let coin = engine.ibc_debit(channel_id, &task.requester, token_id, task.reward)?;
+            let outsourced = OutsourcedTask {
+                channel_id: channel_id.to_string(),
+                requester: task.requester.clone(),
+                miner: None,
+                result: None,
+            };
+            let task_id = task.id.clone();
+            engine.ibc_mut().send_packet(channel_id, PacketData::TensorTask { task, coin, timeout_blocks }, timeout_height)?;
+            engine.ibc.outsourced.insert(task_id.clone(), outsourced);
+            Ok(task_id)
+        })
+    }
+
+    /// Send the result of a task that came in over IBC back to its chain once its miner is paid
+    pub fn ibc_return_tensor_result(&mut self, task_id: &str, timeout_height: u64) -> TribeResult<Packet> {
+        let escrow = self.tensor_escrows.get(task_id)
+            .ok_or_else(|| TribeError::Contract("Tensor task not found".to_string()))?;
+        let channel_id = escrow.contract_address.strip_prefix(IBC_ESCROW_PREFIX)
+            .ok_or_else(|| TribeError::Bridge(format!("Task {} did not come over IBC", task_id)))?
+            .to_string();
+        let (EscrowStatus::Paid { miner }, Some(result)) = (&escrow.status, &escrow.task.result) else {
+            return Err(TribeError::Bridge(format!("Task {} is not completed", task_id)));
+        };
+        if self.ibc.results_returned.contains(task_id) {
+            return Err(TribeError::Bridge(format!("Result of task {} was already sent", task_id)));
+        }
+
+        let data = PacketData::TensorResult { task_id: task_id.to_string(), miner: miner.clone(), result: result.clone() };
+        let packet = self.ibc_mut().send_packet(&channel_id, data, timeout_height)?;
+        self.ibc.results_returned.insert(task_id.to_string());
+        Ok(packet)
+    }
+
+    fn ibc_on_recv(&mut self, packet: &Packet) -> TribeResult<String> {
+        match &packet.data {
+            PacketData::Transfer { coin, receiver, .. } => self.ibc_credit(packet, coin, receiver),
+            PacketData::TensorTask { task, coin, timeout_blocks } => {
+                if coin.amount != task.reward {
+                    return Err(TribeError::Bridge("Tensor task reward doesn't match the tokens sent".to_string()));
+                }
+                if self.tensor_escrows.contains_key(&task.id) {
+                    return Err(TribeError::Contract("Tensor task already exists".to_string()));
+                }
+                // The reward is held under the task id, as for tasks escrowed here
+                let token_id = self.ibc_credit(packet, coin, &task.id)?;
+                let escrow_address = ibc_escrow_address(&packet.destination_channel);
+                let escrow = TensorEscrow::new(task.clone(), escrow_address, token_id, self.block_height, *timeout_blocks)?;
+                self.record(JournalKey::TensorEscrow(task.id.clone()));
+                self.record(JournalKey::TensorTaskQueue);
+                self.pending_tensor_tasks.push(escrow.task.clone());
+                self.tensor_escrows.insert(task.id.clone(), escrow);
+                Ok(task.id.clone())
+            }
+            PacketData::TensorResult { task_id, miner, result } => {
+                match self.ibc.outsourced.get(task_id) {
+                    Some(task) if task.channel_id == packet.destination_channel && task.result.is_none() => {}
+                    _ => return Err(TribeError::Bridge(format!("Task {} is not awaiting a result", task_id))),
+                }
+                let task = self.ibc_mut().outsourced.get_mut(task_id).expect("outsourced task exists");
+                task.miner = Some(miner.clone());
+                task.result = Some(result.clone());
+                Ok(task_id.clone())
+            }
+        }
+    }
+
+    /// Receive a packet proven sent by the counterparty and carry it out. If that fails
+    /// the packet is still received, with an error acknowledgement for the source to refund.
+    pub fn ibc_recv_packet(&mut self, packet: &Packet, proof_height: u64, proof: &StateProof) -> TribeResult<Acknowledgement> {
+        let block_height = self.block_height;
+        self.ibc_mut().recv_packet(packet, proof_height, proof, block_height)?;
+        let ack = match self.atomically(|engine| engine.ibc_on_recv(packet)) {
+            Ok(result) => Acknowledgement::Success(result),
+            Err(e) => Acknowledgement::Error(e.to_string()),
+        };
+        self.ibc_mut().write_acknowledgement(packet, ack.clone());
+        Ok(ack)
+    }
+
+    /// Return what sending `packet` took
+    fn ibc_undo_send(&mut self, packet: &Packet) -> TribeResult<()> {
+        match &packet.data {
+            PacketData::Transfer { coin, sender, .. } => self.ibc_refund(packet, coin, sender),
+            PacketData::TensorTask { task, coin, .. } => {
+                self.ibc_mut().outsourced.remove(&task.id);
+                self.ibc_refund(packet, coin, &task.requester)
+            }
+            PacketData::TensorResult { task_id, .. } => {
+                self.ibc_mut().results_returned.remove(task_id);
+                Ok(())
+            }
+        }
+    }
+
+    /// Settle a sent packet with the counterparty's proven acknowledgement; error
+    /// acknowledgements are refunded
+    pub fn ibc_acknowledge_packet(
+        &mut self,
+        packet: &Packet,
+        ack: &Acknowledgement,
+        proof_height: u64,
+        proof: &StateProof,
+    ) -> TribeResult<()> {
+        self.atomically(|engine| {
+            let packet = engine.ibc_mut().acknowledge_packet(packet, ack, proof_height, proof)?;
+            if let Acknowledgement::Error(_) = ack {
+                engine.ibc_undo_send(&packet)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Refund a sent packet the counterparty is proven not to have received in time
+    pub fn ibc_timeout_packet(&mut self, packet: &Packet, proof_height: u64, proof: &StateProof) -> TribeResult<()> {
+        self.atomically(|engine| {
+            let packet = engine.ibc_mut().timeout_packet(packet, proof_height, proof)?;
+            engine.ibc_undo_send(&packet)
+        })
     }
 
     /// Get contract execution statistics
@@ -266,6 +1588,30 @@ impl Default for ContractEngine {
     }
 }
 
+/// State restored when an atomic engine operation fails
+struct EngineCheckpoint {
+    token_contracts: HashMap<String, TokenContract>,
+    staking_contracts: HashMap<String, StakingContract>,
+    liquidity_pools: HashMap<String, LiquidityPool>,
+    order_books: HashMap<String, OrderBook>,
+    lending_markets: HashMap<String, LendingMarket>,
+    vm_storage: HashMap<String, Vec<u8>>,
+    deployed_contracts: HashMap<String, Contract>,
+    multisig_wallets: HashMap<String, MultisigWallet>,
+    bridges: HashMap<String, BridgeContract>,
+    ibc: IbcHandler,
+    tensor_escrows: HashMap<String, TensorEscrow>,
+    pending_tensor_tasks: Vec<TensorTask>,
+    native_credits: HashMap<String, u64>,
+}
+
+/// One step of a `ContractEngine::multicall` bundle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum MulticallOperation {
+    Token { token_id: String, operation: TokenOperation },
+    Call(ContractCall),
+}
+
 /// Contract execution statistics
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContractExecutionStats {
@@ -281,6 +1627,7 @@ pub struct ContractExecutionStats {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::contracts::{ContractMetadata, MethodSignature, Parameter};
 
     #[test]
     fn test_contract_engine_creation() {
@@ -289,6 +1636,216 @@ mod tests {
         assert_eq!(engine.token_contracts.len(), 0);
     }
 
+    #[test]
+    fn test_execution_receipts() {
+        let mut engine = ContractEngine::new();
+        let deployment = ContractDeployment::new(ContractType::Token, vec![1], "creator".to_string());
+        let address = engine.deploy_contract(deployment).unwrap();
+
+        let call = ContractCall::new(address.clone(), "transfer".to_string(), vec![1, 2, 3], "creator".to_string());
+        engine.execute_transaction_call("tx1", call).unwrap();
+
+        let receipts = engine.take_receipts();
+        assert_eq!(receipts.len(), 1);
+        assert_eq!(receipts[0].logs[0].address, address);
+        assert_eq!(receipts[0].logs[0].topics, vec!["transfer".to_string()]);
+        assert!(engine.pending_receipts.is_empty());
+    }
+
+    #[test]
+    fn test_multisig_token_transfer() {
+        let mut engine = ContractEngine::new();
+        let signers = vec!["alice".to_string(), "bob".to_string(), "carol".to_string()];
+        let wallet_id = engine.create_multisig(signers, 2).unwrap();
+        let token_id = engine.create_token(
+            "Treasury".to_string(),
+            "TRSY".to_string(),
+            1000000,
+            6,
+            "creator".to_string(),
+        ).unwrap();
+        engine.transfer_token(token_id.clone(), "creator".to_string(), wallet_id.clone(), 5000).unwrap();
+
+        let action = MultisigAction::TokenTransfer {
+            token_id: token_id.clone(),
+            to: "grantee".to_string(),
+            amount: 2000,
+        };
+        let proposal_id = engine.submit_multisig_proposal(&wallet_id, "alice".to_string(), action).unwrap();
+        assert_eq!(engine.get_token_balance(&token_id, "grantee"), 0);
+
+        assert!(engine.approve_proposal(&wallet_id, proposal_id, "bob").unwrap());
+        assert_eq!(engine.get_token_balance(&token_id, "grantee"), 2000);
+        assert_eq!(engine.get_token_balance(&token_id, &wallet_id), 3000);
+        assert!(engine.approve_proposal(&wallet_id, proposal_id, "carol").is_err());
+    }
+
+    #[test]
+    fn test_bridge_round_trip() {
+        let remote_bridge = "0x00000000000000000000000000000000000000b0";
+        let (remote_tribe, remote_usdc) = ("0x00000000000000000000000000000000000000a1", "0x00000000000000000000000000000000000000a2");
+        let mut engine = ContractEngine::new();
+        let attesters = vec!["r1".to_string(), "r2".to_string()];
+        let bridge_id = engine.create_bridge("admin".to_string(), 11155111, remote_bridge, attesters, 2).unwrap();
+        let tribe = engine.create_token("Tribe".to_string(), "TRIBE".to_string(), 1_000, 6, "alice".to_string()).unwrap();
+        engine.add_bridge_route(&bridge_id, "admin", &tribe, remote_tribe, BridgeMode::Lock, 18).unwrap();
+
+        // Wrapped tokens need the bridge as a minter
+        let mut usdc = TokenContract::new_mintable("Bridged USDC".to_string(), "USDC".to_string(), 1, None, 6, "admin".to_string()).unwrap();
+        usdc.is_burnable = true;
+        let usdc_id = usdc.token_info.id.clone();
+        engine.token_contracts.insert(usdc_id.clone(), usdc);
+        assert!(engine.add_bridge_route(&bridge_id, "admin", &usdc_id, remote_usdc, BridgeMode::Mint, 6).is_err());
+        engine.token_contracts.get_mut(&usdc_id).unwrap().add_minter(bridge_id.clone(), "admin".to_string()).unwrap();
+        engine.add_bridge_route(&bridge_id, "admin", &usdc_id, remote_usdc, BridgeMode::Mint, 6).unwrap();
+
+        // Out: TRIBE is locked in the bridge
+        let transfer = engine.bridge_out(&bridge_id, "alice".to_string(), &tribe, "0x00000000000000000000000000000000000000c1", 400).unwrap();
+        assert_eq!(transfer.remote_amount, 400 * 10u128.pow(12));
+        assert_eq!(engine.get_token_balance(&tribe, &bridge_id), 400);
+        assert!(engine.bridge_out(&bridge_id, "alice".to_string(), &tribe, "0x00000000000000000000000000000000000000c1", 5_000).is_err());
+        assert_eq!(engine.get_bridge(&bridge_id).unwrap().outbound.len(), 1);
+
+        // Back in: released to bob once both relayers attest
+        let deposit = BridgeDeposit {
+            remote_chain_id: 11155111,
+            tx_hash: "0x01".to_string(),
+            log_index: 3,
+            remote_token: remote_tribe.to_string(),
+            sender: "0x00000000000000000000000000000000000000c1".to_string(),
+            recipient: "bob".to_string(),
+            amount: 150 * 10u128.pow(12),
+        };
+        assert_eq!(engine.attest_bridge_deposit(&bridge_id, "r1", deposit.clone()).unwrap(), None);
+        assert_eq!(engine.attest_bridge_deposit(&bridge_id, "r2", deposit.clone()).unwrap(), Some(150));
+        assert_eq!(engine.get_token_balance(&tribe, "bob"), 150);
+        assert_eq!(engine.get_bridge(&bridge_id).unwrap().locked[&tribe], 250);
+
+        // Wrapped tokens are minted in and burned out
+        let deposit = BridgeDeposit { tx_hash: "0x02".to_string(), remote_token: remote_usdc.to_string(), amount: 70, ..deposit };
+        engine.attest_bridge_deposit(&bridge_id, "r1", deposit.clone()).unwrap();
+        assert_eq!(engine.attest_bridge_deposit(&bridge_id, "r2", deposit).unwrap(), Some(70));
+        engine.bridge_out(&bridge_id, "bob".to_string(), &usdc_id, "0x00000000000000000000000000000000000000c1", 30).unwrap();
+        assert_eq!(engine.get_token_balance(&usdc_id, "bob"), 40);
+        assert_eq!(engine.token_contracts[&usdc_id].total_supply, 41);
+    }
+
+    #[test]
+    fn test_ibc_transfer_and_tensor_outsourcing() {
+        let validators: HashMap<String, FinalityValidator> = ["v1", "v2", "v3"].iter()
+            .map(|name| (name.to_string(), FinalityValidator { stake: 100, public_key: format!("{}_key", name) }))
+            .collect();
+        // Header of `engine`'s chain signed by 2 of its 3 validators
+        let signed = |engine: &ContractEngine, chain_id: u64, height: u64| {
+            let mut header = SignedHeader::new(engine.ibc.header(chain_id, height, &format!("hash{}", height)));
+            header.sign("v1", "v1_key");
+            header.sign("v2", "v2_key");
+            header
+        };
+        let (mut a, mut b) = (ContractEngine::new(), ContractEngine::new());
+        let client_on_a = a.create_ibc_client(validators.clone(), &b.ibc.header(2, 0, "genesis")).unwrap();
+        let client_on_b = b.create_ibc_client(validators, &a.ibc.header(1, 0, "genesis")).unwrap();
+        // Each chain advances a block and its latest header is relayed to the other
+        let relay = |from: &mut ContractEngine, to: &mut ContractEngine, chain_id: u64, client_id: &str| {
+            from.set_block_height(from.block_height + 1);
+            to.update_ibc_client(client_id, &signed(from, chain_id, from.block_height)).unwrap();
+            from.block_height
+        };
+
+        let chan_a = a.ibc_channel_open_init(&client_on_a).unwrap();
+        let height = relay(&mut a, &mut b, 1, &client_on_b);
+        let chan_b = b.ibc_channel_open_try(&client_on_b, &chan_a, height, &a.ibc.prove(&ibc::channel_key(&chan_a, ChannelState::Init, None))).unwrap();
+        let height = relay(&mut b, &mut a, 2, &client_on_a);
+        let proof = b.ibc.prove(&ibc::channel_key(&chan_b, ChannelState::TryOpen, Some(&chan_a)));
+        a.ibc_channel_open_ack(&chan_a, &chan_b, height, &proof).unwrap();
+        let height = relay(&mut a, &mut b, 1, &client_on_b);
+        b.ibc_channel_open_confirm(&chan_b, height, &a.ibc.prove(&ibc::channel_key(&chan_a, ChannelState::Open, Some(&chan_b)))).unwrap();
+
+        // TRIBE goes out to B as vouchers, and some comes back
+        let tribe = a.create_token("Tribe".to_string(), "TRIBE".to_string(), 1_000, 6, "alice".to_string()).unwrap();
+        let packet = a.ibc_transfer(&chan_a, "alice".to_string(), &tribe, "bob", 300, 1_000).unwrap();
+        let height = relay(&mut a, &mut b, 1, &client_on_b);
+        let ack = b.ibc_recv_packet(&packet, height, &a.ibc.prove(&ibc::commitment_key(&packet))).unwrap();
+        let Acknowledgement::Success(voucher) = ack.clone() else { panic!("transfer failed: {:?}", ack) };
+        assert_eq!(b.get_token_balance(&voucher, "bob"), 300);
+        assert_eq!(b.token_contracts[&voucher].token_info.symbol, "TRIBE");
+        let height = relay(&mut b, &mut a, 2, &client_on_a);
+        a.ibc_acknowledge_packet(&packet, &ack, height, &b.ibc.prove(&ibc::ack_key(&chan_b, packet.sequence, &ack))).unwrap();
+        assert_eq!(a.get_token_balance(&tribe, &ibc_escrow_address(&chan_a)), 300);
+
+        let back = b.ibc_transfer(&chan_b, "bob".to_string(), &voucher, "carol", 100, 1_000).unwrap();
+        assert_eq!(b.token_contracts[&voucher].total_supply, 200);
+        let height = relay(&mut b, &mut a, 2, &client_on_a);
+        assert_eq!(a.ibc_recv_packet(&back, height, &b.ibc.prove(&ibc::commitment_key(&back))).unwrap(), Acknowledgement::Success(tribe.clone()));
+        assert_eq!(a.get_token_balance(&tribe, "carol"), 100);
+
+        // A transfer B never receives is refunded after its timeout
+        let late = a.ibc_transfer(&chan_a, "alice".to_string(), &tribe, "bob", 50, b.block_height + 1).unwrap();
+        assert_eq!(a.get_token_balance(&tribe, "alice"), 650);
+        relay(&mut b, &mut a, 2, &client_on_a);
+        let height = relay(&mut b, &mut a, 2, &client_on_a);
+        a.ibc_timeout_packet(&late, height, &b.ibc.prove(&ibc::receipt_key(&chan_b, late.sequence))).unwrap();
+        assert_eq!(a.get_token_balance(&tribe, "alice"), 700);
+
+        // A tensor task is mined on B, paid in vouchers, and its result returns to A
+        let task = TensorTask {
+            id: String::new(),
+            operation: "relu".to_string(),
+            input_data: vec![-1.0, 2.0],
+            expected_output_size: 2,
+            max_computation_time: 60,
+            reward: 40,
+            requester: "alice".to_string(),
+            completed: false,
+            result: None,
+            assigned_miner: None,
+            created_at: 0,
+        };
+        let task_id = a.ibc_outsource_tensor_task(&chan_a, &tribe, task, 10, 1_000).unwrap();
+        let packet = a.ibc.sent.values().find(|packet| matches!(&packet.data, PacketData::TensorTask { .. })).unwrap().clone();
+        let height = relay(&mut a, &mut b, 1, &client_on_b);
+        assert_eq!(b.ibc_recv_packet(&packet, height, &a.ibc.prove(&ibc::commitment_key(&packet))).unwrap(), Acknowledgement::Success(task_id.clone()));
+        assert_eq!(b.take_tensor_tasks()[0].id, task_id);
+        assert_eq!(b.get_token_balance(&voucher, &task_id), 40);
+        assert!(b.ibc_return_tensor_result(&task_id, 1_000).is_err());
+
+        let escrow = b.tensor_escrows.get_mut(&task_id).unwrap();
+        escrow.status = EscrowStatus::Paid { miner: "miner_b".to_string() };
+        escrow.task.result = Some(vec![0.0, 2.0]);
+        let result = b.ibc_return_tensor_result(&task_id, 1_000).unwrap();
+        let height = relay(&mut b, &mut a, 2, &client_on_a);
+        a.ibc_recv_packet(&result, height, &b.ibc.prove(&ibc::commitment_key(&result))).unwrap();
+        let outsourced = &a.ibc.outsourced[&task_id];
+        assert_eq!((outsourced.miner.as_deref(), outsourced.result.clone()), (Some("miner_b"), Some(vec![0.0, 2.0])));
+    }
+
+    #[test]
+    fn test_vesting_claims() {
+        let mut engine = ContractEngine::new();
+        let token_id = engine.create_token(
+            "Tribe".to_string(),
+            "TRIBE".to_string(),
+            1000000,
+            6,
+            "team".to_string(),
+        ).unwrap();
+
+        let schedule = VestingSchedule::Linear {
+            start: Utc::now() - chrono::Duration::days(50),
+            duration: chrono::Duration::days(100),
+        };
+        let vesting_id = engine.create_vesting(
+            token_id.clone(), "team".to_string(), "alice".to_string(), 10000, schedule,
+        ).unwrap();
+        assert_eq!(engine.get_token_balance(&token_id, &vesting_id), 10000);
+
+        let claimable = engine.get_claimable(&vesting_id);
+        assert!((4999..=5001).contains(&claimable));
+        let claimed = engine.claim_vested(&vesting_id, "alice").unwrap();
+        assert_eq!(engine.get_token_balance(&token_id, "alice"), claimed);
+        assert_eq!(engine.get_token_balance(&token_id, &vesting_id), 10000 - claimed);
+    }
+
     #[test]
     fn test_token_creation() {
         let mut engine = ContractEngine::new();
@@ -304,6 +1861,20 @@ mod tests {
         assert!(engine.token_contracts.contains_key(&token_id));
     }
 
+    #[test]
+    fn test_genesis_tokens() {
+        let spec = ChainSpec::dev();
+        let engine = ContractEngine::from_chain_spec(&spec).unwrap();
+        let token_id = spec.tokens[0].token_id(spec.chain_id);
+        assert_eq!(engine.get_token_balance(&token_id, "dev"), spec.tokens[0].total_supply);
+        assert!(engine.token_contracts[&token_id].is_mintable);
+
+        // Another node builds the same genesis tokens
+        let other = ContractEngine::from_chain_spec(&spec).unwrap();
+        assert!(other.token_contracts.contains_key(&token_id));
+        assert!(ContractEngine::from_chain_spec(&ChainSpec::mainnet()).unwrap().token_contracts.is_empty());
+    }
+
     #[test]
     fn test_token_transfer() {
         let mut engine = ContractEngine::new();
@@ -328,6 +1899,341 @@ mod tests {
         assert_eq!(engine.get_token_balance(&token_id, "recipient"), 1000);
     }
 
+    #[test]
+    fn test_multicall_is_atomic() {
+        let mut engine = ContractEngine::new();
+        let token_id = engine.create_token(
+            "Test Token".to_string(),
+            "TEST".to_string(),
+            1000,
+            6,
+            "trader".to_string(),
+        ).unwrap();
+
+        let transfer = |to: &str, amount| MulticallOperation::Token {
+            token_id: token_id.clone(),
+            operation: TokenOperation::Transfer { from: "trader".to_string(), to: to.to_string(), amount },
+        };
+        let missing_call = MulticallOperation::Call(ContractCall::new(
+            "missing".to_string(), "swap".to_string(), vec![], "trader".to_string(),
+        ));
+
+        assert!(engine.multicall("trader".to_string(), vec![transfer("alice", 100), missing_call]).is_err());
+        assert_eq!(engine.get_token_balance(&token_id, "alice"), 0);
+        assert_eq!(engine.get_token_balance(&token_id, "trader"), 1000);
+
+        engine.multicall("trader".to_string(), vec![transfer("alice", 100), transfer("bob", 50)]).unwrap();
+        assert_eq!(engine.get_token_balance(&token_id, "trader"), 850);
+    }
+
+    #[test]
+    fn test_flash_swap_rolls_back() {
+        let mut engine = ContractEngine::new();
+        let tribe = engine.create_token(
+            "Tribe".to_string(),
+            "TRIBE".to_string(),
+            1000000,
+            6,
+            "creator".to_string(),
+        ).unwrap();
+        let pool_id = engine.create_liquidity_pool(tribe.clone(), "USDC".to_string(), 10000, 20000, "creator".to_string(), 0.003).unwrap();
+        engine.transfer_token(tribe.clone(), "creator".to_string(), pool_id.clone(), 10000).unwrap();
+
+        // Borrower can't cover the fee, so nothing changes
+        let arbitrage = vec![MulticallOperation::Token {
+            token_id: tribe.clone(),
+            operation: TokenOperation::Transfer { from: "borrower".to_string(), to: "dex".to_string(), amount: 5000 },
+        }];
+        assert!(engine.flash_swap(&pool_id, "borrower".to_string(), tribe.clone(), 5000, arbitrage).is_err());
+        assert_eq!(engine.get_token_balance(&tribe, &pool_id), 10000);
+        assert_eq!(engine.get_token_balance(&tribe, "dex"), 0);
+
+        engine.transfer_token(tribe.clone(), "creator".to_string(), "borrower".to_string(), 100).unwrap();
+        let fee = engine.flash_swap(&pool_id, "borrower".to_string(), tribe.clone(), 5000, vec![]).unwrap();
+        assert_eq!(engine.get_token_balance(&tribe, &pool_id), 10000 + fee);
+        assert_eq!(engine.liquidity_pools[&pool_id].reserve_a, 10000 + fee);
+    }
+
+    #[test]
+    fn test_order_book_settlement() {
+        let mut engine = ContractEngine::new();
+        let tribe = engine.create_token("Tribe".to_string(), "TRIBE".to_string(), 1000000, 6, "seller".to_string()).unwrap();
+        let usdc = engine.create_token("USD Coin".to_string(), "USDC".to_string(), 1000000, 6, "buyer".to_string()).unwrap();
+        let book_id = engine.create_order_book(tribe.clone(), usdc.clone(), 0.0, 0.0).unwrap();
+
+        let price = 2 * orderbook::PRICE_PRECISION;
+        let ask = engine.place_limit_order(&book_id, "seller".to_string(), OrderSide::Sell, price, 1000).unwrap();
+        assert_eq!(engine.get_token_balance(&tribe, &book_id), 1000);
+
+        engine.place_limit_order(&book_id, "buyer".to_string(), OrderSide::Buy, price, 400).unwrap();
+        assert_eq!(engine.get_token_balance(&tribe, "buyer"), 400);
+        assert_eq!(engine.get_token_balance(&usdc, "seller"), 800);
+
+        // Can't lock more than the buyer holds; the book is left untouched
+        assert!(engine.place_limit_order(&book_id, "buyer".to_string(), OrderSide::Buy, price, 10_000_000).is_err());
+        assert_eq!(engine.order_books[&book_id].orders[&ask.order_id].remaining, 600);
+
+        assert_eq!(engine.cancel_limit_order(&book_id, ask.order_id, "seller").unwrap(), 600);
+        assert_eq!(engine.get_token_balance(&tribe, &book_id), 0);
+    }
+
+    #[test]
+    fn test_lending_market() {
+        let mut engine = ContractEngine::new();
+        let usdc = engine.create_token("USD Coin".to_string(), "USDC".to_string(), 1000000, 6, "alice".to_string()).unwrap();
+        let tribe = engine.create_token("Tribe".to_string(), "TRIBE".to_string(), 1000000, 6, "bob".to_string()).unwrap();
+
+        // TRIBE has traded at 2 USDC for the last hour
+        let pool_id = engine.create_liquidity_pool(tribe.clone(), usdc.clone(), 10000, 20000, "lp".to_string(), 0.003).unwrap();
+        engine.liquidity_pools.get_mut(&pool_id).unwrap().price_oracle = PriceOracle::new(2.0, chrono::Utc::now() - chrono::Duration::hours(1));
+
+        let market_id = engine.create_lending_market(usdc.clone(), tribe.clone(), pool_id, 0.5, 0.8, 0.001).unwrap();
+        engine.supply_lending(&market_id, "alice".to_string(), 10000).unwrap();
+        engine.deposit_collateral(&market_id, "bob".to_string(), 1000).unwrap();
+        assert_eq!(engine.collateral_price(&market_id).unwrap(), 2.0);
+
+        assert!(engine.borrow(&market_id, "bob".to_string(), 1001).is_err());
+        engine.borrow(&market_id, "bob".to_string(), 1000).unwrap();
+        assert_eq!(engine.get_token_balance(&usdc, "bob"), 1000);
+
+        engine.set_block_height(100);
+        assert!(engine.liquidate(&market_id, "alice".to_string(), "bob", 500).is_err());
+        assert!(engine.repay(&market_id, "bob".to_string(), 2000).is_err()); // Owes 1100 but only holds 1000
+        assert_eq!(engine.repay(&market_id, "bob".to_string(), 500).unwrap(), 500);
+        assert_eq!(engine.lending_markets[&market_id].debt_of("bob"), 600);
+    }
+
+    #[test]
+    fn test_block_journal_rollback() {
+        let mut engine = ContractEngine::new();
+
+        engine.begin_block(1);
+        let token_id = engine.create_token("Tribe".to_string(), "TRIBE".to_string(), 1000, 6, "alice".to_string()).unwrap();
+        engine.transfer_token(token_id.clone(), "alice".to_string(), "bob".to_string(), 100).unwrap();
+        engine.commit_block();
+
+        // A failing step aborts everything earlier in the block
+        engine.begin_block(2);
+        engine.transfer_token(token_id.clone(), "alice".to_string(), "bob".to_string(), 300).unwrap();
+        assert!(engine.transfer_token(token_id.clone(), "bob".to_string(), "carol".to_string(), 5000).is_err());
+        engine.abort_block();
+        assert_eq!(engine.get_token_balance(&token_id, "bob"), 100);
+
+        engine.begin_block(2);
+        engine.transfer_token(token_id.clone(), "bob".to_string(), "carol".to_string(), 50).unwrap();
+        engine.commit_block();
+        assert_eq!(engine.get_token_balance(&token_id, "carol"), 50);
+
+        // Reorg back past the block that created the token
+        engine.revert_to_height(1).unwrap();
+        assert_eq!(engine.get_token_balance(&token_id, "carol"), 0);
+        assert_eq!(engine.get_token_balance(&token_id, "bob"), 100);
+        engine.revert_to_height(0).unwrap();
+        assert!(engine.get_token_info(&token_id).is_none());
+    }
+
+    #[test]
+    fn test_abi_validated_calls() {
+        let mut engine = ContractEngine::new();
+        let transfer = MethodSignature::new(
+            "transfer".to_string(),
+            vec![
+                Parameter::new("to".to_string(), ParameterType::Address),
+                Parameter::new("amount".to_string(), ParameterType::Uint64),
+            ],
+            vec![],
+        );
+        let metadata = ContractMetadata { abi: vec![transfer.clone()], ..ContractMetadata::default() };
+        let deployment = ContractDeployment::new(ContractType::Custom, vec![1, 2, 3], "alice".to_string())
+            .with_metadata(metadata);
+        let address = engine.deploy_contract(deployment).unwrap();
+
+        let call = ContractCall::new(address.clone(), "transfer".to_string(), vec![], "alice".to_string())
+            .with_abi_args(&transfer, &[AbiValue::Address("bob".to_string()), AbiValue::Uint(10)])
+            .unwrap();
+        assert!(engine.validate_call(&call).is_ok());
+
+        // Raw bytes that don't match the schema never reach the VM
+        let raw = ContractCall::new(address.clone(), "transfer".to_string(), vec![1, 2, 3], "alice".to_string());
+        assert!(engine.call_contract(raw).is_err());
+        let unknown = ContractCall::new(address, "mint".to_string(), vec![], "alice".to_string());
+        assert!(engine.validate_call(&unknown).is_err());
+    }
+
+    #[test]
+    fn test_query_contract() {
+        let mut engine = ContractEngine::new();
+        let get = MethodSignature::new("get".to_string(), vec![], vec![]).view();
+        let set = MethodSignature::new("set".to_string(), vec![], vec![]);
+        let metadata = ContractMetadata { abi: vec![get, set], ..ContractMetadata::default() };
+        let deployment = ContractDeployment::new(ContractType::Custom, vec![1], "alice".to_string()).with_metadata(metadata);
+        let address = engine.deploy_contract(deployment).unwrap();
+
+        let query = |method: &str| ContractCall::new(address.clone(), method.to_string(), vec![], "bob".to_string());
+        assert!(engine.query_contract(query("get")).unwrap().success);
+        assert!(engine.query_contract(query("set")).is_err());
+        assert!(engine.query_contract(query("get").with_value(1)).is_err());
+
+        // Writes made while answering a query are dropped
+        let plain = engine.deploy_contract(ContractDeployment::new(ContractType::Custom, vec![2], "alice".to_string())).unwrap();
+        let writes = bincode::serialize(&vec![("a".to_string(), vec![1u8])]).unwrap();
+        let write = ContractCall::new(plain.clone(), vm::STORAGE_WRITE_METHOD.to_string(), writes, "alice".to_string());
+        assert!(engine.query_contract(write).unwrap().success);
+        assert!(!engine.vm.storage.contains_key(&vm::contract_slot_key(&plain, "a")));
+    }
+
+    #[test]
+    fn test_proxy_upgrades() {
+        let mut engine = ContractEngine::new();
+        let deploy = |engine: &mut ContractEngine, code: u8, layout: Vec<StorageSlot>| {
+            let metadata = ContractMetadata { storage_layout: layout, ..ContractMetadata::default() };
+            let deployment = ContractDeployment::new(ContractType::Custom, vec![code], "alice".to_string()).with_metadata(metadata);
+            engine.deploy_contract(deployment).unwrap()
+        };
+        let owner = StorageSlot::new("owner".to_string(), ParameterType::Address);
+        let count = StorageSlot::new("count".to_string(), ParameterType::Uint64);
+        let v1 = deploy(&mut engine, 1, vec![owner.clone(), count.clone()]);
+        let proxy = engine.deploy_proxy(&v1, "alice".to_string(), "alice".to_string()).unwrap();
+        assert!(engine.deploy_proxy(&proxy, "alice".to_string(), "alice".to_string()).is_err());
+
+        // Calls run the implementation's code in the proxy's context
+        let result = engine.call_contract(ContractCall::new(proxy.clone(), "ping".to_string(), vec![], "bob".to_string())).unwrap();
+        assert!(result.success);
+        assert_eq!(result.logs[0].contract_address, proxy);
+
+        // Reordering or retyping existing slots breaks the layout
+        let retyped = deploy(&mut engine, 2, vec![owner.clone(), StorageSlot::new("count".to_string(), ParameterType::Uint32)]);
+        let dropped = deploy(&mut engine, 3, vec![owner.clone()]);
+        assert!(engine.upgrade_proxy(&proxy, &retyped, "alice").is_err());
+        assert!(engine.upgrade_proxy(&proxy, &dropped, "alice").is_err());
+
+        let v2 = deploy(&mut engine, 4, vec![owner, count, StorageSlot::new("paused".to_string(), ParameterType::Bool)]);
+        assert!(engine.upgrade_proxy(&proxy, &v2, "bob").is_err());
+        let args = abi::encode(&[ParameterType::Address], &[AbiValue::Address(v2.clone())]).unwrap();
+        let upgrade = ContractCall::new(proxy.clone(), vm::PROXY_UPGRADE_METHOD.to_string(), args, "alice".to_string());
+        assert!(engine.call_contract(upgrade).unwrap().success);
+        assert_eq!(engine.proxy_implementation(&proxy), Some(v2.clone()));
+
+        // Upgrade rights can move to a governance contract
+        engine.change_proxy_admin(&proxy, "dao".to_string(), "alice").unwrap();
+        assert!(engine.upgrade_proxy(&proxy, &v1, "alice").is_err());
+        assert!(engine.upgrade_proxy(&proxy, &v1, "dao").is_err()); // v1 drops the `paused` slot
+        assert_eq!(engine.proxy_admin(&proxy), Some("dao".to_string()));
+    }
+
+    #[test]
+    fn test_storage_refunds_and_self_destruct() {
+        let mut engine = ContractEngine::new();
+        let deployment = ContractDeployment::new(ContractType::Custom, vec![1], "alice".to_string());
+        let address = engine.deploy_contract(deployment).unwrap();
+        engine.fund_contract(&address, 500).unwrap();
+
+        let write = |writes: Vec<(&str, Vec<u8>)>| {
+            let writes: Vec<(String, Vec<u8>)> = writes.into_iter().map(|(k, v)| (k.to_string(), v)).collect();
+            ContractCall::new(address.clone(), vm::STORAGE_WRITE_METHOD.to_string(), bincode::serialize(&writes).unwrap(), "alice".to_string())
+        };
+        let result = engine.call_contract(write(vec![("a", vec![1]), ("b", vec![2])])).unwrap();
+        assert_eq!(result.gas_used, 2 * vm::STORAGE_SET_GAS);
+
+        // Clearing a slot earns a refund, capped at a fifth of the gas used
+        let result = engine.call_contract(write(vec![("a", vec![])])).unwrap();
+        assert_eq!(engine.vm.gas_refund, vm::STORAGE_UPDATE_GAS / vm::MAX_REFUND_QUOTIENT);
+        assert_eq!(result.gas_used, vm::STORAGE_UPDATE_GAS - engine.vm.gas_refund);
+        assert!(!engine.vm.storage.contains_key(&vm::contract_slot_key(&address, "a")));
+
+        let beneficiary = abi::encode(&[ParameterType::Address], &[AbiValue::Address("bob".to_string())]).unwrap();
+        let destruct = |caller: &str| ContractCall::new(address.clone(), vm::SELF_DESTRUCT_METHOD.to_string(), beneficiary.clone(), caller.to_string());
+        assert!(!engine.call_contract(destruct("mallory")).unwrap().success);
+        assert!(engine.call_contract(destruct("alice")).unwrap().success);
+
+        assert!(engine.get_contract_state(&address).is_none());
+        assert_eq!(engine.vm.storage_bytes(&address), 0);
+        assert_eq!(engine.take_native_credits().get("bob"), Some(&500));
+    }
+
+    #[test]
+    fn test_storage_rent() {
+        let mut engine = ContractEngine::new().with_storage_rent(1);
+        let deploy = |engine: &mut ContractEngine, code: u8| {
+            engine.deploy_contract(ContractDeployment::new(ContractType::Custom, vec![code], "alice".to_string())).unwrap()
+        };
+        let (funded, unfunded) = (deploy(&mut engine, 1), deploy(&mut engine, 2));
+        let bytes = engine.vm.storage_bytes(&funded) as u64;
+        engine.fund_contract(&funded, bytes * 10).unwrap();
+        engine.fund_contract(&unfunded, bytes).unwrap();
+
+        engine.begin_block(1);
+        assert_eq!(engine.get_contract_state(&funded).unwrap().state.balance, bytes * 9);
+        assert_eq!(engine.get_contract_state(&unfunded).unwrap().state.balance, 0);
+
+        // Two more blocks of rent is more than the unfunded contract holds
+        engine.begin_block(3);
+        let evicted = engine.get_contract_state(&unfunded).unwrap();
+        assert!(!evicted.state.is_active);
+        assert_eq!(engine.vm.storage_bytes(&unfunded), 0);
+        assert_eq!(engine.get_contract_state(&funded).unwrap().state.balance, bytes * 7);
+        assert!(engine.call_contract(ContractCall::new(unfunded.clone(), "ping".to_string(), vec![], "bob".to_string())).is_err());
+
+        // Rolling back the block restores the evicted contract
+        engine.abort_block();
+        assert!(engine.get_contract_state(&unfunded).unwrap().state.is_active);
+    }
+
+    #[test]
+    fn test_tensor_escrow() {
+        let mut engine = ContractEngine::new();
+        let token_id = engine.create_token("Tribe".to_string(), "TRIBE".to_string(), 1000, 6, "alice".to_string()).unwrap();
+        let deployment = ContractDeployment::new(ContractType::TensorCompute, vec![1], "alice".to_string());
+        let contract = engine.deploy_contract(deployment).unwrap();
+        let request = TensorTask {
+            id: String::new(),
+            operation: "relu".to_string(),
+            input_data: vec![-1.0, 2.0],
+            expected_output_size: 2,
+            max_computation_time: 60,
+            reward: 100,
+            requester: "alice".to_string(),
+            completed: false,
+            result: None,
+            assigned_miner: None,
+            created_at: 0,
+        };
+
+        engine.begin_block(1);
+        let paid = engine.process_tensor_computation(contract.clone(), token_id.clone(), request.clone(), 10).unwrap();
+        let refunded = engine.process_tensor_computation(contract.clone(), token_id.clone(), request, 10).unwrap();
+        assert_eq!(engine.get_token_balance(&token_id, "alice"), 800);
+        let tasks = engine.take_tensor_tasks();
+        assert_eq!(tasks.iter().map(|task| task.id.clone()).collect::<Vec<_>>(), vec![paid.clone(), refunded.clone()]);
+
+        let result = vec![0.0, 2.0];
+        let mut proof = AI3Proof {
+            task_id: paid.clone(),
+            optimization_factor: 1.0,
+            tensor_hash: "forged".to_string(),
+            computation_time: 5,
+            miner_signature: String::new(),
+            commitment_nonce: 0,
+        };
+        assert!(engine.distribute_tensor_rewards(paid.clone(), "miner".to_string(), &proof, result.clone()).is_err());
+        proof.tensor_hash = engine.tensor_escrows[&paid].task.result_hash(&result);
+        assert_eq!(engine.distribute_tensor_rewards(paid.clone(), "miner".to_string(), &proof, result.clone()).unwrap(), 100);
+        assert_eq!(engine.get_token_balance(&token_id, "miner"), 100);
+        assert!(engine.distribute_tensor_rewards(paid, "miner".to_string(), &proof, result.clone()).is_err());
+
+        // Awarded at 60, so 40 goes back now and only the rest on timeout
+        assert!(engine.award_tensor_task(refunded.clone(), "bob".to_string(), 150).is_err());
+        engine.award_tensor_task(refunded.clone(), "bob".to_string(), 60).unwrap();
+        assert_eq!(engine.get_token_balance(&token_id, "alice"), 840);
+        proof.task_id = refunded.clone();
+        assert!(engine.distribute_tensor_rewards(refunded.clone(), "miner".to_string(), &proof, result).is_err());
+
+        // Nobody delivered the second task before its deadline
+        engine.begin_block(12);
+        assert_eq!(engine.tensor_escrows[&refunded].status, EscrowStatus::Refunded);
+        assert_eq!(engine.get_token_balance(&token_id, "alice"), 900);
+    }
+
     #[test]
     fn test_staking_contract() {
         let mut engine = ContractEngine::new();
@@ -370,12 +2276,28 @@ mod tests {
         ).unwrap();
 
         let pool_id = engine.create_liquidity_pool(
-            token_a,
+            token_a.clone(),
             token_b,
+            10000,
+            20000,
+            "creator".to_string(),
             0.003, // 0.3% fee
         ).unwrap();
 
         assert!(!pool_id.is_empty());
         assert!(engine.liquidity_pools.contains_key(&pool_id));
+
+        let minted = engine.add_liquidity(pool_id.clone(), "lp".to_string(), 1000, 2000, 1).unwrap();
+        assert!(minted > 0);
+        assert!(engine.add_liquidity(pool_id.clone(), "lp".to_string(), 1000, 2000, minted + 1).is_err());
+
+        let amount_out = engine.swap_tokens(pool_id.clone(), "trader".to_string(), token_a.clone(), 1000, 1).unwrap();
+        assert!(amount_out > 0 && amount_out < 2000);
+        assert!(engine.swap_tokens(pool_id.clone(), "trader".to_string(), "NOPE".to_string(), 1000, 0).is_err());
+
+        let stats = engine.get_pool_info(&pool_id).unwrap();
+        assert_eq!(stats.liquidity_providers_count, 2);
+        assert!(engine.get_pool_info("missing").is_none());
+        assert!(engine.add_liquidity("missing".to_string(), "lp".to_string(), 1, 1, 0).is_err());
     }
 } 
\ No newline at end of file
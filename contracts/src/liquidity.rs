@@ -1,7 +1,8 @@
 use tribechain_core::{TribeResult, TribeError};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Utc, Duration};
+use crate::concentrated::{ConcentratedLiquidity, RangeSwap};
 
 /// Liquidity pool contract implementation
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,6 +24,8 @@ pub struct LiquidityPool {
     pub created_at: DateTime<Utc>,
     pub last_trade: Option<DateTime<Utc>>,
     pub price_oracle: PriceOracle,
+    #[serde(default)]
+    pub concentrated: Option<ConcentratedLiquidity>, // Set for range-position pools
 }
 
 /// Individual liquidity position
@@ -39,6 +42,9 @@ pub struct LiquidityPosition {
     pub is_active: bool,
 }
 
+/// Price observations kept for TWAP queries; older ones are dropped
+pub const MAX_OBSERVATIONS: usize = 4096;
+
 /// Price oracle for tracking price history
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PriceOracle {
@@ -46,6 +52,66 @@ pub struct PriceOracle {
     pub price_history: Vec<PricePoint>,
     pub twap_24h: f64, // Time-weighted average price over 24 hours
     pub last_update: DateTime<Utc>,
+    #[serde(default)]
+    pub observations: Vec<PriceObservation>,
+}
+
+/// Running price-time accumulator, written whenever the price changes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceObservation {
+    pub timestamp: DateTime<Utc>,
+    pub price: f64, // Price in effect from `timestamp` until the next observation
+    pub price_cumulative: f64, // Sum of price * seconds up to `timestamp`
+}
+
+impl PriceOracle {
+    pub fn new(price: f64, now: DateTime<Utc>) -> Self {
+        Self {
+            current_price: price,
+            price_history: vec![PricePoint {
+                price,
+                timestamp: now,
+                volume_a: 0,
+                volume_b: 0,
+            }],
+            twap_24h: price,
+            last_update: now,
+            observations: vec![PriceObservation { timestamp: now, price, price_cumulative: 0.0 }],
+        }
+    }
+
+    /// Accumulate the outgoing price up to `now` and start the new one
+    pub fn record(&mut self, price: f64, now: DateTime<Utc>) {
+        let price_cumulative = self.cumulative_at(now).unwrap_or(0.0);
+        self.observations.push(PriceObservation { timestamp: now, price, price_cumulative });
+        if self.observations.len() > MAX_OBSERVATIONS {
+            let excess = self.observations.len() - MAX_OBSERVATIONS;
+            self.observations.drain(..excess);
+        }
+        self.current_price = price;
+        self.last_update = now;
+    }
+
+    /// Accumulator value at `time`, interpolated from the last observation before it
+    fn cumulative_at(&self, time: DateTime<Utc>) -> Option<f64> {
+        let index = self.observations.partition_point(|observation| observation.timestamp <= time);
+        let observation = self.observations.get(index.checked_sub(1)?)?;
+        let seconds = (time - observation.timestamp).num_milliseconds() as f64 / 1000.0;
+        Some(observation.price_cumulative + observation.price * seconds)
+    }
+
+    /// Time-weighted average price over the `window` ending at `now`
+    pub fn twap_at(&self, now: DateTime<Utc>, window: Duration) -> TribeResult<f64> {
+        if window <= Duration::zero() {
+            return Err(TribeError::Contract("TWAP window must be positive".to_string()));
+        }
+        let start = now - window;
+        let (from, to) = match (self.cumulative_at(start), self.cumulative_at(now)) {
+            (Some(from), Some(to)) => (from, to),
+            _ => return Err(TribeError::Contract("TWAP window exceeds oracle history".to_string())),
+        };
+        Ok((to - from) / (window.num_milliseconds() as f64 / 1000.0))
+    }
 }
 
 /// Price point for historical data
@@ -111,15 +177,15 @@ impl LiquidityPool {
         fee_rate: f64,
     ) -> TribeResult<Self> {
         if token_a == token_b {
-            return Err(TribeError::InvalidOperation("Cannot create pool with same tokens".to_string()));
+            return Err(TribeError::Contract("Cannot create pool with same tokens".to_string()));
         }
 
         if initial_a == 0 || initial_b == 0 {
-            return Err(TribeError::InvalidOperation("Initial liquidity cannot be zero".to_string()));
+            return Err(TribeError::Contract("Initial liquidity cannot be zero".to_string()));
         }
 
-        if fee_rate < 0.0 || fee_rate > 0.1 {
-            return Err(TribeError::InvalidOperation("Fee rate must be between 0 and 10%".to_string()));
+        if !(0.0..=0.1).contains(&fee_rate) {
+            return Err(TribeError::Contract("Fee rate must be between 0 and 10%".to_string()));
         }
 
         let pool_id = Self::generate_pool_id(&token_a, &token_b);
@@ -141,17 +207,7 @@ impl LiquidityPool {
         });
 
         let initial_price = initial_b as f64 / initial_a as f64;
-        let price_oracle = PriceOracle {
-            current_price: initial_price,
-            price_history: vec![PricePoint {
-                price: initial_price,
-                timestamp: Utc::now(),
-                volume_a: 0,
-                volume_b: 0,
-            }],
-            twap_24h: initial_price,
-            last_update: Utc::now(),
-        };
+        let price_oracle = PriceOracle::new(initial_price, Utc::now());
 
         Ok(Self {
             id: pool_id,
@@ -171,9 +227,130 @@ impl LiquidityPool {
             created_at: Utc::now(),
             last_trade: None,
             price_oracle,
+            concentrated: None,
+        })
+    }
+
+    /// Create an empty pool where liquidity is provided over price ranges
+    /// instead of along the whole constant-product curve
+    pub fn new_concentrated(
+        token_a: String,
+        token_b: String,
+        initial_price: f64,
+        tick_spacing: i32,
+        fee_rate: f64,
+    ) -> TribeResult<Self> {
+        if token_a == token_b {
+            return Err(TribeError::Contract("Cannot create pool with same tokens".to_string()));
+        }
+
+        if !(0.0..=0.1).contains(&fee_rate) {
+            return Err(TribeError::Contract("Fee rate must be between 0 and 10%".to_string()));
+        }
+
+        let concentrated = ConcentratedLiquidity::new(initial_price, tick_spacing)?;
+        let now = Utc::now();
+        Ok(Self {
+            id: Self::generate_pool_id(&token_a, &token_b),
+            token_a,
+            token_b,
+            reserve_a: 0,
+            reserve_b: 0,
+            total_liquidity: 0,
+            liquidity_providers: HashMap::new(),
+            fee_rate,
+            protocol_fee_rate: 0.0, // Range positions earn the whole fee
+            accumulated_fees_a: 0,
+            accumulated_fees_b: 0,
+            total_volume_a: 0,
+            total_volume_b: 0,
+            is_active: true,
+            created_at: now,
+            last_trade: None,
+            price_oracle: PriceOracle::new(concentrated.price(), now),
+            concentrated: Some(concentrated),
         })
     }
 
+    fn concentrated_mut(&mut self) -> TribeResult<&mut ConcentratedLiquidity> {
+        self.concentrated.as_mut()
+            .ok_or_else(|| TribeError::Contract("Pool does not use range positions".to_string()))
+    }
+
+    fn require_full_range(&self) -> TribeResult<()> {
+        if self.concentrated.is_some() {
+            return Err(TribeError::Contract("Pool uses range positions".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Provide liquidity between two ticks; returns (position id, amount A, amount B) deposited
+    pub fn add_range_liquidity(
+        &mut self,
+        provider: String,
+        tick_lower: i32,
+        tick_upper: i32,
+        amount_a: u64,
+        amount_b: u64,
+    ) -> TribeResult<(u64, u64, u64)> {
+        if !self.is_active {
+            return Err(TribeError::Contract("Pool is not active".to_string()));
+        }
+
+        let (position_id, used_a, used_b) = self.concentrated_mut()?
+            .mint(provider, tick_lower, tick_upper, amount_a, amount_b)?;
+        self.reserve_a += used_a;
+        self.reserve_b += used_b;
+        Ok((position_id, used_a, used_b))
+    }
+
+    /// Close a range position; returns its tokens plus uncollected fees
+    pub fn remove_range_liquidity(&mut self, provider: &str, position_id: u64) -> TribeResult<(u64, u64)> {
+        let (amount_a, amount_b) = self.concentrated_mut()?.burn(position_id, provider)?;
+        self.withdraw_reserves(amount_a, amount_b)
+    }
+
+    /// Collect the fees a range position has earned
+    pub fn collect_range_fees(&mut self, provider: &str, position_id: u64) -> TribeResult<(u64, u64)> {
+        let (fees_a, fees_b) = self.concentrated_mut()?.collect_fees(position_id, provider)?;
+        self.withdraw_reserves(fees_a, fees_b)
+    }
+
+    /// Pay out of the reserves, capped by what the pool holds to absorb rounding
+    fn withdraw_reserves(&mut self, amount_a: u64, amount_b: u64) -> TribeResult<(u64, u64)> {
+        let (amount_a, amount_b) = (amount_a.min(self.reserve_a), amount_b.min(self.reserve_b));
+        self.reserve_a -= amount_a;
+        self.reserve_b -= amount_b;
+        Ok((amount_a, amount_b))
+    }
+
+    fn swap_concentrated(&mut self, is_a_to_b: bool, amount_in: u64, min_amount_out: u64) -> TribeResult<u64> {
+        let fee_rate = self.fee_rate;
+        let concentrated = self.concentrated_mut()?;
+        let mut simulated = concentrated.clone();
+        let RangeSwap { amount_in, amount_out, fee } = simulated.swap(is_a_to_b, amount_in, fee_rate)?;
+        if amount_out < min_amount_out {
+            return Err(TribeError::Contract("Amount out below minimum".to_string()));
+        }
+        *concentrated = simulated;
+
+        if is_a_to_b {
+            self.reserve_a += amount_in;
+            self.reserve_b = self.reserve_b.saturating_sub(amount_out);
+            self.accumulated_fees_a += fee;
+            self.total_volume_a += amount_in;
+        } else {
+            self.reserve_b += amount_in;
+            self.reserve_a = self.reserve_a.saturating_sub(amount_out);
+            self.accumulated_fees_b += fee;
+            self.total_volume_b += amount_in;
+        }
+
+        self.update_price_oracle()?;
+        self.last_trade = Some(Utc::now());
+        Ok(amount_out)
+    }
+
     /// Add liquidity to the pool
     pub fn add_liquidity(
         &mut self,
@@ -182,12 +359,13 @@ impl LiquidityPool {
         amount_b: u64,
         min_liquidity: u64,
     ) -> TribeResult<u64> {
+        self.require_full_range()?;
         if !self.is_active {
-            return Err(TribeError::InvalidOperation("Pool is not active".to_string()));
+            return Err(TribeError::Contract("Pool is not active".to_string()));
         }
 
         if amount_a == 0 || amount_b == 0 {
-            return Err(TribeError::InvalidOperation("Amounts cannot be zero".to_string()));
+            return Err(TribeError::Contract("Amounts cannot be zero".to_string()));
         }
 
         // Calculate optimal amounts based on current ratio
@@ -212,7 +390,7 @@ impl LiquidityPool {
         };
 
         if liquidity_tokens < min_liquidity {
-            return Err(TribeError::InvalidOperation("Insufficient liquidity tokens".to_string()));
+            return Err(TribeError::Contract("Insufficient liquidity tokens".to_string()));
         }
 
         // Update reserves
@@ -251,15 +429,16 @@ impl LiquidityPool {
         min_amount_a: u64,
         min_amount_b: u64,
     ) -> TribeResult<(u64, u64)> {
+        self.require_full_range()?;
         let position = self.liquidity_providers.get_mut(&provider)
-            .ok_or_else(|| TribeError::InvalidOperation("No liquidity position found".to_string()))?;
+            .ok_or_else(|| TribeError::Contract("No liquidity position found".to_string()))?;
 
         if !position.is_active {
-            return Err(TribeError::InvalidOperation("Position is not active".to_string()));
+            return Err(TribeError::Contract("Position is not active".to_string()));
         }
 
         if liquidity_tokens > position.liquidity_tokens {
-            return Err(TribeError::InvalidOperation("Insufficient liquidity tokens".to_string()));
+            return Err(TribeError::Contract("Insufficient liquidity tokens".to_string()));
         }
 
         // Calculate amounts to return
@@ -267,7 +446,7 @@ impl LiquidityPool {
         let amount_b = (liquidity_tokens * self.reserve_b) / self.total_liquidity;
 
         if amount_a < min_amount_a || amount_b < min_amount_b {
-            return Err(TribeError::InvalidOperation("Amounts below minimum".to_string()));
+            return Err(TribeError::Contract("Amounts below minimum".to_string()));
         }
 
         // Update reserves
@@ -296,11 +475,11 @@ impl LiquidityPool {
         min_amount_out: u64,
     ) -> TribeResult<u64> {
         if !self.is_active {
-            return Err(TribeError::InvalidOperation("Pool is not active".to_string()));
+            return Err(TribeError::Contract("Pool is not active".to_string()));
         }
 
         if amount_in == 0 {
-            return Err(TribeError::InvalidOperation("Amount in cannot be zero".to_string()));
+            return Err(TribeError::Contract("Amount in cannot be zero".to_string()));
         }
 
         let (reserve_in, reserve_out, is_a_to_b) = if token_in == self.token_a {
@@ -308,9 +487,13 @@ impl LiquidityPool {
         } else if token_in == self.token_b {
             (self.reserve_b, self.reserve_a, false)
         } else {
-            return Err(TribeError::InvalidOperation("Invalid token".to_string()));
+            return Err(TribeError::Contract("Invalid token".to_string()));
         };
 
+        if self.concentrated.is_some() {
+            return self.swap_concentrated(is_a_to_b, amount_in, min_amount_out);
+        }
+
         // Calculate fee
         let fee = (amount_in as f64 * self.fee_rate) as u64;
         let amount_in_after_fee = amount_in - fee;
@@ -320,7 +503,7 @@ impl LiquidityPool {
         let amount_out = (amount_in_after_fee * reserve_out) / (reserve_in + amount_in_after_fee);
 
         if amount_out < min_amount_out {
-            return Err(TribeError::InvalidOperation("Amount out below minimum".to_string()));
+            return Err(TribeError::Contract("Amount out below minimum".to_string()));
         }
 
         // Calculate price impact
@@ -355,6 +538,54 @@ impl LiquidityPool {
         Ok(amount_out)
     }
 
+    /// Lend `amount` of `token` from the reserves for the duration of `callback`,
+    /// which gets (token, amount, fee) and returns how much it repaid. The reserves
+    /// are only updated if at least amount + fee came back.
+    /// Returns the fee earned.
+    pub fn flash_swap<F>(&mut self, borrower: &str, token: &str, amount: u64, callback: F) -> TribeResult<u64>
+    where
+        F: FnOnce(&str, u64, u64) -> TribeResult<u64>,
+    {
+        if !self.is_active {
+            return Err(TribeError::Contract("Pool is not active".to_string()));
+        }
+
+        let is_token_a = if token == self.token_a {
+            true
+        } else if token == self.token_b {
+            false
+        } else {
+            return Err(TribeError::Contract("Invalid token".to_string()));
+        };
+        let reserve = if is_token_a { self.reserve_a } else { self.reserve_b };
+        if amount == 0 || amount >= reserve {
+            return Err(TribeError::Contract("Flash amount exceeds reserves".to_string()));
+        }
+
+        let fee = ((amount as f64 * self.fee_rate).ceil() as u64).max(1);
+        let repaid = callback(token, amount, fee)?;
+        if repaid < amount + fee {
+            return Err(TribeError::Contract(format!(
+                "Flash swap by {} repaid {} of {}", borrower, repaid, amount + fee
+            )));
+        }
+
+        let earned = repaid - amount;
+        if is_token_a {
+            self.reserve_a += earned;
+            self.accumulated_fees_a += earned;
+        } else {
+            self.reserve_b += earned;
+            self.accumulated_fees_b += earned;
+        }
+        if let Some(concentrated) = self.concentrated.as_mut() {
+            concentrated.donate_fees(is_token_a, earned);
+        }
+
+        self.last_trade = Some(Utc::now());
+        Ok(earned)
+    }
+
     /// Calculate the output amount for a given input (for price quotes)
     pub fn get_amount_out(&self, amount_in: u64, token_in: String) -> TribeResult<u64> {
         if amount_in == 0 {
@@ -366,9 +597,14 @@ impl LiquidityPool {
         } else if token_in == self.token_b {
             (self.reserve_b, self.reserve_a)
         } else {
-            return Err(TribeError::InvalidOperation("Invalid token".to_string()));
+            return Err(TribeError::Contract("Invalid token".to_string()));
         };
 
+        if let Some(concentrated) = &self.concentrated {
+            let quote = concentrated.clone().swap(token_in == self.token_a, amount_in, self.fee_rate)?;
+            return Ok(quote.amount_out);
+        }
+
         let fee = (amount_in as f64 * self.fee_rate) as u64;
         let amount_in_after_fee = amount_in - fee;
         let amount_out = (amount_in_after_fee * reserve_out) / (reserve_in + amount_in_after_fee);
@@ -379,7 +615,7 @@ impl LiquidityPool {
     /// Calculate fees for a liquidity provider
     pub fn calculate_fees(&mut self, provider: &str) -> TribeResult<(u64, u64)> {
         let position = self.liquidity_providers.get_mut(provider)
-            .ok_or_else(|| TribeError::InvalidOperation("No liquidity position found".to_string()))?;
+            .ok_or_else(|| TribeError::Contract("No liquidity position found".to_string()))?;
 
         if !position.is_active {
             return Ok((0, 0));
@@ -403,13 +639,13 @@ impl LiquidityPool {
         self.calculate_fees(&provider)?;
 
         let position = self.liquidity_providers.get_mut(&provider)
-            .ok_or_else(|| TribeError::InvalidOperation("No liquidity position found".to_string()))?;
+            .ok_or_else(|| TribeError::Contract("No liquidity position found".to_string()))?;
 
         let fees_a = position.unclaimed_fees_a;
         let fees_b = position.unclaimed_fees_b;
 
         if fees_a == 0 && fees_b == 0 {
-            return Err(TribeError::InvalidOperation("No fees to claim".to_string()));
+            return Err(TribeError::Contract("No fees to claim".to_string()));
         }
 
         // Reset unclaimed fees
@@ -436,11 +672,13 @@ impl LiquidityPool {
 
     /// Update price oracle with current price
     fn update_price_oracle(&mut self) -> TribeResult<()> {
-        let current_price = self.reserve_b as f64 / self.reserve_a as f64;
+        let current_price = match &self.concentrated {
+            Some(concentrated) => concentrated.price(),
+            None => self.reserve_b as f64 / self.reserve_a as f64,
+        };
         let now = Utc::now();
 
-        self.price_oracle.current_price = current_price;
-        self.price_oracle.last_update = now;
+        self.price_oracle.record(current_price, now);
 
         // Add to price history
         self.price_oracle.price_history.push(PricePoint {
@@ -460,32 +698,20 @@ impl LiquidityPool {
         Ok(())
     }
 
-    /// Calculate time-weighted average price
+    /// Refresh the 24h TWAP from the accumulators, falling back to the current
+    /// price while the pool is younger than a day
     fn calculate_twap(&mut self) -> TribeResult<()> {
-        if self.price_oracle.price_history.len() < 2 {
-            self.price_oracle.twap_24h = self.price_oracle.current_price;
-            return Ok(());
-        }
-
-        let mut weighted_sum = 0.0;
-        let mut total_time = 0.0;
-
-        for i in 1..self.price_oracle.price_history.len() {
-            let prev = &self.price_oracle.price_history[i - 1];
-            let curr = &self.price_oracle.price_history[i];
-            
-            let time_diff = curr.timestamp.signed_duration_since(prev.timestamp).num_seconds() as f64;
-            weighted_sum += prev.price * time_diff;
-            total_time += time_diff;
-        }
-
-        if total_time > 0.0 {
-            self.price_oracle.twap_24h = weighted_sum / total_time;
-        }
-
+        self.price_oracle.twap_24h = self.price_oracle
+            .twap_at(self.price_oracle.last_update, Duration::hours(24))
+            .unwrap_or(self.price_oracle.current_price);
         Ok(())
     }
 
+    /// Time-weighted average price of token A in token B over the last `window`
+    pub fn get_twap(&self, window: Duration) -> TribeResult<f64> {
+        self.price_oracle.twap_at(Utc::now(), window)
+    }
+
     /// Get pool statistics
     pub fn get_stats(&self) -> PoolStats {
         let now = Utc::now();
@@ -550,7 +776,7 @@ impl LiquidityPool {
         } else if token_in == self.token_b {
             (self.reserve_b, self.reserve_a)
         } else {
-            return Err(TribeError::InvalidOperation("Invalid token".to_string()));
+            return Err(TribeError::Contract("Invalid token".to_string()));
         };
 
         let price_before = reserve_out as f64 / reserve_in as f64;
@@ -585,7 +811,7 @@ impl LiquidityPool {
     /// Update fee rate (governance function)
     pub fn update_fee_rate(&mut self, new_fee_rate: f64) -> TribeResult<()> {
         if new_fee_rate < 0.0 || new_fee_rate > 0.1 {
-            return Err(TribeError::InvalidOperation("Fee rate must be between 0 and 10%".to_string()));
+            return Err(TribeError::Contract("Fee rate must be between 0 and 10%".to_string()));
         }
         
         self.fee_rate = new_fee_rate;
@@ -677,6 +903,75 @@ mod tests {
         assert_eq!(price, 2.0); // 20000 / 10000
     }
 
+    #[test]
+    fn test_concentrated_pool() {
+        let mut pool = LiquidityPool::new_concentrated(
+            "STOMP".to_string(),
+            "USDC".to_string(),
+            1.0,
+            10,
+            0.003,
+        ).unwrap();
+        assert!(pool.add_liquidity("provider1".to_string(), 100, 100, 0).is_err());
+
+        let (position, used_a, used_b) = pool.add_range_liquidity(
+            "provider1".to_string(),
+            -100,
+            100,
+            10000,
+            10000,
+        ).unwrap();
+        assert_eq!((pool.reserve_a, pool.reserve_b), (used_a, used_b));
+
+        let quote = pool.get_amount_out(1000, "STOMP".to_string()).unwrap();
+        assert!(pool.swap("trader1".to_string(), "STOMP".to_string(), 1000, quote + 1).is_err());
+        let amount_out = pool.swap("trader1".to_string(), "STOMP".to_string(), 1000, quote).unwrap();
+        assert_eq!(amount_out, quote);
+        assert!(pool.get_price() < 1.0);
+
+        let (fees_a, _) = pool.collect_range_fees("provider1", position).unwrap();
+        assert!(fees_a > 0);
+        let (amount_a, amount_b) = pool.remove_range_liquidity("provider1", position).unwrap();
+        assert!(amount_a > used_a && amount_b < used_b);
+    }
+
+    #[test]
+    fn test_flash_swap_requires_repayment() {
+        let mut pool = LiquidityPool::new(
+            "TRIBE".to_string(),
+            "USDC".to_string(),
+            10000,
+            20000,
+            "provider1".to_string(),
+            0.003,
+        ).unwrap();
+
+        let short = pool.flash_swap("borrower", "TRIBE", 5000, |_, amount, _| Ok(amount));
+        assert!(short.is_err());
+        assert_eq!(pool.reserve_a, 10000);
+
+        let fee = pool.flash_swap("borrower", "TRIBE", 5000, |_, amount, fee| Ok(amount + fee)).unwrap();
+        assert_eq!(fee, 15);
+        assert_eq!(pool.reserve_a, 10015);
+        assert!(pool.flash_swap("borrower", "TRIBE", 20000, |_, amount, fee| Ok(amount + fee)).is_err());
+    }
+
+    #[test]
+    fn test_twap_windows() {
+        let start = Utc::now();
+        let mut oracle = PriceOracle::new(2.0, start);
+        oracle.record(4.0, start + Duration::seconds(60));
+        oracle.record(1.0, start + Duration::seconds(90));
+        let now = start + Duration::seconds(120);
+
+        // 30s at 4.0 then 30s at 1.0
+        assert_eq!(oracle.twap_at(now, Duration::seconds(60)).unwrap(), 2.5);
+        // Half of the window before the first change at 2.0
+        assert_eq!(oracle.twap_at(now, Duration::seconds(120)).unwrap(), (60.0 * 2.0 + 30.0 * 4.0 + 30.0) / 120.0);
+        assert!(oracle.twap_at(now, Duration::seconds(121)).is_err());
+        assert!(oracle.twap_at(now, Duration::zero()).is_err());
+    }
+
     #[test]
     fn test_remove_liquidity() {
         let mut pool = LiquidityPool::new(
@@ -0,0 +1,230 @@
+use tribechain_core::{TribeResult, TribeError};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use chrono::{DateTime, Utc, Duration};
+use sha2::{Digest, Sha256};
+use crate::ContractCall;
+
+/// M-of-N multi-signature wallet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultisigWallet {
+    pub id: String,
+    pub signers: Vec<String>,
+    pub threshold: usize, // Approvals needed to execute a proposal
+    pub balance: u64, // Native TRIBE held by the wallet
+    pub proposals: HashMap<u64, MultisigProposal>,
+    pub next_proposal_id: u64,
+    pub proposal_ttl: Duration,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Inner transaction a proposal executes once approved
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum MultisigAction {
+    Transfer { to: String, amount: u64 },
+    TokenTransfer { token_id: String, to: String, amount: u64 },
+    ContractCall(ContractCall),
+}
+
+/// Proposal lifecycle
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ProposalStatus {
+    Pending,
+    Executed,
+    Expired,
+}
+
+/// Action waiting for signer approvals
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultisigProposal {
+    pub id: u64,
+    pub proposer: String,
+    pub action: MultisigAction,
+    pub approvals: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub status: ProposalStatus,
+}
+
+impl MultisigWallet {
+    /// Create a wallet requiring `threshold` of `signers` to approve each action
+    pub fn new(signers: Vec<String>, threshold: usize) -> TribeResult<Self> {
+        let mut unique = signers.clone();
+        unique.sort();
+        unique.dedup();
+        if unique.len() != signers.len() || signers.iter().any(|s| s.is_empty()) {
+            return Err(TribeError::Contract("Signers must be unique and non-empty".to_string()));
+        }
+        if threshold == 0 || threshold > signers.len() {
+            return Err(TribeError::Contract(format!(
+                "Threshold must be between 1 and {}", signers.len()
+            )));
+        }
+
+        let created_at = Utc::now();
+        Ok(Self {
+            id: Self::generate_wallet_id(&unique, threshold, created_at),
+            signers,
+            threshold,
+            balance: 0,
+            proposals: HashMap::new(),
+            next_proposal_id: 1,
+            proposal_ttl: Duration::days(7),
+            created_at,
+        })
+    }
+
+    fn generate_wallet_id(signers: &[String], threshold: usize, created_at: DateTime<Utc>) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(signers.join(",").as_bytes());
+        hasher.update(threshold.to_le_bytes());
+        hasher.update(created_at.timestamp_nanos_opt().unwrap_or_default().to_le_bytes());
+        format!("multisig_{}", &hex::encode(hasher.finalize())[..16])
+    }
+
+    pub fn is_signer(&self, address: &str) -> bool {
+        self.signers.iter().any(|signer| signer == address)
+    }
+
+    pub fn deposit(&mut self, amount: u64) {
+        self.balance += amount;
+    }
+
+    /// Submit an action; the proposer's approval is counted immediately
+    pub fn submit_proposal(&mut self, proposer: String, action: MultisigAction) -> TribeResult<u64> {
+        if !self.is_signer(&proposer) {
+            return Err(TribeError::Contract("Only signers can submit proposals".to_string()));
+        }
+
+        let id = self.next_proposal_id;
+        self.next_proposal_id += 1;
+        let now = Utc::now();
+        self.proposals.insert(id, MultisigProposal {
+            id,
+            proposer: proposer.clone(),
+            action,
+            approvals: vec![proposer],
+            created_at: now,
+            expires_at: now + self.proposal_ttl,
+            status: ProposalStatus::Pending,
+        });
+        Ok(id)
+    }
+
+    /// Record a signer's approval; returns true once the proposal has enough approvals to execute
+    pub fn approve(&mut self, proposal_id: u64, signer: &str) -> TribeResult<bool> {
+        if !self.is_signer(signer) {
+            return Err(TribeError::Contract("Only signers can approve proposals".to_string()));
+        }
+        let threshold = self.threshold;
+        let proposal = self.pending_proposal(proposal_id)?;
+
+        if !proposal.approvals.iter().any(|approval| approval == signer) {
+            proposal.approvals.push(signer.to_string());
+        }
+        Ok(proposal.approvals.len() >= threshold)
+    }
+
+    /// Whether a pending, unexpired proposal has reached the threshold
+    pub fn is_ready(&self, proposal_id: u64) -> bool {
+        self.proposals.get(&proposal_id)
+            .map(|proposal| {
+                proposal.status == ProposalStatus::Pending
+                    && proposal.expires_at > Utc::now()
+                    && proposal.approvals.len() >= self.threshold
+            })
+            .unwrap_or(false)
+    }
+
+    /// Mark an approved proposal executed, debiting native transfers from the wallet
+    pub fn mark_executed(&mut self, proposal_id: u64) -> TribeResult<()> {
+        if !self.is_ready(proposal_id) {
+            return Err(TribeError::Contract("Proposal is not approved".to_string()));
+        }
+
+        if let Some(MultisigAction::Transfer { amount, .. }) = self.proposals.get(&proposal_id).map(|p| &p.action) {
+            if *amount > self.balance {
+                return Err(TribeError::Contract("Insufficient wallet balance".to_string()));
+            }
+            self.balance -= amount;
+        }
+
+        let proposal = self.pending_proposal(proposal_id)?;
+        proposal.status = ProposalStatus::Executed;
+        Ok(())
+    }
+
+    /// Expire pending proposals past their deadline; returns how many expired
+    pub fn expire_proposals(&mut self, now: DateTime<Utc>) -> usize {
+        let mut expired = 0;
+        for proposal in self.proposals.values_mut() {
+            if proposal.status == ProposalStatus::Pending && proposal.expires_at <= now {
+                proposal.status = ProposalStatus::Expired;
+                expired += 1;
+            }
+        }
+        expired
+    }
+
+    fn pending_proposal(&mut self, proposal_id: u64) -> TribeResult<&mut MultisigProposal> {
+        let proposal = self.proposals.get_mut(&proposal_id)
+            .ok_or_else(|| TribeError::Contract("Proposal not found".to_string()))?;
+
+        if proposal.status == ProposalStatus::Pending && proposal.expires_at <= Utc::now() {
+            proposal.status = ProposalStatus::Expired;
+        }
+        if proposal.status != ProposalStatus::Pending {
+            return Err(TribeError::Contract(format!("Proposal is {:?}", proposal.status)));
+        }
+        Ok(proposal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signers() -> Vec<String> {
+        vec!["alice".to_string(), "bob".to_string(), "carol".to_string()]
+    }
+
+    #[test]
+    fn test_multisig_creation() {
+        assert!(MultisigWallet::new(signers(), 2).is_ok());
+        assert!(MultisigWallet::new(signers(), 0).is_err());
+        assert!(MultisigWallet::new(signers(), 4).is_err());
+        assert!(MultisigWallet::new(vec!["alice".to_string(), "alice".to_string()], 1).is_err());
+    }
+
+    #[test]
+    fn test_multisig_approval_flow() {
+        let mut wallet = MultisigWallet::new(signers(), 2).unwrap();
+        wallet.deposit(1000);
+
+        let action = MultisigAction::Transfer { to: "dave".to_string(), amount: 400 };
+        assert!(wallet.submit_proposal("mallory".to_string(), action.clone()).is_err());
+        let id = wallet.submit_proposal("alice".to_string(), action).unwrap();
+
+        // Approving twice doesn't count twice
+        assert!(!wallet.approve(id, "alice").unwrap());
+        assert!(wallet.mark_executed(id).is_err());
+        assert!(wallet.approve(id, "bob").unwrap());
+
+        wallet.mark_executed(id).unwrap();
+        assert_eq!(wallet.balance, 600);
+        assert!(wallet.approve(id, "carol").is_err());
+    }
+
+    #[test]
+    fn test_multisig_expiry() {
+        let mut wallet = MultisigWallet::new(signers(), 2).unwrap();
+        let id = wallet.submit_proposal(
+            "alice".to_string(),
+            MultisigAction::Transfer { to: "dave".to_string(), amount: 1 },
+        ).unwrap();
+
+        assert_eq!(wallet.expire_proposals(Utc::now() + Duration::days(8)), 1);
+        assert!(wallet.approve(id, "bob").is_err());
+        assert_eq!(wallet.proposals[&id].status, ProposalStatus::Expired);
+    }
+}
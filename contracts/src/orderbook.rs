@@ -0,0 +1,351 @@
+use tribechain_core::{TribeResult, TribeError};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+
+/// Prices are quote units per `PRICE_PRECISION` base units
+pub const PRICE_PRECISION: u64 = 1_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OrderSide {
+    Buy, // Pay quote for base
+    Sell, // Pay base for quote
+}
+
+/// Resting or incoming limit order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LimitOrder {
+    pub id: u64,
+    pub owner: String,
+    pub side: OrderSide,
+    pub price: u64,
+    pub amount: u64, // Base amount ordered
+    pub remaining: u64, // Base amount still open
+    pub escrow: u64, // Locked quote (buys) or base (sells) not yet paid out
+    pub created_at: DateTime<Utc>,
+}
+
+/// A match between a resting maker and an incoming taker
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trade {
+    pub maker_order_id: u64,
+    pub taker_order_id: u64,
+    pub price: u64, // Always the maker's price
+    pub base_amount: u64,
+    pub quote_amount: u64,
+    pub maker_fee: u64,
+    pub taker_fee: u64,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Token the book owes an account after placing, matching or cancelling
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Payout {
+    pub to: String,
+    pub token_id: String,
+    pub amount: u64,
+}
+
+/// Outcome of placing an order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderResult {
+    pub order_id: u64,
+    pub trades: Vec<Trade>,
+    pub payouts: Vec<Payout>,
+    pub resting: u64, // Base amount left on the book
+}
+
+/// Price-time priority limit order book for one token pair. Orders lock their
+/// funds under the book's ID; fees are charged in the token each side receives
+/// and stay with the book.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderBook {
+    pub id: String,
+    pub base_token: String,
+    pub quote_token: String,
+    pub maker_fee_rate: f64,
+    pub taker_fee_rate: f64,
+    pub bids: BTreeMap<u64, VecDeque<u64>>, // price -> order ids, oldest first
+    pub asks: BTreeMap<u64, VecDeque<u64>>,
+    pub orders: HashMap<u64, LimitOrder>,
+    pub next_order_id: u64,
+    pub trades: Vec<Trade>,
+    pub collected_fees_base: u64,
+    pub collected_fees_quote: u64,
+    pub created_at: DateTime<Utc>,
+}
+
+impl OrderBook {
+    pub fn new(base_token: String, quote_token: String, maker_fee_rate: f64, taker_fee_rate: f64) -> TribeResult<Self> {
+        if base_token == quote_token {
+            return Err(TribeError::Contract("Cannot create order book with same tokens".to_string()));
+        }
+        for rate in [maker_fee_rate, taker_fee_rate] {
+            if !(0.0..=0.1).contains(&rate) {
+                return Err(TribeError::Contract("Fee rate must be between 0 and 10%".to_string()));
+            }
+        }
+
+        let created_at = Utc::now();
+        let mut hasher = Sha256::new();
+        hasher.update(base_token.as_bytes());
+        hasher.update(quote_token.as_bytes());
+        hasher.update(created_at.timestamp_nanos_opt().unwrap_or_default().to_le_bytes());
+
+        Ok(Self {
+            id: format!("orderbook_{}", &hex::encode(hasher.finalize())[..16]),
+            base_token,
+            quote_token,
+            maker_fee_rate,
+            taker_fee_rate,
+            bids: BTreeMap::new(),
+            asks: BTreeMap::new(),
+            orders: HashMap::new(),
+            next_order_id: 1,
+            trades: Vec::new(),
+            collected_fees_base: 0,
+            collected_fees_quote: 0,
+            created_at,
+        })
+    }
+
+    /// Quote value of `base_amount` at `price`, rounded down
+    pub fn quote_amount(base_amount: u64, price: u64) -> u64 {
+        (base_amount as u128 * price as u128 / PRICE_PRECISION as u128) as u64
+    }
+
+    /// Token and amount an order must lock: quote for buys (rounded up), base for sells
+    pub fn required_escrow(&self, side: OrderSide, price: u64, amount: u64) -> (String, u64) {
+        match side {
+            OrderSide::Buy => {
+                let quote = (amount as u128 * price as u128).div_ceil(PRICE_PRECISION as u128) as u64;
+                (self.quote_token.clone(), quote)
+            }
+            OrderSide::Sell => (self.base_token.clone(), amount),
+        }
+    }
+
+    pub fn best_bid(&self) -> Option<u64> {
+        self.bids.keys().next_back().copied()
+    }
+
+    pub fn best_ask(&self) -> Option<u64> {
+        self.asks.keys().next().copied()
+    }
+
+    /// Match an order against the opposite side and rest whatever is left.
+    /// The caller is responsible for locking `required_escrow` under the book.
+    pub fn place_order(&mut self, owner: String, side: OrderSide, price: u64, amount: u64) -> TribeResult<OrderResult> {
+        if price == 0 || amount == 0 {
+            return Err(TribeError::Contract("Price and amount must be greater than 0".to_string()));
+        }
+
+        let id = self.next_order_id;
+        self.next_order_id += 1;
+        let (_, escrow) = self.required_escrow(side, price, amount);
+        let mut taker = LimitOrder {
+            id,
+            owner,
+            side,
+            price,
+            amount,
+            remaining: amount,
+            escrow,
+            created_at: Utc::now(),
+        };
+
+        let mut trades = Vec::new();
+        let mut payouts = Vec::new();
+        while taker.remaining > 0 {
+            let level = match side {
+                OrderSide::Buy => self.best_ask().filter(|ask| *ask <= price),
+                OrderSide::Sell => self.best_bid().filter(|bid| *bid >= price),
+            };
+            let level = match level {
+                Some(level) => level,
+                None => break,
+            };
+            let trade = self.fill_at_level(&mut taker, level, &mut payouts)?;
+            trades.push(trade);
+        }
+
+        let resting = taker.remaining;
+        if resting > 0 {
+            let book_side = match side {
+                OrderSide::Buy => &mut self.bids,
+                OrderSide::Sell => &mut self.asks,
+            };
+            book_side.entry(price).or_default().push_back(id);
+            self.orders.insert(id, taker);
+        } else {
+            self.refund_escrow(&taker, &mut payouts);
+        }
+
+        self.trades.extend(trades.iter().cloned());
+        Ok(OrderResult { order_id: id, trades, payouts, resting })
+    }
+
+    /// Fill the taker against the oldest maker order at `level`
+    fn fill_at_level(&mut self, taker: &mut LimitOrder, level: u64, payouts: &mut Vec<Payout>) -> TribeResult<Trade> {
+        let book_side = match taker.side {
+            OrderSide::Buy => &mut self.asks,
+            OrderSide::Sell => &mut self.bids,
+        };
+        let maker_id = *book_side.get(&level)
+            .and_then(|queue| queue.front())
+            .ok_or_else(|| TribeError::Contract("Empty price level".to_string()))?;
+        let mut maker = self.orders.remove(&maker_id)
+            .ok_or_else(|| TribeError::Contract("Order not found".to_string()))?;
+
+        let base_amount = taker.remaining.min(maker.remaining);
+        let quote_amount = Self::quote_amount(base_amount, level);
+        taker.remaining -= base_amount;
+        maker.remaining -= base_amount;
+
+        // Whoever buys receives base; whoever sells receives quote
+        let (buyer, seller) = match taker.side {
+            OrderSide::Buy => (&mut *taker, &mut maker),
+            OrderSide::Sell => (&mut maker, &mut *taker),
+        };
+        buyer.escrow -= quote_amount.min(buyer.escrow);
+        seller.escrow -= base_amount.min(seller.escrow);
+
+        let (maker_rate, taker_rate) = (self.maker_fee_rate, self.taker_fee_rate);
+        let buyer_rate = if taker.side == OrderSide::Buy { taker_rate } else { maker_rate };
+        let seller_rate = if taker.side == OrderSide::Sell { taker_rate } else { maker_rate };
+        let buyer_fee = (base_amount as f64 * buyer_rate) as u64;
+        let seller_fee = (quote_amount as f64 * seller_rate) as u64;
+        self.collected_fees_base += buyer_fee;
+        self.collected_fees_quote += seller_fee;
+
+        let (buyer_owner, seller_owner) = match taker.side {
+            OrderSide::Buy => (taker.owner.clone(), maker.owner.clone()),
+            OrderSide::Sell => (maker.owner.clone(), taker.owner.clone()),
+        };
+        self.push_payout(payouts, buyer_owner, self.base_token.clone(), base_amount - buyer_fee);
+        self.push_payout(payouts, seller_owner, self.quote_token.clone(), quote_amount - seller_fee);
+
+        let (maker_fee, taker_fee) = match taker.side {
+            OrderSide::Buy => (seller_fee, buyer_fee),
+            OrderSide::Sell => (buyer_fee, seller_fee),
+        };
+
+        let book_side = match taker.side {
+            OrderSide::Buy => &mut self.asks,
+            OrderSide::Sell => &mut self.bids,
+        };
+        if maker.remaining == 0 {
+            if let Some(queue) = book_side.get_mut(&level) {
+                queue.pop_front();
+                if queue.is_empty() {
+                    book_side.remove(&level);
+                }
+            }
+            self.refund_escrow(&maker, payouts);
+        } else {
+            self.orders.insert(maker_id, maker);
+        }
+
+        Ok(Trade {
+            maker_order_id: maker_id,
+            taker_order_id: taker.id,
+            price: level,
+            base_amount,
+            quote_amount,
+            maker_fee,
+            taker_fee,
+            timestamp: Utc::now(),
+        })
+    }
+
+    /// Remove an open order, returning its unspent escrow
+    pub fn cancel_order(&mut self, order_id: u64, owner: &str) -> TribeResult<Payout> {
+        match self.orders.get(&order_id) {
+            Some(order) if order.owner == owner => {}
+            Some(_) => return Err(TribeError::Contract("Not the order owner".to_string())),
+            None => return Err(TribeError::Contract("Order not found".to_string())),
+        }
+        let order = self.orders.remove(&order_id)
+            .ok_or_else(|| TribeError::Contract("Order not found".to_string()))?;
+
+        let book_side = match order.side {
+            OrderSide::Buy => &mut self.bids,
+            OrderSide::Sell => &mut self.asks,
+        };
+        if let Some(queue) = book_side.get_mut(&order.price) {
+            queue.retain(|id| *id != order_id);
+            if queue.is_empty() {
+                book_side.remove(&order.price);
+            }
+        }
+
+        let token_id = match order.side {
+            OrderSide::Buy => self.quote_token.clone(),
+            OrderSide::Sell => self.base_token.clone(),
+        };
+        Ok(Payout { to: order.owner, token_id, amount: order.escrow })
+    }
+
+    /// Return escrow a completed order no longer needs (e.g. a buy filled below its limit)
+    fn refund_escrow(&self, order: &LimitOrder, payouts: &mut Vec<Payout>) {
+        let token_id = match order.side {
+            OrderSide::Buy => self.quote_token.clone(),
+            OrderSide::Sell => self.base_token.clone(),
+        };
+        self.push_payout(payouts, order.owner.clone(), token_id, order.escrow);
+    }
+
+    fn push_payout(&self, payouts: &mut Vec<Payout>, to: String, token_id: String, amount: u64) {
+        if amount > 0 {
+            payouts.push(Payout { to, token_id, amount });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn book() -> OrderBook {
+        OrderBook::new("TRIBE".to_string(), "USDC".to_string(), 0.0, 0.01).unwrap()
+    }
+
+    #[test]
+    fn test_partial_fill_at_maker_price() {
+        let mut book = book();
+        let ask = book.place_order("maker".to_string(), OrderSide::Sell, 2 * PRICE_PRECISION, 1000).unwrap();
+        assert_eq!(ask.resting, 1000);
+        assert_eq!(book.best_ask(), Some(2 * PRICE_PRECISION));
+
+        // Buyer bids higher than the ask, fills 600 at the maker's price and gets the difference back
+        let bid = book.place_order("taker".to_string(), OrderSide::Buy, 3 * PRICE_PRECISION, 600).unwrap();
+        assert_eq!(bid.trades.len(), 1);
+        assert_eq!(bid.trades[0].quote_amount, 1200);
+        assert_eq!(bid.trades[0].taker_fee, 6);
+        assert_eq!(bid.resting, 0);
+        assert!(bid.payouts.contains(&Payout { to: "taker".to_string(), token_id: "TRIBE".to_string(), amount: 594 }));
+        assert!(bid.payouts.contains(&Payout { to: "maker".to_string(), token_id: "USDC".to_string(), amount: 1200 }));
+        assert!(bid.payouts.contains(&Payout { to: "taker".to_string(), token_id: "USDC".to_string(), amount: 600 }));
+
+        assert_eq!(book.orders[&ask.order_id].remaining, 400);
+        assert!(book.cancel_order(ask.order_id, "taker").is_err());
+        let refund = book.cancel_order(ask.order_id, "maker").unwrap();
+        assert_eq!(refund.amount, 400);
+        assert!(book.asks.is_empty());
+    }
+
+    #[test]
+    fn test_price_time_priority() {
+        let mut book = book();
+        let first = book.place_order("bob".to_string(), OrderSide::Buy, PRICE_PRECISION, 100).unwrap();
+        book.place_order("carol".to_string(), OrderSide::Buy, PRICE_PRECISION, 100).unwrap();
+        let better = book.place_order("dave".to_string(), OrderSide::Buy, 2 * PRICE_PRECISION, 50).unwrap();
+
+        let sell = book.place_order("alice".to_string(), OrderSide::Sell, PRICE_PRECISION, 120).unwrap();
+        let makers: Vec<u64> = sell.trades.iter().map(|trade| trade.maker_order_id).collect();
+        assert_eq!(makers, vec![better.order_id, first.order_id]);
+        assert_eq!(book.orders[&first.order_id].remaining, 30);
+        assert_eq!(book.best_bid(), Some(PRICE_PRECISION));
+    }
+}
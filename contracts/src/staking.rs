@@ -1,4 +1,4 @@
-use tribechain_core::{TribeResult, TribeError};
+use tribechain_core::{TribeResult, TribeError, ValidatorPerformanceReport};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use chrono::{DateTime, Utc, Duration};
@@ -22,6 +22,94 @@ pub struct StakingContract {
     pub last_reward_calculation: DateTime<Utc>,
     pub lock_period: Duration,
     pub early_withdrawal_penalty: f64,
+    #[serde(default = "default_unbonding_period")]
+    pub unbonding_period: Duration, // How long unstaked tokens stay slashable
+    #[serde(default)]
+    pub unbonding: Vec<UnbondingEntry>,
+    #[serde(default)]
+    pub emission_curve: EmissionCurve,
+    #[serde(default = "default_epoch_length")]
+    pub epoch_length: Duration,
+    #[serde(default)]
+    pub current_epoch: u64,
+    #[serde(default = "Utc::now")]
+    pub epoch_started_at: DateTime<Utc>,
+    #[serde(default)]
+    pub reward_per_stake: u128, // Rewards per staked token since creation, scaled by REWARD_PRECISION
+    #[serde(default = "default_max_missed_blocks")]
+    pub max_missed_blocks: u64, // Missed blocks per epoch tolerated before slashing
+    #[serde(default = "default_missed_block_slash")]
+    pub missed_block_slash: f64,
+    #[serde(default)]
+    pub reported_epochs: HashMap<String, u64>, // Validator -> last epoch a performance report was applied for
+}
+
+/// Fixed-point scale of `reward_per_stake`
+pub const REWARD_PRECISION: u128 = 1_000_000_000_000;
+
+fn default_unbonding_period() -> Duration {
+    Duration::days(21)
+}
+
+fn default_epoch_length() -> Duration {
+    Duration::days(1)
+}
+
+fn default_max_missed_blocks() -> u64 {
+    50
+}
+
+fn default_missed_block_slash() -> f64 {
+    0.01
+}
+
+/// How many reward tokens each epoch emits
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum EmissionCurve {
+    /// Constant annual rate on bonded stake
+    Fixed { annual_rate: f64 },
+    /// Annual rate on bonded stake shrinking by `decay` every epoch
+    Decaying { initial_rate: f64, decay: f64 },
+    /// Up to `per_epoch` paid out of `reward_pool`, never more than it holds
+    RewardPool { per_epoch: u64 },
+}
+
+impl Default for EmissionCurve {
+    fn default() -> Self {
+        EmissionCurve::Fixed { annual_rate: 0.0 }
+    }
+}
+
+impl EmissionCurve {
+    /// Rewards emitted for `epoch`, covering `year_fraction` of a year
+    pub fn emission(&self, epoch: u64, bonded: u64, year_fraction: f64, reward_pool: u64) -> u64 {
+        match self {
+            EmissionCurve::Fixed { annual_rate } => {
+                (bonded as f64 * annual_rate * year_fraction) as u64
+            }
+            EmissionCurve::Decaying { initial_rate, decay } => {
+                let rate = initial_rate * (1.0 - decay).powi(epoch.min(i32::MAX as u64) as i32);
+                (bonded as f64 * rate * year_fraction) as u64
+            }
+            EmissionCurve::RewardPool { per_epoch } => (*per_epoch).min(reward_pool),
+        }
+    }
+}
+
+/// Unstaked amount waiting out the unbonding period
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnbondingEntry {
+    pub staker: String,
+    pub validator: String,
+    pub amount: u64,
+    pub created_at: DateTime<Utc>,
+    pub release_at: DateTime<Utc>,
+}
+
+impl UnbondingEntry {
+    pub fn is_mature(&self, now: DateTime<Utc>) -> bool {
+        now >= self.release_at
+    }
 }
 
 /// Individual stake information
@@ -36,6 +124,8 @@ pub struct StakeInfo {
     pub last_reward_claim: DateTime<Utc>,
     pub is_active: bool,
     pub auto_compound: bool,
+    #[serde(default)]
+    pub reward_debt: u128, // Share of `reward_per_stake` already credited
 }
 
 /// Validator information
@@ -117,15 +207,15 @@ impl StakingContract {
         reward_rate: f64,
     ) -> TribeResult<Self> {
         if token_id.is_empty() || validator.is_empty() {
-            return Err(TribeError::InvalidOperation("Token ID and validator cannot be empty".to_string()));
+            return Err(TribeError::Contract("Token ID and validator cannot be empty".to_string()));
         }
 
         if min_stake == 0 {
-            return Err(TribeError::InvalidOperation("Minimum stake must be greater than 0".to_string()));
+            return Err(TribeError::Contract("Minimum stake must be greater than 0".to_string()));
         }
 
         if reward_rate < 0.0 || reward_rate > 1.0 {
-            return Err(TribeError::InvalidOperation("Reward rate must be between 0 and 1".to_string()));
+            return Err(TribeError::Contract("Reward rate must be between 0 and 1".to_string()));
         }
 
         let contract_id = Self::generate_contract_id(&token_id, &validator);
@@ -165,6 +255,16 @@ impl StakingContract {
             last_reward_calculation: Utc::now(),
             lock_period: Duration::days(30), // Default 30-day lock
             early_withdrawal_penalty: 0.1, // 10% penalty
+            unbonding_period: default_unbonding_period(),
+            unbonding: Vec::new(),
+            emission_curve: EmissionCurve::Fixed { annual_rate: reward_rate },
+            epoch_length: default_epoch_length(),
+            current_epoch: 0,
+            epoch_started_at: Utc::now(),
+            reward_per_stake: 0,
+            max_missed_blocks: default_max_missed_blocks(),
+            missed_block_slash: default_missed_block_slash(),
+            reported_epochs: HashMap::new(),
         })
     }
 
@@ -176,26 +276,32 @@ impl StakingContract {
         duration: u64, // Duration in days
     ) -> TribeResult<()> {
         if !self.is_active {
-            return Err(TribeError::InvalidOperation("Staking contract is not active".to_string()));
+            return Err(TribeError::Contract("Staking contract is not active".to_string()));
         }
 
         if amount < self.min_stake {
-            return Err(TribeError::InvalidOperation("Amount below minimum stake".to_string()));
+            return Err(TribeError::Contract("Amount below minimum stake".to_string()));
         }
 
         if let Some(max_stake) = self.max_stake {
             if amount > max_stake {
-                return Err(TribeError::InvalidOperation("Amount exceeds maximum stake".to_string()));
+                return Err(TribeError::Contract("Amount exceeds maximum stake".to_string()));
             }
         }
 
         let lock_until = Utc::now() + Duration::days(duration as i64);
         
         // Check if staker already has a stake
-        if let Some(existing_stake) = self.stakes.get_mut(&staker) {
+        if self.stakes.contains_key(&staker) {
+            self.calculate_rewards(&staker)?;
+            let reward_per_stake = self.reward_per_stake;
+            let existing_stake = self.stakes.get_mut(&staker).unwrap();
+
             // Add to existing stake
             existing_stake.amount += amount;
+            existing_stake.is_active = true;
             existing_stake.lock_until = lock_until.max(existing_stake.lock_until);
+            existing_stake.reward_debt = Self::reward_debt_for(existing_stake.amount, reward_per_stake);
         } else {
             // Create new stake
             let stake_info = StakeInfo {
@@ -208,6 +314,7 @@ impl StakingContract {
                 last_reward_claim: Utc::now(),
                 is_active: true,
                 auto_compound: false,
+                reward_debt: Self::reward_debt_for(amount, self.reward_per_stake),
             };
             self.stakes.insert(staker, stake_info);
         }
@@ -221,20 +328,47 @@ impl StakingContract {
         Ok(())
     }
 
-    /// Unstake tokens
+    /// Set the emission curve used at each epoch boundary
+    pub fn with_emission_curve(mut self, emission_curve: EmissionCurve) -> Self {
+        self.emission_curve = emission_curve;
+        self
+    }
+
+    pub fn with_epoch_length(mut self, epoch_length: Duration) -> Self {
+        self.epoch_length = epoch_length;
+        self
+    }
+
+    /// Add tokens to the pool `EmissionCurve::RewardPool` pays out of
+    pub fn fund_rewards(&mut self, amount: u64) {
+        self.reward_pool += amount;
+    }
+
+    /// Set how long unstaked tokens stay slashable before they can be withdrawn
+    pub fn with_unbonding_period(mut self, unbonding_period: Duration) -> Self {
+        self.unbonding_period = unbonding_period;
+        self
+    }
+
+    /// Unstake tokens into the unbonding queue; returns the amount queued after any penalty
     pub fn unstake(&mut self, staker: String, amount: u64) -> TribeResult<u64> {
-        let stake = self.stakes.get_mut(&staker)
-            .ok_or_else(|| TribeError::InvalidOperation("No stake found for staker".to_string()))?;
+        let stake = self.stakes.get(&staker)
+            .ok_or_else(|| TribeError::Contract("No stake found for staker".to_string()))?;
 
         if !stake.is_active {
-            return Err(TribeError::InvalidOperation("Stake is not active".to_string()));
+            return Err(TribeError::Contract("Stake is not active".to_string()));
         }
 
         if amount > stake.amount {
-            return Err(TribeError::InvalidOperation("Insufficient staked amount".to_string()));
+            return Err(TribeError::Contract("Insufficient staked amount".to_string()));
         }
 
+        // Calculate and claim any pending rewards
+        self.calculate_rewards(&staker)?;
+
         let now = Utc::now();
+        let stake = self.stakes.get_mut(&staker)
+            .ok_or_else(|| TribeError::Contract("No stake found for staker".to_string()))?;
         let mut penalty = 0u64;
 
         // Check if lock period has passed
@@ -243,11 +377,9 @@ impl StakingContract {
             penalty = (amount as f64 * self.early_withdrawal_penalty) as u64;
         }
 
-        // Calculate and claim any pending rewards
-        self.calculate_rewards(&staker)?;
-
         // Update stake
         stake.amount -= amount;
+        stake.reward_debt = Self::reward_debt_for(stake.amount, self.reward_per_stake);
         if stake.amount == 0 {
             stake.is_active = false;
         }
@@ -259,8 +391,45 @@ impl StakingContract {
 
         self.total_staked = self.total_staked.saturating_sub(amount);
 
-        // Return amount minus penalty
-        Ok(amount - penalty)
+        // Amount minus penalty stays slashable until the unbonding period ends
+        let queued = amount - penalty;
+        if queued > 0 {
+            self.unbonding.push(UnbondingEntry {
+                staker: staker.clone(),
+                validator: stake.delegated_to.clone(),
+                amount: queued,
+                created_at: now,
+                release_at: now + self.unbonding_period,
+            });
+        }
+
+        Ok(queued)
+    }
+
+    /// Release a staker's matured unbonding entries; returns the amount withdrawn
+    pub fn withdraw_unbonded(&mut self, staker: &str, now: DateTime<Utc>) -> TribeResult<u64> {
+        let mut withdrawn = 0u64;
+        self.unbonding.retain(|entry| {
+            if entry.staker == staker && entry.is_mature(now) {
+                withdrawn += entry.amount;
+                false
+            } else {
+                true
+            }
+        });
+
+        if withdrawn == 0 {
+            return Err(TribeError::Contract("No unbonded tokens ready to withdraw".to_string()));
+        }
+        Ok(withdrawn)
+    }
+
+    /// Amount a staker still has waiting in the unbonding queue
+    pub fn pending_unbonding(&self, staker: &str) -> u64 {
+        self.unbonding.iter()
+            .filter(|entry| entry.staker == staker)
+            .map(|entry| entry.amount)
+            .sum()
     }
 
     /// Delegate to a different validator
@@ -271,20 +440,24 @@ impl StakingContract {
         amount: u64,
     ) -> TribeResult<()> {
         if !self.validators.contains_key(&validator) {
-            return Err(TribeError::InvalidOperation("Validator not found".to_string()));
+            return Err(TribeError::Contract("Validator not found".to_string()));
         }
 
         let validator_info = self.validators.get(&validator).unwrap();
         if !validator_info.is_active || validator_info.is_jailed {
-            return Err(TribeError::InvalidOperation("Validator is not active or jailed".to_string()));
+            return Err(TribeError::Contract("Validator is not active or jailed".to_string()));
         }
 
         if amount < self.min_stake {
-            return Err(TribeError::InvalidOperation("Amount below minimum stake".to_string()));
+            return Err(TribeError::Contract("Amount below minimum stake".to_string()));
         }
 
         // Create or update stake
-        if let Some(stake) = self.stakes.get_mut(&staker) {
+        if self.stakes.contains_key(&staker) {
+            self.calculate_rewards(&staker)?;
+            let reward_per_stake = self.reward_per_stake;
+            let stake = self.stakes.get_mut(&staker).unwrap();
+
             // Redelegate from current validator
             if let Some(current_validator) = self.validators.get_mut(&stake.delegated_to) {
                 current_validator.total_delegated = current_validator.total_delegated.saturating_sub(stake.amount);
@@ -292,6 +465,7 @@ impl StakingContract {
 
             stake.delegated_to = validator.clone();
             stake.amount = amount;
+            stake.reward_debt = Self::reward_debt_for(amount, reward_per_stake);
         } else {
             // New delegation
             let stake_info = StakeInfo {
@@ -304,6 +478,7 @@ impl StakingContract {
                 last_reward_claim: Utc::now(),
                 is_active: true,
                 auto_compound: false,
+                reward_debt: Self::reward_debt_for(amount, self.reward_per_stake),
             };
             self.stakes.insert(staker, stake_info);
         }
@@ -316,37 +491,73 @@ impl StakingContract {
         Ok(())
     }
 
-    /// Calculate rewards for a staker
+    /// Close every epoch that has ended by `now`, crediting its emission to all
+    /// active stakes through the reward accumulator; returns the amount emitted
+    pub fn advance_epoch(&mut self, now: DateTime<Utc>) -> TribeResult<u64> {
+        if self.epoch_length <= Duration::zero() {
+            return Err(TribeError::Contract("Epoch length must be positive".to_string()));
+        }
+
+        let bonded: u64 = self.stakes.values()
+            .filter(|stake| stake.is_active)
+            .map(|stake| stake.amount)
+            .sum();
+        let year_fraction = self.epoch_length.num_seconds() as f64 / Duration::days(365).num_seconds() as f64;
+        let mut emitted = 0u64;
+
+        while now >= self.epoch_started_at + self.epoch_length {
+            let emission = self.emission_curve.emission(self.current_epoch, bonded, year_fraction, self.reward_pool);
+            if bonded > 0 && emission > 0 {
+                self.reward_per_stake += emission as u128 * REWARD_PRECISION / bonded as u128;
+                if let EmissionCurve::RewardPool { .. } = self.emission_curve {
+                    self.reward_pool -= emission;
+                }
+                emitted += emission;
+            }
+
+            self.current_epoch += 1;
+            self.epoch_started_at += self.epoch_length;
+        }
+
+        self.last_reward_calculation = now;
+        Ok(emitted)
+    }
+
+    /// Rewards credited to a stake by closed epochs but not yet settled, before commission
+    pub fn pending_rewards(&self, staker: &str) -> u64 {
+        self.stakes.get(staker)
+            .filter(|stake| stake.is_active)
+            .map(|stake| Self::reward_debt_for(stake.amount, self.reward_per_stake).saturating_sub(stake.reward_debt) as u64)
+            .unwrap_or(0)
+    }
+
+    fn reward_debt_for(amount: u64, reward_per_stake: u128) -> u128 {
+        amount as u128 * reward_per_stake / REWARD_PRECISION
+    }
+
+    /// Settle a staker's pending epoch rewards into `accumulated_rewards`
     pub fn calculate_rewards(&mut self, staker: &str) -> TribeResult<u64> {
+        let pending = self.pending_rewards(staker);
         let stake = self.stakes.get_mut(staker)
-            .ok_or_else(|| TribeError::InvalidOperation("No stake found for staker".to_string()))?;
+            .ok_or_else(|| TribeError::Contract("No stake found for staker".to_string()))?;
 
         if !stake.is_active {
             return Ok(0);
         }
 
-        let now = Utc::now();
-        let time_diff = now.signed_duration_since(stake.last_reward_claim);
-        let days = time_diff.num_days() as f64;
-
-        if days <= 0.0 {
-            return Ok(0);
-        }
+        stake.reward_debt = Self::reward_debt_for(stake.amount, self.reward_per_stake);
+        stake.last_reward_claim = Utc::now();
 
         // Get validator commission rate
         let commission_rate = self.validators.get(&stake.delegated_to)
             .map(|v| v.commission_rate)
             .unwrap_or(0.0);
 
-        // Calculate base rewards (annual rate / 365 * days * amount)
-        let base_reward = (self.reward_rate / 365.0 * days * stake.amount as f64) as u64;
-        
         // Apply validator commission
-        let commission = (base_reward as f64 * commission_rate) as u64;
-        let net_reward = base_reward - commission;
+        let commission = (pending as f64 * commission_rate) as u64;
+        let net_reward = pending - commission;
 
         stake.accumulated_rewards += net_reward;
-        stake.last_reward_claim = now;
 
         Ok(net_reward)
     }
@@ -357,12 +568,12 @@ impl StakingContract {
         let new_rewards = self.calculate_rewards(&staker)?;
         
         let stake = self.stakes.get_mut(&staker)
-            .ok_or_else(|| TribeError::InvalidOperation("No stake found for staker".to_string()))?;
+            .ok_or_else(|| TribeError::Contract("No stake found for staker".to_string()))?;
 
         let total_rewards = stake.accumulated_rewards;
         
         if total_rewards == 0 {
-            return Err(TribeError::InvalidOperation("No rewards to claim".to_string()));
+            return Err(TribeError::Contract("No rewards to claim".to_string()));
         }
 
         // Reset accumulated rewards
@@ -382,11 +593,11 @@ impl StakingContract {
         self_stake: u64,
     ) -> TribeResult<()> {
         if self.validators.contains_key(&validator_address) {
-            return Err(TribeError::InvalidOperation("Validator already exists".to_string()));
+            return Err(TribeError::Contract("Validator already exists".to_string()));
         }
 
         if commission_rate < 0.0 || commission_rate > 1.0 {
-            return Err(TribeError::InvalidOperation("Commission rate must be between 0 and 1".to_string()));
+            return Err(TribeError::Contract("Commission rate must be between 0 and 1".to_string()));
         }
 
         let validator_info = ValidatorInfo {
@@ -417,19 +628,29 @@ impl StakingContract {
         percentage: f64,
     ) -> TribeResult<u64> {
         let validator_info = self.validators.get_mut(&validator)
-            .ok_or_else(|| TribeError::InvalidOperation("Validator not found".to_string()))?;
+            .ok_or_else(|| TribeError::Contract("Validator not found".to_string()))?;
 
         if percentage < 0.0 || percentage > 1.0 {
-            return Err(TribeError::InvalidOperation("Slash percentage must be between 0 and 1".to_string()));
+            return Err(TribeError::Contract("Slash percentage must be between 0 and 1".to_string()));
         }
 
         let slash_amount = (validator_info.total_delegated as f64 * percentage) as u64;
+
+        // Settle rewards earned before the slash
+        let delegators: Vec<String> = self.stakes.values()
+            .filter(|stake| stake.delegated_to == validator && stake.is_active)
+            .map(|stake| stake.staker.clone())
+            .collect();
+        for delegator in &delegators {
+            self.calculate_rewards(delegator)?;
+        }
         
         // Apply slashing to all delegators
         for stake in self.stakes.values_mut() {
             if stake.delegated_to == validator && stake.is_active {
                 let stake_slash = (stake.amount as f64 * percentage) as u64;
                 stake.amount = stake.amount.saturating_sub(stake_slash);
+                stake.reward_debt = Self::reward_debt_for(stake.amount, self.reward_per_stake);
                 
                 if stake.amount == 0 {
                     stake.is_active = false;
@@ -437,7 +658,20 @@ impl StakingContract {
             }
         }
 
+        // Stake still unbonding from this validator is slashed too
+        let now = Utc::now();
+        let mut unbonding_slash = 0u64;
+        for entry in self.unbonding.iter_mut() {
+            if entry.validator == validator && !entry.is_mature(now) {
+                let entry_slash = (entry.amount as f64 * percentage) as u64;
+                entry.amount -= entry_slash;
+                unbonding_slash += entry_slash;
+            }
+        }
+        self.unbonding.retain(|entry| entry.amount > 0);
+
         // Update validator info
+        let validator_info = self.validators.get_mut(&validator).unwrap();
         validator_info.total_delegated = validator_info.total_delegated.saturating_sub(slash_amount);
         validator_info.slash_count += 1;
         validator_info.is_jailed = true;
@@ -445,21 +679,21 @@ impl StakingContract {
 
         self.total_staked = self.total_staked.saturating_sub(slash_amount);
 
-        Ok(slash_amount)
+        Ok(slash_amount + unbonding_slash)
     }
 
     /// Unjail a validator
     pub fn unjail_validator(&mut self, validator: String) -> TribeResult<()> {
         let validator_info = self.validators.get_mut(&validator)
-            .ok_or_else(|| TribeError::InvalidOperation("Validator not found".to_string()))?;
+            .ok_or_else(|| TribeError::Contract("Validator not found".to_string()))?;
 
         if !validator_info.is_jailed {
-            return Err(TribeError::InvalidOperation("Validator is not jailed".to_string()));
+            return Err(TribeError::Contract("Validator is not jailed".to_string()));
         }
 
         if let Some(jail_until) = validator_info.jail_until {
             if Utc::now() < jail_until {
-                return Err(TribeError::InvalidOperation("Jail period has not ended".to_string()));
+                return Err(TribeError::Contract("Jail period has not ended".to_string()));
             }
         }
 
@@ -504,6 +738,17 @@ impl StakingContract {
         hex::encode(&hash[..16])
     }
 
+    /// Bonded stake of every active, unjailed validator, for consensus validator selection
+    pub fn validator_stakes(&self) -> Vec<(String, u64)> {
+        let mut stakes: Vec<(String, u64)> = self.validators.values()
+            .filter(|v| v.is_active && !v.is_jailed)
+            .map(|v| (v.address.clone(), v.self_stake + v.total_delegated))
+            .filter(|(_, stake)| *stake > 0)
+            .collect();
+        stakes.sort();
+        stakes
+    }
+
     /// Get stake info for a staker
     pub fn get_stake_info(&self, staker: &str) -> Option<&StakeInfo> {
         self.stakes.get(staker)
@@ -517,7 +762,7 @@ impl StakingContract {
     /// Update validator uptime
     pub fn update_validator_uptime(&mut self, validator: String, uptime: f64) -> TribeResult<()> {
         let validator_info = self.validators.get_mut(&validator)
-            .ok_or_else(|| TribeError::InvalidOperation("Validator not found".to_string()))?;
+            .ok_or_else(|| TribeError::Contract("Validator not found".to_string()))?;
 
         validator_info.uptime = uptime.clamp(0.0, 1.0);
         validator_info.last_activity = Utc::now();
@@ -531,10 +776,34 @@ impl StakingContract {
         Ok(())
     }
 
+    /// Apply a consensus performance report: update uptime (jailing below 50%) and slash
+    /// validators that missed more than `max_missed_blocks`. Returns the amount slashed.
+    /// Reports must already be verified, e.g. by `ConsensusEngine::report_performance`.
+    pub fn apply_performance_report(&mut self, report: &ValidatorPerformanceReport) -> TribeResult<u64> {
+        if !self.validators.contains_key(&report.validator) {
+            return Err(TribeError::Contract("Validator not found".to_string()));
+        }
+        if self.reported_epochs.get(&report.validator).is_some_and(|&epoch| epoch >= report.epoch) {
+            return Err(TribeError::Contract(format!(
+                "Performance for epoch {} already applied", report.epoch
+            )));
+        }
+        self.reported_epochs.insert(report.validator.clone(), report.epoch);
+
+        self.update_validator_uptime(report.validator.clone(), report.uptime())?;
+
+        let missed = report.missed_blocks();
+        if missed > self.max_missed_blocks {
+            let reason = SlashingReason::MissedBlocks(missed.min(u32::MAX as u64) as u32);
+            return self.slash_validator(report.validator.clone(), reason, self.missed_block_slash);
+        }
+        Ok(0)
+    }
+
     /// Set auto-compound for a stake
     pub fn set_auto_compound(&mut self, staker: String, auto_compound: bool) -> TribeResult<()> {
         let stake = self.stakes.get_mut(&staker)
-            .ok_or_else(|| TribeError::InvalidOperation("No stake found for staker".to_string()))?;
+            .ok_or_else(|| TribeError::Contract("No stake found for staker".to_string()))?;
 
         stake.auto_compound = auto_compound;
         Ok(())
@@ -554,6 +823,7 @@ impl StakingContract {
         // Add rewards to stake amount
         stake.amount += total_rewards;
         stake.accumulated_rewards = 0;
+        stake.reward_debt = Self::reward_debt_for(stake.amount, self.reward_per_stake);
 
         // Update validator delegation
         if let Some(validator) = self.validators.get_mut(&stake.delegated_to) {
@@ -633,6 +903,34 @@ mod tests {
         
         let stake_info = contract.get_stake_info("staker1").unwrap();
         assert_eq!(stake_info.amount, 3000);
+        assert_eq!(contract.pending_unbonding("staker1"), returned);
+    }
+
+    #[test]
+    fn test_unbonding_queue() {
+        let mut contract = StakingContract::new(
+            "token123".to_string(),
+            "validator1".to_string(),
+            1000,
+            0.1,
+        ).unwrap().with_unbonding_period(Duration::days(7));
+
+        contract.stake("staker1".to_string(), 5000, 0).unwrap();
+        assert_eq!(contract.unstake("staker1".to_string(), 2000).unwrap(), 2000);
+        assert_eq!(contract.total_staked, 3000);
+
+        // Still slashable while unbonding
+        let slashed = contract.slash_validator(
+            "validator1".to_string(),
+            SlashingReason::Downtime,
+            0.1,
+        ).unwrap();
+        assert_eq!(slashed, 500);
+        assert_eq!(contract.pending_unbonding("staker1"), 1800);
+
+        assert!(contract.withdraw_unbonded("staker1", Utc::now()).is_err());
+        assert_eq!(contract.withdraw_unbonded("staker1", Utc::now() + Duration::days(7)).unwrap(), 1800);
+        assert_eq!(contract.pending_unbonding("staker1"), 0);
     }
 
     #[test]
@@ -682,12 +980,70 @@ mod tests {
 
         contract.stake("staker1".to_string(), 10000, 30).unwrap();
         
-        // Manually set last reward claim to 1 day ago for testing
-        let stake = contract.stakes.get_mut("staker1").unwrap();
-        stake.last_reward_claim = Utc::now() - Duration::days(1);
+        // Close one daily epoch
+        let emitted = contract.advance_epoch(contract.epoch_started_at + Duration::days(1)).unwrap();
+        assert_eq!(emitted, 10);
+        assert_eq!(contract.current_epoch, 1);
         
         let rewards = contract.calculate_rewards("staker1").unwrap();
         // Should be approximately 10 tokens (10000 * 0.365 / 365)
         assert!(rewards >= 9 && rewards <= 11);
+        assert_eq!(contract.calculate_rewards("staker1").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_reward_pool_epochs() {
+        let mut contract = StakingContract::new(
+            "token123".to_string(),
+            "validator1".to_string(),
+            1000,
+            0.1,
+        ).unwrap().with_emission_curve(EmissionCurve::RewardPool { per_epoch: 1000 });
+        contract.validators.get_mut("validator1").unwrap().commission_rate = 0.0;
+        contract.fund_rewards(2500);
+
+        contract.stake("staker1".to_string(), 1000, 0).unwrap();
+        contract.stake("staker2".to_string(), 3000, 0).unwrap();
+
+        // Three epochs, but the pool only covers two and a half
+        let start = contract.epoch_started_at;
+        assert_eq!(contract.advance_epoch(start + Duration::days(3)).unwrap(), 2500);
+        assert_eq!(contract.reward_pool, 0);
+        assert_eq!(contract.pending_rewards("staker1"), 625);
+        assert_eq!(contract.pending_rewards("staker2"), 1875);
+
+        // Stake added later doesn't share earlier epochs
+        contract.stake("staker3".to_string(), 4000, 0).unwrap();
+        assert_eq!(contract.pending_rewards("staker3"), 0);
+        assert_eq!(contract.claim_rewards("staker1".to_string()).unwrap(), 625);
+    }
+
+    #[test]
+    fn test_performance_reports() {
+        let mut contract = StakingContract::new(
+            "token123".to_string(),
+            "validator1".to_string(),
+            1000,
+            0.1,
+        ).unwrap();
+        contract.stake("staker1".to_string(), 10000, 0).unwrap();
+
+        let healthy = ValidatorPerformanceReport::new("validator1".to_string(), 1, 100, 90, "validator2".to_string());
+        assert_eq!(contract.apply_performance_report(&healthy).unwrap(), 0);
+        assert_eq!(contract.validators["validator1"].uptime, 0.9);
+        assert!(contract.apply_performance_report(&healthy).is_err());
+
+        // Missing 60 of 100 slots jails and slashes 1%
+        let poor = ValidatorPerformanceReport::new("validator1".to_string(), 2, 100, 40, "validator2".to_string());
+        assert_eq!(contract.apply_performance_report(&poor).unwrap(), 100);
+        assert!(contract.validators["validator1"].is_jailed);
+    }
+
+    #[test]
+    fn test_decaying_emission() {
+        let curve = EmissionCurve::Decaying { initial_rate: 0.365, decay: 0.5 };
+        let day = 1.0 / 365.0;
+        assert_eq!(curve.emission(0, 10000, day, 0), 10);
+        assert_eq!(curve.emission(1, 10000, day, 0), 5);
     }
 } 
\ No newline at end of file
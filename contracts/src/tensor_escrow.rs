@@ -0,0 +1,153 @@
+use tribechain_core::{TribeResult, TribeError, TensorTask, AI3Proof};
+use serde::{Deserialize, Serialize};
+
+/// Blocks a miner has to deliver before the requester can be refunded
+pub const DEFAULT_TENSOR_TIMEOUT_BLOCKS: u64 = 100;
+
+/// Where an escrowed reward ended up
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum EscrowStatus {
+    Open,
+    Paid { miner: String },
+    Refunded,
+}
+
+/// Reward locked for one tensor computation until a valid proof arrives or the task times out
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TensorEscrow {
+    pub task: TensorTask, // Handed to the mining pool as-is
+    pub contract_address: String, // TensorCompute contract holding the escrow
+    pub token_id: String,
+    pub task_hash: String, // `TensorTask::task_hash` when escrowed
+    pub created_height: u64,
+    pub deadline_height: u64,
+    pub status: EscrowStatus,
+    #[serde(default)]
+    pub agreed_price: Option<u64>, // Winning marketplace bid; the rest of the reward went back to the requester
+}
+
+impl TensorEscrow {
+    pub fn new(
+        task: TensorTask,
+        contract_address: String,
+        token_id: String,
+        created_height: u64,
+        timeout_blocks: u64,
+    ) -> TribeResult<Self> {
+        if task.reward == 0 {
+            return Err(TribeError::Contract("Tensor task reward must be positive".to_string()));
+        }
+        if task.input_data.is_empty() || task.expected_output_size == 0 {
+            return Err(TribeError::Contract("Tensor task needs input data and an output size".to_string()));
+        }
+        if timeout_blocks == 0 {
+            return Err(TribeError::Contract("Tensor task timeout must be positive".to_string()));
+        }
+
+        Ok(Self {
+            task_hash: task.task_hash(),
+            task,
+            contract_address,
+            token_id,
+            created_height,
+            deadline_height: created_height + timeout_blocks,
+            status: EscrowStatus::Open,
+            agreed_price: None,
+        })
+    }
+
+    /// Amount still held for the task
+    pub fn locked_amount(&self) -> u64 {
+        self.agreed_price.unwrap_or(self.task.reward)
+    }
+
+    pub fn is_expired(&self, block_height: u64) -> bool {
+        block_height > self.deadline_height
+    }
+
+    /// Check a miner's proof and result against the escrowed task
+    pub fn verify(&self, miner: &str, proof: &AI3Proof, result: &[f32], block_height: u64) -> TribeResult<()> {
+        if self.status != EscrowStatus::Open {
+            return Err(TribeError::Contract("Tensor task is already settled".to_string()));
+        }
+        if self.is_expired(block_height) {
+            return Err(TribeError::Contract("Tensor task has timed out".to_string()));
+        }
+        if let Some(assigned) = &self.task.assigned_miner {
+            if assigned != miner {
+                return Err(TribeError::Contract("Tensor task was awarded to another miner".to_string()));
+            }
+        }
+        if proof.task_id != self.task.id {
+            return Err(TribeError::Contract("Proof is for a different task".to_string()));
+        }
+        if result.len() != self.task.expected_output_size {
+            return Err(TribeError::Contract(format!(
+                "Expected {} output values, got {}", self.task.expected_output_size, result.len()
+            )));
+        }
+        if proof.computation_time > self.task.max_computation_time {
+            return Err(TribeError::Contract("Computation took longer than the task allows".to_string()));
+        }
+        if !(0.1..=2.0).contains(&proof.optimization_factor) {
+            return Err(TribeError::Contract("Optimization factor out of range".to_string()));
+        }
+        if proof.tensor_hash != self.task.result_hash(result) {
+            return Err(TribeError::Contract("Proof does not match the task hash and result".to_string()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task() -> TensorTask {
+        TensorTask {
+            id: "task-1".to_string(),
+            operation: "relu".to_string(),
+            input_data: vec![-1.0, 2.0],
+            expected_output_size: 2,
+            max_computation_time: 60,
+            reward: 100,
+            requester: "alice".to_string(),
+            completed: false,
+            result: None,
+            assigned_miner: None,
+            created_at: 0,
+        }
+    }
+
+    fn proof(task_id: &str, tensor_hash: String) -> AI3Proof {
+        AI3Proof {
+            task_id: task_id.to_string(),
+            optimization_factor: 1.0,
+            tensor_hash,
+            computation_time: 10,
+            miner_signature: String::new(),
+            commitment_nonce: 0,
+        }
+    }
+
+    #[test]
+    fn test_proof_verification() {
+        let escrow = TensorEscrow::new(task(), "tensor".to_string(), "TRIBE".to_string(), 10, 5).unwrap();
+        let result = vec![0.0, 2.0];
+        let hash = escrow.task.result_hash(&result);
+
+        assert!(escrow.verify("miner", &proof("task-1", hash.clone()), &result, 15).is_ok());
+        assert!(escrow.verify("miner", &proof("task-1", hash.clone()), &result, 16).is_err()); // Timed out
+        assert!(escrow.verify("miner", &proof("task-2", hash.clone()), &result, 12).is_err());
+        assert!(escrow.verify("miner", &proof("task-1", hash), &[0.0, 3.0], 12).is_err());
+        assert!(escrow.verify("miner", &proof("task-1", "forged".to_string()), &result, 12).is_err());
+
+        let mut awarded = escrow.clone();
+        awarded.task.assigned_miner = Some("bob".to_string());
+        assert!(awarded.verify("miner", &proof("task-1", escrow.task.result_hash(&result)), &result, 12).is_err());
+
+        let mut unpaid = task();
+        unpaid.reward = 0;
+        assert!(TensorEscrow::new(unpaid, "tensor".to_string(), "TRIBE".to_string(), 10, 5).is_err());
+    }
+}
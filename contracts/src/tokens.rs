@@ -1,4 +1,4 @@
-use tribechain_core::{TribeResult, TribeError};
+use tribechain_core::{TribeResult, TribeError, Storage};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use chrono::{DateTime, Utc};
@@ -17,6 +17,12 @@ pub struct TokenContract {
     pub is_paused: bool,
     pub owner: String,
     pub minters: Vec<String>,
+    #[serde(default)]
+    pub permit_nonces: HashMap<String, u64>, // owner -> next permit nonce
+    #[serde(default)]
+    pub snapshots: TokenSnapshots,
+    #[serde(default)]
+    pub event_log: TokenEventLog,
     pub created_at: DateTime<Utc>,
     pub last_updated: DateTime<Utc>,
 }
@@ -34,6 +40,9 @@ pub struct TokenInfo {
     pub social_links: HashMap<String, String>,
 }
 
+/// Events returned per page by `TokenContract::get_transfers`
+pub const EVENT_PAGE_SIZE: usize = 50;
+
 /// Token operation types
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum TokenOperation {
@@ -74,6 +83,78 @@ pub enum TokenOperation {
     },
 }
 
+/// Balance history for snapshot queries. Values are copied on write: an account's
+/// balance is recorded for the current snapshot only when it's about to change.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TokenSnapshots {
+    pub current_id: u64, // 0 until the first snapshot is taken
+    pub account_history: HashMap<String, Vec<(u64, u64)>>, // address -> (snapshot id, balance at it)
+    pub supply_history: Vec<(u64, u64)>, // (snapshot id, total supply at it)
+}
+
+impl TokenSnapshots {
+    /// Record `value` for the current snapshot unless it's already recorded
+    fn record(history: &mut Vec<(u64, u64)>, current_id: u64, value: u64) {
+        if current_id == 0 {
+            return;
+        }
+        if history.last().is_none_or(|(id, _)| *id < current_id) {
+            history.push((current_id, value));
+        }
+    }
+
+    /// Value at `snapshot_id`: the first entry recorded at or after it, else `current`
+    fn value_at(history: &[(u64, u64)], snapshot_id: u64, current: u64) -> u64 {
+        let index = history.partition_point(|(id, _)| *id < snapshot_id);
+        history.get(index).map_or(current, |(_, value)| *value)
+    }
+}
+
+/// Typed approval message an owner signs off-chain for `TokenContract::permit`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PermitMessage {
+    pub token_id: String,
+    pub owner: String,
+    pub spender: String,
+    pub amount: u64,
+    pub nonce: u64,
+    pub deadline: DateTime<Utc>,
+}
+
+impl PermitMessage {
+    /// Hash of the typed message; the type tag keeps it from colliding with other signed data
+    pub fn digest(&self) -> String {
+        use sha2::{Sha256, Digest};
+
+        let mut hasher = Sha256::new();
+        hasher.update(b"Permit(token_id,owner,spender,amount,nonce,deadline)");
+        hasher.update(self.token_id.as_bytes());
+        hasher.update(self.owner.as_bytes());
+        hasher.update(self.spender.as_bytes());
+        hasher.update(self.amount.to_le_bytes());
+        hasher.update(self.nonce.to_le_bytes());
+        hasher.update(self.deadline.timestamp().to_le_bytes());
+        hex::encode(hasher.finalize())
+    }
+
+    /// Sign the message (simplified - in real implementation would use proper cryptography)
+    pub fn sign(&self, private_key: &str) -> String {
+        Self::signature_for(&self.digest(), private_key)
+    }
+
+    pub fn verify(&self, signature: &str, public_key: &str) -> bool {
+        Self::signature_for(&self.digest(), public_key) == signature
+    }
+
+    fn signature_for(digest: &str, key: &str) -> String {
+        use sha2::{Sha256, Digest};
+
+        let mut hasher = Sha256::new();
+        hasher.update(format!("{}{}", digest, key).as_bytes());
+        hex::encode(hasher.finalize())
+    }
+}
+
 /// Token balance information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TokenBalance {
@@ -91,6 +172,8 @@ pub struct TransferEvent {
     pub amount: u64,
     pub timestamp: DateTime<Utc>,
     pub transaction_hash: String,
+    #[serde(default)]
+    pub block_index: u64,
 }
 
 /// Token approval event
@@ -101,6 +184,63 @@ pub struct ApprovalEvent {
     pub amount: u64,
     pub timestamp: DateTime<Utc>,
     pub transaction_hash: String,
+    #[serde(default)]
+    pub block_index: u64,
+}
+
+/// Append-only transfer and approval history. Mints are transfers from "" and
+/// burns transfers to "". Events are stamped with the block and transaction set
+/// through `TokenContract::set_block_context`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TokenEventLog {
+    pub transfers: Vec<TransferEvent>,
+    pub approvals: Vec<ApprovalEvent>,
+    pub block_index: u64,
+    pub transaction_hash: String,
+}
+
+impl TokenEventLog {
+    fn storage_key(token_id: &str) -> String {
+        format!("token_events_{}", token_id)
+    }
+
+    /// Persist the log under the token's ID
+    pub fn save(&self, storage: &dyn Storage, token_id: &str) -> TribeResult<()> {
+        let serialized = bincode::serialize(self)
+            .map_err(|e| TribeError::Storage(format!("Failed to serialize token events: {}", e)))?;
+        storage.save_data(&Self::storage_key(token_id), &serialized)
+    }
+
+    /// Load a token's log; empty if none was saved
+    pub fn load(storage: &dyn Storage, token_id: &str) -> TribeResult<Self> {
+        match storage.load_data(&Self::storage_key(token_id))? {
+            Some(data) => bincode::deserialize(&data)
+                .map_err(|e| TribeError::Storage(format!("Failed to deserialize token events: {}", e))),
+            None => Ok(Self::default()),
+        }
+    }
+
+    fn record_transfer(&mut self, from: &str, to: &str, amount: u64) {
+        self.transfers.push(TransferEvent {
+            from: from.to_string(),
+            to: to.to_string(),
+            amount,
+            timestamp: Utc::now(),
+            transaction_hash: self.transaction_hash.clone(),
+            block_index: self.block_index,
+        });
+    }
+
+    fn record_approval(&mut self, owner: &str, spender: &str, amount: u64) {
+        self.approvals.push(ApprovalEvent {
+            owner: owner.to_string(),
+            spender: spender.to_string(),
+            amount,
+            timestamp: Utc::now(),
+            transaction_hash: self.transaction_hash.clone(),
+            block_index: self.block_index,
+        });
+    }
 }
 
 /// Token statistics
@@ -126,11 +266,11 @@ impl TokenContract {
         creator: String,
     ) -> TribeResult<Self> {
         if name.is_empty() || symbol.is_empty() {
-            return Err(TribeError::InvalidOperation("Token name and symbol cannot be empty".to_string()));
+            return Err(TribeError::Token("Token name and symbol cannot be empty".to_string()));
         }
 
         if total_supply == 0 {
-            return Err(TribeError::InvalidOperation("Total supply must be greater than 0".to_string()));
+            return Err(TribeError::Token("Total supply must be greater than 0".to_string()));
         }
 
         let token_id = Self::generate_token_id(&name, &symbol, &creator);
@@ -158,6 +298,9 @@ impl TokenContract {
             is_paused: false,
             owner: creator.clone(),
             minters: vec![creator],
+            permit_nonces: HashMap::new(),
+            snapshots: TokenSnapshots::default(),
+            event_log: TokenEventLog::default(),
             created_at: Utc::now(),
             last_updated: Utc::now(),
         })
@@ -194,41 +337,70 @@ impl TokenContract {
     /// Transfer tokens
     pub fn transfer(&mut self, from: String, to: String, amount: u64) -> TribeResult<()> {
         if self.is_paused {
-            return Err(TribeError::InvalidOperation("Token transfers are paused".to_string()));
+            return Err(TribeError::Token("Token transfers are paused".to_string()));
         }
 
         if from == to {
-            return Err(TribeError::InvalidOperation("Cannot transfer to self".to_string()));
+            return Err(TribeError::Token("Cannot transfer to self".to_string()));
         }
 
         if amount == 0 {
-            return Err(TribeError::InvalidOperation("Transfer amount must be greater than 0".to_string()));
+            return Err(TribeError::Token("Transfer amount must be greater than 0".to_string()));
         }
 
         let from_balance = self.balances.get(&from).copied().unwrap_or(0);
         if from_balance < amount {
-            return Err(TribeError::InvalidOperation("Insufficient balance".to_string()));
+            return Err(TribeError::Token("Insufficient balance".to_string()));
         }
 
         // Update balances
-        self.balances.insert(from.clone(), from_balance - amount);
-        let to_balance = self.balances.get(&to).copied().unwrap_or(0);
-        self.balances.insert(to.clone(), to_balance + amount);
+        self.set_balance(&from, from_balance - amount);
+        let to_balance = self.balance_of(&to);
+        self.set_balance(&to, to_balance + amount);
+        self.event_log.record_transfer(&from, &to, amount);
 
         self.last_updated = Utc::now();
         Ok(())
     }
 
+    /// Transfer to many recipients at once; nothing moves unless every transfer can
+    pub fn batch_transfer(&mut self, from: String, transfers: Vec<(String, u64)>) -> TribeResult<()> {
+        if self.is_paused {
+            return Err(TribeError::Token("Token transfers are paused".to_string()));
+        }
+
+        let mut total: u64 = 0;
+        for (to, amount) in &transfers {
+            if *to == from {
+                return Err(TribeError::Token("Cannot transfer to self".to_string()));
+            }
+            if *amount == 0 {
+                return Err(TribeError::Token("Transfer amount must be greater than 0".to_string()));
+            }
+            total = total.checked_add(*amount)
+                .ok_or_else(|| TribeError::Token("Batch total overflows".to_string()))?;
+        }
+        if self.balance_of(&from) < total {
+            return Err(TribeError::Token("Insufficient balance".to_string()));
+        }
+
+        for (to, amount) in transfers {
+            self.transfer(from.clone(), to, amount)?;
+        }
+        Ok(())
+    }
+
     /// Approve spender to spend tokens
     pub fn approve(&mut self, owner: String, spender: String, amount: u64) -> TribeResult<()> {
         if self.is_paused {
-            return Err(TribeError::InvalidOperation("Token operations are paused".to_string()));
+            return Err(TribeError::Token("Token operations are paused".to_string()));
         }
 
         if owner == spender {
-            return Err(TribeError::InvalidOperation("Cannot approve self".to_string()));
+            return Err(TribeError::Token("Cannot approve self".to_string()));
         }
 
+        self.event_log.record_approval(&owner, &spender, amount);
         self.allowances
             .entry(owner)
             .or_insert_with(HashMap::new)
@@ -238,6 +410,42 @@ impl TokenContract {
         Ok(())
     }
 
+    /// Permit message the owner must sign for their next permit
+    pub fn permit_message(&self, owner: &str, spender: &str, amount: u64, deadline: DateTime<Utc>) -> PermitMessage {
+        PermitMessage {
+            token_id: self.token_info.id.clone(),
+            owner: owner.to_string(),
+            spender: spender.to_string(),
+            amount,
+            nonce: self.permit_nonces.get(owner).copied().unwrap_or(0),
+            deadline,
+        }
+    }
+
+    /// Approve a spender with the owner's off-chain signature, so anyone can submit it.
+    /// The owner's address is the verifying key, and each permit consumes the owner's nonce.
+    pub fn permit(
+        &mut self,
+        owner: String,
+        spender: String,
+        amount: u64,
+        deadline: DateTime<Utc>,
+        signature: &str,
+    ) -> TribeResult<()> {
+        if Utc::now() > deadline {
+            return Err(TribeError::Token("Permit has expired".to_string()));
+        }
+
+        let message = self.permit_message(&owner, &spender, amount, deadline);
+        if !message.verify(signature, &owner) {
+            return Err(TribeError::Token("Invalid permit signature".to_string()));
+        }
+
+        self.approve(owner.clone(), spender, amount)?;
+        *self.permit_nonces.entry(owner).or_insert(0) += 1;
+        Ok(())
+    }
+
     /// Transfer tokens from one account to another using allowance
     pub fn transfer_from(
         &mut self,
@@ -247,7 +455,7 @@ impl TokenContract {
         amount: u64,
     ) -> TribeResult<()> {
         if self.is_paused {
-            return Err(TribeError::InvalidOperation("Token transfers are paused".to_string()));
+            return Err(TribeError::Token("Token transfers are paused".to_string()));
         }
 
         // Check allowance
@@ -258,7 +466,7 @@ impl TokenContract {
             .unwrap_or(0);
 
         if allowance < amount {
-            return Err(TribeError::InvalidOperation("Insufficient allowance".to_string()));
+            return Err(TribeError::Token("Insufficient allowance".to_string()));
         }
 
         // Perform transfer
@@ -278,32 +486,33 @@ impl TokenContract {
     /// Mint new tokens
     pub fn mint(&mut self, to: String, amount: u64, minter: String) -> TribeResult<()> {
         if !self.is_mintable {
-            return Err(TribeError::InvalidOperation("Token is not mintable".to_string()));
+            return Err(TribeError::Token("Token is not mintable".to_string()));
         }
 
         if !self.minters.contains(&minter) {
-            return Err(TribeError::InvalidOperation("Caller is not authorized to mint".to_string()));
+            return Err(TribeError::Token("Caller is not authorized to mint".to_string()));
         }
 
         if self.is_paused {
-            return Err(TribeError::InvalidOperation("Token operations are paused".to_string()));
+            return Err(TribeError::Token("Token operations are paused".to_string()));
         }
 
         if amount == 0 {
-            return Err(TribeError::InvalidOperation("Mint amount must be greater than 0".to_string()));
+            return Err(TribeError::Token("Mint amount must be greater than 0".to_string()));
         }
 
         // Check max supply
         if let Some(max_supply) = self.max_supply {
             if self.total_supply + amount > max_supply {
-                return Err(TribeError::InvalidOperation("Minting would exceed max supply".to_string()));
+                return Err(TribeError::Token("Minting would exceed max supply".to_string()));
             }
         }
 
         // Update balances and total supply
-        let to_balance = self.balances.get(&to).copied().unwrap_or(0);
-        self.balances.insert(to, to_balance + amount);
-        self.total_supply += amount;
+        let to_balance = self.balance_of(&to);
+        self.set_balance(&to, to_balance + amount);
+        self.set_total_supply(self.total_supply + amount);
+        self.event_log.record_transfer("", &to, amount);
 
         self.last_updated = Utc::now();
         Ok(())
@@ -312,25 +521,26 @@ impl TokenContract {
     /// Burn tokens
     pub fn burn(&mut self, from: String, amount: u64) -> TribeResult<()> {
         if !self.is_burnable {
-            return Err(TribeError::InvalidOperation("Token is not burnable".to_string()));
+            return Err(TribeError::Token("Token is not burnable".to_string()));
         }
 
         if self.is_paused {
-            return Err(TribeError::InvalidOperation("Token operations are paused".to_string()));
+            return Err(TribeError::Token("Token operations are paused".to_string()));
         }
 
         if amount == 0 {
-            return Err(TribeError::InvalidOperation("Burn amount must be greater than 0".to_string()));
+            return Err(TribeError::Token("Burn amount must be greater than 0".to_string()));
         }
 
         let from_balance = self.balances.get(&from).copied().unwrap_or(0);
         if from_balance < amount {
-            return Err(TribeError::InvalidOperation("Insufficient balance to burn".to_string()));
+            return Err(TribeError::Token("Insufficient balance to burn".to_string()));
         }
 
         // Update balance and total supply
-        self.balances.insert(from, from_balance - amount);
-        self.total_supply -= amount;
+        self.set_balance(&from, from_balance - amount);
+        self.set_total_supply(self.total_supply - amount);
+        self.event_log.record_transfer(&from, "", amount);
 
         self.last_updated = Utc::now();
         Ok(())
@@ -339,11 +549,11 @@ impl TokenContract {
     /// Pause token operations
     pub fn pause(&mut self, caller: String) -> TribeResult<()> {
         if !self.is_pausable {
-            return Err(TribeError::InvalidOperation("Token is not pausable".to_string()));
+            return Err(TribeError::Token("Token is not pausable".to_string()));
         }
 
         if caller != self.owner {
-            return Err(TribeError::InvalidOperation("Only owner can pause token".to_string()));
+            return Err(TribeError::Token("Only owner can pause token".to_string()));
         }
 
         self.is_paused = true;
@@ -354,7 +564,7 @@ impl TokenContract {
     /// Unpause token operations
     pub fn unpause(&mut self, caller: String) -> TribeResult<()> {
         if caller != self.owner {
-            return Err(TribeError::InvalidOperation("Only owner can unpause token".to_string()));
+            return Err(TribeError::Token("Only owner can unpause token".to_string()));
         }
 
         self.is_paused = false;
@@ -365,7 +575,7 @@ impl TokenContract {
     /// Add a minter
     pub fn add_minter(&mut self, minter: String, caller: String) -> TribeResult<()> {
         if caller != self.owner {
-            return Err(TribeError::InvalidOperation("Only owner can add minters".to_string()));
+            return Err(TribeError::Token("Only owner can add minters".to_string()));
         }
 
         if !self.minters.contains(&minter) {
@@ -379,7 +589,7 @@ impl TokenContract {
     /// Remove a minter
     pub fn remove_minter(&mut self, minter: String, caller: String) -> TribeResult<()> {
         if caller != self.owner {
-            return Err(TribeError::InvalidOperation("Only owner can remove minters".to_string()));
+            return Err(TribeError::Token("Only owner can remove minters".to_string()));
         }
 
         self.minters.retain(|m| m != &minter);
@@ -390,7 +600,7 @@ impl TokenContract {
     /// Transfer ownership
     pub fn transfer_ownership(&mut self, new_owner: String, caller: String) -> TribeResult<()> {
         if caller != self.owner {
-            return Err(TribeError::InvalidOperation("Only owner can transfer ownership".to_string()));
+            return Err(TribeError::Token("Only owner can transfer ownership".to_string()));
         }
 
         self.owner = new_owner;
@@ -403,6 +613,71 @@ impl TokenContract {
         self.balances.get(address).copied().unwrap_or(0)
     }
 
+    /// Block and transaction that subsequent events belong to
+    pub fn set_block_context(&mut self, block_index: u64, transaction_hash: String) {
+        self.event_log.block_index = block_index;
+        self.event_log.transaction_hash = transaction_hash;
+    }
+
+    /// Transfers sent or received by `address`, newest first, `EVENT_PAGE_SIZE` per page
+    pub fn get_transfers(&self, address: &str, page: usize) -> Vec<&TransferEvent> {
+        self.event_log.transfers.iter()
+            .rev()
+            .filter(|event| event.from == address || event.to == address)
+            .skip(page * EVENT_PAGE_SIZE)
+            .take(EVENT_PAGE_SIZE)
+            .collect()
+    }
+
+    /// Approvals granted by or to `address`, newest first
+    pub fn get_approvals(&self, address: &str, page: usize) -> Vec<&ApprovalEvent> {
+        self.event_log.approvals.iter()
+            .rev()
+            .filter(|event| event.owner == address || event.spender == address)
+            .skip(page * EVENT_PAGE_SIZE)
+            .take(EVENT_PAGE_SIZE)
+            .collect()
+    }
+
+    /// Freeze current balances and supply for later queries; returns the snapshot id
+    pub fn snapshot(&mut self) -> u64 {
+        self.snapshots.current_id += 1;
+        self.snapshots.current_id
+    }
+
+    /// Balance an address held when `snapshot_id` was taken
+    pub fn balance_of_at(&self, address: &str, snapshot_id: u64) -> TribeResult<u64> {
+        self.check_snapshot_id(snapshot_id)?;
+        let history = self.snapshots.account_history.get(address).map(Vec::as_slice).unwrap_or(&[]);
+        Ok(TokenSnapshots::value_at(history, snapshot_id, self.balance_of(address)))
+    }
+
+    /// Total supply when `snapshot_id` was taken
+    pub fn total_supply_at(&self, snapshot_id: u64) -> TribeResult<u64> {
+        self.check_snapshot_id(snapshot_id)?;
+        Ok(TokenSnapshots::value_at(&self.snapshots.supply_history, snapshot_id, self.total_supply))
+    }
+
+    fn check_snapshot_id(&self, snapshot_id: u64) -> TribeResult<()> {
+        if snapshot_id == 0 || snapshot_id > self.snapshots.current_id {
+            return Err(TribeError::Token(format!("Unknown snapshot {}", snapshot_id)));
+        }
+        Ok(())
+    }
+
+    /// Every balance change goes through here so snapshots see the old value
+    fn set_balance(&mut self, address: &str, balance: u64) {
+        let previous = self.balance_of(address);
+        let history = self.snapshots.account_history.entry(address.to_string()).or_default();
+        TokenSnapshots::record(history, self.snapshots.current_id, previous);
+        self.balances.insert(address.to_string(), balance);
+    }
+
+    fn set_total_supply(&mut self, total_supply: u64) {
+        TokenSnapshots::record(&mut self.snapshots.supply_history, self.snapshots.current_id, self.total_supply);
+        self.total_supply = total_supply;
+    }
+
     /// Get allowance
     pub fn allowance(&self, owner: &str, spender: &str) -> u64 {
         self.allowances
@@ -438,7 +713,7 @@ impl TokenContract {
         caller: String,
     ) -> TribeResult<()> {
         if caller != self.owner {
-            return Err(TribeError::InvalidOperation("Only owner can update metadata".to_string()));
+            return Err(TribeError::Token("Only owner can update metadata".to_string()));
         }
 
         if let Some(desc) = description {
@@ -460,7 +735,7 @@ impl TokenContract {
     /// Add social link
     pub fn add_social_link(&mut self, platform: String, url: String, caller: String) -> TribeResult<()> {
         if caller != self.owner {
-            return Err(TribeError::InvalidOperation("Only owner can add social links".to_string()));
+            return Err(TribeError::Token("Only owner can add social links".to_string()));
         }
 
         self.token_info.social_links.insert(platform, url);
@@ -487,48 +762,48 @@ impl TokenContract {
         match operation {
             TokenOperation::Transfer { from, amount, .. } => {
                 if from != caller {
-                    return Err(TribeError::InvalidOperation("Can only transfer own tokens".to_string()));
+                    return Err(TribeError::Token("Can only transfer own tokens".to_string()));
                 }
                 if self.balance_of(from) < *amount {
-                    return Err(TribeError::InvalidOperation("Insufficient balance".to_string()));
+                    return Err(TribeError::Token("Insufficient balance".to_string()));
                 }
             }
             TokenOperation::Approve { owner, .. } => {
                 if owner != caller {
-                    return Err(TribeError::InvalidOperation("Can only approve own tokens".to_string()));
+                    return Err(TribeError::Token("Can only approve own tokens".to_string()));
                 }
             }
             TokenOperation::TransferFrom { spender, from, amount, .. } => {
                 if spender != caller {
-                    return Err(TribeError::InvalidOperation("Invalid spender".to_string()));
+                    return Err(TribeError::Token("Invalid spender".to_string()));
                 }
                 if self.allowance(from, spender) < *amount {
-                    return Err(TribeError::InvalidOperation("Insufficient allowance".to_string()));
+                    return Err(TribeError::Token("Insufficient allowance".to_string()));
                 }
             }
             TokenOperation::Mint { .. } => {
                 if !self.minters.contains(&caller.to_string()) {
-                    return Err(TribeError::InvalidOperation("Not authorized to mint".to_string()));
+                    return Err(TribeError::Token("Not authorized to mint".to_string()));
                 }
             }
             TokenOperation::Burn { from, .. } => {
                 if from != caller {
-                    return Err(TribeError::InvalidOperation("Can only burn own tokens".to_string()));
+                    return Err(TribeError::Token("Can only burn own tokens".to_string()));
                 }
             }
             TokenOperation::Pause | TokenOperation::Unpause => {
                 if caller != self.owner {
-                    return Err(TribeError::InvalidOperation("Only owner can pause/unpause".to_string()));
+                    return Err(TribeError::Token("Only owner can pause/unpause".to_string()));
                 }
             }
             TokenOperation::AddMinter { .. } | TokenOperation::RemoveMinter { .. } => {
                 if caller != self.owner {
-                    return Err(TribeError::InvalidOperation("Only owner can manage minters".to_string()));
+                    return Err(TribeError::Token("Only owner can manage minters".to_string()));
                 }
             }
             TokenOperation::TransferOwnership { .. } => {
                 if caller != self.owner {
-                    return Err(TribeError::InvalidOperation("Only owner can transfer ownership".to_string()));
+                    return Err(TribeError::Token("Only owner can transfer ownership".to_string()));
                 }
             }
         }
@@ -614,6 +889,56 @@ mod tests {
         assert!(token.transfer("recipient".to_string(), "other".to_string(), 2000).is_err());
     }
 
+    #[test]
+    fn test_batch_transfer() {
+        let mut token = TokenContract::new(
+            "Test Token".to_string(),
+            "TEST".to_string(),
+            1000,
+            6,
+            "creator".to_string(),
+        ).unwrap();
+
+        let airdrop = vec![("alice".to_string(), 100), ("bob".to_string(), 200)];
+        assert!(token.batch_transfer("creator".to_string(), airdrop).is_ok());
+        assert_eq!(token.balance_of("alice"), 100);
+        assert_eq!(token.balance_of("bob"), 200);
+
+        // Over the balance in total: nobody gets anything
+        let too_much = vec![("carol".to_string(), 600), ("dave".to_string(), 600)];
+        assert!(token.batch_transfer("creator".to_string(), too_much).is_err());
+        assert_eq!(token.balance_of("carol"), 0);
+        assert_eq!(token.balance_of("creator"), 700);
+    }
+
+    #[test]
+    fn test_transfer_history() {
+        let mut token = TokenContract::new(
+            "Test Token".to_string(),
+            "TEST".to_string(),
+            100000,
+            6,
+            "creator".to_string(),
+        ).unwrap();
+
+        token.set_block_context(7, "tx7".to_string());
+        for _ in 0..EVENT_PAGE_SIZE + 5 {
+            token.transfer("creator".to_string(), "alice".to_string(), 1).unwrap();
+        }
+        token.approve("alice".to_string(), "bob".to_string(), 10).unwrap();
+        token.set_block_context(8, "tx8".to_string());
+        token.transfer("alice".to_string(), "bob".to_string(), 2).unwrap();
+
+        let first = token.get_transfers("alice", 0);
+        assert_eq!(first.len(), EVENT_PAGE_SIZE);
+        assert_eq!(first[0].block_index, 8);
+        assert_eq!(first[0].to, "bob");
+        assert_eq!(first[1].transaction_hash, "tx7");
+        assert_eq!(token.get_transfers("alice", 1).len(), 6);
+        assert_eq!(token.get_transfers("bob", 0).len(), 1);
+        assert_eq!(token.get_approvals("bob", 0)[0].amount, 10);
+    }
+
     #[test]
     fn test_token_approval() {
         let mut token = TokenContract::new(
@@ -641,6 +966,57 @@ mod tests {
         assert_eq!(token.allowance("creator", "spender"), 500);
     }
 
+    #[test]
+    fn test_token_permit() {
+        let mut token = TokenContract::new(
+            "Test Token".to_string(),
+            "TEST".to_string(),
+            1000000,
+            6,
+            "creator".to_string(),
+        ).unwrap();
+        let deadline = Utc::now() + chrono::Duration::hours(1);
+
+        let signature = token.permit_message("creator", "spender", 700, deadline).sign("creator");
+        assert!(token.permit("creator".to_string(), "spender".to_string(), 800, deadline, &signature).is_err());
+        assert!(token.permit("creator".to_string(), "spender".to_string(), 700, deadline, &signature).is_ok());
+        assert_eq!(token.allowance("creator", "spender"), 700);
+
+        // The nonce moved on, so the same signature can't be replayed
+        assert!(token.permit("creator".to_string(), "spender".to_string(), 700, deadline, &signature).is_err());
+
+        let expired = Utc::now() - chrono::Duration::seconds(1);
+        let signature = token.permit_message("creator", "spender", 1, expired).sign("creator");
+        assert!(token.permit("creator".to_string(), "spender".to_string(), 1, expired, &signature).is_err());
+    }
+
+    #[test]
+    fn test_token_snapshots() {
+        let mut token = TokenContract::new_mintable(
+            "Gov Token".to_string(),
+            "GOV".to_string(),
+            1000,
+            None,
+            6,
+            "creator".to_string(),
+        ).unwrap();
+        assert!(token.balance_of_at("creator", 1).is_err());
+
+        let first = token.snapshot();
+        token.transfer("creator".to_string(), "alice".to_string(), 300).unwrap();
+        token.transfer("creator".to_string(), "alice".to_string(), 100).unwrap();
+        let second = token.snapshot();
+        token.mint("bob".to_string(), 500, "creator".to_string()).unwrap();
+
+        assert_eq!(token.balance_of_at("creator", first).unwrap(), 1000);
+        assert_eq!(token.balance_of_at("alice", first).unwrap(), 0);
+        assert_eq!(token.balance_of_at("alice", second).unwrap(), 400);
+        assert_eq!(token.balance_of_at("bob", second).unwrap(), 0);
+        assert_eq!(token.total_supply_at(first).unwrap(), 1000);
+        assert_eq!(token.total_supply_at(second).unwrap(), 1000);
+        assert_eq!(token.total_supply, 1500);
+    }
+
     #[test]
     fn test_mintable_token() {
         let mut token = TokenContract::new_mintable(
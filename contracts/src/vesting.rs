@@ -0,0 +1,265 @@
+use tribechain_core::{TribeResult, TribeError};
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc, Duration};
+use sha2::{Digest, Sha256};
+use crate::{ContractCall, vm::LogEntry};
+
+/// How an allocation unlocks over time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum VestingSchedule {
+    /// Unlocks evenly from `start` until `start + duration`
+    Linear { start: DateTime<Utc>, duration: Duration },
+    /// Nothing unlocks before `start + cliff`; then vests as if linear from `start`
+    Cliff { start: DateTime<Utc>, cliff: Duration, duration: Duration },
+}
+
+impl VestingSchedule {
+    pub fn start(&self) -> DateTime<Utc> {
+        match self {
+            VestingSchedule::Linear { start, .. } | VestingSchedule::Cliff { start, .. } => *start,
+        }
+    }
+
+    pub fn end(&self) -> DateTime<Utc> {
+        match self {
+            VestingSchedule::Linear { start, duration } | VestingSchedule::Cliff { start, duration, .. } => *start + *duration,
+        }
+    }
+
+    fn validate(&self) -> TribeResult<()> {
+        match self {
+            VestingSchedule::Linear { duration, .. } if *duration <= Duration::zero() => {
+                Err(TribeError::Contract("Vesting duration must be positive".to_string()))
+            }
+            VestingSchedule::Cliff { cliff, duration, .. } if *duration <= Duration::zero() || *cliff < Duration::zero() || cliff > duration => {
+                Err(TribeError::Contract("Cliff must be within a positive vesting duration".to_string()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Portion of `total` unlocked at `now`
+    pub fn vested(&self, total: u64, now: DateTime<Utc>) -> u64 {
+        if let VestingSchedule::Cliff { start, cliff, .. } = self {
+            if now < *start + *cliff {
+                return 0;
+            }
+        }
+        if now <= self.start() {
+            return 0;
+        }
+        if now >= self.end() {
+            return total;
+        }
+
+        let elapsed = (now - self.start()).num_seconds() as u128;
+        let duration = (self.end() - self.start()).num_seconds() as u128;
+        (total as u128 * elapsed / duration) as u64
+    }
+}
+
+/// Token allocation released to a beneficiary on a vesting schedule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VestingContract {
+    pub id: String,
+    pub token_id: String,
+    pub creator: String,
+    pub beneficiary: String,
+    pub total_amount: u64,
+    pub released: u64,
+    pub schedule: VestingSchedule,
+    pub events: Vec<LogEntry>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl VestingContract {
+    pub fn new(
+        token_id: String,
+        creator: String,
+        beneficiary: String,
+        total_amount: u64,
+        schedule: VestingSchedule,
+    ) -> TribeResult<Self> {
+        if token_id.is_empty() || beneficiary.is_empty() {
+            return Err(TribeError::Contract("Token ID and beneficiary cannot be empty".to_string()));
+        }
+        if total_amount == 0 {
+            return Err(TribeError::Contract("Vesting amount must be greater than zero".to_string()));
+        }
+        schedule.validate()?;
+
+        let created_at = Utc::now();
+        let id = Self::generate_vesting_id(&token_id, &beneficiary, created_at);
+        let mut contract = Self {
+            id,
+            token_id,
+            creator,
+            beneficiary,
+            total_amount,
+            released: 0,
+            schedule,
+            events: Vec::new(),
+            created_at,
+        };
+        contract.emit("vesting_created", total_amount, created_at);
+        Ok(contract)
+    }
+
+    fn generate_vesting_id(token_id: &str, beneficiary: &str, created_at: DateTime<Utc>) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(token_id.as_bytes());
+        hasher.update(beneficiary.as_bytes());
+        hasher.update(created_at.timestamp_nanos_opt().unwrap_or_default().to_le_bytes());
+        format!("vesting_{}", &hex::encode(hasher.finalize())[..16])
+    }
+
+    pub fn vested_amount(&self, now: DateTime<Utc>) -> u64 {
+        self.schedule.vested(self.total_amount, now)
+    }
+
+    /// Unlocked but not yet claimed
+    pub fn claimable(&self, now: DateTime<Utc>) -> u64 {
+        self.vested_amount(now).saturating_sub(self.released)
+    }
+
+    /// Release everything claimable to the beneficiary; returns the amount released
+    pub fn claim(&mut self, claimant: &str, now: DateTime<Utc>) -> TribeResult<u64> {
+        if claimant != self.beneficiary {
+            return Err(TribeError::Contract("Only the beneficiary can claim".to_string()));
+        }
+        let amount = self.claimable(now);
+        if amount == 0 {
+            return Err(TribeError::Contract("Nothing to claim yet".to_string()));
+        }
+
+        self.released += amount;
+        self.emit("tokens_released", amount, now);
+        Ok(amount)
+    }
+
+    pub fn is_fully_released(&self) -> bool {
+        self.released >= self.total_amount
+    }
+
+    fn emit(&mut self, event: &str, amount: u64, timestamp: DateTime<Utc>) {
+        self.events.push(LogEntry {
+            contract_address: self.id.clone(),
+            topics: vec![event.to_string(), self.beneficiary.clone()],
+            data: amount.to_le_bytes().to_vec(),
+            timestamp,
+        });
+    }
+}
+
+/// Contract call held back until `release_at`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Timelock {
+    pub id: String,
+    pub creator: String,
+    pub call: ContractCall,
+    pub release_at: DateTime<Utc>,
+    pub executed: bool,
+    pub cancelled: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Timelock {
+    pub fn new(creator: String, call: ContractCall, release_at: DateTime<Utc>) -> Self {
+        let created_at = Utc::now();
+        let mut hasher = Sha256::new();
+        hasher.update(creator.as_bytes());
+        hasher.update(call.contract_address.as_bytes());
+        hasher.update(call.method.as_bytes());
+        hasher.update(created_at.timestamp_nanos_opt().unwrap_or_default().to_le_bytes());
+
+        Self {
+            id: format!("timelock_{}", &hex::encode(hasher.finalize())[..16]),
+            creator,
+            call,
+            release_at,
+            executed: false,
+            cancelled: false,
+            created_at,
+        }
+    }
+
+    pub fn is_ready(&self, now: DateTime<Utc>) -> bool {
+        !self.executed && !self.cancelled && now >= self.release_at
+    }
+
+    /// Take the call for execution once the lock has expired
+    pub fn release(&mut self, now: DateTime<Utc>) -> TribeResult<ContractCall> {
+        if self.executed || self.cancelled {
+            return Err(TribeError::Contract("Timelock already settled".to_string()));
+        }
+        if now < self.release_at {
+            return Err(TribeError::Contract(format!("Timelock locked until {}", self.release_at)));
+        }
+        self.executed = true;
+        Ok(self.call.clone())
+    }
+
+    /// Creator can cancel while the call is still locked
+    pub fn cancel(&mut self, caller: &str) -> TribeResult<()> {
+        if caller != self.creator {
+            return Err(TribeError::Contract("Only the creator can cancel a timelock".to_string()));
+        }
+        if self.executed || self.cancelled {
+            return Err(TribeError::Contract("Timelock already settled".to_string()));
+        }
+        self.cancelled = true;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_linear_vesting() {
+        let start = Utc::now();
+        let schedule = VestingSchedule::Linear { start, duration: Duration::days(100) };
+        let mut vesting = VestingContract::new(
+            "tribe".to_string(), "team".to_string(), "alice".to_string(), 1000, schedule,
+        ).unwrap();
+
+        assert_eq!(vesting.claimable(start), 0);
+        assert_eq!(vesting.claimable(start + Duration::days(25)), 250);
+        assert!(vesting.claim("bob", start + Duration::days(25)).is_err());
+        assert_eq!(vesting.claim("alice", start + Duration::days(25)).unwrap(), 250);
+        assert_eq!(vesting.claimable(start + Duration::days(25)), 0);
+        assert_eq!(vesting.claim("alice", start + Duration::days(200)).unwrap(), 750);
+        assert!(vesting.is_fully_released());
+        assert_eq!(vesting.events.len(), 3);
+    }
+
+    #[test]
+    fn test_cliff_vesting() {
+        let start = Utc::now();
+        let schedule = VestingSchedule::Cliff { start, cliff: Duration::days(30), duration: Duration::days(120) };
+        let vesting = VestingContract::new(
+            "stomp".to_string(), "team".to_string(), "alice".to_string(), 1200, schedule,
+        ).unwrap();
+
+        assert_eq!(vesting.claimable(start + Duration::days(29)), 0);
+        assert_eq!(vesting.claimable(start + Duration::days(30)), 300);
+        assert_eq!(vesting.claimable(start + Duration::days(120)), 1200);
+
+        let bad = VestingSchedule::Cliff { start, cliff: Duration::days(200), duration: Duration::days(120) };
+        assert!(VestingContract::new("stomp".to_string(), "team".to_string(), "alice".to_string(), 1, bad).is_err());
+    }
+
+    #[test]
+    fn test_timelock() {
+        let now = Utc::now();
+        let call = ContractCall::new("contract1".to_string(), "upgrade".to_string(), vec![], "dao".to_string());
+        let mut timelock = Timelock::new("dao".to_string(), call, now + Duration::days(2));
+
+        assert!(!timelock.is_ready(now));
+        assert!(timelock.release(now).is_err());
+        assert!(timelock.cancel("mallory").is_err());
+        assert_eq!(timelock.release(now + Duration::days(2)).unwrap().method, "upgrade");
+        assert!(timelock.release(now + Duration::days(3)).is_err());
+    }
+}
@@ -1,8 +1,67 @@
-use tribechain_core::{TribeResult, TribeError};
+use tribechain_core::{TribeResult, TribeError, Log, Receipt};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::time::{Duration, Instant};
 
+/// Method name of the host function one contract uses to call another
+pub const CALL_CONTRACT_METHOD: &str = "call_contract";
+/// Gas charged to the caller for a cross-contract call, before forwarding
+pub const CALL_BASE_GAS: u64 = 700;
+
+/// Host function writing the calling contract's storage; takes bincode `Vec<(key, value)>`,
+/// where an empty value clears the slot
+pub const STORAGE_WRITE_METHOD: &str = "storage_write";
+/// Host function deleting the called contract and its storage, callable by its owner;
+/// takes an ABI-encoded beneficiary
+pub const SELF_DESTRUCT_METHOD: &str = "self_destruct";
+/// Gas for writing a previously empty storage slot
+pub const STORAGE_SET_GAS: u64 = 20000;
+/// Gas for overwriting or clearing a storage slot
+pub const STORAGE_UPDATE_GAS: u64 = 5000;
+/// Gas refunded for each storage slot cleared
+pub const STORAGE_CLEAR_REFUND: u64 = 4800;
+/// Refunds are capped at gas used divided by this
+pub const MAX_REFUND_QUOTIENT: u64 = 5;
+/// Gas charged for `self_destruct`
+pub const SELF_DESTRUCT_GAS: u64 = 5000;
+
+/// Host functions whose results differ between nodes; calls to them always fail
+pub const NONDETERMINISTIC_HOST_FUNCTIONS: &[&str] = &[
+    "current_time",
+    "random_bytes",
+    "env_var",
+    "read_file",
+    "http_request",
+    "spawn_thread",
+];
+/// Memory one top-level execution may allocate for arguments, frames and storage writes
+pub const DEFAULT_MEMORY_LIMIT: usize = 16 * 1024 * 1024;
+
+/// Proxy method that points the proxy at a new implementation; takes an ABI-encoded address
+pub const PROXY_UPGRADE_METHOD: &str = "upgrade_to";
+/// Proxy method that hands upgrade rights to a new admin; takes an ABI-encoded address
+pub const PROXY_CHANGE_ADMIN_METHOD: &str = "change_admin";
+
+/// Storage key holding a proxy's implementation address
+pub fn proxy_implementation_key(proxy: &str) -> String {
+    format!("contract:{}:implementation", proxy)
+}
+
+/// Prefix of every storage key belonging to `contract`
+pub fn contract_storage_prefix(contract: &str) -> String {
+    format!("contract:{}:", contract)
+}
+
+/// Storage key of a slot written through `storage_write`
+pub fn contract_slot_key(contract: &str, slot: &str) -> String {
+    format!("contract:{}:slot:{}", contract, slot)
+}
+
+/// Storage key holding the address allowed to upgrade a proxy
+pub fn proxy_admin_key(proxy: &str) -> String {
+    format!("contract:{}:admin", proxy)
+}
+
 /// Virtual machine for executing smart contracts
 #[derive(Debug)]
 pub struct ContractVM {
@@ -16,6 +75,12 @@ pub struct ContractVM {
     pub max_call_depth: usize,
     pub execution_timeout: Duration,
     pub stats: VMStats,
+    pub gas_refund: u64, // Refund earned by the current call; the capped refund once it returns
+    pub destructed: Vec<(String, String)>, // (contract, beneficiary) destroyed by the last call
+    pub memory_limit: usize,
+    pub memory_used: usize, // Allocated by the current execution
+    pub execution_started: Option<Instant>,
+    pub denied_host_functions: HashSet<String>,
 }
 
 /// VM execution state
@@ -90,6 +155,8 @@ pub struct VMStats {
     pub max_execution_time: Duration,
     pub contracts_deployed: u64,
     pub methods_called: HashMap<String, u64>,
+    #[serde(default)]
+    pub total_gas_refunded: u64,
 }
 
 impl ContractVM {
@@ -106,6 +173,12 @@ impl ContractVM {
             max_call_depth: 10,
             execution_timeout: Duration::from_secs(30),
             stats: VMStats::default(),
+            gas_refund: 0,
+            destructed: Vec::new(),
+            memory_limit: DEFAULT_MEMORY_LIMIT,
+            memory_used: 0,
+            execution_started: None,
+            denied_host_functions: NONDETERMINISTIC_HOST_FUNCTIONS.iter().map(|name| name.to_string()).collect(),
         }
     }
 
@@ -126,7 +199,7 @@ impl ContractVM {
             let constructor_result = self.execute_constructor(&contract_address, &deployment)?;
             if !constructor_result.success {
                 self.state = VMState::Error(constructor_result.error.unwrap_or_default());
-                return Err(TribeError::InvalidOperation("Constructor execution failed".to_string()));
+                return Err(TribeError::Contract("Constructor execution failed".to_string()));
             }
         }
 
@@ -149,10 +222,26 @@ impl ContractVM {
 
     /// Call a contract method
     pub fn call(&mut self, contract: &super::Contract, call: super::ContractCall) -> TribeResult<ExecutionResult> {
+        self.call_with_contracts(contract, call, &HashMap::new())
+    }
+
+    /// Call a contract method that may reach the other `contracts` through the
+    /// `call_contract` host function
+    pub fn call_with_contracts(
+        &mut self,
+        contract: &super::Contract,
+        call: super::ContractCall,
+        contracts: &HashMap<String, super::Contract>,
+    ) -> TribeResult<ExecutionResult> {
         let start_time = Instant::now();
         self.state = VMState::Running;
         self.gas_used = 0;
         self.call_depth = 0;
+        self.gas_refund = 0;
+        self.destructed.clear();
+        self.memory_used = 0;
+        self.execution_started = Some(start_time);
+        let storage_snapshot = self.storage.clone();
 
         // Set gas limit for this execution
         self.gas_limit = call.gas_limit.unwrap_or(1_000_000);
@@ -171,7 +260,28 @@ impl ContractVM {
         self.execution_stack.push(frame);
 
         // Execute the method
-        let result = self.execute_method(contract, &call);
+        let mut result = if self.allocate(call.args.len()) {
+            self.execute_method(contract, &call, contracts)
+        } else {
+            self.sandbox_failure(format!("Memory limit of {} bytes exceeded", self.memory_limit))
+        };
+        // Work can't be preempted, so an overrun is caught when the call returns
+        if result.success && start_time.elapsed() >= self.execution_timeout {
+            self.state = VMState::Timeout;
+            result = self.sandbox_failure(format!("Execution timed out after {:?}", self.execution_timeout));
+        }
+        self.execution_started = None;
+
+        // Failed calls keep no storage changes; successful ones get their refund, capped
+        if result.success {
+            self.gas_refund = self.gas_refund.min(result.gas_used / MAX_REFUND_QUOTIENT);
+            result.gas_used -= self.gas_refund;
+            self.stats.total_gas_refunded += self.gas_refund;
+        } else {
+            self.storage = storage_snapshot;
+            self.gas_refund = 0;
+            self.destructed.clear();
+        }
 
         // Calculate execution time
         let execution_time = start_time.elapsed();
@@ -197,12 +307,25 @@ impl ContractVM {
     }
 
     /// Execute a contract method
-    fn execute_method(&mut self, contract: &super::Contract, call: &super::ContractCall) -> ExecutionResult {
+    fn execute_method(
+        &mut self,
+        contract: &super::Contract,
+        call: &super::ContractCall,
+        contracts: &HashMap<String, super::Contract>,
+    ) -> ExecutionResult {
         let mut logs = Vec::new();
         let mut state_changes = HashMap::new();
 
-        // Check execution timeout
-        let start_time = Instant::now();
+        if let Some(error) = self.sandbox_violation(&call.method) {
+            return self.sandbox_failure(error);
+        }
+
+        match call.method.as_str() {
+            CALL_CONTRACT_METHOD => return self.execute_contract_call(call, contracts, &mut logs, &mut state_changes),
+            STORAGE_WRITE_METHOD => return self.execute_storage_write(call, &mut state_changes),
+            SELF_DESTRUCT_METHOD => return self.execute_self_destruct(contract, call, &mut logs, &mut state_changes),
+            _ => {}
+        }
 
         // Simulate method execution based on contract type
         match contract.contract_type {
@@ -218,9 +341,48 @@ impl ContractVM {
             super::ContractType::TensorCompute => {
                 self.execute_tensor_method(call, &mut logs, &mut state_changes)
             }
-            super::ContractType::Custom => {
+            super::ContractType::Multisig | super::ContractType::OrderBook | super::ContractType::Custom => {
                 self.execute_custom_method(call, &mut logs, &mut state_changes)
             }
+            super::ContractType::Proxy => {
+                self.execute_proxy_method(contract, call, contracts)
+            }
+        }
+    }
+
+    /// Run `call` with the code of the proxy's implementation. The call keeps the
+    /// proxy's address, so storage and logs belong to the proxy.
+    fn execute_proxy_method(
+        &mut self,
+        proxy: &super::Contract,
+        call: &super::ContractCall,
+        contracts: &HashMap<String, super::Contract>,
+    ) -> ExecutionResult {
+        let failure = |vm: &Self, error: String| ExecutionResult {
+            success: false,
+            return_data: Vec::new(),
+            gas_used: vm.gas_used,
+            error: Some(error),
+            logs: Vec::new(),
+            state_changes: HashMap::new(),
+            execution_time: Duration::from_millis(0),
+        };
+
+        if call.method == PROXY_UPGRADE_METHOD || call.method == PROXY_CHANGE_ADMIN_METHOD {
+            return failure(self, "Proxy admin methods are handled by the contract engine".to_string());
+        }
+        if !self.consume_gas(CALL_BASE_GAS) {
+            return failure(self, "Out of gas".to_string());
+        }
+        let implementation = self.storage.get(&proxy_implementation_key(&proxy.address))
+            .and_then(|address| String::from_utf8(address.clone()).ok())
+            .and_then(|address| contracts.get(&address));
+        match implementation {
+            Some(implementation) if implementation.contract_type != super::ContractType::Proxy => {
+                self.execute_method(implementation, call, contracts)
+            }
+            Some(_) => failure(self, "Proxy implementation cannot be a proxy".to_string()),
+            None => failure(self, format!("Proxy {} has no implementation", proxy.address)),
         }
     }
 
@@ -281,6 +443,7 @@ impl ContractVM {
         let gas_cost = match call.method.as_str() {
             "stake" => 50000,
             "unstake" => 40000,
+            "withdraw_unbonded" => 30000,
             "claim_rewards" => 30000,
             "delegate" => 35000,
             _ => 20000,
@@ -455,6 +618,274 @@ impl ContractVM {
         }
     }
 
+    /// Host function: run the bincode-encoded `ContractCall` in `call.args` as a nested call.
+    /// The callee gets at most 63/64 of the remaining gas, may not re-enter a contract already
+    /// on the call stack, and its storage changes are rolled back if it fails.
+    fn execute_contract_call(
+        &mut self,
+        call: &super::ContractCall,
+        contracts: &HashMap<String, super::Contract>,
+        logs: &mut Vec<LogEntry>,
+        state_changes: &mut HashMap<String, Vec<u8>>,
+    ) -> ExecutionResult {
+        let failure = |vm: &Self, error: String| ExecutionResult {
+            success: false,
+            return_data: Vec::new(),
+            gas_used: vm.gas_used,
+            error: Some(error),
+            logs: Vec::new(),
+            state_changes: HashMap::new(),
+            execution_time: Duration::from_millis(0),
+        };
+
+        if !self.consume_gas(CALL_BASE_GAS) {
+            return failure(self, "Out of gas".to_string());
+        }
+
+        let mut inner: super::ContractCall = match bincode::deserialize(&call.args) {
+            Ok(inner) => inner,
+            Err(e) => return failure(self, format!("Invalid call_contract arguments: {}", e)),
+        };
+        // The callee sees the calling contract as its caller
+        inner.caller = call.contract_address.clone();
+
+        // The bottom frame is the top-level call, so frames above it are nested calls
+        if self.execution_stack.len() > self.max_call_depth {
+            return failure(self, format!("Call depth limit of {} exceeded", self.max_call_depth));
+        }
+        if !self.allocate(inner.args.len()) {
+            return failure(self, format!("Memory limit of {} bytes exceeded", self.memory_limit));
+        }
+        if self.execution_stack.iter().any(|frame| frame.contract_address == inner.contract_address) {
+            return failure(self, format!("Reentrant call to {}", inner.contract_address));
+        }
+        let callee = match contracts.get(&inner.contract_address) {
+            Some(callee) => callee,
+            None => return failure(self, format!("Contract not found: {}", inner.contract_address)),
+        };
+
+        let remaining = self.gas_limit - self.gas_used;
+        let forwarded = (remaining - remaining / 64).min(inner.gas_limit.unwrap_or(u64::MAX));
+        let (caller_gas_limit, caller_gas_used) = (self.gas_limit, self.gas_used);
+        let (storage_snapshot, refund_snapshot, destructed_len) = (self.storage.clone(), self.gas_refund, self.destructed.len());
+
+        self.gas_limit = forwarded;
+        self.gas_used = 0;
+        self.call_depth += 1;
+        self.execution_stack.push(ExecutionFrame {
+            contract_address: inner.contract_address.clone(),
+            method: inner.method.clone(),
+            args: inner.args.clone(),
+            gas_limit: forwarded,
+            gas_used: 0,
+            local_storage: HashMap::new(),
+            return_data: None,
+        });
+
+        let result = self.execute_method(callee, &inner, contracts);
+
+        self.execution_stack.pop();
+        self.call_depth -= 1;
+        self.gas_limit = caller_gas_limit;
+        self.gas_used = caller_gas_used + result.gas_used;
+
+        if !result.success {
+            self.storage = storage_snapshot;
+            self.gas_refund = refund_snapshot;
+            self.destructed.truncate(destructed_len);
+            self.state = VMState::Running;
+            return failure(self, format!(
+                "Call to {} failed: {}", inner.contract_address, result.error.unwrap_or_default()
+            ));
+        }
+
+        // The callee already wrote its storage; just report the changes
+        state_changes.extend(result.state_changes);
+        logs.extend(result.logs);
+
+        ExecutionResult {
+            success: true,
+            return_data: result.return_data,
+            gas_used: self.gas_used,
+            error: None,
+            logs: logs.clone(),
+            state_changes: state_changes.clone(),
+            execution_time: result.execution_time,
+        }
+    }
+
+    /// Host function: write or clear slots of the calling contract's storage.
+    /// Clearing a slot that held a value earns `STORAGE_CLEAR_REFUND`.
+    fn execute_storage_write(
+        &mut self,
+        call: &super::ContractCall,
+        state_changes: &mut HashMap<String, Vec<u8>>,
+    ) -> ExecutionResult {
+        let failure = |vm: &Self, error: String| ExecutionResult {
+            success: false,
+            return_data: Vec::new(),
+            gas_used: vm.gas_used,
+            error: Some(error),
+            logs: Vec::new(),
+            state_changes: HashMap::new(),
+            execution_time: Duration::from_millis(0),
+        };
+
+        let writes: Vec<(String, Vec<u8>)> = match bincode::deserialize(&call.args) {
+            Ok(writes) => writes,
+            Err(e) => return failure(self, format!("Invalid storage_write arguments: {}", e)),
+        };
+        for (slot, value) in writes {
+            let key = contract_slot_key(&call.contract_address, &slot);
+            let exists = self.storage.contains_key(&key);
+            let gas = if exists || value.is_empty() { STORAGE_UPDATE_GAS } else { STORAGE_SET_GAS };
+            if !self.consume_gas(gas) {
+                return failure(self, "Out of gas".to_string());
+            }
+            if !self.allocate(key.len() + value.len()) {
+                return failure(self, format!("Memory limit of {} bytes exceeded", self.memory_limit));
+            }
+            if let Some(error) = self.sandbox_violation(STORAGE_WRITE_METHOD) {
+                return failure(self, error);
+            }
+
+            if value.is_empty() {
+                if self.storage.remove(&key).is_some() {
+                    self.gas_refund += STORAGE_CLEAR_REFUND;
+                }
+            } else {
+                self.storage.insert(key.clone(), value.clone());
+            }
+            state_changes.insert(key, value);
+        }
+
+        ExecutionResult {
+            success: true,
+            return_data: vec![1],
+            gas_used: self.gas_used,
+            error: None,
+            logs: Vec::new(),
+            state_changes: state_changes.clone(),
+            execution_time: Duration::from_millis(0),
+        }
+    }
+
+    /// Host function: free all of the calling contract's storage, refunding gas per slot,
+    /// and mark it destroyed so the engine can remove it and pay its balance to the beneficiary
+    fn execute_self_destruct(
+        &mut self,
+        contract: &super::Contract,
+        call: &super::ContractCall,
+        logs: &mut Vec<LogEntry>,
+        state_changes: &mut HashMap<String, Vec<u8>>,
+    ) -> ExecutionResult {
+        let failure = |vm: &Self, error: String| ExecutionResult {
+            success: false,
+            return_data: Vec::new(),
+            gas_used: vm.gas_used,
+            error: Some(error),
+            logs: Vec::new(),
+            state_changes: HashMap::new(),
+            execution_time: Duration::from_millis(0),
+        };
+
+        if !self.consume_gas(SELF_DESTRUCT_GAS) {
+            return failure(self, "Out of gas".to_string());
+        }
+        let beneficiary = match crate::abi::decode(&[super::contracts::ParameterType::Address], &call.args) {
+            Ok(mut values) => match values.pop() {
+                Some(crate::abi::AbiValue::Address(beneficiary)) => beneficiary,
+                _ => return failure(self, "Expected a beneficiary address".to_string()),
+            },
+            Err(e) => return failure(self, e.to_string()),
+        };
+        if call.caller != contract.state.owner {
+            return failure(self, "Only the contract owner can self-destruct it".to_string());
+        }
+        if beneficiary == call.contract_address {
+            return failure(self, "A contract cannot be its own beneficiary".to_string());
+        }
+
+        let prefix = contract_storage_prefix(&call.contract_address);
+        let freed: Vec<String> = self.storage.keys().filter(|key| key.starts_with(&prefix)).cloned().collect();
+        for key in freed {
+            self.storage.remove(&key);
+            self.gas_refund += STORAGE_CLEAR_REFUND;
+            state_changes.insert(key, Vec::new());
+        }
+        self.destructed.push((call.contract_address.clone(), beneficiary.clone()));
+
+        logs.push(LogEntry {
+            contract_address: call.contract_address.clone(),
+            topics: vec![SELF_DESTRUCT_METHOD.to_string(), beneficiary],
+            data: Vec::new(),
+            timestamp: chrono::Utc::now(),
+        });
+
+        ExecutionResult {
+            success: true,
+            return_data: vec![1],
+            gas_used: self.gas_used,
+            error: None,
+            logs: logs.clone(),
+            state_changes: state_changes.clone(),
+            execution_time: Duration::from_millis(0),
+        }
+    }
+
+    /// Why the current execution must stop before running `method`, if it must
+    fn sandbox_violation(&mut self, method: &str) -> Option<String> {
+        if self.denied_host_functions.contains(method) {
+            return Some(format!("Host function {} is not allowed", method));
+        }
+        if self.memory_used > self.memory_limit {
+            return Some(format!("Memory limit of {} bytes exceeded", self.memory_limit));
+        }
+        if self.execution_started.is_some_and(|started| started.elapsed() >= self.execution_timeout) {
+            self.state = VMState::Timeout;
+            return Some(format!("Execution timed out after {:?}", self.execution_timeout));
+        }
+        None
+    }
+
+    fn sandbox_failure(&self, error: String) -> ExecutionResult {
+        ExecutionResult {
+            success: false,
+            return_data: Vec::new(),
+            gas_used: self.gas_used,
+            error: Some(error),
+            logs: Vec::new(),
+            state_changes: HashMap::new(),
+            execution_time: Duration::from_millis(0),
+        }
+    }
+
+    /// Count `bytes` against the memory limit; false once it is exceeded
+    fn allocate(&mut self, bytes: usize) -> bool {
+        self.memory_used = self.memory_used.saturating_add(bytes);
+        self.memory_used <= self.memory_limit
+    }
+
+    /// Set the memory cap for each execution
+    pub fn set_memory_limit(&mut self, limit: usize) {
+        self.memory_limit = limit;
+    }
+
+    /// Bytes of storage held by `contract`, keys included
+    pub fn storage_bytes(&self, contract: &str) -> usize {
+        let prefix = contract_storage_prefix(contract);
+        self.storage.iter()
+            .filter(|(key, _)| key.starts_with(&prefix))
+            .map(|(key, value)| key.len() + value.len())
+            .sum()
+    }
+
+    /// Remove every storage slot of `contract`
+    pub fn clear_contract_storage(&mut self, contract: &str) {
+        let prefix = contract_storage_prefix(contract);
+        self.storage.retain(|key, _| !key.starts_with(&prefix));
+    }
+
     /// Execute constructor
     fn execute_constructor(
         &mut self,
@@ -498,12 +929,12 @@ impl ContractVM {
     fn validate_deployment(&self, deployment: &super::ContractDeployment) -> TribeResult<()> {
         // Check code size limits
         if deployment.code.len() > 1024 * 1024 { // 1MB limit
-            return Err(TribeError::InvalidOperation("Contract code too large".to_string()));
+            return Err(TribeError::Contract("Contract code too large".to_string()));
         }
 
         // Check for malicious patterns (simplified)
         if deployment.code.is_empty() {
-            return Err(TribeError::InvalidOperation("Empty contract code".to_string()));
+            return Err(TribeError::Contract("Empty contract code".to_string()));
         }
 
         Ok(())
@@ -556,6 +987,7 @@ impl ContractVM {
         self.gas_used = 0;
         self.execution_stack.clear();
         self.memory.clear();
+        self.memory_used = 0;
         self.call_depth = 0;
     }
 
@@ -585,6 +1017,28 @@ impl ContractVM {
     }
 }
 
+impl ExecutionResult {
+    /// Receipt kept with the block once the calling transaction is included
+    pub fn to_receipt(&self, tx_hash: &str) -> Receipt {
+        Receipt {
+            tx_hash: tx_hash.to_string(),
+            success: self.success,
+            gas_used: self.gas_used,
+            logs: self.logs.iter().map(LogEntry::to_log).collect(),
+        }
+    }
+}
+
+impl LogEntry {
+    pub fn to_log(&self) -> Log {
+        Log {
+            address: self.contract_address.clone(),
+            topics: self.topics.clone(),
+            data: self.data.clone(),
+        }
+    }
+}
+
 impl Default for ContractVM {
     fn default() -> Self {
         Self::new()
@@ -594,6 +1048,7 @@ impl Default for ContractVM {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{Contract, ContractCall, ContractType};
 
     #[test]
     fn test_vm_creation() {
@@ -631,6 +1086,82 @@ mod tests {
         assert_eq!(address1.len(), 40); // 20 bytes in hex
     }
 
+    fn custom_contracts(addresses: &[&str]) -> HashMap<String, Contract> {
+        addresses.iter()
+            .map(|address| {
+                let contract = Contract::new(address.to_string(), ContractType::Custom, vec![1, 2, 3], vec![], "deployer".to_string());
+                (address.to_string(), contract)
+            })
+            .collect()
+    }
+
+    /// `from` calling `inner` through the host function
+    fn nested_call(from: &str, inner: ContractCall) -> ContractCall {
+        ContractCall::new(
+            from.to_string(),
+            CALL_CONTRACT_METHOD.to_string(),
+            bincode::serialize(&inner).unwrap(),
+            "alice".to_string(),
+        )
+    }
+
+    fn ping(address: &str) -> ContractCall {
+        ContractCall::new(address.to_string(), "ping".to_string(), vec![], "alice".to_string())
+    }
+
+    #[test]
+    fn test_cross_contract_call() {
+        let contracts = custom_contracts(&["a", "b"]);
+        let mut vm = ContractVM::new();
+
+        let result = vm.call_with_contracts(&contracts["a"], nested_call("a", ping("b")), &contracts).unwrap();
+        assert!(result.success);
+        assert_eq!(result.logs[0].contract_address, "b");
+        assert_eq!(result.gas_used, CALL_BASE_GAS + 20000);
+
+        // b calling back into a is rejected and nothing from the failed call is kept
+        let reentrant = nested_call("a", nested_call("b", ping("a")));
+        let result = vm.call_with_contracts(&contracts["a"], reentrant, &contracts).unwrap();
+        assert!(!result.success);
+        assert!(result.error.unwrap().contains("Reentrant call to a"));
+        assert!(result.logs.is_empty());
+    }
+
+    #[test]
+    fn test_call_depth_limit() {
+        let contracts = custom_contracts(&["a", "b", "c"]);
+        let mut vm = ContractVM::new();
+        vm.max_call_depth = 1;
+
+        let chained = nested_call("a", nested_call("b", ping("c")));
+        let result = vm.call_with_contracts(&contracts["a"], chained, &contracts).unwrap();
+        assert!(!result.success);
+        assert!(result.error.unwrap().contains("Call depth limit"));
+    }
+
+    #[test]
+    fn test_sandbox_limits() {
+        let contracts = custom_contracts(&["a"]);
+        let mut vm = ContractVM::new();
+
+        let random = ContractCall::new("a".to_string(), "random_bytes".to_string(), vec![], "alice".to_string());
+        let result = vm.call(&contracts["a"], random).unwrap();
+        assert!(result.error.unwrap().contains("not allowed"));
+
+        // A write past the memory cap fails and leaves storage untouched
+        vm.set_memory_limit(64);
+        let writes = vec![("big".to_string(), vec![7u8; 100])];
+        let write = ContractCall::new("a".to_string(), STORAGE_WRITE_METHOD.to_string(), bincode::serialize(&writes).unwrap(), "alice".to_string());
+        let result = vm.call(&contracts["a"], write).unwrap();
+        assert!(result.error.unwrap().contains("Memory limit"));
+        assert!(vm.storage.is_empty());
+
+        vm.set_memory_limit(DEFAULT_MEMORY_LIMIT);
+        vm.set_execution_timeout(Duration::ZERO);
+        let result = vm.call(&contracts["a"], ping("a")).unwrap();
+        assert!(result.error.unwrap().contains("timed out"));
+    }
+
     #[test]
     fn test_vm_reset() {
         let mut vm = ContractVM::new();
@@ -0,0 +1,258 @@
+use crate::error::{TribeError, TribeResult};
+use crate::transaction::MAINNET_CHAIN_ID;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::str::FromStr;
+
+/// Human-readable part of main network addresses
+pub const MAINNET_HRP: &str = "trb";
+/// Human-readable part of testnet and devnet addresses, so they can't be mistaken for mainnet ones
+pub const TESTNET_HRP: &str = "ttrb";
+
+/// Bytes an address encodes
+pub const ADDRESS_LENGTH: usize = 20;
+
+const CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const SEPARATOR: char = '1';
+const CHECKSUM_LENGTH: usize = 6;
+const MAX_LENGTH: usize = 90;
+const GENERATORS: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+
+/// Address prefix of the network with `chain_id`
+pub fn hrp_for_chain(chain_id: u64) -> &'static str {
+    if chain_id == MAINNET_CHAIN_ID { MAINNET_HRP } else { TESTNET_HRP }
+}
+
+fn invalid(message: impl Into<String>) -> TribeError {
+    TribeError::Address(message.into())
+}
+
+fn polymod(values: &[u8]) -> u32 {
+    values.iter().fold(1u32, |checksum, value| {
+        let top = checksum >> 25;
+        let checksum = ((checksum & 0x1ff_ffff) << 5) ^ *value as u32;
+        GENERATORS.iter().enumerate()
+            .filter(|(bit, _)| (top >> bit) & 1 == 1)
+            .fold(checksum, |checksum, (_, generator)| checksum ^ generator)
+    })
+}
+
+fn hrp_expand(hrp: &str) -> Vec<u8> {
+    let bytes = hrp.as_bytes();
+    bytes.iter().map(|byte| byte >> 5)
+        .chain(std::iter::once(0))
+        .chain(bytes.iter().map(|byte| byte & 31))
+        .collect()
+}
+
+/// Regroup bits, e.g. bytes into the 5-bit groups bech32 characters carry
+fn convert_bits(data: &[u8], from: u32, to: u32, pad: bool) -> Option<Vec<u8>> {
+    let mut accumulator = 0u32;
+    let mut bits = 0u32;
+    let mut out = Vec::new();
+    let max = (1u32 << to) - 1;
+    for value in data {
+        accumulator = (accumulator << from) | *value as u32;
+        bits += from;
+        while bits >= to {
+            bits -= to;
+            out.push(((accumulator >> bits) & max) as u8);
+        }
+    }
+    if pad && bits > 0 {
+        out.push(((accumulator << (to - bits)) & max) as u8);
+    } else if !pad && (bits >= from || (accumulator << (to - bits)) & max != 0) {
+        return None; // Leftover bits that aren't zero padding
+    }
+    Some(out)
+}
+
+/// Account address: 20 bytes bech32-encoded under a network prefix, e.g. `trb1...`.
+/// The checksum catches typos before funds are sent to a mistyped address.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Address {
+    hrp: String,
+    payload: [u8; ADDRESS_LENGTH],
+}
+
+impl Address {
+    pub fn new(hrp: &str, payload: [u8; ADDRESS_LENGTH]) -> Self {
+        Self { hrp: hrp.to_lowercase(), payload }
+    }
+
+    /// Address of the account signing with `key`
+    pub fn from_key(hrp: &str, key: &str) -> Self {
+        Self::from_public_key(hrp, &crate::keys::public_key(key))
+    }
+
+    /// Address of the account whose hex public key is `public_key`
+    pub fn from_public_key(hrp: &str, public_key: &str) -> Self {
+        let digest = Sha256::digest(public_key.as_bytes());
+        let mut payload = [0u8; ADDRESS_LENGTH];
+        payload.copy_from_slice(&digest[..ADDRESS_LENGTH]);
+        Self::new(hrp, payload)
+    }
+
+    /// Address with the 20 bytes given as hex
+    pub fn from_hex(hrp: &str, hex_payload: &str) -> TribeResult<Self> {
+        let bytes = hex::decode(hex_payload.trim_start_matches("0x")).map_err(|e| invalid(format!("bad hex: {}", e)))?;
+        let payload: [u8; ADDRESS_LENGTH] = bytes.try_into()
+            .map_err(|_| invalid(format!("expected {} bytes of hex", ADDRESS_LENGTH)))?;
+        Ok(Self::new(hrp, payload))
+    }
+
+    /// Decode an address of any network, checking its checksum
+    pub fn parse(address: &str) -> TribeResult<Self> {
+        if address.len() > MAX_LENGTH {
+            return Err(invalid("too long"));
+        }
+        if address.chars().any(|c| c.is_ascii_uppercase()) && address.chars().any(|c| c.is_ascii_lowercase()) {
+            return Err(invalid("mixes upper and lower case"));
+        }
+        let address = address.to_lowercase();
+        let (hrp, data) = address.rsplit_once(SEPARATOR).ok_or_else(|| invalid("no separator"))?;
+        if hrp.is_empty() || data.len() < CHECKSUM_LENGTH {
+            return Err(invalid("too short"));
+        }
+        let values = data.bytes()
+            .map(|c| CHARSET.iter().position(|&valid| valid == c).map(|value| value as u8))
+            .collect::<Option<Vec<u8>>>()
+            .ok_or_else(|| invalid("invalid character"))?;
+
+        let mut checked = hrp_expand(hrp);
+        checked.extend_from_slice(&values);
+        if polymod(&checked) != 1 {
+            return Err(invalid("checksum mismatch"));
+        }
+        let bytes = convert_bits(&values[..values.len() - CHECKSUM_LENGTH], 5, 8, false)
+            .ok_or_else(|| invalid("invalid padding"))?;
+        let payload: [u8; ADDRESS_LENGTH] = bytes.try_into()
+            .map_err(|_| invalid(format!("expected {} bytes", ADDRESS_LENGTH)))?;
+        Ok(Self::new(hrp, payload))
+    }
+
+    /// Decode an address, which must belong to the network with `chain_id`
+    pub fn parse_for_chain(address: &str, chain_id: u64) -> TribeResult<Self> {
+        let parsed = Self::parse(address)?;
+        let expected = hrp_for_chain(chain_id);
+        if parsed.hrp != expected {
+            return Err(invalid(format!("{} addresses belong to another network; expected {}", parsed.hrp, expected)));
+        }
+        Ok(parsed)
+    }
+
+    pub fn hrp(&self) -> &str {
+        &self.hrp
+    }
+
+    pub fn payload(&self) -> &[u8; ADDRESS_LENGTH] {
+        &self.payload
+    }
+
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.payload)
+    }
+
+    /// The same account under another network's prefix
+    pub fn with_hrp(&self, hrp: &str) -> Self {
+        Self::new(hrp, self.payload)
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut values = convert_bits(&self.payload, 8, 5, true).unwrap_or_default();
+        let mut checked = hrp_expand(&self.hrp);
+        checked.extend_from_slice(&values);
+        checked.extend_from_slice(&[0; CHECKSUM_LENGTH]);
+        let checksum = polymod(&checked) ^ 1;
+        values.extend((0..CHECKSUM_LENGTH).map(|i| ((checksum >> (5 * (5 - i))) & 31) as u8));
+
+        let data: String = values.iter().map(|value| CHARSET[*value as usize] as char).collect();
+        write!(f, "{}{}{}", self.hrp, SEPARATOR, data)
+    }
+}
+
+impl FromStr for Address {
+    type Err = TribeError;
+
+    fn from_str(address: &str) -> TribeResult<Self> {
+        Self::parse(address)
+    }
+}
+
+/// Whether `address` is written as a TribeChain address rather than a legacy account
+/// name such as a genesis allocation's
+pub fn is_bech32_form(address: &str) -> bool {
+    let address = address.to_lowercase();
+    [MAINNET_HRP, TESTNET_HRP].iter().any(|hrp| {
+        address.strip_prefix(hrp).is_some_and(|rest| rest.starts_with(SEPARATOR))
+    })
+}
+
+/// Check an address given as input for queries: anything in address form must be a
+/// valid address of `chain_id`'s network, while legacy account names pass as they are
+pub fn check_address_input(address: &str, chain_id: u64) -> TribeResult<()> {
+    if is_bech32_form(address) {
+        Address::parse_for_chain(address, chain_id)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transaction::{DEVNET_CHAIN_ID, TESTNET_CHAIN_ID};
+
+    const PAYLOAD: &str = "751e76e8199196d454941c45d1b3a323f1433bd6";
+
+    #[test]
+    fn test_encoding_matches_reference_bech32() {
+        // Same payload as BIP-173's P2WPKH example, without its witness version
+        let address = Address::from_hex(MAINNET_HRP, PAYLOAD).unwrap();
+        assert_eq!(address.to_string(), "trb1w508d6qejxtdg4y5r3zarvary0c5xw7ks2t3sk");
+        assert_eq!(address.with_hrp(TESTNET_HRP).to_string(), "ttrb1w508d6qejxtdg4y5r3zarvary0c5xw7k7lz4s8");
+
+        let parsed: Address = "TRB1W508D6QEJXTDG4Y5R3ZARVARY0C5XW7KS2T3SK".parse().unwrap();
+        assert_eq!((parsed.hrp(), parsed.to_hex()), (MAINNET_HRP, PAYLOAD.to_string()));
+
+        let derived = Address::from_key(MAINNET_HRP, "alice-key");
+        assert_eq!(derived, Address::from_public_key(MAINNET_HRP, &crate::keys::public_key("alice-key")));
+        assert_eq!(Address::parse(&derived.to_string()).unwrap(), derived);
+        assert_ne!(derived, Address::from_key(MAINNET_HRP, "bob-key"));
+    }
+
+    #[test]
+    fn test_mistyped_addresses_are_rejected() {
+        let address = Address::from_key(MAINNET_HRP, "alice-key").to_string();
+        let mut typo = address.clone().into_bytes();
+        let last = typo.len() - 1;
+        typo[last] = if typo[last] == b'q' { b'p' } else { b'q' };
+        let typo = String::from_utf8(typo).unwrap();
+
+        for bad in [typo.as_str(), "trb1w508d6qejxtdg4y5r3zarvary0c5xw7ks2t3sK", "trb1w508d6qejxtdg4y5r3zarvary0c5xw7kb2t3sk", "trbw508d6qejxtdg4y5", "trb1qqqqq"] {
+            assert!(matches!(Address::parse(bad), Err(TribeError::Address(_))), "{}", bad);
+        }
+        assert!(Address::parse(&format!("trb1{}", "q".repeat(MAX_LENGTH))).is_err());
+        assert!(Address::from_hex(MAINNET_HRP, "abcd").is_err());
+    }
+
+    #[test]
+    fn test_addresses_belong_to_one_network() {
+        assert_eq!(hrp_for_chain(MAINNET_CHAIN_ID), MAINNET_HRP);
+        assert_eq!((hrp_for_chain(TESTNET_CHAIN_ID), hrp_for_chain(DEVNET_CHAIN_ID)), (TESTNET_HRP, TESTNET_HRP));
+
+        let mainnet = Address::from_key(MAINNET_HRP, "alice-key").to_string();
+        let testnet = Address::from_key(TESTNET_HRP, "alice-key").to_string();
+        assert!(Address::parse_for_chain(&mainnet, MAINNET_CHAIN_ID).is_ok());
+        assert!(matches!(Address::parse_for_chain(&mainnet, TESTNET_CHAIN_ID), Err(TribeError::Address(_))));
+        assert!(matches!(Address::parse_for_chain(&testnet, MAINNET_CHAIN_ID), Err(TribeError::Address(_))));
+
+        // Legacy account names pass; anything written as an address must be this network's
+        assert!(is_bech32_form(&testnet) && !is_bech32_form("alice"));
+        assert!(check_address_input("alice", MAINNET_CHAIN_ID).is_ok());
+        assert!(check_address_input(&testnet, TESTNET_CHAIN_ID).is_ok());
+        assert!(check_address_input(&testnet, MAINNET_CHAIN_ID).is_err());
+        assert!(check_address_input("trb1notanaddress", MAINNET_CHAIN_ID).is_err());
+    }
+}
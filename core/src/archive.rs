@@ -0,0 +1,257 @@
+use sha2::{Digest, Sha256};
+use std::io::{Read, Write};
+use crate::codec::Versioned;
+use crate::{Block, TribeChain, TribeResult, TribeError};
+
+/// Chain archive layout:
+/// magic | format version | chain ID | first index | last index,
+/// then per block: length (u32 BE) | versioned block | SHA-256 of the block bytes,
+/// then a zero length and the number of blocks written, so truncation is detected
+pub const ARCHIVE_MAGIC: &[u8; 4] = b"TRBX";
+pub const ARCHIVE_VERSION: u8 = 1;
+
+/// Largest encoded block an archive may hold
+const MAX_ARCHIVED_BLOCK: u32 = 64 * 1024 * 1024;
+
+/// Blocks validated together when importing
+const IMPORT_BATCH: usize = 256;
+
+/// What an archive says it holds
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveHeader {
+    pub chain_id: u64,
+    pub from: u64,
+    pub to: u64,
+}
+
+/// Outcome of `TribeChain::import_archive`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ArchiveImport {
+    pub imported: u64,
+    pub skipped: u64, // Already on our chain
+}
+
+fn io_error(action: &str, e: std::io::Error) -> TribeError {
+    TribeError::Storage(format!("Failed to {} chain archive: {}", action, e))
+}
+
+fn corrupt(message: impl Into<String>) -> TribeError {
+    TribeError::Storage(format!("Corrupt chain archive: {}", message.into()))
+}
+
+fn read_array<const N: usize>(input: &mut impl Read) -> TribeResult<[u8; N]> {
+    let mut buf = [0u8; N];
+    input.read_exact(&mut buf).map_err(|e| io_error("read", e))?;
+    Ok(buf)
+}
+
+/// Write blocks `from..=to` of `chain` to `out`; returns how many were written
+pub fn export_blocks<W: Write>(chain: &TribeChain, from: u64, to: u64, mut out: W) -> TribeResult<u64> {
+    if from > to || to as usize >= chain.blocks.len() {
+        return Err(TribeError::Blockchain(format!(
+            "Cannot export blocks {}..={} from a chain of {}", from, to, chain.blocks.len()
+        )));
+    }
+
+    let mut header = ARCHIVE_MAGIC.to_vec();
+    header.push(ARCHIVE_VERSION);
+    for value in [chain.chain_id, from, to] {
+        header.extend_from_slice(&value.to_be_bytes());
+    }
+    out.write_all(&header).map_err(|e| io_error("write", e))?;
+
+    for block in &chain.blocks[from as usize..=to as usize] {
+        let data = block.encode_versioned()?;
+        let len = u32::try_from(data.len()).ok().filter(|len| *len <= MAX_ARCHIVED_BLOCK)
+            .ok_or_else(|| TribeError::Storage(format!("Block {} is too large to archive", block.index)))?;
+        out.write_all(&len.to_be_bytes()).map_err(|e| io_error("write", e))?;
+        out.write_all(&data).map_err(|e| io_error("write", e))?;
+        out.write_all(&Sha256::digest(&data)).map_err(|e| io_error("write", e))?;
+    }
+
+    let count = to - from + 1;
+    out.write_all(&0u32.to_be_bytes()).map_err(|e| io_error("write", e))?;
+    out.write_all(&count.to_be_bytes()).map_err(|e| io_error("write", e))?;
+    out.flush().map_err(|e| io_error("write", e))?;
+    Ok(count)
+}
+
+/// Streams blocks out of an archive, checking each one's digest as it is read
+pub struct ArchiveReader<R: Read> {
+    input: R,
+    pub header: ArchiveHeader,
+    read: u64,
+    finished: bool,
+}
+
+impl<R: Read> ArchiveReader<R> {
+    pub fn new(mut input: R) -> TribeResult<Self> {
+        if &read_array::<4>(&mut input)? != ARCHIVE_MAGIC {
+            return Err(corrupt("not a chain archive"));
+        }
+        let [version] = read_array::<1>(&mut input)?;
+        if version != ARCHIVE_VERSION {
+            return Err(corrupt(format!("unsupported archive version {}", version)));
+        }
+        let chain_id = u64::from_be_bytes(read_array(&mut input)?);
+        let from = u64::from_be_bytes(read_array(&mut input)?);
+        let to = u64::from_be_bytes(read_array(&mut input)?);
+        if from > to {
+            return Err(corrupt(format!("block range {}..={} is empty", from, to)));
+        }
+        Ok(Self { input, header: ArchiveHeader { chain_id, from, to }, read: 0, finished: false })
+    }
+
+    fn next_block(&mut self) -> TribeResult<Option<Block>> {
+        let len = u32::from_be_bytes(read_array(&mut self.input)?);
+        if len == 0 {
+            let count = u64::from_be_bytes(read_array(&mut self.input)?);
+            if count != self.read || count != self.header.to - self.header.from + 1 {
+                return Err(corrupt(format!("holds {} blocks, trailer says {}", self.read, count)));
+            }
+            return Ok(None);
+        }
+        if len > MAX_ARCHIVED_BLOCK {
+            return Err(corrupt(format!("block record of {} bytes", len)));
+        }
+
+        let mut data = vec![0u8; len as usize];
+        self.input.read_exact(&mut data).map_err(|e| io_error("read", e))?;
+        let digest = read_array::<32>(&mut self.input)?;
+        let expected = self.header.from + self.read;
+        if digest[..] != Sha256::digest(&data)[..] {
+            return Err(corrupt(format!("checksum mismatch in block {}", expected)));
+        }
+        let block = Block::decode_versioned(&data)?;
+        if block.index != expected {
+            return Err(corrupt(format!("expected block {}, found {}", expected, block.index)));
+        }
+        self.read += 1;
+        Ok(Some(block))
+    }
+}
+
+impl<R: Read> Iterator for ArchiveReader<R> {
+    type Item = TribeResult<Block>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let next = self.next_block().transpose();
+        if !matches!(next, Some(Ok(_))) {
+            self.finished = true;
+        }
+        next
+    }
+}
+
+impl TribeChain {
+    /// Write blocks `from..=to` to `out` in the archive format
+    pub fn export_archive<W: Write>(&self, from: u64, to: u64, out: W) -> TribeResult<u64> {
+        export_blocks(self, from, to, out)
+    }
+
+    /// Import an archive exported from this chain. Blocks we already have must match
+    /// ours and are skipped; the rest are validated and applied in batches. Batches
+    /// read before a corrupt or invalid block stay imported.
+    pub fn import_archive<R: Read>(&mut self, input: R) -> TribeResult<ArchiveImport> {
+        let reader = ArchiveReader::new(input)?;
+        if reader.header.chain_id != self.chain_id {
+            return Err(TribeError::Blockchain(format!(
+                "Archive is for chain ID {}, this chain is {}", reader.header.chain_id, self.chain_id
+            )));
+        }
+        if reader.header.from > self.blocks.len() as u64 {
+            return Err(TribeError::Blockchain(format!(
+                "Archive starts at block {} but this chain ends at {}", reader.header.from, self.blocks.len() - 1
+            )));
+        }
+
+        let mut summary = ArchiveImport::default();
+        let mut batch = Vec::with_capacity(IMPORT_BATCH);
+        for block in reader {
+            let block = block?;
+            if let Some(ours) = self.blocks.get(block.index as usize) {
+                if ours.hash != block.hash {
+                    return Err(TribeError::InvalidBlock(format!(
+                        "Archive block {} differs from ours; it is from another fork", block.index
+                    )));
+                }
+                summary.skipped += 1;
+                continue;
+            }
+            batch.push(block);
+            if batch.len() == IMPORT_BATCH {
+                summary.imported += self.import_blocks(std::mem::take(&mut batch))? as u64;
+            }
+        }
+        summary.imported += self.import_blocks(batch)? as u64;
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{chain_of, dev_chain};
+
+    fn exported(chain: &TribeChain) -> Vec<u8> {
+        let mut archive = Vec::new();
+        chain.export_archive(0, chain.blocks.len() as u64 - 1, &mut archive).unwrap();
+        archive
+    }
+
+    #[test]
+    fn test_archive_round_trip() {
+        let source = chain_of(3, "miner");
+        let archive = exported(&source);
+
+        let mut chain = dev_chain();
+        let summary = chain.import_archive(&archive[..]).unwrap();
+        assert_eq!(summary, ArchiveImport { imported: 3, skipped: 1 });
+        assert_eq!(chain.blocks.last().unwrap().hash, source.blocks.last().unwrap().hash);
+
+        // Importing again changes nothing
+        let summary = chain.import_archive(&archive[..]).unwrap();
+        assert_eq!(summary, ArchiveImport { imported: 0, skipped: 4 });
+
+        // A partial export carries just its range
+        let mut partial = Vec::new();
+        assert_eq!(source.export_archive(2, 3, &mut partial).unwrap(), 2);
+        let reader = ArchiveReader::new(&partial[..]).unwrap();
+        assert_eq!(reader.header, ArchiveHeader { chain_id: source.chain_id, from: 2, to: 3 });
+        let indexes: Vec<u64> = reader.map(|block| block.unwrap().index).collect();
+        assert_eq!(indexes, vec![2, 3]);
+        assert!(source.export_archive(3, 4, &mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_damaged_archives_are_rejected() {
+        let archive = exported(&chain_of(2, "miner"));
+        let import = |archive: &[u8]| dev_chain().import_archive(archive);
+
+        let mut magic = archive.clone();
+        magic[0] = b'X';
+        assert!(matches!(import(&magic), Err(TribeError::Storage(_))));
+
+        // Chain ID field of the header
+        let mut other_chain = archive.clone();
+        other_chain[5..13].copy_from_slice(&u64::MAX.to_be_bytes());
+        assert!(matches!(import(&other_chain), Err(TribeError::Blockchain(_))));
+
+        // First byte of the genesis block record, past the header and its length
+        let mut flipped = archive.clone();
+        flipped[29 + 4] ^= 0xFF;
+        let error = import(&flipped).unwrap_err().to_string();
+        assert!(error.contains("checksum mismatch in block 0"), "{}", error);
+
+        let truncated = &archive[..archive.len() - 8];
+        assert!(matches!(import(truncated), Err(TribeError::Storage(_))));
+
+        // Archives of another fork don't overwrite our blocks
+        let mut ours = chain_of(1, "miner");
+        let error = ours.import_archive(&exported(&chain_of(1, "rival"))[..]).unwrap_err();
+        assert!(matches!(error, TribeError::InvalidBlock(_)), "{}", error);
+    }
+}
@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use chrono::Utc;
-use crate::{Transaction, TribeResult};
+use crate::{keys, LogsBloom, Transaction, TribeResult, BLOCK_GAS_LIMIT};
 
 /// Block structure for TribeChain
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,6 +16,35 @@ pub struct Block {
     pub miner: String,
     pub merkle_root: String,
     pub ai3_proof: Option<AI3Proof>,
+    #[serde(default)]
+    pub validator_signature: Option<String>, // Proposer's signature over `hash` in stake-based consensus
+    #[serde(default = "default_gas_limit")]
+    pub gas_limit: u64,
+    #[serde(default)]
+    pub state_root: String, // Balances after this block; empty only on blocks from before state roots
+}
+
+fn default_gas_limit() -> u64 {
+    BLOCK_GAS_LIMIT
+}
+
+/// A block without its transactions, for lookups that don't need the body
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockHeader {
+    pub index: u64,
+    pub timestamp: u64,
+    pub previous_hash: String,
+    pub hash: String,
+    pub nonce: u64,
+    pub difficulty: u64,
+    pub miner: String,
+    pub merkle_root: String,
+    pub gas_limit: u64,
+    pub transaction_count: usize,
+    #[serde(default)]
+    pub state_root: String,
+    #[serde(default)]
+    pub bloom: Option<LogsBloom>, // None on headers stored before blooms, which match every query
 }
 
 /// AI3 Proof structure for tensor mining
@@ -26,6 +55,193 @@ pub struct AI3Proof {
     pub tensor_hash: String,
     pub computation_time: u64,
     pub miner_signature: String,
+    #[serde(default)]
+    pub commitment_nonce: u64, // Nonce the result was committed with, revealed here
+}
+
+/// Which proof found a block under merged mining
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MinedBy {
+    Hash, // The block hash meets the difficulty
+    Tensor, // The AI3 proof's commitment carries at least as much work
+}
+
+/// Expected hashes to find one with `difficulty` leading zero hex digits
+pub fn difficulty_work(difficulty: u64) -> f64 {
+    16f64.powi(difficulty.min(i32::MAX as u64) as i32)
+}
+
+/// Work a hex hash demonstrates through its leading zero digits
+pub fn hash_work(hash: &str) -> f64 {
+    difficulty_work(hash.chars().take_while(|&c| c == '0').count() as u64)
+}
+
+/// Hash the tensor path's work is measured on: a commitment bound to the parent and
+/// transactions of the block revealing it, so the same proof can't be replayed on
+/// another block
+pub fn tensor_work_hash(commitment_hash: &str, previous_hash: &str, merkle_root: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(commitment_hash.as_bytes());
+    hasher.update(previous_hash.as_bytes());
+    hasher.update(merkle_root.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+impl AI3Proof {
+    /// Hash of the commitment this proof reveals for `miner`
+    pub fn commitment_hash(&self, miner: &str) -> String {
+        AI3Commitment::compute_hash(&self.task_id, miner, &self.tensor_hash, self.commitment_nonce)
+    }
+
+    /// Work the proof carries toward finding a block mined by `miner` on top of
+    /// `previous_hash` with transactions `merkle_root`
+    pub fn work(&self, miner: &str, previous_hash: &str, merkle_root: &str) -> f64 {
+        hash_work(&tensor_work_hash(&self.commitment_hash(miner), previous_hash, merkle_root))
+    }
+}
+
+/// Hash commitment to an AI3 result, published at least one block before the reveal
+/// so the output cannot be copied from gossip.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AI3Commitment {
+    pub task_id: String,
+    pub miner: String,
+    pub commitment_hash: String,
+    pub block_height: u64,
+}
+
+impl AI3Commitment {
+    pub fn new(task_id: String, miner: String, tensor_hash: &str, nonce: u64, block_height: u64) -> Self {
+        let commitment_hash = Self::compute_hash(&task_id, &miner, tensor_hash, nonce);
+        Self {
+            task_id,
+            miner,
+            commitment_hash,
+            block_height,
+        }
+    }
+
+    /// Commitment binds the miner so a copied commitment cannot be revealed by someone else
+    pub fn compute_hash(task_id: &str, miner: &str, tensor_hash: &str, nonce: u64) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(task_id.as_bytes());
+        hasher.update(miner.as_bytes());
+        hasher.update(tensor_hash.as_bytes());
+        hasher.update(nonce.to_le_bytes());
+        hex::encode(hasher.finalize())
+    }
+
+    /// Check a revealed tensor hash and nonce against this commitment
+    pub fn matches(&self, tensor_hash: &str, nonce: u64) -> bool {
+        Self::compute_hash(&self.task_id, &self.miner, tensor_hash, nonce) == self.commitment_hash
+    }
+}
+
+/// Two conflicting blocks signed by the same validator at the same height
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DoubleSignEvidence {
+    pub validator: String,
+    pub block_height: u64,
+    pub first_hash: String,
+    pub first_signature: String,
+    pub second_hash: String,
+    pub second_signature: String,
+}
+
+impl DoubleSignEvidence {
+    /// Evidence from two blocks, if they really are a double sign
+    pub fn from_blocks(first: &Block, second: &Block) -> Option<Self> {
+        if first.miner != second.miner || first.index != second.index || first.hash == second.hash {
+            return None;
+        }
+
+        Some(Self {
+            validator: first.miner.clone(),
+            block_height: first.index,
+            first_hash: first.hash.clone(),
+            first_signature: first.validator_signature.clone()?,
+            second_hash: second.hash.clone(),
+            second_signature: second.validator_signature.clone()?,
+        })
+    }
+
+    /// Stable identifier so the same evidence is only applied once
+    pub fn id(&self) -> String {
+        let (a, b) = if self.first_hash < self.second_hash {
+            (&self.first_hash, &self.second_hash)
+        } else {
+            (&self.second_hash, &self.first_hash)
+        };
+        let mut hasher = Sha256::new();
+        hasher.update(self.validator.as_bytes());
+        hasher.update(self.block_height.to_le_bytes());
+        hasher.update(a.as_bytes());
+        hasher.update(b.as_bytes());
+        hex::encode(hasher.finalize())
+    }
+
+    /// Check both signatures against the validator's key
+    pub fn verify(&self, public_key: &str) -> bool {
+        self.first_hash != self.second_hash
+            && keys::verify(self.first_hash.as_bytes(), &self.first_signature, public_key)
+            && keys::verify(self.second_hash.as_bytes(), &self.second_signature, public_key)
+    }
+}
+
+/// A reporting validator's signed record of another validator's block production over one epoch
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ValidatorPerformanceReport {
+    pub validator: String,
+    pub epoch: u64,
+    pub assigned_slots: u64,
+    pub produced_blocks: u64,
+    pub reporter: String,
+    pub signature: String,
+}
+
+impl ValidatorPerformanceReport {
+    pub fn new(validator: String, epoch: u64, assigned_slots: u64, produced_blocks: u64, reporter: String) -> Self {
+        Self {
+            validator,
+            epoch,
+            assigned_slots,
+            produced_blocks: produced_blocks.min(assigned_slots),
+            reporter,
+            signature: String::new(),
+        }
+    }
+
+    pub fn missed_blocks(&self) -> u64 {
+        self.assigned_slots - self.produced_blocks
+    }
+
+    /// Share of assigned slots produced, 1.0 when nothing was assigned
+    pub fn uptime(&self) -> f64 {
+        if self.assigned_slots == 0 {
+            return 1.0;
+        }
+        self.produced_blocks as f64 / self.assigned_slots as f64
+    }
+
+    /// Hash of the report contents, which is what the reporter signs
+    pub fn id(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.validator.as_bytes());
+        hasher.update(self.epoch.to_le_bytes());
+        hasher.update(self.assigned_slots.to_le_bytes());
+        hasher.update(self.produced_blocks.to_le_bytes());
+        hasher.update(self.reporter.as_bytes());
+        hex::encode(hasher.finalize())
+    }
+
+    pub fn sign(&mut self, private_key: &str) {
+        self.signature = keys::sign(self.id().as_bytes(), private_key);
+    }
+
+    /// Check the signature against the reporter's ed25519 public key
+    pub fn verify(&self, public_key: &str) -> bool {
+        keys::verify(self.id().as_bytes(), &self.signature, public_key)
+    }
 }
 
 impl Block {
@@ -50,6 +266,9 @@ impl Block {
             miner,
             merkle_root,
             ai3_proof: None,
+            validator_signature: None,
+            gas_limit: BLOCK_GAS_LIMIT,
+            state_root: String::new(),
         }
     }
 
@@ -66,15 +285,49 @@ impl Block {
             miner: "genesis".to_string(),
             merkle_root: "0".repeat(64),
             ai3_proof: None,
+            validator_signature: None,
+            gas_limit: BLOCK_GAS_LIMIT,
+            state_root: String::new(),
         };
         
         genesis.hash = genesis.calculate_hash();
         genesis
     }
 
+    pub fn header(&self) -> BlockHeader {
+        BlockHeader {
+            index: self.index,
+            timestamp: self.timestamp,
+            previous_hash: self.previous_hash.clone(),
+            hash: self.hash.clone(),
+            nonce: self.nonce,
+            difficulty: self.difficulty,
+            miner: self.miner.clone(),
+            merkle_root: self.merkle_root.clone(),
+            gas_limit: self.gas_limit,
+            transaction_count: self.transactions.len(),
+            state_root: self.state_root.clone(),
+            bloom: Some(self.address_bloom()),
+        }
+    }
+
+    /// Bloom filter over every address the block's transactions touch
+    pub fn address_bloom(&self) -> LogsBloom {
+        let mut bloom = LogsBloom::new();
+        for address in self.transactions.iter().flat_map(Transaction::involved_addresses) {
+            bloom.accrue(&address);
+        }
+        bloom
+    }
+
+    /// Gas consumed by the block's transactions
+    pub fn gas_used(&self) -> u64 {
+        self.transactions.iter().map(Transaction::gas).sum()
+    }
+
     /// Calculate block hash
     pub fn calculate_hash(&self) -> String {
-        let data = format!(
+        let mut data = format!(
             "{}{}{}{}{}{}{}{}",
             self.index,
             self.timestamp,
@@ -85,6 +338,8 @@ impl Block {
             self.merkle_root,
             serde_json::to_string(&self.ai3_proof).unwrap_or_default()
         );
+        // Only committed when set, so blocks from before state roots keep their hashes
+        data.push_str(&self.state_root);
         
         let mut hasher = Sha256::new();
         hasher.update(data.as_bytes());
@@ -109,7 +364,7 @@ impl Block {
             self.nonce += 1;
             
             // Prevent infinite loop in case of very high difficulty
-            if self.nonce % 100000 == 0 {
+            if self.nonce.is_multiple_of(100000) {
                 println!("Mining progress: nonce = {}", self.nonce);
             }
         }
@@ -117,18 +372,51 @@ impl Block {
         Ok(())
     }
 
-    /// Mine block with AI3 proof
+    /// Mine block with AI3 proof; no nonce search is needed if the proof alone carries
+    /// enough work
     pub fn mine_with_ai3_proof(&mut self, difficulty: u64, ai3_proof: AI3Proof) -> TribeResult<()> {
         self.ai3_proof = Some(ai3_proof);
+        self.difficulty = difficulty;
+        self.hash = self.calculate_hash();
+        if self.mined_by(difficulty).is_some() {
+            return Ok(());
+        }
         self.mine_block(difficulty)
     }
 
+    /// Sign the block hash as its proposing validator
+    pub fn sign_as_validator(&mut self, private_key: &str) {
+        self.validator_signature = Some(keys::sign(self.hash.as_bytes(), private_key));
+    }
+
+    /// Verify the proposing validator's signature against its ed25519 public key
+    pub fn verify_validator_signature(&self, public_key: &str) -> bool {
+        self.validator_signature.as_deref()
+            .is_some_and(|signature| keys::verify(self.hash.as_bytes(), signature, public_key))
+    }
+
+    /// Check the merkle root commits to exactly this block's transactions
+    pub fn has_valid_merkle_root(&self) -> bool {
+        self.merkle_root == Self::calculate_merkle_root(&self.transactions)
+    }
+
     /// Validate block hash
     pub fn is_valid_hash(&self, difficulty: u64) -> bool {
         let target = "0".repeat(difficulty as usize);
         self.hash.starts_with(&target) && self.hash == self.calculate_hash()
     }
 
+    /// How the block meets `difficulty`: by its hash, or by the work of its AI3 proof
+    /// bound to this block's header
+    pub fn mined_by(&self, difficulty: u64) -> Option<MinedBy> {
+        if self.hash.starts_with(&"0".repeat(difficulty as usize)) {
+            return Some(MinedBy::Hash);
+        }
+        self.ai3_proof.as_ref()
+            .filter(|proof| proof.work(&self.miner, &self.previous_hash, &self.merkle_root) >= difficulty_work(difficulty))
+            .map(|_| MinedBy::Tensor)
+    }
+
     /// Calculate merkle root of transactions
     fn calculate_merkle_root(transactions: &[Transaction]) -> String {
         if transactions.is_empty() {
@@ -189,11 +477,103 @@ impl Block {
             return Ok(false);
         }
 
-        // Validate difficulty
-        if !self.is_valid_hash(self.difficulty) {
+        // Validate difficulty, met by either the hash or the tensor proof
+        if self.mined_by(self.difficulty).is_none() {
+            return Ok(false);
+        }
+
+        // Check gas limit
+        if self.gas_limit > BLOCK_GAS_LIMIT || self.gas_used() > self.gas_limit {
             return Ok(false);
         }
 
         Ok(true)
     }
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proof(commitment_nonce: u64) -> AI3Proof {
+        AI3Proof {
+            task_id: "task".to_string(),
+            optimization_factor: 1.0,
+            tensor_hash: "tensor".to_string(),
+            computation_time: 0,
+            miner_signature: String::new(),
+            commitment_nonce,
+        }
+    }
+
+    /// Block on `parent` whose AI3 proof alone meets `difficulty`
+    fn tensor_mined(parent: &str, difficulty: u64) -> Block {
+        let mut block = Block::new(1, parent.to_string(), Vec::new(), "miner".to_string());
+        block.difficulty = difficulty;
+        block.hash = "f".repeat(64);
+        for nonce in 0.. {
+            block.ai3_proof = Some(proof(nonce));
+            if block.mined_by(difficulty) == Some(MinedBy::Tensor) {
+                break;
+            }
+        }
+        block
+    }
+
+    #[test]
+    fn test_tensor_work_is_bound_to_the_header() {
+        let block = tensor_mined(&"a".repeat(64), 2);
+        let ai3_proof = block.ai3_proof.clone().unwrap();
+        assert!(ai3_proof.work(&block.miner, &block.previous_hash, &block.merkle_root) >= difficulty_work(2));
+
+        // The same proof replayed on another parent carries no useful work
+        let mut replayed = block.clone();
+        replayed.previous_hash = "b".repeat(64);
+        assert_eq!(replayed.mined_by(2), None);
+
+        // Nor on the same parent with different transactions
+        let mut replayed = block.clone();
+        replayed.merkle_root = "c".repeat(64);
+        assert_eq!(replayed.mined_by(2), None);
+
+        // Or revealed by another miner
+        let mut replayed = block;
+        replayed.miner = "mallory".to_string();
+        assert_eq!(replayed.mined_by(2), None);
+    }
+
+    #[test]
+    fn test_double_sign_evidence_needs_the_validators_signatures() {
+        let public_key = keys::public_key("alice-key");
+        let signed = |transactions: Vec<Transaction>, private_key: &str| {
+            let mut block = Block::new(1, "0".repeat(64), transactions, "alice".to_string());
+            block.hash = block.calculate_hash();
+            block.sign_as_validator(private_key);
+            block
+        };
+        let first = signed(Vec::new(), "alice-key");
+        assert!(first.verify_validator_signature(&public_key));
+        assert!(!first.verify_validator_signature(&keys::public_key("mallory-key")));
+
+        let transfer = Transaction::new("alice".to_string(), crate::TransactionType::Transfer { to: "bob".to_string(), amount: 1 }, 0, 0);
+        let second = signed(vec![transfer.clone()], "alice-key");
+        let evidence = DoubleSignEvidence::from_blocks(&first, &second).unwrap();
+        assert!(evidence.verify(&public_key));
+
+        // Blocks someone else signed in alice's name don't prove she double signed
+        let framed = DoubleSignEvidence::from_blocks(&first, &signed(vec![transfer], "mallory-key")).unwrap();
+        assert!(!framed.verify(&public_key));
+    }
+
+    #[test]
+    fn test_performance_reports_need_the_reporters_signature() {
+        let mut report = ValidatorPerformanceReport::new("bob".to_string(), 3, 10, 4, "alice".to_string());
+        report.sign("alice-key");
+        assert!(report.verify(&keys::public_key("alice-key")));
+        assert!(!report.verify(&keys::public_key("mallory-key")));
+
+        // Signatures don't carry over to altered figures
+        let mut inflated = report.clone();
+        inflated.produced_blocks = 10;
+        assert!(!inflated.verify(&keys::public_key("alice-key")));
+    }
+}
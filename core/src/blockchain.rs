@@ -1,6 +1,11 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use crate::{Block, Transaction, TransactionType, Storage, TribeResult, TribeError, AI3Proof};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use sha2::{Digest, Sha256};
+use crate::fees::{self, FEE_HISTORY_BLOCKS};
+use crate::validation;
+use crate::codec;
+use crate::{Block, BlockHeader, MinedBy, difficulty_work, Transaction, TransactionType, Storage, StorageConfig, ImportIntent, open_storage, AddressIndex, AddressTxRef, TribeResult, TribeError, AI3Proof, AI3Commitment, Attestation, FinalityGadget, BLOCK_GAS_LIMIT, BlockReceipts, LogFilter, LogsBloom, MatchedLog, Receipt, StateTrie, StateProof, ChainSpec, MissingParent, OrphanTransactions};
 
 /// Miner information
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -29,19 +34,79 @@ pub struct TensorTask {
     pub created_at: u64,
 }
 
-/// Main TribeChain blockchain structure
+impl TensorTask {
+    /// Hash binding the task's id, requester, operation and inputs
+    pub fn task_hash(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.id.as_bytes());
+        hasher.update(self.requester.as_bytes());
+        hasher.update(self.operation.as_bytes());
+        for value in &self.input_data {
+            hasher.update(value.to_le_bytes());
+        }
+        hasher.update((self.expected_output_size as u64).to_le_bytes());
+        hex::encode(hasher.finalize())
+    }
+
+    /// Tensor hash an AI3 proof carries for `result` of this task
+    pub fn result_hash(&self, result: &[f32]) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.task_hash().as_bytes());
+        for value in result {
+            hasher.update(value.to_le_bytes());
+        }
+        hex::encode(hasher.finalize())
+    }
+}
+
+/// Main TribeChain blockchain structure. Its snapshot is versioned like blocks; a
+/// field added here needs a new `CHAIN_ENCODING_VERSION` and a legacy layout
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TribeChain {
+    #[serde(with = "codec::versioned_vec")]
     pub blocks: Vec<Block>,
+    #[serde(with = "codec::versioned_vec")]
     pub pending_transactions: Vec<Transaction>,
+    #[serde(skip)]
+    pub orphan_transactions: OrphanTransactions, // Waiting on a parent; peers re-announce them after a restart
     pub difficulty: u64,
-    pub mining_reward: u64,
-    pub balances: HashMap<String, u64>,
+    pub mining_reward: u64, // Reward for the next block, kept in step with the spec's emission schedule
+    pub balances: StateTrie, // Committed to by each block's `state_root`
     #[serde(skip)]
-    pub storage: Option<Storage>,
+    pub storage: Option<Arc<dyn Storage>>,
     pub tensor_tasks: Vec<TensorTask>,
     pub active_miners: HashMap<String, MinerInfo>,
     pub ai3_difficulty_multiplier: f32,
+    pub ai3_commitments: HashMap<String, AI3Commitment>, // "task_id:miner" -> commitment
+    pub ai3_reveal_delay: u64, // Blocks between commitment and reveal
+    pub delegate_votes: HashMap<String, Vec<String>>, // voter -> approved delegates
+    pub finality: FinalityGadget,
+    pub chain_id: u64, // Transactions for other chains are rejected
+    #[serde(skip)]
+    pub address_index: AddressIndex, // Rebuilt from blocks on load
+    #[serde(skip)]
+    pub blooms: Vec<LogsBloom>, // Parallel to `blocks`: touched addresses and log topics; rebuilt on load
+    pub receipts: BTreeMap<u64, BlockReceipts>, // block index -> contract execution receipts
+    pub account_nonces: HashMap<String, u64>, // sender -> next nonce it may use
+    pub chain_spec: ChainSpec, // Parameters the chain was created with
+}
+
+/// Transactions returned per page by `get_transactions_for_address`
+pub const ADDRESS_TX_PAGE_SIZE: usize = 50;
+
+/// Most blocks `get_difficulty_history` returns per call
+pub const DIFFICULTY_HISTORY_LIMIT: u64 = 10_000;
+
+pub(crate) fn default_ai3_reveal_delay() -> u64 {
+    1
+}
+
+/// What `TribeChain::repair` did with an interrupted block import
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RepairOutcome {
+    Clean, // No import was interrupted
+    Replayed { index: u64 }, // The stored block was applied to the chain state
+    RolledBack { index: u64 }, // The partly written block was removed
 }
 
 /// Blockchain statistics
@@ -50,6 +115,8 @@ pub struct BlockchainStats {
     pub block_count: u64,
     pub transaction_count: u64,
     pub pending_transactions: u64,
+    #[serde(default)]
+    pub orphan_transactions: u64,
     pub difficulty: u64,
     pub mining_reward: u64,
     pub total_supply: u64,
@@ -58,31 +125,109 @@ pub struct BlockchainStats {
     pub active_miners: u64,
     pub tensor_tasks: u64,
     pub ai3_difficulty_multiplier: f32,
+    pub finalized_height: u64,
+}
+
+/// Next block assembled by the node for an external miner, which only has to find a
+/// nonce meeting `difficulty` and hand the block back to `submit_block`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockTemplate {
+    pub block: Block, // Transactions ordered by fee and state root filled in; nonce not searched yet
+    pub difficulty: u64, // Leading zero hex digits the block hash, or the AI3 proof's commitment, needs
+    pub reward: u64, // Credited to the miner, AI3 bonus included
+    pub ai3_commitments: Vec<AI3Commitment>, // The miner's commitments that may be revealed in this block
+    #[serde(default)]
+    pub mined_by: Option<MinedBy>, // Set when the block is valid as it is, usually because its AI3 proof carries enough work
+}
+
+/// Recent mining activity, estimated from the difficulty and timing of the last blocks
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MiningStatus {
+    pub window_blocks: u64,
+    pub avg_block_time: f64, // Seconds
+    pub hash_rate: f64, // Hashes per second the difficulty implies at that block time
+    pub ai3_proofs: u64, // Blocks in the window carrying an AI3 proof
+    pub ai3_tasks_per_hour: f64,
+    pub pending_tasks: u64,
+    pub completed_tasks: u64,
+}
+
+/// Difficulty and timing of one block, for charting network health
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DifficultySample {
+    pub index: u64,
+    pub timestamp: u64,
+    pub difficulty: u64,
+    pub block_time: u64, // Seconds since the previous block, 0 for genesis
 }
 
 impl TribeChain {
-    /// Create a new TribeChain
+    /// Open the chain at `storage_path`, creating a mainnet chain if there is none
     pub fn new(storage_path: &str) -> TribeResult<Self> {
-        let storage = Storage::new(storage_path)?;
+        Self::with_storage_config(storage_path, &StorageConfig::default())
+    }
+
+    /// Open the chain at `storage_path` with tuned storage, creating a mainnet chain if there is none
+    pub fn with_storage_config(storage_path: &str, storage_config: &StorageConfig) -> TribeResult<Self> {
+        Self::open(storage_path, storage_config, None)
+    }
+
+    /// Open the chain at `storage_path`, creating it from `spec` if there is none. An
+    /// existing chain must have been created from the same spec.
+    pub fn with_chain_spec(storage_path: &str, storage_config: &StorageConfig, spec: &ChainSpec) -> TribeResult<Self> {
+        Self::open(storage_path, storage_config, Some(spec))
+    }
+
+    fn open(storage_path: &str, storage_config: &StorageConfig, spec: Option<&ChainSpec>) -> TribeResult<Self> {
+        let storage = open_storage(storage_path, storage_config)?;
+        if let Some(intent) = storage.pending_import()? {
+            return Err(TribeError::Storage(format!(
+                "Import of block {} was interrupted; run `tribechain repair`", intent.index
+            )));
+        }
         
-        // Try to load existing blockchain
-        match storage.load_blockchain() {
-            Ok(mut blockchain) => {
+        // Load the existing blockchain; one that fails to decode is an error, never replaced
+        match storage.has_blockchain()? {
+            true => {
+                let mut blockchain = storage.load_blockchain()?;
+                if let Some(spec) = spec {
+                    let genesis_hash = blockchain.blocks.first().map(|block| block.hash.clone()).unwrap_or_default();
+                    if blockchain.chain_id != spec.chain_id || genesis_hash != spec.genesis_block().hash {
+                        return Err(TribeError::Blockchain(format!(
+                            "{} holds a different chain than the {} spec", storage_path, spec.name
+                        )));
+                    }
+                }
                 blockchain.storage = Some(storage);
+                blockchain.rebuild_indices();
                 Ok(blockchain)
             }
-            Err(_) => {
+            false => {
+                let spec = spec.cloned().unwrap_or_else(ChainSpec::mainnet);
+                spec.validate()?;
+
                 // Create new blockchain with genesis block
                 let mut blockchain = TribeChain {
                     blocks: Vec::new(),
                     pending_transactions: Vec::new(),
-                    difficulty: 4, // Starting difficulty
-                    mining_reward: 50_000_000, // 50 TRIBE tokens (with 6 decimals)
-                    balances: HashMap::new(),
+                    orphan_transactions: OrphanTransactions::default(),
+                    difficulty: spec.difficulty.initial,
+                    mining_reward: spec.reward_at(1),
+                    balances: StateTrie::new(),
                     storage: Some(storage),
                     tensor_tasks: Vec::new(),
                     active_miners: HashMap::new(),
-                    ai3_difficulty_multiplier: 1.5, // AI3 mining is 50% more difficult
+                    ai3_difficulty_multiplier: spec.difficulty.ai3_multiplier,
+                    ai3_commitments: HashMap::new(),
+                    ai3_reveal_delay: default_ai3_reveal_delay(),
+                    delegate_votes: HashMap::new(),
+                    finality: FinalityGadget::default(),
+                    chain_id: spec.chain_id,
+                    address_index: AddressIndex::default(),
+                    blooms: Vec::new(),
+                    receipts: BTreeMap::new(),
+                    account_nonces: HashMap::new(),
+                    chain_spec: spec,
                 };
                 
                 // Create genesis block
@@ -94,11 +239,12 @@ impl TribeChain {
 
     /// Create the genesis block
     fn create_genesis_block(&mut self) -> TribeResult<()> {
-        let genesis_block = Block::genesis();
+        let genesis_block = self.chain_spec.genesis_block();
+        self.blooms.push(genesis_block.address_bloom());
         self.blocks.push(genesis_block);
         
         // Initialize genesis balances
-        self.balances.insert("genesis".to_string(), 1_000_000_000_000); // 1M TRIBE tokens
+        self.balances = self.chain_spec.genesis_state();
         
         // Save to storage
         if let Some(storage) = &self.storage {
@@ -108,12 +254,31 @@ impl TribeChain {
         Ok(())
     }
 
-    /// Add a new transaction to the pending pool
+    /// Add a new transaction to the pending pool. One whose nonce is past the sender's
+    /// queued transactions is held as an orphan until the transactions in between arrive.
     pub fn add_transaction(&mut self, transaction: Transaction) -> TribeResult<()> {
+        self.queue_transaction(transaction)?;
+        self.promote_orphans(|_| false);
+        Ok(())
+    }
+
+    fn queue_transaction(&mut self, transaction: Transaction) -> TribeResult<()> {
+        if self.pending_transactions.iter().any(|pending| pending.hash == transaction.hash) {
+            return Err(TribeError::Mempool(format!("Transaction {} is already pending", transaction.hash)));
+        }
+        let next = self.get_pending_nonce(&transaction.from);
+        if transaction.nonce > next {
+            let missing = MissingParent::Nonce { from: transaction.from.clone(), nonce: transaction.nonce - 1 };
+            return self.hold_orphan(transaction, missing);
+        }
+
         // Validate transaction
         if !self.validate_transaction(&transaction)? {
             return Err(TribeError::InvalidTransaction("Transaction validation failed".to_string()));
         }
+        // Only admission checks recipients; blocks from before addresses were checked still import
+        transaction.validate_recipients()?;
+        self.orphan_transactions.remove(&transaction.hash);
         
         // Add to pending transactions
         self.pending_transactions.push(transaction.clone());
@@ -126,51 +291,118 @@ impl TribeChain {
         Ok(())
     }
 
+    /// Hold a transaction until `missing` arrives. Checks that don't depend on the
+    /// parent are made now; the rest when it is promoted.
+    pub fn hold_orphan(&mut self, transaction: Transaction, missing: MissingParent) -> TribeResult<()> {
+        if self.pending_transactions.iter().any(|pending| pending.hash == transaction.hash) {
+            return Err(TribeError::Mempool(format!("Transaction {} is already pending", transaction.hash)));
+        }
+        if !transaction.validate()?
+            || !transaction.verify_signature()
+            || transaction.chain_id != self.chain_id
+            || transaction.nonce < self.get_account_nonce(&transaction.from)
+        {
+            return Err(TribeError::InvalidTransaction("Transaction validation failed".to_string()));
+        }
+        transaction.validate_recipients()?;
+        self.orphan_transactions.insert(transaction, missing)
+    }
+
+    /// Move orphans whose parent has arrived into the pending pool, returning their hashes.
+    /// Nonce parents are resolved from the chain; tokens and contracts are resolved by
+    /// `exists`, since they live outside it. Orphans that no longer validate are dropped.
+    pub fn promote_orphans(&mut self, exists: impl Fn(&MissingParent) -> bool) -> Vec<String> {
+        let mut promoted = Vec::new();
+        loop {
+            let resolved: Vec<String> = self.orphan_transactions.iter()
+                .filter(|orphan| match &orphan.missing {
+                    MissingParent::Nonce { from, nonce } => self.get_pending_nonce(from) > *nonce,
+                    parent => exists(parent),
+                })
+                .map(|orphan| orphan.transaction.hash.clone())
+                .collect();
+            if resolved.is_empty() {
+                return promoted;
+            }
+
+            let mut ready: Vec<Transaction> = resolved.iter()
+                .filter_map(|hash| self.orphan_transactions.remove(hash))
+                .map(|orphan| orphan.transaction)
+                .collect();
+            ready.sort_by_key(|transaction| transaction.nonce);
+            for transaction in ready {
+                let hash = transaction.hash.clone();
+                // A resolved orphan may still be ahead of its sender's nonces, and waits again
+                if self.queue_transaction(transaction).is_ok() && !self.orphan_transactions.contains(&hash) {
+                    promoted.push(hash);
+                }
+            }
+        }
+    }
+
     /// Validate a transaction
     fn validate_transaction(&self, transaction: &Transaction) -> TribeResult<bool> {
         // Basic validation
-        if !transaction.validate()? {
+        if !transaction.validate()? || !transaction.verify_signature() {
+            return Ok(false);
+        }
+
+        // Reject transactions signed for another network
+        if transaction.chain_id != self.chain_id {
+            return Ok(false);
+        }
+
+        // Replay protection: a nonce can only be used once per sender
+        if transaction.nonce < self.get_account_nonce(&transaction.from) {
+            return Ok(false);
+        }
+        if self.pending_transactions.iter().any(|tx| tx.from == transaction.from && tx.nonce == transaction.nonce) {
             return Ok(false);
         }
 
-        // Check if sender has sufficient balance
-        let sender_balance = self.balances.get(&transaction.from).unwrap_or(&0);
+        // Check if sender has sufficient balance, not counting immature mining rewards
+        let sender_balance = self.get_spendable_balance(&transaction.from);
         
         match &transaction.transaction_type {
             TransactionType::Transfer { amount, .. } => {
-                if sender_balance < &(amount + transaction.fee) {
+                if sender_balance < *amount + transaction.fee {
                     return Ok(false);
                 }
             }
             TransactionType::TokenCreate { .. } => {
                 // Token creation requires minimum balance
-                if *sender_balance < 1_000_000 + transaction.fee { // 1 TRIBE token + fee
+                if sender_balance < 1_000_000 + transaction.fee { // 1 TRIBE token + fee
                     return Ok(false);
                 }
             }
             TransactionType::TokenTransfer { amount, .. } => {
-                if sender_balance < &(amount + transaction.fee) {
+                if sender_balance < *amount + transaction.fee {
                     return Ok(false);
                 }
             }
             TransactionType::Stake { amount, .. } => {
-                if sender_balance < &(amount + transaction.fee) {
+                if sender_balance < *amount + transaction.fee {
                     return Ok(false);
                 }
             }
             TransactionType::TensorCompute { reward, .. } => {
                 // Tensor compute requires balance for reward + fee
-                if sender_balance < &(reward + transaction.fee) {
+                if sender_balance < *reward + transaction.fee {
                     return Ok(false);
                 }
             }
             TransactionType::ContractDeploy { .. } => {
-                if *sender_balance < transaction.fee {
+                if sender_balance < transaction.fee {
                     return Ok(false);
                 }
             }
             TransactionType::ContractCall { value, .. } => {
-                if sender_balance < &(value + transaction.fee) {
+                if sender_balance < *value + transaction.fee {
+                    return Ok(false);
+                }
+            }
+            TransactionType::DelegateVote { .. } => {
+                if sender_balance < transaction.fee {
                     return Ok(false);
                 }
             }
@@ -185,6 +417,24 @@ impl TribeChain {
             return Err(TribeError::Mining("No pending transactions to mine".to_string()));
         }
         
+        // Mine the template (find valid nonce), then connect it like any submitted block
+        let mut block = self.get_block_template(&miner_address, None)?.block;
+        block.mine_block(block.difficulty)?;
+        self.submit_block(block.clone())?;
+        
+        Ok(block)
+    }
+
+    /// Assemble the next block for `miner`: ready transactions ordered by fee, the state
+    /// root after its reward and, with `ai3_proof`, the AI3 bonus and difficulty. Only
+    /// valid on top of the current tip.
+    pub fn get_block_template(&self, miner: &str, ai3_proof: Option<AI3Proof>) -> TribeResult<BlockTemplate> {
+        if let Some(proof) = &ai3_proof {
+            if !self.validate_ai3_proof(proof, miner)? {
+                return Err(TribeError::AI3("Invalid AI3 proof".to_string()));
+            }
+        }
+
         // Get previous block hash
         let previous_hash = if let Some(last_block) = self.blocks.last() {
             last_block.hash.clone()
@@ -192,33 +442,104 @@ impl TribeChain {
             "0".repeat(64)
         };
         
-        // Create new block
+        // Create new block from the best-paying transactions that fit
+        let transactions = fees::select_transactions(&self.ready_transactions(), BLOCK_GAS_LIMIT);
         let mut block = Block::new(
             self.blocks.len() as u64,
             previous_hash,
-            self.pending_transactions.clone(),
-            miner_address.clone(),
+            transactions,
+            miner.to_string(),
         );
-        
-        // Mine the block (find valid nonce)
-        block.mine_block(self.difficulty)?;
-        
-        // Add block to chain
-        self.add_block(block.clone())?;
-        
-        // Reward miner
-        let current_balance = self.balances.get(&miner_address).unwrap_or(&0);
-        self.balances.insert(miner_address, current_balance + self.mining_reward);
-        
-        // Clear pending transactions
-        self.pending_transactions.clear();
-        
-        // Save to storage
-        if let Some(storage) = &self.storage {
-            storage.save_blockchain(self)?;
+        block.ai3_proof = ai3_proof;
+        block.difficulty = self.required_difficulty(&block);
+        block.state_root = self.compute_state_root(&block)?;
+        block.hash = block.calculate_hash();
+
+        let height = self.blocks.len() as u64;
+        let mut ai3_commitments: Vec<AI3Commitment> = self.ai3_commitments.values()
+            .filter(|commitment| commitment.miner == miner && height >= commitment.block_height + self.ai3_reveal_delay)
+            .cloned()
+            .collect();
+        ai3_commitments.sort_by(|a, b| a.task_id.cmp(&b.task_id));
+
+        Ok(BlockTemplate {
+            difficulty: block.difficulty,
+            reward: self.block_reward(&block)?,
+            mined_by: block.mined_by(block.difficulty),
+            block,
+            ai3_commitments,
+        })
+    }
+
+    /// Difficulty a block must be mined at; AI3-adjusted if it carries a proof
+    fn required_difficulty(&self, block: &Block) -> u64 {
+        match block.ai3_proof {
+            Some(_) => (self.difficulty as f32 * self.ai3_difficulty_multiplier) as u64,
+            None => self.difficulty,
         }
-        
-        Ok(block)
+    }
+
+    /// Connect a block mined from a template. It must extend the tip at the required
+    /// difficulty with a valid AI3 proof, if it carries one.
+    pub fn submit_block(&mut self, block: Block) -> TribeResult<()> {
+        if self.blocks.last().is_some_and(|tip| tip.hash != block.previous_hash) {
+            return Err(TribeError::InvalidBlock("Block does not extend the current tip; fetch a new template".to_string()));
+        }
+        if let Some(proof) = &block.ai3_proof {
+            if !self.validate_ai3_proof(proof, &block.miner)? {
+                return Err(TribeError::AI3("Invalid AI3 proof".to_string()));
+            }
+        }
+
+        // Add block to chain, crediting the miner's reward and completing its task
+        self.add_block(block)
+    }
+
+    fn remove_included(&mut self, block: &Block) {
+        let included: Vec<&str> = block.transactions.iter().map(|tx| tx.hash.as_str()).collect();
+        self.pending_transactions.retain(|tx| !included.contains(&tx.hash.as_str()));
+    }
+
+    /// Pending transactions whose nonces continue their sender's sequence without gaps
+    fn ready_transactions(&self) -> Vec<Transaction> {
+        let mut pending: Vec<&Transaction> = self.pending_transactions.iter().collect();
+        pending.sort_by_key(|tx| tx.nonce);
+
+        let mut next_nonces: HashMap<&str, u64> = HashMap::new();
+        let mut ready = Vec::new();
+        for tx in pending {
+            let next = next_nonces.entry(tx.from.as_str()).or_insert_with(|| self.get_account_nonce(&tx.from));
+            if tx.nonce == *next {
+                *next += 1;
+                ready.push(tx.clone());
+            }
+        }
+        ready
+    }
+
+    /// Whether the next block would include any pending transaction
+    pub fn has_ready_transactions(&self) -> bool {
+        !self.ready_transactions().is_empty()
+    }
+
+    /// Next nonce `address` must use, counting only confirmed transactions
+    pub fn get_account_nonce(&self, address: &str) -> u64 {
+        self.account_nonces.get(address).copied().unwrap_or(0)
+    }
+
+    /// Next nonce `address` should use, counting transactions already queued in the mempool
+    pub fn get_pending_nonce(&self, address: &str) -> u64 {
+        let mut next = self.get_account_nonce(address);
+        while self.pending_transactions.iter().any(|tx| tx.from == address && tx.nonce == next) {
+            next += 1;
+        }
+        next
+    }
+
+    /// Suggested gas price for inclusion within `target_blocks`, based on recent blocks
+    pub fn estimate_fee(&self, target_blocks: u64) -> u64 {
+        let start = self.blocks.len().saturating_sub(FEE_HISTORY_BLOCKS);
+        fees::estimate_gas_price(&self.blocks[start..], target_blocks)
     }
 
     /// Mine a block with AI3 proof (enhanced mining)
@@ -227,61 +548,57 @@ impl TribeChain {
             return Err(TribeError::Mining("No pending transactions to mine".to_string()));
         }
         
-        // Validate AI3 proof
-        if !self.validate_ai3_proof(&ai3_proof)? {
-            return Err(TribeError::AI3("Invalid AI3 proof".to_string()));
-        }
-        
-        // Get previous block hash
-        let previous_hash = if let Some(last_block) = self.blocks.last() {
-            last_block.hash.clone()
-        } else {
-            "0".repeat(64)
-        };
-        
-        // Create new block
-        let mut block = Block::new(
-            self.blocks.len() as u64,
-            previous_hash,
-            self.pending_transactions.clone(),
-            miner_address.clone(),
-        );
-        
-        // Calculate AI3 adjusted difficulty
-        let ai3_difficulty = (self.difficulty as f32 * self.ai3_difficulty_multiplier) as u64;
-        
-        // Mine the block with AI3 proof
-        block.mine_with_ai3_proof(ai3_difficulty, ai3_proof.clone())?;
+        // The template validates the proof and carries the AI3 adjusted difficulty
+        let mut block = self.get_block_template(&miner_address, Some(ai3_proof.clone()))?.block;
+        block.mine_with_ai3_proof(block.difficulty, ai3_proof)?;
         
-        // Add block to chain
-        self.add_block(block.clone())?;
-        
-        // Enhanced reward for AI3 mining
-        let ai3_bonus = (self.mining_reward as f32 * ai3_proof.optimization_factor) as u64;
-        let total_reward = self.mining_reward + ai3_bonus;
-        
-        let current_balance = self.balances.get(&miner_address).unwrap_or(&0);
-        self.balances.insert(miner_address.clone(), current_balance + total_reward);
+        // Connecting it credits the enhanced AI3 reward and completes the task
+        self.submit_block(block.clone())?;
         
-        // Mark tensor task as completed if applicable
-        if let Some(task) = self.tensor_tasks.iter_mut().find(|t| t.id == ai3_proof.task_id) {
-            task.completed = true;
-            task.assigned_miner = Some(miner_address);
+        Ok(block)
+    }
+
+    /// Record a miner's commitment to an AI3 result ahead of revealing it
+    pub fn submit_ai3_commitment(&mut self, mut commitment: AI3Commitment) -> TribeResult<()> {
+        let task = self.tensor_tasks.iter()
+            .find(|t| t.id == commitment.task_id)
+            .ok_or_else(|| TribeError::AI3("Task not found".to_string()))?;
+        if task.completed {
+            return Err(TribeError::AI3("Task already completed".to_string()));
         }
-        
-        // Clear pending transactions
-        self.pending_transactions.clear();
-        
-        // Save to storage
+
+        // The first commitment counts; re-committing would restart the reveal delay
+        let key = Self::commitment_key(&commitment.task_id, &commitment.miner);
+        if self.ai3_commitments.contains_key(&key) {
+            return Err(TribeError::AI3("Commitment already submitted".to_string()));
+        }
+
+        commitment.block_height = self.blocks.len() as u64;
+        self.ai3_commitments.insert(key, commitment);
         if let Some(storage) = &self.storage {
             storage.save_blockchain(self)?;
         }
-        
-        Ok(block)
+        Ok(())
+    }
+
+    /// Check that an AI3 proof reveals a commitment made early enough by `miner`
+    fn validate_ai3_reveal(&self, proof: &AI3Proof, miner: &str) -> bool {
+        let commitment = match self.ai3_commitments.get(&Self::commitment_key(&proof.task_id, miner)) {
+            Some(commitment) => commitment,
+            None => return false,
+        };
+
+        let height = self.blocks.len() as u64;
+        height >= commitment.block_height + self.ai3_reveal_delay
+            && commitment.matches(&proof.tensor_hash, proof.commitment_nonce)
+    }
+
+    fn commitment_key(task_id: &str, miner: &str) -> String {
+        format!("{}:{}", task_id, miner)
     }
 
     /// Validate AI3 proof
-    fn validate_ai3_proof(&self, proof: &AI3Proof) -> TribeResult<bool> {
+    fn validate_ai3_proof(&self, proof: &AI3Proof, miner: &str) -> TribeResult<bool> {
         // Check if task exists
         let task = self.tensor_tasks.iter()
             .find(|t| t.id == proof.task_id)
@@ -292,8 +609,8 @@ impl TribeChain {
             return Ok(false);
         }
         
-        // Validate optimization factor (should be between 0.1 and 2.0)
-        if proof.optimization_factor < 0.1 || proof.optimization_factor > 2.0 {
+        // Validate optimization factor (should be between 0.1 and 2.0); NaN fails too
+        if !(0.1..=2.0).contains(&proof.optimization_factor) {
             return Ok(false);
         }
         
@@ -301,109 +618,410 @@ impl TribeChain {
         if proof.computation_time > task.max_computation_time {
             return Ok(false);
         }
-        
-        // In a real implementation, we would verify the tensor computation
-        // For now, we'll accept the proof if basic checks pass
-        Ok(true)
+
+        // The tensor itself isn't on chain, only its hash. It has to be the known
+        // answer when the task has one, and a well-formed digest otherwise
+        let tensor_ok = match &task.result {
+            Some(result) => proof.tensor_hash == task.result_hash(result),
+            None => proof.tensor_hash.len() == 64 && proof.tensor_hash.chars().all(|c| c.is_ascii_hexdigit()),
+        };
+        if !tensor_ok {
+            return Ok(false);
+        }
+
+        // Result must have been committed before it could be seen on the network
+        Ok(self.validate_ai3_reveal(proof, miner))
     }
 
-    /// Add a block to the chain
+    /// Apply a checkpoint attestation; returns the newly finalized height, if any
+    pub fn submit_attestation(&mut self, attestation: Attestation) -> TribeResult<Option<u64>> {
+        let height = attestation.checkpoint.height;
+        let block = self.blocks.get(height as usize)
+            .ok_or_else(|| TribeError::InvalidBlock(format!("No block at checkpoint height {}", height)))?;
+        if block.hash != attestation.checkpoint.block_hash {
+            return Err(TribeError::InvalidBlock("Attestation is for a block not on this chain".to_string()));
+        }
+
+        Ok(self.finality.add_attestation(attestation)?.map(|checkpoint| checkpoint.height))
+    }
+
+    pub fn finalized_height(&self) -> u64 {
+        self.finality.finalized_height()
+    }
+
+    /// Refuse to switch to a fork that diverges at or below the finalized height.
+    /// `fork_height` is the last block both chains share.
+    pub fn ensure_reorg_allowed(&self, fork_height: u64) -> TribeResult<()> {
+        let finalized = self.finalized_height();
+        if fork_height < finalized {
+            return Err(TribeError::InvalidBlock(format!(
+                "Reorg from height {} would revert finalized block {}", fork_height, finalized
+            )));
+        }
+        Ok(())
+    }
+
+    /// Add a block to the chain, crediting its miner's reward
     pub fn add_block(&mut self, block: Block) -> TribeResult<()> {
-        // Validate block
-        let previous_block = self.blocks.last();
-        if !block.validate(previous_block)? {
-            return Err(TribeError::InvalidBlock("Block validation failed".to_string()));
+        validation::check_block(&block, self.blocks.last(), self.chain_id)?;
+        self.apply_block(block)
+    }
+
+    /// Import consecutive blocks, as during initial sync. Every block's structure and
+    /// transactions are checked in parallel up front; state is then applied block by
+    /// block. Blocks before the first invalid one stay imported.
+    pub fn import_blocks(&mut self, blocks: Vec<Block>) -> TribeResult<usize> {
+        let checks = validation::check_blocks(&blocks, self.blocks.last(), self.chain_id);
+        let mut imported = 0;
+        for (block, check) in blocks.into_iter().zip(checks) {
+            check?;
+            self.apply_block(block)?;
+            imported += 1;
+        }
+        Ok(imported)
+    }
+
+    /// Tensor proof and state stages of import, for a block whose stateless checks passed
+    fn apply_block(&mut self, block: Block) -> TribeResult<()> {
+        if block.get_size() > self.chain_spec.max_block_size {
+            return Err(TribeError::InvalidBlock(format!(
+                "Block {} is {} bytes, more than the {} allowed", block.index, block.get_size(), self.chain_spec.max_block_size
+            )));
+        }
+        if self.chain_spec.block_consensus(&block).is_none() {
+            return Err(TribeError::InvalidBlock(format!(
+                "Block {} was produced by a consensus the {} chain doesn't enable", block.index, self.chain_spec.name
+            )));
+        }
+        let required = self.required_difficulty(&block);
+        if block.difficulty < required {
+            return Err(TribeError::InvalidBlock(format!(
+                "Block {} is mined at difficulty {}, below the required {}", block.index, block.difficulty, required
+            )));
+        }
+        if let Some(proof) = &block.ai3_proof {
+            if !self.validate_ai3_proof(proof, &block.miner)? {
+                return Err(TribeError::InvalidBlock(format!("Block {} carries an invalid AI3 proof", block.index)));
+            }
         }
         
+        // Each sender's transactions must continue its nonce sequence exactly
+        let mut next_nonces: HashMap<&str, u64> = HashMap::new();
+        for transaction in &block.transactions {
+            let next = next_nonces.entry(transaction.from.as_str())
+                .or_insert_with(|| self.get_account_nonce(&transaction.from));
+            if transaction.nonce != *next {
+                return Err(TribeError::InvalidBlock(format!(
+                    "Transaction {} has nonce {}, expected {}", transaction.hash, transaction.nonce, next
+                )));
+            }
+            *next += 1;
+        }
+
+        // Balance changes are worked out aside so a block committing to a different
+        // state root is rejected before anything is written
+        if block.state_root.is_empty() {
+            return Err(TribeError::InvalidBlock(format!("Block {} does not commit to a state root", block.index)));
+        }
+        let changes = self.post_state(&block)?;
+        let state_root = self.balances.root_with(&changes);
+        if block.state_root != state_root {
+            return Err(TribeError::InvalidBlock(format!(
+                "Block {} commits to state root {}, expected {}", block.index, block.state_root, state_root
+            )));
+        }
+
+        // Log the import so a crash before the snapshot is saved can be repaired
+        let intent = ImportIntent { index: self.blocks.len() as u64, block_hash: block.hash.clone() };
+        if let Some(storage) = &self.storage {
+            storage.begin_import(&intent)?;
+        }
+
         // Process transactions in the block
         for transaction in &block.transactions {
-            self.process_transaction(transaction)?;
+            self.process_transaction(transaction);
+        }
+        for (address, balance) in changes {
+            self.balances.insert(address, balance);
+        }
+
+        // Mark tensor task as completed if applicable
+        if let Some(proof) = &block.ai3_proof {
+            if let Some(task) = self.tensor_tasks.iter_mut().find(|t| t.id == proof.task_id) {
+                task.completed = true;
+                task.assigned_miner = Some(block.miner.clone());
+            }
+            self.ai3_commitments.retain(|_, commitment| commitment.task_id != proof.task_id);
         }
+
+        // Drop included transactions; the rest wait for a later block
+        self.remove_included(&block);
         
         // Add block to chain
         self.blocks.push(block.clone());
+        self.mining_reward = self.chain_spec.reward_at(self.blocks.len() as u64);
         
         // Adjust difficulty if needed
         self.adjust_difficulty();
         
-        // Save block to storage
+        // Index transactions by address
+        let touched = self.address_index.index_block(&block);
+        self.blooms.push(block.address_bloom());
+
+        // Save block, its transactions and index entries in one batch
         if let Some(storage) = &self.storage {
-            storage.save_block(&block, self.blocks.len() as u64 - 1)?;
+            let addresses: Vec<(&str, &[AddressTxRef])> = touched.iter()
+                .map(|address| (address.as_str(), self.address_index.get(address)))
+                .collect();
+            storage.import_block(&block, intent.index, &addresses)?;
+            storage.commit_import(self)?;
         }
+
+        // Orphans whose missing nonces this block confirmed can be queued now
+        self.promote_orphans(|_| false);
         
         Ok(())
     }
 
-    /// Process a transaction (update balances)
-    fn process_transaction(&mut self, transaction: &Transaction) -> TribeResult<()> {
-        match &transaction.transaction_type {
-            TransactionType::Transfer { to, amount } => {
-                // Deduct from sender
-                let sender_balance = self.balances.get(&transaction.from).unwrap_or(&0);
-                self.balances.insert(transaction.from.clone(), sender_balance - amount - transaction.fee);
-                
-                // Add to receiver
-                let receiver_balance = self.balances.get(to).unwrap_or(&0);
-                self.balances.insert(to.clone(), receiver_balance + amount);
-            }
-            TransactionType::TokenCreate { .. } => {
-                // Token creation fee
-                let sender_balance = self.balances.get(&transaction.from).unwrap_or(&0);
-                self.balances.insert(transaction.from.clone(), sender_balance - 1_000_000 - transaction.fee);
-            }
-            TransactionType::TokenTransfer { to, amount, .. } => {
-                // Similar to regular transfer but for tokens
-                let sender_balance = self.balances.get(&transaction.from).unwrap_or(&0);
-                self.balances.insert(transaction.from.clone(), sender_balance - amount - transaction.fee);
-                
-                let receiver_balance = self.balances.get(to).unwrap_or(&0);
-                self.balances.insert(to.clone(), receiver_balance + amount);
-            }
-            TransactionType::Stake { amount, .. } => {
-                // Deduct staked amount from balance
-                let sender_balance = self.balances.get(&transaction.from).unwrap_or(&0);
-                self.balances.insert(transaction.from.clone(), sender_balance - amount - transaction.fee);
-            }
-            TransactionType::TensorCompute { reward, .. } => {
-                // Deduct computation fee and reward
-                let sender_balance = self.balances.get(&transaction.from).unwrap_or(&0);
-                self.balances.insert(transaction.from.clone(), sender_balance - reward - transaction.fee);
+    /// Finish or undo a block import a crash cut short. A block that was fully written
+    /// is replayed onto the last saved state; one that wasn't, or no longer validates
+    /// against it, is rolled back.
+    pub fn repair(storage_path: &str) -> TribeResult<RepairOutcome> {
+        Self::repair_storage(open_storage(storage_path, &StorageConfig::default())?)
+    }
+
+    fn repair_storage(storage: Arc<dyn Storage>) -> TribeResult<RepairOutcome> {
+        let intent = match storage.pending_import()? {
+            Some(intent) => intent,
+            None => return Ok(RepairOutcome::Clean),
+        };
+
+        let mut blockchain = storage.load_blockchain()?;
+        blockchain.storage = Some(storage.clone());
+        blockchain.rebuild_indices();
+        if blockchain.blocks.get(intent.index as usize).is_some_and(|block| block.hash == intent.block_hash) {
+            // Already in the saved state; only the record was left behind
+            storage.commit_import(&blockchain)?;
+            return Ok(RepairOutcome::Replayed { index: intent.index });
+        }
+        let stored = storage.load_block(intent.index).ok().filter(|block| block.hash == intent.block_hash);
+        if let Some(block) = stored {
+            if blockchain.blocks.len() as u64 == intent.index && blockchain.add_block(block).is_ok() {
+                return Ok(RepairOutcome::Replayed { index: intent.index });
             }
-            TransactionType::ContractDeploy { .. } => {
-                // Deduct deployment fee
-                let sender_balance = self.balances.get(&transaction.from).unwrap_or(&0);
-                self.balances.insert(transaction.from.clone(), sender_balance - transaction.fee);
+        }
+
+        storage.rollback_import(&intent)?;
+        Ok(RepairOutcome::RolledBack { index: intent.index })
+    }
+
+    /// Rebuild the address index and blooms, which aren't part of the snapshot
+    fn rebuild_indices(&mut self) {
+        self.blooms.clear();
+        for block in &self.blocks {
+            self.address_index.index_block(block);
+            let mut bloom = block.address_bloom();
+            if let Some(receipts) = self.receipts.get(&block.index) {
+                bloom.merge(&receipts.bloom);
             }
-            TransactionType::ContractCall { value, .. } => {
-                // Deduct call value and fee
-                let sender_balance = self.balances.get(&transaction.from).unwrap_or(&0);
-                self.balances.insert(transaction.from.clone(), sender_balance - value - transaction.fee);
+            self.blooms.push(bloom);
+        }
+    }
+
+    /// Persist the chain, including pending transactions and miner registrations, and
+    /// flush storage; called on shutdown so nothing buffered is lost
+    pub fn flush(&self) -> TribeResult<()> {
+        if let Some(storage) = &self.storage {
+            storage.save_blockchain(self)?;
+            storage.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Reward for producing `block`: the emission schedule's reward at its height, plus
+    /// a bonus scaled by the optimization factor of its AI3 proof. Blocks don't claim a
+    /// reward; it is credited by the chain, and a block committing to any other state root
+    /// is rejected on import.
+    pub fn block_reward(&self, block: &Block) -> TribeResult<u64> {
+        let reward = self.chain_spec.reward_at(block.index);
+        let overflow = || TribeError::InvalidBlock(format!("Block {}'s reward overflows", block.index));
+        let ai3_bonus = match &block.ai3_proof {
+            Some(proof) => {
+                let bonus = reward as f32 * proof.optimization_factor;
+                if !(0.0..u64::MAX as f32).contains(&bonus) {
+                    return Err(overflow());
+                }
+                bonus as u64
             }
+            None => 0,
+        };
+        reward.checked_add(ai3_bonus).ok_or_else(overflow)
+    }
+
+    /// Rewards `address` mined that can't be spent yet in a block at `height`: those
+    /// from the last `coinbase_maturity` blocks. Their rewards were checked on import.
+    fn immature_rewards(&self, address: &str, height: u64) -> u64 {
+        let start = (height + 1).saturating_sub(self.chain_spec.coinbase_maturity).max(1);
+        (start..height)
+            .filter_map(|index| self.blocks.get(index as usize))
+            .filter(|block| block.miner == address)
+            .filter_map(|block| self.block_reward(block).ok())
+            .fold(0, u64::saturating_add)
+    }
+
+    /// Balance `address` can spend in the next block, leaving out immature mining rewards
+    pub fn get_spendable_balance(&self, address: &str) -> u64 {
+        let locked = self.immature_rewards(address, self.blocks.len() as u64);
+        self.get_balance(address).saturating_sub(locked)
+    }
+
+    /// New balances of the accounts `block`'s transactions and miner's reward touch
+    fn post_state(&self, block: &Block) -> TribeResult<HashMap<String, u64>> {
+        let mut changes = HashMap::new();
+        for transaction in &block.transactions {
+            let locked = self.immature_rewards(&transaction.from, block.index);
+            self.apply_balance_changes(&mut changes, transaction, locked)?;
+        }
+        let miner_balance = self.balance_after(&changes, &block.miner)
+            .checked_add(self.block_reward(block)?)
+            .ok_or_else(|| TribeError::InvalidBlock(format!(
+                "Block {}'s reward overflows {}'s balance", block.index, block.miner
+            )))?;
+        changes.insert(block.miner.clone(), miner_balance);
+        Ok(changes)
+    }
+
+    /// Balance of `address` with `changes` applied
+    fn balance_after(&self, changes: &HashMap<String, u64>, address: &str) -> u64 {
+        changes.get(address).copied().unwrap_or_else(|| self.get_balance(address))
+    }
+
+    /// State root `block` should commit to on top of the current chain
+    pub fn compute_state_root(&self, block: &Block) -> TribeResult<String> {
+        Ok(self.balances.root_with(&self.post_state(block)?))
+    }
+
+    /// Proof of `address`'s balance under the latest state root
+    pub fn get_state_proof(&self, address: &str) -> StateProof {
+        self.balances.proof(address)
+    }
+
+    /// Debit a transaction's sender, who can't spend the `locked` part of their balance,
+    /// and credit its receiver, recording the new balances in `changes`
+    fn apply_balance_changes(&self, changes: &mut HashMap<String, u64>, transaction: &Transaction, locked: u64) -> TribeResult<()> {
+        let (debit, credit) = match &transaction.transaction_type {
+            TransactionType::Transfer { to, amount } => (*amount, Some((to, *amount))),
+            TransactionType::TokenCreate { .. } => (1_000_000, None), // Token creation fee
+            TransactionType::TokenTransfer { to, amount, .. } => (*amount, Some((to, *amount))),
+            TransactionType::Stake { amount, .. } => (*amount, None),
+            TransactionType::TensorCompute { reward, .. } => (*reward, None),
+            TransactionType::ContractDeploy { .. } => (0, None),
+            TransactionType::ContractCall { value, .. } => (*value, None),
+            TransactionType::DelegateVote { .. } => (0, None),
+        };
+
+        let sender_balance = self.balance_after(changes, &transaction.from);
+        let total = debit.checked_add(transaction.fee)
+            .filter(|total| *total <= sender_balance)
+            .ok_or_else(|| TribeError::InvalidBlock(format!(
+                "Transaction {} spends more than {}'s balance", transaction.hash, transaction.from
+            )))?;
+        if total > sender_balance.saturating_sub(locked) {
+            return Err(TribeError::InvalidBlock(format!(
+                "Transaction {} spends {}'s immature mining rewards", transaction.hash, transaction.from
+            )));
+        }
+        changes.insert(transaction.from.clone(), sender_balance - total);
+
+        if let Some((to, amount)) = credit {
+            let receiver_balance = self.balance_after(changes, to).checked_add(amount)
+                .ok_or_else(|| TribeError::InvalidBlock(format!(
+                    "Transaction {} overflows {}'s balance", transaction.hash, to
+                )))?;
+            changes.insert(to.clone(), receiver_balance);
         }
-        
         Ok(())
     }
 
+    /// Process a transaction's nonce and delegate vote; balances are applied by `post_state`
+    fn process_transaction(&mut self, transaction: &Transaction) {
+        if let TransactionType::DelegateVote { delegates } = &transaction.transaction_type {
+            let mut delegates = delegates.clone();
+            delegates.sort();
+            delegates.dedup();
+            if delegates.is_empty() {
+                self.delegate_votes.remove(&transaction.from);
+            } else {
+                self.delegate_votes.insert(transaction.from.clone(), delegates);
+            }
+        }
+
+        self.account_nonces.insert(transaction.from.clone(), transaction.nonce + 1);
+    }
+
     /// Adjust mining difficulty based on block time
     fn adjust_difficulty(&mut self) {
-        if self.blocks.len() < 10 {
+        let window = self.chain_spec.difficulty.adjustment_window;
+        if self.chain_spec.difficulty.fixed || self.blocks.len() < window {
             return; // Not enough blocks to adjust
         }
         
-        let recent_blocks = &self.blocks[self.blocks.len() - 10..];
+        let recent_blocks = &self.blocks[self.blocks.len() - window..];
         let time_diff = recent_blocks.last().unwrap().timestamp - recent_blocks.first().unwrap().timestamp;
-        let target_time = 10 * 60; // 10 minutes per block target
+        let target_time = window as u64 * self.chain_spec.block_time_secs;
         
         if time_diff < target_time / 2 {
             self.difficulty += 1; // Increase difficulty
-        } else if time_diff > target_time * 2 && self.difficulty > 1 {
+        } else if time_diff > target_time * 2 && self.difficulty > self.chain_spec.difficulty.minimum {
             self.difficulty -= 1; // Decrease difficulty
         }
     }
 
     /// Get balance for an address
     pub fn get_balance(&self, address: &str) -> u64 {
-        *self.balances.get(address).unwrap_or(&0)
+        self.balances.get(address).unwrap_or(0)
+    }
+
+    /// Balance counting only funds received in blocks with at least `min_confirmations`
+    /// confirmations, so credits a reorg could still take away aren't spent. Spends count
+    /// as soon as they are in a block; 0 or 1 gives the balance at the tip.
+    pub fn get_confirmed_balance(&self, address: &str, min_confirmations: u64) -> u64 {
+        let shallow = (min_confirmations.saturating_sub(1) as usize).min(self.blocks.len().saturating_sub(1));
+        let unconfirmed: u64 = self.blocks[self.blocks.len() - shallow..].iter()
+            .map(|block| self.credits_to(block, address))
+            .sum();
+        self.get_balance(address).saturating_sub(unconfirmed)
+    }
+
+    /// Blocks on top of `block_hash` at `block_index`, counting itself; None if that
+    /// block is no longer on the chain
+    pub fn get_confirmations(&self, block_index: u64, block_hash: &str) -> Option<u64> {
+        let block = self.blocks.get(block_index as usize).filter(|block| block.hash == block_hash)?;
+        Some(self.blocks.len() as u64 - block.index)
+    }
+
+    /// What `block` credited to `address`, including its reward if `address` mined it
+    fn credits_to(&self, block: &Block, address: &str) -> u64 {
+        let received: u64 = block.transactions.iter()
+            .map(|transaction| match &transaction.transaction_type {
+                TransactionType::Transfer { to, amount } | TransactionType::TokenTransfer { to, amount, .. } if to == address => *amount,
+                _ => 0,
+            })
+            .sum();
+        let reward = if block.miner == address { self.block_reward(block).unwrap_or(0) } else { 0 };
+        received + reward
+    }
+
+    /// Votes per delegate, each voter weighted by their current balance
+    pub fn delegate_vote_tally(&self) -> HashMap<String, u64> {
+        let mut tally = HashMap::new();
+        for (voter, delegates) in &self.delegate_votes {
+            let weight = self.get_balance(voter);
+            for delegate in delegates {
+                *tally.entry(delegate.clone()).or_insert(0u64) += weight;
+            }
+        }
+        tally
     }
 
     /// Get blockchain statistics
@@ -420,6 +1038,7 @@ impl TribeChain {
             block_count: self.blocks.len() as u64,
             transaction_count: self.blocks.iter().map(|b| b.transactions.len()).sum::<usize>() as u64,
             pending_transactions: self.pending_transactions.len() as u64,
+            orphan_transactions: self.orphan_transactions.len() as u64,
             difficulty: self.difficulty,
             mining_reward: self.mining_reward,
             total_supply,
@@ -428,7 +1047,81 @@ impl TribeChain {
             active_miners: self.active_miners.len() as u64,
             tensor_tasks: self.tensor_tasks.len() as u64,
             ai3_difficulty_multiplier: self.ai3_difficulty_multiplier,
+            finalized_height: self.finalized_height(),
+        }
+    }
+
+    /// Mining activity over the last `window` blocks
+    pub fn mining_status(&self, window: usize) -> MiningStatus {
+        let completed_tasks = self.tensor_tasks.iter().filter(|task| task.completed).count() as u64;
+        let mut status = MiningStatus {
+            pending_tasks: self.tensor_tasks.len() as u64 - completed_tasks,
+            completed_tasks,
+            ..MiningStatus::default()
+        };
+        // The genesis block wasn't mined
+        let start = self.blocks.len().saturating_sub(window).max(1);
+        let Some(recent) = self.blocks.get(start - 1..).filter(|recent| recent.len() > 1) else { return status };
+
+        let span = recent[recent.len() - 1].timestamp.saturating_sub(recent[0].timestamp).max(1) as f64;
+        let mined = &recent[1..];
+        status.window_blocks = mined.len() as u64;
+        status.avg_block_time = span / mined.len() as f64;
+        status.hash_rate = self.get_network_hashrate(window);
+        status.ai3_proofs = mined.iter().filter(|block| block.ai3_proof.is_some()).count() as u64;
+        status.ai3_tasks_per_hour = status.ai3_proofs as f64 * 3600.0 / span;
+        status
+    }
+
+    /// Network hashes per second over the last `window` mined blocks, estimated from
+    /// their stored headers' difficulty and timestamps
+    pub fn get_network_hashrate(&self, window: usize) -> f64 {
+        let tip = self.blocks.len().saturating_sub(1) as u64;
+        // The block before the window anchors its first block time
+        let headers = self.headers(tip.saturating_sub(window as u64), tip);
+        if headers.len() < 2 {
+            return 0.0;
         }
+
+        let span = headers[headers.len() - 1].timestamp.saturating_sub(headers[0].timestamp).max(1) as f64;
+        // Work is counted as consensus counts it, whether the hash or the tensor proof met the difficulty
+        let hashes: f64 = headers[1..].iter().map(|header| difficulty_work(header.difficulty)).sum();
+        hashes / span
+    }
+
+    /// Difficulty and block time of blocks `from..=to`, read from stored headers
+    pub fn get_difficulty_history(&self, from: u64, to: u64) -> TribeResult<Vec<DifficultySample>> {
+        let to = to.min(self.blocks.len().saturating_sub(1) as u64);
+        if from > to {
+            return Err(TribeError::InvalidBlock(format!("Block range {}..={} is empty", from, to)));
+        }
+        if to - from >= DIFFICULTY_HISTORY_LIMIT {
+            return Err(TribeError::InvalidBlock(format!("Block range {}..={} exceeds {} blocks", from, to, DIFFICULTY_HISTORY_LIMIT)));
+        }
+
+        let headers = self.headers(from.saturating_sub(1), to);
+        let samples = headers.iter().enumerate()
+            .filter(|(_, header)| header.index >= from)
+            .map(|(i, header)| DifficultySample {
+                index: header.index,
+                timestamp: header.timestamp,
+                difficulty: header.difficulty,
+                block_time: i.checked_sub(1)
+                    .map(|prev| header.timestamp.saturating_sub(headers[prev].timestamp))
+                    .unwrap_or(0),
+            })
+            .collect();
+        Ok(samples)
+    }
+
+    /// Headers of blocks `from..=to`, from storage when the chain is persisted
+    fn headers(&self, from: u64, to: u64) -> Vec<BlockHeader> {
+        (from..=to)
+            .filter_map(|index| {
+                let stored = self.storage.as_ref().and_then(|storage| storage.load_header(index).ok());
+                stored.or_else(|| self.blocks.get(index as usize).map(Block::header))
+            })
+            .collect()
     }
 
     /// Add a tensor computation task
@@ -480,6 +1173,100 @@ impl TribeChain {
         self.blocks.get(index as usize)
     }
 
+    /// One page of the transactions touching `address`, newest first
+    pub fn get_transactions_for_address(&self, address: &str, page: usize) -> Vec<(&AddressTxRef, &Transaction)> {
+        self.address_index.page(address, page, ADDRESS_TX_PAGE_SIZE)
+            .into_iter()
+            .filter_map(|tx_ref| {
+                self.blocks.get(tx_ref.block_index as usize)
+                    .and_then(|block| block.transactions.get(tx_ref.position))
+                    .map(|tx| (tx_ref, tx))
+            })
+            .collect()
+    }
+
+    /// Transactions touching `address` in blocks `from..=to`, oldest first, for wallet
+    /// rescans; blocks whose bloom rules the address out aren't opened
+    pub fn scan_address(&self, address: &str, from: u64, to: u64) -> Vec<(AddressTxRef, &Transaction)> {
+        let to = to.min(self.blocks.len().saturating_sub(1) as u64);
+        if from > to {
+            return Vec::new();
+        }
+        self.blocks[from as usize..=to as usize].iter()
+            .filter(|block| self.blooms.get(block.index as usize).is_none_or(|bloom| bloom.might_contain(address)))
+            .flat_map(|block| {
+                block.transactions.iter().enumerate()
+                    .filter(|(_, tx)| tx.involved_addresses().iter().any(|involved| involved == address))
+                    .map(move |(position, tx)| {
+                        let tx_ref = AddressTxRef {
+                            tx_hash: tx.hash.clone(),
+                            block_index: block.index,
+                            block_hash: block.hash.clone(),
+                            position,
+                        };
+                        (tx_ref, tx)
+                    })
+            })
+            .collect()
+    }
+
+    /// Attach contract execution receipts to an imported block, adding their log
+    /// addresses and topics to its bloom
+    pub fn attach_receipts(&mut self, block_index: u64, receipts: Vec<Receipt>) -> TribeResult<()> {
+        let block = self.blocks.get(block_index as usize)
+            .ok_or_else(|| TribeError::Blockchain(format!("Block {} not found", block_index)))?;
+        let block_receipts = BlockReceipts::new(block_index, block.hash.clone(), receipts);
+        let mut header = block.header();
+        if let Some(bloom) = self.blooms.get_mut(block_index as usize) {
+            bloom.merge(&block_receipts.bloom);
+            header.bloom = Some(bloom.clone());
+        }
+        self.receipts.insert(block_index, block_receipts);
+
+        if let Some(storage) = &self.storage {
+            storage.save_header(&header)?;
+            storage.save_blockchain(self)?;
+        }
+        Ok(())
+    }
+
+    /// Get the receipt of a contract transaction
+    pub fn get_receipt(&self, tx_hash: &str) -> Option<&Receipt> {
+        self.receipts.values()
+            .flat_map(|block| &block.receipts)
+            .find(|receipt| receipt.tx_hash == tx_hash)
+    }
+
+    /// Logs matching `filter`, oldest first; blocks ruled out by their bloom are skipped
+    pub fn get_logs(&self, filter: &LogFilter) -> Vec<MatchedLog> {
+        let from = filter.from_block.unwrap_or(0);
+        let to = filter.to_block.unwrap_or(u64::MAX);
+        let mut matched = Vec::new();
+        if from > to {
+            return matched;
+        }
+
+        for block in self.receipts.range(from..=to).map(|(_, block)| block) {
+            if !filter.might_match(&block.bloom) {
+                continue;
+            }
+            let logs = block.receipts.iter()
+                .flat_map(|receipt| receipt.logs.iter().map(move |log| (receipt, log)));
+            for (log_index, (receipt, log)) in logs.enumerate() {
+                if filter.matches(log) {
+                    matched.push(MatchedLog {
+                        block_index: block.block_index,
+                        block_hash: block.block_hash.clone(),
+                        tx_hash: receipt.tx_hash.clone(),
+                        log_index,
+                        log: log.clone(),
+                    });
+                }
+            }
+        }
+        matched
+    }
+
     /// Get transaction by hash
     pub fn get_transaction(&self, hash: &str) -> Option<&Transaction> {
         for block in &self.blocks {
@@ -491,4 +1278,261 @@ impl TribeChain {
         }
         None
     }
-} 
\ No newline at end of file
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{dev_chain, next_block};
+
+    fn ai3_proof() -> AI3Proof {
+        AI3Proof {
+            task_id: "task".to_string(),
+            optimization_factor: 1.0,
+            tensor_hash: "ab".repeat(32),
+            computation_time: 0,
+            miner_signature: String::new(),
+            commitment_nonce: 0,
+        }
+    }
+
+    #[test]
+    fn test_ai3_blocks_must_meet_the_multiplied_difficulty() {
+        let mut chain = dev_chain();
+        chain.ai3_difficulty_multiplier = 2.0;
+
+        // Any import path, not just template submission, applies the multiplier
+        let block = next_block(&chain, Some(ai3_proof()), chain.difficulty);
+        let error = chain.add_block(block).unwrap_err().to_string();
+        assert!(error.contains("below the required 2"), "{}", error);
+
+        // At the multiplied difficulty the block gets as far as the proof check
+        let block = next_block(&chain, Some(ai3_proof()), 2);
+        let error = chain.add_block(block).unwrap_err().to_string();
+        assert!(error.contains("Task not found"), "{}", error);
+
+        // Blocks without a proof only need the plain difficulty
+        let block = next_block(&chain, None, chain.difficulty);
+        chain.add_block(block).unwrap();
+        assert_eq!(chain.blocks.len(), 2);
+    }
+
+    /// Chain with a task for `ai3_proof()` whose commitment is old enough to reveal
+    fn chain_with_committed_task() -> TribeChain {
+        let mut chain = dev_chain();
+        chain.add_tensor_task(TensorTask {
+            id: "task".to_string(),
+            operation: "relu".to_string(),
+            input_data: vec![1.0],
+            expected_output_size: 1,
+            max_computation_time: 60,
+            reward: 0,
+            requester: "dev".to_string(),
+            completed: false,
+            result: None,
+            assigned_miner: None,
+            created_at: 0,
+        }).unwrap();
+        let proof = ai3_proof();
+        chain.submit_ai3_commitment(AI3Commitment::new("task".to_string(), "miner".to_string(), &proof.tensor_hash, 0, 0)).unwrap();
+        let block = next_block(&chain, None, chain.difficulty);
+        chain.add_block(block).unwrap();
+        chain
+    }
+
+    #[test]
+    fn test_imported_ai3_block_completes_its_task() {
+        let mut chain = chain_with_committed_task();
+
+        // Imported through add_block, as from a peer, rather than submitted from a template
+        let block = next_block(&chain, Some(ai3_proof()), chain.difficulty);
+        chain.add_block(block).unwrap();
+        assert!(chain.tensor_tasks[0].completed);
+        assert_eq!(chain.tensor_tasks[0].assigned_miner.as_deref(), Some("miner"));
+        assert!(chain.ai3_commitments.is_empty());
+    }
+
+    #[test]
+    fn test_imported_ai3_proofs_are_fully_validated() {
+        let mut chain = chain_with_committed_task();
+
+        // A peer's block can't inflate its reward through the optimization factor
+        let mut inflated = next_block(&chain, None, chain.difficulty);
+        inflated.ai3_proof = Some(AI3Proof { optimization_factor: 1e6, ..ai3_proof() });
+        inflated.mine_block(chain.difficulty).unwrap();
+        let error = chain.add_block(inflated.clone()).unwrap_err().to_string();
+        assert!(error.contains("invalid AI3 proof"), "{}", error);
+        inflated.ai3_proof = Some(AI3Proof { optimization_factor: f32::MAX, ..ai3_proof() });
+        assert!(chain.block_reward(&inflated).is_err());
+
+        let slow = AI3Proof { computation_time: 61, ..ai3_proof() };
+        assert!(chain.add_block(next_block(&chain, Some(slow), chain.difficulty)).is_err());
+        let malformed = AI3Proof { tensor_hash: "tensor".to_string(), ..ai3_proof() };
+        assert!(chain.add_block(next_block(&chain, Some(malformed), chain.difficulty)).is_err());
+
+        // Once the task has a known answer the revealed tensor must match it
+        chain.tensor_tasks[0].result = Some(vec![2.0]);
+        assert!(chain.add_block(next_block(&chain, Some(ai3_proof()), chain.difficulty)).is_err());
+        chain.tensor_tasks[0].result = None;
+
+        chain.add_block(next_block(&chain, Some(ai3_proof()), chain.difficulty)).unwrap();
+        assert!(chain.tensor_tasks[0].completed);
+    }
+
+    /// Crash partway through importing `block`: its intent is logged and, if
+    /// `written`, the block too, but the snapshot is never saved
+    fn crash_during_import(chain: &TribeChain, block: &Block, written: bool) -> Arc<dyn Storage> {
+        let storage = chain.storage.clone().unwrap();
+        storage.begin_import(&ImportIntent { index: block.index, block_hash: block.hash.clone() }).unwrap();
+        if written {
+            storage.import_block(block, block.index, &[]).unwrap();
+        }
+        storage
+    }
+
+    #[test]
+    fn test_repair_replays_a_written_block() {
+        let chain = dev_chain();
+        let block = next_block(&chain, None, chain.difficulty);
+        let storage = crash_during_import(&chain, &block, true);
+        assert_eq!(storage.load_blockchain().unwrap().blocks.len(), 1);
+
+        assert_eq!(TribeChain::repair_storage(storage.clone()).unwrap(), RepairOutcome::Replayed { index: 1 });
+        assert!(storage.pending_import().unwrap().is_none());
+        let repaired = storage.load_blockchain().unwrap();
+        assert_eq!(repaired.blocks.last().unwrap().hash, block.hash);
+        assert_eq!(repaired.get_balance("miner"), chain.block_reward(&block).unwrap());
+
+        // Nothing is left to repair
+        assert_eq!(TribeChain::repair_storage(storage).unwrap(), RepairOutcome::Clean);
+    }
+
+    #[test]
+    fn test_repair_rolls_back_unwritten_and_invalid_blocks() {
+        let chain = dev_chain();
+        let block = next_block(&chain, None, chain.difficulty);
+        let storage = crash_during_import(&chain, &block, false);
+        assert_eq!(TribeChain::repair_storage(storage.clone()).unwrap(), RepairOutcome::RolledBack { index: 1 });
+        assert!(storage.pending_import().unwrap().is_none());
+        assert!(storage.load_block(1).is_err());
+
+        // A written block that doesn't apply to the saved state is removed too
+        let mut invalid = Block::new(1, block.previous_hash.clone(), Vec::new(), "miner".to_string());
+        invalid.state_root = "0".repeat(64);
+        invalid.mine_block(chain.difficulty).unwrap();
+        let storage = crash_during_import(&chain, &invalid, true);
+        assert_eq!(TribeChain::repair_storage(storage.clone()).unwrap(), RepairOutcome::RolledBack { index: 1 });
+        assert!(storage.load_block(1).is_err());
+        assert_eq!(storage.load_blockchain().unwrap().blocks.len(), 1);
+    }
+
+    #[test]
+    fn test_transactions_need_their_senders_signature() {
+        let mut chain = dev_chain();
+        chain.balances.insert("alice".to_string(), 1_000_000);
+        let bob = crate::Address::from_key(crate::hrp_for_chain(chain.chain_id), "bob-key").to_string();
+        let transfer = Transaction::new("alice".to_string(), TransactionType::Transfer { to: bob, amount: 5 }, 1, 0)
+            .with_chain_id(chain.chain_id);
+        assert!(matches!(chain.add_transaction(transfer.clone()), Err(TribeError::InvalidTransaction(_))));
+
+        // A block carrying it is refused too, whoever mined it
+        let tip = chain.blocks.last().unwrap();
+        let mut block = Block::new(tip.index + 1, tip.hash.clone(), vec![transfer.clone()], "miner".to_string());
+        block.state_root = chain.compute_state_root(&block).unwrap();
+        block.mine_block(chain.difficulty).unwrap();
+        let error = chain.add_block(block).unwrap_err().to_string();
+        assert!(error.contains("invalid signature"), "{}", error);
+
+        let mut signed = transfer;
+        signed.sign("alice-key").unwrap();
+        chain.add_transaction(signed.clone()).unwrap();
+        // Resubmitting it is a pool error rather than an invalid transaction
+        assert!(matches!(chain.add_transaction(signed), Err(TribeError::Mempool(_))));
+        chain.mine_block("miner".to_string()).unwrap();
+        assert_eq!(chain.blocks.len(), 2);
+    }
+
+    #[test]
+    fn test_address_scans_skip_blocks_their_bloom_rules_out() {
+        let mut chain = dev_chain();
+        chain.balances.insert("alice".to_string(), 1_000_000);
+        let bob = crate::Address::from_key(crate::hrp_for_chain(chain.chain_id), "bob-key").to_string();
+        let mut transfer = Transaction::new("alice".to_string(), TransactionType::Transfer { to: bob.clone(), amount: 5 }, 1, 0)
+            .with_chain_id(chain.chain_id);
+        transfer.sign("alice-key").unwrap();
+        chain.add_transaction(transfer.clone()).unwrap();
+        chain.mine_block("miner".to_string()).unwrap();
+        let block = next_block(&chain, None, chain.difficulty);
+        chain.add_block(block).unwrap();
+
+        let found = chain.scan_address(&bob, 0, 10);
+        assert_eq!(found.len(), 1);
+        assert_eq!((found[0].0.block_index, found[0].0.position, &found[0].1.hash), (1, 0, &transfer.hash));
+        assert!(chain.scan_address("carol", 0, 10).is_empty());
+        assert!(chain.scan_address(&bob, 2, 10).is_empty());
+        assert!(!chain.blooms[2].might_contain(&bob));
+
+        // A block whose bloom rules the address out isn't opened at all
+        let bloom = std::mem::take(&mut chain.blooms[1]);
+        assert!(chain.scan_address(&bob, 0, 10).is_empty());
+        chain.blooms[1] = bloom;
+
+        // Receipt logs join the block's bloom and its stored header
+        let log = crate::Log { address: "token".to_string(), topics: vec!["Transfer".to_string()], data: Vec::new() };
+        let receipt = Receipt { tx_hash: transfer.hash.clone(), success: true, gas_used: 0, logs: vec![log] };
+        chain.attach_receipts(2, vec![receipt]).unwrap();
+        assert!(chain.blooms[2].might_contain("Transfer"));
+        let storage = chain.storage.clone().unwrap();
+        assert!(storage.load_header(2).unwrap().bloom.unwrap().might_contain("token"));
+
+        // Blooms aren't in the snapshot; loading rebuilds them, receipts included
+        let mut reloaded = storage.load_blockchain().unwrap();
+        reloaded.rebuild_indices();
+        assert_eq!(reloaded.blooms, chain.blooms);
+    }
+
+    #[test]
+    fn test_blocks_must_commit_to_the_post_block_state() {
+        let mut chain = dev_chain();
+        let genesis_root = chain.balances.root();
+
+        let mut missing = next_block(&chain, None, chain.difficulty);
+        missing.state_root.clear();
+        missing.mine_block(chain.difficulty).unwrap();
+        let error = chain.add_block(missing).unwrap_err().to_string();
+        assert!(error.contains("does not commit to a state root"), "{}", error);
+
+        // Committing to the state before the block's reward isn't enough
+        let mut stale = next_block(&chain, None, chain.difficulty);
+        stale.state_root = genesis_root.clone();
+        stale.mine_block(chain.difficulty).unwrap();
+        let error = chain.add_block(stale).unwrap_err().to_string();
+        assert!(error.contains("expected"), "{}", error);
+        assert_eq!(chain.balances.root(), genesis_root);
+
+        let block = next_block(&chain, None, chain.difficulty);
+        chain.add_block(block.clone()).unwrap();
+        assert_eq!(chain.balances.root(), block.state_root);
+    }
+
+    #[test]
+    fn test_blocks_of_disabled_consensus_are_rejected() {
+        let mut spec = ChainSpec::dev();
+        spec.consensus = vec![crate::ConsensusType::ProofOfWork];
+        let mut chain = TribeChain::with_chain_spec("unused", &StorageConfig::memory(), &spec).unwrap();
+
+        // Tensor proof of work isn't enabled, so an AI3 block is refused before its proof is looked at
+        let block = next_block(&chain, Some(ai3_proof()), 2 * chain.difficulty);
+        let error = chain.add_block(block).unwrap_err().to_string();
+        assert!(error.contains("doesn't enable"), "{}", error);
+
+        // Nor is proof of stake, so a validator-signed block is refused too
+        let mut signed = next_block(&chain, None, chain.difficulty);
+        signed.sign_as_validator("validator-key");
+        assert!(chain.add_block(signed).unwrap_err().to_string().contains("doesn't enable"));
+
+        let block = next_block(&chain, None, chain.difficulty);
+        chain.add_block(block).unwrap();
+        assert_eq!(chain.blocks.len(), 2);
+    }
+}
@@ -0,0 +1,286 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use crate::{Block, StateTrie, TribeResult, TribeError, MAINNET_CHAIN_ID, TESTNET_CHAIN_ID, DEVNET_CHAIN_ID};
+
+/// Consensus types supported
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConsensusType {
+    ProofOfWork,
+    ProofOfStake,
+    DelegatedProofOfStake,
+    TensorProofOfWork, // AI3-specific consensus
+}
+
+/// How proof-of-work difficulty starts and is retargeted
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DifficultyParams {
+    pub initial: u64,
+    pub minimum: u64,
+    pub adjustment_window: usize, // Blocks compared against the target block time
+    pub ai3_multiplier: f32, // AI3 blocks are mined at this multiple of the difficulty
+    #[serde(default)]
+    pub fixed: bool, // Never retargeted, for chains mined on demand
+}
+
+/// How the block reward shrinks with height, starting from the spec's `mining_reward`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EmissionSchedule {
+    #[default]
+    Constant,
+    Halving { interval: u64 }, // Reward halves every `interval` blocks
+    Decay { interval: u64, percent: u64 }, // Reward shrinks by `percent`% every `interval` blocks
+}
+
+/// Token created in the genesis state with its whole supply held by `owner`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GenesisToken {
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+    pub total_supply: u64,
+    pub owner: String,
+    #[serde(default)]
+    pub mintable: bool,
+}
+
+/// Parameters and genesis state of a network, loaded from TOML or JSON
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChainSpec {
+    pub name: String,
+    pub chain_id: u64,
+    pub genesis_timestamp: u64,
+    #[serde(default)]
+    pub genesis_allocations: BTreeMap<String, u64>, // address -> native balance
+    pub block_time_secs: u64, // Target time between blocks
+    pub max_block_size: usize, // Serialized bytes
+    pub mining_reward: u64, // Reward for the first blocks; `emission` shrinks it from there
+    #[serde(default)]
+    pub emission: EmissionSchedule,
+    #[serde(default)]
+    pub coinbase_maturity: u64, // Blocks before a mining reward can be spent
+    pub difficulty: DifficultyParams,
+    pub consensus: Vec<ConsensusType>,
+    #[serde(default)]
+    pub tokens: Vec<GenesisToken>,
+}
+
+/// Names accepted by `ChainSpec::preset`
+pub const CHAIN_PRESETS: [&str; 3] = ["mainnet", "testnet", "dev"];
+
+const TRIBE: u64 = 1_000_000; // Base units per TRIBE
+
+impl Default for ChainSpec {
+    fn default() -> Self {
+        Self::mainnet()
+    }
+}
+
+impl GenesisToken {
+    /// ID the token gets on `chain_id`, the same on every node
+    pub fn token_id(&self, chain_id: u64) -> String {
+        use sha2::{Digest, Sha256};
+
+        let mut hasher = Sha256::new();
+        hasher.update(chain_id.to_le_bytes());
+        hasher.update(self.symbol.as_bytes());
+        hasher.update(self.owner.as_bytes());
+        hex::encode(&hasher.finalize()[..16])
+    }
+}
+
+impl ChainSpec {
+    pub fn mainnet() -> Self {
+        Self {
+            name: "mainnet".to_string(),
+            chain_id: MAINNET_CHAIN_ID,
+            genesis_timestamp: 1640995200, // Jan 1, 2022
+            genesis_allocations: BTreeMap::from([("genesis".to_string(), 1_000_000 * TRIBE)]),
+            block_time_secs: 60,
+            max_block_size: 1024 * 1024,
+            mining_reward: 50 * TRIBE,
+            emission: EmissionSchedule::Halving { interval: 210_000 },
+            coinbase_maturity: 100,
+            difficulty: DifficultyParams { initial: 4, minimum: 1, adjustment_window: 10, ai3_multiplier: 1.5, fixed: false },
+            consensus: vec![ConsensusType::ProofOfWork, ConsensusType::TensorProofOfWork],
+            tokens: Vec::new(),
+        }
+    }
+
+    pub fn testnet() -> Self {
+        Self {
+            name: "testnet".to_string(),
+            chain_id: TESTNET_CHAIN_ID,
+            genesis_allocations: BTreeMap::from([
+                ("genesis".to_string(), 1_000_000 * TRIBE),
+                ("faucet".to_string(), 100_000 * TRIBE),
+            ]),
+            difficulty: DifficultyParams { initial: 3, ..Self::mainnet().difficulty },
+            ..Self::mainnet()
+        }
+    }
+
+    /// Single-node development chain: low difficulty, fast blocks and a funded `dev` account
+    pub fn dev() -> Self {
+        Self {
+            name: "dev".to_string(),
+            chain_id: DEVNET_CHAIN_ID,
+            genesis_allocations: BTreeMap::from([
+                ("genesis".to_string(), 1_000_000 * TRIBE),
+                ("dev".to_string(), 1_000_000 * TRIBE),
+            ]),
+            block_time_secs: 5,
+            emission: EmissionSchedule::Constant,
+            coinbase_maturity: 0, // Rewards spendable at once
+            difficulty: DifficultyParams { initial: 1, minimum: 1, adjustment_window: 10, ai3_multiplier: 1.0, fixed: false },
+            consensus: vec![
+                ConsensusType::ProofOfWork,
+                ConsensusType::TensorProofOfWork,
+                ConsensusType::ProofOfStake,
+                ConsensusType::DelegatedProofOfStake,
+            ],
+            tokens: vec![GenesisToken {
+                name: "Dev Token".to_string(),
+                symbol: "DEV".to_string(),
+                decimals: 6,
+                total_supply: 1_000_000 * TRIBE,
+                owner: "dev".to_string(),
+                mintable: true,
+            }],
+            ..Self::mainnet()
+        }
+    }
+
+    /// Built-in spec by name, one of `CHAIN_PRESETS`
+    pub fn preset(name: &str) -> Option<Self> {
+        match name {
+            "mainnet" => Some(Self::mainnet()),
+            "testnet" => Some(Self::testnet()),
+            "dev" | "devnet" => Some(Self::dev()),
+            _ => None,
+        }
+    }
+
+    /// Load a spec file, parsed as TOML for `.toml` files and JSON otherwise
+    pub fn load(path: &Path) -> TribeResult<Self> {
+        let data = std::fs::read_to_string(path)
+            .map_err(|e| TribeError::Storage(format!("Failed to read chain spec {}: {}", path.display(), e)))?;
+        let spec: Self = if path.extension().is_some_and(|ext| ext == "toml") {
+            toml::from_str(&data).map_err(|e| TribeError::Storage(format!("Invalid chain spec {}: {}", path.display(), e)))?
+        } else {
+            serde_json::from_str(&data).map_err(|e| TribeError::Storage(format!("Invalid chain spec {}: {}", path.display(), e)))?
+        };
+        spec.validate()?;
+        Ok(spec)
+    }
+
+    /// A preset by name, or else a spec file at that path
+    pub fn from_name_or_path(chain: &str) -> TribeResult<Self> {
+        match Self::preset(chain) {
+            Some(spec) => Ok(spec),
+            None => Self::load(Path::new(chain)),
+        }
+    }
+
+    /// Write the spec in the format `load` reads back for `path`
+    pub fn save(&self, path: &Path) -> TribeResult<()> {
+        let data = if path.extension().is_some_and(|ext| ext == "toml") {
+            toml::to_string_pretty(self).map_err(|e| TribeError::Storage(format!("Failed to serialize chain spec: {}", e)))?
+        } else {
+            serde_json::to_string_pretty(self).map_err(|e| TribeError::Storage(format!("Failed to serialize chain spec: {}", e)))?
+        };
+        std::fs::write(path, data)
+            .map_err(|e| TribeError::Storage(format!("Failed to write chain spec {}: {}", path.display(), e)))
+    }
+
+    pub fn validate(&self) -> TribeResult<()> {
+        let invalid = |message: &str| Err(TribeError::Blockchain(format!("Chain spec {}: {}", self.name, message)));
+        if self.block_time_secs == 0 || self.max_block_size == 0 {
+            return invalid("block time and max block size must be positive");
+        }
+        if self.difficulty.minimum == 0 || self.difficulty.initial < self.difficulty.minimum {
+            return invalid("initial difficulty must be at least the minimum, which must be positive");
+        }
+        if self.difficulty.adjustment_window < 2 {
+            return invalid("difficulty adjustment window must span at least 2 blocks");
+        }
+        if self.consensus.is_empty() {
+            return invalid("no consensus enabled");
+        }
+        if self.genesis_allocations.values().try_fold(0u64, |total, balance| total.checked_add(*balance)).is_none() {
+            return invalid("genesis allocations overflow the supply");
+        }
+        match self.emission {
+            EmissionSchedule::Halving { interval: 0 } | EmissionSchedule::Decay { interval: 0, .. } => {
+                return invalid("emission interval must be positive");
+            }
+            EmissionSchedule::Decay { percent, .. } if percent > 100 => {
+                return invalid("emission decay is a percentage of at most 100");
+            }
+            _ => {}
+        }
+        for token in &self.tokens {
+            if token.name.is_empty() || token.symbol.is_empty() || token.total_supply == 0 {
+                return invalid("genesis tokens need a name, symbol and supply");
+            }
+        }
+        Ok(())
+    }
+
+    /// Block reward at `height` under the emission schedule, before any AI3 bonus
+    pub fn reward_at(&self, height: u64) -> u64 {
+        match self.emission {
+            EmissionSchedule::Constant => self.mining_reward,
+            EmissionSchedule::Halving { interval } => {
+                let halvings = height / interval;
+                if halvings >= 64 { 0 } else { self.mining_reward >> halvings }
+            }
+            EmissionSchedule::Decay { interval, percent } => {
+                let mut reward = self.mining_reward as u128;
+                for _ in 0..height / interval {
+                    if reward == 0 {
+                        break;
+                    }
+                    reward = reward * (100 - percent as u128) / 100;
+                }
+                reward as u64
+            }
+        }
+    }
+
+    pub fn is_enabled(&self, consensus: ConsensusType) -> bool {
+        self.consensus.contains(&consensus)
+    }
+
+    /// Consensus that produced `block`, if this chain enables it: a validator's signature
+    /// marks either form of proof of stake, an AI3 proof tensor proof of work, and
+    /// anything else plain proof of work
+    pub fn block_consensus(&self, block: &Block) -> Option<ConsensusType> {
+        let candidates: &[ConsensusType] = if block.validator_signature.is_some() {
+            &[ConsensusType::ProofOfStake, ConsensusType::DelegatedProofOfStake]
+        } else if block.ai3_proof.is_some() {
+            &[ConsensusType::TensorProofOfWork]
+        } else {
+            &[ConsensusType::ProofOfWork]
+        };
+        candidates.iter().copied().find(|consensus| self.is_enabled(*consensus))
+    }
+
+    /// Balances before the first block
+    pub fn genesis_state(&self) -> StateTrie {
+        let mut state = StateTrie::new();
+        for (address, balance) in &self.genesis_allocations {
+            state.insert(address.clone(), *balance);
+        }
+        state
+    }
+
+    /// Genesis block, committing to the genesis allocations
+    pub fn genesis_block(&self) -> Block {
+        let mut genesis = Block::genesis();
+        genesis.timestamp = self.genesis_timestamp;
+        genesis.state_root = self.genesis_state().root();
+        genesis.hash = genesis.calculate_hash();
+        genesis
+    }
+}
@@ -0,0 +1,602 @@
+use serde::de::{DeserializeOwned, Error as _};
+use serde::ser::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+use crate::blockchain::default_ai3_reveal_delay;
+use crate::{AI3Proof, Block, BlockHeader, ChainSpec, MinerInfo, TensorTask, Transaction, TransactionType, TribeChain, TribeResult, TribeError, BLOCK_GAS_LIMIT, MAINNET_CHAIN_ID};
+
+/// Layouts written by this version; bumped whenever a field is added or changed.
+/// Version 1 is the layout the first release wrote, without a version byte. Bincode
+/// is positional, so `#[serde(default)]` never fills in a field missing from an older
+/// record; every earlier layout is spelled out below instead.
+pub const BLOCK_ENCODING_VERSION: u8 = 3;
+pub const HEADER_ENCODING_VERSION: u8 = 3;
+pub const TRANSACTION_ENCODING_VERSION: u8 = 3;
+pub const CHAIN_ENCODING_VERSION: u8 = 2;
+
+fn codec_error(what: &str, message: impl std::fmt::Display) -> TribeError {
+    TribeError::Storage(format!("Failed to decode {}: {}", what, message))
+}
+
+fn decode_body<T: DeserializeOwned>(body: &[u8], what: &str) -> TribeResult<T> {
+    bincode::deserialize(body).map_err(|e| codec_error(what, e))
+}
+
+/// A record stored and sent as a version byte followed by the bincode of that
+/// version's layout. Decoding accepts every earlier layout, upgrading it, so adding
+/// a field doesn't strand existing databases or peers.
+pub trait Versioned: Serialize + DeserializeOwned {
+    const VERSION: u8;
+    const NAME: &'static str;
+
+    /// Decode the body of an earlier `version`
+    fn decode_legacy(version: u8, _body: &[u8]) -> TribeResult<Self> {
+        Err(codec_error(Self::NAME, format!("unknown encoding version {}", version)))
+    }
+
+    /// Decode a record written before records carried a version byte
+    fn decode_unversioned(data: &[u8]) -> TribeResult<Self> {
+        decode_body(data, Self::NAME)
+    }
+
+    fn encode_versioned(&self) -> TribeResult<Vec<u8>> {
+        let mut data = vec![Self::VERSION];
+        bincode::serialize_into(&mut data, self)
+            .map_err(|e| TribeError::Storage(format!("Failed to serialize {}: {}", Self::NAME, e)))?;
+        Ok(data)
+    }
+
+    fn decode_versioned(data: &[u8]) -> TribeResult<Self> {
+        let (&version, body) = data.split_first().ok_or_else(|| codec_error(Self::NAME, "empty record"))?;
+        if version == Self::VERSION {
+            decode_body(body, Self::NAME)
+        } else if version > Self::VERSION {
+            Err(codec_error(Self::NAME, format!(
+                "encoding version {} is newer than the {} this node reads; upgrade it", version, Self::VERSION
+            )))
+        } else {
+            Self::decode_legacy(version, body)
+        }
+    }
+}
+
+/// `Transaction` as first released, before chain IDs and gas prices
+#[derive(Deserialize)]
+struct TransactionV1 {
+    id: String,
+    from: String,
+    transaction_type: TransactionType,
+    fee: u64,
+    timestamp: u64,
+    nonce: u64,
+    signature: String,
+    hash: String,
+}
+
+impl From<TransactionV1> for Transaction {
+    fn from(v1: TransactionV1) -> Self {
+        Transaction {
+            id: v1.id,
+            from: v1.from,
+            transaction_type: v1.transaction_type,
+            fee: v1.fee,
+            timestamp: v1.timestamp,
+            nonce: v1.nonce,
+            chain_id: MAINNET_CHAIN_ID,
+            gas_price: 0,
+            public_key: String::new(),
+            signature: v1.signature,
+            hash: v1.hash,
+        }
+    }
+}
+
+/// `Transaction` before public keys were attached to signatures
+#[derive(Deserialize)]
+#[cfg_attr(test, derive(Serialize))]
+struct TransactionV2 {
+    id: String,
+    from: String,
+    transaction_type: TransactionType,
+    fee: u64,
+    timestamp: u64,
+    nonce: u64,
+    chain_id: u64,
+    gas_price: u64,
+    signature: String,
+    hash: String,
+}
+
+impl From<TransactionV2> for Transaction {
+    fn from(v2: TransactionV2) -> Self {
+        Transaction {
+            id: v2.id,
+            from: v2.from,
+            transaction_type: v2.transaction_type,
+            fee: v2.fee,
+            timestamp: v2.timestamp,
+            nonce: v2.nonce,
+            chain_id: v2.chain_id,
+            gas_price: v2.gas_price,
+            public_key: String::new(),
+            signature: v2.signature,
+            hash: v2.hash,
+        }
+    }
+}
+
+/// `AI3Proof` as first released, before commit-reveal
+#[derive(Deserialize)]
+struct AI3ProofV1 {
+    task_id: String,
+    optimization_factor: f32,
+    tensor_hash: String,
+    computation_time: u64,
+    miner_signature: String,
+}
+
+impl From<AI3ProofV1> for AI3Proof {
+    fn from(v1: AI3ProofV1) -> Self {
+        AI3Proof {
+            task_id: v1.task_id,
+            optimization_factor: v1.optimization_factor,
+            tensor_hash: v1.tensor_hash,
+            computation_time: v1.computation_time,
+            miner_signature: v1.miner_signature,
+            commitment_nonce: 0,
+        }
+    }
+}
+
+/// `Block` as first released, before validator signatures, gas limits and state roots
+#[derive(Deserialize)]
+struct BlockV1 {
+    index: u64,
+    timestamp: u64,
+    previous_hash: String,
+    hash: String,
+    nonce: u64,
+    difficulty: u64,
+    transactions: Vec<TransactionV1>,
+    miner: String,
+    merkle_root: String,
+    ai3_proof: Option<AI3ProofV1>,
+}
+
+impl From<BlockV1> for Block {
+    fn from(v1: BlockV1) -> Self {
+        Block {
+            index: v1.index,
+            timestamp: v1.timestamp,
+            previous_hash: v1.previous_hash,
+            hash: v1.hash,
+            nonce: v1.nonce,
+            difficulty: v1.difficulty,
+            transactions: v1.transactions.into_iter().map(Transaction::from).collect(),
+            miner: v1.miner,
+            merkle_root: v1.merkle_root,
+            ai3_proof: v1.ai3_proof.map(AI3Proof::from),
+            validator_signature: None,
+            gas_limit: BLOCK_GAS_LIMIT,
+            state_root: String::new(),
+        }
+    }
+}
+
+/// `Block` with transactions from before public keys were attached
+#[derive(Deserialize)]
+struct BlockV2 {
+    index: u64,
+    timestamp: u64,
+    previous_hash: String,
+    hash: String,
+    nonce: u64,
+    difficulty: u64,
+    transactions: Vec<TransactionV2>,
+    miner: String,
+    merkle_root: String,
+    ai3_proof: Option<AI3Proof>,
+    validator_signature: Option<String>,
+    gas_limit: u64,
+    state_root: String,
+}
+
+impl From<BlockV2> for Block {
+    fn from(v2: BlockV2) -> Self {
+        Block {
+            index: v2.index,
+            timestamp: v2.timestamp,
+            previous_hash: v2.previous_hash,
+            hash: v2.hash,
+            nonce: v2.nonce,
+            difficulty: v2.difficulty,
+            transactions: v2.transactions.into_iter().map(Transaction::from).collect(),
+            miner: v2.miner,
+            merkle_root: v2.merkle_root,
+            ai3_proof: v2.ai3_proof,
+            validator_signature: v2.validator_signature,
+            gas_limit: v2.gas_limit,
+            state_root: v2.state_root,
+        }
+    }
+}
+
+/// `TribeChain` snapshot as first released, with blocks and transactions inline in
+/// their first layouts and balances as a plain map
+#[derive(Deserialize)]
+struct TribeChainV1 {
+    blocks: Vec<BlockV1>,
+    pending_transactions: Vec<TransactionV1>,
+    difficulty: u64,
+    mining_reward: u64,
+    balances: HashMap<String, u64>,
+    tensor_tasks: Vec<TensorTask>,
+    active_miners: HashMap<String, MinerInfo>,
+    ai3_difficulty_multiplier: f32,
+}
+
+impl From<TribeChainV1> for TribeChain {
+    fn from(v1: TribeChainV1) -> Self {
+        let blocks: Vec<Block> = v1.blocks.into_iter().map(Block::from).collect();
+
+        // Nonces weren't tracked yet; each sender's next one follows its last confirmed
+        let mut account_nonces = HashMap::new();
+        for transaction in blocks.iter().flat_map(|block| &block.transactions) {
+            let next = account_nonces.entry(transaction.from.clone()).or_insert(0);
+            *next = (*next).max(transaction.nonce + 1);
+        }
+
+        TribeChain {
+            blocks,
+            pending_transactions: v1.pending_transactions.into_iter().map(Transaction::from).collect(),
+            orphan_transactions: Default::default(),
+            difficulty: v1.difficulty,
+            mining_reward: v1.mining_reward,
+            balances: v1.balances.into(),
+            storage: None,
+            tensor_tasks: v1.tensor_tasks,
+            active_miners: v1.active_miners,
+            ai3_difficulty_multiplier: v1.ai3_difficulty_multiplier,
+            ai3_commitments: HashMap::new(),
+            ai3_reveal_delay: default_ai3_reveal_delay(),
+            delegate_votes: HashMap::new(),
+            finality: Default::default(),
+            chain_id: MAINNET_CHAIN_ID,
+            address_index: Default::default(),
+            blooms: Vec::new(),
+            receipts: Default::default(),
+            account_nonces,
+            chain_spec: ChainSpec::mainnet(),
+        }
+    }
+}
+
+/// `BlockHeader` before state roots
+#[derive(Deserialize)]
+struct BlockHeaderV1 {
+    index: u64,
+    timestamp: u64,
+    previous_hash: String,
+    hash: String,
+    nonce: u64,
+    difficulty: u64,
+    miner: String,
+    merkle_root: String,
+    gas_limit: u64,
+    transaction_count: usize,
+}
+
+impl From<BlockHeaderV1> for BlockHeader {
+    fn from(v1: BlockHeaderV1) -> Self {
+        BlockHeader {
+            index: v1.index,
+            timestamp: v1.timestamp,
+            previous_hash: v1.previous_hash,
+            hash: v1.hash,
+            nonce: v1.nonce,
+            difficulty: v1.difficulty,
+            miner: v1.miner,
+            merkle_root: v1.merkle_root,
+            gas_limit: v1.gas_limit,
+            transaction_count: v1.transaction_count,
+            state_root: String::new(),
+            bloom: None,
+        }
+    }
+}
+
+/// `BlockHeader` before blooms
+#[derive(Deserialize)]
+#[cfg_attr(test, derive(Serialize))]
+struct BlockHeaderV2 {
+    index: u64,
+    timestamp: u64,
+    previous_hash: String,
+    hash: String,
+    nonce: u64,
+    difficulty: u64,
+    miner: String,
+    merkle_root: String,
+    gas_limit: u64,
+    transaction_count: usize,
+    state_root: String,
+}
+
+impl From<BlockHeaderV2> for BlockHeader {
+    fn from(v2: BlockHeaderV2) -> Self {
+        BlockHeader {
+            index: v2.index,
+            timestamp: v2.timestamp,
+            previous_hash: v2.previous_hash,
+            hash: v2.hash,
+            nonce: v2.nonce,
+            difficulty: v2.difficulty,
+            miner: v2.miner,
+            merkle_root: v2.merkle_root,
+            gas_limit: v2.gas_limit,
+            transaction_count: v2.transaction_count,
+            state_root: v2.state_root,
+            bloom: None,
+        }
+    }
+}
+
+/// Embedded transactions are part of the block layout; changing theirs bumps both versions
+impl Versioned for Block {
+    const VERSION: u8 = BLOCK_ENCODING_VERSION;
+    const NAME: &'static str = "block";
+
+    fn decode_legacy(version: u8, body: &[u8]) -> TribeResult<Self> {
+        match version {
+            1 => decode_body::<BlockV1>(body, Self::NAME).map(Block::from),
+            2 => decode_body::<BlockV2>(body, Self::NAME).map(Block::from),
+            _ => Err(codec_error(Self::NAME, format!("unknown encoding version {}", version))),
+        }
+    }
+
+    fn decode_unversioned(data: &[u8]) -> TribeResult<Self> {
+        Self::decode_legacy(1, data)
+    }
+}
+
+impl Versioned for BlockHeader {
+    const VERSION: u8 = HEADER_ENCODING_VERSION;
+    const NAME: &'static str = "block header";
+
+    fn decode_legacy(version: u8, body: &[u8]) -> TribeResult<Self> {
+        match version {
+            1 => decode_body::<BlockHeaderV1>(body, Self::NAME).map(BlockHeader::from),
+            2 => decode_body::<BlockHeaderV2>(body, Self::NAME).map(BlockHeader::from),
+            _ => Err(codec_error(Self::NAME, format!("unknown encoding version {}", version))),
+        }
+    }
+
+    // Headers stored before versioning predate blooms
+    fn decode_unversioned(data: &[u8]) -> TribeResult<Self> {
+        Self::decode_legacy(2, data).or_else(|_| Self::decode_legacy(1, data))
+    }
+}
+
+impl Versioned for Transaction {
+    const VERSION: u8 = TRANSACTION_ENCODING_VERSION;
+    const NAME: &'static str = "transaction";
+
+    fn decode_legacy(version: u8, body: &[u8]) -> TribeResult<Self> {
+        match version {
+            1 => decode_body::<TransactionV1>(body, Self::NAME).map(Transaction::from),
+            2 => decode_body::<TransactionV2>(body, Self::NAME).map(Transaction::from),
+            _ => Err(codec_error(Self::NAME, format!("unknown encoding version {}", version))),
+        }
+    }
+
+    fn decode_unversioned(data: &[u8]) -> TribeResult<Self> {
+        Self::decode_legacy(1, data)
+    }
+}
+
+/// Blocks and pending transactions are stored as versioned records inside the
+/// snapshot, so changing their layouts doesn't change this one
+impl Versioned for TribeChain {
+    const VERSION: u8 = CHAIN_ENCODING_VERSION;
+    const NAME: &'static str = "blockchain";
+
+    fn decode_legacy(version: u8, body: &[u8]) -> TribeResult<Self> {
+        match version {
+            1 => decode_body::<TribeChainV1>(body, Self::NAME).map(TribeChain::from),
+            _ => Err(codec_error(Self::NAME, format!("unknown encoding version {}", version))),
+        }
+    }
+
+    fn decode_unversioned(data: &[u8]) -> TribeResult<Self> {
+        Self::decode_legacy(1, data)
+    }
+}
+
+/// `#[serde(with)]` for a versioned field: the versioned bytes in binary formats,
+/// the plain value in human-readable ones
+pub mod versioned {
+    use super::*;
+
+    pub fn serialize<T: Versioned, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            return value.serialize(serializer);
+        }
+        value.encode_versioned().map_err(S::Error::custom)?.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, T: Versioned, D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error> {
+        if deserializer.is_human_readable() {
+            return T::deserialize(deserializer);
+        }
+        T::decode_versioned(&Vec::<u8>::deserialize(deserializer)?).map_err(D::Error::custom)
+    }
+}
+
+/// `#[serde(with)]` for a list of versioned records, encoded like `versioned`
+pub mod versioned_vec {
+    use super::*;
+
+    pub fn serialize<T: Versioned, S: Serializer>(values: &[T], serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            return values.serialize(serializer);
+        }
+        values.iter()
+            .map(Versioned::encode_versioned)
+            .collect::<TribeResult<Vec<_>>>()
+            .map_err(S::Error::custom)?
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, T: Versioned, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<T>, D::Error> {
+        if deserializer.is_human_readable() {
+            return Vec::<T>::deserialize(deserializer);
+        }
+        Vec::<Vec<u8>>::deserialize(deserializer)?
+            .iter()
+            .map(|data| T::decode_versioned(data))
+            .collect::<TribeResult<_>>()
+            .map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Written with bincode by the first release (commit 9a0163f), before records
+    // carried a version byte
+    const BASELINE_TRANSACTION: &[u8] = include_bytes!("../fixtures/baseline/transaction.bin");
+    const BASELINE_BLOCK: &[u8] = include_bytes!("../fixtures/baseline/block.bin");
+    const BASELINE_BLOCKCHAIN: &[u8] = include_bytes!("../fixtures/baseline/blockchain.bin");
+
+    fn assert_baseline_transaction(transaction: &Transaction) {
+        assert_eq!(transaction.id, "tx-1");
+        assert_eq!(transaction.from, "alice");
+        assert!(matches!(&transaction.transaction_type, TransactionType::Transfer { to, amount: 5 } if to == "bob"));
+        assert_eq!((transaction.fee, transaction.timestamp, transaction.nonce), (1, 1_700_000_000, 0));
+        assert_eq!((transaction.chain_id, transaction.gas_price), (MAINNET_CHAIN_ID, 0));
+        assert_eq!((transaction.signature.as_str(), transaction.hash.as_str()), ("sig", "tx-hash"));
+    }
+
+    fn assert_baseline_block(block: &Block) {
+        assert_eq!((block.index, block.timestamp, block.nonce, block.difficulty), (1, 1_700_000_100, 42, 4));
+        assert_eq!((block.previous_hash.as_str(), block.hash.as_str()), ("parent", "block-hash"));
+        assert_eq!((block.miner.as_str(), block.merkle_root.as_str()), ("miner", "root"));
+        assert_eq!(block.transactions.len(), 1);
+        assert_baseline_transaction(&block.transactions[0]);
+        let proof = block.ai3_proof.as_ref().unwrap();
+        assert_eq!((proof.task_id.as_str(), proof.tensor_hash.as_str(), proof.miner_signature.as_str()), ("task", "tensor", "miner-sig"));
+        assert_eq!((proof.optimization_factor, proof.computation_time, proof.commitment_nonce), (1.5, 7, 0));
+        assert_eq!((block.validator_signature.as_deref(), block.gas_limit, block.state_root.as_str()), (None, BLOCK_GAS_LIMIT, ""));
+    }
+
+    #[test]
+    fn test_baseline_transaction_round_trips() {
+        let transaction = Transaction::decode_unversioned(BASELINE_TRANSACTION).unwrap();
+        assert_baseline_transaction(&transaction);
+
+        // Prefixed with version 1 it is the same record
+        let versioned = [&[1u8][..], BASELINE_TRANSACTION].concat();
+        assert_baseline_transaction(&Transaction::decode_versioned(&versioned).unwrap());
+
+        let encoded = transaction.encode_versioned().unwrap();
+        assert_eq!(encoded[0], TRANSACTION_ENCODING_VERSION);
+        assert_baseline_transaction(&Transaction::decode_versioned(&encoded).unwrap());
+    }
+
+    #[test]
+    fn test_baseline_block_round_trips() {
+        let block = Block::decode_unversioned(BASELINE_BLOCK).unwrap();
+        assert_baseline_block(&block);
+
+        let encoded = block.encode_versioned().unwrap();
+        assert_eq!(encoded[0], BLOCK_ENCODING_VERSION);
+        assert_baseline_block(&Block::decode_versioned(&encoded).unwrap());
+
+        // The current layout isn't mistaken for the baseline one
+        assert!(Block::decode_unversioned(&encoded[1..]).is_err());
+    }
+
+    #[test]
+    fn test_baseline_snapshot_round_trips() {
+        let chain = TribeChain::decode_unversioned(BASELINE_BLOCKCHAIN).unwrap();
+        assert_eq!(chain.blocks.len(), 1);
+        assert_baseline_block(&chain.blocks[0]);
+        assert_baseline_transaction(&chain.pending_transactions[0]);
+        assert_eq!((chain.difficulty, chain.mining_reward, chain.ai3_difficulty_multiplier), (4, 50_000_000, 1.5));
+        assert_eq!(chain.balances.get("alice"), Some(95));
+        assert_eq!(chain.tensor_tasks[0].input_data, vec![1.0, -1.0]);
+        assert_eq!(chain.active_miners["miner"].hash_rate, 100);
+        assert_eq!(chain.account_nonces.get("alice"), Some(&1));
+        assert_eq!(chain.chain_id, MAINNET_CHAIN_ID);
+
+        let encoded = chain.encode_versioned().unwrap();
+        assert_eq!(encoded[0], CHAIN_ENCODING_VERSION);
+        let decoded = TribeChain::decode_versioned(&encoded).unwrap();
+        assert_baseline_block(&decoded.blocks[0]);
+        assert_eq!(decoded.balances, chain.balances);
+        assert_eq!(decoded.account_nonces, chain.account_nonces);
+        assert_eq!(decoded.ai3_reveal_delay, chain.ai3_reveal_delay);
+    }
+
+    #[test]
+    fn test_unsigned_key_layout_upgrades() {
+        let v2 = TransactionV2 {
+            id: "tx-2".to_string(),
+            from: "alice".to_string(),
+            transaction_type: TransactionType::Transfer { to: "bob".to_string(), amount: 5 },
+            fee: 1,
+            timestamp: 1_700_000_000,
+            nonce: 3,
+            chain_id: 7,
+            gas_price: 2,
+            signature: "sig".to_string(),
+            hash: "tx-hash".to_string(),
+        };
+        let versioned = [vec![2u8], bincode::serialize(&v2).unwrap()].concat();
+        let transaction = Transaction::decode_versioned(&versioned).unwrap();
+        assert_eq!((transaction.nonce, transaction.chain_id, transaction.gas_price), (3, 7, 2));
+        assert_eq!((transaction.signature.as_str(), transaction.public_key.as_str()), ("sig", ""));
+    }
+
+    #[test]
+    fn test_headers_before_blooms_match_every_query() {
+        let v2 = BlockHeaderV2 {
+            index: 1,
+            timestamp: 1_700_000_100,
+            previous_hash: "parent".to_string(),
+            hash: "block-hash".to_string(),
+            nonce: 42,
+            difficulty: 4,
+            miner: "miner".to_string(),
+            merkle_root: "root".to_string(),
+            gas_limit: BLOCK_GAS_LIMIT,
+            transaction_count: 1,
+            state_root: "state".to_string(),
+        };
+        let body = bincode::serialize(&v2).unwrap();
+        for header in [
+            BlockHeader::decode_versioned(&[vec![2u8], body.clone()].concat()).unwrap(),
+            BlockHeader::decode_unversioned(&body).unwrap(),
+        ] {
+            assert_eq!((header.hash.as_str(), header.state_root.as_str()), ("block-hash", "state"));
+            assert_eq!(header.bloom, None);
+        }
+
+        // Current headers keep theirs
+        let block = Block::decode_unversioned(BASELINE_BLOCK).unwrap();
+        let encoded = block.header().encode_versioned().unwrap();
+        assert_eq!(encoded[0], HEADER_ENCODING_VERSION);
+        let bloom = BlockHeader::decode_versioned(&encoded).unwrap().bloom.unwrap();
+        assert!(bloom.might_contain("alice") && bloom.might_contain("bob"));
+    }
+
+    #[test]
+    fn test_newer_encoding_is_refused() {
+        let mut encoded = Transaction::decode_unversioned(BASELINE_TRANSACTION).unwrap().encode_versioned().unwrap();
+        encoded[0] = TRANSACTION_ENCODING_VERSION + 1;
+        let error = Transaction::decode_versioned(&encoded).unwrap_err().to_string();
+        assert!(error.contains("upgrade"), "{}", error);
+        assert!(Transaction::decode_versioned(&[]).is_err());
+    }
+}
@@ -4,7 +4,8 @@ use std::fmt;
 /// Result type for TribeChain operations
 pub type TribeResult<T> = Result<T, TribeError>;
 
-/// Error types for TribeChain
+/// Error types for TribeChain, one per failure domain. Each has a stable numeric
+/// `code` that RPC responses carry, so clients can branch on the domain.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum TribeError {
     /// Invalid block error
@@ -27,6 +28,117 @@ pub enum TribeError {
     Contract(String),
     /// Generic error
     Generic(String),
+    /// Consensus rule or validator set error
+    Consensus(String),
+    /// Transaction pool admission error
+    Mempool(String),
+    /// Tensor shape or operation error
+    Tensor(String),
+    /// ESP device error
+    Esp(String),
+    /// RPC request error
+    Rpc(String),
+    /// Malformed address or one of another network
+    Address(String),
+    /// Cross-chain bridge transfer or relaying error
+    Bridge(String),
+}
+
+impl TribeError {
+    /// Stable code of the error's domain. Codes are never reused or renumbered;
+    /// new domains take the next free one.
+    pub fn code(&self) -> i32 {
+        match self {
+            TribeError::Generic(_) => 1000,
+            TribeError::InvalidBlock(_) => 1001,
+            TribeError::InvalidTransaction(_) => 1002,
+            TribeError::Mining(_) => 1003,
+            TribeError::Storage(_) => 1004,
+            TribeError::Network(_) => 1005,
+            TribeError::Token(_) => 1006,
+            TribeError::AI3(_) => 1007,
+            TribeError::Blockchain(_) => 1008,
+            TribeError::Contract(_) => 1009,
+            TribeError::Consensus(_) => 1010,
+            TribeError::Mempool(_) => 1011,
+            TribeError::Tensor(_) => 1012,
+            TribeError::Esp(_) => 1013,
+            TribeError::Rpc(_) => 1014,
+            TribeError::Address(_) => 1015,
+            TribeError::Bridge(_) => 1016,
+        }
+    }
+
+    /// Rebuild an error from its code and message, as an RPC client receives them
+    pub fn from_code(code: i32, message: String) -> Option<Self> {
+        let error = match code {
+            1000 => TribeError::Generic(message),
+            1001 => TribeError::InvalidBlock(message),
+            1002 => TribeError::InvalidTransaction(message),
+            1003 => TribeError::Mining(message),
+            1004 => TribeError::Storage(message),
+            1005 => TribeError::Network(message),
+            1006 => TribeError::Token(message),
+            1007 => TribeError::AI3(message),
+            1008 => TribeError::Blockchain(message),
+            1009 => TribeError::Contract(message),
+            1010 => TribeError::Consensus(message),
+            1011 => TribeError::Mempool(message),
+            1012 => TribeError::Tensor(message),
+            1013 => TribeError::Esp(message),
+            1014 => TribeError::Rpc(message),
+            1015 => TribeError::Address(message),
+            1016 => TribeError::Bridge(message),
+            _ => return None,
+        };
+        Some(error)
+    }
+
+    /// Domain name, the variant's
+    pub fn kind(&self) -> &'static str {
+        match self {
+            TribeError::Generic(_) => "Generic",
+            TribeError::InvalidBlock(_) => "InvalidBlock",
+            TribeError::InvalidTransaction(_) => "InvalidTransaction",
+            TribeError::Mining(_) => "Mining",
+            TribeError::Storage(_) => "Storage",
+            TribeError::Network(_) => "Network",
+            TribeError::Token(_) => "Token",
+            TribeError::AI3(_) => "AI3",
+            TribeError::Blockchain(_) => "Blockchain",
+            TribeError::Contract(_) => "Contract",
+            TribeError::Consensus(_) => "Consensus",
+            TribeError::Mempool(_) => "Mempool",
+            TribeError::Tensor(_) => "Tensor",
+            TribeError::Esp(_) => "Esp",
+            TribeError::Rpc(_) => "Rpc",
+            TribeError::Address(_) => "Address",
+            TribeError::Bridge(_) => "Bridge",
+        }
+    }
+
+    /// The message without the domain prefix `Display` adds
+    pub fn message(&self) -> &str {
+        match self {
+            TribeError::Generic(msg)
+            | TribeError::InvalidBlock(msg)
+            | TribeError::InvalidTransaction(msg)
+            | TribeError::Mining(msg)
+            | TribeError::Storage(msg)
+            | TribeError::Network(msg)
+            | TribeError::Token(msg)
+            | TribeError::AI3(msg)
+            | TribeError::Blockchain(msg)
+            | TribeError::Contract(msg)
+            | TribeError::Consensus(msg)
+            | TribeError::Mempool(msg)
+            | TribeError::Tensor(msg)
+            | TribeError::Esp(msg)
+            | TribeError::Rpc(msg)
+            | TribeError::Address(msg)
+            | TribeError::Bridge(msg) => msg,
+        }
+    }
 }
 
 impl fmt::Display for TribeError {
@@ -42,8 +154,55 @@ impl fmt::Display for TribeError {
             TribeError::Blockchain(msg) => write!(f, "Blockchain error: {}", msg),
             TribeError::Contract(msg) => write!(f, "Contract error: {}", msg),
             TribeError::Generic(msg) => write!(f, "Error: {}", msg),
+            TribeError::Consensus(msg) => write!(f, "Consensus error: {}", msg),
+            TribeError::Mempool(msg) => write!(f, "Mempool error: {}", msg),
+            TribeError::Tensor(msg) => write!(f, "Tensor error: {}", msg),
+            TribeError::Esp(msg) => write!(f, "ESP error: {}", msg),
+            TribeError::Rpc(msg) => write!(f, "RPC error: {}", msg),
+            TribeError::Address(msg) => write!(f, "Invalid address: {}", msg),
+            TribeError::Bridge(msg) => write!(f, "Bridge error: {}", msg),
         }
     }
 }
 
-impl std::error::Error for TribeError {} 
\ No newline at end of file
+impl std::error::Error for TribeError {} 
+
+impl From<serde_json::Error> for TribeError {
+    fn from(error: serde_json::Error) -> Self {
+        TribeError::Generic(format!("Serialization failed: {}", error))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn every_domain() -> Vec<TribeError> {
+        (1000..1100).filter_map(|code| TribeError::from_code(code, format!("failure {}", code))).collect()
+    }
+
+    #[test]
+    fn test_codes_survive_the_rpc_round_trip() {
+        let errors = every_domain();
+        assert_eq!(errors.len(), 17);
+        let kinds: HashSet<&str> = errors.iter().map(TribeError::kind).collect();
+        assert_eq!(kinds.len(), errors.len());
+
+        for error in &errors {
+            let rebuilt = TribeError::from_code(error.code(), error.message().to_string()).unwrap();
+            assert_eq!((rebuilt.kind(), rebuilt.message()), (error.kind(), error.message()));
+            assert!(error.to_string().ends_with(error.message()));
+        }
+        // JSON-RPC's own codes aren't node errors
+        assert!(TribeError::from_code(-32602, "Missing address".to_string()).is_none());
+    }
+
+    #[test]
+    fn test_codes_are_stable() {
+        assert_eq!(TribeError::Generic(String::new()).code(), 1000);
+        assert_eq!(TribeError::Contract(String::new()).code(), 1009);
+        assert_eq!(TribeError::Mempool(String::new()).code(), 1011);
+        assert_eq!(TribeError::Bridge(String::new()).code(), 1016);
+    }
+}
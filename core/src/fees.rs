@@ -0,0 +1,150 @@
+use std::collections::{HashMap, VecDeque};
+use crate::{Block, Transaction, TransactionType};
+
+/// Most gas a block may consume
+pub const BLOCK_GAS_LIMIT: u64 = 10_000_000;
+
+/// Lowest gas price the fee estimator will suggest
+pub const MIN_GAS_PRICE: u64 = 1;
+
+/// Recent blocks considered when estimating fees
+pub const FEE_HISTORY_BLOCKS: usize = 20;
+
+/// Gas charged for including a transaction, before any contract execution
+pub fn intrinsic_gas(transaction: &Transaction) -> u64 {
+    let base = 21_000;
+    match &transaction.transaction_type {
+        TransactionType::Transfer { .. } | TransactionType::TokenTransfer { .. } => base,
+        TransactionType::TokenCreate { .. } => base + 50_000,
+        TransactionType::Stake { .. } => base + 10_000,
+        TransactionType::TensorCompute { input_data, .. } => base + 4 * input_data.len() as u64,
+        TransactionType::ContractDeploy { code, constructor_args } => {
+            base + 32_000 + 16 * (code.len() + constructor_args.len()) as u64
+        }
+        TransactionType::ContractCall { args, .. } => base + 16 * args.len() as u64,
+        TransactionType::DelegateVote { delegates } => base + 1_000 * delegates.len() as u64,
+    }
+}
+
+/// Pick pending transactions for a block: highest gas price first, oldest first on
+/// ties, until `gas_limit` is used up. A sender's transactions are taken in nonce
+/// order, so a cheap early nonce holds back its later ones.
+pub fn select_transactions(pending: &[Transaction], gas_limit: u64) -> Vec<Transaction> {
+    let mut by_sender: HashMap<&str, VecDeque<&Transaction>> = HashMap::new();
+    let mut sorted: Vec<&Transaction> = pending.iter().collect();
+    sorted.sort_by_key(|transaction| transaction.nonce);
+    for transaction in sorted {
+        by_sender.entry(transaction.from.as_str()).or_default().push_back(transaction);
+    }
+
+    let mut gas_used = 0;
+    let mut selected = Vec::new();
+    loop {
+        // Best-paying transaction among each sender's next one
+        let best = by_sender.iter()
+            .filter_map(|(sender, queue)| queue.front().map(|transaction| (*sender, *transaction)))
+            .max_by(|(_, a), (_, b)| a.gas_price.cmp(&b.gas_price).then(b.timestamp.cmp(&a.timestamp)).then(b.hash.cmp(&a.hash)));
+        let (sender, transaction) = match best {
+            Some(best) => best,
+            None => break,
+        };
+
+        let gas = intrinsic_gas(transaction);
+        if gas_used + gas <= gas_limit {
+            gas_used += gas;
+            selected.push(transaction.clone());
+            if let Some(queue) = by_sender.get_mut(sender) {
+                queue.pop_front();
+            }
+        } else {
+            by_sender.remove(sender); // Its later nonces can't go in without this one
+        }
+    }
+    selected
+}
+
+/// Suggest a gas price likely to be included within `target_blocks`, from the gas
+/// prices paid in recent blocks. Sooner targets use a higher percentile; when blocks
+/// have spare room the cheapest recent price is enough.
+pub fn estimate_gas_price(recent_blocks: &[Block], target_blocks: u64) -> u64 {
+    let mut prices: Vec<u64> = recent_blocks.iter()
+        .flat_map(|block| &block.transactions)
+        .map(|transaction| transaction.gas_price)
+        .collect();
+    if prices.is_empty() {
+        return MIN_GAS_PRICE;
+    }
+    prices.sort_unstable();
+
+    let gas_used: u64 = recent_blocks.iter().map(Block::gas_used).sum();
+    let gas_limit: u64 = recent_blocks.iter().map(|block| block.gas_limit).sum();
+    let congested = gas_limit > 0 && gas_used * 2 > gas_limit;
+
+    let percentile = if !congested {
+        0
+    } else {
+        match target_blocks {
+            0 | 1 => 90,
+            2 => 75,
+            3..=5 => 50,
+            _ => 25,
+        }
+    };
+    let index = (prices.len() - 1) * percentile / 100;
+    prices[index].max(MIN_GAS_PRICE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transfer(from: &str, nonce: u64, gas_price: u64) -> Transaction {
+        Transaction::new(from.to_string(), TransactionType::Transfer { to: "bob".to_string(), amount: 1 }, 0, nonce)
+            .with_gas_price(gas_price)
+    }
+
+    fn picked(selected: &[Transaction]) -> Vec<(String, u64)> {
+        selected.iter().map(|transaction| (transaction.from.clone(), transaction.nonce)).collect()
+    }
+
+    #[test]
+    fn test_select_transactions_by_gas_price() {
+        let pending = vec![transfer("alice", 0, 5), transfer("carol", 0, 20), transfer("dave", 0, 10)];
+        let selected = select_transactions(&pending, BLOCK_GAS_LIMIT);
+        assert_eq!(picked(&selected), vec![
+            ("carol".to_string(), 0), ("dave".to_string(), 0), ("alice".to_string(), 0),
+        ]);
+    }
+
+    #[test]
+    fn test_select_transactions_keeps_sender_nonce_order() {
+        // Alice's well-paying nonce 1 waits behind her cheap nonce 0
+        let pending = vec![transfer("alice", 1, 50), transfer("carol", 0, 10), transfer("alice", 0, 1)];
+        let selected = select_transactions(&pending, BLOCK_GAS_LIMIT);
+        assert_eq!(picked(&selected), vec![
+            ("carol".to_string(), 0), ("alice".to_string(), 0), ("alice".to_string(), 1),
+        ]);
+    }
+
+    #[test]
+    fn test_select_transactions_respects_gas_limit() {
+        let pending = vec![transfer("alice", 0, 30), transfer("alice", 1, 30), transfer("carol", 0, 20)];
+        let gas = intrinsic_gas(&pending[0]);
+
+        // Room for two: both of alice's outbid carol
+        let selected = select_transactions(&pending, 2 * gas);
+        assert_eq!(picked(&selected), vec![("alice".to_string(), 0), ("alice".to_string(), 1)]);
+        assert!(selected.iter().map(intrinsic_gas).sum::<u64>() <= 2 * gas);
+
+        // A sender whose next nonce doesn't fit loses its later ones too, leaving no gap
+        let pending = vec![
+            Transaction::new("alice".to_string(), TransactionType::ContractDeploy { code: vec![0; 100], constructor_args: Vec::new() }, 0, 0)
+                .with_gas_price(30),
+            transfer("alice", 1, 30),
+            transfer("carol", 0, 20),
+        ];
+        let selected = select_transactions(&pending, gas);
+        assert_eq!(picked(&selected), vec![("carol".to_string(), 0)]);
+        assert!(select_transactions(&pending, gas - 1).is_empty());
+    }
+}
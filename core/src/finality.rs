@@ -0,0 +1,185 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use crate::{TribeResult, TribeError};
+
+/// Block a checkpoint attestation votes for
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub height: u64,
+    pub block_hash: String,
+}
+
+/// A validator's signed vote that a checkpoint is canonical
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Attestation {
+    pub validator: String,
+    pub checkpoint: Checkpoint,
+    pub signature: String,
+}
+
+impl Attestation {
+    /// Sign a checkpoint (simplified - in real implementation would use proper cryptography)
+    pub fn new(validator: String, checkpoint: Checkpoint, private_key: &str) -> Self {
+        let signature = Self::signature_for(&checkpoint, private_key);
+        Self { validator, checkpoint, signature }
+    }
+
+    pub fn verify(&self, public_key: &str) -> bool {
+        self.signature == Self::signature_for(&self.checkpoint, public_key)
+    }
+
+    fn signature_for(checkpoint: &Checkpoint, key: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(format!("{}{}{}", checkpoint.height, checkpoint.block_hash, key).as_bytes());
+        hex::encode(hasher.finalize())
+    }
+}
+
+/// Validator allowed to attest, with its voting weight
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FinalityValidator {
+    pub stake: u64,
+    pub public_key: String,
+}
+
+/// Checkpoint finality: every `interval` blocks validators attest, and a checkpoint
+/// backed by at least 2/3 of total stake becomes final.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FinalityGadget {
+    pub interval: u64,
+    pub validators: HashMap<String, FinalityValidator>,
+    pub finalized: Option<Checkpoint>,
+    votes: HashMap<u64, HashMap<String, Checkpoint>>, // height -> validator -> checkpoint voted for
+}
+
+impl FinalityGadget {
+    pub fn new(interval: u64) -> Self {
+        Self {
+            interval: interval.max(1),
+            validators: HashMap::new(),
+            finalized: None,
+            votes: HashMap::new(),
+        }
+    }
+
+    pub fn set_validators(&mut self, validators: HashMap<String, FinalityValidator>) {
+        self.validators = validators;
+    }
+
+    pub fn is_checkpoint_height(&self, height: u64) -> bool {
+        height > 0 && height.is_multiple_of(self.interval)
+    }
+
+    pub fn finalized_height(&self) -> u64 {
+        self.finalized.as_ref().map(|checkpoint| checkpoint.height).unwrap_or(0)
+    }
+
+    pub fn total_stake(&self) -> u64 {
+        self.validators.values().map(|validator| validator.stake).sum()
+    }
+
+    /// Stake attesting to exactly this checkpoint
+    pub fn attested_stake(&self, checkpoint: &Checkpoint) -> u64 {
+        self.votes.get(&checkpoint.height)
+            .map(|votes| {
+                votes.iter()
+                    .filter(|(_, voted)| *voted == checkpoint)
+                    .filter_map(|(validator, _)| self.validators.get(validator))
+                    .map(|validator| validator.stake)
+                    .sum()
+            })
+            .unwrap_or(0)
+    }
+
+    /// Record an attestation; returns the checkpoint if it just became final
+    pub fn add_attestation(&mut self, attestation: Attestation) -> TribeResult<Option<Checkpoint>> {
+        let checkpoint = attestation.checkpoint.clone();
+        if !self.is_checkpoint_height(checkpoint.height) {
+            return Err(TribeError::InvalidBlock(format!("Height {} is not a checkpoint", checkpoint.height)));
+        }
+        if checkpoint.height <= self.finalized_height() {
+            return Ok(None); // Already settled
+        }
+
+        let validator = self.validators.get(&attestation.validator)
+            .ok_or_else(|| TribeError::InvalidBlock(format!("Unknown validator: {}", attestation.validator)))?;
+        if !attestation.verify(&validator.public_key) {
+            return Err(TribeError::InvalidBlock("Invalid attestation signature".to_string()));
+        }
+
+        // First vote per validator and height counts; a second, conflicting one is ignored
+        self.votes.entry(checkpoint.height)
+            .or_default()
+            .entry(attestation.validator)
+            .or_insert_with(|| checkpoint.clone());
+
+        let total = self.total_stake() as u128;
+        if total > 0 && self.attested_stake(&checkpoint) as u128 * 3 >= total * 2 {
+            self.votes.retain(|height, _| *height > checkpoint.height);
+            self.finalized = Some(checkpoint.clone());
+            return Ok(Some(checkpoint));
+        }
+        Ok(None)
+    }
+}
+
+impl Default for FinalityGadget {
+    fn default() -> Self {
+        Self::new(32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gadget(stakes: &[(&str, u64)]) -> FinalityGadget {
+        let mut gadget = FinalityGadget::new(10);
+        gadget.set_validators(stakes.iter()
+            .map(|(name, stake)| (name.to_string(), FinalityValidator { stake: *stake, public_key: format!("{}_key", name) }))
+            .collect());
+        gadget
+    }
+
+    fn attest(validator: &str, height: u64, block_hash: &str) -> Attestation {
+        let checkpoint = Checkpoint { height, block_hash: block_hash.to_string() };
+        Attestation::new(validator.to_string(), checkpoint, &format!("{}_key", validator))
+    }
+
+    #[test]
+    fn test_checkpoint_finalizes_at_two_thirds_of_stake() {
+        let mut gadget = gadget(&[("v1", 40), ("v2", 30), ("v3", 30)]);
+        assert!(gadget.is_checkpoint_height(20));
+        assert!(!gadget.is_checkpoint_height(0));
+        assert!(!gadget.is_checkpoint_height(15));
+
+        // 40 of 100 is short of 2/3
+        assert_eq!(gadget.add_attestation(attest("v1", 10, "a")).unwrap(), None);
+        // A vote for a competing block doesn't count toward "a"
+        assert_eq!(gadget.add_attestation(attest("v2", 10, "b")).unwrap(), None);
+        assert_eq!(gadget.attested_stake(&Checkpoint { height: 10, block_hash: "a".to_string() }), 40);
+        // A second vote by the same validator is ignored
+        assert_eq!(gadget.add_attestation(attest("v2", 10, "a")).unwrap(), None);
+
+        // 70 of 100 backs "a", which becomes final
+        let finalized = gadget.add_attestation(attest("v3", 10, "a")).unwrap();
+        assert_eq!(finalized, Some(Checkpoint { height: 10, block_hash: "a".to_string() }));
+        assert_eq!(gadget.finalized_height(), 10);
+        // Settled heights take no more votes
+        assert_eq!(gadget.add_attestation(attest("v3", 10, "b")).unwrap(), None);
+        assert_eq!(gadget.finalized.as_ref().unwrap().block_hash, "a");
+    }
+
+    #[test]
+    fn test_invalid_attestations_are_rejected() {
+        let mut gadget = gadget(&[("v1", 1)]);
+        assert!(matches!(gadget.add_attestation(attest("v1", 5, "a")), Err(TribeError::InvalidBlock(_))));
+        assert!(matches!(gadget.add_attestation(attest("mallory", 10, "a")), Err(TribeError::InvalidBlock(_))));
+
+        let checkpoint = Checkpoint { height: 10, block_hash: "a".to_string() };
+        let forged = Attestation::new("v1".to_string(), checkpoint, "mallory_key");
+        assert!(matches!(gadget.add_attestation(forged), Err(TribeError::InvalidBlock(_))));
+        assert_eq!(gadget.finalized_height(), 0);
+    }
+}
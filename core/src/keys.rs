@@ -0,0 +1,49 @@
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use sha2::{Digest, Sha256};
+
+/// Ed25519 key for a private key string. Keystore keys are hex of any length, so the
+/// seed is their SHA-256 rather than the raw bytes.
+fn signing_key(private_key: &str) -> SigningKey {
+    SigningKey::from_bytes(&Sha256::digest(private_key.as_bytes()).into())
+}
+
+/// Public key matching `private_key`, as hex
+pub fn public_key(private_key: &str) -> String {
+    hex::encode(signing_key(private_key).verifying_key().to_bytes())
+}
+
+/// Sign `message`, returning the signature as hex
+pub fn sign(message: &[u8], private_key: &str) -> String {
+    hex::encode(signing_key(private_key).sign(message).to_bytes())
+}
+
+/// Check a hex signature of `message` against a hex public key; malformed hex fails
+pub fn verify(message: &[u8], signature: &str, public_key: &str) -> bool {
+    let key = match hex::decode(public_key).ok().and_then(|bytes| <[u8; 32]>::try_from(bytes).ok()) {
+        Some(bytes) => bytes,
+        None => return false,
+    };
+    let signature = match hex::decode(signature).ok().and_then(|bytes| <[u8; 64]>::try_from(bytes).ok()) {
+        Some(bytes) => Signature::from_bytes(&bytes),
+        None => return false,
+    };
+    VerifyingKey::from_bytes(&key)
+        .map(|key| key.verify(message, &signature).is_ok())
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signatures_verify_only_under_their_key() {
+        let signature = sign(b"message", "alice-key");
+        assert!(verify(b"message", &signature, &public_key("alice-key")));
+        assert!(!verify(b"other message", &signature, &public_key("alice-key")));
+        assert!(!verify(b"message", &signature, &public_key("mallory-key")));
+        // The private key is not a public key
+        assert!(!verify(b"message", &signature, "alice-key"));
+        assert!(!verify(b"message", "not hex", &public_key("alice-key")));
+    }
+}
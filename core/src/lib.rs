@@ -3,10 +3,40 @@ pub mod block;
 pub mod transaction;
 pub mod blockchain;
 pub mod storage;
+pub mod finality;
+pub mod receipts;
+pub mod fees;
+pub mod telemetry;
+pub mod validation;
+pub mod state;
+pub mod chain_spec;
+pub mod codec;
+pub mod archive;
+pub mod maintenance;
+pub mod shutdown;
+pub mod address;
+pub mod keys;
+pub mod orphans;
+#[cfg(test)]
+mod testing;
 
 // Re-export main types
 pub use error::{TribeError, TribeResult};
-pub use block::{Block, AI3Proof};
-pub use transaction::{Transaction, TransactionType};
-pub use blockchain::{TribeChain, MinerInfo, TensorTask, BlockchainStats};
-pub use storage::{Storage, StorageStats}; 
\ No newline at end of file
+pub use block::{Block, BlockHeader, AI3Proof, AI3Commitment, MinedBy, difficulty_work, hash_work, tensor_work_hash, DoubleSignEvidence, ValidatorPerformanceReport};
+pub use transaction::{Transaction, TransactionType, MAINNET_CHAIN_ID, TESTNET_CHAIN_ID, DEVNET_CHAIN_ID};
+pub use blockchain::{TribeChain, RepairOutcome, MinerInfo, TensorTask, BlockchainStats, MiningStatus, DifficultySample, BlockTemplate, ADDRESS_TX_PAGE_SIZE, DIFFICULTY_HISTORY_LIMIT};
+pub use storage::{Storage, MemoryStorage, StorageOp, StorageBackend, StorageConfig, StorageCompaction, StorageStats, ImportIntent, open_storage, AddressIndex, AddressTxRef, COLUMN_FAMILIES};
+#[cfg(feature = "storage")]
+pub use storage::RocksStorage;
+pub use finality::{FinalityGadget, FinalityValidator, Checkpoint, Attestation};
+pub use receipts::{Log, Receipt, LogsBloom, BlockReceipts, LogFilter, MatchedLog};
+pub use fees::{BLOCK_GAS_LIMIT, MIN_GAS_PRICE};
+pub use state::{StateTrie, StateProof, EMPTY_STATE_ROOT};
+pub use codec::{Versioned, BLOCK_ENCODING_VERSION, HEADER_ENCODING_VERSION, TRANSACTION_ENCODING_VERSION, CHAIN_ENCODING_VERSION};
+pub use archive::{ArchiveReader, ArchiveHeader, ArchiveImport, export_blocks, ARCHIVE_MAGIC, ARCHIVE_VERSION};
+pub use maintenance::{MaintenanceScheduler, MaintenanceHandle, MaintenanceConfig, MaintenanceProgress, MaintenanceTask, PruneStats};
+pub use shutdown::{Shutdown, ShutdownGuard};
+pub use address::{Address, hrp_for_chain, check_address_input, MAINNET_HRP, TESTNET_HRP};
+pub use chain_spec::{ChainSpec, ConsensusType, DifficultyParams, EmissionSchedule, GenesisToken, CHAIN_PRESETS};
+pub use orphans::{OrphanTransactions, OrphanTransaction, MissingParent, MAX_ORPHAN_TRANSACTIONS, MAX_ORPHANS_PER_SENDER};
+pub use telemetry::{TelemetryStore, DeviceTelemetry, MetricBucket, MetricSummary, AlertThreshold, TelemetryAlert, TELEMETRY_TIERS};
\ No newline at end of file
@@ -0,0 +1,371 @@
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::{Timelike, Utc};
+use tokio::sync::{watch, Mutex, RwLock};
+use tokio::task::JoinHandle;
+use crate::{Storage, TelemetryStore, TribeChain, TribeResult, TribeError, COLUMN_FAMILIES};
+
+/// State key of the scheduler's progress, reported by `Storage::get_stats`
+pub const MAINTENANCE_KEY: &str = "maintenance";
+
+/// Jobs the scheduler runs, in the order it runs them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MaintenanceTask {
+    Prune, // Old receipts and stale AI3 commitments
+    Compact, // Storage column families, indices included
+    Downsample, // Telemetry buckets past their tier's retention
+}
+
+/// When and how much the scheduler does
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MaintenanceConfig {
+    pub interval_secs: u64, // Between checks for a run
+    pub idle_only: bool, // Skip runs while the node is syncing
+    #[serde(default)]
+    pub window: Option<(u32, u32)>, // UTC hours [start, end) a run may begin in; may wrap midnight
+    pub receipt_retention: u64, // Blocks below the tip whose receipts are kept
+    pub compact: bool,
+    pub downsample_telemetry: bool,
+}
+
+impl Default for MaintenanceConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: 600,
+            idle_only: true,
+            window: None,
+            receipt_retention: 100_000,
+            compact: true,
+            downsample_telemetry: true,
+        }
+    }
+}
+
+impl MaintenanceConfig {
+    pub fn with_interval_secs(mut self, interval_secs: u64) -> Self {
+        self.interval_secs = interval_secs;
+        self
+    }
+
+    pub fn with_idle_only(mut self, idle_only: bool) -> Self {
+        self.idle_only = idle_only;
+        self
+    }
+
+    pub fn with_window(mut self, start_hour: u32, end_hour: u32) -> Self {
+        self.window = Some((start_hour, end_hour));
+        self
+    }
+
+    pub fn with_receipt_retention(mut self, receipt_retention: u64) -> Self {
+        self.receipt_retention = receipt_retention;
+        self
+    }
+
+    pub fn with_compact(mut self, compact: bool) -> Self {
+        self.compact = compact;
+        self
+    }
+
+    pub fn with_downsample_telemetry(mut self, downsample_telemetry: bool) -> Self {
+        self.downsample_telemetry = downsample_telemetry;
+        self
+    }
+
+    /// Whether a run may begin at `hour` (UTC)
+    pub fn in_window(&self, hour: u32) -> bool {
+        match self.window {
+            None => true,
+            Some((start, end)) if start <= end => hour >= start && hour < end,
+            Some((start, end)) => hour >= start || hour < end,
+        }
+    }
+}
+
+/// What the scheduler has done so far; totals count every run since the database was created
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MaintenanceProgress {
+    pub runs: u64,
+    pub skipped: u64, // Checks outside the window or during sync
+    pub running: Option<MaintenanceTask>,
+    pub last_started: u64,
+    pub last_completed: u64,
+    pub pruned_receipts: u64,
+    pub pruned_commitments: u64,
+    pub compactions: u64, // Column families compacted
+    pub downsampled_buckets: u64,
+    pub last_error: Option<String>,
+}
+
+/// What one `TribeChain::prune` removed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PruneStats {
+    pub receipts: u64,
+    pub commitments: u64,
+}
+
+impl TribeChain {
+    /// Drop receipts of blocks more than `receipt_retention` below the tip, and AI3
+    /// commitments whose task is completed or that are too old to still be revealed
+    pub fn prune(&mut self, receipt_retention: u64) -> TribeResult<PruneStats> {
+        let horizon = (self.blocks.len() as u64).saturating_sub(receipt_retention);
+        let kept = self.receipts.split_off(&horizon);
+        let receipts = std::mem::replace(&mut self.receipts, kept).len() as u64;
+
+        let before = self.ai3_commitments.len();
+        let tasks = &self.tensor_tasks;
+        self.ai3_commitments.retain(|_, commitment| {
+            commitment.block_height >= horizon
+                && tasks.iter().any(|task| task.id == commitment.task_id && !task.completed)
+        });
+        let stats = PruneStats { receipts, commitments: (before - self.ai3_commitments.len()) as u64 };
+
+        if stats != PruneStats::default() {
+            if let Some(storage) = &self.storage {
+                storage.save_blockchain(self)?;
+            }
+        }
+        Ok(stats)
+    }
+}
+
+fn load_progress(storage: &dyn Storage) -> TribeResult<MaintenanceProgress> {
+    match storage.load_data(MAINTENANCE_KEY)? {
+        Some(data) => bincode::deserialize(&data)
+            .map_err(|e| TribeError::Storage(format!("Failed to deserialize maintenance progress: {}", e))),
+        None => Ok(MaintenanceProgress::default()),
+    }
+}
+
+/// Runs pruning, compaction and telemetry downsampling in the background, within the
+/// configured window and, if `idle_only`, only while the node isn't syncing
+#[derive(Debug)]
+pub struct MaintenanceScheduler {
+    pub config: MaintenanceConfig,
+    chain: Arc<RwLock<TribeChain>>,
+    telemetry: Option<Arc<Mutex<TelemetryStore>>>,
+    syncing: Arc<AtomicBool>,
+    pub progress: MaintenanceProgress,
+    loaded: bool, // Progress read back from storage
+}
+
+/// Handle to a spawned scheduler
+#[derive(Debug)]
+pub struct MaintenanceHandle {
+    syncing: Arc<AtomicBool>,
+    stop: watch::Sender<bool>,
+    task: JoinHandle<MaintenanceScheduler>,
+}
+
+impl MaintenanceScheduler {
+    pub fn new(chain: Arc<RwLock<TribeChain>>, config: MaintenanceConfig) -> Self {
+        Self {
+            config,
+            chain,
+            telemetry: None,
+            syncing: Arc::new(AtomicBool::new(false)),
+            progress: MaintenanceProgress::default(),
+            loaded: false,
+        }
+    }
+
+    pub fn with_telemetry(mut self, telemetry: Arc<Mutex<TelemetryStore>>) -> Self {
+        self.telemetry = Some(telemetry);
+        self
+    }
+
+    /// Flag the sync loop sets while catching up, shared with the handle
+    pub fn syncing(&self) -> Arc<AtomicBool> {
+        self.syncing.clone()
+    }
+
+    fn should_run(&self, now: u64) -> bool {
+        let hour = chrono::DateTime::from_timestamp(now as i64, 0).map(|time| time.hour()).unwrap_or(0);
+        self.config.in_window(hour) && !(self.config.idle_only && self.syncing.load(Ordering::Relaxed))
+    }
+
+    /// Run every enabled task now if the window and sync state allow it; returns
+    /// whether it ran. A failing task is recorded in the progress and ends the run.
+    pub async fn run_once(&mut self, now: u64) -> TribeResult<bool> {
+        let storage = self.chain.read().await.storage.clone();
+        if !self.loaded {
+            if let Some(storage) = &storage {
+                self.progress = load_progress(storage.as_ref())?;
+            }
+            self.loaded = true;
+        }
+        if !self.should_run(now) {
+            self.progress.skipped += 1;
+            self.save_progress(storage.as_deref())?;
+            return Ok(false);
+        }
+
+        self.progress.last_started = now;
+        let result = self.run_tasks(storage.as_ref(), now).await;
+        self.progress.running = None;
+        self.progress.runs += 1;
+        match &result {
+            Ok(()) => {
+                self.progress.last_completed = now;
+                self.progress.last_error = None;
+            }
+            Err(e) => self.progress.last_error = Some(e.to_string()),
+        }
+        self.save_progress(storage.as_deref())?;
+        result.map(|()| true)
+    }
+
+    async fn run_tasks(&mut self, storage: Option<&Arc<dyn Storage>>, now: u64) -> TribeResult<()> {
+        self.begin(MaintenanceTask::Prune, storage)?;
+        let pruned = self.chain.write().await.prune(self.config.receipt_retention)?;
+        self.progress.pruned_receipts += pruned.receipts;
+        self.progress.pruned_commitments += pruned.commitments;
+
+        if let (true, Some(storage)) = (self.config.compact, storage) {
+            self.begin(MaintenanceTask::Compact, Some(storage))?;
+            for family in COLUMN_FAMILIES {
+                // Compaction blocks on disk I/O; keep it off the async workers
+                let storage = storage.clone();
+                tokio::task::spawn_blocking(move || storage.compact(family))
+                    .await
+                    .map_err(|e| TribeError::Storage(format!("Compaction of {} panicked: {}", family, e)))??;
+                self.progress.compactions += 1;
+            }
+        }
+
+        if let (true, Some(telemetry)) = (self.config.downsample_telemetry, &self.telemetry) {
+            let telemetry = telemetry.clone();
+            self.begin(MaintenanceTask::Downsample, storage)?;
+            self.progress.downsampled_buckets += telemetry.lock().await.downsample(now)? as u64;
+        }
+        Ok(())
+    }
+
+    fn begin(&mut self, task: MaintenanceTask, storage: Option<&Arc<dyn Storage>>) -> TribeResult<()> {
+        self.progress.running = Some(task);
+        self.save_progress(storage.map(Arc::as_ref))
+    }
+
+    fn save_progress(&self, storage: Option<&dyn Storage>) -> TribeResult<()> {
+        let Some(storage) = storage else { return Ok(()) };
+        let serialized = bincode::serialize(&self.progress)
+            .map_err(|e| TribeError::Storage(format!("Failed to serialize maintenance progress: {}", e)))?;
+        storage.save_data(MAINTENANCE_KEY, &serialized)
+    }
+
+    /// Check for a run every `interval_secs` on a tokio task until stopped or the
+    /// handle is dropped. Failed runs are recorded and retried at the next check.
+    pub fn spawn(mut self) -> MaintenanceHandle {
+        let syncing = self.syncing();
+        let (stop, mut stopped) = watch::channel(false);
+        let period = Duration::from_secs(self.config.interval_secs.max(1));
+        let task = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {
+                        let now = Utc::now().timestamp() as u64;
+                        if let Err(e) = self.run_once(now).await {
+                            eprintln!("Maintenance run failed: {}", e);
+                        }
+                    }
+                    _ = stopped.changed() => break,
+                }
+            }
+            self
+        });
+        MaintenanceHandle { syncing, stop, task }
+    }
+}
+
+impl MaintenanceHandle {
+    /// Tell the scheduler whether the node is syncing; `idle_only` runs wait until it isn't
+    pub fn set_syncing(&self, syncing: bool) {
+        self.syncing.store(syncing, Ordering::Relaxed);
+    }
+
+    /// Stop after any run in progress finishes, returning the scheduler
+    pub async fn stop(self) -> TribeResult<MaintenanceScheduler> {
+        let _ = self.stop.send(true);
+        self.task.await
+            .map_err(|e| TribeError::Generic(format!("Maintenance task panicked: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AI3Commitment, TELEMETRY_TIERS};
+    use crate::testing::chain_of;
+
+    /// Dev chain with `length` empty blocks on top of genesis, each with receipts
+    fn chain_with_receipts(length: u64) -> TribeChain {
+        let mut chain = chain_of(length, "miner");
+        for index in 0..=length {
+            chain.attach_receipts(index, Vec::new()).unwrap();
+        }
+        chain
+    }
+
+    #[test]
+    fn test_window_may_wrap_midnight() {
+        let config = MaintenanceConfig::default();
+        assert!((0..24).all(|hour| config.in_window(hour)));
+
+        let night = config.clone().with_window(22, 4);
+        assert!(night.in_window(23) && night.in_window(0) && night.in_window(3));
+        assert!(!night.in_window(4) && !night.in_window(12));
+
+        let office = config.with_window(9, 17);
+        assert!(office.in_window(9) && office.in_window(16));
+        assert!(!office.in_window(17) && !office.in_window(2));
+    }
+
+    #[test]
+    fn test_prune_keeps_recent_receipts_and_live_commitments() {
+        let mut chain = chain_with_receipts(4);
+        chain.ai3_commitments.insert("done:miner".to_string(), AI3Commitment::new("done".to_string(), "miner".to_string(), "hash", 0, 4));
+        chain.ai3_commitments.insert("old:miner".to_string(), AI3Commitment::new("old".to_string(), "miner".to_string(), "hash", 0, 1));
+
+        // Neither commitment's task is open, so both go
+        let stats = chain.prune(2).unwrap();
+        assert_eq!(stats, PruneStats { receipts: 3, commitments: 2 });
+        assert_eq!(chain.receipts.keys().copied().collect::<Vec<_>>(), vec![3, 4]);
+        assert_eq!(chain.prune(2).unwrap(), PruneStats::default());
+    }
+
+    #[tokio::test]
+    async fn test_runs_wait_for_sync_and_progress_persists() {
+        let chain = Arc::new(RwLock::new(chain_with_receipts(3)));
+        let config = MaintenanceConfig::default().with_receipt_retention(1);
+        let mut telemetry = TelemetryStore::new();
+        telemetry.record("esp32", 1_700_000_100 - 366 * 86_400, &[("temperature".to_string(), 40.0)]).unwrap();
+        let telemetry = Arc::new(Mutex::new(telemetry));
+        let mut scheduler = MaintenanceScheduler::new(chain.clone(), config.clone()).with_telemetry(telemetry.clone());
+
+        scheduler.syncing().store(true, Ordering::Relaxed);
+        assert!(!scheduler.run_once(1_700_000_000).await.unwrap());
+        assert_eq!(chain.read().await.receipts.len(), 4);
+
+        scheduler.syncing().store(false, Ordering::Relaxed);
+        assert!(scheduler.run_once(1_700_000_100).await.unwrap());
+        assert_eq!(chain.read().await.receipts.len(), 1);
+        assert_eq!(scheduler.progress.running, None);
+        assert_eq!(scheduler.progress.compactions, COLUMN_FAMILIES.len() as u64);
+        // A year-old report has aged out of every tier, taking the device with it
+        assert_eq!(scheduler.progress.downsampled_buckets, TELEMETRY_TIERS.len() as u64);
+        assert!(telemetry.lock().await.devices.is_empty());
+
+        // A new scheduler picks up where the last one left off
+        let mut restarted = MaintenanceScheduler::new(chain, config.with_window(0, 0));
+        assert!(!restarted.run_once(1_700_000_200).await.unwrap());
+        let progress = &restarted.progress;
+        assert_eq!((progress.runs, progress.skipped, progress.pruned_receipts), (1, 2, 3));
+        assert_eq!(progress.last_completed, 1_700_000_100);
+        let storage = restarted.chain.read().await.storage.clone().unwrap();
+        assert_eq!(storage.get_stats().unwrap().maintenance.as_ref(), Some(progress));
+    }
+}
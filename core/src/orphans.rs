@@ -0,0 +1,103 @@
+use std::collections::{HashMap, VecDeque};
+use serde::{Deserialize, Serialize};
+use crate::{Transaction, TransactionType, TribeError, TribeResult};
+
+/// Most transactions held waiting on a parent; the oldest are evicted past this
+pub const MAX_ORPHAN_TRANSACTIONS: usize = 1_000;
+/// Most orphans a single sender may have waiting, so one account can't fill the pool
+pub const MAX_ORPHANS_PER_SENDER: usize = 64;
+
+/// What an orphan transaction is waiting for
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MissingParent {
+    Nonce { from: String, nonce: u64 }, // The sender's transaction with this nonce
+    Token(String), // Token that hasn't been created yet
+    Contract(String), // Contract that hasn't been deployed yet
+}
+
+impl MissingParent {
+    /// Token or contract `transaction` needs to exist before it can run
+    pub fn referenced_by(transaction: &Transaction) -> Option<Self> {
+        match &transaction.transaction_type {
+            TransactionType::TokenTransfer { token_id, .. } => Some(Self::Token(token_id.clone())),
+            TransactionType::ContractCall { contract_address, .. } => Some(Self::Contract(contract_address.clone())),
+            _ => None,
+        }
+    }
+}
+
+/// A transaction held until its parent arrives
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrphanTransaction {
+    pub transaction: Transaction,
+    pub missing: MissingParent,
+}
+
+/// Bounded pool of transactions that can't enter the mempool yet
+#[derive(Debug, Clone)]
+pub struct OrphanTransactions {
+    pub max_orphans: usize,
+    pub max_per_sender: usize,
+    orphans: HashMap<String, OrphanTransaction>,
+    order: VecDeque<String>, // Oldest first, for eviction
+}
+
+impl OrphanTransactions {
+    pub fn new(max_orphans: usize, max_per_sender: usize) -> Self {
+        Self { max_orphans, max_per_sender, orphans: HashMap::new(), order: VecDeque::new() }
+    }
+
+    /// Hold `transaction` until `missing` arrives, evicting the oldest orphan if the pool is full
+    pub fn insert(&mut self, transaction: Transaction, missing: MissingParent) -> TribeResult<()> {
+        if self.orphans.contains_key(&transaction.hash) {
+            return Err(TribeError::Mempool(format!("Transaction {} is already waiting on its parent", transaction.hash)));
+        }
+        let held = self.orphans.values().filter(|orphan| orphan.transaction.from == transaction.from).count();
+        if held >= self.max_per_sender {
+            return Err(TribeError::Mempool(format!("{} already has {} orphan transactions", transaction.from, held)));
+        }
+
+        self.order.push_back(transaction.hash.clone());
+        self.orphans.insert(transaction.hash.clone(), OrphanTransaction { transaction, missing });
+        while self.orphans.len() > self.max_orphans {
+            match self.order.pop_front() {
+                Some(hash) => { self.orphans.remove(&hash); }
+                None => break,
+            }
+        }
+        Ok(())
+    }
+
+    pub fn remove(&mut self, hash: &str) -> Option<OrphanTransaction> {
+        let orphan = self.orphans.remove(hash)?;
+        self.order.retain(|held| held != hash);
+        Some(orphan)
+    }
+
+    pub fn get(&self, hash: &str) -> Option<&OrphanTransaction> {
+        self.orphans.get(hash)
+    }
+
+    pub fn contains(&self, hash: &str) -> bool {
+        self.orphans.contains_key(hash)
+    }
+
+    /// Orphans, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = &OrphanTransaction> {
+        self.order.iter().filter_map(|hash| self.orphans.get(hash))
+    }
+
+    pub fn len(&self) -> usize {
+        self.orphans.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.orphans.is_empty()
+    }
+}
+
+impl Default for OrphanTransactions {
+    fn default() -> Self {
+        Self::new(MAX_ORPHAN_TRANSACTIONS, MAX_ORPHANS_PER_SENDER)
+    }
+}
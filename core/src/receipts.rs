@@ -0,0 +1,143 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Size of a block's logs bloom filter in bytes (2048 bits)
+pub const BLOOM_BYTES: usize = 256;
+
+/// Event emitted by a contract during execution
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Log {
+    pub address: String,
+    pub topics: Vec<String>,
+    pub data: Vec<u8>,
+}
+
+/// Outcome of executing a transaction, kept after the block is imported
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Receipt {
+    pub tx_hash: String,
+    pub success: bool,
+    pub gas_used: u64,
+    pub logs: Vec<Log>,
+}
+
+/// Bloom filter over the addresses and log topics in a block, so log and address
+/// queries can skip blocks that certainly don't match
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogsBloom(pub Vec<u8>);
+
+impl LogsBloom {
+    pub fn new() -> Self {
+        Self(vec![0u8; BLOOM_BYTES])
+    }
+
+    pub fn from_receipts(receipts: &[Receipt]) -> Self {
+        let mut bloom = Self::new();
+        for log in receipts.iter().flat_map(|receipt| &receipt.logs) {
+            bloom.accrue(&log.address);
+            for topic in &log.topics {
+                bloom.accrue(topic);
+            }
+        }
+        bloom
+    }
+
+    pub fn accrue(&mut self, item: &str) {
+        for bit in Self::bits(item) {
+            self.0[bit / 8] |= 1 << (bit % 8);
+        }
+    }
+
+    /// Add every item `other` holds
+    pub fn merge(&mut self, other: &LogsBloom) {
+        for (byte, other) in self.0.iter_mut().zip(&other.0) {
+            *byte |= other;
+        }
+    }
+
+    /// False means `item` is definitely absent; true may be a false positive
+    pub fn might_contain(&self, item: &str) -> bool {
+        Self::bits(item).iter().all(|&bit| self.0[bit / 8] & (1 << (bit % 8)) != 0)
+    }
+
+    /// Three 11-bit positions taken from the item's hash
+    fn bits(item: &str) -> [usize; 3] {
+        let digest = Sha256::digest(item.as_bytes());
+        let mut bits = [0usize; 3];
+        for (i, bit) in bits.iter_mut().enumerate() {
+            *bit = ((digest[2 * i] as usize) << 8 | digest[2 * i + 1] as usize) % (BLOOM_BYTES * 8);
+        }
+        bits
+    }
+}
+
+impl Default for LogsBloom {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Receipts for every contract transaction in a block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockReceipts {
+    pub block_index: u64,
+    pub block_hash: String,
+    pub bloom: LogsBloom,
+    pub receipts: Vec<Receipt>,
+}
+
+impl BlockReceipts {
+    pub fn new(block_index: u64, block_hash: String, receipts: Vec<Receipt>) -> Self {
+        Self {
+            block_index,
+            block_hash,
+            bloom: LogsBloom::from_receipts(&receipts),
+            receipts,
+        }
+    }
+}
+
+/// Log query: block range, emitting contracts, and topics by position.
+/// Empty `addresses` matches any contract; a `None` topic matches anything in that position.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LogFilter {
+    pub from_block: Option<u64>,
+    pub to_block: Option<u64>,
+    #[serde(default)]
+    pub addresses: Vec<String>,
+    #[serde(default)]
+    pub topics: Vec<Option<String>>,
+}
+
+impl LogFilter {
+    pub fn in_range(&self, block_index: u64) -> bool {
+        self.from_block.is_none_or(|from| block_index >= from)
+            && self.to_block.is_none_or(|to| block_index <= to)
+    }
+
+    /// Whether a block with this bloom can hold a matching log
+    pub fn might_match(&self, bloom: &LogsBloom) -> bool {
+        let address_ok = self.addresses.is_empty() || self.addresses.iter().any(|address| bloom.might_contain(address));
+        address_ok && self.topics.iter().flatten().all(|topic| bloom.might_contain(topic))
+    }
+
+    pub fn matches(&self, log: &Log) -> bool {
+        if !self.addresses.is_empty() && !self.addresses.contains(&log.address) {
+            return false;
+        }
+        self.topics.iter().enumerate().all(|(position, topic)| match topic {
+            Some(topic) => log.topics.get(position) == Some(topic),
+            None => true,
+        })
+    }
+}
+
+/// A log returned by a query, with where it was emitted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatchedLog {
+    pub block_index: u64,
+    pub block_hash: String,
+    pub tx_hash: String,
+    pub log_index: usize, // Position among all logs in the block
+    pub log: Log,
+}
@@ -0,0 +1,161 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{watch, Notify};
+use crate::{TribeResult, TribeError};
+
+/// Coordinates a clean stop: long-running loops watch for the trigger, and work that
+/// must finish before exit, such as an AI3 task being checkpointed, holds a guard the
+/// shutdown waits on. Clones share the same state.
+#[derive(Debug, Clone)]
+pub struct Shutdown {
+    trigger: Arc<watch::Sender<bool>>,
+    in_flight: Arc<AtomicUsize>,
+    idle: Arc<Notify>, // Woken when the last guard drops
+}
+
+/// Held while work that shutdown should wait for is running
+#[derive(Debug)]
+pub struct ShutdownGuard {
+    in_flight: Arc<AtomicUsize>,
+    idle: Arc<Notify>,
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        let (trigger, _) = watch::channel(false);
+        Self {
+            trigger: Arc::new(trigger),
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            idle: Arc::new(Notify::new()),
+        }
+    }
+
+    /// Start shutting down; every `wait` returns
+    pub fn trigger(&self) {
+        self.trigger.send_replace(true);
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        *self.trigger.borrow()
+    }
+
+    /// Resolve once shutdown is triggered
+    pub async fn wait(&self) {
+        let mut triggered = self.trigger.subscribe();
+        // The sender lives in `self`, so this only errors if it already fired
+        let _ = triggered.wait_for(|triggered| *triggered).await;
+    }
+
+    /// Trigger on SIGINT (Ctrl-C) or, on Unix, SIGTERM
+    pub fn listen_for_signals(&self) -> TribeResult<()> {
+        #[cfg(unix)]
+        let mut terminate = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .map_err(|e| TribeError::Generic(format!("Failed to listen for SIGTERM: {}", e)))?;
+        let shutdown = self.clone();
+        tokio::spawn(async move {
+            #[cfg(unix)]
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = terminate.recv() => {}
+            }
+            #[cfg(not(unix))]
+            let _ = tokio::signal::ctrl_c().await;
+            shutdown.trigger();
+        });
+        Ok(())
+    }
+
+    /// Mark work shutdown should wait for until the guard drops
+    pub fn track(&self) -> ShutdownGuard {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        ShutdownGuard { in_flight: self.in_flight.clone(), idle: self.idle.clone() }
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::SeqCst)
+    }
+
+    /// Wait up to `timeout` for every guard to drop; false if some were still held
+    pub async fn wait_for_tasks(&self, timeout: Duration) -> bool {
+        let drained = async {
+            loop {
+                // Registered before the check so a guard dropping in between still wakes us
+                let idle = self.idle.notified();
+                if self.in_flight() == 0 {
+                    return;
+                }
+                idle.await;
+            }
+        };
+        tokio::time::timeout(timeout, drained).await.is_ok()
+    }
+}
+
+impl Drop for ShutdownGuard {
+    fn drop(&mut self) {
+        if self.in_flight.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.idle.notify_waiters();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Transaction, TransactionType};
+    use crate::testing::dev_chain;
+
+    #[tokio::test]
+    async fn test_trigger_stops_loops_after_tracked_work_finishes() {
+        let shutdown = Shutdown::new();
+        let chain = Arc::new(tokio::sync::RwLock::new(dev_chain()));
+        chain.write().await.pending_transactions.push(
+            Transaction::new("alice".to_string(), TransactionType::Transfer { to: "bob".to_string(), amount: 5 }, 1, 0),
+        );
+
+        // A worker that checkpoints its task before it notices the trigger
+        let worker = {
+            let shutdown = shutdown.clone();
+            let guard = shutdown.track();
+            tokio::spawn(async move {
+                shutdown.wait().await;
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                drop(guard);
+            })
+        };
+        assert_eq!(shutdown.in_flight(), 1);
+        assert!(!shutdown.is_triggered());
+
+        shutdown.trigger();
+        assert!(shutdown.is_triggered());
+        shutdown.wait().await; // Already triggered: returns at once
+        assert!(shutdown.wait_for_tasks(Duration::from_secs(5)).await);
+        assert_eq!(shutdown.in_flight(), 0);
+        worker.await.unwrap();
+
+        // Pending transactions outlive the process once the chain is flushed
+        let chain = chain.read().await;
+        chain.flush().unwrap();
+        let saved = chain.storage.as_ref().unwrap().load_blockchain().unwrap();
+        assert_eq!(saved.pending_transactions.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_tasks_gives_up_on_stuck_work() {
+        let shutdown = Shutdown::new();
+        let stuck = shutdown.track();
+        shutdown.trigger();
+        assert!(!shutdown.wait_for_tasks(Duration::from_millis(20)).await);
+        assert_eq!(shutdown.in_flight(), 1);
+
+        drop(stuck);
+        assert!(shutdown.wait_for_tasks(Duration::from_millis(20)).await);
+    }
+}
@@ -0,0 +1,331 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+
+/// Root of a trie with no accounts
+pub const EMPTY_STATE_ROOT: [u8; 32] = [0u8; 32];
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+type StateKey = [u8; 32];
+type NodeId = (u16, StateKey); // Depth and the key bits above it, the rest zeroed
+
+/// Account balances in a sparse Merkle tree keyed by the SHA-256 of the address.
+/// Empty subtrees hash to zero and a subtree holding a single account hashes to that
+/// account's leaf, so the root only costs as many levels as needed to tell keys apart.
+/// Hashes of subtrees holding several accounts are cached and rehashed along the
+/// changed account's path on each insert.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "HashMap<String, u64>", into = "HashMap<String, u64>")]
+pub struct StateTrie {
+    accounts: BTreeMap<StateKey, (String, u64)>, // Ordered by key bits, most significant first
+    nodes: HashMap<NodeId, StateKey>,
+}
+
+/// Proof of an address's balance, or of it having no account, under a state root
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateProof {
+    pub address: String,
+    pub balance: Option<u64>, // None proves the address has no account
+    pub root: String,
+    pub siblings: Vec<String>, // Sibling hashes from the root down to the address's subtree
+    pub neighbour: Option<(String, u64)>, // Lone account in that subtree when it isn't the address's
+}
+
+fn state_key(address: &str) -> StateKey {
+    Sha256::digest(address.as_bytes()).into()
+}
+
+fn bit(key: &StateKey, depth: usize) -> bool {
+    key[depth / 8] & (0x80 >> (depth % 8)) != 0
+}
+
+fn with_bit(key: &StateKey, depth: usize, set: bool) -> StateKey {
+    let mut key = *key;
+    if set {
+        key[depth / 8] |= 0x80 >> (depth % 8);
+    } else {
+        key[depth / 8] &= !(0x80 >> (depth % 8));
+    }
+    key
+}
+
+/// First and last keys sharing `key`'s first `depth` bits
+fn subtree_range(key: &StateKey, depth: usize) -> (StateKey, StateKey) {
+    let (mut first, mut last) = (*key, *key);
+    if depth < 256 {
+        let byte = depth / 8;
+        let below = 0xffu8 >> (depth % 8); // Bits of this byte past the prefix
+        first[byte] &= !below;
+        last[byte] |= below;
+        first[byte + 1..].fill(0);
+        last[byte + 1..].fill(0xff);
+    }
+    (first, last)
+}
+
+fn leaf_hash(key: &StateKey, balance: u64) -> StateKey {
+    let mut hasher = Sha256::new();
+    hasher.update([LEAF_PREFIX]);
+    hasher.update(key);
+    hasher.update(balance.to_be_bytes());
+    hasher.finalize().into()
+}
+
+fn node_hash(left: &StateKey, right: &StateKey) -> StateKey {
+    let mut hasher = Sha256::new();
+    hasher.update([NODE_PREFIX]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Hash of the subtree at `depth` holding `leaves`, which share their first `depth` bits,
+/// caching its nodes in `nodes`
+fn build_subtree(nodes: &mut HashMap<NodeId, StateKey>, leaves: &[(&StateKey, u64)], depth: usize) -> StateKey {
+    match leaves {
+        [] => EMPTY_STATE_ROOT,
+        [(key, balance)] => leaf_hash(key, *balance),
+        _ => {
+            let split = leaves.partition_point(|(key, _)| !bit(key, depth));
+            let hash = node_hash(
+                &build_subtree(nodes, &leaves[..split], depth + 1),
+                &build_subtree(nodes, &leaves[split..], depth + 1),
+            );
+            nodes.insert((depth as u16, subtree_range(leaves[0].0, depth).0), hash);
+            hash
+        }
+    }
+}
+
+impl StateTrie {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, address: &str) -> Option<u64> {
+        self.accounts.get(&state_key(address)).map(|(_, balance)| *balance)
+    }
+
+    pub fn insert(&mut self, address: String, balance: u64) -> Option<u64> {
+        let key = state_key(&address);
+        let old = self.accounts.insert(key, (address, balance)).map(|(_, old)| old);
+        self.rehash_path(&key);
+        old
+    }
+
+    pub fn len(&self) -> usize {
+        self.accounts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, u64)> {
+        self.accounts.values().map(|(address, balance)| (address.as_str(), *balance))
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = u64> + '_ {
+        self.accounts.values().map(|(_, balance)| *balance)
+    }
+
+    /// Accounts in the subtree at `depth` on `key`'s path
+    fn subtree(&self, key: &StateKey, depth: usize) -> impl Iterator<Item = (&StateKey, u64)> {
+        let (first, last) = subtree_range(key, depth);
+        self.accounts.range(first..=last).map(|(key, (_, balance))| (key, *balance))
+    }
+
+    /// Hash of the subtree at `depth` on `key`'s path
+    fn hash_at(&self, key: &StateKey, depth: usize) -> StateKey {
+        let mut leaves = self.subtree(key, depth);
+        match (leaves.next(), leaves.next()) {
+            (None, _) => EMPTY_STATE_ROOT,
+            (Some((key, balance)), None) => leaf_hash(key, balance),
+            _ => self.nodes[&(depth as u16, subtree_range(key, depth).0)],
+        }
+    }
+
+    /// Recompute the cached nodes above `key` after its balance changed, deepest first.
+    /// Accounts are never removed, so no cached node stops holding several accounts.
+    fn rehash_path(&mut self, key: &StateKey) {
+        let mut depth = 0;
+        while self.subtree(key, depth).nth(1).is_some() {
+            depth += 1;
+        }
+        for depth in (0..depth).rev() {
+            let path = self.hash_at(key, depth + 1);
+            let sibling = self.hash_at(&with_bit(key, depth, !bit(key, depth)), depth + 1);
+            let hash = if bit(key, depth) { node_hash(&sibling, &path) } else { node_hash(&path, &sibling) };
+            self.nodes.insert((depth as u16, subtree_range(key, depth).0), hash);
+        }
+    }
+
+    /// Hex root committing to every balance
+    pub fn root(&self) -> String {
+        hex::encode(self.hash_at(&EMPTY_STATE_ROOT, 0))
+    }
+
+    /// Root the trie would have with `changes` applied, leaving it as it is. Only
+    /// subtrees holding a changed account are rehashed.
+    pub fn root_with(&self, changes: &HashMap<String, u64>) -> String {
+        let mut changed: Vec<(StateKey, u64)> = changes.iter()
+            .map(|(address, balance)| (state_key(address), *balance))
+            .collect();
+        changed.sort_unstable();
+        hex::encode(self.hash_with(&EMPTY_STATE_ROOT, 0, &changed))
+    }
+
+    /// Hash of the subtree at `depth` on `path`'s path with `changed`, the changed
+    /// accounts inside it, applied
+    fn hash_with(&self, path: &StateKey, depth: usize, changed: &[(StateKey, u64)]) -> StateKey {
+        match changed {
+            [] => self.hash_at(path, depth),
+            [(key, balance)] if self.subtree(key, depth).all(|(other, _)| other == key) => leaf_hash(key, *balance),
+            _ => {
+                let split = changed.partition_point(|(key, _)| !bit(key, depth));
+                node_hash(
+                    &self.hash_with(&with_bit(path, depth, false), depth + 1, &changed[..split]),
+                    &self.hash_with(&with_bit(path, depth, true), depth + 1, &changed[split..]),
+                )
+            }
+        }
+    }
+
+    /// Proof of `address`'s balance, or of its absence, under `root()`
+    pub fn proof(&self, address: &str) -> StateProof {
+        let key = state_key(address);
+        let mut siblings = Vec::new();
+        while self.subtree(&key, siblings.len()).nth(1).is_some() {
+            let depth = siblings.len();
+            let sibling = with_bit(&key, depth, !bit(&key, depth));
+            siblings.push(hex::encode(self.hash_at(&sibling, depth + 1)));
+        }
+
+        let (balance, neighbour) = match self.subtree(&key, siblings.len()).next() {
+            Some((k, balance)) if *k == key => (Some(balance), None),
+            Some((k, balance)) => (None, Some((self.accounts[k].0.clone(), balance))),
+            None => (None, None),
+        };
+        StateProof { address: address.to_string(), balance, root: self.root(), siblings, neighbour }
+    }
+}
+
+impl StateProof {
+    /// Check the proof hashes up to its `root`; light clients compare that with a
+    /// block's `state_root`
+    pub fn verify(&self) -> bool {
+        let key = state_key(&self.address);
+        let depth = self.siblings.len();
+        if depth > 256 {
+            return false;
+        }
+        let mut hash = match (&self.balance, &self.neighbour) {
+            (Some(balance), None) => leaf_hash(&key, *balance),
+            (None, None) => EMPTY_STATE_ROOT,
+            (None, Some((address, balance))) => {
+                // The neighbour must sit in the address's subtree without being it
+                let other = state_key(address);
+                if other == key || (0..depth).any(|d| bit(&other, d) != bit(&key, d)) {
+                    return false;
+                }
+                leaf_hash(&other, *balance)
+            }
+            (Some(_), Some(_)) => return false,
+        };
+
+        for (depth, sibling) in self.siblings.iter().enumerate().rev() {
+            let sibling: StateKey = match hex::decode(sibling).ok().and_then(|s| s.try_into().ok()) {
+                Some(sibling) => sibling,
+                None => return false,
+            };
+            hash = if bit(&key, depth) { node_hash(&sibling, &hash) } else { node_hash(&hash, &sibling) };
+        }
+        hex::encode(hash) == self.root
+    }
+}
+
+impl From<HashMap<String, u64>> for StateTrie {
+    fn from(balances: HashMap<String, u64>) -> Self {
+        let accounts: BTreeMap<StateKey, (String, u64)> = balances.into_iter()
+            .map(|(address, balance)| (state_key(&address), (address, balance)))
+            .collect();
+        let leaves: Vec<(&StateKey, u64)> = accounts.iter().map(|(key, (_, balance))| (key, *balance)).collect();
+        let mut nodes = HashMap::new();
+        build_subtree(&mut nodes, &leaves, 0);
+        Self { accounts, nodes }
+    }
+}
+
+impl From<StateTrie> for HashMap<String, u64> {
+    fn from(trie: StateTrie) -> Self {
+        trie.accounts.into_values().collect()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn balances(count: u64) -> HashMap<String, u64> {
+        (0..count).map(|i| (format!("account-{}", i), i * 10)).collect()
+    }
+
+    #[test]
+    fn test_incremental_root_matches_a_rebuild() {
+        let mut trie = StateTrie::new();
+        assert_eq!(trie.root(), hex::encode(EMPTY_STATE_ROOT));
+        for (address, balance) in balances(200) {
+            trie.insert(address, balance);
+        }
+        assert_eq!(trie.root(), StateTrie::from(balances(200)).root());
+
+        // Updating a balance rehashes its path
+        let mut updated = balances(200);
+        updated.insert("account-7".to_string(), 1);
+        trie.insert("account-7".to_string(), 1);
+        assert_eq!(trie.root(), StateTrie::from(updated).root());
+        assert_eq!(trie, StateTrie::from(HashMap::from(trie.clone())));
+    }
+
+    #[test]
+    fn test_root_with_changes_leaves_the_trie_alone() {
+        let trie = StateTrie::from(balances(50));
+        let root = trie.root();
+        let changes = HashMap::from([
+            ("account-3".to_string(), 999),
+            ("newcomer".to_string(), 5),
+        ]);
+
+        let mut applied = trie.clone();
+        for (address, balance) in changes.clone() {
+            applied.insert(address, balance);
+        }
+        assert_eq!(trie.root_with(&changes), applied.root());
+        assert_eq!(trie.root_with(&HashMap::new()), root);
+        assert_eq!(trie.root(), root);
+
+        let mut single = StateTrie::new();
+        single.insert("only".to_string(), 1);
+        assert_eq!(StateTrie::new().root_with(&HashMap::from([("only".to_string(), 1)])), single.root());
+    }
+
+    #[test]
+    fn test_proofs_verify_against_the_cached_root() {
+        let mut trie = StateTrie::from(balances(30));
+        trie.insert("late".to_string(), 42);
+
+        let proof = trie.proof("late");
+        assert_eq!((proof.balance, proof.root.clone()), (Some(42), trie.root()));
+        assert!(proof.verify());
+        let absent = trie.proof("nobody");
+        assert_eq!(absent.balance, None);
+        assert!(absent.verify());
+
+        let mut forged = proof;
+        forged.balance = Some(43);
+        assert!(!forged.verify());
+    }
+}
@@ -1,222 +1,595 @@
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
 #[cfg(feature = "storage")]
-use rocksdb::{DB, Options};
-use crate::{TribeChain, Block, Transaction, TribeResult, TribeError};
+use rocksdb::{BlockBasedOptions, Cache, ColumnFamily, ColumnFamilyDescriptor, DBCompactionStyle, DBCompressionType, IteratorMode, Options, WriteBatch, DB};
+use crate::{TribeChain, Block, BlockHeader, Transaction, TribeResult, TribeError};
+use crate::codec::Versioned;
+use crate::maintenance::{MaintenanceProgress, MAINTENANCE_KEY};
 
-/// Storage backend for TribeChain
-#[derive(Debug, Clone)]
-pub struct Storage {
-    #[cfg(feature = "storage")]
-    db: DB,
-    #[cfg(not(feature = "storage"))]
-    _phantom: std::marker::PhantomData<()>,
+/// Full blocks, keyed by big-endian index
+pub const CF_BLOCKS: &str = "blocks";
+/// Block headers with their address and topic blooms, keyed like blocks
+pub const CF_HEADERS: &str = "headers";
+/// Transactions, keyed by hash
+pub const CF_TRANSACTIONS: &str = "transactions";
+/// The chain state snapshot and arbitrary key-value data
+pub const CF_STATE: &str = "state";
+/// Address and block hash lookups
+pub const CF_INDICES: &str = "indices";
+
+pub const COLUMN_FAMILIES: [&str; 5] = [CF_BLOCKS, CF_HEADERS, CF_TRANSACTIONS, CF_STATE, CF_INDICES];
+
+/// State key of the serialized chain
+const BLOCKCHAIN_KEY: &str = "blockchain";
+
+/// State key of the storage format; records written before it existed carry no
+/// encoding version byte
+const FORMAT_KEY: &str = "storage_format";
+const STORAGE_FORMAT: u8 = 1;
+
+/// State key of the import in progress, if any
+const IMPORT_WAL_KEY: &str = "import_wal";
+
+/// Write-ahead record of a block import, stored before any of the block is written
+/// and cleared in the same batch as the chain snapshot that includes it. Finding one
+/// on startup means the import was cut short.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportIntent {
+    pub index: u64,
+    pub block_hash: String,
+}
+
+/// Where a node keeps its chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    #[default]
+    RocksDb, // On disk; in memory, with a warning, when built without the `storage` feature
+    Memory, // Lost on exit, for tests and throwaway devnets
+}
+
+impl std::str::FromStr for StorageBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "rocksdb" => Ok(StorageBackend::RocksDb),
+            "memory" => Ok(StorageBackend::Memory),
+            _ => Err(format!("Unknown storage backend: {}", s)),
+        }
+    }
 }
 
-impl Storage {
-    /// Create a new storage instance
+/// How RocksDB compacts each column family
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum StorageCompaction {
+    #[default]
+    Level, // Lower space and read amplification
+    Universal, // Lower write amplification for import-heavy nodes
+}
+
+/// Storage backend selection and the RocksDB tuning applied to every column family
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageConfig {
+    #[serde(default, alias = "storage")]
+    pub backend: StorageBackend,
+    pub block_cache_mb: usize, // LRU cache shared by all column families
+    pub write_buffer_mb: usize, // Memtable size per column family
+    pub max_open_files: i32, // -1 keeps every table file open
+    pub max_background_jobs: i32,
+    pub compaction: StorageCompaction,
+    pub compression: bool, // LZ4 table files
+    pub bloom_filters: bool, // 10 bits per key, for point lookups by hash
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            backend: StorageBackend::RocksDb,
+            block_cache_mb: 64,
+            write_buffer_mb: 16,
+            max_open_files: 512,
+            max_background_jobs: 2,
+            compaction: StorageCompaction::Level,
+            compression: true,
+            bloom_filters: true,
+        }
+    }
+}
+
+impl StorageConfig {
+    /// In-memory storage; the RocksDB tuning is unused
+    pub fn memory() -> Self {
+        Self::default().with_backend(StorageBackend::Memory)
+    }
+
+    pub fn with_backend(mut self, backend: StorageBackend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    pub fn with_block_cache_mb(mut self, block_cache_mb: usize) -> Self {
+        self.block_cache_mb = block_cache_mb;
+        self
+    }
+
+    pub fn with_write_buffer_mb(mut self, write_buffer_mb: usize) -> Self {
+        self.write_buffer_mb = write_buffer_mb;
+        self
+    }
+
+    pub fn with_compaction(mut self, compaction: StorageCompaction) -> Self {
+        self.compaction = compaction;
+        self
+    }
+
+    pub fn with_compression(mut self, compression: bool) -> Self {
+        self.compression = compression;
+        self
+    }
+
     #[cfg(feature = "storage")]
-    pub fn new(path: &str) -> TribeResult<Self> {
+    fn column_family_options(&self, cache: &Cache) -> Options {
+        let mut table = BlockBasedOptions::default();
+        table.set_block_cache(cache);
+        if self.bloom_filters {
+            table.set_bloom_filter(10.0, false);
+        }
+
         let mut opts = Options::default();
-        opts.create_if_missing(true);
-        
-        let db = DB::open(&opts, path)
-            .map_err(|e| TribeError::Storage(format!("Failed to open database: {}", e)))?;
-        
-        Ok(Storage { db })
+        opts.set_block_based_table_factory(&table);
+        opts.set_write_buffer_size(self.write_buffer_mb * 1024 * 1024);
+        opts.set_compaction_style(match self.compaction {
+            StorageCompaction::Level => DBCompactionStyle::Level,
+            StorageCompaction::Universal => DBCompactionStyle::Universal,
+        });
+        if self.compaction == StorageCompaction::Level {
+            opts.set_level_compaction_dynamic_level_bytes(true);
+        }
+        opts.set_compression_type(if self.compression { DBCompressionType::Lz4 } else { DBCompressionType::None });
+        opts
     }
+}
 
-    /// Create a new storage instance (no-op when storage feature is disabled)
-    #[cfg(not(feature = "storage"))]
-    pub fn new(_path: &str) -> TribeResult<Self> {
-        Ok(Storage { _phantom: std::marker::PhantomData })
+/// Key of block `index`; big-endian so iteration runs in chain order
+fn block_key(index: u64) -> [u8; 8] {
+    index.to_be_bytes()
+}
+
+fn address_key(address: &str) -> String {
+    format!("addr:{}", address)
+}
+
+fn block_hash_key(hash: &str) -> String {
+    format!("hash:{}", hash)
+}
+
+fn serialize<T: Serialize + ?Sized>(value: &T, what: &str) -> TribeResult<Vec<u8>> {
+    bincode::serialize(value).map_err(|e| TribeError::Storage(format!("Failed to serialize {}: {}", what, e)))
+}
+
+fn deserialize<T: serde::de::DeserializeOwned>(data: &[u8], what: &str) -> TribeResult<T> {
+    bincode::deserialize(data).map_err(|e| TribeError::Storage(format!("Failed to deserialize {}: {}", what, e)))
+}
+
+/// One write of an atomic batch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StorageOp {
+    Put { family: &'static str, key: Vec<u8>, value: Vec<u8> },
+    Delete { family: &'static str, key: Vec<u8> },
+}
+
+impl StorageOp {
+    pub fn put(family: &'static str, key: impl AsRef<[u8]>, value: Vec<u8>) -> Self {
+        StorageOp::Put { family, key: key.as_ref().to_vec(), value }
     }
 
-    /// Save the entire blockchain
-    #[cfg(feature = "storage")]
-    pub fn save_blockchain(&self, blockchain: &TribeChain) -> TribeResult<()> {
-        let serialized = bincode::serialize(blockchain)
-            .map_err(|e| TribeError::Storage(format!("Failed to serialize blockchain: {}", e)))?;
-        
-        self.db.put(b"blockchain", serialized)
-            .map_err(|e| TribeError::Storage(format!("Failed to save blockchain: {}", e)))?;
-        
+    pub fn delete(family: &'static str, key: impl AsRef<[u8]>) -> Self {
+        StorageOp::Delete { family, key: key.as_ref().to_vec() }
+    }
+}
+
+/// A block, its header and its hash lookup
+fn block_ops(block: &Block, index: u64) -> TribeResult<Vec<StorageOp>> {
+    Ok(vec![
+        StorageOp::put(CF_BLOCKS, block_key(index), block.encode_versioned()?),
+        StorageOp::put(CF_HEADERS, block_key(index), block.header().encode_versioned()?),
+        StorageOp::put(CF_INDICES, block_hash_key(&block.hash), block_key(index).to_vec()),
+    ])
+}
+
+/// Storage backend for TribeChain: column families of ordered key-value records with
+/// atomic batch writes. Backends implement the three primitives; chain records are
+/// read and written through the provided methods.
+pub trait Storage: std::fmt::Debug + Send + Sync {
+    fn get(&self, family: &str, key: &[u8]) -> TribeResult<Option<Vec<u8>>>;
+
+    /// Apply every op of `batch` or none of them
+    fn write(&self, batch: Vec<StorageOp>) -> TribeResult<()>;
+
+    /// Visit every record of a family in key order
+    fn for_each(&self, family: &str, f: &mut dyn FnMut(&[u8], &[u8])) -> TribeResult<()>;
+
+    /// Reclaim space left by deleted and overwritten records of a family
+    fn compact(&self, _family: &str) -> TribeResult<()> {
         Ok(())
     }
 
-    /// Save the entire blockchain (no-op when storage feature is disabled)
-    #[cfg(not(feature = "storage"))]
-    pub fn save_blockchain(&self, _blockchain: &TribeChain) -> TribeResult<()> {
+    /// Write buffered records through to durable storage
+    fn flush(&self) -> TribeResult<()> {
         Ok(())
     }
 
+    /// Save the entire blockchain
+    fn save_blockchain(&self, blockchain: &TribeChain) -> TribeResult<()> {
+        self.write(vec![StorageOp::put(CF_STATE, BLOCKCHAIN_KEY, blockchain.encode_versioned()?)])
+    }
+
+    fn has_blockchain(&self) -> TribeResult<bool> {
+        Ok(self.get(CF_STATE, BLOCKCHAIN_KEY.as_bytes())?.is_some())
+    }
+
     /// Load the blockchain
-    #[cfg(feature = "storage")]
-    pub fn load_blockchain(&self) -> TribeResult<TribeChain> {
-        let data = self.db.get(b"blockchain")
-            .map_err(|e| TribeError::Storage(format!("Failed to load blockchain: {}", e)))?
+    fn load_blockchain(&self) -> TribeResult<TribeChain> {
+        let data = self.get(CF_STATE, BLOCKCHAIN_KEY.as_bytes())?
             .ok_or_else(|| TribeError::Storage("Blockchain not found".to_string()))?;
-        
-        let blockchain = bincode::deserialize(&data)
-            .map_err(|e| TribeError::Storage(format!("Failed to deserialize blockchain: {}", e)))?;
-        
-        Ok(blockchain)
+        TribeChain::decode_versioned(&data)
     }
 
-    /// Load the blockchain (returns error when storage feature is disabled)
-    #[cfg(not(feature = "storage"))]
-    pub fn load_blockchain(&self) -> TribeResult<TribeChain> {
-        Err(TribeError::Storage("Storage feature not enabled".to_string()))
+    /// Save a block and its header
+    fn save_block(&self, block: &Block, index: u64) -> TribeResult<()> {
+        self.write(block_ops(block, index)?)
     }
 
-    /// Save a block
-    #[cfg(feature = "storage")]
-    pub fn save_block(&self, block: &Block, index: u64) -> TribeResult<()> {
-        let key = format!("block_{}", index);
-        let serialized = bincode::serialize(block)
-            .map_err(|e| TribeError::Storage(format!("Failed to serialize block: {}", e)))?;
-        
-        self.db.put(key.as_bytes(), serialized)
-            .map_err(|e| TribeError::Storage(format!("Failed to save block: {}", e)))?;
-        
-        Ok(())
+    /// Write an imported block with its header, transactions and the address index
+    /// entries it changed, atomically: after a crash either all of it is stored or none
+    fn import_block(&self, block: &Block, index: u64, addresses: &[(&str, &[AddressTxRef])]) -> TribeResult<()> {
+        let mut batch = block_ops(block, index)?;
+        for transaction in &block.transactions {
+            batch.push(StorageOp::put(CF_TRANSACTIONS, &transaction.hash, transaction.encode_versioned()?));
+        }
+        for (address, refs) in addresses {
+            batch.push(StorageOp::put(CF_INDICES, address_key(address), serialize(refs, "address index")?));
+        }
+        self.write(batch)
     }
 
-    /// Save a block (no-op when storage feature is disabled)
-    #[cfg(not(feature = "storage"))]
-    pub fn save_block(&self, _block: &Block, _index: u64) -> TribeResult<()> {
-        Ok(())
+    /// Record that the import of a block is starting
+    fn begin_import(&self, intent: &ImportIntent) -> TribeResult<()> {
+        self.write(vec![StorageOp::put(CF_STATE, IMPORT_WAL_KEY, serialize(intent, "import record")?)])
+    }
+
+    /// Save the chain snapshot including the imported block and clear its record, atomically
+    fn commit_import(&self, blockchain: &TribeChain) -> TribeResult<()> {
+        self.write(vec![
+            StorageOp::put(CF_STATE, BLOCKCHAIN_KEY, blockchain.encode_versioned()?),
+            StorageOp::delete(CF_STATE, IMPORT_WAL_KEY),
+        ])
+    }
+
+    /// The import a crash interrupted, if any
+    fn pending_import(&self) -> TribeResult<Option<ImportIntent>> {
+        self.get(CF_STATE, IMPORT_WAL_KEY.as_bytes())?
+            .map(|data| deserialize(&data, "import record"))
+            .transpose()
+    }
+
+    /// Undo whatever of an interrupted import was written: the block, its header and
+    /// hash lookup, and its address index entries. Its transactions stay stored, as
+    /// pending ones are.
+    fn rollback_import(&self, intent: &ImportIntent) -> TribeResult<()> {
+        let mut batch = Vec::new();
+        let stored = self.get(CF_BLOCKS, &block_key(intent.index))?
+            .map(|data| Block::decode_versioned(&data))
+            .transpose()?
+            .filter(|block| block.hash == intent.block_hash);
+        if let Some(block) = stored {
+            batch.push(StorageOp::delete(CF_BLOCKS, block_key(intent.index)));
+            batch.push(StorageOp::delete(CF_HEADERS, block_key(intent.index)));
+            batch.push(StorageOp::delete(CF_INDICES, block_hash_key(&block.hash)));
+            for address in block.transactions.iter().flat_map(Transaction::involved_addresses) {
+                let refs: Vec<AddressTxRef> = self.load_address_transactions(&address)?
+                    .into_iter()
+                    .filter(|tx_ref| tx_ref.block_hash != block.hash)
+                    .collect();
+                batch.push(StorageOp::put(CF_INDICES, address_key(&address), serialize(&refs, "address index")?));
+            }
+        }
+        batch.push(StorageOp::delete(CF_STATE, IMPORT_WAL_KEY));
+        self.write(batch)
     }
 
     /// Load a block by index
-    #[cfg(feature = "storage")]
-    pub fn load_block(&self, index: u64) -> TribeResult<Block> {
-        let key = format!("block_{}", index);
-        let data = self.db.get(key.as_bytes())
-            .map_err(|e| TribeError::Storage(format!("Failed to load block: {}", e)))?
+    fn load_block(&self, index: u64) -> TribeResult<Block> {
+        let data = self.get(CF_BLOCKS, &block_key(index))?
             .ok_or_else(|| TribeError::Storage(format!("Block {} not found", index)))?;
-        
-        let block = bincode::deserialize(&data)
-            .map_err(|e| TribeError::Storage(format!("Failed to deserialize block: {}", e)))?;
-        
-        Ok(block)
+        Block::decode_versioned(&data)
     }
 
-    /// Load a block by index (returns error when storage feature is disabled)
-    #[cfg(not(feature = "storage"))]
-    pub fn load_block(&self, _index: u64) -> TribeResult<Block> {
-        Err(TribeError::Storage("Storage feature not enabled".to_string()))
+    /// Load a block by hash
+    fn load_block_by_hash(&self, hash: &str) -> TribeResult<Block> {
+        let index = self.get(CF_INDICES, block_hash_key(hash).as_bytes())?
+            .and_then(|index| <[u8; 8]>::try_from(index.as_slice()).ok())
+            .ok_or_else(|| TribeError::Storage(format!("Block {} not found", hash)))?;
+        self.load_block(u64::from_be_bytes(index))
     }
 
-    /// Save a transaction
-    #[cfg(feature = "storage")]
-    pub fn save_transaction(&self, transaction: &Transaction) -> TribeResult<()> {
-        let key = format!("tx_{}", transaction.hash);
-        let serialized = bincode::serialize(transaction)
-            .map_err(|e| TribeError::Storage(format!("Failed to serialize transaction: {}", e)))?;
-        
-        self.db.put(key.as_bytes(), serialized)
-            .map_err(|e| TribeError::Storage(format!("Failed to save transaction: {}", e)))?;
-        
-        Ok(())
+    /// Overwrite a stored header, as when receipts add to its bloom
+    fn save_header(&self, header: &BlockHeader) -> TribeResult<()> {
+        self.write(vec![StorageOp::put(CF_HEADERS, block_key(header.index), header.encode_versioned()?)])
     }
 
-    /// Save a transaction (no-op when storage feature is disabled)
-    #[cfg(not(feature = "storage"))]
-    pub fn save_transaction(&self, _transaction: &Transaction) -> TribeResult<()> {
-        Ok(())
+    /// Load a block's header without its transactions
+    fn load_header(&self, index: u64) -> TribeResult<BlockHeader> {
+        let data = self.get(CF_HEADERS, &block_key(index))?
+            .ok_or_else(|| TribeError::Storage(format!("Header {} not found", index)))?;
+        BlockHeader::decode_versioned(&data)
+    }
+
+    /// Save a transaction
+    fn save_transaction(&self, transaction: &Transaction) -> TribeResult<()> {
+        self.write(vec![StorageOp::put(CF_TRANSACTIONS, &transaction.hash, transaction.encode_versioned()?)])
     }
 
     /// Load a transaction by hash
-    #[cfg(feature = "storage")]
-    pub fn load_transaction(&self, hash: &str) -> TribeResult<Transaction> {
-        let key = format!("tx_{}", hash);
-        let data = self.db.get(key.as_bytes())
-            .map_err(|e| TribeError::Storage(format!("Failed to load transaction: {}", e)))?
+    fn load_transaction(&self, hash: &str) -> TribeResult<Transaction> {
+        let data = self.get(CF_TRANSACTIONS, hash.as_bytes())?
             .ok_or_else(|| TribeError::Storage(format!("Transaction {} not found", hash)))?;
-        
-        let transaction = bincode::deserialize(&data)
-            .map_err(|e| TribeError::Storage(format!("Failed to deserialize transaction: {}", e)))?;
-        
-        Ok(transaction)
+        Transaction::decode_versioned(&data)
     }
 
-    /// Load a transaction by hash (returns error when storage feature is disabled)
-    #[cfg(not(feature = "storage"))]
-    pub fn load_transaction(&self, _hash: &str) -> TribeResult<Transaction> {
-        Err(TribeError::Storage("Storage feature not enabled".to_string()))
+    /// Save key-value pair
+    fn save_data(&self, key: &str, value: &[u8]) -> TribeResult<()> {
+        self.write(vec![StorageOp::put(CF_STATE, key, value.to_vec())])
     }
 
-    /// Save key-value pair
-    #[cfg(feature = "storage")]
-    pub fn save_data(&self, key: &str, value: &[u8]) -> TribeResult<()> {
-        self.db.put(key.as_bytes(), value)
-            .map_err(|e| TribeError::Storage(format!("Failed to save data: {}", e)))?;
-        
-        Ok(())
+    /// Load data by key
+    fn load_data(&self, key: &str) -> TribeResult<Option<Vec<u8>>> {
+        self.get(CF_STATE, key.as_bytes())
     }
 
-    /// Save key-value pair (no-op when storage feature is disabled)
-    #[cfg(not(feature = "storage"))]
-    pub fn save_data(&self, _key: &str, _value: &[u8]) -> TribeResult<()> {
-        Ok(())
+    /// Delete data by key
+    fn delete_data(&self, key: &str) -> TribeResult<()> {
+        self.write(vec![StorageOp::delete(CF_STATE, key)])
     }
 
-    /// Load data by key
-    #[cfg(feature = "storage")]
-    pub fn load_data(&self, key: &str) -> TribeResult<Option<Vec<u8>>> {
-        let data = self.db.get(key.as_bytes())
-            .map_err(|e| TribeError::Storage(format!("Failed to load data: {}", e)))?;
-        
-        Ok(data)
+    /// Save the transactions touching an address
+    fn save_address_transactions(&self, address: &str, refs: &[AddressTxRef]) -> TribeResult<()> {
+        self.write(vec![StorageOp::put(CF_INDICES, address_key(address), serialize(refs, "address index")?)])
     }
 
-    /// Load data by key (returns None when storage feature is disabled)
-    #[cfg(not(feature = "storage"))]
-    pub fn load_data(&self, _key: &str) -> TribeResult<Option<Vec<u8>>> {
-        Ok(None)
+    /// Load the transactions touching an address
+    fn load_address_transactions(&self, address: &str) -> TribeResult<Vec<AddressTxRef>> {
+        match self.get(CF_INDICES, address_key(address).as_bytes())? {
+            Some(data) => deserialize(&data, "address index"),
+            None => Ok(Vec::new()),
+        }
     }
 
-    /// Delete data by key
-    #[cfg(feature = "storage")]
-    pub fn delete_data(&self, key: &str) -> TribeResult<()> {
-        self.db.delete(key.as_bytes())
-            .map_err(|e| TribeError::Storage(format!("Failed to delete data: {}", e)))?;
-        
+    /// Rewrite blocks, headers and transactions stored before records were versioned
+    /// with their version byte, in one batch. Databases already in the current format
+    /// are left alone.
+    fn migrate_encoding(&self) -> TribeResult<()> {
+        if self.get(CF_STATE, FORMAT_KEY.as_bytes())?.is_some() {
+            return Ok(());
+        }
+        let mut batch = Vec::new();
+        for family in [CF_BLOCKS, CF_HEADERS, CF_TRANSACTIONS] {
+            let mut records = Vec::new();
+            self.for_each(family, &mut |key, value| records.push((key.to_vec(), value.to_vec())))?;
+            for (key, value) in records {
+                let encoded = match family {
+                    CF_BLOCKS => Block::decode_unversioned(&value)?.encode_versioned()?,
+                    CF_HEADERS => BlockHeader::decode_unversioned(&value)?.encode_versioned()?,
+                    _ => Transaction::decode_unversioned(&value)?.encode_versioned()?,
+                };
+                batch.push(StorageOp::put(family, key, encoded));
+            }
+        }
+        batch.push(StorageOp::put(CF_STATE, FORMAT_KEY, vec![STORAGE_FORMAT]));
+        self.write(batch)
+    }
+
+    /// Get database statistics
+    fn get_stats(&self) -> TribeResult<StorageStats> {
+        let mut stats = StorageStats { total_keys: 0, total_size: 0, column_families: BTreeMap::new(), maintenance: None };
+        for name in COLUMN_FAMILIES {
+            let mut keys = 0;
+            self.for_each(name, &mut |key, value| {
+                keys += 1;
+                stats.total_size += key.len() + value.len();
+            })?;
+            stats.total_keys += keys;
+            stats.column_families.insert(name.to_string(), keys);
+        }
+        stats.maintenance = self.load_data(MAINTENANCE_KEY)?
+            .map(|data| deserialize::<MaintenanceProgress>(&data, "maintenance progress"))
+            .transpose()?;
+        Ok(stats)
+    }
+}
+
+/// Open the backend `config` selects. Without the `storage` feature RocksDB is
+/// unavailable and the chain is kept in memory, which is logged since nothing is persisted.
+pub fn open_storage(path: &str, config: &StorageConfig) -> TribeResult<Arc<dyn Storage>> {
+    match config.backend {
+        #[cfg(feature = "storage")]
+        StorageBackend::RocksDb => Ok(Arc::new(RocksStorage::open(path, config)?)),
+        #[cfg(not(feature = "storage"))]
+        StorageBackend::RocksDb => {
+            eprintln!("Built without the `storage` feature; {} is kept in memory and lost on exit", path);
+            Ok(Arc::new(MemoryStorage::new()))
+        }
+        StorageBackend::Memory => Ok(Arc::new(MemoryStorage::new())),
+    }
+}
+
+/// One column family's records, in key order
+type MemoryFamily = BTreeMap<Vec<u8>, Vec<u8>>;
+
+/// Column families held in ordered in-memory maps
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    families: RwLock<HashMap<String, MemoryFamily>>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+fn lock_poisoned() -> TribeError {
+    TribeError::Storage("Memory storage lock poisoned".to_string())
+}
+
+impl Storage for MemoryStorage {
+    fn get(&self, family: &str, key: &[u8]) -> TribeResult<Option<Vec<u8>>> {
+        let families = self.families.read().map_err(|_| lock_poisoned())?;
+        Ok(families.get(family).and_then(|records| records.get(key)).cloned())
+    }
+
+    fn write(&self, batch: Vec<StorageOp>) -> TribeResult<()> {
+        // One write lock over the whole batch keeps it atomic to readers
+        let mut families = self.families.write().map_err(|_| lock_poisoned())?;
+        for op in batch {
+            match op {
+                StorageOp::Put { family, key, value } => {
+                    families.entry(family.to_string()).or_default().insert(key, value);
+                }
+                StorageOp::Delete { family, key } => {
+                    if let Some(records) = families.get_mut(family) {
+                        records.remove(&key);
+                    }
+                }
+            }
+        }
         Ok(())
     }
 
-    /// Delete data by key (no-op when storage feature is disabled)
-    #[cfg(not(feature = "storage"))]
-    pub fn delete_data(&self, _key: &str) -> TribeResult<()> {
+    fn for_each(&self, family: &str, f: &mut dyn FnMut(&[u8], &[u8])) -> TribeResult<()> {
+        let families = self.families.read().map_err(|_| lock_poisoned())?;
+        for (key, value) in families.get(family).into_iter().flatten() {
+            f(key, value);
+        }
         Ok(())
     }
+}
 
-    /// Get database statistics
-    #[cfg(feature = "storage")]
-    pub fn get_stats(&self) -> TribeResult<StorageStats> {
-        // Get approximate number of keys
-        let mut iter = self.db.iterator(rocksdb::IteratorMode::Start);
-        let mut total_keys = 0;
-        let mut total_size = 0;
-        
-        while let Some(Ok((key, value))) = iter.next() {
-            total_keys += 1;
-            total_size += key.len() + value.len();
+/// RocksDB storage, one column family per kind of record
+#[cfg(feature = "storage")]
+#[derive(Debug)]
+pub struct RocksStorage {
+    db: DB,
+}
+
+#[cfg(feature = "storage")]
+impl RocksStorage {
+    /// Open or create the database at `path`, versioning records written without an
+    /// encoding version and moving ones written under the old single-namespace key
+    /// prefixes into their column families
+    pub fn open(path: &str, config: &StorageConfig) -> TribeResult<Self> {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.create_missing_column_families(true);
+        opts.set_max_open_files(config.max_open_files);
+        opts.set_max_background_jobs(config.max_background_jobs);
+
+        let cache = Cache::new_lru_cache(config.block_cache_mb * 1024 * 1024);
+        let families = COLUMN_FAMILIES.iter()
+            .map(|name| ColumnFamilyDescriptor::new(*name, config.column_family_options(&cache)));
+        let db = DB::open_cf_descriptors(&opts, path, families)
+            .map_err(|e| TribeError::Storage(format!("Failed to open database: {}", e)))?;
+
+        let storage = RocksStorage { db };
+        storage.migrate_encoding()?;
+        storage.migrate_legacy_keys()?;
+        Ok(storage)
+    }
+
+    fn cf(&self, name: &str) -> TribeResult<&ColumnFamily> {
+        self.db.cf_handle(name)
+            .ok_or_else(|| TribeError::Storage(format!("Missing column family {}", name)))
+    }
+
+    /// Move records from the default column family, where they were kept under
+    /// `block_`, `tx_` and `addr_` prefixes, in one batch
+    fn migrate_legacy_keys(&self) -> TribeResult<()> {
+        let mut batch = Vec::new();
+        let mut legacy_keys = Vec::new();
+        for entry in self.db.iterator(IteratorMode::Start) {
+            let (key, value) = entry.map_err(|e| TribeError::Storage(format!("Failed to read legacy keys: {}", e)))?;
+            let name = String::from_utf8_lossy(&key);
+            if let Some(index) = name.strip_prefix("block_").and_then(|index| index.parse().ok()) {
+                batch.extend(block_ops(&Block::decode_unversioned(&value)?, index)?);
+            } else if let Some(hash) = name.strip_prefix("tx_") {
+                batch.push(StorageOp::put(CF_TRANSACTIONS, hash, Transaction::decode_unversioned(&value)?.encode_versioned()?));
+            } else if let Some(address) = name.strip_prefix("addr_") {
+                batch.push(StorageOp::put(CF_INDICES, address_key(address), value.to_vec()));
+            } else if name == BLOCKCHAIN_KEY {
+                batch.push(StorageOp::put(CF_STATE, BLOCKCHAIN_KEY, TribeChain::decode_unversioned(&value)?.encode_versioned()?));
+            } else {
+                batch.push(StorageOp::put(CF_STATE, &key, value.to_vec()));
+            }
+            legacy_keys.push(key);
+        }
+        if legacy_keys.is_empty() {
+            return Ok(());
+        }
+        let mut batch = self.write_batch(batch)?;
+        for key in legacy_keys {
+            batch.delete(key);
+        }
+        self.db.write(batch)
+            .map_err(|e| TribeError::Storage(format!("Failed to migrate legacy keys: {}", e)))
+    }
+
+    fn write_batch(&self, ops: Vec<StorageOp>) -> TribeResult<WriteBatch> {
+        let mut batch = WriteBatch::default();
+        for op in ops {
+            match op {
+                StorageOp::Put { family, key, value } => batch.put_cf(self.cf(family)?, key, value),
+                StorageOp::Delete { family, key } => batch.delete_cf(self.cf(family)?, key),
+            }
         }
-        
-        Ok(StorageStats {
-            total_keys,
-            total_size,
-        })
+        Ok(batch)
+    }
+}
+
+#[cfg(feature = "storage")]
+impl Storage for RocksStorage {
+    fn get(&self, family: &str, key: &[u8]) -> TribeResult<Option<Vec<u8>>> {
+        self.db.get_cf(self.cf(family)?, key)
+            .map_err(|e| TribeError::Storage(format!("Failed to read {}: {}", family, e)))
+    }
+
+    fn write(&self, batch: Vec<StorageOp>) -> TribeResult<()> {
+        self.db.write(self.write_batch(batch)?)
+            .map_err(|e| TribeError::Storage(format!("Failed to write batch: {}", e)))
+    }
+
+    fn for_each(&self, family: &str, f: &mut dyn FnMut(&[u8], &[u8])) -> TribeResult<()> {
+        for entry in self.db.iterator_cf(self.cf(family)?, IteratorMode::Start) {
+            let (key, value) = entry.map_err(|e| TribeError::Storage(format!("Failed to read {}: {}", family, e)))?;
+            f(&key, &value);
+        }
+        Ok(())
+    }
+
+    fn compact(&self, family: &str) -> TribeResult<()> {
+        self.db.compact_range_cf(self.cf(family)?, None::<&[u8]>, None::<&[u8]>);
+        Ok(())
     }
 
-    /// Get database statistics (returns empty stats when storage feature is disabled)
-    #[cfg(not(feature = "storage"))]
-    pub fn get_stats(&self) -> TribeResult<StorageStats> {
-        Ok(StorageStats {
-            total_keys: 0,
-            total_size: 0,
-        })
+    fn flush(&self) -> TribeResult<()> {
+        for family in COLUMN_FAMILIES {
+            self.db.flush_cf(self.cf(family)?)
+                .map_err(|e| TribeError::Storage(format!("Failed to flush {}: {}", family, e)))?;
+        }
+        self.db.flush_wal(true)
+            .map_err(|e| TribeError::Storage(format!("Failed to flush write-ahead log: {}", e)))
     }
 }
 
@@ -225,4 +598,215 @@ impl Storage {
 pub struct StorageStats {
     pub total_keys: usize,
     pub total_size: usize,
-} 
\ No newline at end of file
+    #[serde(default)]
+    pub column_families: BTreeMap<String, usize>, // column family -> key count
+    #[serde(default)]
+    pub maintenance: Option<MaintenanceProgress>, // None until the scheduler first runs
+}
+
+/// Where a transaction touching an address sits on chain
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AddressTxRef {
+    pub tx_hash: String,
+    pub block_index: u64,
+    pub block_hash: String,
+    pub position: usize, // Index within the block's transactions
+}
+
+/// Address -> transactions touching it, in chain order
+#[derive(Debug, Clone, Default)]
+pub struct AddressIndex {
+    entries: HashMap<String, Vec<AddressTxRef>>,
+}
+
+impl AddressIndex {
+    /// Index every transaction in `block`; returns the addresses that changed
+    pub fn index_block(&mut self, block: &Block) -> Vec<String> {
+        let mut touched = Vec::new();
+        for (position, transaction) in block.transactions.iter().enumerate() {
+            for address in transaction.involved_addresses() {
+                self.entries.entry(address.clone()).or_default().push(AddressTxRef {
+                    tx_hash: transaction.hash.clone(),
+                    block_index: block.index,
+                    block_hash: block.hash.clone(),
+                    position,
+                });
+                if !touched.contains(&address) {
+                    touched.push(address);
+                }
+            }
+        }
+        touched
+    }
+
+    pub fn get(&self, address: &str) -> &[AddressTxRef] {
+        self.entries.get(address).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// One page of an address's transactions, newest first
+    pub fn page(&self, address: &str, page: usize, page_size: usize) -> Vec<&AddressTxRef> {
+        self.get(address).iter()
+            .rev()
+            .skip(page.saturating_mul(page_size))
+            .take(page_size)
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TransactionType;
+
+    fn transfer(from: &str, to: &str, nonce: u64) -> Transaction {
+        Transaction::new(from.to_string(), TransactionType::Transfer { to: to.to_string(), amount: 5 }, 0, nonce)
+    }
+
+    fn block(index: u64, transactions: Vec<Transaction>) -> Block {
+        let mut block = Block::new(index, "0".repeat(64), transactions, "miner".to_string());
+        block.hash = block.calculate_hash();
+        block
+    }
+
+    #[test]
+    fn test_memory_storage_batches_and_iteration() {
+        let storage = MemoryStorage::new();
+        assert_eq!(storage.get(CF_STATE, b"a").unwrap(), None);
+
+        storage.write(vec![
+            StorageOp::put(CF_STATE, "b", vec![2]),
+            StorageOp::put(CF_STATE, "a", vec![1]),
+            StorageOp::put(CF_INDICES, "a", vec![9]),
+        ]).unwrap();
+        assert_eq!(storage.get(CF_STATE, b"a").unwrap(), Some(vec![1]));
+        assert_eq!(storage.get(CF_INDICES, b"a").unwrap(), Some(vec![9]), "families are separate namespaces");
+
+        // Ops apply in order: a later put wins, a delete removes
+        storage.write(vec![
+            StorageOp::put(CF_STATE, "c", vec![3]),
+            StorageOp::delete(CF_STATE, "b"),
+            StorageOp::put(CF_STATE, "a", vec![4]),
+            StorageOp::delete(CF_BLOCKS, "missing"),
+        ]).unwrap();
+
+        let mut records = Vec::new();
+        storage.for_each(CF_STATE, &mut |key, value| records.push((key.to_vec(), value.to_vec()))).unwrap();
+        assert_eq!(records, vec![(b"a".to_vec(), vec![4]), (b"c".to_vec(), vec![3])]);
+
+        let mut count = 0;
+        storage.for_each(CF_HEADERS, &mut |_, _| count += 1).unwrap();
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn test_memory_storage_chain_records() {
+        let storage = MemoryStorage::new();
+        let transaction = transfer("alice", "bob", 0);
+        let block = block(3, vec![transaction.clone()]);
+
+        storage.save_block(&block, 3).unwrap();
+        assert_eq!(storage.load_block(3).unwrap().hash, block.hash);
+        assert_eq!(storage.load_block_by_hash(&block.hash).unwrap().index, 3);
+        assert_eq!(storage.load_header(3).unwrap(), block.header());
+        assert!(matches!(storage.load_block(4), Err(TribeError::Storage(_))));
+        assert!(storage.load_block_by_hash("unknown").is_err());
+
+        storage.save_transaction(&transaction).unwrap();
+        assert_eq!(storage.load_transaction(&transaction.hash).unwrap().id, transaction.id);
+        assert!(storage.load_transaction("unknown").is_err());
+
+        storage.save_data("key", b"value").unwrap();
+        assert_eq!(storage.load_data("key").unwrap(), Some(b"value".to_vec()));
+        storage.delete_data("key").unwrap();
+        assert_eq!(storage.load_data("key").unwrap(), None);
+
+        let refs = vec![AddressTxRef { tx_hash: transaction.hash.clone(), block_index: 3, block_hash: block.hash.clone(), position: 0 }];
+        assert!(storage.load_address_transactions("alice").unwrap().is_empty());
+        storage.save_address_transactions("alice", &refs).unwrap();
+        assert_eq!(storage.load_address_transactions("alice").unwrap(), refs);
+
+        let stats = storage.get_stats().unwrap();
+        assert_eq!(stats.column_families[CF_BLOCKS], 1);
+        assert_eq!(stats.column_families[CF_TRANSACTIONS], 1);
+        assert_eq!(stats.total_keys, 5); // Block, header, hash lookup, transaction and address
+        assert!(stats.maintenance.is_none());
+    }
+
+    #[test]
+    fn test_rollback_import_removes_partial_block() {
+        let storage = MemoryStorage::new();
+        let earlier = block(1, vec![transfer("alice", "bob", 0)]);
+        let earlier_refs = vec![AddressTxRef {
+            tx_hash: earlier.transactions[0].hash.clone(), block_index: 1, block_hash: earlier.hash.clone(), position: 0,
+        }];
+        storage.import_block(&earlier, 1, &[("alice", &earlier_refs), ("bob", &earlier_refs)]).unwrap();
+
+        // The import logs its intent and writes the block, then crashes before the snapshot
+        let partial = block(2, vec![transfer("alice", "carol", 1)]);
+        let intent = ImportIntent { index: 2, block_hash: partial.hash.clone() };
+        storage.begin_import(&intent).unwrap();
+        let partial_ref = AddressTxRef {
+            tx_hash: partial.transactions[0].hash.clone(), block_index: 2, block_hash: partial.hash.clone(), position: 0,
+        };
+        let alice_refs = vec![earlier_refs[0].clone(), partial_ref.clone()];
+        storage.import_block(&partial, 2, &[("alice", &alice_refs), ("carol", &[partial_ref])]).unwrap();
+        assert_eq!(storage.pending_import().unwrap(), Some(intent.clone()));
+
+        storage.rollback_import(&intent).unwrap();
+        assert_eq!(storage.pending_import().unwrap(), None);
+        assert!(storage.load_block(2).is_err());
+        assert!(storage.load_header(2).is_err());
+        assert!(storage.load_block_by_hash(&partial.hash).is_err());
+        assert_eq!(storage.load_address_transactions("alice").unwrap(), earlier_refs);
+        assert!(storage.load_address_transactions("carol").unwrap().is_empty());
+        assert!(!storage.has_blockchain().unwrap());
+
+        // The earlier, committed block is untouched
+        assert_eq!(storage.load_block(1).unwrap().hash, earlier.hash);
+        assert_eq!(storage.load_address_transactions("bob").unwrap(), earlier_refs);
+    }
+
+    #[test]
+    fn test_rollback_import_before_block_was_written() {
+        let storage = MemoryStorage::new();
+        let intent = ImportIntent { index: 1, block_hash: "never-written".to_string() };
+        storage.begin_import(&intent).unwrap();
+
+        // A different block at the same index isn't the import's and is kept
+        let other = block(1, Vec::new());
+        storage.save_block(&other, 1).unwrap();
+
+        storage.rollback_import(&intent).unwrap();
+        assert_eq!(storage.pending_import().unwrap(), None);
+        assert_eq!(storage.load_block(1).unwrap().hash, other.hash);
+    }
+
+    #[test]
+    fn test_commit_import_saves_snapshot_and_clears_intent() {
+        let chain = crate::testing::dev_chain();
+        let storage = MemoryStorage::new();
+        let intent = ImportIntent { index: 1, block_hash: "next".to_string() };
+        storage.begin_import(&intent).unwrap();
+        assert_eq!(storage.pending_import().unwrap(), Some(intent));
+        assert!(!storage.has_blockchain().unwrap());
+
+        storage.commit_import(&chain).unwrap();
+        assert_eq!(storage.pending_import().unwrap(), None);
+        assert_eq!(storage.load_blockchain().unwrap().blocks[0].hash, chain.blocks[0].hash);
+    }
+
+    #[test]
+    fn test_memory_backend_is_selected_by_config() {
+        let storage = open_storage("unused", &StorageConfig::memory()).unwrap();
+        assert!(!storage.has_blockchain().unwrap());
+        assert!(storage.pending_import().unwrap().is_none());
+    }
+}
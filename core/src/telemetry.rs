@@ -0,0 +1,259 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+use crate::{Storage, TribeResult, TribeError};
+
+/// Downsampling tiers as (bucket width, retention) in seconds, finest first
+pub const TELEMETRY_TIERS: [(u64, u64); 3] = [
+    (60, 86_400),              // Minutes for a day
+    (3_600, 30 * 86_400),      // Hours for a month
+    (86_400, 365 * 86_400),    // Days for a year
+];
+
+/// Alerts kept for `recent_alerts`
+pub const MAX_TELEMETRY_ALERTS: usize = 256;
+
+const DEVICE_LIST_KEY: &str = "telemetry_devices";
+
+/// Min, max and mean of one metric over a bucket
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MetricSummary {
+    pub min: f64,
+    pub max: f64,
+    pub sum: f64,
+    pub count: u32,
+}
+
+impl MetricSummary {
+    fn new(value: f64) -> Self {
+        Self { min: value, max: value, sum: value, count: 1 }
+    }
+
+    fn add(&mut self, value: f64) {
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.sum += value;
+        self.count += 1;
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.sum / self.count as f64
+    }
+}
+
+/// Metrics a device reported during one bucket of time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetricBucket {
+    pub start: u64,
+    pub width: u64,
+    pub metrics: BTreeMap<String, MetricSummary>,
+}
+
+/// One device's series at every tier
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeviceTelemetry {
+    pub tiers: Vec<Vec<MetricBucket>>, // Parallel to `TELEMETRY_TIERS`, oldest bucket first
+    pub last_seen: u64,
+}
+
+/// Alert raised when a metric goes above `max`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertThreshold {
+    pub metric: String,
+    pub max: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TelemetryAlert {
+    pub device_id: String,
+    pub metric: String,
+    pub value: f64,
+    pub threshold: f64,
+    pub timestamp: u64,
+}
+
+/// Downsampled per-device metric series with threshold alerts, persisted to storage
+#[derive(Debug, Clone, Default)]
+pub struct TelemetryStore {
+    pub devices: HashMap<String, DeviceTelemetry>,
+    pub thresholds: Vec<AlertThreshold>,
+    pub alerts: VecDeque<TelemetryAlert>, // Newest last
+    breached: HashSet<(String, String)>, // (device, metric) pairs above their threshold
+    storage: Option<Arc<dyn Storage>>,
+}
+
+impl TelemetryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Persist series to `storage`, loading any it already holds
+    pub fn with_storage(mut self, storage: Arc<dyn Storage>) -> TribeResult<Self> {
+        let device_ids: Vec<String> = match storage.load_data(DEVICE_LIST_KEY)? {
+            Some(data) => bincode::deserialize(&data)
+                .map_err(|e| TribeError::Storage(format!("Failed to deserialize telemetry devices: {}", e)))?,
+            None => Vec::new(),
+        };
+        for device_id in device_ids {
+            if let Some(data) = storage.load_data(&format!("telemetry_{}", device_id))? {
+                let series = bincode::deserialize(&data)
+                    .map_err(|e| TribeError::Storage(format!("Failed to deserialize telemetry: {}", e)))?;
+                self.devices.insert(device_id, series);
+            }
+        }
+        self.storage = Some(storage);
+        Ok(self)
+    }
+
+    pub fn with_threshold(mut self, metric: &str, max: f64) -> Self {
+        self.thresholds.push(AlertThreshold { metric: metric.to_string(), max });
+        self
+    }
+
+    /// Fold one report into the device's series, returning alerts it raised. A metric
+    /// alerts once when it crosses its threshold, and again only after dropping back.
+    pub fn record(&mut self, device_id: &str, timestamp: u64, metrics: &[(String, f64)]) -> TribeResult<Vec<TelemetryAlert>> {
+        let is_new = !self.devices.contains_key(device_id);
+        let series = self.devices.entry(device_id.to_string()).or_default();
+        series.tiers.resize(TELEMETRY_TIERS.len(), Vec::new());
+        series.last_seen = series.last_seen.max(timestamp);
+
+        for (tier, &(width, retention)) in series.tiers.iter_mut().zip(TELEMETRY_TIERS.iter()) {
+            let start = timestamp - timestamp % width;
+            let bucket = match tier.binary_search_by_key(&start, |bucket| bucket.start) {
+                Ok(index) => &mut tier[index],
+                Err(index) => {
+                    tier.insert(index, MetricBucket { start, width, metrics: BTreeMap::new() });
+                    &mut tier[index]
+                }
+            };
+            for (name, value) in metrics {
+                bucket.metrics.entry(name.clone())
+                    .and_modify(|summary| summary.add(*value))
+                    .or_insert_with(|| MetricSummary::new(*value));
+            }
+            let cutoff = series.last_seen.saturating_sub(retention);
+            tier.retain(|bucket| bucket.start + bucket.width > cutoff);
+        }
+
+        let mut raised = Vec::new();
+        for threshold in &self.thresholds {
+            let Some((_, value)) = metrics.iter().find(|(name, _)| *name == threshold.metric) else { continue };
+            let key = (device_id.to_string(), threshold.metric.clone());
+            if *value <= threshold.max {
+                self.breached.remove(&key);
+            } else if self.breached.insert(key) {
+                raised.push(TelemetryAlert {
+                    device_id: device_id.to_string(),
+                    metric: threshold.metric.clone(),
+                    value: *value,
+                    threshold: threshold.max,
+                    timestamp,
+                });
+            }
+        }
+        for alert in &raised {
+            if self.alerts.len() == MAX_TELEMETRY_ALERTS {
+                self.alerts.pop_front();
+            }
+            self.alerts.push_back(alert.clone());
+        }
+
+        if let Some(storage) = &self.storage {
+            let serialized = bincode::serialize(&self.devices[device_id])
+                .map_err(|e| TribeError::Storage(format!("Failed to serialize telemetry: {}", e)))?;
+            storage.save_data(&format!("telemetry_{}", device_id), &serialized)?;
+            if is_new {
+                let device_ids: Vec<&String> = self.devices.keys().collect();
+                let serialized = bincode::serialize(&device_ids)
+                    .map_err(|e| TribeError::Storage(format!("Failed to serialize telemetry devices: {}", e)))?;
+                storage.save_data(DEVICE_LIST_KEY, &serialized)?;
+            }
+        }
+        Ok(raised)
+    }
+
+    /// Drop buckets that have aged out of their tier as of `now`, and devices left with
+    /// none, so series of devices that stopped reporting expire too; returns buckets dropped
+    pub fn downsample(&mut self, now: u64) -> TribeResult<usize> {
+        let mut dropped = 0;
+        let mut changed = Vec::new();
+        for (device_id, series) in &mut self.devices {
+            let before: usize = series.tiers.iter().map(Vec::len).sum();
+            for (tier, &(_, retention)) in series.tiers.iter_mut().zip(TELEMETRY_TIERS.iter()) {
+                let cutoff = now.saturating_sub(retention);
+                tier.retain(|bucket| bucket.start + bucket.width > cutoff);
+            }
+            let after: usize = series.tiers.iter().map(Vec::len).sum();
+            if after < before {
+                dropped += before - after;
+                changed.push(device_id.clone());
+            }
+        }
+        let expired: Vec<String> = changed.iter()
+            .filter(|device_id| self.devices[*device_id].tiers.iter().all(Vec::is_empty))
+            .cloned()
+            .collect();
+        for device_id in &expired {
+            self.devices.remove(device_id);
+        }
+
+        if let Some(storage) = &self.storage {
+            for device_id in &changed {
+                match self.devices.get(device_id) {
+                    Some(series) => {
+                        let serialized = bincode::serialize(series)
+                            .map_err(|e| TribeError::Storage(format!("Failed to serialize telemetry: {}", e)))?;
+                        storage.save_data(&format!("telemetry_{}", device_id), &serialized)?;
+                    }
+                    None => storage.delete_data(&format!("telemetry_{}", device_id))?,
+                }
+            }
+            if !expired.is_empty() {
+                let device_ids: Vec<&String> = self.devices.keys().collect();
+                let serialized = bincode::serialize(&device_ids)
+                    .map_err(|e| TribeError::Storage(format!("Failed to serialize telemetry devices: {}", e)))?;
+                storage.save_data(DEVICE_LIST_KEY, &serialized)?;
+            }
+        }
+        Ok(dropped)
+    }
+
+    /// Buckets overlapping `[from, to]`, from the finest tier that still covers `from`
+    pub fn get_device_metrics(&self, device_id: &str, from: u64, to: u64) -> Vec<&MetricBucket> {
+        let Some(series) = self.devices.get(device_id) else { return Vec::new() };
+        let tier = TELEMETRY_TIERS.iter()
+            .position(|&(_, retention)| from >= series.last_seen.saturating_sub(retention))
+            .unwrap_or(TELEMETRY_TIERS.len() - 1);
+        series.tiers.get(tier)
+            .map(|buckets| {
+                buckets.iter()
+                    .filter(|bucket| bucket.start + bucket.width > from && bucket.start <= to)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// The device's most recent minute bucket
+    pub fn latest_metrics(&self, device_id: &str) -> Option<&MetricBucket> {
+        self.devices.get(device_id)?.tiers.first()?.last()
+    }
+
+    /// Metrics of the device currently above their threshold
+    pub fn breached_metrics(&self, device_id: &str) -> Vec<&str> {
+        let mut metrics: Vec<&str> = self.breached.iter()
+            .filter(|(device, _)| device == device_id)
+            .map(|(_, metric)| metric.as_str())
+            .collect();
+        metrics.sort_unstable();
+        metrics
+    }
+
+    /// Alerts raised recently, newest first, optionally for one device
+    pub fn recent_alerts(&self, device_id: Option<&str>) -> Vec<&TelemetryAlert> {
+        self.alerts.iter()
+            .rev()
+            .filter(|alert| device_id.is_none_or(|id| alert.device_id == id))
+            .collect()
+    }
+}
@@ -0,0 +1,30 @@
+use crate::{AI3Proof, Block, ChainSpec, StorageConfig, TribeChain};
+
+/// In-memory chain on the dev spec
+pub(crate) fn dev_chain() -> TribeChain {
+    TribeChain::with_chain_spec("unused", &StorageConfig::memory(), &ChainSpec::dev()).unwrap()
+}
+
+/// Empty block by `miner` on the tip mined at `difficulty`, committing to the post-block state
+pub(crate) fn block_by(chain: &TribeChain, miner: &str, ai3_proof: Option<AI3Proof>, difficulty: u64) -> Block {
+    let tip = chain.blocks.last().unwrap();
+    let mut block = Block::new(tip.index + 1, tip.hash.clone(), Vec::new(), miner.to_string());
+    block.ai3_proof = ai3_proof;
+    block.state_root = chain.compute_state_root(&block).unwrap();
+    block.mine_block(difficulty).unwrap();
+    block
+}
+
+pub(crate) fn next_block(chain: &TribeChain, ai3_proof: Option<AI3Proof>, difficulty: u64) -> Block {
+    block_by(chain, "miner", ai3_proof, difficulty)
+}
+
+/// Dev chain with `length` empty blocks by `miner` on top of genesis
+pub(crate) fn chain_of(length: u64, miner: &str) -> TribeChain {
+    let mut chain = dev_chain();
+    for _ in 0..length {
+        let block = block_by(&chain, miner, None, chain.difficulty);
+        chain.add_block(block).unwrap();
+    }
+    chain
+}
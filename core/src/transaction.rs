@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use chrono::Utc;
 use uuid::Uuid;
-use crate::TribeResult;
+use crate::{TribeError, TribeResult};
 
 /// Transaction types supported by TribeChain
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -48,6 +48,23 @@ pub enum TransactionType {
         args: Vec<u8>,
         value: u64,
     },
+    /// Approval vote for DPoS delegates, replacing the sender's previous vote
+    DelegateVote {
+        delegates: Vec<String>,
+    },
+}
+
+/// Most delegates a single vote may approve
+pub const MAX_DELEGATE_VOTES: usize = 30;
+
+/// Chain ID of the main network; testnets and devnets use their own so
+/// transactions signed for one network can't be replayed on another
+pub const MAINNET_CHAIN_ID: u64 = 1;
+pub const TESTNET_CHAIN_ID: u64 = 2;
+pub const DEVNET_CHAIN_ID: u64 = 1337;
+
+fn default_chain_id() -> u64 {
+    MAINNET_CHAIN_ID
 }
 
 /// Transaction structure
@@ -59,6 +76,12 @@ pub struct Transaction {
     pub fee: u64,
     pub timestamp: u64,
     pub nonce: u64,
+    #[serde(default = "default_chain_id")]
+    pub chain_id: u64,
+    #[serde(default)]
+    pub gas_price: u64, // Miners include higher-priced transactions first
+    #[serde(default)]
+    pub public_key: String, // Hex ed25519 key the signature verifies under
     pub signature: String,
     pub hash: String,
 }
@@ -81,6 +104,9 @@ impl Transaction {
             fee,
             timestamp,
             nonce,
+            chain_id: MAINNET_CHAIN_ID,
+            gas_price: 0,
+            public_key: String::new(),
             signature: String::new(),
             hash: String::new(),
         };
@@ -89,16 +115,38 @@ impl Transaction {
         tx
     }
 
+    /// Bid `gas_price` per unit of gas; the fee becomes the intrinsic gas times the price
+    pub fn with_gas_price(mut self, gas_price: u64) -> Self {
+        self.gas_price = gas_price;
+        self.fee = self.gas() * gas_price;
+        self.hash = self.calculate_hash();
+        self
+    }
+
+    /// Gas this transaction consumes when included
+    pub fn gas(&self) -> u64 {
+        crate::fees::intrinsic_gas(self)
+    }
+
+    /// Bind the transaction to another network's chain ID (before signing)
+    pub fn with_chain_id(mut self, chain_id: u64) -> Self {
+        self.chain_id = chain_id;
+        self.hash = self.calculate_hash();
+        self
+    }
+
     /// Calculate transaction hash
     pub fn calculate_hash(&self) -> String {
         let data = format!(
-            "{}{}{}{}{}{}",
+            "{}{}{}{}{}{}{}{}",
+            self.chain_id,
             self.id,
             self.from,
             serde_json::to_string(&self.transaction_type).unwrap_or_default(),
             self.fee,
             self.timestamp,
-            self.nonce
+            self.nonce,
+            self.gas_price
         );
         
         let mut hasher = Sha256::new();
@@ -106,22 +154,69 @@ impl Transaction {
         hex::encode(hasher.finalize())
     }
 
-    /// Sign transaction (simplified - in real implementation would use proper cryptography)
+    /// Sign the transaction hash with `private_key`, attaching the matching public key
     pub fn sign(&mut self, private_key: &str) -> TribeResult<()> {
-        let message = format!("{}{}", self.hash, private_key);
-        let mut hasher = Sha256::new();
-        hasher.update(message.as_bytes());
-        self.signature = hex::encode(hasher.finalize());
+        self.public_key = crate::keys::public_key(private_key);
+        self.signature = crate::keys::sign(self.hash.as_bytes(), private_key);
         Ok(())
     }
 
-    /// Verify transaction signature
-    pub fn verify_signature(&self, public_key: &str) -> bool {
-        let message = format!("{}{}", self.hash, public_key);
-        let mut hasher = Sha256::new();
-        hasher.update(message.as_bytes());
-        let expected_signature = hex::encode(hasher.finalize());
-        self.signature == expected_signature
+    /// Check the signature is over this transaction's hash and, for a sender in address
+    /// form, that the signing key is the sender's. Legacy account names such as genesis
+    /// allocations aren't derived from a key, so only the signature is checked for them.
+    pub fn verify_signature(&self) -> bool {
+        if !crate::keys::verify(self.hash.as_bytes(), &self.signature, &self.public_key) {
+            return false;
+        }
+        if !crate::address::is_bech32_form(&self.from) {
+            return true;
+        }
+        let hrp = crate::address::hrp_for_chain(self.chain_id);
+        crate::address::Address::parse_for_chain(&self.from, self.chain_id)
+            .is_ok_and(|sender| sender == crate::address::Address::from_public_key(hrp, &self.public_key))
+    }
+
+    /// Accounts this transaction sends funds or votes to; contract addresses are the
+    /// contract engine's own and aren't included
+    pub fn recipient_addresses(&self) -> Vec<&str> {
+        match &self.transaction_type {
+            TransactionType::Transfer { to, .. } | TransactionType::TokenTransfer { to, .. } => vec![to.as_str()],
+            TransactionType::Stake { validator, .. } => vec![validator.as_str()],
+            TransactionType::DelegateVote { delegates } => delegates.iter().map(String::as_str).collect(),
+            TransactionType::TokenCreate { .. }
+            | TransactionType::TensorCompute { .. }
+            | TransactionType::ContractDeploy { .. }
+            | TransactionType::ContractCall { .. } => Vec::new(),
+        }
+    }
+
+    /// Check every recipient is a well-formed address of the network this transaction
+    /// is bound to
+    pub fn validate_recipients(&self) -> TribeResult<()> {
+        for recipient in self.recipient_addresses() {
+            crate::address::Address::parse_for_chain(recipient, self.chain_id)
+                .map_err(|e| TribeError::Address(format!("recipient {}: {}", recipient, e.message())))?;
+        }
+        Ok(())
+    }
+
+    /// Addresses this transaction touches: the sender plus any recipient, validator or contract
+    pub fn involved_addresses(&self) -> Vec<String> {
+        let mut addresses = vec![self.from.clone()];
+        match &self.transaction_type {
+            TransactionType::Transfer { to, .. } | TransactionType::TokenTransfer { to, .. } => {
+                addresses.push(to.clone());
+            }
+            TransactionType::Stake { validator, .. } => addresses.push(validator.clone()),
+            TransactionType::ContractCall { contract_address, .. } => addresses.push(contract_address.clone()),
+            TransactionType::DelegateVote { delegates } => addresses.extend(delegates.iter().cloned()),
+            TransactionType::TokenCreate { .. }
+            | TransactionType::TensorCompute { .. }
+            | TransactionType::ContractDeploy { .. } => {}
+        }
+        addresses.sort();
+        addresses.dedup();
+        addresses
     }
 
     /// Validate transaction
@@ -174,6 +269,11 @@ impl Transaction {
                     return Ok(false);
                 }
             }
+            TransactionType::DelegateVote { delegates } => {
+                if delegates.len() > MAX_DELEGATE_VOTES || delegates.iter().any(|d| d.is_empty()) {
+                    return Ok(false);
+                }
+            }
         }
 
         Ok(true)
@@ -0,0 +1,93 @@
+use rayon::prelude::*;
+use crate::{Block, Transaction, TribeResult, TribeError};
+
+/// Checks of one transaction that don't depend on chain state: its hash, its type's
+/// rules, the network it was signed for and its signature
+fn check_transaction(transaction: &Transaction, chain_id: u64) -> TribeResult<()> {
+    if transaction.chain_id != chain_id {
+        return Err(TribeError::InvalidBlock(format!(
+            "Transaction {} is for chain {}", transaction.hash, transaction.chain_id
+        )));
+    }
+    if !transaction.validate()? {
+        return Err(TribeError::InvalidBlock(format!("Transaction {} failed validation", transaction.hash)));
+    }
+    if !transaction.verify_signature() {
+        return Err(TribeError::InvalidBlock(format!("Transaction {} has an invalid signature", transaction.hash)));
+    }
+    Ok(())
+}
+
+/// Check a block's transactions on the rayon pool. Results are merged in block order,
+/// so the error names the first invalid transaction whichever thread found it.
+pub fn check_transactions(transactions: &[Transaction], chain_id: u64) -> TribeResult<()> {
+    let results: Vec<TribeResult<()>> = transactions.par_iter()
+        .map(|transaction| check_transaction(transaction, chain_id))
+        .collect();
+    results.into_iter().collect()
+}
+
+/// First stage of import: everything about `block` that doesn't depend on chain state
+pub fn check_block(block: &Block, previous: Option<&Block>, chain_id: u64) -> TribeResult<()> {
+    if !block.validate(previous)? {
+        return Err(TribeError::InvalidBlock("Block validation failed".to_string()));
+    }
+    check_transactions(&block.transactions, chain_id)
+}
+
+/// First stage for consecutive blocks, each checked against the one before it and the
+/// first against `tip`, all in parallel. Returns one result per block.
+pub fn check_blocks(blocks: &[Block], tip: Option<&Block>, chain_id: u64) -> Vec<TribeResult<()>> {
+    (0..blocks.len()).into_par_iter()
+        .map(|i| {
+            let previous = if i == 0 { tip } else { Some(&blocks[i - 1]) };
+            check_block(&blocks[i], previous, chain_id)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Address, TransactionType, DEVNET_CHAIN_ID, TESTNET_HRP};
+
+    fn signed(key: &str, nonce: u64) -> Transaction {
+        let from = Address::from_key(TESTNET_HRP, key).to_string();
+        let mut transaction = Transaction::new(from, TransactionType::Transfer { to: "bob".to_string(), amount: 1 }, 0, nonce)
+            .with_chain_id(DEVNET_CHAIN_ID);
+        transaction.sign(key).unwrap();
+        transaction
+    }
+
+    #[test]
+    fn test_bad_signatures_are_rejected() {
+        assert!(check_transaction(&signed("alice-key", 0), DEVNET_CHAIN_ID).is_ok());
+
+        let mut unsigned = signed("alice-key", 0);
+        unsigned.signature.clear();
+        assert!(matches!(check_transaction(&unsigned, DEVNET_CHAIN_ID), Err(TribeError::InvalidBlock(_))));
+
+        // Signed over a different transaction
+        let mut tampered = signed("alice-key", 0);
+        tampered.signature = signed("alice-key", 1).signature;
+        assert!(check_transaction(&tampered, DEVNET_CHAIN_ID).is_err());
+
+        // A valid signature by a key that isn't the sender's
+        let mut impersonated = signed("alice-key", 0);
+        impersonated.sign("mallory-key").unwrap();
+        assert!(check_transaction(&impersonated, DEVNET_CHAIN_ID).is_err());
+    }
+
+    #[test]
+    fn test_parallel_check_matches_serial() {
+        let mut transactions: Vec<Transaction> = (0..64).map(|nonce| signed("alice-key", nonce)).collect();
+        transactions[40].signature = transactions[41].signature.clone();
+        transactions[50].signature.clear();
+
+        let serial = transactions.iter()
+            .try_for_each(|transaction| check_transaction(transaction, DEVNET_CHAIN_ID));
+        let parallel = check_transactions(&transactions, DEVNET_CHAIN_ID);
+        assert_eq!(parallel.unwrap_err().to_string(), serial.unwrap_err().to_string());
+        assert!(check_transactions(&transactions[..40], DEVNET_CHAIN_ID).is_ok());
+    }
+}
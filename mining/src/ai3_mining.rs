@@ -1,9 +1,10 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use chrono::{DateTime, Utc};
 use tokio::sync::RwLock;
 use std::sync::Arc;
-use tribechain_core::{TribeResult, TribeError, Block};
+use tribechain_core::{TribeResult, TribeError, Block, AI3Commitment, TensorTask};
+use crate::calibration::DifficultyCalibrator;
 
 // Import from ai3-lib mining module
 use ai3_lib::mining::{
@@ -26,9 +27,19 @@ pub struct AI3Miner {
     pub lib_miner: LibAI3Miner,
     pub ai3_engine: AI3Engine,
     pub active_tasks: Arc<RwLock<HashMap<String, MiningTask>>>,
+    pub finished_tasks: Arc<RwLock<HashMap<String, MiningTask>>>, // Tasks whose results may still be revealed
     pub completed_results: Arc<RwLock<HashMap<String, AI3MiningResult>>>,
     pub esp_miners: HashMap<String, ESPMinerWrapper>,
     pub stats: AI3MiningStats,
+    pub pending_reveals: HashMap<String, PendingReveal>,
+    pub reveal_delay: u64, // Blocks to wait between commit and reveal
+}
+
+/// Result held back until its commitment has been on chain long enough
+#[derive(Debug, Clone)]
+pub struct PendingReveal {
+    pub result: AI3MiningResult,
+    pub commitment: AI3Commitment,
 }
 
 /// Wrapper for ESP miners
@@ -61,6 +72,12 @@ pub struct AI3Proof {
     pub computation_hash: String,
     pub difficulty_met: bool,
     pub verification_data: Vec<u8>,
+    #[serde(default)]
+    pub nonce: u64, // Mining nonce behind `input_hash`
+    #[serde(default)]
+    pub commitment_hash: String,
+    #[serde(default)]
+    pub commitment_nonce: u64,
 }
 
 /// AI3 mining statistics
@@ -89,9 +106,12 @@ impl AI3Miner {
             lib_miner,
             ai3_engine: AI3Engine::new(),
             active_tasks: Arc::new(RwLock::new(HashMap::new())),
+            finished_tasks: Arc::new(RwLock::new(HashMap::new())),
             completed_results: Arc::new(RwLock::new(HashMap::new())),
             esp_miners: HashMap::new(),
             stats: AI3MiningStats::default(),
+            pending_reveals: HashMap::new(),
+            reveal_delay: 1,
         }
     }
 
@@ -160,12 +180,13 @@ impl AI3Miner {
         
         // Convert block hash to tensor
         let hash_bytes: Vec<u8> = hex::decode(&block.hash)
-            .map_err(|_| TribeError::InvalidOperation("Invalid block hash".to_string()))?;
+            .map_err(|_| TribeError::Mining("Invalid block hash".to_string()))?;
         
         let hash_floats: Vec<f32> = hash_bytes.iter().map(|&b| b as f32 / 255.0).collect();
         let hash_tensor = Tensor::new(
             TensorShape::new(vec![hash_floats.len()]),
             TensorData::F32(hash_floats),
+            None,
         )?;
         tensors.push(hash_tensor);
 
@@ -187,6 +208,7 @@ impl AI3Miner {
                 let tx_tensor = Tensor::new(
                     TensorShape::new(vec![tx_data.len()]),
                     TensorData::F32(tx_data),
+                    None,
                 )?;
                 tensors.push(tx_tensor);
             }
@@ -194,7 +216,7 @@ impl AI3Miner {
 
         // Add block metadata tensor
         let metadata = vec![
-            block.height as f32,
+            block.index as f32,
             block.timestamp as f32,
             block.nonce as f32,
             block.difficulty as f32,
@@ -203,6 +225,7 @@ impl AI3Miner {
         let metadata_tensor = Tensor::new(
             TensorShape::new(vec![metadata.len()]),
             TensorData::F32(metadata),
+            None,
         )?;
         tensors.push(metadata_tensor);
 
@@ -229,17 +252,19 @@ impl AI3Miner {
         lib_miner.assign_task(task.clone())?;
 
         // Try mining step using ai3-lib
-        if let Some(lib_result) = lib_miner.mine_step()? {
+        if let Some(mut lib_result) = lib_miner.mine_step()? {
+            // Check our own result the way the network will before committing to it
+            lib_result.validate(&task)?;
+
             // Convert lib result to blockchain-compatible result
             let ai3_result = self.convert_to_ai3_result(lib_result, self.id.clone()).await?;
             
             // Store completed result
-            let mut completed_results = self.completed_results.write().await;
-            completed_results.insert(task_id.to_string(), ai3_result.clone());
+            self.completed_results.write().await.insert(task_id.to_string(), ai3_result.clone());
             
-            // Remove from active tasks
-            let mut active_tasks = self.active_tasks.write().await;
-            active_tasks.remove(task_id);
+            // Move out of active tasks, keeping the task around to validate the reveal
+            self.active_tasks.write().await.remove(task_id);
+            self.finished_tasks.write().await.insert(task_id.to_string(), task);
             
             // Update stats
             self.update_stats(&ai3_result).await;
@@ -253,35 +278,38 @@ impl AI3Miner {
 
     /// Try mining with ESP devices
     async fn try_esp_mining(&mut self, task: &MiningTask) -> TribeResult<Option<AI3MiningResult>> {
+        let mut found = None;
         for (miner_id, esp_miner) in &mut self.esp_miners {
-            match esp_miner {
+            let result = match esp_miner {
                 ESPMinerWrapper::ESP32(esp32) => {
                     // Check if ESP32 can handle the task
-                    if esp32.base_miner.can_handle_task(task) {
-                        // Assign task to the base miner
-                        esp32.base_miner.assign_task(task.clone())?;
-                        
-                        // Try mining step
-                        if let Some(result) = esp32.mine_step()? {
-                            return Ok(Some(self.convert_to_ai3_result(result, miner_id.clone()).await?));
-                        }
+                    if !esp32.base_miner.can_handle_task(task) {
+                        continue;
                     }
+                    // Assign task to the base miner and try a mining step
+                    esp32.base_miner.assign_task(task.clone())?;
+                    esp32.mine_step()?
                 }
                 ESPMinerWrapper::ESP8266(esp8266) => {
                     // Check if ESP8266 can handle the task
-                    if esp8266.esp32_miner.base_miner.can_handle_task(task) {
-                        // Assign task to the base miner
-                        esp8266.esp32_miner.base_miner.assign_task(task.clone())?;
-                        
-                        // Try mining step
-                        if let Some(result) = esp8266.mine_step()? {
-                            return Ok(Some(self.convert_to_ai3_result(result, miner_id.clone()).await?));
-                        }
+                    if !esp8266.esp32_miner.base_miner.can_handle_task(task) {
+                        continue;
                     }
+                    // Assign task to the base miner and try a mining step
+                    esp8266.esp32_miner.base_miner.assign_task(task.clone())?;
+                    esp8266.mine_step()?
                 }
+            };
+            if let Some(result) = result {
+                found = Some((miner_id.clone(), result));
+                break;
             }
         }
-        Ok(None)
+
+        match found {
+            Some((miner_id, result)) => Ok(Some(self.convert_to_ai3_result(result, miner_id).await?)),
+            None => Ok(None),
+        }
     }
 
     /// Convert ai3-lib mining result to blockchain-compatible result
@@ -290,13 +318,26 @@ impl AI3Miner {
         lib_result: LibMiningResult,
         miner_id: String,
     ) -> TribeResult<AI3MiningResult> {
+        let output_hash = lib_result.output_tensor.calculate_hash();
+        // The mining nonce is public, so the commitment is salted with a secret one
+        // instead; it only leaves this miner with the reveal
+        let commitment_nonce: u64 = rand::random();
+        let commitment_hash = AI3Commitment::compute_hash(
+            &lib_result.task_id,
+            &miner_id,
+            &output_hash,
+            commitment_nonce,
+        );
         let ai3_proof = AI3Proof {
             operation_type: "tensor_operation".to_string(),
             input_hash: lib_result.hash.clone(),
-            output_hash: lib_result.output_tensor.calculate_hash(),
+            output_hash,
             computation_hash: format!("{}_{}", lib_result.nonce, lib_result.computation_time),
             difficulty_met: lib_result.is_valid,
             verification_data: self.create_verification_data(&lib_result)?,
+            nonce: lib_result.nonce,
+            commitment_hash,
+            commitment_nonce,
         };
 
         Ok(AI3MiningResult {
@@ -317,7 +358,7 @@ impl AI3Miner {
         use sha2::{Digest, Sha256};
         let mut hasher = Sha256::new();
         hasher.update(result.task_id.as_bytes());
-        hasher.update(&result.nonce.to_le_bytes());
+        hasher.update(result.nonce.to_le_bytes());
         hasher.update(result.output_tensor.calculate_hash().as_bytes());
         Ok(hasher.finalize().to_vec())
     }
@@ -341,31 +382,81 @@ impl AI3Miner {
         self.stats.total_tensor_operations += 1;
     }
 
+    /// Hold back a completed result and return the commitment to broadcast in its place
+    pub async fn commit_result(&mut self, task_id: &str, block_height: u64) -> TribeResult<AI3Commitment> {
+        let result = self.completed_results.write().await.remove(task_id)
+            .ok_or_else(|| TribeError::AI3(format!("No completed result for task {}", task_id)))?;
+
+        let commitment = AI3Commitment::new(
+            result.task_id.clone(),
+            result.miner_id.clone(),
+            &result.ai3_proof.output_hash,
+            result.ai3_proof.commitment_nonce,
+            block_height,
+        );
+        self.pending_reveals.insert(task_id.to_string(), PendingReveal {
+            result,
+            commitment: commitment.clone(),
+        });
+
+        Ok(commitment)
+    }
+
+    /// Release a committed result once `reveal_delay` blocks have passed
+    pub fn reveal_result(&mut self, task_id: &str, current_height: u64) -> Option<AI3MiningResult> {
+        let ready = self.pending_reveals.get(task_id)
+            .map(|pending| current_height >= pending.commitment.block_height + self.reveal_delay)
+            .unwrap_or(false);
+
+        if ready {
+            self.pending_reveals.remove(task_id).map(|pending| pending.result)
+        } else {
+            None
+        }
+    }
+
     /// Validate AI3 mining result
     pub async fn validate_result(&self, result: &AI3MiningResult) -> TribeResult<bool> {
-        let task = {
-            let active_tasks = self.active_tasks.read().await;
-            match active_tasks.get(&result.task_id) {
-                Some(task) => task.clone(),
-                None => {
-                    // Check completed tasks
-                    return Ok(result.is_valid);
-                }
-            }
+        // The revealed output must match what the miner committed to
+        let expected_commitment = AI3Commitment::compute_hash(
+            &result.task_id,
+            &result.miner_id,
+            &result.tensor_result.calculate_hash(),
+            result.ai3_proof.commitment_nonce,
+        );
+        if expected_commitment != result.ai3_proof.commitment_hash {
+            return Ok(false);
+        }
+
+        // Results for tasks we never handed out can't be checked, so they don't count
+        let task = match self.known_task(&result.task_id).await {
+            Some(task) => task,
+            None => return Ok(false),
         };
 
-        self.validate_ai3_proof(&result.ai3_proof, &task).await
+        self.validate_ai3_proof(result, &task)
     }
 
-    /// Validate AI3 proof against task
-    async fn validate_ai3_proof(&self, proof: &AI3Proof, task: &MiningTask) -> TribeResult<bool> {
-        // Verify computation hash meets difficulty
-        if !task.meets_difficulty(&proof.computation_hash) {
-            return Ok(false);
+    /// Look up a task that is still being mined or whose result is awaiting reveal
+    async fn known_task(&self, task_id: &str) -> Option<MiningTask> {
+        if let Some(task) = self.active_tasks.read().await.get(task_id) {
+            return Some(task.clone());
         }
+        self.finished_tasks.read().await.get(task_id).cloned()
+    }
 
-        // Additional AI3-specific validations would go here
-        Ok(proof.difficulty_met)
+    /// Validate AI3 proof against task
+    fn validate_ai3_proof(&self, result: &AI3MiningResult, task: &MiningTask) -> TribeResult<bool> {
+        // Re-run the task's own checks: hash against nonce and difficulty, then the output
+        let mut lib_result = LibMiningResult::new(
+            result.task_id.clone(),
+            result.miner_id.clone(),
+            result.ai3_proof.nonce,
+            result.ai3_proof.input_hash.clone(),
+            result.tensor_result.clone(),
+            result.computation_time,
+        );
+        lib_result.validate(task)
     }
 
     /// Get miner capabilities from ai3-lib
@@ -394,6 +485,9 @@ impl AI3Miner {
     pub async fn cleanup_expired_tasks(&mut self) {
         let mut active_tasks = self.active_tasks.write().await;
         active_tasks.retain(|_, task| !task.is_expired());
+        let pending_reveals = &self.pending_reveals;
+        self.finished_tasks.write().await
+            .retain(|task_id, task| !task.is_expired() || pending_reveals.contains_key(task_id));
     }
 }
 
@@ -419,6 +513,8 @@ pub struct AI3MiningPool {
     pub miners: HashMap<String, AI3Miner>,
     pub task_distributor: TaskDistributor,
     pub pool_stats: AI3PoolStats,
+    pub calibrator: DifficultyCalibrator,
+    pub queued_tasks: VecDeque<MiningTask>, // Escrowed contract tasks waiting for distribution
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -438,6 +534,8 @@ impl AI3MiningPool {
             miners: HashMap::new(),
             task_distributor: TaskDistributor::new(),
             pool_stats: AI3PoolStats::default(),
+            calibrator: DifficultyCalibrator::default(),
+            queued_tasks: VecDeque::new(),
         }
     }
 
@@ -456,10 +554,45 @@ impl AI3MiningPool {
 
     /// Distribute task using ai3-lib TaskDistributor
     pub async fn distribute_task(&mut self, task: MiningTask) -> TribeResult<Vec<String>> {
-        let lib_miners: Vec<_> = self.miners.values().map(|m| &m.lib_miner).collect();
+        let lib_miners: Vec<_> = self.miners.values().map(|m| m.lib_miner.clone()).collect();
         self.task_distributor.distribute(task, &lib_miners)
     }
 
+    /// Queue tasks escrowed by tensor computation contracts, keeping their ids so
+    /// proofs can be matched back to the escrow. Returns how many were queued.
+    pub fn queue_escrow_tasks(&mut self, tasks: Vec<TensorTask>, base_difficulty: u64) -> TribeResult<usize> {
+        let count = tasks.len();
+        for task in tasks {
+            let difficulty = self.calibrator.difficulty_for(&task.operation, base_difficulty);
+            let shape = TensorShape::new(vec![task.input_data.len()]);
+            let input = Tensor::from_vec(task.input_data, shape)?;
+            let mut mining_task = MiningTask::new(
+                task.operation,
+                vec![input],
+                difficulty,
+                task.reward,
+                task.max_computation_time,
+                task.requester,
+            )
+            .with_expected_output(vec![task.expected_output_size]);
+            mining_task.id = task.id;
+            self.queued_tasks.push_back(mining_task);
+        }
+        Ok(count)
+    }
+
+    /// Hand every queued escrow task to the pool's miners.
+    /// Returns each task id with the miners it was assigned to.
+    pub async fn distribute_queued_tasks(&mut self) -> TribeResult<Vec<(String, Vec<String>)>> {
+        let mut assignments = Vec::new();
+        while let Some(task) = self.queued_tasks.pop_front() {
+            let task_id = task.id.clone();
+            let miners = self.distribute_task(task).await?;
+            assignments.push((task_id, miners));
+        }
+        Ok(assignments)
+    }
+
     pub fn get_pool_stats(&self) -> AI3PoolStats {
         self.pool_stats.clone()
     }
@@ -510,9 +643,60 @@ mod tests {
             computation_hash: "test_computation".to_string(),
             difficulty_met: true,
             verification_data: vec![1, 2, 3, 4],
+            nonce: 0,
+            commitment_hash: String::new(),
+            commitment_nonce: 0,
         };
         
         assert!(proof.difficulty_met);
         assert_eq!(proof.operation_type, "matrix_multiply");
     }
-} 
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_commit_reveal_delay() {
+        let mut miner = AI3Miner::new("test_miner".to_string());
+        let task = MiningTask::new(
+            "relu".to_string(),
+            vec![Tensor::vector(vec![-1.0, 2.0, -3.0, 4.0])],
+            0,
+            100,
+            60,
+            "requester".to_string(),
+        );
+        let task_id = task.id.clone();
+        miner.active_tasks.write().await.insert(task_id.clone(), task);
+        let result = miner.mine_step(&task_id).await.unwrap().unwrap();
+        assert_ne!(result.ai3_proof.commitment_nonce, result.ai3_proof.nonce);
+
+        let commitment = miner.commit_result(&task_id, 10).await.unwrap();
+        assert_eq!(miner.get_completed_results_count().await, 0);
+        assert!(miner.reveal_result(&task_id, 10).is_none());
+
+        let revealed = miner.reveal_result(&task_id, 11).unwrap();
+        assert!(commitment.matches(&revealed.ai3_proof.output_hash, revealed.ai3_proof.commitment_nonce));
+        assert!(miner.validate_result(&revealed).await.unwrap());
+
+        // Claiming validity doesn't help a wrong output or an unknown task
+        let mut forged = revealed.clone();
+        forged.tensor_result = Tensor::vector(vec![0.0, 2.0, 0.0, 5.0]);
+        forged.ai3_proof.commitment_hash = AI3Commitment::compute_hash(
+            &forged.task_id,
+            &forged.miner_id,
+            &forged.tensor_result.calculate_hash(),
+            forged.ai3_proof.commitment_nonce,
+        );
+        forged.is_valid = true;
+        assert!(!miner.validate_result(&forged).await.unwrap());
+
+        let mut unknown = revealed;
+        unknown.task_id = "unknown".to_string();
+        unknown.ai3_proof.commitment_hash = AI3Commitment::compute_hash(
+            &unknown.task_id,
+            &unknown.miner_id,
+            &unknown.tensor_result.calculate_hash(),
+            unknown.ai3_proof.commitment_nonce,
+        );
+        unknown.is_valid = true;
+        assert!(!miner.validate_result(&unknown).await.unwrap());
+    }
+}
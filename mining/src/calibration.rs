@@ -0,0 +1,138 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Smoothed compute time of one operation on one miner
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OperationTiming {
+    pub average_ms: f64, // Exponentially weighted moving average
+    pub samples: u64,
+}
+
+/// Per-operation difficulty calibration for tensor proof of work.
+///
+/// Each difficulty step is one more leading hex zero, i.e. 16x the expected
+/// nonce search. An operation that is 16x more expensive than the reference
+/// operation is therefore given one step less difficulty, so tasks of equal
+/// reward take comparable expected time regardless of operation type.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DifficultyCalibrator {
+    pub reference_operation: String,
+    pub smoothing: f64, // Weight of the newest sample
+    pub min_samples: u64,
+    pub min_difficulty: u64,
+    timings: HashMap<String, HashMap<String, OperationTiming>>, // operation -> miner -> timing
+}
+
+impl DifficultyCalibrator {
+    pub fn new(reference_operation: String) -> Self {
+        Self {
+            reference_operation,
+            smoothing: 0.2,
+            min_samples: 3,
+            min_difficulty: 1,
+            timings: HashMap::new(),
+        }
+    }
+
+    /// Record how long `miner_id` took to compute one `operation`
+    pub fn record(&mut self, operation: &str, miner_id: &str, computation_time_ms: u64) {
+        let sample = computation_time_ms.max(1) as f64;
+        let timing = self.timings
+            .entry(operation.to_string())
+            .or_default()
+            .entry(miner_id.to_string())
+            .or_insert(OperationTiming { average_ms: sample, samples: 0 });
+
+        if timing.samples > 0 {
+            timing.average_ms = self.smoothing * sample + (1.0 - self.smoothing) * timing.average_ms;
+        }
+        timing.samples += 1;
+    }
+
+    /// Average compute time of an operation across miners with enough samples
+    pub fn average_time(&self, operation: &str) -> Option<f64> {
+        let timings: Vec<f64> = self.timings.get(operation)?
+            .values()
+            .filter(|timing| timing.samples >= self.min_samples)
+            .map(|timing| timing.average_ms)
+            .collect();
+
+        if timings.is_empty() {
+            None
+        } else {
+            Some(timings.iter().sum::<f64>() / timings.len() as f64)
+        }
+    }
+
+    /// Cost of `operation` relative to the reference operation.
+    /// Compared per miner where possible so fast and slow miners don't skew the ratio.
+    pub fn relative_cost(&self, operation: &str) -> Option<f64> {
+        if operation == self.reference_operation {
+            return Some(1.0);
+        }
+
+        let op_timings = self.timings.get(operation)?;
+        let ref_timings = self.timings.get(&self.reference_operation)?;
+
+        let ratios: Vec<f64> = op_timings.iter()
+            .filter(|(_, timing)| timing.samples >= self.min_samples)
+            .filter_map(|(miner_id, timing)| {
+                ref_timings.get(miner_id)
+                    .filter(|reference| reference.samples >= self.min_samples)
+                    .map(|reference| timing.average_ms / reference.average_ms)
+            })
+            .collect();
+
+        if !ratios.is_empty() {
+            return Some(ratios.iter().sum::<f64>() / ratios.len() as f64);
+        }
+
+        // No miner has run both operations yet, fall back to fleet averages
+        Some(self.average_time(operation)? / self.average_time(&self.reference_operation)?)
+    }
+
+    /// Difficulty for an `operation` task given the reference `base_difficulty`
+    pub fn difficulty_for(&self, operation: &str, base_difficulty: u64) -> u64 {
+        let cost = match self.relative_cost(operation) {
+            Some(cost) if cost > 0.0 => cost,
+            _ => return base_difficulty, // Not calibrated yet
+        };
+
+        let steps = cost.log(16.0).round() as i64;
+        (base_difficulty as i64 - steps).max(self.min_difficulty as i64) as u64
+    }
+
+    pub fn calibrated_operations(&self) -> Vec<String> {
+        self.timings.keys()
+            .filter(|operation| self.average_time(operation).is_some())
+            .cloned()
+            .collect()
+    }
+}
+
+impl Default for DifficultyCalibrator {
+    fn default() -> Self {
+        Self::new("relu".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expensive_operation_gets_lower_difficulty() {
+        let mut calibrator = DifficultyCalibrator::default();
+        for _ in 0..3 {
+            calibrator.record("relu", "cpu", 2);
+            calibrator.record("matrix_multiply", "cpu", 512);
+            calibrator.record("relu", "esp", 40);
+        }
+
+        // 256x more expensive is two hex digits of difficulty
+        assert_eq!(calibrator.difficulty_for("matrix_multiply", 6), 4);
+        assert_eq!(calibrator.difficulty_for("relu", 6), 6);
+        assert_eq!(calibrator.difficulty_for("convolution", 6), 6);
+        assert_eq!(calibrator.difficulty_for("matrix_multiply", 1), 1);
+    }
+}
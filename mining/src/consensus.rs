@@ -1,22 +1,30 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use chrono::{DateTime, Utc};
 use tokio::sync::RwLock;
 use std::sync::Arc;
-use tribechain_core::{TribeResult, TribeError, Block, Transaction};
+use tribechain_core::{TribeResult, TribeError, Block, DoubleSignEvidence, ValidatorPerformanceReport};
+use crate::pos::PosSchedule;
+use crate::dpos::{DposSchedule, DelegateSet};
+use crate::performance::PerformanceTracker;
 
-/// Consensus engine for managing different consensus algorithms
+/// Share of stake burned for signing two blocks at the same height
+pub const DOUBLE_SIGN_SLASH_PERCENTAGE: f64 = 5.0;
+
+/// Consensus engine driving a pluggable `Consensus` implementation
 #[derive(Debug)]
 pub struct ConsensusEngine {
-    pub consensus_type: ConsensusType,
+    pub consensus: Box<dyn Consensus>,
     pub is_running: bool,
     pub stats: ConsensusStats,
     pub validators: Arc<RwLock<HashMap<String, ValidatorInfo>>>,
     pub current_epoch: u64,
     pub last_finalized_block: Option<String>,
+    pub processed_evidence: HashSet<String>,
+    pub performance: PerformanceTracker,
 }
 
-/// Types of consensus algorithms supported
+/// Built-in consensus algorithms, kept as shorthand for their registry names
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ConsensusType {
     ProofOfWork,
@@ -25,6 +33,121 @@ pub enum ConsensusType {
     TensorProofOfWork, // AI3-specific consensus
 }
 
+impl ConsensusType {
+    /// Name the built-in implementation is registered under
+    pub fn name(&self) -> &'static str {
+        match self {
+            ConsensusType::ProofOfWork => "pow",
+            ConsensusType::ProofOfStake => "pos",
+            ConsensusType::DelegatedProofOfStake => "dpos",
+            ConsensusType::TensorProofOfWork => "tensor_pow",
+        }
+    }
+}
+
+/// Engine state a consensus implementation can read while validating
+pub struct ConsensusContext<'a> {
+    pub stats: &'a ConsensusStats,
+    pub validators: &'a HashMap<String, ValidatorInfo>,
+    pub current_epoch: u64,
+}
+
+/// A consensus algorithm that can be plugged into `ConsensusEngine`
+pub trait Consensus: std::fmt::Debug + Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Initialise engine state when consensus starts
+    fn start(&mut self, _stats: &mut ConsensusStats, _current_epoch: &mut u64) -> TribeResult<()> {
+        Ok(())
+    }
+
+    /// Consensus-specific block checks, recorded into `result`
+    fn validate_block(&self, block: &Block, context: &ConsensusContext, result: &mut ValidationResult) -> TribeResult<()>;
+
+    /// Producer scheduled for the slot at `timestamp`, for algorithms that schedule producers
+    fn scheduled_producer(&self, _timestamp: u64, _context: &ConsensusContext) -> Option<String> {
+        None
+    }
+
+    /// Feed on-chain votes in; returns the new epoch if the producer set was recomputed
+    fn update_votes(
+        &mut self,
+        _timestamp: u64,
+        _votes: &HashMap<String, u64>,
+        _validators: &mut HashMap<String, ValidatorInfo>,
+    ) -> Option<u64> {
+        None
+    }
+
+    /// Seconds per slot and slots per epoch, for algorithms that schedule producers
+    fn slot_timing(&self) -> Option<(u64, u64)> {
+        None
+    }
+
+    /// Observe an accepted block; returns producers demoted for missing their slots
+    fn record_block(&mut self, _block: &Block, _validators: &mut HashMap<String, ValidatorInfo>) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// Constructs a fresh consensus instance for each engine
+pub type ConsensusFactory = Arc<dyn Fn() -> Box<dyn Consensus> + Send + Sync>;
+
+/// Named consensus implementations available to engines
+#[derive(Clone, Default)]
+pub struct ConsensusRegistry {
+    factories: HashMap<String, ConsensusFactory>,
+}
+
+impl ConsensusRegistry {
+    /// Registry with no implementations
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry with PoW, PoS, DPoS and tensor PoW registered
+    pub fn with_builtin() -> Self {
+        let mut registry = Self::new();
+        registry.register(ConsensusType::ProofOfWork.name(), || Box::new(ProofOfWorkConsensus::default()));
+        registry.register(ConsensusType::ProofOfStake.name(), || Box::new(ProofOfStakeConsensus::default()));
+        registry.register(ConsensusType::DelegatedProofOfStake.name(), || Box::new(DelegatedProofOfStakeConsensus::default()));
+        registry.register(ConsensusType::TensorProofOfWork.name(), || Box::new(TensorProofOfWorkConsensus::default()));
+        registry
+    }
+
+    /// Add or replace the implementation registered under `name`
+    pub fn register<F>(&mut self, name: &str, factory: F)
+    where
+        F: Fn() -> Box<dyn Consensus> + Send + Sync + 'static,
+    {
+        self.factories.insert(name.to_string(), Arc::new(factory));
+    }
+
+    pub fn create(&self, name: &str) -> TribeResult<Box<dyn Consensus>> {
+        let factory = self.factories.get(name)
+            .ok_or_else(|| TribeError::Consensus(format!("Unknown consensus: {}", name)))?;
+        Ok(factory())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.factories.contains_key(name)
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.factories.keys().cloned().collect();
+        names.sort();
+        names
+    }
+}
+
+impl std::fmt::Debug for ConsensusRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConsensusRegistry")
+            .field("consensus", &self.names())
+            .finish()
+    }
+}
+
 /// Consensus statistics
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConsensusStats {
@@ -50,6 +173,8 @@ pub struct ValidatorInfo {
     pub slash_count: u32,
     pub last_activity: DateTime<Utc>,
     pub commission_rate: f64,
+    #[serde(default)]
+    pub public_key: String, // Hex ed25519 key block signatures are checked against; empty until registered
 }
 
 /// Block validation result
@@ -64,27 +189,37 @@ pub struct ValidationResult {
 
 impl ConsensusEngine {
     pub fn new(consensus_type: ConsensusType) -> TribeResult<Self> {
-        Ok(Self {
-            consensus_type,
+        Self::from_registry(&ConsensusRegistry::with_builtin(), consensus_type.name())
+    }
+
+    /// Create an engine for the implementation registered under `name`
+    pub fn from_registry(registry: &ConsensusRegistry, name: &str) -> TribeResult<Self> {
+        Ok(Self::with_consensus(registry.create(name)?))
+    }
+
+    pub fn with_consensus(consensus: Box<dyn Consensus>) -> Self {
+        Self {
+            consensus,
             is_running: false,
             stats: ConsensusStats::default(),
             validators: Arc::new(RwLock::new(HashMap::new())),
             current_epoch: 0,
             last_finalized_block: None,
-        })
+            processed_evidence: HashSet::new(),
+            performance: PerformanceTracker::default(),
+        }
+    }
+
+    pub fn consensus_name(&self) -> &str {
+        self.consensus.name()
     }
 
     pub async fn start(&mut self) -> TribeResult<()> {
         if self.is_running {
-            return Err(TribeError::InvalidOperation("Consensus already running".to_string()));
+            return Err(TribeError::Consensus("Consensus already running".to_string()));
         }
 
-        match self.consensus_type {
-            ConsensusType::ProofOfWork => self.start_pow().await?,
-            ConsensusType::ProofOfStake => self.start_pos().await?,
-            ConsensusType::DelegatedProofOfStake => self.start_dpos().await?,
-            ConsensusType::TensorProofOfWork => self.start_tensor_pow().await?,
-        }
+        self.consensus.start(&mut self.stats, &mut self.current_epoch)?;
 
         self.is_running = true;
         Ok(())
@@ -122,123 +257,388 @@ impl ConsensusEngine {
         }
 
         // Consensus-specific validation
-        match self.consensus_type {
-            ConsensusType::ProofOfWork => {
-                self.validate_pow_block(block, &mut result).await?;
-            }
-            ConsensusType::ProofOfStake => {
-                self.validate_pos_block(block, &mut result).await?;
-            }
-            ConsensusType::DelegatedProofOfStake => {
-                self.validate_dpos_block(block, &mut result).await?;
-            }
-            ConsensusType::TensorProofOfWork => {
-                self.validate_tensor_pow_block(block, &mut result).await?;
-            }
-        }
+        let validators = self.validators.read().await;
+        let context = ConsensusContext {
+            stats: &self.stats,
+            validators: &validators,
+            current_epoch: self.current_epoch,
+        };
+        self.consensus.validate_block(block, &context, &mut result)?;
 
         result.execution_time = start_time.elapsed().as_millis() as u64;
         Ok(result)
     }
 
-    async fn start_pow(&mut self) -> TribeResult<()> {
-        // Initialize Proof of Work consensus
-        self.stats.current_difficulty = 4; // Starting difficulty
+    pub async fn add_validator(&mut self, validator: ValidatorInfo) -> TribeResult<()> {
+        let mut validators = self.validators.write().await;
+        validators.insert(validator.address.clone(), validator);
+        self.stats.validator_count = validators.len();
         Ok(())
     }
 
-    async fn start_pos(&mut self) -> TribeResult<()> {
-        // Initialize Proof of Stake consensus
-        self.current_epoch = 1;
+    pub async fn remove_validator(&mut self, address: &str) -> TribeResult<()> {
+        let mut validators = self.validators.write().await;
+        validators.remove(address);
+        self.stats.validator_count = validators.len();
         Ok(())
     }
 
-    async fn start_dpos(&mut self) -> TribeResult<()> {
-        // Initialize Delegated Proof of Stake consensus
-        self.current_epoch = 1;
+    /// Set the ed25519 public key a validator's block signatures and evidence are checked against
+    pub async fn register_validator_key(&mut self, address: &str, public_key: String) -> TribeResult<()> {
+        let mut validators = self.validators.write().await;
+        let validator = validators.get_mut(address)
+            .ok_or_else(|| TribeError::Consensus(format!("Unknown validator: {}", address)))?;
+        validator.public_key = public_key;
         Ok(())
     }
 
-    async fn start_tensor_pow(&mut self) -> TribeResult<()> {
-        // Initialize Tensor Proof of Work consensus
-        self.stats.current_difficulty = 3; // Lower difficulty for AI3
+    /// Replace validator stakes, e.g. with `StakingContract::validator_stakes`.
+    /// Validators missing from `stakes` are deactivated rather than removed so their
+    /// slashing history is kept.
+    pub async fn sync_validator_stakes(&mut self, stakes: Vec<(String, u64)>) {
+        let mut validators = self.validators.write().await;
+        let staked: HashSet<String> = stakes.iter().map(|(address, _)| address.clone()).collect();
+
+        for (address, stake) in stakes {
+            validators.entry(address.clone())
+                .and_modify(|validator| validator.stake = stake)
+                .or_insert_with(|| ValidatorInfo::new(address, stake));
+        }
+        for validator in validators.values_mut() {
+            if !staked.contains(&validator.address) {
+                validator.is_active = false;
+            }
+        }
+
+        let total_stake: u64 = validators.values().filter(|v| v.is_active).map(|v| v.stake).sum();
+        for validator in validators.values_mut() {
+            validator.calculate_voting_power(total_stake);
+        }
+        self.stats.validator_count = validators.len();
+    }
+
+    /// Producer scheduled for the slot at `timestamp`, if the consensus schedules producers
+    pub async fn scheduled_producer(&self, timestamp: u64) -> Option<String> {
+        let validators = self.validators.read().await;
+        let context = ConsensusContext {
+            stats: &self.stats,
+            validators: &validators,
+            current_epoch: self.current_epoch,
+        };
+        self.consensus.scheduled_producer(timestamp, &context)
+    }
+
+    /// Pass on-chain delegate votes (e.g. `TribeChain::delegate_vote_tally`) to the consensus
+    pub async fn update_votes(&mut self, timestamp: u64, votes: &HashMap<String, u64>) {
+        let mut validators = self.validators.write().await;
+        if let Some(epoch) = self.consensus.update_votes(timestamp, votes, &mut validators) {
+            self.current_epoch = epoch;
+        }
+        self.stats.validator_count = validators.len();
+    }
+
+    /// Track an accepted block; returns producers demoted for missed slots
+    pub async fn record_block(&mut self, block: &Block) -> Vec<String> {
+        let mut validators = self.validators.write().await;
+
+        if let Some((slot_time, slots_per_epoch)) = self.consensus.slot_timing() {
+            let context = ConsensusContext {
+                stats: &self.stats,
+                validators: &validators,
+                current_epoch: self.current_epoch,
+            };
+            let consensus = &self.consensus;
+            let slot_time = slot_time.max(1);
+            self.performance.record_block(block.timestamp / slot_time, slots_per_epoch, &block.miner, |slot| {
+                consensus.scheduled_producer(slot * slot_time, &context)
+            });
+        }
+
+        self.consensus.record_block(block, &mut validators)
+    }
+
+    /// Signed performance reports for epochs closed since the last call, to relay to peers
+    pub fn take_performance_reports(&mut self, reporter: &str, private_key: &str) -> Vec<ValidatorPerformanceReport> {
+        self.performance.take_reports(reporter, private_key)
+    }
+
+    /// Apply a report signed by a known validator to the reported validator's uptime.
+    /// Returns `true` when the report is new and valid, so callers know to relay it.
+    pub async fn report_performance(&mut self, report: &ValidatorPerformanceReport) -> TribeResult<bool> {
+        let report_id = report.id();
+        if self.performance.processed_reports.contains(&report_id) {
+            return Ok(false);
+        }
+
+        let mut validators = self.validators.write().await;
+        let reporter = validators.get(&report.reporter)
+            .ok_or_else(|| TribeError::Consensus(format!("Unknown reporter: {}", report.reporter)))?;
+        if !reporter.is_active || !report.verify(&reporter.public_key) {
+            return Err(TribeError::Consensus("Invalid performance report".to_string()));
+        }
+
+        let validator = validators.get_mut(&report.validator)
+            .ok_or_else(|| TribeError::Consensus(format!("Unknown validator: {}", report.validator)))?;
+        validator.uptime = report.uptime() * 100.0;
+        self.performance.processed_reports.insert(report_id);
+        Ok(true)
+    }
+
+    /// Slash a validator for double signing.
+    /// Returns `true` when the evidence is new and valid, so callers know to relay it.
+    pub async fn report_evidence(&mut self, evidence: &DoubleSignEvidence) -> TribeResult<bool> {
+        let evidence_id = evidence.id();
+        if self.processed_evidence.contains(&evidence_id) {
+            return Ok(false);
+        }
+
+        let mut validators = self.validators.write().await;
+        let validator = validators.get_mut(&evidence.validator)
+            .ok_or_else(|| TribeError::Consensus(format!("Unknown validator: {}", evidence.validator)))?;
+
+        if !evidence.verify(&validator.public_key) {
+            return Err(TribeError::Consensus("Invalid double sign evidence".to_string()));
+        }
+
+        validator.slash(DOUBLE_SIGN_SLASH_PERCENTAGE);
+        self.processed_evidence.insert(evidence_id);
+        Ok(true)
+    }
+
+    pub fn get_hash_rate(&self) -> f64 {
+        self.stats.network_hash_rate
+    }
+
+    pub fn get_stats(&self) -> ConsensusStats {
+        self.stats.clone()
+    }
+
+    pub fn update_difficulty(&mut self, new_difficulty: u32) {
+        self.stats.current_difficulty = new_difficulty;
+    }
+
+    pub fn update_hash_rate(&mut self, hash_rate: f64) {
+        self.stats.network_hash_rate = hash_rate;
+    }
+
+    pub async fn finalize_block(&mut self, block_hash: String) -> TribeResult<()> {
+        self.last_finalized_block = Some(block_hash);
+        self.stats.blocks_validated += 1;
         Ok(())
     }
+}
+
+/// Leading-zero hash target
+#[derive(Debug, Clone)]
+pub struct ProofOfWorkConsensus {
+    pub initial_difficulty: u32,
+}
+
+impl Default for ProofOfWorkConsensus {
+    fn default() -> Self {
+        Self { initial_difficulty: 4 }
+    }
+}
 
-    async fn validate_pow_block(&self, block: &Block, result: &mut ValidationResult) -> TribeResult<()> {
-        // Validate proof of work
-        let target = "0".repeat(self.stats.current_difficulty as usize);
+impl Consensus for ProofOfWorkConsensus {
+    fn name(&self) -> &str {
+        ConsensusType::ProofOfWork.name()
+    }
+
+    fn start(&mut self, stats: &mut ConsensusStats, _current_epoch: &mut u64) -> TribeResult<()> {
+        stats.current_difficulty = self.initial_difficulty;
+        Ok(())
+    }
+
+    fn validate_block(&self, block: &Block, context: &ConsensusContext, result: &mut ValidationResult) -> TribeResult<()> {
+        let target = "0".repeat(context.stats.current_difficulty as usize);
         if !block.hash.starts_with(&target) {
             result.is_valid = false;
             result.errors.push("Invalid proof of work".to_string());
         }
         Ok(())
     }
+}
 
-    async fn validate_pos_block(&self, block: &Block, result: &mut ValidationResult) -> TribeResult<()> {
-        // Validate proof of stake
-        let validators = self.validators.read().await;
-        
-        if let Some(validator) = validators.get(&block.miner) {
-            if !validator.is_active {
-                result.is_valid = false;
-                result.errors.push("Block mined by inactive validator".to_string());
-            }
-            if validator.stake < 1000 { // Minimum stake requirement
+/// Stake-weighted proposer per slot; blocks must be signed by the scheduled validator
+#[derive(Debug, Clone, Default)]
+pub struct ProofOfStakeConsensus {
+    pub schedule: PosSchedule,
+}
+
+impl Consensus for ProofOfStakeConsensus {
+    fn name(&self) -> &str {
+        ConsensusType::ProofOfStake.name()
+    }
+
+    fn start(&mut self, _stats: &mut ConsensusStats, current_epoch: &mut u64) -> TribeResult<()> {
+        let now = Utc::now().timestamp() as u64;
+        *current_epoch = self.schedule.epoch_of(self.schedule.slot_at(now));
+        Ok(())
+    }
+
+    fn validate_block(&self, block: &Block, context: &ConsensusContext, result: &mut ValidationResult) -> TribeResult<()> {
+        let validator = match context.validators.get(&block.miner) {
+            Some(validator) => validator,
+            None => {
                 result.is_valid = false;
-                result.errors.push("Validator has insufficient stake".to_string());
+                result.errors.push("Unknown validator".to_string());
+                return Ok(());
             }
-        } else {
+        };
+
+        if !validator.is_active {
             result.is_valid = false;
-            result.errors.push("Unknown validator".to_string());
+            result.errors.push("Block mined by inactive validator".to_string());
         }
-        
+        if validator.stake < self.schedule.min_stake {
+            result.is_valid = false;
+            result.errors.push("Validator has insufficient stake".to_string());
+        }
+
+        let slot = self.schedule.slot_at(block.timestamp);
+        if self.schedule.proposer(context.validators, slot).as_deref() != Some(block.miner.as_str()) {
+            result.is_valid = false;
+            result.errors.push(format!("Validator is not the proposer for slot {}", slot));
+        }
+
+        if !block.verify_validator_signature(&validator.public_key) {
+            result.is_valid = false;
+            result.errors.push("Invalid validator signature".to_string());
+        }
+
         Ok(())
     }
 
-    async fn validate_dpos_block(&self, block: &Block, result: &mut ValidationResult) -> TribeResult<()> {
-        // Validate delegated proof of stake
-        let validators = self.validators.read().await;
-        
-        if let Some(validator) = validators.get(&block.miner) {
-            if !validator.is_active {
-                result.is_valid = false;
-                result.errors.push("Block produced by inactive delegate".to_string());
-            }
-            
-            // Check if it's the validator's turn to produce a block
-            let slot_time = 3; // 3 seconds per slot
-            let current_slot = (block.timestamp / slot_time) % validators.len() as u64;
-            let validator_index = validators.keys().position(|k| k == &block.miner).unwrap_or(0);
-            
-            if current_slot != validator_index as u64 {
+    fn scheduled_producer(&self, timestamp: u64, context: &ConsensusContext) -> Option<String> {
+        self.schedule.proposer(context.validators, self.schedule.slot_at(timestamp))
+    }
+
+    fn slot_timing(&self) -> Option<(u64, u64)> {
+        Some((self.schedule.slot_time, self.schedule.slots_per_epoch))
+    }
+}
+
+/// Top-voted delegates take turns producing blocks in fixed time slots
+#[derive(Debug, Clone, Default)]
+pub struct DelegatedProofOfStakeConsensus {
+    pub schedule: DposSchedule,
+    pub delegate_set: DelegateSet,
+}
+
+impl Consensus for DelegatedProofOfStakeConsensus {
+    fn name(&self) -> &str {
+        ConsensusType::DelegatedProofOfStake.name()
+    }
+
+    fn start(&mut self, _stats: &mut ConsensusStats, current_epoch: &mut u64) -> TribeResult<()> {
+        let now = Utc::now().timestamp() as u64;
+        *current_epoch = self.schedule.epoch_of(self.schedule.slot_at(now));
+        Ok(())
+    }
+
+    fn validate_block(&self, block: &Block, context: &ConsensusContext, result: &mut ValidationResult) -> TribeResult<()> {
+        let validator = match context.validators.get(&block.miner) {
+            Some(validator) => validator,
+            None => {
                 result.is_valid = false;
-                result.errors.push("Block produced out of turn".to_string());
+                result.errors.push("Unknown delegate".to_string());
+                return Ok(());
             }
-        } else {
+        };
+
+        if !validator.is_active {
             result.is_valid = false;
-            result.errors.push("Unknown delegate".to_string());
+            result.errors.push("Block produced by inactive delegate".to_string());
         }
-        
+
+        // Check if it's the delegate's turn to produce a block
+        if self.scheduled_producer(block.timestamp, context).as_deref() != Some(block.miner.as_str()) {
+            result.is_valid = false;
+            result.errors.push("Block produced out of turn".to_string());
+        }
+
+        if !block.verify_validator_signature(&validator.public_key) {
+            result.is_valid = false;
+            result.errors.push("Invalid delegate signature".to_string());
+        }
+
         Ok(())
     }
 
-    async fn validate_tensor_pow_block(&self, block: &Block, result: &mut ValidationResult) -> TribeResult<()> {
-        // Validate tensor proof of work
+    fn scheduled_producer(&self, timestamp: u64, _context: &ConsensusContext) -> Option<String> {
+        self.delegate_set.producer(self.schedule.slot_at(timestamp)).cloned()
+    }
+
+    fn slot_timing(&self) -> Option<(u64, u64)> {
+        Some((self.schedule.slot_time, self.schedule.slots_per_epoch))
+    }
+
+    fn update_votes(
+        &mut self,
+        timestamp: u64,
+        votes: &HashMap<String, u64>,
+        validators: &mut HashMap<String, ValidatorInfo>,
+    ) -> Option<u64> {
+        let epoch = self.schedule.epoch_of(self.schedule.slot_at(timestamp));
+        if self.delegate_set.epoch == Some(epoch) {
+            return None;
+        }
+
+        self.delegate_set.elect(&self.schedule, epoch, votes);
+        for delegate in &self.delegate_set.delegates {
+            let weight = votes.get(delegate).copied().unwrap_or(0);
+            validators.entry(delegate.clone())
+                .and_modify(|validator| validator.stake = weight)
+                .or_insert_with(|| ValidatorInfo::new(delegate.clone(), weight));
+        }
+        Some(epoch)
+    }
+
+    fn record_block(&mut self, block: &Block, validators: &mut HashMap<String, ValidatorInfo>) -> Vec<String> {
+        let slot = self.schedule.slot_at(block.timestamp);
+        self.delegate_set.record_block(&self.schedule, slot, &block.miner, validators)
+    }
+}
+
+/// Proof of work with a one-step discount for blocks carrying an AI3 proof
+#[derive(Debug, Clone)]
+pub struct TensorProofOfWorkConsensus {
+    pub initial_difficulty: u32,
+}
+
+impl Default for TensorProofOfWorkConsensus {
+    fn default() -> Self {
+        Self { initial_difficulty: 3 } // Lower difficulty for AI3
+    }
+}
+
+impl Consensus for TensorProofOfWorkConsensus {
+    fn name(&self) -> &str {
+        ConsensusType::TensorProofOfWork.name()
+    }
+
+    fn start(&mut self, stats: &mut ConsensusStats, _current_epoch: &mut u64) -> TribeResult<()> {
+        stats.current_difficulty = self.initial_difficulty;
+        Ok(())
+    }
+
+    fn validate_block(&self, block: &Block, context: &ConsensusContext, result: &mut ValidationResult) -> TribeResult<()> {
+        let difficulty = context.stats.current_difficulty;
+
         if let Some(ai3_proof) = &block.ai3_proof {
-            // Validate AI3 proof
             if ai3_proof.task_id.is_empty() {
                 result.is_valid = false;
                 result.errors.push("Invalid AI3 proof: empty task ID".to_string());
             }
-            
-            if ai3_proof.tensor_result.is_empty() {
+
+            if ai3_proof.tensor_hash.is_empty() {
                 result.is_valid = false;
-                result.errors.push("Invalid AI3 proof: empty tensor result".to_string());
+                result.errors.push("Invalid AI3 proof: empty tensor hash".to_string());
             }
-            
+
             // Reduced difficulty for AI3 mining
-            let adjusted_difficulty = std::cmp::max(1, self.stats.current_difficulty - 1);
+            let adjusted_difficulty = std::cmp::max(1, difficulty.saturating_sub(1));
             let target = "0".repeat(adjusted_difficulty as usize);
             if !block.hash.starts_with(&target) {
                 result.is_valid = false;
@@ -246,49 +646,13 @@ impl ConsensusEngine {
             }
         } else {
             // Regular PoW validation
-            let target = "0".repeat(self.stats.current_difficulty as usize);
+            let target = "0".repeat(difficulty as usize);
             if !block.hash.starts_with(&target) {
                 result.is_valid = false;
                 result.errors.push("Invalid proof of work".to_string());
             }
         }
-        
-        Ok(())
-    }
-
-    pub async fn add_validator(&mut self, validator: ValidatorInfo) -> TribeResult<()> {
-        let mut validators = self.validators.write().await;
-        validators.insert(validator.address.clone(), validator);
-        self.stats.validator_count = validators.len();
-        Ok(())
-    }
 
-    pub async fn remove_validator(&mut self, address: &str) -> TribeResult<()> {
-        let mut validators = self.validators.write().await;
-        validators.remove(address);
-        self.stats.validator_count = validators.len();
-        Ok(())
-    }
-
-    pub fn get_hash_rate(&self) -> f64 {
-        self.stats.network_hash_rate
-    }
-
-    pub fn get_stats(&self) -> ConsensusStats {
-        self.stats.clone()
-    }
-
-    pub fn update_difficulty(&mut self, new_difficulty: u32) {
-        self.stats.current_difficulty = new_difficulty;
-    }
-
-    pub fn update_hash_rate(&mut self, hash_rate: f64) {
-        self.stats.network_hash_rate = hash_rate;
-    }
-
-    pub async fn finalize_block(&mut self, block_hash: String) -> TribeResult<()> {
-        self.last_finalized_block = Some(block_hash);
-        self.stats.blocks_validated += 1;
         Ok(())
     }
 }
@@ -320,6 +684,7 @@ impl ValidatorInfo {
             slash_count: 0,
             last_activity: Utc::now(),
             commission_rate: 0.05, // 5% default commission
+            public_key: String::new(),
         }
     }
 
@@ -343,6 +708,7 @@ impl ValidatorInfo {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use tribechain_core::Transaction;
 
     #[tokio::test]
     async fn test_consensus_engine_creation() {
@@ -373,4 +739,127 @@ mod tests {
         engine.remove_validator("validator1").await.unwrap();
         assert_eq!(engine.stats.validator_count, 0);
     }
-} 
\ No newline at end of file
+
+    #[derive(Debug)]
+    struct AuthorityConsensus {
+        authority: String,
+    }
+
+    impl Consensus for AuthorityConsensus {
+        fn name(&self) -> &str {
+            "authority"
+        }
+
+        fn validate_block(&self, block: &Block, _context: &ConsensusContext, result: &mut ValidationResult) -> TribeResult<()> {
+            if block.miner != self.authority {
+                result.is_valid = false;
+                result.errors.push("Not the authority".to_string());
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_consensus_registry() {
+        let mut registry = ConsensusRegistry::with_builtin();
+        registry.register("authority", || Box::new(AuthorityConsensus { authority: "alice".to_string() }));
+        assert!(registry.contains("pow"));
+        assert!(matches!(registry.create("missing"), Err(TribeError::Consensus(_))));
+
+        let mut engine = ConsensusEngine::from_registry(&registry, "authority").unwrap();
+        engine.start().await.unwrap();
+        assert_eq!(engine.consensus_name(), "authority");
+
+        let mut block = Block::new(1, "0".repeat(64), vec![], "bob".to_string());
+        block.hash = block.calculate_hash();
+        let result = engine.validate_block(&block).await.unwrap();
+        assert!(!result.is_valid);
+        assert_eq!(result.errors, vec!["Not the authority".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_pos_validation_and_slashing() {
+        let mut engine = ConsensusEngine::new(ConsensusType::ProofOfStake).unwrap();
+        engine.sync_validator_stakes(vec![("alice".to_string(), 5000), ("bob".to_string(), 5000)]).await;
+        for name in ["alice", "bob"] {
+            engine.register_validator_key(name, tribechain_core::keys::public_key(&format!("{}-key", name))).await.unwrap();
+        }
+        engine.start().await.unwrap();
+
+        let schedule = PosSchedule::default();
+        let timestamp = 1_700_000_000;
+        let validators = engine.validators.read().await.clone();
+        let proposer = schedule.proposer(&validators, schedule.slot_at(timestamp)).unwrap();
+        let genesis = Block::genesis();
+
+        let private_key = format!("{}-key", proposer);
+        let block = schedule.produce_block(&validators, &genesis, vec![], &proposer, &private_key, timestamp).unwrap();
+        assert!(engine.validate_block(&block).await.unwrap().is_valid);
+
+        let mut forged = block.clone();
+        forged.sign_as_validator("mallory-key");
+        assert!(!engine.validate_block(&forged).await.unwrap().is_valid);
+
+        // Same slot, different contents
+        let conflicting = schedule.produce_block(&validators, &genesis, vec![
+            Transaction::new(proposer.clone(), tribechain_core::TransactionType::Transfer { to: "carol".to_string(), amount: 1 }, 0, 0),
+        ], &proposer, &private_key, timestamp).unwrap();
+        let evidence = DoubleSignEvidence::from_blocks(&block, &conflicting).unwrap();
+
+        // A conflicting block signed by someone else frames nobody
+        let mut framing = conflicting.clone();
+        framing.sign_as_validator("mallory-key");
+        let framed = DoubleSignEvidence::from_blocks(&block, &framing).unwrap();
+        assert!(engine.report_evidence(&framed).await.is_err());
+        assert_eq!(engine.validators.read().await[&proposer].stake, 5000);
+
+        assert!(engine.report_evidence(&evidence).await.unwrap());
+        assert!(!engine.report_evidence(&evidence).await.unwrap());
+        assert_eq!(engine.validators.read().await[&proposer].stake, 4750);
+    }
+
+    #[tokio::test]
+    async fn test_dpos_voting_and_schedule() {
+        let mut engine = ConsensusEngine::new(ConsensusType::DelegatedProofOfStake).unwrap();
+        let votes = HashMap::from([("alice".to_string(), 500), ("bob".to_string(), 400)]);
+        let timestamp = 1_700_000_001;
+        engine.update_votes(timestamp, &votes).await;
+        assert_eq!(engine.stats.validator_count, 2);
+        for name in ["alice", "bob"] {
+            engine.register_validator_key(name, tribechain_core::keys::public_key(&format!("{}-key", name))).await.unwrap();
+        }
+        assert!(engine.register_validator_key("carol", String::new()).await.is_err());
+
+        let producer = engine.scheduled_producer(timestamp).await.unwrap();
+        let mut block = Block::new(1, "0".repeat(64), vec![], producer.clone());
+        block.timestamp = timestamp;
+        block.hash = block.calculate_hash();
+        block.sign_as_validator(&format!("{}-key", producer));
+        assert!(engine.validate_block(&block).await.unwrap().is_valid);
+        assert!(engine.record_block(&block).await.is_empty());
+
+        // The other delegate's slot
+        let mut late = block.clone();
+        late.timestamp = timestamp + 3;
+        late.hash = late.calculate_hash();
+        late.sign_as_validator(&format!("{}-key", producer));
+        assert!(!engine.validate_block(&late).await.unwrap().is_valid);
+    }
+
+    #[tokio::test]
+    async fn test_performance_reports() {
+        let mut engine = ConsensusEngine::new(ConsensusType::ProofOfStake).unwrap();
+        engine.sync_validator_stakes(vec![("alice".to_string(), 5000), ("bob".to_string(), 5000)]).await;
+        engine.register_validator_key("alice", tribechain_core::keys::public_key("alice-key")).await.unwrap();
+
+        let mut report = ValidatorPerformanceReport::new("bob".to_string(), 3, 10, 4, "alice".to_string());
+        report.sign("alice-key");
+        assert!(engine.report_performance(&report).await.unwrap());
+        assert!(!engine.report_performance(&report).await.unwrap());
+        assert_eq!(engine.validators.read().await["bob"].uptime, 40.0);
+
+        let mut forged = ValidatorPerformanceReport::new("bob".to_string(), 4, 10, 0, "alice".to_string());
+        forged.sign("mallory-key");
+        assert!(matches!(engine.report_performance(&forged).await, Err(TribeError::Consensus(_))));
+    }
+}
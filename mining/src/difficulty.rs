@@ -1,5 +1,5 @@
 use serde::{Deserialize, Serialize};
-use chrono::{DateTime, Utc, Duration};
+use chrono::{DateTime, Utc};
 use std::collections::VecDeque;
 use tribechain_core::{TribeResult, TribeError};
 
@@ -22,9 +22,26 @@ pub enum DifficultyAlgorithm {
     Bitcoin, // Adjust every 2016 blocks
     Ethereum, // Adjust every block
     AI3Adaptive, // AI3-specific adaptive algorithm
+    Lwma(LwmaParams), // Linearly weighted moving average, adjusts every block
     Custom(CustomDifficultyParams),
 }
 
+/// LWMA parameters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LwmaParams {
+    pub window_size: u64, // Number of solve times averaged
+    pub target_time: u64,
+}
+
+impl Default for LwmaParams {
+    fn default() -> Self {
+        Self {
+            window_size: 60,
+            target_time: 30,
+        }
+    }
+}
+
 /// Custom difficulty parameters
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CustomDifficultyParams {
@@ -58,6 +75,7 @@ impl DifficultyAdjuster {
             DifficultyAlgorithm::Bitcoin => (600, 2016), // 10 minutes, 2016 blocks
             DifficultyAlgorithm::Ethereum => (15, 1), // 15 seconds, every block
             DifficultyAlgorithm::AI3Adaptive => (30, 10), // 30 seconds, every 10 blocks
+            DifficultyAlgorithm::Lwma(params) => (params.target_time, 1),
             DifficultyAlgorithm::Custom(params) => (params.target_time, params.window_size),
         };
 
@@ -88,6 +106,7 @@ impl DifficultyAdjuster {
             DifficultyAlgorithm::Bitcoin => 2016,
             DifficultyAlgorithm::Ethereum => 2048, // Keep more for better calculation
             DifficultyAlgorithm::AI3Adaptive => 100,
+            DifficultyAlgorithm::Lwma(params) => params.window_size + 1,
             DifficultyAlgorithm::Custom(params) => params.window_size * 2,
         };
 
@@ -98,10 +117,11 @@ impl DifficultyAdjuster {
 
     pub fn should_adjust(&self, block_height: u64) -> bool {
         match &self.algorithm {
-            DifficultyAlgorithm::Bitcoin => block_height % self.adjustment_interval == 0,
+            DifficultyAlgorithm::Bitcoin => block_height.is_multiple_of(self.adjustment_interval),
             DifficultyAlgorithm::Ethereum => true, // Adjust every block
-            DifficultyAlgorithm::AI3Adaptive => block_height % self.adjustment_interval == 0,
-            DifficultyAlgorithm::Custom(_) => block_height % self.adjustment_interval == 0,
+            DifficultyAlgorithm::AI3Adaptive => block_height.is_multiple_of(self.adjustment_interval),
+            DifficultyAlgorithm::Lwma(_) => true, // Adjust every block
+            DifficultyAlgorithm::Custom(_) => block_height.is_multiple_of(self.adjustment_interval),
         }
     }
 
@@ -118,6 +138,7 @@ impl DifficultyAdjuster {
             DifficultyAlgorithm::Bitcoin => self.bitcoin_adjustment(block_height)?,
             DifficultyAlgorithm::Ethereum => self.ethereum_adjustment(block_height)?,
             DifficultyAlgorithm::AI3Adaptive => self.ai3_adaptive_adjustment(block_height)?,
+            DifficultyAlgorithm::Lwma(params) => self.lwma_adjustment(block_height, params)?,
             DifficultyAlgorithm::Custom(params) => self.custom_adjustment(block_height, params)?,
         };
 
@@ -126,7 +147,7 @@ impl DifficultyAdjuster {
 
     fn bitcoin_adjustment(&self, block_height: u64) -> TribeResult<DifficultyAdjustment> {
         if self.block_times.len() < self.adjustment_interval as usize {
-            return Err(TribeError::InvalidOperation("Insufficient block data".to_string()));
+            return Err(TribeError::Consensus("Insufficient block data".to_string()));
         }
 
         let recent_blocks: Vec<_> = self.block_times
@@ -162,7 +183,7 @@ impl DifficultyAdjuster {
 
     fn ethereum_adjustment(&self, block_height: u64) -> TribeResult<DifficultyAdjustment> {
         if self.block_times.len() < 2 {
-            return Err(TribeError::InvalidOperation("Insufficient block data".to_string()));
+            return Err(TribeError::Consensus("Insufficient block data".to_string()));
         }
 
         let latest = self.block_times.back().unwrap();
@@ -178,7 +199,7 @@ impl DifficultyAdjuster {
             1.0 - (block_time - self.target_block_time) as f64 / self.target_block_time as f64 * 0.1
         };
 
-        let clamped_factor = adjustment_factor.max(0.9).min(1.1);
+        let clamped_factor = adjustment_factor.clamp(0.9, 1.1);
         let new_difficulty = ((self.current_difficulty as f64 * clamped_factor) as u32)
             .max(self.min_difficulty)
             .min(self.max_difficulty);
@@ -195,7 +216,7 @@ impl DifficultyAdjuster {
 
     fn ai3_adaptive_adjustment(&self, block_height: u64) -> TribeResult<DifficultyAdjustment> {
         if self.block_times.len() < self.adjustment_interval as usize {
-            return Err(TribeError::InvalidOperation("Insufficient block data".to_string()));
+            return Err(TribeError::Consensus("Insufficient block data".to_string()));
         }
 
         let recent_blocks: Vec<_> = self.block_times
@@ -224,13 +245,13 @@ impl DifficultyAdjuster {
         
         // Factor in hash rate changes (AI3 miners may have varying capabilities)
         let hash_rate_factor = if avg_hash_rate > 0.0 {
-            (avg_hash_rate / 1000.0).min(2.0).max(0.5) // Normalize and clamp
+            (avg_hash_rate / 1000.0).clamp(0.5, 2.0) // Normalize and clamp
         } else {
             1.0
         };
 
         let adjustment_factor = base_adjustment * hash_rate_factor;
-        let clamped_factor = adjustment_factor.max(0.5).min(2.0); // More conservative for AI3
+        let clamped_factor = adjustment_factor.clamp(0.5, 2.0); // More conservative for AI3
 
         let new_difficulty = ((self.current_difficulty as f64 * clamped_factor) as u32)
             .max(self.min_difficulty)
@@ -246,9 +267,59 @@ impl DifficultyAdjuster {
         })
     }
 
+    /// LWMA: recent solve times weigh more, so the difficulty tracks sudden
+    /// hash rate swings (e.g. ESP fleets joining or leaving) without oscillating.
+    fn lwma_adjustment(&self, block_height: u64, params: &LwmaParams) -> TribeResult<DifficultyAdjustment> {
+        let target = params.target_time.max(1) as f64;
+        let window = (params.window_size as usize).min(self.block_times.len() - 1);
+        if window == 0 {
+            return Err(TribeError::Consensus("Insufficient block data".to_string()));
+        }
+
+        let records: Vec<_> = self.block_times
+            .iter()
+            .skip(self.block_times.len() - window - 1)
+            .collect();
+
+        // Measure each solve time against the latest timestamp seen so far, so a miner
+        // cannot lower difficulty with a backdated timestamp, and clamp outliers to 6T
+        let mut previous_max = records[0].timestamp;
+        let mut weighted_solve_time = 0.0;
+        let mut difficulty_sum = 0.0;
+        for (i, record) in records.iter().skip(1).enumerate() {
+            let solve_time = record.timestamp
+                .signed_duration_since(previous_max)
+                .num_seconds()
+                .clamp(1, 6 * target as i64) as f64;
+            if record.timestamp > previous_max {
+                previous_max = record.timestamp;
+            }
+
+            weighted_solve_time += (i + 1) as f64 * solve_time;
+            difficulty_sum += record.difficulty as f64;
+        }
+
+        let weight_sum = (window * (window + 1) / 2) as f64;
+        let average_difficulty = difficulty_sum / window as f64;
+        let next_difficulty = average_difficulty * target * weight_sum / weighted_solve_time;
+
+        let new_difficulty = (next_difficulty.round() as u32)
+            .max(self.min_difficulty)
+            .min(self.max_difficulty);
+
+        Ok(DifficultyAdjustment {
+            old_difficulty: self.current_difficulty,
+            new_difficulty,
+            adjustment_factor: new_difficulty as f64 / self.current_difficulty.max(1) as f64,
+            reason: format!("LWMA: weighted solve time {:.1}s over {} blocks, target {}s",
+                weighted_solve_time / weight_sum, window, params.target_time),
+            effective_block_height: block_height,
+        })
+    }
+
     fn custom_adjustment(&self, block_height: u64, params: &CustomDifficultyParams) -> TribeResult<DifficultyAdjustment> {
         if self.block_times.len() < params.window_size as usize {
-            return Err(TribeError::InvalidOperation("Insufficient block data".to_string()));
+            return Err(TribeError::Consensus("Insufficient block data".to_string()));
         }
 
         let recent_blocks: Vec<_> = self.block_times
@@ -338,6 +409,7 @@ impl Default for DifficultyAdjuster {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::Duration;
 
     #[test]
     fn test_difficulty_adjuster_creation() {
@@ -387,7 +459,7 @@ mod tests {
         
         // Add blocks with consistent 30-second intervals (target time)
         for i in 0..10 {
-            adjuster.add_block_time(i + 1, now + Duration::seconds(i * 30), 1000.0);
+            adjuster.add_block_time(i + 1, now + Duration::seconds(i as i64 * 30), 1000.0);
         }
         
         let adjustment = adjuster.calculate_adjustment(10).unwrap();
@@ -397,4 +469,30 @@ mod tests {
         // Should maintain difficulty since we're hitting target time
         assert!((adj.adjustment_factor - 1.0).abs() < 0.1);
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_lwma_adjustment() {
+        let params = LwmaParams { window_size: 10, target_time: 30 };
+        let mut adjuster = DifficultyAdjuster::new(DifficultyAlgorithm::Lwma(params));
+        let now = Utc::now();
+
+        // On-target blocks keep the difficulty
+        for i in 0..11 {
+            adjuster.add_block_time(i, now + Duration::seconds(i as i64 * 30), 1000.0);
+        }
+        let adjustment = adjuster.calculate_adjustment(11).unwrap().unwrap();
+        assert_eq!(adjustment.new_difficulty, 4);
+
+        // Fast recent blocks raise it
+        for i in 11..16 {
+            adjuster.add_block_time(i, now + Duration::seconds(300 + (i as i64 - 10) * 5), 1000.0);
+        }
+        let adjustment = adjuster.calculate_adjustment(16).unwrap().unwrap();
+        assert!(adjustment.new_difficulty > 4);
+
+        // A backdated timestamp counts as a 1s solve, not a negative one
+        adjuster.add_block_time(16, now, 1000.0);
+        let adjustment = adjuster.calculate_adjustment(17).unwrap().unwrap();
+        assert!(adjustment.new_difficulty > 4);
+    }
+}
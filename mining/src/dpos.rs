@@ -0,0 +1,152 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use crate::consensus::ValidatorInfo;
+
+/// Delegate election and production timing for delegated proof of stake
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DposSchedule {
+    pub delegate_count: usize, // Size of the elected producer set
+    pub slot_time: u64, // seconds per slot
+    pub slots_per_epoch: u64, // Delegates are re-elected every epoch
+    pub max_missed_slots: u32, // Consecutive misses before a delegate is demoted
+}
+
+impl DposSchedule {
+    pub fn slot_at(&self, timestamp: u64) -> u64 {
+        timestamp / self.slot_time.max(1)
+    }
+
+    pub fn epoch_of(&self, slot: u64) -> u64 {
+        slot / self.slots_per_epoch.max(1)
+    }
+}
+
+impl Default for DposSchedule {
+    fn default() -> Self {
+        Self {
+            delegate_count: 21,
+            slot_time: 3,
+            slots_per_epoch: 210, // 10 rounds of 21 delegates
+            max_missed_slots: 10,
+        }
+    }
+}
+
+/// Elected delegates for the current epoch and their production record
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DelegateSet {
+    pub epoch: Option<u64>,
+    pub delegates: Vec<String>, // Production order within a round
+    pub missed_slots: HashMap<String, u32>,
+    pub demoted: HashSet<String>,
+    pub last_slot: Option<u64>,
+}
+
+impl DelegateSet {
+    /// Elect the top `schedule.delegate_count` by votes for `epoch`, skipping demoted delegates.
+    /// Ties are broken by address so every node elects the same set.
+    pub fn elect(&mut self, schedule: &DposSchedule, epoch: u64, votes: &HashMap<String, u64>) {
+        let mut candidates: Vec<(&String, u64)> = votes.iter()
+            .filter(|(delegate, &weight)| weight > 0 && !self.demoted.contains(*delegate))
+            .map(|(delegate, &weight)| (delegate, weight))
+            .collect();
+        candidates.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+
+        let mut delegates: Vec<String> = candidates.into_iter()
+            .take(schedule.delegate_count)
+            .map(|(delegate, _)| delegate.clone())
+            .collect();
+        delegates.sort(); // Round order independent of vote swings
+
+        self.delegates = delegates;
+        self.epoch = Some(epoch);
+        self.missed_slots.retain(|delegate, _| self.delegates.contains(delegate));
+    }
+
+    /// Round-robin producer for `slot`
+    pub fn producer(&self, slot: u64) -> Option<&String> {
+        if self.delegates.is_empty() {
+            return None;
+        }
+        self.delegates.get((slot % self.delegates.len() as u64) as usize)
+    }
+
+    /// Record a block produced in `slot`, charging every skipped slot since the last
+    /// recorded one to its scheduled producer. Returns delegates demoted as a result.
+    pub fn record_block(
+        &mut self,
+        schedule: &DposSchedule,
+        slot: u64,
+        producer: &str,
+        validators: &mut HashMap<String, ValidatorInfo>,
+    ) -> Vec<String> {
+        let mut demoted = Vec::new();
+
+        if let Some(last_slot) = self.last_slot {
+            if slot <= last_slot {
+                return demoted; // Already accounted for
+            }
+            for missed in last_slot + 1..slot {
+                let delegate = match self.producer(missed) {
+                    Some(delegate) => delegate.clone(),
+                    None => continue,
+                };
+                let count = self.missed_slots.entry(delegate.clone()).or_insert(0);
+                *count += 1;
+
+                if *count >= schedule.max_missed_slots && self.demoted.insert(delegate.clone()) {
+                    if let Some(validator) = validators.get_mut(&delegate) {
+                        validator.is_active = false;
+                    }
+                    demoted.push(delegate);
+                }
+            }
+        }
+
+        self.missed_slots.insert(producer.to_string(), 0);
+        self.last_slot = Some(slot);
+        demoted
+    }
+
+    /// Allow a demoted delegate to stand for election again
+    pub fn reinstate(&mut self, delegate: &str) {
+        self.demoted.remove(delegate);
+        self.missed_slots.remove(delegate);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_election_round_robin_and_demotion() {
+        let schedule = DposSchedule { delegate_count: 3, max_missed_slots: 2, ..Default::default() };
+        let votes = HashMap::from([
+            ("carol".to_string(), 300),
+            ("alice".to_string(), 500),
+            ("bob".to_string(), 400),
+            ("dave".to_string(), 100),
+        ]);
+
+        let mut set = DelegateSet::default();
+        set.elect(&schedule, 0, &votes);
+        assert_eq!(set.delegates, vec!["alice", "bob", "carol"]);
+        assert_eq!(set.producer(4).map(String::as_str), Some("bob"));
+
+        let mut validators: HashMap<String, ValidatorInfo> = set.delegates.iter()
+            .map(|d| (d.clone(), ValidatorInfo::new(d.clone(), 0)))
+            .collect();
+
+        // carol's slots 2 and 5 are skipped
+        assert!(set.record_block(&schedule, 0, "alice", &mut validators).is_empty());
+        assert!(set.record_block(&schedule, 1, "bob", &mut validators).is_empty());
+        assert!(set.record_block(&schedule, 3, "alice", &mut validators).is_empty());
+        assert!(set.record_block(&schedule, 4, "bob", &mut validators).is_empty());
+        assert_eq!(set.record_block(&schedule, 6, "alice", &mut validators), vec!["carol".to_string()]);
+        assert!(!validators["carol"].is_active);
+
+        set.elect(&schedule, 1, &votes);
+        assert_eq!(set.delegates, vec!["alice", "bob", "dave"]);
+    }
+}
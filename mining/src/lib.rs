@@ -4,14 +4,28 @@ pub mod difficulty;
 pub mod pool;
 pub mod proof_of_work;
 pub mod ai3_mining;
+pub mod stratum;
+pub mod payout;
+pub mod vardiff;
+pub mod calibration;
+pub mod pos;
+pub mod dpos;
+pub mod performance;
 
 // Re-export main types
 pub use miner::{Miner, MinerStats, MinerCapabilities};
-pub use consensus::{ConsensusEngine, ConsensusType, ConsensusStats};
-pub use difficulty::{DifficultyAdjuster, DifficultyAdjustment};
-pub use pool::{MiningPool, PoolStats, MiningShare};
+pub use consensus::{ConsensusEngine, ConsensusType, ConsensusStats, Consensus, ConsensusContext, ConsensusRegistry};
+pub use difficulty::{DifficultyAdjuster, DifficultyAdjustment, DifficultyAlgorithm, LwmaParams};
+pub use pool::{MiningPool, PoolStats, MiningShare, MinerShareStats, RoundShares, PoolRound, ShareOutcome, MAX_POOL_ROUNDS};
 pub use proof_of_work::{ProofOfWork, WorkProof, AI3WorkProof, MiningWork};
 pub use ai3_mining::{AI3Miner, AI3MiningResult, AI3Proof, AI3MiningPool};
+pub use calibration::DifficultyCalibrator;
+pub use pos::PosSchedule;
+pub use dpos::{DposSchedule, DelegateSet};
+pub use performance::PerformanceTracker;
+pub use payout::{PayoutLedger, PoolPayout, ShareRecord};
+pub use vardiff::{VardiffConfig, ShareTarget};
+pub use stratum::{StratumServer, StratumRequest, StratumMethod, StratumResponse, StratumNotification, StratumJob};
 
 // Re-export ai3-lib mining types for convenience
 pub use ai3_lib::mining::{
@@ -48,6 +62,11 @@ impl MiningEngine {
         })
     }
 
+    pub fn with_difficulty_algorithm(mut self, algorithm: difficulty::DifficultyAlgorithm) -> Self {
+        self.difficulty = difficulty::DifficultyAdjuster::new(algorithm);
+        self
+    }
+
     pub fn with_ai3_mining(mut self, pool_id: String) -> Self {
         self.ai3_mining = Some(ai3_mining::AI3MiningPool::new(pool_id));
         self
@@ -55,7 +74,7 @@ impl MiningEngine {
 
     pub async fn start_mining(&mut self) -> TribeResult<()> {
         if self.is_mining {
-            return Err(TribeError::InvalidOperation("Mining already started".to_string()));
+            return Err(TribeError::Mining("Mining already started".to_string()));
         }
         
         self.consensus.start().await?;
@@ -86,7 +105,7 @@ impl MiningEngine {
             ai3_pool.add_miner(miner);
             Ok(())
         } else {
-            Err(TribeError::InvalidOperation("AI3 mining not enabled".to_string()))
+            Err(TribeError::Mining("AI3 mining not enabled".to_string()))
         }
     }
 
@@ -98,14 +117,28 @@ impl MiningEngine {
         difficulty: u64,
     ) -> TribeResult<String> {
         if let Some(ai3_pool) = &mut self.ai3_mining {
+            let difficulty = ai3_pool.calibrator.difficulty_for(&operation_type, difficulty);
+
             // Use the first available miner to create the task
             if let Some(miner) = ai3_pool.miners.values_mut().next() {
                 miner.create_mining_task(&block, operation_type, difficulty).await
             } else {
-                Err(TribeError::InvalidOperation("No AI3 miners available".to_string()))
+                Err(TribeError::Mining("No AI3 miners available".to_string()))
             }
         } else {
-            Err(TribeError::InvalidOperation("AI3 mining not enabled".to_string()))
+            Err(TribeError::Mining("AI3 mining not enabled".to_string()))
+        }
+    }
+
+    /// Hand tasks escrowed by tensor computation contracts (`ContractEngine::take_tensor_tasks`)
+    /// to the AI3 pool and distribute them to its miners
+    pub async fn submit_escrow_tasks(&mut self, tasks: Vec<tribechain_core::TensorTask>) -> TribeResult<Vec<(String, Vec<String>)>> {
+        let base_difficulty = self.difficulty.get_current_difficulty() as u64;
+        if let Some(ai3_pool) = &mut self.ai3_mining {
+            ai3_pool.queue_escrow_tasks(tasks, base_difficulty)?;
+            ai3_pool.distribute_queued_tasks().await
+        } else {
+            Err(TribeError::Mining("AI3 mining not enabled".to_string()))
         }
     }
 
@@ -134,22 +167,32 @@ impl MiningEngine {
     ) -> TribeResult<Option<proof_of_work::WorkProof>> {
         // Try AI3 mining first if available
         if let Some(ai3_pool) = &mut self.ai3_mining {
+            let operation_type = "matrix_multiply".to_string();
+            let difficulty = ai3_pool.calibrator.difficulty_for(
+                &operation_type,
+                self.difficulty.get_current_difficulty() as u64,
+            );
+
             if let Some(ai3_miner) = ai3_pool.miners.get_mut(&miner_id) {
                 // Create AI3 task from block
                 let task_id = ai3_miner.create_mining_task(
                     &work.block_template,
-                    "matrix_multiply".to_string(),
-                    self.difficulty.get_current_difficulty() as u64,
+                    operation_type.clone(),
+                    difficulty,
                 ).await?;
 
                 // Try AI3 mining step
                 if let Some(ai3_result) = ai3_miner.mine_step(&task_id).await? {
+                    ai3_pool.calibrator.record(&operation_type, &miner_id, ai3_result.computation_time);
+
                     // Convert AI3 result to work proof
                     let ai3_work_proof = proof_of_work::AI3WorkProof {
                         task_id: ai3_result.task_id,
                         tensor_result: ai3_result.tensor_result,
                         computation_hash: ai3_result.ai3_proof.computation_hash,
-                        verification_nonce: 0, // Would be set from AI3 result
+                        verification_nonce: ai3_result.ai3_proof.commitment_nonce,
+                        commitment: Some(ai3_result.ai3_proof.commitment_hash),
+                        computation_proof: None,
                     };
 
                     let work_proof = proof_of_work::WorkProof {
@@ -1,7 +1,5 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 use chrono::{DateTime, Utc};
-use tribechain_core::{TribeResult, TribeError};
 
 /// Basic miner structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -262,6 +260,6 @@ mod tests {
         assert_eq!(miner.stats.total_hash_attempts, 1000);
         assert_eq!(miner.stats.successful_hashes, 1);
         assert_eq!(miner.stats.blocks_mined, 1);
-        assert_eq!(miner.stats.average_hash_time, 100.0);
+        assert_eq!(miner.stats.average_hash_time, 0.1); // 100ms over 1000 attempts
     }
 } 
\ No newline at end of file
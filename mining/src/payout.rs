@@ -0,0 +1,213 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use chrono::{DateTime, Utc};
+use tribechain_core::{Transaction, TransactionType};
+
+/// Accepted share as seen by the payout ledger
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShareRecord {
+    pub miner_id: String,
+    pub weight: u64,
+    pub block_height: u64,
+}
+
+/// Reward owed to a miner, ready to be paid on chain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PoolPayout {
+    pub miner_id: String,
+    pub address: String,
+    pub amount: u64,
+    pub block_height: u64,
+    pub created_at: DateTime<Utc>,
+}
+
+impl PoolPayout {
+    /// On-chain transfer from the pool wallet; the fee is paid by the pool
+    pub fn to_transaction(&self, pool_address: &str, fee: u64, nonce: u64) -> Transaction {
+        Transaction::new(
+            pool_address.to_string(),
+            TransactionType::Transfer {
+                to: self.address.clone(),
+                amount: self.amount,
+            },
+            fee,
+            nonce,
+        )
+    }
+}
+
+/// Share windows and per-miner reward accrual for a pool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PayoutLedger {
+    pub window: VecDeque<ShareRecord>, // Most recent shares, oldest first
+    pub max_window: usize,
+    pub round_shares: HashMap<String, u64>, // Weight per miner since the last block
+    pub balances: HashMap<String, u64>, // Accrued, not yet paid
+    pub total_paid: HashMap<String, u64>,
+}
+
+impl PayoutLedger {
+    pub fn new(max_window: usize) -> Self {
+        Self {
+            window: VecDeque::new(),
+            max_window,
+            round_shares: HashMap::new(),
+            balances: HashMap::new(),
+            total_paid: HashMap::new(),
+        }
+    }
+
+    /// Each difficulty step is one more leading hex zero, i.e. 16x the work
+    pub fn share_weight(difficulty: u32) -> u64 {
+        16u64.saturating_pow(difficulty)
+    }
+
+    pub fn record_share(&mut self, miner_id: &str, difficulty: u32, block_height: u64) {
+        let weight = Self::share_weight(difficulty);
+        *self.round_shares.entry(miner_id.to_string()).or_insert(0) += weight;
+
+        self.window.push_back(ShareRecord {
+            miner_id: miner_id.to_string(),
+            weight,
+            block_height,
+        });
+        while self.window.len() > self.max_window {
+            self.window.pop_front();
+        }
+    }
+
+    /// PROP: split by share weight submitted during the round that found the block
+    pub fn credit_proportional(&mut self, reward: u64, finder: &str) -> HashMap<String, u64> {
+        let weights: Vec<(String, u64)> = self.round_shares.drain().collect();
+        self.credit_weighted(reward, weights, finder)
+    }
+
+    /// PPLNS: split by the last `n` shares regardless of round boundaries
+    pub fn credit_pplns(&mut self, reward: u64, n: u64, finder: &str) -> HashMap<String, u64> {
+        let mut weights: HashMap<String, u64> = HashMap::new();
+        for share in self.window.iter().rev().take(n as usize) {
+            *weights.entry(share.miner_id.clone()).or_insert(0) += share.weight;
+        }
+        self.round_shares.clear();
+        self.credit_weighted(reward, weights.into_iter().collect(), finder)
+    }
+
+    /// Solo: the block finder keeps the whole reward
+    pub fn credit_solo(&mut self, reward: u64, finder: &str) -> HashMap<String, u64> {
+        self.round_shares.clear();
+        self.credit_weighted(reward, vec![(finder.to_string(), 1)], finder)
+    }
+
+    fn credit_weighted(&mut self, reward: u64, weights: Vec<(String, u64)>, finder: &str) -> HashMap<String, u64> {
+        let total_weight: u128 = weights.iter().map(|(_, weight)| *weight as u128).sum();
+        let mut credited = HashMap::new();
+
+        for (miner_id, weight) in weights {
+            let amount = (reward as u128 * weight as u128).checked_div(total_weight).unwrap_or(0) as u64;
+            if amount > 0 {
+                credited.insert(miner_id, amount);
+            }
+        }
+
+        // Rounding dust goes to the finder so the full reward is accounted for,
+        // which is all of it when nobody has any weight
+        let dust = reward - credited.values().sum::<u64>();
+        if dust > 0 {
+            *credited.entry(finder.to_string()).or_insert(0) += dust;
+        }
+
+        for (miner_id, amount) in &credited {
+            *self.balances.entry(miner_id.clone()).or_insert(0) += amount;
+        }
+        credited
+    }
+
+    pub fn pending_balance(&self, miner_id: &str) -> u64 {
+        self.balances.get(miner_id).copied().unwrap_or(0)
+    }
+
+    /// Move every balance at or above `threshold` into payouts
+    pub fn take_payouts(
+        &mut self,
+        threshold: u64,
+        block_height: u64,
+        addresses: &HashMap<String, String>,
+    ) -> Vec<PoolPayout> {
+        let due: Vec<String> = self.balances.iter()
+            .filter(|(_, &balance)| balance > 0 && balance >= threshold)
+            .map(|(miner_id, _)| miner_id.clone())
+            .collect();
+
+        let mut payouts = Vec::new();
+        for miner_id in due {
+            let address = match addresses.get(&miner_id) {
+                Some(address) => address.clone(),
+                None => continue, // Keep accruing until the miner's address is known
+            };
+            let amount = self.balances.remove(&miner_id).unwrap_or(0);
+            *self.total_paid.entry(miner_id.clone()).or_insert(0) += amount;
+
+            payouts.push(PoolPayout {
+                miner_id,
+                address,
+                amount,
+                block_height,
+                created_at: Utc::now(),
+            });
+        }
+        payouts
+    }
+}
+
+impl Default for PayoutLedger {
+    fn default() -> Self {
+        Self::new(100_000)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pplns_window() {
+        let mut ledger = PayoutLedger::new(4);
+        ledger.record_share("alice", 1, 1);
+        ledger.record_share("alice", 1, 1);
+        ledger.record_share("bob", 1, 1);
+        ledger.record_share("bob", 1, 1);
+        ledger.record_share("bob", 1, 1);
+        assert_eq!(ledger.window.len(), 4);
+
+        // Last 2 shares are both bob's
+        let credited = ledger.credit_pplns(1000, 2, "bob");
+        assert_eq!(credited.get("bob"), Some(&1000));
+        assert_eq!(credited.get("alice"), None);
+    }
+
+    #[test]
+    fn test_proportional_and_payouts() {
+        let mut ledger = PayoutLedger::default();
+        ledger.record_share("alice", 1, 1);
+        ledger.record_share("bob", 1, 1);
+        ledger.record_share("bob", 1, 1);
+
+        let credited = ledger.credit_proportional(1000, "bob");
+        assert_eq!(credited.values().sum::<u64>(), 1000);
+        assert_eq!(ledger.pending_balance("alice"), 333);
+        assert!(ledger.round_shares.is_empty());
+
+        let addresses = HashMap::from([
+            ("alice".to_string(), "addr_alice".to_string()),
+            ("bob".to_string(), "addr_bob".to_string()),
+        ]);
+        let payouts = ledger.take_payouts(500, 1, &addresses);
+        assert_eq!(payouts.len(), 1);
+        assert_eq!(payouts[0].address, "addr_bob");
+        assert_eq!(ledger.pending_balance("bob"), 0);
+        assert_eq!(ledger.pending_balance("alice"), 333);
+
+        let tx = payouts[0].to_transaction("pool_wallet", 10, 0);
+        assert_eq!(tx.from, "pool_wallet");
+    }
+}
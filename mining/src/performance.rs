@@ -0,0 +1,97 @@
+use std::collections::{BTreeMap, HashSet};
+use tribechain_core::ValidatorPerformanceReport;
+
+/// Per-epoch slot assignments and produced blocks for slot-scheduled consensus
+#[derive(Debug, Clone, Default)]
+pub struct PerformanceTracker {
+    pub epoch: Option<u64>,
+    pub last_slot: Option<u64>,
+    pub assigned: BTreeMap<String, u64>,
+    pub produced: BTreeMap<String, u64>,
+    pub completed: Vec<ValidatorPerformanceReport>, // Closed epochs, not yet signed
+    pub processed_reports: HashSet<String>, // Report ids already applied
+}
+
+impl PerformanceTracker {
+    /// Count slots up to and including `slot`, closing any epochs that ended.
+    /// Slots skipped since the last block were missed by their scheduled producer;
+    /// at most one epoch of them is walked after a long gap.
+    pub fn record_block<F>(&mut self, slot: u64, slots_per_epoch: u64, miner: &str, producer_at: F)
+    where
+        F: Fn(u64) -> Option<String>,
+    {
+        let slots_per_epoch = slots_per_epoch.max(1);
+        let first = match self.last_slot {
+            Some(last) if slot <= last => return, // Already accounted for
+            Some(last) => (last + 1).max(slot.saturating_sub(slots_per_epoch)),
+            None => slot,
+        };
+
+        for current in first..=slot {
+            let epoch = current / slots_per_epoch;
+            if self.epoch != Some(epoch) {
+                self.close_epoch();
+                self.epoch = Some(epoch);
+            }
+
+            if let Some(producer) = producer_at(current) {
+                *self.assigned.entry(producer.clone()).or_insert(0) += 1;
+                if current == slot && producer == miner {
+                    *self.produced.entry(producer).or_insert(0) += 1;
+                }
+            }
+        }
+        self.last_slot = Some(slot);
+    }
+
+    fn close_epoch(&mut self) {
+        let epoch = match self.epoch {
+            Some(epoch) => epoch,
+            None => return,
+        };
+
+        for (validator, assigned) in std::mem::take(&mut self.assigned) {
+            let produced = self.produced.get(&validator).copied().unwrap_or(0);
+            self.completed.push(ValidatorPerformanceReport::new(validator, epoch, assigned, produced, String::new()));
+        }
+        self.produced.clear();
+    }
+
+    /// Reports for closed epochs, signed by `reporter`
+    pub fn take_reports(&mut self, reporter: &str, private_key: &str) -> Vec<ValidatorPerformanceReport> {
+        let mut reports = std::mem::take(&mut self.completed);
+        for report in reports.iter_mut() {
+            report.reporter = reporter.to_string();
+            report.sign(private_key);
+        }
+        reports
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missed_slots_are_reported_per_epoch() {
+        let mut tracker = PerformanceTracker::default();
+        let schedule = |slot: u64| Some(["alice", "bob"][(slot % 2) as usize].to_string());
+
+        // Epochs of 4 slots; bob misses slot 1 and 3
+        tracker.record_block(0, 4, "alice", schedule);
+        tracker.record_block(2, 4, "alice", schedule);
+        tracker.record_block(4, 4, "alice", schedule);
+        assert!(tracker.take_reports("carol", "carol-key").iter().all(|report| report.epoch == 0));
+
+        tracker.record_block(5, 4, "bob", schedule);
+        tracker.record_block(8, 4, "alice", schedule);
+        let reports = tracker.take_reports("carol", "carol-key");
+        assert_eq!(reports.len(), 2);
+
+        let bob = reports.iter().find(|report| report.validator == "bob").unwrap();
+        assert_eq!((bob.epoch, bob.assigned_slots, bob.produced_blocks), (1, 2, 1));
+        assert_eq!(bob.uptime(), 0.5);
+        assert!(bob.verify(&tribechain_core::keys::public_key("carol-key")));
+        assert!(!bob.verify(&tribechain_core::keys::public_key("mallory-key")));
+    }
+}
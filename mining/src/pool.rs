@@ -1,10 +1,18 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use chrono::{DateTime, Utc};
+use std::collections::{HashMap, VecDeque};
+use chrono::{DateTime, Duration, Utc};
 use tokio::sync::RwLock;
 use std::sync::Arc;
-use tribechain_core::{TribeResult, TribeError, Block, Transaction};
-use crate::miner::{Miner, MinerStats, MinerType};
+use tribechain_core::{TribeResult, TribeError, Transaction, Storage};
+use crate::miner::Miner;
+use crate::payout::{PayoutLedger, PoolPayout};
+use crate::vardiff::{ShareTarget, VardiffConfig};
+
+/// Rounds kept in `PoolStats::rounds`, oldest dropped first
+pub const MAX_POOL_ROUNDS: usize = 500;
+
+/// Seconds of accepted shares a miner's hash rate is estimated over
+pub const HASH_RATE_WINDOW_SECS: i64 = 600;
 
 /// Mining pool for coordinating multiple miners
 #[derive(Debug)]
@@ -17,19 +25,25 @@ pub struct MiningPool {
     pub reward_distribution: RewardDistribution,
     pub is_active: bool,
     pub created_at: DateTime<Utc>,
+    pub ledger: PayoutLedger,
+    pub pending_payouts: Vec<PoolPayout>,
+    pub share_targets: HashMap<String, ShareTarget>, // miner id -> vardiff share difficulty
+    storage: Option<Arc<dyn Storage>>,
 }
 
 /// Pool configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PoolConfig {
     pub max_miners: usize,
-    pub min_difficulty: u32,
+    pub min_difficulty: u32, // Share difficulty for every miner when auto adjustment is off
     pub pool_fee_percentage: f64,
     pub payout_threshold: u64,
     pub payout_interval_hours: u64,
-    pub auto_difficulty_adjustment: bool,
+    pub auto_difficulty_adjustment: bool, // Give each miner its own share difficulty
     pub allow_ai3_mining: bool,
     pub require_registration: bool,
+    #[serde(default)]
+    pub vardiff: VardiffConfig,
 }
 
 /// Pool statistics
@@ -46,6 +60,53 @@ pub struct PoolStats {
     pub average_block_time: f64,
     pub pool_luck: f64, // percentage
     pub uptime: f64, // percentage
+    #[serde(default)]
+    pub stale_shares: u64, // Submitted for a job that was already replaced
+    #[serde(default)]
+    pub miners: HashMap<String, MinerShareStats>,
+    #[serde(default)]
+    pub current_round: RoundShares,
+    #[serde(default)]
+    pub rounds: VecDeque<PoolRound>, // Newest last
+}
+
+/// One miner's share accounting
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MinerShareStats {
+    pub accepted_shares: u64,
+    pub stale_shares: u64,
+    pub invalid_shares: u64,
+    pub hash_rate: f64, // Estimated from accepted shares in the last `HASH_RATE_WINDOW_SECS`, as of the last one
+    pub last_share: Option<DateTime<Utc>>,
+    #[serde(skip)]
+    recent_shares: VecDeque<(DateTime<Utc>, u32)>, // Accepted shares in the window and their difficulty
+}
+
+/// Shares submitted since the pool last found a block
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RoundShares {
+    pub started_at: Option<DateTime<Utc>>, // First share of the round
+    pub accepted: HashMap<String, u64>, // miner id -> accepted shares
+    pub stale: u64,
+    pub invalid: u64,
+}
+
+/// A round that ended with the pool finding a block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PoolRound {
+    pub block_height: u64,
+    pub finder: String,
+    pub found_at: DateTime<Utc>,
+    pub reward: u64, // Split among the miners, after the pool fee
+    pub shares: RoundShares,
+}
+
+/// How the pool judged a submitted share
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ShareOutcome {
+    Accepted,
+    Stale,
+    Invalid,
 }
 
 /// Reward distribution methods
@@ -55,6 +116,7 @@ pub enum RewardDistribution {
     PayPerShare, // Fixed payment per share
     PayPerLastNShares(u64), // Based on last N shares
     ScoreBasedShares, // Time-weighted shares
+    Solo, // Block finder takes the whole reward
 }
 
 /// Mining share submitted by a miner
@@ -102,22 +164,58 @@ impl MiningPool {
             reward_distribution: RewardDistribution::Proportional,
             is_active: true,
             created_at: Utc::now(),
+            ledger: PayoutLedger::default(),
+            pending_payouts: Vec::new(),
+            share_targets: HashMap::new(),
+            storage: None,
+        }
+    }
+
+    /// Persist statistics and round history to `storage`, loading any saved for this pool
+    pub fn with_storage(mut self, storage: Arc<dyn Storage>) -> TribeResult<Self> {
+        if let Some(data) = storage.load_data(&Self::stats_key(&self.pool_id))? {
+            let saved: PoolStats = bincode::deserialize(&data)
+                .map_err(|e| TribeError::Storage(format!("Failed to deserialize pool stats: {}", e)))?;
+            // Miner counts describe who is connected now, not before the restart
+            self.stats = PoolStats {
+                total_miners: self.stats.total_miners,
+                active_miners: self.stats.active_miners,
+                total_hash_rate: self.stats.total_hash_rate,
+                ..saved
+            };
         }
+        self.storage = Some(storage);
+        Ok(self)
+    }
+
+    fn stats_key(pool_id: &str) -> String {
+        format!("pool_stats_{}", pool_id)
+    }
+
+    /// Write statistics to storage, if the pool has any
+    pub fn save_stats(&self) -> TribeResult<()> {
+        let Some(storage) = &self.storage else { return Ok(()) };
+        let serialized = bincode::serialize(&self.stats)
+            .map_err(|e| TribeError::Storage(format!("Failed to serialize pool stats: {}", e)))?;
+        storage.save_data(&Self::stats_key(&self.pool_id), &serialized)
     }
 
     pub async fn add_miner(&mut self, miner: Miner) -> TribeResult<()> {
         let mut miners = self.miners.write().await;
         
         if miners.len() >= self.config.max_miners {
-            return Err(TribeError::InvalidOperation("Pool is full".to_string()));
+            return Err(TribeError::Mining("Pool is full".to_string()));
         }
 
         if self.config.require_registration && !miner.is_active {
-            return Err(TribeError::InvalidOperation("Miner must be registered".to_string()));
+            return Err(TribeError::Mining("Miner must be registered".to_string()));
         }
 
+        let target = self.config.vardiff.initial_target(miner.capabilities.hash_rate, Utc::now());
+        self.share_targets.insert(miner.id.clone(), target);
         miners.insert(miner.id.clone(), miner);
         self.stats.total_miners = miners.len();
+        drop(miners);
         self.update_active_miners().await;
         
         Ok(())
@@ -127,28 +225,36 @@ impl MiningPool {
         let mut miners = self.miners.write().await;
         
         if miners.remove(miner_id).is_none() {
-            return Err(TribeError::InvalidOperation("Miner not found".to_string()));
+            return Err(TribeError::Mining("Miner not found".to_string()));
         }
 
+        self.share_targets.remove(miner_id);
         self.stats.total_miners = miners.len();
+        drop(miners);
         self.update_active_miners().await;
         
         Ok(())
     }
 
-    pub async fn submit_share(&mut self, share: MiningShare) -> TribeResult<bool> {
-        let miners = self.miners.read().await;
-        
-        if !miners.contains_key(&share.miner_id) {
-            return Err(TribeError::InvalidOperation("Miner not in pool".to_string()));
+    pub async fn submit_share(&mut self, mut share: MiningShare) -> TribeResult<bool> {
+        if !self.miners.read().await.contains_key(&share.miner_id) {
+            return Err(TribeError::Mining("Miner not in pool".to_string()));
         }
 
-        // Validate share
+        // Shares are validated and credited at the miner's own difficulty
+        share.difficulty = self.share_difficulty(&share.miner_id);
         let is_valid = self.validate_share(&share).await?;
         
         self.stats.total_shares += 1;
         if is_valid {
             self.stats.valid_shares += 1;
+            self.stats.record_share(&share.miner_id, ShareOutcome::Accepted, share.difficulty, share.timestamp);
+            self.ledger.record_share(&share.miner_id, share.difficulty, share.block_height);
+            if self.config.auto_difficulty_adjustment {
+                if let Some(target) = self.share_targets.get_mut(&share.miner_id) {
+                    self.config.vardiff.record_share(target, share.timestamp);
+                }
+            }
             
             // Check if this share solves a block
             if self.is_block_solution(&share) {
@@ -156,11 +262,20 @@ impl MiningPool {
             }
         } else {
             self.stats.invalid_shares += 1;
+            self.stats.record_share(&share.miner_id, ShareOutcome::Invalid, share.difficulty, share.timestamp);
         }
 
         Ok(is_valid)
     }
 
+    /// Count a share `miner_id` submitted for a job that had already been replaced
+    pub fn record_stale_share(&mut self, miner_id: &str, at: DateTime<Utc>) {
+        self.stats.total_shares += 1;
+        self.stats.stale_shares += 1;
+        let difficulty = self.share_difficulty(miner_id);
+        self.stats.record_share(miner_id, ShareOutcome::Stale, difficulty, at);
+    }
+
     async fn validate_share(&self, share: &MiningShare) -> TribeResult<bool> {
         // Basic validation
         if share.hash.is_empty() || share.miner_id.is_empty() {
@@ -168,7 +283,7 @@ impl MiningPool {
         }
 
         // Check difficulty
-        let target = "0".repeat(self.config.min_difficulty as usize);
+        let target = "0".repeat(share.difficulty as usize);
         if !share.hash.starts_with(&target) {
             return Ok(false);
         }
@@ -185,6 +300,14 @@ impl MiningPool {
         Ok(true)
     }
 
+    /// Leading zero hex digits a share from `miner_id` must have
+    pub fn share_difficulty(&self, miner_id: &str) -> u32 {
+        match self.share_targets.get(miner_id) {
+            Some(target) if self.config.auto_difficulty_adjustment => target.difficulty,
+            _ => self.config.min_difficulty,
+        }
+    }
+
     async fn validate_ai3_proof(&self, proof: &AI3ShareProof) -> TribeResult<bool> {
         // Validate AI3 tensor computation proof
         if proof.task_id.is_empty() || proof.tensor_result.is_empty() {
@@ -220,54 +343,61 @@ impl MiningPool {
         self.stats.last_block_time = Some(share.timestamp);
         
         // Calculate and distribute rewards
-        self.distribute_block_reward(share).await?;
+        let reward = self.distribute_block_reward(share).await?;
         
         // Update pool luck
         self.update_pool_luck().await;
-        
-        Ok(())
+
+        self.stats.close_round(share, reward);
+        self.save_stats()
     }
 
-    async fn distribute_block_reward(&mut self, _share: &MiningShare) -> TribeResult<()> {
+    /// Credit the block reward to miners, returning what was split after the pool fee
+    async fn distribute_block_reward(&mut self, share: &MiningShare) -> TribeResult<u64> {
         let block_reward = 50_000_000; // Example: 50 TRIBE tokens
         let pool_fee = (block_reward as f64 * self.config.pool_fee_percentage / 100.0) as u64;
         let miner_reward = block_reward - pool_fee;
 
         match self.reward_distribution {
             RewardDistribution::Proportional => {
-                self.distribute_proportional_reward(miner_reward).await?;
+                self.ledger.credit_proportional(miner_reward, &share.miner_id);
             }
             RewardDistribution::PayPerShare => {
                 self.distribute_pps_reward(miner_reward).await?;
             }
             RewardDistribution::PayPerLastNShares(n) => {
-                self.distribute_pplns_reward(miner_reward, n).await?;
+                self.ledger.credit_pplns(miner_reward, n, &share.miner_id);
             }
             RewardDistribution::ScoreBasedShares => {
                 self.distribute_score_based_reward(miner_reward).await?;
             }
+            RewardDistribution::Solo => {
+                self.ledger.credit_solo(miner_reward, &share.miner_id);
+            }
         }
 
-        Ok(())
+        self.queue_payouts(share.block_height).await;
+        Ok(miner_reward)
     }
 
-    async fn distribute_proportional_reward(&mut self, total_reward: u64) -> TribeResult<()> {
-        let miners = self.miners.read().await;
-        let total_shares: u64 = miners.values().map(|m| m.stats.successful_hashes).sum();
-        
-        if total_shares == 0 {
-            return Ok(());
-        }
+    /// Move balances over the payout threshold into pending payouts
+    async fn queue_payouts(&mut self, block_height: u64) {
+        let addresses: HashMap<String, String> = self.miners.read().await
+            .values()
+            .map(|miner| (miner.id.clone(), miner.address.clone()))
+            .collect();
 
-        for miner in miners.values() {
-            let miner_shares = miner.stats.successful_hashes;
-            let reward = (total_reward as f64 * miner_shares as f64 / total_shares as f64) as u64;
-            
-            // In a real implementation, you would update the miner's balance
-            println!("Miner {} earned {} tokens", miner.id, reward);
-        }
+        let payouts = self.ledger.take_payouts(self.config.payout_threshold, block_height, &addresses);
+        self.pending_payouts.extend(payouts);
+    }
 
-        Ok(())
+    /// Drain pending payouts into signed-ready transfers from the pool wallet
+    pub fn create_payout_transactions(&mut self, pool_address: &str, fee: u64, start_nonce: u64) -> Vec<Transaction> {
+        self.pending_payouts
+            .drain(..)
+            .enumerate()
+            .map(|(i, payout)| payout.to_transaction(pool_address, fee, start_nonce + i as u64))
+            .collect()
     }
 
     async fn distribute_pps_reward(&mut self, _total_reward: u64) -> TribeResult<()> {
@@ -283,26 +413,6 @@ impl MiningPool {
         Ok(())
     }
 
-    async fn distribute_pplns_reward(&mut self, total_reward: u64, n_shares: u64) -> TribeResult<()> {
-        // Pay-per-last-N-shares implementation
-        let miners = self.miners.read().await;
-        let recent_shares: u64 = miners.values()
-            .map(|m| std::cmp::min(m.stats.successful_hashes, n_shares))
-            .sum();
-        
-        if recent_shares == 0 {
-            return Ok(());
-        }
-
-        for miner in miners.values() {
-            let miner_recent_shares = std::cmp::min(miner.stats.successful_hashes, n_shares);
-            let reward = (total_reward as f64 * miner_recent_shares as f64 / recent_shares as f64) as u64;
-            println!("Miner {} earned {} tokens (PPLNS)", miner.id, reward);
-        }
-
-        Ok(())
-    }
-
     async fn distribute_score_based_reward(&mut self, total_reward: u64) -> TribeResult<()> {
         // Score-based shares implementation (time-weighted)
         let miners = self.miners.read().await;
@@ -351,12 +461,12 @@ impl MiningPool {
                 total_shares: miner.stats.total_hash_attempts,
                 valid_shares: miner.stats.successful_hashes,
                 total_earnings: miner.stats.earnings,
-                pending_payout: 0, // Would be calculated from pending shares
+                pending_payout: self.ledger.pending_balance(miner_id),
                 last_payout: None, // Would be tracked separately
                 efficiency_score: miner.get_efficiency_score(),
             })
         } else {
-            Err(TribeError::InvalidOperation("Miner not found".to_string()))
+            Err(TribeError::Mining("Miner not found".to_string()))
         }
     }
 
@@ -376,6 +486,9 @@ impl MiningPool {
     }
 
     pub async fn set_reward_distribution(&mut self, distribution: RewardDistribution) {
+        if let RewardDistribution::PayPerLastNShares(n) = distribution {
+            self.ledger.max_window = self.ledger.max_window.max(n as usize);
+        }
         self.reward_distribution = distribution;
     }
 
@@ -384,6 +497,58 @@ impl MiningPool {
     }
 }
 
+impl PoolStats {
+    /// Count one share toward its miner and the current round
+    pub fn record_share(&mut self, miner_id: &str, outcome: ShareOutcome, difficulty: u32, at: DateTime<Utc>) {
+        let miner = self.miners.entry(miner_id.to_string()).or_default();
+        miner.last_share = Some(at);
+        self.current_round.started_at.get_or_insert(at);
+        match outcome {
+            ShareOutcome::Accepted => {
+                miner.record_accepted(difficulty, at);
+                *self.current_round.accepted.entry(miner_id.to_string()).or_insert(0) += 1;
+            }
+            ShareOutcome::Stale => {
+                miner.stale_shares += 1;
+                self.current_round.stale += 1;
+            }
+            ShareOutcome::Invalid => {
+                miner.invalid_shares += 1;
+                self.current_round.invalid += 1;
+            }
+        }
+    }
+
+    /// End the current round with the block `share` found
+    fn close_round(&mut self, share: &MiningShare, reward: u64) {
+        if self.rounds.len() == MAX_POOL_ROUNDS {
+            self.rounds.pop_front();
+        }
+        self.rounds.push_back(PoolRound {
+            block_height: share.block_height,
+            finder: share.miner_id.clone(),
+            found_at: share.timestamp,
+            reward,
+            shares: std::mem::take(&mut self.current_round),
+        });
+    }
+}
+
+impl MinerShareStats {
+    fn record_accepted(&mut self, difficulty: u32, at: DateTime<Utc>) {
+        self.accepted_shares += 1;
+        self.recent_shares.push_back((at, difficulty));
+        let cutoff = at - Duration::seconds(HASH_RATE_WINDOW_SECS);
+        while self.recent_shares.front().is_some_and(|(time, _)| *time < cutoff) {
+            self.recent_shares.pop_front();
+        }
+
+        // A share at difficulty `d` stands for 16^d hashes on average
+        let hashes: f64 = self.recent_shares.iter().map(|(_, difficulty)| 16f64.powi(*difficulty as i32)).sum();
+        self.hash_rate = hashes / HASH_RATE_WINDOW_SECS as f64;
+    }
+}
+
 impl Default for PoolStats {
     fn default() -> Self {
         Self {
@@ -398,6 +563,10 @@ impl Default for PoolStats {
             average_block_time: 600.0, // 10 minutes
             pool_luck: 100.0,
             uptime: 100.0,
+            stale_shares: 0,
+            miners: HashMap::new(),
+            current_round: RoundShares::default(),
+            rounds: VecDeque::new(),
         }
     }
 }
@@ -413,6 +582,7 @@ impl Default for PoolConfig {
             auto_difficulty_adjustment: true,
             allow_ai3_mining: true,
             require_registration: false,
+            vardiff: VardiffConfig::default(),
         }
     }
 }
@@ -420,7 +590,7 @@ impl Default for PoolConfig {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::miner::{MinerCapabilities, MinerType};
+    use crate::miner::MinerType;
 
     #[tokio::test]
     async fn test_mining_pool_creation() {
@@ -437,8 +607,7 @@ mod tests {
         let config = PoolConfig::default();
         let mut pool = MiningPool::new("pool1".to_string(), "Test Pool".to_string(), config);
         
-        let capabilities = MinerCapabilities::default();
-        let miner = Miner::new("miner1".to_string(), "addr1".to_string(), MinerType::CPU, capabilities);
+        let miner = Miner::new("miner1".to_string(), "addr1".to_string(), MinerType::CPU);
         
         pool.add_miner(miner).await.unwrap();
         assert_eq!(pool.stats.total_miners, 1);
@@ -452,8 +621,7 @@ mod tests {
         let config = PoolConfig::default();
         let mut pool = MiningPool::new("pool1".to_string(), "Test Pool".to_string(), config);
         
-        let capabilities = MinerCapabilities::default();
-        let miner = Miner::new("miner1".to_string(), "addr1".to_string(), MinerType::CPU, capabilities);
+        let miner = Miner::new("miner1".to_string(), "addr1".to_string(), MinerType::CPU);
         pool.add_miner(miner).await.unwrap();
         
         let share = MiningShare {
@@ -472,6 +640,88 @@ mod tests {
         assert_eq!(pool.stats.valid_shares, 1);
     }
 
+    #[tokio::test]
+    async fn test_vardiff_share_targets() {
+        let mut pool = MiningPool::new("pool1".to_string(), "Test Pool".to_string(), PoolConfig::default());
+        pool.add_miner(Miner::new("esp".to_string(), "addr1".to_string(), MinerType::ESP32)).await.unwrap();
+        pool.add_miner(Miner::new("cpu".to_string(), "addr2".to_string(), MinerType::CPU)).await.unwrap();
+        assert_eq!(pool.share_difficulty("esp"), 2);
+        assert_eq!(pool.share_difficulty("cpu"), 3);
+
+        let start = Utc::now();
+        let share = |miner_id: &str, hash: &str, seconds: i64| MiningShare {
+            miner_id: miner_id.to_string(),
+            block_height: 1,
+            nonce: 0,
+            hash: hash.to_string(),
+            difficulty: 0,
+            is_valid: false,
+            timestamp: start + Duration::seconds(seconds),
+            ai3_proof: None,
+        };
+        assert!(pool.submit_share(share("esp", "00ab", 1)).await.unwrap());
+        assert!(!pool.submit_share(share("cpu", "00ab", 1)).await.unwrap());
+
+        // A share every second is well over the target rate
+        for second in 1..=8 {
+            assert!(pool.submit_share(share("cpu", "000a", second)).await.unwrap());
+        }
+        assert_eq!(pool.share_difficulty("cpu"), 4);
+        assert!(!pool.submit_share(share("cpu", "000a", 9)).await.unwrap());
+        assert_eq!(pool.share_difficulty("esp"), 2);
+
+        pool.config.auto_difficulty_adjustment = false;
+        assert_eq!(pool.share_difficulty("cpu"), pool.config.min_difficulty);
+    }
+
+    #[tokio::test]
+    async fn test_miner_accounting_and_rounds() {
+        let storage: Arc<dyn Storage> = Arc::new(tribechain_core::MemoryStorage::new());
+        let mut pool = MiningPool::new("pool1".to_string(), "Test Pool".to_string(), PoolConfig::default())
+            .with_storage(storage.clone())
+            .unwrap();
+        pool.add_miner(Miner::new("cpu".to_string(), "addr1".to_string(), MinerType::CPU)).await.unwrap();
+        pool.add_miner(Miner::new("esp".to_string(), "addr2".to_string(), MinerType::ESP32)).await.unwrap();
+
+        let now = Utc::now();
+        let share = |miner_id: &str, hash: &str| MiningShare {
+            miner_id: miner_id.to_string(),
+            block_height: 7,
+            nonce: 0,
+            hash: hash.to_string(),
+            difficulty: 0,
+            is_valid: false,
+            timestamp: now,
+            ai3_proof: None,
+        };
+        assert!(pool.submit_share(share("esp", "00ab")).await.unwrap());
+        assert!(!pool.submit_share(share("esp", "0abc")).await.unwrap());
+        pool.record_stale_share("esp", now);
+
+        let esp = &pool.stats.miners["esp"];
+        assert_eq!((esp.accepted_shares, esp.invalid_shares, esp.stale_shares), (1, 1, 1));
+        assert_eq!(esp.last_share, Some(now));
+        // One difficulty 2 share over the window
+        assert_eq!(esp.hash_rate, 256.0 / HASH_RATE_WINDOW_SECS as f64);
+        assert_eq!(pool.stats.current_round.accepted["esp"], 1);
+
+        // Six leading zeros also solves the block and closes the round
+        assert!(pool.submit_share(share("cpu", "000000ab")).await.unwrap());
+        let round = &pool.stats.rounds[0];
+        assert_eq!((round.block_height, round.finder.as_str()), (7, "cpu"));
+        assert_eq!(round.shares.accepted.len(), 2);
+        assert_eq!((round.shares.stale, round.shares.invalid), (1, 1));
+        assert_eq!(round.reward, 49_000_000);
+        assert!(pool.stats.current_round.accepted.is_empty());
+
+        let restored = MiningPool::new("pool1".to_string(), "Test Pool".to_string(), PoolConfig::default())
+            .with_storage(storage)
+            .unwrap();
+        assert_eq!(restored.stats.rounds.len(), 1);
+        assert_eq!(restored.stats.miners["esp"].accepted_shares, 1);
+        assert_eq!(restored.stats.total_miners, 0);
+    }
+
     #[test]
     fn test_reward_distribution_types() {
         let proportional = RewardDistribution::Proportional;
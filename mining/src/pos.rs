@@ -0,0 +1,136 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use tribechain_core::{TribeResult, TribeError, Block, Transaction};
+use crate::consensus::ValidatorInfo;
+
+/// Slot and epoch timing for proof of stake block production
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PosSchedule {
+    pub slot_time: u64, // seconds per slot
+    pub slots_per_epoch: u64,
+    pub min_stake: u64,
+}
+
+impl PosSchedule {
+    pub fn slot_at(&self, timestamp: u64) -> u64 {
+        timestamp / self.slot_time.max(1)
+    }
+
+    pub fn epoch_of(&self, slot: u64) -> u64 {
+        slot / self.slots_per_epoch.max(1)
+    }
+
+    /// Validators eligible to propose, sorted by address so every node agrees on order
+    pub fn eligible_stakes(&self, validators: &HashMap<String, ValidatorInfo>) -> Vec<(String, u64)> {
+        let mut stakes: Vec<(String, u64)> = validators.values()
+            .filter(|validator| validator.is_active && validator.stake >= self.min_stake)
+            .map(|validator| (validator.address.clone(), validator.stake))
+            .collect();
+        stakes.sort();
+        stakes
+    }
+
+    /// Stake-weighted proposer for `slot`.
+    /// The draw is seeded by epoch and slot, so the schedule is fixed for the whole epoch
+    /// given the validator set at its start.
+    pub fn proposer(&self, validators: &HashMap<String, ValidatorInfo>, slot: u64) -> Option<String> {
+        let stakes = self.eligible_stakes(validators);
+        let total_stake: u128 = stakes.iter().map(|(_, stake)| *stake as u128).sum();
+        if total_stake == 0 {
+            return None;
+        }
+
+        let mut hasher = Sha256::new();
+        hasher.update(self.epoch_of(slot).to_le_bytes());
+        hasher.update(slot.to_le_bytes());
+        let digest = hasher.finalize();
+        let mut draw_bytes = [0u8; 16];
+        draw_bytes.copy_from_slice(&digest[..16]);
+        let mut draw = u128::from_le_bytes(draw_bytes) % total_stake;
+
+        for (address, stake) in stakes {
+            if draw < stake as u128 {
+                return Some(address);
+            }
+            draw -= stake as u128;
+        }
+        None
+    }
+
+    /// Proposers for every slot of `epoch`
+    pub fn epoch_schedule(&self, validators: &HashMap<String, ValidatorInfo>, epoch: u64) -> Vec<Option<String>> {
+        let first_slot = epoch * self.slots_per_epoch;
+        (first_slot..first_slot + self.slots_per_epoch)
+            .map(|slot| self.proposer(validators, slot))
+            .collect()
+    }
+
+    /// Build and sign a block if `validator` is the proposer for the slot at `timestamp`
+    pub fn produce_block(
+        &self,
+        validators: &HashMap<String, ValidatorInfo>,
+        previous: &Block,
+        transactions: Vec<Transaction>,
+        validator: &str,
+        private_key: &str,
+        timestamp: u64,
+    ) -> TribeResult<Block> {
+        let slot = self.slot_at(timestamp);
+        match self.proposer(validators, slot) {
+            Some(proposer) if proposer == validator => {}
+            Some(proposer) => {
+                return Err(TribeError::InvalidBlock(format!("Slot {} belongs to {}", slot, proposer)));
+            }
+            None => return Err(TribeError::InvalidBlock("No eligible validators".to_string())),
+        }
+
+        let mut block = Block::new(previous.index + 1, previous.hash.clone(), transactions, validator.to_string());
+        block.timestamp = timestamp;
+        block.difficulty = 0; // Stake replaces work
+        block.hash = block.calculate_hash();
+        block.sign_as_validator(private_key);
+        Ok(block)
+    }
+}
+
+impl Default for PosSchedule {
+    fn default() -> Self {
+        Self {
+            slot_time: 10,
+            slots_per_epoch: 32,
+            min_stake: 1000,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stake_weighted_schedule() {
+        let schedule = PosSchedule { slots_per_epoch: 1000, ..Default::default() };
+        let mut validators = HashMap::new();
+        validators.insert("whale".to_string(), ValidatorInfo::new("whale".to_string(), 9000));
+        validators.insert("minnow".to_string(), ValidatorInfo::new("minnow".to_string(), 1000));
+        validators.insert("dust".to_string(), ValidatorInfo::new("dust".to_string(), 10));
+
+        let slots = schedule.epoch_schedule(&validators, 0);
+        let whale_slots = slots.iter().filter(|p| p.as_deref() == Some("whale")).count();
+        assert!(slots.iter().all(|p| p.as_deref() != Some("dust")));
+        assert!((850..950).contains(&whale_slots), "whale got {} slots", whale_slots);
+
+        // Only the scheduled proposer may produce
+        let genesis = Block::genesis();
+        let timestamp = 1_700_000_000;
+        let proposer = schedule.proposer(&validators, schedule.slot_at(timestamp)).unwrap();
+        let other = if proposer == "whale" { "minnow" } else { "whale" };
+        assert!(schedule.produce_block(&validators, &genesis, vec![], other, other, timestamp).is_err());
+
+        let private_key = format!("{}-key", proposer);
+        let block = schedule.produce_block(&validators, &genesis, vec![], &proposer, &private_key, timestamp).unwrap();
+        assert!(block.verify_validator_signature(&tribechain_core::keys::public_key(&private_key)));
+        assert!(!block.verify_validator_signature(&tribechain_core::keys::public_key(other)));
+    }
+}
@@ -1,8 +1,12 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use chrono::{DateTime, Utc};
-use tribechain_core::{TribeResult, TribeError, Block, Transaction};
-use ai3_lib::{Tensor, MiningTask as AI3Task, MiningResult as AI3Result, AI3Miner};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use chrono::{DateTime, TimeZone, Utc};
+use tribechain_core::{TribeResult, Block, AI3Commitment, MinedBy, difficulty_work, hash_work, tensor_work_hash};
+use ai3_lib::{Tensor, MiningTask as AI3Task, MiningResult as AI3Result, AI3Miner, ComputationProof};
+use ai3_lib::mining::proofs::{DEFAULT_PROOF_ROUNDS, DEFAULT_PROOF_SAMPLES};
+use ai3_lib::mining::verification_challenge;
 
 /// Proof of Work mining implementation
 #[derive(Debug, Clone)]
@@ -11,6 +15,10 @@ pub struct ProofOfWork {
     pub target_block_time: u64, // seconds
     pub max_nonce: u64,
     pub ai3_integration: bool,
+    pub ai3_multiplier: f32, // Proofs carrying a tensor result are held to this multiple of the difficulty
+    pub worker_threads: usize,
+    pub last_hash_rate: f64, // Aggregate hashes per second of the last mining run
+    pub proof_mode: bool, // Attach succinct computation proofs for light validators
 }
 
 /// Work proof structure
@@ -31,6 +39,10 @@ pub struct AI3WorkProof {
     pub tensor_result: Tensor,
     pub computation_hash: String,
     pub verification_nonce: u64,
+    #[serde(default)]
+    pub commitment: Option<String>, // Commitment published before this reveal
+    #[serde(default)]
+    pub computation_proof: Option<Vec<u8>>, // Encoded `ComputationProof`, in proof mode
 }
 
 /// Mining work unit
@@ -50,14 +62,33 @@ impl ProofOfWork {
             target_block_time,
             max_nonce: u64::MAX,
             ai3_integration: true,
+            ai3_multiplier: 1.5,
+            worker_threads: std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+            last_hash_rate: 0.0,
+            proof_mode: false,
         }
     }
 
+    pub fn with_worker_threads(mut self, threads: usize) -> Self {
+        self.worker_threads = threads.max(1);
+        self
+    }
+
     pub fn with_ai3_integration(mut self, enabled: bool) -> Self {
         self.ai3_integration = enabled;
         self
     }
 
+    pub fn with_ai3_multiplier(mut self, multiplier: f32) -> Self {
+        self.ai3_multiplier = multiplier;
+        self
+    }
+
+    pub fn with_proof_mode(mut self, enabled: bool) -> Self {
+        self.proof_mode = enabled;
+        self
+    }
+
     /// Create mining work from block template
     pub fn create_work(&self, mut block: Block, ai3_task: Option<AI3Task>) -> MiningWork {
         let target = self.calculate_target();
@@ -74,53 +105,132 @@ impl ProofOfWork {
         }
     }
 
-    /// Mine a block using proof of work
+    /// Mine a block using proof of work.
+    /// A tensor result whose commitment carries enough work finds the block outright;
+    /// otherwise the nonce range is split into one contiguous slice per worker thread
+    /// and the first worker to find a solution stops the others.
     pub fn mine_block(
-        &self,
+        &mut self,
         work: &mut MiningWork,
         miner_id: String,
         ai3_miner: Option<&mut AI3Miner>,
     ) -> TribeResult<Option<WorkProof>> {
         let start_time = std::time::Instant::now();
-        
-        for nonce in work.start_nonce..=work.end_nonce {
-            work.block_template.nonce = nonce;
-            work.block_template.timestamp = Utc::now().timestamp() as u64;
-            
-            let hash = work.block_template.calculate_hash();
-            
-            // Check if hash meets difficulty target
-            if self.meets_difficulty(&hash, &work.target) {
-                let mut proof = WorkProof {
-                    block_hash: hash,
-                    nonce,
-                    timestamp: Utc::now(),
-                    difficulty: self.difficulty,
-                    miner_id: miner_id.clone(),
-                    ai3_proof: None,
-                };
-
-                // If AI3 integration is enabled and we have a task
-                if self.ai3_integration && work.ai3_task.is_some() && ai3_miner.is_some() {
-                    if let Some(ai3_proof) = self.mine_ai3_component(
-                        work.ai3_task.as_ref().unwrap(),
-                        ai3_miner.unwrap(),
-                        &hash,
-                    )? {
-                        proof.ai3_proof = Some(ai3_proof);
-                    }
-                }
 
+        let mut ai3_proof = None;
+        if let (true, Some(task), Some(ai3_miner)) = (self.ai3_integration, work.ai3_task.as_ref(), ai3_miner) {
+            let block = &mut work.block_template;
+            block.nonce = work.start_nonce;
+            block.timestamp = Utc::now().timestamp() as u64;
+            let hash = block.calculate_hash();
+            ai3_proof = self.mine_ai3_component(task, ai3_miner, &hash, &miner_id)?;
+
+            let proof = WorkProof {
+                block_hash: hash,
+                nonce: block.nonce,
+                timestamp: Utc.timestamp_opt(block.timestamp as i64, 0).single().unwrap_or_else(Utc::now),
+                difficulty: self.difficulty,
+                miner_id: miner_id.clone(),
+                ai3_proof: ai3_proof.clone(),
+            };
+            if self.mined_by(&proof, block) == Some(MinedBy::Tensor) {
                 return Ok(Some(proof));
             }
+        }
 
-            // Check for timeout (prevent infinite mining)
-            if start_time.elapsed().as_secs() > 300 { // 5 minute timeout
-                break;
-            }
+        let threads = self.worker_threads.max(1) as u64;
+        let span = work.end_nonce.saturating_sub(work.start_nonce);
+        let chunk = (span / threads).saturating_add(1);
+
+        let stop = AtomicBool::new(false);
+        let hashes = AtomicU64::new(0);
+        let found: Mutex<Option<(u64, u64, String)>> = Mutex::new(None); // (nonce, timestamp, hash)
+
+        // A tensor result riding along holds the hash to the AI3 difficulty
+        let target = match &ai3_proof {
+            Some(ai3_proof) => "0".repeat(self.required_difficulty(Some(ai3_proof)) as usize),
+            None => work.target.clone(),
+        };
+
+        {
+            let pow = &*self;
+            let (stop, hashes, found) = (&stop, &hashes, &found);
+            let target = target.as_str();
+
+            std::thread::scope(|scope| {
+                for worker in 0..threads {
+                    let offset = worker.saturating_mul(chunk);
+                    if offset > span {
+                        break;
+                    }
+                    let range_start = work.start_nonce + offset;
+                    let range_end = range_start.saturating_add(chunk - 1).min(work.end_nonce);
+                    let mut block = work.block_template.clone();
+
+                    scope.spawn(move || {
+                        let mut local_hashes = 0u64;
+                        for nonce in range_start..=range_end {
+                            if stop.load(Ordering::Relaxed) {
+                                break;
+                            }
+
+                            block.nonce = nonce;
+                            block.timestamp = Utc::now().timestamp() as u64;
+                            let hash = block.calculate_hash();
+                            local_hashes += 1;
+
+                            if pow.meets_difficulty(&hash, target) {
+                                let mut found = found.lock().unwrap();
+                                if found.is_none() {
+                                    *found = Some((nonce, block.timestamp, hash));
+                                }
+                                stop.store(true, Ordering::Relaxed);
+                                break;
+                            }
+
+                            if local_hashes.is_multiple_of(1024) {
+                                hashes.fetch_add(local_hashes, Ordering::Relaxed);
+                                local_hashes = 0;
+
+                                // Check for timeout (prevent infinite mining)
+                                if start_time.elapsed().as_secs() > 300 { // 5 minute timeout
+                                    stop.store(true, Ordering::Relaxed);
+                                    break;
+                                }
+                            }
+                        }
+                        hashes.fetch_add(local_hashes, Ordering::Relaxed);
+                    });
+                }
+            });
         }
 
-        Ok(None)
+        let elapsed = start_time.elapsed().as_secs_f64();
+        if elapsed > 0.0 {
+            self.last_hash_rate = hashes.load(Ordering::Relaxed) as f64 / elapsed;
+        }
+
+        let (nonce, timestamp, hash) = match found.into_inner().unwrap() {
+            Some(solution) => solution,
+            None => return Ok(None),
+        };
+        work.block_template.nonce = nonce;
+        work.block_template.timestamp = timestamp;
+
+        // The tensor result still rides along, rebound to the hash that was found
+        let ai3_proof = ai3_proof.map(|mut ai3_proof| {
+            ai3_proof.computation_hash = self.ai3_proof_hash(&ai3_proof, &hash);
+            ai3_proof
+        });
+
+        Ok(Some(WorkProof {
+            block_hash: hash,
+            nonce,
+            timestamp: Utc.timestamp_opt(timestamp as i64, 0).single().unwrap_or_else(Utc::now),
+            difficulty: self.difficulty,
+            miner_id,
+            ai3_proof,
+        }))
     }
 
     /// Mine AI3 tensor component
@@ -129,6 +239,7 @@ impl ProofOfWork {
         ai3_task: &AI3Task,
         ai3_miner: &mut AI3Miner,
         block_hash: &str,
+        miner_id: &str,
     ) -> TribeResult<Option<AI3WorkProof>> {
         // Assign AI3 task to miner
         ai3_miner.assign_task(ai3_task.clone())?;
@@ -138,12 +249,26 @@ impl ProofOfWork {
             // Validate AI3 result
             if ai3_result.is_valid {
                 let computation_hash = self.calculate_ai3_hash(&ai3_result, block_hash);
+                let commitment = AI3Commitment::compute_hash(
+                    &ai3_result.task_id,
+                    miner_id,
+                    &ai3_result.output_tensor.calculate_hash(),
+                    ai3_result.nonce,
+                );
+                let computation_proof = if self.proof_mode && ComputationProof::supports(&ai3_task.operation_type) {
+                    let proof = ComputationProof::generate(ai3_task, &ai3_result.output_tensor, DEFAULT_PROOF_ROUNDS)?;
+                    Some(proof.to_bytes()?)
+                } else {
+                    None
+                };
                 
                 return Ok(Some(AI3WorkProof {
                     task_id: ai3_result.task_id,
                     tensor_result: ai3_result.output_tensor,
                     computation_hash,
                     verification_nonce: ai3_result.nonce,
+                    commitment: Some(commitment),
+                    computation_proof,
                 }));
             }
         }
@@ -174,10 +299,56 @@ impl ProofOfWork {
         hash.starts_with(target)
     }
 
-    /// Verify proof of work
-    pub fn verify_proof(&self, proof: &WorkProof, block: &Block) -> TribeResult<bool> {
-        // Verify basic PoW
-        if !self.meets_difficulty(&proof.block_hash, &self.calculate_target()) {
+    /// Difficulty a proof is held to; multiplied when it carries a tensor result, as
+    /// the chain does for blocks with an AI3 proof
+    fn required_difficulty(&self, ai3_proof: Option<&AI3WorkProof>) -> u64 {
+        match ai3_proof {
+            Some(_) => (self.difficulty as f32 * self.ai3_multiplier) as u64,
+            None => self.difficulty as u64,
+        }
+    }
+
+    /// How `proof` meets the target for `block`: by its block hash, or by the work of
+    /// its tensor result's commitment bound to the block's parent and transactions.
+    /// Either is enough under merged mining
+    pub fn mined_by(&self, proof: &WorkProof, block: &Block) -> Option<MinedBy> {
+        let difficulty = self.required_difficulty(proof.ai3_proof.as_ref());
+        if self.meets_difficulty(&proof.block_hash, &"0".repeat(difficulty as usize)) {
+            return Some(MinedBy::Hash);
+        }
+        proof.ai3_proof.as_ref()
+            .and_then(|ai3_proof| ai3_proof.commitment.as_deref())
+            .map(|commitment| tensor_work_hash(commitment, &block.previous_hash, &block.merkle_root))
+            .filter(|work_hash| hash_work(work_hash) >= difficulty_work(difficulty))
+            .map(|_| MinedBy::Tensor)
+    }
+
+    /// Verify proof of work. A proof carrying a tensor result also needs its `task`:
+    /// the result is checked under the task's verification policy before the block
+    /// counts as mined by it
+    pub fn verify_proof(&self, proof: &WorkProof, block: &Block, task: Option<&AI3Task>) -> TribeResult<bool> {
+        if !self.verify_work(proof, block)? {
+            return Ok(false);
+        }
+
+        match (&proof.ai3_proof, task) {
+            (None, _) => Ok(true),
+            (Some(ai3_proof), Some(task)) if ai3_proof.task_id == task.id => {
+                // The parent is no older than the block carrying the commitment, so
+                // its hash wasn't known when the miner bound itself to this output
+                let challenge = ai3_proof.commitment.as_deref()
+                    .map(|commitment| verification_challenge(commitment, &block.previous_hash));
+                task.verification.verify(task, &ai3_proof.tensor_result, challenge.as_ref())
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Checks shared by full and light verification: the block hash, the work and the
+    /// AI3 commitment. The tensor result itself is left to the caller
+    fn verify_work(&self, proof: &WorkProof, block: &Block) -> TribeResult<bool> {
+        // Verify basic PoW, or the tensor work standing in for it
+        if self.mined_by(proof, block).is_none() {
             return Ok(false);
         }
 
@@ -193,14 +364,51 @@ impl ProofOfWork {
 
         // Verify AI3 proof if present
         if let Some(ai3_proof) = &proof.ai3_proof {
-            return self.verify_ai3_proof(ai3_proof, &proof.block_hash);
+            return self.verify_ai3_proof(ai3_proof, &proof.block_hash, &proof.miner_id);
         }
 
         Ok(true)
     }
 
+    /// Verify a TensorPoW block as a light validator: the tensor result is checked
+    /// against its computation proof instead of being recomputed from `task`
+    pub fn verify_light(&self, proof: &WorkProof, block: &Block, task: &AI3Task) -> TribeResult<bool> {
+        let ai3_proof = match &proof.ai3_proof {
+            Some(ai3_proof) if ai3_proof.task_id == task.id => ai3_proof,
+            _ => return Ok(false),
+        };
+        let proof_bytes = match &ai3_proof.computation_proof {
+            Some(bytes) => bytes,
+            None => return Ok(false),
+        };
+        if !self.verify_work(proof, block)? {
+            return Ok(false);
+        }
+
+        ComputationProof::from_bytes(proof_bytes)?.verify(task, &ai3_proof.tensor_result, DEFAULT_PROOF_SAMPLES)
+    }
+
     /// Verify AI3 proof component
-    fn verify_ai3_proof(&self, ai3_proof: &AI3WorkProof, block_hash: &str) -> TribeResult<bool> {
+    fn verify_ai3_proof(&self, ai3_proof: &AI3WorkProof, block_hash: &str, miner_id: &str) -> TribeResult<bool> {
+        // A revealed result must open the commitment made by this miner
+        if let Some(commitment) = &ai3_proof.commitment {
+            let expected = AI3Commitment::compute_hash(
+                &ai3_proof.task_id,
+                miner_id,
+                &ai3_proof.tensor_result.calculate_hash(),
+                ai3_proof.verification_nonce,
+            );
+            if &expected != commitment {
+                return Ok(false);
+            }
+        }
+
+        // Verify computation hash
+        Ok(self.ai3_proof_hash(ai3_proof, block_hash) == ai3_proof.computation_hash)
+    }
+
+    /// Computation hash binding `ai3_proof`'s result to `block_hash`
+    fn ai3_proof_hash(&self, ai3_proof: &AI3WorkProof, block_hash: &str) -> String {
         // Create a mock AI3 result for verification
         let ai3_result = AI3Result::new(
             ai3_proof.task_id.clone(),
@@ -210,10 +418,7 @@ impl ProofOfWork {
             ai3_proof.tensor_result.clone(),
             0, // computation time not needed for verification
         );
-
-        // Verify computation hash
-        let expected_hash = self.calculate_ai3_hash(&ai3_result, block_hash);
-        Ok(expected_hash == ai3_proof.computation_hash)
+        self.calculate_ai3_hash(&ai3_result, block_hash)
     }
 
     /// Adjust difficulty based on block time
@@ -245,6 +450,8 @@ impl ProofOfWork {
             target_block_time: self.target_block_time,
             expected_hash_rate: self.calculate_expected_hash_rate(),
             ai3_integration_enabled: self.ai3_integration,
+            measured_hash_rate: self.last_hash_rate,
+            worker_threads: self.worker_threads,
         }
     }
 }
@@ -256,6 +463,8 @@ pub struct MiningStats {
     pub target_block_time: u64,
     pub expected_hash_rate: f64,
     pub ai3_integration_enabled: bool,
+    pub measured_hash_rate: f64,
+    pub worker_threads: usize,
 }
 
 /// Batch mining for multiple work units
@@ -304,7 +513,6 @@ impl BatchMiner {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use tribechain_core::{Transaction, TransactionType};
 
     #[test]
     fn test_proof_of_work_creation() {
@@ -353,7 +561,6 @@ mod tests {
             "prev_hash".to_string(),
             vec![],
             "miner".to_string(),
-            4,
         );
         
         let work = pow.create_work(block, None);
@@ -372,7 +579,6 @@ mod tests {
             "prev_hash".to_string(),
             vec![],
             "miner".to_string(),
-            1,
         );
         
         let work = batch_miner.pow.create_work(block, None);
@@ -382,4 +588,150 @@ mod tests {
         // With difficulty 1, we should find a proof quickly
         assert!(!proofs.is_empty() || batch_miner.work_queue.is_empty());
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_light_verification() {
+        let pow = ProofOfWork::new(1, 600).with_proof_mode(true);
+        let a = Tensor::random(ai3_lib::TensorShape::matrix(4, 3));
+        let b = Tensor::random(ai3_lib::TensorShape::matrix(3, 5));
+        let task = AI3Task::new("matrix_multiply".to_string(), vec![a, b], 0, 10, 60, "requester".to_string());
+        let output = task.execute_operation().unwrap();
+
+        let block = Block::new(1, "prev_hash".to_string(), vec![], "miner".to_string());
+        let result = AI3Result::new(task.id.clone(), "miner".to_string(), 7, String::new(), output.clone(), 1);
+        let mut proof = WorkProof {
+            block_hash: block.calculate_hash(),
+            nonce: block.nonce,
+            timestamp: Utc.timestamp_opt(block.timestamp as i64, 0).single().unwrap(),
+            difficulty: 0,
+            miner_id: "miner".to_string(),
+            ai3_proof: Some(AI3WorkProof {
+                task_id: task.id.clone(),
+                tensor_result: output.clone(),
+                computation_hash: pow.calculate_ai3_hash(&result, &block.calculate_hash()),
+                verification_nonce: 7,
+                commitment: None,
+                computation_proof: Some(ComputationProof::generate(&task, &output, DEFAULT_PROOF_ROUNDS).unwrap().to_bytes().unwrap()),
+            }),
+        };
+        let pow = ProofOfWork { difficulty: 0, ..pow };
+        assert!(pow.verify_light(&proof, &block, &task).unwrap());
+
+        // Without proof bytes a light validator can't accept the block
+        proof.ai3_proof.as_mut().unwrap().computation_proof = None;
+        assert!(!pow.verify_light(&proof, &block, &task).unwrap());
+    }
+
+    #[test]
+    fn test_merged_mining_paths() {
+        let pow = ProofOfWork::new(1, 600).with_ai3_multiplier(2.0);
+        let task = AI3Task::new("relu".to_string(), vec![Tensor::random(ai3_lib::TensorShape::vector(4))], 0, 10, 60, "requester".to_string());
+        let output = task.execute_operation().unwrap();
+
+        // A block whose hash misses the target
+        let mut block = Block::new(1, "prev_hash".to_string(), vec![], "miner".to_string());
+        while block.calculate_hash().starts_with('0') {
+            block.nonce += 1;
+        }
+        let block_hash = block.calculate_hash();
+
+        // A tensor result committed with a nonce whose work on this block meets it instead
+        let commitment_for = |nonce| AI3Commitment::compute_hash(&task.id, "miner", &output.calculate_hash(), nonce);
+        let work_hash = |nonce, block: &Block| tensor_work_hash(&commitment_for(nonce), &block.previous_hash, &block.merkle_root);
+        // Exactly the two zero digits the multiplied difficulty asks for
+        let meets = |hash: String| hash.starts_with("00") && !hash.starts_with("000");
+        let nonce = (0..).find(|&nonce| meets(work_hash(nonce, &block))).unwrap();
+        let result = AI3Result::new(task.id.clone(), "miner".to_string(), nonce, String::new(), output.clone(), 1);
+        let mut proof = WorkProof {
+            block_hash: block_hash.clone(),
+            nonce: block.nonce,
+            timestamp: Utc.timestamp_opt(block.timestamp as i64, 0).single().unwrap(),
+            difficulty: 1,
+            miner_id: "miner".to_string(),
+            ai3_proof: Some(AI3WorkProof {
+                task_id: task.id.clone(),
+                tensor_result: output.clone(),
+                computation_hash: pow.calculate_ai3_hash(&result, &block_hash),
+                verification_nonce: nonce,
+                commitment: Some(commitment_for(nonce)),
+                computation_proof: None,
+            }),
+        };
+        assert_eq!(pow.mined_by(&proof, &block), Some(MinedBy::Tensor));
+        assert!(pow.verify_proof(&proof, &block, Some(&task)).unwrap());
+
+        // Without the multiplier the same work is no longer enough
+        assert_eq!(pow.clone().with_ai3_multiplier(3.0).mined_by(&proof, &block), None);
+
+        // The same proof is rejected on a block with a different parent
+        let other = (0..)
+            .map(|i| Block { previous_hash: format!("other_hash_{}", i), ..block.clone() })
+            .find(|other| !work_hash(nonce, other).starts_with("00"))
+            .unwrap();
+        assert_eq!(pow.mined_by(&proof, &other), None);
+        assert!(!pow.verify_proof(&proof, &other, Some(&task)).unwrap());
+
+        // The tensor path only counts with a commitment that opens to the result
+        proof.ai3_proof.as_mut().unwrap().verification_nonce = nonce + 1;
+        assert!(!pow.verify_proof(&proof, &block, Some(&task)).unwrap());
+        proof.ai3_proof = None;
+        assert_eq!(pow.mined_by(&proof, &block), None);
+        assert!(!pow.verify_proof(&proof, &block, Some(&task)).unwrap());
+    }
+
+    #[test]
+    fn test_tensor_result_is_verified() {
+        let pow = ProofOfWork::new(0, 600);
+        let task = AI3Task::new("relu".to_string(), vec![Tensor::random(ai3_lib::TensorShape::vector(4))], 0, 10, 60, "requester".to_string());
+        let block = Block::new(1, "prev_hash".to_string(), vec![], "miner".to_string());
+        let block_hash = block.calculate_hash();
+        let proof_for = |output: Tensor| {
+            let result = AI3Result::new(task.id.clone(), "miner".to_string(), 0, String::new(), output.clone(), 1);
+            WorkProof {
+                block_hash: block_hash.clone(),
+                nonce: block.nonce,
+                timestamp: Utc.timestamp_opt(block.timestamp as i64, 0).single().unwrap(),
+                difficulty: 0,
+                miner_id: "miner".to_string(),
+                ai3_proof: Some(AI3WorkProof {
+                    task_id: task.id.clone(),
+                    tensor_result: output,
+                    computation_hash: pow.calculate_ai3_hash(&result, &block_hash),
+                    verification_nonce: 0,
+                    commitment: None,
+                    computation_proof: None,
+                }),
+            }
+        };
+
+        let proof = proof_for(task.execute_operation().unwrap());
+        assert!(pow.verify_proof(&proof, &block, Some(&task)).unwrap());
+
+        // Without its task, or against another, the result can't be checked
+        let other = AI3Task::new("relu".to_string(), task.input_tensors.clone(), 0, 10, 60, "requester".to_string());
+        assert!(!pow.verify_proof(&proof, &block, None).unwrap());
+        assert!(!pow.verify_proof(&proof, &block, Some(&other)).unwrap());
+
+        // A result that doesn't answer the task is rejected, however well bound
+        let forged = proof_for(Tensor::from_vec(vec![-1.0; 4], ai3_lib::TensorShape::vector(4)).unwrap());
+        assert!(!pow.verify_proof(&forged, &block, Some(&task)).unwrap());
+    }
+
+    #[test]
+    fn test_multithreaded_mining() {
+        let mut pow = ProofOfWork::new(2, 600).with_worker_threads(4);
+        let block = Block::new(
+            1,
+            "prev_hash".to_string(),
+            vec![],
+            "miner".to_string(),
+        );
+
+        let mut work = pow.create_work(block, None);
+        let proof = pow.mine_block(&mut work, "test_miner".to_string(), None).unwrap().unwrap();
+
+        assert!(proof.block_hash.starts_with("00"));
+        assert!(pow.verify_proof(&proof, &work.block_template, None).unwrap());
+        assert!(pow.get_mining_stats().measured_hash_rate > 0.0);
+    }
+}
@@ -0,0 +1,362 @@
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use chrono::Utc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, RwLock};
+use tribechain_core::{TribeResult, TribeError};
+use crate::miner::{Miner, MinerType};
+use crate::pool::{MiningPool, MiningShare, AI3ShareProof};
+
+/// Client request, one JSON object per line: `{"id":1,"method":"subscribe","params":{...}}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StratumRequest {
+    pub id: u64,
+    #[serde(flatten)]
+    pub method: StratumMethod,
+}
+
+/// Methods a miner may call on the pool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "method", content = "params", rename_all = "snake_case")]
+pub enum StratumMethod {
+    Subscribe {
+        user_agent: String,
+        #[serde(default)]
+        miner_type: Option<MinerType>,
+    },
+    Authorize {
+        worker: String,
+        #[serde(default)]
+        password: String,
+    },
+    Submit {
+        job_id: String,
+        nonce: u64,
+        hash: String,
+        #[serde(default)]
+        ai3_proof: Option<AI3ShareProof>,
+    },
+}
+
+/// Reply to a request with the same `id`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StratumResponse {
+    pub id: u64,
+    pub result: Option<serde_json::Value>,
+    pub error: Option<String>,
+}
+
+/// Unsolicited server push
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "method", content = "params", rename_all = "snake_case")]
+pub enum StratumNotification {
+    Notify(StratumJob),
+    SetDifficulty { difficulty: u32 },
+}
+
+/// Work handed out to subscribed miners
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StratumJob {
+    pub job_id: String,
+    pub block_height: u64,
+    pub previous_hash: String,
+    pub difficulty: u32,
+    pub clean_jobs: bool, // Drop any in-flight work for earlier jobs
+}
+
+/// Per-connection protocol state
+#[derive(Debug, Clone, Default)]
+pub struct StratumSession {
+    pub session_id: String,
+    pub user_agent: Option<String>,
+    pub miner_type: Option<MinerType>,
+    pub worker: Option<String>,
+    pub difficulty: Option<u32>, // Share difficulty last sent to the miner
+}
+
+impl StratumSession {
+    pub fn is_subscribed(&self) -> bool {
+        self.user_agent.is_some()
+    }
+
+    pub fn is_authorized(&self) -> bool {
+        self.worker.is_some()
+    }
+}
+
+impl StratumResponse {
+    pub fn ok(id: u64, result: serde_json::Value) -> Self {
+        Self { id, result: Some(result), error: None }
+    }
+
+    pub fn error(id: u64, message: impl Into<String>) -> Self {
+        Self { id, result: None, error: Some(message.into()) }
+    }
+}
+
+/// JSON-lines TCP front end for a `MiningPool`
+#[derive(Debug)]
+pub struct StratumServer {
+    pub pool: Arc<RwLock<MiningPool>>,
+    current_job: RwLock<Option<StratumJob>>,
+    jobs: broadcast::Sender<StratumJob>,
+}
+
+impl StratumServer {
+    pub fn new(pool: Arc<RwLock<MiningPool>>) -> Self {
+        let (jobs, _) = broadcast::channel(16);
+        Self {
+            pool,
+            current_job: RwLock::new(None),
+            jobs,
+        }
+    }
+
+    /// Accept miner connections until the listener fails
+    pub async fn listen(self: Arc<Self>, addr: &str) -> TribeResult<()> {
+        let listener = TcpListener::bind(addr).await
+            .map_err(|e| TribeError::Network(format!("Failed to bind stratum listener: {}", e)))?;
+
+        loop {
+            let (stream, _) = listener.accept().await
+                .map_err(|e| TribeError::Network(format!("Failed to accept connection: {}", e)))?;
+
+            let server = Arc::clone(&self);
+            tokio::spawn(async move {
+                if let Err(e) = server.handle_connection(stream).await {
+                    eprintln!("Stratum connection closed: {}", e);
+                }
+            });
+        }
+    }
+
+    /// Publish a new job to every subscribed connection
+    pub async fn notify(&self, job: StratumJob) {
+        *self.current_job.write().await = Some(job.clone());
+        let _ = self.jobs.send(job); // No receivers just means nobody is subscribed yet
+    }
+
+    pub async fn current_job(&self) -> Option<StratumJob> {
+        self.current_job.read().await.clone()
+    }
+
+    async fn handle_connection(&self, stream: TcpStream) -> TribeResult<()> {
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        let mut jobs = self.jobs.subscribe();
+        let mut session = StratumSession {
+            session_id: uuid::Uuid::new_v4().to_string(),
+            ..Default::default()
+        };
+
+        loop {
+            tokio::select! {
+                line = lines.next_line() => {
+                    let line = match line.map_err(|e| TribeError::Network(e.to_string()))? {
+                        Some(line) => line,
+                        None => return Ok(()), // Client hung up
+                    };
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+
+                    let was_subscribed = session.is_subscribed();
+                    let response = match serde_json::from_str::<StratumRequest>(&line) {
+                        Ok(request) => self.handle_request(&mut session, request).await,
+                        Err(e) => StratumResponse::error(0, format!("Malformed request: {}", e)),
+                    };
+                    write_line(&mut writer, &response).await?;
+
+                    // Authorizing and vardiff retargets change the worker's share difficulty
+                    if let Some(difficulty) = self.difficulty_update(&mut session).await {
+                        write_line(&mut writer, &StratumNotification::SetDifficulty { difficulty }).await?;
+                    }
+
+                    // Newly subscribed miners get the current job right away
+                    if !was_subscribed && session.is_subscribed() {
+                        if let Some(job) = self.current_job().await {
+                            write_line(&mut writer, &StratumNotification::Notify(job)).await?;
+                        }
+                    }
+                }
+                job = jobs.recv() => {
+                    match job {
+                        Ok(job) if session.is_subscribed() => {
+                            write_line(&mut writer, &StratumNotification::Notify(job)).await?;
+                        }
+                        Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                        Err(broadcast::error::RecvError::Closed) => return Ok(()),
+                    }
+                }
+            }
+        }
+    }
+
+    /// The worker's share difficulty, if it differs from what the session last sent
+    pub async fn difficulty_update(&self, session: &mut StratumSession) -> Option<u32> {
+        let difficulty = self.pool.read().await.share_difficulty(session.worker.as_ref()?);
+        if session.difficulty == Some(difficulty) {
+            return None;
+        }
+        session.difficulty = Some(difficulty);
+        Some(difficulty)
+    }
+
+    /// Apply one request to the session and pool
+    pub async fn handle_request(&self, session: &mut StratumSession, request: StratumRequest) -> StratumResponse {
+        match request.method {
+            StratumMethod::Subscribe { user_agent, miner_type } => {
+                session.user_agent = Some(user_agent);
+                session.miner_type = miner_type;
+                let difficulty = self.pool.read().await.config.min_difficulty;
+                StratumResponse::ok(request.id, serde_json::json!({
+                    "session_id": session.session_id,
+                    "difficulty": difficulty,
+                }))
+            }
+            StratumMethod::Authorize { worker, .. } => {
+                if !session.is_subscribed() {
+                    return StratumResponse::error(request.id, "Not subscribed");
+                }
+
+                let mut pool = self.pool.write().await;
+                let known = pool.miners.read().await.contains_key(&worker);
+                if !known {
+                    let miner_type = session.miner_type.clone().unwrap_or(MinerType::CPU);
+                    let miner = Miner::new(worker.clone(), worker.clone(), miner_type);
+                    if let Err(e) = pool.add_miner(miner).await {
+                        return StratumResponse::error(request.id, e.to_string());
+                    }
+                }
+
+                session.worker = Some(worker);
+                StratumResponse::ok(request.id, serde_json::json!(true))
+            }
+            StratumMethod::Submit { job_id, nonce, hash, ai3_proof } => {
+                let worker = match &session.worker {
+                    Some(worker) => worker.clone(),
+                    None => return StratumResponse::error(request.id, "Not authorized"),
+                };
+
+                let job = match self.current_job().await {
+                    Some(job) if job.job_id == job_id => job,
+                    _ => {
+                        self.pool.write().await.record_stale_share(&worker, Utc::now());
+                        return StratumResponse::error(request.id, "Stale job");
+                    }
+                };
+
+                let share = MiningShare {
+                    miner_id: worker,
+                    block_height: job.block_height,
+                    nonce,
+                    hash,
+                    difficulty: job.difficulty,
+                    is_valid: false,
+                    timestamp: Utc::now(),
+                    ai3_proof,
+                };
+
+                match self.pool.write().await.submit_share(share).await {
+                    Ok(accepted) => StratumResponse::ok(request.id, serde_json::json!(accepted)),
+                    Err(e) => StratumResponse::error(request.id, e.to_string()),
+                }
+            }
+        }
+    }
+}
+
+async fn write_line<T: Serialize>(writer: &mut tokio::net::tcp::OwnedWriteHalf, message: &T) -> TribeResult<()> {
+    let mut line = serde_json::to_vec(message)
+        .map_err(|e| TribeError::Network(format!("Failed to encode message: {}", e)))?;
+    line.push(b'\n');
+    writer.write_all(&line).await
+        .map_err(|e| TribeError::Network(format!("Failed to write message: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pool::PoolConfig;
+
+    fn test_server() -> StratumServer {
+        let pool = MiningPool::new("pool1".to_string(), "Test Pool".to_string(), PoolConfig::default());
+        StratumServer::new(Arc::new(RwLock::new(pool)))
+    }
+
+    #[test]
+    fn test_request_framing() {
+        let line = r#"{"id":1,"method":"authorize","params":{"worker":"esp_1"}}"#;
+        let request: StratumRequest = serde_json::from_str(line).unwrap();
+        assert_eq!(request.id, 1);
+        assert!(matches!(request.method, StratumMethod::Authorize { ref worker, .. } if worker == "esp_1"));
+
+        let job = StratumJob {
+            job_id: "job1".to_string(),
+            block_height: 7,
+            previous_hash: "00ff".to_string(),
+            difficulty: 4,
+            clean_jobs: true,
+        };
+        let encoded = serde_json::to_string(&StratumNotification::Notify(job)).unwrap();
+        assert!(encoded.starts_with(r#"{"method":"notify","params":{"job_id":"job1""#));
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_authorize_submit() {
+        let server = test_server();
+        let mut session = StratumSession::default();
+
+        let submit = StratumRequest {
+            id: 3,
+            method: StratumMethod::Submit {
+                job_id: "job1".to_string(),
+                nonce: 42,
+                hash: "0000abcd".to_string(),
+                ai3_proof: None,
+            },
+        };
+        assert!(server.handle_request(&mut session, submit.clone()).await.error.is_some());
+
+        let subscribe = StratumRequest {
+            id: 1,
+            method: StratumMethod::Subscribe { user_agent: "esp32/1.0".to_string(), miner_type: Some(MinerType::ESP32) },
+        };
+        assert!(server.handle_request(&mut session, subscribe).await.error.is_none());
+
+        let authorize = StratumRequest {
+            id: 2,
+            method: StratumMethod::Authorize { worker: "esp_1".to_string(), password: String::new() },
+        };
+        assert!(server.handle_request(&mut session, authorize).await.error.is_none());
+        assert_eq!(server.pool.read().await.stats.total_miners, 1);
+        assert_eq!(server.difficulty_update(&mut session).await, Some(2));
+        assert_eq!(server.difficulty_update(&mut session).await, None);
+
+        server.notify(StratumJob {
+            job_id: "job1".to_string(),
+            block_height: 1,
+            previous_hash: "00ff".to_string(),
+            difficulty: 4,
+            clean_jobs: true,
+        }).await;
+        let response = server.handle_request(&mut session, submit).await;
+        assert_eq!(response.result, Some(serde_json::json!(true)));
+
+        let stale = StratumRequest {
+            id: 4,
+            method: StratumMethod::Submit {
+                job_id: "job0".to_string(),
+                nonce: 43,
+                hash: "0000abcd".to_string(),
+                ai3_proof: None,
+            },
+        };
+        assert!(server.handle_request(&mut session, stale).await.error.is_some());
+        let pool = server.pool.read().await;
+        assert_eq!(pool.stats.miners["esp_1"].accepted_shares, 1);
+        assert_eq!(pool.stats.miners["esp_1"].stale_shares, 1);
+        assert_eq!(pool.stats.stale_shares, 1);
+    }
+}
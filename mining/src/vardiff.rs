@@ -0,0 +1,100 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+
+/// Variable share difficulty, so miners of very different speeds each submit
+/// shares at roughly the same rate
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VardiffConfig {
+    pub target_share_secs: f64, // Aim for one share per miner this often
+    pub retarget_shares: u32, // Retarget after this many shares...
+    pub retarget_secs: i64, // ...or once this long has passed since the last retarget
+    pub min_difficulty: u32,
+    pub max_difficulty: u32,
+}
+
+/// One miner's share difficulty and the shares it submitted since the last retarget
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShareTarget {
+    pub difficulty: u32,
+    pub window_start: DateTime<Utc>,
+    pub window_shares: u32,
+}
+
+impl VardiffConfig {
+    /// Difficulty at which `hash_rate` finds one share per target interval
+    pub fn difficulty_for_rate(&self, hash_rate: f64) -> u32 {
+        let hashes_per_share = (hash_rate * self.target_share_secs).max(1.0);
+        // Each difficulty step is one more leading hex zero, 16x the hashes per share
+        let difficulty = hashes_per_share.log(16.0).round() as u32;
+        difficulty.clamp(self.min_difficulty, self.max_difficulty.max(self.min_difficulty))
+    }
+
+    /// Starting target for a miner advertising `hash_rate`
+    pub fn initial_target(&self, hash_rate: f64, now: DateTime<Utc>) -> ShareTarget {
+        ShareTarget {
+            difficulty: self.difficulty_for_rate(hash_rate),
+            window_start: now,
+            window_shares: 0,
+        }
+    }
+
+    /// Count a share accepted at `at`, retargeting from the observed rate once the
+    /// window is full. Returns the new difficulty if it changed
+    pub fn record_share(&self, target: &mut ShareTarget, at: DateTime<Utc>) -> Option<u32> {
+        target.window_shares += 1;
+        let elapsed = (at - target.window_start).num_milliseconds() as f64 / 1000.0;
+        if target.window_shares < self.retarget_shares && elapsed < self.retarget_secs as f64 {
+            return None;
+        }
+
+        let hashes = target.window_shares as f64 * 16f64.powi(target.difficulty as i32);
+        let difficulty = self.difficulty_for_rate(hashes / elapsed.max(1.0));
+        target.window_start = at;
+        target.window_shares = 0;
+        if difficulty == target.difficulty {
+            return None;
+        }
+        target.difficulty = difficulty;
+        Some(difficulty)
+    }
+}
+
+impl Default for VardiffConfig {
+    fn default() -> Self {
+        Self {
+            target_share_secs: 15.0,
+            retarget_shares: 8,
+            retarget_secs: 120,
+            min_difficulty: 1,
+            max_difficulty: 16,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_difficulty_follows_share_rate() {
+        let config = VardiffConfig::default();
+        // 10 H/s over 15s is 150 hashes, closest to 16^2
+        assert_eq!(config.difficulty_for_rate(10.0), 2);
+        assert_eq!(config.difficulty_for_rate(0.0), 1);
+        assert_eq!(config.difficulty_for_rate(f64::MAX), 16);
+
+        let start = Utc::now();
+        let mut target = config.initial_target(10.0, start);
+        // Eight shares in eight seconds is far faster than one per 15s
+        for second in 1..8 {
+            assert_eq!(config.record_share(&mut target, start + Duration::seconds(second)), None);
+        }
+        assert_eq!(config.record_share(&mut target, start + Duration::seconds(8)), Some(3));
+        assert_eq!(target.window_shares, 0);
+
+        // A single share after the retarget period brings it back down
+        let later = start + Duration::seconds(8 + 600);
+        assert_eq!(config.record_share(&mut target, later), Some(2));
+    }
+}
@@ -0,0 +1,209 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Duration, Utc};
+use tribechain_core::{TribeResult, TribeError};
+
+/// File the address book is stored in, inside the node's data directory
+pub const ADDRESS_BOOK_FILE: &str = "peers.json";
+
+/// Most addresses sent in a single `Addr` message
+pub const MAX_ADDR_PER_MESSAGE: usize = 1000;
+
+/// A peer address we have heard of, with how well it has worked for us
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AddressEntry {
+    pub address: String,
+    pub last_seen: DateTime<Utc>,
+    pub score: i32, // Raised on successful connections, lowered on failures
+    pub failures: u32, // Consecutive failed connection attempts
+    pub source: Option<String>, // Peer that told us about it; None if we connected ourselves
+}
+
+/// Reply to `GetAddr`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AddrMessage {
+    pub addresses: Vec<AddressEntry>,
+}
+
+/// Known peer addresses, persisted so a restarted node can rejoin without bootstrap nodes
+#[derive(Debug, Clone)]
+pub struct AddressBook {
+    pub path: Option<PathBuf>,
+    pub max_entries: usize,
+    pub max_failures: u32, // Entries failing this many times in a row are forgotten
+    entries: HashMap<String, AddressEntry>,
+}
+
+impl AddressBook {
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            path: None,
+            max_entries,
+            max_failures: 10,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Open the address book in `data_dir`, starting empty if none was saved yet
+    pub fn load(data_dir: &str) -> TribeResult<Self> {
+        let path = Path::new(data_dir).join(ADDRESS_BOOK_FILE);
+        let mut book = Self::default();
+
+        if path.exists() {
+            let contents = std::fs::read_to_string(&path)
+                .map_err(|e| TribeError::Storage(format!("Failed to read address book: {}", e)))?;
+            let entries: Vec<AddressEntry> = serde_json::from_str(&contents)
+                .map_err(|e| TribeError::Storage(format!("Failed to parse address book: {}", e)))?;
+            for entry in entries {
+                book.entries.insert(entry.address.clone(), entry);
+            }
+        }
+
+        book.path = Some(path);
+        Ok(book)
+    }
+
+    pub fn save(&self) -> TribeResult<()> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| TribeError::Storage(format!("Failed to create data directory: {}", e)))?;
+        }
+
+        let contents = serde_json::to_string_pretty(&self.best(self.max_entries))
+            .map_err(|e| TribeError::Storage(format!("Failed to serialize address book: {}", e)))?;
+        std::fs::write(path, contents)
+            .map_err(|e| TribeError::Storage(format!("Failed to write address book: {}", e)))
+    }
+
+    /// Learn addresses from a peer's `Addr` message; known entries only get their timestamp refreshed
+    pub fn add_from_peer(&mut self, source: &str, addresses: Vec<AddressEntry>) -> usize {
+        let mut added = 0;
+        for entry in addresses.into_iter().take(MAX_ADDR_PER_MESSAGE) {
+            let last_seen = entry.last_seen.min(Utc::now()); // Don't trust timestamps from the future
+            match self.entries.get_mut(&entry.address) {
+                Some(known) => known.last_seen = known.last_seen.max(last_seen),
+                None => {
+                    self.entries.insert(entry.address.clone(), AddressEntry {
+                        address: entry.address,
+                        last_seen,
+                        score: 0,
+                        failures: 0,
+                        source: Some(source.to_string()),
+                    });
+                    added += 1;
+                }
+            }
+        }
+        self.evict();
+        added
+    }
+
+    pub fn record_success(&mut self, address: &str) {
+        let entry = self.entries.entry(address.to_string()).or_insert_with(|| AddressEntry {
+            address: address.to_string(),
+            last_seen: Utc::now(),
+            score: 0,
+            failures: 0,
+            source: None,
+        });
+        entry.last_seen = Utc::now();
+        entry.score = (entry.score + 1).min(100);
+        entry.failures = 0;
+        self.evict();
+    }
+
+    pub fn record_failure(&mut self, address: &str) {
+        if let Some(entry) = self.entries.get_mut(address) {
+            entry.score = (entry.score - 2).max(-100);
+            entry.failures += 1;
+            if entry.failures >= self.max_failures {
+                self.entries.remove(address);
+            }
+        }
+    }
+
+    /// Up to `limit` addresses, best score and most recently seen first
+    pub fn best(&self, limit: usize) -> Vec<AddressEntry> {
+        let mut entries: Vec<AddressEntry> = self.entries.values().cloned().collect();
+        entries.sort_by(|a, b| b.score.cmp(&a.score).then(b.last_seen.cmp(&a.last_seen)));
+        entries.truncate(limit);
+        entries
+    }
+
+    /// Addresses worth sharing in reply to `GetAddr`: seen within the last `max_age`
+    pub fn shareable(&self, max_age: Duration) -> Vec<AddressEntry> {
+        let cutoff = Utc::now() - max_age;
+        self.best(MAX_ADDR_PER_MESSAGE).into_iter()
+            .filter(|entry| entry.last_seen >= cutoff && entry.score >= 0)
+            .collect()
+    }
+
+    pub fn get(&self, address: &str) -> Option<&AddressEntry> {
+        self.entries.get(address)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn evict(&mut self) {
+        if self.entries.len() <= self.max_entries {
+            return;
+        }
+        let keep: Vec<String> = self.best(self.max_entries).into_iter().map(|entry| entry.address).collect();
+        self.entries.retain(|address, _| keep.contains(address));
+    }
+}
+
+impl Default for AddressBook {
+    fn default() -> Self {
+        Self::new(2000)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(address: &str) -> AddressEntry {
+        AddressEntry {
+            address: address.to_string(),
+            last_seen: Utc::now(),
+            score: 50, // Remote scores are ignored
+            failures: 0,
+            source: None,
+        }
+    }
+
+    #[test]
+    fn test_address_book_persistence_and_scoring() {
+        let dir = std::env::temp_dir().join(format!("tribechain-addrbook-{}", uuid::Uuid::new_v4()));
+        let data_dir = dir.to_str().unwrap();
+
+        let mut book = AddressBook::load(data_dir).unwrap();
+        assert!(book.is_empty());
+        assert_eq!(book.add_from_peer("peer1", vec![entry("10.0.0.1:8333"), entry("10.0.0.2:8333")]), 2);
+        assert_eq!(book.add_from_peer("peer2", vec![entry("10.0.0.1:8333")]), 0);
+
+        book.record_success("10.0.0.2:8333");
+        book.record_failure("10.0.0.1:8333");
+        assert_eq!(book.best(1)[0].address, "10.0.0.2:8333");
+        assert_eq!(book.shareable(Duration::hours(3)).len(), 1);
+        book.save().unwrap();
+
+        let reloaded = AddressBook::load(data_dir).unwrap();
+        assert_eq!(reloaded.len(), 2);
+        assert_eq!(reloaded.get("10.0.0.2:8333").unwrap().score, 1);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
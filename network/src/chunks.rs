@@ -0,0 +1,155 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Most chunks asked of one peer per `GetChunks`
+pub const MAX_CHUNKS_PER_REQUEST: usize = 16;
+
+/// Chunk hashes a peer can serve
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkAnnounce {
+    pub hashes: Vec<String>,
+}
+
+/// Ask a peer for chunks it announced
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetChunks {
+    pub hashes: Vec<String>,
+}
+
+/// Reply to `GetChunks`; chunks the peer no longer holds are left out
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkData {
+    pub chunks: Vec<(String, Vec<u8>)>,
+}
+
+/// Content-addressed chunks of task input data held locally and known on peers.
+/// Hashes are SHA-256 of the chunk bytes, matching the manifests tasks carry.
+#[derive(Debug, Clone, Default)]
+pub struct ChunkExchange {
+    pub chunks: HashMap<String, Vec<u8>>,
+    pub providers: HashMap<String, HashSet<String>>, // chunk hash -> peers that announced it
+    pub requested: HashMap<String, String>, // chunk hash -> peer asked for it
+}
+
+pub(crate) fn chunk_hash(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+impl ChunkExchange {
+    /// Hold chunks to serve and return the announcement for peers
+    pub fn publish(&mut self, chunks: Vec<(String, Vec<u8>)>) -> ChunkAnnounce {
+        let mut hashes = Vec::with_capacity(chunks.len());
+        for (hash, bytes) in chunks {
+            if chunk_hash(&bytes) == hash {
+                hashes.push(hash.clone());
+                self.chunks.insert(hash, bytes);
+            }
+        }
+        ChunkAnnounce { hashes }
+    }
+
+    pub fn record_announce(&mut self, peer_id: &str, announce: ChunkAnnounce) {
+        for hash in announce.hashes {
+            self.providers.entry(hash).or_default().insert(peer_id.to_string());
+        }
+    }
+
+    /// Requests for the `wanted` chunks not held or already in flight, spread over the
+    /// peers announcing them. Chunks nobody announced are skipped until someone does.
+    pub fn requests_for(&mut self, wanted: &[String]) -> BTreeMap<String, GetChunks> {
+        let mut load: HashMap<String, usize> = HashMap::new();
+        let mut requests: BTreeMap<String, GetChunks> = BTreeMap::new();
+        for hash in wanted {
+            if self.chunks.contains_key(hash) || self.requested.contains_key(hash) {
+                continue;
+            }
+            let peer = self.providers.get(hash).and_then(|peers| {
+                peers.iter()
+                    .filter(|peer| load.get(*peer).copied().unwrap_or(0) < MAX_CHUNKS_PER_REQUEST)
+                    .min_by_key(|peer| (load.get(*peer).copied().unwrap_or(0), (*peer).clone()))
+                    .cloned()
+            });
+            if let Some(peer) = peer {
+                *load.entry(peer.clone()).or_insert(0) += 1;
+                self.requested.insert(hash.clone(), peer.clone());
+                requests.entry(peer).or_insert_with(|| GetChunks { hashes: Vec::new() }).hashes.push(hash.clone());
+            }
+        }
+        requests
+    }
+
+    pub fn serve(&self, request: &GetChunks) -> ChunkData {
+        let chunks = request.hashes.iter()
+            .take(MAX_CHUNKS_PER_REQUEST)
+            .filter_map(|hash| self.chunks.get(hash).map(|bytes| (hash.clone(), bytes.clone())))
+            .collect();
+        ChunkData { chunks }
+    }
+
+    /// Keep chunks from `peer_id` that we asked it for and that match their hash.
+    /// Returns the accepted chunks; a peer serving bad bytes stops being a provider.
+    pub fn receive(&mut self, peer_id: &str, data: ChunkData) -> Vec<(String, Vec<u8>)> {
+        let mut accepted = Vec::new();
+        for (hash, bytes) in data.chunks {
+            if self.requested.get(&hash).map(String::as_str) != Some(peer_id) {
+                continue;
+            }
+            self.requested.remove(&hash);
+            if chunk_hash(&bytes) != hash {
+                if let Some(peers) = self.providers.get_mut(&hash) {
+                    peers.remove(peer_id);
+                }
+                continue;
+            }
+            self.chunks.insert(hash.clone(), bytes.clone());
+            accepted.push((hash, bytes));
+        }
+        accepted
+    }
+
+    /// Forget a peer, so chunks it was asked for can be requested elsewhere
+    pub fn remove_peer(&mut self, peer_id: &str) {
+        for peers in self.providers.values_mut() {
+            peers.remove(peer_id);
+        }
+        self.providers.retain(|_, peers| !peers.is_empty());
+        self.requested.retain(|_, peer| peer != peer_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunks_are_fetched_and_verified() {
+        let data = vec![1u8, 2, 3, 4];
+        let hash = chunk_hash(&data);
+        let mut seeder = ChunkExchange::default();
+        let announce = seeder.publish(vec![(hash.clone(), data.clone()), ("bogus".to_string(), vec![9])]);
+        assert_eq!(announce.hashes, vec![hash.clone()]);
+
+        let mut miner = ChunkExchange::default();
+        assert!(miner.requests_for(std::slice::from_ref(&hash)).is_empty()); // Nobody announced it yet
+        miner.record_announce("seeder", announce.clone());
+        miner.record_announce("liar", announce);
+
+        // The chunk goes to one provider; a corrupted reply drops that provider
+        let requests = miner.requests_for(std::slice::from_ref(&hash));
+        assert_eq!(requests.len(), 1);
+        assert!(miner.requests_for(std::slice::from_ref(&hash)).is_empty()); // Already in flight
+        let (peer, request) = requests.into_iter().next().unwrap();
+        assert_eq!(peer, "liar");
+        let forged = ChunkData { chunks: vec![(hash.clone(), vec![0, 0, 0, 0])] };
+        assert!(miner.receive("liar", forged).is_empty());
+        assert!(!miner.providers[&hash].contains("liar"));
+
+        let requests = miner.requests_for(std::slice::from_ref(&hash));
+        assert_eq!(requests.keys().collect::<Vec<_>>(), vec!["seeder"]);
+        assert_eq!(request.hashes, vec![hash.clone()]);
+        let reply = seeder.serve(&requests["seeder"]);
+        assert_eq!(miner.receive("seeder", reply), vec![(hash.clone(), data)]);
+        assert!(miner.chunks.contains_key(&hash));
+    }
+}
@@ -0,0 +1,173 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use tribechain_core::{Block, Transaction, TribeResult, TribeError};
+
+/// Block announced as its header plus short transaction IDs; peers rebuild the
+/// body from their mempool instead of downloading every transaction again.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompactBlock {
+    #[serde(with = "tribechain_core::codec::versioned")]
+    pub header: Block, // `transactions` left empty
+    pub short_ids: Vec<u64>,
+}
+
+/// Ask the announcing peer for transactions we could not find locally
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetBlockTransactions {
+    pub block_hash: String,
+    pub indexes: Vec<usize>,
+}
+
+/// Reply to `GetBlockTransactions`, in the order requested
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockTransactions {
+    pub block_hash: String,
+    #[serde(with = "tribechain_core::codec::versioned_vec")]
+    pub transactions: Vec<Transaction>,
+}
+
+/// Outcome of rebuilding a compact block from the mempool
+#[derive(Debug, Clone)]
+pub enum Reconstruction {
+    Complete(Block),
+    Incomplete(PartialBlock),
+}
+
+/// Compact block waiting on missing transactions
+#[derive(Debug, Clone)]
+pub struct PartialBlock {
+    pub header: Block,
+    pub slots: Vec<Option<Transaction>>,
+}
+
+impl CompactBlock {
+    pub fn from_block(block: &Block) -> Self {
+        let short_ids = block.transactions.iter()
+            .map(|tx| Self::short_id(&block.hash, &tx.hash))
+            .collect();
+        let mut header = block.clone();
+        header.transactions = Vec::new();
+        Self { header, short_ids }
+    }
+
+    /// 48-bit transaction ID salted with the block hash, so collisions can't be
+    /// precomputed across blocks
+    pub fn short_id(block_hash: &str, tx_hash: &str) -> u64 {
+        let mut hasher = Sha256::new();
+        hasher.update(block_hash.as_bytes());
+        hasher.update(tx_hash.as_bytes());
+        let digest = hasher.finalize();
+        let mut bytes = [0u8; 8];
+        bytes[..6].copy_from_slice(&digest[..6]);
+        u64::from_le_bytes(bytes)
+    }
+
+    /// Fill transactions from `mempool`; anything not found is left for a follow-up request
+    pub fn reconstruct<'a>(&self, mempool: impl IntoIterator<Item = &'a Transaction>) -> Reconstruction {
+        let mut by_short_id: HashMap<u64, &Transaction> = HashMap::new();
+        for tx in mempool {
+            by_short_id.insert(Self::short_id(&self.header.hash, &tx.hash), tx);
+        }
+
+        let partial = PartialBlock {
+            header: self.header.clone(),
+            slots: self.short_ids.iter()
+                .map(|short_id| by_short_id.get(short_id).map(|tx| (*tx).clone()))
+                .collect(),
+        };
+        partial.try_complete()
+    }
+}
+
+impl PartialBlock {
+    pub fn missing_indexes(&self) -> Vec<usize> {
+        self.slots.iter()
+            .enumerate()
+            .filter(|(_, slot)| slot.is_none())
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    pub fn request(&self) -> GetBlockTransactions {
+        GetBlockTransactions {
+            block_hash: self.header.hash.clone(),
+            indexes: self.missing_indexes(),
+        }
+    }
+
+    /// Apply a `BlockTransactions` reply to the slots it was requested for
+    pub fn fill(mut self, response: BlockTransactions) -> TribeResult<Reconstruction> {
+        let missing = self.missing_indexes();
+        if response.block_hash != self.header.hash || response.transactions.len() != missing.len() {
+            return Err(TribeError::Network("Block transactions do not match the request".to_string()));
+        }
+
+        for (index, tx) in missing.into_iter().zip(response.transactions) {
+            self.slots[index] = Some(tx);
+        }
+        Ok(self.try_complete())
+    }
+
+    fn try_complete(self) -> Reconstruction {
+        if self.slots.iter().any(Option::is_none) {
+            return Reconstruction::Incomplete(self);
+        }
+
+        let mut block = self.header.clone();
+        block.transactions = self.slots.iter().flatten().cloned().collect();
+        Reconstruction::Complete(block)
+    }
+}
+
+/// Serve a `GetBlockTransactions` request from a full block
+pub fn block_transactions(block: &Block, request: &GetBlockTransactions) -> TribeResult<BlockTransactions> {
+    let transactions = request.indexes.iter()
+        .map(|&index| block.transactions.get(index).cloned()
+            .ok_or_else(|| TribeError::Network(format!("Transaction index {} out of range", index))))
+        .collect::<TribeResult<Vec<_>>>()?;
+
+    Ok(BlockTransactions {
+        block_hash: block.hash.clone(),
+        transactions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tribechain_core::TransactionType;
+
+    fn transfer(to: &str) -> Transaction {
+        Transaction::new("alice".to_string(), TransactionType::Transfer { to: to.to_string(), amount: 10 }, 1, 0)
+    }
+
+    #[test]
+    fn test_compact_block_round_trip() {
+        let txs = vec![transfer("bob"), transfer("carol"), transfer("dave")];
+        let mut block = Block::new(1, "0".repeat(64), txs.clone(), "miner".to_string());
+        block.hash = block.calculate_hash();
+
+        let compact = CompactBlock::from_block(&block);
+        assert!(compact.header.transactions.is_empty());
+        assert_eq!(compact.short_ids.len(), 3);
+
+        // Peer is missing carol's transfer
+        let mempool = vec![txs[0].clone(), txs[2].clone(), transfer("eve")];
+        let partial = match compact.reconstruct(&mempool) {
+            Reconstruction::Incomplete(partial) => partial,
+            Reconstruction::Complete(_) => panic!("should be missing a transaction"),
+        };
+        let request = partial.request();
+        assert_eq!(request.indexes, vec![1]);
+
+        let response = block_transactions(&block, &request).unwrap();
+        match partial.fill(response).unwrap() {
+            Reconstruction::Complete(rebuilt) => {
+                assert!(rebuilt.has_valid_merkle_root());
+                assert_eq!(rebuilt.transactions[1].hash, txs[1].hash);
+            }
+            Reconstruction::Incomplete(_) => panic!("should be complete"),
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+use tribechain_core::{TribeResult, TribeError, Block, BlockTemplate, ConsensusType, DoubleSignEvidence, ValidatorPerformanceReport};
+use tribechain_mining::ConsensusType as EngineConsensus;
+
+/// Block production for this node on top of the mining crate's consensus engine, which
+/// tracks validators, slashing and producer schedules
+#[derive(Debug)]
+pub struct ConsensusEngine {
+    pub consensus_type: ConsensusType,
+    pub engine: tribechain_mining::ConsensusEngine,
+    pub validator_key: Option<String>, // Signs produced blocks under proof of stake
+}
+
+impl ConsensusEngine {
+    pub fn new(consensus_type: ConsensusType) -> TribeResult<Self> {
+        let engine_consensus = match consensus_type {
+            ConsensusType::ProofOfWork => EngineConsensus::ProofOfWork,
+            ConsensusType::ProofOfStake => EngineConsensus::ProofOfStake,
+            ConsensusType::DelegatedProofOfStake => EngineConsensus::DelegatedProofOfStake,
+            ConsensusType::TensorProofOfWork => EngineConsensus::TensorProofOfWork,
+        };
+
+        Ok(Self {
+            consensus_type,
+            engine: tribechain_mining::ConsensusEngine::new(engine_consensus)?,
+            validator_key: None,
+        })
+    }
+
+    pub fn with_validator_key(mut self, private_key: String) -> Self {
+        self.validator_key = Some(private_key);
+        self
+    }
+
+    pub async fn start(&mut self) -> TribeResult<()> {
+        self.engine.start().await
+    }
+
+    pub async fn stop(&mut self) -> TribeResult<()> {
+        self.engine.stop().await
+    }
+
+    /// Seal a block template: find its nonce and, under proof of stake, sign it as this
+    /// node's validator. The nonce search runs off the async runtime.
+    pub async fn mine_block(&mut self, template: BlockTemplate) -> TribeResult<Block> {
+        let validator_key = match self.consensus_type {
+            ConsensusType::ProofOfStake | ConsensusType::DelegatedProofOfStake => Some(
+                self.validator_key.clone()
+                    .ok_or_else(|| TribeError::Consensus("Proof of stake needs a validator key to sign blocks".to_string()))?,
+            ),
+            ConsensusType::ProofOfWork | ConsensusType::TensorProofOfWork => None,
+        };
+
+        let mut block = template.block;
+        let difficulty = template.difficulty;
+        let mut block = tokio::task::spawn_blocking(move || block.mine_block(difficulty).map(|()| block))
+            .await
+            .map_err(|e| TribeError::Consensus(format!("Mining task failed: {}", e)))??;
+
+        if let Some(private_key) = validator_key {
+            block.sign_as_validator(&private_key);
+        }
+        Ok(block)
+    }
+
+    /// Track an accepted block; returns producers demoted for missed slots
+    pub async fn record_block(&mut self, block: &Block) -> Vec<String> {
+        self.engine.record_block(block).await
+    }
+
+    /// Slash a double-signing validator. Returns `true` when the evidence is new, so
+    /// callers know to relay it.
+    pub async fn report_evidence(&mut self, evidence: &DoubleSignEvidence) -> TribeResult<bool> {
+        self.engine.report_evidence(evidence).await
+    }
+
+    /// This node's signed reports on validators for epochs closed since the last call
+    pub fn take_performance_reports(&mut self, reporter: &str, private_key: &str) -> Vec<ValidatorPerformanceReport> {
+        self.engine.take_performance_reports(reporter, private_key)
+    }
+
+    /// Apply a validator's report on another's uptime. Returns `true` when the report is
+    /// new, so callers know to relay it.
+    pub async fn report_performance(&mut self, report: &ValidatorPerformanceReport) -> TribeResult<bool> {
+        self.engine.report_performance(report).await
+    }
+
+    /// Pass on-chain delegate votes to consensus that elects its producers
+    pub async fn update_votes(&mut self, timestamp: u64, votes: &HashMap<String, u64>) {
+        self.engine.update_votes(timestamp, votes).await
+    }
+
+    /// Producer owning the slot at `timestamp`, if the consensus schedules producers
+    pub async fn scheduled_producer(&self, timestamp: u64) -> Option<String> {
+        self.engine.scheduled_producer(timestamp).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tribechain_core::{keys, ChainSpec, StorageConfig, TribeChain};
+    use tribechain_mining::consensus::ValidatorInfo;
+
+    fn template() -> BlockTemplate {
+        let chain = TribeChain::with_chain_spec("unused", &StorageConfig::memory(), &ChainSpec::dev()).unwrap();
+        chain.get_block_template("node", None).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_mined_blocks_meet_the_template_difficulty() {
+        let mut consensus = ConsensusEngine::new(ConsensusType::ProofOfWork).unwrap();
+        let template = template();
+        let difficulty = template.difficulty;
+
+        let block = consensus.mine_block(template).await.unwrap();
+        assert!(block.is_valid_hash(difficulty));
+        assert!(block.validator_signature.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_proof_of_stake_blocks_are_signed() {
+        let mut consensus = ConsensusEngine::new(ConsensusType::ProofOfStake).unwrap();
+        assert!(consensus.mine_block(template()).await.is_err());
+
+        let mut consensus = consensus.with_validator_key("validator-key".to_string());
+        let block = consensus.mine_block(template()).await.unwrap();
+        assert!(block.verify_validator_signature(&keys::public_key("validator-key")));
+    }
+
+    /// A validator on `consensus` holding `key`
+    async fn add_validator(consensus: &mut ConsensusEngine, address: &str, key: &str) {
+        consensus.engine.add_validator(ValidatorInfo::new(address.to_string(), 1_000)).await.unwrap();
+        consensus.engine.register_validator_key(address, keys::public_key(key)).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_double_sign_evidence_is_applied_once() {
+        let mut consensus = ConsensusEngine::new(ConsensusType::ProofOfStake).unwrap();
+        add_validator(&mut consensus, "node", "node-key").await;
+
+        let signed = |nonce| {
+            let mut block = template().block;
+            block.nonce = nonce;
+            block.hash = block.calculate_hash();
+            block.sign_as_validator("node-key");
+            block
+        };
+        let evidence = DoubleSignEvidence::from_blocks(&signed(1), &signed(2)).unwrap();
+
+        assert!(consensus.report_evidence(&evidence).await.unwrap());
+        assert!(!consensus.report_evidence(&evidence).await.unwrap());
+        assert!(consensus.engine.validators.read().await["node"].stake < 1_000);
+
+        let mut forged = evidence.clone();
+        forged.validator = "stranger".to_string();
+        assert!(consensus.report_evidence(&forged).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_performance_reports_need_a_known_reporter() {
+        let mut consensus = ConsensusEngine::new(ConsensusType::DelegatedProofOfStake).unwrap();
+        add_validator(&mut consensus, "alice", "alice-key").await;
+        add_validator(&mut consensus, "bob", "bob-key").await;
+
+        let mut report = ValidatorPerformanceReport::new("bob".to_string(), 3, 10, 8, "alice".to_string());
+        report.sign("alice-key");
+        assert!(consensus.report_performance(&report).await.unwrap());
+        assert!(!consensus.report_performance(&report).await.unwrap());
+        assert_eq!(consensus.engine.validators.read().await["bob"].uptime, 80.0);
+
+        let mut misattributed = ValidatorPerformanceReport::new("alice".to_string(), 3, 10, 2, "bob".to_string());
+        misattributed.sign("mallory-key");
+        assert!(consensus.report_performance(&misattributed).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_delegate_votes_set_the_schedule() {
+        let now = chrono::Utc::now().timestamp() as u64;
+        let mut pow = ConsensusEngine::new(ConsensusType::ProofOfWork).unwrap();
+        pow.update_votes(now, &HashMap::from([("alice".to_string(), 100)])).await;
+        assert_eq!(pow.scheduled_producer(now).await, None);
+
+        let mut dpos = ConsensusEngine::new(ConsensusType::DelegatedProofOfStake).unwrap();
+        assert_eq!(dpos.scheduled_producer(now).await, None);
+        dpos.update_votes(now, &HashMap::from([("alice".to_string(), 100)])).await;
+        assert_eq!(dpos.scheduled_producer(now).await.as_deref(), Some("alice"));
+    }
+}
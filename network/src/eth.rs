@@ -0,0 +1,291 @@
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use tribechain_core::{check_address_input, hrp_for_chain, Address, LogFilter, MatchedLog, Transaction, TribeChain};
+use tribechain_contracts::{ContractCall, ContractEngine};
+use crate::rpc::{param, RpcRequest, RpcResponse, RpcServer, INVALID_PARAMS, METHOD_NOT_FOUND};
+
+/// Ethereum-style methods answered by `RpcServer::handle_eth` when the compatibility layer is on
+pub const ETH_METHODS: &[&str] = &["eth_chainId", "net_version", "eth_blockNumber", "eth_getBalance", "eth_call", "eth_getLogs"];
+
+/// Ethereum-style methods that change the chain, answered by `RpcServer::handle_eth_mut`
+pub const ETH_WRITE_METHODS: &[&str] = &["eth_sendRawTransaction"];
+
+/// Error code Ethereum nodes use for a call that reverted
+pub const EXECUTION_REVERTED: i32 = 3;
+
+/// Ethereum tools count balances in 18-decimal units; TRIBE amounts have 6
+const WEI_PER_UNIT: u128 = 1_000_000_000_000;
+
+/// Selector `eth_call` data starts with to name a contract method: the first four bytes
+/// of the SHA-256 of its name
+pub fn selector(method: &str) -> [u8; 4] {
+    let digest = Sha256::digest(method.as_bytes());
+    [digest[0], digest[1], digest[2], digest[3]]
+}
+
+/// Topic as Ethereum tools see it: the SHA-256 of the TribeChain topic, as 32 bytes of hex
+pub fn topic_hash(topic: &str) -> String {
+    format!("0x{}", hex::encode(Sha256::digest(topic.as_bytes())))
+}
+
+fn quantity(value: u128) -> Value {
+    json!(format!("{:#x}", value))
+}
+
+/// Hex with the `0x` prefix, for hashes and contract addresses that are plain hex
+fn prefixed(value: &str) -> String {
+    match value.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        true => format!("0x{}", value),
+        false => value.to_string(),
+    }
+}
+
+fn unprefixed(value: &str) -> String {
+    value.trim_start_matches("0x").to_lowercase()
+}
+
+/// Block number of a quantity or tag; TribeChain has no pending block, so `pending` is the best one
+fn block_number(tag: Option<&Value>, height: u64) -> Result<u64, String> {
+    match tag.and_then(Value::as_str) {
+        None | Some("latest" | "pending" | "safe" | "finalized") => Ok(height),
+        Some("earliest") => Ok(0),
+        Some(number) => u64::from_str_radix(number.trim_start_matches("0x"), 16)
+            .map_err(|_| format!("Invalid block number {}", number)),
+    }
+}
+
+/// Account an Ethereum-style address refers to: 20 bytes of hex are the payload of an
+/// address on this chain's network, anything else must already be one
+fn account(address: &str, chain_id: u64) -> Result<String, String> {
+    if address.starts_with("0x") {
+        return Address::from_hex(hrp_for_chain(chain_id), address).map(|address| address.to_string()).map_err(|e| e.to_string());
+    }
+    check_address_input(address, chain_id).map_err(|e| e.to_string())?;
+    Ok(address.to_string())
+}
+
+/// Topic filter positions: null matches anything, otherwise any of the listed hashes
+fn topic_filters(topics: Option<&Value>) -> Vec<Option<Vec<String>>> {
+    topics.and_then(Value::as_array).into_iter().flatten().map(|topic| match topic {
+        Value::String(hash) => Some(vec![hash.to_lowercase()]),
+        Value::Array(hashes) => Some(hashes.iter().filter_map(Value::as_str).map(str::to_lowercase).collect()),
+        _ => None,
+    }).collect()
+}
+
+fn eth_log(chain: &TribeChain, matched: &MatchedLog) -> Value {
+    let transaction_index = chain.get_block(matched.block_index)
+        .and_then(|block| block.transactions.iter().position(|tx| tx.hash == matched.tx_hash))
+        .unwrap_or(0);
+    json!({
+        "address": prefixed(&matched.log.address),
+        "topics": matched.log.topics.iter().map(|topic| topic_hash(topic)).collect::<Vec<_>>(),
+        "data": format!("0x{}", hex::encode(&matched.log.data)),
+        "blockNumber": quantity(matched.block_index as u128),
+        "blockHash": prefixed(&matched.block_hash),
+        "transactionHash": prefixed(&matched.tx_hash),
+        "transactionIndex": quantity(transaction_index as u128),
+        "logIndex": quantity(matched.log_index as u128),
+        "removed": false,
+    })
+}
+
+impl RpcServer {
+    /// Answer `ETH_METHODS` in Ethereum's encoding: hex quantities, 18-decimal balances and
+    /// `0x` addresses standing for this network's address with the same 20 bytes. `eth_call`
+    /// needs `engine`; anything else is answered from the chain.
+    pub fn handle_eth(&self, chain: &TribeChain, engine: Option<&mut ContractEngine>, request: RpcRequest) -> RpcResponse {
+        let id = request.id.clone();
+        let height = chain.blocks.len().saturating_sub(1) as u64;
+        match request.method.as_str() {
+            "eth_chainId" => RpcResponse::success(id, quantity(chain.chain_id as u128)),
+            "net_version" => RpcResponse::success(id, json!(chain.chain_id.to_string())),
+            "eth_blockNumber" => RpcResponse::success(id, quantity(height as u128)),
+            "eth_getBalance" => {
+                let address = match param(&request.params, 0, "address").and_then(Value::as_str).map(|address| account(address, chain.chain_id)) {
+                    Some(Ok(address)) => address,
+                    Some(Err(message)) => return RpcResponse::failure(id, INVALID_PARAMS, message),
+                    None => return RpcResponse::failure(id, INVALID_PARAMS, "Missing address"),
+                };
+                // Only the current state is kept
+                match block_number(param(&request.params, 1, "block"), height) {
+                    Ok(block) if block == height => {
+                        RpcResponse::success(id, quantity(chain.get_balance(&address) as u128 * WEI_PER_UNIT))
+                    }
+                    Ok(block) => RpcResponse::failure(id, INVALID_PARAMS, format!("No state for block {}; only the latest is kept", block)),
+                    Err(message) => RpcResponse::failure(id, INVALID_PARAMS, message),
+                }
+            }
+            "eth_call" => {
+                let Some(engine) = engine else {
+                    return RpcResponse::failure(id, METHOD_NOT_FOUND, "This node does not serve contract calls");
+                };
+                let call = param(&request.params, 0, "transaction").cloned().unwrap_or(Value::Null);
+                let (Some(to), Some(data)) = (call["to"].as_str(), call["data"].as_str().or(call["input"].as_str())) else {
+                    return RpcResponse::failure(id, INVALID_PARAMS, "Missing to or data");
+                };
+                let data = match hex::decode(unprefixed(data)) {
+                    Ok(data) if data.len() >= 4 => data,
+                    _ => return RpcResponse::failure(id, INVALID_PARAMS, "data must be a 4-byte method selector followed by the encoded arguments"),
+                };
+                let address = unprefixed(to);
+                let method = engine.get_contract_state(&address)
+                    .and_then(|contract| contract.metadata.abi.iter().find(|signature| selector(&signature.name) == data[..4]))
+                    .map(|signature| signature.name.clone());
+                let Some(method) = method else {
+                    return RpcResponse::failure(id, INVALID_PARAMS, format!("No method of {} has selector 0x{}", to, hex::encode(&data[..4])));
+                };
+                let caller = call["from"].as_str().unwrap_or_default().to_string();
+
+                match engine.query_contract(ContractCall::new(address, method, data[4..].to_vec(), caller)) {
+                    Ok(result) if result.success => RpcResponse::success(id, json!(format!("0x{}", hex::encode(&result.return_data)))),
+                    Ok(result) => RpcResponse::failure(id, EXECUTION_REVERTED, format!("execution reverted: {}", result.error.unwrap_or_default())),
+                    Err(e) => RpcResponse::from_error(id, &e),
+                }
+            }
+            "eth_getLogs" => {
+                let filter = param(&request.params, 0, "filter").and_then(Value::as_object).cloned().unwrap_or_else(Map::new);
+                let range = match filter.get("blockHash").and_then(Value::as_str) {
+                    Some(hash) => match chain.blocks.iter().find(|block| block.hash == unprefixed(hash)) {
+                        Some(block) => Ok((block.index, block.index)),
+                        None => return RpcResponse::success(id, json!([])),
+                    },
+                    None => block_number(filter.get("fromBlock"), height)
+                        .and_then(|from| Ok((from, block_number(filter.get("toBlock"), height)?))),
+                };
+                let (from_block, to_block) = match range {
+                    Ok(range) => range,
+                    Err(message) => return RpcResponse::failure(id, INVALID_PARAMS, message),
+                };
+                let addresses = match filter.get("address") {
+                    Some(Value::String(address)) => vec![unprefixed(address)],
+                    Some(Value::Array(addresses)) => addresses.iter().filter_map(Value::as_str).map(unprefixed).collect(),
+                    _ => Vec::new(),
+                };
+                // Topics are matched by hash, so the bloom only narrows by address
+                let topics = topic_filters(filter.get("topics"));
+                let log_filter = LogFilter { from_block: Some(from_block), to_block: Some(to_block), addresses, topics: Vec::new() };
+
+                let logs: Vec<Value> = chain.get_logs(&log_filter).iter()
+                    .filter(|matched| topics.iter().enumerate().all(|(position, wanted)| match wanted {
+                        Some(hashes) => matched.log.topics.get(position).is_some_and(|topic| hashes.contains(&topic_hash(topic))),
+                        None => true,
+                    }))
+                    .map(|matched| eth_log(chain, matched))
+                    .collect();
+                RpcResponse::success(id, json!(logs))
+            }
+            method => RpcResponse::failure(id, METHOD_NOT_FOUND, format!("Method not found: {}", method)),
+        }
+    }
+
+    /// Answer `eth_sendRawTransaction`, whose raw bytes are a signed TribeChain transaction
+    /// in binary form, as `tx sign --binary` writes. Ethereum-encoded transactions are
+    /// refused, since TribeChain signs differently.
+    pub fn handle_eth_mut(&self, chain: &mut TribeChain, request: RpcRequest) -> RpcResponse {
+        let id = request.id.clone();
+        if request.method != "eth_sendRawTransaction" {
+            return self.handle_eth(chain, None, request);
+        }
+        let raw = match param(&request.params, 0, "data").and_then(Value::as_str).map(|raw| hex::decode(unprefixed(raw))) {
+            Some(Ok(raw)) => raw,
+            _ => return RpcResponse::failure(id, INVALID_PARAMS, "Missing or invalid raw transaction hex"),
+        };
+        let transaction: Transaction = match bincode::deserialize(&raw) {
+            Ok(transaction) => transaction,
+            Err(_) => return RpcResponse::failure(
+                id, INVALID_PARAMS, "Not a TribeChain transaction; sign with `tribechain tx sign --binary`",
+            ),
+        };
+        let hash = transaction.hash.clone();
+        match chain.add_transaction(transaction) {
+            Ok(()) => RpcResponse::success(id, json!(prefixed(&hash))),
+            Err(e) => RpcResponse::from_error(id, &e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tribechain_core::{Block, Log, Receipt, TransactionType};
+    use tribechain_contracts::{ContractDeployment, ContractType};
+    use tribechain_contracts::contracts::{ContractMetadata, MethodSignature, Parameter, ParameterType};
+
+    fn request(method: &str, params: Value) -> RpcRequest {
+        RpcRequest { jsonrpc: "2.0".to_string(), id: json!(1), method: method.to_string(), params }
+    }
+
+    #[test]
+    fn test_balances_and_raw_transactions() {
+        let dir = std::env::temp_dir().join(format!("tribechain-eth-{}", uuid::Uuid::new_v4()));
+        let mut chain = TribeChain::new(dir.to_str().unwrap()).unwrap();
+        let alice = Address::from_key(hrp_for_chain(chain.chain_id), "alice-key");
+        chain.balances.insert(alice.to_string(), 1_500_000);
+        let rpc = RpcServer::new(8334).unwrap();
+
+        let chain_id = rpc.handle_eth(&chain, None, request("eth_chainId", json!([]))).result.unwrap();
+        assert_eq!(chain_id, json!(format!("{:#x}", chain.chain_id)));
+        // 1.5 TRIBE in 18-decimal units
+        let params = json!([format!("0x{}", alice.to_hex()), "latest"]);
+        let balance = rpc.handle_eth(&chain, None, request("eth_getBalance", params)).result.unwrap();
+        assert_eq!(balance, json!("0x14d1120d7b160000"));
+        let response = rpc.handle_eth(&chain, None, request("eth_getBalance", json!(["0xaaaa", "latest"])));
+        assert_eq!(response.error.unwrap().code, INVALID_PARAMS);
+        assert_eq!(rpc.handle_eth(&chain, None, request("eth_call", json!([{}]))).error.unwrap().code, METHOD_NOT_FOUND);
+
+        let bob = Address::new(hrp_for_chain(chain.chain_id), [0xbb; 20]).to_string();
+        let mut tx = Transaction::new(alice.to_string(), TransactionType::Transfer { to: bob, amount: 5 }, 1, 0)
+            .with_chain_id(chain.chain_id);
+        tx.sign("alice-key").unwrap();
+        let raw = format!("0x{}", hex::encode(bincode::serialize(&tx).unwrap()));
+        let hash = rpc.handle_eth_mut(&mut chain, request("eth_sendRawTransaction", json!([raw]))).result.unwrap();
+        assert_eq!(hash, json!(format!("0x{}", tx.hash)));
+        assert_eq!(chain.pending_transactions.len(), 1);
+        // An RLP-encoded Ethereum transaction is refused
+        let response = rpc.handle_eth_mut(&mut chain, request("eth_sendRawTransaction", json!(["0xf86c0a8502540be400"])));
+        assert_eq!(response.error.unwrap().code, INVALID_PARAMS);
+    }
+
+    #[test]
+    fn test_calls_and_logs() {
+        let dir = std::env::temp_dir().join(format!("tribechain-eth-{}", uuid::Uuid::new_v4()));
+        let mut chain = TribeChain::new(dir.to_str().unwrap()).unwrap();
+        let mut engine = ContractEngine::new();
+        let paused = MethodSignature::new(
+            "paused".to_string(),
+            vec![],
+            vec![Parameter::new("paused".to_string(), ParameterType::Bool)],
+        ).view();
+        let metadata = ContractMetadata { abi: vec![paused], ..ContractMetadata::default() };
+        let deployment = ContractDeployment::new(ContractType::Custom, vec![1], "alice".to_string()).with_metadata(metadata);
+        let contract = engine.deploy_contract(deployment).unwrap();
+        let rpc = RpcServer::new(8334).unwrap();
+
+        let data = format!("0x{}", hex::encode(selector("paused")));
+        let call = json!([{ "to": format!("0x{}", contract), "data": data }, "latest"]);
+        let response = rpc.handle_eth(&chain, Some(&mut engine), request("eth_call", call));
+        assert!(response.result.unwrap().as_str().unwrap().starts_with("0x"));
+        let call = json!([{ "to": format!("0x{}", contract), "data": "0xdeadbeef" }]);
+        let response = rpc.handle_eth(&chain, Some(&mut engine), request("eth_call", call));
+        assert_eq!(response.error.unwrap().code, INVALID_PARAMS);
+
+        let tx = Transaction::new("alice".to_string(), TransactionType::Transfer { to: "bob".to_string(), amount: 5 }, 1, 0);
+        let previous = chain.get_latest_block().unwrap().hash.clone();
+        let mut block = Block::new(1, previous, vec![tx.clone()], "miner".to_string());
+        block.hash = block.calculate_hash();
+        chain.blocks.push(block.clone());
+        let log = Log { address: contract.clone(), topics: vec!["transfer".to_string(), "bob".to_string()], data: vec![7] };
+        let receipt = Receipt { tx_hash: tx.hash.clone(), success: true, gas_used: 21000, logs: vec![log] };
+        chain.attach_receipts(1, vec![receipt]).unwrap();
+
+        let filter = json!([{ "fromBlock": "0x1", "address": format!("0x{}", contract), "topics": [topic_hash("transfer")] }]);
+        let logs = rpc.handle_eth(&chain, None, request("eth_getLogs", filter)).result.unwrap();
+        assert_eq!(logs[0]["transactionHash"], json!(format!("0x{}", tx.hash)));
+        assert_eq!(logs[0]["blockNumber"], json!("0x1"));
+        assert_eq!(logs[0]["topics"][1], json!(topic_hash("bob")));
+        assert_eq!(logs[0]["data"], json!("0x07"));
+        let filter = json!([{ "blockHash": format!("0x{}", block.hash), "topics": [null, [topic_hash("carol")]] }]);
+        assert_eq!(rpc.handle_eth(&chain, None, request("eth_getLogs", filter)).result.unwrap(), json!([]));
+    }
+}
@@ -0,0 +1,290 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tribechain_core::{TribeResult, TribeError};
+
+/// Largest frame a peer may send; anything bigger is treated as a protocol violation
+pub const MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
+
+/// One unit on the wire: a 4-byte big-endian length followed by a bincode `Frame`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Frame {
+    pub request_id: u64, // 0 for one-way messages
+    pub is_response: bool,
+    pub payload: Vec<u8>,
+}
+
+impl Frame {
+    pub fn one_way(payload: Vec<u8>) -> Self {
+        Self { request_id: 0, is_response: false, payload }
+    }
+
+    pub fn request(request_id: u64, payload: Vec<u8>) -> Self {
+        Self { request_id, is_response: false, payload }
+    }
+
+    pub fn response(request_id: u64, payload: Vec<u8>) -> Self {
+        Self { request_id, is_response: true, payload }
+    }
+
+    pub fn encode(&self) -> TribeResult<Vec<u8>> {
+        let body = bincode::serialize(self)
+            .map_err(|e| TribeError::Network(format!("Failed to encode frame: {}", e)))?;
+        if body.len() > MAX_FRAME_SIZE {
+            return Err(TribeError::Network(format!("Frame of {} bytes exceeds limit", body.len())));
+        }
+
+        let mut bytes = Vec::with_capacity(4 + body.len());
+        bytes.extend_from_slice(&(body.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(&body);
+        Ok(bytes)
+    }
+}
+
+pub async fn write_frame<W: AsyncWrite + Unpin>(writer: &mut W, frame: &Frame) -> TribeResult<()> {
+    writer.write_all(&frame.encode()?).await
+        .map_err(|e| TribeError::Network(format!("Failed to write frame: {}", e)))?;
+    writer.flush().await
+        .map_err(|e| TribeError::Network(format!("Failed to flush frame: {}", e)))
+}
+
+/// Read the next frame; `None` when the peer closed the connection between frames
+pub async fn read_frame<R: AsyncRead + Unpin>(reader: &mut R) -> TribeResult<Option<Frame>> {
+    let mut length = [0u8; 4];
+    match reader.read_exact(&mut length).await {
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(TribeError::Network(format!("Failed to read frame length: {}", e))),
+    }
+
+    let length = u32::from_be_bytes(length) as usize;
+    if length > MAX_FRAME_SIZE {
+        return Err(TribeError::Network(format!("Peer sent oversized frame of {} bytes", length)));
+    }
+
+    let mut body = vec![0u8; length];
+    reader.read_exact(&mut body).await
+        .map_err(|e| TribeError::Network(format!("Failed to read frame body: {}", e)))?;
+    bincode::deserialize(&body)
+        .map(Some)
+        .map_err(|e| TribeError::Network(format!("Malformed frame: {}", e)))
+}
+
+/// Bounded outbound queue drained by a dedicated writer task, so producers never
+/// wait on a slow socket. A full queue is reported to the caller instead of blocking.
+#[derive(Debug)]
+pub struct PeerQueue {
+    sender: mpsc::Sender<Frame>,
+    capacity: usize,
+    writer: JoinHandle<()>,
+}
+
+impl PeerQueue {
+    pub fn spawn<W>(mut writer: W, capacity: usize, write_timeout: Duration) -> Self
+    where
+        W: AsyncWrite + Unpin + Send + 'static,
+    {
+        let capacity = capacity.max(1);
+        let (sender, mut receiver) = mpsc::channel::<Frame>(capacity);
+        let writer = tokio::spawn(async move {
+            while let Some(frame) = receiver.recv().await {
+                match tokio::time::timeout(write_timeout, write_frame(&mut writer, &frame)).await {
+                    Ok(Ok(())) => {}
+                    Ok(Err(_)) | Err(_) => break, // Dead or stalled peer; dropping the receiver fails later sends
+                }
+            }
+        });
+
+        Self { sender, capacity, writer }
+    }
+
+    pub fn try_send(&self, frame: Frame) -> TribeResult<()> {
+        self.sender.try_send(frame).map_err(|e| match e {
+            mpsc::error::TrySendError::Full(_) => TribeError::Network("Peer send queue is full".to_string()),
+            mpsc::error::TrySendError::Closed(_) => TribeError::Network("Peer connection is closed".to_string()),
+        })
+    }
+
+    pub fn queued(&self) -> usize {
+        self.capacity - self.sender.capacity()
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.sender.is_closed()
+    }
+}
+
+impl Drop for PeerQueue {
+    fn drop(&mut self) {
+        self.writer.abort();
+    }
+}
+
+/// Requests awaiting a response frame, keyed by request id
+#[derive(Debug, Default)]
+pub struct PendingRequests {
+    next_id: AtomicU64,
+    waiting: Mutex<HashMap<u64, oneshot::Sender<Vec<u8>>>>,
+}
+
+impl PendingRequests {
+    pub fn register(&self) -> (u64, oneshot::Receiver<Vec<u8>>) {
+        let request_id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1; // 0 is reserved for one-way
+        let (sender, receiver) = oneshot::channel();
+        self.waiting.lock().unwrap().insert(request_id, sender);
+        (request_id, receiver)
+    }
+
+    /// Hand a response frame to its waiting request; false if nobody is waiting any more
+    pub fn complete(&self, frame: Frame) -> bool {
+        let sender = self.waiting.lock().unwrap().remove(&frame.request_id);
+        match sender {
+            Some(sender) => sender.send(frame.payload).is_ok(),
+            None => false,
+        }
+    }
+
+    pub fn cancel(&self, request_id: u64) {
+        self.waiting.lock().unwrap().remove(&request_id);
+    }
+
+    pub fn len(&self) -> usize {
+        self.waiting.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Outbound side of a framed peer connection
+#[derive(Debug)]
+pub struct FramedPeer {
+    pub queue: PeerQueue,
+    pub pending: Arc<PendingRequests>,
+    pub request_timeout: Duration,
+}
+
+impl FramedPeer {
+    pub fn new(queue: PeerQueue, request_timeout: Duration) -> Self {
+        Self {
+            queue,
+            pending: Arc::new(PendingRequests::default()),
+            request_timeout,
+        }
+    }
+
+    /// Split `stream` into a queued writer and a reader task. Responses are matched to
+    /// pending requests; every other frame is forwarded on the returned channel.
+    pub fn connect<S>(stream: S, capacity: usize, request_timeout: Duration) -> (Self, mpsc::Receiver<Frame>)
+    where
+        S: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        let (mut reader, writer) = tokio::io::split(stream);
+        let peer = Self::new(PeerQueue::spawn(writer, capacity, request_timeout), request_timeout);
+        let pending = Arc::clone(&peer.pending);
+        let (inbound_tx, inbound) = mpsc::channel(capacity.max(1));
+
+        tokio::spawn(async move {
+            while let Ok(Some(frame)) = read_frame(&mut reader).await {
+                if frame.is_response {
+                    pending.complete(frame);
+                } else if inbound_tx.send(frame).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        (peer, inbound)
+    }
+
+    pub fn send(&self, payload: Vec<u8>) -> TribeResult<()> {
+        self.queue.try_send(Frame::one_way(payload))
+    }
+
+    pub fn reply(&self, request_id: u64, payload: Vec<u8>) -> TribeResult<()> {
+        self.queue.try_send(Frame::response(request_id, payload))
+    }
+
+    /// Send a request and wait for its response; times out and cancels rather than
+    /// waiting on a slow peer forever
+    pub async fn request(&self, payload: Vec<u8>) -> TribeResult<Vec<u8>> {
+        let (request_id, response) = self.pending.register();
+        if let Err(e) = self.queue.try_send(Frame::request(request_id, payload)) {
+            self.pending.cancel(request_id);
+            return Err(e);
+        }
+
+        match tokio::time::timeout(self.request_timeout, response).await {
+            Ok(Ok(payload)) => Ok(payload),
+            Ok(Err(_)) => Err(TribeError::Network("Request cancelled".to_string())),
+            Err(_) => {
+                self.pending.cancel(request_id);
+                Err(TribeError::Network(format!("Request {} timed out", request_id)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_frame_round_trip_and_limits() {
+        let (mut client, mut server) = tokio::io::duplex(1024);
+        let frame = Frame::request(7, b"sync".to_vec());
+        write_frame(&mut client, &frame).await.unwrap();
+        assert_eq!(read_frame(&mut server).await.unwrap(), Some(frame));
+
+        client.write_all(&(MAX_FRAME_SIZE as u32 + 1).to_be_bytes()).await.unwrap();
+        assert!(read_frame(&mut server).await.is_err());
+
+        drop(client);
+        assert_eq!(read_frame(&mut server).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_backpressure_and_request_timeout() {
+        // Nobody reads the other end, so the writer stalls once the pipe fills
+        let (client, _server) = tokio::io::duplex(64);
+        let peer = FramedPeer::new(
+            PeerQueue::spawn(client, 2, Duration::from_secs(5)),
+            Duration::from_millis(50),
+        );
+
+        let mut rejected = false;
+        for _ in 0..10 {
+            if peer.send(vec![0u8; 128]).is_err() {
+                rejected = true;
+                break;
+            }
+            tokio::task::yield_now().await;
+        }
+        assert!(rejected, "full queue should reject instead of blocking");
+
+        assert!(peer.request(b"ping".to_vec()).await.is_err());
+        assert!(peer.pending.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_request_response() {
+        let (client, server) = tokio::io::duplex(4096);
+        let (peer, _) = FramedPeer::connect(client, 8, Duration::from_secs(1));
+        let (remote, mut inbound) = FramedPeer::connect(server, 8, Duration::from_secs(1));
+
+        tokio::spawn(async move {
+            while let Some(frame) = inbound.recv().await {
+                remote.reply(frame.request_id, frame.payload).unwrap();
+            }
+        });
+
+        assert_eq!(peer.request(b"ping".to_vec()).await.unwrap(), b"ping".to_vec());
+        assert!(peer.pending.is_empty());
+    }
+}
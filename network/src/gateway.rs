@@ -0,0 +1,326 @@
+use ai3_lib::esp_compat::protocol::operation_code;
+use ai3_lib::esp_compat::ESPTensorUtils;
+use ai3_lib::{ESPCompatibility, ESPDeviceType, MiningResult, MiningTask, MiningTaskKind, TaskDistributor, Tensor, TensorShape};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tribechain_core::TelemetryStore;
+
+/// Paths ESP firmware calls, relative to the node's RPC address
+pub const TASK_PATH: &str = "/api/mining/task";
+pub const RESULT_PATH: &str = "/api/mining/result";
+pub const SUBMIT_PATH: &str = "/api/mining/submit"; // Older firmware's name for `RESULT_PATH`
+pub const CONFIG_PATH: &str = "/api/mining/config";
+pub const TELEMETRY_PATH: &str = "/api/device/telemetry";
+
+/// Prefix of every path the gateway answers
+pub const GATEWAY_PREFIX: &str = "/api/";
+
+/// HTTP request as the gateway sees it; header names are lowercase
+#[derive(Debug, Clone, Default)]
+pub struct GatewayRequest {
+    pub method: String,
+    pub path: String,
+    pub query: HashMap<String, String>,
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+impl GatewayRequest {
+    /// Split `target`, e.g. `/api/mining/config?device=ESP32`, into path and query
+    pub fn new(method: &str, target: &str, headers: HashMap<String, String>, body: Vec<u8>) -> Self {
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let query = query.split('&')
+            .filter_map(|pair| pair.split_once('=').or(Some((pair, ""))))
+            .filter(|(name, _)| !name.is_empty())
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        Self { method: method.to_string(), path: path.to_string(), query, headers, body }
+    }
+
+    /// Header `header`, else query parameter `param`
+    fn lookup(&self, header: &str, param: &str) -> Option<&str> {
+        self.headers.get(header).or_else(|| self.query.get(param)).map(String::as_str).filter(|value| !value.is_empty())
+    }
+
+    fn miner_id(&self) -> Option<&str> {
+        self.lookup("x-miner-id", "miner_id")
+    }
+
+    fn device_type(&self) -> Result<Option<ESPDeviceType>, String> {
+        self.lookup("x-device-type", "device")
+            .map(|device| serde_json::from_value(json!(device.to_uppercase())).map_err(|_| format!("Unknown device type {}", device)))
+            .transpose()
+    }
+}
+
+/// Status line and JSON body to answer with; a null body is sent empty
+#[derive(Debug, Clone, PartialEq)]
+pub struct GatewayResponse {
+    pub status: &'static str,
+    pub body: Value,
+}
+
+impl GatewayResponse {
+    fn ok(body: Value) -> Self {
+        Self { status: "200 OK", body }
+    }
+
+    fn error(status: &'static str, message: impl Into<String>) -> Self {
+        Self { status, body: json!({ "error": message.into() }) }
+    }
+}
+
+/// Result body a device posts to `RESULT_PATH`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ResultSubmission {
+    task_id: String,
+    #[serde(default)]
+    miner_id: Option<String>, // Else the `X-Miner-ID` header
+    nonce: u64,
+    hash: String,
+    #[serde(default)]
+    output: Vec<f32>,
+    #[serde(default)]
+    output_shape: Option<Vec<usize>>, // Else the task's expected shape, else a vector
+    #[serde(default)]
+    computation_time_ms: Option<u64>,
+    #[serde(default)]
+    computation_time_us: Option<u64>, // What older firmware reports
+}
+
+/// Telemetry body a device posts to `TELEMETRY_PATH`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TelemetryReport {
+    #[serde(default, alias = "device_id")]
+    device_id: Option<String>, // Else the `X-Miner-ID` header
+    metrics: HashMap<String, f64>,
+    #[serde(default)]
+    timestamp: Option<u64>,
+}
+
+/// REST endpoints the generated ESP firmware polls: tasks come from the node's
+/// `TaskDistributor`, telemetry goes to the device registry behind `getDevices`
+#[derive(Debug, Clone)]
+pub struct MiningGateway {
+    pub distributor: Arc<Mutex<TaskDistributor>>,
+    pub telemetry: Option<Arc<Mutex<TelemetryStore>>>, // Telemetry posts are refused without it
+}
+
+impl MiningGateway {
+    pub fn new(distributor: Arc<Mutex<TaskDistributor>>, telemetry: Option<Arc<Mutex<TelemetryStore>>>) -> Self {
+        Self { distributor, telemetry }
+    }
+
+    /// Answer a request to a path under `GATEWAY_PREFIX`
+    pub async fn handle(&self, request: &GatewayRequest) -> GatewayResponse {
+        match (request.method.as_str(), request.path.as_str()) {
+            ("GET", TASK_PATH) => self.next_task(request).await,
+            ("POST", RESULT_PATH | SUBMIT_PATH) => self.submit_result(request).await,
+            ("GET", CONFIG_PATH) => Self::config(request),
+            ("POST", TELEMETRY_PATH) => self.record_telemetry(request).await,
+            (_, TASK_PATH | RESULT_PATH | SUBMIT_PATH | CONFIG_PATH | TELEMETRY_PATH) => {
+                GatewayResponse::error("405 Method Not Allowed", format!("{} is not allowed on {}", request.method, request.path))
+            }
+            _ => GatewayResponse::error("404 Not Found", format!("No endpoint at {}", request.path)),
+        }
+    }
+
+    /// Assign the oldest pending task the device can run, or answer 204 when there is none
+    async fn next_task(&self, request: &GatewayRequest) -> GatewayResponse {
+        let Some(miner_id) = request.miner_id() else {
+            return GatewayResponse::error("400 Bad Request", "Missing X-Miner-ID header");
+        };
+        let device_type = match request.device_type() {
+            Ok(device_type) => device_type,
+            Err(e) => return GatewayResponse::error("400 Bad Request", e),
+        };
+
+        let mut distributor = self.distributor.lock().await;
+        let mut candidates: Vec<&MiningTask> = distributor.pending_tasks.values()
+            .filter(|task| Self::servable(task, device_type.as_ref()))
+            .collect();
+        candidates.sort_by_key(|task| task.created_at);
+        let Some(task_id) = candidates.first().map(|task| task.id.clone()) else {
+            return GatewayResponse { status: "204 No Content", body: Value::Null };
+        };
+        match distributor.assign(&task_id, miner_id) {
+            Ok(task) => GatewayResponse::ok(Self::task_json(&task)),
+            Err(e) => GatewayResponse::error("500 Internal Server Error", e.to_string()),
+        }
+    }
+
+    /// Whether a device can be handed `task` over HTTP: single-miner, unexpired, with its
+    /// inputs inline and, if the device said what it is, fitting in its memory
+    fn servable(task: &MiningTask, device_type: Option<&ESPDeviceType>) -> bool {
+        task.kind == MiningTaskKind::Single
+            && !task.is_expired()
+            && task.input_refs.is_empty()
+            && task.input_shards.is_empty()
+            && operation_code(&task.operation_type).is_ok()
+            && device_type.is_none_or(|device| ESPTensorUtils::can_run_on_esp(&task.input_tensors, &task.operation_type, device))
+    }
+
+    /// The task as firmware reads it: `operation` is the wire code, `dimensions` and
+    /// `inputData` describe the first input, `inputs` all of them
+    fn task_json(task: &MiningTask) -> Value {
+        let inputs: Vec<Value> = task.input_tensors.iter()
+            .map(|tensor| json!({
+                "shape": tensor.shape.dimensions,
+                "data": tensor.data.as_f32_vec().unwrap_or_default(),
+            }))
+            .collect();
+        let first = task.input_tensors.first();
+        let mut dimensions = first.map(|tensor| tensor.shape.dimensions.clone()).unwrap_or_default();
+        dimensions.resize(dimensions.len().max(2), 1);
+        json!({
+            "id": task.id,
+            "operation": operation_code(&task.operation_type).unwrap_or_default(),
+            "operationName": task.operation_type,
+            "difficulty": task.difficulty,
+            "reward": task.reward,
+            "dimensions": dimensions,
+            "inputData": first.and_then(|tensor| tensor.data.as_f32_vec().ok()).unwrap_or_default(),
+            "inputs": inputs,
+            "nonceStart": task.nonce_range.0,
+            "nonceEnd": task.nonce_range.1,
+            "maxComputationTime": task.max_computation_time,
+        })
+    }
+
+    /// Check and record a result for a task assigned to the submitting miner
+    async fn submit_result(&self, request: &GatewayRequest) -> GatewayResponse {
+        let submission: ResultSubmission = match serde_json::from_slice(&request.body) {
+            Ok(submission) => submission,
+            Err(e) => return GatewayResponse::error("400 Bad Request", format!("Invalid result: {}", e)),
+        };
+        let Some(miner_id) = submission.miner_id.clone().or_else(|| request.miner_id().map(str::to_string)) else {
+            return GatewayResponse::error("400 Bad Request", "Missing minerId");
+        };
+
+        let mut distributor = self.distributor.lock().await;
+        let task = match distributor.active_tasks.get(&submission.task_id) {
+            Some((_, assignee)) if *assignee != miner_id => {
+                return GatewayResponse::error("403 Forbidden", "Task is assigned to another miner");
+            }
+            Some((task, _)) => task,
+            None => return GatewayResponse::error("404 Not Found", "Task not found in active tasks"),
+        };
+        let shape = submission.output_shape.clone()
+            .or_else(|| task.expected_output_shape.clone())
+            .unwrap_or_else(|| vec![submission.output.len()]);
+        let output = match Tensor::from_vec(submission.output, TensorShape::new(shape)) {
+            Ok(output) => output,
+            Err(e) => return GatewayResponse::error("400 Bad Request", e.to_string()),
+        };
+        let computation_time = submission.computation_time_ms
+            .or(submission.computation_time_us.map(|us| us / 1000))
+            .unwrap_or_default();
+        let result = MiningResult::new(submission.task_id.clone(), miner_id, submission.nonce, submission.hash, output, computation_time);
+
+        if let Err(e) = distributor.submit_result(result) {
+            return GatewayResponse::error("400 Bad Request", e.to_string());
+        }
+        let accepted = distributor.completed_tasks.get(&submission.task_id).is_some_and(|result| result.is_valid);
+        GatewayResponse::ok(json!({ "taskId": submission.task_id, "accepted": accepted }))
+    }
+
+    /// Recommended mining settings for the device type given by `?device=`, ESP32 by default
+    fn config(request: &GatewayRequest) -> GatewayResponse {
+        match request.device_type() {
+            Ok(device_type) => GatewayResponse::ok(json!(ESPCompatibility::get_recommended_config(device_type.unwrap_or(ESPDeviceType::ESP32)))),
+            Err(e) => GatewayResponse::error("400 Bad Request", e),
+        }
+    }
+
+    /// Record a device's metrics, as `pushTelemetry` does
+    async fn record_telemetry(&self, request: &GatewayRequest) -> GatewayResponse {
+        let Some(telemetry) = &self.telemetry else {
+            return GatewayResponse::error("503 Service Unavailable", "This node does not record telemetry");
+        };
+        let report: TelemetryReport = match serde_json::from_slice(&request.body) {
+            Ok(report) => report,
+            Err(e) => return GatewayResponse::error("400 Bad Request", format!("Invalid telemetry: {}", e)),
+        };
+        let Some(device_id) = report.device_id.clone().or_else(|| request.miner_id().map(str::to_string)) else {
+            return GatewayResponse::error("400 Bad Request", "Missing deviceId");
+        };
+        let timestamp = report.timestamp.unwrap_or_else(|| chrono::Utc::now().timestamp() as u64);
+        let metrics: Vec<(String, f64)> = report.metrics.into_iter().collect();
+        match telemetry.lock().await.record(&device_id, timestamp, &metrics) {
+            Ok(alerts) => GatewayResponse::ok(json!({ "alerts": alerts })),
+            Err(e) => GatewayResponse::error("400 Bad Request", e.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: &str, target: &str, miner: Option<&str>, body: Value) -> GatewayRequest {
+        let headers = miner.map(|miner| ("x-miner-id".to_string(), miner.to_string())).into_iter().collect();
+        let body = if body.is_null() { Vec::new() } else { serde_json::to_vec(&body).unwrap() };
+        GatewayRequest::new(method, target, headers, body)
+    }
+
+    fn gateway() -> MiningGateway {
+        let mut distributor = TaskDistributor::new();
+        let task = MiningTask::new("relu".to_string(), vec![Tensor::vector(vec![-1.0, 2.0])], 1, 50, 300, "requester".to_string());
+        distributor.add_task(task);
+        MiningGateway::new(Arc::new(Mutex::new(distributor)), Some(Arc::new(Mutex::new(TelemetryStore::new()))))
+    }
+
+    #[tokio::test]
+    async fn test_task_and_result() {
+        let gateway = gateway();
+        assert_eq!(gateway.handle(&request("GET", TASK_PATH, None, Value::Null)).await.status, "400 Bad Request");
+
+        let response = gateway.handle(&request("GET", TASK_PATH, Some("esp-1"), Value::Null)).await;
+        assert_eq!(response.status, "200 OK");
+        assert_eq!(response.body["operation"], 2);
+        assert_eq!(response.body["dimensions"], json!([2, 1]));
+        assert_eq!(response.body["inputData"], json!([-1.0, 2.0]));
+        let task_id = response.body["id"].as_str().unwrap().to_string();
+
+        // Only one task was queued
+        let response = gateway.handle(&request("GET", TASK_PATH, Some("esp-2"), Value::Null)).await;
+        assert_eq!(response.status, "204 No Content");
+
+        let task = gateway.distributor.lock().await.active_tasks[&task_id].0.clone();
+        let nonce = (0..).find(|nonce| task.meets_difficulty(&task.calculate_hash(*nonce))).unwrap();
+        let result = json!({ "taskId": task_id, "nonce": nonce, "hash": task.calculate_hash(nonce), "output": [0.0, 2.0], "computationTimeUs": 4000 });
+
+        let response = gateway.handle(&request("POST", RESULT_PATH, Some("esp-2"), result.clone())).await;
+        assert_eq!(response.status, "403 Forbidden");
+        let response = gateway.handle(&request("POST", SUBMIT_PATH, Some("esp-1"), result)).await;
+        assert_eq!(response.status, "200 OK");
+        assert_eq!(response.body["accepted"], true);
+        assert_eq!(gateway.distributor.lock().await.completed_tasks[&task_id].computation_time, 4);
+    }
+
+    #[tokio::test]
+    async fn test_config_and_telemetry() {
+        let gateway = gateway();
+        let response = gateway.handle(&request("GET", "/api/mining/config?device=esp8266", None, Value::Null)).await;
+        assert_eq!(response.status, "200 OK");
+        assert_eq!(response.body["device_type"], "ESP8266");
+        assert!(response.body["power_save_mode"].as_bool().unwrap());
+        let response = gateway.handle(&request("GET", "/api/mining/config?device=pdp11", None, Value::Null)).await;
+        assert_eq!(response.status, "400 Bad Request");
+
+        let report = json!({ "metrics": { "temperature_c": 41.5 }, "timestamp": 1_000 });
+        let response = gateway.handle(&request("POST", TELEMETRY_PATH, Some("esp-1"), report)).await;
+        assert_eq!(response.status, "200 OK");
+        let telemetry = gateway.telemetry.as_ref().unwrap().lock().await;
+        assert_eq!(telemetry.devices["esp-1"].last_seen, 1_000);
+        drop(telemetry);
+
+        assert_eq!(gateway.handle(&request("GET", TELEMETRY_PATH, None, Value::Null)).await.status, "405 Method Not Allowed");
+        assert_eq!(gateway.handle(&request("GET", "/api/unknown", None, Value::Null)).await.status, "404 Not Found");
+    }
+}
@@ -0,0 +1,734 @@
+use async_graphql::connection::{Connection, CursorType, Edge};
+use async_graphql::{Context, EmptyMutation, EmptySubscription, Json, Object, Schema, SimpleObject};
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tribechain_core::{check_address_input, Block, Receipt, TensorTask, Transaction, TribeChain};
+use tribechain_contracts::{ContractEngine, LiquidityPool, StakeInfo, StakingContract, TokenContract};
+
+/// Items a page holds when `first` isn't given, and the most it may ask for
+pub const DEFAULT_PAGE_SIZE: usize = 20;
+pub const MAX_PAGE_SIZE: usize = 100;
+
+/// Deepest nesting a query may use, so one request can't walk the whole chain
+const MAX_QUERY_DEPTH: usize = 12;
+
+/// What the resolvers read from; each takes the locks it needs for as long as it needs them
+#[derive(Clone)]
+struct Sources {
+    chain: Arc<RwLock<TribeChain>>,
+    contracts: Option<Arc<RwLock<ContractEngine>>>,
+}
+
+impl Sources {
+    fn contracts(&self) -> async_graphql::Result<&Arc<RwLock<ContractEngine>>> {
+        self.contracts.as_ref().ok_or_else(|| "This node does not serve contract state".into())
+    }
+}
+
+/// Schema served at `/graphql`
+#[derive(Clone)]
+pub struct GraphqlEndpoint {
+    pub schema: Schema<QueryRoot, EmptyMutation, EmptySubscription>,
+}
+
+impl GraphqlEndpoint {
+    /// Token, staking and liquidity queries fail unless `contracts` is given
+    pub fn new(chain: Arc<RwLock<TribeChain>>, contracts: Option<Arc<RwLock<ContractEngine>>>) -> Self {
+        let schema = Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+            .data(Sources { chain, contracts })
+            .limit_depth(MAX_QUERY_DEPTH)
+            .finish();
+        Self { schema }
+    }
+
+    /// Answer a GraphQL-over-HTTP body: one `{"query", "variables"}` request or a batch of them
+    pub async fn execute(&self, body: &[u8]) -> serde_json::Value {
+        match serde_json::from_slice::<async_graphql::BatchRequest>(body) {
+            Ok(batch) => serde_json::to_value(self.schema.execute_batch(batch).await).unwrap_or_default(),
+            Err(e) => serde_json::json!({ "errors": [{ "message": format!("Invalid request: {}", e) }] }),
+        }
+    }
+}
+
+impl fmt::Debug for GraphqlEndpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("GraphqlEndpoint")
+    }
+}
+
+/// Cursor pagination over `total` items: `fetch` is asked for the range of positions
+/// on the page, and each item's cursor is its position
+fn page<T, F>(total: usize, first: Option<i32>, after: Option<String>, fetch: F) -> async_graphql::Result<Connection<usize, T>>
+where
+    T: async_graphql::OutputType,
+    F: FnOnce(Range<usize>) -> Vec<T>,
+{
+    let start = match after {
+        Some(cursor) => usize::decode_cursor(&cursor).map_err(|_| "Invalid cursor")? + 1,
+        None => 0,
+    };
+    let size = first.map_or(DEFAULT_PAGE_SIZE, |first| (first.max(0) as usize).min(MAX_PAGE_SIZE));
+    let end = start.saturating_add(size).min(total);
+    let start = start.min(end);
+
+    let mut connection = Connection::new(start > 0, end < total);
+    connection.edges.extend(fetch(start..end).into_iter().enumerate().map(|(offset, node)| Edge::new(start + offset, node)));
+    Ok(connection)
+}
+
+/// Block and position of a mined transaction, found through the sender's address index
+fn locate(chain: &TribeChain, transaction: &Transaction) -> Option<u64> {
+    chain.address_index.get(&transaction.from).iter()
+        .find(|tx_ref| tx_ref.tx_hash == transaction.hash)
+        .map(|tx_ref| tx_ref.block_index)
+}
+
+fn checked_address(chain: &TribeChain, address: &str) -> async_graphql::Result<()> {
+    check_address_input(address, chain.chain_id).map_err(|e| e.to_string().into())
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    async fn chain(&self, ctx: &Context<'_>) -> async_graphql::Result<ChainInfo> {
+        let chain = ctx.data::<Sources>()?.chain.read().await;
+        Ok(ChainInfo {
+            chain_id: chain.chain_id,
+            height: chain.blocks.len().saturating_sub(1) as u64,
+            best_hash: chain.get_latest_block().map(|block| block.hash.clone()).unwrap_or_default(),
+            finalized_height: chain.finalized_height(),
+            difficulty: chain.difficulty,
+            pending_transactions: chain.pending_transactions.len() as u64,
+        })
+    }
+
+    /// Block by height or hash
+    async fn block(&self, ctx: &Context<'_>, height: Option<u64>, hash: Option<String>) -> async_graphql::Result<Option<BlockObject>> {
+        let chain = ctx.data::<Sources>()?.chain.read().await;
+        let block = match (height, hash) {
+            (Some(height), _) => chain.get_block(height),
+            (None, Some(hash)) => chain.blocks.iter().find(|block| block.hash == hash),
+            (None, None) => return Err("Give a height or a hash".into()),
+        };
+        Ok(block.cloned().map(BlockObject))
+    }
+
+    /// Blocks, newest first
+    async fn blocks(&self, ctx: &Context<'_>, first: Option<i32>, after: Option<String>) -> async_graphql::Result<Connection<usize, BlockObject>> {
+        let chain = ctx.data::<Sources>()?.chain.read().await;
+        let total = chain.blocks.len();
+        page(total, first, after, |range| range.map(|position| BlockObject(chain.blocks[total - 1 - position].clone())).collect())
+    }
+
+    /// Mined or pending transaction by hash
+    async fn transaction(&self, ctx: &Context<'_>, hash: String) -> async_graphql::Result<Option<TransactionObject>> {
+        let chain = ctx.data::<Sources>()?.chain.read().await;
+        let mined = chain.blocks.iter().find_map(|block| {
+            block.transactions.iter().find(|tx| tx.hash == hash).map(|tx| TransactionObject::mined(tx, block.index))
+        });
+        let pending = || chain.pending_transactions.iter().find(|tx| tx.hash == hash).map(TransactionObject::pending);
+        Ok(mined.or_else(pending))
+    }
+
+    /// Mempool transactions in the order miners take them
+    async fn pending_transactions(&self, ctx: &Context<'_>, first: Option<i32>, after: Option<String>) -> async_graphql::Result<Connection<usize, TransactionObject>> {
+        let chain = ctx.data::<Sources>()?.chain.read().await;
+        page(chain.pending_transactions.len(), first, after, |range| {
+            chain.pending_transactions[range].iter().map(TransactionObject::pending).collect()
+        })
+    }
+
+    async fn account(&self, ctx: &Context<'_>, address: String) -> async_graphql::Result<Account> {
+        checked_address(&*ctx.data::<Sources>()?.chain.read().await, &address)?;
+        Ok(Account { address })
+    }
+
+    /// Tokens, by ID
+    async fn tokens(&self, ctx: &Context<'_>, first: Option<i32>, after: Option<String>) -> async_graphql::Result<Connection<usize, TokenObject>> {
+        let engine = ctx.data::<Sources>()?.contracts()?.read().await;
+        let mut tokens: Vec<&TokenContract> = engine.token_contracts.values().collect();
+        tokens.sort_by(|a, b| a.token_info.id.cmp(&b.token_info.id));
+        page(tokens.len(), first, after, |range| tokens[range].iter().map(|token| TokenObject((*token).clone())).collect())
+    }
+
+    async fn token(&self, ctx: &Context<'_>, id: String) -> async_graphql::Result<Option<TokenObject>> {
+        let engine = ctx.data::<Sources>()?.contracts()?.read().await;
+        Ok(engine.token_contracts.get(&id).cloned().map(TokenObject))
+    }
+
+    /// Staking contracts, by ID
+    async fn staking_contracts(&self, ctx: &Context<'_>, first: Option<i32>, after: Option<String>) -> async_graphql::Result<Connection<usize, StakingObject>> {
+        let engine = ctx.data::<Sources>()?.contracts()?.read().await;
+        let mut contracts: Vec<&StakingContract> = engine.staking_contracts.values().collect();
+        contracts.sort_by(|a, b| a.id.cmp(&b.id));
+        page(contracts.len(), first, after, |range| contracts[range].iter().map(|contract| StakingObject((*contract).clone())).collect())
+    }
+
+    /// Liquidity pools, by ID
+    async fn liquidity_pools(&self, ctx: &Context<'_>, first: Option<i32>, after: Option<String>) -> async_graphql::Result<Connection<usize, PoolObject>> {
+        let engine = ctx.data::<Sources>()?.contracts()?.read().await;
+        let mut pools: Vec<&LiquidityPool> = engine.liquidity_pools.values().collect();
+        pools.sort_by(|a, b| a.id.cmp(&b.id));
+        page(pools.len(), first, after, |range| pools[range].iter().map(|pool| PoolObject((*pool).clone())).collect())
+    }
+
+    async fn liquidity_pool(&self, ctx: &Context<'_>, id: String) -> async_graphql::Result<Option<PoolObject>> {
+        let engine = ctx.data::<Sources>()?.contracts()?.read().await;
+        Ok(engine.liquidity_pools.get(&id).cloned().map(PoolObject))
+    }
+
+    /// AI3 tensor tasks, newest first; `pending` keeps only the ones not completed yet
+    async fn tensor_tasks(&self, ctx: &Context<'_>, pending: Option<bool>, first: Option<i32>, after: Option<String>) -> async_graphql::Result<Connection<usize, TensorTaskObject>> {
+        let chain = ctx.data::<Sources>()?.chain.read().await;
+        let tasks: Vec<&TensorTask> = chain.tensor_tasks.iter().rev()
+            .filter(|task| !pending.unwrap_or(false) || !task.completed)
+            .collect();
+        page(tasks.len(), first, after, |range| tasks[range].iter().map(|task| TensorTaskObject((*task).clone())).collect())
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct ChainInfo {
+    chain_id: u64,
+    height: u64,
+    best_hash: String,
+    finalized_height: u64,
+    difficulty: u64,
+    pending_transactions: u64,
+}
+
+pub struct BlockObject(Block);
+
+#[Object(name = "Block")]
+impl BlockObject {
+    async fn height(&self) -> u64 {
+        self.0.index
+    }
+
+    async fn hash(&self) -> &str {
+        &self.0.hash
+    }
+
+    async fn previous_hash(&self) -> &str {
+        &self.0.previous_hash
+    }
+
+    async fn timestamp(&self) -> u64 {
+        self.0.timestamp
+    }
+
+    async fn miner(&self) -> &str {
+        &self.0.miner
+    }
+
+    async fn difficulty(&self) -> u64 {
+        self.0.difficulty
+    }
+
+    async fn nonce(&self) -> u64 {
+        self.0.nonce
+    }
+
+    async fn merkle_root(&self) -> &str {
+        &self.0.merkle_root
+    }
+
+    async fn state_root(&self) -> &str {
+        &self.0.state_root
+    }
+
+    async fn transaction_count(&self) -> usize {
+        self.0.transactions.len()
+    }
+
+    /// Transactions in block order
+    async fn transactions(&self, first: Option<i32>, after: Option<String>) -> async_graphql::Result<Connection<usize, TransactionObject>> {
+        page(self.0.transactions.len(), first, after, |range| {
+            self.0.transactions[range].iter().map(|tx| TransactionObject::mined(tx, self.0.index)).collect()
+        })
+    }
+
+    async fn parent(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<BlockObject>> {
+        let chain = ctx.data::<Sources>()?.chain.read().await;
+        Ok(self.0.index.checked_sub(1).and_then(|height| chain.get_block(height)).cloned().map(BlockObject))
+    }
+}
+
+pub struct TransactionObject {
+    transaction: Transaction,
+    block_height: Option<u64>, // None while pending, or when not known yet
+}
+
+impl TransactionObject {
+    fn mined(transaction: &Transaction, block_height: u64) -> Self {
+        Self { transaction: transaction.clone(), block_height: Some(block_height) }
+    }
+
+    fn pending(transaction: &Transaction) -> Self {
+        Self { transaction: transaction.clone(), block_height: None }
+    }
+}
+
+#[Object(name = "Transaction")]
+impl TransactionObject {
+    async fn hash(&self) -> &str {
+        &self.transaction.hash
+    }
+
+    async fn from(&self) -> &str {
+        &self.transaction.from
+    }
+
+    async fn nonce(&self) -> u64 {
+        self.transaction.nonce
+    }
+
+    async fn fee(&self) -> u64 {
+        self.transaction.fee
+    }
+
+    async fn gas_price(&self) -> u64 {
+        self.transaction.gas_price
+    }
+
+    async fn timestamp(&self) -> u64 {
+        self.transaction.timestamp
+    }
+
+    /// Variant of the transaction type, e.g. `Transfer`
+    async fn kind(&self) -> String {
+        match serde_json::to_value(&self.transaction.transaction_type) {
+            Ok(serde_json::Value::Object(fields)) => fields.keys().next().cloned().unwrap_or_default(),
+            Ok(serde_json::Value::String(kind)) => kind,
+            _ => String::new(),
+        }
+    }
+
+    /// The transaction type with its fields, as JSON
+    async fn details(&self) -> Json<serde_json::Value> {
+        Json(serde_json::to_value(&self.transaction.transaction_type).unwrap_or_default())
+    }
+
+    async fn recipients(&self) -> Vec<String> {
+        self.transaction.recipient_addresses().into_iter().map(str::to_string).collect()
+    }
+
+    async fn signed(&self) -> bool {
+        !self.transaction.signature.is_empty()
+    }
+
+    /// Block holding the transaction; null while it is pending
+    async fn block(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<BlockObject>> {
+        let chain = ctx.data::<Sources>()?.chain.read().await;
+        let height = self.block_height.or_else(|| locate(&chain, &self.transaction));
+        Ok(height.and_then(|height| chain.get_block(height)).cloned().map(BlockObject))
+    }
+
+    /// Execution receipt, for contract transactions
+    async fn receipt(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<ReceiptObject>> {
+        let chain = ctx.data::<Sources>()?.chain.read().await;
+        Ok(chain.get_receipt(&self.transaction.hash).cloned().map(ReceiptObject))
+    }
+}
+
+pub struct ReceiptObject(Receipt);
+
+#[Object(name = "Receipt")]
+impl ReceiptObject {
+    async fn success(&self) -> bool {
+        self.0.success
+    }
+
+    async fn gas_used(&self) -> u64 {
+        self.0.gas_used
+    }
+
+    async fn logs(&self) -> Vec<LogObject> {
+        self.0.logs.iter().map(|log| LogObject {
+            address: log.address.clone(),
+            topics: log.topics.clone(),
+            data: hex::encode(&log.data),
+        }).collect()
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "Log")]
+pub struct LogObject {
+    address: String,
+    topics: Vec<String>,
+    data: String, // Hex
+}
+
+pub struct Account {
+    address: String,
+}
+
+#[Object]
+impl Account {
+    async fn address(&self) -> &str {
+        &self.address
+    }
+
+    async fn balance(&self, ctx: &Context<'_>) -> async_graphql::Result<u64> {
+        Ok(ctx.data::<Sources>()?.chain.read().await.get_balance(&self.address))
+    }
+
+    /// Next nonce from the chain's point of view, and counting the mempool
+    async fn nonce(&self, ctx: &Context<'_>) -> async_graphql::Result<u64> {
+        Ok(ctx.data::<Sources>()?.chain.read().await.get_account_nonce(&self.address))
+    }
+
+    async fn pending_nonce(&self, ctx: &Context<'_>) -> async_graphql::Result<u64> {
+        Ok(ctx.data::<Sources>()?.chain.read().await.get_pending_nonce(&self.address))
+    }
+
+    /// Mined transactions touching the account, newest first, from the address index
+    async fn transactions(&self, ctx: &Context<'_>, first: Option<i32>, after: Option<String>) -> async_graphql::Result<Connection<usize, TransactionObject>> {
+        let chain = ctx.data::<Sources>()?.chain.read().await;
+        let refs = chain.address_index.get(&self.address);
+        page(refs.len(), first, after, |range| {
+            range.filter_map(|position| {
+                let tx_ref = &refs[refs.len() - 1 - position];
+                chain.get_block(tx_ref.block_index)
+                    .and_then(|block| block.transactions.get(tx_ref.position))
+                    .map(|tx| TransactionObject::mined(tx, tx_ref.block_index))
+            }).collect()
+        })
+    }
+
+    /// Tokens the account holds, by token ID
+    async fn token_balances(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<TokenBalance>> {
+        let engine = ctx.data::<Sources>()?.contracts()?.read().await;
+        let mut balances: Vec<TokenBalance> = engine.token_contracts.values()
+            .filter_map(|token| token.balances.get(&self.address).filter(|balance| **balance > 0).map(|balance| TokenBalance {
+                token: TokenObject(token.clone()),
+                balance: *balance,
+            }))
+            .collect();
+        balances.sort_by(|a, b| a.token.0.token_info.id.cmp(&b.token.0.token_info.id));
+        Ok(balances)
+    }
+
+    async fn staking_positions(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<StakingPosition>> {
+        let engine = ctx.data::<Sources>()?.contracts()?.read().await;
+        let mut positions: Vec<StakingPosition> = engine.staking_contracts.values()
+            .filter_map(|contract| contract.stakes.get(&self.address).map(|stake| StakingPosition::new(contract, stake)))
+            .collect();
+        positions.sort_by(|a, b| a.contract_id.cmp(&b.contract_id));
+        Ok(positions)
+    }
+
+    async fn liquidity_positions(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<LiquidityPositionObject>> {
+        let engine = ctx.data::<Sources>()?.contracts()?.read().await;
+        let mut positions: Vec<LiquidityPositionObject> = engine.liquidity_pools.values()
+            .filter_map(|pool| pool.liquidity_providers.get(&self.address).map(|position| LiquidityPositionObject {
+                pool_id: pool.id.clone(),
+                liquidity_tokens: position.liquidity_tokens,
+                unclaimed_fees_a: position.unclaimed_fees_a,
+                unclaimed_fees_b: position.unclaimed_fees_b,
+            }))
+            .collect();
+        positions.sort_by(|a, b| a.pool_id.cmp(&b.pool_id));
+        Ok(positions)
+    }
+}
+
+pub struct TokenObject(TokenContract);
+
+#[Object(name = "Token")]
+impl TokenObject {
+    async fn id(&self) -> &str {
+        &self.0.token_info.id
+    }
+
+    async fn name(&self) -> &str {
+        &self.0.token_info.name
+    }
+
+    async fn symbol(&self) -> &str {
+        &self.0.token_info.symbol
+    }
+
+    async fn decimals(&self) -> u8 {
+        self.0.token_info.decimals
+    }
+
+    async fn total_supply(&self) -> u64 {
+        self.0.total_supply
+    }
+
+    async fn max_supply(&self) -> Option<u64> {
+        self.0.max_supply
+    }
+
+    async fn owner(&self) -> &str {
+        &self.0.owner
+    }
+
+    async fn paused(&self) -> bool {
+        self.0.is_paused
+    }
+
+    async fn holder_count(&self) -> usize {
+        self.0.balances.values().filter(|balance| **balance > 0).count()
+    }
+
+    async fn balance_of(&self, address: String) -> u64 {
+        self.0.balances.get(&address).copied().unwrap_or(0)
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct TokenBalance {
+    token: TokenObject,
+    balance: u64,
+}
+
+pub struct StakingObject(StakingContract);
+
+#[Object(name = "StakingContract")]
+impl StakingObject {
+    async fn id(&self) -> &str {
+        &self.0.id
+    }
+
+    async fn token_id(&self) -> &str {
+        &self.0.token_id
+    }
+
+    async fn validator(&self) -> &str {
+        &self.0.validator
+    }
+
+    async fn total_staked(&self) -> u64 {
+        self.0.total_staked
+    }
+
+    async fn reward_rate(&self) -> f64 {
+        self.0.reward_rate
+    }
+
+    async fn active(&self) -> bool {
+        self.0.is_active
+    }
+
+    /// Stakes, largest first
+    async fn positions(&self, first: Option<i32>, after: Option<String>) -> async_graphql::Result<Connection<usize, StakingPosition>> {
+        let mut stakes: Vec<&StakeInfo> = self.0.stakes.values().collect();
+        stakes.sort_by(|a, b| b.amount.cmp(&a.amount).then_with(|| a.staker.cmp(&b.staker)));
+        page(stakes.len(), first, after, |range| stakes[range].iter().map(|stake| StakingPosition::new(&self.0, stake)).collect())
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct StakingPosition {
+    contract_id: String,
+    staker: String,
+    amount: u64,
+    delegated_to: String,
+    staked_at: String, // RFC 3339
+    lock_until: String,
+    accumulated_rewards: u64,
+    active: bool,
+}
+
+impl StakingPosition {
+    fn new(contract: &StakingContract, stake: &StakeInfo) -> Self {
+        Self {
+            contract_id: contract.id.clone(),
+            staker: stake.staker.clone(),
+            amount: stake.amount,
+            delegated_to: stake.delegated_to.clone(),
+            staked_at: stake.staked_at.to_rfc3339(),
+            lock_until: stake.lock_until.to_rfc3339(),
+            accumulated_rewards: stake.accumulated_rewards,
+            active: stake.is_active,
+        }
+    }
+}
+
+pub struct PoolObject(LiquidityPool);
+
+#[Object(name = "LiquidityPool")]
+impl PoolObject {
+    async fn id(&self) -> &str {
+        &self.0.id
+    }
+
+    async fn token_a(&self) -> &str {
+        &self.0.token_a
+    }
+
+    async fn token_b(&self) -> &str {
+        &self.0.token_b
+    }
+
+    async fn reserve_a(&self) -> u64 {
+        self.0.reserve_a
+    }
+
+    async fn reserve_b(&self) -> u64 {
+        self.0.reserve_b
+    }
+
+    /// Token B per token A
+    async fn price(&self) -> f64 {
+        self.0.get_price()
+    }
+
+    async fn total_liquidity(&self) -> u64 {
+        self.0.total_liquidity
+    }
+
+    async fn fee_rate(&self) -> f64 {
+        self.0.fee_rate
+    }
+
+    async fn volume_a(&self) -> u64 {
+        self.0.total_volume_a
+    }
+
+    async fn volume_b(&self) -> u64 {
+        self.0.total_volume_b
+    }
+
+    async fn active(&self) -> bool {
+        self.0.is_active
+    }
+
+    async fn provider_count(&self) -> usize {
+        self.0.liquidity_providers.len()
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "LiquidityPosition")]
+pub struct LiquidityPositionObject {
+    pool_id: String,
+    liquidity_tokens: u64,
+    unclaimed_fees_a: u64,
+    unclaimed_fees_b: u64,
+}
+
+pub struct TensorTaskObject(TensorTask);
+
+#[Object(name = "TensorTask")]
+impl TensorTaskObject {
+    async fn id(&self) -> &str {
+        &self.0.id
+    }
+
+    async fn operation(&self) -> &str {
+        &self.0.operation
+    }
+
+    async fn requester(&self) -> &str {
+        &self.0.requester
+    }
+
+    async fn reward(&self) -> u64 {
+        self.0.reward
+    }
+
+    async fn input_size(&self) -> usize {
+        self.0.input_data.len()
+    }
+
+    async fn expected_output_size(&self) -> usize {
+        self.0.expected_output_size
+    }
+
+    async fn completed(&self) -> bool {
+        self.0.completed
+    }
+
+    async fn assigned_miner(&self) -> Option<&str> {
+        self.0.assigned_miner.as_deref()
+    }
+
+    async fn result(&self) -> Option<Vec<f32>> {
+        self.0.result.clone()
+    }
+
+    async fn created_at(&self) -> u64 {
+        self.0.created_at
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tribechain_core::TransactionType;
+
+    fn chain_with_blocks(blocks: u64) -> TribeChain {
+        let dir = std::env::temp_dir().join(format!("tribechain-graphql-{}", uuid::Uuid::new_v4()));
+        let mut chain = TribeChain::new(dir.to_str().unwrap()).unwrap();
+        for index in 1..=blocks {
+            let tx = Transaction::new("alice".to_string(), TransactionType::Transfer { to: "bob".to_string(), amount: index }, 1, index);
+            let previous = chain.get_latest_block().unwrap().hash.clone();
+            let mut block = Block::new(index, previous, vec![tx], "miner".to_string());
+            block.hash = block.calculate_hash();
+            chain.blocks.push(block.clone());
+            chain.address_index.index_block(&block);
+        }
+        chain
+    }
+
+    async fn query(endpoint: &GraphqlEndpoint, query: &str) -> serde_json::Value {
+        endpoint.execute(json!({ "query": query }).to_string().as_bytes()).await
+    }
+
+    #[tokio::test]
+    async fn test_nested_queries_and_pagination() {
+        let chain = Arc::new(RwLock::new(chain_with_blocks(3)));
+        let endpoint = GraphqlEndpoint::new(chain, None);
+
+        let result = query(&endpoint, "{ blocks(first: 2) { edges { cursor node { height transactions { nodes { hash } } } } pageInfo { hasNextPage endCursor } } }").await;
+        let blocks = &result["data"]["blocks"];
+        assert_eq!(blocks["edges"][0]["node"]["height"], json!(3));
+        assert_eq!(blocks["edges"][1]["node"]["height"], json!(2));
+        assert_eq!(blocks["pageInfo"]["hasNextPage"], json!(true));
+
+        let next = format!("{{ blocks(first: 5, after: {}) {{ nodes {{ height }} pageInfo {{ hasNextPage }} }} }}", blocks["pageInfo"]["endCursor"]);
+        let result = query(&endpoint, &next).await;
+        assert_eq!(result["data"]["blocks"]["nodes"], json!([{ "height": 1 }, { "height": 0 }]));
+        assert_eq!(result["data"]["blocks"]["pageInfo"]["hasNextPage"], json!(false));
+
+        // Account history comes from the address index, and links back to the blocks
+        let result = query(&endpoint, r#"{ account(address: "bob") { transactions(first: 1) { nodes { kind block { height } details } } } }"#).await;
+        let transaction = &result["data"]["account"]["transactions"]["nodes"][0];
+        assert_eq!(transaction["kind"], json!("Transfer"));
+        assert_eq!(transaction["block"]["height"], json!(3));
+        assert_eq!(transaction["details"]["Transfer"]["amount"], json!(3));
+
+        let result = query(&endpoint, "{ tokens { nodes { id } } }").await;
+        assert!(result["errors"][0]["message"].as_str().unwrap().contains("contract state"));
+    }
+
+    #[tokio::test]
+    async fn test_contract_state_queries() {
+        let chain = Arc::new(RwLock::new(chain_with_blocks(0)));
+        let mut engine = ContractEngine::new();
+        let mut token = TokenContract::new("Foo".to_string(), "FOO".to_string(), 1_000, 6, "alice".to_string()).unwrap();
+        token.transfer("alice".to_string(), "bob".to_string(), 250).unwrap();
+        engine.token_contracts.insert(token.token_info.id.clone(), token);
+        engine.create_liquidity_pool("TRIBE".to_string(), "FOO".to_string(), 10_000, 20_000, "bob".to_string(), 0.003).unwrap();
+        let endpoint = GraphqlEndpoint::new(chain, Some(Arc::new(RwLock::new(engine))));
+
+        let result = query(&endpoint, r#"{ account(address: "bob") { tokenBalances { balance token { symbol holderCount } } liquidityPositions { liquidityTokens } } }"#).await;
+        let account = &result["data"]["account"];
+        assert_eq!(account["tokenBalances"], json!([{ "balance": 250, "token": { "symbol": "FOO", "holderCount": 2 } }]));
+        assert_eq!(account["liquidityPositions"].as_array().unwrap().len(), 1);
+
+        let result = query(&endpoint, "{ liquidityPools { nodes { tokenA price } } }").await;
+        assert_eq!(result["data"]["liquidityPools"]["nodes"][0], json!({ "tokenA": "TRIBE", "price": 2.0 }));
+
+        let result = query(&endpoint, r#"{ account(address: "trb1qqqq") { balance } }"#).await;
+        assert!(result["errors"].is_array());
+    }
+}
@@ -0,0 +1,149 @@
+use serde::{Deserialize, Serialize};
+use tribechain_core::{TribeResult, TribeError, MAINNET_CHAIN_ID};
+
+pub use tribechain_core::{TESTNET_CHAIN_ID, DEVNET_CHAIN_ID};
+
+/// Magic bytes identifying each network on the wire
+pub const MAINNET_MAGIC: [u8; 4] = *b"TRB\x01";
+pub const TESTNET_MAGIC: [u8; 4] = *b"TRB\x02";
+pub const DEVNET_MAGIC: [u8; 4] = *b"TRBd";
+
+/// Protocol version we speak, and the oldest one we still accept. Version 3 sends
+/// blocks and transactions in their versioned encodings.
+pub const PROTOCOL_VERSION: u32 = 3;
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+
+/// Capability flags advertised in the handshake
+pub const FEATURE_AI3_TASKS: u32 = 1 << 0;
+pub const FEATURE_COMPACT_BLOCKS: u32 = 1 << 1;
+pub const FEATURE_PEER_EXCHANGE: u32 = 1 << 2;
+
+/// What a peer does on the network
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NodeType {
+    Full, // Stores and serves the whole chain
+    Light, // Headers only; can't serve blocks
+    Miner, // Full node that also produces blocks
+}
+
+/// First message on every connection; peers from another network are refused
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Handshake {
+    pub network_magic: [u8; 4],
+    pub chain_id: u64,
+    pub node_id: String,
+    pub protocol_version: u32,
+    pub node_type: NodeType,
+    pub best_height: u64,
+    pub features: u32,
+}
+
+impl Handshake {
+    pub fn new(network_magic: [u8; 4], chain_id: u64, node_id: String) -> Self {
+        Self {
+            network_magic,
+            chain_id,
+            node_id,
+            protocol_version: PROTOCOL_VERSION,
+            node_type: NodeType::Full,
+            best_height: 0,
+            features: FEATURE_AI3_TASKS | FEATURE_COMPACT_BLOCKS | FEATURE_PEER_EXCHANGE,
+        }
+    }
+
+    pub fn with_node_type(mut self, node_type: NodeType) -> Self {
+        self.node_type = node_type;
+        self
+    }
+
+    pub fn with_best_height(mut self, best_height: u64) -> Self {
+        self.best_height = best_height;
+        self
+    }
+
+    pub fn with_features(mut self, features: u32) -> Self {
+        self.features = features;
+        self
+    }
+
+    pub fn supports(&self, feature: u32) -> bool {
+        self.features & feature == feature
+    }
+
+    /// Whether the peer can serve full blocks for sync
+    pub fn serves_blocks(&self) -> bool {
+        self.node_type != NodeType::Light
+    }
+
+    /// Version both sides speak
+    pub fn negotiated_version(&self, local: &Handshake) -> u32 {
+        self.protocol_version.min(local.protocol_version)
+    }
+
+    /// Check a remote handshake against our own
+    pub fn verify(&self, local: &Handshake) -> TribeResult<()> {
+        if self.network_magic != local.network_magic {
+            return Err(TribeError::Network(format!(
+                "Peer {} is on another network (magic {:02x?})", self.node_id, self.network_magic
+            )));
+        }
+        if self.chain_id != local.chain_id {
+            return Err(TribeError::Network(format!(
+                "Peer {} has chain ID {}, expected {}", self.node_id, self.chain_id, local.chain_id
+            )));
+        }
+        if self.protocol_version < MIN_PROTOCOL_VERSION {
+            return Err(TribeError::Network(format!(
+                "Peer {} speaks protocol {}, minimum is {}", self.node_id, self.protocol_version, MIN_PROTOCOL_VERSION
+            )));
+        }
+        if self.node_id == local.node_id {
+            return Err(TribeError::Network("Connected to ourselves".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Default magic for a chain ID; unknown chains are treated as devnets
+pub fn magic_for_chain(chain_id: u64) -> [u8; 4] {
+    match chain_id {
+        MAINNET_CHAIN_ID => MAINNET_MAGIC,
+        TESTNET_CHAIN_ID => TESTNET_MAGIC,
+        _ => DEVNET_MAGIC,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_handshake_rejects_other_networks() {
+        let local = Handshake::new(MAINNET_MAGIC, MAINNET_CHAIN_ID, "local".to_string());
+
+        assert!(Handshake::new(MAINNET_MAGIC, MAINNET_CHAIN_ID, "remote".to_string()).verify(&local).is_ok());
+        assert!(Handshake::new(TESTNET_MAGIC, MAINNET_CHAIN_ID, "remote".to_string()).verify(&local).is_err());
+        assert!(Handshake::new(MAINNET_MAGIC, TESTNET_CHAIN_ID, "remote".to_string()).verify(&local).is_err());
+        assert!(Handshake::new(MAINNET_MAGIC, MAINNET_CHAIN_ID, "local".to_string()).verify(&local).is_err());
+        assert_eq!(magic_for_chain(DEVNET_CHAIN_ID), DEVNET_MAGIC);
+    }
+
+    #[test]
+    fn test_handshake_capabilities() {
+        let local = Handshake::new(MAINNET_MAGIC, MAINNET_CHAIN_ID, "local".to_string());
+        let mut old = Handshake::new(MAINNET_MAGIC, MAINNET_CHAIN_ID, "old".to_string())
+            .with_node_type(NodeType::Light)
+            .with_best_height(120)
+            .with_features(FEATURE_AI3_TASKS);
+        old.protocol_version = 1;
+
+        assert!(old.verify(&local).is_ok());
+        assert_eq!(old.negotiated_version(&local), 1);
+        assert!(old.supports(FEATURE_AI3_TASKS));
+        assert!(!old.supports(FEATURE_COMPACT_BLOCKS));
+        assert!(!old.serves_blocks());
+
+        old.protocol_version = 0;
+        assert!(old.verify(&local).is_err());
+    }
+}
@@ -0,0 +1,541 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use tribechain_core::{
+    AddressTxRef, Block, Storage, StorageOp, Transaction, TransactionType, TribeError, TribeResult,
+    ADDRESS_TX_PAGE_SIZE,
+};
+use tribechain_core::storage::{CF_INDICES, CF_STATE};
+use crate::rpc::RpcClient;
+
+/// Blocks the indexer can roll back on a reorg
+pub const INDEXER_UNDO_DEPTH: u64 = 100;
+
+/// Blocks fetched from the node per `sync` call at most
+pub const MAX_SYNC_BLOCKS: u64 = 1000;
+
+const TIP_KEY: &str = "explorer:tip";
+
+fn index_error(message: impl Into<String>) -> TribeError {
+    TribeError::Storage(message.into())
+}
+
+fn encode<T: Serialize + ?Sized>(value: &T, what: &str) -> TribeResult<Vec<u8>> {
+    bincode::serialize(value).map_err(|e| index_error(format!("Failed to serialize {}: {}", what, e)))
+}
+
+fn decode<T: DeserializeOwned>(data: &[u8], what: &str) -> TribeResult<T> {
+    bincode::deserialize(data).map_err(|e| index_error(format!("Failed to deserialize {}: {}", what, e)))
+}
+
+fn transaction_key(hash: &str) -> String {
+    format!("explorer:tx:{}", hash)
+}
+
+fn address_key(address: &str) -> String {
+    format!("explorer:addr:{}", address)
+}
+
+fn token_key(token_id: &str) -> String {
+    format!("explorer:token:{}", token_id)
+}
+
+fn token_address_key(address: &str) -> String {
+    format!("explorer:token_addr:{}", address)
+}
+
+fn calls_key(contract: &str) -> String {
+    format!("explorer:calls:{}", contract)
+}
+
+fn hash_key(height: u64) -> String {
+    format!("explorer:hash:{}", height)
+}
+
+fn requester_key(requester: &str) -> String {
+    format!("explorer:tasks_req:{}", requester)
+}
+
+fn miner_key(miner: &str) -> String {
+    format!("explorer:tasks_miner:{}", miner)
+}
+
+/// Big-endian so undo records sort in chain order
+fn undo_key(index: u64) -> Vec<u8> {
+    let mut key = b"explorer:undo:".to_vec();
+    key.extend_from_slice(&index.to_be_bytes());
+    key
+}
+
+/// Newest-first page of a table kept in chain order
+fn page<T: Clone>(records: &[T], page: usize, page_size: usize) -> Vec<T> {
+    records.iter().rev().skip(page.saturating_mul(page_size)).take(page_size).cloned().collect()
+}
+
+/// Last block indexed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexTip {
+    pub height: u64,
+    pub hash: String,
+}
+
+/// A transaction with where it was included
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexedTransaction {
+    pub transaction: Transaction,
+    pub block_index: u64,
+    pub block_hash: String,
+    pub position: usize,
+    pub timestamp: u64, // Block time
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TokenTransferRecord {
+    pub tx_hash: String,
+    pub block_index: u64,
+    pub token_id: String,
+    pub from: String,
+    pub to: String,
+    pub amount: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContractCallRecord {
+    pub tx_hash: String,
+    pub block_index: u64,
+    pub contract_address: String,
+    pub caller: String,
+    pub method: String,
+    pub value: u64,
+}
+
+/// An AI3 task, as requested by a `TensorCompute` transaction or solved in a block's proof
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TaskRecord {
+    Requested {
+        tx_hash: String,
+        block_index: u64,
+        requester: String,
+        operation: String,
+        reward: u64,
+    },
+    Mined {
+        task_id: String,
+        block_index: u64,
+        block_hash: String,
+        miner: String,
+        optimization_factor: f32,
+        computation_time: u64,
+    },
+}
+
+/// Rows one block added, with what each key held before, so a reorg can drop them
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct BlockUndo {
+    previous: Vec<(String, Option<Vec<u8>>)>,
+}
+
+/// A block's new rows, grouped by the stored list they extend
+#[derive(Default)]
+struct BlockRows {
+    addresses: BTreeMap<String, Vec<AddressTxRef>>,
+    transfers: BTreeMap<String, Vec<TokenTransferRecord>>,
+    calls: BTreeMap<String, Vec<ContractCallRecord>>,
+    tasks: BTreeMap<String, Vec<TaskRecord>>,
+}
+
+/// Explorer backend: follows a node's blocks and keeps denormalized tables of
+/// transactions by address, token transfers, contract calls and AI3 tasks by requester
+/// and miner in its own storage, with query methods over them.
+#[derive(Debug)]
+pub struct Indexer {
+    storage: Arc<dyn Storage>,
+    tip: Option<IndexTip>,
+}
+
+impl Indexer {
+    /// Resume from whatever `storage` already holds
+    pub fn open(storage: Arc<dyn Storage>) -> TribeResult<Self> {
+        let tip = match storage.get(CF_STATE, TIP_KEY.as_bytes())? {
+            Some(data) => Some(decode(&data, "indexer tip")?),
+            None => None,
+        };
+        Ok(Self { storage, tip })
+    }
+
+    pub fn tip(&self) -> Option<&IndexTip> {
+        self.tip.as_ref()
+    }
+
+    /// Height of the next block to index
+    pub fn next_height(&self) -> u64 {
+        self.tip.as_ref().map(|tip| tip.height + 1).unwrap_or(0)
+    }
+
+    fn load<T: DeserializeOwned>(&self, key: &str, what: &str) -> TribeResult<Option<T>> {
+        match self.storage.get(CF_INDICES, key.as_bytes())? {
+            Some(data) => Ok(Some(decode(&data, what)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn load_list<T: DeserializeOwned>(&self, key: &str, what: &str) -> TribeResult<Vec<T>> {
+        Ok(self.load(key, what)?.unwrap_or_default())
+    }
+
+    /// Queue `key`'s stored list extended by `rows`, remembering its old value
+    fn append<T: Serialize + DeserializeOwned>(
+        &self,
+        batch: &mut Vec<StorageOp>,
+        undo: &mut BlockUndo,
+        key: String,
+        rows: Vec<T>,
+        what: &str,
+    ) -> TribeResult<()> {
+        let stored = self.storage.get(CF_INDICES, key.as_bytes())?;
+        let mut list: Vec<T> = match &stored {
+            Some(data) => decode(data, what)?,
+            None => Vec::new(),
+        };
+        list.extend(rows);
+        batch.push(StorageOp::put(CF_INDICES, key.clone(), encode(&list, what)?));
+        undo.previous.push((key, stored));
+        Ok(())
+    }
+
+    /// Index the block after the tip. It must extend the tip; `rollback` first on a reorg.
+    pub fn index_block(&mut self, block: &Block) -> TribeResult<()> {
+        if block.index != self.next_height() {
+            return Err(index_error(format!("Expected block {}, got {}", self.next_height(), block.index)));
+        }
+        if let Some(tip) = &self.tip {
+            if block.previous_hash != tip.hash {
+                return Err(index_error(format!("Block {} does not extend the indexed chain", block.index)));
+            }
+        }
+
+        let mut rows = BlockRows::default();
+        let mut undo = BlockUndo::default();
+        let mut batch = Vec::new();
+        let key = hash_key(block.index);
+        undo.previous.push((key.clone(), self.storage.get(CF_INDICES, key.as_bytes())?));
+        batch.push(StorageOp::put(CF_INDICES, key, block.hash.as_bytes().to_vec()));
+        for (position, transaction) in block.transactions.iter().enumerate() {
+            let indexed = IndexedTransaction {
+                transaction: transaction.clone(),
+                block_index: block.index,
+                block_hash: block.hash.clone(),
+                position,
+                timestamp: block.timestamp,
+            };
+            let key = transaction_key(&transaction.hash);
+            undo.previous.push((key.clone(), self.storage.get(CF_INDICES, key.as_bytes())?));
+            batch.push(StorageOp::put(CF_INDICES, key, encode(&indexed, "indexed transaction")?));
+
+            let reference = AddressTxRef {
+                tx_hash: transaction.hash.clone(),
+                block_index: block.index,
+                block_hash: block.hash.clone(),
+                position,
+            };
+            for address in transaction.involved_addresses() {
+                rows.addresses.entry(address_key(&address)).or_default().push(reference.clone());
+            }
+
+            match &transaction.transaction_type {
+                TransactionType::TokenTransfer { to, amount, token_id } => {
+                    let transfer = TokenTransferRecord {
+                        tx_hash: transaction.hash.clone(),
+                        block_index: block.index,
+                        token_id: token_id.clone(),
+                        from: transaction.from.clone(),
+                        to: to.clone(),
+                        amount: *amount,
+                    };
+                    rows.transfers.entry(token_key(token_id)).or_default().push(transfer.clone());
+                    rows.transfers.entry(token_address_key(&transaction.from)).or_default().push(transfer.clone());
+                    if *to != transaction.from {
+                        rows.transfers.entry(token_address_key(to)).or_default().push(transfer);
+                    }
+                }
+                TransactionType::ContractCall { contract_address, method, value, .. } => {
+                    let call = ContractCallRecord {
+                        tx_hash: transaction.hash.clone(),
+                        block_index: block.index,
+                        contract_address: contract_address.clone(),
+                        caller: transaction.from.clone(),
+                        method: method.clone(),
+                        value: *value,
+                    };
+                    rows.calls.entry(calls_key(contract_address)).or_default().push(call);
+                }
+                TransactionType::TensorCompute { operation, reward, .. } => {
+                    let task = TaskRecord::Requested {
+                        tx_hash: transaction.hash.clone(),
+                        block_index: block.index,
+                        requester: transaction.from.clone(),
+                        operation: operation.clone(),
+                        reward: *reward,
+                    };
+                    rows.tasks.entry(requester_key(&transaction.from)).or_default().push(task);
+                }
+                _ => {}
+            }
+        }
+        if let Some(proof) = &block.ai3_proof {
+            let task = TaskRecord::Mined {
+                task_id: proof.task_id.clone(),
+                block_index: block.index,
+                block_hash: block.hash.clone(),
+                miner: block.miner.clone(),
+                optimization_factor: proof.optimization_factor,
+                computation_time: proof.computation_time,
+            };
+            rows.tasks.entry(miner_key(&block.miner)).or_default().push(task);
+        }
+
+        for (key, list) in rows.addresses {
+            self.append(&mut batch, &mut undo, key, list, "address transactions")?;
+        }
+        for (key, list) in rows.transfers {
+            self.append(&mut batch, &mut undo, key, list, "token transfers")?;
+        }
+        for (key, list) in rows.calls {
+            self.append(&mut batch, &mut undo, key, list, "contract calls")?;
+        }
+        for (key, list) in rows.tasks {
+            self.append(&mut batch, &mut undo, key, list, "tasks")?;
+        }
+        let tip = IndexTip { height: block.index, hash: block.hash.clone() };
+        batch.push(StorageOp::put(CF_INDICES, undo_key(block.index), encode(&undo, "indexer undo")?));
+        if let Some(expired) = block.index.checked_sub(INDEXER_UNDO_DEPTH) {
+            batch.push(StorageOp::delete(CF_INDICES, undo_key(expired)));
+        }
+        batch.push(StorageOp::put(CF_STATE, TIP_KEY, encode(&tip, "indexer tip")?));
+        self.storage.write(batch)?;
+        self.tip = Some(tip);
+        Ok(())
+    }
+
+    /// Drop the indexed blocks above `height`, newest first
+    pub fn rollback(&mut self, height: u64) -> TribeResult<()> {
+        while let Some(tip) = self.tip.clone().filter(|tip| tip.height > height) {
+            let undo: BlockUndo = match self.storage.get(CF_INDICES, &undo_key(tip.height))? {
+                Some(data) => decode(&data, "indexer undo")?,
+                None => return Err(index_error(format!("No undo record for block {}; reindex from scratch", tip.height))),
+            };
+            let mut batch: Vec<StorageOp> = undo.previous.into_iter()
+                .rev()
+                .map(|(key, previous)| match previous {
+                    Some(value) => StorageOp::put(CF_INDICES, key, value),
+                    None => StorageOp::delete(CF_INDICES, key),
+                })
+                .collect();
+            batch.push(StorageOp::delete(CF_INDICES, undo_key(tip.height)));
+
+            // Above `height`, so never the genesis block
+            let parent = IndexTip { height: tip.height - 1, hash: self.block_hash(tip.height - 1)? };
+            batch.push(StorageOp::put(CF_STATE, TIP_KEY, encode(&parent, "indexer tip")?));
+            self.storage.write(batch)?;
+            self.tip = Some(parent);
+        }
+        Ok(())
+    }
+
+    /// Hash of indexed block `height`
+    pub fn block_hash(&self, height: u64) -> TribeResult<String> {
+        match self.storage.get(CF_INDICES, hash_key(height).as_bytes())? {
+            Some(hash) => String::from_utf8(hash).map_err(|_| index_error("Corrupt block hash")),
+            None => Err(index_error(format!("Block {} is not indexed", height))),
+        }
+    }
+
+    /// Index the node's new blocks, at most `MAX_SYNC_BLOCKS` per call. A block that
+    /// doesn't extend the tip means the node reorganized, and the tip is rolled back
+    /// until it does. Returns the blocks indexed.
+    pub async fn sync(&mut self, client: &mut RpcClient) -> TribeResult<u64> {
+        let node_height = client.call("getBlockNumber", json!([])).await?
+            .as_u64()
+            .ok_or_else(|| index_error("Node returned no block number"))?;
+        let mut indexed = 0;
+        while self.next_height() <= node_height && indexed < MAX_SYNC_BLOCKS {
+            let block: Block = match client.call("getBlock", json!([self.next_height()])).await? {
+                Value::Null => break,
+                value => serde_json::from_value(value).map_err(|e| index_error(format!("Invalid block from node: {}", e)))?,
+            };
+            match &self.tip {
+                Some(tip) if block.previous_hash != tip.hash => {
+                    if tip.height == 0 {
+                        return Err(index_error("Node has a different genesis block; index into new storage"));
+                    }
+                    self.rollback(tip.height - 1)?;
+                }
+                _ => {
+                    self.index_block(&block)?;
+                    indexed += 1;
+                }
+            }
+        }
+        Ok(indexed)
+    }
+
+    pub fn transaction(&self, hash: &str) -> TribeResult<Option<IndexedTransaction>> {
+        self.load(&transaction_key(hash), "indexed transaction")
+    }
+
+    /// Transactions sending to or from `address`, newest first
+    pub fn address_transactions(&self, address: &str, page_index: usize) -> TribeResult<Vec<AddressTxRef>> {
+        let refs: Vec<AddressTxRef> = self.load_list(&address_key(address), "address transactions")?;
+        Ok(page(&refs, page_index, ADDRESS_TX_PAGE_SIZE))
+    }
+
+    pub fn token_transfers(&self, token_id: &str, page_index: usize) -> TribeResult<Vec<TokenTransferRecord>> {
+        let transfers: Vec<TokenTransferRecord> = self.load_list(&token_key(token_id), "token transfers")?;
+        Ok(page(&transfers, page_index, ADDRESS_TX_PAGE_SIZE))
+    }
+
+    /// Token transfers sent or received by `address`, of any token
+    pub fn address_token_transfers(&self, address: &str, page_index: usize) -> TribeResult<Vec<TokenTransferRecord>> {
+        let transfers: Vec<TokenTransferRecord> = self.load_list(&token_address_key(address), "token transfers")?;
+        Ok(page(&transfers, page_index, ADDRESS_TX_PAGE_SIZE))
+    }
+
+    pub fn contract_calls(&self, contract_address: &str, page_index: usize) -> TribeResult<Vec<ContractCallRecord>> {
+        let calls: Vec<ContractCallRecord> = self.load_list(&calls_key(contract_address), "contract calls")?;
+        Ok(page(&calls, page_index, ADDRESS_TX_PAGE_SIZE))
+    }
+
+    pub fn tasks_by_requester(&self, requester: &str, page_index: usize) -> TribeResult<Vec<TaskRecord>> {
+        let tasks: Vec<TaskRecord> = self.load_list(&requester_key(requester), "tasks")?;
+        Ok(page(&tasks, page_index, ADDRESS_TX_PAGE_SIZE))
+    }
+
+    pub fn tasks_by_miner(&self, miner: &str, page_index: usize) -> TribeResult<Vec<TaskRecord>> {
+        let tasks: Vec<TaskRecord> = self.load_list(&miner_key(miner), "tasks")?;
+        Ok(page(&tasks, page_index, ADDRESS_TX_PAGE_SIZE))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::{RpcServer, RpcState};
+    use tokio::net::TcpListener;
+    use tokio::sync::RwLock;
+    use tribechain_core::{AI3Proof, MemoryStorage, TribeChain};
+
+    fn block(parent: &Block, transactions: Vec<Transaction>, miner: &str) -> Block {
+        let mut block = Block::new(parent.index + 1, parent.hash.clone(), transactions, miner.to_string());
+        block.hash = block.calculate_hash();
+        block
+    }
+
+    fn token_transfer(from: &str, to: &str, amount: u64, nonce: u64) -> Transaction {
+        let transfer = TransactionType::TokenTransfer { to: to.to_string(), amount, token_id: "stomp".to_string() };
+        Transaction::new(from.to_string(), transfer, 1, nonce)
+    }
+
+    #[test]
+    fn test_index_block_tables() {
+        let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+        let mut indexer = Indexer::open(storage.clone()).unwrap();
+        let genesis = Block::genesis();
+        indexer.index_block(&genesis).unwrap();
+
+        let call = TransactionType::ContractCall {
+            contract_address: "contract1".to_string(),
+            method: "vote".to_string(),
+            args: vec![],
+            value: 3,
+        };
+        let compute = TransactionType::TensorCompute {
+            operation: "relu".to_string(),
+            input_data: vec![-1.0, 2.0],
+            expected_output_size: 2,
+            max_computation_time: 60,
+            reward: 1_000,
+        };
+        let transactions = vec![
+            token_transfer("alice", "bob", 5, 0),
+            token_transfer("bob", "carol", 2, 0),
+            Transaction::new("alice".to_string(), call, 1, 1),
+            Transaction::new("alice".to_string(), compute, 1, 2),
+        ];
+        let mut first = block(&genesis, transactions.clone(), "miner1");
+        first.ai3_proof = Some(AI3Proof {
+            task_id: "task1".to_string(),
+            optimization_factor: 1.5,
+            tensor_hash: "hash".to_string(),
+            computation_time: 40,
+            miner_signature: String::new(),
+            commitment_nonce: 0,
+        });
+        first.hash = first.calculate_hash();
+        assert!(indexer.index_block(&block(&first, vec![], "miner1")).is_err()); // Skips a height
+        indexer.index_block(&first).unwrap();
+        let second = block(&first, vec![token_transfer("carol", "alice", 1, 1)], "miner2");
+        indexer.index_block(&second).unwrap();
+
+        assert_eq!(indexer.transaction(&transactions[2].hash).unwrap().unwrap().position, 2);
+        let alice: Vec<u64> = indexer.address_transactions("alice", 0).unwrap().iter().map(|r| r.block_index).collect();
+        assert_eq!(alice, vec![2, 1, 1, 1]);
+        let stomp: Vec<u64> = indexer.token_transfers("stomp", 0).unwrap().iter().map(|t| t.amount).collect();
+        assert_eq!(stomp, vec![1, 2, 5]);
+        assert_eq!(indexer.address_token_transfers("bob", 0).unwrap().len(), 2);
+        assert_eq!(indexer.contract_calls("contract1", 0).unwrap()[0].method, "vote");
+        assert!(matches!(&indexer.tasks_by_requester("alice", 0).unwrap()[..], [TaskRecord::Requested { reward: 1_000, .. }]));
+        assert!(matches!(&indexer.tasks_by_miner("miner1", 0).unwrap()[..], [TaskRecord::Mined { task_id, .. }] if task_id == "task1"));
+
+        // Undone block by block, and the tip survives reopening
+        indexer.rollback(1).unwrap();
+        assert_eq!(indexer.tip(), Some(&IndexTip { height: 1, hash: first.hash.clone() }));
+        assert_eq!(indexer.token_transfers("stomp", 0).unwrap().len(), 2);
+        assert!(indexer.address_token_transfers("carol", 0).unwrap().iter().all(|t| t.block_index == 1));
+        indexer.rollback(0).unwrap();
+        assert!(indexer.transaction(&transactions[0].hash).unwrap().is_none());
+        assert!(indexer.tasks_by_miner("miner1", 0).unwrap().is_empty());
+        assert_eq!(Indexer::open(storage).unwrap().next_height(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_sync_follows_reorg() {
+        let dir = std::env::temp_dir().join(format!("tribechain-indexer-{}", uuid::Uuid::new_v4()));
+        let mut chain = TribeChain::new(dir.to_str().unwrap()).unwrap();
+        let first = block(chain.get_latest_block().unwrap(), vec![token_transfer("alice", "bob", 5, 0)], "miner");
+        let stale = block(&first, vec![token_transfer("bob", "carol", 2, 0)], "miner");
+        chain.blocks.extend([first.clone(), stale]);
+        let state = RpcState::new(Arc::new(RwLock::new(chain)));
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        drop(listener);
+        let serving = state.clone();
+        let listen_addr = addr.clone();
+        tokio::spawn(async move { RpcServer::new(0).unwrap().listen(&listen_addr, serving).await });
+        let mut client = loop {
+            match RpcClient::connect(&addr).await {
+                Ok(client) => break client,
+                Err(_) => tokio::time::sleep(std::time::Duration::from_millis(10)).await,
+            }
+        };
+
+        let mut indexer = Indexer::open(Arc::new(MemoryStorage::new())).unwrap();
+        assert_eq!(indexer.sync(&mut client).await.unwrap(), 3);
+        assert_eq!(indexer.address_token_transfers("carol", 0).unwrap().len(), 1);
+
+        // The node switches to a longer fork from block 1
+        {
+            let mut chain = state.chain.write().await;
+            let replacement = block(&first, vec![token_transfer("bob", "dave", 4, 0)], "other");
+            let next = block(&replacement, vec![], "other");
+            chain.blocks.truncate(2);
+            chain.blocks.extend([replacement, next]);
+        }
+        assert_eq!(indexer.sync(&mut client).await.unwrap(), 2);
+        assert_eq!(indexer.tip().unwrap().height, 3);
+        assert!(indexer.address_token_transfers("carol", 0).unwrap().is_empty());
+        assert_eq!(indexer.address_token_transfers("dave", 0).unwrap()[0].amount, 4);
+        assert_eq!(indexer.tasks_by_miner("other", 0).unwrap().len(), 0);
+    }
+}
@@ -4,7 +4,19 @@ pub mod node;
 pub mod consensus;
 pub mod p2p;
 pub mod rpc;
+pub mod eth;
+pub mod graphql;
+pub mod gateway;
+pub mod relayer;
+pub mod indexer;
 pub mod sync;
+pub mod orphans;
+pub mod compact;
+pub mod framing;
+pub mod ratelimit;
+pub mod addrbook;
+pub mod handshake;
+pub mod chunks;
 
 pub use peer::*;
 pub use protocol::*;
@@ -12,13 +24,28 @@ pub use node::*;
 pub use consensus::*;
 pub use p2p::*;
 pub use rpc::*;
+pub use eth::*;
+pub use graphql::GraphqlEndpoint;
+pub use gateway::{MiningGateway, GatewayRequest, GatewayResponse};
+pub use relayer::{BridgeRelayer, RelayerConfig, EvmClient};
+pub use indexer::{Indexer, IndexTip, IndexedTransaction, TokenTransferRecord, ContractCallRecord, TaskRecord};
 pub use sync::*;
+pub use orphans::*;
+pub use compact::*;
+pub use framing::*;
+pub use ratelimit::*;
+pub use addrbook::*;
+pub use handshake::*;
+pub use chunks::*;
 
 use tribechain_core::{TribeResult, TribeError};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use chrono::{DateTime, Utc};
 
+/// Blocks `NetworkStats.network_hash_rate` is estimated over
+pub const HASH_RATE_WINDOW: usize = 100;
+
 /// Network manager for TribeChain
 #[derive(Debug)]
 pub struct NetworkManager {
@@ -27,6 +54,11 @@ pub struct NetworkManager {
     pub p2p: p2p::P2PNetwork,
     pub rpc: rpc::RpcServer,
     pub sync: sync::SyncManager,
+    pub pending_compact: HashMap<String, compact::PartialBlock>, // block hash -> awaiting transactions
+    pub address_book: addrbook::AddressBook,
+    pub performance_reports: Vec<tribechain_core::ValidatorPerformanceReport>, // Verified, awaiting the staking contracts
+    pub chunk_exchange: chunks::ChunkExchange,
+    pub received_chunks: Vec<(String, Vec<u8>)>, // Verified task data chunks, awaiting the AI3 engine
     pub is_running: bool,
 }
 
@@ -34,24 +66,25 @@ pub struct NetworkManager {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NetworkConfig {
     pub node_id: String,
+    pub chain_id: u64, // Peers and transactions for other chains are rejected
+    pub network_magic: [u8; 4],
+    pub node_type: handshake::NodeType,
     pub listen_address: String,
     pub port: u16,
     pub bootstrap_nodes: Vec<String>,
     pub max_peers: usize,
+    pub data_dir: String, // Where the chain and address book are persisted
     pub consensus_type: ConsensusType,
     pub mining_enabled: bool,
     pub rpc_enabled: bool,
     pub rpc_port: u16,
+    pub send_queue_capacity: usize, // Frames buffered per peer before sends are refused
+    pub request_timeout_secs: u64,
+    #[serde(default)]
+    pub rate_limits: ratelimit::RateLimitConfig,
 }
 
-/// Consensus types supported
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum ConsensusType {
-    ProofOfWork,
-    ProofOfStake,
-    DelegatedProofOfStake,
-    TensorProofOfWork, // AI3-specific consensus
-}
+pub use tribechain_core::ConsensusType;
 
 /// Network statistics
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -62,6 +95,8 @@ pub struct NetworkStats {
     pub network_hash_rate: f64,
     pub uptime: chrono::Duration,
     pub sync_status: SyncStatus,
+    pub orphan_blocks: usize,
+    pub orphan_rate: f64,
 }
 
 /// Synchronization status
@@ -77,10 +112,10 @@ impl NetworkManager {
     /// Create a new network manager
     pub fn new(config: NetworkConfig) -> TribeResult<Self> {
         let node = node::Node::new(config.clone())?;
-        let consensus = consensus::ConsensusEngine::new(config.consensus_type.clone())?;
+        let consensus = consensus::ConsensusEngine::new(config.consensus_type)?;
         let p2p = p2p::P2PNetwork::new(config.clone())?;
         let rpc = rpc::RpcServer::new(config.rpc_port)?;
-        let sync = sync::SyncManager::new()?;
+        let sync = sync::SyncManager::new(std::time::Duration::from_secs(config.request_timeout_secs))?;
 
         Ok(Self {
             node,
@@ -88,6 +123,11 @@ impl NetworkManager {
             p2p,
             rpc,
             sync,
+            pending_compact: HashMap::new(),
+            address_book: addrbook::AddressBook::load(&config.data_dir)?,
+            performance_reports: Vec::new(),
+            chunk_exchange: chunks::ChunkExchange::default(),
+            received_chunks: Vec::new(),
             is_running: false,
         })
     }
@@ -95,11 +135,14 @@ impl NetworkManager {
     /// Start the network
     pub async fn start(&mut self) -> TribeResult<()> {
         if self.is_running {
-            return Err(TribeError::InvalidOperation("Network is already running".to_string()));
+            return Err(TribeError::Network("Network is already running".to_string()));
         }
 
         // Start P2P network
         self.p2p.start().await?;
+
+        // Rejoin through peers remembered from previous runs
+        self.connect_known_peers().await;
         
         // Start consensus engine
         self.consensus.start().await?;
@@ -131,6 +174,7 @@ impl NetworkManager {
         self.rpc.stop().await?;
         self.consensus.stop().await?;
         self.p2p.stop().await?;
+        self.address_book.save()?;
 
         self.is_running = false;
         Ok(())
@@ -142,16 +186,21 @@ impl NetworkManager {
             connected_peers: self.p2p.get_peer_count(),
             total_transactions: self.node.get_transaction_count(),
             blocks_processed: self.node.get_block_count(),
-            network_hash_rate: self.consensus.get_hash_rate(),
+            network_hash_rate: self.node.get_network_hashrate(HASH_RATE_WINDOW),
             uptime: self.node.get_uptime(),
             sync_status: self.sync.get_status(),
+            orphan_blocks: self.node.orphans.len(),
+            orphan_rate: self.node.orphans.stats().orphan_rate,
         }
     }
 
     /// Broadcast a transaction to the network
     pub async fn broadcast_transaction(&mut self, transaction: tribechain_core::Transaction) -> TribeResult<()> {
         // Validate transaction
-        transaction.validate()?;
+        if !transaction.validate()? {
+            return Err(TribeError::InvalidTransaction(format!("Transaction {} failed validation", transaction.hash)));
+        }
+        self.check_chain_id(&transaction)?;
         
         // Add to local mempool
         self.node.add_transaction(transaction.clone())?;
@@ -164,28 +213,189 @@ impl NetworkManager {
 
     /// Broadcast a block to the network
     pub async fn broadcast_block(&mut self, block: tribechain_core::Block) -> TribeResult<()> {
-        // Validate block
-        block.validate()?;
-        
-        // Add to local blockchain
+        // Validated against our tip as it is connected
         self.node.add_block(block.clone())?;
+        self.consensus.record_block(&block).await;
         
-        // Broadcast to peers
-        self.p2p.broadcast_block(block).await?;
+        self.p2p.broadcast_compact_block(&block)?;
         
         Ok(())
     }
 
+    /// Report a double-signing validator and propagate the evidence to peers
+    pub async fn broadcast_slashing_evidence(&mut self, evidence: tribechain_core::DoubleSignEvidence) -> TribeResult<()> {
+        if self.consensus.report_evidence(&evidence).await? {
+            self.p2p.broadcast_slashing_evidence(evidence).await?;
+        }
+        Ok(())
+    }
+
+    /// Sign this validator's reports for closed consensus epochs and relay them to peers
+    pub async fn broadcast_performance_reports(&mut self, reporter: &str, private_key: &str) -> TribeResult<()> {
+        for report in self.consensus.take_performance_reports(reporter, private_key) {
+            if self.consensus.report_performance(&report).await? {
+                self.relay_performance_report(&report)?;
+                self.performance_reports.push(report);
+            }
+        }
+        Ok(())
+    }
+
+    fn relay_performance_report(&mut self, report: &tribechain_core::ValidatorPerformanceReport) -> TribeResult<()> {
+        let message = p2p::NetworkMessage::new(
+            p2p::MessageType::PerformanceReport,
+            self.node.config.node_id.clone(),
+            serde_json::to_vec(report)?,
+        );
+        self.p2p.broadcast(&message);
+        Ok(())
+    }
+
+    /// Verified performance reports to apply with `ContractEngine::apply_performance_report`
+    pub fn take_performance_reports(&mut self) -> Vec<tribechain_core::ValidatorPerformanceReport> {
+        std::mem::take(&mut self.performance_reports)
+    }
+
+    /// Serve task data chunks and announce them to peers
+    pub fn announce_chunks(&mut self, chunks: Vec<(String, Vec<u8>)>) -> TribeResult<()> {
+        let announce = self.chunk_exchange.publish(chunks);
+        if announce.hashes.is_empty() {
+            return Ok(());
+        }
+
+        let message = p2p::NetworkMessage::new(
+            p2p::MessageType::ChunkAnnounce,
+            self.node.config.node_id.clone(),
+            serde_json::to_vec(&announce)?,
+        );
+        self.p2p.broadcast(&message);
+        Ok(())
+    }
+
+    /// Ask announcing peers for the chunks a miner's assigned tasks need
+    pub fn fetch_chunks(&mut self, wanted: &[String]) -> TribeResult<()> {
+        for (peer_id, request) in self.chunk_exchange.requests_for(wanted) {
+            let message = p2p::NetworkMessage::new(
+                p2p::MessageType::GetChunks,
+                self.node.config.node_id.clone(),
+                serde_json::to_vec(&request)?,
+            );
+            if self.send_to_peer(&peer_id, &message).is_err() {
+                self.chunk_exchange.remove_peer(&peer_id);
+            }
+        }
+        Ok(())
+    }
+
+    /// Verified chunks to hand to `AI3Engine::add_chunk`
+    pub fn take_received_chunks(&mut self) -> Vec<(String, Vec<u8>)> {
+        std::mem::take(&mut self.received_chunks)
+    }
+
     /// Connect to a peer
     pub async fn connect_peer(&mut self, address: String) -> TribeResult<()> {
-        self.p2p.connect_peer(address).await
+        match self.p2p.connect_peer(address.clone()).await {
+            Ok(()) => {
+                self.address_book.record_success(&address);
+                self.send_handshake(&address)?;
+                // Ask the new peer who else it knows
+                let get_addr = p2p::NetworkMessage::new(
+                    p2p::MessageType::GetAddr,
+                    self.node.config.node_id.clone(),
+                    Vec::new(),
+                );
+                self.send_to_peer(&address, &get_addr)
+            }
+            Err(e) => {
+                self.address_book.record_failure(&address);
+                Err(e)
+            }
+        }
+    }
+
+    /// Handshake we send to every peer
+    pub fn local_handshake(&self) -> handshake::Handshake {
+        let node_type = if self.node.config.mining_enabled {
+            handshake::NodeType::Miner
+        } else {
+            self.node.config.node_type
+        };
+
+        handshake::Handshake::new(
+            self.node.config.network_magic,
+            self.node.config.chain_id,
+            self.node.config.node_id.clone(),
+        )
+        .with_node_type(node_type)
+        .with_best_height(self.node.get_block_count().saturating_sub(1))
+    }
+
+    /// Handshaken peers advertising `feature`
+    pub fn peers_with_feature(&self, feature: u32) -> Vec<String> {
+        self.p2p.peers_with_feature(feature)
+    }
+
+    /// Peer to sync from: the tallest one that can serve blocks
+    pub fn best_sync_peer(&self) -> Option<String> {
+        self.p2p.best_sync_peer()
+    }
+
+    fn send_handshake(&mut self, peer: &str) -> TribeResult<()> {
+        let handshake = self.local_handshake();
+        self.p2p.send_handshake(peer, &handshake)
+    }
+
+    fn check_chain_id(&self, transaction: &tribechain_core::Transaction) -> TribeResult<()> {
+        if transaction.chain_id != self.node.config.chain_id {
+            return Err(TribeError::InvalidTransaction(format!(
+                "Transaction {} is for chain {}, expected {}",
+                transaction.hash, transaction.chain_id, self.node.config.chain_id
+            )));
+        }
+        Ok(())
+    }
+
+    /// Dial the bootstrap nodes, then the best addresses from the address book until
+    /// `max_peers` is reached
+    async fn connect_known_peers(&mut self) {
+        for address in self.node.config.bootstrap_nodes.clone() {
+            let _ = self.connect_peer(address).await;
+        }
+
+        let wanted = self.node.config.max_peers.saturating_sub(self.p2p.get_peer_count());
+        for entry in self.address_book.best(wanted) {
+            if self.node.config.bootstrap_nodes.contains(&entry.address) {
+                continue; // Dialled above
+            }
+            let _ = self.connect_peer(entry.address).await;
+        }
     }
 
     /// Disconnect from a peer
     pub async fn disconnect_peer(&mut self, peer_id: String) -> TribeResult<()> {
+        self.chunk_exchange.remove_peer(&peer_id);
         self.p2p.disconnect_peer(peer_id).await
     }
 
+    /// Peers and sync state as served by `getNetworkStatus`
+    pub fn network_status(&self) -> rpc::NetworkStatus {
+        let peers = self.p2p.get_peers().into_iter()
+            .filter_map(|peer| {
+                let handshake = peer.handshake?;
+                Some(rpc::PeerStatus {
+                    peer_id: peer.peer_id,
+                    node_type: handshake.node_type,
+                    best_height: handshake.best_height,
+                    protocol_version: handshake.protocol_version,
+                })
+            })
+            .collect();
+        rpc::NetworkStatus {
+            peers,
+            syncing: matches!(self.sync.get_status(), SyncStatus::Syncing { .. }),
+        }
+    }
+
     /// Get connected peers
     pub fn get_peers(&self) -> Vec<p2p::PeerInfo> {
         self.p2p.get_peers()
@@ -196,59 +406,268 @@ impl NetworkManager {
         match message.message_type {
             p2p::MessageType::Transaction => {
                 let transaction: tribechain_core::Transaction = serde_json::from_slice(&message.data)?;
+                self.check_chain_id(&transaction)?;
                 self.node.add_transaction(transaction)?;
             }
             p2p::MessageType::Block => {
                 let block: tribechain_core::Block = serde_json::from_slice(&message.data)?;
-                self.node.add_block(block)?;
+                self.receive_block(block, Some(message.sender)).await;
+            }
+            p2p::MessageType::CompactBlock => {
+                let compact: compact::CompactBlock = serde_json::from_slice(&message.data)?;
+                if self.get_block(compact.header.hash.clone()).is_some() {
+                    return Ok(());
+                }
+
+                let mempool = self.node.get_pending_transactions()?;
+                match compact.reconstruct(&mempool) {
+                    compact::Reconstruction::Complete(block) => {
+                        self.accept_reconstructed(block, message.sender).await?;
+                    }
+                    compact::Reconstruction::Incomplete(partial) => {
+                        let request = partial.request();
+                        self.pending_compact.insert(partial.header.hash.clone(), partial);
+                        self.request_block_transactions(&message.sender, request)?;
+                    }
+                }
+            }
+            p2p::MessageType::GetBlockTransactions => {
+                let request: compact::GetBlockTransactions = serde_json::from_slice(&message.data)?;
+                if let Some(block) = self.get_block(request.block_hash.clone()) {
+                    let response = compact::block_transactions(&block, &request)?;
+                    let reply = p2p::NetworkMessage::new(
+                        p2p::MessageType::BlockTransactions,
+                        self.node.config.node_id.clone(),
+                        serde_json::to_vec(&response)?,
+                    );
+                    self.send_to_peer(&message.sender, &reply)?;
+                }
+            }
+            p2p::MessageType::BlockTransactions => {
+                let response: compact::BlockTransactions = serde_json::from_slice(&message.data)?;
+                if let Some(partial) = self.pending_compact.remove(&response.block_hash) {
+                    match partial.fill(response)? {
+                        compact::Reconstruction::Complete(block) => {
+                            self.accept_reconstructed(block, message.sender).await?;
+                        }
+                        compact::Reconstruction::Incomplete(partial) => {
+                            // Peer didn't send everything; ask again for what's left
+                            let request = partial.request();
+                            self.pending_compact.insert(partial.header.hash.clone(), partial);
+                            self.request_block_transactions(&message.sender, request)?;
+                        }
+                    }
+                }
             }
             p2p::MessageType::Ping => {
                 // Respond with pong
                 let pong = p2p::NetworkMessage::new_pong(self.node.config.node_id.clone());
-                self.p2p.send_message(message.sender, pong).await?;
+                self.send_to_peer(&message.sender, &pong)?;
+            }
+            p2p::MessageType::Handshake => {
+                let remote: handshake::Handshake = serde_json::from_slice(&message.data)?;
+                if let Err(e) = remote.verify(&self.local_handshake()) {
+                    self.disconnect_peer(message.sender).await?;
+                    return Err(e);
+                }
+                self.p2p.set_peer_handshake(&message.sender, remote)?;
+                // Answer peers that dialled us; our own handshake already went out otherwise
+                if self.p2p.get_peer(&message.sender).is_some_and(|peer| !peer.handshake_sent) {
+                    self.send_handshake(&message.sender)?;
+                }
+            }
+            p2p::MessageType::GetAddr => {
+                let addr = addrbook::AddrMessage {
+                    addresses: self.address_book.shareable(chrono::Duration::hours(3)),
+                };
+                let reply = p2p::NetworkMessage::new(
+                    p2p::MessageType::Addr,
+                    self.node.config.node_id.clone(),
+                    serde_json::to_vec(&addr)?,
+                );
+                self.send_to_peer(&message.sender, &reply)?;
+            }
+            p2p::MessageType::Addr => {
+                let addr: addrbook::AddrMessage = serde_json::from_slice(&message.data)?;
+                self.address_book.add_from_peer(&message.sender, addr.addresses);
             }
             p2p::MessageType::Pong => {
                 // Update peer last seen
                 self.p2p.update_peer_activity(message.sender)?;
             }
             p2p::MessageType::SyncRequest => {
-                // Handle sync request
-                self.sync.handle_sync_request(message).await?;
+                let reply = self.sync.handle_sync_request(&self.node, &message)?;
+                self.send_to_peer(&message.sender, &reply)?;
             }
             p2p::MessageType::SyncResponse => {
-                // Handle sync response
-                self.sync.handle_sync_response(message).await?;
+                let blocks = self.sync.handle_sync_response(&message)?;
+                if !blocks.is_empty() {
+                    for block in blocks {
+                        self.receive_block(block, Some(message.sender.clone())).await;
+                    }
+                    self.sync().await?; // Ask for the next batch
+                }
+            }
+            p2p::MessageType::SlashingEvidence => {
+                // Apply and relay evidence we haven't seen yet
+                let evidence: tribechain_core::DoubleSignEvidence = serde_json::from_slice(&message.data)?;
+                if self.consensus.report_evidence(&evidence).await? {
+                    self.p2p.broadcast_slashing_evidence(evidence).await?;
+                }
+            }
+            p2p::MessageType::ChunkAnnounce => {
+                let announce: chunks::ChunkAnnounce = serde_json::from_slice(&message.data)?;
+                self.chunk_exchange.record_announce(&message.sender, announce);
+            }
+            p2p::MessageType::GetChunks => {
+                let request: chunks::GetChunks = serde_json::from_slice(&message.data)?;
+                let reply = p2p::NetworkMessage::new(
+                    p2p::MessageType::ChunkData,
+                    self.node.config.node_id.clone(),
+                    serde_json::to_vec(&self.chunk_exchange.serve(&request))?,
+                );
+                self.send_to_peer(&message.sender, &reply)?;
+            }
+            p2p::MessageType::ChunkData => {
+                let data: chunks::ChunkData = serde_json::from_slice(&message.data)?;
+                let accepted = self.chunk_exchange.receive(&message.sender, data);
+                self.received_chunks.extend(accepted);
+            }
+            p2p::MessageType::PerformanceReport => {
+                // Apply and relay reports we haven't seen yet
+                let report: tribechain_core::ValidatorPerformanceReport = serde_json::from_slice(&message.data)?;
+                if self.consensus.report_performance(&report).await? {
+                    self.relay_performance_report(&report)?;
+                    self.performance_reports.push(report);
+                }
             }
         }
         Ok(())
     }
 
+    /// Connect a block from the network through the node, which keeps it as an orphan if
+    /// it can't connect yet, and let consensus see every block that connected
+    async fn receive_block(&mut self, block: tribechain_core::Block, received_from: Option<String>) {
+        for block in self.node.receive_block(block, received_from) {
+            self.consensus.record_block(&block).await;
+        }
+    }
+
+    /// Connect a block rebuilt from a compact announcement. A short ID collision shows up as
+    /// a bad merkle root, in which case every transaction is fetched from the peer instead.
+    async fn accept_reconstructed(&mut self, block: tribechain_core::Block, sender: String) -> TribeResult<()> {
+        if block.has_valid_merkle_root() {
+            self.receive_block(block, Some(sender)).await;
+            return Ok(());
+        }
+
+        let mut header = block;
+        let transaction_count = header.transactions.len();
+        header.transactions.clear();
+        let partial = compact::PartialBlock {
+            header,
+            slots: vec![None; transaction_count],
+        };
+        let request = partial.request();
+        self.pending_compact.insert(partial.header.hash.clone(), partial);
+        self.request_block_transactions(&sender, request)
+    }
+
+    fn request_block_transactions(&mut self, peer: &str, request: compact::GetBlockTransactions) -> TribeResult<()> {
+        let message = p2p::NetworkMessage::new(
+            p2p::MessageType::GetBlockTransactions,
+            self.node.config.node_id.clone(),
+            serde_json::to_vec(&request)?,
+        );
+        self.send_to_peer(peer, &message)
+    }
+
+    /// Start framing a peer stream accepted by the caller. Its inbound frames are
+    /// delivered by `next_frame`, for the caller to feed into `handle_frame`.
+    pub fn attach_peer<S>(&mut self, peer_id: String, stream: S) -> TribeResult<()>
+    where
+        S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Send + 'static,
+    {
+        self.p2p.attach_peer(peer_id, stream, false)
+    }
+
+    /// Next frame from any connected peer, with the peer it came from
+    pub async fn next_frame(&mut self) -> Option<(String, framing::Frame)> {
+        self.p2p.next_frame().await
+    }
+
+    /// Decode an inbound frame from `attach_peer` and handle the message it carries.
+    /// Oversized or rate-limited messages are dropped; peers that keep flooding are disconnected.
+    pub async fn handle_frame(&mut self, peer_id: &str, frame: framing::Frame) -> TribeResult<()> {
+        let decision = self.p2p.check_size(peer_id, frame.payload.len());
+        self.enforce_rate_limit(peer_id, decision).await?;
+
+        let mut message = p2p::NetworkMessage::decode(&frame.payload)?;
+        message.sender = peer_id.to_string(); // Replies go back over the connection it came in on
+        let decision = self.p2p.check_rate(peer_id, message.message_type);
+        self.enforce_rate_limit(peer_id, decision).await?;
+
+        // Nothing but the handshake is accepted until the peer proves it's on our network
+        if message.message_type != p2p::MessageType::Handshake && !self.p2p.is_ready(peer_id) {
+            return Err(TribeError::Network(format!("Peer {} has not completed the handshake", peer_id)));
+        }
+
+        self.handle_message(message).await
+    }
+
+    async fn enforce_rate_limit(&mut self, peer_id: &str, decision: ratelimit::RateDecision) -> TribeResult<()> {
+        match decision {
+            ratelimit::RateDecision::Allow => Ok(()),
+            ratelimit::RateDecision::Drop(reason) => {
+                Err(TribeError::Network(format!("Dropped message from {}: {}", peer_id, reason)))
+            }
+            ratelimit::RateDecision::Disconnect(reason) => {
+                self.disconnect_peer(peer_id.to_string()).await?;
+                Err(TribeError::Network(format!("Disconnected {}: {}", peer_id, reason)))
+            }
+        }
+    }
+
+    /// Queue a message for a peer without waiting on its socket.
+    /// A peer whose queue is full is too slow to keep up and is dropped.
+    pub fn send_to_peer(&mut self, peer_id: &str, message: &p2p::NetworkMessage) -> TribeResult<()> {
+        self.p2p.send(peer_id, message)
+    }
+
     /// Mine a new block (if mining is enabled)
     pub async fn mine_block(&mut self) -> TribeResult<Option<tribechain_core::Block>> {
         if !self.node.config.mining_enabled {
             return Ok(None);
         }
 
-        // Get pending transactions
-        let transactions = self.node.get_pending_transactions()?;
-        
-        if transactions.is_empty() {
-            return Ok(None);
+        // Scheduled-producer consensus (DPoS) only lets the delegate owning the slot produce
+        let now = Utc::now().timestamp() as u64;
+        self.consensus.update_votes(now, &self.node.get_delegate_vote_tally()).await;
+        if let Some(producer) = self.consensus.scheduled_producer(now).await {
+            if producer != self.node.config.node_id {
+                return Ok(None);
+            }
         }
 
+        // Highest gas price first, up to the block gas limit
+        let Some(template) = self.node.get_block_template()? else {
+            return Ok(None);
+        };
+
         // Mine block using consensus engine
-        let block = self.consensus.mine_block(transactions).await?;
+        let block = self.consensus.mine_block(template).await?;
         
         // Add to blockchain and broadcast
         self.node.add_block(block.clone())?;
-        self.p2p.broadcast_block(block.clone()).await?;
+        self.consensus.record_block(&block).await;
+        self.p2p.broadcast_compact_block(&block)?;
         
         Ok(Some(block))
     }
 
     /// Sync with the network
     pub async fn sync(&mut self) -> TribeResult<()> {
-        self.sync.start_sync(&mut self.p2p).await
+        self.sync.start_sync(&mut self.p2p, self.node.get_block_count())
     }
 
     /// Get blockchain info
@@ -256,6 +675,21 @@ impl NetworkManager {
         self.node.get_blockchain_info()
     }
 
+    /// Next nonce a wallet should use for `address`, including queued transactions
+    pub fn get_account_nonce(&self, address: &str) -> u64 {
+        self.node.get_account_nonce(address)
+    }
+
+    /// Gas price suggested for inclusion within `target_blocks`
+    pub fn estimate_fee(&self, target_blocks: u64) -> u64 {
+        self.node.estimate_fee(target_blocks)
+    }
+
+    /// Height of the latest checkpoint backed by 2/3 of validator stake
+    pub fn get_finalized_height(&self) -> u64 {
+        self.node.get_blockchain_info().finalized_height
+    }
+
     /// Get mempool info
     pub fn get_mempool_info(&self) -> node::MempoolInfo {
         self.node.get_mempool_info()
@@ -266,9 +700,9 @@ impl NetworkManager {
         self.node.validate_blockchain()
     }
 
-    /// Get block by hash
+    /// Get block by hash, including known orphans so peers can fetch competing blocks
     pub fn get_block(&self, hash: String) -> Option<tribechain_core::Block> {
-        self.node.get_block(hash)
+        self.node.find_block(&hash)
     }
 
     /// Get transaction by hash
@@ -348,6 +782,7 @@ impl NetworkManager {
             sync_status: self.sync.get_status(),
             last_block_time: self.node.get_last_block_time(),
             mempool_size: self.node.get_mempool_size(),
+            finalized_height: self.get_finalized_height(),
         }
     }
 }
@@ -360,20 +795,28 @@ pub struct NetworkHealth {
     pub sync_status: SyncStatus,
     pub last_block_time: Option<DateTime<Utc>>,
     pub mempool_size: usize,
+    pub finalized_height: u64,
 }
 
 impl Default for NetworkConfig {
     fn default() -> Self {
         Self {
             node_id: uuid::Uuid::new_v4().to_string(),
+            chain_id: tribechain_core::MAINNET_CHAIN_ID,
+            network_magic: handshake::MAINNET_MAGIC,
+            node_type: handshake::NodeType::Full,
             listen_address: "0.0.0.0".to_string(),
             port: 8333,
             bootstrap_nodes: vec![],
             max_peers: 50,
+            data_dir: "./data".to_string(),
             consensus_type: ConsensusType::ProofOfWork,
             mining_enabled: false,
             rpc_enabled: true,
             rpc_port: 8334,
+            send_queue_capacity: 256,
+            request_timeout_secs: 10,
+            rate_limits: ratelimit::RateLimitConfig::default(),
         }
     }
 }
@@ -382,17 +825,31 @@ impl Default for NetworkConfig {
 mod tests {
     use super::*;
 
+    /// Default config for a node keeping its data in a fresh temporary directory
+    fn test_config(node_id: &str) -> NetworkConfig {
+        let dir = std::env::temp_dir().join(format!("tribechain-network-{}", uuid::Uuid::new_v4()));
+        NetworkConfig {
+            node_id: node_id.to_string(),
+            data_dir: dir.to_string_lossy().into_owned(),
+            ..Default::default()
+        }
+    }
+
+    /// Hand the next frame `network` received to `handle_frame`
+    async fn deliver(network: &mut NetworkManager) -> TribeResult<()> {
+        let (peer_id, frame) = network.next_frame().await.unwrap();
+        network.handle_frame(&peer_id, frame).await
+    }
+
     #[tokio::test]
     async fn test_network_manager_creation() {
-        let config = NetworkConfig::default();
-        let network = NetworkManager::new(config);
+        let network = NetworkManager::new(test_config("node"));
         assert!(network.is_ok());
     }
 
     #[tokio::test]
     async fn test_network_start_stop() {
-        let config = NetworkConfig::default();
-        let mut network = NetworkManager::new(config).unwrap();
+        let mut network = NetworkManager::new(test_config("node")).unwrap();
         
         // Start network
         assert!(network.start().await.is_ok());
@@ -431,4 +888,51 @@ mod tests {
         assert!(dpos_json.contains("DelegatedProofOfStake"));
         assert!(tpow_json.contains("TensorProofOfWork"));
     }
-} 
\ No newline at end of file
+
+    /// Two nodes connected over an in-memory stream, not yet handshaken
+    fn connected_pair() -> (NetworkManager, NetworkManager) {
+        let mut alice = NetworkManager::new(test_config("alice")).unwrap();
+        let mut bob = NetworkManager::new(test_config("bob")).unwrap();
+        let (alice_end, bob_end) = tokio::io::duplex(64 * 1024);
+        alice.attach_peer("bob".to_string(), alice_end).unwrap();
+        bob.attach_peer("alice".to_string(), bob_end).unwrap();
+        (alice, bob)
+    }
+
+    /// Alice greets Bob, who answers with his own handshake
+    async fn handshake(alice: &mut NetworkManager, bob: &mut NetworkManager) {
+        alice.send_handshake("bob").unwrap();
+        deliver(bob).await.unwrap();
+        deliver(alice).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_messages_wait_for_the_handshake() {
+        let (mut alice, mut bob) = connected_pair();
+        bob.send_to_peer("alice", &p2p::NetworkMessage::new_ping("bob".to_string())).unwrap();
+        assert!(deliver(&mut alice).await.is_err());
+
+        handshake(&mut alice, &mut bob).await;
+        assert_eq!(alice.peers_with_feature(handshake::FEATURE_COMPACT_BLOCKS), vec!["bob".to_string()]);
+        assert_eq!(bob.network_status().peers.len(), 1);
+
+        bob.send_to_peer("alice", &p2p::NetworkMessage::new_ping("bob".to_string())).unwrap();
+        deliver(&mut alice).await.unwrap();
+        deliver(&mut bob).await.unwrap(); // Alice's pong
+    }
+
+    #[tokio::test]
+    async fn test_chunks_are_fetched_from_announcing_peers() {
+        let (mut alice, mut bob) = connected_pair();
+        handshake(&mut alice, &mut bob).await;
+
+        let data = vec![7u8; 32];
+        let hash = chunks::chunk_hash(&data);
+        alice.announce_chunks(vec![(hash.clone(), data.clone())]).unwrap();
+        deliver(&mut bob).await.unwrap();
+        bob.fetch_chunks(std::slice::from_ref(&hash)).unwrap();
+        deliver(&mut alice).await.unwrap();
+        deliver(&mut bob).await.unwrap();
+        assert_eq!(bob.take_received_chunks(), vec![(hash, data)]);
+    }
+}
@@ -0,0 +1,374 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use chrono::{DateTime, TimeZone, Utc};
+use tribechain_core::{
+    TribeResult, TribeError, TribeChain, Block, BlockTemplate, Transaction, TransactionType, ChainSpec, StorageConfig,
+    CHAIN_PRESETS,
+};
+use crate::NetworkConfig;
+use crate::orphans::OrphanPool;
+
+/// Subdirectory of the data dir holding the chain
+pub const CHAIN_DIR: &str = "chain";
+
+/// The local chain and mempool, plus the blocks from peers that didn't connect to it
+#[derive(Debug)]
+pub struct Node {
+    pub config: NetworkConfig,
+    pub blockchain: TribeChain,
+    pub orphans: OrphanPool,
+    pub started_at: DateTime<Utc>,
+    pub is_running: bool,
+}
+
+/// Chain summary served to peers and operators
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockchainInfo {
+    pub chain_id: u64,
+    pub height: u64,
+    pub best_block_hash: String,
+    pub difficulty: u64,
+    pub total_supply: u64,
+    pub finalized_height: u64,
+    pub orphan_blocks: usize,
+}
+
+/// Mempool summary
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MempoolInfo {
+    pub size: usize,
+    pub orphan_transactions: usize, // Waiting on an earlier nonce
+    pub total_fees: u64,
+}
+
+impl Node {
+    /// Open the chain in `config.data_dir` for the preset matching `config.chain_id`
+    pub fn new(config: NetworkConfig) -> TribeResult<Self> {
+        let spec = CHAIN_PRESETS.iter()
+            .filter_map(|name| ChainSpec::preset(name))
+            .find(|spec| spec.chain_id == config.chain_id)
+            .ok_or_else(|| TribeError::Network(format!("No chain preset for chain ID {}", config.chain_id)))?;
+        let path = Path::new(&config.data_dir).join(CHAIN_DIR);
+        let blockchain = TribeChain::with_chain_spec(&path.to_string_lossy(), &StorageConfig::default(), &spec)?;
+        Self::with_blockchain(config, blockchain)
+    }
+
+    /// Serve an already opened chain, which must be for `config.chain_id`
+    pub fn with_blockchain(config: NetworkConfig, blockchain: TribeChain) -> TribeResult<Self> {
+        if blockchain.chain_id != config.chain_id {
+            return Err(TribeError::Network(format!(
+                "Chain is for chain ID {}, node is configured for {}", blockchain.chain_id, config.chain_id
+            )));
+        }
+
+        Ok(Self {
+            config,
+            blockchain,
+            orphans: OrphanPool::default(),
+            started_at: Utc::now(),
+            is_running: false,
+        })
+    }
+
+    pub async fn start(&mut self) -> TribeResult<()> {
+        self.started_at = Utc::now();
+        self.is_running = true;
+        Ok(())
+    }
+
+    pub async fn stop(&mut self) -> TribeResult<()> {
+        self.blockchain.flush()?;
+        self.is_running = false;
+        Ok(())
+    }
+
+    pub fn add_transaction(&mut self, transaction: Transaction) -> TribeResult<()> {
+        self.blockchain.add_transaction(transaction)
+    }
+
+    /// Connect a block on top of the tip
+    pub fn add_block(&mut self, block: Block) -> TribeResult<()> {
+        self.blockchain.add_block(block)?;
+        self.orphans.record_accepted();
+        Ok(())
+    }
+
+    /// Connect consecutive blocks in one batch; returns how many connected before the
+    /// first one the chain refused
+    pub fn import_blocks(&mut self, blocks: Vec<Block>) -> TribeResult<usize> {
+        let height = self.get_block_count();
+        let result = self.blockchain.import_blocks(blocks);
+        for _ in height..self.get_block_count() {
+            self.orphans.record_accepted();
+        }
+        result
+    }
+
+    /// Connect a block from the network, keeping it as an orphan if its parent is unknown
+    /// or it extends a side fork. Returns every block that connected, in order.
+    /// Orphans waiting on a newly connected block are connected after it: during sync they
+    /// are the next stretch of the chain, so the run they form is imported as one batch with
+    /// its blocks checked in parallel.
+    pub fn receive_block(&mut self, block: Block, received_from: Option<String>) -> Vec<Block> {
+        let mut connected = Vec::new();
+        let mut queue = vec![(block, received_from)];
+
+        while let Some((block, received_from)) = queue.pop() {
+            match self.add_block(block.clone()) {
+                Ok(()) => {
+                    let run = self.take_orphan_run(&block.hash, &mut queue);
+                    connected.push(block);
+
+                    let imported = self.import_blocks(run.clone()).unwrap_or(0); // Stops at the first block it refuses
+                    let mut run = run.into_iter();
+                    connected.extend(run.by_ref().take(imported));
+                    // The refused block and those after it are retried one at a time, in order
+                    queue.extend(run.rev().map(|block| (block, None)));
+                }
+                Err(_) => {
+                    if self.get_block(block.hash.clone()).is_some() {
+                        continue; // Already on our chain
+                    }
+                    let Some(tip) = self.blockchain.blocks.last() else { continue };
+                    let parent = self.find_block(&block.previous_hash);
+                    // Invalid blocks are dropped rather than stored
+                    if let Some(reason) = OrphanPool::classify(&block, tip, parent.as_ref(), self.config.chain_id) {
+                        self.orphans.insert(block, received_from, reason);
+                    }
+                }
+            }
+        }
+        connected
+    }
+
+    /// Take the chain of orphans descending from `parent`, following the first child at
+    /// each step. Competing children are queued to be connected on their own.
+    fn take_orphan_run(&mut self, parent: &str, queue: &mut Vec<(Block, Option<String>)>) -> Vec<Block> {
+        let mut run: Vec<Block> = Vec::new();
+        loop {
+            let parent = run.last().map(|block| block.hash.as_str()).unwrap_or(parent);
+            let mut children = self.orphans.take_children(parent).into_iter();
+            let Some(next) = children.next() else { return run };
+            queue.extend(children.map(|child| (child, None)));
+            run.push(next);
+        }
+    }
+
+    /// Canonical block by hash
+    pub fn get_block(&self, hash: String) -> Option<Block> {
+        self.blockchain.blocks.iter().rev().find(|block| block.hash == hash).cloned()
+    }
+
+    /// Canonical or orphaned block by hash, so peers can fetch competing blocks
+    pub fn find_block(&self, hash: &str) -> Option<Block> {
+        self.get_block(hash.to_string())
+            .or_else(|| self.orphans.get(hash).cloned())
+    }
+
+    pub fn get_latest_block(&self) -> Option<Block> {
+        self.blockchain.get_latest_block().cloned()
+    }
+
+    /// Canonical blocks from `height`, at most `limit` of them
+    pub fn get_blocks_from(&self, height: u64, limit: usize) -> Vec<Block> {
+        self.blockchain.blocks.iter().skip(height as usize).take(limit).cloned().collect()
+    }
+
+    pub fn get_block_count(&self) -> u64 {
+        self.blockchain.blocks.len() as u64
+    }
+
+    pub fn get_transaction_count(&self) -> u64 {
+        self.blockchain.blocks.iter().map(|block| block.transactions.len() as u64).sum()
+    }
+
+    pub fn get_network_hashrate(&self, window: usize) -> f64 {
+        self.blockchain.get_network_hashrate(window)
+    }
+
+    pub fn get_uptime(&self) -> chrono::Duration {
+        Utc::now() - self.started_at
+    }
+
+    pub fn get_pending_transactions(&self) -> TribeResult<Vec<Transaction>> {
+        Ok(self.blockchain.pending_transactions.clone())
+    }
+
+    /// Next block for this node to mine, or None when no pending transaction is ready
+    pub fn get_block_template(&self) -> TribeResult<Option<BlockTemplate>> {
+        if !self.blockchain.has_ready_transactions() {
+            return Ok(None);
+        }
+        self.blockchain.get_block_template(&self.config.node_id, None).map(Some)
+    }
+
+    pub fn get_delegate_vote_tally(&self) -> HashMap<String, u64> {
+        self.blockchain.delegate_vote_tally()
+    }
+
+    pub fn get_blockchain_info(&self) -> BlockchainInfo {
+        let stats = self.blockchain.get_stats();
+        BlockchainInfo {
+            chain_id: self.blockchain.chain_id,
+            height: stats.block_count.saturating_sub(1),
+            best_block_hash: self.blockchain.blocks.last().map(|block| block.hash.clone()).unwrap_or_default(),
+            difficulty: stats.difficulty,
+            total_supply: stats.total_supply,
+            finalized_height: stats.finalized_height,
+            orphan_blocks: self.orphans.len(),
+        }
+    }
+
+    /// Next nonce a wallet should use for `address`, including queued transactions
+    pub fn get_account_nonce(&self, address: &str) -> u64 {
+        self.blockchain.get_pending_nonce(address)
+    }
+
+    pub fn estimate_fee(&self, target_blocks: u64) -> u64 {
+        self.blockchain.estimate_fee(target_blocks)
+    }
+
+    pub fn get_mempool_info(&self) -> MempoolInfo {
+        MempoolInfo {
+            size: self.blockchain.pending_transactions.len(),
+            orphan_transactions: self.blockchain.orphan_transactions.len(),
+            total_fees: self.blockchain.pending_transactions.iter().map(|tx| tx.fee).sum(),
+        }
+    }
+
+    pub fn get_mempool_size(&self) -> usize {
+        self.blockchain.pending_transactions.len()
+    }
+
+    /// Re-check every block's structure against its parent
+    pub fn validate_blockchain(&self) -> TribeResult<bool> {
+        for pair in self.blockchain.blocks.windows(2) {
+            if !pair[1].validate(Some(&pair[0]))? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Confirmed or pending transaction by hash
+    pub fn get_transaction(&self, hash: String) -> Option<Transaction> {
+        self.blockchain.get_transaction(&hash).cloned()
+            .or_else(|| self.blockchain.pending_transactions.iter().find(|tx| tx.hash == hash).cloned())
+    }
+
+    pub fn get_balance(&self, address: String) -> u64 {
+        self.blockchain.get_balance(&address)
+    }
+
+    pub fn get_last_block_time(&self) -> Option<DateTime<Utc>> {
+        let block = self.blockchain.get_latest_block()?;
+        Utc.timestamp_opt(block.timestamp as i64, 0).single()
+    }
+
+    pub fn create_transaction(&self, from: String, to: String, amount: u64, private_key: String) -> TribeResult<Transaction> {
+        self.signed_transaction(from, TransactionType::Transfer { to, amount }, &private_key)
+    }
+
+    pub fn create_contract_deployment(
+        &self,
+        deployer: String,
+        code: Vec<u8>,
+        constructor_args: Vec<u8>,
+        private_key: String,
+    ) -> TribeResult<Transaction> {
+        self.signed_transaction(deployer, TransactionType::ContractDeploy { code, constructor_args }, &private_key)
+    }
+
+    pub fn create_contract_call(
+        &self,
+        caller: String,
+        contract_address: String,
+        method: String,
+        args: Vec<u8>,
+        private_key: String,
+    ) -> TribeResult<Transaction> {
+        let call = TransactionType::ContractCall { contract_address, method, args, value: 0 };
+        self.signed_transaction(caller, call, &private_key)
+    }
+
+    /// Transaction from `from` at its next nonce and the current gas price for the next block
+    fn signed_transaction(&self, from: String, transaction_type: TransactionType, private_key: &str) -> TribeResult<Transaction> {
+        let nonce = self.get_account_nonce(&from);
+        let mut transaction = Transaction::new(from, transaction_type, 0, nonce)
+            .with_chain_id(self.config.chain_id)
+            .with_gas_price(self.estimate_fee(1));
+        transaction.validate_recipients()?;
+        transaction.sign(private_key)?;
+        Ok(transaction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orphans::OrphanReason;
+    use tribechain_core::DEVNET_CHAIN_ID;
+
+    fn dev_node() -> Node {
+        let chain = TribeChain::with_chain_spec("unused", &StorageConfig::memory(), &ChainSpec::dev()).unwrap();
+        let config = NetworkConfig { chain_id: DEVNET_CHAIN_ID, ..Default::default() };
+        Node::with_blockchain(config, chain).unwrap()
+    }
+
+    /// Blocks `count` deep mined on a copy of `node`'s chain, as a peer would relay them
+    fn mined_blocks(node: &Node, miner: &str, count: usize) -> Vec<Block> {
+        let mut chain = node.blockchain.clone();
+        chain.storage = None; // Keep the peer's writes out of the node's store
+        (0..count)
+            .map(|_| {
+                let mut block = chain.get_block_template(miner, None).unwrap().block;
+                block.mine_block(block.difficulty).unwrap();
+                chain.submit_block(block.clone()).unwrap();
+                block
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_orphans_connect_once_their_parent_arrives() {
+        let mut node = dev_node();
+        let blocks = mined_blocks(&node, "alice", 3);
+
+        // Children arriving first are held until the parent shows up
+        assert!(node.receive_block(blocks[2].clone(), Some("peer1".to_string())).is_empty());
+        assert!(node.receive_block(blocks[1].clone(), Some("peer1".to_string())).is_empty());
+        assert_eq!(node.orphans.len(), 2);
+        assert_eq!(node.find_block(&blocks[2].hash).map(|block| block.index), Some(3));
+        assert!(node.get_block(blocks[2].hash.clone()).is_none());
+
+        let connected = node.receive_block(blocks[0].clone(), None);
+        let hashes: Vec<&str> = connected.iter().map(|block| block.hash.as_str()).collect();
+        assert_eq!(hashes, blocks.iter().map(|block| block.hash.as_str()).collect::<Vec<_>>());
+        assert_eq!(node.get_block_count(), 4);
+        assert!(node.orphans.is_empty());
+        assert!(node.validate_blockchain().unwrap());
+    }
+
+    #[test]
+    fn test_competing_blocks_are_kept_and_invalid_ones_dropped() {
+        let mut node = dev_node();
+        let ours = mined_blocks(&node, "alice", 1);
+        let theirs = mined_blocks(&node, "bob", 1);
+
+        assert_eq!(node.receive_block(ours[0].clone(), None).len(), 1);
+        assert!(node.receive_block(theirs[0].clone(), Some("peer2".to_string())).is_empty());
+        assert_eq!(node.orphans.at_height(1)[0].reason, OrphanReason::Competing);
+        assert!(node.find_block(&theirs[0].hash).is_some());
+
+        // A block on our own tip that the chain refuses is invalid, not an orphan
+        let mut forged = mined_blocks(&node, "mallory", 1).remove(0);
+        forged.miner = "eve".to_string();
+        assert!(node.receive_block(forged.clone(), None).is_empty());
+        assert!(node.find_block(&forged.hash).is_none());
+
+        let stats = node.orphans.stats();
+        assert_eq!((stats.total_orphaned, stats.total_accepted), (1, 1));
+        assert_eq!(node.get_blockchain_info().orphan_blocks, 1);
+    }
+}
@@ -0,0 +1,213 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use chrono::{DateTime, Utc};
+use tribechain_core::{validation, Block};
+
+/// A block that did not make it onto the canonical chain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrphanBlock {
+    pub block: Block,
+    pub received_from: Option<String>,
+    pub received_at: DateTime<Utc>,
+    pub reason: OrphanReason,
+}
+
+/// Why a block was set aside instead of connected
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum OrphanReason {
+    UnknownParent, // Arrived before its parent
+    Competing, // Valid sibling of a canonical block (uncle)
+    Stale, // At or below the tip but not on our chain
+}
+
+/// Orphan counters for diagnosing propagation problems
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OrphanStats {
+    pub stored: usize,
+    pub total_orphaned: u64,
+    pub total_accepted: u64,
+    pub orphan_rate: f64, // Orphaned / (orphaned + accepted)
+}
+
+/// Bounded store of non-canonical blocks, kept so they can be served to peers
+/// and reconnected if their parent shows up later.
+#[derive(Debug, Clone)]
+pub struct OrphanPool {
+    pub max_orphans: usize,
+    blocks: HashMap<String, OrphanBlock>,
+    order: VecDeque<String>, // Oldest first, for eviction
+    total_orphaned: u64,
+    total_accepted: u64,
+}
+
+impl OrphanPool {
+    pub fn new(max_orphans: usize) -> Self {
+        Self {
+            max_orphans,
+            blocks: HashMap::new(),
+            order: VecDeque::new(),
+            total_orphaned: 0,
+            total_accepted: 0,
+        }
+    }
+
+    /// Classify a block the chain refused, relative to the current tip. Only blocks that
+    /// could still become canonical are kept: those whose parent hasn't arrived, and
+    /// side-fork blocks passing the checks that don't need the fork's state. A block
+    /// building on the tip was refused as invalid and gets None, as does any that fails
+    /// those checks.
+    pub fn classify(block: &Block, tip: &Block, parent: Option<&Block>, chain_id: u64) -> Option<OrphanReason> {
+        let Some(parent) = parent else { return Some(OrphanReason::UnknownParent) };
+        if block.previous_hash == tip.hash || validation::check_block(block, Some(parent), chain_id).is_err() {
+            return None;
+        }
+        Some(if block.index == tip.index { OrphanReason::Competing } else { OrphanReason::Stale })
+    }
+
+    /// Store a non-canonical block; returns false if it was already known
+    pub fn insert(&mut self, block: Block, received_from: Option<String>, reason: OrphanReason) -> bool {
+        if self.blocks.contains_key(&block.hash) {
+            return false;
+        }
+
+        self.total_orphaned += 1;
+        self.order.push_back(block.hash.clone());
+        self.blocks.insert(block.hash.clone(), OrphanBlock {
+            block,
+            received_from,
+            received_at: Utc::now(),
+            reason,
+        });
+
+        while self.blocks.len() > self.max_orphans {
+            match self.order.pop_front() {
+                Some(hash) => { self.blocks.remove(&hash); }
+                None => break,
+            }
+        }
+        true
+    }
+
+    /// Count a block that connected to the canonical chain
+    pub fn record_accepted(&mut self) {
+        self.total_accepted += 1;
+    }
+
+    pub fn get(&self, hash: &str) -> Option<&Block> {
+        self.blocks.get(hash).map(|orphan| &orphan.block)
+    }
+
+    /// Orphans waiting on `parent_hash`, removed so the caller can try to connect them
+    pub fn take_children(&mut self, parent_hash: &str) -> Vec<Block> {
+        let children: Vec<String> = self.blocks.values()
+            .filter(|orphan| orphan.block.previous_hash == parent_hash)
+            .map(|orphan| orphan.block.hash.clone())
+            .collect();
+
+        self.order.retain(|hash| !children.contains(hash));
+        let mut blocks: Vec<Block> = children.iter()
+            .filter_map(|hash| self.blocks.remove(hash))
+            .map(|orphan| orphan.block)
+            .collect();
+        blocks.sort_by_key(|block| block.index);
+        blocks
+    }
+
+    /// Competing blocks seen at `height`
+    pub fn at_height(&self, height: u64) -> Vec<&OrphanBlock> {
+        self.blocks.values().filter(|orphan| orphan.block.index == height).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    pub fn stats(&self) -> OrphanStats {
+        let seen = self.total_orphaned + self.total_accepted;
+        OrphanStats {
+            stored: self.blocks.len(),
+            total_orphaned: self.total_orphaned,
+            total_accepted: self.total_accepted,
+            orphan_rate: if seen > 0 { self.total_orphaned as f64 / seen as f64 } else { 0.0 },
+        }
+    }
+}
+
+impl Default for OrphanPool {
+    fn default() -> Self {
+        Self::new(500)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(index: u64, previous_hash: &str, miner: &str) -> Block {
+        let mut block = Block::new(index, previous_hash.to_string(), vec![], miner.to_string());
+        block.hash = block.calculate_hash();
+        block
+    }
+
+    #[test]
+    fn test_only_blocks_that_could_become_canonical_are_kept() {
+        let chain_id = tribechain_core::MAINNET_CHAIN_ID;
+        let mined = |index, previous: &Block, miner: &str| {
+            let mut block = Block::new(index, previous.hash.clone(), vec![], miner.to_string());
+            block.mine_block(1).unwrap();
+            block
+        };
+        let genesis = block(0, "0", "genesis");
+        let tip = mined(1, &genesis, "alice");
+
+        let early = block(7, "missing", "bob");
+        assert_eq!(OrphanPool::classify(&early, &tip, None, chain_id), Some(OrphanReason::UnknownParent));
+
+        // A sibling of the tip is an uncle; anything deeper on a side fork is stale
+        let uncle = mined(1, &genesis, "carol");
+        assert_eq!(OrphanPool::classify(&uncle, &tip, Some(&genesis), chain_id), Some(OrphanReason::Competing));
+        let fork = mined(2, &uncle, "carol");
+        let deeper = mined(1, &genesis, "dave");
+        assert_eq!(OrphanPool::classify(&deeper, &fork, Some(&genesis), chain_id), Some(OrphanReason::Stale));
+
+        // The chain refused a block on its own tip, so it is invalid
+        let child = mined(2, &tip, "bob");
+        assert_eq!(OrphanPool::classify(&child, &tip, Some(&tip), chain_id), None);
+
+        // So is a side-fork block failing the stateless checks
+        let mut forged = mined(1, &genesis, "mallory");
+        forged.miner = "eve".to_string();
+        assert_eq!(OrphanPool::classify(&forged, &tip, Some(&genesis), chain_id), None);
+        let skipped = mined(3, &genesis, "mallory");
+        assert_eq!(OrphanPool::classify(&skipped, &tip, Some(&genesis), chain_id), None);
+    }
+
+    #[test]
+    fn test_orphan_pool() {
+        let mut pool = OrphanPool::new(2);
+        let uncle = block(5, "parent", "alice");
+        let early = block(7, "missing", "bob");
+
+        assert!(pool.insert(uncle.clone(), Some("peer1".to_string()), OrphanReason::Competing));
+        assert!(!pool.insert(uncle.clone(), None, OrphanReason::Competing));
+        pool.insert(early.clone(), None, OrphanReason::UnknownParent);
+        for _ in 0..6 {
+            pool.record_accepted();
+        }
+
+        assert_eq!(pool.get(&uncle.hash).map(|b| b.miner.as_str()), Some("alice"));
+        assert_eq!(pool.stats().orphan_rate, 0.25);
+        assert_eq!(pool.take_children("missing").len(), 1);
+        assert!(pool.get(&early.hash).is_none());
+
+        // Oldest is evicted past capacity
+        pool.insert(block(8, "x", "carol"), None, OrphanReason::Stale);
+        pool.insert(block(9, "y", "dave"), None, OrphanReason::Stale);
+        assert_eq!(pool.len(), 2);
+        assert!(pool.get(&uncle.hash).is_none());
+    }
+}
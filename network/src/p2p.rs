@@ -0,0 +1,338 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::mpsc;
+use chrono::Utc;
+use tribechain_core::{TribeResult, TribeError, Block, Transaction, DoubleSignEvidence};
+use crate::NetworkConfig;
+use crate::compact::CompactBlock;
+use crate::framing::{Frame, FramedPeer};
+use crate::handshake::{Handshake, FEATURE_COMPACT_BLOCKS};
+use crate::ratelimit::{RateDecision, RateLimiter};
+
+pub use crate::peer::PeerInfo;
+pub use crate::protocol::{MessageType, NetworkMessage};
+
+/// Frames from all peers waiting to be handled; peer readers wait once it is full
+const INBOUND_QUEUE_CAPACITY: usize = 1024;
+
+/// Framed connections to peers, their handshake state and inbound rate limits
+#[derive(Debug)]
+pub struct P2PNetwork {
+    pub node_id: String,
+    pub max_peers: usize,
+    pub send_queue_capacity: usize, // Frames buffered per peer before sends are refused
+    pub request_timeout: Duration,
+    pub peers: HashMap<String, PeerInfo>,
+    pub rate_limiter: RateLimiter,
+    pub is_running: bool,
+    connections: HashMap<String, FramedPeer>,
+    inbound_tx: mpsc::Sender<(String, Frame)>,
+    inbound: mpsc::Receiver<(String, Frame)>,
+}
+
+impl P2PNetwork {
+    pub fn new(config: NetworkConfig) -> TribeResult<Self> {
+        let (inbound_tx, inbound) = mpsc::channel(INBOUND_QUEUE_CAPACITY);
+        Ok(Self {
+            node_id: config.node_id,
+            max_peers: config.max_peers,
+            send_queue_capacity: config.send_queue_capacity,
+            request_timeout: Duration::from_secs(config.request_timeout_secs),
+            peers: HashMap::new(),
+            rate_limiter: RateLimiter::new(config.rate_limits),
+            is_running: false,
+            connections: HashMap::new(),
+            inbound_tx,
+            inbound,
+        })
+    }
+
+    pub async fn start(&mut self) -> TribeResult<()> {
+        if self.is_running {
+            return Err(TribeError::Network("P2P network is already running".to_string()));
+        }
+        self.is_running = true;
+        Ok(())
+    }
+
+    pub async fn stop(&mut self) -> TribeResult<()> {
+        self.connections.clear();
+        self.peers.clear();
+        self.is_running = false;
+        Ok(())
+    }
+
+    /// Dial `address` and start framing the connection
+    pub async fn connect_peer(&mut self, address: String) -> TribeResult<()> {
+        if self.connections.contains_key(&address) {
+            return Ok(());
+        }
+
+        let stream = tokio::time::timeout(self.request_timeout, tokio::net::TcpStream::connect(&address)).await
+            .map_err(|_| TribeError::Network(format!("Connecting to {} timed out", address)))?
+            .map_err(|e| TribeError::Network(format!("Failed to connect to {}: {}", address, e)))?;
+        self.attach_peer(address, stream, true)
+    }
+
+    /// Start framing an established connection. Its inbound frames are delivered by `next_frame`.
+    pub fn attach_peer<S>(&mut self, peer_id: String, stream: S, outbound: bool) -> TribeResult<()>
+    where
+        S: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        if !self.connections.contains_key(&peer_id) && self.connections.len() >= self.max_peers {
+            return Err(TribeError::Network(format!("Refusing {}: already connected to {} peers", peer_id, self.max_peers)));
+        }
+
+        let (connection, mut frames) = FramedPeer::connect(stream, self.send_queue_capacity, self.request_timeout);
+        let inbound = self.inbound_tx.clone();
+        let source = peer_id.clone();
+        tokio::spawn(async move {
+            while let Some(frame) = frames.recv().await {
+                if inbound.send((source.clone(), frame)).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        self.connections.insert(peer_id.clone(), connection);
+        self.peers.insert(peer_id.clone(), PeerInfo::new(peer_id, outbound));
+        Ok(())
+    }
+
+    /// Next frame received from any peer, with the peer it came from
+    pub async fn next_frame(&mut self) -> Option<(String, Frame)> {
+        self.inbound.recv().await
+    }
+
+    /// Check an inbound frame's size before it is decoded
+    pub fn check_size(&mut self, peer_id: &str, size: usize) -> RateDecision {
+        self.rate_limiter.check_size(peer_id, size)
+    }
+
+    /// Charge a decoded message to the peer's overall and per-type token buckets
+    pub fn check_rate(&mut self, peer_id: &str, message_type: MessageType) -> RateDecision {
+        self.rate_limiter.check(peer_id, message_type)
+    }
+
+    /// Queue a message for a peer without waiting on its socket.
+    /// A peer whose queue is full is too slow to keep up and is dropped.
+    pub fn send(&mut self, peer_id: &str, message: &NetworkMessage) -> TribeResult<()> {
+        let payload = message.encode()?;
+        let connection = self.connections.get(peer_id)
+            .ok_or_else(|| TribeError::Network(format!("Peer {} is not connected", peer_id)))?;
+
+        if let Err(e) = connection.send(payload) {
+            self.drop_peer(peer_id);
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Send to every handshaken peer; returns how many could take the message
+    pub fn broadcast(&mut self, message: &NetworkMessage) -> usize {
+        self.ready_peers().iter()
+            .filter(|peer_id| self.send(peer_id, message).is_ok())
+            .count()
+    }
+
+    pub async fn broadcast_transaction(&mut self, transaction: Transaction) -> TribeResult<()> {
+        let message = NetworkMessage::new(MessageType::Transaction, self.node_id.clone(), serde_json::to_vec(&transaction)?);
+        self.broadcast(&message);
+        Ok(())
+    }
+
+    /// Relay a block as a compact block to peers that can rebuild it from their mempool;
+    /// the rest get the full block
+    pub fn broadcast_compact_block(&mut self, block: &Block) -> TribeResult<()> {
+        let compact = NetworkMessage::new(
+            MessageType::CompactBlock,
+            self.node_id.clone(),
+            serde_json::to_vec(&CompactBlock::from_block(block))?,
+        );
+        let full = NetworkMessage::new(MessageType::Block, self.node_id.clone(), serde_json::to_vec(block)?);
+
+        for peer_id in self.ready_peers() {
+            let message = if self.peers[&peer_id].supports(FEATURE_COMPACT_BLOCKS) { &compact } else { &full };
+            // A slow peer is dropped by send; keep relaying to the rest
+            let _ = self.send(&peer_id, message);
+        }
+        Ok(())
+    }
+
+    pub async fn broadcast_slashing_evidence(&mut self, evidence: DoubleSignEvidence) -> TribeResult<()> {
+        let message = NetworkMessage::new(MessageType::SlashingEvidence, self.node_id.clone(), serde_json::to_vec(&evidence)?);
+        self.broadcast(&message);
+        Ok(())
+    }
+
+    /// Store a peer's accepted handshake; messages are only relayed to it from here on
+    pub fn set_peer_handshake(&mut self, peer_id: &str, handshake: Handshake) -> TribeResult<()> {
+        let peer = self.peer_mut(peer_id)?;
+        peer.handshake = Some(handshake);
+        peer.last_seen = Utc::now();
+        Ok(())
+    }
+
+    pub fn send_handshake(&mut self, peer_id: &str, handshake: &Handshake) -> TribeResult<()> {
+        let message = NetworkMessage::new(MessageType::Handshake, self.node_id.clone(), serde_json::to_vec(handshake)?);
+        self.send(peer_id, &message)?;
+        self.peer_mut(peer_id)?.handshake_sent = true;
+        Ok(())
+    }
+
+    pub fn update_peer_activity(&mut self, peer_id: String) -> TribeResult<()> {
+        self.peer_mut(&peer_id)?.last_seen = Utc::now();
+        Ok(())
+    }
+
+    pub async fn disconnect_peer(&mut self, peer_id: String) -> TribeResult<()> {
+        self.drop_peer(&peer_id);
+        Ok(())
+    }
+
+    fn drop_peer(&mut self, peer_id: &str) {
+        self.connections.remove(peer_id);
+        self.peers.remove(peer_id);
+        self.rate_limiter.remove_peer(peer_id);
+    }
+
+    fn peer_mut(&mut self, peer_id: &str) -> TribeResult<&mut PeerInfo> {
+        self.peers.get_mut(peer_id)
+            .ok_or_else(|| TribeError::Network(format!("Peer {} is not connected", peer_id)))
+    }
+
+    pub fn get_peer(&self, peer_id: &str) -> Option<&PeerInfo> {
+        self.peers.get(peer_id)
+    }
+
+    pub fn get_peers(&self) -> Vec<PeerInfo> {
+        let mut peers: Vec<PeerInfo> = self.peers.values().cloned().collect();
+        peers.sort_by(|a, b| a.peer_id.cmp(&b.peer_id));
+        peers
+    }
+
+    pub fn get_peer_count(&self) -> usize {
+        self.connections.len()
+    }
+
+    pub fn is_ready(&self, peer_id: &str) -> bool {
+        self.peers.get(peer_id).is_some_and(PeerInfo::is_ready)
+    }
+
+    /// Handshaken peers, in a stable order
+    pub fn ready_peers(&self) -> Vec<String> {
+        let mut peers: Vec<String> = self.peers.values()
+            .filter(|peer| peer.is_ready())
+            .map(|peer| peer.peer_id.clone())
+            .collect();
+        peers.sort();
+        peers
+    }
+
+    /// Handshaken peers advertising `feature`
+    pub fn peers_with_feature(&self, feature: u32) -> Vec<String> {
+        self.ready_peers().into_iter()
+            .filter(|peer_id| self.peers[peer_id].supports(feature))
+            .collect()
+    }
+
+    /// Peer to sync from: the tallest one that can serve blocks
+    pub fn best_sync_peer(&self) -> Option<String> {
+        self.peers.values()
+            .filter(|peer| peer.serves_blocks())
+            .max_by_key(|peer| peer.best_height())
+            .map(|peer| peer.peer_id.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handshake::{FEATURE_AI3_TASKS, MAINNET_MAGIC};
+    use crate::ratelimit::RateLimitConfig;
+    use tribechain_core::MAINNET_CHAIN_ID;
+
+    fn network(node_id: &str) -> P2PNetwork {
+        P2PNetwork::new(NetworkConfig { node_id: node_id.to_string(), ..Default::default() }).unwrap()
+    }
+
+    fn handshake(node_id: &str) -> Handshake {
+        Handshake::new(MAINNET_MAGIC, MAINNET_CHAIN_ID, node_id.to_string())
+    }
+
+    /// Connect `remote` to `local` over an in-memory stream, both sides handshaken
+    fn link(local: &mut P2PNetwork, remote: &mut P2PNetwork, remote_handshake: Handshake) {
+        let (near, far) = tokio::io::duplex(64 * 1024);
+        local.attach_peer(remote.node_id.clone(), near, true).unwrap();
+        remote.attach_peer(local.node_id.clone(), far, false).unwrap();
+        local.set_peer_handshake(&remote.node_id, remote_handshake).unwrap();
+        let local_handshake = handshake(&local.node_id);
+        remote.set_peer_handshake(&local.node_id, local_handshake).unwrap();
+    }
+
+    async fn receive(network: &mut P2PNetwork) -> Option<(String, NetworkMessage)> {
+        let (peer_id, frame) = tokio::time::timeout(Duration::from_millis(200), network.next_frame()).await.ok()??;
+        Some((peer_id, NetworkMessage::decode(&frame.payload).unwrap()))
+    }
+
+    #[tokio::test]
+    async fn test_messages_reach_the_peer() {
+        let mut alice = network("alice");
+        let mut bob = network("bob");
+        link(&mut alice, &mut bob, handshake("bob"));
+
+        let ping = NetworkMessage::new_ping("alice".to_string());
+        alice.send("bob", &ping).unwrap();
+        assert_eq!(receive(&mut bob).await, Some(("alice".to_string(), ping)));
+        assert!(alice.send("carol", &NetworkMessage::new_ping("alice".to_string())).is_err());
+
+        alice.disconnect_peer("bob".to_string()).await.unwrap();
+        assert_eq!(alice.get_peer_count(), 0);
+        assert!(alice.get_peer("bob").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_compact_blocks_go_to_capable_peers() {
+        let mut alice = network("alice");
+        let mut bob = network("bob");
+        let mut carol = network("carol");
+        let mut dave = network("dave");
+        link(&mut alice, &mut bob, handshake("bob"));
+        link(&mut alice, &mut carol, handshake("carol").with_features(FEATURE_AI3_TASKS));
+        let (near, far) = tokio::io::duplex(64 * 1024);
+        alice.attach_peer("dave".to_string(), near, false).unwrap(); // No handshake yet
+        dave.attach_peer("alice".to_string(), far, true).unwrap();
+
+        assert_eq!(alice.peers_with_feature(FEATURE_COMPACT_BLOCKS), vec!["bob".to_string()]);
+
+        let mut block = Block::new(1, "0".repeat(64), Vec::new(), "alice".to_string());
+        block.hash = block.calculate_hash();
+        alice.broadcast_compact_block(&block).unwrap();
+
+        assert_eq!(receive(&mut bob).await.unwrap().1.message_type, MessageType::CompactBlock);
+        assert_eq!(receive(&mut carol).await.unwrap().1.message_type, MessageType::Block);
+        assert!(receive(&mut dave).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_flooding_peer_is_disconnected() {
+        let config = NetworkConfig {
+            rate_limits: RateLimitConfig { max_violations: 2, ..Default::default() },
+            ..Default::default()
+        };
+        let mut alice = P2PNetwork::new(config).unwrap();
+        let mut bob = network("bob");
+        link(&mut alice, &mut bob, handshake("bob"));
+
+        let decisions: Vec<RateDecision> = (0..7).map(|_| alice.check_rate("bob", MessageType::SyncRequest)).collect();
+        assert!(decisions[..5].iter().all(|decision| *decision == RateDecision::Allow));
+        assert!(matches!(decisions[5], RateDecision::Drop(_)));
+        assert!(matches!(decisions[6], RateDecision::Disconnect(_)));
+        // Message types without their own limit only count against the peer's overall budget
+        assert_eq!(alice.check_rate("bob", MessageType::Transaction), RateDecision::Allow);
+
+        alice.disconnect_peer("bob".to_string()).await.unwrap();
+        assert_eq!(alice.rate_limiter.violations("bob"), 0);
+    }
+}
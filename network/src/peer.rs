@@ -0,0 +1,70 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use crate::handshake::Handshake;
+
+/// A connected peer as tracked by `P2PNetwork`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeerInfo {
+    pub peer_id: String, // Address we dialled, or the one the connection was accepted from
+    pub outbound: bool,
+    pub connected_at: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    pub handshake: Option<Handshake>, // Set once the peer's handshake is accepted
+    pub handshake_sent: bool,
+}
+
+impl PeerInfo {
+    pub fn new(peer_id: String, outbound: bool) -> Self {
+        let now = Utc::now();
+        Self {
+            peer_id,
+            outbound,
+            connected_at: now,
+            last_seen: now,
+            handshake: None,
+            handshake_sent: false,
+        }
+    }
+
+    /// Whether the peer proved it is on our network; nothing else is relayed to it before
+    pub fn is_ready(&self) -> bool {
+        self.handshake.is_some()
+    }
+
+    pub fn supports(&self, feature: u32) -> bool {
+        self.handshake.as_ref().is_some_and(|handshake| handshake.supports(feature))
+    }
+
+    pub fn serves_blocks(&self) -> bool {
+        self.handshake.as_ref().is_some_and(Handshake::serves_blocks)
+    }
+
+    pub fn best_height(&self) -> u64 {
+        self.handshake.as_ref().map(|handshake| handshake.best_height).unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handshake::{NodeType, FEATURE_COMPACT_BLOCKS, MAINNET_MAGIC};
+    use tribechain_core::MAINNET_CHAIN_ID;
+
+    #[test]
+    fn test_capabilities_come_from_the_handshake() {
+        let mut peer = PeerInfo::new("10.0.0.1:8333".to_string(), true);
+        assert!(!peer.is_ready());
+        assert!(!peer.supports(FEATURE_COMPACT_BLOCKS));
+        assert!(!peer.serves_blocks());
+
+        peer.handshake = Some(
+            Handshake::new(MAINNET_MAGIC, MAINNET_CHAIN_ID, "node-b".to_string())
+                .with_node_type(NodeType::Light)
+                .with_best_height(42),
+        );
+        assert!(peer.is_ready());
+        assert!(peer.supports(FEATURE_COMPACT_BLOCKS));
+        assert!(!peer.serves_blocks());
+        assert_eq!(peer.best_height(), 42);
+    }
+}
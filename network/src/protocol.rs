@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use tribechain_core::{TribeResult, TribeError};
+
+/// What a `NetworkMessage` carries; `data` holds the JSON payload for the type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MessageType {
+    Transaction,
+    Block,
+    CompactBlock, // Header plus short transaction IDs, rebuilt from the mempool
+    GetBlockTransactions, // Transactions missing from a compact block
+    BlockTransactions,
+    Ping,
+    Pong,
+    Handshake, // First message on every connection
+    GetAddr,
+    Addr,
+    SyncRequest,
+    SyncResponse,
+    SlashingEvidence,
+    PerformanceReport,
+    ChunkAnnounce,
+    GetChunks,
+    ChunkData,
+}
+
+/// Envelope for every message exchanged between peers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NetworkMessage {
+    pub id: String,
+    pub message_type: MessageType,
+    pub sender: String, // Node ID of the peer that sent it
+    pub data: Vec<u8>,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl NetworkMessage {
+    pub fn new(message_type: MessageType, sender: String, data: Vec<u8>) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            message_type,
+            sender,
+            data,
+            timestamp: Utc::now(),
+        }
+    }
+
+    pub fn new_ping(sender: String) -> Self {
+        Self::new(MessageType::Ping, sender, Vec::new())
+    }
+
+    pub fn new_pong(sender: String) -> Self {
+        Self::new(MessageType::Pong, sender, Vec::new())
+    }
+
+    /// Payload of a frame on the wire
+    pub fn encode(&self) -> TribeResult<Vec<u8>> {
+        bincode::serialize(self)
+            .map_err(|e| TribeError::Network(format!("Failed to encode message: {}", e)))
+    }
+
+    pub fn decode(payload: &[u8]) -> TribeResult<Self> {
+        bincode::deserialize(payload)
+            .map_err(|e| TribeError::Network(format!("Malformed message: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_round_trip() {
+        let message = NetworkMessage::new(MessageType::SyncRequest, "node-a".to_string(), b"{}".to_vec());
+        assert_eq!(NetworkMessage::decode(&message.encode().unwrap()).unwrap(), message);
+        assert!(NetworkMessage::decode(&[0xff; 3]).is_err());
+
+        let pong = NetworkMessage::new_pong("node-b".to_string());
+        assert_eq!(pong.message_type, MessageType::Pong);
+        assert!(pong.data.is_empty());
+    }
+}
@@ -0,0 +1,185 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Instant;
+use crate::protocol::MessageType;
+
+/// Refill rate and burst size of a token bucket
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RateLimit {
+    pub per_second: f64,
+    pub burst: f64,
+}
+
+/// Limits applied to every peer's inbound traffic
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    pub per_peer: RateLimit, // All messages from one peer
+    pub per_message_type: HashMap<MessageType, RateLimit>, // Tighter limits for expensive message types
+    pub max_message_size: usize, // Checked before deserialization
+    pub max_violations: u32, // Rejected messages before the peer is disconnected
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        let mut per_message_type = HashMap::new();
+        per_message_type.insert(MessageType::SyncRequest, RateLimit { per_second: 1.0, burst: 5.0 });
+        per_message_type.insert(MessageType::GetBlockTransactions, RateLimit { per_second: 5.0, burst: 20.0 });
+        per_message_type.insert(MessageType::Ping, RateLimit { per_second: 1.0, burst: 3.0 });
+
+        Self {
+            per_peer: RateLimit { per_second: 100.0, burst: 500.0 },
+            per_message_type,
+            max_message_size: 4 * 1024 * 1024,
+            max_violations: 20,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    pub limit: RateLimit,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(limit: RateLimit, now: Instant) -> Self {
+        Self {
+            limit,
+            tokens: limit.burst,
+            last_refill: now,
+        }
+    }
+
+    pub fn try_take(&mut self, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.limit.per_second).min(self.limit.burst);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// What to do with an inbound message
+#[derive(Debug, Clone, PartialEq)]
+pub enum RateDecision {
+    Allow,
+    Drop(String), // Reject this message only
+    Disconnect(String), // Peer keeps flooding
+}
+
+/// Per-peer and per-message-type token buckets
+#[derive(Debug, Clone, Default)]
+pub struct RateLimiter {
+    pub config: RateLimitConfig,
+    peers: HashMap<String, TokenBucket>,
+    message_types: HashMap<(String, MessageType), TokenBucket>,
+    violations: HashMap<String, u32>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    /// Check a message's size before it is decoded
+    pub fn check_size(&mut self, peer_id: &str, size: usize) -> RateDecision {
+        if size > self.config.max_message_size {
+            return self.violation(peer_id, format!("Message of {} bytes exceeds limit", size));
+        }
+        RateDecision::Allow
+    }
+
+    pub fn check(&mut self, peer_id: &str, message_type: MessageType) -> RateDecision {
+        self.check_at(peer_id, message_type, Instant::now())
+    }
+
+    pub fn check_at(&mut self, peer_id: &str, message_type: MessageType, now: Instant) -> RateDecision {
+        let per_peer = self.config.per_peer;
+        let peer_ok = self.peers.entry(peer_id.to_string())
+            .or_insert_with(|| TokenBucket::new(per_peer, now))
+            .try_take(now);
+        if !peer_ok {
+            return self.violation(peer_id, "Peer message rate exceeded".to_string());
+        }
+
+        if let Some(&limit) = self.config.per_message_type.get(&message_type) {
+            let type_ok = self.message_types.entry((peer_id.to_string(), message_type))
+                .or_insert_with(|| TokenBucket::new(limit, now))
+                .try_take(now);
+            if !type_ok {
+                return self.violation(peer_id, format!("{:?} rate exceeded", message_type));
+            }
+        }
+
+        RateDecision::Allow
+    }
+
+    fn violation(&mut self, peer_id: &str, reason: String) -> RateDecision {
+        let count = self.violations.entry(peer_id.to_string()).or_insert(0);
+        *count += 1;
+        if *count >= self.config.max_violations {
+            RateDecision::Disconnect(reason)
+        } else {
+            RateDecision::Drop(reason)
+        }
+    }
+
+    /// Forget a disconnected peer's buckets and violations
+    pub fn remove_peer(&mut self, peer_id: &str) {
+        self.peers.remove(peer_id);
+        self.message_types.retain(|(peer, _), _| peer != peer_id);
+        self.violations.remove(peer_id);
+    }
+
+    pub fn violations(&self, peer_id: &str) -> u32 {
+        self.violations.get(peer_id).copied().unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_sync_request_flood_disconnects() {
+        let mut limiter = RateLimiter::new(RateLimitConfig { max_violations: 3, ..Default::default() });
+        let start = Instant::now();
+
+        for _ in 0..5 {
+            assert_eq!(limiter.check_at("peer1", MessageType::SyncRequest, start), RateDecision::Allow);
+        }
+        assert!(matches!(limiter.check_at("peer1", MessageType::SyncRequest, start), RateDecision::Drop(_)));
+
+        // Other message types and other peers are unaffected
+        assert_eq!(limiter.check_at("peer1", MessageType::Transaction, start), RateDecision::Allow);
+        assert_eq!(limiter.check_at("peer2", MessageType::SyncRequest, start), RateDecision::Allow);
+
+        // Bucket refills over time
+        assert_eq!(limiter.check_at("peer1", MessageType::SyncRequest, start + Duration::from_secs(1)), RateDecision::Allow);
+
+        assert!(matches!(limiter.check_at("peer1", MessageType::SyncRequest, start + Duration::from_secs(1)), RateDecision::Drop(_)));
+        assert!(matches!(limiter.check_size("peer1", 64 * 1024 * 1024), RateDecision::Disconnect(_)));
+
+        limiter.remove_peer("peer1");
+        assert_eq!(limiter.violations("peer1"), 0);
+    }
+
+    #[test]
+    fn test_config_names_message_types() {
+        let json = serde_json::to_string(&RateLimitConfig::default()).unwrap();
+        assert!(json.contains("\"SyncRequest\""));
+
+        let config: RateLimitConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(config.per_message_type[&MessageType::Ping], RateLimit { per_second: 1.0, burst: 3.0 });
+    }
+}
@@ -0,0 +1,390 @@
+use serde_json::{json, Value};
+use sha3::{Digest, Keccak256};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tribechain_contracts::{normalize_evm_address, BridgeDeposit};
+use tribechain_core::{TribeError, TribeResult};
+
+/// Event the bridge contract on the EVM chain emits for each deposit. `token` and
+/// `sender` are indexed; `recipient` is the TribeChain address to credit.
+pub const DEPOSIT_EVENT: &str = "Deposit(address,address,string,uint256)";
+
+/// Confirmations waited for by default before a deposit is reported
+pub const DEFAULT_CONFIRMATIONS: u64 = 12;
+
+/// Most blocks one `eth_getLogs` query covers, so catching up stays within node limits
+const MAX_BLOCKS_PER_POLL: u64 = 2_000;
+
+/// Largest EVM RPC response read
+const MAX_RESPONSE: usize = 16 * 1024 * 1024;
+
+fn relay_error(message: impl Into<String>) -> TribeError {
+    TribeError::Bridge(message.into())
+}
+
+/// `topics[0]` of logs of the event with `signature`, as Solidity computes it
+pub fn event_topic(signature: &str) -> String {
+    format!("0x{}", hex::encode(Keccak256::digest(signature.as_bytes())))
+}
+
+pub fn deposit_topic() -> String {
+    event_topic(DEPOSIT_EVENT)
+}
+
+/// Where the relayer watches for deposits
+#[derive(Debug, Clone, PartialEq)]
+pub struct RelayerConfig {
+    pub rpc_url: String, // http:// JSON-RPC endpoint of an EVM node
+    pub remote_chain_id: u64,
+    pub bridge_address: String, // Contract emitting `DEPOSIT_EVENT`
+    pub confirmations: u64, // Blocks a deposit must be buried under before it is reported
+    pub start_block: u64, // First block scanned
+}
+
+impl RelayerConfig {
+    pub fn new(rpc_url: &str, remote_chain_id: u64, bridge_address: &str) -> Self {
+        Self {
+            rpc_url: rpc_url.to_string(),
+            remote_chain_id,
+            bridge_address: bridge_address.to_string(),
+            confirmations: DEFAULT_CONFIRMATIONS,
+            start_block: 0,
+        }
+    }
+
+    pub fn with_confirmations(mut self, confirmations: u64) -> Self {
+        self.confirmations = confirmations;
+        self
+    }
+
+    pub fn with_start_block(mut self, start_block: u64) -> Self {
+        self.start_block = start_block;
+        self
+    }
+}
+
+/// JSON-RPC client for an EVM node, one HTTP/1.1 request per call
+#[derive(Debug, Clone)]
+pub struct EvmClient {
+    host: String,
+    port: u16,
+    path: String,
+    next_id: u64,
+}
+
+impl EvmClient {
+    /// `url` is `http://host[:port][/path]`; TLS endpoints need a local proxy
+    pub fn new(url: &str) -> TribeResult<Self> {
+        let rest = url.strip_prefix("http://")
+            .ok_or_else(|| relay_error(format!("EVM RPC URL {} must start with http://", url)))?;
+        let (authority, path) = match rest.find('/') {
+            Some(index) => (&rest[..index], &rest[index..]),
+            None => (rest, "/"),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().map_err(|_| relay_error(format!("Invalid port in {}", url)))?),
+            None => (authority, 80),
+        };
+        if host.is_empty() {
+            return Err(relay_error(format!("No host in {}", url)));
+        }
+        Ok(Self { host: host.to_string(), port, path: path.to_string(), next_id: 1 })
+    }
+
+    pub async fn call(&mut self, method: &str, params: Value) -> TribeResult<Value> {
+        let body = serde_json::to_vec(&json!({ "jsonrpc": "2.0", "id": self.next_id, "method": method, "params": params }))
+            .map_err(|e| relay_error(format!("Failed to encode request: {}", e)))?;
+        self.next_id += 1;
+
+        let mut stream = TcpStream::connect((self.host.as_str(), self.port)).await
+            .map_err(|e| relay_error(format!("Failed to connect to {}:{}: {}", self.host, self.port, e)))?;
+        let head = format!(
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            self.path, self.host, body.len()
+        );
+        stream.write_all(head.as_bytes()).await.map_err(|e| relay_error(e.to_string()))?;
+        stream.write_all(&body).await.map_err(|e| relay_error(e.to_string()))?;
+        let mut response = Vec::new();
+        stream.take(MAX_RESPONSE as u64 + 1).read_to_end(&mut response).await.map_err(|e| relay_error(e.to_string()))?;
+        if response.len() > MAX_RESPONSE {
+            return Err(relay_error("EVM RPC response too large"));
+        }
+
+        let body = http_body(&response)?;
+        let response: Value = serde_json::from_slice(&body)
+            .map_err(|e| relay_error(format!("Invalid EVM RPC response: {}", e)))?;
+        match response.get("error") {
+            Some(error) => Err(relay_error(format!("EVM node refused {}: {}", method, error["message"].as_str().unwrap_or("unknown error")))),
+            None => Ok(response.get("result").cloned().unwrap_or(Value::Null)),
+        }
+    }
+}
+
+/// Body of an HTTP response, checking for a 200 status and undoing chunked encoding
+fn http_body(response: &[u8]) -> TribeResult<Vec<u8>> {
+    let split = response.windows(4).position(|window| window == b"\r\n\r\n")
+        .ok_or_else(|| relay_error("Truncated HTTP response"))?;
+    let head = String::from_utf8_lossy(&response[..split]);
+    let body = &response[split + 4..];
+    let status = head.lines().next().and_then(|line| line.split_whitespace().nth(1)).unwrap_or_default();
+    if status != "200" {
+        return Err(relay_error(format!("EVM RPC answered HTTP {}", status)));
+    }
+    let chunked = head.lines().any(|line| {
+        line.split_once(':').is_some_and(|(name, value)| {
+            name.trim().eq_ignore_ascii_case("transfer-encoding") && value.trim().eq_ignore_ascii_case("chunked")
+        })
+    });
+    if !chunked {
+        return Ok(body.to_vec());
+    }
+
+    let (mut decoded, mut rest) = (Vec::new(), body);
+    loop {
+        let line_end = rest.windows(2).position(|window| window == b"\r\n")
+            .ok_or_else(|| relay_error("Truncated chunk"))?;
+        let size_field = String::from_utf8_lossy(&rest[..line_end]);
+        let size = usize::from_str_radix(size_field.split(';').next().unwrap_or_default().trim(), 16)
+            .map_err(|_| relay_error("Invalid chunk size"))?;
+        rest = &rest[line_end + 2..];
+        if size == 0 {
+            return Ok(decoded);
+        }
+        if rest.len() < size {
+            return Err(relay_error("Truncated chunk"));
+        }
+        decoded.extend_from_slice(&rest[..size]);
+        rest = rest.get(size + 2..).unwrap_or_default();
+    }
+}
+
+/// Parse a `0x`-prefixed hex quantity
+fn quantity(value: &Value) -> TribeResult<u64> {
+    value.as_str()
+        .and_then(|hex| u64::from_str_radix(hex.trim_start_matches("0x"), 16).ok())
+        .ok_or_else(|| relay_error(format!("Invalid quantity {}", value)))
+}
+
+/// The 32-byte word at `index` of ABI-encoded `data`
+fn word(data: &[u8], index: usize) -> TribeResult<&[u8]> {
+    data.get(index * 32..(index + 1) * 32).ok_or_else(|| relay_error("Truncated event data"))
+}
+
+/// A word holding a small integer, e.g. an offset or a length
+fn word_usize(word: &[u8]) -> TribeResult<usize> {
+    if word[..24].iter().any(|byte| *byte != 0) {
+        return Err(relay_error("Event field out of range"));
+    }
+    Ok(u64::from_be_bytes(word[24..].try_into().unwrap_or_default()) as usize)
+}
+
+/// Address held in an indexed topic
+fn topic_address(topic: &Value) -> TribeResult<String> {
+    let topic = topic.as_str().ok_or_else(|| relay_error("Missing topic"))?;
+    let hex_part = topic.trim_start_matches("0x");
+    if hex_part.len() != 64 || !hex_part.is_ascii() {
+        return Err(relay_error(format!("Invalid topic {}", topic)));
+    }
+    normalize_evm_address(&format!("0x{}", &hex_part[24..]))
+}
+
+/// Read a deposit from a log `eth_getLogs` returned for `DEPOSIT_EVENT`
+pub fn decode_deposit(log: &Value, remote_chain_id: u64) -> TribeResult<BridgeDeposit> {
+    let topics = log["topics"].as_array().ok_or_else(|| relay_error("Log has no topics"))?;
+    if topics.len() != 3 || topics[0].as_str().map(str::to_lowercase) != Some(deposit_topic()) {
+        return Err(relay_error("Not a deposit event"));
+    }
+    let data = log["data"].as_str()
+        .and_then(|data| hex::decode(data.trim_start_matches("0x")).ok())
+        .ok_or_else(|| relay_error("Invalid event data"))?;
+
+    // data is (string recipient, uint256 amount): the string's offset, the amount, then the string
+    let offset = word_usize(word(&data, 0)?)?;
+    let amount = word(&data, 1)?;
+    if amount[..16].iter().any(|byte| *byte != 0) {
+        return Err(relay_error("Deposit amount out of range"));
+    }
+    let amount = u128::from_be_bytes(amount[16..].try_into().unwrap_or_default());
+    if offset % 32 != 0 {
+        return Err(relay_error("Misaligned recipient"));
+    }
+    let length = word_usize(word(&data, offset / 32)?)?;
+    let recipient = (offset + 32).checked_add(length)
+        .and_then(|end| data.get(offset + 32..end))
+        .ok_or_else(|| relay_error("Truncated recipient"))?;
+    let recipient = String::from_utf8(recipient.to_vec()).map_err(|_| relay_error("Recipient is not UTF-8"))?;
+
+    Ok(BridgeDeposit {
+        remote_chain_id,
+        tx_hash: log["transactionHash"].as_str().ok_or_else(|| relay_error("Log has no transaction hash"))?.to_lowercase(),
+        log_index: quantity(&log["logIndex"])?,
+        remote_token: topic_address(&topics[1])?,
+        sender: topic_address(&topics[2])?,
+        recipient,
+        amount,
+    })
+}
+
+/// Watches the EVM bridge contract for deposits, reporting each once it is
+/// `confirmations` blocks deep
+#[derive(Debug, Clone)]
+pub struct BridgeRelayer {
+    pub config: RelayerConfig,
+    pub next_block: u64, // First block not scanned yet
+    client: EvmClient,
+}
+
+impl BridgeRelayer {
+    /// Connect to the EVM node, which must be on `config.remote_chain_id`
+    pub async fn connect(config: RelayerConfig) -> TribeResult<Self> {
+        let mut client = EvmClient::new(&config.rpc_url)?;
+        let chain_id = quantity(&client.call("eth_chainId", json!([])).await?)?;
+        if chain_id != config.remote_chain_id {
+            return Err(relay_error(format!("EVM node is on chain {}, not {}", chain_id, config.remote_chain_id)));
+        }
+        normalize_evm_address(&config.bridge_address)?;
+        Ok(Self { next_block: config.start_block, config, client })
+    }
+
+    /// Deposits in confirmed blocks not scanned yet. Logs that don't decode are skipped.
+    pub async fn poll(&mut self) -> TribeResult<Vec<BridgeDeposit>> {
+        let head = quantity(&self.client.call("eth_blockNumber", json!([])).await?)?;
+        let Some(confirmed) = head.checked_sub(self.config.confirmations) else {
+            return Ok(Vec::new());
+        };
+        if confirmed < self.next_block {
+            return Ok(Vec::new());
+        }
+        let to_block = confirmed.min(self.next_block + MAX_BLOCKS_PER_POLL - 1);
+
+        let filter = json!({
+            "fromBlock": format!("0x{:x}", self.next_block),
+            "toBlock": format!("0x{:x}", to_block),
+            "address": self.config.bridge_address,
+            "topics": [deposit_topic()],
+        });
+        let logs = self.client.call("eth_getLogs", json!([filter])).await?;
+        let deposits = logs.as_array().map(Vec::as_slice).unwrap_or_default().iter()
+            .filter(|log| log["removed"] != json!(true))
+            .filter_map(|log| match decode_deposit(log, self.config.remote_chain_id) {
+                Ok(deposit) => Some(deposit),
+                Err(e) => {
+                    eprintln!("Skipping bridge log {}: {}", log["transactionHash"], e);
+                    None
+                }
+            })
+            .collect();
+        self.next_block = to_block + 1;
+        Ok(deposits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncBufReadExt, BufReader};
+    use tokio::net::TcpListener;
+
+    /// ABI encoding of `(string recipient, uint256 amount)`
+    fn deposit_data(recipient: &str, amount: u128) -> String {
+        let mut data = vec![0u8; 64];
+        data[31] = 0x40;
+        data[48..64].copy_from_slice(&amount.to_be_bytes());
+        let mut length = [0u8; 32];
+        length[24..].copy_from_slice(&(recipient.len() as u64).to_be_bytes());
+        data.extend_from_slice(&length);
+        data.extend_from_slice(recipient.as_bytes());
+        data.resize(data.len().div_ceil(32) * 32, 0);
+        format!("0x{}", hex::encode(data))
+    }
+
+    fn deposit_log(recipient: &str, amount: u128) -> Value {
+        json!({
+            "transactionHash": "0xAB",
+            "logIndex": "0x2",
+            "topics": [
+                deposit_topic(),
+                format!("0x{:0>64}", "00000000000000000000000000000000000000a1"),
+                format!("0x{:0>64}", "00000000000000000000000000000000000000C1"),
+            ],
+            "data": deposit_data(recipient, amount),
+            "removed": false,
+        })
+    }
+
+    #[test]
+    fn test_decode_deposit() {
+        assert_eq!(
+            event_topic("Transfer(address,address,uint256)"),
+            "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"
+        );
+        let deposit = decode_deposit(&deposit_log("trb1qqqq", 5 * 10u128.pow(18)), 5).unwrap();
+        assert_eq!(deposit.remote_token, "0x00000000000000000000000000000000000000a1");
+        assert_eq!(deposit.sender, "0x00000000000000000000000000000000000000c1");
+        assert_eq!((deposit.recipient.as_str(), deposit.amount, deposit.log_index), ("trb1qqqq", 5 * 10u128.pow(18), 2));
+        assert_eq!(deposit.tx_hash, "0xab");
+
+        let mut truncated = deposit_log("trb1qqqq", 1);
+        truncated["data"] = json!("0x00");
+        assert!(decode_deposit(&truncated, 5).is_err());
+        let mut other_event = deposit_log("trb1qqqq", 1);
+        other_event["topics"][0] = json!(format!("0x{}", "11".repeat(32)));
+        assert!(decode_deposit(&other_event, 5).is_err());
+    }
+
+    #[test]
+    fn test_chunked_body() {
+        let response = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n4\r\n{\"a\"\r\n3\r\n:1}\r\n0\r\n\r\n";
+        assert_eq!(http_body(response).unwrap(), b"{\"a\":1}");
+        assert!(http_body(b"HTTP/1.1 502 Bad Gateway\r\n\r\n").is_err());
+        assert!(EvmClient::new("https://example.org").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_poll_confirmed_deposits() {
+        // Stands in for an EVM node 20 blocks in, with one deposit in its logs
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/rpc", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).await.unwrap();
+                    if let Some(length) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                        content_length = length.trim().parse().unwrap();
+                    }
+                    if line == "\r\n" {
+                        break;
+                    }
+                }
+                let mut body = vec![0u8; content_length];
+                reader.read_exact(&mut body).await.unwrap();
+                let request: Value = serde_json::from_slice(&body).unwrap();
+                let result = match request["method"].as_str().unwrap() {
+                    "eth_chainId" => json!("0x5"),
+                    "eth_blockNumber" => json!("0x14"),
+                    "eth_getLogs" => {
+                        assert_eq!(request["params"][0]["toBlock"], json!("0x8"));
+                        json!([deposit_log("alice", 7), { "removed": true }])
+                    }
+                    _ => Value::Null,
+                };
+                let body = json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }).to_string();
+                let response = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}", body.len(), body);
+                reader.get_mut().write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        let config = RelayerConfig::new(&url, 5, "0x00000000000000000000000000000000000000b0");
+        assert!(BridgeRelayer::connect(RelayerConfig { remote_chain_id: 1, ..config.clone() }).await.is_err());
+        let mut relayer = BridgeRelayer::connect(config).await.unwrap();
+        let deposits = relayer.poll().await.unwrap();
+        assert_eq!(deposits.len(), 1);
+        assert_eq!((deposits[0].recipient.as_str(), deposits[0].amount), ("alice", 7));
+        assert_eq!(relayer.next_block, 9);
+        assert!(relayer.poll().await.unwrap().is_empty()); // Nothing newer is confirmed
+    }
+}
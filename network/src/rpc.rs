@@ -0,0 +1,1749 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{Mutex, RwLock};
+use tribechain_core::{check_address_input, Block, LogFilter, MissingParent, TelemetryStore, TensorTask, Transaction, TransactionType, TribeChain, TribeError, TribeResult};
+use tribechain_contracts::{ContractCall, ContractEngine};
+use tribechain_mining::{MiningPool, MAX_POOL_ROUNDS};
+use ai3_lib::TaskDistributor;
+use crate::eth::{ETH_METHODS, ETH_WRITE_METHODS};
+use crate::gateway::{GatewayRequest, MiningGateway, GATEWAY_PREFIX};
+use crate::graphql::GraphqlEndpoint;
+
+/// JSON-RPC 2.0 error codes; failures from the node itself carry `TribeError::code`
+pub const PARSE_ERROR: i32 = -32700;
+pub const METHOD_NOT_FOUND: i32 = -32601;
+pub const INVALID_PARAMS: i32 = -32602;
+pub const INVALID_REQUEST: i32 = -32600;
+
+/// HTTP path GraphQL queries are posted to
+pub const GRAPHQL_PATH: &str = "/graphql";
+
+/// Largest HTTP request body accepted
+const MAX_HTTP_BODY: usize = 4 * 1024 * 1024;
+
+/// Methods that change the chain, answered by `RpcServer::handle_mut`
+pub const WRITE_METHODS: &[&str] = &["sendTransaction", "submitTensorTask", "submitBlock"];
+
+/// Methods answered by `RpcServer::handle_with_contracts`
+pub const CONTRACT_METHODS: &[&str] = &[
+    "getTwap", "getContractAbi", "encodeContractCall", "getBridgeTransfers", "getIbcChannel", "getIbcProof",
+];
+
+/// Methods answered by `RpcServer::handle_contract_query`, which runs contract code
+pub const CONTRACT_QUERY_METHODS: &[&str] = &["queryContract"];
+
+/// Methods answered by `RpcServer::handle_with_telemetry`
+pub const TELEMETRY_METHODS: &[&str] = &["pushTelemetry", "getDeviceMetrics", "getTelemetryAlerts", "getDevices"];
+
+/// Methods answered by `RpcServer::handle_with_pool`
+pub const POOL_METHODS: &[&str] = &["getPoolStats", "getPoolMiner", "getPoolRounds"];
+
+/// Methods whose first parameter, `address`, is an account address
+pub const ADDRESS_METHODS: &[&str] = &[
+    "getBalance", "getStateProof", "getAccountNonce", "getTransactionsForAddress", "scanAddress",
+];
+
+/// Methods answered by `RpcServer::handle_with_network`
+pub const NETWORK_METHODS: &[&str] = &["getNetworkStatus"];
+
+/// Peer and sync view the node publishes for `getNetworkStatus`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NetworkStatus {
+    pub peers: Vec<PeerStatus>,
+    pub syncing: bool,
+}
+
+/// A handshaken peer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PeerStatus {
+    pub peer_id: String,
+    pub node_type: crate::handshake::NodeType,
+    pub best_height: u64,
+    pub protocol_version: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcRequest {
+    pub jsonrpc: String,
+    pub id: Value,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcError {
+    pub code: i32,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>, // {"kind": ...} naming the error's domain
+}
+
+impl RpcError {
+    /// The node error this carries, if it is one
+    pub fn to_tribe_error(&self) -> Option<TribeError> {
+        TribeError::from_code(self.code, self.message.clone())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcResponse {
+    pub jsonrpc: String,
+    pub id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+}
+
+impl RpcResponse {
+    pub fn success(id: Value, result: Value) -> Self {
+        Self { jsonrpc: "2.0".to_string(), id, result: Some(result), error: None }
+    }
+
+    pub fn failure(id: Value, code: i32, message: impl Into<String>) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            id,
+            result: None,
+            error: Some(RpcError { code, message: message.into(), data: None }),
+        }
+    }
+
+    /// Failure carrying a node error's code and domain
+    pub fn from_error(id: Value, error: &TribeError) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            id,
+            result: None,
+            error: Some(RpcError {
+                code: error.code(),
+                message: error.message().to_string(),
+                data: Some(json!({ "kind": error.kind() })),
+            }),
+        }
+    }
+}
+
+/// JSON-RPC request dispatcher over the local chain
+#[derive(Debug, Clone)]
+pub struct RpcServer {
+    pub port: u16,
+    pub is_running: bool,
+}
+
+impl RpcServer {
+    pub fn new(port: u16) -> TribeResult<Self> {
+        Ok(Self { port, is_running: false })
+    }
+
+    pub async fn start(&mut self) -> TribeResult<()> {
+        if self.is_running {
+            return Err(TribeError::Rpc("RPC server is already running".to_string()));
+        }
+        self.is_running = true;
+        Ok(())
+    }
+
+    pub async fn stop(&mut self) -> TribeResult<()> {
+        self.is_running = false;
+        Ok(())
+    }
+
+    /// Parse and answer a raw JSON-RPC request body
+    pub fn handle_raw(&self, chain: &TribeChain, body: &[u8]) -> RpcResponse {
+        match serde_json::from_slice::<RpcRequest>(body) {
+            Ok(request) => self.handle(chain, request),
+            Err(e) => RpcResponse::failure(Value::Null, PARSE_ERROR, format!("Parse error: {}", e)),
+        }
+    }
+
+    /// Answer contract-state methods from `engine`, anything else from the chain
+    pub fn handle_with_contracts(&self, chain: &TribeChain, engine: &ContractEngine, request: RpcRequest) -> RpcResponse {
+        let id = request.id.clone();
+        match request.method.as_str() {
+            "getTwap" => {
+                let pool_id = match param_str(&request.params, 0, "pool_id") {
+                    Some(pool_id) => pool_id,
+                    None => return RpcResponse::failure(id, INVALID_PARAMS, "Missing pool_id"),
+                };
+                let window = param(&request.params, 1, "window_secs").and_then(Value::as_u64).unwrap_or(86400);
+                match engine.get_twap(&pool_id, window) {
+                    Ok(twap) => RpcResponse::success(id, json!({ "pool_id": pool_id, "window_secs": window, "twap": twap })),
+                    Err(e) => RpcResponse::from_error(id, &e),
+                }
+            }
+            "getContractAbi" => {
+                let address = match param_str(&request.params, 0, "contract_address") {
+                    Some(address) => address,
+                    None => return RpcResponse::failure(id, INVALID_PARAMS, "Missing contract_address"),
+                };
+                match engine.get_contract_state(&address) {
+                    Some(contract) => RpcResponse::success(id, json!(contract.metadata.abi)),
+                    None => RpcResponse::failure(id, INVALID_PARAMS, "Contract not found"),
+                }
+            }
+            "getBridgeTransfers" => {
+                let bridge_id = match param_str(&request.params, 0, "bridge_id") {
+                    Some(bridge_id) => bridge_id,
+                    None => return RpcResponse::failure(id, INVALID_PARAMS, "Missing bridge_id"),
+                };
+                let Some(bridge) = engine.get_bridge(&bridge_id) else {
+                    return RpcResponse::failure(id, INVALID_PARAMS, "Bridge not found");
+                };
+                let from_nonce = param(&request.params, 1, "from_nonce").and_then(Value::as_u64).unwrap_or(1);
+                // Remote amounts exceed what JSON numbers hold exactly, so they are strings
+                let transfers: Vec<Value> = bridge.outbound_since(from_nonce).iter()
+                    .map(|transfer| json!({
+                        "nonce": transfer.nonce,
+                        "token_id": transfer.token_id,
+                        "remote_token": transfer.remote_token,
+                        "sender": transfer.sender,
+                        "recipient": transfer.recipient,
+                        "amount": transfer.amount,
+                        "remote_amount": transfer.remote_amount.to_string(),
+                        "block_height": transfer.block_height,
+                    }))
+                    .collect();
+                RpcResponse::success(id, json!({
+                    "bridge_id": bridge.id,
+                    "remote_chain_id": bridge.remote_chain_id,
+                    "remote_bridge": bridge.remote_bridge,
+                    "paused": bridge.paused,
+                    "locked": bridge.locked,
+                    "transfers": transfers,
+                }))
+            }
+            "getIbcChannel" => {
+                let channel_id = match param_str(&request.params, 0, "channel_id") {
+                    Some(channel_id) => channel_id,
+                    None => return RpcResponse::failure(id, INVALID_PARAMS, "Missing channel_id"),
+                };
+                let channel = match engine.ibc.channel(&channel_id) {
+                    Ok(channel) => channel,
+                    Err(e) => return RpcResponse::from_error(id, &e),
+                };
+                // Relayers deliver the pending packets and carry the acks back
+                let mut pending: Vec<_> = engine.ibc.sent.values().filter(|packet| packet.source_channel == channel_id).collect();
+                pending.sort_by_key(|packet| packet.sequence);
+                let counterparty = channel.counterparty_channel_id.clone().unwrap_or_default();
+                let acks: serde_json::Map<String, Value> = engine.ibc.acks.iter()
+                    .filter(|(key, _)| key.split_once('/').map(|(source, _)| source) == Some(counterparty.as_str()))
+                    .map(|(key, ack)| (key.clone(), json!(ack)))
+                    .collect();
+                RpcResponse::success(id, json!({
+                    "channel": channel,
+                    "client": engine.ibc.clients.get(&channel.client_id),
+                    "pending": pending,
+                    "acks": acks,
+                }))
+            }
+            "getIbcProof" => {
+                let key = match param_str(&request.params, 0, "key") {
+                    Some(key) => key,
+                    None => return RpcResponse::failure(id, INVALID_PARAMS, "Missing key"),
+                };
+                let Some(block) = chain.get_latest_block() else {
+                    return RpcResponse::from_error(id, &TribeError::Blockchain("Chain has no blocks".to_string()));
+                };
+                // The header is for this chain's validators to sign; the proof verifies under its root
+                RpcResponse::success(id, json!({
+                    "header": engine.ibc.header(chain.chain_id, block.index, &block.hash),
+                    "proof": engine.ibc.prove(&key),
+                }))
+            }
+            "encodeContractCall" => {
+                let (address, method) = match (
+                    param_str(&request.params, 0, "contract_address"),
+                    param_str(&request.params, 1, "method"),
+                ) {
+                    (Some(address), Some(method)) => (address, method),
+                    _ => return RpcResponse::failure(id, INVALID_PARAMS, "Missing contract_address or method"),
+                };
+                let args = param(&request.params, 2, "args").and_then(Value::as_array).cloned().unwrap_or_default();
+                let signature = match engine.get_contract_state(&address).and_then(|c| c.get_method_signature(&method)) {
+                    Some(signature) => signature,
+                    None => return RpcResponse::failure(id, INVALID_PARAMS, format!("No ABI entry for {}", method)),
+                };
+
+                // Encoded args are checked the same way the engine checks them before execution
+                let encoded = signature.encode_json_inputs(&args).and_then(|data| {
+                    let call = ContractCall::new(address.clone(), method.clone(), data.clone(), String::new());
+                    engine.validate_call(&call).map(|_| data)
+                });
+                match encoded {
+                    Ok(data) => RpcResponse::success(id, json!({ "data": hex::encode(data) })),
+                    Err(e) => RpcResponse::from_error(id, &e),
+                }
+            }
+            _ => self.handle(chain, request),
+        }
+    }
+
+    /// Accept device telemetry pushes and answer metric and alert queries from `telemetry`,
+    /// anything else from the chain
+    pub fn handle_with_telemetry(&self, chain: &TribeChain, telemetry: &mut TelemetryStore, request: RpcRequest) -> RpcResponse {
+        let id = request.id.clone();
+        match request.method.as_str() {
+            "pushTelemetry" => {
+                let device_id = match param_str(&request.params, 0, "device_id") {
+                    Some(device_id) => device_id,
+                    None => return RpcResponse::failure(id, INVALID_PARAMS, "Missing device_id"),
+                };
+                let metrics = param(&request.params, 1, "metrics").and_then(Value::as_object).and_then(|metrics| {
+                    metrics.iter()
+                        .map(|(name, value)| value.as_f64().map(|value| (name.clone(), value)))
+                        .collect::<Option<Vec<_>>>()
+                });
+                let metrics = match metrics {
+                    Some(metrics) => metrics,
+                    None => return RpcResponse::failure(id, INVALID_PARAMS, "Missing or non-numeric metrics"),
+                };
+                let timestamp = param(&request.params, 2, "timestamp")
+                    .and_then(Value::as_u64)
+                    .unwrap_or_else(|| chrono::Utc::now().timestamp() as u64);
+                match telemetry.record(&device_id, timestamp, &metrics) {
+                    Ok(alerts) => RpcResponse::success(id, json!({ "alerts": alerts })),
+                    Err(e) => RpcResponse::from_error(id, &e),
+                }
+            }
+            "getDeviceMetrics" => {
+                let device_id = match param_str(&request.params, 0, "device_id") {
+                    Some(device_id) => device_id,
+                    None => return RpcResponse::failure(id, INVALID_PARAMS, "Missing device_id"),
+                };
+                let to = param(&request.params, 2, "to")
+                    .and_then(Value::as_u64)
+                    .unwrap_or_else(|| chrono::Utc::now().timestamp() as u64);
+                let from = param(&request.params, 1, "from").and_then(Value::as_u64).unwrap_or(to.saturating_sub(3600));
+                RpcResponse::success(id, json!({
+                    "device_id": device_id,
+                    "buckets": telemetry.get_device_metrics(&device_id, from, to),
+                }))
+            }
+            "getTelemetryAlerts" => {
+                let device_id = param_str(&request.params, 0, "device_id");
+                RpcResponse::success(id, json!(telemetry.recent_alerts(device_id.as_deref())))
+            }
+            "getDevices" => {
+                let mut devices: Vec<Value> = telemetry.devices.iter()
+                    .map(|(device_id, series)| json!({
+                        "device_id": device_id,
+                        "last_seen": series.last_seen,
+                        "metrics": telemetry.latest_metrics(device_id)
+                            .map(|bucket| bucket.metrics.iter().map(|(name, summary)| (name.clone(), json!(summary.mean()))).collect::<serde_json::Map<_, _>>())
+                            .unwrap_or_default(),
+                        "alerting": telemetry.breached_metrics(device_id),
+                    }))
+                    .collect();
+                devices.sort_by(|a, b| a["device_id"].as_str().cmp(&b["device_id"].as_str()));
+                RpcResponse::success(id, json!(devices))
+            }
+            _ => self.handle(chain, request),
+        }
+    }
+
+    /// Answer mining pool dashboard queries from `pool`, anything else from the chain
+    pub fn handle_with_pool(&self, chain: &TribeChain, pool: &MiningPool, request: RpcRequest) -> RpcResponse {
+        let id = request.id.clone();
+        match request.method.as_str() {
+            "getPoolStats" => {
+                // Round history is paged through `getPoolRounds`
+                let mut stats = json!(pool.stats);
+                if let Some(stats) = stats.as_object_mut() {
+                    stats.remove("rounds");
+                }
+                RpcResponse::success(id, stats)
+            }
+            "getPoolMiner" => {
+                let Some(miner_id) = param_str(&request.params, 0, "miner_id") else {
+                    return RpcResponse::failure(id, INVALID_PARAMS, "Missing miner_id");
+                };
+                let Some(stats) = pool.stats.miners.get(&miner_id) else {
+                    return RpcResponse::failure(id, INVALID_PARAMS, format!("Miner {} hasn't submitted any shares", miner_id));
+                };
+                RpcResponse::success(id, json!({
+                    "miner_id": miner_id,
+                    "share_difficulty": pool.share_difficulty(&miner_id),
+                    "pending_payout": pool.ledger.pending_balance(&miner_id),
+                    "round_shares": pool.stats.current_round.accepted.get(&miner_id).copied().unwrap_or(0),
+                    "stats": stats,
+                }))
+            }
+            "getPoolRounds" => {
+                let count = param(&request.params, 0, "count").and_then(Value::as_u64).unwrap_or(20) as usize;
+                let rounds: Vec<_> = pool.stats.rounds.iter().rev().take(count.min(MAX_POOL_ROUNDS)).collect();
+                RpcResponse::success(id, json!(rounds))
+            }
+            _ => self.handle(chain, request),
+        }
+    }
+
+    /// Answer peer and sync queries from `network`, anything else from the chain
+    pub fn handle_with_network(&self, chain: &TribeChain, network: &NetworkStatus, request: RpcRequest) -> RpcResponse {
+        let id = request.id.clone();
+        match request.method.as_str() {
+            "getNetworkStatus" => {
+                let height = chain.blocks.len().saturating_sub(1) as u64;
+                let best_peer_height = network.peers.iter().map(|peer| peer.best_height).max().unwrap_or(0);
+                RpcResponse::success(id, json!({
+                    "height": height,
+                    "best_peer_height": best_peer_height,
+                    "behind": best_peer_height.saturating_sub(height),
+                    "syncing": network.syncing,
+                    "peers": network.peers,
+                }))
+            }
+            _ => self.handle(chain, request),
+        }
+    }
+
+    pub fn handle(&self, chain: &TribeChain, request: RpcRequest) -> RpcResponse {
+        let id = request.id.clone();
+        // A mistyped address is an error rather than an empty account
+        if ADDRESS_METHODS.contains(&request.method.as_str()) {
+            if let Some(Err(e)) = param_str(&request.params, 0, "address").map(|address| check_address_input(&address, chain.chain_id)) {
+                return RpcResponse::failure(id, INVALID_PARAMS, e.to_string());
+            }
+        }
+        match request.method.as_str() {
+            "getChainInfo" => RpcResponse::success(id, json!({
+                "chain_id": chain.chain_id,
+                "height": chain.blocks.len().saturating_sub(1),
+                "best_hash": chain.get_latest_block().map(|block| block.hash.clone()),
+                "finalized_height": chain.finalized_height(),
+            })),
+            "getStats" => RpcResponse::success(id, json!(chain.get_stats())),
+            "getBlockNumber" => RpcResponse::success(id, json!(chain.blocks.len().saturating_sub(1))),
+            "getBlock" => match param(&request.params, 0, "block") {
+                Some(Value::Number(index)) => RpcResponse::success(id, json!(index.as_u64().and_then(|index| chain.get_block(index)))),
+                Some(Value::String(hash)) => RpcResponse::success(id, json!(chain.blocks.iter().find(|block| &block.hash == hash))),
+                _ => RpcResponse::failure(id, INVALID_PARAMS, "Missing block height or hash"),
+            },
+            "getPendingTensorTasks" => RpcResponse::success(id, json!(chain.get_pending_tensor_tasks())),
+            "getBlockTemplate" => {
+                let Some(miner) = param_str(&request.params, 0, "miner") else {
+                    return RpcResponse::failure(id, INVALID_PARAMS, "Missing miner address");
+                };
+                let ai3_proof = match param(&request.params, 1, "ai3_proof").filter(|proof| !proof.is_null()) {
+                    Some(proof) => match serde_json::from_value(proof.clone()) {
+                        Ok(proof) => Some(proof),
+                        Err(e) => return RpcResponse::failure(id, INVALID_PARAMS, format!("Invalid AI3 proof: {}", e)),
+                    },
+                    None => None,
+                };
+                match chain.get_block_template(&miner, ai3_proof) {
+                    Ok(template) => RpcResponse::success(id, json!(template)),
+                    Err(e) => RpcResponse::from_error(id, &e),
+                }
+            }
+            "getMiningStatus" => {
+                let window = param(&request.params, 0, "window").and_then(Value::as_u64).unwrap_or(100);
+                RpcResponse::success(id, json!(chain.mining_status(window as usize)))
+            }
+            "getNetworkHashrate" => {
+                let window = param(&request.params, 0, "window").and_then(Value::as_u64).unwrap_or(100);
+                RpcResponse::success(id, json!(chain.get_network_hashrate(window as usize)))
+            }
+            "getDifficultyHistory" => {
+                let Some(from) = param(&request.params, 0, "from").and_then(Value::as_u64) else {
+                    return RpcResponse::failure(id, INVALID_PARAMS, "Missing from block");
+                };
+                let to = param(&request.params, 1, "to").and_then(Value::as_u64).unwrap_or(u64::MAX);
+                match chain.get_difficulty_history(from, to) {
+                    Ok(samples) => RpcResponse::success(id, json!(samples)),
+                    Err(e) => RpcResponse::from_error(id, &e),
+                }
+            }
+            "getBalance" => match param_str(&request.params, 0, "address") {
+                Some(address) => {
+                    let min_confirmations = param(&request.params, 1, "min_confirmations").and_then(Value::as_u64).unwrap_or(0);
+                    RpcResponse::success(id, json!(chain.get_confirmed_balance(&address, min_confirmations)))
+                }
+                None => RpcResponse::failure(id, INVALID_PARAMS, "Missing address"),
+            },
+            "getStateProof" => match param_str(&request.params, 0, "address") {
+                Some(address) => RpcResponse::success(id, json!({
+                    "block_index": chain.blocks.len().saturating_sub(1),
+                    "proof": chain.get_state_proof(&address),
+                })),
+                None => RpcResponse::failure(id, INVALID_PARAMS, "Missing address"),
+            },
+            "getAccountNonce" => match param_str(&request.params, 0, "address") {
+                Some(address) => RpcResponse::success(id, json!({
+                    "nonce": chain.get_account_nonce(&address),
+                    "pending_nonce": chain.get_pending_nonce(&address),
+                })),
+                None => RpcResponse::failure(id, INVALID_PARAMS, "Missing address"),
+            },
+            "getTransactionsForAddress" => {
+                let address = match param_str(&request.params, 0, "address") {
+                    Some(address) => address,
+                    None => return RpcResponse::failure(id, INVALID_PARAMS, "Missing address"),
+                };
+                let page = param(&request.params, 1, "page").and_then(Value::as_u64).unwrap_or(0) as usize;
+
+                let transactions: Vec<Value> = chain.get_transactions_for_address(&address, page)
+                    .into_iter()
+                    .map(|(tx_ref, tx)| json!({
+                        "block_index": tx_ref.block_index,
+                        "block_hash": tx_ref.block_hash,
+                        "transaction": tx,
+                    }))
+                    .collect();
+                RpcResponse::success(id, json!({ "address": address, "page": page, "transactions": transactions }))
+            }
+            "scanAddress" => {
+                let address = match param_str(&request.params, 0, "address") {
+                    Some(address) => address,
+                    None => return RpcResponse::failure(id, INVALID_PARAMS, "Missing address"),
+                };
+                let from = param(&request.params, 1, "from_block").and_then(Value::as_u64).unwrap_or(0);
+                let to = param(&request.params, 2, "to_block").and_then(Value::as_u64).unwrap_or(u64::MAX);
+
+                let transactions: Vec<Value> = chain.scan_address(&address, from, to)
+                    .into_iter()
+                    .map(|(tx_ref, tx)| json!({
+                        "block_index": tx_ref.block_index,
+                        "block_hash": tx_ref.block_hash,
+                        "confirmations": chain.get_confirmations(tx_ref.block_index, &tx_ref.block_hash),
+                        "transaction": tx,
+                    }))
+                    .collect();
+                // Mempool transactions touching the address, which have no confirmations yet
+                let pending: Vec<&Transaction> = chain.pending_transactions.iter()
+                    .filter(|tx| tx.involved_addresses().contains(&address))
+                    .collect();
+                RpcResponse::success(id, json!({ "address": address, "transactions": transactions, "pending": pending }))
+            }
+            "estimateFee" => {
+                let target_blocks = param(&request.params, 0, "target_blocks").and_then(Value::as_u64).unwrap_or(1);
+                RpcResponse::success(id, json!({
+                    "target_blocks": target_blocks,
+                    "gas_price": chain.estimate_fee(target_blocks),
+                }))
+            }
+            "getTransactionReceipt" => match param_str(&request.params, 0, "tx_hash") {
+                Some(tx_hash) => RpcResponse::success(id, json!(chain.get_receipt(&tx_hash))),
+                None => RpcResponse::failure(id, INVALID_PARAMS, "Missing tx_hash"),
+            },
+            "getLogs" => {
+                let filter = param(&request.params, 0, "filter")
+                    .cloned()
+                    .map(serde_json::from_value::<LogFilter>)
+                    .unwrap_or_else(|| Ok(LogFilter::default()));
+                match filter {
+                    Ok(filter) => RpcResponse::success(id, json!(chain.get_logs(&filter))),
+                    Err(e) => RpcResponse::failure(id, INVALID_PARAMS, format!("Invalid filter: {}", e)),
+                }
+            }
+            method => RpcResponse::failure(id, METHOD_NOT_FOUND, format!("Method not found: {}", method)),
+        }
+    }
+
+    /// Answer `WRITE_METHODS`, anything else from the chain
+    /// Answer `queryContract`, running a view method against `engine` without a transaction
+    pub fn handle_contract_query(&self, chain: &TribeChain, engine: &mut ContractEngine, request: RpcRequest) -> RpcResponse {
+        let id = request.id.clone();
+        if request.method != "queryContract" {
+            return self.handle(chain, request);
+        }
+        let (address, method) = match (
+            param_str(&request.params, 0, "contract_address"),
+            param_str(&request.params, 1, "method"),
+        ) {
+            (Some(address), Some(method)) => (address, method),
+            _ => return RpcResponse::failure(id, INVALID_PARAMS, "Missing contract_address or method"),
+        };
+        let args = param(&request.params, 2, "args").and_then(Value::as_array).cloned().unwrap_or_default();
+        let caller = param_str(&request.params, 3, "caller").unwrap_or_default();
+        let signature = engine.get_contract_state(&address).and_then(|c| c.get_method_signature(&method)).cloned();
+        let data = match &signature {
+            Some(signature) => signature.encode_json_inputs(&args),
+            None if args.is_empty() => Ok(Vec::new()), // Contracts without an ABI take no JSON arguments
+            None => return RpcResponse::failure(id, INVALID_PARAMS, format!("No ABI entry for {}", method)),
+        };
+
+        match data.and_then(|data| engine.query_contract(ContractCall::new(address, method, data, caller))) {
+            Ok(result) => {
+                // Return data that doesn't match the declared outputs is still shown raw
+                let outputs = signature.filter(|_| result.success).and_then(|signature| signature.decode_json_outputs(&result.return_data).ok());
+                RpcResponse::success(id, json!({
+                    "success": result.success,
+                    "gas_used": result.gas_used,
+                    "return_data": hex::encode(&result.return_data),
+                    "outputs": outputs,
+                    "error": result.error,
+                }))
+            }
+            Err(e) => RpcResponse::from_error(id, &e),
+        }
+    }
+
+    pub fn handle_mut(&self, chain: &mut TribeChain, request: RpcRequest) -> RpcResponse {
+        let id = request.id.clone();
+        match request.method.as_str() {
+            "sendTransaction" => {
+                let transaction = match param_transaction(&request.params) {
+                    Ok(transaction) => transaction,
+                    Err(message) => return RpcResponse::failure(id, INVALID_PARAMS, message),
+                };
+                let hash = transaction.hash.clone();
+                match chain.add_transaction(transaction) {
+                    Ok(()) => RpcResponse::success(id, json!({ "tx_hash": hash, "orphan": chain.orphan_transactions.contains(&hash) })),
+                    Err(e) => RpcResponse::from_error(id, &e),
+                }
+            }
+            "submitTensorTask" => {
+                let transaction = match param_transaction(&request.params) {
+                    Ok(transaction) => transaction,
+                    Err(message) => return RpcResponse::failure(id, INVALID_PARAMS, message),
+                };
+                let TransactionType::TensorCompute { operation, input_data, expected_output_size, max_computation_time, reward } =
+                    transaction.transaction_type.clone()
+                else {
+                    return RpcResponse::failure(id, INVALID_PARAMS, "Not a tensor compute transaction");
+                };
+                // The task is named after the transaction paying for it
+                let task = TensorTask {
+                    id: transaction.hash.clone(),
+                    operation,
+                    input_data,
+                    expected_output_size,
+                    max_computation_time,
+                    reward,
+                    requester: transaction.from.clone(),
+                    completed: false,
+                    result: None,
+                    assigned_miner: None,
+                    created_at: transaction.timestamp,
+                };
+                let hash = transaction.hash.clone();
+                match chain.add_transaction(transaction).and_then(|()| chain.add_tensor_task(task)) {
+                    Ok(()) => RpcResponse::success(id, json!({ "tx_hash": hash, "task_id": hash })),
+                    Err(e) => RpcResponse::from_error(id, &e),
+                }
+            }
+            "submitBlock" => {
+                let block: Block = match param(&request.params, 0, "block").map(|block| serde_json::from_value(block.clone())) {
+                    Some(Ok(block)) => block,
+                    Some(Err(e)) => return RpcResponse::failure(id, INVALID_PARAMS, format!("Invalid block: {}", e)),
+                    None => return RpcResponse::failure(id, INVALID_PARAMS, "Missing block"),
+                };
+                let (index, hash) = (block.index, block.hash.clone());
+                match chain.submit_block(block) {
+                    Ok(()) => RpcResponse::success(id, json!({ "index": index, "hash": hash })),
+                    Err(e) => RpcResponse::from_error(id, &e),
+                }
+            }
+            _ => self.handle(chain, request),
+        }
+    }
+
+    /// Answer `WRITE_METHODS` with `engine` attached: a transaction using a token or contract
+    /// the engine doesn't have yet is held as an orphan, and orphans whose token or contract
+    /// has appeared since are queued
+    pub fn handle_mut_with_contracts(&self, chain: &mut TribeChain, engine: &ContractEngine, request: RpcRequest) -> RpcResponse {
+        let exists = |parent: &MissingParent| match parent {
+            MissingParent::Token(token_id) => engine.get_token_info(token_id).is_some(),
+            MissingParent::Contract(address) => engine.get_contract_state(address).is_some(),
+            MissingParent::Nonce { .. } => false,
+        };
+        chain.promote_orphans(exists);
+        if request.method != "sendTransaction" {
+            return self.handle_mut(chain, request);
+        }
+
+        let id = request.id.clone();
+        let transaction = match param_transaction(&request.params) {
+            Ok(transaction) => transaction,
+            Err(message) => return RpcResponse::failure(id, INVALID_PARAMS, message),
+        };
+        let hash = transaction.hash.clone();
+        let result = match MissingParent::referenced_by(&transaction).filter(|parent| !exists(parent)) {
+            Some(missing) => chain.hold_orphan(transaction, missing),
+            None => chain.add_transaction(transaction),
+        };
+        match result {
+            Ok(()) => RpcResponse::success(id, json!({ "tx_hash": hash, "orphan": chain.orphan_transactions.contains(&hash) })),
+            Err(e) => RpcResponse::from_error(id, &e),
+        }
+    }
+
+    /// Serve JSON-RPC over TCP, one request per line answered by one response line,
+    /// until the listener fails
+    pub async fn listen(&self, addr: &str, state: RpcState) -> TribeResult<()> {
+        let listener = TcpListener::bind(addr).await
+            .map_err(|e| TribeError::Rpc(format!("Failed to bind RPC listener: {}", e)))?;
+
+        loop {
+            let (stream, _) = listener.accept().await
+                .map_err(|e| TribeError::Rpc(format!("Failed to accept connection: {}", e)))?;
+
+            let (server, state) = (self.clone(), state.clone());
+            tokio::spawn(async move {
+                if let Err(e) = server.handle_connection(stream, state).await {
+                    eprintln!("RPC connection closed: {}", e);
+                }
+            });
+        }
+    }
+
+    async fn handle_connection(&self, stream: TcpStream, state: RpcState) -> TribeResult<()> {
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        while let Some(line) = lines.next_line().await.map_err(|e| TribeError::Rpc(e.to_string()))? {
+            if line.trim().is_empty() {
+                continue;
+            }
+            // Ethereum tooling, GraphQL clients and ESP firmware speak HTTP; the rest of the
+            // connection is HTTP too
+            if state.serves_http() && (line.starts_with("POST ") || line.starts_with("GET ")) {
+                return self.serve_http(lines.into_inner(), writer, line, &state).await;
+            }
+            let response = match serde_json::from_str::<RpcRequest>(&line) {
+                Ok(request) => state.dispatch(self, request).await,
+                Err(e) => RpcResponse::failure(Value::Null, PARSE_ERROR, format!("Parse error: {}", e)),
+            };
+            write_line(&mut writer, &response).await?;
+        }
+        Ok(()) // Client hung up
+    }
+
+    /// Answer a JSON-RPC body: one request, or a batch answered in order
+    async fn answer_body(&self, state: &RpcState, body: &[u8]) -> Value {
+        match serde_json::from_slice::<Value>(body) {
+            Ok(Value::Array(batch)) if !batch.is_empty() => {
+                let mut responses = Vec::new();
+                for request in batch {
+                    responses.push(json!(state.answer(self, request).await));
+                }
+                Value::Array(responses)
+            }
+            Ok(Value::Array(_)) => json!(RpcResponse::failure(Value::Null, INVALID_REQUEST, "Empty batch")),
+            Ok(request) => json!(state.answer(self, request).await),
+            Err(e) => json!(RpcResponse::failure(Value::Null, PARSE_ERROR, format!("Parse error: {}", e))),
+        }
+    }
+
+    /// Answer HTTP/1.1 requests, starting from `request_line`, until the client closes the
+    /// connection or asks to. Posts to `GRAPHQL_PATH` are GraphQL queries when it is served
+    /// and paths under `GATEWAY_PREFIX` go to the mining gateway when it is; any other post
+    /// is JSON-RPC, with a body holding one request or a batch of them.
+    async fn serve_http(
+        &self,
+        mut reader: BufReader<OwnedReadHalf>,
+        mut writer: OwnedWriteHalf,
+        mut request_line: String,
+        state: &RpcState,
+    ) -> TribeResult<()> {
+        loop {
+            let mut content_length = 0;
+            let mut close = request_line.ends_with("HTTP/1.0");
+            let mut headers = HashMap::new();
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header).await.map_err(|e| TribeError::Rpc(e.to_string()))?;
+                let header = header.trim_end();
+                if header.is_empty() {
+                    break;
+                }
+                let Some((name, value)) = header.split_once(':') else { continue };
+                match name.trim().to_ascii_lowercase().as_str() {
+                    "content-length" => content_length = value.trim().parse().unwrap_or(0),
+                    "connection" => close = value.trim().eq_ignore_ascii_case("close"),
+                    name => {
+                        headers.insert(name.to_string(), value.trim().to_string());
+                    }
+                }
+            }
+            if content_length > MAX_HTTP_BODY {
+                return write_http(&mut writer, "413 Payload Too Large", b"").await;
+            }
+            let mut body = vec![0u8; content_length];
+            reader.read_exact(&mut body).await.map_err(|e| TribeError::Rpc(e.to_string()))?;
+
+            let mut parts = request_line.split_whitespace();
+            let (method, target) = (parts.next().unwrap_or_default(), parts.next().unwrap_or("/"));
+            let (status, response) = match (&state.graphql, &state.mining) {
+                (Some(graphql), _) if target == GRAPHQL_PATH => ("200 OK", graphql.execute(&body).await),
+                (_, Some(mining)) if target.starts_with(GATEWAY_PREFIX) => {
+                    let response = mining.handle(&GatewayRequest::new(method, target, headers, body)).await;
+                    (response.status, response.body)
+                }
+                _ if method != "POST" => ("405 Method Not Allowed", Value::Null),
+                _ => ("200 OK", self.answer_body(state, &body).await),
+            };
+            let response = match response {
+                Value::Null => Vec::new(),
+                response => serde_json::to_vec(&response)
+                    .map_err(|e| TribeError::Rpc(format!("Failed to encode message: {}", e)))?,
+            };
+            write_http(&mut writer, status, &response).await?;
+
+            request_line.clear();
+            if close || reader.read_line(&mut request_line).await.map_err(|e| TribeError::Rpc(e.to_string()))? == 0 {
+                return Ok(());
+            }
+            request_line = request_line.trim_end().to_string();
+        }
+    }
+}
+
+/// What a listening `RpcServer` answers from; contract, telemetry and network methods
+/// are only served when their state is attached
+#[derive(Debug, Clone)]
+pub struct RpcState {
+    pub chain: Arc<RwLock<TribeChain>>,
+    pub contracts: Option<Arc<RwLock<ContractEngine>>>,
+    pub telemetry: Option<Arc<Mutex<TelemetryStore>>>,
+    pub network: Option<Arc<RwLock<NetworkStatus>>>, // Refreshed by the node's network loop
+    pub eth_compat: bool, // Also answer `ETH_METHODS` and JSON-RPC over HTTP
+    pub graphql: Option<GraphqlEndpoint>, // Queries posted to `GRAPHQL_PATH`
+    pub mining: Option<MiningGateway>, // ESP firmware's REST calls under `GATEWAY_PREFIX`
+    pub pool: Option<Arc<RwLock<MiningPool>>>,
+}
+
+impl RpcState {
+    pub fn new(chain: Arc<RwLock<TribeChain>>) -> Self {
+        Self { chain, contracts: None, telemetry: None, network: None, eth_compat: false, graphql: None, mining: None, pool: None }
+    }
+
+    pub fn with_contracts(mut self, contracts: Arc<RwLock<ContractEngine>>) -> Self {
+        self.contracts = Some(contracts);
+        self
+    }
+
+    pub fn with_telemetry(mut self, telemetry: Arc<Mutex<TelemetryStore>>) -> Self {
+        self.telemetry = Some(telemetry);
+        self
+    }
+
+    pub fn with_network(mut self, network: Arc<RwLock<NetworkStatus>>) -> Self {
+        self.network = Some(network);
+        self
+    }
+
+    /// Serve the pool's miner and round statistics
+    pub fn with_pool(mut self, pool: Arc<RwLock<MiningPool>>) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+
+    /// Serve Ethereum tooling too, through the `eth_*` methods and HTTP
+    pub fn with_eth_compat(mut self) -> Self {
+        self.eth_compat = true;
+        self
+    }
+
+    /// Serve GraphQL queries over the chain and, if attached already, the contracts
+    pub fn with_graphql(mut self) -> Self {
+        self.graphql = Some(GraphqlEndpoint::new(self.chain.clone(), self.contracts.clone()));
+        self
+    }
+
+    /// Serve ESP firmware's task, result, config and telemetry endpoints from
+    /// `distributor` and, if attached already, the telemetry store
+    pub fn with_mining_gateway(mut self, distributor: Arc<Mutex<TaskDistributor>>) -> Self {
+        self.mining = Some(MiningGateway::new(distributor, self.telemetry.clone()));
+        self
+    }
+
+    fn serves_http(&self) -> bool {
+        self.eth_compat || self.graphql.is_some() || self.mining.is_some()
+    }
+
+    /// Answer one request of an HTTP body
+    async fn answer(&self, server: &RpcServer, request: Value) -> RpcResponse {
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+        match serde_json::from_value::<RpcRequest>(request) {
+            Ok(request) => self.dispatch(server, request).await,
+            Err(e) => RpcResponse::failure(id, INVALID_REQUEST, format!("Invalid request: {}", e)),
+        }
+    }
+
+    /// Answer `request` with the handler for its method, holding the chain lock only
+    /// as long as it needs
+    pub async fn dispatch(&self, server: &RpcServer, request: RpcRequest) -> RpcResponse {
+        let method = request.method.as_str();
+        if WRITE_METHODS.contains(&method) {
+            let mut chain = self.chain.write().await;
+            return match &self.contracts {
+                Some(contracts) => server.handle_mut_with_contracts(&mut chain, &*contracts.read().await, request),
+                None => server.handle_mut(&mut chain, request),
+            };
+        }
+        if self.eth_compat && ETH_WRITE_METHODS.contains(&method) {
+            return server.handle_eth_mut(&mut *self.chain.write().await, request);
+        }
+        let chain = self.chain.read().await;
+        if self.eth_compat && ETH_METHODS.contains(&method) {
+            return match &self.contracts {
+                Some(contracts) => server.handle_eth(&chain, Some(&mut *contracts.write().await), request),
+                None => server.handle_eth(&chain, None, request),
+            };
+        }
+        match (&self.contracts, &self.telemetry, &self.network, &self.pool) {
+            (Some(contracts), _, _, _) if CONTRACT_QUERY_METHODS.contains(&method) => {
+                server.handle_contract_query(&chain, &mut *contracts.write().await, request)
+            }
+            (Some(contracts), _, _, _) if CONTRACT_METHODS.contains(&method) => {
+                server.handle_with_contracts(&chain, &*contracts.read().await, request)
+            }
+            (_, Some(telemetry), _, _) if TELEMETRY_METHODS.contains(&method) => {
+                server.handle_with_telemetry(&chain, &mut *telemetry.lock().await, request)
+            }
+            (_, _, Some(network), _) if NETWORK_METHODS.contains(&method) => {
+                server.handle_with_network(&chain, &*network.read().await, request)
+            }
+            (_, _, _, Some(pool)) if POOL_METHODS.contains(&method) => {
+                server.handle_with_pool(&chain, &*pool.read().await, request)
+            }
+            _ => server.handle(&chain, request),
+        }
+    }
+}
+
+/// Connection to a node's RPC listener
+#[derive(Debug)]
+pub struct RpcClient {
+    lines: Lines<BufReader<OwnedReadHalf>>,
+    writer: OwnedWriteHalf,
+    next_id: u64,
+}
+
+impl RpcClient {
+    pub async fn connect(addr: &str) -> TribeResult<Self> {
+        let stream = TcpStream::connect(addr).await
+            .map_err(|e| TribeError::Rpc(format!("Failed to connect to {}: {}", addr, e)))?;
+        let (reader, writer) = stream.into_split();
+        Ok(Self { lines: BufReader::new(reader).lines(), writer, next_id: 1 })
+    }
+
+    /// Call `method` and return its result. Node failures come back as the
+    /// `TribeError` they were raised as; protocol failures as `TribeError::Rpc`.
+    pub async fn call(&mut self, method: &str, params: Value) -> TribeResult<Value> {
+        let request = RpcRequest { jsonrpc: "2.0".to_string(), id: json!(self.next_id), method: method.to_string(), params };
+        self.next_id += 1;
+        write_line(&mut self.writer, &request).await?;
+
+        let line = self.lines.next_line().await
+            .map_err(|e| TribeError::Rpc(e.to_string()))?
+            .ok_or_else(|| TribeError::Rpc("Node closed the connection".to_string()))?;
+        let response: RpcResponse = serde_json::from_str(&line)
+            .map_err(|e| TribeError::Rpc(format!("Invalid response: {}", e)))?;
+        match response.error {
+            Some(error) => Err(error.to_tribe_error()
+                .unwrap_or_else(|| TribeError::Rpc(format!("{} (code {})", error.message, error.code)))),
+            None => Ok(response.result.unwrap_or(Value::Null)),
+        }
+    }
+}
+
+async fn write_line(writer: &mut OwnedWriteHalf, message: &impl Serialize) -> TribeResult<()> {
+    let mut line = serde_json::to_vec(message)
+        .map_err(|e| TribeError::Rpc(format!("Failed to encode message: {}", e)))?;
+    line.push(b'\n');
+    writer.write_all(&line).await.map_err(|e| TribeError::Rpc(e.to_string()))
+}
+
+async fn write_http(writer: &mut OwnedWriteHalf, status: &str, body: &[u8]) -> TribeResult<()> {
+    let head = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n", status, body.len()
+    );
+    writer.write_all(head.as_bytes()).await.map_err(|e| TribeError::Rpc(e.to_string()))?;
+    writer.write_all(body).await.map_err(|e| TribeError::Rpc(e.to_string()))
+}
+
+/// Transaction passed as the first or `transaction` parameter
+fn param_transaction(params: &Value) -> Result<Transaction, String> {
+    let transaction = param(params, 0, "transaction").ok_or("Missing transaction")?;
+    serde_json::from_value(transaction.clone()).map_err(|e| format!("Invalid transaction: {}", e))
+}
+
+/// Positional or named parameter
+pub(crate) fn param<'a>(params: &'a Value, index: usize, name: &str) -> Option<&'a Value> {
+    match params {
+        Value::Array(values) => values.get(index),
+        Value::Object(values) => values.get(name),
+        _ => None,
+    }
+}
+
+fn param_str(params: &Value, index: usize, name: &str) -> Option<String> {
+    param(params, index, name).and_then(Value::as_str).map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tribechain_core::{Block, Log, Receipt, Transaction, TransactionType};
+    use tribechain_contracts::PriceOracle;
+
+    /// `transaction` signed by its sender, whose key in these tests is `<sender>-key`
+    fn signed(mut transaction: Transaction) -> Transaction {
+        let key = format!("{}-key", transaction.from);
+        transaction.sign(&key).unwrap();
+        transaction
+    }
+
+    #[test]
+    fn test_address_and_log_queries() {
+        let dir = std::env::temp_dir().join(format!("tribechain-rpc-{}", uuid::Uuid::new_v4()));
+        let mut chain = TribeChain::new(dir.to_str().unwrap()).unwrap();
+
+        let tx = Transaction::new("alice".to_string(), TransactionType::Transfer { to: "bob".to_string(), amount: 5 }, 1, 0);
+        let previous = chain.get_latest_block().unwrap().hash.clone();
+        let mut block = Block::new(1, previous, vec![tx.clone()], "miner".to_string());
+        block.hash = block.calculate_hash();
+        chain.blocks.push(block.clone());
+        chain.address_index.index_block(&block);
+
+        let rpc = RpcServer::new(8334).unwrap();
+        let body = br#"{"jsonrpc":"2.0","id":1,"method":"getTransactionsForAddress","params":["bob"]}"#;
+        let response = rpc.handle_raw(&chain, body);
+        let result = response.result.unwrap();
+        assert_eq!(result["transactions"][0]["transaction"]["hash"], json!(tx.hash));
+        assert_eq!(result["transactions"][0]["block_index"], json!(1));
+        let body = br#"{"jsonrpc":"2.0","id":6,"method":"scanAddress","params":{"address":"alice","from_block":1}}"#;
+        let result = rpc.handle_raw(&chain, body).result.unwrap();
+        assert_eq!(result["transactions"][0]["transaction"]["hash"], json!(tx.hash));
+        let body = br#"{"jsonrpc":"2.0","id":7,"method":"scanAddress","params":["carol", 0, 1]}"#;
+        assert_eq!(rpc.handle_raw(&chain, body).result.unwrap()["transactions"], json!([]));
+
+        let receipt = Receipt {
+            tx_hash: tx.hash.clone(),
+            success: true,
+            gas_used: 21000,
+            logs: vec![Log { address: "token1".to_string(), topics: vec!["transfer".to_string()], data: vec![] }],
+        };
+        chain.attach_receipts(1, vec![receipt]).unwrap();
+        let body = br#"{"jsonrpc":"2.0","id":3,"method":"getLogs","params":[{"from_block":1,"topics":["transfer"]}]}"#;
+        let logs = rpc.handle_raw(&chain, body).result.unwrap();
+        assert_eq!(logs[0]["tx_hash"], json!(tx.hash));
+        let body = br#"{"jsonrpc":"2.0","id":4,"method":"getLogs","params":[{"addresses":["token2"]}]}"#;
+        assert_eq!(rpc.handle_raw(&chain, body).result.unwrap(), json!([]));
+
+        let body = br#"{"jsonrpc":"2.0","id":5,"method":"estimateFee","params":{"target_blocks":3}}"#;
+        assert_eq!(rpc.handle_raw(&chain, body).result.unwrap()["gas_price"], json!(tribechain_core::MIN_GAS_PRICE));
+
+        let missing = rpc.handle_raw(&chain, br#"{"jsonrpc":"2.0","id":2,"method":"nope"}"#);
+        assert_eq!(missing.error.unwrap().code, METHOD_NOT_FOUND);
+    }
+
+    #[test]
+    fn test_state_proof() {
+        let dir = std::env::temp_dir().join(format!("tribechain-rpc-{}", uuid::Uuid::new_v4()));
+        let chain = TribeChain::new(dir.to_str().unwrap()).unwrap();
+        let rpc = RpcServer::new(8334).unwrap();
+
+        let body = br#"{"jsonrpc":"2.0","id":1,"method":"getStateProof","params":["genesis"]}"#;
+        let result = rpc.handle_raw(&chain, body).result.unwrap();
+        let proof: tribechain_core::StateProof = serde_json::from_value(result["proof"].clone()).unwrap();
+        assert_eq!(proof.balance, Some(chain.get_balance("genesis")));
+        assert_eq!(proof.root, chain.balances.root());
+        assert!(proof.verify());
+
+        let body = br#"{"jsonrpc":"2.0","id":2,"method":"getStateProof","params":{"address":"nobody"}}"#;
+        let result = rpc.handle_raw(&chain, body).result.unwrap();
+        let proof: tribechain_core::StateProof = serde_json::from_value(result["proof"].clone()).unwrap();
+        assert_eq!(proof.balance, None);
+        assert!(proof.verify());
+    }
+
+    #[test]
+    fn test_twap_query() {
+        let dir = std::env::temp_dir().join(format!("tribechain-rpc-{}", uuid::Uuid::new_v4()));
+        let chain = TribeChain::new(dir.to_str().unwrap()).unwrap();
+        let mut engine = ContractEngine::new();
+        let pool_id = engine.create_liquidity_pool("TRIBE".to_string(), "USDC".to_string(), 10000, 20000, "lp".to_string(), 0.003).unwrap();
+        engine.liquidity_pools.get_mut(&pool_id).unwrap().price_oracle = PriceOracle::new(2.0, chrono::Utc::now() - chrono::Duration::hours(1));
+
+        let rpc = RpcServer::new(8334).unwrap();
+        let request = |params: Value| RpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: json!(1),
+            method: "getTwap".to_string(),
+            params,
+        };
+        let response = rpc.handle_with_contracts(&chain, &engine, request(json!([pool_id, 600])));
+        assert_eq!(response.result.unwrap()["twap"], json!(2.0));
+
+        let response = rpc.handle_with_contracts(&chain, &engine, request(json!({ "pool_id": pool_id, "window_secs": 7200 })));
+        let error = response.error.unwrap();
+        assert_eq!(error.code, TribeError::Contract(String::new()).code());
+        assert_eq!(error.data, Some(json!({ "kind": "Contract" })));
+        assert!(matches!(error.to_tribe_error(), Some(TribeError::Contract(_))));
+    }
+
+    #[test]
+    fn test_encode_contract_call() {
+        use tribechain_contracts::{ContractDeployment, ContractType};
+        use tribechain_contracts::contracts::{ContractMetadata, MethodSignature, Parameter, ParameterType};
+
+        let dir = std::env::temp_dir().join(format!("tribechain-rpc-{}", uuid::Uuid::new_v4()));
+        let chain = TribeChain::new(dir.to_str().unwrap()).unwrap();
+        let mut engine = ContractEngine::new();
+        let transfer = MethodSignature::new(
+            "transfer".to_string(),
+            vec![
+                Parameter::new("to".to_string(), ParameterType::Address),
+                Parameter::new("amount".to_string(), ParameterType::Uint8),
+            ],
+            vec![],
+        );
+        let paused = MethodSignature::new(
+            "paused".to_string(),
+            vec![],
+            vec![Parameter::new("paused".to_string(), ParameterType::Bool)],
+        ).view();
+        let metadata = ContractMetadata { abi: vec![transfer, paused], ..ContractMetadata::default() };
+        let deployment = ContractDeployment::new(ContractType::Custom, vec![1], "alice".to_string()).with_metadata(metadata);
+        let address = engine.deploy_contract(deployment).unwrap();
+
+        let rpc = RpcServer::new(8334).unwrap();
+        let request = |method: &str, params: Value| RpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: json!(1),
+            method: method.to_string(),
+            params,
+        };
+        let abi = rpc.handle_with_contracts(&chain, &engine, request("getContractAbi", json!([address]))).result.unwrap();
+        assert_eq!(abi[0]["name"], json!("transfer"));
+
+        let response = rpc.handle_with_contracts(&chain, &engine, request("encodeContractCall", json!([address, "transfer", ["bob", 7]])));
+        assert_eq!(response.result.unwrap()["data"], json!("00000003626f6207"));
+
+        let response = rpc.handle_with_contracts(&chain, &engine, request("encodeContractCall", json!([address, "transfer", ["bob", 300]])));
+        assert_eq!(response.error.unwrap().code, TribeError::Contract(String::new()).code());
+        let result = rpc.handle_contract_query(&chain, &mut engine, request("queryContract", json!([address, "paused"]))).result.unwrap();
+        assert_eq!(result["outputs"], json!([true]));
+        // State-changing methods must go through a transaction
+        let response = rpc.handle_contract_query(&chain, &mut engine, request("queryContract", json!([address, "transfer", ["bob", 7]])));
+        assert_eq!(response.error.unwrap().code, TribeError::Contract(String::new()).code());
+    }
+
+    #[test]
+    fn test_bridge_transfers() {
+        use tribechain_contracts::BridgeMode;
+
+        let dir = std::env::temp_dir().join(format!("tribechain-rpc-{}", uuid::Uuid::new_v4()));
+        let chain = TribeChain::new(dir.to_str().unwrap()).unwrap();
+        let mut engine = ContractEngine::new();
+        let remote_tribe = "0x00000000000000000000000000000000000000a1";
+        let bridge_id = engine.create_bridge(
+            "admin".to_string(), 1, "0x00000000000000000000000000000000000000b0", vec!["r1".to_string()], 1,
+        ).unwrap();
+        let tribe = engine.create_token("Tribe".to_string(), "TRIBE".to_string(), 1_000, 6, "alice".to_string()).unwrap();
+        engine.add_bridge_route(&bridge_id, "admin", &tribe, remote_tribe, BridgeMode::Lock, 18).unwrap();
+        for amount in [100, 200] {
+            engine.bridge_out(&bridge_id, "alice".to_string(), &tribe, "0x00000000000000000000000000000000000000c1", amount).unwrap();
+        }
+
+        let rpc = RpcServer::new(8334).unwrap();
+        let request = RpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: json!(1),
+            method: "getBridgeTransfers".to_string(),
+            params: json!([bridge_id, 2]),
+        };
+        let result = rpc.handle_with_contracts(&chain, &engine, request).result.unwrap();
+        assert_eq!(result["locked"][&tribe], json!(300));
+        assert_eq!(result["transfers"].as_array().unwrap().len(), 1);
+        assert_eq!(result["transfers"][0]["remote_amount"], json!("200000000000000"));
+    }
+
+    #[test]
+    fn test_ibc_channel_and_proof() {
+        use tribechain_core::FinalityValidator;
+
+        let dir = std::env::temp_dir().join(format!("tribechain-rpc-{}", uuid::Uuid::new_v4()));
+        let chain = TribeChain::new(dir.to_str().unwrap()).unwrap();
+        let mut engine = ContractEngine::new();
+        let validators = HashMap::from([("v1".to_string(), FinalityValidator { stake: 1, public_key: "v1_key".to_string() })]);
+        let client_id = engine.create_ibc_client(validators, &ContractEngine::new().ibc.header(2, 0, "genesis")).unwrap();
+        let channel_id = engine.ibc_channel_open_init(&client_id).unwrap();
+
+        let rpc = RpcServer::new(8334).unwrap();
+        let request = |method: &str, params: Value| RpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: json!(1),
+            method: method.to_string(),
+            params,
+        };
+        let result = rpc.handle_with_contracts(&chain, &engine, request("getIbcChannel", json!([channel_id]))).result.unwrap();
+        assert_eq!(result["channel"]["state"], json!("Init"));
+        assert_eq!(result["client"]["chain_id"], json!(2));
+        assert!(rpc.handle_with_contracts(&chain, &engine, request("getIbcChannel", json!(["channel-9"]))).error.is_some());
+
+        let key = tribechain_contracts::ibc::channel_key(&channel_id, tribechain_contracts::ChannelState::Init, None);
+        let result = rpc.handle_with_contracts(&chain, &engine, request("getIbcProof", json!([key]))).result.unwrap();
+        let proof: tribechain_core::StateProof = serde_json::from_value(result["proof"].clone()).unwrap();
+        assert!(proof.verify() && proof.balance == Some(1));
+        assert_eq!(result["header"]["commitment_root"], json!(proof.root));
+        assert_eq!(result["header"]["chain_id"], json!(chain.chain_id));
+    }
+
+    #[tokio::test]
+    async fn test_pool_statistics() {
+        use tribechain_mining::{Miner, MiningShare, miner::MinerType, pool::PoolConfig};
+
+        let dir = std::env::temp_dir().join(format!("tribechain-rpc-{}", uuid::Uuid::new_v4()));
+        let chain = TribeChain::new(dir.to_str().unwrap()).unwrap();
+        let mut pool = MiningPool::new("pool1".to_string(), "Test Pool".to_string(), PoolConfig::default());
+        pool.add_miner(Miner::new("esp".to_string(), "addr1".to_string(), MinerType::ESP32)).await.unwrap();
+        for (height, hash) in [(1, "00ab"), (1, "000000ab"), (2, "00cd")] {
+            let share = MiningShare {
+                miner_id: "esp".to_string(),
+                block_height: height,
+                nonce: 0,
+                hash: hash.to_string(),
+                difficulty: 0,
+                is_valid: false,
+                timestamp: chrono::Utc::now(),
+                ai3_proof: None,
+            };
+            assert!(pool.submit_share(share).await.unwrap());
+        }
+
+        let server = RpcServer::new(8334).unwrap();
+        let state = RpcState::new(Arc::new(RwLock::new(chain))).with_pool(Arc::new(RwLock::new(pool)));
+        let request = |method: &str, params: Value| RpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: json!(1),
+            method: method.to_string(),
+            params,
+        };
+
+        let stats = state.dispatch(&server, request("getPoolStats", json!([]))).await.result.unwrap();
+        assert_eq!(stats["valid_shares"], json!(3));
+        assert_eq!(stats["miners"]["esp"]["accepted_shares"], json!(3));
+        assert!(stats.get("rounds").is_none());
+
+        let miner = state.dispatch(&server, request("getPoolMiner", json!(["esp"]))).await.result.unwrap();
+        assert_eq!(miner["share_difficulty"], json!(2));
+        assert_eq!(miner["round_shares"], json!(1));
+        assert!(state.dispatch(&server, request("getPoolMiner", json!(["cpu"]))).await.error.is_some());
+
+        let rounds = state.dispatch(&server, request("getPoolRounds", json!([5]))).await.result.unwrap();
+        assert_eq!(rounds.as_array().unwrap().len(), 1);
+        assert_eq!(rounds[0]["finder"], json!("esp"));
+        assert_eq!(rounds[0]["shares"]["accepted"]["esp"], json!(2));
+    }
+
+    #[test]
+    fn test_device_telemetry() {
+        let dir = std::env::temp_dir().join(format!("tribechain-rpc-{}", uuid::Uuid::new_v4()));
+        let chain = TribeChain::new(dir.to_str().unwrap()).unwrap();
+        let mut telemetry = TelemetryStore::new().with_threshold("temperature_c", 75.0);
+
+        let rpc = RpcServer::new(8334).unwrap();
+        let request = |method: &str, params: Value| RpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: json!(1),
+            method: method.to_string(),
+            params,
+        };
+        for (timestamp, temperature) in [(960, 60.0), (1_000, 80.0), (1_090, 82.0)] {
+            let params = json!({ "device_id": "esp-1", "metrics": { "temperature_c": temperature }, "timestamp": timestamp });
+            let response = rpc.handle_with_telemetry(&chain, &mut telemetry, request("pushTelemetry", params));
+            let alerts = response.result.unwrap()["alerts"].as_array().unwrap().len();
+            assert_eq!(alerts, usize::from(timestamp == 1_000)); // Only the crossing alerts
+        }
+
+        let response = rpc.handle_with_telemetry(&chain, &mut telemetry, request("getDeviceMetrics", json!(["esp-1", 900, 1_100])));
+        let buckets = response.result.unwrap()["buckets"].clone();
+        assert_eq!(buckets.as_array().unwrap().len(), 2); // Minute buckets at 960 and 1080
+        assert_eq!(buckets[0]["metrics"]["temperature_c"]["max"], json!(80.0));
+        assert_eq!(buckets[0]["metrics"]["temperature_c"]["count"], json!(2));
+
+        let response = rpc.handle_with_telemetry(&chain, &mut telemetry, request("getTelemetryAlerts", json!(["esp-1"])));
+        assert_eq!(response.result.unwrap()[0]["value"], json!(80.0));
+
+        let response = rpc.handle_with_telemetry(&chain, &mut telemetry, request("getDevices", json!([])));
+        let devices = response.result.unwrap();
+        assert_eq!(devices[0]["last_seen"], json!(1_090));
+        assert_eq!(devices[0]["metrics"]["temperature_c"], json!(82.0));
+        assert_eq!(devices[0]["alerting"], json!(["temperature_c"]));
+
+        let response = rpc.handle_with_telemetry(&chain, &mut telemetry, request("pushTelemetry", json!(["esp-1", { "temperature_c": "hot" }])));
+        assert_eq!(response.error.unwrap().code, INVALID_PARAMS);
+    }
+
+    #[test]
+    fn test_confirmed_balance() {
+        let dir = std::env::temp_dir().join(format!("tribechain-rpc-{}", uuid::Uuid::new_v4()));
+        let mut chain = TribeChain::new(dir.to_str().unwrap()).unwrap();
+        let transfer = |amount, nonce| Transaction::new("alice".to_string(), TransactionType::Transfer { to: "bob".to_string(), amount }, 1, nonce);
+        let received = transfer(30, 0);
+        for (transactions, miner) in [(vec![received.clone()], "miner"), (vec![transfer(20, 1)], "miner"), (vec![], "bob")] {
+            let previous = chain.get_latest_block().unwrap().hash.clone();
+            let mut block = Block::new(chain.blocks.len() as u64, previous, transactions, miner.to_string());
+            block.hash = block.calculate_hash();
+            chain.address_index.index_block(&block);
+            chain.blocks.push(block);
+        }
+        let reward = chain.block_reward(chain.get_latest_block().unwrap()).unwrap();
+        chain.balances.insert("bob".to_string(), 50 + reward);
+        chain.pending_transactions.push(transfer(5, 2));
+
+        let rpc = RpcServer::new(8334).unwrap();
+        let balance = |params: Value| {
+            let request = RpcRequest { jsonrpc: "2.0".to_string(), id: json!(1), method: "getBalance".to_string(), params };
+            rpc.handle(&chain, request).result.unwrap()
+        };
+        assert_eq!(balance(json!(["bob"])), json!(50 + reward));
+        assert_eq!(balance(json!(["bob", 1])), json!(50 + reward));
+        assert_eq!(balance(json!({ "address": "bob", "min_confirmations": 2 })), json!(50));
+        assert_eq!(balance(json!(["bob", 3])), json!(30));
+        assert_eq!(balance(json!(["bob", 100])), json!(0));
+
+        let body = br#"{"jsonrpc":"2.0","id":2,"method":"scanAddress","params":["bob"]}"#;
+        let result = rpc.handle_raw(&chain, body).result.unwrap();
+        assert_eq!(result["transactions"][0]["transaction"]["hash"], json!(received.hash));
+        assert_eq!(result["transactions"][0]["confirmations"], json!(3));
+        assert_eq!(result["pending"][0]["nonce"], json!(2));
+        assert_eq!(chain.get_confirmations(1, "stale"), None);
+    }
+
+    #[test]
+    fn test_block_template() {
+        let dir = std::env::temp_dir().join(format!("tribechain-rpc-{}", uuid::Uuid::new_v4()));
+        let mut chain = TribeChain::new(dir.to_str().unwrap()).unwrap();
+        chain.difficulty = 1;
+        chain.balances.insert("alice".to_string(), 10_000_000);
+        let chain_id = chain.chain_id;
+        let bob = tribechain_core::Address::from_key(tribechain_core::hrp_for_chain(chain_id), "bob").to_string();
+        let transfer = |nonce, gas_price| signed(
+            Transaction::new("alice".to_string(), TransactionType::Transfer { to: bob.clone(), amount: 5 }, 1, nonce)
+                .with_chain_id(chain_id)
+                .with_gas_price(gas_price)
+        );
+        chain.add_transaction(transfer(0, tribechain_core::MIN_GAS_PRICE)).unwrap();
+        chain.add_transaction(transfer(1, tribechain_core::MIN_GAS_PRICE * 2)).unwrap();
+
+        let rpc = RpcServer::new(8334).unwrap();
+        let request = |method: &str, params: Value| RpcRequest { jsonrpc: "2.0".to_string(), id: json!(1), method: method.to_string(), params };
+        let template = rpc.handle(&chain, request("getBlockTemplate", json!(["miner"]))).result.unwrap();
+        assert_eq!(template["difficulty"], json!(1));
+        assert_eq!(template["reward"], json!(chain.mining_reward));
+        let mut block: Block = serde_json::from_value(template["block"].clone()).unwrap();
+        let nonces: Vec<u64> = block.transactions.iter().map(|tx| tx.nonce).collect();
+        assert_eq!(nonces, vec![0, 1]); // The sender's sequence comes before fee order
+
+        // Blocks below the required difficulty, or on top of a stale tip, are refused
+        let mut easy = block.clone();
+        easy.mine_block(0).unwrap();
+        assert!(rpc.handle_mut(&mut chain, request("submitBlock", json!([easy]))).error.is_some());
+        block.mine_block(1).unwrap();
+        let result = rpc.handle_mut(&mut chain, request("submitBlock", json!({ "block": block }))).result.unwrap();
+        assert_eq!(result, json!({ "index": 1, "hash": block.hash }));
+        assert!(chain.pending_transactions.is_empty());
+        assert_eq!(chain.get_balance("miner"), chain.mining_reward);
+        assert!(rpc.handle_mut(&mut chain, request("submitBlock", json!([block]))).error.is_some());
+        assert!(rpc.handle(&chain, request("getBlockTemplate", json!(["miner", { "task_id": "none" }]))).error.is_some());
+    }
+
+    #[test]
+    fn test_merged_mining_tensor_path() {
+        use tribechain_core::{AI3Commitment, tensor_work_hash};
+
+        let dir = std::env::temp_dir().join(format!("tribechain-rpc-{}", uuid::Uuid::new_v4()));
+        let mut chain = TribeChain::new(dir.to_str().unwrap()).unwrap();
+        chain.difficulty = 1;
+        chain.ai3_difficulty_multiplier = 2.0;
+        chain.add_tensor_task(TensorTask {
+            id: "task-1".to_string(),
+            operation: "relu".to_string(),
+            input_data: vec![1.0, -1.0],
+            expected_output_size: 2,
+            max_computation_time: 60,
+            reward: 0,
+            requester: "alice".to_string(),
+            completed: false,
+            result: None,
+            assigned_miner: None,
+            created_at: 0,
+        }).unwrap();
+
+        // The revealing block will build on this one, with no transactions
+        let previous = chain.get_latest_block().unwrap().hash.clone();
+        let mut parent = Block::new(1, previous, Vec::new(), "other".to_string());
+        parent.hash = parent.calculate_hash();
+
+        // Grind the commitment nonce until its work on that block meets the AI3 difficulty of 2
+        let tensor = "ab".repeat(32);
+        let work_hash = |nonce| tensor_work_hash(&AI3Commitment::compute_hash("task-1", "miner", &tensor, nonce), &parent.hash, &"0".repeat(64));
+        let nonce = (0..).find(|&nonce| work_hash(nonce).starts_with("00")).unwrap();
+        chain.submit_ai3_commitment(AI3Commitment::new("task-1".to_string(), "miner".to_string(), &tensor, nonce, 0)).unwrap();
+        chain.blocks.push(parent);
+
+        let rpc = RpcServer::new(8334).unwrap();
+        let request = |method: &str, params: Value| RpcRequest { jsonrpc: "2.0".to_string(), id: json!(1), method: method.to_string(), params };
+        let proof = |nonce| json!({
+            "task_id": "task-1",
+            "optimization_factor": 1.0,
+            "tensor_hash": tensor,
+            "computation_time": 1,
+            "miner_signature": "",
+            "commitment_nonce": nonce,
+        });
+        // A proof that doesn't open the commitment gets no template
+        assert!(rpc.handle(&chain, request("getBlockTemplate", json!(["miner", proof(nonce + 1)]))).error.is_some());
+
+        // Past the proof's work the block still needs a hash meeting the difficulty
+        chain.ai3_difficulty_multiplier = 8.0;
+        let template = rpc.handle(&chain, request("getBlockTemplate", json!(["miner", proof(nonce)]))).result.unwrap();
+        let block: Block = serde_json::from_value(template["block"].clone()).unwrap();
+        if !block.hash.starts_with("00000000") {
+            assert_eq!(template["mined_by"], Value::Null);
+            assert!(rpc.handle_mut(&mut chain, request("submitBlock", json!([block]))).error.is_some());
+        }
+
+        // Within it the template is a valid block as it is, with no nonce search
+        chain.ai3_difficulty_multiplier = 2.0;
+        let template = rpc.handle(&chain, request("getBlockTemplate", json!(["miner", proof(nonce)]))).result.unwrap();
+        assert_eq!(template["difficulty"], json!(2));
+        let block: Block = serde_json::from_value(template["block"].clone()).unwrap();
+        let expected = if block.hash.starts_with("00") { "Hash" } else { "Tensor" };
+        assert_eq!(template["mined_by"], json!(expected));
+        assert!(rpc.handle_mut(&mut chain, request("submitBlock", json!([block]))).error.is_none());
+        assert!(chain.tensor_tasks[0].completed);
+    }
+
+    #[test]
+    fn test_emission_and_coinbase_maturity() {
+        use tribechain_core::{ChainSpec, EmissionSchedule, StorageConfig};
+
+        let mut spec = ChainSpec::dev();
+        spec.emission = EmissionSchedule::Halving { interval: 2 };
+        spec.coinbase_maturity = 2;
+        spec.difficulty.fixed = true;
+        spec.genesis_allocations.insert("alice".to_string(), 10_000_000);
+        let dir = std::env::temp_dir().join(format!("tribechain-rpc-{}", uuid::Uuid::new_v4()));
+        let mut chain = TribeChain::with_chain_spec(dir.to_str().unwrap(), &StorageConfig::default(), &spec).unwrap();
+        let chain_id = chain.chain_id;
+        let bob = tribechain_core::Address::from_key(tribechain_core::hrp_for_chain(chain_id), "bob").to_string();
+        let transfer = |from: &str, amount, nonce| {
+            signed(Transaction::new(from.to_string(), TransactionType::Transfer { to: bob.clone(), amount }, 1, nonce).with_chain_id(chain_id))
+        };
+        let rpc = RpcServer::new(8334).unwrap();
+        let send = |transaction: Transaction| RpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: json!(1),
+            method: "sendTransaction".to_string(),
+            params: json!([transaction]),
+        };
+
+        let reward = spec.mining_reward;
+        chain.add_transaction(transfer("alice", 5, 0)).unwrap();
+        chain.mine_block("miner".to_string()).unwrap();
+        assert_eq!(chain.get_balance("miner"), reward);
+        assert_eq!(chain.mining_reward, reward / 2); // Block 2 starts the first halving
+        // Block 1's reward can't be spent in block 2
+        assert_eq!(chain.get_spendable_balance("miner"), 0);
+        assert!(rpc.handle_mut(&mut chain, send(transfer("miner", reward / 2, 0))).error.is_some());
+
+        chain.add_transaction(transfer("alice", 5, 1)).unwrap();
+        chain.mine_block("miner".to_string()).unwrap();
+        assert_eq!(chain.get_balance("miner"), reward + reward / 2);
+        assert_eq!(chain.get_spendable_balance("miner"), reward);
+        assert!(rpc.handle_mut(&mut chain, send(transfer("miner", reward / 2, 0))).result.is_some());
+        chain.mine_block("miner".to_string()).unwrap();
+        assert_eq!(chain.get_balance(&bob), 10 + reward / 2);
+
+        // A block crediting anything but the scheduled reward doesn't match its state root
+        chain.add_transaction(transfer("alice", 5, 2)).unwrap();
+        let mut block = chain.get_block_template("miner", None).unwrap().block;
+        chain.chain_spec.mining_reward *= 2;
+        block.mine_block(block.difficulty).unwrap();
+        assert!(chain.submit_block(block).is_err());
+    }
+
+    #[test]
+    fn test_orphan_transactions() {
+        use tribechain_contracts::{ContractDeployment, ContractType};
+
+        let dir = std::env::temp_dir().join(format!("tribechain-rpc-{}", uuid::Uuid::new_v4()));
+        let mut chain = TribeChain::new(dir.to_str().unwrap()).unwrap();
+        chain.balances.insert("alice".to_string(), 10_000_000);
+        let chain_id = chain.chain_id;
+        let bob = tribechain_core::Address::from_key(tribechain_core::hrp_for_chain(chain_id), "bob").to_string();
+        let transfer = |nonce| signed(
+            Transaction::new("alice".to_string(), TransactionType::Transfer { to: bob.clone(), amount: 5 }, 1, nonce)
+                .with_chain_id(chain_id)
+        );
+        let send = |transaction: &Transaction| RpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: json!(1),
+            method: "sendTransaction".to_string(),
+            params: json!([transaction]),
+        };
+        let rpc = RpcServer::new(8334).unwrap();
+
+        // Nonce 2 waits for nonce 1, which waits for nonce 0
+        let (first, second, third) = (transfer(0), transfer(1), transfer(2));
+        assert_eq!(rpc.handle_mut(&mut chain, send(&third)).result.unwrap()["orphan"], json!(true));
+        assert_eq!(rpc.handle_mut(&mut chain, send(&second)).result.unwrap()["orphan"], json!(true));
+        assert!(rpc.handle_mut(&mut chain, send(&third)).error.is_some());
+        assert!(chain.pending_transactions.is_empty());
+        assert_eq!(rpc.handle_mut(&mut chain, send(&first)).result.unwrap()["orphan"], json!(false));
+        assert_eq!(chain.get_pending_nonce("alice"), 3);
+        assert!(chain.orphan_transactions.is_empty());
+
+        // A call to a contract the engine hasn't deployed waits for the deployment
+        let deployment = ContractDeployment::new(ContractType::Custom, vec![1], "alice".to_string());
+        let address = ContractEngine::new().deploy_contract(deployment.clone()).unwrap();
+        let call = TransactionType::ContractCall { contract_address: address, method: "ping".to_string(), args: vec![], value: 0 };
+        let call = signed(Transaction::new("alice".to_string(), call, 1, 3).with_chain_id(chain_id));
+        let mut engine = ContractEngine::new();
+        assert_eq!(rpc.handle_mut_with_contracts(&mut chain, &engine, send(&call)).result.unwrap()["orphan"], json!(true));
+        assert_eq!(chain.get_stats().orphan_transactions, 1);
+        engine.deploy_contract(deployment).unwrap();
+        assert_eq!(rpc.handle_mut_with_contracts(&mut chain, &engine, send(&transfer(4))).result.unwrap()["orphan"], json!(false));
+        assert_eq!(chain.get_pending_nonce("alice"), 5);
+        assert!(chain.orphan_transactions.is_empty());
+    }
+
+    #[test]
+    fn test_network_and_mining_status() {
+        let dir = std::env::temp_dir().join(format!("tribechain-rpc-{}", uuid::Uuid::new_v4()));
+        let mut chain = TribeChain::new(dir.to_str().unwrap()).unwrap();
+        for index in 1..=2 {
+            let previous = chain.get_latest_block().unwrap().hash.clone();
+            let mut block = Block::new(index, previous, Vec::new(), "miner".to_string());
+            block.timestamp = chain.get_latest_block().unwrap().timestamp + 30;
+            block.hash = block.calculate_hash();
+            chain.blocks.push(block);
+        }
+
+        let rpc = RpcServer::new(8334).unwrap();
+        let body = br#"{"jsonrpc":"2.0","id":1,"method":"getMiningStatus","params":[10]}"#;
+        let status = rpc.handle_raw(&chain, body).result.unwrap();
+        assert_eq!(status["window_blocks"], json!(2));
+        assert_eq!(status["avg_block_time"], json!(30.0));
+
+        let network = NetworkStatus {
+            peers: vec![PeerStatus {
+                peer_id: "peer-1".to_string(),
+                node_type: crate::handshake::NodeType::Full,
+                best_height: 5,
+                protocol_version: 1,
+            }],
+            syncing: true,
+        };
+        let request = RpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: json!(2),
+            method: "getNetworkStatus".to_string(),
+            params: json!([]),
+        };
+        let status = rpc.handle_with_network(&chain, &network, request.clone()).result.unwrap();
+        assert_eq!(status["height"], json!(2));
+        assert_eq!(status["behind"], json!(3));
+        assert_eq!(status["peers"][0]["peer_id"], json!("peer-1"));
+        // Without a network view the method isn't served
+        assert!(rpc.handle(&chain, request).error.is_some());
+    }
+
+    #[test]
+    fn test_hashrate_and_difficulty_history() {
+        let dir = std::env::temp_dir().join(format!("tribechain-rpc-{}", uuid::Uuid::new_v4()));
+        let mut chain = TribeChain::new(dir.to_str().unwrap()).unwrap();
+        for (index, block_time) in [(1, 20), (2, 40)] {
+            let previous = chain.get_latest_block().unwrap().hash.clone();
+            let mut block = Block::new(index, previous, Vec::new(), "miner".to_string());
+            block.timestamp = chain.get_latest_block().unwrap().timestamp + block_time;
+            block.difficulty = index + 1;
+            block.hash = block.calculate_hash();
+            chain.blocks.push(block);
+        }
+
+        let rpc = RpcServer::new(8334).unwrap();
+        let body = br#"{"jsonrpc":"2.0","id":1,"method":"getNetworkHashrate","params":[1]}"#;
+        assert_eq!(rpc.handle_raw(&chain, body).result.unwrap(), json!(4096.0 / 40.0));
+        // 16^2 + 16^3 expected hashes over 60 seconds
+        let body = br#"{"jsonrpc":"2.0","id":2,"method":"getNetworkHashrate","params":[]}"#;
+        assert_eq!(rpc.handle_raw(&chain, body).result.unwrap(), json!(4352.0 / 60.0));
+
+        let body = br#"{"jsonrpc":"2.0","id":3,"method":"getDifficultyHistory","params":[1]}"#;
+        let history = rpc.handle_raw(&chain, body).result.unwrap();
+        assert_eq!(history.as_array().unwrap().len(), 2);
+        assert_eq!(history[0]["difficulty"], json!(2));
+        assert_eq!(history[0]["block_time"], json!(20));
+        assert_eq!(history[1]["index"], json!(2));
+        assert_eq!(history[1]["block_time"], json!(40));
+
+        let body = br#"{"jsonrpc":"2.0","id":4,"method":"getDifficultyHistory","params":[0, 0]}"#;
+        assert_eq!(rpc.handle_raw(&chain, body).result.unwrap()[0]["block_time"], json!(0));
+        let body = br#"{"jsonrpc":"2.0","id":5,"method":"getDifficultyHistory","params":[5]}"#;
+        assert!(rpc.handle_raw(&chain, body).error.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_node_errors_carry_their_domain() {
+        let response = RpcResponse::from_error(json!(1), &TribeError::Tensor("Shape 2x2 doesn't match data length 3".to_string()));
+        let error = response.error.unwrap();
+        assert_eq!((error.code, error.message.as_str()), (1012, "Shape 2x2 doesn't match data length 3"));
+        assert_eq!(error.data, Some(json!({ "kind": "Tensor" })));
+        assert!(matches!(error.to_tribe_error(), Some(TribeError::Tensor(_))));
+
+        // Protocol failures keep their JSON-RPC codes and map to no node error
+        let error = RpcResponse::failure(json!(2), INVALID_PARAMS, "Missing address").error.unwrap();
+        assert!(error.to_tribe_error().is_none());
+
+        let mut server = RpcServer::new(0).unwrap();
+        server.start().await.unwrap();
+        assert!(matches!(server.start().await, Err(TribeError::Rpc(_))));
+    }
+
+    #[tokio::test]
+    async fn test_listener_round_trip() {
+        let dir = std::env::temp_dir().join(format!("tribechain-rpc-{}", uuid::Uuid::new_v4()));
+        let mut chain = TribeChain::new(dir.to_str().unwrap()).unwrap();
+        chain.balances.insert("alice".to_string(), 10_000_000);
+        let chain_id = chain.chain_id;
+        let state = RpcState::new(Arc::new(RwLock::new(chain)));
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        drop(listener);
+        let server = RpcServer::new(0).unwrap();
+        let serving = state.clone();
+        let listen_addr = addr.clone();
+        tokio::spawn(async move { server.listen(&listen_addr, serving).await });
+
+        let mut client = loop {
+            match RpcClient::connect(&addr).await {
+                Ok(client) => break client,
+                Err(_) => tokio::time::sleep(std::time::Duration::from_millis(10)).await,
+            }
+        };
+        assert_eq!(client.call("getBlockNumber", json!([])).await.unwrap(), json!(0));
+        let genesis = client.call("getBlock", json!([0])).await.unwrap();
+        assert_eq!(client.call("getBlock", json!({ "block": genesis["hash"] })).await.unwrap(), genesis);
+        assert_eq!(client.call("getChainInfo", json!([])).await.unwrap()["best_hash"], genesis["hash"]);
+
+        // Recipients must be addresses of this network
+        let unchecked = signed(Transaction::new("alice".to_string(), TransactionType::Transfer { to: "bob".to_string(), amount: 5 }, 1, 0)
+            .with_chain_id(chain_id));
+        let error = client.call("sendTransaction", json!([unchecked])).await.unwrap_err();
+        assert!(matches!(error, TribeError::Address(_)));
+        let bob = tribechain_core::Address::from_key(tribechain_core::hrp_for_chain(chain_id), "bob").to_string();
+        let mistyped = format!("{}{}", &bob[..bob.len() - 1], if bob.ends_with('q') { 'p' } else { 'q' });
+        assert!(client.call("getBalance", json!([mistyped])).await.is_err());
+        assert_eq!(client.call("getBalance", json!([bob])).await.unwrap(), json!(0));
+
+        let tx = signed(Transaction::new("alice".to_string(), TransactionType::Transfer { to: bob, amount: 5 }, 1, 0)
+            .with_chain_id(chain_id));
+        let result = client.call("sendTransaction", json!([tx])).await.unwrap();
+        assert_eq!(result["tx_hash"], json!(tx.hash));
+        // Resubmitting is refused by the mempool, and the client gets the node's error back
+        let error = client.call("sendTransaction", json!({ "transaction": tx })).await.unwrap_err();
+        assert!(matches!(error, TribeError::Mempool(_)));
+
+        let compute = TransactionType::TensorCompute {
+            operation: "relu".to_string(),
+            input_data: vec![-1.0, 2.0],
+            expected_output_size: 2,
+            max_computation_time: 60,
+            reward: 1_000,
+        };
+        let task_tx = signed(Transaction::new("alice".to_string(), compute, 1, 1).with_chain_id(chain_id));
+        let result = client.call("submitTensorTask", json!([task_tx])).await.unwrap();
+        assert_eq!(result["task_id"], json!(task_tx.hash));
+        let tasks = client.call("getPendingTensorTasks", json!([])).await.unwrap();
+        assert_eq!(tasks[0]["requester"], json!("alice"));
+        assert!(matches!(client.call("submitTensorTask", json!([tx])).await, Err(TribeError::Rpc(_))));
+
+        assert_eq!(state.chain.read().await.pending_transactions.len(), 2);
+        assert!(matches!(client.call("getTwap", json!(["pool"])).await, Err(TribeError::Rpc(_)))); // No contracts attached
+    }
+
+    /// Post `body` to `path` over a kept-alive HTTP connection and return the response body
+    async fn post(stream: &mut BufReader<TcpStream>, path: &str, body: &str) -> Value {
+        let (status, response) = http(stream, &format!("POST {}", path), "", body).await;
+        assert_eq!(status, 200);
+        response
+    }
+
+    /// Send `request_line` with extra `headers`, returning the status code and JSON body
+    async fn http(stream: &mut BufReader<TcpStream>, request_line: &str, headers: &str, body: &str) -> (u16, Value) {
+        let request = format!(
+            "{} HTTP/1.1\r\n{}Content-Type: application/json\r\nContent-Length: {}\r\n\r\n{}", request_line, headers, body.len(), body
+        );
+        stream.get_mut().write_all(request.as_bytes()).await.unwrap();
+        let mut status = String::new();
+        stream.read_line(&mut status).await.unwrap();
+        let status = status.split_whitespace().nth(1).unwrap().parse().unwrap();
+        let mut content_length = 0;
+        loop {
+            let mut header = String::new();
+            stream.read_line(&mut header).await.unwrap();
+            match header.trim_end().split_once(": ") {
+                Some(("Content-Length", length)) => content_length = length.parse().unwrap(),
+                Some(_) => {}
+                None => break,
+            }
+        }
+        let mut body = vec![0u8; content_length];
+        stream.read_exact(&mut body).await.unwrap();
+        (status, if body.is_empty() { Value::Null } else { serde_json::from_slice(&body).unwrap() })
+    }
+
+    #[tokio::test]
+    async fn test_http_eth_compat_graphql_and_gateway() {
+        let dir = std::env::temp_dir().join(format!("tribechain-rpc-{}", uuid::Uuid::new_v4()));
+        let chain = Arc::new(RwLock::new(TribeChain::new(dir.to_str().unwrap()).unwrap()));
+        let server = RpcServer::new(0).unwrap();
+        let mut addrs = Vec::new();
+        let full = RpcState::new(chain.clone())
+            .with_eth_compat()
+            .with_graphql()
+            .with_mining_gateway(Arc::new(Mutex::new(TaskDistributor::new())));
+        for state in [RpcState::new(chain.clone()), full] {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap().to_string();
+            drop(listener);
+            let (server, listen_addr) = (server.clone(), addr.clone());
+            tokio::spawn(async move { server.listen(&listen_addr, state).await });
+            addrs.push(addr);
+        }
+        let connect = |addr: String| async move {
+            loop {
+                match TcpStream::connect(&addr).await {
+                    Ok(stream) => break stream,
+                    Err(_) => tokio::time::sleep(std::time::Duration::from_millis(10)).await,
+                }
+            }
+        };
+
+        // Without the compatibility layer the eth_* methods aren't served
+        drop(connect(addrs[0].clone()).await);
+        let mut client = RpcClient::connect(&addrs[0]).await.unwrap();
+        assert!(matches!(client.call("eth_blockNumber", json!([])).await, Err(TribeError::Rpc(_))));
+
+        let mut stream = BufReader::new(connect(addrs[1].clone()).await);
+        let body = r#"[{"jsonrpc":"2.0","id":1,"method":"eth_blockNumber","params":[]},{"jsonrpc":"2.0","id":2,"method":"getBlockNumber"}]"#;
+        let responses = post(&mut stream, "/", body).await;
+        assert_eq!(responses[0]["result"], json!("0x0"));
+        assert_eq!(responses[1]["result"], json!(0));
+
+        let response = post(&mut stream, GRAPHQL_PATH, r#"{"query":"{ chain { height } block(height: 0) { hash } }"}"#).await;
+        assert_eq!(response["data"]["chain"]["height"], json!(0));
+        assert_eq!(response["data"]["block"]["hash"], json!(chain.read().await.blocks[0].hash));
+
+        // ESP firmware polls the gateway with GETs on the same connection
+        let (status, _) = http(&mut stream, "GET /api/mining/task", "X-Miner-ID: esp-1\r\n", "").await;
+        assert_eq!(status, 204);
+        let (status, config) = http(&mut stream, "GET /api/mining/config?device=ESP32", "", "").await;
+        assert_eq!(status, 200);
+        assert_eq!(config["device_type"], json!("ESP32"));
+        assert_eq!(http(&mut stream, "GET /", "", "").await.0, 405);
+    }
+}
@@ -0,0 +1,169 @@
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tribechain_core::{TribeResult, TribeError, Block};
+use crate::SyncStatus;
+use crate::node::Node;
+use crate::p2p::{MessageType, NetworkMessage, P2PNetwork, PeerInfo};
+
+/// Most blocks served in one `SyncResponse`
+pub const SYNC_BATCH_SIZE: u64 = 500;
+
+/// Ask a peer for its canonical blocks from `from_height` on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncRequest {
+    pub from_height: u64,
+    pub max_blocks: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncResponse {
+    pub blocks: Vec<Block>,
+    pub best_height: u64,
+}
+
+/// Block download from the tallest peer, one batch at a time
+#[derive(Debug)]
+pub struct SyncManager {
+    pub status: SyncStatus,
+    pub sync_peer: Option<String>,
+    pub request_timeout: Duration, // An unanswered batch is asked for again after this
+    requested_at: Option<Instant>,
+    pub is_running: bool,
+}
+
+impl SyncManager {
+    pub fn new(request_timeout: Duration) -> TribeResult<Self> {
+        Ok(Self {
+            status: SyncStatus::NotSynced,
+            sync_peer: None,
+            request_timeout,
+            requested_at: None,
+            is_running: false,
+        })
+    }
+
+    pub async fn start(&mut self) -> TribeResult<()> {
+        self.is_running = true;
+        Ok(())
+    }
+
+    pub async fn stop(&mut self) -> TribeResult<()> {
+        self.sync_peer = None;
+        self.requested_at = None;
+        self.is_running = false;
+        Ok(())
+    }
+
+    pub fn get_status(&self) -> SyncStatus {
+        self.status.clone()
+    }
+
+    /// Ask the tallest peer for the next batch after our `block_count` blocks. While a
+    /// request is outstanding nothing is sent; once it times out any peer may be asked.
+    pub fn start_sync(&mut self, p2p: &mut P2PNetwork, block_count: u64) -> TribeResult<()> {
+        if self.requested_at.is_some_and(|at| at.elapsed() < self.request_timeout) {
+            return Ok(());
+        }
+        self.requested_at = None;
+        self.sync_peer = None;
+
+        let Some(peer_id) = p2p.best_sync_peer() else {
+            self.status = SyncStatus::NotSynced;
+            return Ok(());
+        };
+        let target = p2p.get_peer(&peer_id).map(PeerInfo::best_height).unwrap_or(0);
+        if target < block_count {
+            self.status = SyncStatus::Synced;
+            return Ok(());
+        }
+
+        let request = SyncRequest { from_height: block_count, max_blocks: SYNC_BATCH_SIZE };
+        let message = NetworkMessage::new(MessageType::SyncRequest, p2p.node_id.clone(), serde_json::to_vec(&request)?);
+        p2p.send(&peer_id, &message)?;
+
+        self.sync_peer = Some(peer_id);
+        self.requested_at = Some(Instant::now());
+        self.status = SyncStatus::Syncing { current_block: block_count.saturating_sub(1), target_block: target };
+        Ok(())
+    }
+
+    /// Answer a peer's request from our canonical chain
+    pub fn handle_sync_request(&self, node: &Node, message: &NetworkMessage) -> TribeResult<NetworkMessage> {
+        let request: SyncRequest = serde_json::from_slice(&message.data)?;
+        let response = SyncResponse {
+            blocks: node.get_blocks_from(request.from_height, request.max_blocks.min(SYNC_BATCH_SIZE) as usize),
+            best_height: node.get_block_count().saturating_sub(1),
+        };
+        Ok(NetworkMessage::new(MessageType::SyncResponse, node.config.node_id.clone(), serde_json::to_vec(&response)?))
+    }
+
+    /// Blocks from the peer we are syncing from, for the caller to connect before asking
+    /// for the next batch with `start_sync`. An empty batch means we caught up with it.
+    pub fn handle_sync_response(&mut self, message: &NetworkMessage) -> TribeResult<Vec<Block>> {
+        if self.sync_peer.as_deref() != Some(message.sender.as_str()) {
+            return Err(TribeError::Network(format!("Unsolicited sync response from {}", message.sender)));
+        }
+
+        let response: SyncResponse = serde_json::from_slice(&message.data)?;
+        self.requested_at = None;
+        if response.blocks.is_empty() {
+            self.sync_peer = None;
+            self.status = SyncStatus::Synced;
+        }
+        Ok(response.blocks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NetworkConfig;
+    use crate::handshake::{Handshake, MAINNET_MAGIC};
+    use tribechain_core::{ChainSpec, StorageConfig, TribeChain, DEVNET_CHAIN_ID, MAINNET_CHAIN_ID};
+
+    #[test]
+    fn test_requests_are_served_from_the_canonical_chain() {
+        let mut chain = TribeChain::with_chain_spec("unused", &StorageConfig::memory(), &ChainSpec::dev()).unwrap();
+        for _ in 0..3 {
+            let mut block = chain.get_block_template("miner", None).unwrap().block;
+            block.mine_block(block.difficulty).unwrap();
+            chain.submit_block(block).unwrap();
+        }
+        let node = Node::with_blockchain(NetworkConfig { chain_id: DEVNET_CHAIN_ID, ..Default::default() }, chain).unwrap();
+
+        let sync = SyncManager::new(Duration::from_secs(1)).unwrap();
+        let request = SyncRequest { from_height: 2, max_blocks: 10 };
+        let message = NetworkMessage::new(MessageType::SyncRequest, "peer".to_string(), serde_json::to_vec(&request).unwrap());
+        let reply = sync.handle_sync_request(&node, &message).unwrap();
+
+        let response: SyncResponse = serde_json::from_slice(&reply.data).unwrap();
+        assert_eq!(response.best_height, 3);
+        assert_eq!(response.blocks.iter().map(|block| block.index).collect::<Vec<_>>(), vec![2, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_sync_follows_the_tallest_peer() {
+        let mut p2p = P2PNetwork::new(NetworkConfig::default()).unwrap();
+        let mut sync = SyncManager::new(Duration::from_secs(60)).unwrap();
+        sync.start_sync(&mut p2p, 1).unwrap();
+        assert!(matches!(sync.get_status(), SyncStatus::NotSynced));
+
+        let (near, _far) = tokio::io::duplex(64 * 1024);
+        p2p.attach_peer("tall".to_string(), near, true).unwrap();
+        let handshake = Handshake::new(MAINNET_MAGIC, MAINNET_CHAIN_ID, "tall".to_string()).with_best_height(9);
+        p2p.set_peer_handshake("tall", handshake).unwrap();
+
+        sync.start_sync(&mut p2p, 1).unwrap();
+        assert!(matches!(sync.get_status(), SyncStatus::Syncing { current_block: 0, target_block: 9 }));
+        assert_eq!(sync.sync_peer.as_deref(), Some("tall"));
+
+        let response = |sender: &str, blocks: Vec<Block>| {
+            let data = serde_json::to_vec(&SyncResponse { blocks, best_height: 9 }).unwrap();
+            NetworkMessage::new(MessageType::SyncResponse, sender.to_string(), data)
+        };
+        assert!(sync.handle_sync_response(&response("stranger", vec![Block::genesis()])).is_err());
+        assert_eq!(sync.handle_sync_response(&response("tall", vec![Block::genesis()])).unwrap().len(), 1);
+        assert!(sync.handle_sync_response(&response("tall", Vec::new())).unwrap().is_empty());
+        assert!(matches!(sync.get_status(), SyncStatus::Synced));
+    }
+}
@@ -0,0 +1,356 @@
+use clap::parser::ValueSource;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tribechain::{StorageConfig, TribeResult, TribeError};
+
+/// Read from the working directory when `--config` isn't given
+pub const DEFAULT_CONFIG_FILE: &str = "tribechain.toml";
+
+/// Prefix of environment overrides, as in `TRIBECHAIN_NODE_PORT=9000`
+pub const ENV_PREFIX: &str = "TRIBECHAIN_";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NodeConfig {
+    pub data_dir: String,
+    pub port: u16,
+    pub chain: String, // Preset or chain spec file used by `init`
+    pub connect: Vec<String>, // Peers dialled on start
+    pub keystore: Option<String>, // Account signing keys; <data_dir>/keystore if unset
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RpcConfig {
+    pub enabled: bool,
+    pub bind: String, // Loopback by default; the RPC can submit transactions
+    pub port: u16,
+    pub eth_compat: bool, // Also serve eth_* methods over HTTP for Ethereum tooling
+    pub graphql: bool, // Also serve GraphQL queries at /graphql
+    pub mining_api: bool, // Also serve ESP firmware's REST endpoints under /api/
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MiningConfig {
+    pub address: Option<String>, // Reward address; `mine` takes it positionally otherwise
+    pub capabilities: Option<String>, // Report from `mine bench`
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EspConfig {
+    pub node_url: String,
+    pub threads: u8,
+    pub ai3: bool,
+    pub power_limit: f32, // Watts
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DaemonConfig {
+    pub pid_file: Option<String>, // <data_dir>/tribechain.pid if unset
+    pub socket: Option<String>, // Control socket; <data_dir>/tribechain.sock if unset
+    pub log_file: Option<String>, // <data_dir>/logs/tribechain.log if unset
+    pub log_max_mb: u64, // Rotate once the log reaches this size
+    pub log_daily: bool, // Also rotate at UTC midnight
+    pub log_keep: usize, // Rotated logs kept
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BridgeConfig {
+    pub enabled: bool, // Relay deposits from the EVM chain into the bridge contract
+    pub bridge_id: String, // Bridge contract attested to
+    pub attester: String, // This node's attester account on the bridge
+    pub rpc_url: String, // HTTP JSON-RPC endpoint of the EVM chain
+    pub remote_chain_id: u64,
+    pub bridge_address: String, // Bridge contract on the EVM chain
+    pub confirmations: u64, // Blocks a deposit must be buried under before relaying
+    pub start_block: u64, // First EVM block scanned
+    pub poll_secs: u64,
+}
+
+/// Effective settings: defaults, overridden by the config file, then `TRIBECHAIN_*`
+/// environment variables, then flags given on the command line
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TribeConfig {
+    pub node: NodeConfig,
+    pub rpc: RpcConfig,
+    pub mining: MiningConfig,
+    pub esp: EspConfig,
+    pub storage: StorageConfig,
+    pub daemon: DaemonConfig,
+    pub bridge: BridgeConfig,
+}
+
+impl Default for NodeConfig {
+    fn default() -> Self {
+        Self {
+            data_dir: "./data".to_string(),
+            port: 8333,
+            chain: "mainnet".to_string(),
+            connect: Vec::new(),
+            keystore: None,
+        }
+    }
+}
+
+impl Default for RpcConfig {
+    fn default() -> Self {
+        Self { enabled: true, bind: "127.0.0.1".to_string(), port: 8334, eth_compat: false, graphql: false, mining_api: false }
+    }
+}
+
+impl Default for EspConfig {
+    fn default() -> Self {
+        Self {
+            node_url: "http://localhost:8333".to_string(),
+            threads: 2,
+            ai3: false,
+            power_limit: 3.0,
+        }
+    }
+}
+
+impl Default for DaemonConfig {
+    fn default() -> Self {
+        Self {
+            pid_file: None,
+            socket: None,
+            log_file: None,
+            log_max_mb: 64,
+            log_daily: true,
+            log_keep: 7,
+        }
+    }
+}
+
+impl Default for BridgeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bridge_id: String::new(),
+            attester: String::new(),
+            rpc_url: "http://localhost:8545".to_string(),
+            remote_chain_id: 1,
+            bridge_address: String::new(),
+            confirmations: 12,
+            start_block: 0,
+            poll_secs: 15,
+        }
+    }
+}
+
+fn invalid(message: String) -> TribeError {
+    TribeError::Generic(format!("Invalid configuration: {}", message))
+}
+
+/// Parse an environment value as the type of the setting it replaces
+fn env_value(current: Option<&toml::Value>, raw: &str) -> Result<toml::Value, String> {
+    let value = match current {
+        Some(toml::Value::Integer(_)) => toml::Value::Integer(raw.parse().map_err(|_| "expected an integer")?),
+        Some(toml::Value::Float(_)) => toml::Value::Float(raw.parse().map_err(|_| "expected a number")?),
+        Some(toml::Value::Boolean(_)) => toml::Value::Boolean(raw.parse().map_err(|_| "expected true or false")?),
+        Some(toml::Value::Array(_)) => toml::Value::Array(
+            raw.split(',').map(str::trim).filter(|item| !item.is_empty()).map(|item| toml::Value::String(item.to_string())).collect()
+        ),
+        _ => toml::Value::String(raw.to_string()),
+    };
+    Ok(value)
+}
+
+fn parse_flag<T: std::str::FromStr>(matches: &clap::ArgMatches, id: &str) -> TribeResult<T> {
+    matches.get_one::<String>(id)
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| invalid(format!("--{} must be a number", id)))
+}
+
+impl TribeConfig {
+    /// Read a config file; sections and keys it leaves out keep their defaults
+    pub fn from_file(path: &Path) -> TribeResult<Self> {
+        let data = std::fs::read_to_string(path)
+            .map_err(|e| TribeError::Storage(format!("Failed to read config {}: {}", path.display(), e)))?;
+        toml::from_str(&data).map_err(|e| invalid(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Apply `TRIBECHAIN_<SECTION>_<KEY>` variables, e.g. `TRIBECHAIN_STORAGE_BLOCK_CACHE_MB`.
+    /// Lists are comma-separated. Unknown names are errors rather than silently ignored.
+    pub fn apply_env(&mut self, vars: impl IntoIterator<Item = (String, String)>) -> TribeResult<()> {
+        let mut tree = toml::Value::try_from(&*self).map_err(|e| invalid(e.to_string()))?;
+        let mut changed = false;
+        for (name, raw) in vars {
+            let Some(setting) = name.strip_prefix(ENV_PREFIX) else { continue };
+            let setting = setting.to_lowercase();
+            let (section, key) = setting.split_once('_').ok_or_else(|| invalid(format!("{} names no setting", name)))?;
+            let table = tree.get_mut(section)
+                .and_then(toml::Value::as_table_mut)
+                .ok_or_else(|| invalid(format!("{}: no [{}] section", name, section)))?;
+            let value = env_value(table.get(key), &raw).map_err(|e| invalid(format!("{}: {}", name, e)))?;
+            table.insert(key.to_string(), value);
+            changed = true;
+        }
+        if changed {
+            *self = tree.try_into().map_err(|e: toml::de::Error| invalid(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Apply the flags of `command` that were actually given, so their defaults don't
+    /// mask the file and environment
+    pub fn apply_cli(&mut self, command: &str, matches: &clap::ArgMatches) -> TribeResult<()> {
+        let given = |id: &str| {
+            matches.try_get_raw(id).ok().flatten().is_some()
+                && matches.value_source(id) == Some(ValueSource::CommandLine)
+        };
+        let value = |id: &str| matches.get_one::<String>(id).cloned().unwrap_or_default();
+
+        if given("data-dir") {
+            self.node.data_dir = value("data-dir");
+        }
+        if given("port") {
+            self.node.port = parse_flag(matches, "port")?;
+        }
+        if given("connect") {
+            self.node.connect = matches.get_many::<String>("connect").into_iter().flatten().cloned().collect();
+        }
+        if given("chain") {
+            self.node.chain = value("chain");
+        }
+        if given("storage") {
+            self.storage.backend = value("storage").parse().map_err(invalid)?;
+        }
+        // Other commands' `address` arguments name accounts and contracts
+        if command == "mine" && given("address") {
+            self.mining.address = Some(value("address"));
+        }
+        if given("capabilities") {
+            self.mining.capabilities = Some(value("capabilities"));
+        }
+        if given("node-url") {
+            self.esp.node_url = value("node-url");
+        }
+        if given("threads") {
+            self.esp.threads = parse_flag(matches, "threads")?;
+        }
+        if given("power-limit") {
+            self.esp.power_limit = parse_flag(matches, "power-limit")?;
+        }
+        if matches.try_get_raw("ai3").is_ok() && matches.get_flag("ai3") {
+            self.esp.ai3 = true;
+        }
+        Ok(())
+    }
+
+    /// Settings for the command `matches` selects: the file named by `--config`, or
+    /// `tribechain.toml` if present, then the environment, then every subcommand's flags
+    pub fn load(matches: &clap::ArgMatches) -> TribeResult<Self> {
+        let mut config = match matches.get_one::<String>("config") {
+            Some(path) => Self::from_file(Path::new(path))?,
+            None if Path::new(DEFAULT_CONFIG_FILE).exists() => Self::from_file(Path::new(DEFAULT_CONFIG_FILE))?,
+            None => Self::default(),
+        };
+        config.apply_env(std::env::vars())?;
+
+        let mut current = matches;
+        while let Some((command, sub_matches)) = current.subcommand() {
+            config.apply_cli(command, sub_matches)?;
+            current = sub_matches;
+        }
+        Ok(config)
+    }
+
+    fn data_path(&self, configured: &Option<String>, default: &str) -> PathBuf {
+        configured.as_ref().map(PathBuf::from).unwrap_or_else(|| Path::new(&self.node.data_dir).join(default))
+    }
+
+    pub fn rpc_address(&self) -> String {
+        format!("{}:{}", self.rpc.bind, self.rpc.port)
+    }
+
+    pub fn pid_file(&self) -> PathBuf {
+        self.data_path(&self.daemon.pid_file, "tribechain.pid")
+    }
+
+    pub fn control_socket(&self) -> PathBuf {
+        self.data_path(&self.daemon.socket, "tribechain.sock")
+    }
+
+    pub fn keystore_dir(&self) -> PathBuf {
+        self.data_path(&self.node.keystore, "keystore")
+    }
+
+    /// Watch-only accounts of `wallet watch`
+    pub fn watchlist_file(&self) -> PathBuf {
+        Path::new(&self.node.data_dir).join("watchlist.json")
+    }
+
+    pub fn log_file(&self) -> PathBuf {
+        self.data_path(&self.daemon.log_file, "logs/tribechain.log")
+    }
+
+    pub fn to_toml(&self) -> TribeResult<String> {
+        toml::to_string_pretty(self).map_err(|e| invalid(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::{Arg, Command};
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+    }
+
+    #[test]
+    fn test_file_then_env_then_flags() {
+        let path = std::env::temp_dir().join(format!("tribechain-config-{}.toml", std::process::id()));
+        std::fs::write(&path, "[node]\nport = 9000\ndata_dir = \"/srv/tribe\"\n\n[rpc]\ngraphql = true\n").unwrap();
+        let mut config = TribeConfig::from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!((config.node.port, config.node.data_dir.as_str(), config.rpc.graphql), (9000, "/srv/tribe", true));
+        assert_eq!(config.rpc.port, RpcConfig::default().port); // Left out, so defaulted
+
+        config.apply_env(vars(&[
+            ("TRIBECHAIN_NODE_PORT", "9100"),
+            ("TRIBECHAIN_NODE_CONNECT", "10.0.0.1:8333, 10.0.0.2:8333"),
+            ("TRIBECHAIN_STORAGE_BLOCK_CACHE_MB", "512"),
+            ("HOME", "/root"),
+        ])).unwrap();
+        assert_eq!(config.node.port, 9100);
+        assert_eq!(config.node.connect, vec!["10.0.0.1:8333".to_string(), "10.0.0.2:8333".to_string()]);
+        assert_eq!(config.storage.block_cache_mb, 512);
+
+        // Only flags actually given override; `data-dir` falls back to its clap default
+        let command = Command::new("node")
+            .arg(Arg::new("port").long("port"))
+            .arg(Arg::new("data-dir").long("data-dir").default_value("./data"));
+        let matches = command.get_matches_from(["node", "--port", "9200"]);
+        config.apply_cli("node", &matches).unwrap();
+        assert_eq!((config.node.port, config.node.data_dir.as_str()), (9200, "/srv/tribe"));
+        assert_eq!(config.keystore_dir(), Path::new("/srv/tribe/keystore"));
+
+        let printed = config.to_toml().unwrap();
+        assert_eq!(toml::from_str::<TribeConfig>(&printed).unwrap(), config);
+    }
+
+    #[test]
+    fn test_bad_settings_are_errors() {
+        let mut config = TribeConfig::default();
+        assert!(config.apply_env(vars(&[("TRIBECHAIN_NODE_PORT", "many")])).is_err());
+        assert!(config.apply_env(vars(&[("TRIBECHAIN_WALLET_NAME", "main")])).is_err());
+        assert!(config.apply_env(vars(&[("TRIBECHAIN_NODE_COLOUR", "blue")])).is_err());
+        assert_eq!(config, TribeConfig::default());
+
+        let path = std::env::temp_dir().join(format!("tribechain-config-typo-{}.toml", std::process::id()));
+        std::fs::write(&path, "[node]\nprot = 9000\n").unwrap();
+        let result = TribeConfig::from_file(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(result.is_err());
+
+        let matches = Command::new("node").arg(Arg::new("port").long("port")).get_matches_from(["node", "--port", "http"]);
+        assert!(config.apply_cli("node", &matches).is_err());
+    }
+}
@@ -0,0 +1,274 @@
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+use serde_json::{json, Value};
+use std::path::Path;
+use tribechain::{RpcClient, Transaction, TransactionType, TribeResult, TribeError};
+use crate::keystore::Keystore;
+use crate::signer::{KeystoreSigner, Signer};
+
+/// Console commands and their arguments, as listed by `help`
+const COMMANDS: &[(&str, &str)] = &[
+    ("help", "Show this list"),
+    ("info", "Chain ID, height and best block"),
+    ("stats", "Chain statistics"),
+    ("block", "[HEIGHT|HASH]  Block, the latest if omitted"),
+    ("balance", "ADDRESS"),
+    ("nonce", "ADDRESS  Confirmed and pending nonce"),
+    ("history", "ADDRESS [PAGE]  Transactions touching an address"),
+    ("receipt", "TX_HASH"),
+    ("send", "FROM TO AMOUNT  Transfer AMOUNT TRIBE"),
+    ("call", "FROM CONTRACT METHOD [VALUE] [JSON_ARGS]  Submit a contract call"),
+    ("tensor", "REQUESTER OPERATION DATA [REWARD]  Submit a tensor task; DATA is comma-separated"),
+    ("tasks", "Pending tensor tasks"),
+    ("rpc", "METHOD [JSON_PARAMS]  Raw RPC call"),
+    ("exit", "Leave the console"),
+];
+
+/// Methods offered for completion after `rpc`
+const RPC_METHODS: &[&str] = &[
+    "getChainInfo", "getStats", "getBlockNumber", "getBlock", "getPendingTensorTasks", "getMiningStatus",
+    "getNetworkHashrate", "getDifficultyHistory", "getBlockTemplate", "submitBlock", "getNetworkStatus",
+    "getBalance", "getStateProof", "getAccountNonce", "getTransactionsForAddress", "scanAddress",
+    "estimateFee", "getTransactionReceipt", "getLogs", "sendTransaction", "submitTensorTask",
+    "getTwap", "getContractAbi", "encodeContractCall",
+    "pushTelemetry", "getDeviceMetrics", "getTelemetryAlerts", "getDevices",
+    "getPoolStats", "getPoolMiner", "getPoolRounds",
+];
+
+/// Reward for tensor tasks submitted without one: 0.1 TRIBE
+const DEFAULT_TENSOR_REWARD: u64 = 100_000;
+
+/// Completes command names, and method names after `rpc`
+struct ConsoleHelper;
+
+impl Completer for ConsoleHelper {
+    type Candidate = String;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<String>)> {
+        let line = &line[..pos];
+        let start = line.rfind(char::is_whitespace).map_or(0, |space| space + 1);
+        let word = &line[start..];
+        let candidates: Vec<&str> = match line[..start].split_whitespace().collect::<Vec<_>>().as_slice() {
+            [] => COMMANDS.iter().map(|(name, _)| *name).collect(),
+            ["rpc"] => RPC_METHODS.to_vec(),
+            _ => Vec::new(),
+        };
+        let matches = candidates.into_iter().filter(|candidate| candidate.starts_with(word)).map(str::to_string).collect();
+        Ok((start, matches))
+    }
+}
+
+impl Hinter for ConsoleHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ConsoleHelper {}
+
+impl Validator for ConsoleHelper {}
+
+impl Helper for ConsoleHelper {}
+
+/// Split off the next whitespace-separated word
+fn next_word(input: &str) -> (Option<&str>, &str) {
+    let input = input.trim_start();
+    if input.is_empty() {
+        return (None, input);
+    }
+    let end = input.find(char::is_whitespace).unwrap_or(input.len());
+    (Some(&input[..end]), &input[end..])
+}
+
+fn usage(command: &str) -> TribeError {
+    let arguments = COMMANDS.iter().find(|(name, _)| *name == command).map_or("", |(_, arguments)| arguments);
+    TribeError::Generic(format!("Usage: {} {}", command, arguments))
+}
+
+fn parse_json(input: &str, default: Value) -> TribeResult<Value> {
+    match input.trim() {
+        "" => Ok(default),
+        input => serde_json::from_str(input).map_err(|e| TribeError::Generic(format!("Invalid JSON: {}", e))),
+    }
+}
+
+/// Session against one node's RPC
+pub struct Console {
+    client: RpcClient,
+    chain_id: u64, // Transactions are bound to the node's chain
+    keystore: Keystore, // Keys transactions are signed with
+}
+
+impl Console {
+    pub async fn connect(addr: &str, keystore: Keystore) -> TribeResult<Self> {
+        let mut client = RpcClient::connect(addr).await?;
+        let info = client.call("getChainInfo", json!([])).await?;
+        let chain_id = info["chain_id"].as_u64()
+            .ok_or_else(|| TribeError::Rpc("Node did not report its chain ID".to_string()))?;
+        Ok(Self { client, chain_id, keystore })
+    }
+
+    /// Run one console command and return what it printed
+    pub async fn execute(&mut self, line: &str) -> TribeResult<Value> {
+        let (command, rest) = next_word(line);
+        let Some(command) = command else { return Ok(Value::Null) };
+        let (first, after_first) = next_word(rest);
+        let (second, after_second) = next_word(after_first);
+
+        match command {
+            "help" => Ok(Value::String(
+                COMMANDS.iter().map(|(name, arguments)| format!("  {:<8} {}", name, arguments)).collect::<Vec<_>>().join("\n")
+            )),
+            "info" => self.client.call("getChainInfo", json!([])).await,
+            "stats" => self.client.call("getStats", json!([])).await,
+            "block" => {
+                let block = match first {
+                    None => self.client.call("getBlockNumber", json!([])).await?,
+                    Some(block) => block.parse::<u64>().map(Value::from).unwrap_or_else(|_| json!(block)),
+                };
+                self.client.call("getBlock", json!([block])).await
+            }
+            "balance" => {
+                let address = first.ok_or_else(|| usage(command))?;
+                self.client.call("getBalance", json!([address])).await
+            }
+            "nonce" => {
+                let address = first.ok_or_else(|| usage(command))?;
+                self.client.call("getAccountNonce", json!([address])).await
+            }
+            "history" => {
+                let address = first.ok_or_else(|| usage(command))?;
+                let page: u64 = second.map(str::parse).transpose().map_err(|_| usage(command))?.unwrap_or(0);
+                self.client.call("getTransactionsForAddress", json!([address, page])).await
+            }
+            "receipt" => {
+                let tx_hash = first.ok_or_else(|| usage(command))?;
+                self.client.call("getTransactionReceipt", json!([tx_hash])).await
+            }
+            "send" => {
+                let (Some(from), Some(to), (Some(amount), _)) = (first, second, next_word(after_second)) else {
+                    return Err(usage(command));
+                };
+                let amount = amount.parse::<f64>().map_err(|_| usage(command))?;
+                let transfer = TransactionType::Transfer { to: to.to_string(), amount: (amount * 1_000_000.0) as u64 };
+                self.submit("sendTransaction", from, transfer).await
+            }
+            "call" => {
+                let (Some(from), Some(contract), (Some(method), rest)) = (first, second, next_word(after_second)) else {
+                    return Err(usage(command));
+                };
+                let (value, args) = match next_word(rest) {
+                    (Some(value), args) if value.parse::<u64>().is_ok() => (value.parse().unwrap_or(0), args),
+                    _ => (0, rest),
+                };
+                let args = parse_json(args, json!([]))?;
+                let encoded = self.client.call("encodeContractCall", json!([contract, method, args])).await?;
+                let args = hex::decode(encoded["data"].as_str().unwrap_or_default())
+                    .map_err(|e| TribeError::Rpc(format!("Invalid encoded call: {}", e)))?;
+                let call = TransactionType::ContractCall {
+                    contract_address: contract.to_string(),
+                    method: method.to_string(),
+                    args,
+                    value,
+                };
+                self.submit("sendTransaction", from, call).await
+            }
+            "tensor" => {
+                let (Some(requester), Some(operation), (Some(data), rest)) = (first, second, next_word(after_second)) else {
+                    return Err(usage(command));
+                };
+                let input_data = data.split(',')
+                    .map(|value| value.trim().parse::<f32>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| usage(command))?;
+                let reward = next_word(rest).0.map(str::parse).transpose().map_err(|_| usage(command))?
+                    .unwrap_or(DEFAULT_TENSOR_REWARD);
+                let compute = TransactionType::TensorCompute {
+                    operation: operation.to_string(),
+                    expected_output_size: input_data.len(),
+                    input_data,
+                    max_computation_time: 60,
+                    reward,
+                };
+                self.submit("submitTensorTask", requester, compute).await
+            }
+            "tasks" => self.client.call("getPendingTensorTasks", json!([])).await,
+            "rpc" => {
+                let method = first.ok_or_else(|| usage(command))?;
+                let params = parse_json(after_first, json!([]))?;
+                self.client.call(method, params).await
+            }
+            command => Err(TribeError::Generic(format!("Unknown command: {}; try help", command))),
+        }
+    }
+
+    /// Build a transaction from `from` at its next nonce and the node's current gas
+    /// price, sign it with `from`'s keystore key and submit it with `method`
+    async fn submit(&mut self, method: &str, from: &str, transaction_type: TransactionType) -> TribeResult<Value> {
+        let mut signer = KeystoreSigner::new(&self.keystore, from)?;
+        let nonce = self.client.call("getAccountNonce", json!([from])).await?["pending_nonce"].as_u64().unwrap_or(0);
+        let gas_price = self.client.call("estimateFee", json!([1])).await?["gas_price"].as_u64().unwrap_or(0);
+        let mut transaction = Transaction::new(from.to_string(), transaction_type, 0, nonce)
+            .with_chain_id(self.chain_id)
+            .with_gas_price(gas_price);
+        signer.sign(&mut transaction)?;
+        self.client.call(method, json!([transaction])).await
+    }
+}
+
+fn print_output(output: &Value) {
+    match output {
+        Value::String(text) => println!("{}", text),
+        output => println!("{}", serde_json::to_string_pretty(output).unwrap_or_default()),
+    }
+}
+
+/// Interactive session until `exit` or end of input; history is kept in `history`
+pub async fn run(addr: &str, keystore: Keystore, history: &Path) -> TribeResult<()> {
+    let mut console = Console::connect(addr, keystore).await?;
+    let mut editor: Editor<ConsoleHelper, DefaultHistory> = Editor::new()
+        .map_err(|e| TribeError::Generic(format!("Failed to start the console: {}", e)))?;
+    editor.set_helper(Some(ConsoleHelper));
+    let _ = editor.load_history(history); // None yet on first use
+
+    println!("Connected to {} (chain ID {}); type help for commands", addr, console.chain_id);
+    loop {
+        // Reading blocks; keep it off the runtime's other tasks
+        let line = match tokio::task::block_in_place(|| editor.readline("tribe> ")) {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(TribeError::Generic(format!("Failed to read input: {}", e))),
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let _ = editor.add_history_entry(line);
+        if matches!(line, "exit" | "quit") {
+            break;
+        }
+        match console.execute(line).await {
+            Ok(output) => print_output(&output),
+            Err(e) => eprintln!("{}", e),
+        }
+    }
+
+    if let Some(parent) = history.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    editor.save_history(history)
+        .map_err(|e| TribeError::Generic(format!("Failed to save console history: {}", e)))
+}
+
+/// Run the `;`-separated `commands` without prompting, stopping at the first failure
+pub async fn exec(addr: &str, keystore: Keystore, commands: &str) -> TribeResult<()> {
+    let mut console = Console::connect(addr, keystore).await?;
+    for command in commands.split(';').map(str::trim).filter(|command| !command.is_empty()) {
+        print_output(&console.execute(command).await?);
+    }
+    Ok(())
+}
@@ -0,0 +1,430 @@
+use chrono::{NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+use tokio::net::{UnixListener, UnixStream};
+use tribechain::{Shutdown, TribeResult, TribeError};
+use crate::config::TribeConfig;
+use crate::DAEMON_CHILD_FLAG;
+
+/// How long `--daemon` waits for the detached node to write its PID file
+const DETACH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Longest a control request or reply may take
+const CONTROL_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn io_error(action: &str, path: &Path, e: std::io::Error) -> TribeError {
+    TribeError::Generic(format!("Failed to {} {}: {}", action, path.display(), e))
+}
+
+/// Whether a process with this ID exists
+pub fn process_alive(pid: u32) -> bool {
+    // Signal 0 only checks the process exists and we may signal it; EPERM means it exists
+    // SAFETY: sending a signal has no memory-safety requirements
+    let signalled = unsafe { libc::kill(pid as libc::pid_t, 0) } == 0;
+    signalled || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// PID recorded in `path`, if the file exists and holds one
+pub fn read_pid(path: &Path) -> Option<u32> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// PID file held for as long as the node runs, so a second node can't open the same
+/// data directory. Removed on drop.
+#[derive(Debug)]
+pub struct PidFile {
+    path: PathBuf,
+}
+
+impl PidFile {
+    /// Record our PID, replacing a stale file left by a node that crashed
+    pub fn acquire(path: &Path) -> TribeResult<Self> {
+        if let Some(pid) = read_pid(path).filter(|pid| *pid != std::process::id()) {
+            if process_alive(pid) {
+                return Err(TribeError::Generic(format!(
+                    "A node is already running with PID {} ({})", pid, path.display()
+                )));
+            }
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| io_error("create", parent, e))?;
+        }
+        fs::write(path, format!("{}\n", std::process::id())).map_err(|e| io_error("write", path, e))?;
+        Ok(Self { path: path.to_path_buf() })
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        // Leave the file alone if another node has since taken it over
+        if read_pid(&self.path) == Some(std::process::id()) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Log file rotated once it reaches `max_bytes` and, if `daily`, at UTC midnight.
+/// Rotated files are numbered `.1` (newest) to `.keep`; older ones are deleted.
+#[derive(Debug)]
+pub struct RotatingLog {
+    path: PathBuf,
+    max_bytes: u64,
+    daily: bool,
+    keep: usize,
+    file: File,
+    size: u64,
+    day: NaiveDate, // When the current file was started
+}
+
+impl RotatingLog {
+    pub fn open(path: &Path, max_bytes: u64, daily: bool, keep: usize) -> TribeResult<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| io_error("create", parent, e))?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)
+            .map_err(|e| io_error("open", path, e))?;
+        let metadata = file.metadata().map_err(|e| io_error("read", path, e))?;
+        let day = metadata.modified().ok()
+            .map(|modified| chrono::DateTime::<Utc>::from(modified).date_naive())
+            .unwrap_or_else(|| Utc::now().date_naive());
+        Ok(Self { path: path.to_path_buf(), max_bytes, daily, keep, file, size: metadata.len(), day })
+    }
+
+    fn rotated(&self, n: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{}", n));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&mut self, today: NaiveDate) -> TribeResult<()> {
+        if self.keep == 0 {
+            let _ = fs::remove_file(&self.path);
+        } else {
+            let _ = fs::remove_file(self.rotated(self.keep));
+            for n in (1..self.keep).rev() {
+                let _ = fs::rename(self.rotated(n), self.rotated(n + 1));
+            }
+            fs::rename(&self.path, self.rotated(1)).map_err(|e| io_error("rotate", &self.path, e))?;
+        }
+        self.file = OpenOptions::new().create(true).append(true).open(&self.path)
+            .map_err(|e| io_error("open", &self.path, e))?;
+        self.size = 0;
+        self.day = today;
+        Ok(())
+    }
+
+    /// Append a timestamped line, rotating first if it's due
+    pub fn write_line(&mut self, line: &str) -> TribeResult<()> {
+        let now = Utc::now();
+        let entry = format!("{} {}\n", now.format("%Y-%m-%dT%H:%M:%SZ"), line);
+        let today = now.date_naive();
+        let full = self.size > 0 && self.size + entry.len() as u64 > self.max_bytes;
+        if full || (self.daily && self.size > 0 && today != self.day) {
+            self.rotate(today)?;
+        }
+        self.file.write_all(entry.as_bytes()).map_err(|e| io_error("write", &self.path, e))?;
+        self.size += entry.len() as u64;
+        Ok(())
+    }
+}
+
+/// Start this command again as a detached node and return its PID. The child is
+/// given `--daemon-child` in place of `--daemon` and no terminal.
+pub fn detach(config: &TribeConfig) -> TribeResult<u32> {
+    if let Some(pid) = read_pid(&config.pid_file()).filter(|pid| process_alive(*pid)) {
+        return Err(TribeError::Generic(format!("A node is already running with PID {}", pid)));
+    }
+    let exe = std::env::current_exe()
+        .map_err(|e| TribeError::Generic(format!("Cannot find the tribechain executable: {}", e)))?;
+    let mut args: Vec<String> = std::env::args().skip(1).filter(|arg| arg != "--daemon").collect();
+    args.push(format!("--{}", DAEMON_CHILD_FLAG));
+
+    let mut child = std::process::Command::new(exe)
+        .args(args)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .map_err(|e| TribeError::Generic(format!("Failed to start the node in the background: {}", e)))?;
+
+    // Report a node that fails on startup, e.g. on a bad config, rather than leave it unnoticed
+    let deadline = std::time::Instant::now() + DETACH_TIMEOUT;
+    while read_pid(&config.pid_file()) != Some(child.id()) {
+        if let Ok(Some(exit)) = child.try_wait() {
+            return Err(TribeError::Generic(format!(
+                "Node exited on startup ({}); see {}", exit, config.log_file().display()
+            )));
+        }
+        if std::time::Instant::now() >= deadline {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    Ok(child.id())
+}
+
+/// Ask a node to stop with SIGTERM, for when its control socket is unreachable
+pub fn terminate(pid: u32) -> TribeResult<()> {
+    // SAFETY: sending a signal has no memory-safety requirements
+    if unsafe { libc::kill(pid as libc::pid_t, libc::SIGTERM) } != 0 {
+        return Err(TribeError::Generic(format!(
+            "Failed to signal PID {}: {}", pid, std::io::Error::last_os_error()
+        )));
+    }
+    Ok(())
+}
+
+/// Wait up to `timeout` for `pid` to exit; false if it is still running
+pub async fn wait_for_exit(pid: u32, timeout: Duration) -> bool {
+    let deadline = tokio::time::Instant::now() + timeout;
+    while process_alive(pid) {
+        if tokio::time::Instant::now() >= deadline {
+            return false;
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+    true
+}
+
+/// In the detached child: leave the terminal's session and send stdout and stderr
+/// to the rotating log
+pub fn enter_daemon(config: &TribeConfig) -> TribeResult<()> {
+    let log_path = config.log_file();
+    let mut log = RotatingLog::open(
+        &log_path,
+        config.daemon.log_max_mb.max(1) * 1024 * 1024,
+        config.daemon.log_daily,
+        config.daemon.log_keep,
+    )?;
+
+    let mut fds = [0 as libc::c_int; 2];
+    // SAFETY: plain syscalls on descriptors this process owns
+    unsafe {
+        libc::setsid();
+        if libc::pipe(fds.as_mut_ptr()) != 0
+            || libc::dup2(fds[1], libc::STDOUT_FILENO) < 0
+            || libc::dup2(fds[1], libc::STDERR_FILENO) < 0
+        {
+            return Err(io_error("redirect output to", &log_path, std::io::Error::last_os_error()));
+        }
+        libc::close(fds[1]);
+    }
+
+    // SAFETY: the read end was just created and nothing else owns it
+    let output = unsafe { <File as std::os::fd::FromRawFd>::from_raw_fd(fds[0]) };
+    std::thread::spawn(move || {
+        for line in BufReader::new(output).lines() {
+            let Ok(line) = line else { break };
+            // Nowhere left to report a failing log
+            let _ = log.write_line(&line);
+        }
+    });
+    Ok(())
+}
+
+/// What `tribechain status` reports
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeStatus {
+    pub pid: u32,
+    pub started_at: u64,
+    pub uptime_secs: u64,
+    pub data_dir: String,
+    pub port: u16,
+    pub rpc_port: Option<u16>,
+    pub daemon: bool,
+    pub log_file: Option<String>,
+}
+
+/// Commands accepted on the control socket, one per line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ControlCommand {
+    Status,
+    Stop,
+}
+
+/// Listens on the node's control socket until shutdown; the socket file is removed
+/// when this drops
+#[derive(Debug)]
+pub struct ControlServer {
+    path: PathBuf,
+}
+
+impl ControlServer {
+    /// Answer `status` with `status` (its uptime filled in per request), and trigger
+    /// `shutdown` on `stop`
+    pub fn spawn(path: &Path, status: NodeStatus, shutdown: Shutdown) -> TribeResult<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| io_error("create", parent, e))?;
+        }
+        // A socket left by a crashed node would make bind fail; the PID file has
+        // already shown no live node owns it
+        let _ = fs::remove_file(path);
+        let listener = UnixListener::bind(path).map_err(|e| io_error("bind control socket", path, e))?;
+
+        let stopped = shutdown.clone();
+        tokio::spawn(async move {
+            loop {
+                let stream = tokio::select! {
+                    accepted = listener.accept() => match accepted {
+                        Ok((stream, _)) => stream,
+                        Err(e) => {
+                            eprintln!("Control socket failed: {}", e);
+                            break;
+                        }
+                    },
+                    _ = stopped.wait() => break,
+                };
+                let (status, shutdown) = (status.clone(), shutdown.clone());
+                tokio::spawn(async move {
+                    if let Err(e) = handle_control(stream, status, shutdown).await {
+                        eprintln!("Control request failed: {}", e);
+                    }
+                });
+            }
+        });
+        Ok(Self { path: path.to_path_buf() })
+    }
+}
+
+impl Drop for ControlServer {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+async fn handle_control(stream: UnixStream, mut status: NodeStatus, shutdown: Shutdown) -> TribeResult<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut line = String::new();
+    tokio::time::timeout(CONTROL_TIMEOUT, tokio::io::BufReader::new(reader).read_line(&mut line))
+        .await
+        .map_err(|_| TribeError::Generic("Control request timed out".to_string()))?
+        .map_err(|e| TribeError::Generic(format!("Failed to read control request: {}", e)))?;
+
+    let reply = match serde_json::from_value::<ControlCommand>(serde_json::Value::String(line.trim().to_string())) {
+        Ok(ControlCommand::Status) => {
+            status.uptime_secs = (Utc::now().timestamp() as u64).saturating_sub(status.started_at);
+            serde_json::to_value(&status).unwrap_or_default()
+        }
+        Ok(ControlCommand::Stop) => {
+            println!("Stop requested over the control socket");
+            shutdown.trigger();
+            serde_json::json!({ "stopping": true })
+        }
+        Err(_) => serde_json::json!({ "error": format!("Unknown command: {}", line.trim()) }),
+    };
+    writer.write_all(format!("{}\n", reply).as_bytes()).await
+        .map_err(|e| TribeError::Generic(format!("Failed to send control reply: {}", e)))
+}
+
+/// Send `command` to the node listening on `path` and return its reply
+pub async fn send_control(path: &Path, command: ControlCommand) -> TribeResult<serde_json::Value> {
+    let exchange = async {
+        let stream = UnixStream::connect(path).await.map_err(|e| io_error("connect to", path, e))?;
+        let (reader, mut writer) = stream.into_split();
+        let request = serde_json::to_value(command).unwrap_or_default();
+        writer.write_all(format!("{}\n", request.as_str().unwrap_or_default()).as_bytes()).await
+            .map_err(|e| io_error("write to", path, e))?;
+        let mut line = String::new();
+        tokio::io::BufReader::new(reader).read_line(&mut line).await
+            .map_err(|e| io_error("read from", path, e))?;
+        serde_json::from_str(&line)
+            .map_err(|e| TribeError::Generic(format!("Invalid control reply: {}", e)))
+    };
+    tokio::time::timeout(CONTROL_TIMEOUT, exchange).await
+        .map_err(|_| TribeError::Generic(format!("No reply from {}", path.display())))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("tribechain-{}-{}", name, uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_pid_file_excludes_a_second_live_node() {
+        let dir = temp_dir("pid");
+        let path = dir.join("tribechain.pid");
+
+        // A crashed node's PID is replaced
+        let mut exited = std::process::Command::new("true").spawn().unwrap();
+        exited.wait().unwrap();
+        fs::write(&path, format!("{}\n", exited.id())).unwrap();
+        let pid_file = PidFile::acquire(&path).unwrap();
+        assert_eq!(read_pid(&path), Some(std::process::id()));
+        drop(pid_file);
+        assert!(!path.exists());
+
+        // A live one isn't; PID 1 always runs
+        fs::write(&path, "1\n").unwrap();
+        assert!(PidFile::acquire(&path).is_err());
+        assert_eq!(read_pid(&path), Some(1));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_log_rotates_by_size_and_keeps_the_newest() {
+        let dir = temp_dir("log");
+        let path = dir.join("tribechain.log");
+        let mut log = RotatingLog::open(&path, 64, false, 2).unwrap();
+        for n in 0..4 {
+            log.write_line(&format!("entry {} {}", n, "x".repeat(30))).unwrap();
+        }
+
+        // One entry per file once the limit is passed; the oldest fell off
+        let read = |path: PathBuf| fs::read_to_string(path).unwrap();
+        assert!(read(path.clone()).contains("entry 3"));
+        assert!(read(log.rotated(1)).contains("entry 2"));
+        assert!(read(log.rotated(2)).contains("entry 1"));
+        assert!(!log.rotated(3).exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_control_socket_reports_status_and_stops() {
+        let dir = temp_dir("control");
+        let path = dir.join("tribechain.sock");
+        let status = NodeStatus {
+            pid: std::process::id(),
+            started_at: Utc::now().timestamp() as u64,
+            uptime_secs: 0,
+            data_dir: dir.display().to_string(),
+            port: 8333,
+            rpc_port: Some(8334),
+            daemon: false,
+            log_file: None,
+        };
+        let shutdown = Shutdown::new();
+        let server = ControlServer::spawn(&path, status, shutdown.clone()).unwrap();
+
+        let reply = send_control(&path, ControlCommand::Status).await.unwrap();
+        assert_eq!(reply["pid"], serde_json::json!(std::process::id()));
+        assert_eq!(reply["rpc_port"], serde_json::json!(8334));
+        assert!(!shutdown.is_triggered());
+
+        // Unknown commands are answered, not dropped
+        let stream = UnixStream::connect(&path).await.unwrap();
+        let (reader, mut writer) = stream.into_split();
+        writer.write_all(b"restart\n").await.unwrap();
+        let mut line = String::new();
+        tokio::io::BufReader::new(reader).read_line(&mut line).await.unwrap();
+        assert!(line.contains("Unknown command: restart"), "{}", line);
+
+        let reply = send_control(&path, ControlCommand::Stop).await.unwrap();
+        assert_eq!(reply, serde_json::json!({ "stopping": true }));
+        assert!(shutdown.is_triggered());
+
+        drop(server);
+        assert!(!path.exists());
+        assert!(send_control(&path, ControlCommand::Status).await.is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,295 @@
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Paragraph, Row, Sparkline, Table};
+use ratatui::{DefaultTerminal, Frame};
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use tribechain::{RpcClient, TribeResult, TribeError};
+
+/// Blocks `getMiningStatus` averages over
+const MINING_WINDOW: u64 = 100;
+
+/// Hash-rate samples kept for the sparkline
+const HASH_RATE_HISTORY: usize = 120;
+
+/// Latest answers from the node; `None` where a method failed or isn't served
+#[derive(Default)]
+struct Snapshot {
+    chain: Option<Value>,
+    stats: Option<Value>,
+    fee: Option<Value>,
+    network: Option<Value>,
+    mining: Option<Value>,
+    devices: Option<Value>,
+}
+
+/// Polls one node's RPC and renders what it reports
+struct Dashboard {
+    addr: String,
+    client: Option<RpcClient>, // Reconnected on the next refresh after a failure
+    snapshot: Snapshot,
+    hash_rates: VecDeque<u64>,
+    error: Option<String>,
+    updated_at: u64,
+}
+
+fn ui_error(e: std::io::Error) -> TribeError {
+    TribeError::Generic(format!("Terminal error: {}", e))
+}
+
+/// `value[key]` for display, `-` when missing
+fn field(value: &Option<Value>, key: &str) -> String {
+    match value.as_ref().map(|value| &value[key]) {
+        None | Some(Value::Null) => "-".to_string(),
+        Some(Value::String(text)) => text.clone(),
+        Some(Value::Number(number)) if number.is_f64() => format!("{:.2}", number.as_f64().unwrap_or_default()),
+        Some(other) => other.to_string(),
+    }
+}
+
+fn format_hash_rate(rate: f64) -> String {
+    let units = ["H/s", "kH/s", "MH/s", "GH/s", "TH/s"];
+    let mut rate = rate;
+    let mut unit = 0;
+    while rate >= 1000.0 && unit < units.len() - 1 {
+        rate /= 1000.0;
+        unit += 1;
+    }
+    format!("{:.2} {}", rate, units[unit])
+}
+
+fn format_age(now: u64, timestamp: u64) -> String {
+    match now.saturating_sub(timestamp) {
+        seconds if seconds < 60 => format!("{}s ago", seconds),
+        seconds if seconds < 3600 => format!("{}m ago", seconds / 60),
+        seconds => format!("{}h ago", seconds / 3600),
+    }
+}
+
+fn panel(title: &str) -> Block<'_> {
+    Block::default().borders(Borders::ALL).title(title)
+}
+
+fn key_value(rows: Vec<(&str, String)>) -> Vec<Line<'static>> {
+    rows.into_iter().map(|(key, value)| Line::from(format!("{:<12} {}", key, value))).collect()
+}
+
+impl Dashboard {
+    fn new(addr: &str) -> Self {
+        Self {
+            addr: addr.to_string(),
+            client: None,
+            snapshot: Snapshot::default(),
+            hash_rates: VecDeque::with_capacity(HASH_RATE_HISTORY),
+            error: None,
+            updated_at: 0,
+        }
+    }
+
+    async fn refresh(&mut self) {
+        if self.client.is_none() {
+            match RpcClient::connect(&self.addr).await {
+                Ok(client) => self.client = Some(client),
+                Err(e) => {
+                    self.error = Some(e.to_string());
+                    return;
+                }
+            }
+        }
+        let Some(client) = self.client.as_mut() else { return };
+
+        let chain = match client.call("getChainInfo", json!([])).await {
+            Ok(chain) => chain,
+            Err(e) => {
+                self.error = Some(e.to_string());
+                self.client = None;
+                return;
+            }
+        };
+        // Optional methods stay blank on nodes that don't serve them
+        self.snapshot = Snapshot {
+            chain: Some(chain),
+            stats: client.call("getStats", json!([])).await.ok(),
+            fee: client.call("estimateFee", json!([1])).await.ok(),
+            network: client.call("getNetworkStatus", json!([])).await.ok(),
+            mining: client.call("getMiningStatus", json!([MINING_WINDOW])).await.ok(),
+            devices: client.call("getDevices", json!([])).await.ok(),
+        };
+        if let Some(rate) = self.snapshot.mining.as_ref().and_then(|mining| mining["hash_rate"].as_f64()) {
+            if self.hash_rates.len() == HASH_RATE_HISTORY {
+                self.hash_rates.pop_front();
+            }
+            self.hash_rates.push_back(rate as u64);
+        }
+        self.error = None;
+        self.updated_at = chrono::Utc::now().timestamp() as u64;
+    }
+
+    fn render(&self, frame: &mut Frame) {
+        let [top, middle, devices, footer] = Layout::vertical([
+            Constraint::Length(7),
+            Constraint::Min(8),
+            Constraint::Percentage(40),
+            Constraint::Length(1),
+        ]).areas(frame.area());
+        let [sync, mempool, mining, ai3] = Layout::horizontal([Constraint::Ratio(1, 4); 4]).areas(top);
+        let [peers, hash_rate] = Layout::horizontal([Constraint::Percentage(50); 2]).areas(middle);
+
+        self.render_sync(frame, sync);
+        self.render_mempool(frame, mempool);
+        self.render_mining(frame, mining);
+        self.render_ai3(frame, ai3);
+        self.render_peers(frame, peers);
+        self.render_hash_rate(frame, hash_rate);
+        self.render_devices(frame, devices);
+
+        let status = match &self.error {
+            Some(error) => Line::styled(format!(" {}: {}", self.addr, error), Style::default().fg(Color::Red)),
+            None => Line::from(format!(" {}  updated {}  q quit, r refresh", self.addr, format_age(chrono::Utc::now().timestamp() as u64, self.updated_at))),
+        };
+        frame.render_widget(Paragraph::new(status), footer);
+    }
+
+    fn render_sync(&self, frame: &mut Frame, area: Rect) {
+        let Snapshot { chain, network, .. } = &self.snapshot;
+        let state = match network {
+            None => "not served".to_string(),
+            Some(network) if network["syncing"].as_bool() == Some(true) => format!("syncing, {} behind", network["behind"]),
+            Some(network) if network["behind"].as_u64().unwrap_or(0) > 0 => format!("{} behind", network["behind"]),
+            Some(_) => "synced".to_string(),
+        };
+        let best_hash = field(chain, "best_hash");
+        let lines = key_value(vec![
+            ("State", state),
+            ("Height", field(chain, "height")),
+            ("Finalized", field(chain, "finalized_height")),
+            ("Peer height", field(network, "best_peer_height")),
+            ("Best hash", best_hash.chars().take(16).collect()),
+        ]);
+        frame.render_widget(Paragraph::new(lines).block(panel("Sync")), area);
+    }
+
+    fn render_mempool(&self, frame: &mut Frame, area: Rect) {
+        let Snapshot { stats, fee, .. } = &self.snapshot;
+        let lines = key_value(vec![
+            ("Pending", field(stats, "pending_transactions")),
+            ("Gas price", field(fee, "gas_price")),
+            ("Transactions", field(stats, "transaction_count")),
+        ]);
+        frame.render_widget(Paragraph::new(lines).block(panel("Mempool")), area);
+    }
+
+    fn render_mining(&self, frame: &mut Frame, area: Rect) {
+        let Snapshot { stats, mining, .. } = &self.snapshot;
+        let hash_rate = mining.as_ref().and_then(|mining| mining["hash_rate"].as_f64()).map_or("-".to_string(), format_hash_rate);
+        let lines = key_value(vec![
+            ("Hash rate", hash_rate),
+            ("Block time", mining.as_ref().and_then(|mining| mining["avg_block_time"].as_f64()).map_or("-".to_string(), |seconds| format!("{:.0}s", seconds))),
+            ("Difficulty", field(stats, "difficulty")),
+            ("Miners", field(stats, "active_miners")),
+        ]);
+        frame.render_widget(Paragraph::new(lines).block(panel("Mining")), area);
+    }
+
+    fn render_ai3(&self, frame: &mut Frame, area: Rect) {
+        let mining = &self.snapshot.mining;
+        let lines = key_value(vec![
+            ("Tasks/hour", field(mining, "ai3_tasks_per_hour")),
+            ("Proofs", format!("{} of {} blocks", field(mining, "ai3_proofs"), field(mining, "window_blocks"))),
+            ("Pending", field(mining, "pending_tasks")),
+            ("Completed", field(mining, "completed_tasks")),
+        ]);
+        frame.render_widget(Paragraph::new(lines).block(panel("AI3 tasks")), area);
+    }
+
+    fn render_peers(&self, frame: &mut Frame, area: Rect) {
+        let Some(network) = &self.snapshot.network else {
+            frame.render_widget(Paragraph::new("Network status not served by this node").block(panel("Peers")), area);
+            return;
+        };
+        let rows = network["peers"].as_array().into_iter().flatten().map(|peer| Row::new(vec![
+            peer["peer_id"].as_str().unwrap_or_default().to_string(),
+            peer["node_type"].as_str().unwrap_or_default().to_string(),
+            peer["best_height"].to_string(),
+            peer["protocol_version"].to_string(),
+        ]));
+        let table = Table::new(rows, [Constraint::Fill(1), Constraint::Length(6), Constraint::Length(10), Constraint::Length(8)])
+            .header(Row::new(vec!["Peer", "Type", "Height", "Protocol"]).style(Style::default().add_modifier(Modifier::BOLD)))
+            .block(panel("Peers"));
+        frame.render_widget(table, area);
+    }
+
+    fn render_hash_rate(&self, frame: &mut Frame, area: Rect) {
+        let samples: Vec<u64> = self.hash_rates.iter().copied().collect();
+        let sparkline = Sparkline::default()
+            .data(&samples)
+            .style(Style::default().fg(Color::Cyan))
+            .block(panel("Hash rate"));
+        frame.render_widget(sparkline, area);
+    }
+
+    fn render_devices(&self, frame: &mut Frame, area: Rect) {
+        let Some(devices) = &self.snapshot.devices else {
+            frame.render_widget(Paragraph::new("Telemetry not served by this node").block(panel("ESP devices")), area);
+            return;
+        };
+        let now = chrono::Utc::now().timestamp() as u64;
+        let rows = devices.as_array().into_iter().flatten().map(|device| {
+            let metrics = device["metrics"].as_object().into_iter().flatten()
+                .map(|(name, mean)| format!("{}={:.2}", name, mean.as_f64().unwrap_or_default()))
+                .collect::<Vec<_>>()
+                .join("  ");
+            let alerting: Vec<&str> = device["alerting"].as_array().into_iter().flatten().filter_map(Value::as_str).collect();
+            let style = if alerting.is_empty() { Style::default() } else { Style::default().fg(Color::Red) };
+            Row::new(vec![
+                device["device_id"].as_str().unwrap_or_default().to_string(),
+                format_age(now, device["last_seen"].as_u64().unwrap_or_default()),
+                metrics,
+                alerting.join(", "),
+            ]).style(style)
+        });
+        let table = Table::new(rows, [Constraint::Length(20), Constraint::Length(10), Constraint::Fill(1), Constraint::Length(24)])
+            .header(Row::new(vec!["Device", "Last seen", "Latest metrics", "Alerting"]).style(Style::default().add_modifier(Modifier::BOLD)))
+            .block(panel("ESP devices"));
+        frame.render_widget(table, area);
+    }
+
+    async fn run(&mut self, terminal: &mut DefaultTerminal, interval: Duration) -> TribeResult<()> {
+        loop {
+            self.refresh().await;
+            terminal.draw(|frame| self.render(frame)).map_err(ui_error)?;
+
+            let next_refresh = Instant::now() + interval;
+            while let Some(remaining) = next_refresh.checked_duration_since(Instant::now()) {
+                // Polling blocks; keep it off the runtime's other tasks
+                if !tokio::task::block_in_place(|| event::poll(remaining)).map_err(ui_error)? {
+                    continue;
+                }
+                match event::read().map_err(ui_error)? {
+                    Event::Key(key) if key.kind == KeyEventKind::Press => match key.code {
+                        KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(()),
+                        KeyCode::Char('r') => break,
+                        _ => {}
+                    },
+                    Event::Resize(..) => {
+                        terminal.draw(|frame| self.render(frame)).map_err(ui_error)?;
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+/// Full-screen status of the node at `addr`, refreshed every `interval` until `q`
+pub async fn run(addr: &str, interval: Duration) -> TribeResult<()> {
+    let mut dashboard = Dashboard::new(addr);
+    let mut terminal = ratatui::init();
+    let result = dashboard.run(&mut terminal, interval).await;
+    ratatui::restore();
+    result
+}
@@ -0,0 +1,124 @@
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use sha2::{Digest, Sha256};
+use tribechain::{hrp_for_chain, Address, ChainSpec, DifficultyParams, Shutdown, TribeChain, TribeResult, DEVNET_CHAIN_ID};
+use crate::keystore::Keystore;
+
+/// Developer accounts funded in the dev chain's genesis
+pub const DEV_ACCOUNTS: usize = 10;
+
+/// Starting balance of each developer account: 10,000 TRIBE
+const DEV_ACCOUNT_BALANCE: u64 = 10_000 * 1_000_000;
+
+/// How often the auto-miner checks the mempool
+const AUTO_MINE_POLL: Duration = Duration::from_millis(200);
+
+/// Signing key of developer account `index`. The keys are the same everywhere, so
+/// scripts and tests can rely on them; they must never hold real funds.
+pub fn dev_key(index: usize) -> String {
+    hex::encode(Sha256::digest(format!("tribechain dev account {}", index).as_bytes()))
+}
+
+pub fn dev_account(index: usize) -> String {
+    Address::from_key(hrp_for_chain(DEVNET_CHAIN_ID), &dev_key(index)).to_string()
+}
+
+/// The dev preset with funded developer accounts, one-second blocks and a difficulty
+/// that stays trivial however fast blocks are mined
+pub fn dev_spec() -> ChainSpec {
+    let mut spec = ChainSpec::dev();
+    spec.block_time_secs = 1;
+    spec.difficulty = DifficultyParams { initial: 1, minimum: 1, fixed: true, ..spec.difficulty };
+    for index in 0..DEV_ACCOUNTS {
+        spec.genesis_allocations.insert(dev_account(index), DEV_ACCOUNT_BALANCE);
+    }
+    spec
+}
+
+/// Make sure every developer account has its key in `keystore`, then list them
+pub fn print_accounts(keystore: &Keystore) -> TribeResult<()> {
+    println!("Developer accounts ({} TRIBE each; the keys are public, never use them elsewhere):", DEV_ACCOUNT_BALANCE / 1_000_000);
+    for index in 0..DEV_ACCOUNTS {
+        let (address, key) = (dev_account(index), dev_key(index));
+        if keystore.key(&address).is_err() {
+            keystore.import(&address, &key)?;
+        }
+        println!("  {}  {}", address, key);
+    }
+    println!("Their keys are in the keystore, so commands can sign with `--from <address>`");
+    Ok(())
+}
+
+/// Mine a block as soon as pending transactions can go into one, until shutdown
+pub async fn auto_mine(chain: Arc<RwLock<TribeChain>>, miner_address: String, shutdown: Shutdown) {
+    while !shutdown.is_triggered() {
+        tokio::select! {
+            _ = tokio::time::sleep(AUTO_MINE_POLL) => {}
+            _ = shutdown.wait() => break,
+        }
+        let mut chain = chain.write().await;
+        // Transactions waiting on an earlier nonce would only produce empty blocks
+        if !chain.has_ready_transactions() {
+            continue;
+        }
+        match chain.mine_block(miner_address.clone()) {
+            Ok(block) => println!("Mined block #{} with {} transactions", block.index, block.transactions.len()),
+            Err(e) => eprintln!("Mining failed: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tribechain::{StorageConfig, Transaction, TransactionType};
+
+    fn transfer(from: usize, to: usize, nonce: u64) -> Transaction {
+        let mut transaction = Transaction::new(dev_account(from), TransactionType::Transfer { to: dev_account(to), amount: 5 }, 1, nonce)
+            .with_chain_id(DEVNET_CHAIN_ID);
+        transaction.sign(&dev_key(from)).unwrap();
+        transaction
+    }
+
+    #[test]
+    fn test_dev_accounts_are_funded_and_imported() {
+        let spec = dev_spec();
+        assert!((0..DEV_ACCOUNTS).all(|index| spec.genesis_allocations.get(&dev_account(index)) == Some(&DEV_ACCOUNT_BALANCE)));
+        assert!(spec.difficulty.fixed);
+        assert_ne!(dev_account(0), dev_account(1));
+
+        let dir = std::env::temp_dir().join(format!("tribechain-dev-{}", uuid::Uuid::new_v4()));
+        let keystore = Keystore::open(&dir);
+        print_accounts(&keystore).unwrap();
+        print_accounts(&keystore).unwrap(); // Keys already there are left alone
+        assert_eq!(keystore.key(&dev_account(3)).unwrap(), dev_key(3));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_auto_mine_mines_ready_transactions_until_shutdown() {
+        let chain = TribeChain::with_chain_spec("unused", &StorageConfig::memory(), &dev_spec()).unwrap();
+        let chain = Arc::new(RwLock::new(chain));
+        let shutdown = Shutdown::new();
+        let miner = tokio::spawn(auto_mine(chain.clone(), dev_account(9), shutdown.clone()));
+
+        chain.write().await.add_transaction(transfer(0, 1, 0)).unwrap();
+        tokio::time::timeout(Duration::from_secs(10), async {
+            while chain.read().await.blocks.len() < 2 {
+                tokio::time::sleep(AUTO_MINE_POLL).await;
+            }
+        }).await.unwrap();
+        assert_eq!(chain.read().await.balances.get(&dev_account(1)), Some(DEV_ACCOUNT_BALANCE + 5));
+
+        // A transaction waiting on an earlier nonce doesn't produce empty blocks
+        chain.write().await.add_transaction(transfer(0, 1, 2)).unwrap();
+        tokio::time::sleep(AUTO_MINE_POLL * 3).await;
+        let chain_now = chain.read().await;
+        assert_eq!((chain_now.blocks.len(), chain_now.difficulty), (2, 1));
+        drop(chain_now);
+
+        shutdown.trigger();
+        tokio::time::timeout(Duration::from_secs(5), miner).await.unwrap().unwrap();
+    }
+}
@@ -0,0 +1,93 @@
+use rand::RngCore;
+use std::path::{Path, PathBuf};
+use tribechain::{Address, TribeResult, TribeError};
+
+/// Signing keys of local accounts, one `<address>.key` file of hex per account
+pub struct Keystore {
+    dir: PathBuf,
+}
+
+fn random_key() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+impl Keystore {
+    pub fn open(dir: &Path) -> Self {
+        Self { dir: dir.to_path_buf() }
+    }
+
+    fn path(&self, address: &str) -> TribeResult<PathBuf> {
+        // Addresses become file names; keep them inside the keystore
+        let valid = !address.is_empty()
+            && address.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        if !valid {
+            return Err(TribeError::Generic(format!("Invalid account address: {}", address)));
+        }
+        Ok(self.dir.join(format!("{}.key", address)))
+    }
+
+    /// Key `address` signs with
+    pub fn key(&self, address: &str) -> TribeResult<String> {
+        let path = self.path(address)?;
+        match std::fs::read_to_string(&path) {
+            Ok(key) => Ok(key.trim().to_string()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(TribeError::Generic(format!(
+                "No key for {} in {}; create one with `tribechain wallet keygen {}`",
+                address, self.dir.display(), address
+            ))),
+            Err(e) => Err(TribeError::Storage(format!("Failed to read key {}: {}", path.display(), e))),
+        }
+    }
+
+    /// Create a random key for `address`; an existing key is never replaced
+    pub fn generate(&self, address: &str) -> TribeResult<PathBuf> {
+        self.import(address, &random_key())
+    }
+
+    /// Create a random key and the address derived from it, under the network prefix `hrp`
+    pub fn generate_account(&self, hrp: &str) -> TribeResult<(String, PathBuf)> {
+        let key = random_key();
+        let address = Address::from_key(hrp, &key).to_string();
+        let path = self.import(&address, &key)?;
+        Ok((address, path))
+    }
+
+    /// Store `key` as the signing key of `address`; an existing key is never replaced
+    pub fn import(&self, address: &str, key: &str) -> TribeResult<PathBuf> {
+        let path = self.path(address)?;
+        std::fs::create_dir_all(&self.dir)
+            .map_err(|e| TribeError::Storage(format!("Failed to create keystore {}: {}", self.dir.display(), e)))?;
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600); // Readable by the owner only
+        let mut file = options.open(&path).map_err(|e| match e.kind() {
+            std::io::ErrorKind::AlreadyExists => TribeError::Generic(format!("{} already has a key", address)),
+            _ => TribeError::Storage(format!("Failed to create key {}: {}", path.display(), e)),
+        })?;
+        std::io::Write::write_all(&mut file, key.as_bytes())
+            .map_err(|e| TribeError::Storage(format!("Failed to write key {}: {}", path.display(), e)))?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_accounts_sign_for_their_address() {
+        let dir = std::env::temp_dir().join(format!("tribechain-keystore-{}", uuid::Uuid::new_v4()));
+        let keystore = Keystore::open(&dir);
+        let (address, path) = keystore.generate_account(tribechain::TESTNET_HRP).unwrap();
+        assert!(address.starts_with("ttrb1") && path.ends_with(format!("{}.key", address)));
+
+        let key = keystore.key(&address).unwrap();
+        assert_eq!(Address::from_key(tribechain::TESTNET_HRP, &key), Address::parse(&address).unwrap());
+        assert!(keystore.import(&address, "other-key").is_err());
+        assert!(keystore.key("../escape").is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
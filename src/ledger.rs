@@ -0,0 +1,276 @@
+use std::io::{Read, Write};
+use tribechain::{Transaction, TribeResult, TribeError};
+
+/// USB vendor ID of Ledger devices
+const LEDGER_VENDOR_ID: &str = "00002C97";
+
+/// HID reports are 64 bytes; every one starts with the channel, tag and sequence number
+const PACKET_SIZE: usize = 64;
+const CHANNEL: u16 = 0x0101;
+const TAG_APDU: u8 = 0x05;
+
+/// APDU class and instructions of the TribeChain device app
+const CLA: u8 = 0xE0;
+const INS_GET_ADDRESS: u8 = 0x02;
+const INS_GET_PUBLIC_KEY: u8 = 0x03;
+const INS_SIGN_TRANSACTION: u8 = 0x04;
+const P1_FIRST_CHUNK: u8 = 0x00;
+const P1_MORE_CHUNKS: u8 = 0x80;
+const MAX_CHUNK: usize = 255; // An APDU's data length is a single byte
+
+const SW_OK: u16 = 0x9000;
+const SW_USER_REJECTED: u16 = 0x6985;
+const SW_WRONG_APP: [u16; 2] = [0x6D00, 0x6E00]; // Instruction or class not supported
+const SW_LOCKED: u16 = 0x5515;
+
+/// Split `apdu` into HID reports, each prefixed with report ID 0 as hidraw expects
+fn frame(apdu: &[u8]) -> Vec<[u8; PACKET_SIZE + 1]> {
+    let mut payload = (apdu.len() as u16).to_be_bytes().to_vec(); // The first report carries the length
+    payload.extend_from_slice(apdu);
+    payload.chunks(PACKET_SIZE - 5).enumerate().map(|(sequence, chunk)| {
+        let mut report = [0u8; PACKET_SIZE + 1];
+        report[1..3].copy_from_slice(&CHANNEL.to_be_bytes());
+        report[3] = TAG_APDU;
+        report[4..6].copy_from_slice(&(sequence as u16).to_be_bytes());
+        report[6..6 + chunk.len()].copy_from_slice(chunk);
+        report
+    }).collect()
+}
+
+/// Read reports until the whole response is in, returning it with the status word
+fn read_response(device: &mut impl Read) -> TribeResult<Vec<u8>> {
+    let mut response = Vec::new();
+    let mut expected = None;
+    let mut sequence = 0u16;
+    while expected.is_none_or(|length| response.len() < length) {
+        let mut report = [0u8; PACKET_SIZE];
+        device.read_exact(&mut report)
+            .map_err(|e| TribeError::Generic(format!("Failed to read from the Ledger: {}", e)))?;
+        let header_ok = report[0..2] == CHANNEL.to_be_bytes()
+            && report[2] == TAG_APDU
+            && report[3..5] == sequence.to_be_bytes();
+        if !header_ok {
+            return Err(TribeError::Generic("Unexpected report from the Ledger".to_string()));
+        }
+        let data = match expected {
+            None => {
+                expected = Some(u16::from_be_bytes([report[5], report[6]]) as usize);
+                &report[7..]
+            }
+            Some(_) => &report[5..],
+        };
+        response.extend_from_slice(data);
+        sequence += 1;
+    }
+    response.truncate(expected.unwrap_or(0));
+    Ok(response)
+}
+
+fn status_error(status: u16) -> TribeError {
+    let message = match status {
+        SW_USER_REJECTED => "Rejected on the Ledger".to_string(),
+        SW_LOCKED => "The Ledger is locked; unlock it with its PIN".to_string(),
+        status if SW_WRONG_APP.contains(&status) => "Open the TribeChain app on the Ledger".to_string(),
+        status => format!("The Ledger answered with status {:04X}", status),
+    };
+    TribeError::Generic(message)
+}
+
+/// Ledger running the TribeChain app, reached over HID
+pub struct LedgerDevice<D: Read + Write> {
+    device: D,
+}
+
+#[cfg(target_os = "linux")]
+impl LedgerDevice<std::fs::File> {
+    /// Open the first Ledger found among the hidraw devices
+    pub fn open() -> TribeResult<Self> {
+        let entries = std::fs::read_dir("/sys/class/hidraw")
+            .map_err(|e| TribeError::Generic(format!("Cannot list HID devices: {}", e)))?;
+        for entry in entries.flatten() {
+            let uevent = std::fs::read_to_string(entry.path().join("device/uevent")).unwrap_or_default();
+            let is_ledger = uevent.lines().any(|line| line.starts_with("HID_ID=") && line.contains(LEDGER_VENDOR_ID));
+            // Interface 0 carries APDUs; the others are for U2F and the like
+            let interface = std::fs::read_to_string(entry.path().join("device/../bInterfaceNumber")).unwrap_or_default();
+            if !is_ledger || interface.trim() != "00" {
+                continue;
+            }
+            let path = std::path::Path::new("/dev").join(entry.file_name());
+            let device = std::fs::OpenOptions::new().read(true).write(true).open(&path)
+                .map_err(|e| TribeError::Generic(format!(
+                    "Cannot open {}: {}; check the udev rules give your user access", path.display(), e
+                )))?;
+            return Ok(Self { device });
+        }
+        Err(TribeError::Generic("No Ledger found; connect and unlock it".to_string()))
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+impl LedgerDevice<std::fs::File> {
+    pub fn open() -> TribeResult<Self> {
+        Err(TribeError::Generic("Ledger signing is only supported on Linux".to_string()))
+    }
+}
+
+impl<D: Read + Write> LedgerDevice<D> {
+    pub fn new(device: D) -> Self {
+        Self { device }
+    }
+
+    /// Send one APDU and return the response data, failing on any status but success
+    pub fn exchange(&mut self, ins: u8, p1: u8, data: &[u8]) -> TribeResult<Vec<u8>> {
+        let mut apdu = vec![CLA, ins, p1, 0x00, data.len() as u8];
+        apdu.extend_from_slice(data);
+        for report in frame(&apdu) {
+            self.device.write_all(&report)
+                .map_err(|e| TribeError::Generic(format!("Failed to write to the Ledger: {}", e)))?;
+        }
+        let mut response = read_response(&mut self.device)?;
+        if response.len() < 2 {
+            return Err(TribeError::Generic("Short response from the Ledger".to_string()));
+        }
+        let status = response.split_off(response.len() - 2);
+        match u16::from_be_bytes([status[0], status[1]]) {
+            SW_OK => Ok(response),
+            status => Err(status_error(status)),
+        }
+    }
+
+    /// Account the device signs for
+    pub fn address(&mut self) -> TribeResult<String> {
+        let response = self.exchange(INS_GET_ADDRESS, P1_FIRST_CHUNK, &[])?;
+        String::from_utf8(response).map_err(|_| TribeError::Generic("The Ledger returned an invalid address".to_string()))
+    }
+
+    /// Public key the device's signatures verify under, as hex
+    pub fn public_key(&mut self) -> TribeResult<String> {
+        let response = self.exchange(INS_GET_PUBLIC_KEY, P1_FIRST_CHUNK, &[])?;
+        if response.len() != 32 {
+            return Err(TribeError::Generic("The Ledger returned an invalid public key".to_string()));
+        }
+        Ok(hex::encode(response))
+    }
+
+    /// Have the device show `transaction` for confirmation and sign it; blocks until the
+    /// user approves or rejects it on the device
+    pub fn sign_transaction(&mut self, transaction: &Transaction) -> TribeResult<String> {
+        let encoded = bincode::serialize(transaction)
+            .map_err(|e| TribeError::Generic(format!("Failed to encode transaction: {}", e)))?;
+        let mut signature = Vec::new();
+        for (index, chunk) in encoded.chunks(MAX_CHUNK).enumerate() {
+            let p1 = if index == 0 { P1_FIRST_CHUNK } else { P1_MORE_CHUNKS };
+            // Only the last chunk's response holds the signature
+            signature = self.exchange(INS_SIGN_TRANSACTION, p1, chunk)?;
+        }
+        Ok(hex::encode(signature))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// Device that records what the host writes and plays back canned reports
+    struct MockDevice {
+        written: Vec<u8>,
+        reports: Cursor<Vec<u8>>,
+    }
+
+    impl Read for MockDevice {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.reports.read(buf)
+        }
+    }
+
+    impl Write for MockDevice {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.written.write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Reports the device sends for `response`; they lack the host-side report ID
+    fn device_reports(response: &[u8]) -> Vec<u8> {
+        frame(response).iter().flat_map(|report| report[1..].to_vec()).collect()
+    }
+
+    fn device(response: &[u8]) -> LedgerDevice<MockDevice> {
+        LedgerDevice::new(MockDevice { written: Vec::new(), reports: Cursor::new(device_reports(response)) })
+    }
+
+    #[test]
+    fn test_long_apdus_span_sequenced_reports() {
+        let apdu: Vec<u8> = (0..200u8).collect();
+        let reports = frame(&apdu);
+        assert_eq!(reports.len(), 4); // 2 length bytes + 200 bytes, 59 per report
+
+        let mut payload = Vec::new();
+        for (sequence, report) in reports.iter().enumerate() {
+            assert_eq!(report[0], 0);
+            assert_eq!(report[1..3], CHANNEL.to_be_bytes());
+            assert_eq!(report[3], TAG_APDU);
+            assert_eq!(report[4..6], (sequence as u16).to_be_bytes());
+            payload.extend_from_slice(&report[6..]);
+        }
+        assert_eq!(payload[0..2], 200u16.to_be_bytes());
+        assert_eq!(payload[2..202], apdu[..]);
+        assert!(payload[202..].iter().all(|byte| *byte == 0));
+
+        // Reading the same reports back drops the padding
+        let mut stream = Cursor::new(device_reports(&apdu));
+        assert_eq!(read_response(&mut stream).unwrap(), apdu);
+    }
+
+    #[test]
+    fn test_malformed_reports_are_rejected() {
+        let response: Vec<u8> = (0..100u8).collect();
+        let corrupt = |offset: usize| {
+            let mut reports = device_reports(&response);
+            reports[offset] ^= 0xFF;
+            read_response(&mut Cursor::new(reports))
+        };
+        assert!(corrupt(0).is_err()); // Channel
+        assert!(corrupt(2).is_err()); // Tag
+        assert!(corrupt(PACKET_SIZE + 4).is_err()); // Second report's sequence number
+
+        // A response cut short fails rather than returning part of it
+        let mut reports = device_reports(&response);
+        reports.truncate(PACKET_SIZE);
+        assert!(read_response(&mut Cursor::new(reports)).is_err());
+    }
+
+    #[test]
+    fn test_exchange_strips_the_status_word() {
+        let key = [7u8; 32];
+        let mut ledger = device(&[&key[..], &SW_OK.to_be_bytes()].concat());
+        assert_eq!(ledger.public_key().unwrap(), hex::encode(key));
+
+        // The request went out as one report: class, instruction, P1, P2, no data
+        let written = &ledger.device.written;
+        assert_eq!(written.len(), PACKET_SIZE + 1);
+        assert_eq!(written[6..8], 5u16.to_be_bytes());
+        assert_eq!(written[8..13], [CLA, INS_GET_PUBLIC_KEY, P1_FIRST_CHUNK, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn test_error_statuses_are_explained() {
+        let error = |status: u16| {
+            match device(&status.to_be_bytes()).exchange(INS_GET_ADDRESS, P1_FIRST_CHUNK, &[]) {
+                Err(TribeError::Generic(message)) => message,
+                other => panic!("status {:04X} gave {:?}", status, other),
+            }
+        };
+        assert_eq!(error(SW_USER_REJECTED), "Rejected on the Ledger");
+        assert_eq!(error(SW_LOCKED), "The Ledger is locked; unlock it with its PIN");
+        assert_eq!(error(0x6E00), "Open the TribeChain app on the Ledger");
+        assert_eq!(error(0x6A80), "The Ledger answered with status 6A80");
+
+        assert!(device(&[0x90]).exchange(INS_GET_ADDRESS, P1_FIRST_CHUNK, &[]).is_err());
+        assert!(device(&[&[1u8; 31][..], &SW_OK.to_be_bytes()].concat()).public_key().is_err());
+    }
+}
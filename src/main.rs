@@ -3,11 +3,42 @@ use std::net::SocketAddr;
 use tokio;
 use tribechain::{
     TribeChain, NetworkNode, Transaction, TransactionType, TensorTask, MinerInfo,
-    AI3Engine, TokenManager, TokenInfo, TokenType, TribeResult, TribeError
+    AI3Engine, TokenManager, TokenInfo, TokenType, TribeResult, TribeError,
+    BenchmarkReport, RepairOutcome, ChainSpec, Shutdown, RpcServer, RpcState, ESPCompatibility,
+    RpcClient, ContractEngine, GRAPHQL_PATH, TaskDistributor, BridgeRelayer, RelayerConfig, Address, hrp_for_chain, MAINNET_HRP, TESTNET_HRP,
 };
+use tribechain_contracts::contracts::MethodSignature;
+use ai3_lib::mining::benchmark::BENCHMARK_OPERATIONS;
+use std::path::Path;
 use std::process;
+use std::sync::Arc;
+use tokio::sync::{Mutex, RwLock};
+use std::time::Duration;
 
+/// How long shutdown waits for in-flight AI3 tasks before exiting anyway
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(30);
+
+mod config;
+mod console;
+#[cfg(unix)]
+mod daemon;
+mod dashboard;
+mod dev;
+mod keystore;
+mod ledger;
+mod signer;
+mod tx;
+mod watchlist;
 mod esp32_miner;
+use config::{BridgeConfig, TribeConfig};
+use keystore::Keystore;
+use signer::{KeystoreSigner, LedgerSigner, Signer};
+use watchlist::WatchList;
+#[cfg(unix)]
+use daemon::{ControlCommand, ControlServer, NodeStatus, PidFile};
+
+/// Hidden `node` flag the process started by `--daemon` is given
+const DAEMON_CHILD_FLAG: &str = "daemon-child";
 use esp32_miner::{ESP32Miner, ESP32Config};
 
 #[tokio::main]
@@ -16,6 +47,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .version("1.0.0")
         .author("BitTribe")
         .about("TribeChain - AI-Powered Blockchain with Tensor Mining")
+        .arg(
+            Arg::new("config")
+                .long("config")
+                .value_name("FILE")
+                .help("Config file; ./tribechain.toml is used if present")
+                .global(true)
+        )
         .subcommand(
             Command::new("node")
                 .about("Start a TribeChain node")
@@ -25,7 +63,6 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                         .long("port")
                         .value_name("PORT")
                         .help("Port to listen on")
-                        .default_value("8333")
                 )
                 .arg(
                     Arg::new("data-dir")
@@ -33,7 +70,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                         .long("data-dir")
                         .value_name("DIR")
                         .help("Data directory for blockchain storage")
-                        .default_value("./data")
+                )
+                .arg(
+                    Arg::new("storage")
+                        .long("storage")
+                        .value_name("BACKEND")
+                        .help("Storage backend: rocksdb, or memory for a throwaway node")
                 )
                 .arg(
                     Arg::new("connect")
@@ -43,18 +85,66 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                         .help("Connect to peer (format: ip:port)")
                         .action(clap::ArgAction::Append)
                 )
+                .arg(
+                    Arg::new("dev")
+                        .long("dev")
+                        .help("Run a single-node dev chain that mines as transactions arrive, with ten funded developer accounts")
+                        .action(clap::ArgAction::SetTrue)
+                )
+                .arg(
+                    Arg::new("daemon")
+                        .long("daemon")
+                        .help("Run in the background, logging to daemon.log_file")
+                        .action(clap::ArgAction::SetTrue)
+                )
+                .arg(
+                    Arg::new(DAEMON_CHILD_FLAG)
+                        .long(DAEMON_CHILD_FLAG)
+                        .action(clap::ArgAction::SetTrue)
+                        .hide(true)
+                )
+        )
+        .subcommand(
+            Command::new("stop")
+                .about("Stop a running node")
+                .arg(
+                    Arg::new("data-dir")
+                        .short('d')
+                        .long("data-dir")
+                        .value_name("DIR")
+                        .help("Data directory of the node")
+                )
+        )
+        .subcommand(
+            Command::new("status")
+                .about("Show whether a node is running and its status")
+                .arg(
+                    Arg::new("data-dir")
+                        .short('d')
+                        .long("data-dir")
+                        .value_name("DIR")
+                        .help("Data directory of the node")
+                )
         )
         .subcommand(
             Command::new("wallet")
                 .about("Wallet operations")
+                .arg(Arg::new("rpc")
+                    .long("rpc")
+                    .value_name("ADDRESS")
+                    .help("Node RPC address; rpc.bind and rpc.port from the config if omitted")
+                    .global(true))
                 .subcommand(
                     Command::new("balance")
-                        .about("Check balance")
+                        .about("Check balance; watch-only accounts, brought up to date, if no address is given")
                         .arg(
                             Arg::new("address")
                                 .help("Address to check")
-                                .required(true)
                         )
+                        .arg(Arg::new("min-confirmations")
+                            .long("min-confirmations")
+                            .value_name("BLOCKS")
+                            .help("Only count funds received at least this many blocks deep"))
                 )
                 .subcommand(
                     Command::new("send")
@@ -74,15 +164,57 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                                 .help("Amount to send")
                                 .required(true)
                         )
+                        .arg(contract_gas_price_arg())
+                        .arg(ledger_arg())
+                )
+                .subcommand(
+                    Command::new("keygen")
+                        .about("Create a signing key in the local keystore, with a new address derived from it")
+                        .arg(
+                            Arg::new("address")
+                                .help("Existing account to create the key for instead")
+                        )
+                )
+                .subcommand(
+                    Command::new("watch")
+                        .about("Track an address's balance and history without its key, rescanning the chain for it")
+                        .arg(Arg::new("address").help("Address to watch").required(true))
+                        .arg(Arg::new("label")
+                            .long("label")
+                            .value_name("LABEL")
+                            .help("Name shown next to the address"))
+                        .arg(Arg::new("from-block")
+                            .long("from-block")
+                            .value_name("HEIGHT")
+                            .help("Skip history before this block, e.g. when the address is new"))
+                )
+                .subcommand(
+                    Command::new("unwatch")
+                        .about("Stop watching an address")
+                        .arg(Arg::new("address").help("Watched address").required(true))
+                )
+                .subcommand(
+                    Command::new("rescan")
+                        .about("Scan new blocks for watched addresses")
+                        .arg(Arg::new("address").help("Watched address; all of them if omitted"))
+                        .arg(Arg::new("full")
+                            .long("full")
+                            .help("Rescan from the genesis block")
+                            .action(clap::ArgAction::SetTrue))
+                )
+                .subcommand(
+                    Command::new("history")
+                        .about("Transactions found for a watched address")
+                        .arg(Arg::new("address").help("Watched address").required(true))
                 )
         )
         .subcommand(
             Command::new("mine")
                 .about("Mining operations")
+                .subcommand_negates_reqs(true)
                 .arg(
                     Arg::new("address")
-                        .help("Miner address")
-                        .required(true)
+                        .help("Miner address; mining.address from the config if omitted")
                 )
                 .arg(
                     Arg::new("data-dir")
@@ -90,7 +222,30 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                         .long("data-dir")
                         .value_name("DIR")
                         .help("Data directory for blockchain storage")
-                        .default_value("./data")
+                )
+                .arg(
+                    Arg::new("capabilities")
+                        .long("capabilities")
+                        .value_name("FILE")
+                        .help("Capability report from `mine bench` used to register the miner")
+                )
+                .subcommand(
+                    Command::new("bench")
+                        .about("Benchmark tensor operations and PoW hashing")
+                        .arg(
+                            Arg::new("duration")
+                                .long("duration")
+                                .value_name("MS")
+                                .help("Time spent on each operation in milliseconds")
+                                .default_value("1000")
+                        )
+                        .arg(
+                            Arg::new("output")
+                                .short('o')
+                                .long("output")
+                                .value_name("FILE")
+                                .help("Write the JSON capability report to a file")
+                        )
                 )
         )
         .subcommand(
@@ -102,7 +257,82 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                         .long("data-dir")
                         .value_name("DIR")
                         .help("Data directory for blockchain storage")
-                        .default_value("./data")
+                )
+        )
+        .subcommand(
+            Command::new("init")
+                .about("Create a chain's genesis state in a data directory")
+                .arg(
+                    Arg::new("chain")
+                        .long("chain")
+                        .value_name("CHAIN")
+                        .help("Preset (mainnet, testnet, dev) or path to a TOML/JSON chain spec")
+                )
+                .arg(
+                    Arg::new("data-dir")
+                        .short('d')
+                        .long("data-dir")
+                        .value_name("DIR")
+                        .help("Data directory for blockchain storage")
+                )
+        )
+        .subcommand(
+            Command::new("export")
+                .about("Write a range of blocks to an archive file")
+                .arg(
+                    Arg::new("from")
+                        .long("from")
+                        .value_name("HEIGHT")
+                        .help("First block to export")
+                        .default_value("0")
+                )
+                .arg(
+                    Arg::new("to")
+                        .long("to")
+                        .value_name("HEIGHT")
+                        .help("Last block to export; the chain tip if omitted")
+                )
+                .arg(
+                    Arg::new("out")
+                        .short('o')
+                        .long("out")
+                        .value_name("FILE")
+                        .help("Archive file to write")
+                        .required(true)
+                )
+                .arg(
+                    Arg::new("data-dir")
+                        .short('d')
+                        .long("data-dir")
+                        .value_name("DIR")
+                        .help("Data directory for blockchain storage")
+                )
+        )
+        .subcommand(
+            Command::new("import")
+                .about("Validate and apply the blocks of an archive file")
+                .arg(
+                    Arg::new("file")
+                        .help("Archive written by `export`")
+                        .required(true)
+                )
+                .arg(
+                    Arg::new("data-dir")
+                        .short('d')
+                        .long("data-dir")
+                        .value_name("DIR")
+                        .help("Data directory for blockchain storage")
+                )
+        )
+        .subcommand(
+            Command::new("repair")
+                .about("Replay or roll back a block import interrupted by a crash")
+                .arg(
+                    Arg::new("data-dir")
+                        .short('d')
+                        .long("data-dir")
+                        .value_name("DIR")
+                        .help("Data directory for blockchain storage")
                 )
         )
         .subcommand(
@@ -117,6 +347,163 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                         .arg(Arg::new("creator").help("Creator address").required(true))
                 )
         )
+        .subcommand(
+            Command::new("contract")
+                .about("Smart contract operations against a running node's RPC")
+                .arg(Arg::new("rpc")
+                    .long("rpc")
+                    .value_name("ADDRESS")
+                    .help("Node RPC address; rpc.bind and rpc.port from the config if omitted")
+                    .global(true))
+                .subcommand(
+                    Command::new("deploy")
+                        .about("Deploy contract code, signed with the deployer's keystore key")
+                        .arg(Arg::new("code").help("Contract code file, e.g. a .wasm module").required(true))
+                        .arg(Arg::new("args")
+                            .long("args")
+                            .value_name("JSON")
+                            .help("Constructor arguments as a JSON array; needs --abi")
+                            .default_value("[]"))
+                        .arg(Arg::new("abi")
+                            .long("abi")
+                            .value_name("FILE")
+                            .help("JSON file with the contract's method signatures, including `constructor`"))
+                        .arg(Arg::new("from")
+                            .long("from")
+                            .value_name("ADDRESS")
+                            .help("Deployer address")
+                            .required(true))
+                        .arg(contract_gas_price_arg())
+                        .arg(ledger_arg())
+                        .arg(contract_wait_arg())
+                )
+                .subcommand(
+                    Command::new("call")
+                        .about("Submit a contract call, checked against the contract ABI")
+                        .arg(Arg::new("address").help("Contract address").required(true))
+                        .arg(Arg::new("method").help("Method name").required(true))
+                        .arg(contract_args_arg())
+                        .arg(Arg::new("abi")
+                            .long("abi")
+                            .value_name("FILE")
+                            .help("JSON file with the contract's method signatures; fetched from the node if omitted"))
+                        .arg(Arg::new("from")
+                            .long("from")
+                            .value_name("ADDRESS")
+                            .help("Caller address")
+                            .required(true))
+                        .arg(Arg::new("value")
+                            .long("value")
+                            .value_name("AMOUNT")
+                            .help("Value sent with the call")
+                            .default_value("0"))
+                        .arg(contract_gas_price_arg())
+                        .arg(ledger_arg())
+                        .arg(contract_wait_arg())
+                )
+                .subcommand(
+                    Command::new("query")
+                        .about("Run a view method and print its result, without a transaction")
+                        .arg(Arg::new("address").help("Contract address").required(true))
+                        .arg(Arg::new("method").help("Method name").required(true))
+                        .arg(contract_args_arg())
+                        .arg(Arg::new("from")
+                            .long("from")
+                            .value_name("ADDRESS")
+                            .help("Caller the method sees"))
+                )
+        )
+        .subcommand(
+            Command::new("address")
+                .about("Convert and check account addresses")
+                .subcommand(
+                    Command::new("encode")
+                        .about("Encode 20 bytes of hex as an address of the configured network")
+                        .arg(Arg::new("hex").help("Address bytes as hex").required(true))
+                        .arg(Arg::new("hrp")
+                            .long("hrp")
+                            .value_name("PREFIX")
+                            .help("Network prefix, trb or ttrb; node.chain's if omitted"))
+                )
+                .subcommand(
+                    Command::new("decode")
+                        .about("Check an address's checksum and show its network and bytes")
+                        .arg(Arg::new("address").help("Address").required(true))
+                )
+        )
+        .subcommand(
+            Command::new("tx")
+                .about("Build transactions, sign them offline and broadcast them later")
+                .arg(Arg::new("rpc")
+                    .long("rpc")
+                    .value_name("ADDRESS")
+                    .help("Node RPC address; rpc.bind and rpc.port from the config if omitted")
+                    .global(true))
+                .subcommand(
+                    Command::new("build")
+                        .about("Build an unsigned transaction; the node fills in whatever --nonce, --chain-id and --gas-price leave out")
+                        .subcommand(
+                            tx_build_command("transfer", "Transfer TRIBE")
+                                .arg(Arg::new("to").help("Recipient address").required(true))
+                                .arg(Arg::new("amount").help("Amount in TRIBE").required(true))
+                        )
+                        .subcommand(
+                            tx_build_command("token-create", "Create a token, its whole supply held by the sender")
+                                .arg(Arg::new("name").help("Token name").required(true))
+                                .arg(Arg::new("symbol").help("Token symbol").required(true))
+                                .arg(Arg::new("supply").help("Total supply in base units").required(true))
+                                .arg(Arg::new("decimals")
+                                    .long("decimals")
+                                    .value_name("DECIMALS")
+                                    .help("Decimal places")
+                                    .default_value("6"))
+                        )
+                        .subcommand(
+                            tx_build_command("token-transfer", "Transfer a token")
+                                .arg(Arg::new("token").help("Token ID").required(true))
+                                .arg(Arg::new("to").help("Recipient address").required(true))
+                                .arg(Arg::new("amount").help("Amount in the token's base units").required(true))
+                        )
+                        .subcommand(
+                            tx_build_command("deploy", "Deploy contract code")
+                                .arg(Arg::new("code").help("Contract code file, e.g. a .wasm module").required(true))
+                                .arg(contract_args_arg().help("Constructor arguments as a JSON array; needs --abi"))
+                                .arg(Arg::new("abi")
+                                    .long("abi")
+                                    .value_name("FILE")
+                                    .help("JSON file with the contract's method signatures, including `constructor`"))
+                        )
+                        .subcommand(
+                            tx_build_command("call", "Call a contract method, checked against the contract ABI")
+                                .arg(Arg::new("address").help("Contract address").required(true))
+                                .arg(Arg::new("method").help("Method name").required(true))
+                                .arg(contract_args_arg())
+                                .arg(Arg::new("abi")
+                                    .long("abi")
+                                    .value_name("FILE")
+                                    .help("JSON file with the contract's method signatures; fetched from the node if omitted"))
+                                .arg(Arg::new("value")
+                                    .long("value")
+                                    .value_name("AMOUNT")
+                                    .help("Value sent with the call")
+                                    .default_value("0"))
+                        )
+                )
+                .subcommand(
+                    Command::new("sign")
+                        .about("Sign a built transaction with the sender's keystore key, without contacting a node")
+                        .arg(Arg::new("file").help("Transaction file from `tx build`").required(true))
+                        .arg(tx_out_arg())
+                        .arg(tx_binary_arg())
+                        .arg(ledger_arg())
+                )
+                .subcommand(
+                    Command::new("send")
+                        .about("Broadcast a signed transaction")
+                        .arg(Arg::new("file").help("Transaction file from `tx sign`").required(true))
+                        .arg(contract_wait_arg())
+                )
+        )
         .subcommand(
             Command::new("ai3")
                 .about("AI3 tensor operations")
@@ -156,14 +543,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                             .short('n')
                             .long("node-url")
                             .value_name("URL")
-                            .help("TribeChain node URL")
-                            .default_value("http://localhost:8333"))
+                            .help("TribeChain node URL"))
                         .arg(Arg::new("threads")
                             .short('t')
                             .long("threads")
                             .value_name("THREADS")
-                            .help("Number of mining threads")
-                            .default_value("2"))
+                            .help("Number of mining threads"))
                         .arg(Arg::new("ai3")
                             .long("ai3")
                             .help("Enable AI3 tensor mining")
@@ -172,8 +557,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                             .short('p')
                             .long("power-limit")
                             .value_name("WATTS")
-                            .help("Power consumption limit in watts")
-                            .default_value("3.0"))
+                            .help("Power consumption limit in watts"))
                 )
                 .subcommand(
                     Command::new("stats")
@@ -186,41 +570,144 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                             .required(true))
                 )
         )
+        .subcommand(
+            Command::new("console")
+                .about("Interactive console attached to a running node's RPC")
+                .arg(
+                    Arg::new("rpc")
+                        .long("rpc")
+                        .value_name("ADDRESS")
+                        .help("Node RPC address; rpc.bind and rpc.port from the config if omitted")
+                )
+                .arg(
+                    Arg::new("exec")
+                        .short('e')
+                        .long("exec")
+                        .value_name("COMMANDS")
+                        .help("Run these `;`-separated commands and exit")
+                )
+        )
+        .subcommand(
+            Command::new("dashboard")
+                .about("Live terminal view of a running node, its miners and ESP devices")
+                .arg(
+                    Arg::new("rpc")
+                        .long("rpc")
+                        .value_name("ADDRESS")
+                        .help("Node RPC address; rpc.bind and rpc.port from the config if omitted")
+                )
+                .arg(
+                    Arg::new("interval")
+                        .long("interval")
+                        .value_name("SECONDS")
+                        .help("Seconds between refreshes")
+                        .default_value("2")
+                )
+        )
+        .subcommand(
+            Command::new("config")
+                .about("Configuration file operations")
+                .subcommand(
+                    Command::new("print")
+                        .about("Print the effective configuration after file and environment overrides")
+                )
+        )
         .get_matches();
 
+    // Defaults < config file < TRIBECHAIN_* environment < command-line flags
+    let config = TribeConfig::load(&matches)?;
+
     match matches.subcommand() {
         Some(("node", sub_matches)) => {
-            start_node(sub_matches).await?;
+            if sub_matches.get_flag("daemon") {
+                detach_node(&config)?;
+            } else {
+                start_node(&config, sub_matches.get_flag(DAEMON_CHILD_FLAG), sub_matches.get_flag("dev")).await?;
+            }
+        }
+        #[cfg(unix)]
+        Some(("stop", _)) => {
+            stop_node(&config).await?;
+        }
+        #[cfg(unix)]
+        Some(("status", _)) => {
+            node_status(&config).await?;
         }
         Some(("wallet", sub_matches)) => {
-            handle_wallet_commands(sub_matches).await?;
+            handle_wallet_commands(sub_matches, &config).await?;
         }
         Some(("mine", sub_matches)) => {
-            start_mining(sub_matches).await?;
+            match sub_matches.subcommand() {
+                Some(("bench", bench_matches)) => run_mining_benchmark(bench_matches)?,
+                _ => start_mining(&config).await?,
+            }
+        }
+        Some(("stats", _)) => {
+            show_stats(&config).await?;
+        }
+        Some(("init", _)) => {
+            init_chain(&config)?;
+        }
+        Some(("export", sub_matches)) => {
+            export_chain(sub_matches, &config)?;
+        }
+        Some(("import", sub_matches)) => {
+            import_chain(sub_matches, &config)?;
         }
-        Some(("stats", sub_matches)) => {
-            show_stats(sub_matches).await?;
+        Some(("repair", _)) => {
+            repair_storage(&config)?;
         }
         Some(("token", sub_matches)) => {
-            handle_token_commands(sub_matches).await?;
+            handle_token_commands(sub_matches, &config).await?;
+        }
+        Some(("contract", sub_matches)) => {
+            handle_contract_commands(sub_matches, &config).await?;
+        }
+        Some(("tx", sub_matches)) => {
+            handle_tx_commands(sub_matches, &config).await?;
+        }
+        Some(("address", sub_matches)) => {
+            handle_address_commands(sub_matches, &config)?;
         }
         Some(("ai3", sub_matches)) => {
-            handle_ai3_commands(sub_matches).await?;
+            handle_ai3_commands(sub_matches, &config).await?;
+        }
+        Some(("console", sub_matches)) => {
+            let addr = sub_matches.get_one::<String>("rpc").cloned().unwrap_or_else(|| config.rpc_address());
+            let keystore = Keystore::open(&config.keystore_dir());
+            match sub_matches.get_one::<String>("exec") {
+                Some(commands) => console::exec(&addr, keystore, commands).await?,
+                None => console::run(&addr, keystore, &Path::new(&config.node.data_dir).join("console_history")).await?,
+            }
+        }
+        Some(("dashboard", sub_matches)) => {
+            let addr = sub_matches.get_one::<String>("rpc").cloned().unwrap_or_else(|| config.rpc_address());
+            let interval: u64 = sub_matches.get_one::<String>("interval")
+                .and_then(|interval| interval.parse().ok())
+                .filter(|interval| *interval > 0)
+                .ok_or_else(|| TribeError::Generic("--interval must be a positive number of seconds".to_string()))?;
+            dashboard::run(&addr, Duration::from_secs(interval)).await?;
+        }
+        Some(("config", sub_matches)) => {
+            match sub_matches.subcommand() {
+                Some(("print", _)) => print!("{}", config.to_toml()?),
+                _ => println!("Available config commands: print"),
+            }
         }
         Some(("esp32", sub_matches)) => {
             match sub_matches.subcommand() {
                 Some(("mine", esp32_matches)) => {
-                    let config = ESP32Config {
+                    let esp32_config = ESP32Config {
                         device_id: esp32_matches.get_one::<String>("device-id").unwrap().clone(),
                         wifi_ssid: esp32_matches.get_one::<String>("wifi-ssid").unwrap().clone(),
                         wifi_password: esp32_matches.get_one::<String>("wifi-password").unwrap().clone(),
-                        node_url: esp32_matches.get_one::<String>("node-url").unwrap().clone(),
-                        mining_threads: esp32_matches.get_one::<String>("threads").unwrap().parse().unwrap_or(2),
-                        ai3_enabled: esp32_matches.get_flag("ai3"),
-                        power_limit: esp32_matches.get_one::<String>("power-limit").unwrap().parse().unwrap_or(3.0),
+                        node_url: config.esp.node_url.clone(),
+                        mining_threads: config.esp.threads,
+                        ai3_enabled: config.esp.ai3,
+                        power_limit: config.esp.power_limit,
                     };
                     
-                    if let Err(e) = start_esp32_mining(config).await {
+                    if let Err(e) = start_esp32_mining(esp32_config).await {
                         eprintln!("ESP32 mining failed: {}", e);
                         process::exit(1);
                     }
@@ -242,13 +729,31 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-async fn start_node(matches: &clap::ArgMatches) -> TribeResult<()> {
-    let port: u16 = matches.get_one::<String>("port")
-        .unwrap()
-        .parse()
-        .map_err(|_| TribeError::Generic("Invalid port number".to_string()))?;
-    
-    let data_dir = matches.get_one::<String>("data-dir").unwrap();
+#[cfg(unix)]
+fn detach_node(config: &TribeConfig) -> TribeResult<()> {
+    let pid = daemon::detach(config)?;
+    println!("Node started in the background with PID {}", pid);
+    println!("Logging to {}", config.log_file().display());
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn detach_node(_config: &TribeConfig) -> TribeResult<()> {
+    Err(TribeError::Generic("--daemon is only supported on Unix; use a service manager instead".to_string()))
+}
+
+#[cfg_attr(not(unix), allow(unused_variables))]
+async fn start_node(config: &TribeConfig, daemon: bool, dev: bool) -> TribeResult<()> {
+    #[cfg(unix)]
+    if daemon {
+        daemon::enter_daemon(config)?;
+    }
+    // Held until the node exits; refuses to start a second node on the same data directory
+    #[cfg(unix)]
+    let _pid_file = PidFile::acquire(&config.pid_file())?;
+
+    let port = config.node.port;
+    let data_dir = &config.node.data_dir;
     let listen_addr: SocketAddr = format!("0.0.0.0:{}", port).parse()
         .map_err(|_| TribeError::Network("Invalid listen address".to_string()))?;
 
@@ -256,86 +761,330 @@ async fn start_node(matches: &clap::ArgMatches) -> TribeResult<()> {
     println!("Data directory: {}", data_dir);
     println!("Listening on: {}", listen_addr);
 
-    // Initialize blockchain
-    let blockchain = TribeChain::new(data_dir)?;
+    // Initialize blockchain, shared by the network and the RPC listener. The dev chain
+    // lives in its own directory so it never meets the configured chain's data.
+    let blockchain = if dev {
+        let dev_dir = Path::new(data_dir).join("dev").display().to_string();
+        println!("Dev chain in: {}", dev_dir);
+        TribeChain::with_chain_spec(&dev_dir, &config.storage, &dev::dev_spec())?
+    } else {
+        TribeChain::with_storage_config(data_dir, &config.storage)?
+    };
+    let blockchain = Arc::new(RwLock::new(blockchain));
+    let contracts = Arc::new(RwLock::new(ContractEngine::new()));
+    if config.rpc.enabled {
+        let rpc_addr = config.rpc_address();
+        println!("RPC listening on: {}", rpc_addr);
+        let rpc = RpcServer::new(config.rpc.port)?;
+        // ESP devices report over `pushTelemetry`; kept alongside the chain
+        let telemetry = ESPCompatibility::telemetry_store();
+        let telemetry = match blockchain.read().await.storage.clone() {
+            Some(storage) => telemetry.with_storage(storage)?,
+            None => telemetry,
+        };
+        let mut state = RpcState::new(blockchain.clone())
+            .with_contracts(contracts.clone())
+            .with_telemetry(Arc::new(Mutex::new(telemetry)));
+        if config.rpc.eth_compat {
+            println!("Ethereum-compatible RPC enabled (HTTP POST to http://{})", rpc_addr);
+            state = state.with_eth_compat();
+        }
+        if config.rpc.graphql {
+            println!("GraphQL queries at http://{}{}", rpc_addr, GRAPHQL_PATH);
+            state = state.with_graphql();
+        }
+        if config.rpc.mining_api {
+            // Tasks queued in the distributor are handed to devices that poll for them
+            println!("ESP mining API at http://{}/api/", rpc_addr);
+            state = state.with_mining_gateway(Arc::new(Mutex::new(TaskDistributor::new())));
+        }
+        tokio::spawn(async move {
+            if let Err(e) = rpc.listen(&rpc_addr, state).await {
+                eprintln!("RPC listener failed: {}", e);
+            }
+        });
+    }
+    let shutdown = Shutdown::new();
+    shutdown.listen_for_signals()?;
+    if config.bridge.enabled {
+        println!("Relaying deposits from {} to bridge {}", config.bridge.rpc_url, config.bridge.bridge_id);
+        tokio::spawn(relay_bridge(config.bridge.clone(), contracts.clone(), shutdown.clone()));
+    }
+    if dev {
+        dev::print_accounts(&Keystore::open(&config.keystore_dir()))?;
+        let miner_address = config.mining.address.clone().unwrap_or_else(|| dev::dev_account(0));
+        println!("Mining on demand; rewards go to {}", miner_address);
+        tokio::spawn(dev::auto_mine(blockchain.clone(), miner_address, shutdown.clone()));
+    }
     let node_id = format!("node_{}", port);
     
     // Create network node
     let network_node = NetworkNode::new(node_id, listen_addr, blockchain);
 
-    // Connect to peers if specified
-    if let Some(peers) = matches.get_many::<String>("connect") {
-        for peer_addr in peers {
-            let addr: SocketAddr = peer_addr.parse()
-                .map_err(|_| TribeError::Network(format!("Invalid peer address: {}", peer_addr)))?;
-            
-            println!("Connecting to peer: {}", addr);
-            if let Err(e) = network_node.connect_to_peer(addr).await {
-                eprintln!("Failed to connect to peer {}: {}", addr, e);
+    // Connect to peers if specified; a dev chain stays on its own
+    for peer_addr in config.node.connect.iter().filter(|_| !dev) {
+        let addr: SocketAddr = peer_addr.parse()
+            .map_err(|_| TribeError::Network(format!("Invalid peer address: {}", peer_addr)))?;
+        
+        println!("Connecting to peer: {}", addr);
+        if let Err(e) = network_node.connect_to_peer(addr).await {
+            eprintln!("Failed to connect to peer {}: {}", addr, e);
+        }
+    }
+
+    // Run until SIGINT, SIGTERM or `tribechain stop`, then stop the network, which
+    // saves the address book
+    #[cfg(unix)]
+    let status = NodeStatus {
+        pid: process::id(),
+        started_at: chrono::Utc::now().timestamp() as u64,
+        uptime_secs: 0,
+        data_dir: data_dir.clone(),
+        port,
+        rpc_port: config.rpc.enabled.then_some(config.rpc.port),
+        daemon,
+        log_file: daemon.then(|| config.log_file().display().to_string()),
+    };
+    #[cfg(unix)]
+    let _control = ControlServer::spawn(&config.control_socket(), status, shutdown.clone())?;
+    tokio::select! {
+        result = network_node.start() => result?,
+        _ = shutdown.wait() => println!("Shutting down node..."),
+    }
+    network_node.stop().await?;
+
+    Ok(())
+}
+
+#[cfg(unix)]
+/// Attest confirmed deposits on the EVM chain to the bridge contract as this node's
+/// attester until shutdown
+async fn relay_bridge(config: BridgeConfig, contracts: Arc<RwLock<ContractEngine>>, shutdown: Shutdown) {
+    let relayer_config = RelayerConfig::new(&config.rpc_url, config.remote_chain_id, &config.bridge_address)
+        .with_confirmations(config.confirmations)
+        .with_start_block(config.start_block);
+    let mut relayer = match BridgeRelayer::connect(relayer_config).await {
+        Ok(relayer) => relayer,
+        Err(e) => {
+            eprintln!("Bridge relayer failed to start: {}", e);
+            return;
+        }
+    };
+    while !shutdown.is_triggered() {
+        match relayer.poll().await {
+            Ok(deposits) => {
+                let mut engine = contracts.write().await;
+                for deposit in deposits {
+                    let key = deposit.key();
+                    match engine.attest_bridge_deposit(&config.bridge_id, &config.attester, deposit) {
+                        Ok(Some(amount)) => println!("Bridged deposit {} ({} units)", key, amount),
+                        Ok(None) => println!("Attested deposit {}", key),
+                        Err(e) => eprintln!("Attesting deposit {} failed: {}", key, e),
+                    }
+                }
             }
+            Err(e) => eprintln!("Bridge relayer poll failed: {}", e),
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_secs(config.poll_secs.max(1))) => {}
+            _ = shutdown.wait() => break,
         }
     }
+}
 
-    // Start the node
-    network_node.start().await?;
+async fn stop_node(config: &TribeConfig) -> TribeResult<()> {
+    let Some(pid) = daemon::read_pid(&config.pid_file()).filter(|pid| daemon::process_alive(*pid)) else {
+        println!("No node is running in {}", config.node.data_dir);
+        return Ok(());
+    };
+    if let Err(e) = daemon::send_control(&config.control_socket(), ControlCommand::Stop).await {
+        eprintln!("{}; sending SIGTERM to PID {}", e, pid);
+        daemon::terminate(pid)?;
+    }
 
+    println!("Stopping node (PID {})...", pid);
+    // The node itself waits up to SHUTDOWN_GRACE for AI3 tasks
+    if !daemon::wait_for_exit(pid, SHUTDOWN_GRACE + Duration::from_secs(10)).await {
+        return Err(TribeError::Generic(format!("Node (PID {}) is still running", pid)));
+    }
+    println!("Node stopped");
     Ok(())
 }
 
-async fn handle_wallet_commands(matches: &clap::ArgMatches) -> TribeResult<()> {
+#[cfg(unix)]
+async fn node_status(config: &TribeConfig) -> TribeResult<()> {
+    let Some(pid) = daemon::read_pid(&config.pid_file()).filter(|pid| daemon::process_alive(*pid)) else {
+        println!("No node is running in {}", config.node.data_dir);
+        // LSB status code for "program is not running", for init scripts
+        process::exit(3);
+    };
+    match daemon::send_control(&config.control_socket(), ControlCommand::Status).await {
+        Ok(reply) => {
+            let status: NodeStatus = serde_json::from_value(reply)
+                .map_err(|e| TribeError::Generic(format!("Invalid status reply: {}", e)))?;
+            println!("=== TribeChain Node ===");
+            println!("PID: {}", status.pid);
+            println!("Uptime: {} seconds", status.uptime_secs);
+            println!("Data directory: {}", status.data_dir);
+            println!("Port: {}", status.port);
+            if let Some(rpc_port) = status.rpc_port {
+                println!("RPC port: {}", rpc_port);
+            }
+            println!("Daemon: {}", status.daemon);
+            if let Some(log_file) = status.log_file {
+                println!("Log file: {}", log_file);
+            }
+        }
+        Err(e) => println!("Node running with PID {}, but its control socket is unavailable: {}", pid, e),
+    }
+    Ok(())
+}
+
+async fn handle_wallet_commands(matches: &clap::ArgMatches, config: &TribeConfig) -> TribeResult<()> {
     match matches.subcommand() {
         Some(("balance", sub_matches)) => {
-            let address = sub_matches.get_one::<String>("address").unwrap();
-            let blockchain = TribeChain::new("./data")?;
+            let Some(address) = sub_matches.get_one::<String>("address") else {
+                return watched_balances(sub_matches, config).await;
+            };
+            let blockchain = TribeChain::new(&config.node.data_dir)?;
             let balance = blockchain.get_balance(address);
             println!("Balance for {}: {} TRIBE", address, balance as f64 / 1_000_000.0);
+            if let Some(min_confirmations) = number_arg(sub_matches, "min-confirmations")? {
+                let confirmed = blockchain.get_confirmed_balance(address, min_confirmations);
+                println!("With {} confirmations: {} TRIBE", min_confirmations, confirmed as f64 / 1_000_000.0);
+            }
         }
         Some(("send", sub_matches)) => {
             let from = sub_matches.get_one::<String>("from").unwrap();
             let to = sub_matches.get_one::<String>("to").unwrap();
-            let amount: u64 = sub_matches.get_one::<String>("amount")
+            let amount = sub_matches.get_one::<String>("amount")
                 .unwrap()
                 .parse::<f64>()
-                .map_err(|_| TribeError::Generic("Invalid amount".to_string()))?
-                as u64 * 1_000_000; // Convert to smallest unit
+                .map_err(|_| TribeError::Generic("Invalid amount".to_string()))?;
+            let amount = (amount * 1_000_000.0) as u64; // Convert to smallest unit
 
-            let mut blockchain = TribeChain::new("./data")?;
-            
-            let transaction = Transaction::new(
-                from.clone(),
-                TransactionType::Transfer {
-                    to: to.clone(),
-                    amount,
-                }
-            );
-
-            blockchain.add_transaction(transaction)?;
-            println!("Transaction added to pending pool");
+            // Signed like any other transaction and submitted to the running node
+            let mut signer = signer_for(sub_matches, config, from)?;
+            let addr = sub_matches.get_one::<String>("rpc").cloned().unwrap_or_else(|| config.rpc_address());
+            let mut client = RpcClient::connect(&addr).await?;
+            let transfer = TransactionType::Transfer { to: to.clone(), amount };
+            submit_signed(&mut client, signer.as_mut(), transfer, number_arg(sub_matches, "gas-price")?).await?;
             println!("From: {}", from);
             println!("To: {}", to);
             println!("Amount: {} TRIBE", amount as f64 / 1_000_000.0);
         }
+        Some(("keygen", sub_matches)) => {
+            let keystore = Keystore::open(&config.keystore_dir());
+            let (address, path) = match sub_matches.get_one::<String>("address") {
+                Some(address) => (address.clone(), keystore.generate(address)?),
+                None => keystore.generate_account(config_hrp(config)?)?,
+            };
+            println!("Key for {} written to {}", address, path.display());
+        }
+        Some(("watch", sub_matches)) => {
+            let address = sub_matches.get_one::<String>("address").unwrap();
+            let from_block = number_arg(sub_matches, "from-block")?.unwrap_or(0);
+            let mut watchlist = WatchList::open(&config.watchlist_file())?;
+            watchlist.watch(address, sub_matches.get_one::<String>("label").cloned(), from_block)?;
+            watchlist.save()?;
+            println!("Watching {}", address);
+
+            // The address stays watched if the node can't be reached; `rescan` catches up later
+            let addr = sub_matches.get_one::<String>("rpc").cloned().unwrap_or_else(|| config.rpc_address());
+            let scanned = match RpcClient::connect(&addr).await {
+                Ok(mut client) => watchlist.rescan(&mut client, address, false).await,
+                Err(e) => Err(e),
+            };
+            match scanned {
+                Ok(_) => {
+                    watchlist.save()?;
+                    println!("{}", watchlist::summary(address, &watchlist.accounts[address]));
+                }
+                Err(e) => println!("Rescan failed: {}; run `tribechain wallet rescan {}` later", e, address),
+            }
+        }
+        Some(("unwatch", sub_matches)) => {
+            let address = sub_matches.get_one::<String>("address").unwrap();
+            let mut watchlist = WatchList::open(&config.watchlist_file())?;
+            watchlist.unwatch(address)?;
+            watchlist.save()?;
+            println!("No longer watching {}", address);
+        }
+        Some(("rescan", sub_matches)) => {
+            let mut watchlist = WatchList::open(&config.watchlist_file())?;
+            let addresses: Vec<String> = match sub_matches.get_one::<String>("address") {
+                Some(address) => vec![address.clone()],
+                None => watchlist.accounts.keys().cloned().collect(),
+            };
+            let addr = sub_matches.get_one::<String>("rpc").cloned().unwrap_or_else(|| config.rpc_address());
+            let mut client = RpcClient::connect(&addr).await?;
+            for address in addresses {
+                let found = watchlist.rescan(&mut client, &address, sub_matches.get_flag("full")).await?;
+                watchlist.save()?;
+                println!("Found {} transactions; {}", found, watchlist::summary(&address, &watchlist.accounts[&address]));
+            }
+        }
+        Some(("history", sub_matches)) => {
+            let address = sub_matches.get_one::<String>("address").unwrap();
+            let watchlist = WatchList::open(&config.watchlist_file())?;
+            let account = watchlist.accounts.get(address)
+                .ok_or_else(|| TribeError::Generic(format!("{} is not watched; add it with `tribechain wallet watch`", address)))?;
+            println!("{}", watchlist::summary(address, account));
+            let history: Vec<serde_json::Value> = account.transactions.iter().map(watchlist::history_entry).collect();
+            println!("{}", serde_json::to_string_pretty(&history).unwrap_or_default());
+        }
         _ => {
-            println!("Available wallet commands: balance, send");
+            println!("Available wallet commands: balance, send, keygen, watch, unwatch, rescan, history");
         }
     }
 
     Ok(())
 }
 
-async fn start_mining(matches: &clap::ArgMatches) -> TribeResult<()> {
-    let miner_address = matches.get_one::<String>("address").unwrap();
-    let data_dir = matches.get_one::<String>("data-dir").unwrap();
+/// Bring every watch-only account up to date and list them; the last scan is shown
+/// if the node can't be reached
+async fn watched_balances(matches: &clap::ArgMatches, config: &TribeConfig) -> TribeResult<()> {
+    let mut watchlist = WatchList::open(&config.watchlist_file())?;
+    if watchlist.accounts.is_empty() {
+        println!("No watched addresses; add one with `tribechain wallet watch ADDRESS`");
+        return Ok(());
+    }
+    let addr = matches.get_one::<String>("rpc").cloned().unwrap_or_else(|| config.rpc_address());
+    match RpcClient::connect(&addr).await {
+        Ok(mut client) => {
+            let addresses: Vec<String> = watchlist.accounts.keys().cloned().collect();
+            for address in addresses {
+                watchlist.rescan(&mut client, &address, false).await?;
+            }
+            watchlist.save()?;
+        }
+        Err(e) => println!("{}; showing the last scan", e),
+    }
+    for (address, account) in &watchlist.accounts {
+        println!("{}", watchlist::summary(address, account));
+    }
+    Ok(())
+}
+
+async fn start_mining(config: &TribeConfig) -> TribeResult<()> {
+    let miner_address = config.mining.address.as_ref()
+        .ok_or_else(|| TribeError::Mining("No miner address given and mining.address is not configured".to_string()))?;
 
     println!("Starting mining for address: {}", miner_address);
     
-    let mut blockchain = TribeChain::new(data_dir)?;
+    let mut blockchain = TribeChain::with_storage_config(&config.node.data_dir, &config.storage)?;
+
+    // Measured compute power if a benchmark report was given
+    let compute_power = match &config.mining.capabilities {
+        Some(path) => BenchmarkReport::load(Path::new(path))?.compute_power(),
+        None => 1000,
+    };
     
     // Register miner
     let miner_info = MinerInfo {
         id: miner_address.clone(),
         device_type: "CPU".to_string(),
-        compute_power: 1000,
+        compute_power,
         last_seen: std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
@@ -344,7 +1093,9 @@ async fn start_mining(matches: &clap::ArgMatches) -> TribeResult<()> {
     
     blockchain.register_miner(miner_info)?;
 
-    loop {
+    let shutdown = Shutdown::new();
+    shutdown.listen_for_signals()?;
+    while !shutdown.is_triggered() {
         if !blockchain.pending_transactions.is_empty() {
             println!("Mining new block...");
             match blockchain.mine_block(miner_address.clone()) {
@@ -369,6 +1120,11 @@ async fn start_mining(matches: &clap::ArgMatches) -> TribeResult<()> {
             
             let mut ai3_engine = AI3Engine::new();
             for task in pending_tasks {
+                // Tasks already started finish and are recorded; the rest wait for the next run
+                if shutdown.is_triggered() {
+                    break;
+                }
+                let _in_flight = shutdown.track();
                 match ai3_engine.execute_tensor_operation(&task.operation, &task.input_data) {
                     Ok(result) => {
                         blockchain.complete_tensor_task(&task.id, result)?;
@@ -381,13 +1137,44 @@ async fn start_mining(matches: &clap::ArgMatches) -> TribeResult<()> {
             }
         }
 
-        tokio::time::sleep(tokio::time::Duration::from_secs(10)).await;
+        tokio::select! {
+            _ = tokio::time::sleep(tokio::time::Duration::from_secs(10)) => {}
+            _ = shutdown.wait() => {}
+        }
+    }
+
+    println!("Shutting down miner...");
+    if !shutdown.wait_for_tasks(SHUTDOWN_GRACE).await {
+        eprintln!("{} AI3 tasks still running; exiting without them", shutdown.in_flight());
+    }
+    // Keeps pending transactions and the miner registration for the next start
+    blockchain.flush()?;
+    Ok(())
+}
+
+fn run_mining_benchmark(matches: &clap::ArgMatches) -> TribeResult<()> {
+    let duration_ms: u64 = matches.get_one::<String>("duration")
+        .unwrap()
+        .parse()
+        .map_err(|_| TribeError::Generic("Invalid duration".to_string()))?;
+
+    eprintln!("Benchmarking {} operations, {} ms each...", BENCHMARK_OPERATIONS.len() + 1, duration_ms);
+    let report = BenchmarkReport::run(Duration::from_millis(duration_ms))?;
+
+    match matches.get_one::<String>("output") {
+        Some(path) => {
+            report.save(Path::new(path))?;
+            eprintln!("Capability report written to {}", path);
+            eprintln!("Compute power: {}", report.compute_power());
+        }
+        None => println!("{}", report.to_json()?),
     }
+
+    Ok(())
 }
 
-async fn show_stats(matches: &clap::ArgMatches) -> TribeResult<()> {
-    let data_dir = matches.get_one::<String>("data-dir").unwrap();
-    let blockchain = TribeChain::new(data_dir)?;
+async fn show_stats(config: &TribeConfig) -> TribeResult<()> {
+    let blockchain = TribeChain::with_storage_config(&config.node.data_dir, &config.storage)?;
     let stats = blockchain.get_stats();
 
     println!("=== TribeChain Statistics ===");
@@ -405,7 +1192,71 @@ async fn show_stats(matches: &clap::ArgMatches) -> TribeResult<()> {
     Ok(())
 }
 
-async fn handle_token_commands(matches: &clap::ArgMatches) -> TribeResult<()> {
+fn init_chain(config: &TribeConfig) -> TribeResult<()> {
+    let data_dir = &config.node.data_dir;
+    let spec = ChainSpec::from_name_or_path(&config.node.chain)?;
+
+    let blockchain = TribeChain::with_chain_spec(data_dir, &config.storage, &spec)?;
+
+    // Keep the spec next to the chain so other nodes can be started from it
+    std::fs::create_dir_all(data_dir)
+        .map_err(|e| TribeError::Storage(format!("Failed to create {}: {}", data_dir, e)))?;
+    spec.save(&Path::new(data_dir).join("chain_spec.toml"))?;
+
+    println!("Initialized {} chain (ID {}) in {}", spec.name, spec.chain_id, data_dir);
+    println!("Genesis hash: {}", blockchain.blocks[0].hash);
+    println!("State root: {}", blockchain.blocks[0].state_root);
+    for (address, balance) in &spec.genesis_allocations {
+        println!("  {}: {}", address, balance);
+    }
+    for token in &spec.tokens {
+        println!("  Token {} ({}): {} held by {}", token.symbol, token.token_id(spec.chain_id), token.total_supply, token.owner);
+    }
+    Ok(())
+}
+
+fn export_chain(matches: &clap::ArgMatches, config: &TribeConfig) -> TribeResult<()> {
+    let out = matches.get_one::<String>("out").unwrap();
+    let blockchain = TribeChain::with_storage_config(&config.node.data_dir, &config.storage)?;
+    let from: u64 = matches.get_one::<String>("from")
+        .unwrap()
+        .parse()
+        .map_err(|_| TribeError::Generic("Invalid --from height".to_string()))?;
+    let to: u64 = match matches.get_one::<String>("to") {
+        Some(to) => to.parse().map_err(|_| TribeError::Generic("Invalid --to height".to_string()))?,
+        None => blockchain.blocks.len() as u64 - 1,
+    };
+
+    let file = std::fs::File::create(out)
+        .map_err(|e| TribeError::Storage(format!("Failed to create {}: {}", out, e)))?;
+    let count = blockchain.export_archive(from, to, std::io::BufWriter::new(file))?;
+    println!("Exported {} blocks ({}..={}) to {}", count, from, to, out);
+    Ok(())
+}
+
+fn import_chain(matches: &clap::ArgMatches, config: &TribeConfig) -> TribeResult<()> {
+    let path = matches.get_one::<String>("file").unwrap();
+    let mut blockchain = TribeChain::with_storage_config(&config.node.data_dir, &config.storage)?;
+
+    let file = std::fs::File::open(path)
+        .map_err(|e| TribeError::Storage(format!("Failed to open {}: {}", path, e)))?;
+    let summary = blockchain.import_archive(std::io::BufReader::new(file))?;
+    println!("Imported {} blocks, {} already present; chain height {}",
+        summary.imported, summary.skipped, blockchain.blocks.len() - 1);
+    Ok(())
+}
+
+fn repair_storage(config: &TribeConfig) -> TribeResult<()> {
+    let data_dir = &config.node.data_dir;
+    match TribeChain::repair(data_dir)? {
+        RepairOutcome::Clean => println!("No interrupted block import found in {}", data_dir),
+        RepairOutcome::Replayed { index } => println!("Replayed interrupted import of block {}", index),
+        RepairOutcome::RolledBack { index } => println!("Rolled back interrupted import of block {}", index),
+    }
+    Ok(())
+}
+
+async fn handle_token_commands(matches: &clap::ArgMatches, config: &TribeConfig) -> TribeResult<()> {
     match matches.subcommand() {
         Some(("create", sub_matches)) => {
             let name = sub_matches.get_one::<String>("name").unwrap();
@@ -416,7 +1267,7 @@ async fn handle_token_commands(matches: &clap::ArgMatches) -> TribeResult<()> {
                 .map_err(|_| TribeError::Generic("Invalid supply".to_string()))?;
             let creator = sub_matches.get_one::<String>("creator").unwrap();
 
-            let mut blockchain = TribeChain::new("./data")?;
+            let mut blockchain = TribeChain::new(&config.node.data_dir)?;
             
             let token_info = TokenInfo {
                 token_type: if symbol == "STOMP" { TokenType::STOMP } else { TokenType::AUM },
@@ -449,7 +1300,402 @@ async fn handle_token_commands(matches: &clap::ArgMatches) -> TribeResult<()> {
     Ok(())
 }
 
-async fn handle_ai3_commands(matches: &clap::ArgMatches) -> TribeResult<()> {
+fn contract_args_arg() -> Arg {
+    Arg::new("args")
+        .long("args")
+        .value_name("JSON")
+        .help("Arguments as a JSON array")
+        .default_value("[]")
+}
+
+fn contract_gas_price_arg() -> Arg {
+    Arg::new("gas-price")
+        .long("gas-price")
+        .value_name("PRICE")
+        .help("Gas price; the node's estimate if omitted")
+}
+
+fn contract_wait_arg() -> Arg {
+    Arg::new("wait")
+        .long("wait")
+        .value_name("SECONDS")
+        .help("How long to wait for the receipt")
+        .default_value("60")
+}
+
+fn number_arg<T: std::str::FromStr>(matches: &clap::ArgMatches, id: &str) -> TribeResult<Option<T>> {
+    // Not every command defines every flag
+    matches.try_get_one::<String>(id).ok().flatten()
+        .map(|value| value.parse().map_err(|_| TribeError::Generic(format!("--{} must be a number", id))))
+        .transpose()
+}
+
+fn read_abi(path: &str) -> TribeResult<Vec<MethodSignature>> {
+    let abi_json = std::fs::read_to_string(path)
+        .map_err(|e| TribeError::Generic(format!("Cannot read ABI file: {}", e)))?;
+    serde_json::from_str(&abi_json).map_err(|e| TribeError::Generic(format!("Invalid ABI file: {}", e)))
+}
+
+fn json_args(matches: &clap::ArgMatches) -> TribeResult<Vec<serde_json::Value>> {
+    serde_json::from_str(matches.get_one::<String>("args").unwrap())
+        .map_err(|e| TribeError::Generic(format!("Arguments must be a JSON array: {}", e)))
+}
+
+async fn node_abi(client: &mut RpcClient, address: &str) -> TribeResult<Vec<MethodSignature>> {
+    serde_json::from_value(client.call("getContractAbi", serde_json::json!([address])).await?)
+        .map_err(|e| TribeError::Rpc(format!("Invalid ABI from the node: {}", e)))
+}
+
+/// Deployment of the `code` file, with constructor arguments encoded like any other
+/// call against the ABI's `constructor`
+fn deploy_transaction(matches: &clap::ArgMatches, args: &[serde_json::Value]) -> TribeResult<TransactionType> {
+    let path = matches.get_one::<String>("code").unwrap();
+    let code = std::fs::read(path)
+        .map_err(|e| TribeError::Generic(format!("Cannot read contract code {}: {}", path, e)))?;
+    let constructor_args = match matches.get_one::<String>("abi") {
+        Some(abi_path) => match read_abi(abi_path)?.iter().find(|signature| signature.name == "constructor") {
+            Some(constructor) => constructor.encode_json_inputs(args)?,
+            None if args.is_empty() => Vec::new(),
+            None => return Err(TribeError::Generic("The ABI has no constructor to take --args".to_string())),
+        },
+        None if args.is_empty() => Vec::new(),
+        None => return Err(TribeError::Generic("Constructor --args need an --abi".to_string())),
+    };
+    Ok(TransactionType::ContractDeploy { code, constructor_args })
+}
+
+/// Call of `method` on `address`, rejected before it reaches the pool if it doesn't
+/// match the ABI
+fn call_transaction(
+    matches: &clap::ArgMatches,
+    abi: &[MethodSignature],
+    args: &[serde_json::Value],
+) -> TribeResult<TransactionType> {
+    let address = matches.get_one::<String>("address").unwrap();
+    let method = matches.get_one::<String>("method").unwrap();
+    let value: u64 = number_arg(matches, "value")?.unwrap_or(0);
+    let signature = abi.iter()
+        .find(|signature| &signature.name == method)
+        .ok_or_else(|| TribeError::Generic(format!("Method {} is not in the ABI", method)))?;
+    if value > 0 && !signature.is_payable {
+        return Err(TribeError::Generic(format!("Method {} is not payable", method)));
+    }
+    Ok(TransactionType::ContractCall {
+        contract_address: address.clone(),
+        method: method.clone(),
+        args: signature.encode_json_inputs(args)?,
+        value,
+    })
+}
+
+fn ledger_arg() -> Arg {
+    Arg::new("ledger")
+        .long("ledger")
+        .help("Sign on a connected Ledger, confirming on the device, instead of with the keystore key")
+        .action(clap::ArgAction::SetTrue)
+}
+
+/// Signer for `from`: a Ledger if `--ledger` was given, the keystore otherwise
+fn signer_for(matches: &clap::ArgMatches, config: &TribeConfig, from: &str) -> TribeResult<Box<dyn Signer>> {
+    if matches.get_flag("ledger") {
+        Ok(Box::new(LedgerSigner::connect(from)?))
+    } else {
+        Ok(Box::new(KeystoreSigner::new(&Keystore::open(&config.keystore_dir()), from)?))
+    }
+}
+
+/// Sign a transaction from the signer's account at its next nonce, and submit it
+async fn submit_signed(
+    client: &mut RpcClient,
+    signer: &mut dyn Signer,
+    transaction_type: TransactionType,
+    gas_price: Option<u64>,
+) -> TribeResult<String> {
+    let from = signer.address().to_string();
+    let chain_id = client.call("getChainInfo", serde_json::json!([])).await?["chain_id"].as_u64()
+        .ok_or_else(|| TribeError::Rpc("Node did not report its chain ID".to_string()))?;
+    let nonce = client.call("getAccountNonce", serde_json::json!([from])).await?["pending_nonce"].as_u64().unwrap_or(0);
+    let gas_price = match gas_price {
+        Some(gas_price) => gas_price,
+        None => client.call("estimateFee", serde_json::json!([1])).await?["gas_price"].as_u64().unwrap_or(0),
+    };
+
+    let mut transaction = Transaction::new(from, transaction_type, 0, nonce)
+        .with_chain_id(chain_id)
+        .with_gas_price(gas_price);
+    // Checked before signing, so a mistyped recipient never reaches a Ledger prompt
+    transaction.validate_recipients()?;
+    signer.sign(&mut transaction)?;
+    client.call("sendTransaction", serde_json::json!([transaction])).await?;
+    println!("Transaction: {}", transaction.hash);
+    println!("Fee: {}", transaction.fee);
+    Ok(transaction.hash)
+}
+
+/// Print the receipt of `tx_hash` once its block is in, or say it is still pending after `wait`
+async fn print_receipt(client: &mut RpcClient, tx_hash: &str, wait: Duration) -> TribeResult<()> {
+    let deadline = tokio::time::Instant::now() + wait;
+    loop {
+        let receipt = client.call("getTransactionReceipt", serde_json::json!([tx_hash])).await?;
+        if !receipt.is_null() {
+            println!("Receipt:");
+            println!("{}", serde_json::to_string_pretty(&receipt).unwrap_or_default());
+            return Ok(());
+        }
+        if tokio::time::Instant::now() >= deadline {
+            println!("Not yet in a block; check later with: tribechain console -e \"receipt {}\"", tx_hash);
+            return Ok(());
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+}
+
+async fn handle_contract_commands(matches: &clap::ArgMatches, config: &TribeConfig) -> TribeResult<()> {
+    let Some((command, sub_matches)) = matches.subcommand() else {
+        println!("Available contract commands: deploy, call, query");
+        return Ok(());
+    };
+    let addr = sub_matches.get_one::<String>("rpc").cloned().unwrap_or_else(|| config.rpc_address());
+    let mut client = RpcClient::connect(&addr).await?;
+    let args = json_args(sub_matches)?;
+    let wait = Duration::from_secs(number_arg(sub_matches, "wait")?.unwrap_or(0));
+
+    match command {
+        "deploy" => {
+            let path = sub_matches.get_one::<String>("code").unwrap();
+            let from = sub_matches.get_one::<String>("from").unwrap();
+            let deploy = deploy_transaction(sub_matches, &args)?;
+
+            if let TransactionType::ContractDeploy { code, .. } = &deploy {
+                println!("Deploying {} ({} bytes) from {}", path, code.len(), from);
+            }
+            let mut signer = signer_for(sub_matches, config, from)?;
+            let tx_hash = submit_signed(&mut client, signer.as_mut(), deploy, number_arg(sub_matches, "gas-price")?).await?;
+            print_receipt(&mut client, &tx_hash, wait).await?;
+        }
+        "call" => {
+            let address = sub_matches.get_one::<String>("address").unwrap();
+            let method = sub_matches.get_one::<String>("method").unwrap();
+            let from = sub_matches.get_one::<String>("from").unwrap();
+            let abi = match sub_matches.get_one::<String>("abi") {
+                Some(abi_path) => read_abi(abi_path)?,
+                None => node_abi(&mut client, address).await?,
+            };
+            let call = call_transaction(sub_matches, &abi, &args)?;
+
+            println!("Contract: {}", address);
+            println!("Method: {}", method);
+            if let TransactionType::ContractCall { args, .. } = &call {
+                println!("Encoded args: {}", hex::encode(args));
+            }
+            let mut signer = signer_for(sub_matches, config, from)?;
+            let tx_hash = submit_signed(&mut client, signer.as_mut(), call, number_arg(sub_matches, "gas-price")?).await?;
+            print_receipt(&mut client, &tx_hash, wait).await?;
+        }
+        "query" => {
+            let address = sub_matches.get_one::<String>("address").unwrap();
+            let method = sub_matches.get_one::<String>("method").unwrap();
+            let caller = sub_matches.get_one::<String>("from").cloned().unwrap_or_default();
+            let result = client.call("queryContract", serde_json::json!([address, method, args, caller])).await?;
+            println!("{}", serde_json::to_string_pretty(&result).unwrap_or_default());
+        }
+        _ => println!("Available contract commands: deploy, call, query"),
+    }
+
+    Ok(())
+}
+
+/// Address prefix of the network `node.chain` names
+fn config_hrp(config: &TribeConfig) -> TribeResult<&'static str> {
+    Ok(hrp_for_chain(ChainSpec::from_name_or_path(&config.node.chain)?.chain_id))
+}
+
+fn handle_address_commands(matches: &clap::ArgMatches, config: &TribeConfig) -> TribeResult<()> {
+    match matches.subcommand() {
+        Some(("encode", sub_matches)) => {
+            let hrp = match sub_matches.get_one::<String>("hrp") {
+                Some(hrp) => hrp.clone(),
+                None => config_hrp(config)?.to_string(),
+            };
+            println!("{}", Address::from_hex(&hrp, sub_matches.get_one::<String>("hex").unwrap())?);
+        }
+        Some(("decode", sub_matches)) => {
+            let address = Address::parse(sub_matches.get_one::<String>("address").unwrap())?;
+            let network = match address.hrp() {
+                MAINNET_HRP => "mainnet",
+                TESTNET_HRP => "testnet or devnet",
+                _ => "not a TribeChain network",
+            };
+            println!("Network: {} ({})", address.hrp(), network);
+            println!("Bytes: {}", address.to_hex());
+        }
+        _ => println!("Available address commands: encode, decode"),
+    }
+    Ok(())
+}
+
+/// `tx build` subcommand with the sender and the fields a node would otherwise supply
+fn tx_build_command(name: &'static str, about: &'static str) -> Command {
+    Command::new(name)
+        .about(about)
+        .arg(Arg::new("from")
+            .long("from")
+            .value_name("ADDRESS")
+            .help("Sender address")
+            .required(true))
+        .arg(Arg::new("nonce")
+            .long("nonce")
+            .value_name("NONCE")
+            .help("Sender nonce; the node's pending nonce if omitted"))
+        .arg(Arg::new("chain-id")
+            .long("chain-id")
+            .value_name("ID")
+            .help("Chain the transaction is bound to; the node's if omitted"))
+        .arg(contract_gas_price_arg())
+        .arg(tx_out_arg())
+        .arg(tx_binary_arg())
+}
+
+fn tx_out_arg() -> Arg {
+    Arg::new("out")
+        .short('o')
+        .long("out")
+        .value_name("FILE")
+        .help("Write the transaction here instead of printing it")
+}
+
+fn tx_binary_arg() -> Arg {
+    Arg::new("binary")
+        .long("binary")
+        .help("Write bincode instead of JSON; needs --out")
+        .action(clap::ArgAction::SetTrue)
+}
+
+fn tx_format(matches: &clap::ArgMatches) -> tx::TxFormat {
+    if matches.get_flag("binary") { tx::TxFormat::Binary } else { tx::TxFormat::Json }
+}
+
+/// Client for `addr`, connected the first time something has to come from the node
+async fn lazy_client<'a>(client: &'a mut Option<RpcClient>, addr: &str) -> TribeResult<&'a mut RpcClient> {
+    if client.is_none() {
+        let connected = RpcClient::connect(addr).await.map_err(|e| match e {
+            TribeError::Rpc(message) => TribeError::Rpc(format!(
+                "{}; to build offline give --nonce, --chain-id and --gas-price (and --abi for calls)", message
+            )),
+            e => e,
+        })?;
+        *client = Some(connected);
+    }
+    Ok(client.as_mut().unwrap())
+}
+
+async fn handle_tx_commands(matches: &clap::ArgMatches, config: &TribeConfig) -> TribeResult<()> {
+    let Some((command, sub_matches)) = matches.subcommand() else {
+        println!("Available tx commands: build, sign, send");
+        return Ok(());
+    };
+    let addr = sub_matches.get_one::<String>("rpc").cloned().unwrap_or_else(|| config.rpc_address());
+
+    match command {
+        "build" => {
+            let Some((kind, build_matches)) = sub_matches.subcommand() else {
+                println!("Available transactions: transfer, token-create, token-transfer, deploy, call");
+                return Ok(());
+            };
+            let from = build_matches.get_one::<String>("from").unwrap();
+            let text = |id: &str| build_matches.get_one::<String>(id).cloned().unwrap_or_default();
+            let mut client = None;
+
+            let transaction_type = match kind {
+                "transfer" => {
+                    let amount: f64 = number_arg(build_matches, "amount")?.unwrap_or(0.0);
+                    TransactionType::Transfer { to: text("to"), amount: (amount * 1_000_000.0) as u64 }
+                }
+                "token-create" => TransactionType::TokenCreate {
+                    name: text("name"),
+                    symbol: text("symbol"),
+                    total_supply: number_arg(build_matches, "supply")?.unwrap_or(0),
+                    decimals: number_arg(build_matches, "decimals")?.unwrap_or(6),
+                },
+                "token-transfer" => TransactionType::TokenTransfer {
+                    to: text("to"),
+                    amount: number_arg(build_matches, "amount")?.unwrap_or(0),
+                    token_id: text("token"),
+                },
+                "deploy" => deploy_transaction(build_matches, &json_args(build_matches)?)?,
+                "call" => {
+                    let abi = match build_matches.get_one::<String>("abi") {
+                        Some(abi_path) => read_abi(abi_path)?,
+                        None => node_abi(lazy_client(&mut client, &addr).await?, &text("address")).await?,
+                    };
+                    call_transaction(build_matches, &abi, &json_args(build_matches)?)?
+                }
+                _ => {
+                    println!("Available transactions: transfer, token-create, token-transfer, deploy, call");
+                    return Ok(());
+                }
+            };
+
+            let nonce = match number_arg(build_matches, "nonce")? {
+                Some(nonce) => nonce,
+                None => lazy_client(&mut client, &addr).await?
+                    .call("getAccountNonce", serde_json::json!([from])).await?["pending_nonce"].as_u64().unwrap_or(0),
+            };
+            let chain_id = match number_arg(build_matches, "chain-id")? {
+                Some(chain_id) => chain_id,
+                None => lazy_client(&mut client, &addr).await?
+                    .call("getChainInfo", serde_json::json!([])).await?["chain_id"].as_u64()
+                    .ok_or_else(|| TribeError::Rpc("Node did not report its chain ID".to_string()))?,
+            };
+            let gas_price = match number_arg(build_matches, "gas-price")? {
+                Some(gas_price) => gas_price,
+                None => lazy_client(&mut client, &addr).await?
+                    .call("estimateFee", serde_json::json!([1])).await?["gas_price"].as_u64().unwrap_or(0),
+            };
+
+            let transaction = Transaction::new(from.clone(), transaction_type, 0, nonce)
+                .with_chain_id(chain_id)
+                .with_gas_price(gas_price);
+            if !transaction.validate()? {
+                return Err(TribeError::InvalidTransaction("Amounts and supplies must be above zero".to_string()));
+            }
+            transaction.validate_recipients()?;
+            let out = build_matches.get_one::<String>("out").map(Path::new);
+            tx::write(&transaction, out, tx_format(build_matches))?;
+            if let Some(out) = out {
+                println!("{}", tx::describe(&transaction));
+                println!("Unsigned transaction written to {}", out.display());
+            }
+        }
+        "sign" => {
+            // Nothing here touches the network, so this can run on an air-gapped machine
+            let mut transaction = tx::read(Path::new(sub_matches.get_one::<String>("file").unwrap()))?;
+            signer_for(sub_matches, config, &transaction.from)?.sign(&mut transaction)?;
+            let out = sub_matches.get_one::<String>("out").map(Path::new);
+            tx::write(&transaction, out, tx_format(sub_matches))?;
+            if let Some(out) = out {
+                println!("{}", tx::describe(&transaction));
+                println!("Signed transaction written to {}", out.display());
+            }
+        }
+        "send" => {
+            let transaction = tx::read(Path::new(sub_matches.get_one::<String>("file").unwrap()))?;
+            if transaction.signature.is_empty() {
+                return Err(TribeError::InvalidTransaction("Transaction is not signed; run `tribechain tx sign` first".to_string()));
+            }
+            let mut client = RpcClient::connect(&addr).await?;
+            client.call("sendTransaction", serde_json::json!([transaction])).await?;
+            println!("Transaction: {}", transaction.hash);
+            let wait = Duration::from_secs(number_arg(sub_matches, "wait")?.unwrap_or(0));
+            print_receipt(&mut client, &transaction.hash, wait).await?;
+        }
+        _ => println!("Available tx commands: build, sign, send"),
+    }
+
+    Ok(())
+}
+
+async fn handle_ai3_commands(matches: &clap::ArgMatches, config: &TribeConfig) -> TribeResult<()> {
     match matches.subcommand() {
         Some(("compute", sub_matches)) => {
             let operation = sub_matches.get_one::<String>("operation").unwrap();
@@ -463,7 +1709,7 @@ async fn handle_ai3_commands(matches: &clap::ArgMatches) -> TribeResult<()> {
                 .collect::<Result<Vec<_>, _>>()
                 .map_err(|_| TribeError::Generic("Invalid input data format".to_string()))?;
 
-            let mut blockchain = TribeChain::new("./data")?;
+            let mut blockchain = TribeChain::new(&config.node.data_dir)?;
             
             let task = TensorTask {
                 id: format!("task_{}", std::time::SystemTime::now()
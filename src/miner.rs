@@ -52,15 +52,20 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Create AI3 miner
     let miner = AI3Miner::new(miner_id.clone(), difficulty);
     
+    // Threads finish the task they're on after SIGINT or SIGTERM, then exit
+    let shutdown = Shutdown::new();
+    shutdown.listen_for_signals()?;
+
     // Start mining tasks
     let mut handles = Vec::new();
     
     for i in 0..threads {
         let miner_clone = miner.clone();
         let miner_id_clone = format!("{}-{}", miner_id, i);
+        let shutdown = shutdown.clone();
         
         let handle = tokio::spawn(async move {
-            loop {
+            while !shutdown.is_triggered() {
                 // Generate a random tensor task for demonstration
                 match TensorTask::generate_random(difficulty, 1000) {
                     Ok(task) => {
@@ -86,7 +91,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 }
                 
                 // Small delay between mining attempts
-                tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+                tokio::select! {
+                    _ = tokio::time::sleep(tokio::time::Duration::from_secs(1)) => {}
+                    _ = shutdown.wait() => {}
+                }
             }
         });
         
@@ -97,6 +105,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     for handle in handles {
         handle.await?;
     }
+    println!("Miner stopped");
 
     Ok(())
 } 
\ No newline at end of file
@@ -0,0 +1,68 @@
+use tribechain::{Transaction, TribeResult, TribeError};
+use crate::keystore::Keystore;
+use crate::ledger::LedgerDevice;
+
+/// Something that can sign transactions for one account
+pub trait Signer {
+    /// Account the signatures are for
+    fn address(&self) -> &str;
+
+    fn sign(&mut self, transaction: &mut Transaction) -> TribeResult<()>;
+}
+
+/// Signs with a key from the local keystore
+pub struct KeystoreSigner {
+    address: String,
+    key: String,
+}
+
+impl KeystoreSigner {
+    pub fn new(keystore: &Keystore, address: &str) -> TribeResult<Self> {
+        Ok(Self { address: address.to_string(), key: keystore.key(address)? })
+    }
+}
+
+impl Signer for KeystoreSigner {
+    fn address(&self) -> &str {
+        &self.address
+    }
+
+    fn sign(&mut self, transaction: &mut Transaction) -> TribeResult<()> {
+        transaction.sign(&self.key)
+    }
+}
+
+/// Signs on a Ledger, which shows the transaction and waits for the user to confirm it
+pub struct LedgerSigner {
+    device: LedgerDevice<std::fs::File>,
+    address: String,
+    public_key: String,
+}
+
+impl LedgerSigner {
+    /// Connect to the Ledger, which must hold the key of `address`
+    pub fn connect(address: &str) -> TribeResult<Self> {
+        let mut device = LedgerDevice::open()?;
+        let device_address = device.address()?;
+        if device_address != address {
+            return Err(TribeError::Generic(format!(
+                "The Ledger signs for {}, not {}", device_address, address
+            )));
+        }
+        let public_key = device.public_key()?;
+        Ok(Self { device, address: device_address, public_key })
+    }
+}
+
+impl Signer for LedgerSigner {
+    fn address(&self) -> &str {
+        &self.address
+    }
+
+    fn sign(&mut self, transaction: &mut Transaction) -> TribeResult<()> {
+        println!("Confirm the transaction on the Ledger...");
+        transaction.public_key = self.public_key.clone();
+        transaction.signature = self.device.sign_transaction(transaction)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,143 @@
+use std::path::Path;
+use tribechain::{Transaction, TransactionType, TribeResult, TribeError};
+
+/// Encodings of a transaction file passed between `tx build`, `tx sign` and `tx send`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxFormat {
+    Json,
+    Binary, // bincode, for transfer by QR code or removable media
+}
+
+/// Read a transaction file in either format, refusing one whose hash no longer
+/// matches its contents
+pub fn read(path: &Path) -> TribeResult<Transaction> {
+    let data = std::fs::read(path)
+        .map_err(|e| TribeError::Generic(format!("Cannot read transaction file {}: {}", path.display(), e)))?;
+    // bincode starts with the ID's length, never with a brace
+    let transaction: Transaction = match data.iter().find(|byte| !byte.is_ascii_whitespace()) {
+        Some(b'{') => serde_json::from_slice(&data)
+            .map_err(|e| TribeError::InvalidTransaction(format!("{}: {}", path.display(), e)))?,
+        _ => bincode::deserialize(&data)
+            .map_err(|_| TribeError::InvalidTransaction(format!("{} is not a transaction file", path.display())))?,
+    };
+    if !transaction.validate()? {
+        return Err(TribeError::InvalidTransaction(format!(
+            "{} is malformed or was changed after it was built", path.display()
+        )));
+    }
+    Ok(transaction)
+}
+
+/// Write `transaction` to `path`, or print it when no path is given (JSON only)
+pub fn write(transaction: &Transaction, path: Option<&Path>, format: TxFormat) -> TribeResult<()> {
+    let data = match format {
+        TxFormat::Json => serde_json::to_vec_pretty(transaction)
+            .map_err(|e| TribeError::Generic(format!("Failed to encode transaction: {}", e)))?,
+        TxFormat::Binary => bincode::serialize(transaction)
+            .map_err(|e| TribeError::Generic(format!("Failed to encode transaction: {}", e)))?,
+    };
+    match path {
+        Some(path) => std::fs::write(path, data)
+            .map_err(|e| TribeError::Generic(format!("Cannot write transaction file {}: {}", path.display(), e))),
+        None if format == TxFormat::Json => {
+            println!("{}", String::from_utf8_lossy(&data));
+            Ok(())
+        }
+        None => Err(TribeError::Generic("Binary transactions need --out".to_string())),
+    }
+}
+
+/// What signing `transaction` authorises, for review before the key is used
+pub fn describe(transaction: &Transaction) -> String {
+    let action = match &transaction.transaction_type {
+        TransactionType::Transfer { to, amount } => {
+            format!("Transfer {} TRIBE to {}", *amount as f64 / 1_000_000.0, to)
+        }
+        TransactionType::TokenCreate { name, symbol, total_supply, decimals } => {
+            format!("Create token {} ({}), supply {} with {} decimals", name, symbol, total_supply, decimals)
+        }
+        TransactionType::TokenTransfer { to, amount, token_id } => {
+            format!("Transfer {} units of token {} to {}", amount, token_id, to)
+        }
+        TransactionType::Stake { amount, validator, duration } => {
+            format!("Stake {} TRIBE with {} for {} seconds", *amount as f64 / 1_000_000.0, validator, duration)
+        }
+        TransactionType::TensorCompute { operation, input_data, reward, .. } => {
+            format!("Tensor task {} on {} values, reward {}", operation, input_data.len(), reward)
+        }
+        TransactionType::ContractDeploy { code, constructor_args } => {
+            format!("Deploy {} bytes of contract code, constructor args {}", code.len(), hex::encode(constructor_args))
+        }
+        TransactionType::ContractCall { contract_address, method, args, value } => {
+            format!("Call {}.{} with value {}, args {}", contract_address, method, value, hex::encode(args))
+        }
+        TransactionType::DelegateVote { delegates } => format!("Vote for {}", delegates.join(", ")),
+    };
+    let signed = if transaction.signature.is_empty() { "no" } else { "yes" };
+    [
+        format!("Action:    {}", action),
+        format!("From:      {}", transaction.from),
+        format!("Nonce:     {}", transaction.nonce),
+        format!("Chain ID:  {}", transaction.chain_id),
+        format!("Gas price: {}", transaction.gas_price),
+        format!("Fee:       {}", transaction.fee),
+        format!("Hash:      {}", transaction.hash),
+        format!("Signed:    {}", signed),
+    ].join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transfer() -> Transaction {
+        Transaction::new("alice".to_string(), TransactionType::Transfer { to: "bob".to_string(), amount: 2_500_000 }, 1, 4)
+    }
+
+    #[test]
+    fn test_files_round_trip_in_both_formats() {
+        let dir = std::env::temp_dir().join(format!("tribechain-tx-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut transaction = transfer();
+        transaction.sign("alice-key").unwrap();
+
+        for (format, name) in [(TxFormat::Json, "tx.json"), (TxFormat::Binary, "tx.bin")] {
+            let path = dir.join(name);
+            write(&transaction, Some(&path), format).unwrap();
+            let read_back = read(&path).unwrap();
+            assert_eq!((read_back.hash.as_str(), read_back.signature.as_str()), (transaction.hash.as_str(), transaction.signature.as_str()));
+            assert!(read_back.verify_signature());
+        }
+        assert!(write(&transaction, None, TxFormat::Binary).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_altered_files_are_refused() {
+        let dir = std::env::temp_dir().join(format!("tribechain-tx-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("tx.json");
+        write(&transfer(), Some(&path), TxFormat::Json).unwrap();
+
+        // Raising the amount after building leaves the hash behind
+        let json = std::fs::read_to_string(&path).unwrap().replace("2500000", "9500000");
+        std::fs::write(&path, json).unwrap();
+        assert!(matches!(read(&path), Err(TribeError::InvalidTransaction(_))));
+
+        std::fs::write(&path, b"not a transaction").unwrap();
+        assert!(matches!(read(&path), Err(TribeError::InvalidTransaction(_))));
+        assert!(read(&dir.join("missing.json")).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_describe_shows_what_is_signed() {
+        let mut transaction = transfer();
+        let unsigned = describe(&transaction);
+        assert!(unsigned.contains("Transfer 2.5 TRIBE to bob"), "{}", unsigned);
+        assert!(unsigned.contains("Nonce:     4") && unsigned.contains("Signed:    no"), "{}", unsigned);
+
+        transaction.sign("alice-key").unwrap();
+        assert!(describe(&transaction).contains("Signed:    yes"));
+    }
+}
@@ -0,0 +1,313 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tribechain::{RpcClient, Transaction, TribeResult, TribeError};
+
+/// Blocks deep a transaction must be before the wallet counts it as confirmed
+pub const CONFIRMATIONS: u64 = 6;
+
+/// Where a watched transaction stands on the node's chain
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TxStatus {
+    Pending, // In the mempool, or fewer than `CONFIRMATIONS` blocks deep
+    #[default]
+    Confirmed,
+    Orphaned, // Its block was reorganised away and it isn't on the new chain
+}
+
+/// Transaction touching a watched address, as found by a rescan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchedTransaction {
+    pub block_index: u64,
+    pub block_hash: String, // Empty while in the mempool
+    pub transaction: Transaction,
+    #[serde(default)]
+    pub confirmations: u64, // As of `scanned_to`
+    #[serde(default)]
+    pub status: TxStatus,
+}
+
+/// Address tracked without its key
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WatchedAccount {
+    pub label: Option<String>,
+    pub balance: u64, // As of `scanned_to`
+    #[serde(default)]
+    pub confirmed_balance: u64, // Excluding funds fewer than `CONFIRMATIONS` blocks deep, safe to spend
+    pub scanned_to: Option<u64>, // Last block rescanned, or the one before where scanning starts
+    pub scanned_hash: String, // Hash of `scanned_to`, to notice it being reorganised away
+    pub transactions: Vec<WatchedTransaction>, // Oldest first
+}
+
+/// Watch-only accounts, kept as JSON in the data directory
+#[derive(Debug, Default)]
+pub struct WatchList {
+    path: PathBuf,
+    pub accounts: BTreeMap<String, WatchedAccount>,
+}
+
+impl WatchList {
+    /// Open the list at `path`; a missing file is an empty list
+    pub fn open(path: &Path) -> TribeResult<Self> {
+        let accounts = match std::fs::read_to_string(path) {
+            Ok(data) => serde_json::from_str(&data)
+                .map_err(|e| TribeError::Storage(format!("Invalid watch list {}: {}", path.display(), e)))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(TribeError::Storage(format!("Failed to read watch list {}: {}", path.display(), e))),
+        };
+        Ok(Self { path: path.to_path_buf(), accounts })
+    }
+
+    pub fn save(&self) -> TribeResult<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| TribeError::Storage(format!("Failed to create {}: {}", parent.display(), e)))?;
+        }
+        let data = serde_json::to_string_pretty(&self.accounts)
+            .map_err(|e| TribeError::Storage(format!("Failed to encode watch list: {}", e)))?;
+        std::fs::write(&self.path, data)
+            .map_err(|e| TribeError::Storage(format!("Failed to write watch list {}: {}", self.path.display(), e)))
+    }
+
+    /// Start watching `address`; history before `from_block` is skipped by rescans
+    pub fn watch(&mut self, address: &str, label: Option<String>, from_block: u64) -> TribeResult<()> {
+        if self.accounts.contains_key(address) {
+            return Err(TribeError::Generic(format!("{} is already watched", address)));
+        }
+        let account = WatchedAccount {
+            label,
+            scanned_to: from_block.checked_sub(1),
+            ..Default::default()
+        };
+        self.accounts.insert(address.to_string(), account);
+        Ok(())
+    }
+
+    pub fn unwatch(&mut self, address: &str) -> TribeResult<()> {
+        self.accounts.remove(address)
+            .map(|_| ())
+            .ok_or_else(|| TribeError::Generic(format!("{} is not watched", address)))
+    }
+
+    /// Bring `address` up to the node's best block, scanning only blocks not yet seen,
+    /// or everything if `full` or the last block scanned is no longer on the chain.
+    /// After a reorg, transactions that aren't found again are kept, marked orphaned.
+    /// Returns how many transactions were found.
+    pub async fn rescan(&mut self, client: &mut RpcClient, address: &str, full: bool) -> TribeResult<usize> {
+        let account = self.accounts.get_mut(address)
+            .ok_or_else(|| TribeError::Generic(format!("{} is not watched", address)))?;
+        let height = client.call("getBlockNumber", json!([])).await?.as_u64()
+            .ok_or_else(|| TribeError::Rpc("Node did not report its height".to_string()))?;
+
+        let mut from = account.scanned_to.map_or(0, |scanned_to| scanned_to + 1);
+        if let Some(scanned_to) = account.scanned_to.filter(|_| !account.scanned_hash.is_empty()) {
+            let block = client.call("getBlock", json!([scanned_to])).await?;
+            if full || block["hash"].as_str() != Some(account.scanned_hash.as_str()) {
+                from = 0;
+            }
+        } else if full {
+            from = 0;
+        }
+        // Mempool entries are found again, in a block or still pending
+        account.transactions.retain(|entry| !entry.block_hash.is_empty());
+        let previous = match from {
+            0 if full => { account.transactions.clear(); Vec::new() }
+            0 => std::mem::take(&mut account.transactions),
+            _ => Vec::new(),
+        };
+
+        let scanned = client.call("scanAddress", json!([address, from, height])).await?;
+        let found: Vec<WatchedTransaction> = scanned["transactions"].as_array().into_iter().flatten()
+            .map(|entry| serde_json::from_value(entry.clone()))
+            .collect::<Result<_, _>>()
+            .map_err(|e| TribeError::Rpc(format!("Invalid scan result: {}", e)))?;
+        let pending: Vec<Transaction> = serde_json::from_value(scanned["pending"].clone()).unwrap_or_default();
+        let best = client.call("getBlock", json!([height])).await?;
+
+        account.balance = client.call("getBalance", json!([address])).await?.as_u64().unwrap_or(0);
+        account.confirmed_balance = client.call("getBalance", json!([address, CONFIRMATIONS])).await?.as_u64().unwrap_or(0);
+        account.scanned_to = Some(height);
+        account.scanned_hash = best["hash"].as_str().unwrap_or_default().to_string();
+        let count = found.len();
+
+        // A rescan after a reorg finds everything still on the chain; the rest was orphaned
+        let orphaned = previous.into_iter()
+            .filter(|entry| !found.iter().any(|again| again.transaction.hash == entry.transaction.hash))
+            .map(|entry| WatchedTransaction { confirmations: 0, status: TxStatus::Orphaned, ..entry });
+        account.transactions.extend(orphaned);
+        account.transactions.extend(found);
+        account.transactions.sort_by_key(|entry| entry.block_index);
+        for entry in account.transactions.iter_mut().filter(|entry| entry.status != TxStatus::Orphaned) {
+            entry.confirmations = (height + 1).saturating_sub(entry.block_index);
+            entry.status = if entry.confirmations >= CONFIRMATIONS { TxStatus::Confirmed } else { TxStatus::Pending };
+        }
+        account.transactions.extend(pending.into_iter().map(|transaction| WatchedTransaction {
+            block_index: 0,
+            block_hash: String::new(),
+            transaction,
+            confirmations: 0,
+            status: TxStatus::Pending,
+        }));
+        Ok(count)
+    }
+}
+
+/// One line per watched account for `wallet balance` and `wallet watch`
+pub fn summary(address: &str, account: &WatchedAccount) -> String {
+    let label = account.label.as_ref().map(|label| format!(" ({})", label)).unwrap_or_default();
+    match account.scanned_to {
+        Some(height) if !account.scanned_hash.is_empty() => format!(
+            "{}{}: {} TRIBE ({} confirmed), {} transactions, scanned to block {} [watch-only]",
+            address, label, account.balance as f64 / 1_000_000.0, account.confirmed_balance as f64 / 1_000_000.0,
+            account.transactions.len(), height
+        ),
+        _ => format!("{}{}: not scanned yet [watch-only]", address, label),
+    }
+}
+
+/// A watched transaction for `wallet history`
+pub fn history_entry(entry: &WatchedTransaction) -> Value {
+    json!({
+        "block_index": entry.block_index,
+        "status": entry.status,
+        "confirmations": entry.confirmations,
+        "tx_hash": entry.transaction.hash,
+        "from": entry.transaction.from,
+        "type": entry.transaction.transaction_type,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tribechain::TransactionType;
+
+    /// What the fake node has on its chain, and the block the last `scanAddress` started at
+    #[derive(Default)]
+    struct Node {
+        hashes: Vec<String>,
+        mined: Vec<(u64, Transaction)>,
+        pending: Vec<Transaction>,
+        scanned_from: Option<u64>,
+    }
+
+    impl Node {
+        fn answer(&mut self, method: &str, params: &Value) -> Value {
+            match method {
+                "getBlockNumber" => json!(self.hashes.len() - 1),
+                "getBlock" => json!({ "hash": self.hashes[params[0].as_u64().unwrap() as usize] }),
+                "getBalance" if params.get(1).is_some() => json!(10),
+                "getBalance" => json!(30),
+                "scanAddress" => {
+                    let from = params[1].as_u64().unwrap();
+                    self.scanned_from = Some(from);
+                    let transactions: Vec<Value> = self.mined.iter()
+                        .filter(|(index, _)| *index >= from)
+                        .map(|(index, tx)| json!({ "block_index": index, "block_hash": self.hashes[*index as usize], "transaction": tx }))
+                        .collect();
+                    json!({ "transactions": transactions, "pending": self.pending })
+                }
+                _ => Value::Null,
+            }
+        }
+    }
+
+    async fn serve(node: Arc<Mutex<Node>>) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let (reader, mut writer) = stream.into_split();
+            let mut lines = BufReader::new(reader).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let request: Value = serde_json::from_str(&line).unwrap();
+                let result = node.lock().unwrap().answer(request["method"].as_str().unwrap(), &request["params"]);
+                let response = json!({ "jsonrpc": "2.0", "id": request["id"], "result": result });
+                writer.write_all(format!("{}\n", response).as_bytes()).await.unwrap();
+            }
+        });
+        addr
+    }
+
+    fn transfer(nonce: u64) -> Transaction {
+        Transaction::new("alice".to_string(), TransactionType::Transfer { to: "bob".to_string(), amount: 10 }, 1, nonce)
+    }
+
+    fn statuses(list: &WatchList) -> Vec<(u64, u64, TxStatus)> {
+        list.accounts["bob"].transactions.iter().map(|entry| (entry.transaction.nonce, entry.confirmations, entry.status)).collect()
+    }
+
+    #[tokio::test]
+    async fn test_rescans_follow_confirmations_and_reorgs() {
+        let (first, second) = (transfer(0), transfer(1));
+        let node = Arc::new(Mutex::new(Node {
+            hashes: (0..3).map(|index| format!("h{}", index)).collect(),
+            mined: vec![(1, first.clone())],
+            pending: vec![second.clone()],
+            ..Default::default()
+        }));
+        let mut client = RpcClient::connect(&serve(node.clone()).await).await.unwrap();
+        let mut list = WatchList::default();
+        list.watch("bob", Some("savings".to_string()), 0).unwrap();
+        assert!(summary("bob", &list.accounts["bob"]).contains("not scanned yet"));
+
+        assert_eq!(list.rescan(&mut client, "bob", false).await.unwrap(), 1);
+        assert_eq!(statuses(&list), vec![(0, 2, TxStatus::Pending), (1, 0, TxStatus::Pending)]);
+        assert_eq!(list.accounts["bob"].transactions[1].block_hash, "");
+        assert_eq!((list.accounts["bob"].balance, list.accounts["bob"].confirmed_balance), (30, 10));
+
+        // Five more blocks, the second one mined in the last: only the new blocks are scanned
+        {
+            let mut node = node.lock().unwrap();
+            node.hashes.extend((3..8).map(|index| format!("h{}", index)));
+            node.mined.push((7, second.clone()));
+            node.pending.clear();
+        }
+        assert_eq!(list.rescan(&mut client, "bob", false).await.unwrap(), 1);
+        assert_eq!(node.lock().unwrap().scanned_from, Some(3));
+        assert_eq!(statuses(&list), vec![(0, 7, TxStatus::Confirmed), (1, 1, TxStatus::Pending)]);
+        assert!(summary("bob", &list.accounts["bob"]).contains("(savings): 0.00003 TRIBE (0.00001 confirmed), 2 transactions, scanned to block 7"));
+
+        // A reorg from block 1 drops the first: everything is rescanned and it's kept as orphaned
+        {
+            let mut node = node.lock().unwrap();
+            node.hashes = (0..8).map(|index| if index == 0 { "h0".to_string() } else { format!("x{}", index) }).collect();
+            node.mined.retain(|(_, tx)| tx.hash == second.hash);
+        }
+        assert_eq!(list.rescan(&mut client, "bob", false).await.unwrap(), 1);
+        assert_eq!(node.lock().unwrap().scanned_from, Some(0));
+        assert_eq!(statuses(&list), vec![(0, 0, TxStatus::Orphaned), (1, 1, TxStatus::Pending)]);
+        assert_eq!(history_entry(&list.accounts["bob"].transactions[0])["status"], json!("orphaned"));
+
+        // A full rescan forgets what's no longer on the chain
+        assert_eq!(list.rescan(&mut client, "bob", true).await.unwrap(), 1);
+        assert_eq!(statuses(&list), vec![(1, 1, TxStatus::Pending)]);
+        assert!(list.rescan(&mut client, "carol", false).await.is_err());
+    }
+
+    #[test]
+    fn test_watch_list_persists_and_rejects_bad_files() {
+        let path = std::env::temp_dir().join(format!("tribechain-watch-{}", uuid::Uuid::new_v4())).join("watch.json");
+        let mut list = WatchList::open(&path).unwrap();
+        assert!(list.accounts.is_empty());
+
+        list.watch("bob", None, 5).unwrap();
+        assert!(list.watch("bob", None, 0).is_err());
+        list.watch("carol", None, 0).unwrap();
+        list.unwatch("carol").unwrap();
+        assert!(list.unwatch("carol").is_err());
+        list.save().unwrap();
+
+        let reopened = WatchList::open(&path).unwrap();
+        assert_eq!(reopened.accounts.keys().collect::<Vec<_>>(), vec!["bob"]);
+        assert_eq!(reopened.accounts["bob"].scanned_to, Some(4));
+
+        std::fs::write(&path, "not json").unwrap();
+        assert!(matches!(WatchList::open(&path), Err(TribeError::Storage(_))));
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}